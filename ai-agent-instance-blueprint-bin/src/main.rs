@@ -1,7 +1,8 @@
 //! Blueprint runner for ai-agent-instance-blueprint.
 //!
-//! Subscription model: each service instance runs exactly one sandbox.
-//! Simpler than the multi-sandbox blueprint — singleton lifecycle + workflows.
+//! Subscription model: each service instance runs a primary `main` sandbox,
+//! plus any named slots the owner adds via `/api/sandbox/slots/{slot}`.
+//! Simpler than the multi-sandbox blueprint — per-slot lifecycle + workflows.
 
 use ai_agent_instance_blueprint_lib::{
     JOB_WORKFLOW_TICK, bootstrap_workflows_from_chain, router,
@@ -9,7 +10,7 @@ use ai_agent_instance_blueprint_lib::{
 };
 use axum::extract::Path;
 use axum::http::StatusCode;
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{Json, Router as HttpRouter};
 use blueprint_producers_extra::cron::CronJob;
 use blueprint_sdk::contexts::tangle::TangleClientContext;
@@ -120,6 +121,55 @@ async fn workflow_detail_handler(
     .map_err(workflow_status_error)
 }
 
+fn slot_error(
+    error: ai_agent_instance_blueprint_lib::SlotError,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &error {
+        ai_agent_instance_blueprint_lib::SlotError::BadRequest(_) => StatusCode::BAD_REQUEST,
+        ai_agent_instance_blueprint_lib::SlotError::NotFound(_) => StatusCode::NOT_FOUND,
+        ai_agent_instance_blueprint_lib::SlotError::Forbidden(_) => StatusCode::FORBIDDEN,
+        ai_agent_instance_blueprint_lib::SlotError::Internal(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (
+        status,
+        Json(serde_json::json!({ "error": error.message() })),
+    )
+}
+
+async fn slot_provision_handler(
+    sandbox_runtime::session_auth::SessionAuth(caller): sandbox_runtime::session_auth::SessionAuth,
+    Path(slot): Path<String>,
+    Json(body): Json<ai_agent_instance_blueprint_lib::SlotProvisionRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let output =
+        ai_agent_instance_blueprint_lib::provision_slot(caller.as_str(), &slot, body, None)
+            .await
+            .map_err(slot_error)?;
+    Ok(Json(serde_json::json!({
+        "slot": slot,
+        "sandboxId": output.sandbox_id,
+        "sidecarUrl": output.sidecar_url,
+        "sshPort": output.ssh_port,
+    })))
+}
+
+async fn slot_deprovision_handler(
+    sandbox_runtime::session_auth::SessionAuth(caller): sandbox_runtime::session_auth::SessionAuth,
+    Path(slot): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let sandbox_id =
+        ai_agent_instance_blueprint_lib::deprovision_slot(caller.as_str(), &slot, None)
+            .await
+            .map_err(slot_error)?;
+    Ok(Json(serde_json::json!({
+        "slot": slot,
+        "sandboxId": sandbox_id,
+        "deprovisioned": true,
+    })))
+}
+
 fn workflow_status_router() -> HttpRouter {
     HttpRouter::new()
         .route("/api/workflows", get(workflow_list_handler))
@@ -128,19 +178,35 @@ fn workflow_status_router() -> HttpRouter {
             "/api/workflows/{workflow_id}/detail",
             get(workflow_detail_handler),
         )
+        .route(
+            "/api/sandbox/slots/{slot}",
+            post(slot_provision_handler).delete(slot_deprovision_handler),
+        )
 }
 
 #[tokio::main]
 #[allow(clippy::result_large_err)]
 async fn main() -> Result<(), blueprint_sdk::Error> {
+    if sandbox_runtime::operator_config::print_config_requested() {
+        std::process::exit(sandbox_runtime::operator_config::print_config());
+    }
     setup_log();
 
-    // Validate required auth config — SESSION_AUTH_SECRET must be set in production.
+    // Layer the optional operator config file (--config / OPERATOR_CONFIG_FILE)
+    // under the environment before anything reads configuration.
+    let operator_config =
+        sandbox_runtime::operator_config::init().map_err(blueprint_sdk::Error::Other)?;
+
+    // Validate the operator config and required auth config — SESSION_AUTH_SECRET
+    // must be set in production.
     let is_test_mode = std::env::args().any(|a| a == "--test-mode")
         || std::env::var("TEST_MODE")
             .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
             .unwrap_or(false);
-    if let Err(msg) = sandbox_runtime::session_auth::validate_required_config() {
+    if let Err(msg) = operator_config
+        .validate()
+        .and_then(|()| sandbox_runtime::session_auth::validate_required_config())
+    {
         if is_test_mode {
             warn!("Config validation (test mode): {msg}");
         } else {
@@ -148,6 +214,10 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
         }
     }
 
+    // QoS: heartbeat to the OperatorStatusRegistry plus metrics collection.
+    #[cfg(feature = "qos")]
+    let qos_metrics = sandbox_runtime::qos::start_qos_from_env().await;
+
     let env = BlueprintEnvironment::load()?;
 
     let tangle_client = env
@@ -181,9 +251,12 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
     let api_shutdown = tokio::sync::watch::channel(());
     let api_shutdown_tx = api_shutdown.0;
     let api_handle = {
+        let extra_routes = workflow_status_router();
+        #[cfg(feature = "grpc")]
+        let extra_routes = extra_routes.merge(sandbox_runtime::grpc::grpc_router());
         let router = sandbox_runtime::operator_api::operator_api_router_with_tee_and_routes(
             None,
-            workflow_status_router(),
+            extra_routes,
         );
         // Bind 127.0.0.1 by default (loopback only). Set BIND_ALL_INTERFACES=true
         // to bind 0.0.0.0 (all interfaces) for environments where external access
@@ -263,6 +336,12 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
         None
     };
 
+    // Spawn the QoS metrics loop (stops with the API server).
+    #[cfg(feature = "qos")]
+    if let Some(qos) = qos_metrics {
+        sandbox_runtime::qos::spawn_qos_metrics_loop(qos, api_shutdown_tx.subscribe());
+    }
+
     // Spawn reaper background task (idle timeout + max lifetime enforcement).
     {
         let config = ai_agent_instance_blueprint_lib::runtime::SidecarRuntimeConfig::load();
@@ -334,6 +413,41 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
                 }
             }
         });
+
+        // React to the instance container being OOM-killed or dying.
+        if sandbox_runtime::crash_monitor::crash_monitor_enabled() {
+            tokio::spawn(sandbox_runtime::crash_monitor::run_crash_monitor(
+                api_shutdown_tx.subscribe(),
+            ));
+        }
+
+        // Measure the instance's disk usage against `disk_gb`.
+        if sandbox_runtime::disk_quota::disk_quota_enabled() {
+            let check_interval = sandbox_runtime::disk_quota::disk_check_interval();
+            let mut disk_shutdown = api_shutdown_tx.subscribe();
+            tokio::spawn(async move {
+                let mut interval =
+                    tokio::time::interval(std::time::Duration::from_secs(check_interval));
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {
+                            let h = tokio::spawn(
+                                sandbox_runtime::disk_quota::disk_quota_tick()
+                            );
+                            match h.await {
+                                Ok(Err(e)) => warn!("Disk quota check failed: {e}"),
+                                Err(e) => error!("Disk quota check panicked: {e}"),
+                                Ok(Ok(_)) => {}
+                            }
+                        }
+                        _ = disk_shutdown.changed() => {
+                            info!("Disk quota checks shutting down");
+                            break;
+                        }
+                    }
+                }
+            });
+        }
     }
 
     // Spawn escrow watchdog + subscription billing keeper.
@@ -444,6 +558,7 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
             // Do not deprovision on generic process shutdown.
             // Lifecycle transitions are reported explicitly by operator logic.
             info!("Shutdown complete; instance lifecycle is report-driven");
+            sandbox_runtime::logging::shutdown();
         })
         .run()
         .await;
@@ -462,12 +577,5 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
 }
 
 fn setup_log() {
-    use tracing_subscriber::prelude::*;
-    use tracing_subscriber::{EnvFilter, fmt};
-    if tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::from_default_env())
-        .try_init()
-        .is_err()
-    {}
+    sandbox_runtime::logging::init();
 }
@@ -63,6 +63,14 @@ pub fn decode_provision_config(config_bytes: &[u8]) -> Result<ProvisionRequest,
         .or_else(|_| LegacyProvisionRequest::abi_decode(config_bytes).map(ProvisionRequest::from))
         .or_else(|_| ProvisionRequest::abi_decode_params(config_bytes))
         .or_else(|_| ProvisionRequest::abi_decode(config_bytes))
+        .or_else(|_| {
+            ProvisionRequestV3::abi_decode_params(config_bytes).map(ProvisionRequest::from)
+        })
+        .or_else(|_| ProvisionRequestV3::abi_decode(config_bytes).map(ProvisionRequest::from))
+        .or_else(|_| {
+            ProvisionRequestV2::abi_decode_params(config_bytes).map(ProvisionRequest::from)
+        })
+        .or_else(|_| ProvisionRequestV2::abi_decode(config_bytes).map(ProvisionRequest::from))
         .or_else(|_| {
             ProvisionRequestV1::abi_decode_params(config_bytes).map(ProvisionRequest::from)
         })
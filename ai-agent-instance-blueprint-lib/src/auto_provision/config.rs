@@ -13,15 +13,27 @@ pub struct AutoProvisionConfig {
     pub poll_interval_secs: u64,
     /// Maximum number of poll attempts before giving up.
     pub max_attempts: u32,
+    /// How many times a failed provision is retried before giving up.
+    pub provision_retries: u32,
+    /// Backoff before the first provision retry (seconds); doubles per retry.
+    pub retry_backoff_secs: u64,
+    /// Report a final provision failure on-chain (`reportProvisionFailed`).
+    pub report_failure_on_chain: bool,
 }
 
+/// Longest wait between provision retries.
+const MAX_RETRY_BACKOFF_SECS: u64 = 600;
+
 impl AutoProvisionConfig {
     /// Build config from environment variables.
     ///
     /// Required: `BSM_ADDRESS`
     /// Optional: `HTTP_RPC_ENDPOINT` / `RPC_URL` (default: http://127.0.0.1:8545),
     ///           `AUTO_PROVISION_POLL_SECS` (default: 5),
-    ///           `AUTO_PROVISION_MAX_ATTEMPTS` (default: 60)
+    ///           `AUTO_PROVISION_MAX_ATTEMPTS` (default: 60),
+    ///           `AUTO_PROVISION_RETRIES` (default: 3),
+    ///           `AUTO_PROVISION_RETRY_BACKOFF_SECS` (default: 15),
+    ///           `AUTO_PROVISION_REPORT_FAILURE` (default: false)
     pub fn from_env(service_id: u64) -> Option<Self> {
         let bsm_str = std::env::var("BSM_ADDRESS").ok()?;
         let bsm_address: Address = bsm_str.parse().ok().or_else(|| {
@@ -43,12 +55,40 @@ impl AutoProvisionConfig {
             .and_then(|v| v.parse().ok())
             .unwrap_or(60);
 
+        let provision_retries: u32 = std::env::var("AUTO_PROVISION_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+
+        let retry_backoff_secs: u64 = std::env::var("AUTO_PROVISION_RETRY_BACKOFF_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(15);
+
+        let report_failure_on_chain = std::env::var("AUTO_PROVISION_REPORT_FAILURE")
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+
         Some(Self {
             bsm_address,
             http_rpc_endpoint,
             service_id,
             poll_interval_secs,
             max_attempts,
+            provision_retries,
+            retry_backoff_secs,
+            report_failure_on_chain,
         })
     }
+
+    /// Wait before provision retry `retry` (1-based): the base backoff
+    /// doubled per retry, capped at ten minutes.
+    pub fn retry_backoff(&self, retry: u32) -> Duration {
+        let factor = 1u64 << retry.saturating_sub(1).min(16);
+        Duration::from_secs(
+            self.retry_backoff_secs
+                .saturating_mul(factor)
+                .min(MAX_RETRY_BACKOFF_SECS),
+        )
+    }
 }
@@ -8,18 +8,26 @@
 //! 4. Store sandbox record via `set_instance_sandbox()`
 //! 5. Report provision directly to manager contract (`reportProvisioned`)
 //!
+//! Provision failures are retried with backoff; a final failure is persisted
+//! for `GET /api/operator/auto-provision` and optionally reported on-chain
+//! (`reportProvisionFailed`).
 
 use blueprint_sdk::alloy::primitives::Address;
 use blueprint_sdk::alloy::providers::ProviderBuilder;
 use blueprint_sdk::alloy::sol_types::SolValue;
 use blueprint_sdk::{info, warn};
+use sandbox_runtime::auto_provision_status::{
+    clear_auto_provision_failure, record_auto_provision_failure,
+    record_auto_provision_failure_report,
+};
 use std::time::Duration;
 
 use crate::tee::TeeBackend;
 use crate::{
-    IBsmRead, LegacyProvisionRequest, ProvisionRequest, ProvisionRequestV1, clear_instance_sandbox,
-    ensure_local_provision_reported, get_instance_sandbox, mark_pending_provision_report,
-    provision_core, report_local_provision, set_instance_sandbox,
+    IBsmRead, LegacyProvisionRequest, ProvisionRequest, ProvisionRequestV1, ProvisionRequestV2,
+    ProvisionRequestV3, clear_instance_sandbox, ensure_local_provision_reported,
+    get_instance_sandbox, mark_pending_provision_report, provision_core, report_local_provision,
+    report_local_provision_failure, set_instance_sandbox,
 };
 
 mod chain_read;
@@ -7,31 +7,75 @@ use super::*;
 /// 2. Poll `getServiceConfig` until config is available
 /// 3. Decode as `ProvisionRequest` and call `provision_core`
 /// 4. Store the sandbox record
+///
+/// A failed provision is retried `provision_retries` times with exponential
+/// backoff. When it still fails, the failure is persisted (see
+/// `sandbox_runtime::auto_provision_status`) and, with
+/// `report_failure_on_chain`, reported via `reportProvisionFailed`.
 pub async fn run_auto_provision(
     config: AutoProvisionConfig,
     tee: Option<&dyn TeeBackend>,
     report_client: Option<blueprint_sdk::contexts::tangle::TangleClient>,
+) -> Result<(), String> {
+    let mut attempts = 0;
+    let result = auto_provision(&config, tee, report_client.as_ref(), &mut attempts).await;
+    match &result {
+        Ok(()) => {
+            if let Err(err) = clear_auto_provision_failure(config.service_id) {
+                warn!("Auto-provision: failed to clear failure record: {err}");
+            }
+        }
+        Err(error) => record_failure(&config, report_client.as_ref(), attempts, error).await,
+    }
+    result
+}
+
+async fn record_failure(
+    config: &AutoProvisionConfig,
+    report_client: Option<&blueprint_sdk::contexts::tangle::TangleClient>,
+    attempts: u32,
+    error: &str,
+) {
+    if let Err(err) = record_auto_provision_failure(config.service_id, attempts, error) {
+        warn!("Auto-provision: failed to persist failure record: {err}");
+    }
+    if !config.report_failure_on_chain {
+        return;
+    }
+    let outcome = match report_client {
+        Some(client) => report_local_provision_failure(client, config.service_id, error).await,
+        None => Err("no Tangle client available for the on-chain report".to_string()),
+    };
+    if let Err(err) = &outcome {
+        warn!(
+            service_id = config.service_id,
+            error = %err,
+            "Auto-provision: on-chain failure report failed"
+        );
+    }
+    if let Err(err) = record_auto_provision_failure_report(config.service_id, outcome) {
+        warn!("Auto-provision: failed to persist failure report outcome: {err}");
+    }
+}
+
+/// The auto-provision run proper; `attempts` counts `provision_core` calls.
+async fn auto_provision(
+    config: &AutoProvisionConfig,
+    tee: Option<&dyn TeeBackend>,
+    report_client: Option<&blueprint_sdk::contexts::tangle::TangleClient>,
+    attempts: &mut u32,
 ) -> Result<(), String> {
     // Already provisioned locally?
     if let Some(record) = get_instance_sandbox().map_err(|e| e.to_string())? {
         if should_reuse_existing_record(&record, config.service_id, None) {
-            return reuse_existing_instance_record(
-                record,
-                config.service_id,
-                report_client.as_ref(),
-            )
-            .await;
+            return reuse_existing_instance_record(record, config.service_id, report_client).await;
         }
 
         if record.service_id.is_none() {
-            let owner = read_service_owner(&config).await?;
+            let owner = read_service_owner(config).await?;
             if should_reuse_existing_record(&record, config.service_id, Some(&owner)) {
-                return reuse_existing_instance_record(
-                    record,
-                    config.service_id,
-                    report_client.as_ref(),
-                )
-                .await;
+                return reuse_existing_instance_record(record, config.service_id, report_client)
+                    .await;
             }
         }
 
@@ -43,10 +87,10 @@ pub async fn run_auto_provision(
         config.bsm_address, config.service_id, config.poll_interval_secs, config.max_attempts
     );
 
-    let mut attempts = 0;
+    let mut poll_attempts = 0;
     let config_bytes = loop {
-        attempts += 1;
-        match read_service_config(&config).await {
+        poll_attempts += 1;
+        match read_service_config(config).await {
             Ok(Some(bytes)) => {
                 info!(
                     "Auto-provision: service config found ({} bytes)",
@@ -55,25 +99,25 @@ pub async fn run_auto_provision(
                 break bytes;
             }
             Ok(None) => {
-                if attempts >= config.max_attempts {
+                if poll_attempts >= config.max_attempts {
                     return Err(format!(
                         "Auto-provision: no service config after {} attempts",
                         config.max_attempts
                     ));
                 }
-                if attempts % 12 == 1 {
+                if poll_attempts % 12 == 1 {
                     info!(
                         "Auto-provision: waiting for service config (attempt {}/{})",
-                        attempts, config.max_attempts
+                        poll_attempts, config.max_attempts
                     );
                 }
             }
             Err(e) => {
                 warn!(
                     "Auto-provision: RPC error (attempt {}/{}): {e}",
-                    attempts, config.max_attempts
+                    poll_attempts, config.max_attempts
                 );
-                if attempts >= config.max_attempts {
+                if poll_attempts >= config.max_attempts {
                     return Err(format!(
                         "Auto-provision: RPC failed after {} attempts: {e}",
                         config.max_attempts
@@ -92,7 +136,14 @@ pub async fn run_auto_provision(
     };
 
     // Decode config
-    let request = decode_provision_config(&config_bytes)?;
+    let mut request = decode_provision_config(&config_bytes)?;
+    if !request.slot.trim().is_empty() {
+        warn!(
+            slot = %request.slot,
+            "Auto-provision: ignoring slot in service config; auto-provision always targets the main slot"
+        );
+        request.slot.clear();
+    }
     info!(
         "Auto-provision: decoded config — name='{}', image='{}', tee={}",
         request.name, request.image, request.tee_required
@@ -103,7 +154,7 @@ pub async fn run_auto_provision(
     let mut owner_attempts = 0;
     let owner = loop {
         owner_attempts += 1;
-        match read_service_owner(&config).await {
+        match read_service_owner(config).await {
             Ok(addr) if !addr.is_empty() => {
                 info!("Auto-provision: service owner = {addr}");
                 break addr;
@@ -144,15 +195,41 @@ pub async fn run_auto_provision(
         return Ok(());
     }
 
-    // Provision
-    let (output, record) = provision_core(&request, tee, &owner).await?;
+    // Provision, retrying transient failures (image pull flakes, CVM capacity).
+    let (output, record) = loop {
+        *attempts += 1;
+        match provision_core(&request, tee, &owner).await {
+            Ok(provisioned) => break provisioned,
+            Err(err) if *attempts > config.provision_retries => {
+                return Err(format!(
+                    "Auto-provision: provision failed after {} attempts: {err}",
+                    *attempts
+                ));
+            }
+            Err(err) => {
+                let backoff = config.retry_backoff(*attempts);
+                warn!(
+                    "Auto-provision: provision failed (attempt {}/{}), retrying in {}s: {err}",
+                    *attempts,
+                    config.provision_retries + 1,
+                    backoff.as_secs()
+                );
+                tokio::time::sleep(backoff).await;
+            }
+        }
+
+        if get_instance_sandbox().map_err(|e| e.to_string())?.is_some() {
+            info!("Auto-provision: instance was provisioned externally, skipping");
+            return Ok(());
+        }
+    };
     let record = bind_service_id(record, config.service_id);
 
     // Store record
     set_instance_sandbox(record.clone()).map_err(|e| e.to_string())?;
     sync_runtime_service_binding(&record)?;
 
-    if let Some(client) = report_client.as_ref()
+    if let Some(client) = report_client
         && let Err(err) = report_local_provision(client, config.service_id, &output).await
     {
         warn!(
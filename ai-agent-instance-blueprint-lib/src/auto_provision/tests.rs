@@ -40,6 +40,11 @@ fn test_record(service_id: Option<u64>, owner: &str) -> crate::SandboxRecord {
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     }
 }
 
@@ -97,6 +102,8 @@ fn decode_provision_config_roundtrip() {
         tee_type: 0,
         attestation_nonce: String::new(),
         capabilities_json: String::new(),
+        template: String::new(),
+        slot: String::new(),
     };
 
     // On-chain config is stored as params encoding (flat tuple, no outer offset),
@@ -137,6 +144,8 @@ fn decode_provision_config_tuple_encoding() {
         tee_type: 1,
         attestation_nonce: String::new(),
         capabilities_json: String::new(),
+        template: String::new(),
+        slot: String::new(),
     };
 
     // abi_encode() produces tuple encoding (with outer offset prefix).
@@ -174,6 +183,8 @@ fn decode_provision_config_preserves_attestation_nonce() {
         tee_type: 1,
         attestation_nonce: nonce.clone(),
         capabilities_json: String::new(),
+        template: String::new(),
+        slot: String::new(),
     };
 
     let encoded = request.abi_encode_params();
@@ -222,6 +233,73 @@ fn decode_provision_config_legacy_shape_without_using_sidecar_token() {
     assert!(!decoded.tee_required);
 }
 
+#[test]
+fn decode_provision_config_shape_without_template() {
+    use blueprint_sdk::alloy::sol_types::SolValue;
+
+    let request = ProvisionRequestV2 {
+        name: "pre-template".to_string(),
+        image: String::new(),
+        stack: "default".to_string(),
+        agent_identifier: "test-agent".to_string(),
+        env_json: "{}".to_string(),
+        metadata_json: "{}".to_string(),
+        ssh_enabled: false,
+        ssh_public_key: String::new(),
+        web_terminal_enabled: false,
+        max_lifetime_seconds: 3600,
+        idle_timeout_seconds: 900,
+        cpu_cores: 2,
+        memory_mb: 4096,
+        disk_gb: 20,
+        tee_required: false,
+        tee_type: 0,
+        attestation_nonce: String::new(),
+        capabilities_json: r#"["computer_use"]"#.to_string(),
+    };
+
+    let encoded = request.abi_encode_params();
+    let decoded = decode_provision_config(&encoded).unwrap();
+
+    assert_eq!(decoded.name, "pre-template");
+    assert_eq!(decoded.capabilities_json, r#"["computer_use"]"#);
+    assert!(decoded.template.is_empty());
+}
+
+#[test]
+fn decode_provision_config_shape_without_slot() {
+    use blueprint_sdk::alloy::sol_types::SolValue;
+
+    let request = ProvisionRequestV3 {
+        name: "pre-slot".to_string(),
+        image: String::new(),
+        stack: "default".to_string(),
+        agent_identifier: "test-agent".to_string(),
+        env_json: "{}".to_string(),
+        metadata_json: "{}".to_string(),
+        ssh_enabled: false,
+        ssh_public_key: String::new(),
+        web_terminal_enabled: false,
+        max_lifetime_seconds: 3600,
+        idle_timeout_seconds: 900,
+        cpu_cores: 2,
+        memory_mb: 4096,
+        disk_gb: 20,
+        tee_required: false,
+        tee_type: 0,
+        attestation_nonce: String::new(),
+        capabilities_json: String::new(),
+        template: "python-small".to_string(),
+    };
+
+    let encoded = request.abi_encode_params();
+    let decoded = decode_provision_config(&encoded).unwrap();
+
+    assert_eq!(decoded.name, "pre-slot");
+    assert_eq!(decoded.template, "python-small");
+    assert!(decoded.slot.is_empty());
+}
+
 #[test]
 fn decode_provision_config_malformed_bytes_rejected() {
     let garbage = vec![0xDE, 0xAD, 0xBE, 0xEF, 0x01, 0x02, 0x03, 0x04];
@@ -230,3 +308,53 @@ fn decode_provision_config_malformed_bytes_rejected() {
     let err = result.err().unwrap();
     assert!(err.contains("Failed to decode"), "got: {err}");
 }
+
+#[test]
+fn retry_backoff_doubles_and_caps() {
+    let config = AutoProvisionConfig {
+        bsm_address: Address::ZERO,
+        http_rpc_endpoint: "http://127.0.0.1:8545".to_string(),
+        service_id: 1,
+        poll_interval_secs: 5,
+        max_attempts: 60,
+        provision_retries: 3,
+        retry_backoff_secs: 15,
+        report_failure_on_chain: false,
+    };
+    assert_eq!(config.retry_backoff(1), Duration::from_secs(15));
+    assert_eq!(config.retry_backoff(2), Duration::from_secs(30));
+    assert_eq!(config.retry_backoff(3), Duration::from_secs(60));
+    assert_eq!(config.retry_backoff(10), Duration::from_secs(600));
+    assert_eq!(config.retry_backoff(u32::MAX), Duration::from_secs(600));
+}
+
+#[test]
+fn provision_request_layouts_are_pinned() {
+    use blueprint_sdk::alloy::sol_types::SolStruct;
+
+    const V1_FIELDS: &str = "string name,string image,string stack,string agent_identifier,\
+        string env_json,string metadata_json,bool ssh_enabled,string ssh_public_key,\
+        bool web_terminal_enabled,uint64 max_lifetime_seconds,uint64 idle_timeout_seconds,\
+        uint64 cpu_cores,uint64 memory_mb,uint64 disk_gb,bool tee_required,uint8 tee_type";
+    let v2_fields = format!("{V1_FIELDS},string attestation_nonce,string capabilities_json");
+    let v3_fields = format!("{v2_fields},string template");
+
+    assert_eq!(
+        ProvisionRequestV1::eip712_encode_type(),
+        format!("ProvisionRequestV1({V1_FIELDS})")
+    );
+    assert_eq!(
+        ProvisionRequestV2::eip712_encode_type(),
+        format!("ProvisionRequestV2({v2_fields})")
+    );
+    assert_eq!(
+        ProvisionRequestV3::eip712_encode_type(),
+        format!("ProvisionRequestV3({v3_fields})")
+    );
+    assert_eq!(
+        ProvisionRequest::eip712_encode_type(),
+        format!("ProvisionRequest({v3_fields},string slot)"),
+        "ProvisionRequest changed: add the previous layout as a new \
+         ProvisionRequestVn and teach decode_provision_config about it"
+    );
+}
@@ -0,0 +1,44 @@
+//! `billing.alert` webhooks for escrow watchdog results.
+
+use super::{EscrowWatchdogConfig, WatchdogTickResult};
+
+/// Webhook alert level of a tick; `None` when escrow is healthy or unknown.
+pub(super) fn billing_alert_status(result: &WatchdogTickResult) -> Option<&'static str> {
+    match result {
+        WatchdogTickResult::LowBalance { .. } => Some("low_balance"),
+        WatchdogTickResult::Insufficient { .. } => Some("insufficient"),
+        WatchdogTickResult::DeprovisionRequired { .. } => Some("deprovision_required"),
+        WatchdogTickResult::Sufficient { .. } | WatchdogTickResult::TransientError(_) => None,
+    }
+}
+
+/// Send `billing.alert` to the instance owner's webhooks.
+pub(super) fn emit_billing_alert(
+    status: &str,
+    result: &WatchdogTickResult,
+    config: &EscrowWatchdogConfig,
+) {
+    let Ok(Some(record)) = crate::get_instance_sandbox() else {
+        return;
+    };
+    let (periods_remaining, consecutive_failures) = match result {
+        WatchdogTickResult::LowBalance {
+            periods_remaining, ..
+        } => (Some(*periods_remaining), 0),
+        WatchdogTickResult::Insufficient { consecutive, .. }
+        | WatchdogTickResult::DeprovisionRequired { consecutive } => (None, *consecutive),
+        _ => (None, 0),
+    };
+    sandbox_runtime::webhooks::emit(
+        &record.owner,
+        sandbox_runtime::webhooks::WebhookEvent::BillingAlert,
+        serde_json::json!({
+            "status": status,
+            "service_id": config.service_id,
+            "sandbox_id": record.id,
+            "periods_remaining": periods_remaining,
+            "consecutive_failures": consecutive_failures,
+            "max_consecutive_failures": config.max_consecutive_failures,
+        }),
+    );
+}
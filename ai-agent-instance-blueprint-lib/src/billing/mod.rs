@@ -7,7 +7,10 @@
 //! `deprovision_core(None)` to shut down the sandbox gracefully.
 //!
 //! Writes `billing_status.json` to the state directory on each tick for
-//! external observability (monitoring, UI, etc.).
+//! external observability (monitoring, UI, etc.), including the instance
+//! owner's usage this month from `sandbox_runtime::usage_ledger`, and sends a `billing.alert`
+//! webhook to the instance owner when the escrow turns low, insufficient or
+//! triggers deprovisioning.
 //!
 //! Gated behind the `billing` feature flag.
 
@@ -18,6 +21,10 @@ use blueprint_sdk::{error, info, warn};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
 
+mod alerts;
+
+use alerts::{billing_alert_status, emit_billing_alert};
+
 // ─────────────────────────────────────────────────────────────────────────────
 // ABI types for read-only RPC calls
 // ─────────────────────────────────────────────────────────────────────────────
@@ -228,6 +235,10 @@ fn write_billing_status(result: &WatchdogTickResult, config: &EscrowWatchdogConf
         }
         WatchdogTickResult::TransientError(_) => ("rpc_error", None, None, 0, None),
     };
+    let usage = crate::get_instance_sandbox()
+        .ok()
+        .flatten()
+        .and_then(|record| sandbox_runtime::usage_ledger::current_month_usage(&record.owner).ok());
 
     let value = json!({
         "status": status,
@@ -238,6 +249,7 @@ fn write_billing_status(result: &WatchdogTickResult, config: &EscrowWatchdogConf
         "consecutive_failures": consecutive_failures,
         "max_consecutive_failures": config.max_consecutive_failures,
         "periods_remaining": periods_remaining,
+        "usage": usage,
         "updated_at": now,
     });
 
@@ -421,11 +433,20 @@ pub fn spawn_watchdog(
             watchdog.config.low_balance_multiplier
         );
 
+        // Alert on changes only, not on every tick of a lasting condition.
+        let mut last_alert = None;
         loop {
             tokio::select! {
                 _ = ticker.tick() => {
                     let result = watchdog.tick().await;
                     write_billing_status(&result, &watchdog.config);
+                    if !matches!(result, WatchdogTickResult::TransientError(_)) {
+                        let alert = billing_alert_status(&result);
+                        if let Some(status) = alert.filter(|_| alert != last_alert) {
+                            emit_billing_alert(status, &result, &watchdog.config);
+                        }
+                        last_alert = alert;
+                    }
 
                     if let WatchdogTickResult::DeprovisionRequired { .. } = result {
                         trigger_deprovision(
@@ -463,6 +484,19 @@ async fn trigger_deprovision(
 
     info!("escrow-watchdog: triggering auto-deprovision");
 
+    // Named slots share the subscription, so tear them down with `main`.
+    let named_slots = crate::runtime::instance_slots()
+        .and_then(|s| s.values())
+        .unwrap_or_default();
+    for slot in named_slots {
+        if let Err(e) = crate::deprovision_slot_core(&slot.slot, None).await {
+            error!(
+                "escrow-watchdog: deprovision of slot '{}' failed: {e}",
+                slot.slot
+            );
+        }
+    }
+
     match crate::deprovision_core(None).await {
         Ok(_) => {
             info!("escrow-watchdog: sandbox deprovisioned successfully");
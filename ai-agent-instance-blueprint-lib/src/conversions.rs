@@ -0,0 +1,157 @@
+//! ABI → runtime conversions: provision requests into [`CreateSandboxParams`],
+//! and older provision request shapes into the current [`ProvisionRequest`].
+
+use crate::{
+    CreateSandboxParams, LegacyProvisionRequest, ProvisionRequest, ProvisionRequestV1,
+    ProvisionRequestV2, ProvisionRequestV3, TeeConfig, TeeType,
+};
+
+impl From<&ProvisionRequest> for CreateSandboxParams {
+    fn from(r: &ProvisionRequest) -> Self {
+        let tee_config = if r.tee_required {
+            Some(TeeConfig {
+                required: true,
+                tee_type: match r.tee_type {
+                    1 => TeeType::Tdx,
+                    2 => TeeType::Nitro,
+                    3 => TeeType::Sev,
+                    _ => TeeType::None,
+                },
+                attestation_nonce: None,
+            })
+        } else {
+            None
+        };
+
+        Self {
+            name: r.name.to_string(),
+            image: r.image.to_string(),
+            stack: r.stack.to_string(),
+            agent_identifier: r.agent_identifier.to_string(),
+            env_json: r.env_json.to_string(),
+            metadata_json: r.metadata_json.to_string(),
+            ssh_enabled: r.ssh_enabled,
+            ssh_public_key: r.ssh_public_key.to_string(),
+            web_terminal_enabled: r.web_terminal_enabled,
+            max_lifetime_seconds: r.max_lifetime_seconds,
+            idle_timeout_seconds: r.idle_timeout_seconds,
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            owner: String::new(), // Set by the job handler from Caller extractor
+            service_id: None,
+            tee_config,
+            user_env_json: String::new(),
+            port_mappings: Vec::new(), // Parsed from metadata_json at runtime
+            capabilities_json: r.capabilities_json.to_string(),
+        }
+    }
+}
+
+impl From<LegacyProvisionRequest> for ProvisionRequest {
+    fn from(r: LegacyProvisionRequest) -> Self {
+        Self {
+            name: r.name,
+            image: r.image,
+            stack: r.stack,
+            agent_identifier: r.agent_identifier,
+            env_json: r.env_json,
+            metadata_json: r.metadata_json,
+            ssh_enabled: r.ssh_enabled,
+            ssh_public_key: r.ssh_public_key,
+            web_terminal_enabled: r.web_terminal_enabled,
+            max_lifetime_seconds: r.max_lifetime_seconds,
+            idle_timeout_seconds: r.idle_timeout_seconds,
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            tee_required: r.tee_required,
+            tee_type: r.tee_type,
+            attestation_nonce: String::new(),
+            capabilities_json: String::new(),
+            template: String::new(),
+            slot: String::new(),
+        }
+    }
+}
+
+impl From<ProvisionRequestV1> for ProvisionRequest {
+    fn from(r: ProvisionRequestV1) -> Self {
+        Self {
+            name: r.name,
+            image: r.image,
+            stack: r.stack,
+            agent_identifier: r.agent_identifier,
+            env_json: r.env_json,
+            metadata_json: r.metadata_json,
+            ssh_enabled: r.ssh_enabled,
+            ssh_public_key: r.ssh_public_key,
+            web_terminal_enabled: r.web_terminal_enabled,
+            max_lifetime_seconds: r.max_lifetime_seconds,
+            idle_timeout_seconds: r.idle_timeout_seconds,
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            tee_required: r.tee_required,
+            tee_type: r.tee_type,
+            attestation_nonce: String::new(),
+            capabilities_json: String::new(),
+            template: String::new(),
+            slot: String::new(),
+        }
+    }
+}
+
+impl From<ProvisionRequestV2> for ProvisionRequest {
+    fn from(r: ProvisionRequestV2) -> Self {
+        Self {
+            name: r.name,
+            image: r.image,
+            stack: r.stack,
+            agent_identifier: r.agent_identifier,
+            env_json: r.env_json,
+            metadata_json: r.metadata_json,
+            ssh_enabled: r.ssh_enabled,
+            ssh_public_key: r.ssh_public_key,
+            web_terminal_enabled: r.web_terminal_enabled,
+            max_lifetime_seconds: r.max_lifetime_seconds,
+            idle_timeout_seconds: r.idle_timeout_seconds,
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            tee_required: r.tee_required,
+            tee_type: r.tee_type,
+            attestation_nonce: r.attestation_nonce,
+            capabilities_json: r.capabilities_json,
+            template: String::new(),
+            slot: String::new(),
+        }
+    }
+}
+
+impl From<ProvisionRequestV3> for ProvisionRequest {
+    fn from(r: ProvisionRequestV3) -> Self {
+        Self {
+            name: r.name,
+            image: r.image,
+            stack: r.stack,
+            agent_identifier: r.agent_identifier,
+            env_json: r.env_json,
+            metadata_json: r.metadata_json,
+            ssh_enabled: r.ssh_enabled,
+            ssh_public_key: r.ssh_public_key,
+            web_terminal_enabled: r.web_terminal_enabled,
+            max_lifetime_seconds: r.max_lifetime_seconds,
+            idle_timeout_seconds: r.idle_timeout_seconds,
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            tee_required: r.tee_required,
+            tee_type: r.tee_type,
+            attestation_nonce: r.attestation_nonce,
+            capabilities_json: r.capabilities_json,
+            template: r.template,
+            slot: String::new(),
+        }
+    }
+}
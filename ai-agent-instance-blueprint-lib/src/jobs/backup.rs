@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::JsonResponse;
+use crate::http::sidecar_post_json;
+use crate::runtime::{acquire_lifecycle_lock, restart_sidecar, sync_instance_slot_record};
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::util::{build_backup_command, build_restore_command};
+use crate::{
+    InstanceBackupRequest, InstanceRestoreRequest, SandboxRecord, extract_exec_fields_for,
+};
+use sandbox_runtime::exec_policy::{CommandInput, CommandKind, enforce_command_policy};
+use sandbox_runtime::sidecar_compat::SidecarApi;
+
+/// Current [`InstanceBackupManifest`] format version.
+pub const BACKUP_MANIFEST_VERSION: u32 = 1;
+
+/// Record metadata stored alongside the workspace and sidecar state in an
+/// instance backup. Secrets (env, token, SSH keys) are never included; the
+/// restoring operator keeps its own.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceBackupManifest {
+    pub version: u32,
+    pub sandbox_id: String,
+    pub name: String,
+    pub image: String,
+    pub stack: String,
+    pub agent_identifier: String,
+    pub metadata_json: String,
+    pub capabilities_json: String,
+    pub cpu_cores: u64,
+    pub memory_mb: u64,
+    pub disk_gb: u64,
+    pub idle_timeout_seconds: u64,
+    pub max_lifetime_seconds: u64,
+    pub ssh_enabled: bool,
+    pub created_at: u64,
+    pub backed_up_at: u64,
+}
+
+impl InstanceBackupManifest {
+    pub fn from_record(record: &SandboxRecord) -> Self {
+        Self {
+            version: BACKUP_MANIFEST_VERSION,
+            sandbox_id: record.id.clone(),
+            name: record.name.clone(),
+            image: record.original_image.clone(),
+            stack: record.stack.clone(),
+            agent_identifier: record.agent_identifier.clone(),
+            metadata_json: record.metadata_json.clone(),
+            capabilities_json: record.capabilities_json.clone(),
+            cpu_cores: record.cpu_cores,
+            memory_mb: record.memory_mb,
+            disk_gb: record.disk_gb,
+            idle_timeout_seconds: record.idle_timeout_seconds,
+            max_lifetime_seconds: record.max_lifetime_seconds,
+            ssh_enabled: record.ssh_port.is_some(),
+            created_at: record.created_at,
+            backed_up_at: crate::util::now_ts(),
+        }
+    }
+}
+
+async fn run_sidecar_command(record: &SandboxRecord, command: &str) -> Result<String, String> {
+    let input = CommandInput {
+        kind: CommandKind::Snapshot,
+        command,
+        ..Default::default()
+    };
+    enforce_command_policy(&record.id, &record.owner, &input)?;
+    let payload = json!({
+        "command": format!("sh -c {}", crate::util::shell_escape(command)),
+    });
+    let response = sidecar_post_json(
+        &record.sidecar_url,
+        "/terminals/commands",
+        &record.token,
+        payload,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    let (exit_code, stdout, stderr) =
+        extract_exec_fields_for(SidecarApi::for_record(record), &response);
+    if exit_code != 0 {
+        return Err(format!(
+            "command exited with {exit_code}: {}",
+            stderr.trim()
+        ));
+    }
+    Ok(stdout)
+}
+
+/// Core backup logic — testable without TangleArg extractors.
+///
+/// Uploads a `tar.gz` of `/home/agent`, `/var/lib/sidecar` and an
+/// [`InstanceBackupManifest`] to `destination` (`https://` or `s3://`).
+pub async fn run_instance_backup(
+    caller: &str,
+    slot: &str,
+    destination: &str,
+) -> Result<String, String> {
+    if destination.trim().is_empty() {
+        return Err("Backup destination is required".to_string());
+    }
+    let record = super::require_slot_owner(caller, slot)?;
+    let manifest = InstanceBackupManifest::from_record(&record);
+    let manifest_json = serde_json::to_string(&manifest).map_err(|e| e.to_string())?;
+    let command =
+        build_backup_command(destination.trim(), &manifest_json).map_err(|e| e.to_string())?;
+    run_sidecar_command(&record, &command)
+        .await
+        .map_err(|e| format!("Backup failed: {e}"))?;
+    crate::runtime::touch_sandbox(&record.id);
+
+    Ok(json!({
+        "sandboxId": record.id,
+        "destination": destination.trim(),
+        "manifest": manifest,
+    })
+    .to_string())
+}
+
+/// Core restore logic — testable without TangleArg extractors.
+///
+/// Extracts a backup produced by [`run_instance_backup`] (possibly on another
+/// operator) into the slot's sandbox, then restarts it so the sidecar reloads
+/// its state. The slot must already be provisioned; the sandbox keeps its own
+/// ID, token and resources. Returns the archive's manifest for comparison.
+pub async fn run_instance_restore(
+    caller: &str,
+    slot: &str,
+    source: &str,
+) -> Result<String, String> {
+    if source.trim().is_empty() {
+        return Err("Restore source is required".to_string());
+    }
+    let record = super::require_slot_owner(caller, slot)?;
+    let command = build_restore_command(source.trim()).map_err(|e| e.to_string())?;
+
+    let _lock = acquire_lifecycle_lock(&record.id).await;
+    let stdout = run_sidecar_command(&record, &command)
+        .await
+        .map_err(|e| format!("Restore failed: {e}"))?;
+    let manifest = match stdout.trim() {
+        "" => None,
+        raw => Some(
+            serde_json::from_str::<InstanceBackupManifest>(raw)
+                .map_err(|e| format!("Backup manifest is invalid: {e}"))?,
+        ),
+    };
+    if manifest
+        .as_ref()
+        .is_some_and(|m| m.version > BACKUP_MANIFEST_VERSION)
+    {
+        tracing::warn!(sandbox_id = %record.id, "restored backup has a newer manifest version");
+    }
+
+    let restarted = restart_sidecar(&record).await;
+    sync_instance_slot_record(&record.id).map_err(|e| e.to_string())?;
+    let restarted = restarted.map_err(|e| format!("Restore extracted but restart failed: {e}"))?;
+    crate::runtime::touch_sandbox(&restarted.id);
+    sandbox_runtime::circuit_breaker::mark_healthy(&restarted.id);
+
+    Ok(json!({
+        "sandboxId": restarted.id,
+        "sidecarUrl": restarted.sidecar_url,
+        "source": source.trim(),
+        "manifest": manifest,
+        "restored": true,
+    })
+    .to_string())
+}
+
+/// Back up the instance sandbox to a destination URL. Owner-only.
+pub async fn instance_backup(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceBackupRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_BACKUP,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let json =
+                run_instance_backup(&caller_hex, &request.slot, &request.destination).await?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
+
+/// Restore the instance sandbox from a backup archive. Owner-only.
+pub async fn instance_restore(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceRestoreRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_RESTORE,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let json = run_instance_restore(&caller_hex, &request.slot, &request.source).await?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
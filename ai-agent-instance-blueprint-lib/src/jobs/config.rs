@@ -0,0 +1,70 @@
+use serde_json::json;
+
+use crate::InstanceConfigUpdateRequest;
+use crate::JsonResponse;
+use crate::runtime::{
+    SandboxConfigUpdate, acquire_lifecycle_lock, apply_sandbox_config_update,
+    sync_instance_slot_record,
+};
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::tee::TeeBackend;
+
+/// Core config-update logic — testable without TangleArg extractors.
+///
+/// `config_json` is a partial [`SandboxConfigUpdate`] object; omitted fields
+/// are left unchanged. The response reports which fields changed and whether
+/// they were applied to the stored record only, live, or by recreating the
+/// container.
+pub async fn run_instance_config_update(
+    caller: &str,
+    slot: &str,
+    config_json: &str,
+    tee: Option<&dyn TeeBackend>,
+) -> Result<String, String> {
+    let update: SandboxConfigUpdate = serde_json::from_str(config_json)
+        .map_err(|e| format!("config_json must be a JSON object of config fields: {e}"))?;
+    let record = super::require_slot_owner(caller, slot)?;
+
+    let _lock = acquire_lifecycle_lock(&record.id).await;
+    let result = apply_sandbox_config_update(&record.id, &update, tee).await;
+    sync_instance_slot_record(&record.id).map_err(|e| e.to_string())?;
+    let outcome = result?;
+    crate::runtime::touch_sandbox(&record.id);
+
+    let updated = outcome.record;
+    Ok(json!({
+        "sandboxId": updated.id,
+        "sidecarUrl": updated.sidecar_url,
+        "changed": outcome.changed,
+        "mode": outcome.mode.as_str(),
+        "idleTimeoutSeconds": updated.idle_timeout_seconds,
+        "maxLifetimeSeconds": updated.max_lifetime_seconds,
+        "cpuCores": updated.cpu_cores,
+        "memoryMb": updated.memory_mb,
+        "diskGb": updated.disk_gb,
+    })
+    .to_string())
+}
+
+/// Apply a partial configuration update to the instance sandbox. Owner-only.
+pub async fn instance_config_update(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceConfigUpdateRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_CONFIG_UPDATE,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let json =
+                run_instance_config_update(&caller_hex, &request.slot, &request.config_json, None)
+                    .await?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
@@ -7,8 +7,25 @@ use crate::InstancePromptResponse;
 use crate::InstanceTaskRequest;
 use crate::InstanceTaskResponse;
 use crate::http::sidecar_post_json;
-use crate::require_instance_sandbox;
+use crate::require_instance_sandbox_slot;
 use crate::tangle::extract::{Caller, TangleArg, TangleResult};
+use sandbox_runtime::agent_runs::reserve_agent_run;
+use sandbox_runtime::api_types::ExecApiRequest;
+use sandbox_runtime::exec_output::cap_exec_output;
+use sandbox_runtime::exec_policy::{CommandInput, CommandKind, enforce_command_policy};
+use sandbox_runtime::exec_presets::apply_exec_preset;
+use sandbox_runtime::model_policy::{check_model, payload_model, record_model_usage};
+use sandbox_runtime::named_sessions::{
+    forget_named_session, is_stale_session_error, record_named_session, resolve_session,
+};
+use sandbox_runtime::output_schema::{
+    insert_output_schema, parse_output_schema, run_with_output_schema,
+};
+use sandbox_runtime::prompt_templates::apply_prompt_template;
+use sandbox_runtime::runtime::resolve_agent_identifier;
+use sandbox_runtime::sidecar_compat::SidecarApi;
+use sandbox_runtime::tool_policy::{insert_tool_policy, parse_tool_policy, require_tool_echo};
+use sandbox_runtime::usage_ledger::{UsageTotals, record_sandbox_usage, record_usage};
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Exec
@@ -36,8 +53,28 @@ pub fn build_exec_payload(
     payload
 }
 
+/// [`build_exec_payload`] plus inline `stdin` or a `stdin_file` path.
+pub fn build_exec_payload_with_stdin(
+    command: &str,
+    cwd: &str,
+    env_json: &str,
+    timeout_ms: u64,
+    stdin: &str,
+    stdin_file: &str,
+) -> Map<String, Value> {
+    let mut payload = build_exec_payload(command, cwd, env_json, timeout_ms);
+    sandbox_runtime::exec_input::insert_exec_input(&mut payload, stdin, stdin_file);
+    payload
+}
+
 pub fn extract_exec_fields(parsed: &Value) -> (u32, String, String) {
-    let result = parsed.get("result");
+    extract_exec_fields_for(SidecarApi::V1, parsed)
+}
+
+/// [`extract_exec_fields`] for the envelope of the sidecar API the instance
+/// negotiated at provision.
+pub fn extract_exec_fields_for(api: SidecarApi, parsed: &Value) -> (u32, String, String) {
+    let result = api.command_result(parsed);
 
     let exit_code = result
         .and_then(|r| r.get("exitCode"))
@@ -60,17 +97,42 @@ pub fn extract_exec_fields(parsed: &Value) -> (u32, String, String) {
 }
 
 /// Core exec logic — testable without TangleArg extractors.
+///
+/// `request.preset` names an exec preset of the sandbox whose cwd, env,
+/// timeout and shell fill in the request (see `sandbox_runtime::exec_presets`).
 pub async fn run_instance_exec(
     sidecar_url: &str,
     sidecar_token: &str,
     sandbox_id: &str,
     request: &InstanceExecRequest,
 ) -> Result<InstanceExecResponse, String> {
-    let payload = build_exec_payload(
+    sandbox_runtime::exec_input::validate_exec_input(&request.stdin, &request.stdin_file)?;
+    let request =
+        apply_exec_preset(sandbox_id, &ExecApiRequest::from(request)).map_err(|e| e.to_string())?;
+    let record = crate::runtime::get_sandbox_by_id(sandbox_id).ok();
+    let owner = record
+        .as_ref()
+        .map(|record| record.owner.clone())
+        .unwrap_or_default();
+    let api = record
+        .as_ref()
+        .map(SidecarApi::for_record)
+        .unwrap_or_default();
+    let input = CommandInput {
+        kind: CommandKind::Exec,
+        command: &request.command,
+        cwd: &request.cwd,
+        stdin_file: &request.stdin_file,
+        timeout_ms: request.timeout_ms,
+    };
+    enforce_command_policy(sandbox_id, &owner, &input)?;
+    let payload = build_exec_payload_with_stdin(
         &request.command,
         &request.cwd,
         &request.env_json,
         request.timeout_ms,
+        &request.stdin,
+        &request.stdin_file,
     );
 
     let parsed = sidecar_post_json(
@@ -83,13 +145,16 @@ pub async fn run_instance_exec(
     .map_err(|e| e.to_string())?;
 
     crate::runtime::touch_sandbox(sandbox_id);
+    record_usage(&owner, UsageTotals::exec());
 
-    let (exit_code, stdout, stderr) = extract_exec_fields(&parsed);
+    let (exit_code, stdout, stderr) = extract_exec_fields_for(api, &parsed);
+    let output = cap_exec_output(sandbox_id, stdout, stderr);
 
     Ok(InstanceExecResponse {
         exit_code,
-        stdout,
-        stderr,
+        stdout: output.stdout,
+        stderr: output.stderr,
+        overflow_ref: output.overflow_ref.unwrap_or_default(),
     })
 }
 
@@ -97,7 +162,7 @@ pub async fn instance_exec(
     Caller(_caller): Caller,
     TangleArg(request): TangleArg<InstanceExecRequest>,
 ) -> Result<TangleResult<InstanceExecResponse>, String> {
-    let sandbox = require_instance_sandbox()?;
+    let sandbox = require_instance_sandbox_slot(&request.slot)?;
     let resp =
         run_instance_exec(&sandbox.sidecar_url, &sandbox.token, &sandbox.id, &request).await?;
     Ok(TangleResult(resp))
@@ -108,6 +173,7 @@ pub async fn instance_exec(
 // ─────────────────────────────────────────────────────────────────────────────
 
 pub fn build_agent_payload(
+    agent_identifier: &str,
     message: &str,
     session_id: &str,
     model: &str,
@@ -116,9 +182,13 @@ pub fn build_agent_payload(
     extra_metadata: Option<Map<String, Value>>,
 ) -> Result<Map<String, Value>, String> {
     let mut payload = Map::new();
+    let identifier = match agent_identifier.trim() {
+        "" => sandbox_runtime::runtime::DEFAULT_AGENT_IDENTIFIER,
+        id => id,
+    };
     payload.insert(
         "identifier".to_string(),
-        Value::String("default".to_string()),
+        Value::String(identifier.to_string()),
     );
     payload.insert("message".to_string(), Value::String(message.to_string()));
 
@@ -146,6 +216,10 @@ pub fn build_agent_payload(
         metadata.extend(extra);
     }
 
+    if let Some(seed) = crate::verification::deterministic_seed(&metadata) {
+        crate::verification::apply_deterministic_hints(&mut payload, seed);
+    }
+
     if !metadata.is_empty() {
         payload.insert("metadata".to_string(), Value::Object(metadata));
     }
@@ -166,6 +240,8 @@ pub struct AgentResponse {
     pub input_tokens: u32,
     pub output_tokens: u32,
     pub session_id: String,
+    /// Tool policy echoed in the response metadata (see `tool_policy`).
+    pub tools: String,
 }
 
 pub fn parse_agent_response(parsed: &Value, fallback_session_id: &str) -> AgentResponse {
@@ -207,6 +283,7 @@ pub fn parse_agent_response(parsed: &Value, fallback_session_id: &str) -> AgentR
         input_tokens,
         output_tokens,
         session_id,
+        tools: sandbox_runtime::tool_policy::echoed_tools(parsed),
     }
 }
 
@@ -214,13 +291,18 @@ pub async fn call_agent(
     sidecar_url: &str,
     sidecar_token: &str,
     sandbox_id: &str,
-    payload: Map<String, Value>,
+    mut payload: Map<String, Value>,
     fallback_session_id: &str,
 ) -> Result<AgentResponse, String> {
     crate::runtime::touch_sandbox(sandbox_id);
+    apply_prompt_template(sandbox_id, &mut payload)?;
+    // Wait for the sandbox's agent run limit (see `sandbox_runtime::agent_runs`).
+    let mut agent_run = reserve_agent_run(sandbox_id).map_err(|e| e.to_string())?;
+    agent_run.ready().await;
 
     let m = crate::metrics::metrics();
     let _session = m.session_guard();
+    let model = payload_model(&payload);
 
     let parsed = sidecar_post_json(
         sidecar_url,
@@ -235,6 +317,11 @@ pub async fn call_agent(
 
     if resp.success {
         m.record_job(resp.duration_ms, resp.input_tokens, resp.output_tokens);
+        record_model_usage(&model, resp.input_tokens, resp.output_tokens);
+        record_sandbox_usage(
+            sandbox_id,
+            UsageTotals::tokens(resp.input_tokens, resp.output_tokens),
+        );
     } else {
         m.record_failure();
     }
@@ -242,34 +329,79 @@ pub async fn call_agent(
     Ok(resp)
 }
 
+/// Whether a failed run resumed named session `session_id` that the sidecar
+/// no longer has, so it should be retried in a new session.
+fn named_session_is_stale(
+    session_name: &str,
+    session_id: &str,
+    success: bool,
+    error: &str,
+) -> bool {
+    !session_name.trim().is_empty()
+        && !session_id.is_empty()
+        && !success
+        && is_stale_session_error(error)
+}
+
+/// Point `session_name` at the session a successful run ended in.
+fn keep_named_session(sandbox_id: &str, owner: &str, session_name: &str, session_id: &str) {
+    if let Err(e) = record_named_session(sandbox_id, owner, session_name, session_id) {
+        tracing::warn!(sandbox_id, session_name, error = %e, "failed to record named session");
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Prompt
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Core prompt logic — testable without TangleArg extractors.
+///
+/// With `session_name`, the run continues the sandbox owner's named session
+/// (see `sandbox_runtime::named_sessions`).
 pub async fn run_instance_prompt(
     sidecar_url: &str,
     sidecar_token: &str,
     sandbox_id: &str,
     request: &InstancePromptRequest,
 ) -> Result<InstancePromptResponse, String> {
-    let payload = build_agent_payload(
-        &request.message,
-        &request.session_id,
-        &request.model,
-        &request.context_json,
-        request.timeout_ms,
-        None,
-    )?;
-
-    let resp = call_agent(
-        sidecar_url,
-        sidecar_token,
+    let record = sandbox_runtime::runtime::get_sandbox_by_id(sandbox_id).ok();
+    let owner = record.as_ref().map(|r| r.owner.clone()).unwrap_or_default();
+    let grant = check_model(&request.model, request.timeout_ms)?;
+    let agent_identifier = resolve_agent_identifier(&request.agent_identifier, record.as_ref());
+    let session_id = resolve_session(
         sandbox_id,
-        payload,
+        &owner,
+        &request.session_name,
         &request.session_id,
     )
-    .await?;
+    .map_err(|e| e.to_string())?;
+    let run = async |session_id: &str| -> Result<AgentResponse, String> {
+        let mut payload = build_agent_payload(
+            &agent_identifier,
+            &request.message,
+            session_id,
+            &request.model,
+            &request.context_json,
+            grant.timeout_ms,
+            None,
+        )?;
+        grant.apply(&mut payload);
+        call_agent(sidecar_url, sidecar_token, sandbox_id, payload, session_id).await
+    };
+
+    let mut resp = run(&session_id).await?;
+    if named_session_is_stale(
+        &request.session_name,
+        &session_id,
+        resp.success,
+        &resp.error,
+    ) {
+        let _ = forget_named_session(sandbox_id, &owner, &request.session_name);
+        resp = run("").await?;
+    }
+    if resp.success {
+        keep_named_session(sandbox_id, &owner, &request.session_name, &resp.session_id);
+    }
 
     Ok(InstancePromptResponse {
         success: resp.success,
@@ -286,7 +418,8 @@ pub async fn instance_prompt(
     Caller(_caller): Caller,
     TangleArg(request): TangleArg<InstancePromptRequest>,
 ) -> Result<TangleResult<InstancePromptResponse>, String> {
-    let sandbox = require_instance_sandbox()?;
+    let sandbox = require_instance_sandbox_slot(&request.slot)?;
+    sandbox_runtime::disk_quota::ensure_disk_writable(&sandbox.id)?;
     let resp =
         run_instance_prompt(&sandbox.sidecar_url, &sandbox.token, &sandbox.id, &request).await?;
     Ok(TangleResult(resp))
@@ -297,53 +430,94 @@ pub async fn instance_prompt(
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Core task logic — testable without TangleArg extractors.
+///
+/// With `output_schema_json` set, the result is validated against it and
+/// repaired once on mismatch (see `sandbox_runtime::output_schema`). With
+/// `session_name`, the run continues the sandbox owner's named session (see
+/// `sandbox_runtime::named_sessions`).
 pub async fn run_instance_task(
     sidecar_url: &str,
     sidecar_token: &str,
     sandbox_id: &str,
     request: &InstanceTaskRequest,
 ) -> Result<InstanceTaskResponse, String> {
-    let mut extra = Map::new();
-    if request.max_turns > 0 {
-        extra.insert("maxTurns".to_string(), json!(request.max_turns));
-        extra.insert("maxSteps".to_string(), json!(request.max_turns));
-    }
-
-    let payload = build_agent_payload(
-        &request.prompt,
-        &request.session_id,
-        &request.model,
-        &request.context_json,
-        request.timeout_ms,
-        if extra.is_empty() { None } else { Some(extra) },
-    )?;
-
-    let resp = call_agent(
-        sidecar_url,
-        sidecar_token,
+    let schema = parse_output_schema(&request.output_schema_json)?;
+    let tools = parse_tool_policy(&request.tools_json)?;
+    let grant = check_model(&request.model, request.timeout_ms)?;
+    let record = sandbox_runtime::runtime::get_sandbox_by_id(sandbox_id).ok();
+    let owner = record.as_ref().map(|r| r.owner.clone()).unwrap_or_default();
+    let agent_identifier = resolve_agent_identifier(&request.agent_identifier, record.as_ref());
+    let session_id = resolve_session(
         sandbox_id,
-        payload,
+        &owner,
+        &request.session_name,
         &request.session_id,
     )
-    .await?;
+    .map_err(|e| e.to_string())?;
+    let run = async |message: String, session_id: String| -> Result<InstanceTaskResponse, String> {
+        let mut extra = Map::new();
+        if request.max_turns > 0 {
+            extra.insert("maxTurns".to_string(), json!(request.max_turns));
+            extra.insert("maxSteps".to_string(), json!(request.max_turns));
+        }
 
-    Ok(InstanceTaskResponse {
-        success: resp.success,
-        result: resp.response,
-        error: resp.error,
-        trace_id: resp.trace_id,
-        duration_ms: resp.duration_ms,
-        input_tokens: resp.input_tokens,
-        output_tokens: resp.output_tokens,
-        session_id: resp.session_id,
-    })
+        let mut payload = build_agent_payload(
+            &agent_identifier,
+            &message,
+            &session_id,
+            &request.model,
+            &request.context_json,
+            grant.timeout_ms,
+            if extra.is_empty() { None } else { Some(extra) },
+        )?;
+        grant.apply(&mut payload);
+        insert_output_schema(&mut payload, schema.as_ref());
+        insert_tool_policy(&mut payload, tools.as_ref());
+
+        let resp = call_agent(sidecar_url, sidecar_token, sandbox_id, payload, &session_id).await?;
+
+        let mut output = InstanceTaskResponse {
+            success: resp.success,
+            result: resp.response,
+            error: resp.error,
+            trace_id: resp.trace_id,
+            duration_ms: resp.duration_ms,
+            input_tokens: resp.input_tokens,
+            output_tokens: resp.output_tokens,
+            session_id: resp.session_id,
+            tools_json: resp.tools,
+        };
+        require_tool_echo(&mut output, tools.as_ref());
+        Ok(output)
+    };
+    let mut output =
+        run_with_output_schema(schema.as_ref(), &request.prompt, &session_id, &run).await?;
+    if named_session_is_stale(
+        &request.session_name,
+        &session_id,
+        output.success,
+        &output.error,
+    ) {
+        let _ = forget_named_session(sandbox_id, &owner, &request.session_name);
+        output = run_with_output_schema(schema.as_ref(), &request.prompt, "", &run).await?;
+    }
+    if output.success {
+        keep_named_session(
+            sandbox_id,
+            &owner,
+            &request.session_name,
+            &output.session_id,
+        );
+    }
+    Ok(output)
 }
 
 pub async fn instance_task(
     Caller(_caller): Caller,
     TangleArg(request): TangleArg<InstanceTaskRequest>,
 ) -> Result<TangleResult<InstanceTaskResponse>, String> {
-    let sandbox = require_instance_sandbox()?;
+    let sandbox = require_instance_sandbox_slot(&request.slot)?;
+    sandbox_runtime::disk_quota::ensure_disk_writable(&sandbox.id)?;
     let resp =
         run_instance_task(&sandbox.sidecar_url, &sandbox.token, &sandbox.id, &request).await?;
     Ok(TangleResult(resp))
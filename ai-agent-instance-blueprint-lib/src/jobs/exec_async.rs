@@ -0,0 +1,104 @@
+use serde_json::json;
+
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::{InstanceExecRequest, InstanceExecResultRequest, JsonResponse};
+use sandbox_runtime::api_types::ExecApiRequest;
+use sandbox_runtime::executions::{get_execution, start_execution};
+use sandbox_runtime::result_commitment::commit_large_result;
+
+/// Core async exec logic — testable without TangleArg extractors.
+///
+/// Starts `request.command` in the background on the sandbox in
+/// `request.slot` and returns its execution ID at once, so commands may run
+/// far longer than the job timeout. `timeout_ms` bounds the command itself
+/// (0 = one hour). Owner-only.
+pub fn run_instance_exec_async(
+    caller: &str,
+    request: &InstanceExecRequest,
+) -> Result<String, String> {
+    let record = super::require_slot_owner(caller, &request.slot)?;
+    let execution = start_execution(&record, caller, &ExecApiRequest::from(request))
+        .map_err(|e| e.to_string())?;
+    Ok(json!({
+        "executionId": execution.id,
+        "sandboxId": execution.sandbox_id,
+        "status": execution.status,
+        "timeoutMs": execution.timeout_ms,
+    })
+    .to_string())
+}
+
+/// Core result polling logic — testable without TangleArg extractors.
+///
+/// Returns the execution's status and, once it finished, its exit code and
+/// capped output (with `overflowRef` when the output was cut). Oversized
+/// results are replaced by a [`sandbox_runtime::result_commitment`] stub.
+pub fn run_instance_exec_result(
+    caller: &str,
+    slot: &str,
+    execution_id: &str,
+) -> Result<String, String> {
+    let record = super::require_slot_owner(caller, slot)?;
+    let execution = get_execution(&record.id, execution_id).map_err(|e| e.to_string())?;
+    let result = json!({
+        "executionId": execution.id,
+        "sandboxId": execution.sandbox_id,
+        "status": execution.status,
+        "exitCode": execution.exit_code,
+        "stdout": execution.stdout,
+        "stderr": execution.stderr,
+        "overflowRef": execution.overflow_ref.unwrap_or_default(),
+        "error": execution.error,
+        "createdAt": execution.created_at,
+        "completedAt": execution.completed_at,
+    });
+    Ok(commit_large_result(
+        &record.id,
+        result,
+        &["executionId", "sandboxId", "status", "exitCode"],
+    ))
+}
+
+/// Start a background command in the instance sandbox. Owner-only.
+pub async fn instance_exec_async(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceExecRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_EXEC_ASYNC,
+        super::job_input(&caller, &request),
+        async move {
+            let json = run_instance_exec_async(&super::caller_hex(&caller), &request)?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
+
+/// Poll a background command started by [`instance_exec_async`]. Owner-only.
+pub async fn instance_exec_result(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceExecResultRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::traced(
+        service_id,
+        call_id,
+        crate::JOB_EXEC_RESULT,
+        super::job_input(&caller, &request),
+        async move {
+            let json = run_instance_exec_result(
+                &super::caller_hex(&caller),
+                &request.slot,
+                &request.execution_id,
+            )?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
@@ -1,10 +1,81 @@
+pub mod backup;
+pub mod config;
 pub mod exec;
+pub mod exec_async;
 pub mod provision;
+pub mod repair;
+pub mod session_export;
 pub mod snapshot;
 pub mod ssh;
+pub mod status;
+pub mod task_async;
+pub mod upgrade;
 pub mod workflow;
 
-pub(crate) fn caller_hex(caller: &[u8; 20]) -> String {
+use blueprint_sdk::alloy::sol_types::{SolType, SolValue};
+
+use crate::tangle::extract::TangleResult;
+use sandbox_runtime::job_replay::JobInput;
+use sandbox_runtime::job_trace::{self, annotate_json};
+pub use sandbox_runtime::job_trace::{JobOutput, job_input};
+
+/// Lower-case `0x` hex form of a job caller address.
+pub fn caller_hex(caller: &[u8; 20]) -> String {
     let addr = blueprint_sdk::alloy::primitives::Address::from_slice(caller);
     format!("{addr:#x}")
 }
+
+impl JobOutput for crate::JsonResponse {
+    fn annotate(&mut self, trace: &job_trace::JobTrace) {
+        self.json = annotate_json(&self.json, trace);
+    }
+}
+
+/// [`job_trace::traced`], keeping [`crate::is_read_only_job`] jobs out of the
+/// replay log.
+pub async fn traced<T, Fut>(
+    service_id: u64,
+    call_id: u64,
+    job: u8,
+    input: JobInput,
+    run: Fut,
+) -> Result<TangleResult<T>, String>
+where
+    T: JobOutput + SolValue,
+    Fut: std::future::Future<Output = Result<T, String>>,
+{
+    let read_only = crate::is_read_only_job(job);
+    job_trace::traced(service_id, call_id, job, read_only, input, run)
+        .await
+        .map(TangleResult)
+}
+
+/// [`sandbox_runtime::job_dedup::once`] for handlers returning [`TangleResult`].
+pub async fn once<T, Fut>(
+    service_id: u64,
+    call_id: u64,
+    job: u8,
+    input: JobInput,
+    run: Fut,
+) -> Result<TangleResult<T>, String>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType> + JobOutput,
+    Fut: std::future::Future<Output = Result<TangleResult<T>, String>>,
+{
+    let run = async move { run.await.map(|TangleResult(output)| output) };
+    sandbox_runtime::job_dedup::once(service_id, call_id, job, input, run)
+        .await
+        .map(TangleResult)
+}
+
+/// Resolve the sandbox in `slot` and check that `caller` owns it.
+pub fn require_slot_owner(caller: &str, slot: &str) -> Result<crate::SandboxRecord, String> {
+    let record = crate::slots::require_instance_sandbox_slot(slot)?;
+    if !record.owner.is_empty() && !record.owner.eq_ignore_ascii_case(caller) {
+        return Err(format!(
+            "Caller {caller} does not own instance sandbox {}",
+            record.id
+        ));
+    }
+    Ok(record)
+}
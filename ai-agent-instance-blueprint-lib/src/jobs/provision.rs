@@ -6,35 +6,70 @@ use crate::ProvisionOutput;
 use crate::ProvisionRequest;
 use crate::SandboxRecord;
 use crate::runtime::{create_sidecar, delete_sidecar};
+use crate::slots::{
+    acquire_slots_lock, clear_instance_sandbox_slot, get_instance_sandbox_slot, instance_max_slots,
+    is_main_slot, provisioned_slot_count, require_instance_sandbox_slot, set_instance_sandbox_slot,
+};
 use crate::tee::TeeBackend;
-use crate::{clear_instance_sandbox, require_instance_sandbox};
+use sandbox_runtime::templates::apply_template;
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Core logic (reusable by TEE blueprint)
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// Provision a sandbox, optionally inside a TEE.
+/// Provision a sandbox into `request.slot` (empty = `main`), optionally inside a TEE.
 ///
 /// `owner` is the hex address of the service requester (e.g. `"0xabcdef..."`).
 /// When called from auto-provision, this is read from `serviceOwner(serviceId)` on-chain.
 ///
-/// Returns the `ProvisionOutput` (for on-chain result) and the `SandboxRecord`
-/// (for local persistent storage). The caller is responsible for storing the
-/// record via `set_instance_sandbox`.
+/// The slot check, sandbox creation and slot record write run under
+/// [`acquire_slots_lock`], so concurrent provisions cannot both take a slot.
+/// Returns the `ProvisionOutput` (for on-chain result) and the stored
+/// `SandboxRecord`; callers that adjust the record (e.g. binding the service
+/// ID) store it again via `set_instance_sandbox_slot(&request.slot, ..)`.
 pub async fn provision_core(
     request: &ProvisionRequest,
     tee: Option<&dyn TeeBackend>,
     owner: &str,
 ) -> Result<(ProvisionOutput, SandboxRecord), String> {
-    // Fail if already provisioned — deprovision first.
-    if crate::get_instance_sandbox()
+    let _slots = acquire_slots_lock().await;
+    provision_locked(request, tee, owner).await
+}
+
+/// [`provision_core`] for a caller already holding [`acquire_slots_lock`].
+pub(crate) async fn provision_locked(
+    request: &ProvisionRequest,
+    tee: Option<&dyn TeeBackend>,
+    owner: &str,
+) -> Result<(ProvisionOutput, SandboxRecord), String> {
+    let main_slot = is_main_slot(&request.slot).map_err(|e| e.to_string())?;
+
+    // Fail if the slot is already provisioned — deprovision first.
+    if get_instance_sandbox_slot(&request.slot)
         .map_err(|e| e.to_string())?
         .is_some()
     {
-        return Err("Instance already provisioned — deprovision first".to_string());
+        return Err(if main_slot {
+            "Instance already provisioned — deprovision first".to_string()
+        } else {
+            format!(
+                "Instance slot '{}' already provisioned — deprovision first",
+                request.slot.trim()
+            )
+        });
+    }
+    // The cap only gates extra slots so auto-provision of `main` is never blocked.
+    let max_slots = instance_max_slots();
+    if !main_slot && provisioned_slot_count().map_err(|e| e.to_string())? >= max_slots {
+        return Err(format!(
+            "Instance slot limit reached ({max_slots}); deprovision a slot first"
+        ));
     }
 
     let mut params = CreateSandboxParams::from(request);
+    if !request.template.trim().is_empty() {
+        apply_template(&mut params, &request.template).map_err(|e| e.to_string())?;
+    }
     params.owner = owner.to_string();
     if request.tee_required
         && !request.attestation_nonce.trim().is_empty()
@@ -47,10 +82,18 @@ pub async fn provision_core(
     let (record, attestation) = create_sidecar(&params, tee)
         .await
         .map_err(|e| e.to_string())?;
+    set_instance_sandbox_slot(&request.slot, record.clone()).map_err(|e| e.to_string())?;
 
     // Provision SSH key if requested.
     if request.ssh_enabled && !request.ssh_public_key.trim().is_empty() {
-        sandbox_runtime::runtime::provision_ssh_key(&record, None, &request.ssh_public_key).await?;
+        sandbox_runtime::runtime::provision_ssh_key(
+            &record,
+            None,
+            &request.ssh_public_key,
+            None,
+            &record.owner,
+        )
+        .await?;
     }
 
     let ssh_port = record.ssh_port.unwrap_or(0) as u32;
@@ -102,13 +145,22 @@ pub async fn provision_core(
     Ok((output, record))
 }
 
-/// Deprovision the instance sandbox, optionally tearing down a TEE deployment.
+/// Deprovision the `main` slot sandbox, optionally tearing down a TEE deployment.
 ///
 /// Returns the JSON response body and the sandbox ID that was deprovisioned.
 pub async fn deprovision_core(
     tee: Option<&dyn TeeBackend>,
 ) -> Result<(JsonResponse, String), String> {
-    let record = require_instance_sandbox()?;
+    deprovision_slot_core("", tee).await
+}
+
+/// Deprovision the sandbox in `slot` (empty = `main`).
+pub async fn deprovision_slot_core(
+    slot: &str,
+    tee: Option<&dyn TeeBackend>,
+) -> Result<(JsonResponse, String), String> {
+    let _slots = acquire_slots_lock().await;
+    let record = require_instance_sandbox_slot(slot)?;
     delete_sidecar(&record, tee)
         .await
         .map_err(|e| e.to_string())?;
@@ -118,7 +170,7 @@ pub async fn deprovision_core(
         .map_err(|e| e.to_string())?
         .remove(&record.id);
 
-    clear_instance_sandbox().map_err(|e| e.to_string())?;
+    clear_instance_sandbox_slot(slot).map_err(|e| e.to_string())?;
 
     let sandbox_id = record.id.clone();
     let response = json!({
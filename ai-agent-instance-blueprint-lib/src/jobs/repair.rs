@@ -0,0 +1,62 @@
+use serde_json::json;
+
+use crate::InstanceRepairRequest;
+use crate::JsonResponse;
+use crate::runtime::{acquire_lifecycle_lock, repair_sandbox, sync_instance_slot_record};
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::tee::TeeBackend;
+
+/// Core repair logic — testable without TangleArg extractors.
+///
+/// Diagnoses the sandbox in `slot` (missing container, exited container,
+/// unhealthy sidecar, token mismatch) and fixes only what is broken, keeping
+/// the workspace wherever the container or a snapshot still holds it. A
+/// healthy instance is reported with no actions.
+pub async fn run_instance_repair(
+    caller: &str,
+    slot: &str,
+    tee: Option<&dyn TeeBackend>,
+) -> Result<String, String> {
+    let record = super::require_slot_owner(caller, slot)?;
+
+    let _lock = acquire_lifecycle_lock(&record.id).await;
+    let result = repair_sandbox(&record.id, tee).await;
+    sync_instance_slot_record(&record.id).map_err(|e| e.to_string())?;
+    let outcome = result?;
+    if !outcome.actions.is_empty() {
+        crate::runtime::touch_sandbox(&record.id);
+        sandbox_runtime::circuit_breaker::mark_healthy(&record.id);
+    }
+
+    Ok(json!({
+        "sandboxId": outcome.record.id,
+        "sidecarUrl": outcome.record.sidecar_url,
+        "issues": outcome.diagnosis.issues,
+        "actions": outcome.actions,
+        "repaired": !outcome.actions.is_empty(),
+        "workspacePreserved": outcome.workspace_preserved,
+        "diagnosis": outcome.diagnosis,
+    })
+    .to_string())
+}
+
+/// Diagnose and repair the instance sandbox in place. Owner-only.
+pub async fn instance_repair(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceRepairRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_REPAIR,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let json = run_instance_repair(&caller_hex, &request.slot, None).await?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
@@ -0,0 +1,63 @@
+use serde_json::json;
+
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::{InstanceSessionExportRequest, JsonResponse};
+use sandbox_runtime::session_export::{
+    build_session_export, upload_session_export, validate_export_destination,
+};
+
+/// Core session export logic — testable without TangleArg extractors.
+///
+/// Uploads the caller's chat sessions on the sandbox in `slot` (or only
+/// `session_id`) to `destination` as a
+/// [`SessionExport`](sandbox_runtime::session_export::SessionExport).
+pub async fn run_instance_session_export(
+    caller: &str,
+    slot: &str,
+    session_id: &str,
+    destination: &str,
+) -> Result<String, String> {
+    if destination.trim().is_empty() {
+        return Err("Export destination is required".to_string());
+    }
+    validate_export_destination(destination)?;
+    let record = super::require_slot_owner(caller, slot)?;
+    let export = build_session_export(&record.id, caller, session_id.trim())?;
+    let bytes = upload_session_export(destination, &export).await?;
+
+    Ok(json!({
+        "sandboxId": record.id,
+        "destination": destination.trim(),
+        "sessions": export.sessions.len(),
+        "messages": export.message_count(),
+        "bytes": bytes,
+        "exportedAt": export.exported_at,
+    })
+    .to_string())
+}
+
+/// Export the instance sandbox's chat history to a URL. Owner-only.
+pub async fn instance_session_export(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceSessionExportRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_SESSION_EXPORT,
+        super::job_input(&caller, &request),
+        async move {
+            let json = run_instance_session_export(
+                &super::caller_hex(&caller),
+                &request.slot,
+                &request.session_id,
+                &request.destination,
+            )
+            .await?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
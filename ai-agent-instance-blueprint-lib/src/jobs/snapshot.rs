@@ -6,6 +6,7 @@ use crate::http::sidecar_post_json;
 use crate::require_instance_sandbox;
 use crate::tangle::extract::{Caller, TangleArg, TangleResult};
 use crate::util::build_snapshot_command;
+use sandbox_runtime::exec_policy::{CommandInput, CommandKind, enforce_command_policy};
 
 /// Core snapshot logic — testable without TangleArg extractors.
 pub async fn run_instance_snapshot(
@@ -22,6 +23,15 @@ pub async fn run_instance_snapshot(
 
     let command = build_snapshot_command(destination, include_workspace, include_state)
         .map_err(|e| e.to_string())?;
+    let owner = crate::runtime::get_sandbox_by_id(sandbox_id)
+        .map(|record| record.owner)
+        .unwrap_or_default();
+    let input = CommandInput {
+        kind: CommandKind::Snapshot,
+        command: &command,
+        ..Default::default()
+    };
+    enforce_command_policy(sandbox_id, &owner, &input)?;
 
     let payload = json!({
         "command": format!("sh -c {}", crate::util::shell_escape(&command)),
@@ -32,6 +42,12 @@ pub async fn run_instance_snapshot(
         .map_err(|e| e.to_string())?;
 
     crate::runtime::touch_sandbox(sandbox_id);
+    crate::runtime::record_lifecycle_event(
+        sandbox_id,
+        crate::runtime::LifecycleEventKind::Snapshotted,
+        &owner,
+        Some(destination.to_string()),
+    );
 
     Ok(response.to_string())
 }
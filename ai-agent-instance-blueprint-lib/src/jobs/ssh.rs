@@ -1,11 +1,12 @@
 use serde_json::Value;
 
+use crate::InstanceSshListRequest;
 use crate::InstanceSshProvisionRequest;
 use crate::InstanceSshRevokeRequest;
 use crate::JsonResponse;
 use crate::require_instance_sandbox;
-use crate::runtime::get_sandbox_by_url;
-use crate::tangle::extract::{Caller, TangleArg, TangleResult};
+use crate::runtime::{get_sandbox_by_url, list_authorized_ssh_keys};
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
 
 pub async fn provision_key(
     sidecar_url: &str,
@@ -14,10 +15,15 @@ pub async fn provision_key(
     _token: &str,
 ) -> Result<Value, String> {
     let record = get_sandbox_by_url(sidecar_url).map_err(|e| e.to_string())?;
-    let (_, result) =
-        sandbox_runtime::runtime::provision_ssh_key(&record, Some(username), public_key)
-            .await
-            .map_err(|e| e.to_string())?;
+    let (_, result) = sandbox_runtime::runtime::provision_ssh_key(
+        &record,
+        Some(username),
+        public_key,
+        None,
+        &record.owner,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
     Ok(result)
 }
 
@@ -28,22 +34,35 @@ pub async fn revoke_key(
     _token: &str,
 ) -> Result<Value, String> {
     let record = get_sandbox_by_url(sidecar_url).map_err(|e| e.to_string())?;
-    let (_, result) = sandbox_runtime::runtime::revoke_ssh_key(&record, Some(username), public_key)
-        .await
-        .map_err(|e| e.to_string())?;
+    let (_, result) = sandbox_runtime::runtime::revoke_ssh_key(
+        &record,
+        Some(username),
+        public_key,
+        &record.owner,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
     Ok(result)
 }
 
 pub async fn instance_ssh_provision(
-    Caller(_caller): Caller,
+    Caller(caller): Caller,
     TangleArg(request): TangleArg<InstanceSshProvisionRequest>,
 ) -> Result<TangleResult<JsonResponse>, String> {
+    let caller_hex = super::caller_hex(&caller);
     let sandbox = require_instance_sandbox()?;
 
+    let expires_at = sandbox_runtime::runtime::ssh_key_expiry(
+        crate::util::now_ts(),
+        Some(request.expires_in_seconds),
+    )
+    .map_err(|e| e.to_string())?;
     let (username, result) = sandbox_runtime::runtime::provision_ssh_key(
         &sandbox,
         Some(request.username.as_str()),
         &request.public_key,
+        expires_at,
+        &caller_hex,
     )
     .await
     .map_err(|e| e.to_string())?;
@@ -55,21 +74,24 @@ pub async fn instance_ssh_provision(
             "success": true,
             "username": username,
             "result": result.get("result").cloned().unwrap_or(result),
+            "expiresAt": expires_at,
         })
         .to_string(),
     }))
 }
 
 pub async fn instance_ssh_revoke(
-    Caller(_caller): Caller,
+    Caller(caller): Caller,
     TangleArg(request): TangleArg<InstanceSshRevokeRequest>,
 ) -> Result<TangleResult<JsonResponse>, String> {
+    let caller_hex = super::caller_hex(&caller);
     let sandbox = require_instance_sandbox()?;
 
     let (username, result) = sandbox_runtime::runtime::revoke_ssh_key(
         &sandbox,
         Some(request.username.as_str()),
         &request.public_key,
+        &caller_hex,
     )
     .await
     .map_err(|e| e.to_string())?;
@@ -85,3 +107,42 @@ pub async fn instance_ssh_revoke(
         .to_string(),
     }))
 }
+
+/// Core SSH key listing — testable without TangleArg extractors.
+///
+/// Reads the login user's `authorized_keys` in `slot` and returns each key's
+/// fingerprint and comment, with provisioning time and expiry for keys the
+/// operator installed.
+pub async fn run_instance_ssh_list(caller: &str, slot: &str) -> Result<String, String> {
+    let record = super::require_slot_owner(caller, slot)?;
+    let (username, keys) = list_authorized_ssh_keys(&record)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "sandboxId": record.id,
+        "username": username,
+        "keys": keys,
+    })
+    .to_string())
+}
+
+/// List the SSH keys with access to the instance sandbox. Owner-only.
+pub async fn instance_ssh_list(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceSshListRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::traced(
+        service_id,
+        call_id,
+        crate::JOB_SSH_LIST,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let json = run_instance_ssh_list(&caller_hex, &request.slot).await?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
@@ -0,0 +1,51 @@
+use serde_json::json;
+
+use crate::InstanceStatusRequest;
+use crate::JsonResponse;
+use crate::provision_output_from_record;
+use crate::runtime::probe_sandbox_status;
+use crate::slots::require_instance_sandbox_slot;
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+
+/// Core status logic — testable without TangleArg extractors.
+///
+/// Returns the slot's provision output (the same fields reported on-chain at
+/// provision time) plus a live probe of the sidecar and container. Probe
+/// failures are reported in the response rather than failing the job, so a
+/// dead instance still answers.
+pub async fn run_instance_status(slot: &str) -> Result<String, String> {
+    let record = require_instance_sandbox_slot(slot)?;
+    let output = provision_output_from_record(&record);
+    let live = probe_sandbox_status(&record).await;
+    Ok(json!({
+        "sandboxId": output.sandbox_id,
+        "sidecarUrl": output.sidecar_url,
+        "sshPort": output.ssh_port,
+        "teeAttestationJson": output.tee_attestation_json,
+        "teePublicKeyJson": output.tee_public_key_json,
+        "healthy": live.health.healthy,
+        "status": live,
+    })
+    .to_string())
+}
+
+/// Report whether the instance sandbox is alive. Read-only; never touches the
+/// sandbox's activity timestamp.
+pub async fn instance_status(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceStatusRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::traced(
+        service_id,
+        call_id,
+        crate::JOB_STATUS,
+        super::job_input(&caller, &request),
+        async move {
+            let json = run_instance_status(&request.slot).await?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
@@ -0,0 +1,175 @@
+use serde_json::json;
+
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::{
+    InstanceTaskCancelRequest, InstanceTaskRequest, InstanceTaskResultRequest, JsonResponse,
+};
+use sandbox_runtime::api_types::TaskApiRequest;
+use sandbox_runtime::result_commitment::commit_large_result;
+use sandbox_runtime::task_queue::{cancel_task, enqueue_task, get_task};
+
+/// Core task queue logic — testable without TangleArg extractors.
+///
+/// Queues `request` on the sandbox in `request.slot` and returns its task ID
+/// at once; progress and the result are read with
+/// [`run_instance_task_result`]. `timeout_ms` bounds the run (0 = one hour).
+/// Structured output, tool allowlists and agent overrides need the
+/// synchronous task job and are rejected here. Owner-only.
+pub fn run_instance_task_async(
+    caller: &str,
+    request: &InstanceTaskRequest,
+) -> Result<String, String> {
+    if !request.output_schema_json.trim().is_empty()
+        || !request.tools_json.trim().is_empty()
+        || !request.agent_identifier.trim().is_empty()
+    {
+        return Err(
+            "output_schema_json, tools_json and agent_identifier are not supported for queued tasks"
+                .to_string(),
+        );
+    }
+    let record = super::require_slot_owner(caller, &request.slot)?;
+    let task = TaskApiRequest {
+        prompt: request.prompt.clone(),
+        session_id: request.session_id.clone(),
+        session_name: request.session_name.clone(),
+        max_turns: request.max_turns,
+        backend_type: String::new(),
+        model: request.model.clone(),
+        context_json: request.context_json.clone(),
+        timeout_ms: request.timeout_ms,
+        slot: request.slot.clone(),
+    };
+    let task = enqueue_task(&record, caller, &task).map_err(|e| e.to_string())?;
+    Ok(json!({
+        "taskId": task.id,
+        "sandboxId": task.sandbox_id,
+        "status": task.status,
+        "timeoutMs": task.timeout_ms,
+    })
+    .to_string())
+}
+
+/// Core result polling logic — testable without TangleArg extractors.
+///
+/// Returns the task's status and progress and, once it finished, its result.
+/// Oversized results are replaced by a
+/// [`sandbox_runtime::result_commitment`] stub.
+pub fn run_instance_task_result(caller: &str, slot: &str, task_id: &str) -> Result<String, String> {
+    let record = super::require_slot_owner(caller, slot)?;
+    let task = get_task(&record.id, task_id).map_err(|e| e.to_string())?;
+    let result = json!({
+        "taskId": task.id,
+        "sandboxId": task.sandbox_id,
+        "status": task.status,
+        "turnsCompleted": task.progress.turns_completed,
+        "currentTool": task.progress.current_tool.unwrap_or_default(),
+        "result": task.result,
+        "error": task.error,
+        "traceId": task.trace_id,
+        "sessionId": task.session_id,
+        "durationMs": task.duration_ms,
+        "inputTokens": task.input_tokens,
+        "outputTokens": task.output_tokens,
+        "createdAt": task.created_at,
+        "completedAt": task.completed_at,
+    });
+    Ok(commit_large_result(
+        &record.id,
+        result,
+        &["taskId", "sandboxId", "status", "traceId"],
+    ))
+}
+
+/// Core cancellation logic — testable without TangleArg extractors.
+///
+/// Stops a queued or running task: a running task's sidecar run is aborted
+/// and its queue slot freed. Returns the task's final status; finished tasks
+/// are an error. Owner-only.
+pub async fn run_instance_task_cancel(
+    caller: &str,
+    slot: &str,
+    task_id: &str,
+) -> Result<String, String> {
+    let record = super::require_slot_owner(caller, slot)?;
+    let task = cancel_task(&record.id, task_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(json!({
+        "taskId": task.id,
+        "sandboxId": task.sandbox_id,
+        "status": task.status,
+        "turnsCompleted": task.progress.turns_completed,
+        "completedAt": task.completed_at,
+    })
+    .to_string())
+}
+
+/// Queue an agent task in the instance sandbox. Owner-only.
+pub async fn instance_task_async(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceTaskRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_TASK_ASYNC,
+        super::job_input(&caller, &request),
+        async move {
+            let json = run_instance_task_async(&super::caller_hex(&caller), &request)?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
+
+/// Poll a task queued by [`instance_task_async`]. Owner-only.
+pub async fn instance_task_result(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceTaskResultRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::traced(
+        service_id,
+        call_id,
+        crate::JOB_TASK_RESULT,
+        super::job_input(&caller, &request),
+        async move {
+            let json = run_instance_task_result(
+                &super::caller_hex(&caller),
+                &request.slot,
+                &request.task_id,
+            )?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
+
+/// Cancel a task queued by [`instance_task_async`]. Owner-only.
+pub async fn instance_task_cancel(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceTaskCancelRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_TASK_CANCEL,
+        super::job_input(&caller, &request),
+        async move {
+            let json = run_instance_task_cancel(
+                &super::caller_hex(&caller),
+                &request.slot,
+                &request.task_id,
+            )
+            .await?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
@@ -0,0 +1,72 @@
+use serde_json::json;
+
+use crate::InstanceUpgradeRequest;
+use crate::JsonResponse;
+use crate::runtime::{
+    acquire_lifecycle_lock, current_sidecar_image, sync_instance_slot_record,
+    upgrade_sidecar_with_rollback,
+};
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::tee::TeeBackend;
+
+/// Core upgrade logic — testable without TangleArg extractors.
+///
+/// Moves the sandbox in `slot` onto `image` (empty = the operator's current
+/// `SIDECAR_IMAGE`), keeping its sandbox ID, token and workspace. If the new
+/// sidecar fails its health check the sandbox is rolled back to the previous
+/// image and an error is returned.
+pub async fn run_instance_upgrade(
+    caller: &str,
+    slot: &str,
+    image: &str,
+    tee: Option<&dyn TeeBackend>,
+) -> Result<String, String> {
+    let record = super::require_slot_owner(caller, slot)?;
+    let target = if image.trim().is_empty() {
+        current_sidecar_image()
+    } else {
+        image.trim().to_string()
+    };
+
+    let _lock = acquire_lifecycle_lock(&record.id).await;
+    let result = upgrade_sidecar_with_rollback(&record.id, &target, tee).await;
+    // Both success and rollback recreate the container; keep the slot copy
+    // of the record in step with the fleet store either way.
+    sync_instance_slot_record(&record.id).map_err(|e| e.to_string())?;
+    let outcome = result?;
+    crate::runtime::touch_sandbox(&record.id);
+    sandbox_runtime::circuit_breaker::mark_healthy(&record.id);
+
+    Ok(json!({
+        "sandboxId": outcome.record.id,
+        "sidecarUrl": outcome.record.sidecar_url,
+        "fromImage": outcome.from_image,
+        "toImage": outcome.to_image,
+        "workspaceBytes": outcome.workspace_bytes,
+        "upgraded": true,
+    })
+    .to_string())
+}
+
+/// Upgrade the instance sandbox to a new sidecar image with health-checked
+/// rollback. Owner-only.
+pub async fn instance_upgrade(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceUpgradeRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_UPGRADE,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let json =
+                run_instance_upgrade(&caller_hex, &request.slot, &request.image, None).await?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
@@ -4,15 +4,19 @@
 //! sandbox. Every operator in the service independently provisions and runs a
 //! copy of the same sandbox configuration. Customers choose how many operators
 //! (1 for simple use, N for redundancy/TEE verification). Each operator binary
-//! manages its own single sandbox — no cross-operator coordination needed.
-//! Exec/prompt/task jobs are instance-scoped: no sidecar URLs or tokens in
-//! the request — the operator looks them up automatically.
+//! manages its own sandboxes — no cross-operator coordination needed. The
+//! primary sandbox lives in the `main` slot; the owner can add named slots
+//! (see [`slots`]). Exec/prompt/task jobs are instance-scoped: no sidecar URLs
+//! or tokens in the request — the operator looks them up by slot.
 
 pub mod auto_provision;
 #[cfg(feature = "billing")]
 pub mod billing;
+mod conversions;
 pub mod jobs;
 pub mod reporting;
+pub mod slots;
+pub mod verification;
 pub mod workflows;
 
 // Re-export sandbox-runtime modules.
@@ -25,7 +29,9 @@ pub use sandbox_runtime::{
     DEFAULT_SIDECAR_SSH_PORT, DEFAULT_TIMEOUT_SECS, SandboxError, SandboxRecord, SandboxState,
     TeeConfig, TeeType,
 };
-pub use sandbox_runtime::{auth, error, http, metrics, reaper, runtime, store, tee, util};
+pub use sandbox_runtime::{
+    auth, error, http, job_error, metrics, reaper, runtime, store, tee, util,
+};
 
 use blueprint_sdk::Job;
 use blueprint_sdk::Router;
@@ -35,20 +41,42 @@ use once_cell::sync::OnceCell;
 use serde_json::Value;
 
 pub use blueprint_sdk::tangle;
+pub use jobs::backup::{
+    BACKUP_MANIFEST_VERSION, InstanceBackupManifest, instance_backup, instance_restore,
+    run_instance_backup, run_instance_restore,
+};
+pub use jobs::config::{instance_config_update, run_instance_config_update};
 pub use jobs::exec::{
-    AgentResponse, build_agent_payload, build_exec_payload, call_agent, extract_exec_fields,
-    parse_agent_response, run_instance_exec, run_instance_prompt, run_instance_task,
+    AgentResponse, build_agent_payload, build_exec_payload, build_exec_payload_with_stdin,
+    call_agent, extract_exec_fields, extract_exec_fields_for, parse_agent_response,
+    run_instance_exec, run_instance_prompt, run_instance_task,
+};
+pub use jobs::exec_async::{
+    instance_exec_async, instance_exec_result, run_instance_exec_async, run_instance_exec_result,
 };
-pub use jobs::provision::{deprovision_core, provision_core};
+pub use jobs::provision::{deprovision_core, deprovision_slot_core, provision_core};
+pub use jobs::repair::{instance_repair, run_instance_repair};
+pub use jobs::session_export::{instance_session_export, run_instance_session_export};
 pub use jobs::snapshot::run_instance_snapshot;
-pub use jobs::ssh::{provision_key, revoke_key};
+pub use jobs::ssh::{instance_ssh_list, provision_key, revoke_key, run_instance_ssh_list};
+pub use jobs::status::{instance_status, run_instance_status};
+pub use jobs::task_async::{
+    instance_task_async, instance_task_cancel, instance_task_result, run_instance_task_async,
+    run_instance_task_cancel, run_instance_task_result,
+};
+pub use jobs::upgrade::{instance_upgrade, run_instance_upgrade};
 pub use jobs::workflow::{workflow_cancel, workflow_create, workflow_tick_job, workflow_trigger};
 pub use reporting::{
     clear_pending_provision_report, ensure_local_provision_reported, get_pending_provision_report,
     mark_pending_provision_report, provision_output_from_record, report_local_deprovision,
-    report_local_provision, retry_pending_provision_report_once,
+    report_local_provision, report_local_provision_failure, retry_pending_provision_report_once,
     spawn_pending_provision_report_worker, try_report_local_deprovision,
 };
+pub use slots::{
+    SlotError, SlotProvisionRequest, clear_instance_sandbox_slot, deprovision_slot,
+    get_instance_sandbox_slot, provision_slot, require_instance_sandbox_slot,
+    set_instance_sandbox_slot,
+};
 pub use workflows::{
     WorkflowDetail, WorkflowRuntimeStatus, WorkflowStatusError, WorkflowSummary,
     bootstrap_workflows_from_chain, list_workflows_for_owner, workflow_detail_for_owner,
@@ -69,14 +97,60 @@ pub const JOB_WORKFLOW_CREATE: u8 = 2;
 pub const JOB_WORKFLOW_TRIGGER: u8 = 3;
 /// Workflow job shared across cloud and instance modes.
 pub const JOB_WORKFLOW_CANCEL: u8 = 4;
+/// Operator lifecycle job (Rust-only): rolling sidecar image upgrade.
+pub const JOB_UPGRADE: u8 = 8;
+/// Operator lifecycle job (Rust-only): live instance health/status read.
+pub const JOB_STATUS: u8 = 9;
+/// Operator lifecycle job (Rust-only): partial instance configuration update.
+pub const JOB_CONFIG_UPDATE: u8 = 10;
+/// Operator lifecycle job (Rust-only): full instance backup to a URL.
+pub const JOB_BACKUP: u8 = 11;
+/// Operator lifecycle job (Rust-only): restore an instance from a backup.
+pub const JOB_RESTORE: u8 = 12;
+/// Operator lifecycle job (Rust-only): diagnose and repair a broken instance.
+pub const JOB_REPAIR: u8 = 13;
+/// Operator lifecycle job (Rust-only): list SSH keys with access to an
+/// instance. 14 and 15 are taken by the TEE instance blueprint.
+pub const JOB_SSH_LIST: u8 = 16;
+/// Operator lifecycle job (Rust-only): start a command in the background and
+/// return its execution ID, for commands that outlive the job timeout.
+pub const JOB_EXEC_ASYNC: u8 = 17;
+/// Operator lifecycle job (Rust-only): status and output of a background
+/// command started by `JOB_EXEC_ASYNC`.
+pub const JOB_EXEC_RESULT: u8 = 18;
+/// Operator lifecycle job (Rust-only): upload the chat session history of an
+/// instance sandbox as a JSON archive.
+pub const JOB_SESSION_EXPORT: u8 = 19;
+/// Operator lifecycle job (Rust-only): queue an agent task and return its
+/// task ID, for multi-turn tasks that outlive the job timeout.
+pub const JOB_TASK_ASYNC: u8 = 20;
+/// Operator lifecycle job (Rust-only): status, progress and result of a task
+/// queued by `JOB_TASK_ASYNC`.
+pub const JOB_TASK_RESULT: u8 = 21;
+/// Operator lifecycle job (Rust-only): cancel a task queued by
+/// `JOB_TASK_ASYNC`, aborting its sidecar run. 22 and 23 are taken by the
+/// sandbox and TEE instance blueprints.
+pub const JOB_TASK_CANCEL: u8 = 24;
 /// Internal cron job — not registered on-chain, never submitted via submitJob.
 pub const JOB_WORKFLOW_TICK: u8 = 255;
 
+/// Whether `job` only reads state (status, listings, results of background
+/// work), so there is nothing in it to replay.
+pub const fn is_read_only_job(job: u8) -> bool {
+    matches!(
+        job,
+        JOB_STATUS | JOB_SSH_LIST | JOB_EXEC_RESULT | JOB_TASK_RESULT
+    )
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // ABI types
 // ─────────────────────────────────────────────────────────────────────────────
 
 sol! {
+    // Debug renders requests for the job replay log (`job_replay`).
+    #![sol(all_derives)]
+
     struct JsonResponse {
         string json;
     }
@@ -118,6 +192,57 @@ sol! {
         /// so instance auto-provision and direct sandbox-create surfaces
         /// expose the same capability set to customers.
         string capabilities_json;
+        /// Optional operator-registered template name (see `GET /api/templates`).
+        /// Mirrors `SandboxCreateRequest.template`.
+        string template;
+        /// Instance slot to provision into (empty = `main`). Auto-provision
+        /// from the service config always targets `main`.
+        string slot;
+    }
+
+    /// Provision request shape before the `slot` field was added.
+    struct ProvisionRequestV3 {
+        string name;
+        string image;
+        string stack;
+        string agent_identifier;
+        string env_json;
+        string metadata_json;
+        bool ssh_enabled;
+        string ssh_public_key;
+        bool web_terminal_enabled;
+        uint64 max_lifetime_seconds;
+        uint64 idle_timeout_seconds;
+        uint64 cpu_cores;
+        uint64 memory_mb;
+        uint64 disk_gb;
+        bool tee_required;
+        uint8 tee_type;
+        string attestation_nonce;
+        string capabilities_json;
+        string template;
+    }
+
+    /// Provision request shape before the `template` field was added.
+    struct ProvisionRequestV2 {
+        string name;
+        string image;
+        string stack;
+        string agent_identifier;
+        string env_json;
+        string metadata_json;
+        bool ssh_enabled;
+        string ssh_public_key;
+        bool web_terminal_enabled;
+        uint64 max_lifetime_seconds;
+        uint64 idle_timeout_seconds;
+        uint64 cpu_cores;
+        uint64 memory_mb;
+        uint64 disk_gb;
+        bool tee_required;
+        uint8 tee_type;
+        string attestation_nonce;
+        string capabilities_json;
     }
 
     /// Provision request shape before deploy-time attestation nonce was added.
@@ -179,6 +304,8 @@ sol! {
     struct InstanceSshProvisionRequest {
         string username;
         string public_key;
+        /// Revoke the key automatically after this many seconds (0 = never).
+        uint64 expires_in_seconds;
     }
 
     struct InstanceSshRevokeRequest {
@@ -186,6 +313,43 @@ sol! {
         string public_key;
     }
 
+    struct InstanceSshListRequest {
+        string slot;
+    }
+
+    // ── Background exec (instance-scoped) ─────────────────────────────────
+    // `JOB_EXEC_ASYNC` takes the shared `InstanceExecRequest`.
+
+    struct InstanceExecResultRequest {
+        string slot;
+        string execution_id;
+    }
+
+    // ── Queued tasks (instance-scoped) ───────────────────────────────────
+    // `JOB_TASK_ASYNC` takes the shared `InstanceTaskRequest`.
+
+    struct InstanceTaskResultRequest {
+        string slot;
+        string task_id;
+    }
+
+    struct InstanceTaskCancelRequest {
+        string slot;
+        string task_id;
+    }
+
+    // ── Chat session export (instance-scoped) ─────────────────────────────
+
+    /// Export the caller's chat history on the slot's sandbox as JSON.
+    /// `session_id` selects one session (operator or sidecar session ID;
+    /// empty = all sessions). `destination` receives the archive via `PUT`
+    /// (`https://`, e.g. a presigned object-store URL).
+    struct InstanceSessionExportRequest {
+        string slot;
+        string session_id;
+        string destination;
+    }
+
     // ── Snapshot (no sidecar_url/token — instance-scoped) ─────────────────
 
     struct InstanceSnapshotRequest {
@@ -194,6 +358,47 @@ sol! {
         bool include_state;
     }
 
+    // ── Image upgrade (instance-scoped) ───────────────────────────────────
+
+    struct InstanceUpgradeRequest {
+        /// Target sidecar image; empty = the operator's current `SIDECAR_IMAGE`.
+        string image;
+        string slot;
+    }
+
+    // ── Status (instance-scoped) ──────────────────────────────────────────
+
+    struct InstanceStatusRequest {
+        string slot;
+    }
+
+    // ── Config update (instance-scoped) ───────────────────────────────────
+
+    struct InstanceConfigUpdateRequest {
+        string slot;
+        /// Partial JSON object: idle_timeout_seconds, max_lifetime_seconds,
+        /// cpu_cores, memory_mb, disk_gb, web_terminal_enabled.
+        string config_json;
+    }
+
+    // ── Backup / restore (instance-scoped) ────────────────────────────────
+
+    struct InstanceBackupRequest {
+        string slot;
+        string destination;
+    }
+
+    struct InstanceRestoreRequest {
+        string slot;
+        string source;
+    }
+
+    // ── Repair (instance-scoped) ──────────────────────────────────────────
+
+    struct InstanceRepairRequest {
+        string slot;
+    }
+
     // ── Workflows (shared ABI with cloud mode) ────────────────────────────
 
     struct WorkflowCreateRequest {
@@ -213,7 +418,7 @@ sol! {
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
-// Instance state — singleton sandbox for this service instance
+// Instance state — primary (`main` slot) sandbox for this service instance
 // ─────────────────────────────────────────────────────────────────────────────
 
 static INSTANCE_STORE: OnceCell<store::PersistentStore<SandboxRecord>> = OnceCell::new();
@@ -230,7 +435,7 @@ pub fn instance_store() -> error::Result<&'static store::PersistentStore<Sandbox
         .map_err(|err: SandboxError| err)
 }
 
-/// Get the provisioned sandbox record for this instance, if any.
+/// Get the provisioned `main` slot sandbox record for this instance, if any.
 pub fn get_instance_sandbox() -> error::Result<Option<SandboxRecord>> {
     match instance_store()?.get(INSTANCE_KEY)? {
         Some(mut r) => {
@@ -260,102 +465,6 @@ pub fn clear_instance_sandbox() -> error::Result<()> {
     Ok(())
 }
 
-// ─────────────────────────────────────────────────────────────────────────────
-// ABI → runtime conversion
-// ─────────────────────────────────────────────────────────────────────────────
-
-impl From<&ProvisionRequest> for CreateSandboxParams {
-    fn from(r: &ProvisionRequest) -> Self {
-        let tee_config = if r.tee_required {
-            Some(TeeConfig {
-                required: true,
-                tee_type: match r.tee_type {
-                    1 => TeeType::Tdx,
-                    2 => TeeType::Nitro,
-                    3 => TeeType::Sev,
-                    _ => TeeType::None,
-                },
-                attestation_nonce: None,
-            })
-        } else {
-            None
-        };
-
-        Self {
-            name: r.name.to_string(),
-            image: r.image.to_string(),
-            stack: r.stack.to_string(),
-            agent_identifier: r.agent_identifier.to_string(),
-            env_json: r.env_json.to_string(),
-            metadata_json: r.metadata_json.to_string(),
-            ssh_enabled: r.ssh_enabled,
-            ssh_public_key: r.ssh_public_key.to_string(),
-            web_terminal_enabled: r.web_terminal_enabled,
-            max_lifetime_seconds: r.max_lifetime_seconds,
-            idle_timeout_seconds: r.idle_timeout_seconds,
-            cpu_cores: r.cpu_cores,
-            memory_mb: r.memory_mb,
-            disk_gb: r.disk_gb,
-            owner: String::new(), // Set by the job handler from Caller extractor
-            service_id: None,
-            tee_config,
-            user_env_json: String::new(),
-            port_mappings: Vec::new(), // Parsed from metadata_json at runtime
-            capabilities_json: r.capabilities_json.to_string(),
-        }
-    }
-}
-
-impl From<LegacyProvisionRequest> for ProvisionRequest {
-    fn from(r: LegacyProvisionRequest) -> Self {
-        Self {
-            name: r.name,
-            image: r.image,
-            stack: r.stack,
-            agent_identifier: r.agent_identifier,
-            env_json: r.env_json,
-            metadata_json: r.metadata_json,
-            ssh_enabled: r.ssh_enabled,
-            ssh_public_key: r.ssh_public_key,
-            web_terminal_enabled: r.web_terminal_enabled,
-            max_lifetime_seconds: r.max_lifetime_seconds,
-            idle_timeout_seconds: r.idle_timeout_seconds,
-            cpu_cores: r.cpu_cores,
-            memory_mb: r.memory_mb,
-            disk_gb: r.disk_gb,
-            tee_required: r.tee_required,
-            tee_type: r.tee_type,
-            attestation_nonce: String::new(),
-            capabilities_json: String::new(),
-        }
-    }
-}
-
-impl From<ProvisionRequestV1> for ProvisionRequest {
-    fn from(r: ProvisionRequestV1) -> Self {
-        Self {
-            name: r.name,
-            image: r.image,
-            stack: r.stack,
-            agent_identifier: r.agent_identifier,
-            env_json: r.env_json,
-            metadata_json: r.metadata_json,
-            ssh_enabled: r.ssh_enabled,
-            ssh_public_key: r.ssh_public_key,
-            web_terminal_enabled: r.web_terminal_enabled,
-            max_lifetime_seconds: r.max_lifetime_seconds,
-            idle_timeout_seconds: r.idle_timeout_seconds,
-            cpu_cores: r.cpu_cores,
-            memory_mb: r.memory_mb,
-            disk_gb: r.disk_gb,
-            tee_required: r.tee_required,
-            tee_type: r.tee_type,
-            attestation_nonce: String::new(),
-            capabilities_json: String::new(),
-        }
-    }
-}
-
 // ─────────────────────────────────────────────────────────────────────────────
 // Agent response parsing (shared between prompt and task)
 // ─────────────────────────────────────────────────────────────────────────────
@@ -401,13 +510,31 @@ pub fn extract_agent_fields(parsed: &Value) -> (bool, String, String, String) {
 
 /// Router that maps job IDs to handlers.
 ///
-/// State-changing operations remain on-chain (workflow + provision lifecycle).
-/// Read-only ops (exec, prompt, task, snapshot, SSH) are served via the
-/// operator HTTP API.
+/// State-changing operations remain on-chain (workflow + provision lifecycle,
+/// image upgrade), along with the SSH key listing so access can be audited
+/// on-chain, background exec and queued tasks for work that outlives the job
+/// timeout, and chat session export. Other read-only ops (exec, prompt, task, snapshot, SSH provisioning) are
+/// served via the operator HTTP API.
 pub fn router() -> Router {
     Router::new()
         .route(JOB_WORKFLOW_CREATE, workflow_create.layer(TangleLayer))
         .route(JOB_WORKFLOW_TRIGGER, workflow_trigger.layer(TangleLayer))
         .route(JOB_WORKFLOW_CANCEL, workflow_cancel.layer(TangleLayer))
+        .route(JOB_UPGRADE, instance_upgrade.layer(TangleLayer))
+        .route(JOB_STATUS, instance_status.layer(TangleLayer))
+        .route(JOB_CONFIG_UPDATE, instance_config_update.layer(TangleLayer))
+        .route(JOB_BACKUP, instance_backup.layer(TangleLayer))
+        .route(JOB_RESTORE, instance_restore.layer(TangleLayer))
+        .route(JOB_REPAIR, instance_repair.layer(TangleLayer))
+        .route(JOB_SSH_LIST, instance_ssh_list.layer(TangleLayer))
+        .route(JOB_EXEC_ASYNC, instance_exec_async.layer(TangleLayer))
+        .route(JOB_EXEC_RESULT, instance_exec_result.layer(TangleLayer))
+        .route(
+            JOB_SESSION_EXPORT,
+            instance_session_export.layer(TangleLayer),
+        )
+        .route(JOB_TASK_ASYNC, instance_task_async.layer(TangleLayer))
+        .route(JOB_TASK_RESULT, instance_task_result.layer(TangleLayer))
+        .route(JOB_TASK_CANCEL, instance_task_cancel.layer(TangleLayer))
         .route(JOB_WORKFLOW_TICK, workflow_tick_job)
 }
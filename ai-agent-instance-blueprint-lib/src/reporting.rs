@@ -20,6 +20,7 @@ sol! {
         ) external;
 
         function reportDeprovisioned(uint64 serviceId) external;
+        function reportProvisionFailed(uint64 serviceId, string reason) external;
     }
 }
 
@@ -251,6 +252,52 @@ pub async fn report_local_provision(
     Ok(())
 }
 
+/// Report a failed auto-provision to the manager contract
+/// (`reportProvisionFailed`) so the service owner can see the operator gave
+/// up. Returns the transaction hash.
+pub async fn report_local_provision_failure(
+    client: &TangleClient,
+    service_id: u64,
+    reason: &str,
+) -> Result<String, String> {
+    let manager = client
+        .get_blueprint_manager(service_id)
+        .await
+        .map_err(|err| {
+            format!("Failed to resolve blueprint manager for service {service_id}: {err}")
+        })?
+        .ok_or_else(|| format!("No blueprint manager found for service {service_id}"))?;
+
+    let wallet = client
+        .wallet()
+        .map_err(|err| format!("Failed to load operator wallet: {err}"))?;
+    let provider = ProviderBuilder::new()
+        .wallet(wallet)
+        .connect(client.config.http_rpc_endpoint.as_str())
+        .await
+        .map_err(|err| format!("Failed to connect signer provider: {err}"))?;
+
+    let contract = IInstanceLifecycleReporter::new(manager, provider);
+    let receipt = contract
+        .reportProvisionFailed(service_id, reason.to_string())
+        .send()
+        .await
+        .map_err(|err| format!("reportProvisionFailed transaction failed: {err}"))?
+        .get_receipt()
+        .await
+        .map_err(|err| format!("reportProvisionFailed receipt fetch failed: {err}"))?;
+    if !receipt.status() {
+        return Err("reportProvisionFailed transaction reverted".to_string());
+    }
+
+    info!(
+        service_id,
+        tx_hash = %receipt.transaction_hash,
+        "Instance provision failure reported on-chain"
+    );
+    Ok(receipt.transaction_hash.to_string())
+}
+
 /// Retry a pending direct-report payload once.
 ///
 /// Returns `Ok(true)` when a pending record was found and processed.
@@ -429,6 +476,11 @@ mod tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         let output = provision_output_from_record(&record);
@@ -479,6 +531,11 @@ mod tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         let output = provision_output_from_record(&record);
@@ -0,0 +1,242 @@
+//! Named sandbox slots.
+//!
+//! A service instance runs its primary sandbox in the `main` slot (the one
+//! auto-provisioned from the service config). The owner can provision extra
+//! sandboxes into named slots (e.g. `staging`, `worker-1`) so one subscription
+//! runs a small fleet. Exec/prompt/task requests carry a `slot` field; an
+//! empty slot always means `main`.
+//!
+//! `main` is stored in this crate's `instance.json` store under the original
+//! key, so records written before slots existed are unaffected. Named slots
+//! live in the runtime's `instance-slots.json` store.
+
+use serde::Deserialize;
+
+use crate::jobs::provision::provision_locked;
+use crate::runtime::{self, DEFAULT_INSTANCE_SLOT};
+use crate::tee::TeeBackend;
+use crate::{ProvisionOutput, ProvisionRequest, SandboxRecord, TeeType, error};
+
+/// Default cap on provisioned slots (including `main`) per service instance.
+pub const DEFAULT_INSTANCE_MAX_SLOTS: usize = 8;
+
+/// Serializes slot provisioning and deprovisioning for this instance.
+static SLOTS_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Acquire the instance's slot lock. Hold it from the occupancy and
+/// `INSTANCE_MAX_SLOTS` checks until the slot record is stored (or cleared),
+/// so concurrent provisions cannot both pass the checks.
+pub async fn acquire_slots_lock() -> tokio::sync::MutexGuard<'static, ()> {
+    SLOTS_LOCK.lock().await
+}
+
+/// Slot cap from `INSTANCE_MAX_SLOTS` (minimum 1).
+pub fn instance_max_slots() -> usize {
+    std::env::var("INSTANCE_MAX_SLOTS")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_INSTANCE_MAX_SLOTS)
+        .max(1)
+}
+
+/// Whether `slot` names the primary slot (empty or `main`). Errors on an
+/// invalid slot name.
+pub fn is_main_slot(slot: &str) -> error::Result<bool> {
+    Ok(runtime::normalize_instance_slot(slot)? == DEFAULT_INSTANCE_SLOT)
+}
+
+/// Get the sandbox in `slot` (empty = `main`), if provisioned.
+pub fn get_instance_sandbox_slot(slot: &str) -> error::Result<Option<SandboxRecord>> {
+    if is_main_slot(slot)? {
+        crate::get_instance_sandbox()
+    } else {
+        runtime::get_instance_sandbox_slot(slot)
+    }
+}
+
+/// Get the sandbox in `slot` or return an error if it is not provisioned.
+pub fn require_instance_sandbox_slot(slot: &str) -> Result<SandboxRecord, String> {
+    get_instance_sandbox_slot(slot)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| {
+            if slot.trim().is_empty() {
+                "Instance not provisioned".to_string()
+            } else {
+                format!("Instance slot '{}' not provisioned", slot.trim())
+            }
+        })
+}
+
+/// Store the provisioned sandbox record for `slot`.
+pub fn set_instance_sandbox_slot(slot: &str, record: SandboxRecord) -> error::Result<()> {
+    if is_main_slot(slot)? {
+        crate::set_instance_sandbox(record)
+    } else {
+        runtime::set_instance_sandbox_slot(slot, record)
+    }
+}
+
+/// Remove the sandbox record for `slot`.
+pub fn clear_instance_sandbox_slot(slot: &str) -> error::Result<()> {
+    if is_main_slot(slot)? {
+        crate::clear_instance_sandbox()
+    } else {
+        runtime::remove_instance_sandbox_slot(slot).map(|_| ())
+    }
+}
+
+/// Number of provisioned slots, including `main`.
+pub fn provisioned_slot_count() -> error::Result<usize> {
+    let main = usize::from(crate::get_instance_sandbox()?.is_some());
+    Ok(main + runtime::instance_slots()?.values()?.len())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Owner-driven slot provisioning (operator API)
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug)]
+pub enum SlotError {
+    BadRequest(String),
+    NotFound(String),
+    Forbidden(String),
+    Internal(String),
+}
+
+impl SlotError {
+    pub fn message(&self) -> &str {
+        match self {
+            Self::BadRequest(message)
+            | Self::NotFound(message)
+            | Self::Forbidden(message)
+            | Self::Internal(message) => message.as_str(),
+        }
+    }
+}
+
+/// JSON body for `POST /api/sandbox/slots/{slot}`. Empty/zero fields fall
+/// back to the named template (if any) and then to runtime defaults.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SlotProvisionRequest {
+    pub name: String,
+    pub image: String,
+    pub stack: String,
+    pub template: String,
+    pub env_json: String,
+    pub metadata_json: String,
+    pub capabilities_json: String,
+    pub ssh_enabled: bool,
+    pub ssh_public_key: String,
+    pub cpu_cores: u64,
+    pub memory_mb: u64,
+    pub disk_gb: u64,
+    pub idle_timeout_seconds: u64,
+    pub max_lifetime_seconds: u64,
+}
+
+/// Resolve the `main` slot and check that `caller` owns the instance.
+fn require_instance_owner(caller: &str) -> Result<SandboxRecord, SlotError> {
+    let main = crate::get_instance_sandbox()
+        .map_err(|e| SlotError::Internal(e.to_string()))?
+        .ok_or_else(|| SlotError::NotFound("Instance not provisioned".to_string()))?;
+    if main.owner.is_empty() || !main.owner.eq_ignore_ascii_case(caller) {
+        return Err(SlotError::Forbidden(
+            "Not authorized for this instance".to_string(),
+        ));
+    }
+    Ok(main)
+}
+
+fn require_named_slot(slot: &str) -> Result<(), SlotError> {
+    match is_main_slot(slot) {
+        Ok(false) => Ok(()),
+        Ok(true) => Err(SlotError::BadRequest(
+            "The main slot is managed by instance provisioning".to_string(),
+        )),
+        Err(e) => Err(SlotError::BadRequest(e.to_string())),
+    }
+}
+
+/// Provision a sandbox into a named slot on behalf of the instance owner.
+///
+/// The new sandbox inherits the `main` slot's owner, service binding and TEE
+/// requirement. Fails with `BadRequest` for `main`, an invalid name, an
+/// occupied slot or when `INSTANCE_MAX_SLOTS` is reached.
+pub async fn provision_slot(
+    caller: &str,
+    slot: &str,
+    body: SlotProvisionRequest,
+    tee: Option<&dyn TeeBackend>,
+) -> Result<ProvisionOutput, SlotError> {
+    require_named_slot(slot)?;
+    let main = require_instance_owner(caller)?;
+    let tee_config = main.tee_config.as_ref().filter(|c| c.required);
+    let request = ProvisionRequest {
+        name: if body.name.trim().is_empty() {
+            format!("{}-{}", main.name, slot.trim())
+        } else {
+            body.name
+        },
+        image: body.image,
+        stack: body.stack,
+        agent_identifier: main.agent_identifier.clone(),
+        env_json: body.env_json,
+        metadata_json: body.metadata_json,
+        ssh_enabled: body.ssh_enabled,
+        ssh_public_key: body.ssh_public_key,
+        web_terminal_enabled: false,
+        max_lifetime_seconds: body.max_lifetime_seconds,
+        idle_timeout_seconds: body.idle_timeout_seconds,
+        cpu_cores: body.cpu_cores,
+        memory_mb: body.memory_mb,
+        disk_gb: body.disk_gb,
+        tee_required: tee_config.is_some(),
+        tee_type: match tee_config.map(|c| &c.tee_type) {
+            Some(TeeType::Tdx) => 1,
+            Some(TeeType::Nitro) => 2,
+            Some(TeeType::Sev) => 3,
+            _ => 0,
+        },
+        attestation_nonce: String::new(),
+        capabilities_json: body.capabilities_json,
+        template: body.template,
+        slot: slot.trim().to_string(),
+    };
+
+    let _slots = acquire_slots_lock().await;
+    let (output, mut record) = provision_locked(&request, tee, &main.owner)
+        .await
+        .map_err(SlotError::BadRequest)?;
+    record.service_id = main.service_id;
+    if let Some(service_id) = main.service_id {
+        let _ = runtime::sandboxes()
+            .and_then(|s| s.update(&record.id, |r| r.service_id = Some(service_id)));
+    }
+    set_instance_sandbox_slot(slot, record).map_err(|e| SlotError::Internal(e.to_string()))?;
+    Ok(output)
+}
+
+/// Deprovision a named slot on behalf of the instance owner. Returns the
+/// deleted sandbox ID.
+pub async fn deprovision_slot(
+    caller: &str,
+    slot: &str,
+    tee: Option<&dyn TeeBackend>,
+) -> Result<String, SlotError> {
+    require_named_slot(slot)?;
+    require_instance_owner(caller)?;
+    if get_instance_sandbox_slot(slot)
+        .map_err(|e| SlotError::Internal(e.to_string()))?
+        .is_none()
+    {
+        return Err(SlotError::NotFound(format!(
+            "Instance slot '{}' not provisioned",
+            slot.trim()
+        )));
+    }
+    let (_, sandbox_id) = crate::deprovision_slot_core(slot, tee)
+        .await
+        .map_err(SlotError::Internal)?;
+    Ok(sandbox_id)
+}
@@ -0,0 +1,157 @@
+//! Cross-operator result verification for replicated instances.
+//!
+//! Every operator in an N-operator service runs the same job against its own
+//! copy of the sandbox. To compare those outputs, an off-chain aggregator (or
+//! the contract) hashes each operator's result the same way and checks for
+//! agreement. Hashes cover only the parts of a result that should match
+//! across operators: output text is normalized and operator-local fields
+//! (trace IDs, durations, token counts, overflow refs) are left out.
+//!
+//! Each hash is `keccak256(abi.encode(...))` over the normalized fields, so
+//! Solidity can recompute it:
+//!
+//! - exec: `abi.encode(uint32 exitCode, string stdout, string stderr)`
+//! - prompt/task: `abi.encode(bool success, string output)`
+//!
+//! Agent output only converges when sampling is pinned. Requests opt in with
+//! `"deterministic": true` (and optionally `"seed": <u64>`) in `context_json`;
+//! [`apply_deterministic_hints`] then asks the sidecar for temperature 0 and
+//! a fixed seed.
+
+use std::collections::BTreeMap;
+
+use blueprint_sdk::alloy::primitives::{B256, keccak256};
+use blueprint_sdk::alloy::sol_types::SolValue;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::{InstanceExecResponse, InstancePromptResponse, InstanceTaskResponse};
+
+/// Seed used for deterministic agent runs that do not name their own.
+pub const DEFAULT_DETERMINISTIC_SEED: u64 = 0;
+
+/// Normalize output text so cosmetic differences between hosts do not break
+/// hash agreement: ANSI escape sequences are stripped, line endings become
+/// `\n`, trailing whitespace is trimmed from each line, and trailing blank
+/// lines are dropped.
+pub fn normalize_output(text: &str) -> String {
+    let stripped = strip_ansi(text);
+    let lines: Vec<&str> = stripped
+        .split('\n')
+        .map(|line| line.trim_end_matches(['\r', ' ', '\t']))
+        .collect();
+    lines.join("\n").trim_end_matches('\n').to_string()
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            out.push(c);
+            continue;
+        }
+        // CSI sequence: ESC '[' params... final byte in '@'..='~'.
+        if chars.peek() == Some(&'[') {
+            chars.next();
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        } else {
+            chars.next();
+        }
+    }
+    out
+}
+
+/// Hash of an exec result: exit code plus normalized stdout/stderr.
+pub fn exec_result_hash(response: &InstanceExecResponse) -> B256 {
+    keccak256(
+        (
+            response.exit_code,
+            normalize_output(&response.stdout),
+            normalize_output(&response.stderr),
+        )
+            .abi_encode(),
+    )
+}
+
+/// Hash of a prompt result: success flag plus normalized response text.
+pub fn prompt_result_hash(response: &InstancePromptResponse) -> B256 {
+    agent_result_hash(response.success, &response.response)
+}
+
+/// Hash of a task result: success flag plus normalized result text.
+pub fn task_result_hash(response: &InstanceTaskResponse) -> B256 {
+    agent_result_hash(response.success, &response.result)
+}
+
+fn agent_result_hash(success: bool, output: &str) -> B256 {
+    keccak256((success, normalize_output(output)).abi_encode())
+}
+
+/// Seed requested by an agent run's metadata, if it asked for
+/// deterministic mode (`"deterministic": true`).
+pub fn deterministic_seed(metadata: &Map<String, Value>) -> Option<u64> {
+    if metadata.get("deterministic").and_then(Value::as_bool) != Some(true) {
+        return None;
+    }
+    Some(
+        metadata
+            .get("seed")
+            .and_then(Value::as_u64)
+            .unwrap_or(DEFAULT_DETERMINISTIC_SEED),
+    )
+}
+
+/// Ask the sidecar to pin sampling for an `/agents/run` payload: temperature
+/// 0 and a fixed seed, merged into the `backend` options.
+pub fn apply_deterministic_hints(payload: &mut Map<String, Value>, seed: u64) {
+    let backend = payload
+        .entry("backend")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Value::Object(backend) = backend {
+        backend.insert("temperature".to_string(), json!(0));
+        backend.insert("seed".to_string(), json!(seed));
+    }
+}
+
+/// Agreement across operators' hashes for one job call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationSummary {
+    /// Hash reported by the most operators (ties broken by the lowest hash),
+    /// `None` when no results were given.
+    pub majority_hash: Option<B256>,
+    /// Operators whose hash equals `majority_hash`.
+    pub agreeing: Vec<String>,
+    /// Operators whose hash differs.
+    pub dissenting: Vec<String>,
+    /// Whether `agreeing` reaches the requested quorum.
+    pub quorum_reached: bool,
+}
+
+/// Compare `(operator, hash)` pairs and check whether at least `quorum`
+/// operators agree on one hash.
+pub fn compare_result_hashes(results: &[(String, B256)], quorum: usize) -> VerificationSummary {
+    let mut counts: BTreeMap<B256, usize> = BTreeMap::new();
+    for (_, hash) in results {
+        *counts.entry(*hash).or_default() += 1;
+    }
+    let majority_hash = counts
+        .iter()
+        .max_by(|(ha, ca), (hb, cb)| ca.cmp(cb).then(hb.cmp(ha)))
+        .map(|(hash, _)| *hash);
+    let (agreeing, dissenting): (Vec<_>, Vec<_>) = results
+        .iter()
+        .partition(|(_, hash)| Some(*hash) == majority_hash);
+    let agreeing: Vec<String> = agreeing.into_iter().map(|(op, _)| op.clone()).collect();
+    VerificationSummary {
+        majority_hash,
+        quorum_reached: majority_hash.is_some() && agreeing.len() >= quorum,
+        agreeing,
+        dissenting: dissenting.into_iter().map(|(op, _)| op.clone()).collect(),
+    }
+}
@@ -52,6 +52,11 @@ pub async fn run_workflow(entry: &WorkflowEntry) -> Result<WorkflowExecution, St
         model: spec.model.unwrap_or_default(),
         context_json: spec.context_json.unwrap_or_default(),
         timeout_ms: spec.timeout_ms.unwrap_or(0),
+        slot: String::new(),
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: sandbox.agent_identifier.clone(),
+        session_name: String::new(),
     };
 
     let response =
@@ -3,6 +3,7 @@
 //! workflows.
 
 use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
 use cron::Schedule;
 use serde::Deserialize;
 use serde_json::{Value, json};
@@ -1,5 +1,17 @@
 use super::*;
 
+/// Cron `trigger_config`: either a bare cron expression, evaluated in UTC,
+/// or `{"cron": "0 0 9 * * *", "timezone": "Europe/Berlin"}` to evaluate it
+/// in an IANA timezone. Local times are resolved per run, so a 9am schedule
+/// stays at 9am local across DST changes.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CronTrigger {
+    cron: String,
+    #[serde(default)]
+    timezone: Option<String>,
+}
+
 pub fn resolve_next_run(
     trigger_type: &str,
     trigger_config: &str,
@@ -12,16 +24,41 @@ pub fn resolve_next_run(
     Ok(Some(compute_next_run(trigger_config, start)?))
 }
 
-fn compute_next_run(cron_expr: &str, from_ts: u64) -> Result<u64, String> {
+fn compute_next_run(trigger_config: &str, from_ts: u64) -> Result<u64, String> {
+    let (cron_expr, timezone) = parse_cron_trigger(trigger_config)?;
     let schedule =
-        Schedule::from_str(cron_expr).map_err(|err| format!("Invalid cron expression: {err}"))?;
+        Schedule::from_str(&cron_expr).map_err(|err| format!("Invalid cron expression: {err}"))?;
     let base = Utc
         .timestamp_opt(from_ts as i64, 0)
         .single()
         .ok_or_else(|| "Invalid timestamp".to_string())?;
-    schedule
-        .after(&base)
-        .next()
-        .map(|dt| dt.timestamp().max(0) as u64)
+    let next = match timezone {
+        Some(tz) => schedule
+            .after(&base.with_timezone(&tz))
+            .next()
+            .map(|dt| dt.timestamp()),
+        None => schedule.after(&base).next().map(|dt| dt.timestamp()),
+    };
+    next.map(|ts| ts.max(0) as u64)
         .ok_or_else(|| "Cron expression has no future run times".to_string())
 }
+
+fn parse_cron_trigger(trigger_config: &str) -> Result<(String, Option<Tz>), String> {
+    let trimmed = trigger_config.trim();
+    if !trimmed.starts_with('{') {
+        return Ok((trimmed.to_string(), None));
+    }
+    let trigger: CronTrigger = serde_json::from_str(trimmed)
+        .map_err(|err| format!("Invalid cron trigger config: {err}"))?;
+    let timezone = trigger
+        .timezone
+        .as_deref()
+        .map(str::trim)
+        .filter(|tz| !tz.is_empty())
+        .map(|tz| {
+            tz.parse::<Tz>()
+                .map_err(|_| format!("Unknown timezone '{tz}' (expected an IANA name)"))
+        })
+        .transpose()?;
+    Ok((trigger.cron, timezone))
+}
@@ -43,15 +43,39 @@ pub fn store_latest_execution(
             .insert(
                 key,
                 WorkflowRuntimeMetadata {
-                    latest_execution: Some(latest_execution),
+                    latest_execution: Some(latest_execution.clone()),
                 },
             )
             .map_err(|e| e.to_string())?;
     }
 
+    notify_workflow_completed(workflow_id, &latest_execution);
     Ok(())
 }
 
+/// Send `workflow.completed` to the webhooks of the workflow's owner.
+fn notify_workflow_completed(workflow_id: u64, execution: &WorkflowLatestExecution) {
+    let Ok(Some(entry)) =
+        workflows().and_then(|s| s.get(&workflow_key(workflow_id)).map_err(|e| e.to_string()))
+    else {
+        return;
+    };
+    let owner = entry.owner.clone();
+    sandbox_runtime::webhooks::emit(
+        &owner,
+        sandbox_runtime::webhooks::WebhookEvent::WorkflowCompleted,
+        json!({
+            "workflow_id": workflow_id,
+            "name": entry.name,
+            "success": execution.success,
+            "error": execution.error,
+            "trace_id": execution.trace_id,
+            "duration_ms": execution.duration_ms,
+            "executed_at": execution.executed_at,
+        }),
+    );
+}
+
 pub fn store_failed_execution(
     workflow_id: u64,
     error: String,
@@ -123,6 +123,8 @@ async fn instance_full_lifecycle() -> Result<()> {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
+            slot: String::new(),
         };
 
         let (provision_receipt, record) = provision_core(&provision_payload, None, &owner_address)
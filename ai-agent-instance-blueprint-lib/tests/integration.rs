@@ -22,7 +22,7 @@ use serde_json::{Value, json};
 use std::sync::Once;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use wiremock::matchers::{header, method, path};
+use wiremock::matchers::{body_partial_json, header, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 static INIT: Once = Once::new();
@@ -97,6 +97,11 @@ fn insert_sandbox(url: &str, token: &str) -> String {
                 ssh_login_user: None,
                 ssh_authorized_keys: Vec::new(),
                 capabilities_json: String::new(),
+                sidecar_api: None,
+                image_digest: None,
+                crash_history: Vec::new(),
+                lifecycle_events: Vec::new(),
+                clone_image: None,
             },
         )
         .unwrap();
@@ -148,6 +153,11 @@ fn insert_ssh_sandbox(url: &str, token: &str) -> String {
                 ssh_login_user: None,
                 ssh_authorized_keys: Vec::new(),
                 capabilities_json: String::new(),
+                sidecar_api: None,
+                image_digest: None,
+                crash_history: Vec::new(),
+                lifecycle_events: Vec::new(),
+                clone_image: None,
             },
         )
         .unwrap();
@@ -224,6 +234,10 @@ mod exec_tests {
             cwd: String::new(),
             env_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            stdin: String::new(),
+            stdin_file: String::new(),
+            preset: String::new(),
         };
 
         let resp = run_instance_exec(&server.uri(), "tok", &id, &request)
@@ -251,6 +265,10 @@ mod exec_tests {
             cwd: "/tmp".to_string(),
             env_json: r#"{"FOO":"bar"}"#.to_string(),
             timeout_ms: 5000,
+            slot: String::new(),
+            stdin: String::new(),
+            stdin_file: String::new(),
+            preset: String::new(),
         };
 
         let resp = run_instance_exec(&server.uri(), "tok", &id, &request)
@@ -277,6 +295,10 @@ mod exec_tests {
             cwd: String::new(),
             env_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            stdin: String::new(),
+            stdin_file: String::new(),
+            preset: String::new(),
         };
 
         let resp = run_instance_exec(&server.uri(), "tok", &id, &request)
@@ -303,6 +325,10 @@ mod exec_tests {
             cwd: String::new(),
             env_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            stdin: String::new(),
+            stdin_file: String::new(),
+            preset: String::new(),
         };
 
         let result = run_instance_exec(&server.uri(), "tok", &id, &request).await;
@@ -335,6 +361,9 @@ mod prompt_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let resp = run_instance_prompt(&server.uri(), "tok", &id, &request)
@@ -366,6 +395,9 @@ mod prompt_tests {
             model: "gpt-4".to_string(),
             context_json: r#"{"key":"value"}"#.to_string(),
             timeout_ms: 30000,
+            slot: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let resp = run_instance_prompt(&server.uri(), "tok", &id, &request)
@@ -393,6 +425,9 @@ mod prompt_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let resp = run_instance_prompt(&server.uri(), "tok", &id, &request)
@@ -403,6 +438,54 @@ mod prompt_tests {
         assert_eq!(resp.error, "model overloaded");
         rm(&id);
     }
+
+    #[tokio::test]
+    async fn prompt_named_session_is_resumed_and_restarted_when_stale() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .and(body_partial_json(json!({ "sessionId": "s-first" })))
+            .respond_with(mock_agent_error("Session s-first not found"))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .respond_with(mock_agent_ok("first"))
+            .mount(&server)
+            .await;
+
+        let id = insert_sandbox(&server.uri(), "tok");
+        let request = InstancePromptRequest {
+            message: "Hello".to_string(),
+            session_id: String::new(),
+            model: String::new(),
+            context_json: String::new(),
+            timeout_ms: 0,
+            slot: String::new(),
+            agent_identifier: String::new(),
+            session_name: "review".to_string(),
+        };
+
+        // Starts the named session, then resumes it; the sidecar no longer
+        // has it, so the run is retried in a new session.
+        for _ in 0..2 {
+            let resp = run_instance_prompt(&server.uri(), "tok", &id, &request)
+                .await
+                .unwrap();
+            assert!(resp.success);
+        }
+        let requests = server.received_requests().await.unwrap();
+        let sessions: Vec<Option<String>> = requests
+            .iter()
+            .map(|r| {
+                let body: Value = serde_json::from_slice(&r.body).unwrap();
+                body["sessionId"].as_str().map(str::to_string)
+            })
+            .collect();
+        assert_eq!(sessions, vec![None, Some("s-first".to_string()), None]);
+        rm(&id);
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -430,6 +513,11 @@ mod task_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let resp = run_instance_task(&server.uri(), "tok", &id, &request)
@@ -460,6 +548,11 @@ mod task_tests {
             model: "claude-sonnet".to_string(),
             context_json: r#"{"project":"test"}"#.to_string(),
             timeout_ms: 60000,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let resp = run_instance_task(&server.uri(), "tok", &id, &request)
@@ -488,6 +581,11 @@ mod task_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 1000,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let resp = run_instance_task(&server.uri(), "tok", &id, &request)
@@ -516,6 +614,11 @@ mod task_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let result = run_instance_task(&server.uri(), "tok", &id, &request).await;
@@ -620,6 +723,17 @@ mod helper_tests {
         assert!(payload.contains_key("env"));
     }
 
+    #[test]
+    fn build_exec_payload_with_stdin_fields() {
+        let payload = build_exec_payload_with_stdin("cat", "", "", 0, "hello", "");
+        assert_eq!(payload["stdin"], "hello");
+        assert!(!payload.contains_key("stdinFile"));
+        assert_eq!(
+            build_exec_payload("cat", "", "", 0),
+            build_exec_payload_with_stdin("cat", "", "", 0, "", "")
+        );
+    }
+
     #[test]
     fn extract_exec_fields_full() {
         let v = json!({
@@ -687,7 +801,7 @@ mod helper_tests {
 
     #[test]
     fn build_agent_payload_minimal() {
-        let payload = build_agent_payload("hello", "", "", "", 0, None).unwrap();
+        let payload = build_agent_payload("", "hello", "", "", "", 0, None).unwrap();
         assert_eq!(payload["identifier"], "default");
         assert_eq!(payload["message"], "hello");
         assert!(!payload.contains_key("sessionId"));
@@ -695,9 +809,15 @@ mod helper_tests {
         assert!(!payload.contains_key("timeout"));
     }
 
+    #[test]
+    fn build_agent_payload_with_agent_identifier() {
+        let payload = build_agent_payload(" coder ", "hello", "", "", "", 0, None).unwrap();
+        assert_eq!(payload["identifier"], "coder");
+    }
+
     #[test]
     fn build_agent_payload_with_session_and_model() {
-        let payload = build_agent_payload("hello", "sess-1", "gpt-4", "", 30000, None).unwrap();
+        let payload = build_agent_payload("", "hello", "sess-1", "gpt-4", "", 30000, None).unwrap();
         assert_eq!(payload["sessionId"], "sess-1");
         assert_eq!(payload["backend"]["model"], "gpt-4");
         assert_eq!(payload["timeout"], 30000);
@@ -705,7 +825,8 @@ mod helper_tests {
 
     #[test]
     fn build_agent_payload_with_context() {
-        let payload = build_agent_payload("hello", "", "", r#"{"key":"val"}"#, 0, None).unwrap();
+        let payload =
+            build_agent_payload("", "hello", "", "", r#"{"key":"val"}"#, 0, None).unwrap();
         assert_eq!(payload["metadata"]["key"], "val");
     }
 
@@ -715,7 +836,7 @@ mod helper_tests {
         let mut extra = Map::new();
         extra.insert("maxTurns".to_string(), json!(5));
 
-        let payload = build_agent_payload("hello", "", "", "", 0, Some(extra)).unwrap();
+        let payload = build_agent_payload("", "hello", "", "", "", 0, Some(extra)).unwrap();
         assert_eq!(payload["metadata"]["maxTurns"], 5);
     }
 
@@ -843,6 +964,11 @@ mod instance_state_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         set_instance_sandbox(record).unwrap();
@@ -897,6 +1023,11 @@ mod instance_state_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         set_instance_sandbox(record).unwrap();
@@ -905,6 +1036,517 @@ mod instance_state_tests {
         clear_instance_sandbox().unwrap();
         assert!(get_instance_sandbox().unwrap().is_none());
     }
+
+    #[test]
+    fn named_slots_are_independent_of_main() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        clear_instance_sandbox().unwrap();
+        let record = SandboxRecord {
+            id: "slot-worker".to_string(),
+            container_id: "ctr-slot".to_string(),
+            sidecar_url: "http://localhost:2222".to_string(),
+            sidecar_port: 2222,
+            ssh_port: None,
+            token: "slot-tok".to_string(),
+            created_at: util::now_ts(),
+            cpu_cores: 1,
+            memory_mb: 512,
+            state: Default::default(),
+            idle_timeout_seconds: 0,
+            max_lifetime_seconds: 0,
+            last_activity_at: util::now_ts(),
+            stopped_at: None,
+            snapshot_image_id: None,
+            snapshot_s3_url: None,
+            container_removed_at: None,
+            image_removed_at: None,
+            original_image: String::new(),
+            base_env_json: String::new(),
+            user_env_json: String::new(),
+            snapshot_destination: None,
+            tee_deployment_id: None,
+            tee_metadata_json: None,
+            tee_attestation_json: None,
+            name: String::new(),
+            agent_identifier: String::new(),
+            metadata_json: String::new(),
+            disk_gb: 0,
+            stack: String::new(),
+            owner: String::new(),
+            service_id: None,
+            tee_config: None,
+            extra_ports: std::collections::HashMap::new(),
+            ssh_login_user: None,
+            ssh_authorized_keys: Vec::new(),
+            capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
+        };
+
+        set_instance_sandbox_slot("worker-1", record).unwrap();
+        assert!(get_instance_sandbox().unwrap().is_none());
+        assert!(require_instance_sandbox_slot("").is_err());
+        let got = require_instance_sandbox_slot("worker-1").unwrap();
+        assert_eq!(got.id, "slot-worker");
+        assert_eq!(got.token, "slot-tok");
+        assert!(get_instance_sandbox_slot("Not Valid").is_err());
+
+        clear_instance_sandbox_slot("worker-1").unwrap();
+        let err = require_instance_sandbox_slot("worker-1").unwrap_err();
+        assert!(err.contains("'worker-1' not provisioned"), "got: {err}");
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn upgrade_requires_provisioned_slot_and_owner() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        clear_instance_sandbox().unwrap();
+        let err = run_instance_upgrade("0xowner", "", "", None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("not provisioned"), "got: {err}");
+
+        let id = insert_sandbox("http://localhost:2223", "upgrade-tok");
+        let mut record = runtime::get_sandbox_by_id(&id).unwrap();
+        record.owner = "0xowner".to_string();
+        set_instance_sandbox(record).unwrap();
+        let err = run_instance_upgrade("0xintruder", "", "nginx:latest", None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("does not own"), "got: {err}");
+        clear_instance_sandbox().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn repair_leaves_reaped_instance_untouched() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let id = insert_sandbox("http://localhost:2226", "repair-tok");
+        runtime::sandboxes()
+            .unwrap()
+            .update(&id, |r| {
+                r.owner = "0xowner".to_string();
+                r.state = SandboxState::Stopped;
+                r.container_removed_at = Some(util::now_ts());
+            })
+            .unwrap();
+        set_instance_sandbox(runtime::get_sandbox_by_id(&id).unwrap()).unwrap();
+
+        let err = run_instance_repair("0xintruder", "", None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("does not own"), "got: {err}");
+
+        let json = run_instance_repair("0xowner", "", None).await.unwrap();
+        let parsed: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["sandboxId"], id);
+        assert_eq!(parsed["repaired"], false);
+        assert_eq!(parsed["issues"], json!([]));
+        assert_eq!(parsed["diagnosis"]["container"]["status"], "removed");
+        clear_instance_sandbox().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn session_export_uploads_owner_history() {
+        use sandbox_runtime::chat_state::{self, ChatMessageRecord};
+
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/export.json"))
+            .and(wiremock::matchers::body_string_contains("hello agent"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let id = insert_sandbox("http://localhost:2227", "export-tok");
+        runtime::sandboxes()
+            .unwrap()
+            .update(&id, |r| r.owner = "0xowner".to_string())
+            .unwrap();
+        set_instance_sandbox(runtime::get_sandbox_by_id(&id).unwrap()).unwrap();
+        let session =
+            chat_state::create_session(&chat_state::instance_scope(&id), "0xowner", None).unwrap();
+        chat_state::append_message(
+            &session.id,
+            ChatMessageRecord {
+                id: "m1".to_string(),
+                run_id: None,
+                role: "user".to_string(),
+                content: "hello agent".to_string(),
+                created_at: 1,
+                completed_at: None,
+                parts: Vec::new(),
+                trace_id: None,
+                success: None,
+                error: None,
+            },
+        )
+        .unwrap();
+        let destination = format!("{}/export.json", server.uri());
+
+        let err = run_instance_session_export("0xintruder", "", "", &destination)
+            .await
+            .unwrap_err();
+        assert!(err.contains("does not own"), "got: {err}");
+        let err = run_instance_session_export("0xowner", "", "", "ftp://example.com/x")
+            .await
+            .unwrap_err();
+        assert!(err.contains("https"), "got: {err}");
+        assert!(
+            run_instance_session_export("0xowner", "", "missing", &destination)
+                .await
+                .is_err()
+        );
+
+        let json = run_instance_session_export("0xowner", "", &session.id, &destination)
+            .await
+            .unwrap();
+        let parsed: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["sandboxId"], id);
+        assert_eq!(parsed["sessions"], 1);
+        assert_eq!(parsed["messages"], 1);
+        chat_state::delete_session(&session.id).unwrap();
+        clear_instance_sandbox().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn exec_async_returns_id_then_result() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/terminals/commands"))
+            .respond_with(mock_exec_ok("build ok"))
+            .mount(&server)
+            .await;
+        let id = insert_sandbox(&server.uri(), "exec-async-tok");
+        runtime::sandboxes()
+            .unwrap()
+            .update(&id, |r| r.owner = "0xowner".to_string())
+            .unwrap();
+        set_instance_sandbox(runtime::get_sandbox_by_id(&id).unwrap()).unwrap();
+        let request = InstanceExecRequest {
+            command: "make build".to_string(),
+            cwd: String::new(),
+            env_json: String::new(),
+            timeout_ms: 0,
+            slot: String::new(),
+            stdin: String::new(),
+            stdin_file: String::new(),
+            preset: String::new(),
+        };
+
+        let err = run_instance_exec_async("0xintruder", &request).unwrap_err();
+        assert!(err.contains("does not own"), "got: {err}");
+        let started: Value =
+            serde_json::from_str(&run_instance_exec_async("0xowner", &request).unwrap()).unwrap();
+        let execution_id = started["executionId"].as_str().unwrap().to_string();
+        assert_eq!(started["status"], "running");
+
+        let mut result = Value::Null;
+        for _ in 0..50 {
+            result = serde_json::from_str(
+                &run_instance_exec_result("0xowner", "", &execution_id).unwrap(),
+            )
+            .unwrap();
+            if result["status"] != "running" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(result["status"], "completed");
+        assert_eq!(result["exitCode"], 0);
+        assert_eq!(result["stdout"], "build ok");
+        assert!(run_instance_exec_result("0xowner", "", "exec-missing").is_err());
+        clear_instance_sandbox().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn task_async_tracks_progress_then_result() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let server = MockServer::start().await;
+        let body = [
+            r#"event: message.part.updated
+data: {"part":{"type":"tool","tool":"bash"}}"#,
+            r#"event: message.part.updated
+data: {"part":{"type":"step-finish"}}"#,
+            r#"event: message.part.updated
+data: {"part":{"type":"step-finish"}}"#,
+            r#"event: result
+data: {"finalText":"all tests pass","metadata":{"traceId":"trace-q"}}"#,
+        ]
+        .join("\n\n")
+            + "\n\n";
+        Mock::given(method("POST"))
+            .and(path("/agents/run/stream"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .mount(&server)
+            .await;
+        let id = insert_sandbox(&server.uri(), "task-async-tok");
+        runtime::sandboxes()
+            .unwrap()
+            .update(&id, |r| r.owner = "0xowner".to_string())
+            .unwrap();
+        set_instance_sandbox(runtime::get_sandbox_by_id(&id).unwrap()).unwrap();
+        let mut request = InstanceTaskRequest {
+            prompt: "run the test suite".to_string(),
+            session_id: String::new(),
+            max_turns: 8,
+            model: String::new(),
+            context_json: String::new(),
+            timeout_ms: 0,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
+        };
+
+        let err = run_instance_task_async("0xintruder", &request).unwrap_err();
+        assert!(err.contains("does not own"), "got: {err}");
+        let queued: Value =
+            serde_json::from_str(&run_instance_task_async("0xowner", &request).unwrap()).unwrap();
+        let task_id = queued["taskId"].as_str().unwrap().to_string();
+        assert_eq!(queued["sandboxId"], id);
+
+        let mut result = Value::Null;
+        for _ in 0..100 {
+            result =
+                serde_json::from_str(&run_instance_task_result("0xowner", "", &task_id).unwrap())
+                    .unwrap();
+            if result["status"] == "completed" || result["status"] == "failed" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(result["status"], "completed", "got: {result}");
+        assert_eq!(result["result"], "all tests pass");
+        assert_eq!(result["traceId"], "trace-q");
+        assert_eq!(result["turnsCompleted"], 2);
+        assert_eq!(result["currentTool"], "bash");
+        assert!(run_instance_task_result("0xintruder", "", &task_id).is_err());
+        assert!(run_instance_task_result("0xowner", "", "task-missing").is_err());
+
+        request.tools_json = r#"["bash"]"#.to_string();
+        let err = run_instance_task_async("0xowner", &request).unwrap_err();
+        assert!(err.contains("not supported"), "got: {err}");
+        clear_instance_sandbox().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn task_cancel_aborts_running_task() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run/stream"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string("event: result\ndata: {\"finalText\":\"too late\"}\n\n")
+                    .set_delay(Duration::from_secs(30)),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run/cancel"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "success": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let id = insert_sandbox(&server.uri(), "task-cancel-tok");
+        runtime::sandboxes()
+            .unwrap()
+            .update(&id, |r| r.owner = "0xowner".to_string())
+            .unwrap();
+        set_instance_sandbox(runtime::get_sandbox_by_id(&id).unwrap()).unwrap();
+        let request = InstanceTaskRequest {
+            prompt: "refactor everything".to_string(),
+            session_id: String::new(),
+            max_turns: 50,
+            model: String::new(),
+            context_json: String::new(),
+            timeout_ms: 0,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
+        };
+        let queued: Value =
+            serde_json::from_str(&run_instance_task_async("0xowner", &request).unwrap()).unwrap();
+        let task_id = queued["taskId"].as_str().unwrap().to_string();
+        for _ in 0..100 {
+            let result: Value =
+                serde_json::from_str(&run_instance_task_result("0xowner", "", &task_id).unwrap())
+                    .unwrap();
+            if result["status"] == "running" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let err = run_instance_task_cancel("0xintruder", "", &task_id)
+            .await
+            .unwrap_err();
+        assert!(err.contains("does not own"), "got: {err}");
+        let cancelled: Value = serde_json::from_str(
+            &run_instance_task_cancel("0xowner", "", &task_id)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(cancelled["status"], "cancelled");
+
+        let result: Value =
+            serde_json::from_str(&run_instance_task_result("0xowner", "", &task_id).unwrap())
+                .unwrap();
+        assert_eq!(result["status"], "cancelled");
+        assert_eq!(result["result"], "");
+        let err = run_instance_task_cancel("0xowner", "", &task_id)
+            .await
+            .unwrap_err();
+        assert!(err.contains("already finished"), "got: {err}");
+        clear_instance_sandbox().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn ssh_list_requires_owner_and_ssh() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let id = insert_sandbox("http://localhost:2227", "ssh-list-tok");
+        runtime::sandboxes()
+            .unwrap()
+            .update(&id, |r| {
+                r.owner = "0xowner".to_string();
+                r.ssh_port = None;
+            })
+            .unwrap();
+        set_instance_sandbox(runtime::get_sandbox_by_id(&id).unwrap()).unwrap();
+
+        let err = run_instance_ssh_list("0xintruder", "").await.unwrap_err();
+        assert!(err.contains("does not own"), "got: {err}");
+        let err = run_instance_ssh_list("0xowner", "").await.unwrap_err();
+        assert!(err.contains("SSH is not enabled"), "got: {err}");
+        clear_instance_sandbox().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn status_reports_stopped_instance_without_failing() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let id = insert_sandbox("http://localhost:2224", "status-tok");
+        let mut record = runtime::get_sandbox_by_id(&id).unwrap();
+        record.state = SandboxState::Stopped;
+        record.container_removed_at = Some(util::now_ts());
+        set_instance_sandbox(record).unwrap();
+
+        let parsed: Value = serde_json::from_str(&run_instance_status("").await.unwrap()).unwrap();
+        assert_eq!(parsed["sandboxId"], id);
+        assert_eq!(parsed["sidecarUrl"], "http://localhost:2224");
+        assert_eq!(parsed["healthy"], false);
+        assert_eq!(parsed["status"]["container"]["status"], "removed");
+        assert!(parsed["status"]["uptimeSecs"].is_null());
+        assert!(run_instance_status("missing").await.is_err());
+        clear_instance_sandbox().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn config_update_changes_lifetimes_on_record() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let id = insert_sandbox("http://localhost:2225", "config-tok");
+        let mut record = runtime::get_sandbox_by_id(&id).unwrap();
+        record.owner = "0xowner".to_string();
+        set_instance_sandbox(record).unwrap();
+
+        let err = run_instance_config_update("0xowner", "", "[1]", None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("config_json"), "got: {err}");
+        let err = run_instance_config_update("0xother", "", "{}", None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("does not own"), "got: {err}");
+
+        let json =
+            run_instance_config_update("0xowner", "", r#"{"idle_timeout_seconds":600}"#, None)
+                .await
+                .unwrap();
+        let parsed: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["mode"], "record");
+        assert_eq!(parsed["changed"], json!(["idle_timeout_seconds"]));
+        assert_eq!(
+            require_instance_sandbox().unwrap().idle_timeout_seconds,
+            600
+        );
+        assert_eq!(
+            runtime::get_sandbox_by_id(&id)
+                .unwrap()
+                .idle_timeout_seconds,
+            600
+        );
+        clear_instance_sandbox().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn backup_uploads_archive_with_manifest() {
+        init();
+        let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/terminals/commands"))
+            .and(header("authorization", "Bearer backup-tok"))
+            .respond_with(mock_exec_ok(""))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let id = insert_sandbox(&server.uri(), "backup-tok");
+        let mut record = runtime::get_sandbox_by_id(&id).unwrap();
+        record.owner = "0xowner".to_string();
+        record.name = "agent".to_string();
+        set_instance_sandbox(record).unwrap();
+
+        let err = run_instance_backup("0xowner", "", "https://10.0.0.1/b.tar.gz")
+            .await
+            .unwrap_err();
+        assert!(err.contains("private"), "got: {err}");
+        let err = run_instance_restore("0xother", "", "s3://bucket/b.tar.gz")
+            .await
+            .unwrap_err();
+        assert!(err.contains("does not own"), "got: {err}");
+
+        let json = run_instance_backup("0xowner", "", "s3://bucket/b.tar.gz")
+            .await
+            .unwrap();
+        let parsed: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["sandboxId"], id);
+        assert_eq!(parsed["manifest"]["version"], BACKUP_MANIFEST_VERSION);
+        assert_eq!(parsed["manifest"]["name"], "agent");
+        assert_eq!(parsed["manifest"]["memory_mb"], 4096);
+        clear_instance_sandbox().unwrap();
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -921,6 +1563,19 @@ mod job_constants_tests {
         assert_eq!(JOB_WORKFLOW_CREATE, 2);
         assert_eq!(JOB_WORKFLOW_TRIGGER, 3);
         assert_eq!(JOB_WORKFLOW_CANCEL, 4);
+        assert_eq!(JOB_UPGRADE, 8);
+        assert_eq!(JOB_STATUS, 9);
+        assert_eq!(JOB_CONFIG_UPDATE, 10);
+        assert_eq!(JOB_BACKUP, 11);
+        assert_eq!(JOB_RESTORE, 12);
+        assert_eq!(JOB_REPAIR, 13);
+        assert_eq!(JOB_SSH_LIST, 16);
+        assert_eq!(JOB_EXEC_ASYNC, 17);
+        assert_eq!(JOB_EXEC_RESULT, 18);
+        assert_eq!(JOB_SESSION_EXPORT, 19);
+        assert_eq!(JOB_TASK_ASYNC, 20);
+        assert_eq!(JOB_TASK_RESULT, 21);
+        assert_eq!(JOB_TASK_CANCEL, 24);
     }
 }
 
@@ -939,6 +1594,10 @@ mod abi_tests {
             cwd: "/tmp".to_string(),
             env_json: r#"{"A":"1"}"#.to_string(),
             timeout_ms: 5000,
+            slot: String::new(),
+            stdin: String::new(),
+            stdin_file: String::new(),
+            preset: String::new(),
         };
 
         let encoded = request.abi_encode();
@@ -954,6 +1613,7 @@ mod abi_tests {
             exit_code: 42,
             stdout: "output".to_string(),
             stderr: "error".to_string(),
+            overflow_ref: String::new(),
         };
 
         let encoded = response.abi_encode();
@@ -971,6 +1631,9 @@ mod abi_tests {
             model: "gpt-4".to_string(),
             context_json: "{}".to_string(),
             timeout_ms: 30000,
+            slot: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let encoded = request.abi_encode();
@@ -989,6 +1652,11 @@ mod abi_tests {
             model: "claude-sonnet".to_string(),
             context_json: String::new(),
             timeout_ms: 120000,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let encoded = request.abi_encode();
@@ -1019,6 +1687,8 @@ mod abi_tests {
             tee_type: 2,
             attestation_nonce: String::new(), // Nitro
             capabilities_json: String::new(),
+            template: String::new(),
+            slot: String::new(),
         };
 
         let encoded = request.abi_encode();
@@ -1051,12 +1721,14 @@ mod abi_tests {
         let request = InstanceSshProvisionRequest {
             username: "root".to_string(),
             public_key: "ssh-ed25519 AAAA test@host".to_string(),
+            expires_in_seconds: 0,
         };
 
         let encoded = request.abi_encode();
         let decoded = InstanceSshProvisionRequest::abi_decode(&encoded).unwrap();
         assert_eq!(decoded.username, "root");
         assert_eq!(decoded.public_key, "ssh-ed25519 AAAA test@host");
+        assert_eq!(decoded.expires_in_seconds, 0);
     }
 
     #[test]
@@ -1103,6 +1775,8 @@ mod conversion_tests {
             tee_type: 1,
             attestation_nonce: String::new(), // Tdx
             capabilities_json: String::new(),
+            template: String::new(),
+            slot: String::new(),
         };
 
         let params = CreateSandboxParams::from(&request);
@@ -1139,6 +1813,8 @@ mod conversion_tests {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
+            slot: String::new(),
         };
 
         let params = CreateSandboxParams::from(&request);
@@ -1173,6 +1849,8 @@ mod conversion_tests {
                 tee_type: tee_type_id,
                 attestation_nonce: String::new(),
                 capabilities_json: String::new(),
+                template: String::new(),
+                slot: String::new(),
             };
 
             let params = CreateSandboxParams::from(&request);
@@ -1210,6 +1888,9 @@ mod session_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let _resp = run_instance_prompt(&server.uri(), "tok", &id, &request)
@@ -1245,6 +1926,11 @@ mod session_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let resp = run_instance_task(&server.uri(), "tok", &id, &request)
@@ -1279,6 +1965,11 @@ mod session_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
         let resp1 = run_instance_task(&server.uri(), "tok", &id, &req1)
             .await
@@ -1293,6 +1984,11 @@ mod session_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
         let _resp2 = run_instance_task(&server.uri(), "tok", &id, &req2)
             .await
@@ -1329,6 +2025,11 @@ mod session_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
         let req_b = InstanceTaskRequest {
             prompt: "Task B".to_string(),
@@ -1337,6 +2038,11 @@ mod session_tests {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let _a = run_instance_task(&server.uri(), "tok", &id, &req_a)
@@ -1385,6 +2091,9 @@ mod session_tests {
                 model: String::new(),
                 context_json: String::new(),
                 timeout_ms: 0,
+                slot: String::new(),
+                agent_identifier: String::new(),
+                session_name: String::new(),
             },
         )
         .await
@@ -1402,6 +2111,11 @@ mod session_tests {
                 model: String::new(),
                 context_json: String::new(),
                 timeout_ms: 0,
+                slot: String::new(),
+                output_schema_json: String::new(),
+                tools_json: String::new(),
+                agent_identifier: String::new(),
+                session_name: String::new(),
             },
         )
         .await
@@ -1540,6 +2254,11 @@ mod provision_guard_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
         set_instance_sandbox(record).unwrap();
 
@@ -1596,6 +2315,11 @@ mod provision_guard_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
         set_instance_sandbox(record).unwrap();
         assert!(get_instance_sandbox().unwrap().is_some());
@@ -1654,6 +2378,11 @@ mod provision_guard_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         set_instance_sandbox(record).unwrap();
@@ -1726,6 +2455,11 @@ mod provision_guard_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         let record_b = SandboxRecord {
@@ -1766,6 +2500,11 @@ mod provision_guard_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         set_instance_sandbox(record_a).unwrap();
@@ -1826,6 +2565,11 @@ mod provision_guard_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
         set_instance_sandbox(record).unwrap();
 
@@ -1906,6 +2650,11 @@ fn set_instance_for_test_with_owner(url: &str, token: &str, owner: &str) -> Stri
                 ssh_login_user: None,
                 ssh_authorized_keys: Vec::new(),
                 capabilities_json: String::new(),
+                sidecar_api: None,
+                image_digest: None,
+                crash_history: Vec::new(),
+                lifecycle_events: Vec::new(),
+                clone_image: None,
             },
         )
         .unwrap();
@@ -1948,6 +2697,11 @@ fn set_instance_for_test_with_owner(url: &str, token: &str, owner: &str) -> Stri
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     };
     set_instance_sandbox(record).unwrap();
     id
@@ -2622,6 +3376,8 @@ mod auto_provision_tests {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
+            slot: String::new(),
         };
 
         // abi_encode() produces tuple encoding (with outer offset prefix).
@@ -2643,3 +3399,114 @@ mod auto_provision_tests {
         assert!(err.contains("Failed to decode"), "got: {err}");
     }
 }
+
+// ═══════════════════════════════════════════════════════════════════════════
+// CROSS-OPERATOR VERIFICATION TESTS
+// ═══════════════════════════════════════════════════════════════════════════
+
+mod verification_tests {
+    use super::*;
+    use ai_agent_instance_blueprint_lib::verification::*;
+
+    fn exec_response(stdout: &str, overflow_ref: &str) -> InstanceExecResponse {
+        InstanceExecResponse {
+            exit_code: 0,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+            overflow_ref: overflow_ref.to_string(),
+        }
+    }
+
+    #[test]
+    fn normalize_output_ignores_cosmetic_differences() {
+        assert_eq!(
+            normalize_output("\u{1b}[32mok\u{1b}[0m  \r\nline two\t\n\n"),
+            "ok\nline two"
+        );
+        assert_eq!(normalize_output("a\n  b"), "a\n  b", "leading spaces kept");
+    }
+
+    #[test]
+    fn exec_hash_ignores_operator_local_fields() {
+        let a = exec_result_hash(&exec_response("hello\r\n", "overflow-a"));
+        let b = exec_result_hash(&exec_response("hello", ""));
+        assert_eq!(a, b);
+        let c = exec_result_hash(&InstanceExecResponse {
+            exit_code: 1,
+            ..exec_response("hello", "")
+        });
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn task_hash_ignores_trace_and_usage() {
+        let base = InstanceTaskResponse {
+            success: true,
+            result: "done".into(),
+            error: String::new(),
+            trace_id: "t1".into(),
+            duration_ms: 10,
+            input_tokens: 5,
+            output_tokens: 7,
+            session_id: "s1".into(),
+            tools_json: String::new(),
+        };
+        let other = InstanceTaskResponse {
+            trace_id: "t2".into(),
+            duration_ms: 99,
+            session_id: "s2".into(),
+            ..base.clone()
+        };
+        assert_eq!(task_result_hash(&base), task_result_hash(&other));
+        let failed = InstanceTaskResponse {
+            success: false,
+            ..base.clone()
+        };
+        assert_ne!(task_result_hash(&base), task_result_hash(&failed));
+    }
+
+    #[test]
+    fn deterministic_context_pins_sampling() {
+        let payload = build_agent_payload(
+            "",
+            "hi",
+            "",
+            "gpt-4",
+            r#"{"deterministic":true,"seed":7}"#,
+            0,
+            None,
+        )
+        .unwrap();
+        assert_eq!(payload["backend"]["model"], "gpt-4");
+        assert_eq!(payload["backend"]["temperature"], 0);
+        assert_eq!(payload["backend"]["seed"], 7);
+
+        let payload =
+            build_agent_payload("", "hi", "", "", r#"{"deterministic":true}"#, 0, None).unwrap();
+        assert_eq!(payload["backend"]["seed"], DEFAULT_DETERMINISTIC_SEED);
+
+        let payload = build_agent_payload("", "hi", "", "", r#"{"seed":7}"#, 0, None).unwrap();
+        assert!(payload.get("backend").is_none());
+    }
+
+    #[test]
+    fn compare_hashes_finds_majority_and_quorum() {
+        let good = exec_result_hash(&exec_response("ok", ""));
+        let bad = exec_result_hash(&exec_response("tampered", ""));
+        let results = vec![
+            ("op-a".to_string(), good),
+            ("op-b".to_string(), bad),
+            ("op-c".to_string(), good),
+        ];
+        let summary = compare_result_hashes(&results, 2);
+        assert_eq!(summary.majority_hash, Some(good));
+        assert_eq!(summary.agreeing, vec!["op-a", "op-c"]);
+        assert_eq!(summary.dissenting, vec!["op-b"]);
+        assert!(summary.quorum_reached);
+        assert!(!compare_result_hashes(&results, 3).quorum_reached);
+
+        let empty = compare_result_hashes(&[], 1);
+        assert_eq!(empty.majority_hash, None);
+        assert!(!empty.quorum_reached);
+    }
+}
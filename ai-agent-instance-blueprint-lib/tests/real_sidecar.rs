@@ -471,6 +471,10 @@ async fn exec_echo() {
         cwd: String::new(),
         env_json: String::new(),
         timeout_ms: 15000,
+        slot: String::new(),
+        stdin: String::new(),
+        stdin_file: String::new(),
+        preset: String::new(),
     };
 
     let resp = run_instance_exec(&s.url, AUTH_TOKEN, SANDBOX_ID, &request)
@@ -495,6 +499,10 @@ async fn exec_exit_code() {
         cwd: String::new(),
         env_json: String::new(),
         timeout_ms: 15000,
+        slot: String::new(),
+        stdin: String::new(),
+        stdin_file: String::new(),
+        preset: String::new(),
     };
 
     let resp = run_instance_exec(&s.url, AUTH_TOKEN, SANDBOX_ID, &request)
@@ -514,6 +522,10 @@ async fn exec_with_cwd() {
         cwd: "/tmp".to_string(),
         env_json: String::new(),
         timeout_ms: 15000,
+        slot: String::new(),
+        stdin: String::new(),
+        stdin_file: String::new(),
+        preset: String::new(),
     };
 
     let resp = run_instance_exec(&s.url, AUTH_TOKEN, SANDBOX_ID, &request)
@@ -538,6 +550,10 @@ async fn exec_with_env() {
         cwd: String::new(),
         env_json: r#"{"INSTANCE_TEST_VAR": "env-val-xyz"}"#.to_string(),
         timeout_ms: 15000,
+        slot: String::new(),
+        stdin: String::new(),
+        stdin_file: String::new(),
+        preset: String::new(),
     };
 
     let resp = run_instance_exec(&s.url, AUTH_TOKEN, SANDBOX_ID, &request)
@@ -559,6 +575,10 @@ async fn exec_multiline_output() {
         cwd: String::new(),
         env_json: String::new(),
         timeout_ms: 15000,
+        slot: String::new(),
+        stdin: String::new(),
+        stdin_file: String::new(),
+        preset: String::new(),
     };
 
     let resp = run_instance_exec(&s.url, AUTH_TOKEN, SANDBOX_ID, &request)
@@ -584,6 +604,10 @@ async fn concurrent_exec_requests() {
                 cwd: String::new(),
                 env_json: String::new(),
                 timeout_ms: 15000,
+                slot: String::new(),
+                stdin: String::new(),
+                stdin_file: String::new(),
+                preset: String::new(),
             };
             let resp = run_instance_exec(&url, AUTH_TOKEN, SANDBOX_ID, &request).await;
             (i, resp)
@@ -901,6 +925,9 @@ async fn instance_prompt_reaches_sidecar() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: timeout,
+        slot: String::new(),
+        agent_identifier: String::new(),
+        session_name: String::new(),
     };
 
     let result = run_instance_prompt(&s.url, AUTH_TOKEN, SANDBOX_ID, &request).await;
@@ -941,6 +968,11 @@ async fn instance_task_reaches_sidecar() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: timeout,
+        slot: String::new(),
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
+        session_name: String::new(),
     };
 
     let result = run_instance_task(&s.url, AUTH_TOKEN, SANDBOX_ID, &request).await;
@@ -1152,6 +1184,10 @@ async fn large_output_handling() {
         cwd: String::new(),
         env_json: String::new(),
         timeout_ms: 15000,
+        slot: String::new(),
+        stdin: String::new(),
+        stdin_file: String::new(),
+        preset: String::new(),
     };
 
     let resp = run_instance_exec(&s.url, AUTH_TOKEN, SANDBOX_ID, &request)
@@ -1269,6 +1305,9 @@ async fn ai_prompt_returns_real_response() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 60000,
+        slot: String::new(),
+        agent_identifier: String::new(),
+        session_name: String::new(),
     };
 
     let result = run_instance_prompt(&s.url, AUTH_TOKEN, SANDBOX_ID, &request)
@@ -1304,6 +1343,11 @@ async fn ai_task_with_session_continuity() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 60000,
+        slot: String::new(),
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
+        session_name: String::new(),
     };
 
     let result1 = run_instance_task(&s.url, AUTH_TOKEN, SANDBOX_ID, &req1)
@@ -1334,6 +1378,11 @@ async fn ai_task_with_session_continuity() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 60000,
+        slot: String::new(),
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
+        session_name: String::new(),
     };
 
     let result2 = run_instance_task(&s.url, AUTH_TOKEN, SANDBOX_ID, &req2)
@@ -1376,6 +1425,11 @@ async fn ai_task_multiple_independent_sessions() {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 60000,
+            slot: String::new(),
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
+            session_name: String::new(),
         };
 
         let result = run_instance_task(&s.url, AUTH_TOKEN, SANDBOX_ID, &request)
@@ -1421,6 +1475,11 @@ async fn ai_task_with_max_turns() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 60000,
+        slot: String::new(),
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
+        session_name: String::new(),
     };
 
     let result = run_instance_task(&s.url, AUTH_TOKEN, SANDBOX_ID, &request)
@@ -1501,6 +1560,11 @@ async fn ai_agent_writes_and_runs_script() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 240000,
+        slot: String::new(),
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
+        session_name: String::new(),
     };
 
     let result = match run_instance_task(&s.url, AUTH_TOKEN, SANDBOX_ID, &request).await {
@@ -1569,6 +1633,11 @@ async fn ai_agent_full_workflow() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 240000,
+        slot: String::new(),
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
+        session_name: String::new(),
     };
 
     let result = match run_instance_task(&s.url, AUTH_TOKEN, SANDBOX_ID, &request).await {
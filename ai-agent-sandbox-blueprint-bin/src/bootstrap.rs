@@ -1,85 +1,4 @@
-//! Service bootstrap helpers: env/heartbeat config + chain-vs-host capacity validation.
-
-// Only the qos-gated helpers below reference parent-scope items; the capacity
-// check is self-contained.
-#[cfg(feature = "qos")]
-use super::*;
-
-/// Parse a u64 from the first env var that's set in `keys`. Logs a warning
-/// and returns `None` if a value is set but doesn't parse — so operators
-/// see misconfiguration in observability instead of features silently
-/// disabling.
-#[cfg(feature = "qos")]
-pub(crate) fn parse_required_u64_env(keys: &[&str]) -> Option<u64> {
-    for key in keys {
-        match std::env::var(key) {
-            Ok(raw) => match raw.parse::<u64>() {
-                Ok(v) => return Some(v),
-                Err(e) => {
-                    warn!(
-                        env = key,
-                        value = %raw,
-                        err = %e,
-                        "env var is set but not a valid u64; falling back to next key"
-                    );
-                }
-            },
-            Err(_) => continue,
-        }
-    }
-    None
-}
-
-/// Build heartbeat config from environment variables.
-///
-/// Required env vars:
-///   - `SERVICE_ID` or `TANGLE_SERVICE_ID` — the service instance ID
-///   - `BLUEPRINT_ID` or `TANGLE_BLUEPRINT_ID` — the blueprint ID
-///   - `STATUS_REGISTRY_ADDRESS` — the OperatorStatusRegistry contract address
-///
-/// Optional:
-///   - `HEARTBEAT_INTERVAL_SECS` — heartbeat interval (default: 120)
-///   - `HEARTBEAT_MAX_MISSED` — max missed beats before slashing (default: 3)
-#[cfg(feature = "qos")]
-pub(crate) fn build_heartbeat_config() -> Option<HeartbeatConfig> {
-    use std::str::FromStr;
-
-    let service_id: u64 = parse_required_u64_env(&["SERVICE_ID", "TANGLE_SERVICE_ID"])?;
-    let blueprint_id: u64 = parse_required_u64_env(&["BLUEPRINT_ID", "TANGLE_BLUEPRINT_ID"])?;
-
-    let registry_addr_str = std::env::var("STATUS_REGISTRY_ADDRESS").ok()?;
-    let status_registry_address =
-        match blueprint_sdk::alloy::primitives::Address::from_str(&registry_addr_str) {
-            Ok(addr) => addr,
-            Err(e) => {
-                warn!(
-                    value = %registry_addr_str,
-                    err = %e,
-                    "STATUS_REGISTRY_ADDRESS is set but not a valid EVM address; heartbeat disabled"
-                );
-                return None;
-            }
-        };
-
-    let interval_secs: u64 = std::env::var("HEARTBEAT_INTERVAL_SECS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(120);
-
-    let max_missed: u32 = std::env::var("HEARTBEAT_MAX_MISSED")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(3);
-
-    Some(HeartbeatConfig {
-        interval_secs,
-        jitter_percent: 10,
-        service_id,
-        blueprint_id,
-        max_missed_heartbeats: max_missed,
-        status_registry_address,
-    })
-}
+//! Service bootstrap helpers: chain-vs-host capacity validation.
 
 /// Cross-check on-chain capacity vs the host admission cap.
 ///
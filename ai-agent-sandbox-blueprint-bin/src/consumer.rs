@@ -1,34 +1,7 @@
-//! Heartbeat consumer + the reconciling Tangle job-result consumer.
+//! The reconciling Tangle job-result consumer.
 
 use super::*;
 
-/// Logging heartbeat consumer that records heartbeat submissions.
-///
-/// The actual on-chain submission is handled internally by `HeartbeatService`
-/// via ECDSA signing + `submitHeartbeat` contract call. This consumer provides
-/// a hook for blueprint-level logging/monitoring of heartbeat events.
-#[cfg(feature = "qos")]
-#[derive(Clone)]
-pub(crate) struct LoggingHeartbeatConsumer;
-
-#[cfg(feature = "qos")]
-impl HeartbeatConsumer for LoggingHeartbeatConsumer {
-    fn send_heartbeat(
-        &self,
-        status: &blueprint_qos::heartbeat::HeartbeatStatus,
-    ) -> std::pin::Pin<
-        Box<dyn std::future::Future<Output = blueprint_qos::error::Result<()>> + Send + 'static>,
-    > {
-        let service_id = status.service_id;
-        let status_code = status.status_code;
-        let ts = status.timestamp;
-        Box::pin(async move {
-            info!("Heartbeat sent: service={service_id} status={status_code} ts={ts}");
-            Ok(())
-        })
-    }
-}
-
 pub(crate) struct DerivedJobResult {
     service_id: u64,
     call_id: u64,
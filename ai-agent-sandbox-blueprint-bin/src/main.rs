@@ -4,7 +4,8 @@ use ai_agent_sandbox_blueprint_lib::workflows::{
     WorkflowEntry, WorkflowStatusError, workflow_key, workflow_runtime_status_for_owner, workflows,
 };
 use ai_agent_sandbox_blueprint_lib::{
-    JOB_WORKFLOW_TICK, JsonResponse, SandboxCreateOutput, bootstrap_workflows_from_chain, router,
+    JOB_WORKFLOW_TICK, JsonResponse, SandboxCreateOutput, bootstrap_sandboxes_from_chain,
+    bootstrap_workflows_from_chain, router, workflow_chain_sync_interval, workflow_chain_sync_tick,
 };
 use axum::extract::Path;
 use axum::http::StatusCode;
@@ -27,13 +28,6 @@ use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 
-#[cfg(feature = "qos")]
-use blueprint_qos::QoSServiceBuilder;
-#[cfg(feature = "qos")]
-use blueprint_qos::heartbeat::{HeartbeatConfig, HeartbeatConsumer};
-#[cfg(feature = "qos")]
-use blueprint_qos::metrics::MetricsConfig;
-
 mod bootstrap;
 mod consumer;
 mod workflow_status;
@@ -45,15 +39,27 @@ use workflow_status::*;
 #[tokio::main]
 #[allow(clippy::result_large_err)]
 async fn main() -> Result<(), blueprint_sdk::Error> {
+    if sandbox_runtime::operator_config::print_config_requested() {
+        std::process::exit(sandbox_runtime::operator_config::print_config());
+    }
     setup_log();
 
-    // Validate required auth config — SESSION_AUTH_SECRET must be set in production.
+    // Layer the optional operator config file (--config / OPERATOR_CONFIG_FILE)
+    // under the environment before anything reads configuration.
+    let operator_config =
+        sandbox_runtime::operator_config::init().map_err(blueprint_sdk::Error::Other)?;
+
+    // Validate the operator config and required auth config — SESSION_AUTH_SECRET
+    // must be set in production.
     // In test mode (--test-mode flag or TEST_MODE env var), log a warning but continue.
     let is_test_mode = std::env::args().any(|a| a == "--test-mode")
         || std::env::var("TEST_MODE")
             .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
             .unwrap_or(false);
-    if let Err(msg) = sandbox_runtime::session_auth::validate_required_config() {
+    if let Err(msg) = operator_config
+        .validate()
+        .and_then(|()| sandbox_runtime::session_auth::validate_required_config())
+    {
         if is_test_mode {
             warn!("Config validation (test mode): {msg}");
         } else {
@@ -61,90 +67,10 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
         }
     }
 
-    // QoS metrics provider is stored here for deferred spawn (after api_shutdown_tx exists).
+    // Optionally start QoS (heartbeat + metrics collection + on-chain
+    // reporting); the metrics loop is spawned once api_shutdown_tx exists.
     #[cfg(feature = "qos")]
-    let mut qos_deferred: Option<(
-        std::sync::Arc<blueprint_qos::metrics::provider::EnhancedMetricsProvider>,
-        u64,
-    )> = None;
-
-    // Optionally start QoS background service (heartbeat + metrics collection + on-chain reporting)
-    #[cfg(feature = "qos")]
-    {
-        let qos_enabled = std::env::var("QOS_ENABLED")
-            .map(|v| v.eq_ignore_ascii_case("true"))
-            .unwrap_or(false);
-
-        if qos_enabled {
-            let metrics_interval = std::env::var("QOS_METRICS_INTERVAL_SECS")
-                .ok()
-                .and_then(|v| v.parse::<u64>().ok())
-                .unwrap_or(60);
-
-            let dry_run = std::env::var("QOS_DRY_RUN")
-                .map(|v| v.eq_ignore_ascii_case("true"))
-                .unwrap_or(true);
-
-            // Build heartbeat config from environment
-            let heartbeat_config = build_heartbeat_config();
-
-            let mut builder = QoSServiceBuilder::<LoggingHeartbeatConsumer>::new()
-                .with_metrics_config(MetricsConfig::default())
-                .with_dry_run(dry_run);
-
-            // Wire heartbeat if config is available (service_id and blueprint_id set)
-            if let Some(hb_config) = heartbeat_config {
-                let rpc_endpoint = std::env::var("HTTP_RPC_ENDPOINT")
-                    .or_else(|_| std::env::var("RPC_URL"))
-                    .unwrap_or_else(|_| "http://localhost:9944".to_string());
-
-                let keystore_uri = std::env::var("KEYSTORE_URI")
-                    .unwrap_or_else(|_| "file:///tmp/keystore".to_string());
-
-                let registry_address = hb_config.status_registry_address;
-
-                info!(
-                    "Configuring heartbeat: service_id={}, blueprint_id={}, interval={}s, registry={}",
-                    hb_config.service_id,
-                    hb_config.blueprint_id,
-                    hb_config.interval_secs,
-                    registry_address,
-                );
-
-                builder = builder
-                    .with_heartbeat_config(hb_config)
-                    .with_heartbeat_consumer(Arc::new(LoggingHeartbeatConsumer))
-                    .with_http_rpc_endpoint(rpc_endpoint)
-                    .with_keystore_uri(keystore_uri)
-                    .with_status_registry_address(registry_address);
-            }
-
-            match builder.build().await {
-                Ok(qos_service) => {
-                    info!(
-                        "QoS service initialized (metrics_interval={metrics_interval}s, dry_run={dry_run})"
-                    );
-
-                    // Start heartbeat background task if configured
-                    if let Some(hb) = qos_service.heartbeat_service() {
-                        match hb.start_heartbeat().await {
-                            Ok(()) => info!("Heartbeat service started"),
-                            Err(e) => error!("Failed to start heartbeat: {e}"),
-                        }
-                    }
-
-                    // Store QoS provider + interval for deferred spawn (after
-                    // api_shutdown_tx is created — see below).
-                    if let Some(provider) = qos_service.provider() {
-                        qos_deferred = Some((provider, metrics_interval));
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to initialize QoS service: {e} — continuing without QoS");
-                }
-            }
-        }
-    }
+    let qos_metrics = sandbox_runtime::qos::start_qos_from_env().await;
 
     // Optionally initialize TEE backend (when TEE_BACKEND env var is set)
     let tee_backend: Option<std::sync::Arc<dyn sandbox_runtime::tee::TeeBackend>> =
@@ -282,9 +208,12 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
     let api_shutdown = tokio::sync::watch::channel(());
     let api_shutdown_tx = api_shutdown.0;
     let api_handle = {
+        let extra_routes = workflow_status_router();
+        #[cfg(feature = "grpc")]
+        let extra_routes = extra_routes.merge(sandbox_runtime::grpc::grpc_router());
         let router = sandbox_runtime::operator_api::operator_api_router_with_tee_and_routes(
             tee_backend,
-            workflow_status_router(),
+            extra_routes,
         );
         let addr = std::net::SocketAddr::from((bind_addr, api_port));
         info!("Starting operator API on {addr}");
@@ -313,17 +242,24 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
         error!("Failed to load workflows from chain: {err}");
     }
 
+    // Rebuild sandbox records from chain when the state dir was lost, before
+    // reconcile so re-adopted containers are checked like any other record.
+    if let Err(err) = bootstrap_sandboxes_from_chain(&tangle_client, service_id).await {
+        error!("Failed to bootstrap sandboxes from chain: {err}");
+    }
+
     // Reconcile stored sandbox state with Docker reality
     ai_agent_sandbox_blueprint_lib::reaper::reconcile_on_startup().await;
 
-    // Spawn reaper background task (idle timeout + max lifetime enforcement)
-    {
+    // Spawn reaper background task (idle timeout + max lifetime enforcement).
+    // The handles let shutdown wait for a tick that is still running.
+    let background_tasks = {
         let config = ai_agent_sandbox_blueprint_lib::runtime::SidecarRuntimeConfig::load();
         let reaper_interval = config.sandbox_reaper_interval;
         let gc_interval = config.sandbox_gc_interval;
 
         let mut reaper_shutdown = api_shutdown_tx.subscribe();
-        tokio::spawn(async move {
+        let reaper = tokio::spawn(async move {
             let mut interval =
                 tokio::time::interval(std::time::Duration::from_secs(reaper_interval));
             loop {
@@ -348,7 +284,7 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
 
         // Spawn GC background task (stopped sandbox cleanup)
         let mut gc_shutdown = api_shutdown_tx.subscribe();
-        tokio::spawn(async move {
+        let gc = tokio::spawn(async move {
             let mut interval = tokio::time::interval(std::time::Duration::from_secs(gc_interval));
             loop {
                 tokio::select! {
@@ -370,7 +306,7 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
 
         // Spawn session GC background task (expired challenges + sessions cleanup)
         let mut gc_session_shutdown = api_shutdown_tx.subscribe();
-        tokio::spawn(async move {
+        let session_gc = tokio::spawn(async move {
             let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
             loop {
                 tokio::select! {
@@ -389,32 +325,140 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
                 }
             }
         });
-    }
 
-    // Spawn deferred QoS metrics loop now that api_shutdown_tx exists
-    #[cfg(feature = "qos")]
-    if let Some((provider, interval_secs)) = qos_deferred {
-        let mut qos_shutdown = api_shutdown_tx.subscribe();
-        tokio::spawn(async move {
-            use blueprint_qos::metrics::types::MetricsProvider;
+        let mut tasks = vec![reaper, gc, session_gc];
+
+        // Publish per-sandbox proxy routes when a registrar is installed.
+        if sandbox_runtime::proxy_routes::proxy_routes_enabled() {
+            let sync_interval = sandbox_runtime::proxy_routes::proxy_route_sync_interval();
+            let mut routes_shutdown = api_shutdown_tx.subscribe();
+            tasks.push(tokio::spawn(async move {
+                let mut interval =
+                    tokio::time::interval(std::time::Duration::from_secs(sync_interval));
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {
+                            let h = tokio::spawn(
+                                sandbox_runtime::proxy_routes::reconcile_proxy_routes()
+                            );
+                            match h.await {
+                                Ok(Err(e)) => warn!("Proxy route sync failed: {e}"),
+                                Err(e) => error!("Proxy route sync panicked: {e}"),
+                                Ok(Ok(_)) => {}
+                            }
+                        }
+                        _ = routes_shutdown.changed() => {
+                            info!("Proxy route sync shutting down");
+                            break;
+                        }
+                    }
+                }
+            }));
+        }
 
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
-            loop {
-                tokio::select! {
-                    _ = interval.tick() => {
-                        let snapshot =
-                            ai_agent_sandbox_blueprint_lib::metrics::metrics().snapshot();
-                        for (key, value) in snapshot {
-                            provider.add_on_chain_metric(key, value).await;
+        // Pre-pull sidecar images now and on a schedule, pruning unused
+        // images when over the cache budget.
+        if sandbox_runtime::image_cache::image_prepull_enabled() {
+            let prepull_interval = sandbox_runtime::image_cache::image_prepull_interval();
+            let mut prepull_shutdown = api_shutdown_tx.subscribe();
+            tasks.push(tokio::spawn(async move {
+                let mut interval =
+                    tokio::time::interval(std::time::Duration::from_secs(prepull_interval));
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {
+                            let h = tokio::spawn(
+                                sandbox_runtime::image_cache::image_cache_tick()
+                            );
+                            match h.await {
+                                Ok(Err(e)) => warn!("Image pre-pull failed: {e}"),
+                                Err(e) => error!("Image pre-pull panicked: {e}"),
+                                Ok(Ok(_)) => {}
+                            }
+                        }
+                        _ = prepull_shutdown.changed() => {
+                            info!("Image pre-pull shutting down");
+                            break;
                         }
                     }
-                    _ = qos_shutdown.changed() => {
-                        info!("QoS metrics loop shutting down");
-                        break;
+                }
+            }));
+        }
+
+        // React to sandbox containers that were OOM-killed or died.
+        if sandbox_runtime::crash_monitor::crash_monitor_enabled() {
+            tasks.push(tokio::spawn(
+                sandbox_runtime::crash_monitor::run_crash_monitor(api_shutdown_tx.subscribe()),
+            ));
+        }
+
+        // Measure sandbox disk usage against `disk_gb`, warning owners and
+        // suspending agent runs over the quota.
+        if sandbox_runtime::disk_quota::disk_quota_enabled() {
+            let check_interval = sandbox_runtime::disk_quota::disk_check_interval();
+            let mut disk_shutdown = api_shutdown_tx.subscribe();
+            tasks.push(tokio::spawn(async move {
+                let mut interval =
+                    tokio::time::interval(std::time::Duration::from_secs(check_interval));
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {
+                            let h = tokio::spawn(
+                                sandbox_runtime::disk_quota::disk_quota_tick()
+                            );
+                            match h.await {
+                                Ok(Err(e)) => warn!("Disk quota check failed: {e}"),
+                                Err(e) => error!("Disk quota check panicked: {e}"),
+                                Ok(Ok(_)) => {}
+                            }
+                        }
+                        _ = disk_shutdown.changed() => {
+                            info!("Disk quota checks shutting down");
+                            break;
+                        }
                     }
                 }
-            }
-        });
+            }));
+        }
+
+        // Pick up workflows registered or changed on chain after startup.
+        if let Some(sync_interval) = workflow_chain_sync_interval() {
+            let client = tangle_client.clone();
+            let mut sync_shutdown = api_shutdown_tx.subscribe();
+            tasks.push(tokio::spawn(async move {
+                let mut interval =
+                    tokio::time::interval(std::time::Duration::from_secs(sync_interval));
+                // The startup bootstrap just ran; skip the immediate first tick.
+                interval.tick().await;
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {
+                            let client = client.clone();
+                            let h = tokio::spawn(async move {
+                                workflow_chain_sync_tick(&client, service_id).await
+                            });
+                            match h.await {
+                                Ok(Err(e)) => warn!("Workflow chain sync failed: {e}"),
+                                Err(e) => error!("Workflow chain sync panicked: {e}"),
+                                Ok(Ok(_)) => {}
+                            }
+                        }
+                        _ = sync_shutdown.changed() => {
+                            info!("Workflow chain sync shutting down");
+                            break;
+                        }
+                    }
+                }
+            }));
+        }
+
+        tasks
+    };
+
+    // Spawn deferred QoS metrics loop now that api_shutdown_tx exists
+    #[cfg(feature = "qos")]
+    if let Some(qos) = qos_metrics {
+        sandbox_runtime::qos::spawn_qos_metrics_loop(qos, api_shutdown_tx.subscribe());
     }
 
     // Create producer (listens for JobSubmitted events) and consumer (submits results)
@@ -462,13 +506,42 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
         .with_shutdown_handler(async move {
             info!("Shutting down ai-agent-sandbox-blueprint blueprint");
 
-            // Signal the API server to stop accepting new connections and drain in-flight requests.
+            // Refuse new jobs, then give the ones already running a bounded
+            // window to finish and record their results.
+            sandbox_runtime::shutdown::begin_shutdown();
+            let drain_timeout = sandbox_runtime::shutdown::job_drain_timeout();
+            match sandbox_runtime::shutdown::jobs()
+                .wait_idle(drain_timeout)
+                .await
+            {
+                0 => info!("In-flight jobs finished"),
+                n => warn!(
+                    "{n} job(s) still running after {}s; shutting down anyway",
+                    drain_timeout.as_secs()
+                ),
+            }
+
+            // Signal the API server and background tasks to stop; the API drains
+            // in-flight requests and the reaper/GC finish their current tick.
             drop(api_shutdown_tx);
             match tokio::time::timeout(std::time::Duration::from_secs(10), api_handle).await {
                 Ok(Ok(())) => info!("Operator API shut down cleanly"),
                 Ok(Err(e)) => error!("Operator API task panicked: {e}"),
                 Err(_) => warn!("Operator API shutdown timed out after 10s"),
             }
+            let background = futures_util::future::join_all(background_tasks);
+            if tokio::time::timeout(std::time::Duration::from_secs(10), background)
+                .await
+                .is_err()
+            {
+                warn!("Background tasks did not stop within 10s");
+            }
+
+            // Everything that writes state has stopped; make it durable.
+            match sandbox_runtime::store::sync_state_dir() {
+                Ok(n) => info!("Synced {n} state file(s) to disk"),
+                Err(e) => error!("Failed to sync state dir: {e}"),
+            }
 
             // Only unregister from BPM AFTER the API is fully stopped, so the proxy
             // doesn't reject requests while we're still processing them.
@@ -479,6 +552,9 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
                     info!("Unregistered from BPM proxy");
                 }
             }
+
+            // Flush logs still queued for OTLP export.
+            sandbox_runtime::logging::shutdown();
         })
         .run()
         .await;
@@ -491,14 +567,7 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
 }
 
 fn setup_log() {
-    use tracing_subscriber::prelude::*;
-    use tracing_subscriber::{EnvFilter, fmt};
-    if tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::from_default_env())
-        .try_init()
-        .is_err()
-    {}
+    sandbox_runtime::logging::init();
 }
 
 #[cfg(test)]
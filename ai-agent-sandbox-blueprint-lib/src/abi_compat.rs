@@ -0,0 +1,243 @@
+//! Decoding of historical `SandboxCreateRequest` layouts.
+//!
+//! Appending a field to `SandboxCreateRequest` changes its ABI encoding, so
+//! calldata from callers built against an older layout no longer decodes.
+//! Every shipped layout is therefore kept as its own struct:
+//!
+//! | Layout | Fields |
+//! |--------|--------|
+//! | `SandboxCreateRequestV1` | through `tee_type` |
+//! | `SandboxCreateRequestV2` | + `attestation_nonce`, `capabilities_json` |
+//! | `SandboxCreateRequest` (v3) | + `template` |
+//!
+//! [`decode_sandbox_create_request`] accepts any of them and upgrades older
+//! ones to the current struct with the new fields left empty. Changing the
+//! current layout means copying it to a new `SandboxCreateRequestVn` first;
+//! the layout tests below fail until that is done.
+
+use blueprint_sdk::alloy::sol_types::{SolType, SolValue};
+
+use crate::{SandboxCreateRequest, SandboxCreateRequestV1, SandboxCreateRequestV2};
+
+impl From<SandboxCreateRequestV1> for SandboxCreateRequest {
+    fn from(r: SandboxCreateRequestV1) -> Self {
+        Self {
+            name: r.name,
+            image: r.image,
+            stack: r.stack,
+            agent_identifier: r.agent_identifier,
+            env_json: r.env_json,
+            metadata_json: r.metadata_json,
+            ssh_enabled: r.ssh_enabled,
+            ssh_public_key: r.ssh_public_key,
+            web_terminal_enabled: r.web_terminal_enabled,
+            max_lifetime_seconds: r.max_lifetime_seconds,
+            idle_timeout_seconds: r.idle_timeout_seconds,
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            tee_required: r.tee_required,
+            tee_type: r.tee_type,
+            attestation_nonce: String::new(),
+            capabilities_json: String::new(),
+            template: String::new(),
+        }
+    }
+}
+
+impl From<SandboxCreateRequestV2> for SandboxCreateRequest {
+    fn from(r: SandboxCreateRequestV2) -> Self {
+        Self {
+            name: r.name,
+            image: r.image,
+            stack: r.stack,
+            agent_identifier: r.agent_identifier,
+            env_json: r.env_json,
+            metadata_json: r.metadata_json,
+            ssh_enabled: r.ssh_enabled,
+            ssh_public_key: r.ssh_public_key,
+            web_terminal_enabled: r.web_terminal_enabled,
+            max_lifetime_seconds: r.max_lifetime_seconds,
+            idle_timeout_seconds: r.idle_timeout_seconds,
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            tee_required: r.tee_required,
+            tee_type: r.tee_type,
+            attestation_nonce: r.attestation_nonce,
+            capabilities_json: r.capabilities_json,
+            template: String::new(),
+        }
+    }
+}
+
+/// Decode `bytes` as `T` only if `T` re-encodes to exactly `bytes`, in either
+/// tuple or params encoding.
+fn decode_exact<T>(bytes: &[u8]) -> Option<T>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType>,
+{
+    T::abi_decode(bytes)
+        .ok()
+        .filter(|value| value.abi_encode() == bytes)
+        .or_else(|| {
+            T::abi_decode_params(bytes)
+                .ok()
+                .filter(|value| value.abi_encode_params() == bytes)
+        })
+}
+
+/// Decode `bytes` as `T` in tuple or params encoding, ignoring trailing data.
+fn decode_loose<T>(bytes: &[u8]) -> Option<T>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType>,
+{
+    T::abi_decode(bytes)
+        .ok()
+        .or_else(|| T::abi_decode_params(bytes).ok())
+}
+
+/// Decode a `SandboxCreateRequest` in the current or any historical layout.
+///
+/// A shorter layout can decode a prefix of a longer one, so the layout that
+/// re-encodes to exactly `bytes` wins; only when none does (non-canonical
+/// encoders) is the newest layout that decodes at all used.
+pub fn decode_sandbox_create_request(bytes: &[u8]) -> Result<SandboxCreateRequest, String> {
+    decode_exact::<SandboxCreateRequest>(bytes)
+        .or_else(|| decode_exact::<SandboxCreateRequestV2>(bytes).map(Into::into))
+        .or_else(|| decode_exact::<SandboxCreateRequestV1>(bytes).map(Into::into))
+        .or_else(|| decode_loose::<SandboxCreateRequest>(bytes))
+        .or_else(|| decode_loose::<SandboxCreateRequestV2>(bytes).map(Into::into))
+        .or_else(|| decode_loose::<SandboxCreateRequestV1>(bytes).map(Into::into))
+        .ok_or_else(|| {
+            format!(
+                "Failed to decode SandboxCreateRequest ({} bytes) in any known layout",
+                bytes.len()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blueprint_sdk::alloy::sol_types::SolStruct;
+
+    const V1_FIELDS: &str = "string name,string image,string stack,string agent_identifier,\
+        string env_json,string metadata_json,bool ssh_enabled,string ssh_public_key,\
+        bool web_terminal_enabled,uint64 max_lifetime_seconds,uint64 idle_timeout_seconds,\
+        uint64 cpu_cores,uint64 memory_mb,uint64 disk_gb,bool tee_required,uint8 tee_type";
+
+    fn v1() -> SandboxCreateRequestV1 {
+        SandboxCreateRequestV1 {
+            name: "v1-sandbox".to_string(),
+            image: "agent-dev:latest".to_string(),
+            stack: "default".to_string(),
+            agent_identifier: "default-agent".to_string(),
+            env_json: r#"{"A":"1"}"#.to_string(),
+            metadata_json: "{}".to_string(),
+            ssh_enabled: true,
+            ssh_public_key: "ssh-ed25519 AAAA".to_string(),
+            web_terminal_enabled: false,
+            max_lifetime_seconds: 3600,
+            idle_timeout_seconds: 900,
+            cpu_cores: 2,
+            memory_mb: 4096,
+            disk_gb: 20,
+            tee_required: true,
+            tee_type: 1,
+        }
+    }
+
+    fn v2() -> SandboxCreateRequestV2 {
+        let r = v1();
+        SandboxCreateRequestV2 {
+            name: "v2-sandbox".to_string(),
+            image: r.image,
+            stack: r.stack,
+            agent_identifier: r.agent_identifier,
+            env_json: r.env_json,
+            metadata_json: r.metadata_json,
+            ssh_enabled: r.ssh_enabled,
+            ssh_public_key: r.ssh_public_key,
+            web_terminal_enabled: r.web_terminal_enabled,
+            max_lifetime_seconds: r.max_lifetime_seconds,
+            idle_timeout_seconds: r.idle_timeout_seconds,
+            cpu_cores: r.cpu_cores,
+            memory_mb: r.memory_mb,
+            disk_gb: r.disk_gb,
+            tee_required: r.tee_required,
+            tee_type: r.tee_type,
+            attestation_nonce: "ab".repeat(32),
+            capabilities_json: r#"["computer_use"]"#.to_string(),
+        }
+    }
+
+    #[test]
+    fn historical_layouts_are_pinned() {
+        assert_eq!(
+            SandboxCreateRequestV1::eip712_encode_type(),
+            format!("SandboxCreateRequestV1({V1_FIELDS})")
+        );
+        assert_eq!(
+            SandboxCreateRequestV2::eip712_encode_type(),
+            format!(
+                "SandboxCreateRequestV2({V1_FIELDS},string attestation_nonce,\
+                 string capabilities_json)"
+            )
+        );
+        assert_eq!(
+            SandboxCreateRequest::eip712_encode_type(),
+            format!(
+                "SandboxCreateRequest({V1_FIELDS},string attestation_nonce,\
+                 string capabilities_json,string template)"
+            ),
+            "SandboxCreateRequest changed: add the previous layout as a new \
+             SandboxCreateRequestVn and teach decode_sandbox_create_request about it"
+        );
+    }
+
+    #[test]
+    fn v1_calldata_upgrades_with_empty_new_fields() {
+        for encoded in [v1().abi_encode(), v1().abi_encode_params()] {
+            let decoded = decode_sandbox_create_request(&encoded).unwrap();
+            assert_eq!(decoded.name, "v1-sandbox");
+            assert_eq!(decoded.ssh_public_key, "ssh-ed25519 AAAA");
+            assert_eq!(decoded.disk_gb, 20);
+            assert!(decoded.tee_required);
+            assert_eq!(decoded.tee_type, 1);
+            assert!(decoded.attestation_nonce.is_empty());
+            assert!(decoded.capabilities_json.is_empty());
+            assert!(decoded.template.is_empty());
+        }
+    }
+
+    #[test]
+    fn v2_calldata_keeps_nonce_and_capabilities() {
+        for encoded in [v2().abi_encode(), v2().abi_encode_params()] {
+            let decoded = decode_sandbox_create_request(&encoded).unwrap();
+            assert_eq!(decoded.name, "v2-sandbox");
+            assert_eq!(decoded.attestation_nonce, "ab".repeat(32));
+            assert_eq!(decoded.capabilities_json, r#"["computer_use"]"#);
+            assert!(decoded.template.is_empty());
+        }
+    }
+
+    #[test]
+    fn current_calldata_is_not_mistaken_for_a_prefix_layout() {
+        let mut current = SandboxCreateRequest::from(v2());
+        current.name = "v3-sandbox".to_string();
+        current.template = "python-small".to_string();
+        for encoded in [current.abi_encode(), current.abi_encode_params()] {
+            let decoded = decode_sandbox_create_request(&encoded).unwrap();
+            assert_eq!(decoded.name, "v3-sandbox");
+            assert_eq!(decoded.template, "python-small");
+            assert_eq!(decoded.abi_encode(), current.abi_encode());
+        }
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        assert!(decode_sandbox_create_request(&[0xde, 0xad, 0xbe, 0xef]).is_err());
+        assert!(decode_sandbox_create_request(&[]).is_err());
+    }
+}
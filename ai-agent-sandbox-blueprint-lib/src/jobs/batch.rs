@@ -10,6 +10,9 @@ use crate::JsonResponse;
 use crate::jobs::exec::run_task_request;
 use crate::runtime::{create_sidecar, require_sandbox_owner_by_url};
 use crate::tangle::extract::{Caller, TangleArg, TangleResult};
+use sandbox_runtime::exec_output::cap_exec_output;
+use sandbox_runtime::exec_policy::{CommandInput, CommandKind, enforce_command_policy};
+use sandbox_runtime::templates::apply_template;
 
 /// Maximum number of concurrent operations in parallel batch execution.
 const MAX_BATCH_CONCURRENCY: usize = 10;
@@ -29,6 +32,9 @@ pub async fn batch_create(
     }
 
     let mut params = CreateSandboxParams::from(&request.template_request);
+    if !request.template_request.template.trim().is_empty() {
+        apply_template(&mut params, &request.template_request.template)?;
+    }
     params.owner = super::caller_hex(&caller);
     if request.template_request.tee_required
         && !request.template_request.attestation_nonce.trim().is_empty()
@@ -38,6 +44,24 @@ pub async fn batch_create(
             &request.template_request.attestation_nonce,
         )?);
     }
+    // Refuse the whole batch up front rather than create part of it; each
+    // create still re-checks under the creation permit.
+    let config = crate::runtime::SidecarRuntimeConfig::load();
+    sandbox_runtime::owner_quota::check_owner_create(
+        &params.owner,
+        u64::from(request.count),
+        if params.cpu_cores == 0 {
+            config.sandbox_max_cpu_cores
+        } else {
+            params.cpu_cores
+        },
+        if params.memory_mb == 0 {
+            config.sandbox_max_memory_mb
+        } else {
+            params.memory_mb
+        },
+        None,
+    )?;
     let tee = crate::tee_backend().map(|b| b.as_ref());
     let mut sandboxes_out = Vec::with_capacity(request.count as usize);
     for _ in 0..request.count {
@@ -50,8 +74,23 @@ pub async fn batch_create(
         }));
     }
 
+    let batch_id = crate::next_batch_id();
+    crate::batches()
+        .map_err(|e| e.to_string())?
+        .insert(
+            batch_id.clone(),
+            crate::BatchRecord {
+                id: batch_id.clone(),
+                kind: "create".to_string(),
+                owner: params.owner.clone(),
+                results: Value::Array(sandboxes_out.clone()),
+                created_at: crate::util::now_ts(),
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    notify_batch_completed(&params.owner, &batch_id, "create", sandboxes_out.len());
     let response = json!({
-        "batchId": crate::next_batch_id(),
+        "batchId": batch_id,
         "sandboxes": sandboxes_out,
     });
 
@@ -74,10 +113,22 @@ pub async fn batch_task(
 
     let caller_hex = super::caller_hex(&caller);
     let validated = validate_urls_with_owner(&request.sidecar_urls, &caller_hex)?;
+    for url in &request.sidecar_urls {
+        let record = require_sandbox_owner_by_url(url, &caller_hex)?;
+        sandbox_runtime::disk_quota::ensure_disk_writable(&record.id)?;
+    }
+
+    // Owner task quota: the batch runs with as many slots as are free.
+    let wanted = if request.parallel {
+        validated.len().min(MAX_BATCH_CONCURRENCY)
+    } else {
+        1
+    };
+    let quota_slots = sandbox_runtime::owner_quota::acquire_task_slots(&caller_hex, wanted)?;
 
     let results = if request.parallel {
         let mut results = vec![Value::Null; validated.len()];
-        let sem = std::sync::Arc::new(tokio::sync::Semaphore::new(MAX_BATCH_CONCURRENCY));
+        let sem = std::sync::Arc::new(tokio::sync::Semaphore::new(quota_slots.len()));
         let mut set = JoinSet::new();
 
         for (idx, (url, tok)) in validated.iter().enumerate() {
@@ -107,7 +158,9 @@ pub async fn batch_task(
         results
     };
 
-    store_batch("task", results).await
+    drop(quota_slots);
+
+    store_batch("task", &caller_hex, results).await
 }
 
 fn make_task_request(sidecar_url: &str, request: &BatchTaskRequest) -> crate::SandboxTaskRequest {
@@ -119,6 +172,9 @@ fn make_task_request(sidecar_url: &str, request: &BatchTaskRequest) -> crate::Sa
         model: request.model.to_string(),
         context_json: request.context_json.to_string(),
         timeout_ms: request.timeout_ms,
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
     }
 }
 
@@ -160,6 +216,19 @@ pub async fn batch_exec(
 
     let caller_hex = super::caller_hex(&caller);
     let validated = validate_urls_with_owner(&request.sidecar_urls, &caller_hex)?;
+    let input = CommandInput {
+        kind: CommandKind::Exec,
+        command: &request.command,
+        cwd: &request.cwd,
+        timeout_ms: request.timeout_ms,
+        ..Default::default()
+    };
+    for (url, _) in &validated {
+        let sandbox_id = crate::runtime::get_sandbox_by_url_opt(url)
+            .map(|record| record.id)
+            .unwrap_or_default();
+        enforce_command_policy(&sandbox_id, &caller_hex, &input)?;
+    }
 
     let results = if request.parallel {
         let mut results = vec![Value::Null; validated.len()];
@@ -200,7 +269,7 @@ pub async fn batch_exec(
         results
     };
 
-    store_batch("exec", results).await
+    store_batch("exec", &caller_hex, results).await
 }
 
 async fn exec_and_format(
@@ -216,15 +285,23 @@ async fn exec_and_format(
     )
     .await
     .map(|parsed| {
-        if let Some(record) = crate::runtime::get_sandbox_by_url_opt(sidecar_url) {
-            crate::runtime::touch_sandbox(&record.id);
+        let record = crate::runtime::get_sandbox_by_url_opt(sidecar_url);
+        let sandbox_id = record.as_ref().map(|r| r.id.clone()).unwrap_or_default();
+        if !sandbox_id.is_empty() {
+            crate::runtime::touch_sandbox(&sandbox_id);
         }
-        let (exit_code, stdout, stderr) = crate::jobs::exec::extract_exec_fields(&parsed);
+        let api = record
+            .as_ref()
+            .map(sandbox_runtime::sidecar_compat::SidecarApi::for_record)
+            .unwrap_or_default();
+        let (exit_code, stdout, stderr) = crate::jobs::exec::extract_exec_fields_for(api, &parsed);
+        let output = cap_exec_output(&sandbox_id, stdout, stderr);
         json!({
             "sidecarUrl": sidecar_url,
             "exitCode": exit_code,
-            "stdout": stdout,
-            "stderr": stderr,
+            "stdout": output.stdout,
+            "stderr": output.stderr,
+            "overflowRef": output.overflow_ref,
         })
     })
     .unwrap_or_else(|err| {
@@ -240,15 +317,10 @@ async fn exec_and_format(
 // ---------------------------------------------------------------------------
 
 pub async fn batch_collect(
-    Caller(_caller): Caller,
+    Caller(caller): Caller,
     TangleArg(request): TangleArg<BatchCollectRequest>,
 ) -> Result<TangleResult<JsonResponse>, String> {
-    let batch_id = request.batch_id.to_string();
-    let record = crate::batches()
-        .map_err(|e| e.to_string())?
-        .remove(&batch_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Batch not found".to_string())?;
+    let record = take_batch(&request.batch_id, &super::caller_hex(&caller))?;
 
     let response = json!({
         "batchId": record.id,
@@ -265,6 +337,21 @@ pub async fn batch_collect(
 // Shared helpers
 // ---------------------------------------------------------------------------
 
+/// Remove and return a stored batch, provided `caller` is the one who ran it.
+/// A batch owned by someone else is left in place.
+pub fn take_batch(batch_id: &str, caller: &str) -> Result<crate::BatchRecord, String> {
+    let store = crate::batches().map_err(|e| e.to_string())?;
+    let record = store
+        .get(batch_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Batch not found".to_string())?;
+    if record.owner.is_empty() || !record.owner.eq_ignore_ascii_case(caller) {
+        return Err(format!("Caller {caller} does not own batch '{batch_id}'"));
+    }
+    store.remove(batch_id).map_err(|e| e.to_string())?;
+    Ok(record)
+}
+
 /// Validate caller owns all sandboxes at the given URLs. Returns (url, token) pairs.
 fn validate_urls_with_owner(
     urls: &[String],
@@ -278,14 +365,25 @@ fn validate_urls_with_owner(
         .collect()
 }
 
+/// Send `batch.completed` to the caller's webhooks.
+fn notify_batch_completed(owner: &str, batch_id: &str, kind: &str, count: usize) {
+    sandbox_runtime::webhooks::emit(
+        owner,
+        sandbox_runtime::webhooks::WebhookEvent::BatchCompleted,
+        json!({ "batch_id": batch_id, "kind": kind, "count": count }),
+    );
+}
+
 async fn store_batch(
     kind: &str,
+    owner: &str,
     results: Vec<Value>,
 ) -> Result<TangleResult<JsonResponse>, String> {
     let batch_id = crate::next_batch_id();
     let record = crate::BatchRecord {
         id: batch_id.clone(),
         kind: kind.to_string(),
+        owner: owner.to_string(),
         results: Value::Array(results.clone()),
         created_at: crate::util::now_ts(),
     };
@@ -294,6 +392,7 @@ async fn store_batch(
         .map_err(|e| e.to_string())?
         .insert(batch_id.clone(), record)
         .map_err(|e| e.to_string())?;
+    notify_batch_completed(owner, &batch_id, kind, results.len());
 
     let results_key = format!("{kind}Results");
     let response = json!({
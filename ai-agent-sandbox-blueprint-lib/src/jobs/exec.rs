@@ -7,8 +7,19 @@ use crate::SandboxPromptResponse;
 use crate::SandboxTaskRequest;
 use crate::SandboxTaskResponse;
 use crate::http::sidecar_post_json;
-use crate::runtime::require_sandbox_owner_by_url;
+use crate::runtime::{require_sandbox_owner_by_url, resolve_agent_identifier};
 use crate::tangle::extract::{Caller, TangleArg, TangleResult};
+use sandbox_runtime::agent_runs::reserve_agent_run;
+use sandbox_runtime::exec_output::cap_exec_output;
+use sandbox_runtime::exec_policy::{CommandInput, CommandKind, enforce_command_policy};
+use sandbox_runtime::model_policy::{check_model, payload_model, record_model_usage};
+use sandbox_runtime::output_schema::{
+    insert_output_schema, parse_output_schema, run_with_output_schema,
+};
+use sandbox_runtime::prompt_templates::apply_prompt_template;
+use sandbox_runtime::sidecar_compat::SidecarApi;
+use sandbox_runtime::tool_policy::{insert_tool_policy, parse_tool_policy, require_tool_echo};
+use sandbox_runtime::usage_ledger::{UsageTotals, record_usage};
 
 // ---------------------------------------------------------------------------
 // Exec (terminal commands)
@@ -17,8 +28,15 @@ use crate::tangle::extract::{Caller, TangleArg, TangleResult};
 /// Extract exec response fields from the sidecar `/terminals/commands` response.
 ///
 /// Response shape: `{ success, result: { exitCode, stdout, stderr, duration } }`
+/// (sidecar API 1; see [`extract_exec_fields_for`]).
 pub fn extract_exec_fields(parsed: &Value) -> (u32, String, String) {
-    let result = parsed.get("result");
+    extract_exec_fields_for(SidecarApi::V1, parsed)
+}
+
+/// [`extract_exec_fields`] for the response envelope of the sidecar API the
+/// sandbox negotiated at provision.
+pub fn extract_exec_fields_for(api: SidecarApi, parsed: &Value) -> (u32, String, String) {
+    let result = api.command_result(parsed);
 
     let exit_code = result
         .and_then(|r| r.get("exitCode"))
@@ -63,6 +81,22 @@ pub fn build_exec_payload(
     payload
 }
 
+/// [`build_exec_payload`] plus the command's stdin: inline `stdin` text or a
+/// `stdin_file` uploaded to the sandbox beforehand (see
+/// `sandbox_runtime::exec_input`).
+pub fn build_exec_payload_with_stdin(
+    command: &str,
+    cwd: &str,
+    env_json: &str,
+    timeout_ms: u64,
+    stdin: &str,
+    stdin_file: &str,
+) -> Map<String, Value> {
+    let mut payload = build_exec_payload(command, cwd, env_json, timeout_ms);
+    sandbox_runtime::exec_input::insert_exec_input(&mut payload, stdin, stdin_file);
+    payload
+}
+
 /// Run an exec request against a sidecar. Callable from tests without Tangle extractors.
 ///
 /// The `sidecar_token` is passed explicitly rather than being part of the
@@ -71,11 +105,28 @@ pub async fn run_exec_request(
     request: &SandboxExecRequest,
     sidecar_token: &str,
 ) -> Result<SandboxExecResponse, String> {
-    let payload = build_exec_payload(
+    sandbox_runtime::exec_input::validate_exec_input(&request.stdin, &request.stdin_file)?;
+    let record = crate::runtime::get_sandbox_by_url_opt(&request.sidecar_url);
+    let sandbox_id = record.as_ref().map(|r| r.id.clone()).unwrap_or_default();
+    let input = CommandInput {
+        kind: CommandKind::Exec,
+        command: &request.command,
+        cwd: &request.cwd,
+        stdin_file: &request.stdin_file,
+        timeout_ms: request.timeout_ms,
+    };
+    let owner = record
+        .as_ref()
+        .map(|r| r.owner.as_str())
+        .unwrap_or_default();
+    enforce_command_policy(&sandbox_id, owner, &input)?;
+    let payload = build_exec_payload_with_stdin(
         &request.command,
         &request.cwd,
         &request.env_json,
         request.timeout_ms,
+        &request.stdin,
+        &request.stdin_file,
     );
 
     let parsed = sidecar_post_json(
@@ -87,16 +138,23 @@ pub async fn run_exec_request(
     .await
     .map_err(|e| e.to_string())?;
 
-    if let Some(record) = crate::runtime::get_sandbox_by_url_opt(&request.sidecar_url) {
-        crate::runtime::touch_sandbox(&record.id);
+    if !sandbox_id.is_empty() {
+        crate::runtime::touch_sandbox(&sandbox_id);
+        record_usage(owner, UsageTotals::exec());
     }
 
-    let (exit_code, stdout, stderr) = extract_exec_fields(&parsed);
+    let api = record
+        .as_ref()
+        .map(SidecarApi::for_record)
+        .unwrap_or_default();
+    let (exit_code, stdout, stderr) = extract_exec_fields_for(api, &parsed);
+    let output = cap_exec_output(&sandbox_id, stdout, stderr);
 
     Ok(SandboxExecResponse {
         exit_code,
-        stdout,
-        stderr,
+        stdout: output.stdout,
+        stderr: output.stderr,
+        overflow_ref: output.overflow_ref.unwrap_or_default(),
     })
 }
 
@@ -116,11 +174,13 @@ pub async fn sandbox_exec(
 // ---------------------------------------------------------------------------
 
 /// Build the common `/agents/run` payload used by both prompt and task requests.
+/// `agent_identifier` selects the sidecar agent (empty = `"default"`).
 ///
 /// When `backend_profile` is provided, it is set as `backend.profile` so the
 /// sidecar agent session uses it as persistent context. The profile can contain
 /// `systemPrompt`, `resources.instructions`, `permission`, `memory`, etc.
 pub fn build_agent_payload(
+    agent_identifier: &str,
     message: &str,
     session_id: &str,
     model: &str,
@@ -130,9 +190,13 @@ pub fn build_agent_payload(
     backend_profile: Option<&Value>,
 ) -> Result<Map<String, Value>, String> {
     let mut payload = Map::new();
+    let identifier = match agent_identifier.trim() {
+        "" => sandbox_runtime::runtime::DEFAULT_AGENT_IDENTIFIER,
+        id => id,
+    };
     payload.insert(
         "identifier".to_string(),
-        Value::String("default".to_string()),
+        Value::String(identifier.to_string()),
     );
     payload.insert("message".to_string(), Value::String(message.to_string()));
 
@@ -196,6 +260,8 @@ struct AgentResponse {
     input_tokens: u32,
     output_tokens: u32,
     session_id: String,
+    /// Tool policy echoed in the response metadata (see `tool_policy`).
+    tools: String,
 }
 
 fn parse_agent_response(parsed: &Value, fallback_session_id: &str) -> AgentResponse {
@@ -237,6 +303,7 @@ fn parse_agent_response(parsed: &Value, fallback_session_id: &str) -> AgentRespo
         input_tokens,
         output_tokens,
         session_id,
+        tools: sandbox_runtime::tool_policy::echoed_tools(parsed),
     }
 }
 
@@ -244,15 +311,24 @@ fn parse_agent_response(parsed: &Value, fallback_session_id: &str) -> AgentRespo
 async fn call_agent(
     sidecar_url: &str,
     sidecar_token: &str,
-    payload: Map<String, Value>,
+    mut payload: Map<String, Value>,
     fallback_session_id: &str,
 ) -> Result<AgentResponse, String> {
-    if let Some(record) = crate::runtime::get_sandbox_by_url_opt(sidecar_url) {
+    let mut agent_run = None;
+    let record = crate::runtime::get_sandbox_by_url_opt(sidecar_url);
+    if let Some(record) = &record {
         crate::runtime::touch_sandbox(&record.id);
+        apply_prompt_template(&record.id, &mut payload)?;
+        agent_run = Some(reserve_agent_run(&record.id).map_err(|e| e.to_string())?);
+    }
+    // Wait for the sandbox's agent run limit (see `sandbox_runtime::agent_runs`).
+    if let Some(agent_run) = agent_run.as_mut() {
+        agent_run.ready().await;
     }
 
     let m = crate::metrics::metrics();
     let _session = m.session_guard();
+    let model = payload_model(&payload);
 
     let parsed = sidecar_post_json(
         sidecar_url,
@@ -267,6 +343,13 @@ async fn call_agent(
 
     if resp.success {
         m.record_job(resp.duration_ms, resp.input_tokens, resp.output_tokens);
+        record_model_usage(&model, resp.input_tokens, resp.output_tokens);
+        if let Some(record) = &record {
+            record_usage(
+                &record.owner,
+                UsageTotals::tokens(resp.input_tokens, resp.output_tokens),
+            );
+        }
     } else {
         m.record_failure();
     }
@@ -283,15 +366,19 @@ pub async fn run_prompt_request(
     request: &SandboxPromptRequest,
     sidecar_token: &str,
 ) -> Result<SandboxPromptResponse, String> {
-    let payload = build_agent_payload(
+    let record = crate::runtime::get_sandbox_by_url_opt(&request.sidecar_url);
+    let grant = check_model(&request.model, request.timeout_ms)?;
+    let mut payload = build_agent_payload(
+        &resolve_agent_identifier(&request.agent_identifier, record.as_ref()),
         &request.message,
         &request.session_id,
         &request.model,
         &request.context_json,
-        request.timeout_ms,
+        grant.timeout_ms,
         None,
         None,
     )?;
+    grant.apply(&mut payload);
 
     let resp = call_agent(
         &request.sidecar_url,
@@ -318,6 +405,7 @@ pub async fn sandbox_prompt(
 ) -> Result<TangleResult<SandboxPromptResponse>, String> {
     let caller_hex = super::caller_hex(&caller);
     let record = require_sandbox_owner_by_url(&request.sidecar_url, &caller_hex)?;
+    sandbox_runtime::disk_quota::ensure_disk_writable(&record.id)?;
 
     let response = run_prompt_request(&request, &record.token).await?;
     Ok(TangleResult(response))
@@ -355,45 +443,56 @@ pub async fn run_task_request_with_system_prompt(
 /// The profile is a JSON object set as `backend.profile` in the sidecar
 /// `/agents/run` payload. It can contain `systemPrompt`, `resources.instructions`,
 /// `permission`, `memory`, and other sidecar profile fields.
+/// With `output_schema_json` set, the result is validated against it (see
+/// `sandbox_runtime::output_schema`).
 pub async fn run_task_request_with_profile(
     request: &SandboxTaskRequest,
     sidecar_token: &str,
     backend_profile: Option<&Value>,
 ) -> Result<SandboxTaskResponse, String> {
-    let mut extra = Map::new();
-    if request.max_turns > 0 {
-        extra.insert("maxTurns".to_string(), json!(request.max_turns));
-        extra.insert("maxSteps".to_string(), json!(request.max_turns));
-    }
-
-    let payload = build_agent_payload(
-        &request.prompt,
-        &request.session_id,
-        &request.model,
-        &request.context_json,
-        request.timeout_ms,
-        if extra.is_empty() { None } else { Some(extra) },
-        backend_profile,
-    )?;
-
-    let resp = call_agent(
-        &request.sidecar_url,
-        sidecar_token,
-        payload,
-        &request.session_id,
-    )
-    .await?;
+    let schema = parse_output_schema(&request.output_schema_json)?;
+    let tools = parse_tool_policy(&request.tools_json)?;
+    let grant = check_model(&request.model, request.timeout_ms)?;
+    let record = crate::runtime::get_sandbox_by_url_opt(&request.sidecar_url);
+    let agent_identifier = resolve_agent_identifier(&request.agent_identifier, record.as_ref());
+    let run = async |message: String, session_id: String| -> Result<SandboxTaskResponse, String> {
+        let mut extra = Map::new();
+        if request.max_turns > 0 {
+            extra.insert("maxTurns".to_string(), json!(request.max_turns));
+            extra.insert("maxSteps".to_string(), json!(request.max_turns));
+        }
 
-    Ok(SandboxTaskResponse {
-        success: resp.success,
-        result: resp.response,
-        error: resp.error,
-        trace_id: resp.trace_id,
-        duration_ms: resp.duration_ms,
-        input_tokens: resp.input_tokens,
-        output_tokens: resp.output_tokens,
-        session_id: resp.session_id,
-    })
+        let mut payload = build_agent_payload(
+            &agent_identifier,
+            &message,
+            &session_id,
+            &request.model,
+            &request.context_json,
+            grant.timeout_ms,
+            if extra.is_empty() { None } else { Some(extra) },
+            backend_profile,
+        )?;
+        grant.apply(&mut payload);
+        insert_output_schema(&mut payload, schema.as_ref());
+        insert_tool_policy(&mut payload, tools.as_ref());
+
+        let resp = call_agent(&request.sidecar_url, sidecar_token, payload, &session_id).await?;
+
+        let mut output = SandboxTaskResponse {
+            success: resp.success,
+            result: resp.response,
+            error: resp.error,
+            trace_id: resp.trace_id,
+            duration_ms: resp.duration_ms,
+            input_tokens: resp.input_tokens,
+            output_tokens: resp.output_tokens,
+            session_id: resp.session_id,
+            tools_json: resp.tools,
+        };
+        require_tool_echo(&mut output, tools.as_ref());
+        Ok(output)
+    };
+    run_with_output_schema(schema.as_ref(), &request.prompt, &request.session_id, run).await
 }
 
 pub async fn sandbox_task(
@@ -402,134 +501,13 @@ pub async fn sandbox_task(
 ) -> Result<TangleResult<SandboxTaskResponse>, String> {
     let caller_hex = super::caller_hex(&caller);
     let record = require_sandbox_owner_by_url(&request.sidecar_url, &caller_hex)?;
+    sandbox_runtime::disk_quota::ensure_disk_writable(&record.id)?;
+    let _quota_slot = sandbox_runtime::owner_quota::acquire_task_slot(&caller_hex)?;
 
     let response = run_task_request(&request, &record.token).await?;
     Ok(TangleResult(response))
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_build_agent_payload_with_system_prompt() {
-        let profile = system_prompt_to_profile("You are a trading expert.");
-        let payload = build_agent_payload(
-            "hello",
-            "sess-1",
-            "claude-haiku",
-            "",
-            0,
-            None,
-            Some(&profile),
-        )
-        .unwrap();
-
-        let backend = payload.get("backend").unwrap().as_object().unwrap();
-        assert_eq!(backend["model"], "claude-haiku");
-        let p = backend["profile"].as_object().unwrap();
-        assert_eq!(p["systemPrompt"], "You are a trading expert.");
-    }
-
-    #[test]
-    fn test_build_agent_payload_without_profile() {
-        let payload =
-            build_agent_payload("hello", "sess-1", "claude-haiku", "", 0, None, None).unwrap();
-
-        let backend = payload.get("backend").unwrap().as_object().unwrap();
-        assert_eq!(backend["model"], "claude-haiku");
-        assert!(backend.get("profile").is_none());
-    }
-
-    #[test]
-    fn test_build_agent_payload_empty_profile_ignored() {
-        let empty = json!({});
-        let payload = build_agent_payload("hello", "", "", "", 0, None, Some(&empty)).unwrap();
-
-        // No backend at all since model is empty and profile is empty
-        assert!(payload.get("backend").is_none());
-    }
-
-    #[test]
-    fn test_build_agent_payload_full_profile() {
-        let profile = json!({
-            "name": "trading-dex",
-            "resources": {
-                "instructions": {
-                    "content": "You have a persistent workspace.",
-                    "name": "trading-instructions.md"
-                }
-            },
-            "permission": {
-                "bash": "allow",
-                "edit": "allow"
-            },
-            "memory": { "enabled": true }
-        });
-        let payload = build_agent_payload(
-            "trade now",
-            "sess-2",
-            "claude-sonnet",
-            "",
-            0,
-            None,
-            Some(&profile),
-        )
-        .unwrap();
-
-        let backend = payload.get("backend").unwrap().as_object().unwrap();
-        let p = backend["profile"].as_object().unwrap();
-        assert!(
-            p.get("systemPrompt").is_none(),
-            "Full profile should not have systemPrompt"
-        );
-        assert!(p.get("resources").is_some());
-        assert_eq!(p["permission"]["bash"], "allow");
-        assert_eq!(p["memory"]["enabled"], true);
-    }
-
-    #[test]
-    fn test_system_prompt_to_profile() {
-        let profile = system_prompt_to_profile("You are helpful.");
-        let obj = profile.as_object().unwrap();
-        assert_eq!(obj["systemPrompt"], "You are helpful.");
-        assert_eq!(obj.len(), 1);
-    }
-
-    #[test]
-    fn test_build_agent_payload_array_context_json_errors() {
-        let result = build_agent_payload("hi", "", "", "[1,2]", 0, None, None);
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_build_agent_payload_valid_context_merged() {
-        let payload = build_agent_payload("hi", "", "", r#"{"k":"v"}"#, 0, None, None).unwrap();
-        let meta = payload.get("metadata").unwrap().as_object().unwrap();
-        assert_eq!(meta["k"], "v");
-    }
-
-    #[test]
-    fn test_build_agent_payload_whitespace_context_ignored() {
-        let payload = build_agent_payload("hi", "", "", "   ", 0, None, None).unwrap();
-        assert!(payload.get("metadata").is_none());
-    }
-
-    #[test]
-    fn test_build_exec_payload_invalid_env_silently_dropped() {
-        let payload = build_exec_payload("ls", "", "[1]", 0);
-        assert!(payload.get("env").is_none());
-    }
-
-    #[test]
-    fn test_build_exec_payload_valid_env() {
-        let payload = build_exec_payload("ls", "", r#"{"FOO":"bar"}"#, 0);
-        assert_eq!(payload["env"]["FOO"], "bar");
-    }
-
-    #[test]
-    fn test_build_exec_payload_whitespace_env_ignored() {
-        let payload = build_exec_payload("ls", "", "   ", 0);
-        assert!(payload.get("env").is_none());
-    }
-}
+#[path = "exec_tests.rs"]
+mod tests;
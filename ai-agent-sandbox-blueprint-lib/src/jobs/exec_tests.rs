@@ -0,0 +1,136 @@
+use super::*;
+
+#[test]
+fn test_build_agent_payload_with_system_prompt() {
+    let profile = system_prompt_to_profile("You are a trading expert.");
+    let payload = build_agent_payload(
+        "",
+        "hello",
+        "sess-1",
+        "claude-haiku",
+        "",
+        0,
+        None,
+        Some(&profile),
+    )
+    .unwrap();
+
+    let backend = payload.get("backend").unwrap().as_object().unwrap();
+    assert_eq!(backend["model"], "claude-haiku");
+    let p = backend["profile"].as_object().unwrap();
+    assert_eq!(p["systemPrompt"], "You are a trading expert.");
+}
+
+#[test]
+fn test_build_agent_payload_without_profile() {
+    let payload =
+        build_agent_payload("", "hello", "sess-1", "claude-haiku", "", 0, None, None).unwrap();
+
+    let backend = payload.get("backend").unwrap().as_object().unwrap();
+    assert_eq!(backend["model"], "claude-haiku");
+    assert!(backend.get("profile").is_none());
+}
+
+#[test]
+fn test_build_agent_payload_empty_profile_ignored() {
+    let empty = json!({});
+    let payload = build_agent_payload("", "hello", "", "", "", 0, None, Some(&empty)).unwrap();
+
+    // No backend at all since model is empty and profile is empty
+    assert!(payload.get("backend").is_none());
+}
+
+#[test]
+fn test_build_agent_payload_full_profile() {
+    let profile = json!({
+        "name": "trading-dex",
+        "resources": {
+            "instructions": {
+                "content": "You have a persistent workspace.",
+                "name": "trading-instructions.md"
+            }
+        },
+        "permission": {
+            "bash": "allow",
+            "edit": "allow"
+        },
+        "memory": { "enabled": true }
+    });
+    let payload = build_agent_payload(
+        "",
+        "trade now",
+        "sess-2",
+        "claude-sonnet",
+        "",
+        0,
+        None,
+        Some(&profile),
+    )
+    .unwrap();
+
+    let backend = payload.get("backend").unwrap().as_object().unwrap();
+    let p = backend["profile"].as_object().unwrap();
+    assert!(
+        p.get("systemPrompt").is_none(),
+        "Full profile should not have systemPrompt"
+    );
+    assert!(p.get("resources").is_some());
+    assert_eq!(p["permission"]["bash"], "allow");
+    assert_eq!(p["memory"]["enabled"], true);
+}
+
+#[test]
+fn test_system_prompt_to_profile() {
+    let profile = system_prompt_to_profile("You are helpful.");
+    let obj = profile.as_object().unwrap();
+    assert_eq!(obj["systemPrompt"], "You are helpful.");
+    assert_eq!(obj.len(), 1);
+}
+
+#[test]
+fn test_build_agent_payload_array_context_json_errors() {
+    let result = build_agent_payload("", "hi", "", "", "[1,2]", 0, None, None);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_build_agent_payload_valid_context_merged() {
+    let payload = build_agent_payload("", "hi", "", "", r#"{"k":"v"}"#, 0, None, None).unwrap();
+    let meta = payload.get("metadata").unwrap().as_object().unwrap();
+    assert_eq!(meta["k"], "v");
+}
+
+#[test]
+fn test_build_agent_payload_whitespace_context_ignored() {
+    let payload = build_agent_payload("", "hi", "", "", "   ", 0, None, None).unwrap();
+    assert!(payload.get("metadata").is_none());
+}
+
+#[test]
+fn test_build_exec_payload_invalid_env_silently_dropped() {
+    let payload = build_exec_payload("ls", "", "[1]", 0);
+    assert!(payload.get("env").is_none());
+}
+
+#[test]
+fn test_build_exec_payload_valid_env() {
+    let payload = build_exec_payload("ls", "", r#"{"FOO":"bar"}"#, 0);
+    assert_eq!(payload["env"]["FOO"], "bar");
+}
+
+#[test]
+fn test_build_exec_payload_whitespace_env_ignored() {
+    let payload = build_exec_payload("ls", "", "   ", 0);
+    assert!(payload.get("env").is_none());
+}
+
+#[test]
+fn test_build_exec_payload_with_stdin() {
+    let payload = build_exec_payload_with_stdin("python -", "", "", 0, "print(1)\n", "");
+    assert_eq!(payload["stdin"], "print(1)\n");
+    assert!(payload.get("stdinFile").is_none());
+
+    let payload = build_exec_payload_with_stdin("psql", "", "", 0, "", "seed.sql");
+    assert_eq!(payload["stdinFile"], "seed.sql");
+    assert!(payload.get("stdin").is_none());
+}
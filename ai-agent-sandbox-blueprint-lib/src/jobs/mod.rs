@@ -1,9 +1,66 @@
 pub mod batch;
 pub mod exec;
+pub mod read;
 pub mod sandbox;
 pub mod ssh;
 pub mod workflow;
 
+use blueprint_sdk::alloy::sol_types::{SolType, SolValue};
+
+use crate::tangle::extract::TangleResult;
+use sandbox_runtime::job_replay::JobInput;
+use sandbox_runtime::job_trace::{self, annotate_json};
+pub(crate) use sandbox_runtime::job_trace::{JobOutput, job_input};
+
+impl JobOutput for crate::JsonResponse {
+    fn annotate(&mut self, trace: &job_trace::JobTrace) {
+        self.json = annotate_json(&self.json, trace);
+    }
+}
+
+impl JobOutput for crate::SandboxCreateOutput {
+    fn annotate(&mut self, trace: &job_trace::JobTrace) {
+        self.json = annotate_json(&self.json, trace);
+    }
+}
+
+/// [`job_trace::traced`], keeping [`crate::is_read_only_job`] jobs out of the
+/// replay log.
+pub(crate) async fn traced<T, Fut>(
+    service_id: u64,
+    call_id: u64,
+    job: u8,
+    input: JobInput,
+    run: Fut,
+) -> Result<TangleResult<T>, String>
+where
+    T: JobOutput + SolValue,
+    Fut: std::future::Future<Output = Result<T, String>>,
+{
+    let read_only = crate::is_read_only_job(job);
+    job_trace::traced(service_id, call_id, job, read_only, input, run)
+        .await
+        .map(TangleResult)
+}
+
+/// [`sandbox_runtime::job_dedup::once`] for handlers returning [`TangleResult`].
+pub(crate) async fn once<T, Fut>(
+    service_id: u64,
+    call_id: u64,
+    job: u8,
+    input: JobInput,
+    run: Fut,
+) -> Result<TangleResult<T>, String>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType> + JobOutput,
+    Fut: std::future::Future<Output = Result<TangleResult<T>, String>>,
+{
+    let run = async move { run.await.map(|TangleResult(output)| output) };
+    sandbox_runtime::job_dedup::once(service_id, call_id, job, input, run)
+        .await
+        .map(TangleResult)
+}
+
 /// Convert a raw 20-byte EVM caller address to a lowercase hex string with `0x` prefix.
 pub(crate) fn caller_hex(bytes: &[u8; 20]) -> String {
     let mut s = String::with_capacity(42);
@@ -0,0 +1,199 @@
+//! Read-only job family (IDs 50+).
+//!
+//! Compact JSON views of the caller's sandboxes, workflows and usage for
+//! callers that only talk to the chain. They read the operator's local
+//! store — never a sidecar — and change nothing, so they skip
+//! [`super::once`]'s dedup and the replay log. Each caller is limited by
+//! [`sandbox_runtime::rate_limit::check_read_job`], separately from the
+//! HTTP API tiers. Job outputs are public on-chain, so views leave out
+//! tokens, sidecar URLs, env and workflow results.
+
+use serde_json::{Value, json};
+
+use crate::JsonResponse;
+use crate::ReadPageRequest;
+use crate::SandboxIdRequest;
+use crate::SandboxRecord;
+use crate::SandboxState;
+use crate::WorkflowControlRequest;
+use crate::runtime::{require_sandbox_owner, sandboxes};
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::workflows::workflow_runtime_status_for_owner;
+use sandbox_runtime::job_replay::JobInput;
+use sandbox_runtime::owner_quota;
+use sandbox_runtime::rate_limit::check_read_job;
+
+/// Page size when a listing asks for `limit: 0`.
+pub const DEFAULT_READ_PAGE: u32 = 50;
+/// Largest page a listing returns.
+pub const MAX_READ_PAGE: u32 = 100;
+
+fn check_rate(caller: &str) -> Result<(), String> {
+    check_read_job(caller)
+        .map_err(|retry| format!("Read job rate limit exceeded; retry after {retry}s"))
+}
+
+/// Public view of a sandbox record.
+pub fn sandbox_view(record: &SandboxRecord) -> Value {
+    json!({
+        "sandboxId": record.id,
+        "name": record.name,
+        "state": match record.state {
+            SandboxState::Running => "running",
+            SandboxState::Stopped => "stopped",
+        },
+        "image": record.original_image,
+        "agentIdentifier": record.agent_identifier,
+        "cpuCores": record.cpu_cores,
+        "memoryMb": record.memory_mb,
+        "diskGb": record.disk_gb,
+        "createdAt": record.created_at,
+        "lastActivityAt": record.last_activity_at,
+        "serviceId": record.service_id,
+    })
+}
+
+/// Core list logic — testable without TangleArg extractors. Newest first.
+pub fn run_read_sandbox_list(caller: &str, offset: u32, limit: u32) -> Result<String, String> {
+    let mut owned: Vec<SandboxRecord> = sandboxes()
+        .and_then(|s| s.values())
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|r| !r.owner.is_empty() && r.owner.eq_ignore_ascii_case(caller))
+        .collect();
+    owned.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+
+    let limit = match limit {
+        0 => DEFAULT_READ_PAGE,
+        n => n.min(MAX_READ_PAGE),
+    };
+    let page: Vec<Value> = owned
+        .iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .map(sandbox_view)
+        .collect();
+    Ok(json!({ "total": owned.len(), "offset": offset, "sandboxes": page }).to_string())
+}
+
+pub fn run_read_sandbox_get(caller: &str, sandbox_id: &str) -> Result<String, String> {
+    let record = require_sandbox_owner(sandbox_id, caller).map_err(|e| e.to_string())?;
+    Ok(sandbox_view(&record).to_string())
+}
+
+/// Workflow status without the latest run's result or error text.
+pub fn run_read_workflow_status(caller: &str, workflow_id: u64) -> Result<String, String> {
+    let status = workflow_runtime_status_for_owner(workflow_id, caller)
+        .map_err(|e| e.message().to_string())?;
+    let latest = status.latest_execution.map(|execution| {
+        json!({
+            "executedAt": execution.executed_at,
+            "success": execution.success,
+            "durationMs": execution.duration_ms,
+            "traceId": execution.trace_id,
+        })
+    });
+    Ok(json!({
+        "workflowId": status.workflow_id,
+        "targetStatus": status.target_status,
+        "runnable": status.runnable,
+        "running": status.running,
+        "lastRunAt": status.last_run_at,
+        "nextRunAt": status.next_run_at,
+        "latestExecution": latest,
+    })
+    .to_string())
+}
+
+pub fn run_read_usage(caller: &str) -> Result<String, String> {
+    let (usage, quota) = owner_quota::usage_for(caller).map_err(|e| e.to_string())?;
+    Ok(json!({ "usage": usage, "quota": quota }).to_string())
+}
+
+pub async fn read_sandbox_list(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<ReadPageRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::traced(
+        service_id,
+        call_id,
+        crate::JOB_READ_SANDBOX_LIST,
+        super::job_input(&caller, &request),
+        async move {
+            let caller = super::caller_hex(&caller);
+            check_rate(&caller)?;
+            let json = run_read_sandbox_list(&caller, request.offset, request.limit)?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
+
+pub async fn read_sandbox_get(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<SandboxIdRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::traced(
+        service_id,
+        call_id,
+        crate::JOB_READ_SANDBOX_GET,
+        super::job_input(&caller, &request),
+        async move {
+            let caller = super::caller_hex(&caller);
+            check_rate(&caller)?;
+            let json = run_read_sandbox_get(&caller, &request.sandbox_id)?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
+
+pub async fn read_workflow_status(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<WorkflowControlRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::traced(
+        service_id,
+        call_id,
+        crate::JOB_READ_WORKFLOW_STATUS,
+        super::job_input(&caller, &request),
+        async move {
+            let caller = super::caller_hex(&caller);
+            check_rate(&caller)?;
+            let json = run_read_workflow_status(&caller, request.workflow_id)?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
+
+/// The caller's usage and quota. Takes no arguments.
+pub async fn read_usage(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+) -> Result<TangleResult<JsonResponse>, String> {
+    let caller = super::caller_hex(&caller);
+    let input = JobInput {
+        caller: caller.clone(),
+        ..Default::default()
+    };
+    super::traced(
+        service_id,
+        call_id,
+        crate::JOB_READ_USAGE,
+        input,
+        async move {
+            check_rate(&caller)?;
+            let json = run_read_usage(&caller)?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
@@ -1,161 +1,317 @@
+use bytes::Bytes;
 use serde_json::json;
 
 use crate::CreateSandboxParams;
 use crate::JsonResponse;
+use crate::SandboxCloneRequest;
 use crate::SandboxCreateOutput;
-use crate::SandboxCreateRequest;
+use crate::SandboxEnvUpdateRequest;
 use crate::SandboxIdRequest;
 use crate::SandboxSnapshotRequest;
 use crate::http::sidecar_post_json;
 use crate::runtime::{
-    create_sidecar, delete_sidecar, require_sandbox_owner, require_sandbox_owner_by_url,
-    resume_sidecar, sandboxes, stop_sidecar,
+    CloneSandboxOverrides, acquire_lifecycle_lock, clone_sidecar, create_sidecar, delete_sidecar,
+    require_sandbox_owner, require_sandbox_owner_by_url, restart_sidecar, resume_sidecar,
+    rotate_sidecar_token, sandboxes, stop_sidecar,
 };
 use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
 use crate::util::build_snapshot_command;
+use sandbox_runtime::exec_policy::{CommandInput, CommandKind, enforce_command_policy};
 use sandbox_runtime::provision_progress::{self, ProvisionPhase};
+use sandbox_runtime::templates::apply_template;
 
 pub async fn sandbox_create(
     Caller(caller): Caller,
     ServiceId(service_id): ServiceId,
     CallId(call_id): CallId,
-    TangleArg(request): TangleArg<SandboxCreateRequest>,
+    body: Bytes,
 ) -> Result<TangleResult<SandboxCreateOutput>, String> {
-    // Track provision progress for this call
-    let _ = provision_progress::start_provision(call_id);
-    let _ = provision_progress::update_provision_metadata(
+    // Decoded by hand rather than through `TangleArg` so callers still built
+    // against a pre-`template` layout keep working.
+    let request = crate::decode_sandbox_create_request(&body)?;
+    super::once(
+        service_id,
         call_id,
-        json!({
-            "service_id": service_id,
-        }),
-    );
-
-    let _ = provision_progress::update_provision(
-        call_id,
-        ProvisionPhase::ImagePull,
-        Some("Preparing sandbox image".into()),
-        None,
-        None,
-    );
+        crate::JOB_SANDBOX_CREATE,
+        super::job_input(&caller, &request),
+        async move {
+            // Track provision progress for this call
+            let _ = provision_progress::start_provision(call_id);
+            let _ = provision_progress::update_provision_metadata(
+                call_id,
+                json!({
+                    "service_id": service_id,
+                }),
+            );
 
-    let mut params = CreateSandboxParams::from(&request);
-    params.owner = super::caller_hex(&caller);
-    params.service_id = Some(service_id);
-    if request.tee_required
-        && !request.attestation_nonce.trim().is_empty()
-        && let Some(cfg) = params.tee_config.as_mut()
-    {
-        cfg.attestation_nonce = Some(crate::tee::decode_attestation_nonce_hex(
-            &request.attestation_nonce,
-        )?);
-    }
+            let _ = provision_progress::update_provision(
+                call_id,
+                ProvisionPhase::ImagePull,
+                Some("Preparing sandbox image".into()),
+                None,
+                None,
+            );
 
-    let _ = provision_progress::update_provision(
-        call_id,
-        ProvisionPhase::ContainerCreate,
-        Some("Creating container".into()),
-        None,
-        None,
-    );
+            let mut params = CreateSandboxParams::from(&request);
+            if !request.template.trim().is_empty() {
+                apply_template(&mut params, &request.template).map_err(|e| {
+                    let _ = provision_progress::update_provision(
+                        call_id,
+                        ProvisionPhase::Failed,
+                        Some(format!("Template resolution failed: {e}")),
+                        None,
+                        None,
+                    );
+                    e
+                })?;
+            }
+            params.owner = super::caller_hex(&caller);
+            params.service_id = Some(service_id);
+            if request.tee_required
+                && !request.attestation_nonce.trim().is_empty()
+                && let Some(cfg) = params.tee_config.as_mut()
+            {
+                cfg.attestation_nonce = Some(crate::tee::decode_attestation_nonce_hex(
+                    &request.attestation_nonce,
+                )?);
+            }
 
-    let tee = crate::tee_backend().map(|b| b.as_ref());
-    let (record, attestation) = create_sidecar(&params, tee).await.map_err(|e| {
-        let _ = provision_progress::update_provision(
-            call_id,
-            ProvisionPhase::Failed,
-            Some(format!("Container creation failed: {e}")),
-            None,
-            None,
-        );
-        e
-    })?;
-
-    let _ = provision_progress::update_provision(
-        call_id,
-        ProvisionPhase::ContainerStart,
-        Some("Container started, configuring".into()),
-        Some(record.id.clone()),
-        None,
-    );
+            let _ = provision_progress::update_provision(
+                call_id,
+                ProvisionPhase::ContainerCreate,
+                Some("Creating container".into()),
+                None,
+                None,
+            );
 
-    if request.ssh_enabled && !request.ssh_public_key.trim().is_empty() {
-        sandbox_runtime::runtime::provision_ssh_key(&record, None, &request.ssh_public_key)
-            .await
-            .map(|_| ())
-            .map_err(|e| {
+            let tee = crate::tee_backend().map(|b| b.as_ref());
+            let (record, attestation) = create_sidecar(&params, tee).await.map_err(|e| {
                 let _ = provision_progress::update_provision(
                     call_id,
                     ProvisionPhase::Failed,
-                    Some(format!("SSH key provisioning failed: {e}")),
-                    Some(record.id.clone()),
+                    Some(format!("Container creation failed: {e}")),
+                    None,
                     None,
                 );
                 e
             })?;
-    }
 
-    let _ = provision_progress::update_provision(
-        call_id,
-        ProvisionPhase::Ready,
-        Some("Sandbox ready".into()),
-        Some(record.id.clone()),
-        Some(record.sidecar_url.clone()),
-    );
+            let _ = provision_progress::update_provision(
+                call_id,
+                ProvisionPhase::ContainerStart,
+                Some("Container started, configuring".into()),
+                Some(record.id.clone()),
+                None,
+            );
 
-    // If TEE was used, serialize attestation and derive the public key.
-    let tee_attestation_json = attestation
-        .as_ref()
-        .map(|att| serde_json::to_string(att).unwrap_or_default())
-        .unwrap_or_default();
-
-    let tee_public_key_json =
-        if let (Some(dep_id), Some(backend)) = (&record.tee_deployment_id, crate::tee_backend()) {
-            match backend.derive_public_key(dep_id).await {
-                Ok(pk) => serde_json::to_string(&pk).unwrap_or_default(),
-                Err(_) => String::new(),
+            if request.ssh_enabled && !request.ssh_public_key.trim().is_empty() {
+                sandbox_runtime::runtime::provision_ssh_key(
+                    &record,
+                    None,
+                    &request.ssh_public_key,
+                    None,
+                    &record.owner,
+                )
+                .await
+                .map(|_| ())
+                .map_err(|e| {
+                    let _ = provision_progress::update_provision(
+                        call_id,
+                        ProvisionPhase::Failed,
+                        Some(format!("SSH key provisioning failed: {e}")),
+                        Some(record.id.clone()),
+                        None,
+                    );
+                    e
+                })?;
             }
-        } else {
-            String::new()
-        };
 
-    let response = json!({
-        "sandboxId": record.id,
-        "sidecarUrl": record.sidecar_url,
-        "token": record.token,
-        "sshPort": record.ssh_port,
-        "teeAttestationJson": tee_attestation_json,
-        "teePublicKeyJson": tee_public_key_json,
-    });
+            let _ = provision_progress::update_provision(
+                call_id,
+                ProvisionPhase::Ready,
+                Some("Sandbox ready".into()),
+                Some(record.id.clone()),
+                Some(record.sidecar_url.clone()),
+            );
 
-    Ok(TangleResult(SandboxCreateOutput {
-        sandboxId: record.id.clone(),
-        json: response.to_string(),
-    }))
+            // If TEE was used, serialize attestation and derive the public key.
+            let tee_attestation_json = attestation
+                .as_ref()
+                .map(|att| serde_json::to_string(att).unwrap_or_default())
+                .unwrap_or_default();
+
+            let tee_public_key_json = if let (Some(dep_id), Some(backend)) =
+                (&record.tee_deployment_id, crate::tee_backend())
+            {
+                match backend.derive_public_key(dep_id).await {
+                    Ok(pk) => serde_json::to_string(&pk).unwrap_or_default(),
+                    Err(_) => String::new(),
+                }
+            } else {
+                String::new()
+            };
+
+            let response = json!({
+                "sandboxId": record.id,
+                "sidecarUrl": record.sidecar_url,
+                "token": record.token,
+                "sshPort": record.ssh_port,
+                "teeAttestationJson": tee_attestation_json,
+                "teePublicKeyJson": tee_public_key_json,
+            });
+
+            Ok(TangleResult(SandboxCreateOutput {
+                sandboxId: record.id.clone(),
+                json: response.to_string(),
+            }))
+        },
+    )
+    .await
 }
 
 pub async fn sandbox_delete(
     Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
     TangleArg(request): TangleArg<SandboxIdRequest>,
 ) -> Result<TangleResult<JsonResponse>, String> {
-    let caller_hex = super::caller_hex(&caller);
-    let record = require_sandbox_owner(&request.sandbox_id, &caller_hex)?;
-    let tee = crate::tee_backend().map(|b| b.as_ref());
-    delete_sidecar(&record, tee).await?;
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_SANDBOX_DELETE,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let record = require_sandbox_owner(&request.sandbox_id, &caller_hex)?;
+            let tee = crate::tee_backend().map(|b| b.as_ref());
+            delete_sidecar(&record, tee).await?;
 
-    let sandbox_id = request.sandbox_id.to_string();
-    sandboxes()
-        .map_err(|e| e.to_string())?
-        .remove(&sandbox_id)
-        .map_err(|e| e.to_string())?;
+            let sandbox_id = request.sandbox_id.to_string();
+            sandboxes()
+                .map_err(|e| e.to_string())?
+                .remove(&sandbox_id)
+                .map_err(|e| e.to_string())?;
 
-    let response = json!({
-        "sandboxId": request.sandbox_id,
-        "deleted": true,
-    });
+            let response = json!({
+                "sandboxId": request.sandbox_id,
+                "deleted": true,
+            });
 
-    Ok(TangleResult(JsonResponse {
-        json: response.to_string(),
-    }))
+            Ok(TangleResult(JsonResponse {
+                json: response.to_string(),
+            }))
+        },
+    )
+    .await
+}
+
+pub async fn sandbox_env_update(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<SandboxEnvUpdateRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_ENV_UPDATE,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            require_sandbox_owner(&request.sandbox_id, &caller_hex)?;
+
+            let updates = match crate::util::parse_json_object(&request.env_json, "env_json")? {
+                Some(serde_json::Value::Object(map)) => map,
+                _ => serde_json::Map::new(),
+            };
+            if !(updates.is_empty() && request.replace) {
+                sandbox_runtime::api_types::validate_secrets_map(&updates)?;
+            }
+            // Calldata is public: plaintext values belong on the signed operator API.
+            sandbox_runtime::secret_provisioning::validate_secret_refs_only(&updates)?;
+
+            let _lock = acquire_lifecycle_lock(&request.sandbox_id).await;
+            let tee = crate::tee_backend().map(|b| b.as_ref());
+            let record = sandbox_runtime::secret_provisioning::update_user_env(
+                &request.sandbox_id,
+                updates,
+                request.replace,
+                tee,
+            )
+            .await?;
+
+            // Only key names are returned — values are secrets and results land on-chain.
+            let mut env_keys: Vec<String> =
+                serde_json::from_str::<serde_json::Map<_, _>>(&record.user_env_json)
+                    .map(|env| env.keys().cloned().collect())
+                    .unwrap_or_default();
+            env_keys.sort();
+
+            let response = json!({
+                "sandboxId": record.id,
+                "sidecarUrl": record.sidecar_url,
+                "envKeys": env_keys,
+                "replaced": request.replace,
+            });
+
+            Ok(TangleResult(JsonResponse {
+                json: response.to_string(),
+            }))
+        },
+    )
+    .await
+}
+
+pub async fn sandbox_clone(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<SandboxCloneRequest>,
+) -> Result<TangleResult<SandboxCreateOutput>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_SANDBOX_CLONE,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let source = require_sandbox_owner(&request.sandbox_id, &caller_hex)?;
+            crate::util::parse_json_object(&request.env_json, "env_json")?;
+            crate::util::parse_json_object(&request.metadata_json, "metadata_json")?;
+
+            let overrides = CloneSandboxOverrides {
+                name: request.name.to_string(),
+                env_json: request.env_json.to_string(),
+                metadata_json: request.metadata_json.to_string(),
+                cpu_cores: request.cpu_cores,
+                memory_mb: request.memory_mb,
+                disk_gb: request.disk_gb,
+                idle_timeout_seconds: request.idle_timeout_seconds,
+                max_lifetime_seconds: request.max_lifetime_seconds,
+                inherit_user_env: request.inherit_secrets,
+            };
+
+            // Hold the source's lifecycle lock so it can't be stopped, recreated, or
+            // deleted while its container is being committed.
+            let _lock = acquire_lifecycle_lock(&source.id).await;
+            let tee = crate::tee_backend().map(|b| b.as_ref());
+            let record = clone_sidecar(&source, &overrides, tee).await?;
+
+            let response = json!({
+                "sandboxId": record.id,
+                "sidecarUrl": record.sidecar_url,
+                "token": record.token,
+                "sshPort": record.ssh_port,
+                "clonedFrom": source.id,
+            });
+
+            Ok(TangleResult(SandboxCreateOutput {
+                sandboxId: record.id.clone(),
+                json: response.to_string(),
+            }))
+        },
+    )
+    .await
 }
 
 pub async fn sandbox_stop(
@@ -194,6 +350,76 @@ pub async fn sandbox_resume(
     }))
 }
 
+/// Restart a sandbox (stop + start) and wait for its sidecar to pass `/health`.
+pub async fn sandbox_restart(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<SandboxIdRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_SANDBOX_RESTART,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let record = require_sandbox_owner(&request.sandbox_id, &caller_hex)?;
+            let _lock = acquire_lifecycle_lock(&record.id).await;
+            let restarted = restart_sidecar(&record).await?;
+            crate::runtime::touch_sandbox(&restarted.id);
+
+            let response = json!({
+                "sandboxId": restarted.id,
+                "sidecarUrl": restarted.sidecar_url,
+                "restarted": true,
+            });
+
+            Ok(TangleResult(JsonResponse {
+                json: response.to_string(),
+            }))
+        },
+    )
+    .await
+}
+
+/// Replace a sandbox's sidecar auth token; the old token stops working.
+///
+/// The new token is not part of the on-chain result, so this job revokes a
+/// leaked token. Owners who need the new token rotate through the operator API
+/// (`POST /api/sandboxes/{id}/token/rotate`), which returns it.
+pub async fn sandbox_token_rotate(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<SandboxIdRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    super::once(
+        service_id,
+        call_id,
+        crate::JOB_TOKEN_ROTATE,
+        super::job_input(&caller, &request),
+        async move {
+            let caller_hex = super::caller_hex(&caller);
+            let record = require_sandbox_owner(&request.sandbox_id, &caller_hex)?;
+            let _lock = acquire_lifecycle_lock(&record.id).await;
+            let rotated = rotate_sidecar_token(&record.id, &caller_hex, None).await?;
+            crate::runtime::touch_sandbox(&rotated.id);
+
+            let response = json!({
+                "sandboxId": rotated.id,
+                "sidecarUrl": rotated.sidecar_url,
+                "tokenRotated": true,
+            });
+
+            Ok(TangleResult(JsonResponse {
+                json: response.to_string(),
+            }))
+        },
+    )
+    .await
+}
+
 pub async fn sandbox_snapshot(
     Caller(caller): Caller,
     TangleArg(request): TangleArg<SandboxSnapshotRequest>,
@@ -210,6 +436,12 @@ pub async fn sandbox_snapshot(
         request.include_workspace,
         request.include_state,
     )?;
+    let input = CommandInput {
+        kind: CommandKind::Snapshot,
+        command: &command,
+        ..Default::default()
+    };
+    enforce_command_policy(&record.id, &caller_hex, &input)?;
 
     let payload = json!({
         "command": format!("sh -c {}", crate::util::shell_escape(&command)),
@@ -224,6 +456,12 @@ pub async fn sandbox_snapshot(
     .await?;
 
     crate::runtime::touch_sandbox(&record.id);
+    crate::runtime::record_lifecycle_event(
+        &record.id,
+        crate::runtime::LifecycleEventKind::Snapshotted,
+        &caller_hex,
+        Some(request.destination.clone()),
+    );
 
     Ok(TangleResult(JsonResponse {
         json: response.to_string(),
@@ -13,10 +13,15 @@ pub async fn provision_key(
     _token: &str,
 ) -> Result<Value, String> {
     let record = get_sandbox_by_url(sidecar_url).map_err(|e| e.to_string())?;
-    let (_, result) =
-        sandbox_runtime::runtime::provision_ssh_key(&record, Some(username), public_key)
-            .await
-            .map_err(|e| e.to_string())?;
+    let (_, result) = sandbox_runtime::runtime::provision_ssh_key(
+        &record,
+        Some(username),
+        public_key,
+        None,
+        &record.owner,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
     Ok(result)
 }
 
@@ -27,9 +32,14 @@ pub async fn revoke_key(
     _token: &str,
 ) -> Result<Value, String> {
     let record = get_sandbox_by_url(sidecar_url).map_err(|e| e.to_string())?;
-    let (_, result) = sandbox_runtime::runtime::revoke_ssh_key(&record, Some(username), public_key)
-        .await
-        .map_err(|e| e.to_string())?;
+    let (_, result) = sandbox_runtime::runtime::revoke_ssh_key(
+        &record,
+        Some(username),
+        public_key,
+        &record.owner,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
     Ok(result)
 }
 
@@ -40,10 +50,17 @@ pub async fn ssh_provision(
     let caller_hex = super::caller_hex(&caller);
     let record = require_sandbox_owner_by_url(&request.sidecar_url, &caller_hex)?;
 
+    let expires_at = sandbox_runtime::runtime::ssh_key_expiry(
+        crate::util::now_ts(),
+        Some(request.expires_in_seconds),
+    )
+    .map_err(|e| e.to_string())?;
     let (username, result) = sandbox_runtime::runtime::provision_ssh_key(
         &record,
         Some(request.username.as_str()),
         &request.public_key,
+        expires_at,
+        &caller_hex,
     )
     .await
     .map_err(|e| e.to_string())?;
@@ -55,6 +72,7 @@ pub async fn ssh_provision(
             "success": true,
             "username": username,
             "result": result.get("result").cloned().unwrap_or(result),
+            "expiresAt": expires_at,
         })
         .to_string(),
     }))
@@ -71,6 +89,7 @@ pub async fn ssh_revoke(
         &record,
         Some(request.username.as_str()),
         &request.public_key,
+        &caller_hex,
     )
     .await
     .map_err(|e| e.to_string())?;
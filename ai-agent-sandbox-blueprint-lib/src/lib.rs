@@ -3,7 +3,9 @@
 //! Event-driven multi-sandbox blueprint. For the shared container runtime
 //! used by this and other blueprints, see `sandbox-runtime`.
 
+pub mod abi_compat;
 pub mod jobs;
+pub mod sandbox_bootstrap;
 pub mod workflows;
 
 // Re-export sandbox-runtime modules so existing consumers (job handlers,
@@ -13,7 +15,9 @@ pub use sandbox_runtime::{
     DEFAULT_SIDECAR_SSH_PORT, DEFAULT_TIMEOUT_SECS, SandboxError, SandboxRecord, SandboxState,
     TeeConfig, TeeType,
 };
-pub use sandbox_runtime::{auth, error, http, metrics, reaper, runtime, store, tee, util};
+pub use sandbox_runtime::{
+    auth, error, http, job_error, metrics, reaper, runtime, store, tee, util,
+};
 
 use blueprint_sdk::Job;
 use blueprint_sdk::Router;
@@ -21,16 +25,25 @@ use blueprint_sdk::alloy::sol;
 use blueprint_sdk::tangle::TangleLayer;
 use serde_json::Value;
 
+pub use abi_compat::decode_sandbox_create_request;
 pub use blueprint_sdk::tangle;
 pub use jobs::exec::{
-    build_exec_payload, extract_exec_fields, run_exec_request, run_prompt_request,
-    run_task_request, run_task_request_with_profile, run_task_request_with_system_prompt,
-    system_prompt_to_profile,
+    build_exec_payload, build_exec_payload_with_stdin, extract_exec_fields,
+    extract_exec_fields_for, run_exec_request, run_prompt_request, run_task_request,
+    run_task_request_with_profile, run_task_request_with_system_prompt, system_prompt_to_profile,
+};
+pub use jobs::read::{read_sandbox_get, read_sandbox_list, read_usage, read_workflow_status};
+pub use jobs::sandbox::{
+    sandbox_clone, sandbox_create, sandbox_delete, sandbox_env_update, sandbox_restart,
+    sandbox_token_rotate,
 };
-pub use jobs::sandbox::{sandbox_create, sandbox_delete};
 pub use jobs::ssh::{provision_key, revoke_key};
 pub use jobs::workflow::{workflow_cancel, workflow_create, workflow_tick_job, workflow_trigger};
-pub use workflows::bootstrap_workflows_from_chain;
+pub use sandbox_bootstrap::bootstrap_sandboxes_from_chain;
+pub use workflows::{
+    WorkflowSyncMode, WorkflowSyncReport, bootstrap_workflows_from_chain,
+    sync_workflows_from_chain, workflow_chain_sync_interval, workflow_chain_sync_tick,
+};
 
 /// Job IDs — must match the sequential indices in RegisterBlueprint.s.sol.
 pub const JOB_SANDBOX_CREATE: u8 = 0;
@@ -38,12 +51,32 @@ pub const JOB_SANDBOX_DELETE: u8 = 1;
 pub const JOB_WORKFLOW_CREATE: u8 = 2;
 pub const JOB_WORKFLOW_TRIGGER: u8 = 3;
 pub const JOB_WORKFLOW_CANCEL: u8 = 4;
+pub const JOB_ENV_UPDATE: u8 = 5;
+pub const JOB_SANDBOX_RESTART: u8 = 6;
+pub const JOB_SANDBOX_CLONE: u8 = 7;
+/// Operator lifecycle job (Rust-only): rotate a sandbox's sidecar auth token.
+/// 8–21 are taken by the instance and TEE instance blueprints.
+pub const JOB_TOKEN_ROTATE: u8 = 22;
+/// Read-only job family (see `jobs::read`): compact views that never mutate
+/// state or call a sidecar, rate limited per caller.
+pub const JOB_READ_SANDBOX_LIST: u8 = 50;
+pub const JOB_READ_SANDBOX_GET: u8 = 51;
+pub const JOB_READ_WORKFLOW_STATUS: u8 = 52;
+pub const JOB_READ_USAGE: u8 = 53;
 /// Internal cron job — not registered on-chain, never submitted via submitJob.
 pub const JOB_WORKFLOW_TICK: u8 = 255;
 
+/// Whether `job` belongs to the read-only family.
+pub const fn is_read_only_job(job: u8) -> bool {
+    matches!(job, JOB_READ_SANDBOX_LIST..=JOB_READ_USAGE)
+}
+
 pub const MAX_BATCH_COUNT: u32 = 50;
 
 sol! {
+    // Debug renders requests for the job replay log (`job_replay`).
+    #![sol(all_derives)]
+
     /// Generic JSON response payload.
     struct JsonResponse {
         string json;
@@ -96,6 +129,58 @@ sol! {
         /// to match the existing `_json` convention on this struct
         /// (`env_json`, `metadata_json`) so the ABI stays uniform.
         string capabilities_json;
+        /// Optional operator-registered template name (see `GET /api/templates`).
+        /// The template pre-fills every field left empty or zero above.
+        string template;
+    }
+
+    /// Sandbox create request shape before the `template` field was added.
+    struct SandboxCreateRequestV2 {
+        string name;
+        string image;
+        string stack;
+        string agent_identifier;
+        string env_json;
+        string metadata_json;
+        bool ssh_enabled;
+        string ssh_public_key;
+        bool web_terminal_enabled;
+        uint64 max_lifetime_seconds;
+        uint64 idle_timeout_seconds;
+        uint64 cpu_cores;
+        uint64 memory_mb;
+        uint64 disk_gb;
+        bool tee_required;
+        uint8 tee_type;
+        string attestation_nonce;
+        string capabilities_json;
+    }
+
+    /// Sandbox create request shape before the attestation nonce and
+    /// capabilities were added.
+    struct SandboxCreateRequestV1 {
+        string name;
+        string image;
+        string stack;
+        string agent_identifier;
+        string env_json;
+        string metadata_json;
+        bool ssh_enabled;
+        string ssh_public_key;
+        bool web_terminal_enabled;
+        uint64 max_lifetime_seconds;
+        uint64 idle_timeout_seconds;
+        uint64 cpu_cores;
+        uint64 memory_mb;
+        uint64 disk_gb;
+        bool tee_required;
+        uint8 tee_type;
+    }
+
+    /// Page of a read-only listing (`limit` 0 = default page size).
+    struct ReadPageRequest {
+        uint32 offset;
+        uint32 limit;
     }
 
     /// Sandbox identifier request.
@@ -103,6 +188,40 @@ sol! {
         string sandbox_id;
     }
 
+    /// Sandbox user environment update request.
+    ///
+    /// `env_json` is merged into the persisted user env (keys set to `null`
+    /// are removed). With `replace`, the user env is overwritten instead.
+    /// Calldata is public, so every non-null value must be a
+    /// `{"secretRef": "<uri>"}`; plaintext goes through the operator API.
+    /// The sidecar is recreated to apply it; the workspace, token, and
+    /// sandbox ID are preserved.
+    struct SandboxEnvUpdateRequest {
+        string sandbox_id;
+        string env_json;
+        bool replace;
+    }
+
+    /// Sandbox clone request.
+    ///
+    /// Creates a new sandbox from the current workspace of `sandbox_id`.
+    /// Empty strings and zero values inherit the source's settings;
+    /// `env_json` is merged on top of the source's base env. The clone always
+    /// gets a fresh sidecar token. User secrets are only copied when
+    /// `inherit_secrets` is set.
+    struct SandboxCloneRequest {
+        string sandbox_id;
+        string name;
+        string env_json;
+        string metadata_json;
+        uint64 cpu_cores;
+        uint64 memory_mb;
+        uint64 disk_gb;
+        uint64 idle_timeout_seconds;
+        uint64 max_lifetime_seconds;
+        bool inherit_secrets;
+    }
+
     /// Sandbox snapshot request.
     ///
     /// Auth: the on-chain `Caller` must own the sandbox at `sidecar_url`.
@@ -124,13 +243,22 @@ sol! {
         string cwd;
         string env_json;
         uint64 timeout_ms;
+        /// Text piped to the command's stdin (empty = stdin closed).
+        string stdin;
+        /// Sandbox file piped to stdin instead; exclusive with `stdin`.
+        string stdin_file;
     }
 
     /// Exec response from sandbox sidecar.
+    ///
+    /// `stdout`/`stderr` are capped at `EXEC_OUTPUT_MAX_BYTES` each. When
+    /// either was cut, `overflow_ref` names the stored full output, fetchable
+    /// via `GET /api/sandboxes/{id}/exec/overflow/{overflow_ref}`.
     struct SandboxExecResponse {
         uint32 exit_code;
         string stdout;
         string stderr;
+        string overflow_ref;
     }
 
     /// Prompt request for a sandbox sidecar.
@@ -144,6 +272,8 @@ sol! {
         string model;
         string context_json;
         uint64 timeout_ms;
+        /// Sidecar agent to run (empty = the sandbox's `agent_identifier`).
+        string agent_identifier;
     }
 
     /// Prompt response from sandbox sidecar.
@@ -169,6 +299,14 @@ sol! {
         string model;
         string context_json;
         uint64 timeout_ms;
+        /// JSON Schema the result must satisfy (empty = free text). A
+        /// mismatch gets one repair round, then `success = false`.
+        string output_schema_json;
+        /// Tool allowlist for the agent (empty = unrestricted); see
+        /// `sandbox_runtime::tool_policy`.
+        string tools_json;
+        /// Sidecar agent to run (empty = the sandbox's `agent_identifier`).
+        string agent_identifier;
     }
 
     /// Task response from sandbox sidecar.
@@ -181,6 +319,8 @@ sol! {
         uint32 input_tokens;
         uint32 output_tokens;
         string session_id;
+        /// Tool policy the sidecar confirmed applying (empty = none).
+        string tools_json;
     }
 
     /// Batch sandbox create request.
@@ -250,6 +390,8 @@ sol! {
         string sidecar_url;
         string username;
         string public_key;
+        /// Revoke the key automatically after this many seconds (0 = never).
+        uint64 expires_in_seconds;
     }
 
     /// SSH revoke request.
@@ -263,6 +405,28 @@ sol! {
     }
 }
 
+impl sandbox_runtime::output_schema::TaskOutput for SandboxTaskResponse {
+    fn succeeded(&self) -> bool {
+        self.success
+    }
+    fn result_text(&self) -> &str {
+        &self.result
+    }
+    fn session(&self) -> &str {
+        &self.session_id
+    }
+    fn tools_echo(&self) -> &str {
+        &self.tools_json
+    }
+    fn set_result(&mut self, result: String) {
+        self.result = result;
+    }
+    fn fail(&mut self, error: String) {
+        self.success = false;
+        self.error = error;
+    }
+}
+
 /// Convert an ABI `SandboxCreateRequest` into runtime-level `CreateSandboxParams`.
 impl From<&SandboxCreateRequest> for CreateSandboxParams {
     fn from(r: &SandboxCreateRequest) -> Self {
@@ -310,6 +474,10 @@ impl From<&SandboxCreateRequest> for CreateSandboxParams {
 pub struct BatchRecord {
     pub id: String,
     pub kind: String,
+    /// Caller that ran the batch; only they can collect it. Empty on records
+    /// written before owners were tracked, which nobody can collect.
+    #[serde(default)]
+    pub owner: String,
     pub results: Value,
     pub created_at: u64,
 }
@@ -389,9 +557,9 @@ pub fn extract_agent_fields(parsed: &Value) -> (bool, String, String, String) {
 
 /// Router that maps job IDs to handlers.
 ///
-/// Only state-changing operations remain on-chain (5 jobs).
-/// Read-only ops (exec, prompt, task, stop, resume, snapshot, SSH)
-/// are served via the operator HTTP API.
+/// State-changing operations, plus the compact read-only family (50+) for
+/// callers that only talk to the chain. Everything else (exec, prompt, task,
+/// stop, resume, snapshot, SSH) is served via the operator HTTP API.
 pub fn router() -> Router {
     Router::new()
         .route(JOB_SANDBOX_CREATE, sandbox_create.layer(TangleLayer))
@@ -399,6 +567,17 @@ pub fn router() -> Router {
         .route(JOB_WORKFLOW_CREATE, workflow_create.layer(TangleLayer))
         .route(JOB_WORKFLOW_TRIGGER, workflow_trigger.layer(TangleLayer))
         .route(JOB_WORKFLOW_CANCEL, workflow_cancel.layer(TangleLayer))
+        .route(JOB_ENV_UPDATE, sandbox_env_update.layer(TangleLayer))
+        .route(JOB_SANDBOX_RESTART, sandbox_restart.layer(TangleLayer))
+        .route(JOB_SANDBOX_CLONE, sandbox_clone.layer(TangleLayer))
+        .route(JOB_TOKEN_ROTATE, sandbox_token_rotate.layer(TangleLayer))
+        .route(JOB_READ_SANDBOX_LIST, read_sandbox_list.layer(TangleLayer))
+        .route(JOB_READ_SANDBOX_GET, read_sandbox_get.layer(TangleLayer))
+        .route(
+            JOB_READ_WORKFLOW_STATUS,
+            read_workflow_status.layer(TangleLayer),
+        )
+        .route(JOB_READ_USAGE, read_usage.layer(TangleLayer))
         .route(JOB_WORKFLOW_TICK, workflow_tick_job)
 }
 
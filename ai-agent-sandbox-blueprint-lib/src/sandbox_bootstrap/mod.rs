@@ -0,0 +1,370 @@
+//! Sandbox bootstrap from chain.
+//!
+//! If the operator's state dir is lost, every customer sandbox becomes
+//! unmanageable even though the chain still records it. Like
+//! [`crate::workflows::bootstrap_workflows_from_chain`], this rebuilds local
+//! state from the chain: it replays this operator's `JobResultSubmitted`
+//! logs for the service's create and clone jobs, pairs them with the
+//! `JobSubmitted` requests, keeps the sandboxes the manager still routes to
+//! this operator (`getSandboxOperator` / `isSandboxActive`), and hands each to
+//! [`crate::runtime::adopt_sandbox`], which re-attaches surviving containers.
+//!
+//! Env:
+//! - `SANDBOX_CHAIN_BOOTSTRAP`: `auto` (default; only when the sandbox store
+//!   is empty), `always`, or `off`.
+//! - `TANGLE_CONTRACT_ADDRESS`: Tangle contract emitting the job logs;
+//!   the bootstrap is skipped without it.
+//! - `SANDBOX_CHAIN_BOOTSTRAP_FROM_BLOCK` (default `0`) and
+//!   `SANDBOX_CHAIN_BOOTSTRAP_BLOCK_RANGE` (default `10000`, per `eth_getLogs`).
+
+use blueprint_sdk::alloy::primitives::{Address, B256, Bytes, U256};
+use blueprint_sdk::alloy::providers::Provider;
+use blueprint_sdk::alloy::rpc::types::{Filter, Log};
+use blueprint_sdk::alloy::sol;
+use blueprint_sdk::alloy::sol_types::{SolEvent, SolValue};
+use blueprint_sdk::contexts::tangle::TangleClient;
+use blueprint_sdk::{info, warn};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::runtime::{AdoptOutcome, RecoveredSandbox, adopt_sandbox, merge_env_json, sandboxes};
+use crate::{
+    CreateSandboxParams, JOB_SANDBOX_CLONE, JOB_SANDBOX_CREATE, SandboxCloneRequest,
+    SandboxCreateOutput,
+};
+
+#[cfg(test)]
+mod tests;
+
+sol! {
+    interface ITangleJobEvents {
+        event JobSubmitted(uint64 indexed serviceId, uint64 indexed callId, uint8 jobIndex, address caller, bytes inputs);
+        event JobResultSubmitted(uint64 indexed serviceId, uint64 indexed callId, address indexed operator, bytes result);
+    }
+
+    #[sol(rpc)]
+    interface ISandboxRegistryReader {
+        function getSandboxOperator(string sandboxId) external view returns (address);
+        function isSandboxActive(string sandboxId) external view returns (bool);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SandboxBootstrapMode {
+    Auto,
+    Always,
+    Off,
+}
+
+#[derive(Clone, Debug)]
+pub struct SandboxBootstrapConfig {
+    pub mode: SandboxBootstrapMode,
+    pub tangle_contract: Option<Address>,
+    pub from_block: u64,
+    pub block_range: u64,
+}
+
+impl SandboxBootstrapConfig {
+    pub fn from_env() -> Self {
+        let mode = match std::env::var("SANDBOX_CHAIN_BOOTSTRAP")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "always" => SandboxBootstrapMode::Always,
+            "off" | "false" | "0" => SandboxBootstrapMode::Off,
+            _ => SandboxBootstrapMode::Auto,
+        };
+        let tangle_contract = std::env::var("TANGLE_CONTRACT_ADDRESS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok());
+        let from_block = std::env::var("SANDBOX_CHAIN_BOOTSTRAP_FROM_BLOCK")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let block_range = std::env::var("SANDBOX_CHAIN_BOOTSTRAP_BLOCK_RANGE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(10_000);
+        Self {
+            mode,
+            tangle_contract,
+            from_block,
+            block_range,
+        }
+    }
+}
+
+/// What a bootstrap pass found and did.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct SandboxBootstrapReport {
+    /// Create/clone results of this operator found on chain.
+    pub results: usize,
+    /// Sandboxes no longer active or routed to this operator.
+    pub inactive: usize,
+    pub already_known: usize,
+    pub running: usize,
+    pub stopped: usize,
+    /// Active on chain but without a container to re-attach.
+    pub missing: usize,
+    pub failed: usize,
+}
+
+/// A successful create or clone job, as replayed from the chain.
+#[derive(Clone, Debug)]
+pub(crate) struct ChainCreate {
+    pub job: u8,
+    pub caller: Address,
+    pub inputs: Vec<u8>,
+    pub output: Vec<u8>,
+}
+
+pub(crate) fn owner_hex(caller: &Address) -> String {
+    crate::jobs::caller_hex(&caller.0.0)
+}
+
+/// Endpoint, token and SSH port from a create result's JSON.
+fn output_endpoint(json: &str) -> (String, String, Option<u16>) {
+    let value: Value = serde_json::from_str(json).unwrap_or(Value::Null);
+    let text = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let ssh_port = value
+        .get("sshPort")
+        .and_then(Value::as_u64)
+        .and_then(|p| u16::try_from(p).ok());
+    (text("sidecarUrl"), text("token"), ssh_port)
+}
+
+/// Rebuild the sandboxes created by `creates` (in chain order). Clones take
+/// their settings from a source rebuilt earlier in the same replay.
+pub(crate) fn recover_sandboxes(service_id: u64, creates: &[ChainCreate]) -> Vec<RecoveredSandbox> {
+    let mut recovered: Vec<RecoveredSandbox> = Vec::new();
+    for create in creates {
+        let output = match SandboxCreateOutput::abi_decode(&create.output) {
+            Ok(output) if !output.sandboxId.is_empty() => output,
+            _ => continue,
+        };
+        let mut params = match create.job {
+            JOB_SANDBOX_CREATE => {
+                let Ok(request) = crate::decode_sandbox_create_request(&create.inputs) else {
+                    continue;
+                };
+                let mut params = CreateSandboxParams::from(&request);
+                if !request.template.trim().is_empty()
+                    && let Err(err) =
+                        sandbox_runtime::templates::apply_template(&mut params, &request.template)
+                {
+                    warn!(
+                        sandbox_id = %output.sandboxId,
+                        "sandbox bootstrap: template '{}' not applied: {err}", request.template
+                    );
+                }
+                params
+            }
+            JOB_SANDBOX_CLONE => {
+                let Ok(request) = SandboxCloneRequest::abi_decode(&create.inputs) else {
+                    continue;
+                };
+                let Some(source) = recovered
+                    .iter()
+                    .find(|s| s.sandbox_id == request.sandbox_id)
+                else {
+                    continue;
+                };
+                let mut params = source.params.clone();
+                if !request.name.is_empty() {
+                    params.name = request.name.clone();
+                }
+                if !request.metadata_json.is_empty() {
+                    params.metadata_json = request.metadata_json.clone();
+                }
+                params.env_json = merge_env_json(&params.env_json, &request.env_json);
+                for (value, target) in [
+                    (request.cpu_cores, &mut params.cpu_cores),
+                    (request.memory_mb, &mut params.memory_mb),
+                    (request.disk_gb, &mut params.disk_gb),
+                    (
+                        request.idle_timeout_seconds,
+                        &mut params.idle_timeout_seconds,
+                    ),
+                    (
+                        request.max_lifetime_seconds,
+                        &mut params.max_lifetime_seconds,
+                    ),
+                ] {
+                    if value > 0 {
+                        *target = value;
+                    }
+                }
+                params.user_env_json = String::new();
+                params
+            }
+            _ => continue,
+        };
+        params.owner = owner_hex(&create.caller);
+        params.service_id = Some(service_id);
+        let (sidecar_url, token, ssh_port) = output_endpoint(&output.json);
+        recovered.retain(|s| s.sandbox_id != output.sandboxId);
+        recovered.push(RecoveredSandbox {
+            sandbox_id: output.sandboxId.clone(),
+            params,
+            sidecar_url,
+            token,
+            ssh_port,
+        });
+    }
+    recovered
+}
+
+async fn fetch_logs(
+    client: &TangleClient,
+    filter: Filter,
+    config: &SandboxBootstrapConfig,
+    latest: u64,
+) -> Result<Vec<Log>, String> {
+    let mut logs = Vec::new();
+    let mut from = config.from_block;
+    while from <= latest {
+        let to = from.saturating_add(config.block_range - 1).min(latest);
+        let chunk = client
+            .provider()
+            .get_logs(&filter.clone().from_block(from).to_block(to))
+            .await
+            .map_err(|err| format!("eth_getLogs {from}..={to} failed: {err}"))?;
+        logs.extend(chunk);
+        from = to + 1;
+    }
+    Ok(logs)
+}
+
+/// Rebuild sandbox records for `service_id` from the chain (see module docs).
+pub async fn bootstrap_sandboxes_from_chain(
+    client: &TangleClient,
+    service_id: u64,
+) -> Result<SandboxBootstrapReport, String> {
+    let config = SandboxBootstrapConfig::from_env();
+    let mut report = SandboxBootstrapReport::default();
+    match config.mode {
+        SandboxBootstrapMode::Off => return Ok(report),
+        SandboxBootstrapMode::Auto => {
+            let existing = sandboxes()
+                .and_then(|s| s.values())
+                .map_err(|e| e.to_string())?;
+            if !existing.is_empty() {
+                return Ok(report);
+            }
+        }
+        SandboxBootstrapMode::Always => {}
+    }
+    let Some(tangle) = config.tangle_contract else {
+        warn!("sandbox bootstrap: TANGLE_CONTRACT_ADDRESS not set, skipping");
+        return Ok(report);
+    };
+    let Some(manager) = client
+        .get_blueprint_manager(service_id)
+        .await
+        .map_err(|err| format!("Failed to get blueprint manager: {err}"))?
+    else {
+        return Ok(report);
+    };
+
+    let operator = client.account();
+    let latest = client
+        .provider()
+        .get_block_number()
+        .await
+        .map_err(|err| format!("Failed to read block number: {err}"))?;
+    let service_topic = B256::from(U256::from(service_id));
+
+    let result_filter = Filter::new()
+        .address(tangle)
+        .event_signature(ITangleJobEvents::JobResultSubmitted::SIGNATURE_HASH)
+        .topic1(service_topic)
+        .topic3(operator.into_word());
+    let mut results: Vec<(u64, Bytes)> = Vec::new();
+    for log in fetch_logs(client, result_filter, &config, latest).await? {
+        if let Ok(decoded) = log.log_decode::<ITangleJobEvents::JobResultSubmitted>() {
+            let event = decoded.inner.data;
+            results.push((event.callId, event.result));
+        }
+    }
+    if results.is_empty() {
+        return Ok(report);
+    }
+
+    let call_filter = Filter::new()
+        .address(tangle)
+        .event_signature(ITangleJobEvents::JobSubmitted::SIGNATURE_HASH)
+        .topic1(service_topic);
+    let mut calls: HashMap<u64, ITangleJobEvents::JobSubmitted> = HashMap::new();
+    for log in fetch_logs(client, call_filter, &config, latest).await? {
+        if let Ok(decoded) = log.log_decode::<ITangleJobEvents::JobSubmitted>() {
+            let event = decoded.inner.data;
+            if matches!(event.jobIndex, JOB_SANDBOX_CREATE | JOB_SANDBOX_CLONE) {
+                calls.insert(event.callId, event);
+            }
+        }
+    }
+
+    let creates: Vec<ChainCreate> = results
+        .into_iter()
+        .filter_map(|(call_id, output)| {
+            let call = calls.get(&call_id)?;
+            Some(ChainCreate {
+                job: call.jobIndex,
+                caller: call.caller,
+                inputs: call.inputs.to_vec(),
+                output: output.to_vec(),
+            })
+        })
+        .collect();
+    report.results = creates.len();
+
+    let registry = ISandboxRegistryReader::new(manager, client.provider().clone());
+    for recovered in recover_sandboxes(service_id, &creates) {
+        let sandbox_id = recovered.sandbox_id.clone();
+        let routed = registry
+            .getSandboxOperator(sandbox_id.clone())
+            .call()
+            .await
+            .map_err(|err| format!("getSandboxOperator({sandbox_id}) failed: {err}"))?;
+        let active = registry
+            .isSandboxActive(sandbox_id.clone())
+            .call()
+            .await
+            .map_err(|err| format!("isSandboxActive({sandbox_id}) failed: {err}"))?;
+        if routed != operator || !active {
+            report.inactive += 1;
+            continue;
+        }
+        match adopt_sandbox(recovered).await {
+            Ok(AdoptOutcome::AlreadyKnown) => report.already_known += 1,
+            Ok(AdoptOutcome::Running) => report.running += 1,
+            Ok(AdoptOutcome::Stopped) => report.stopped += 1,
+            Ok(AdoptOutcome::Missing) => report.missing += 1,
+            Err(err) => {
+                warn!("sandbox bootstrap: failed to adopt {sandbox_id}: {err}");
+                report.failed += 1;
+            }
+        }
+    }
+
+    info!(
+        service_id,
+        results = report.results,
+        running = report.running,
+        stopped = report.stopped,
+        missing = report.missing,
+        inactive = report.inactive,
+        failed = report.failed,
+        "sandbox bootstrap from chain complete"
+    );
+    Ok(report)
+}
@@ -0,0 +1,158 @@
+use super::*;
+use crate::SandboxCreateRequest;
+
+fn create_request(name: &str) -> SandboxCreateRequest {
+    SandboxCreateRequest {
+        name: name.to_string(),
+        image: "ghcr.io/tangle-network/sidecar:latest".to_string(),
+        stack: String::new(),
+        agent_identifier: "default".to_string(),
+        env_json: r#"{"A":"1"}"#.to_string(),
+        metadata_json: "{}".to_string(),
+        ssh_enabled: false,
+        ssh_public_key: String::new(),
+        web_terminal_enabled: false,
+        max_lifetime_seconds: 3600,
+        idle_timeout_seconds: 600,
+        cpu_cores: 2,
+        memory_mb: 2048,
+        disk_gb: 10,
+        tee_required: false,
+        tee_type: 0,
+        attestation_nonce: String::new(),
+        capabilities_json: String::new(),
+        template: String::new(),
+    }
+}
+
+fn create_output(sandbox_id: &str, port: u16) -> Vec<u8> {
+    SandboxCreateOutput {
+        sandboxId: sandbox_id.to_string(),
+        json: serde_json::json!({
+            "sandboxId": sandbox_id,
+            "sidecarUrl": format!("http://127.0.0.1:{port}"),
+            "token": format!("token-{sandbox_id}"),
+            "sshPort": 2222,
+        })
+        .to_string(),
+    }
+    .abi_encode()
+}
+
+#[test]
+fn recover_sandboxes_rebuilds_creates_and_clones() {
+    let caller = Address::repeat_byte(0xab);
+    let clone = SandboxCloneRequest {
+        sandbox_id: "sandbox-a".to_string(),
+        name: "copy".to_string(),
+        env_json: r#"{"B":"2"}"#.to_string(),
+        metadata_json: String::new(),
+        cpu_cores: 4,
+        memory_mb: 0,
+        disk_gb: 0,
+        idle_timeout_seconds: 0,
+        max_lifetime_seconds: 0,
+        inherit_secrets: false,
+    };
+    let creates = vec![
+        ChainCreate {
+            job: JOB_SANDBOX_CREATE,
+            caller,
+            inputs: create_request("original").abi_encode(),
+            output: create_output("sandbox-a", 9100),
+        },
+        ChainCreate {
+            job: JOB_SANDBOX_CLONE,
+            caller,
+            inputs: clone.abi_encode(),
+            output: create_output("sandbox-b", 9101),
+        },
+        // Garbage inputs are skipped, not fatal.
+        ChainCreate {
+            job: JOB_SANDBOX_CREATE,
+            caller,
+            inputs: vec![1, 2, 3],
+            output: create_output("sandbox-c", 9102),
+        },
+    ];
+
+    let recovered = recover_sandboxes(7, &creates);
+    assert_eq!(recovered.len(), 2);
+
+    let original = &recovered[0];
+    assert_eq!(original.sandbox_id, "sandbox-a");
+    assert_eq!(original.params.owner, owner_hex(&caller));
+    assert_eq!(original.params.service_id, Some(7));
+    assert_eq!(original.params.cpu_cores, 2);
+    assert_eq!(original.sidecar_url, "http://127.0.0.1:9100");
+    assert_eq!(original.token, "token-sandbox-a");
+    assert_eq!(original.ssh_port, Some(2222));
+
+    let copy = &recovered[1];
+    assert_eq!(copy.sandbox_id, "sandbox-b");
+    assert_eq!(copy.params.name, "copy");
+    assert_eq!(copy.params.cpu_cores, 4);
+    assert_eq!(copy.params.memory_mb, 2048);
+    let env: Value = serde_json::from_str(&copy.params.env_json).unwrap();
+    assert_eq!(env["A"], "1");
+    assert_eq!(env["B"], "2");
+}
+
+#[test]
+fn clone_of_unknown_source_is_skipped() {
+    let clone = SandboxCloneRequest {
+        sandbox_id: "sandbox-elsewhere".to_string(),
+        name: String::new(),
+        env_json: String::new(),
+        metadata_json: String::new(),
+        cpu_cores: 0,
+        memory_mb: 0,
+        disk_gb: 0,
+        idle_timeout_seconds: 0,
+        max_lifetime_seconds: 0,
+        inherit_secrets: false,
+    };
+    let creates = vec![ChainCreate {
+        job: JOB_SANDBOX_CLONE,
+        caller: Address::ZERO,
+        inputs: clone.abi_encode(),
+        output: create_output("sandbox-z", 9103),
+    }];
+    assert!(recover_sandboxes(1, &creates).is_empty());
+}
+
+#[test]
+fn recover_sandboxes_accepts_pre_template_create_calldata() {
+    let current = create_request("legacy");
+    let legacy = crate::SandboxCreateRequestV2 {
+        name: current.name,
+        image: current.image,
+        stack: current.stack,
+        agent_identifier: current.agent_identifier,
+        env_json: current.env_json,
+        metadata_json: current.metadata_json,
+        ssh_enabled: current.ssh_enabled,
+        ssh_public_key: current.ssh_public_key,
+        web_terminal_enabled: current.web_terminal_enabled,
+        max_lifetime_seconds: current.max_lifetime_seconds,
+        idle_timeout_seconds: current.idle_timeout_seconds,
+        cpu_cores: current.cpu_cores,
+        memory_mb: current.memory_mb,
+        disk_gb: current.disk_gb,
+        tee_required: current.tee_required,
+        tee_type: current.tee_type,
+        attestation_nonce: String::new(),
+        capabilities_json: r#"["computer_use"]"#.to_string(),
+    };
+    let creates = vec![ChainCreate {
+        job: JOB_SANDBOX_CREATE,
+        caller: Address::repeat_byte(0xcd),
+        inputs: legacy.abi_encode(),
+        output: create_output("sandbox-legacy", 9103),
+    }];
+
+    let recovered = recover_sandboxes(7, &creates);
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].params.name, "legacy");
+    assert_eq!(recovered[0].params.capabilities_json, r#"["computer_use"]"#);
+}
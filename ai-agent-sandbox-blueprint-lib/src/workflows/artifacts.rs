@@ -0,0 +1,170 @@
+//! Workflow run artifacts.
+//!
+//! A task spec may list `artifacts`: paths in the sandbox (relative ones
+//! resolve under `/home/agent`) that the run produces. After every run each
+//! path is archived by the sidecar and PUT to
+//! `{destination}workflow-{id}/{executed_at}/{n}-{name}.tar.gz`, where the
+//! destination is the spec's `artifact_destination` or the operator's
+//! `WORKFLOW_ARTIFACT_DESTINATION` (`https://` or `s3://`, validated like
+//! snapshot destinations). The URLs, or the per-path error, are recorded on
+//! the execution. Upload failures never fail the run itself.
+
+use super::*;
+
+use sandbox_runtime::sidecar_compat::SidecarApi;
+use sandbox_runtime::util::build_artifact_command;
+
+/// Most artifact paths a workflow may declare.
+pub const MAX_WORKFLOW_ARTIFACTS: usize = 16;
+const WORKSPACE_DIR: &str = "/home/agent";
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowArtifact {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn default_artifact_destination() -> Option<String> {
+    std::env::var("WORKFLOW_ARTIFACT_DESTINATION")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Absolute sandbox path for a declared artifact.
+pub fn resolve_artifact_path(path: &str) -> String {
+    let path = path.trim();
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{WORKSPACE_DIR}/{path}")
+    }
+}
+
+/// Upload URL of the `index`-th artifact of a run.
+pub fn artifact_url(
+    destination: &str,
+    workflow_id: u64,
+    executed_at: u64,
+    index: usize,
+    path: &str,
+) -> String {
+    let name: String = path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let separator = if destination.ends_with('/') { "" } else { "/" };
+    format!("{destination}{separator}workflow-{workflow_id}/{executed_at}/{index}-{name}.tar.gz")
+}
+
+/// Reject artifact declarations that can never upload.
+pub fn validate_workflow_artifacts(spec: &WorkflowTaskSpec) -> Result<(), String> {
+    if spec.artifacts.is_empty() {
+        return Ok(());
+    }
+    if spec.artifacts.len() > MAX_WORKFLOW_ARTIFACTS {
+        return Err(format!(
+            "workflow_json declares {} artifacts (max {MAX_WORKFLOW_ARTIFACTS})",
+            spec.artifacts.len()
+        ));
+    }
+    let destination = spec
+        .artifact_destination
+        .clone()
+        .or_else(default_artifact_destination)
+        .ok_or_else(|| {
+            "workflow_json declares artifacts but neither artifact_destination nor \
+             WORKFLOW_ARTIFACT_DESTINATION is set"
+                .to_string()
+        })?;
+    for (index, path) in spec.artifacts.iter().enumerate() {
+        let url = artifact_url(&destination, 0, 0, index, path);
+        build_artifact_command(&url, &resolve_artifact_path(path)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Upload the `declared` artifact paths from `record` to `destination`
+/// (the spec's `artifact_destination`) or the operator default.
+pub async fn upload_workflow_artifacts(
+    record: &crate::SandboxRecord,
+    token: &str,
+    workflow_id: u64,
+    executed_at: u64,
+    declared: &[String],
+    destination: Option<&str>,
+) -> Vec<WorkflowArtifact> {
+    if declared.is_empty() {
+        return Vec::new();
+    }
+    let destination = destination
+        .map(str::to_string)
+        .or_else(default_artifact_destination);
+
+    let mut artifacts = Vec::with_capacity(declared.len());
+    for (index, declared) in declared.iter().take(MAX_WORKFLOW_ARTIFACTS).enumerate() {
+        let path = resolve_artifact_path(declared);
+        let outcome = match destination.as_deref() {
+            Some(destination) => {
+                let url = artifact_url(destination, workflow_id, executed_at, index, &path);
+                upload_artifact(record, token, &url, &path)
+                    .await
+                    .map(|()| url)
+            }
+            None => Err("no artifact destination configured".to_string()),
+        };
+        if let Err(err) = &outcome {
+            tracing::warn!(workflow_id, path = %path, "Workflow artifact upload failed: {err}");
+        }
+        artifacts.push(WorkflowArtifact {
+            path,
+            url: outcome.as_ref().ok().cloned(),
+            error: outcome.err(),
+        });
+    }
+    artifacts
+}
+
+async fn upload_artifact(
+    record: &crate::SandboxRecord,
+    token: &str,
+    url: &str,
+    path: &str,
+) -> Result<(), String> {
+    let command = build_artifact_command(url, path).map_err(|e| e.to_string())?;
+    let payload = json!({
+        "command": format!("sh -c {}", crate::util::shell_escape(&command)),
+    });
+    let response =
+        crate::http::sidecar_post_json(&record.sidecar_url, "/terminals/commands", token, payload)
+            .await
+            .map_err(|e| e.to_string())?;
+    let result = SidecarApi::for_record(record).command_result(&response);
+    let exit_code = result
+        .and_then(|r| r.get("exitCode"))
+        .and_then(Value::as_i64)
+        .unwrap_or(0);
+    if exit_code != 0 {
+        let stderr = result
+            .and_then(|r| r.get("stderr"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim();
+        return Err(format!("artifact upload exited with {exit_code}: {stderr}"));
+    }
+    Ok(())
+}
@@ -1,25 +1,140 @@
 use super::*;
 
-pub async fn bootstrap_workflows_from_chain(
+use once_cell::sync::Lazy;
+
+/// How much of the on-chain workflow registry a sync re-reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WorkflowSyncMode {
+    /// Rebuild the local store from every on-chain workflow.
+    #[default]
+    Full,
+    /// Only re-apply workflows whose `updatedAt` moved past the previous
+    /// sync for this service, and deactivate (rather than drop) local ones
+    /// the chain no longer has; falls back to `Full` on the first sync.
+    Incremental,
+}
+
+/// What a workflow sync found and did.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct WorkflowSyncReport {
+    /// Workflow IDs registered on chain.
+    pub on_chain: usize,
+    pub created: usize,
+    pub updated: usize,
+    /// Local workflows no longer registered on chain.
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+impl WorkflowSyncReport {
+    /// Local workflows the sync had to correct.
+    pub fn drift(&self) -> usize {
+        self.created + self.updated + self.removed
+    }
+}
+
+/// Workflow registry of one service: the manager contract, resolved once,
+/// and the newest `updatedAt` applied by the last sync.
+#[derive(Clone)]
+struct WorkflowRegistry {
+    contract: blueprint_sdk::alloy::contract::ContractInstance<
+        blueprint_sdk::alloy::providers::DynProvider,
+    >,
+    synced_through: Option<u64>,
+}
+
+static WORKFLOW_REGISTRY_INTERFACE: Lazy<blueprint_sdk::alloy::contract::Interface> =
+    Lazy::new(|| {
+        let abi: blueprint_sdk::alloy::json_abi::JsonAbi =
+            serde_json::from_str(WORKFLOW_REGISTRY_ABI).expect("workflow registry ABI is valid");
+        blueprint_sdk::alloy::contract::Interface::new(abi)
+    });
+static WORKFLOW_REGISTRIES: Lazy<Mutex<HashMap<u64, WorkflowRegistry>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+async fn workflow_registry(
     client: &blueprint_sdk::contexts::tangle::TangleClient,
     service_id: u64,
-) -> Result<(), String> {
+) -> Result<Option<WorkflowRegistry>, String> {
+    if let Some(registry) = WORKFLOW_REGISTRIES
+        .lock()
+        .map_err(|_| "Workflow registry cache poisoned".to_string())?
+        .get(&service_id)
+    {
+        return Ok(Some(registry.clone()));
+    }
+
     let manager = client
         .get_blueprint_manager(service_id)
         .await
         .map_err(|err| format!("Failed to get blueprint manager: {err}"))?;
+    // Not cached: a manager may still be attached to the service later.
     let Some(manager) = manager else {
-        return Ok(());
+        return Ok(None);
     };
 
-    let abi: blueprint_sdk::alloy::json_abi::JsonAbi = serde_json::from_str(WORKFLOW_REGISTRY_ABI)
-        .map_err(|err| format!("Invalid workflow ABI: {err}"))?;
-    let interface = blueprint_sdk::alloy::contract::Interface::new(abi);
-    let contract = blueprint_sdk::alloy::contract::ContractInstance::new(
-        manager,
-        client.provider().clone(),
-        interface,
-    );
+    let registry = WorkflowRegistry {
+        contract: blueprint_sdk::alloy::contract::ContractInstance::new(
+            manager,
+            blueprint_sdk::alloy::providers::Provider::erased(client.provider().clone()),
+            WORKFLOW_REGISTRY_INTERFACE.clone(),
+        ),
+        synced_through: None,
+    };
+    WORKFLOW_REGISTRIES
+        .lock()
+        .map_err(|_| "Workflow registry cache poisoned".to_string())?
+        .entry(service_id)
+        .or_insert_with(|| registry.clone());
+    Ok(Some(registry))
+}
+
+fn record_synced_through(service_id: u64, updated_at: u64) {
+    if let Ok(mut registries) = WORKFLOW_REGISTRIES.lock()
+        && let Some(registry) = registries.get_mut(&service_id)
+    {
+        registry.synced_through = Some(registry.synced_through.unwrap_or(0).max(updated_at));
+    }
+}
+
+/// Whether an incremental sync has to re-apply a workflow last updated on
+/// chain at `updated_at`.
+pub(crate) fn workflow_needs_sync(
+    updated_at: u64,
+    synced_through: Option<u64>,
+    known_locally: bool,
+) -> bool {
+    match synced_through {
+        Some(watermark) => !known_locally || updated_at > watermark,
+        None => true,
+    }
+}
+
+pub async fn bootstrap_workflows_from_chain(
+    client: &blueprint_sdk::contexts::tangle::TangleClient,
+    service_id: u64,
+) -> Result<(), String> {
+    sync_workflows_from_chain(client, service_id, WorkflowSyncMode::Full)
+        .await
+        .map(|_| ())
+}
+
+/// Bring the local workflow store in line with the on-chain registry of
+/// `service_id`. Chain state wins; only local ownership metadata is kept.
+pub async fn sync_workflows_from_chain(
+    client: &blueprint_sdk::contexts::tangle::TangleClient,
+    service_id: u64,
+    mode: WorkflowSyncMode,
+) -> Result<WorkflowSyncReport, String> {
+    let mut report = WorkflowSyncReport::default();
+    let Some(registry) = workflow_registry(client, service_id).await? else {
+        return Ok(report);
+    };
+    let contract = &registry.contract;
+    let synced_through = match mode {
+        WorkflowSyncMode::Full => None,
+        WorkflowSyncMode::Incremental => registry.synced_through,
+    };
 
     let ids = contract
         .function(
@@ -32,13 +147,15 @@ pub async fn bootstrap_workflows_from_chain(
         .map_err(|err| format!("Failed to read workflow IDs: {err}"))?;
 
     let ids = parse_workflow_ids(ids)?;
-    let existing_entries: HashMap<String, WorkflowEntry> = workflows()?
+    report.on_chain = ids.len();
+    let mut existing_entries: HashMap<String, WorkflowEntry> = workflows()?
         .values()
         .map_err(|e| e.to_string())?
         .into_iter()
         .map(|entry| (workflow_key(entry.id), entry))
         .collect();
     let mut entries: HashMap<String, WorkflowEntry> = HashMap::new();
+    let mut newest_update = 0;
     for workflow_id in ids {
         let output = contract
             .function(
@@ -52,14 +169,93 @@ pub async fn bootstrap_workflows_from_chain(
             .call()
             .await
             .map_err(|err| format!("Failed to read workflow {workflow_id}: {err}"))?;
-        let mut entry = parse_workflow_config(workflow_id, output)?;
+        let updated_at = parse_workflow_updated_at(&output)?;
+        newest_update = newest_update.max(updated_at);
         let key = workflow_key(workflow_id);
-        merge_local_workflow_metadata(&mut entry, existing_entries.get(&key))?;
+        let existing = existing_entries.remove(&key);
+        if !workflow_needs_sync(updated_at, synced_through, existing.is_some()) {
+            report.unchanged += 1;
+            continue;
+        }
+        let mut entry = parse_workflow_config(workflow_id, output)?;
+        merge_local_workflow_metadata(&mut entry, existing.as_ref())?;
+        if existing.is_some() {
+            report.updated += 1;
+        } else {
+            report.created += 1;
+        }
         entries.insert(key, entry);
     }
+    // Whatever is left locally is no longer registered on chain.
+    let store = workflows()?;
+    if synced_through.is_none() {
+        report.removed = existing_entries.len();
+        store.replace(entries).map_err(|e| e.to_string())?;
+    } else {
+        for (key, entry) in existing_entries.iter().filter(|(_, e)| e.active) {
+            store
+                .update(key, |e| {
+                    e.active = false;
+                    e.next_run_at = None;
+                })
+                .map_err(|e| e.to_string())?;
+            tracing::info!(
+                workflow_id = entry.id,
+                "Deactivated workflow missing on chain"
+            );
+            report.removed += 1;
+        }
+        for (key, entry) in entries {
+            store.insert(key, entry).map_err(|e| e.to_string())?;
+        }
+    }
+    record_synced_through(service_id, newest_update);
+    Ok(report)
+}
 
-    workflows()?.replace(entries).map_err(|e| e.to_string())?;
-    Ok(())
+/// Seconds between periodic workflow re-syncs unless
+/// `WORKFLOW_CHAIN_SYNC_SECS` says otherwise.
+pub const DEFAULT_WORKFLOW_CHAIN_SYNC_SECS: u64 = 300;
+
+/// Seconds between periodic re-syncs: `WORKFLOW_CHAIN_SYNC_SECS`, default
+/// [`DEFAULT_WORKFLOW_CHAIN_SYNC_SECS`]; `0` turns the re-sync off.
+pub fn workflow_chain_sync_interval() -> Option<u64> {
+    match std::env::var("WORKFLOW_CHAIN_SYNC_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+    {
+        Some(0) => None,
+        Some(secs) => Some(secs),
+        None => Some(DEFAULT_WORKFLOW_CHAIN_SYNC_SECS),
+    }
+}
+
+/// One periodic re-sync: pick up workflows registered, changed or cancelled
+/// on chain since the last sync and record the drift it corrected.
+pub async fn workflow_chain_sync_tick(
+    client: &blueprint_sdk::contexts::tangle::TangleClient,
+    service_id: u64,
+) -> Result<WorkflowSyncReport, String> {
+    match sync_workflows_from_chain(client, service_id, WorkflowSyncMode::Incremental).await {
+        Ok(report) => {
+            let drift = report.drift();
+            if drift > 0 {
+                tracing::info!(
+                    service_id,
+                    created = report.created,
+                    updated = report.updated,
+                    deactivated = report.removed,
+                    "Workflow store drifted from chain; re-synced"
+                );
+            }
+            crate::metrics::metrics().record_workflow_sync(drift as u64, now_ts());
+            Ok(report)
+        }
+        Err(err) => {
+            crate::metrics::metrics().record_workflow_sync_failure();
+            Err(err)
+        }
+    }
 }
 
 pub(crate) fn parse_workflow_ids(
@@ -84,10 +280,9 @@ pub(crate) fn parse_workflow_ids(
     Ok(parsed)
 }
 
-fn parse_workflow_config(
-    workflow_id: u64,
-    values: Vec<blueprint_sdk::alloy::dyn_abi::DynSolValue>,
-) -> Result<WorkflowEntry, String> {
+fn workflow_fields(
+    values: &[blueprint_sdk::alloy::dyn_abi::DynSolValue],
+) -> Result<&[blueprint_sdk::alloy::dyn_abi::DynSolValue], String> {
     let first = values
         .first()
         .ok_or_else(|| "Missing workflow output".to_string())?;
@@ -97,6 +292,21 @@ fn parse_workflow_config(
     if fields.len() != 12 {
         return Err("Unexpected workflow tuple size".to_string());
     }
+    Ok(fields)
+}
+
+/// The `updatedAt` field of a `getWorkflow` result.
+pub(crate) fn parse_workflow_updated_at(
+    values: &[blueprint_sdk::alloy::dyn_abi::DynSolValue],
+) -> Result<u64, String> {
+    dyn_u64(&workflow_fields(values)?[10])
+}
+
+fn parse_workflow_config(
+    workflow_id: u64,
+    values: Vec<blueprint_sdk::alloy::dyn_abi::DynSolValue>,
+) -> Result<WorkflowEntry, String> {
+    let fields = workflow_fields(&values)?;
 
     let name = dyn_string(&fields[0])?;
     let workflow_json = dyn_string(&fields[1])?;
@@ -0,0 +1,196 @@
+//! Sandbox-per-run workflows.
+//!
+//! A workflow whose `sandbox_config_json` sets `"ephemeral": true` has no
+//! long-lived target: every run provisions a fresh sandbox from that config,
+//! runs the task on it, and deletes it afterwards, so scheduled runs start
+//! from a clean, reproducible environment.
+
+use super::*;
+
+use crate::CreateSandboxParams;
+
+/// How long a fresh sandbox may take to pass `/health`.
+pub const EPHEMERAL_READY_TIMEOUT_SECS: u64 = 120;
+/// Lifetime cap when the config sets none, so the reaper removes a sandbox
+/// whose teardown failed.
+pub const EPHEMERAL_DEFAULT_MAX_LIFETIME_SECS: u64 = 3600;
+
+/// `sandbox_config_json` of an ephemeral workflow, e.g.
+/// `{"ephemeral": true, "template": "claude-code", "env": {"ANTHROPIC_API_KEY": "..."}}`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EphemeralSandboxConfig {
+    #[serde(default)]
+    pub ephemeral: bool,
+    #[serde(default)]
+    pub template: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub stack: String,
+    #[serde(default)]
+    pub agent_identifier: String,
+    #[serde(default)]
+    pub env: serde_json::Map<String, Value>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub cpu_cores: u64,
+    #[serde(default)]
+    pub memory_mb: u64,
+    #[serde(default)]
+    pub disk_gb: u64,
+    #[serde(default)]
+    pub max_lifetime_seconds: u64,
+}
+
+/// The ephemeral config of a workflow, or `None` when it runs on a
+/// long-lived target.
+pub fn ephemeral_sandbox_config(
+    sandbox_config_json: &str,
+) -> Result<Option<EphemeralSandboxConfig>, String> {
+    let trimmed = sandbox_config_json.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    let Ok(Value::Object(map)) = serde_json::from_str::<Value>(trimmed) else {
+        return Ok(None);
+    };
+    if !map
+        .get("ephemeral")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        return Ok(None);
+    }
+    serde_json::from_value(Value::Object(map))
+        .map(Some)
+        .map_err(|err| format!("Invalid ephemeral sandbox_config_json: {err}"))
+}
+
+pub fn is_ephemeral_workflow(entry: &WorkflowEntry) -> bool {
+    matches!(
+        ephemeral_sandbox_config(&entry.sandbox_config_json),
+        Ok(Some(_))
+    )
+}
+
+impl EphemeralSandboxConfig {
+    /// Create parameters for one run of workflow `workflow_id`.
+    pub fn create_params(
+        &self,
+        workflow_id: u64,
+        owner: &str,
+        service_id: Option<u64>,
+    ) -> Result<CreateSandboxParams, String> {
+        let mut params = CreateSandboxParams {
+            name: format!("wf-{workflow_id}-{}", now_ts()),
+            image: self.image.clone(),
+            stack: self.stack.clone(),
+            agent_identifier: self.agent_identifier.clone(),
+            env_json: Value::Object(self.env.clone()).to_string(),
+            cpu_cores: self.cpu_cores,
+            memory_mb: self.memory_mb,
+            disk_gb: self.disk_gb,
+            max_lifetime_seconds: if self.max_lifetime_seconds == 0 {
+                EPHEMERAL_DEFAULT_MAX_LIFETIME_SECS
+            } else {
+                self.max_lifetime_seconds
+            },
+            owner: owner.to_string(),
+            service_id,
+            capabilities_json: if self.capabilities.is_empty() {
+                String::new()
+            } else {
+                json!(self.capabilities).to_string()
+            },
+            ..Default::default()
+        };
+        if !self.template.trim().is_empty() {
+            sandbox_runtime::templates::apply_template(&mut params, &self.template)
+                .map_err(|err| err.to_string())?;
+        }
+        Ok(params)
+    }
+}
+
+/// Check an ephemeral workflow can run before it is stored: the config must
+/// name an agent and carry AI credentials, since there is no existing
+/// sandbox to take them from.
+pub fn validate_ephemeral_workflow(
+    config: &EphemeralSandboxConfig,
+    workflow_json: &str,
+) -> Result<(), String> {
+    parse_workflow_task_spec(workflow_json)?;
+    let params = config.create_params(0, "", None)?;
+    if params.agent_identifier.trim().is_empty() {
+        return Err(
+            "Ephemeral workflows need an agent_identifier (or a template that sets one)"
+                .to_string(),
+        );
+    }
+    let has_credentials = crate::runtime::workflow_runtime_credentials_available(&params.env_json)
+        .map_err(|err| err.to_string())?;
+    if !has_credentials {
+        return Err(
+            "Ephemeral workflows require AI credentials in the sandbox_config_json env".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// A sandbox provisioned for one workflow run. Call [`Self::teardown`] once
+/// the run is over, whatever its outcome.
+pub struct EphemeralSandbox {
+    pub record: crate::SandboxRecord,
+}
+
+impl EphemeralSandbox {
+    pub async fn provision(
+        entry: &WorkflowEntry,
+        config: &EphemeralSandboxConfig,
+    ) -> Result<Self, String> {
+        let service_id = (entry.target_service_id != 0).then_some(entry.target_service_id);
+        let params = config.create_params(entry.id, &entry.owner, service_id)?;
+        let tee = crate::tee_backend().map(|b| b.as_ref());
+        let (record, _attestation) = crate::runtime::create_sidecar(&params, tee)
+            .await
+            .map_err(|err| format!("Failed to provision ephemeral sandbox: {err}"))?;
+        let sandbox = Self { record };
+        if !crate::runtime::wait_for_sidecar_health(
+            &sandbox.record.sidecar_url,
+            EPHEMERAL_READY_TIMEOUT_SECS,
+        )
+        .await
+        {
+            sandbox.teardown().await;
+            return Err(format!(
+                "Ephemeral sandbox did not become healthy within {EPHEMERAL_READY_TIMEOUT_SECS}s"
+            ));
+        }
+        tracing::info!(
+            workflow_id = entry.id,
+            sandbox_id = %sandbox.record.id,
+            "Provisioned ephemeral workflow sandbox"
+        );
+        Ok(sandbox)
+    }
+
+    /// Delete the sandbox and its record. Failures are logged; the
+    /// sandbox's lifetime cap lets the reaper finish the job.
+    pub async fn teardown(self) {
+        let tee = crate::tee_backend().map(|b| b.as_ref());
+        if let Err(err) = crate::runtime::delete_sidecar(&self.record, tee).await {
+            tracing::warn!(
+                sandbox_id = %self.record.id,
+                "Failed to delete ephemeral workflow sandbox: {err}"
+            );
+            return;
+        }
+        if let Err(err) = crate::runtime::sandboxes().and_then(|s| s.remove(&self.record.id)) {
+            tracing::warn!(
+                sandbox_id = %self.record.id,
+                "Failed to remove ephemeral workflow sandbox record: {err}"
+            );
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Workflow tick leadership across replicas.
+//!
+//! Operators running redundant copies of the blueprint point
+//! `WORKFLOW_LEADER_LOCK_DIR` at a directory every replica can write (a
+//! shared volume). Each tick, a replica renews or takes the lease in
+//! `workflow-leader.lease`; only the holder runs due cron workflows. A lease
+//! expires `WORKFLOW_LEADER_LEASE_SECS` (default 90) after its last renewal,
+//! so a crashed leader is replaced within one lease. The leader re-checks
+//! (and renews) the lease before each workflow run, so one that lost it
+//! mid-tick stops starting runs. Without the env var there is no
+//! coordination and every tick leads, as before.
+//!
+//! Lease updates are serialized with an advisory `flock` on
+//! `workflow-leader.lock`; a replica that finds it held skips leadership for
+//! that tick. The kernel drops the lock when its holder exits, so a crash
+//! never leaves it behind and there is no stale-lock takeover to race on.
+
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+const LEASE_FILE: &str = "workflow-leader.lease";
+const LOCK_FILE: &str = "workflow-leader.lock";
+/// Default lease length: longer than the default one-minute cron tick so a
+/// healthy leader renews before it expires.
+pub const DEFAULT_LEADER_LEASE_SECS: u64 = 90;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaderLease {
+    pub holder: String,
+    pub expires_at: u64,
+}
+
+/// This replica's lease holder ID: `WORKFLOW_REPLICA_ID`, else
+/// `{hostname}-{pid}-{random}`.
+pub fn replica_id() -> &'static str {
+    static ID: Lazy<String> = Lazy::new(|| {
+        std::env::var("WORKFLOW_REPLICA_ID")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| {
+                let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "replica".into());
+                let nonce = uuid::Uuid::new_v4().simple().to_string();
+                format!("{host}-{}-{}", std::process::id(), &nonce[..8])
+            })
+    });
+    &ID
+}
+
+fn lease_secs() -> u64 {
+    std::env::var("WORKFLOW_LEADER_LEASE_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_LEADER_LEASE_SECS)
+}
+
+/// Whether `holder` may take or renew the lease at `now`.
+pub fn may_lead(current: Option<&LeaderLease>, holder: &str, now: u64) -> bool {
+    match current {
+        None => true,
+        Some(lease) => lease.holder == holder || lease.expires_at <= now,
+    }
+}
+
+/// Whether this replica leads the tick at `now`. Also called before each
+/// workflow run of a tick, renewing the lease.
+pub fn acquire_tick_leadership(now: u64) -> bool {
+    let Some(dir) = std::env::var("WORKFLOW_LEADER_LOCK_DIR")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+    else {
+        return true;
+    };
+    match try_acquire_lease(Path::new(&dir), replica_id(), now, lease_secs()) {
+        Ok(leader) => leader,
+        Err(err) => {
+            // Running without a lease could double-run workflows; skipping a
+            // tick only delays them.
+            tracing::warn!(dir, error = %err, "Workflow leader lease unavailable; skipping tick");
+            false
+        }
+    }
+}
+
+/// Take or renew the lease in `dir` for `holder`. `Ok(false)` when another
+/// replica holds it or is updating it right now.
+pub fn try_acquire_lease(
+    dir: &Path,
+    holder: &str,
+    now: u64,
+    lease_secs: u64,
+) -> std::io::Result<bool> {
+    std::fs::create_dir_all(dir)?;
+    let Some(_lock) = LockFile::try_lock(&dir.join(LOCK_FILE))? else {
+        return Ok(false);
+    };
+
+    let lease_path = dir.join(LEASE_FILE);
+    let current = match std::fs::read(&lease_path) {
+        Ok(bytes) => serde_json::from_slice::<LeaderLease>(&bytes).ok(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+    if !may_lead(current.as_ref(), holder, now) {
+        return Ok(false);
+    }
+    if current.as_ref().is_none_or(|lease| lease.holder != holder) {
+        tracing::info!(holder, "Took workflow tick leadership");
+    }
+
+    let lease = LeaderLease {
+        holder: holder.to_string(),
+        expires_at: now.saturating_add(lease_secs),
+    };
+    let tmp = dir.join(format!("{LEASE_FILE}.{holder}.tmp"));
+    std::fs::write(&tmp, serde_json::to_vec(&lease)?)?;
+    std::fs::rename(&tmp, &lease_path)?;
+    Ok(true)
+}
+
+/// The exclusive `flock` on the lease lock file, released when dropped.
+struct LockFile(std::fs::File);
+
+impl LockFile {
+    fn try_lock(path: &Path) -> std::io::Result<Option<Self>> {
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self(file))),
+            Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+            Err(std::fs::TryLockError::Error(err)) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod leader_tests {
+    use super::*;
+
+    #[test]
+    fn lease_goes_to_holder_or_after_expiry() {
+        let lease = LeaderLease {
+            holder: "a".into(),
+            expires_at: 100,
+        };
+        assert!(may_lead(None, "b", 50));
+        assert!(may_lead(Some(&lease), "a", 50));
+        assert!(!may_lead(Some(&lease), "b", 99));
+        assert!(may_lead(Some(&lease), "b", 100));
+    }
+
+    #[test]
+    fn only_one_replica_leads_until_the_lease_expires() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(try_acquire_lease(dir.path(), "a", 1_000, 90).unwrap());
+        assert!(!try_acquire_lease(dir.path(), "b", 1_010, 90).unwrap());
+        // The leader renews...
+        assert!(try_acquire_lease(dir.path(), "a", 1_060, 90).unwrap());
+        assert!(!try_acquire_lease(dir.path(), "b", 1_120, 90).unwrap());
+        // ...and once it stops, another replica takes over.
+        assert!(try_acquire_lease(dir.path(), "b", 1_150, 90).unwrap());
+        assert!(!try_acquire_lease(dir.path(), "a", 1_160, 90).unwrap());
+    }
+
+    #[test]
+    fn held_lock_skips_and_leftover_lock_file_does_not() {
+        let dir = tempfile::tempdir().unwrap();
+        let held = LockFile::try_lock(&dir.path().join(LOCK_FILE))
+            .unwrap()
+            .unwrap();
+        assert!(!try_acquire_lease(dir.path(), "a", 1_000, 90).unwrap());
+        // A crashed holder leaves the file but not the lock.
+        drop(held);
+        assert!(try_acquire_lease(dir.path(), "a", 1_000, 90).unwrap());
+    }
+
+    #[test]
+    fn racing_replicas_elect_one_leader() {
+        let dir = tempfile::tempdir().unwrap();
+        let barrier = std::sync::Barrier::new(8);
+        let leaders = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    let (dir, barrier) = (dir.path(), &barrier);
+                    scope.spawn(move || {
+                        barrier.wait();
+                        try_acquire_lease(dir, &format!("r{i}"), 1_000, 90).unwrap()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .filter(|h| h.join().unwrap_or(false))
+                .count()
+        });
+        assert_eq!(leaders, 1, "exactly one replica should take the lease");
+    }
+}
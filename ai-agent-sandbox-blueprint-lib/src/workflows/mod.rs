@@ -1,4 +1,5 @@
 use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
 use cron::Schedule;
 use serde::Deserialize;
 use serde_json::{Value, json};
@@ -12,14 +13,20 @@ use crate::jobs::exec::run_task_request_with_profile;
 use crate::store::PersistentStore;
 use crate::util::now_ts;
 
+mod artifacts;
 mod chain;
+mod ephemeral;
+mod leader;
 mod run;
 mod schedule;
 mod spec;
 mod status;
 mod store;
 
+pub use artifacts::*;
 pub use chain::*;
+pub use ephemeral::*;
+pub use leader::*;
 pub use run::*;
 pub use schedule::*;
 pub use spec::*;
@@ -69,6 +76,8 @@ pub struct WorkflowLatestExecution {
     pub input_tokens: u32,
     pub output_tokens: u32,
     pub session_id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<WorkflowArtifact>,
 }
 
 impl WorkflowLatestExecution {
@@ -83,6 +92,7 @@ impl WorkflowLatestExecution {
             input_tokens: 0,
             output_tokens: 0,
             session_id: String::new(),
+            artifacts: Vec::new(),
         }
     }
 }
@@ -207,4 +217,10 @@ pub struct WorkflowTaskSpec {
     /// `memory`, etc.
     #[serde(default)]
     pub backend_profile_json: Option<String>,
+    /// Sandbox paths uploaded after every run (see `artifacts.rs`).
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+    /// Overrides `WORKFLOW_ARTIFACT_DESTINATION` for this workflow.
+    #[serde(default)]
+    pub artifact_destination: Option<String>,
 }
@@ -1,9 +1,50 @@
 use super::*;
 
+use futures_util::StreamExt;
+
 pub async fn run_workflow(entry: &WorkflowEntry) -> Result<WorkflowExecution, String> {
-    let spec = parse_workflow_task_spec(entry.workflow_json.as_str())?;
+    run_workflow_within(entry, None).await
+}
+
+/// [`run_workflow`], failing the run if the task is still going after
+/// `timeout`. An ephemeral sandbox is torn down either way.
+pub async fn run_workflow_within(
+    entry: &WorkflowEntry,
+    timeout: Option<std::time::Duration>,
+) -> Result<WorkflowExecution, String> {
+    let spec = resolve_workflow_task_spec(entry).await?;
+    if let Some(config) = ephemeral_sandbox_config(&entry.sandbox_config_json)? {
+        let sandbox = EphemeralSandbox::provision(entry, &config).await?;
+        let result = with_timeout(timeout, run_workflow_on(entry, spec, &sandbox.record)).await;
+        sandbox.teardown().await;
+        return result;
+    }
     let record = resolve_workflow_sandbox(entry)?;
+    with_timeout(timeout, run_workflow_on(entry, spec, &record)).await
+}
 
+pub(crate) async fn with_timeout<T>(
+    timeout: Option<std::time::Duration>,
+    run: impl std::future::Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    let Some(timeout) = timeout else {
+        return run.await;
+    };
+    tokio::time::timeout(timeout, run)
+        .await
+        .unwrap_or_else(|_| {
+            Err(format!(
+                "Workflow run timed out after {}s",
+                timeout.as_secs()
+            ))
+        })
+}
+
+async fn run_workflow_on(
+    entry: &WorkflowEntry,
+    spec: WorkflowTaskSpec,
+    record: &crate::SandboxRecord,
+) -> Result<WorkflowExecution, String> {
     // Fast-fail: if the sandbox has no agent configured, the sidecar will
     // reject the request with "No factory registered for agent identifier".
     // Fail immediately with a clear message instead of burning a timeout.
@@ -46,6 +87,9 @@ pub async fn run_workflow(entry: &WorkflowEntry) -> Result<WorkflowExecution, St
         model: spec.model.unwrap_or_default(),
         context_json: spec.context_json.unwrap_or_default(),
         timeout_ms: spec.timeout_ms.unwrap_or(0),
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: record.agent_identifier.clone(),
     };
 
     // Resolve backend profile: prefer backend_profile_json, fall back to
@@ -64,6 +108,15 @@ pub async fn run_workflow(entry: &WorkflowEntry) -> Result<WorkflowExecution, St
     let response =
         run_task_request_with_profile(&request, &token, backend_profile.as_ref()).await?;
     let now = now_ts();
+    let artifacts = upload_workflow_artifacts(
+        record,
+        &token,
+        entry.id,
+        now,
+        &spec.artifacts,
+        spec.artifact_destination.as_deref(),
+    )
+    .await;
     let next_run_at = resolve_next_run(&entry.trigger_type, &entry.trigger_config, Some(now))?;
     let latest_execution = WorkflowLatestExecution {
         executed_at: now,
@@ -75,6 +128,7 @@ pub async fn run_workflow(entry: &WorkflowEntry) -> Result<WorkflowExecution, St
         input_tokens: response.input_tokens,
         output_tokens: response.output_tokens,
         session_id: response.session_id.clone(),
+        artifacts: artifacts.clone(),
     };
 
     Ok(WorkflowExecution {
@@ -93,6 +147,7 @@ pub async fn run_workflow(entry: &WorkflowEntry) -> Result<WorkflowExecution, St
                 "inputTokens": response.input_tokens,
                 "outputTokens": response.output_tokens,
                 "sessionId": response.session_id,
+                "artifacts": artifacts,
             }
         }),
         last_run_at: now,
@@ -110,13 +165,45 @@ pub fn apply_workflow_execution(
     entry.next_run_at = next_run_at;
 }
 
-pub async fn workflow_tick() -> Result<Value, String> {
-    let now = now_ts();
-    let all = workflows()?.values().map_err(|e| e.to_string())?;
+/// Upper bound on completion-trigger stages run within one tick, so a cycle
+/// of `workflow_complete` triggers advances at most this far per tick.
+const MAX_CHAIN_ROUNDS: usize = 8;
+/// Due workflows a tick runs at once unless `WORKFLOW_TICK_PARALLELISM` says
+/// otherwise.
+pub const DEFAULT_WORKFLOW_TICK_PARALLELISM: usize = 4;
+/// Longest a ticked run may take unless `WORKFLOW_RUN_TIMEOUT_SECS` says
+/// otherwise; a run cut off is recorded as failed.
+pub const DEFAULT_WORKFLOW_RUN_TIMEOUT_SECS: u64 = 1800;
+
+fn env_positive(key: &str) -> Option<u64> {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&v| v > 0)
+}
+
+pub fn workflow_tick_parallelism() -> usize {
+    env_positive("WORKFLOW_TICK_PARALLELISM")
+        .map_or(DEFAULT_WORKFLOW_TICK_PARALLELISM, |v| v as usize)
+}
 
-    let due: Vec<u64> = all
+pub fn workflow_run_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(
+        env_positive("WORKFLOW_RUN_TIMEOUT_SECS").unwrap_or(DEFAULT_WORKFLOW_RUN_TIMEOUT_SECS),
+    )
+}
+
+/// Due workflows at `now`: cron workflows whose `next_run_at` has passed and
+/// completion-triggered workflows marked by [`mark_dependents_due`].
+fn due_workflows(now: u64, completion_only: bool) -> Result<Vec<u64>, String> {
+    let all = workflows()?.values().map_err(|e| e.to_string())?;
+    Ok(all
         .iter()
-        .filter(|e| e.active && e.trigger_type == "cron")
+        .filter(|e| e.active)
+        .filter(|e| {
+            e.trigger_type == WORKFLOW_COMPLETE_TRIGGER
+                || (!completion_only && e.trigger_type == "cron")
+        })
         .filter(|entry| {
             !matches!(
                 resolve_workflow_target_status(entry),
@@ -124,58 +211,70 @@ pub async fn workflow_tick() -> Result<Value, String> {
             )
         })
         .filter_map(|e| e.next_run_at.filter(|&t| t <= now).map(|_| e.id))
-        .collect();
+        .collect())
+}
 
+pub async fn workflow_tick() -> Result<Value, String> {
+    let now = now_ts();
+    let due = due_workflows(now, false)?;
+
+    if !acquire_tick_leadership(now) {
+        // Another replica runs these. Keep the schedule in step so this
+        // replica doesn't fire a backlog of missed runs if it takes over.
+        for workflow_id in &due {
+            let key = workflow_key(*workflow_id);
+            workflows()?
+                .update(&key, |e| {
+                    e.next_run_at = resolve_next_run(&e.trigger_type, &e.trigger_config, Some(now))
+                        .ok()
+                        .flatten();
+                })
+                .map_err(|e| e.to_string())?;
+        }
+        return Ok(json!({
+            "executed": [],
+            "count": 0,
+            "leader": false,
+        }));
+    }
+
+    let parallelism = workflow_tick_parallelism();
+    let timeout = workflow_run_timeout();
     let mut executed = Vec::new();
-    for workflow_id in due {
-        let _run_guard = match acquire_workflow_run(workflow_id) {
-            Ok(guard) => guard,
-            Err(_) => {
-                tracing::debug!("Workflow {workflow_id} already running, skipping");
-                continue;
-            }
-        };
-
-        let key = workflow_key(workflow_id);
-        let entry = match workflows()?.get(&key).map_err(|e| e.to_string())? {
-            Some(e) if e.active => e,
-            _ => continue,
-        };
-
-        // Advance next_run_at BEFORE starting the run to prevent duplicate
-        // executions when the cron fires faster than the workflow completes.
-        let tentative_next =
-            resolve_next_run(&entry.trigger_type, &entry.trigger_config, Some(now))
-                .ok()
-                .flatten();
-        workflows()?
-            .update(&key, |e| {
-                e.next_run_at = tentative_next;
+    let mut due = due;
+    for _ in 0..MAX_CHAIN_ROUNDS {
+        if due.is_empty() {
+            break;
+        }
+        // Up to `parallelism` runs at once, collected as each finishes, so a
+        // slow agent task doesn't hold back the rest of the tick.
+        let mut runs = futures_util::stream::iter(due)
+            .map(|workflow_id| async move {
+                // The lease may have lapsed during a long tick; re-check it
+                // (renewing it) before starting each run.
+                if !acquire_tick_leadership(now_ts()) {
+                    tracing::info!(workflow_id, "Lost workflow tick leadership; skipping run");
+                    return (workflow_id, Ok(None));
+                }
+                (workflow_id, tick_workflow(workflow_id, now, timeout).await)
             })
-            .map_err(|e| e.to_string())?;
-
-        match run_workflow(&entry).await {
-            Ok(execution) => {
-                let last_run_at = execution.last_run_at;
-                let next_run_at = execution.next_run_at;
-                store_latest_execution(workflow_id, execution.latest_execution.clone())?;
-                workflows()?
-                    .update(&key, |e| {
-                        e.last_run_at = Some(last_run_at);
-                        e.next_run_at = next_run_at;
-                    })
-                    .map_err(|e| e.to_string())?;
-                executed.push(execution.response);
-            }
-            Err(err) => {
-                store_failed_execution(workflow_id, err.clone())?;
-                executed.push(json!({
-                    "workflowId": workflow_id,
-                    "status": "error",
-                    "error": err,
-                }));
+            .buffer_unordered(parallelism);
+        while let Some((workflow_id, outcome)) = runs.next().await {
+            match outcome {
+                Ok(Some(response)) => executed.push(response),
+                Ok(None) => {}
+                Err(err) => {
+                    tracing::warn!(workflow_id, "Workflow tick bookkeeping failed: {err}");
+                    executed.push(json!({
+                        "workflowId": workflow_id,
+                        "status": "error",
+                        "error": err,
+                    }));
+                }
             }
         }
+        // Run the stages whose upstream just finished in this same tick.
+        due = due_workflows(now_ts(), true)?;
     }
 
     Ok(json!({
@@ -183,3 +282,57 @@ pub async fn workflow_tick() -> Result<Value, String> {
         "count": executed.len(),
     }))
 }
+
+async fn tick_workflow(
+    workflow_id: u64,
+    now: u64,
+    timeout: std::time::Duration,
+) -> Result<Option<Value>, String> {
+    let _run_guard = match acquire_workflow_run(workflow_id) {
+        Ok(guard) => guard,
+        Err(_) => {
+            tracing::debug!("Workflow {workflow_id} already running, skipping");
+            return Ok(None);
+        }
+    };
+
+    let key = workflow_key(workflow_id);
+    let entry = match workflows()?.get(&key).map_err(|e| e.to_string())? {
+        Some(e) if e.active => e,
+        _ => return Ok(None),
+    };
+
+    // Advance next_run_at BEFORE starting the run to prevent duplicate
+    // executions when the cron fires faster than the workflow completes.
+    let tentative_next = resolve_next_run(&entry.trigger_type, &entry.trigger_config, Some(now))
+        .ok()
+        .flatten();
+    workflows()?
+        .update(&key, |e| {
+            e.next_run_at = tentative_next;
+        })
+        .map_err(|e| e.to_string())?;
+
+    match run_workflow_within(&entry, Some(timeout)).await {
+        Ok(execution) => {
+            let last_run_at = execution.last_run_at;
+            let next_run_at = execution.next_run_at;
+            store_latest_execution(workflow_id, execution.latest_execution.clone())?;
+            workflows()?
+                .update(&key, |e| {
+                    e.last_run_at = Some(last_run_at);
+                    e.next_run_at = next_run_at;
+                })
+                .map_err(|e| e.to_string())?;
+            Ok(Some(execution.response))
+        }
+        Err(err) => {
+            store_failed_execution(workflow_id, err.clone())?;
+            Ok(Some(json!({
+                "workflowId": workflow_id,
+                "status": "error",
+                "error": err,
+            })))
+        }
+    }
+}
@@ -1,5 +1,76 @@
 use super::*;
 
+/// Cron `trigger_config`: either a bare cron expression, evaluated in UTC,
+/// or `{"cron": "0 0 9 * * *", "timezone": "Europe/Berlin"}` to evaluate it
+/// in an IANA timezone. Local times are resolved per run, so a 9am schedule
+/// stays at 9am local across DST changes.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CronTrigger {
+    cron: String,
+    #[serde(default)]
+    timezone: Option<String>,
+}
+
+/// Trigger type that runs a workflow when another one finishes.
+pub const WORKFLOW_COMPLETE_TRIGGER: &str = "workflow_complete";
+
+/// `trigger_config` of a [`WORKFLOW_COMPLETE_TRIGGER`] workflow, e.g.
+/// `{"workflow_id": 12, "on": "success"}`. `on` is `success` (default),
+/// `failure` or `any`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompletionTrigger {
+    pub workflow_id: u64,
+    #[serde(default)]
+    pub on: CompletionFilter,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionFilter {
+    #[default]
+    Success,
+    Failure,
+    Any,
+}
+
+impl CompletionTrigger {
+    pub fn parse(trigger_config: &str) -> Result<Self, String> {
+        serde_json::from_str(trigger_config.trim())
+            .map_err(|err| format!("Invalid workflow_complete trigger config: {err}"))
+    }
+
+    /// Whether a run of `workflow_id` ending with `success` fires this trigger.
+    pub fn fires_on(&self, workflow_id: u64, success: bool) -> bool {
+        self.workflow_id == workflow_id
+            && match self.on {
+                CompletionFilter::Success => success,
+                CompletionFilter::Failure => !success,
+                CompletionFilter::Any => true,
+            }
+    }
+}
+
+/// Validate `trigger_config` for a new workflow `workflow_id`.
+pub fn validate_trigger_config(
+    trigger_type: &str,
+    trigger_config: &str,
+    workflow_id: u64,
+) -> Result<(), String> {
+    match trigger_type {
+        "cron" => resolve_next_run(trigger_type, trigger_config, None).map(|_| ()),
+        WORKFLOW_COMPLETE_TRIGGER => {
+            let trigger = CompletionTrigger::parse(trigger_config)?;
+            if trigger.workflow_id == workflow_id {
+                return Err("A workflow cannot be triggered by its own completion".to_string());
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
 pub fn resolve_next_run(
     trigger_type: &str,
     trigger_config: &str,
@@ -12,16 +83,41 @@ pub fn resolve_next_run(
     Ok(Some(compute_next_run(trigger_config, start)?))
 }
 
-fn compute_next_run(cron_expr: &str, from_ts: u64) -> Result<u64, String> {
+fn compute_next_run(trigger_config: &str, from_ts: u64) -> Result<u64, String> {
+    let (cron_expr, timezone) = parse_cron_trigger(trigger_config)?;
     let schedule =
-        Schedule::from_str(cron_expr).map_err(|err| format!("Invalid cron expression: {err}"))?;
+        Schedule::from_str(&cron_expr).map_err(|err| format!("Invalid cron expression: {err}"))?;
     let base = Utc
         .timestamp_opt(from_ts as i64, 0)
         .single()
         .ok_or_else(|| "Invalid timestamp".to_string())?;
-    schedule
-        .after(&base)
-        .next()
-        .map(|dt| dt.timestamp().max(0) as u64)
+    let next = match timezone {
+        Some(tz) => schedule
+            .after(&base.with_timezone(&tz))
+            .next()
+            .map(|dt| dt.timestamp()),
+        None => schedule.after(&base).next().map(|dt| dt.timestamp()),
+    };
+    next.map(|ts| ts.max(0) as u64)
         .ok_or_else(|| "Cron expression has no future run times".to_string())
 }
+
+fn parse_cron_trigger(trigger_config: &str) -> Result<(String, Option<Tz>), String> {
+    let trimmed = trigger_config.trim();
+    if !trimmed.starts_with('{') {
+        return Ok((trimmed.to_string(), None));
+    }
+    let trigger: CronTrigger = serde_json::from_str(trimmed)
+        .map_err(|err| format!("Invalid cron trigger config: {err}"))?;
+    let timezone = trigger
+        .timezone
+        .as_deref()
+        .map(str::trim)
+        .filter(|tz| !tz.is_empty())
+        .map(|tz| {
+            tz.parse::<Tz>()
+                .map_err(|_| format!("Unknown timezone '{tz}' (expected an IANA name)"))
+        })
+        .transpose()?;
+    Ok((trigger.cron, timezone))
+}
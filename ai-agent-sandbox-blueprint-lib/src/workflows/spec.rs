@@ -1,6 +1,6 @@
 use super::*;
 
-pub fn parse_workflow_task_spec(workflow_json: &str) -> Result<WorkflowTaskSpec, String> {
+fn parse_workflow_value(workflow_json: &str) -> Result<Value, String> {
     if workflow_json.trim().is_empty() {
         return Err("workflow_json is required".to_string());
     }
@@ -9,6 +9,38 @@ pub fn parse_workflow_task_spec(workflow_json: &str) -> Result<WorkflowTaskSpec,
         .map_err(|err| format!("workflow_json must be valid task JSON: {err}"))
 }
 
+fn task_spec_from_value(value: Value) -> Result<WorkflowTaskSpec, String> {
+    serde_json::from_value(value)
+        .map_err(|err| format!("workflow_json must be valid task JSON: {err}"))
+}
+
+/// Parse a workflow definition for inspection. `{"secretRef": ...}`
+/// placeholders read as empty strings; only [`resolve_workflow_task_spec`]
+/// fills them in.
+pub fn parse_workflow_task_spec(workflow_json: &str) -> Result<WorkflowTaskSpec, String> {
+    let mut value = parse_workflow_value(workflow_json)?;
+    sandbox_runtime::secret_provisioning::redact_secret_placeholders(&mut value);
+    task_spec_from_value(value)
+}
+
+/// Parse a workflow definition for a run, resolving its secret placeholders
+/// against the workflow owner's secrets. The plaintext lives only in the
+/// returned spec.
+pub async fn resolve_workflow_task_spec(entry: &WorkflowEntry) -> Result<WorkflowTaskSpec, String> {
+    let mut value = parse_workflow_value(&entry.workflow_json)?;
+    sandbox_runtime::secret_provisioning::resolve_secret_placeholders(&entry.owner, &mut value)
+        .await
+        .map_err(|err| format!("Failed to resolve workflow secrets: {err}"))?;
+    task_spec_from_value(value)
+}
+
+/// Reject placeholders `owner` cannot resolve when the workflow is created.
+pub fn validate_workflow_secret_refs(owner: &str, workflow_json: &str) -> Result<(), String> {
+    let value = parse_workflow_value(workflow_json)?;
+    sandbox_runtime::secret_provisioning::validate_secret_placeholders(owner, &value)
+        .map_err(|err| err.to_string())
+}
+
 pub fn validate_workflow_execution_ready(workflow_json: &str) -> Result<WorkflowTaskSpec, String> {
     let spec = parse_workflow_task_spec(workflow_json)?;
     let sidecar_url = spec.sidecar_url.as_deref().ok_or_else(|| {
@@ -84,6 +84,10 @@ fn workflow_detail_from_entry(
 pub(crate) fn resolve_workflow_target_status(
     entry: &WorkflowEntry,
 ) -> Result<WorkflowTargetStatus, String> {
+    if is_ephemeral_workflow(entry) {
+        // Provisioned per run; there is no standing target to lose.
+        return Ok(WorkflowTargetStatus::Available);
+    }
     if entry.target_kind == WORKFLOW_TARGET_SANDBOX && !entry.target_sandbox_id.trim().is_empty() {
         return match crate::runtime::get_sandbox_by_id(entry.target_sandbox_id.as_str()) {
             Ok(_) => Ok(WorkflowTargetStatus::Available),
@@ -108,6 +112,18 @@ fn resolve_workflow_effective_state_for_owner(
     entry: &WorkflowEntry,
     caller: &str,
 ) -> Result<WorkflowEffectiveState, WorkflowStatusError> {
+    if is_ephemeral_workflow(entry) {
+        return if owner_matches(entry, caller) {
+            Ok(workflow_effective_state_from_target_status(
+                entry,
+                WorkflowTargetStatus::Available,
+            ))
+        } else {
+            Err(WorkflowStatusError::Forbidden(
+                "Caller does not own this workflow".to_string(),
+            ))
+        };
+    }
     if entry.target_kind == WORKFLOW_TARGET_SANDBOX && !entry.target_sandbox_id.trim().is_empty() {
         return match crate::runtime::require_sandbox_owner(entry.target_sandbox_id.as_str(), caller)
         {
@@ -148,7 +164,11 @@ fn resolve_workflow_effective_state_for_owner(
     }
 }
 
-fn resolve_workflow_owner(entry: &WorkflowEntry) -> Result<Option<String>, String> {
+pub(super) fn resolve_workflow_owner(entry: &WorkflowEntry) -> Result<Option<String>, String> {
+    if is_ephemeral_workflow(entry) {
+        // Only the create job knows the caller; chain-synced copies stay unowned.
+        return Ok(None);
+    }
     if entry.target_kind == WORKFLOW_TARGET_SANDBOX && !entry.target_sandbox_id.trim().is_empty() {
         return match crate::runtime::get_sandbox_by_id(entry.target_sandbox_id.as_str()) {
             Ok(record) if !record.owner.is_empty() => Ok(Some(record.owner)),
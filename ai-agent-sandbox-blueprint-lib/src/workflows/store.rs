@@ -78,15 +78,75 @@ pub fn store_latest_execution(
             .insert(
                 key,
                 WorkflowRuntimeMetadata {
-                    latest_execution: Some(latest_execution),
+                    latest_execution: Some(latest_execution.clone()),
                 },
             )
             .map_err(|e| e.to_string())?;
     }
 
+    notify_workflow_completed(workflow_id, &latest_execution);
+    if let Err(err) = mark_dependents_due(workflow_id, latest_execution.success) {
+        tracing::warn!("Failed to queue workflows chained after {workflow_id}: {err}");
+    }
+    Ok(())
+}
+
+/// Make active `workflow_complete` workflows listening for this run due, so
+/// the next tick (or the current one, see `workflow_tick`) runs them.
+fn mark_dependents_due(workflow_id: u64, success: bool) -> Result<(), String> {
+    let dependents: Vec<u64> = workflows()?
+        .values()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|e| e.active && e.trigger_type == WORKFLOW_COMPLETE_TRIGGER)
+        .filter(|e| {
+            CompletionTrigger::parse(&e.trigger_config)
+                .is_ok_and(|trigger| trigger.fires_on(workflow_id, success))
+        })
+        .map(|e| e.id)
+        .collect();
+    let now = now_ts();
+    for dependent in dependents {
+        tracing::debug!("Workflow {workflow_id} finished; queueing workflow {dependent}");
+        workflows()?
+            .update(&workflow_key(dependent), |e| e.next_run_at = Some(now))
+            .map_err(|e| e.to_string())?;
+    }
     Ok(())
 }
 
+/// Send `workflow.completed` to the webhooks of the workflow's owner.
+fn notify_workflow_completed(workflow_id: u64, execution: &WorkflowLatestExecution) {
+    let Ok(Some(entry)) =
+        workflows().and_then(|s| s.get(&workflow_key(workflow_id)).map_err(|e| e.to_string()))
+    else {
+        return;
+    };
+    // On-chain workflows carry no owner; fall back to the target sandbox's.
+    let owner = if entry.owner.is_empty() {
+        super::status::resolve_workflow_owner(&entry)
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    } else {
+        entry.owner.clone()
+    };
+    sandbox_runtime::webhooks::emit(
+        &owner,
+        sandbox_runtime::webhooks::WebhookEvent::WorkflowCompleted,
+        json!({
+            "workflow_id": workflow_id,
+            "name": entry.name,
+            "success": execution.success,
+            "error": execution.error,
+            "trace_id": execution.trace_id,
+            "duration_ms": execution.duration_ms,
+            "executed_at": execution.executed_at,
+            "artifacts": execution.artifacts,
+        }),
+    );
+}
+
 pub fn store_failed_execution(
     workflow_id: u64,
     error: String,
@@ -72,3 +72,232 @@ fn workflow_run_guard_tracks_running_state() {
     drop(guard);
     assert!(!is_workflow_running(workflow_id));
 }
+
+fn utc_ts(y: i32, m: u32, d: u32, h: u32) -> u64 {
+    Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap().timestamp() as u64
+}
+
+#[test]
+fn cron_without_timezone_runs_in_utc() {
+    let next = resolve_next_run("cron", "0 0 9 * * *", Some(utc_ts(2026, 7, 1, 0))).unwrap();
+    assert_eq!(next, Some(utc_ts(2026, 7, 1, 9)));
+}
+
+#[test]
+fn cron_timezone_follows_local_time_across_dst() {
+    let config = r#"{"cron": "0 0 9 * * *", "timezone": "America/New_York"}"#;
+    // 9am EDT (UTC-4) in summer, 9am EST (UTC-5) in winter.
+    let summer = resolve_next_run("cron", config, Some(utc_ts(2026, 7, 1, 0))).unwrap();
+    assert_eq!(summer, Some(utc_ts(2026, 7, 1, 13)));
+    let winter = resolve_next_run("cron", config, Some(utc_ts(2026, 1, 15, 0))).unwrap();
+    assert_eq!(winter, Some(utc_ts(2026, 1, 15, 14)));
+    // The day after clocks go back (2026-11-01), 9am is 14:00 UTC again.
+    let after_fall_back = resolve_next_run("cron", config, Some(utc_ts(2026, 11, 1, 12))).unwrap();
+    assert_eq!(after_fall_back, Some(utc_ts(2026, 11, 1, 14)));
+}
+
+#[test]
+fn cron_trigger_config_rejects_unknown_timezone() {
+    let config = r#"{"cron": "0 0 9 * * *", "timezone": "Mars/Olympus"}"#;
+    let err = resolve_next_run("cron", config, Some(0)).unwrap_err();
+    assert!(err.contains("Unknown timezone"), "{err}");
+}
+
+#[test]
+fn completion_trigger_filters_on_outcome() {
+    let on_success = CompletionTrigger::parse(r#"{"workflow_id": 7}"#).unwrap();
+    assert!(on_success.fires_on(7, true));
+    assert!(!on_success.fires_on(7, false));
+    assert!(!on_success.fires_on(8, true));
+
+    let on_failure = CompletionTrigger::parse(r#"{"workflow_id": 7, "on": "failure"}"#).unwrap();
+    assert!(on_failure.fires_on(7, false));
+    assert!(!on_failure.fires_on(7, true));
+
+    let on_any = CompletionTrigger::parse(r#"{"workflow_id": 7, "on": "any"}"#).unwrap();
+    assert!(on_any.fires_on(7, true) && on_any.fires_on(7, false));
+}
+
+#[test]
+fn completion_trigger_config_is_validated() {
+    validate_trigger_config(WORKFLOW_COMPLETE_TRIGGER, r#"{"workflow_id": 7}"#, 8).unwrap();
+    let err =
+        validate_trigger_config(WORKFLOW_COMPLETE_TRIGGER, r#"{"workflow_id": 8}"#, 8).unwrap_err();
+    assert!(err.contains("its own completion"), "{err}");
+    assert!(
+        validate_trigger_config(
+            WORKFLOW_COMPLETE_TRIGGER,
+            r#"{"workflow_id": 7, "on": "done"}"#,
+            8
+        )
+        .is_err()
+    );
+    assert!(validate_trigger_config(WORKFLOW_COMPLETE_TRIGGER, "", 8).is_err());
+    // A completion-triggered workflow has no schedule of its own.
+    assert_eq!(
+        resolve_next_run(WORKFLOW_COMPLETE_TRIGGER, r#"{"workflow_id": 7}"#, Some(0)).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn ephemeral_config_only_applies_when_flagged() {
+    assert!(ephemeral_sandbox_config("").unwrap().is_none());
+    assert!(ephemeral_sandbox_config("{}").unwrap().is_none());
+    assert!(
+        ephemeral_sandbox_config(r#"{"image": "x"}"#)
+            .unwrap()
+            .is_none()
+    );
+    assert!(ephemeral_sandbox_config("not json").unwrap().is_none());
+    assert!(ephemeral_sandbox_config(r#"{"ephemeral": true, "cpu_cores": "two"}"#).is_err());
+
+    let config = ephemeral_sandbox_config(
+        r#"{"ephemeral": true, "agent_identifier": "default", "env": {"ANTHROPIC_API_KEY": "sk"}, "memory_mb": 2048}"#,
+    )
+    .unwrap()
+    .unwrap();
+    let params = config.create_params(9, "0xabc", Some(3)).unwrap();
+    assert!(params.name.starts_with("wf-9-"));
+    assert_eq!(params.owner, "0xabc");
+    assert_eq!(params.service_id, Some(3));
+    assert_eq!(params.memory_mb, 2048);
+    assert_eq!(
+        params.max_lifetime_seconds,
+        EPHEMERAL_DEFAULT_MAX_LIFETIME_SECS
+    );
+    assert!(params.env_json.contains("ANTHROPIC_API_KEY"));
+}
+
+#[test]
+fn ephemeral_workflow_needs_agent_and_credentials() {
+    let task = r#"{"prompt": "summarize"}"#;
+    let config = |json: &str| ephemeral_sandbox_config(json).unwrap().unwrap();
+
+    validate_ephemeral_workflow(
+        &config(r#"{"ephemeral": true, "agent_identifier": "default", "env": {"ANTHROPIC_API_KEY": "sk"}}"#),
+        task,
+    )
+    .unwrap();
+    let err = validate_ephemeral_workflow(
+        &config(r#"{"ephemeral": true, "env": {"ANTHROPIC_API_KEY": "sk"}}"#),
+        task,
+    )
+    .unwrap_err();
+    assert!(err.contains("agent_identifier"), "{err}");
+    let err = validate_ephemeral_workflow(
+        &config(r#"{"ephemeral": true, "agent_identifier": "default"}"#),
+        task,
+    )
+    .unwrap_err();
+    assert!(err.contains("credentials"), "{err}");
+}
+
+#[test]
+fn artifact_paths_and_urls() {
+    assert_eq!(
+        resolve_artifact_path("out/report.md"),
+        "/home/agent/out/report.md"
+    );
+    assert_eq!(resolve_artifact_path("/tmp/data"), "/tmp/data");
+    assert_eq!(
+        artifact_url(
+            "s3://bucket/runs",
+            4,
+            1_700_000_000,
+            1,
+            "/home/agent/out dir/"
+        ),
+        "s3://bucket/runs/workflow-4/1700000000/1-out_dir.tar.gz"
+    );
+    assert_eq!(
+        artifact_url("s3://bucket/", 4, 5, 0, "/home/agent/report.md"),
+        "s3://bucket/workflow-4/5/0-report.md.tar.gz"
+    );
+}
+
+#[test]
+fn workflow_artifacts_are_validated_at_creation() {
+    let spec = |json: &str| parse_workflow_task_spec(json).unwrap();
+    validate_workflow_artifacts(&spec(r#"{"prompt": "p"}"#)).unwrap();
+    validate_workflow_artifacts(&spec(
+        r#"{"prompt": "p", "artifacts": ["out/report.md"], "artifact_destination": "s3://bucket/wf"}"#,
+    ))
+    .unwrap();
+    assert!(
+        validate_workflow_artifacts(&spec(
+            r#"{"prompt": "p", "artifacts": ["../../etc"], "artifact_destination": "s3://bucket/wf"}"#,
+        ))
+        .is_err()
+    );
+    assert!(
+        validate_workflow_artifacts(&spec(
+            r#"{"prompt": "p", "artifacts": ["out"], "artifact_destination": "http://bucket/wf"}"#,
+        ))
+        .is_err()
+    );
+}
+
+#[test]
+fn secret_placeholders_parse_as_empty_strings() {
+    let spec = parse_workflow_task_spec(
+        r#"{"prompt": "post the report", "context_json": {"secretRef": "api-key"}, "sidecar_token": {"secretRef": "aws-sm://prod/token"}}"#,
+    )
+    .unwrap();
+    assert_eq!(spec.prompt, "post the report");
+    assert_eq!(spec.context_json.as_deref(), Some(""));
+    assert_eq!(spec.sidecar_token.as_deref(), Some(""));
+}
+
+#[tokio::test]
+async fn run_timeout_fails_slow_runs_only() {
+    let slow = async {
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        Ok::<_, String>(())
+    };
+    let err = with_timeout(Some(std::time::Duration::from_millis(10)), slow)
+        .await
+        .unwrap_err();
+    assert!(err.contains("timed out"), "{err}");
+
+    let fast = async { Ok::<_, String>(7) };
+    assert_eq!(
+        with_timeout(Some(std::time::Duration::from_secs(5)), fast).await,
+        Ok(7)
+    );
+    assert_eq!(
+        with_timeout(None, async { Ok::<_, String>(1) }).await,
+        Ok(1)
+    );
+}
+
+#[test]
+fn incremental_sync_skips_known_unchanged_workflows() {
+    // First sync (no watermark) applies everything.
+    assert!(workflow_needs_sync(5, None, true));
+    assert!(!workflow_needs_sync(5, Some(10), true));
+    assert!(!workflow_needs_sync(10, Some(10), true));
+    assert!(workflow_needs_sync(11, Some(10), true));
+    // A workflow missing locally is always (re)applied.
+    assert!(workflow_needs_sync(5, Some(10), false));
+}
+
+#[test]
+fn workflow_updated_at_reads_tuple_field() {
+    let mut fields = vec![DynSolValue::String(String::new()); 5];
+    fields.push(DynSolValue::Uint(U256::from(0u8), 8));
+    fields.push(DynSolValue::String(String::new()));
+    fields.push(DynSolValue::Uint(U256::from(0u64), 64));
+    fields.push(DynSolValue::Bool(true));
+    fields.push(DynSolValue::Uint(U256::from(100u64), 64));
+    fields.push(DynSolValue::Uint(U256::from(250u64), 64));
+    fields.push(DynSolValue::Uint(U256::from(0u64), 64));
+    assert_eq!(
+        parse_workflow_updated_at(&[DynSolValue::Tuple(fields.clone())]).unwrap(),
+        250
+    );
+
+    fields.pop();
+    assert!(parse_workflow_updated_at(&[DynSolValue::Tuple(fields)]).is_err());
+    assert!(parse_workflow_updated_at(&[]).is_err());
+}
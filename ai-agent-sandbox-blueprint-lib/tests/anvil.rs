@@ -73,6 +73,7 @@ async fn runs_sandbox_jobs_end_to_end() -> Result<()> {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
         }
         .abi_encode();
 
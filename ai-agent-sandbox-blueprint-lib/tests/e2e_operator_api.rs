@@ -140,6 +140,7 @@ async fn sandbox_full_lifecycle() -> Result<()> {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
         }
         .abi_encode();
 
@@ -834,6 +835,7 @@ async fn workflow_create_and_cancel() -> Result<()> {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
         }
         .abi_encode();
 
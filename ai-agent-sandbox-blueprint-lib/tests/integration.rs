@@ -13,7 +13,7 @@
 use ai_agent_sandbox_blueprint_lib::http::sidecar_post_json;
 use ai_agent_sandbox_blueprint_lib::jobs::exec::run_task_request;
 use ai_agent_sandbox_blueprint_lib::jobs::exec::{
-    extract_exec_fields, run_exec_request, run_prompt_request,
+    extract_exec_fields, extract_exec_fields_for, run_exec_request, run_prompt_request,
 };
 use ai_agent_sandbox_blueprint_lib::jobs::ssh::{provision_key, revoke_key};
 use ai_agent_sandbox_blueprint_lib::runtime::{
@@ -32,7 +32,7 @@ use blueprint_sdk::alloy::sol_types::SolValue;
 use serde_json::{Value, json};
 use std::sync::Once;
 use std::sync::atomic::{AtomicU64, Ordering};
-use wiremock::matchers::{header, method, path};
+use wiremock::matchers::{body_string_contains, header, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 use sandbox_runtime::provision_progress::{self, ProvisionPhase};
@@ -105,6 +105,11 @@ fn insert_sandbox(url: &str, token: &str) -> String {
                 ssh_login_user: None,
                 ssh_authorized_keys: Vec::new(),
                 capabilities_json: String::new(),
+                sidecar_api: None,
+                image_digest: None,
+                crash_history: Vec::new(),
+                lifecycle_events: Vec::new(),
+                clone_image: None,
             },
         )
         .unwrap();
@@ -156,6 +161,11 @@ fn insert_ssh_sandbox(url: &str, token: &str) -> String {
                 ssh_login_user: None,
                 ssh_authorized_keys: Vec::new(),
                 capabilities_json: String::new(),
+                sidecar_api: None,
+                image_digest: None,
+                crash_history: Vec::new(),
+                lifecycle_events: Vec::new(),
+                clone_image: None,
             },
         )
         .unwrap();
@@ -207,6 +217,11 @@ fn insert_sandbox_with_owner(url: &str, token: &str, owner: &str) -> String {
                 ssh_login_user: None,
                 ssh_authorized_keys: Vec::new(),
                 capabilities_json: String::new(),
+                sidecar_api: None,
+                image_digest: None,
+                crash_history: Vec::new(),
+                lifecycle_events: Vec::new(),
+                clone_image: None,
             },
         )
         .unwrap();
@@ -248,6 +263,9 @@ fn task_req(url: &str, prompt: &str) -> SandboxTaskRequest {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 0,
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
     }
 }
 
@@ -404,22 +422,44 @@ mod ownership_enforcement {
     }
 
     #[test]
-    fn batch_collect_no_ownership_field() {
+    fn batch_collect_requires_owner() {
         init();
         let batch_id = format!("batch-own-{}", uid());
         let record = BatchRecord {
             id: batch_id.clone(),
             kind: "task".into(),
+            owner: "0xAaAa".into(),
             results: json!([{"success": true}]),
             created_at: now_ts(),
         };
         batches().unwrap().insert(batch_id.clone(), record).unwrap();
 
-        // BatchRecord has no owner field — any caller can retrieve results.
-        // This documents the design gap identified in issue #22.
-        let stored = batches().unwrap().get(&batch_id).unwrap().unwrap();
-        assert_eq!(stored.kind, "task");
+        let err = jobs::batch::take_batch(&batch_id, "0xbbbb").unwrap_err();
+        assert!(err.contains("does not own"), "{err}");
+        assert!(
+            batches().unwrap().get(&batch_id).unwrap().is_some(),
+            "a rejected collect must not consume the batch"
+        );
 
+        let collected = jobs::batch::take_batch(&batch_id, "0xaaaa").unwrap();
+        assert_eq!(collected.kind, "task");
+        assert!(batches().unwrap().get(&batch_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn batch_without_owner_is_not_collectable() {
+        init();
+        let batch_id = format!("batch-legacy-{}", uid());
+        let legacy: BatchRecord = serde_json::from_value(json!({
+            "id": batch_id,
+            "kind": "exec",
+            "results": [],
+            "created_at": now_ts(),
+        }))
+        .unwrap();
+        assert!(legacy.owner.is_empty());
+        batches().unwrap().insert(batch_id.clone(), legacy).unwrap();
+        assert!(jobs::batch::take_batch(&batch_id, "").is_err());
         batches().unwrap().remove(&batch_id).unwrap();
     }
 }
@@ -447,6 +487,8 @@ mod exec_job {
             cwd: "/app".into(),
             env_json: r#"{"FOO":"bar"}"#.into(),
             timeout_ms: 5000,
+            stdin: String::new(),
+            stdin_file: String::new(),
         };
         let resp = run_exec_request(&req, "t").await.unwrap();
         assert_eq!(resp.exit_code, 0);
@@ -473,6 +515,21 @@ mod exec_job {
         assert!(err.is_empty());
     }
 
+    #[test]
+    fn extract_exec_fields_follows_negotiated_api() {
+        use sandbox_runtime::sidecar_compat::SidecarApi;
+
+        let v2 = json!({
+            "success": true,
+            "data": {"exitCode": 7, "stdout": "v2", "stderr": ""}
+        });
+        let (code, out, _) = extract_exec_fields_for(SidecarApi::V2, &v2);
+        assert_eq!(code, 7);
+        assert_eq!(out, "v2");
+        // The API 1 adapter doesn't read the API 2 envelope.
+        assert_eq!(extract_exec_fields(&v2).0, 0);
+    }
+
     #[tokio::test]
     async fn payload_includes_cwd_env_timeout() {
         let srv = MockServer::start().await;
@@ -489,6 +546,8 @@ mod exec_job {
             cwd: "/workspace".into(),
             env_json: r#"{"NODE_ENV":"test"}"#.into(),
             timeout_ms: 3000,
+            stdin: String::new(),
+            stdin_file: String::new(),
         };
         run_exec_request(&req, "t").await.unwrap();
     }
@@ -509,6 +568,8 @@ mod exec_job {
             cwd: String::new(),
             env_json: String::new(),
             timeout_ms: 0,
+            stdin: String::new(),
+            stdin_file: String::new(),
         };
         run_exec_request(&req, "t").await.unwrap();
     }
@@ -542,6 +603,7 @@ mod prompt_job {
             model: "claude-4".into(),
             context_json: r#"{"key":"val"}"#.into(),
             timeout_ms: 10000,
+            agent_identifier: String::new(),
         };
         let resp = run_prompt_request(&req, "t").await.unwrap();
         assert!(resp.success);
@@ -577,12 +639,90 @@ mod prompt_job {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            agent_identifier: String::new(),
         };
         let resp = run_prompt_request(&req, "t").await.unwrap();
         assert!(!resp.success);
         assert_eq!(resp.error, "rate limited");
         assert!(m.failed_jobs.load(Ordering::Relaxed) > before);
     }
+
+    #[tokio::test]
+    async fn agent_identifier_from_record_or_request() {
+        let srv = MockServer::start().await;
+        let id = insert_sandbox(&srv.uri(), "t");
+        sandboxes()
+            .unwrap()
+            .update(&id, |r| r.agent_identifier = "researcher".into())
+            .unwrap();
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .and(body_string_contains(r#""identifier":"researcher""#))
+            .respond_with(mock_agent_ok("from-record"))
+            .expect(1)
+            .mount(&srv)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .and(body_string_contains(r#""identifier":"coder""#))
+            .respond_with(mock_agent_ok("from-request"))
+            .expect(1)
+            .mount(&srv)
+            .await;
+
+        let mut req = SandboxPromptRequest {
+            sidecar_url: srv.uri(),
+            message: "go".into(),
+            session_id: String::new(),
+            model: String::new(),
+            context_json: String::new(),
+            timeout_ms: 0,
+            agent_identifier: String::new(),
+        };
+        let resp = run_prompt_request(&req, "t").await.unwrap();
+        assert_eq!(resp.response, "from-record");
+
+        req.agent_identifier = "coder".into();
+        let resp = run_prompt_request(&req, "t").await.unwrap();
+        assert_eq!(resp.response, "from-request");
+    }
+
+    #[tokio::test]
+    async fn prompt_template_is_applied() {
+        use sandbox_runtime::prompt_templates::{PromptTemplateInput, put_prompt_template};
+
+        let srv = MockServer::start().await;
+        let id = insert_sandbox(&srv.uri(), "t");
+        let template = PromptTemplateInput {
+            system_prompt: "You are {{persona}}.".into(),
+            prefix: "Repo: {{repo}}".into(),
+            variables: [("persona".to_string(), "a reviewer".to_string())].into(),
+            ..Default::default()
+        };
+        put_prompt_template(&id, "review", template, "0xowner").unwrap();
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .and(body_string_contains(r#""message":"Repo: core\n\ngo""#))
+            .and(body_string_contains(
+                r#""systemPrompt":"You are a reviewer.""#,
+            ))
+            .respond_with(mock_agent_ok("templated"))
+            .expect(1)
+            .mount(&srv)
+            .await;
+
+        let req = SandboxPromptRequest {
+            sidecar_url: srv.uri(),
+            message: "go".into(),
+            session_id: String::new(),
+            model: String::new(),
+            context_json: r#"{"promptTemplate":"review","promptVariables":{"repo":"core"}}"#.into(),
+            timeout_ms: 0,
+            agent_identifier: String::new(),
+        };
+        let resp = run_prompt_request(&req, "t").await.unwrap();
+        assert_eq!(resp.response, "templated");
+    }
 }
 
 // ─── JOB 12: sandbox_task (via run_task_request) ─────────────────────────────
@@ -628,10 +768,100 @@ mod task_job {
             model: "claude".into(),
             context_json: r#"{"project":"x"}"#.into(),
             timeout_ms: 30000,
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
         };
         let resp = run_task_request(&req, "t").await.unwrap();
         assert!(resp.success);
     }
+
+    #[tokio::test]
+    async fn output_schema_repairs_once_then_returns_json() {
+        let srv = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .and(body_string_contains("did not conform"))
+            .respond_with(mock_agent_ok("```json\n{\"ok\": true}\n```"))
+            .expect(1)
+            .mount(&srv)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .and(body_string_contains("outputSchema"))
+            .respond_with(mock_agent_ok("sure, it is ok"))
+            .expect(1)
+            .mount(&srv)
+            .await;
+
+        let mut req = task_req(&srv.uri(), "check");
+        req.output_schema_json =
+            r#"{"type":"object","required":["ok"],"properties":{"ok":{"type":"boolean"}}}"#.into();
+        let resp = run_task_request(&req, "t").await.unwrap();
+        assert!(resp.success, "{}", resp.error);
+        assert_eq!(resp.result, r#"{"ok":true}"#);
+    }
+
+    #[tokio::test]
+    async fn output_schema_mismatch_marks_failure() {
+        let srv = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .respond_with(mock_agent_ok("not json"))
+            .expect(2)
+            .mount(&srv)
+            .await;
+
+        let mut req = task_req(&srv.uri(), "check");
+        req.output_schema_json = r#"{"type":"object"}"#.into();
+        let resp = run_task_request(&req, "t").await.unwrap();
+        assert!(!resp.success);
+        assert!(resp.error.contains("output_schema_json"));
+
+        req.output_schema_json = "[]".into();
+        assert!(run_task_request(&req, "t").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn tools_policy_is_forwarded_and_echoed() {
+        let srv = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .and(body_string_contains(r#""tools":{"allow":["Read"]"#))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true, "response": "done", "sessionId": "s-1",
+                "metadata": {"harness": "claude", "tools": {"allow": ["Read"], "deny": []}}
+            })))
+            .expect(1)
+            .mount(&srv)
+            .await;
+
+        let mut req = task_req(&srv.uri(), "read only");
+        req.tools_json = r#"{"allow":["Read"]}"#.into();
+        let resp = run_task_request(&req, "t").await.unwrap();
+        assert!(resp.success, "{}", resp.error);
+        assert_eq!(resp.tools_json, r#"{"allow":["Read"],"deny":[]}"#);
+    }
+
+    #[tokio::test]
+    async fn tools_policy_without_echo_fails() {
+        let srv = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/agents/run"))
+            .respond_with(mock_agent_ok("unrestricted"))
+            .mount(&srv)
+            .await;
+
+        let mut req = task_req(&srv.uri(), "read only");
+        req.tools_json = r#"{"allow":["Read"],"network":false}"#.into();
+        let resp = run_task_request(&req, "t").await.unwrap();
+        assert!(!resp.success);
+        assert!(resp.error.contains("tools_json"));
+        assert!(resp.result.is_empty());
+
+        req.tools_json = r#"{"allow":["curl evil"]}"#.into();
+        assert!(run_task_request(&req, "t").await.is_err());
+    }
 }
 
 // ─── JOB 4: sandbox_snapshot (build_snapshot_command) ────────────────────────
@@ -784,6 +1014,7 @@ mod batch_jobs {
         let record = BatchRecord {
             id: batch_id.clone(),
             kind: "task".into(),
+            owner: "0xowner".into(),
             results: json!([{"success": true, "result": "done"}]),
             created_at: now_ts(),
         };
@@ -1402,6 +1633,9 @@ mod response_parsing {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
         };
         let resp = run_task_request(&req, "t").await.unwrap();
         assert_eq!(resp.session_id, "from-meta");
@@ -1430,6 +1664,9 @@ mod response_parsing {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
         };
         let resp = run_task_request(&req, "t").await.unwrap();
         assert_eq!(resp.session_id, "req-session");
@@ -1463,6 +1700,7 @@ mod abi {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
         };
         let d = SandboxCreateRequest::abi_decode(&req.abi_encode()).unwrap();
         assert_eq!(d.name, "t");
@@ -1485,6 +1723,8 @@ mod abi {
             cwd: "/w".into(),
             env_json: "{}".into(),
             timeout_ms: 5000,
+            stdin: String::new(),
+            stdin_file: String::new(),
         };
         let d = SandboxExecRequest::abi_decode(&exec.abi_encode()).unwrap();
         assert_eq!(d.command, "ls");
@@ -1494,6 +1734,7 @@ mod abi {
             exit_code: 1,
             stdout: "out".into(),
             stderr: "err".into(),
+            overflow_ref: String::new(),
         };
         let d = SandboxExecResponse::abi_decode(&exec_r.abi_encode()).unwrap();
         assert_eq!(d.exit_code, 1);
@@ -1505,6 +1746,7 @@ mod abi {
             model: "m".into(),
             context_json: "{}".into(),
             timeout_ms: 1000,
+            agent_identifier: String::new(),
         };
         let d = SandboxPromptRequest::abi_decode(&prompt.abi_encode()).unwrap();
         assert_eq!(d.message, "hi");
@@ -1530,6 +1772,9 @@ mod abi {
             model: "claude".into(),
             context_json: "{}".into(),
             timeout_ms: 60000,
+            output_schema_json: String::new(),
+            tools_json: String::new(),
+            agent_identifier: String::new(),
         };
         let d = SandboxTaskRequest::abi_decode(&task.abi_encode()).unwrap();
         assert_eq!(d.prompt, "build");
@@ -1544,6 +1789,7 @@ mod abi {
             input_tokens: 2000,
             output_tokens: 800,
             session_id: "sx".into(),
+            tools_json: String::new(),
         };
         let d = SandboxTaskResponse::abi_decode(&task_r.abi_encode()).unwrap();
         assert_eq!(d.duration_ms, 15000);
@@ -1599,6 +1845,7 @@ mod abi {
                 tee_type: 0,
                 attestation_nonce: String::new(),
                 capabilities_json: String::new(),
+                template: String::new(),
             },
             operators: vec![Address::ZERO],
             distribution: "round-robin".into(),
@@ -1624,9 +1871,11 @@ mod abi {
             sidecar_url: "http://h".into(),
             username: "dev".into(),
             public_key: "ssh-ed25519 AAAA".into(),
+            expires_in_seconds: 3600,
         };
         let d = SshProvisionRequest::abi_decode(&ssh.abi_encode()).unwrap();
         assert_eq!(d.username, "dev");
+        assert_eq!(d.expires_in_seconds, 3600);
 
         let ssh_r = SshRevokeRequest {
             sidecar_url: "http://h".into(),
@@ -1664,6 +1913,7 @@ mod abi {
             tee_type,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
         }
     }
 
@@ -1709,6 +1959,68 @@ mod abi {
     }
 }
 
+// ─── Read-only Jobs ─────────────────────────────────────────────────────────
+
+mod read_jobs {
+    use super::*;
+    use ai_agent_sandbox_blueprint_lib::jobs::read::{
+        run_read_sandbox_get, run_read_sandbox_list, run_read_usage,
+    };
+
+    #[test]
+    fn read_family_is_marked() {
+        assert!(is_read_only_job(JOB_READ_SANDBOX_LIST));
+        assert!(is_read_only_job(JOB_READ_USAGE));
+        assert!(!is_read_only_job(JOB_SANDBOX_CREATE));
+        assert!(!is_read_only_job(JOB_WORKFLOW_TICK));
+    }
+
+    #[test]
+    fn list_shows_only_the_callers_sandboxes_without_secrets() {
+        let owner = format!("0x{}", uid());
+        let mine = insert_sandbox_with_owner("http://read-mine:8080", "tok-mine", &owner);
+        let other = insert_sandbox_with_owner("http://read-other:8080", "tok-other", "0xbbbb");
+
+        let listed: Value =
+            serde_json::from_str(&run_read_sandbox_list(&owner, 0, 0).unwrap()).unwrap();
+        assert_eq!(listed["total"], 1);
+        assert_eq!(listed["sandboxes"][0]["sandboxId"], mine.as_str());
+        let raw = listed.to_string();
+        assert!(!raw.contains("tok-mine"));
+        assert!(!raw.contains("read-mine"));
+
+        let past_end: Value =
+            serde_json::from_str(&run_read_sandbox_list(&owner, 1, 10).unwrap()).unwrap();
+        assert_eq!(past_end["total"], 1);
+        assert_eq!(past_end["sandboxes"].as_array().unwrap().len(), 0);
+
+        rm(&mine);
+        rm(&other);
+    }
+
+    #[test]
+    fn get_is_owner_only() {
+        let owner = format!("0x{}", uid());
+        let id = insert_sandbox_with_owner("http://read-get:8080", "tok-get", &owner);
+        let view: Value =
+            serde_json::from_str(&run_read_sandbox_get(&owner, &id).unwrap()).unwrap();
+        assert_eq!(view["sandboxId"], id.as_str());
+        assert_eq!(view["cpuCores"], 2);
+        assert!(run_read_sandbox_get("0xcccc", &id).is_err());
+        rm(&id);
+    }
+
+    #[test]
+    fn usage_counts_the_callers_sandboxes() {
+        let owner = format!("0x{}", uid());
+        let id = insert_sandbox_with_owner("http://read-usage:8080", "tok-usage", &owner);
+        let usage: Value = serde_json::from_str(&run_read_usage(&owner).unwrap()).unwrap();
+        assert_eq!(usage["usage"]["sandboxes"], 1);
+        assert_eq!(usage["usage"]["cpu_cores"], 2);
+        rm(&id);
+    }
+}
+
 // ─── Provision Progress ─────────────────────────────────────────────────────
 
 mod provision_progress_tests {
@@ -1847,6 +2159,8 @@ mod errors {
             cwd: String::new(),
             env_json: String::new(),
             timeout_ms: 0,
+            stdin: String::new(),
+            stdin_file: String::new(),
         };
         assert!(run_exec_request(&req, "t").await.is_err());
     }
@@ -1881,6 +2195,7 @@ mod errors {
             model: String::new(),
             context_json: String::new(),
             timeout_ms: 0,
+            agent_identifier: String::new(),
         };
         assert!(run_prompt_request(&req, "t").await.is_err());
     }
@@ -1960,6 +2275,7 @@ mod docker {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
         };
 
         let record = match create_sidecar(&CreateSandboxParams::from(&request), None).await {
@@ -2042,6 +2358,7 @@ mod docker {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
         };
 
         let record = match create_sidecar(&CreateSandboxParams::from(&request), None).await {
@@ -2106,6 +2423,7 @@ mod docker {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
         };
 
         let record = match create_sidecar(&CreateSandboxParams::from(&request), None).await {
@@ -2208,6 +2526,7 @@ mod docker {
             tee_type: 0,
             attestation_nonce: String::new(),
             capabilities_json: String::new(),
+            template: String::new(),
         };
 
         let record = match create_sidecar(&CreateSandboxParams::from(&request), None).await {
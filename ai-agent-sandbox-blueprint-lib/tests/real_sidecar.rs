@@ -674,6 +674,8 @@ async fn blueprint_run_exec_with_cwd_and_env() {
         cwd: "/tmp".to_string(),
         env_json: r#"{"MY_VAR": "test123"}"#.to_string(),
         timeout_ms: 15000,
+        stdin: String::new(),
+        stdin_file: String::new(),
     };
 
     let result = ai_agent_sandbox_blueprint_lib::run_exec_request(&request, AUTH_TOKEN).await;
@@ -699,6 +701,8 @@ async fn blueprint_run_exec_request_works_against_real_sidecar() {
         cwd: String::new(),
         env_json: String::new(),
         timeout_ms: 15000,
+        stdin: String::new(),
+        stdin_file: String::new(),
     };
 
     let result = ai_agent_sandbox_blueprint_lib::run_exec_request(&request, AUTH_TOKEN).await;
@@ -718,6 +722,39 @@ async fn blueprint_run_exec_request_works_against_real_sidecar() {
     }
 }
 
+/// `stdin` is piped to the command and `stdin_file` reads a sandbox file.
+#[tokio::test]
+async fn blueprint_run_exec_request_pipes_stdin() {
+    skip_unless_real!();
+    let s = ensure_sidecar().await;
+
+    let mut request = SandboxExecRequest {
+        sidecar_url: s.url.clone(),
+        command: "tr a-z A-Z".to_string(),
+        cwd: String::new(),
+        env_json: String::new(),
+        timeout_ms: 15000,
+        stdin: "piped-input".to_string(),
+        stdin_file: String::new(),
+    };
+    let resp = ai_agent_sandbox_blueprint_lib::run_exec_request(&request, AUTH_TOKEN)
+        .await
+        .expect("exec with stdin");
+    assert_eq!(resp.stdout.trim(), "PIPED-INPUT");
+
+    request.command = "echo from-file > /tmp/stdin-test.txt".to_string();
+    request.stdin = String::new();
+    ai_agent_sandbox_blueprint_lib::run_exec_request(&request, AUTH_TOKEN)
+        .await
+        .expect("write input file");
+    request.command = "cat".to_string();
+    request.stdin_file = "/tmp/stdin-test.txt".to_string();
+    let resp = ai_agent_sandbox_blueprint_lib::run_exec_request(&request, AUTH_TOKEN)
+        .await
+        .expect("exec with stdin_file");
+    assert_eq!(resp.stdout.trim(), "from-file");
+}
+
 /// Verify non-zero exit codes propagate through run_exec_request.
 #[tokio::test]
 async fn blueprint_run_exec_captures_exit_code() {
@@ -730,6 +767,8 @@ async fn blueprint_run_exec_captures_exit_code() {
         cwd: String::new(),
         env_json: String::new(),
         timeout_ms: 15000,
+        stdin: String::new(),
+        stdin_file: String::new(),
     };
 
     let result = ai_agent_sandbox_blueprint_lib::run_exec_request(&request, AUTH_TOKEN).await;
@@ -899,6 +938,7 @@ async fn blueprint_run_prompt_reaches_real_sidecar() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: timeout,
+        agent_identifier: String::new(),
     };
 
     let result = ai_agent_sandbox_blueprint_lib::run_prompt_request(&request, AUTH_TOKEN).await;
@@ -1118,6 +1158,9 @@ async fn blueprint_run_task_request_reaches_real_sidecar() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: timeout,
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
     };
 
     let result = ai_agent_sandbox_blueprint_lib::run_task_request(&request, AUTH_TOKEN).await;
@@ -1593,6 +1636,7 @@ async fn ai_agent_prompt_returns_real_response() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 60000,
+        agent_identifier: String::new(),
     };
 
     let result = ai_agent_sandbox_blueprint_lib::run_prompt_request(&request, AUTH_TOKEN)
@@ -1630,6 +1674,9 @@ async fn ai_agent_task_with_session_continuity() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 60000,
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
     };
 
     let result1 = ai_agent_sandbox_blueprint_lib::run_task_request(&request1, AUTH_TOKEN)
@@ -1661,6 +1708,9 @@ async fn ai_agent_task_with_session_continuity() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 60000,
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
     };
 
     let result2 = ai_agent_sandbox_blueprint_lib::run_task_request(&request2, AUTH_TOKEN)
@@ -1702,6 +1752,9 @@ async fn ai_agent_task_with_max_turns() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 60000,
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
     };
 
     let result = ai_agent_sandbox_blueprint_lib::run_task_request(&request, AUTH_TOKEN)
@@ -1801,6 +1854,9 @@ async fn ai_agent_writes_and_runs_python_script() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 240000,
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
     };
 
     let result = match ai_agent_sandbox_blueprint_lib::run_task_request(&request, AUTH_TOKEN).await
@@ -1884,6 +1940,9 @@ Install pandas with pip first if needed."#;
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 240000,
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
     };
 
     let result = match ai_agent_sandbox_blueprint_lib::run_task_request(&request, AUTH_TOKEN).await
@@ -2038,6 +2097,9 @@ async fn ai_agent_full_workflow_install_code_execute() {
         model: String::new(),
         context_json: String::new(),
         timeout_ms: 240000,
+        output_schema_json: String::new(),
+        tools_json: String::new(),
+        agent_identifier: String::new(),
     };
 
     let result = match ai_agent_sandbox_blueprint_lib::run_task_request(&request, AUTH_TOKEN).await
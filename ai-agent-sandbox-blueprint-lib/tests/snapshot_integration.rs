@@ -5,6 +5,7 @@
 //! - S3 upload/download via MinIO (cold tier)
 //! - Tiered GC transitions: Hot → Warm → Cold → Gone
 //! - Resume from each tier
+//! - Cloning a warm-tier sandbox without its secrets
 //! - User BYOS3 preservation
 //!
 //! Run:
@@ -19,11 +20,12 @@ use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use ai_agent_sandbox_blueprint_lib::runtime::{
-    SandboxRecord, SandboxState, commit_container, create_sidecar, delete_sidecar, docker_builder,
-    remove_snapshot_image, resume_sidecar, sandboxes, stop_sidecar,
+    CloneSandboxOverrides, SandboxRecord, SandboxState, clone_sidecar, commit_container,
+    create_sidecar, delete_sidecar, docker_builder, remove_snapshot_image, resume_sidecar,
+    sandboxes, stop_sidecar,
 };
 use ai_agent_sandbox_blueprint_lib::{CreateSandboxParams, SandboxCreateRequest};
-use docktopus::bollard::container::RemoveContainerOptions;
+use docktopus::bollard::container::{InspectContainerOptions, RemoveContainerOptions};
 use reqwest::Client;
 
 // ---------------------------------------------------------------------------
@@ -160,6 +162,7 @@ async fn create_test_sandbox() -> SandboxRecord {
         tee_type: 0,
         attestation_nonce: String::new(),
         capabilities_json: String::new(),
+        template: String::new(),
     };
     create_sidecar(&CreateSandboxParams::from(&request), None)
         .await
@@ -189,6 +192,7 @@ async fn create_test_sandbox_with_destination(dest: &str) -> SandboxRecord {
         tee_type: 0,
         attestation_nonce: String::new(),
         capabilities_json: String::new(),
+        template: String::new(),
     };
     create_sidecar(&CreateSandboxParams::from(&request), None)
         .await
@@ -824,6 +828,11 @@ async fn tiered_gc_cold_to_gone_real() {
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     };
 
     sandboxes()
@@ -927,6 +936,11 @@ async fn user_byos3_never_deleted_by_gc() {
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     };
 
     sandboxes()
@@ -1256,3 +1270,96 @@ async fn full_lifecycle_all_tiers() {
     eprintln!("Phase 6: GC to gone OK");
     eprintln!("=== FULL LIFECYCLE TEST PASSED ===");
 }
+
+// ===================================================================
+// Test 10: clone_from_snapshot_drops_source_secrets_real
+// ===================================================================
+
+#[tokio::test]
+async fn clone_from_snapshot_drops_source_secrets_real() {
+    skip_unless_snapshot!();
+    setup_test_env();
+    if !docker_ok().await {
+        eprintln!("Skipped (Docker not available)");
+        return;
+    }
+
+    let request = SandboxCreateRequest {
+        name: "snapshot-clone-test".to_string(),
+        image: String::new(),
+        stack: String::new(),
+        agent_identifier: String::new(),
+        env_json: String::new(),
+        metadata_json: String::new(),
+        ssh_enabled: false,
+        ssh_public_key: String::new(),
+        web_terminal_enabled: false,
+        max_lifetime_seconds: 3600,
+        idle_timeout_seconds: 3600,
+        cpu_cores: 0,
+        memory_mb: 0,
+        disk_gb: 0,
+        tee_required: false,
+        tee_type: 0,
+        attestation_nonce: String::new(),
+        capabilities_json: String::new(),
+        template: String::new(),
+    };
+    let mut params = CreateSandboxParams::from(&request);
+    params.user_env_json = r#"{"SOURCE_SECRET":"snapshot-secret-value"}"#.to_string();
+    let (record, _) = create_sidecar(&params, None)
+        .await
+        .expect("Failed to create source sandbox");
+    wait_healthy(&record.sidecar_url, 60).await;
+
+    // Move the source to the warm tier: snapshot image, no container.
+    stop_sidecar(&record).await.expect("stop should succeed");
+    let image_id = commit_container(&record)
+        .await
+        .expect("commit should succeed");
+    delete_sidecar(&record, None)
+        .await
+        .expect("delete should succeed");
+    sandboxes()
+        .unwrap()
+        .update(&record.id, |r| {
+            r.snapshot_image_id = Some(image_id.clone());
+            r.container_removed_at = Some(ai_agent_sandbox_blueprint_lib::util::now_ts());
+        })
+        .unwrap();
+    let source = sandboxes().unwrap().get(&record.id).unwrap().unwrap();
+
+    let clone = clone_sidecar(&source, &CloneSandboxOverrides::default(), None)
+        .await
+        .expect("clone from snapshot should succeed");
+    assert!(
+        clone.clone_image.is_some(),
+        "clone should own a scrubbed image"
+    );
+
+    let builder = docker_builder().await.unwrap();
+    let env = builder
+        .client()
+        .inspect_container(&clone.container_id, None::<InspectContainerOptions>)
+        .await
+        .expect("inspect clone")
+        .config
+        .and_then(|c| c.env)
+        .unwrap_or_default();
+    assert!(
+        !env.iter().any(|e| e.contains("snapshot-secret-value")),
+        "clone env must not carry the source's user secrets: {env:?}"
+    );
+    assert!(
+        !env.contains(&format!("SIDECAR_AUTH_TOKEN={}", source.token)),
+        "clone env must not carry the source's token"
+    );
+    assert!(env.contains(&format!("SIDECAR_AUTH_TOKEN={}", clone.token)));
+
+    cleanup_sandbox(&clone).await;
+    if let Some(image) = &clone.clone_image {
+        let _ = remove_snapshot_image(image).await;
+    }
+    cleanup_sandbox(&source).await;
+    eprintln!("PASSED: clone_from_snapshot_drops_source_secrets_real");
+}
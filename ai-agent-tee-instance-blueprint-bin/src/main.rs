@@ -10,7 +10,7 @@ use ai_agent_tee_instance_blueprint_lib::{
 };
 use axum::extract::Path;
 use axum::http::StatusCode;
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{Json, Router as HttpRouter};
 use blueprint_producers_extra::cron::CronJob;
 use blueprint_sdk::contexts::tangle::TangleClientContext;
@@ -115,6 +115,72 @@ async fn workflow_detail_handler(
         .map_err(workflow_status_error)
 }
 
+fn slot_error(
+    error: ai_agent_tee_instance_blueprint_lib::SlotError,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &error {
+        ai_agent_tee_instance_blueprint_lib::SlotError::BadRequest(_) => StatusCode::BAD_REQUEST,
+        ai_agent_tee_instance_blueprint_lib::SlotError::NotFound(_) => StatusCode::NOT_FOUND,
+        ai_agent_tee_instance_blueprint_lib::SlotError::Forbidden(_) => StatusCode::FORBIDDEN,
+        ai_agent_tee_instance_blueprint_lib::SlotError::Internal(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (
+        status,
+        Json(serde_json::json!({ "error": error.message() })),
+    )
+}
+
+async fn slot_provision_handler(
+    sandbox_runtime::session_auth::SessionAuth(caller): sandbox_runtime::session_auth::SessionAuth,
+    Path(slot): Path<String>,
+    Json(body): Json<ai_agent_tee_instance_blueprint_lib::SlotProvisionRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let tee = ai_agent_tee_instance_blueprint_lib::tee_backend().map_err(|e| {
+        slot_error(ai_agent_tee_instance_blueprint_lib::SlotError::Internal(
+            format!("TEE backend not available: {e}"),
+        ))
+    })?;
+    let output = ai_agent_tee_instance_blueprint_lib::provision_slot(
+        caller.as_str(),
+        &slot,
+        body,
+        Some(tee.as_ref()),
+    )
+    .await
+    .map_err(slot_error)?;
+    Ok(Json(serde_json::json!({
+        "slot": slot,
+        "sandboxId": output.sandbox_id,
+        "sidecarUrl": output.sidecar_url,
+        "sshPort": output.ssh_port,
+    })))
+}
+
+async fn slot_deprovision_handler(
+    sandbox_runtime::session_auth::SessionAuth(caller): sandbox_runtime::session_auth::SessionAuth,
+    Path(slot): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let tee = ai_agent_tee_instance_blueprint_lib::tee_backend().map_err(|e| {
+        slot_error(ai_agent_tee_instance_blueprint_lib::SlotError::Internal(
+            format!("TEE backend not available: {e}"),
+        ))
+    })?;
+    let sandbox_id = ai_agent_tee_instance_blueprint_lib::deprovision_slot(
+        caller.as_str(),
+        &slot,
+        Some(tee.as_ref()),
+    )
+    .await
+    .map_err(slot_error)?;
+    Ok(Json(serde_json::json!({
+        "slot": slot,
+        "sandboxId": sandbox_id,
+        "deprovisioned": true,
+    })))
+}
+
 fn workflow_status_router() -> HttpRouter {
     HttpRouter::new()
         .route("/api/workflows", get(workflow_list_handler))
@@ -123,19 +189,35 @@ fn workflow_status_router() -> HttpRouter {
             "/api/workflows/{workflow_id}/detail",
             get(workflow_detail_handler),
         )
+        .route(
+            "/api/sandbox/slots/{slot}",
+            post(slot_provision_handler).delete(slot_deprovision_handler),
+        )
 }
 
 #[tokio::main]
 #[allow(clippy::result_large_err)]
 async fn main() -> Result<(), blueprint_sdk::Error> {
+    if sandbox_runtime::operator_config::print_config_requested() {
+        std::process::exit(sandbox_runtime::operator_config::print_config());
+    }
     setup_log();
 
-    // Validate required auth config — SESSION_AUTH_SECRET must be set in production.
+    // Layer the optional operator config file (--config / OPERATOR_CONFIG_FILE)
+    // under the environment before anything reads configuration.
+    let operator_config =
+        sandbox_runtime::operator_config::init().map_err(blueprint_sdk::Error::Other)?;
+
+    // Validate the operator config and required auth config — SESSION_AUTH_SECRET
+    // must be set in production.
     let is_test_mode = std::env::args().any(|a| a == "--test-mode")
         || std::env::var("TEST_MODE")
             .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
             .unwrap_or(false);
-    if let Err(msg) = sandbox_runtime::session_auth::validate_required_config() {
+    if let Err(msg) = operator_config
+        .validate()
+        .and_then(|()| sandbox_runtime::session_auth::validate_required_config())
+    {
         if is_test_mode {
             warn!("Config validation (test mode): {msg}");
         } else {
@@ -143,6 +225,10 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
         }
     }
 
+    // QoS: heartbeat to the OperatorStatusRegistry plus metrics collection.
+    #[cfg(feature = "qos")]
+    let qos_metrics = sandbox_runtime::qos::start_qos_from_env().await;
+
     // ── TEE backend ──────────────────────────────────────────────────────
     let backend = sandbox_runtime::tee::backend_factory::backend_from_env()
         .map_err(|e| blueprint_sdk::Error::Other(format!("Failed to create TEE backend: {e}")))?;
@@ -187,9 +273,12 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
     let api_shutdown = tokio::sync::watch::channel(());
     let api_shutdown_tx = api_shutdown.0;
     let api_handle = {
+        let extra_routes = workflow_status_router();
+        #[cfg(feature = "grpc")]
+        let extra_routes = extra_routes.merge(sandbox_runtime::grpc::grpc_router());
         let router = sandbox_runtime::operator_api::operator_api_router_with_tee_and_routes(
             Some(tee_for_api),
-            workflow_status_router(),
+            extra_routes,
         );
         let bind_all = std::env::var("BIND_ALL_INTERFACES")
             .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
@@ -258,6 +347,12 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
         None
     };
 
+    // Spawn the QoS metrics loop (stops with the API server).
+    #[cfg(feature = "qos")]
+    if let Some(qos) = qos_metrics {
+        sandbox_runtime::qos::spawn_qos_metrics_loop(qos, api_shutdown_tx.subscribe());
+    }
+
     // Spawn reaper background task (idle timeout + max lifetime enforcement).
     {
         let config = ai_agent_tee_instance_blueprint_lib::runtime::SidecarRuntimeConfig::load();
@@ -437,6 +532,7 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
             // Do not deprovision on generic process shutdown.
             // Lifecycle transitions are reported explicitly by operator logic.
             info!("Shutdown complete; TEE instance lifecycle is report-driven");
+            sandbox_runtime::logging::shutdown();
         })
         .run()
         .await;
@@ -449,12 +545,5 @@ async fn main() -> Result<(), blueprint_sdk::Error> {
 }
 
 fn setup_log() {
-    use tracing_subscriber::prelude::*;
-    use tracing_subscriber::{EnvFilter, fmt};
-    if tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::from_default_env())
-        .try_init()
-        .is_err()
-    {}
+    sandbox_runtime::logging::init();
 }
@@ -0,0 +1,109 @@
+use ai_agent_instance_blueprint_lib::jobs::{caller_hex, require_slot_owner, traced};
+use serde_json::json;
+
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::tee::{
+    TeeBackend, decode_attestation_nonce_hex, expected_measurements_from_env,
+    pad_attestation_nonce, verify_attestation,
+};
+use crate::{InstanceAttestationRequest, JsonResponse, runtime};
+
+/// Core attestation logic — testable without TangleArg extractors.
+///
+/// Fetches a fresh report for the live deployment in `slot` (bound to
+/// `attestation_nonce` when given, hex 32-64 bytes), evaluates it server-side,
+/// and stores it as the sandbox's current attestation. The TEE-bound public
+/// key is included when the backend supports sealed secrets.
+pub async fn run_instance_attestation(
+    caller: &str,
+    slot: &str,
+    attestation_nonce: &str,
+    backend: &dyn TeeBackend,
+) -> Result<String, String> {
+    let record = require_slot_owner(caller, slot)?;
+    let deployment_id = record
+        .tee_deployment_id
+        .clone()
+        .ok_or_else(|| format!("Instance sandbox {} is not a TEE deployment", record.id))?;
+
+    let report_data = if attestation_nonce.trim().is_empty() {
+        None
+    } else {
+        if !backend.supports_attestation_report_data() {
+            return Err(format!(
+                "TEE backend {:?} does not support caller-supplied attestation nonces",
+                backend.tee_type()
+            ));
+        }
+        decode_attestation_nonce_hex(attestation_nonce)
+            .and_then(|nonce| pad_attestation_nonce(&nonce))
+            .map_err(|e| e.to_string())?
+    };
+
+    let attestation = backend
+        .attestation(&deployment_id, report_data)
+        .await
+        .map_err(|e| format!("Attestation failed: {e}"))?;
+    let verification = verify_attestation(
+        &attestation,
+        &backend.tee_type(),
+        &expected_measurements_from_env(),
+        report_data.as_ref(),
+    );
+    let public_key = match backend.derive_public_key(&deployment_id).await {
+        Ok(pk) => Some(pk),
+        Err(e) => {
+            tracing::warn!(
+                sandbox_id = %record.id,
+                error = %e,
+                "TEE public key derivation failed during attestation refresh"
+            );
+            None
+        }
+    };
+
+    let attestation_json = serde_json::to_string(&attestation).map_err(|e| e.to_string())?;
+    runtime::sandboxes()
+        .and_then(|store| {
+            store.update(&record.id, |r| {
+                r.tee_attestation_json = Some(attestation_json.clone());
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    runtime::sync_instance_slot_record(&record.id).map_err(|e| e.to_string())?;
+
+    Ok(json!({
+        "sandboxId": record.id,
+        "attestation": attestation,
+        "verification": verification,
+        "publicKey": public_key,
+    })
+    .to_string())
+}
+
+/// Re-attest the instance's TEE deployment on demand. Owner-only.
+pub async fn instance_attestation(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceAttestationRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    traced(
+        service_id,
+        call_id,
+        crate::JOB_ATTESTATION,
+        ai_agent_instance_blueprint_lib::jobs::job_input(&caller, &request),
+        async move {
+            let backend = crate::tee_backend().map_err(|e| e.to_string())?;
+            let json = run_instance_attestation(
+                &caller_hex(&caller),
+                &request.slot,
+                &request.attestation_nonce,
+                backend.as_ref(),
+            )
+            .await?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
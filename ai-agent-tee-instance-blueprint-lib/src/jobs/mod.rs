@@ -0,0 +1,6 @@
+//! TEE-only job handlers. Shared instance jobs live in
+//! `ai_agent_instance_blueprint_lib::jobs`.
+
+pub mod attestation;
+pub mod replicated;
+pub mod sealed_secrets;
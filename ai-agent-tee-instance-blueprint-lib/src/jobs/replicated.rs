@@ -0,0 +1,95 @@
+use ai_agent_instance_blueprint_lib::jobs::{caller_hex, require_slot_owner, traced};
+use serde_json::json;
+
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::tee::TeeBackend;
+use crate::tee::replication::{
+    ReplicaResponse, decode_request_nonce, replica_report_data, result_hash,
+};
+use crate::{InstanceExecRequest, InstanceReplicatedExecRequest, JsonResponse, run_instance_exec};
+
+/// Core replicated exec logic — testable without TangleArg extractors.
+///
+/// Runs the command in the TEE deployment in `slot` and attests to the
+/// result: the report data binds `request_nonce` and the result hash (see
+/// `sandbox_runtime::tee::replication`). Response signing must be on, since an
+/// aggregator only counts replicas it can attribute to an operator; the
+/// signature is added by `traced`. Output cut at the exec cap is not part of
+/// the agreed result.
+pub async fn run_instance_replicated_exec(
+    caller: &str,
+    request: &InstanceReplicatedExecRequest,
+    backend: &dyn TeeBackend,
+) -> Result<String, String> {
+    let record = require_slot_owner(caller, &request.slot)?;
+    let deployment_id = record
+        .tee_deployment_id
+        .clone()
+        .ok_or_else(|| format!("Instance sandbox {} is not a TEE deployment", record.id))?;
+    if sandbox_runtime::response_signing::signer().is_none() {
+        return Err(
+            "Replicated results must be signed; set OPERATOR_RESPONSE_SIGNING=true".to_string(),
+        );
+    }
+    if !backend.supports_attestation_report_data() {
+        return Err(format!(
+            "TEE backend {:?} cannot bind results into attestation report data",
+            backend.tee_type()
+        ));
+    }
+    let request_nonce = decode_request_nonce(&request.request_nonce).map_err(|e| e.to_string())?;
+
+    let exec = InstanceExecRequest {
+        command: request.command.clone(),
+        cwd: request.cwd.clone(),
+        env_json: request.env_json.clone(),
+        timeout_ms: request.timeout_ms,
+        slot: request.slot.clone(),
+        stdin: request.stdin.clone(),
+        stdin_file: String::new(),
+        preset: String::new(),
+    };
+    let output = run_instance_exec(&record.sidecar_url, &record.token, &record.id, &exec).await?;
+    let result = json!({
+        "exitCode": output.exit_code,
+        "stdout": output.stdout,
+        "stderr": output.stderr,
+    });
+
+    let report_data = replica_report_data(&request_nonce, &result_hash(&result));
+    let attestation = backend
+        .attestation(&deployment_id, Some(report_data))
+        .await
+        .map_err(|e| format!("Attestation failed: {e}"))?;
+
+    serde_json::to_string(&ReplicaResponse::new(
+        &record.id,
+        &request_nonce,
+        result,
+        attestation,
+    ))
+    .map_err(|e| e.to_string())
+}
+
+/// Run a command as one replica of an N-of-M replicated service. Owner-only.
+pub async fn instance_replicated_exec(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceReplicatedExecRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    traced(
+        service_id,
+        call_id,
+        crate::JOB_REPLICATED_EXEC,
+        ai_agent_instance_blueprint_lib::jobs::job_input(&caller, &request),
+        async move {
+            let backend = crate::tee_backend().map_err(|e| e.to_string())?;
+            let json =
+                run_instance_replicated_exec(&caller_hex(&caller), &request, backend.as_ref())
+                    .await?;
+            Ok(JsonResponse { json })
+        },
+    )
+    .await
+}
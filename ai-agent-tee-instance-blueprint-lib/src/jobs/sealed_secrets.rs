@@ -0,0 +1,86 @@
+use ai_agent_instance_blueprint_lib::jobs::{caller_hex, require_slot_owner};
+use serde_json::json;
+
+use crate::tangle::extract::{CallId, Caller, ServiceId, TangleArg, TangleResult};
+use crate::tee::sealed_secrets::SealedSecret;
+use crate::tee::sealed_secrets_api::check_release_gate;
+use crate::tee::{TeeBackend, expected_measurements_from_env};
+use crate::{InstanceSealedSecretsRequest, JsonResponse};
+
+/// Core sealed-secret injection logic — testable without TangleArg extractors.
+///
+/// Forwards a blob sealed to the enclave's public key (see the attestation
+/// job's `publicKey`) to the TEE deployment in `slot`. Passes the same
+/// release gate as `POST /api/sandboxes/{id}/tee/sealed-secrets`, so on-chain
+/// callers get the same attestation guarantees as web sessions. The operator
+/// never sees plaintext.
+pub async fn run_instance_sealed_secrets(
+    caller: &str,
+    slot: &str,
+    sealed_secret: &SealedSecret,
+    backend: &dyn TeeBackend,
+) -> Result<String, String> {
+    if sealed_secret.ciphertext.is_empty() {
+        return Err("Sealed secret ciphertext is required".to_string());
+    }
+    let record = require_slot_owner(caller, slot)?;
+    let deployment_id = record
+        .tee_deployment_id
+        .clone()
+        .ok_or_else(|| format!("Instance sandbox {} is not a TEE deployment", record.id))?;
+
+    let server_enforced =
+        check_release_gate(backend, &deployment_id, &expected_measurements_from_env())
+            .await
+            .map_err(|e| e.to_string())?;
+    let result = backend
+        .inject_sealed_secrets(&deployment_id, sealed_secret)
+        .await
+        .map_err(|e| format!("Sealed secret injection failed: {e}"))?;
+    if !result.success {
+        return Err(format!(
+            "Sealed secret injection failed: {}",
+            result.error.as_deref().unwrap_or("rejected by enclave")
+        ));
+    }
+
+    Ok(json!({
+        "sandboxId": record.id,
+        "success": result.success,
+        "secretsCount": result.secrets_count,
+        "serverEnforced": server_enforced,
+    })
+    .to_string())
+}
+
+/// Provision secrets sealed to the instance's enclave key. Owner-only.
+pub async fn instance_sealed_secrets(
+    Caller(caller): Caller,
+    ServiceId(service_id): ServiceId,
+    CallId(call_id): CallId,
+    TangleArg(request): TangleArg<InstanceSealedSecretsRequest>,
+) -> Result<TangleResult<JsonResponse>, String> {
+    ai_agent_instance_blueprint_lib::jobs::once(
+        service_id,
+        call_id,
+        crate::JOB_SEALED_SECRETS,
+        ai_agent_instance_blueprint_lib::jobs::job_input(&caller, &request),
+        async move {
+            let backend = crate::tee_backend().map_err(|e| e.to_string())?;
+            let sealed_secret = SealedSecret {
+                algorithm: request.algorithm,
+                ciphertext: request.ciphertext.to_vec(),
+                nonce: request.nonce.to_vec(),
+            };
+            let json = run_instance_sealed_secrets(
+                &caller_hex(&caller),
+                &request.slot,
+                &sealed_secret,
+                backend.as_ref(),
+            )
+            .await?;
+            Ok(TangleResult(JsonResponse { json }))
+        },
+    )
+    .await
+}
@@ -11,6 +11,8 @@
 #[cfg(feature = "billing")]
 pub use ai_agent_instance_blueprint_lib::billing;
 
+pub mod jobs;
+
 // Re-export from base instance blueprint — explicit to avoid leaking the base
 // `router()` (callers should use `tee_router()`) and `jobs` module (shadowed
 // by our own).
@@ -23,16 +25,32 @@ pub use ai_agent_instance_blueprint_lib::{
     DEFAULT_SIDECAR_IMAGE,
     DEFAULT_SIDECAR_SSH_PORT,
     DEFAULT_TIMEOUT_SECS,
+    InstanceConfigUpdateRequest,
     InstanceExecRequest,
     InstanceExecResponse,
+    InstanceExecResultRequest,
     InstancePromptRequest,
     InstancePromptResponse,
+    InstanceSessionExportRequest,
     InstanceSnapshotRequest,
+    InstanceSshListRequest,
     InstanceSshProvisionRequest,
     InstanceSshRevokeRequest,
+    InstanceStatusRequest,
+    InstanceTaskCancelRequest,
     InstanceTaskRequest,
     InstanceTaskResponse,
+    InstanceTaskResultRequest,
     // Job IDs
+    JOB_CONFIG_UPDATE,
+    JOB_EXEC_ASYNC,
+    JOB_EXEC_RESULT,
+    JOB_SESSION_EXPORT,
+    JOB_SSH_LIST,
+    JOB_STATUS,
+    JOB_TASK_ASYNC,
+    JOB_TASK_CANCEL,
+    JOB_TASK_RESULT,
     JOB_WORKFLOW_CANCEL,
     JOB_WORKFLOW_CREATE,
     JOB_WORKFLOW_TICK,
@@ -44,6 +62,8 @@ pub use ai_agent_instance_blueprint_lib::{
     SandboxError,
     SandboxRecord,
     SandboxState,
+    SlotError,
+    SlotProvisionRequest,
     TeeConfig,
     TeeType,
     WorkflowDetail,
@@ -57,31 +77,58 @@ pub use ai_agent_instance_blueprint_lib::{
     // Exec helpers
     build_agent_payload,
     build_exec_payload,
+    build_exec_payload_with_stdin,
     call_agent,
     clear_instance_sandbox,
+    clear_instance_sandbox_slot,
     deprovision_core,
+    deprovision_slot,
+    deprovision_slot_core,
     error,
     // Agent response parsing
     extract_agent_fields,
     extract_exec_fields,
     get_instance_sandbox,
+    get_instance_sandbox_slot,
     http,
+    instance_config_update,
+    instance_exec_async,
+    instance_exec_result,
+    instance_session_export,
+    instance_ssh_list,
+    instance_status,
     // Instance state
     instance_store,
+    instance_task_async,
+    instance_task_cancel,
+    instance_task_result,
     list_workflows_for_owner,
     metrics,
     parse_agent_response,
     provision_core,
     // SSH helpers
     provision_key,
+    provision_slot,
     reaper,
     require_instance_sandbox,
+    require_instance_sandbox_slot,
     revoke_key,
+    run_instance_config_update,
     run_instance_exec,
+    run_instance_exec_async,
+    run_instance_exec_result,
     run_instance_prompt,
+    run_instance_session_export,
+    run_instance_ssh_list,
+    run_instance_status,
     run_instance_task,
+    run_instance_task_async,
+    run_instance_task_cancel,
+    run_instance_task_result,
     runtime,
     set_instance_sandbox,
+    set_instance_sandbox_slot,
+    slots,
     spawn_pending_provision_report_worker,
     store,
     tangle,
@@ -97,24 +144,105 @@ pub use ai_agent_instance_blueprint_lib::{
 
 use blueprint_sdk::Job;
 use blueprint_sdk::Router;
+use blueprint_sdk::alloy::sol;
 use blueprint_sdk::tangle::TangleLayer;
 
+pub use jobs::attestation::{instance_attestation, run_instance_attestation};
+pub use jobs::replicated::{instance_replicated_exec, run_instance_replicated_exec};
+pub use jobs::sealed_secrets::{instance_sealed_secrets, run_instance_sealed_secrets};
 // Re-export TEE backend singleton from sandbox-runtime.
 pub use sandbox_runtime::tee::{init_tee_backend, tee_backend};
 
+// ─────────────────────────────────────────────────────────────────────────────
+// TEE-only job IDs — numbered after the shared instance lifecycle jobs.
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Operator lifecycle job (Rust-only): fresh attestation for the live deployment.
+pub const JOB_ATTESTATION: u8 = 14;
+
+/// Operator lifecycle job (Rust-only): inject secrets sealed to the enclave key.
+pub const JOB_SEALED_SECRETS: u8 = 15;
+
+/// Operator job (Rust-only): run a command for an N-of-M replicated service and
+/// return the attested, signed result for aggregation.
+pub const JOB_REPLICATED_EXEC: u8 = 23;
+
+sol! {
+    // Debug renders requests for the job replay log (`job_replay`).
+    #![sol(all_derives)]
+
+    // ── Attestation (instance-scoped) ─────────────────────────────────────
+
+    struct InstanceAttestationRequest {
+        string slot;
+        /// Optional hex-encoded 32-64 byte nonce bound into the report data.
+        string attestation_nonce;
+    }
+
+    // ── Sealed secrets (instance-scoped) ──────────────────────────────────
+
+    struct InstanceSealedSecretsRequest {
+        string slot;
+        /// Encryption algorithm, e.g. "x25519-xsalsa20-poly1305".
+        string algorithm;
+        /// Secrets sealed to the enclave's public key.
+        bytes ciphertext;
+        bytes nonce;
+    }
+
+    // ── Replicated exec (instance-scoped) ─────────────────────────────────
+
+    struct InstanceReplicatedExecRequest {
+        string slot;
+        string command;
+        string cwd;
+        string env_json;
+        uint64 timeout_ms;
+        string stdin;
+        /// Hex 32-byte nonce the caller sends to every replica; bound into
+        /// each replica's attestation together with the result hash.
+        string request_nonce;
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Router
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Build the TEE instance blueprint router.
 ///
-/// Uses the shared workflow handlers.
-/// Read-only ops (exec, prompt, task, snapshot, SSH) are served via the
-/// operator HTTP API.
+/// Uses the shared workflow, status, config-update (TEE sandboxes accept
+/// lifetime changes only), SSH key listing, background exec, session export
+/// and task queue handlers, plus the TEE-only on-demand attestation,
+/// sealed-secrets and replicated exec jobs. Image upgrades are not routed:
+/// TEE sandboxes cannot swap images without breaking attestation.
+/// Other read-only ops (exec, prompt, task, snapshot, SSH provisioning) are
+/// served via the operator HTTP API.
 pub fn tee_router() -> Router {
     Router::new()
         .route(JOB_WORKFLOW_CREATE, workflow_create.layer(TangleLayer))
         .route(JOB_WORKFLOW_TRIGGER, workflow_trigger.layer(TangleLayer))
         .route(JOB_WORKFLOW_CANCEL, workflow_cancel.layer(TangleLayer))
+        .route(JOB_STATUS, instance_status.layer(TangleLayer))
+        .route(JOB_CONFIG_UPDATE, instance_config_update.layer(TangleLayer))
+        .route(JOB_SSH_LIST, instance_ssh_list.layer(TangleLayer))
+        .route(JOB_EXEC_ASYNC, instance_exec_async.layer(TangleLayer))
+        .route(JOB_EXEC_RESULT, instance_exec_result.layer(TangleLayer))
+        .route(
+            JOB_SESSION_EXPORT,
+            instance_session_export.layer(TangleLayer),
+        )
+        .route(JOB_TASK_ASYNC, instance_task_async.layer(TangleLayer))
+        .route(JOB_TASK_RESULT, instance_task_result.layer(TangleLayer))
+        .route(JOB_TASK_CANCEL, instance_task_cancel.layer(TangleLayer))
+        .route(JOB_ATTESTATION, instance_attestation.layer(TangleLayer))
+        .route(
+            JOB_SEALED_SECRETS,
+            instance_sealed_secrets.layer(TangleLayer),
+        )
+        .route(
+            JOB_REPLICATED_EXEC,
+            instance_replicated_exec.layer(TangleLayer),
+        )
         .route(JOB_WORKFLOW_TICK, workflow_tick_job)
 }
@@ -32,6 +32,8 @@ fn make_provision_request(name: &str, tee_required: bool, tee_type: u8) -> Provi
         tee_type,
         attestation_nonce: String::new(),
         capabilities_json: String::new(),
+        template: String::new(),
+        slot: String::new(),
     }
 }
 
@@ -45,6 +47,8 @@ fn init() {
             std::env::set_var("SIDECAR_IMAGE", "nginx:alpine");
             std::env::set_var("SIDECAR_PULL_IMAGE", "false");
             std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+            // Mock sidecars never answer `/health`; skip the API handshake.
+            std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
             std::env::set_var("REQUEST_TIMEOUT_SECS", "10");
         }
     });
@@ -78,6 +82,8 @@ fn decode_provision_config_tee_required_tdx() {
         tee_type: 1,
         attestation_nonce: String::new(), // Tdx
         capabilities_json: String::new(),
+        template: String::new(),
+        slot: String::new(),
     };
 
     let encoded = req.abi_encode_params();
@@ -284,6 +290,11 @@ fn tee_fields_persistence_roundtrip() {
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
     };
 
     set_instance_sandbox(record).unwrap();
@@ -20,6 +20,8 @@ fn init() {
             std::env::set_var("SIDECAR_IMAGE", "nginx:alpine");
             std::env::set_var("SIDECAR_PULL_IMAGE", "false");
             std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+            // Mock sidecars never answer `/health`; skip the API handshake.
+            std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
             std::env::set_var("REQUEST_TIMEOUT_SECS", "10");
         }
     });
@@ -84,6 +86,11 @@ fn tee_provision_idempotent_returns_stored_attestation() {
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
     };
 
     set_instance_sandbox(record).unwrap();
@@ -174,6 +181,11 @@ fn tee_deprovision_clears_instance_sandbox() {
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     };
 
     set_instance_sandbox(record).unwrap();
@@ -213,3 +225,199 @@ fn tee_deploy_params_includes_extra_ports() {
     assert_eq!(deploy.ssh_port, Some(22));
     assert_eq!(deploy.cpu_cores, 2);
 }
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ON-DEMAND ATTESTATION JOB
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+#[allow(clippy::await_holding_lock)]
+async fn tee_attestation_job_refreshes_stored_report() {
+    init();
+    let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    use ai_agent_tee_instance_blueprint_lib::*;
+    use sandbox_runtime::tee::mock::MockTeeBackend;
+
+    let record = SandboxRecord {
+        id: "tee-integ-attest".into(),
+        container_id: "tee-mock-attest-1".into(),
+        sidecar_url: "http://localhost:9997".into(),
+        sidecar_port: 8080,
+        ssh_port: None,
+        token: "tok".into(),
+        created_at: 1000,
+        cpu_cores: 1,
+        memory_mb: 512,
+        state: SandboxState::Running,
+        idle_timeout_seconds: 300,
+        max_lifetime_seconds: 3600,
+        last_activity_at: 1000,
+        stopped_at: None,
+        snapshot_image_id: None,
+        snapshot_s3_url: None,
+        container_removed_at: None,
+        image_removed_at: None,
+        original_image: "nginx:alpine".into(),
+        base_env_json: String::new(),
+        user_env_json: String::new(),
+        snapshot_destination: None,
+        tee_deployment_id: Some("mock-attest-1".into()),
+        tee_metadata_json: Some("{}".into()),
+        tee_attestation_json: None,
+        name: "attest".into(),
+        agent_identifier: String::new(),
+        metadata_json: String::new(),
+        disk_gb: 10,
+        stack: String::new(),
+        owner: "0xowner".into(),
+        service_id: None,
+        tee_config: None,
+        extra_ports: std::collections::HashMap::new(),
+        ssh_login_user: None,
+        ssh_authorized_keys: Vec::new(),
+        capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
+    };
+    runtime::sandboxes()
+        .unwrap()
+        .insert(record.id.clone(), record.clone())
+        .unwrap();
+    set_instance_sandbox(record).unwrap();
+    let backend = MockTeeBackend::new(TeeType::Tdx);
+
+    let err = run_instance_attestation("0xother", "", "", &backend)
+        .await
+        .unwrap_err();
+    assert!(err.contains("does not own"), "got: {err}");
+    let err = run_instance_attestation("0xowner", "", "zz", &backend)
+        .await
+        .unwrap_err();
+    assert!(!err.is_empty());
+
+    let json = run_instance_attestation("0xowner", "", &"ab".repeat(32), &backend)
+        .await
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed["sandboxId"], "tee-integ-attest");
+    assert_eq!(parsed["attestation"]["timestamp"], 1_700_000_000u64);
+    assert!(parsed["verification"]["verdict"].is_object());
+    assert!(parsed["publicKey"].is_object());
+    let stored = get_instance_sandbox().unwrap().unwrap();
+    assert!(
+        stored
+            .tee_attestation_json
+            .is_some_and(|a| a.contains("1700000000"))
+    );
+
+    clear_instance_sandbox().unwrap();
+    runtime::sandboxes()
+        .unwrap()
+        .remove("tee-integ-attest")
+        .unwrap();
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ON-CHAIN SEALED SECRETS JOB
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+#[allow(clippy::await_holding_lock)]
+async fn tee_sealed_secrets_job_passes_release_gate() {
+    init();
+    let _guard = INSTANCE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    use ai_agent_tee_instance_blueprint_lib::*;
+    use sandbox_runtime::tee::mock::MockTeeBackend;
+    use sandbox_runtime::tee::sealed_secrets::SealedSecret;
+    use std::sync::atomic::Ordering;
+
+    let record = SandboxRecord {
+        id: "tee-integ-sealed".into(),
+        container_id: "tee-mock-sealed-1".into(),
+        sidecar_url: "http://localhost:9996".into(),
+        sidecar_port: 8080,
+        ssh_port: None,
+        token: "tok".into(),
+        created_at: 1000,
+        cpu_cores: 1,
+        memory_mb: 512,
+        state: SandboxState::Running,
+        idle_timeout_seconds: 300,
+        max_lifetime_seconds: 3600,
+        last_activity_at: 1000,
+        stopped_at: None,
+        snapshot_image_id: None,
+        snapshot_s3_url: None,
+        container_removed_at: None,
+        image_removed_at: None,
+        original_image: "nginx:alpine".into(),
+        base_env_json: String::new(),
+        user_env_json: String::new(),
+        snapshot_destination: None,
+        tee_deployment_id: Some("mock-sealed-1".into()),
+        tee_metadata_json: Some("{}".into()),
+        tee_attestation_json: None,
+        name: "sealed".into(),
+        agent_identifier: String::new(),
+        metadata_json: String::new(),
+        disk_gb: 10,
+        stack: String::new(),
+        owner: "0xowner".into(),
+        service_id: None,
+        tee_config: None,
+        extra_ports: std::collections::HashMap::new(),
+        ssh_login_user: None,
+        ssh_authorized_keys: Vec::new(),
+        capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
+    };
+    runtime::sandboxes()
+        .unwrap()
+        .insert(record.id.clone(), record.clone())
+        .unwrap();
+    set_instance_sandbox(record).unwrap();
+    let backend = MockTeeBackend::new(TeeType::Tdx);
+    let sealed = SealedSecret {
+        algorithm: "x25519-xsalsa20-poly1305".into(),
+        ciphertext: vec![1, 2, 3],
+        nonce: vec![4, 5, 6],
+    };
+
+    let err = run_instance_sealed_secrets("0xother", "", &sealed, &backend)
+        .await
+        .unwrap_err();
+    assert!(err.contains("does not own"), "got: {err}");
+
+    // No pinned measurement and pinning required (the default) → fail closed
+    // before anything reaches the enclave.
+    let err = run_instance_sealed_secrets("0xowner", "", &sealed, &backend)
+        .await
+        .unwrap_err();
+    assert!(err.contains("no server-pinned"), "got: {err}");
+    assert_eq!(backend.inject_secrets_count.load(Ordering::Relaxed), 0);
+
+    // SAFETY: guarded by INSTANCE_LOCK; no other test reads this variable.
+    unsafe { std::env::set_var("SANDBOX_TEE_REQUIRE_PINNED_MEASUREMENT", "false") };
+    let result = run_instance_sealed_secrets("0xowner", "", &sealed, &backend).await;
+    unsafe { std::env::remove_var("SANDBOX_TEE_REQUIRE_PINNED_MEASUREMENT") };
+    let parsed: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
+    assert_eq!(parsed["sandboxId"], "tee-integ-sealed");
+    assert_eq!(parsed["secretsCount"], 3);
+    assert_eq!(parsed["serverEnforced"], false);
+    assert_eq!(backend.inject_secrets_count.load(Ordering::Relaxed), 1);
+
+    clear_instance_sandbox().unwrap();
+    runtime::sandboxes()
+        .unwrap()
+        .remove("tee-integ-sealed")
+        .unwrap();
+}
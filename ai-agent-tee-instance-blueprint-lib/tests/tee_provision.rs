@@ -28,6 +28,8 @@ fn init() {
             std::env::set_var("SIDECAR_IMAGE", "nginx:alpine");
             std::env::set_var("SIDECAR_PULL_IMAGE", "false");
             std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+            // Mock sidecars never answer `/health`; skip the API handshake.
+            std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
             std::env::set_var("REQUEST_TIMEOUT_SECS", "10");
         }
     });
@@ -53,6 +55,8 @@ fn tee_provision_request() -> ProvisionRequest {
         tee_type: 1,
         attestation_nonce: String::new(), // Tdx
         capabilities_json: String::new(),
+        template: String::new(),
+        slot: String::new(),
     }
 }
 
@@ -290,6 +294,43 @@ async fn provision_core_tee_already_provisioned_rejects() {
     cleanup(Some(&record.id));
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn provision_core_concurrent_calls_take_the_slot_once() {
+    init();
+    let _guard = INSTANCE_LOCK.lock().await;
+    cleanup(None);
+
+    let mock = std::sync::Arc::new(MockTeeBackend::new(TeeType::Tdx));
+    let owner = "0xdeadbeef00000000000000000000000000000008";
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let mock = mock.clone();
+            tokio::spawn(async move {
+                provision_core(&tee_provision_request(), Some(mock.as_ref()), owner).await
+            })
+        })
+        .collect();
+    let mut provisioned = Vec::new();
+    let mut rejected = 0;
+    for handle in handles {
+        match handle.await.unwrap() {
+            Ok((_, record)) => provisioned.push(record),
+            Err(err) => {
+                assert!(err.contains("already provisioned"), "{err}");
+                rejected += 1;
+            }
+        }
+    }
+
+    assert_eq!(provisioned.len(), 1, "exactly one provision should win");
+    assert_eq!(rejected, 3);
+    assert_eq!(mock.deploy_count.load(Ordering::Relaxed), 1);
+    let stored = get_instance_sandbox().unwrap().expect("winner is stored");
+    assert_eq!(stored.id, provisioned[0].id);
+
+    cleanup(Some(&stored.id));
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // DEPROVISION
 // ═══════════════════════════════════════════════════════════════════════════
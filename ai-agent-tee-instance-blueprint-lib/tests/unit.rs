@@ -22,6 +22,8 @@ fn init() {
             std::env::set_var("SIDECAR_IMAGE", "nginx:alpine");
             std::env::set_var("SIDECAR_PULL_IMAGE", "true");
             std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+            // Mock sidecars never answer `/health`; skip the API handshake.
+            std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
             std::env::set_var("REQUEST_TIMEOUT_SECS", "10");
         }
     });
@@ -39,6 +41,16 @@ mod re_export_tests {
         assert_eq!(JOB_WORKFLOW_CREATE, 2);
         assert_eq!(JOB_WORKFLOW_TRIGGER, 3);
         assert_eq!(JOB_WORKFLOW_CANCEL, 4);
+        assert_eq!(JOB_ATTESTATION, 14);
+        assert_eq!(JOB_SEALED_SECRETS, 15);
+        assert_eq!(JOB_SSH_LIST, 16);
+        assert_eq!(JOB_EXEC_ASYNC, 17);
+        assert_eq!(JOB_EXEC_RESULT, 18);
+        assert_eq!(JOB_SESSION_EXPORT, 19);
+        assert_eq!(JOB_TASK_ASYNC, 20);
+        assert_eq!(JOB_TASK_RESULT, 21);
+        assert_eq!(JOB_REPLICATED_EXEC, 23);
+        assert_eq!(JOB_TASK_CANCEL, 24);
         assert_eq!(JOB_WORKFLOW_TICK, 255);
     }
 
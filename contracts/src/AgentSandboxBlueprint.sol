@@ -22,8 +22,11 @@ interface ITangleServiceOperatorView {
  *        - TEE instance mode (instanceMode=true, teeRequired=true): Same as instance
  *          but requires TEE attestation on provision.
  *
- *      5 on-chain jobs (state-changing only). All read-only operations (exec, prompt,
- *      task, stop, resume, snapshot, SSH) are served via the operator HTTP API.
+ *      On-chain jobs per mode (see `jobIds`): cloud mode runs sandbox lifecycle jobs
+ *      routed to the operator hosting the sandbox plus read-only views; instance modes
+ *      run per-instance maintenance and background jobs; workflows run in every mode.
+ *      Interactive operations (exec, prompt, task, stop, resume, snapshot, SSH) are
+ *      served via the operator HTTP API.
  *
  *      Heavy internal handlers (capacity selection, sandbox create/delete, instance
  *      provision/deprovision, workflow CRUD) are extracted to `SandboxLogic` so the
@@ -36,7 +39,7 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
     using SandboxLogic for *;
 
     // ═══════════════════════════════════════════════════════════════════════════
-    // JOB IDS (5 total — state-changing only)
+    // JOB IDS
     // ═══════════════════════════════════════════════════════════════════════════
 
     uint8 public constant JOB_SANDBOX_CREATE = 0;
@@ -44,6 +47,34 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
     uint8 public constant JOB_WORKFLOW_CREATE = 2;
     uint8 public constant JOB_WORKFLOW_TRIGGER = 3;
     uint8 public constant JOB_WORKFLOW_CANCEL = 4;
+    // Cloud: routed to the operator hosting the request's `sandbox_id`.
+    uint8 public constant JOB_ENV_UPDATE = 5;
+    uint8 public constant JOB_SANDBOX_RESTART = 6;
+    uint8 public constant JOB_SANDBOX_CLONE = 7;
+    uint8 public constant JOB_TOKEN_ROTATE = 22;
+    // Instance (both modes unless noted).
+    uint8 public constant JOB_UPGRADE = 8; // non-TEE only
+    uint8 public constant JOB_STATUS = 9;
+    uint8 public constant JOB_CONFIG_UPDATE = 10;
+    uint8 public constant JOB_BACKUP = 11; // non-TEE only
+    uint8 public constant JOB_RESTORE = 12; // non-TEE only
+    uint8 public constant JOB_REPAIR = 13; // non-TEE only
+    uint8 public constant JOB_SSH_LIST = 16;
+    uint8 public constant JOB_EXEC_ASYNC = 17;
+    uint8 public constant JOB_EXEC_RESULT = 18;
+    uint8 public constant JOB_SESSION_EXPORT = 19;
+    uint8 public constant JOB_TASK_ASYNC = 20;
+    uint8 public constant JOB_TASK_RESULT = 21;
+    uint8 public constant JOB_TASK_CANCEL = 24;
+    // TEE instance only.
+    uint8 public constant JOB_ATTESTATION = 14;
+    uint8 public constant JOB_SEALED_SECRETS = 15;
+    uint8 public constant JOB_REPLICATED_EXEC = 23;
+    // Cloud read-only views.
+    uint8 public constant JOB_READ_SANDBOX_LIST = 50;
+    uint8 public constant JOB_READ_SANDBOX_GET = 51;
+    uint8 public constant JOB_READ_WORKFLOW_STATUS = 52;
+    uint8 public constant JOB_READ_USAGE = 53;
     uint8 public constant WORKFLOW_TARGET_SANDBOX = 0;
     uint8 public constant WORKFLOW_TARGET_INSTANCE = 1;
 
@@ -63,6 +94,15 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
     uint256 public constant PRICE_MULT_WORKFLOW_CREATE = 2;
     uint256 public constant PRICE_MULT_WORKFLOW_TRIGGER = 5;
     uint256 public constant PRICE_MULT_WORKFLOW_CANCEL = 1;
+    /// @notice Read-only views, result polls and cancellation.
+    uint256 public constant PRICE_MULT_READ = 1;
+    /// @notice Sidecar recreate or restart: env update, restart, token rotation,
+    ///         config update, repair, attestation, sealed secrets.
+    uint256 public constant PRICE_MULT_RECONFIGURE = 5;
+    /// @notice Background runs: async exec/task, session export, replicated exec.
+    uint256 public constant PRICE_MULT_RUN = 5;
+    /// @notice Workspace transfer: upgrade, backup, restore.
+    uint256 public constant PRICE_MULT_WORKSPACE_TRANSFER = 20;
 
     // ═══════════════════════════════════════════════════════════════════════════
     // ARRAY BOUNDS (storage-griefing prevention)
@@ -88,6 +128,7 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
 
     event OperatorProvisioned(uint64 indexed serviceId, address indexed operator, string sandboxId, string sidecarUrl);
     event OperatorDeprovisioned(uint64 indexed serviceId, address indexed operator);
+    event OperatorProvisionFailed(uint64 indexed serviceId, address indexed operator, string reason);
     event TeeAttestationStored(uint64 indexed serviceId, address indexed operator, bytes32 attestationHash);
     event ServiceTerminationReceived(uint64 indexed serviceId, address indexed owner);
     event ServiceConfigStored(uint64 indexed serviceId, uint64 indexed requestId);
@@ -192,36 +233,19 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
     // JOB METADATA
     // ═══════════════════════════════════════════════════════════════════════════
 
-    /// @notice Returns all supported job IDs for this deployment mode.
-    /// @dev Instance mode only exposes workflow jobs (2..4).
+    /// @notice Returns all supported job IDs for this deployment mode, ascending.
     function jobIds() external view returns (uint8[] memory ids) {
-        if (SandboxStorage.load().instanceMode) {
-            ids = new uint8[](3);
-            ids[0] = JOB_WORKFLOW_CREATE;
-            ids[1] = JOB_WORKFLOW_TRIGGER;
-            ids[2] = JOB_WORKFLOW_CANCEL;
-            return ids;
-        }
-
-        ids = new uint8[](5);
-        ids[0] = JOB_SANDBOX_CREATE;
-        ids[1] = JOB_SANDBOX_DELETE;
-        ids[2] = JOB_WORKFLOW_CREATE;
-        ids[3] = JOB_WORKFLOW_TRIGGER;
-        ids[4] = JOB_WORKFLOW_CANCEL;
+        return _jobIds();
     }
 
     /// @notice Returns true if this blueprint supports the given job ID.
     function supportsJob(uint8 jobId) external view returns (bool) {
-        if (SandboxStorage.load().instanceMode) {
-            return jobId >= JOB_WORKFLOW_CREATE && jobId <= JOB_WORKFLOW_CANCEL;
-        }
-        return jobId <= JOB_WORKFLOW_CANCEL;
+        return _supportsJob(jobId);
     }
 
     /// @notice Returns the total number of on-chain jobs exposed.
     function jobCount() external view returns (uint256) {
-        return SandboxStorage.load().instanceMode ? 3 : 5;
+        return _jobIds().length;
     }
 
     // ═══════════════════════════════════════════════════════════════════════════
@@ -341,23 +365,31 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
             emit OperatorAssigned(serviceId, jobCallId, selected);
         } else if (job == JOB_SANDBOX_DELETE) {
             if ($.instanceMode) revert CloudModeOnly();
-            SandboxTypes.SandboxIdRequest memory request = abi.decode(inputs, (SandboxTypes.SandboxIdRequest));
-            string memory sandboxId = request.sandbox_id;
-            bytes32 sandboxHash = keccak256(bytes(sandboxId));
-            address routed = $.sandboxOperator[sandboxHash];
-            if (routed == address(0)) revert SandboxTypes.SandboxNotFound(sandboxHash);
+            _routeToSandboxOperator(serviceId, jobCallId, inputs);
             // tnt-core 0.19: onJobResult only receives inputsHash, so cache the
             // raw request here for handleDeleteResult to consume at result time.
             $.jobCallInputs[serviceId][jobCallId] = inputs;
-            emit OperatorRouted(serviceId, jobCallId, routed);
+        } else if (job == JOB_SANDBOX_CLONE) {
+            if ($.instanceMode) revert CloudModeOnly();
+            // The clone is created next to its source, so it takes a slot on
+            // the source's operator; handleCreateResult registers it.
+            address routed = _routeToSandboxOperator(serviceId, jobCallId, inputs);
+            if ($.operatorMaxCapacity[routed] <= $.operatorActiveSandboxes[routed]) {
+                revert SandboxTypes.NoAvailableCapacity();
+            }
+            $.createAssignments[serviceId][jobCallId] = routed;
+        } else if (job == JOB_ENV_UPDATE || job == JOB_SANDBOX_RESTART || job == JOB_TOKEN_ROTATE) {
+            if ($.instanceMode) revert CloudModeOnly();
+            _routeToSandboxOperator(serviceId, jobCallId, inputs);
         } else if (job == JOB_WORKFLOW_CREATE || job == JOB_WORKFLOW_TRIGGER || job == JOB_WORKFLOW_CANCEL) {
             // Supported in both cloud and instance modes. The workflow result
             // handlers decode the original inputs (config / workflowId), which
             // 0.19's onJobResult no longer forwards — cache them at call time.
             $.jobCallInputs[serviceId][jobCallId] = inputs;
-        } else {
+        } else if (!_supportsJob(job)) {
             revert UnknownJobId(job);
         }
+        // Remaining jobs (read-only views, instance jobs) keep no contract state.
     }
 
     // ═══════════════════════════════════════════════════════════════════════════
@@ -373,9 +405,9 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
         bytes calldata outputs
     ) external payable override onlyFromTangle {
         SandboxStorage.Data storage $ = SandboxStorage.load();
-        if (job == JOB_SANDBOX_CREATE) {
-            // CREATE derives all state from `outputs` (the sandbox id the
-            // operator returned); no cached inputs needed.
+        if (job == JOB_SANDBOX_CREATE || job == JOB_SANDBOX_CLONE) {
+            // CREATE and CLONE derive all state from `outputs` (the sandbox id
+            // the operator returned); no cached inputs needed.
             if ($.instanceMode) revert CloudModeOnly();
             SandboxLogic.handleCreateResult(serviceId, jobCallId, operator, outputs);
         } else if (job == JOB_SANDBOX_DELETE) {
@@ -393,7 +425,7 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
             bytes memory inputs = _consumeJobCallInputs(serviceId, jobCallId, inputsHash);
             (uint64 workflowId) = abi.decode(inputs, (uint64));
             SandboxLogic.cancelWorkflow(workflowId);
-        } else {
+        } else if (!_supportsJob(job)) {
             revert UnknownJobId(job);
         }
     }
@@ -439,6 +471,14 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
         SandboxLogic.handleDeprovisionResult(serviceId, msg.sender);
     }
 
+    /// @notice Operator gave up provisioning its instance (e.g. after exhausting auto-provision
+    /// retries). Informational: records nothing, so the service owner can react off-chain.
+    function reportProvisionFailed(uint64 serviceId, string calldata reason) external {
+        if (!SandboxStorage.load().instanceMode) revert InstanceModeOnly();
+        _requireActiveServiceOperator(serviceId, msg.sender);
+        emit SandboxTypes.OperatorProvisionFailed(serviceId, msg.sender, reason);
+    }
+
     function getRequiredResultCount(uint64, uint8) external pure override returns (uint32) {
         return 1;
     }
@@ -544,48 +584,84 @@ contract AgentSandboxBlueprint is OperatorSelectionBase {
         view
         returns (uint8[] memory jobIndexes, uint256[] memory rates)
     {
-        if (SandboxStorage.load().instanceMode) {
-            jobIndexes = new uint8[](3);
-            rates = new uint256[](3);
-
-            jobIndexes[0] = JOB_WORKFLOW_CREATE;
-            rates[0] = baseRate * PRICE_MULT_WORKFLOW_CREATE;
-            jobIndexes[1] = JOB_WORKFLOW_TRIGGER;
-            rates[1] = baseRate * PRICE_MULT_WORKFLOW_TRIGGER;
-            jobIndexes[2] = JOB_WORKFLOW_CANCEL;
-            rates[2] = baseRate * PRICE_MULT_WORKFLOW_CANCEL;
-            return (jobIndexes, rates);
+        jobIndexes = _jobIds();
+        rates = new uint256[](jobIndexes.length);
+        for (uint256 i = 0; i < jobIndexes.length; i++) {
+            rates[i] = baseRate * _priceMultiplier(jobIndexes[i]);
         }
-
-        jobIndexes = new uint8[](5);
-        rates = new uint256[](5);
-
-        jobIndexes[0] = JOB_SANDBOX_CREATE;
-        rates[0] = baseRate * PRICE_MULT_SANDBOX_CREATE;
-        jobIndexes[1] = JOB_SANDBOX_DELETE;
-        rates[1] = baseRate * PRICE_MULT_SANDBOX_DELETE;
-        jobIndexes[2] = JOB_WORKFLOW_CREATE;
-        rates[2] = baseRate * PRICE_MULT_WORKFLOW_CREATE;
-        jobIndexes[3] = JOB_WORKFLOW_TRIGGER;
-        rates[3] = baseRate * PRICE_MULT_WORKFLOW_TRIGGER;
-        jobIndexes[4] = JOB_WORKFLOW_CANCEL;
-        rates[4] = baseRate * PRICE_MULT_WORKFLOW_CANCEL;
     }
 
     function getJobPriceMultiplier(uint8 jobId) external view returns (uint256) {
-        if (SandboxStorage.load().instanceMode && jobId < JOB_WORKFLOW_CREATE) return 0;
-        if (jobId == JOB_SANDBOX_CREATE) return PRICE_MULT_SANDBOX_CREATE;
-        if (jobId == JOB_SANDBOX_DELETE) return PRICE_MULT_SANDBOX_DELETE;
-        if (jobId == JOB_WORKFLOW_CREATE) return PRICE_MULT_WORKFLOW_CREATE;
-        if (jobId == JOB_WORKFLOW_TRIGGER) return PRICE_MULT_WORKFLOW_TRIGGER;
-        if (jobId == JOB_WORKFLOW_CANCEL) return PRICE_MULT_WORKFLOW_CANCEL;
-        return 0;
+        return _supportsJob(jobId) ? _priceMultiplier(jobId) : 0;
     }
 
     // ═══════════════════════════════════════════════════════════════════════════
     // INTERNALS
     // ═══════════════════════════════════════════════════════════════════════════
 
+    /// @notice Whether `job` runs in this deployment mode. Mirrors the job
+    ///         routers of the sandbox, instance and TEE instance operators.
+    function _supportsJob(uint8 job) internal view returns (bool) {
+        SandboxStorage.Data storage $ = SandboxStorage.load();
+        if (job >= JOB_WORKFLOW_CREATE && job <= JOB_WORKFLOW_CANCEL) return true;
+        if (!$.instanceMode) {
+            return job <= JOB_SANDBOX_CLONE || job == JOB_TOKEN_ROTATE
+                || (job >= JOB_READ_SANDBOX_LIST && job <= JOB_READ_USAGE);
+        }
+        if (job == JOB_ATTESTATION || job == JOB_SEALED_SECRETS || job == JOB_REPLICATED_EXEC) {
+            return $.teeRequired;
+        }
+        if (job == JOB_UPGRADE || (job >= JOB_BACKUP && job <= JOB_REPAIR)) return !$.teeRequired;
+        return job == JOB_STATUS || job == JOB_CONFIG_UPDATE || (job >= JOB_SSH_LIST && job <= JOB_TASK_RESULT)
+            || job == JOB_TASK_CANCEL;
+    }
+
+    /// @notice Supported job IDs, ascending.
+    function _jobIds() internal view returns (uint8[] memory ids) {
+        ids = new uint8[](uint256(JOB_READ_USAGE) + 1);
+        uint256 count = 0;
+        for (uint8 job = 0; job <= JOB_READ_USAGE; job++) {
+            if (_supportsJob(job)) ids[count++] = job;
+        }
+        assembly {
+            mstore(ids, count)
+        }
+    }
+
+    function _priceMultiplier(uint8 job) internal pure returns (uint256) {
+        if (job == JOB_SANDBOX_CREATE || job == JOB_SANDBOX_CLONE) return PRICE_MULT_SANDBOX_CREATE;
+        if (job == JOB_SANDBOX_DELETE) return PRICE_MULT_SANDBOX_DELETE;
+        if (job == JOB_WORKFLOW_CREATE) return PRICE_MULT_WORKFLOW_CREATE;
+        if (job == JOB_WORKFLOW_TRIGGER) return PRICE_MULT_WORKFLOW_TRIGGER;
+        if (job == JOB_WORKFLOW_CANCEL) return PRICE_MULT_WORKFLOW_CANCEL;
+        if (job == JOB_UPGRADE || job == JOB_BACKUP || job == JOB_RESTORE) return PRICE_MULT_WORKSPACE_TRANSFER;
+        if (job == JOB_EXEC_ASYNC || job == JOB_SESSION_EXPORT || job == JOB_TASK_ASYNC || job == JOB_REPLICATED_EXEC) {
+            return PRICE_MULT_RUN;
+        }
+        if (
+            job == JOB_ENV_UPDATE || job == JOB_SANDBOX_RESTART || job == JOB_TOKEN_ROTATE || job == JOB_CONFIG_UPDATE
+                || job == JOB_REPAIR || job == JOB_ATTESTATION || job == JOB_SEALED_SECRETS
+        ) {
+            return PRICE_MULT_RECONFIGURE;
+        }
+        return PRICE_MULT_READ;
+    }
+
+    /// @notice Emits `OperatorRouted` for the operator hosting the sandbox the
+    ///         request names. Every sandbox-targeted request starts with
+    ///         `string sandbox_id`, so decoding the `SandboxIdRequest` prefix
+    ///         reads it from any of them.
+    function _routeToSandboxOperator(uint64 serviceId, uint64 jobCallId, bytes calldata inputs)
+        internal
+        returns (address routed)
+    {
+        SandboxTypes.SandboxIdRequest memory request = abi.decode(inputs, (SandboxTypes.SandboxIdRequest));
+        bytes32 sandboxHash = keccak256(bytes(request.sandbox_id));
+        routed = SandboxStorage.load().sandboxOperator[sandboxHash];
+        if (routed == address(0)) revert SandboxTypes.SandboxNotFound(sandboxHash);
+        emit OperatorRouted(serviceId, jobCallId, routed);
+    }
+
     /// @notice Validates that `operator` is currently active on `serviceId` in Tangle.
     function _requireActiveServiceOperator(uint64 serviceId, address operator) internal view {
         bool allowed = false;
@@ -75,6 +75,7 @@ library SandboxTypes {
     event WorkflowCanceled(uint64 indexed workflow_id, uint64 canceled_at);
     event OperatorProvisioned(uint64 indexed serviceId, address indexed operator, string sandboxId, string sidecarUrl);
     event OperatorDeprovisioned(uint64 indexed serviceId, address indexed operator);
+    event OperatorProvisionFailed(uint64 indexed serviceId, address indexed operator, string reason);
     event TeeAttestationStored(uint64 indexed serviceId, address indexed operator, bytes32 attestationHash);
 
     // ═══════════════════════════════════════════════════════════════════════════
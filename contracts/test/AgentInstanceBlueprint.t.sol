@@ -88,6 +88,25 @@ contract AgentInstanceBlueprintTest is InstanceBlueprintTestSetup {
         assertEq(instance.getOperatorCount(testServiceId), 0);
     }
 
+    function test_reportProvisionFailedEmitsEvent() public {
+        setServiceOperator(testServiceId, operator1, true);
+
+        vm.expectEmit(true, true, false, true);
+        emit SandboxTypes.OperatorProvisionFailed(testServiceId, operator1, "image pull failed");
+
+        vm.prank(operator1);
+        instance.reportProvisionFailed(testServiceId, "image pull failed");
+        assertFalse(instance.isOperatorProvisioned(testServiceId, operator1));
+    }
+
+    function test_reportProvisionFailedNonServiceOperatorReverts() public {
+        vm.prank(operator1);
+        vm.expectRevert(
+            abi.encodeWithSelector(AgentSandboxBlueprint.OperatorNotInService.selector, testServiceId, operator1)
+        );
+        instance.reportProvisionFailed(testServiceId, "image pull failed");
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════
     // DEPROVISION FLOW
     // ═══════════════════════════════════════════════════════════════════════════
@@ -174,7 +193,14 @@ contract AgentInstanceBlueprintTest is InstanceBlueprintTestSetup {
         assertEq(instance.getJobPriceMultiplier(0), 0);
         assertEq(instance.getJobPriceMultiplier(1), 0);
         assertEq(instance.getJobPriceMultiplier(255), 0);
-        assertEq(instance.getJobPriceMultiplier(8), 0);
+        assertEq(instance.getJobPriceMultiplier(14), 0); // TEE instance only
+        assertEq(instance.getJobPriceMultiplier(50), 0); // cloud only
+    }
+
+    function test_instanceJobPrices() public view {
+        assertEq(instance.getJobPriceMultiplier(8), instance.PRICE_MULT_WORKSPACE_TRANSFER());
+        assertEq(instance.getJobPriceMultiplier(9), instance.PRICE_MULT_READ());
+        assertEq(instance.getJobPriceMultiplier(20), instance.PRICE_MULT_RUN());
     }
 
     // ═══════════════════════════════════════════════════════════════════════════
@@ -203,17 +229,40 @@ contract AgentInstanceBlueprintTest is InstanceBlueprintTestSetup {
 
     function test_jobMetadata() public view {
         uint8[] memory ids = instance.jobIds();
-        assertEq(ids.length, 3);
+        assertEq(ids.length, 16);
         assertEq(ids[0], 2); // WORKFLOW_CREATE
         assertEq(ids[2], 4); // WORKFLOW_CANCEL
+        assertEq(ids[3], 8); // UPGRADE
+        assertEq(ids[8], 13); // REPAIR
+        assertEq(ids[9], 16); // SSH_LIST
+        assertEq(ids[15], 24); // TASK_CANCEL
 
         assertFalse(instance.supportsJob(0));
         assertFalse(instance.supportsJob(1));
         assertTrue(instance.supportsJob(2));
         assertTrue(instance.supportsJob(4));
         assertFalse(instance.supportsJob(5));
+        assertTrue(instance.supportsJob(9));
+        assertFalse(instance.supportsJob(14));
+        assertFalse(instance.supportsJob(22));
+        assertFalse(instance.supportsJob(50));
+
+        assertEq(instance.jobCount(), 16);
+    }
+
+    function test_instanceJobsKeepNoState() public {
+        simulateJobCall(testServiceId, instance.JOB_STATUS(), 2900, abi.encode(string("")));
+        vm.prank(tangleCore);
+        instance.onJobResult(
+            testServiceId, instance.JOB_STATUS(), 2900, operator1, bytes32(0), encodeJsonOutputs("{}")
+        );
+        assertFalse(instance.isProvisioned(testServiceId));
+    }
 
-        assertEq(instance.jobCount(), 3);
+    function test_instanceModeRejectsCloudSandboxJobs() public {
+        vm.prank(tangleCore);
+        vm.expectRevert(AgentSandboxBlueprint.CloudModeOnly.selector);
+        instance.onJobCall(testServiceId, 7, 2901, bytes(""));
     }
 
     // ═══════════════════════════════════════════════════════════════════════════
@@ -578,14 +627,14 @@ contract AgentInstanceBlueprintTest is InstanceBlueprintTestSetup {
 
     function test_unknownJobIdRevertsOnJobCallInstanceMode() public {
         vm.prank(tangleCore);
-        vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, 7));
-        instance.onJobCall(testServiceId, 7, 3000, bytes(""));
+        vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, 30));
+        instance.onJobCall(testServiceId, 30, 3000, bytes(""));
     }
 
     function test_unknownJobIdRevertsOnJobResultInstanceMode() public {
         vm.prank(tangleCore);
-        vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, 7));
-        instance.onJobResult(testServiceId, 7, 3001, operator1, keccak256(bytes("")), bytes(""));
+        vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, 30));
+        instance.onJobResult(testServiceId, 30, 3001, operator1, keccak256(bytes("")), bytes(""));
     }
 
     // ═══════════════════════════════════════════════════════════════════════════
@@ -207,6 +207,107 @@ contract AgentSandboxBlueprintTest is BlueprintTestSetup {
         blueprint.onJobCall(1, 1, 70, encodeSandboxIdInputs("no-such-sandbox"));
     }
 
+    function test_routeEnvUpdateRestartAndRotateToSandboxOperator() public {
+        registerOperator(operator1, 10);
+        registerOperator(operator2, 10);
+        _createSandbox(1, 62, operator2, "sandbox-env");
+
+        vm.expectEmit(true, true, true, true);
+        emit AgentSandboxBlueprint.OperatorRouted(1, 63, operator2);
+        simulateJobCall(
+            1,
+            blueprint.JOB_ENV_UPDATE(),
+            63,
+            abi.encode(EnvUpdateRequest({sandbox_id: "sandbox-env", env_json: "{}", replace: false}))
+        );
+
+        vm.expectEmit(true, true, true, true);
+        emit AgentSandboxBlueprint.OperatorRouted(1, 64, operator2);
+        simulateJobCall(1, blueprint.JOB_SANDBOX_RESTART(), 64, encodeSandboxIdInputs("sandbox-env"));
+
+        vm.expectEmit(true, true, true, true);
+        emit AgentSandboxBlueprint.OperatorRouted(1, 65, operator2);
+        simulateJobCall(1, blueprint.JOB_TOKEN_ROTATE(), 65, encodeSandboxIdInputs("sandbox-env"));
+
+        // Results change no routing state.
+        vm.prank(tangleCore);
+        blueprint.onJobResult(1, blueprint.JOB_SANDBOX_RESTART(), 64, operator2, bytes32(0), encodeJsonOutputs("{}"));
+        assertEq(blueprint.getSandboxOperator("sandbox-env"), operator2);
+    }
+
+    function test_routeEnvUpdateRevertsUnknownSandbox() public {
+        bytes32 unknownHash = keccak256(bytes("no-such-sandbox"));
+        vm.prank(tangleCore);
+        vm.expectRevert(abi.encodeWithSelector(SandboxTypes.SandboxNotFound.selector, unknownHash));
+        blueprint.onJobCall(
+            1, 5, 66, abi.encode(EnvUpdateRequest({sandbox_id: "no-such-sandbox", env_json: "{}", replace: true}))
+        );
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // SANDBOX CLONE (onJobCall + onJobResult)
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    function test_cloneRegistersSandboxOnSourceOperator() public {
+        registerOperator(operator1, 10);
+        registerOperator(operator2, 10);
+        _createSandbox(1, 80, operator1, "sandbox-src");
+
+        vm.expectEmit(true, true, true, true);
+        emit AgentSandboxBlueprint.OperatorRouted(1, 81, operator1);
+        simulateJobCall(1, blueprint.JOB_SANDBOX_CLONE(), 81, _encodeCloneInputs("sandbox-src"));
+
+        vm.prank(tangleCore);
+        blueprint.onJobResult(
+            1,
+            blueprint.JOB_SANDBOX_CLONE(),
+            81,
+            operator1,
+            keccak256(_encodeCloneInputs("sandbox-src")),
+            encodeSandboxCreateOutputs("sandbox-clone", "{}")
+        );
+
+        assertEq(blueprint.getSandboxOperator("sandbox-clone"), operator1);
+        (uint32 active,) = blueprint.getOperatorLoad(operator1);
+        assertEq(active, 2);
+        assertEq(blueprint.totalActiveSandboxes(), 2);
+    }
+
+    function test_cloneResultRejectsOtherOperator() public {
+        registerOperator(operator1, 10);
+        registerOperator(operator2, 10);
+        _createSandbox(1, 82, operator1, "sandbox-src2");
+        simulateJobCall(1, blueprint.JOB_SANDBOX_CLONE(), 83, _encodeCloneInputs("sandbox-src2"));
+
+        vm.prank(tangleCore);
+        vm.expectRevert(abi.encodeWithSelector(SandboxTypes.OperatorMismatch.selector, operator1, operator2));
+        blueprint.onJobResult(
+            1, 7, 83, operator2, keccak256(_encodeCloneInputs("sandbox-src2")), encodeSandboxCreateOutputs("c", "{}")
+        );
+    }
+
+    function test_cloneRevertsWhenSourceOperatorFull() public {
+        registerOperator(operator1, 1);
+        _createSandbox(1, 84, operator1, "sandbox-full");
+
+        vm.prank(tangleCore);
+        vm.expectRevert(SandboxTypes.NoAvailableCapacity.selector);
+        blueprint.onJobCall(1, 7, 85, _encodeCloneInputs("sandbox-full"));
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // READ-ONLY JOBS
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    function test_readOnlyJobsKeepNoState() public {
+        for (uint8 jobId = 50; jobId <= 53; jobId++) {
+            simulateJobCall(1, jobId, uint64(90 + jobId), abi.encode(uint32(0), uint32(0)));
+            vm.prank(tangleCore);
+            blueprint.onJobResult(1, jobId, uint64(90 + jobId), operator1, bytes32(0), encodeJsonOutputs("{}"));
+        }
+        assertEq(blueprint.totalActiveSandboxes(), 0);
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════
     // SANDBOX DELETE (onJobResult + SANDBOX_DELETE)
     // ═══════════════════════════════════════════════════════════════════════════
@@ -685,6 +786,28 @@ contract AgentSandboxBlueprintTest is BlueprintTestSetup {
     // getRequiredResultCount
     // ═══════════════════════════════════════════════════════════════════════════
 
+    function test_jobMetadataCloud() public view {
+        uint8[] memory ids = blueprint.jobIds();
+        assertEq(ids.length, 13);
+        for (uint256 i = 0; i < 8; i++) {
+            assertEq(ids[i], i);
+        }
+        assertEq(ids[8], 22); // TOKEN_ROTATE
+        assertEq(ids[9], 50); // READ_SANDBOX_LIST
+        assertEq(ids[12], 53); // READ_USAGE
+        assertEq(blueprint.jobCount(), 13);
+
+        assertTrue(blueprint.supportsJob(5));
+        assertFalse(blueprint.supportsJob(8)); // instance only
+        assertFalse(blueprint.supportsJob(14)); // TEE instance only
+        assertFalse(blueprint.supportsJob(54));
+
+        assertEq(blueprint.getJobPriceMultiplier(7), blueprint.PRICE_MULT_SANDBOX_CREATE());
+        assertEq(blueprint.getJobPriceMultiplier(5), blueprint.PRICE_MULT_RECONFIGURE());
+        assertEq(blueprint.getJobPriceMultiplier(51), blueprint.PRICE_MULT_READ());
+        assertEq(blueprint.getJobPriceMultiplier(8), 0);
+    }
+
     function test_getRequiredResultCountAlwaysReturnsOne() public view {
         assertEq(blueprint.getRequiredResultCount(1, blueprint.JOB_SANDBOX_CREATE()), 1);
         assertEq(blueprint.getRequiredResultCount(1, blueprint.JOB_SANDBOX_DELETE()), 1);
@@ -758,12 +881,12 @@ contract AgentSandboxBlueprintTest is BlueprintTestSetup {
     // ═══════════════════════════════════════════════════════════════════════════
 
     function test_cloudModeRejectsUnknownJobs() public {
-        for (uint8 jobId = 5; jobId <= 6; jobId++) {
+        for (uint8 jobId = 8; jobId <= 9; jobId++) {
             vm.prank(tangleCore);
             vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, jobId));
             blueprint.onJobCall(1, jobId, uint64(960 + jobId), bytes(""));
         }
-        for (uint8 jobId = 5; jobId <= 6; jobId++) {
+        for (uint8 jobId = 8; jobId <= 9; jobId++) {
             vm.prank(tangleCore);
             vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, jobId));
             blueprint.onJobResult(1, jobId, uint64(962 + jobId), operator1, keccak256(bytes("")), bytes(""));
@@ -867,8 +990,8 @@ contract AgentSandboxBlueprintTest is BlueprintTestSetup {
 
     function test_unknownJobIdRevertsOnJobCall() public {
         vm.prank(tangleCore);
-        vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, 7));
-        blueprint.onJobCall(1, 7, 970, bytes(""));
+        vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, 30));
+        blueprint.onJobCall(1, 30, 970, bytes(""));
     }
 
     function test_unknownJobIdRevertsOnJobCallHighId() public {
@@ -879,8 +1002,8 @@ contract AgentSandboxBlueprintTest is BlueprintTestSetup {
 
     function test_unknownJobIdRevertsOnJobResult() public {
         vm.prank(tangleCore);
-        vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, 7));
-        blueprint.onJobResult(1, 7, 972, operator1, keccak256(bytes("")), bytes(""));
+        vm.expectRevert(abi.encodeWithSelector(AgentSandboxBlueprint.UnknownJobId.selector, 30));
+        blueprint.onJobResult(1, 30, 972, operator1, keccak256(bytes("")), bytes(""));
     }
 
     function test_unknownJobIdRevertsOnJobResultHighId() public {
@@ -980,8 +1103,8 @@ contract AgentSandboxBlueprintTest is BlueprintTestSetup {
         // It will revert on overflow due to Solidity 0.8 checked math.
         vm.assume(baseRate <= type(uint256).max / 50);
         (uint8[] memory jobs, uint256[] memory rates) = blueprint.getDefaultJobRates(baseRate);
-        assertTrue(jobs.length == 5);
-        assertTrue(rates.length == 5);
+        assertTrue(jobs.length == 13);
+        assertTrue(rates.length == 13);
         // Verify all rates are >= baseRate (multiplied by >= 1)
         for (uint256 i = 0; i < rates.length; i++) {
             assertTrue(rates[i] >= baseRate);
@@ -1221,4 +1344,42 @@ contract AgentSandboxBlueprintTest is BlueprintTestSetup {
             encodeSandboxCreateOutputs(sandboxId, "{}")
         );
     }
+
+    /// @dev Mirrors the operator's `SandboxEnvUpdateRequest`.
+    struct EnvUpdateRequest {
+        string sandbox_id;
+        string env_json;
+        bool replace;
+    }
+
+    /// @dev Mirrors the operator's `SandboxCloneRequest`.
+    struct CloneRequest {
+        string sandbox_id;
+        string name;
+        string env_json;
+        string metadata_json;
+        uint64 cpu_cores;
+        uint64 memory_mb;
+        uint64 disk_gb;
+        uint64 idle_timeout_seconds;
+        uint64 max_lifetime_seconds;
+        bool inherit_secrets;
+    }
+
+    function _encodeCloneInputs(string memory sandboxId) internal pure returns (bytes memory) {
+        return abi.encode(
+            CloneRequest({
+                sandbox_id: sandboxId,
+                name: "",
+                env_json: "",
+                metadata_json: "",
+                cpu_cores: 0,
+                memory_mb: 0,
+                disk_gb: 0,
+                idle_timeout_seconds: 0,
+                max_lifetime_seconds: 0,
+                inherit_secrets: false
+            })
+        );
+    }
 }
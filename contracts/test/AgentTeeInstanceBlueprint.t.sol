@@ -95,6 +95,22 @@ contract AgentTeeInstanceBlueprintTest is TeeInstanceBlueprintTestSetup {
     // METADATA
     // ═══════════════════════════════════════════════════════════════════════════
 
+    function test_jobMetadata() public view {
+        uint8[] memory ids = teeInstance.jobIds();
+        assertEq(ids.length, 15);
+        assertEq(ids[3], 9); // STATUS
+        assertEq(ids[5], 14); // ATTESTATION
+        assertEq(ids[6], 15); // SEALED_SECRETS
+        assertEq(ids[13], 23); // REPLICATED_EXEC
+        assertEq(ids[14], 24); // TASK_CANCEL
+        assertEq(teeInstance.jobCount(), 15);
+
+        assertTrue(teeInstance.supportsJob(14));
+        assertFalse(teeInstance.supportsJob(8)); // UPGRADE is not served in a TEE
+        assertFalse(teeInstance.supportsJob(11)); // BACKUP
+        assertFalse(teeInstance.supportsJob(0));
+    }
+
     function test_blueprintName() public view {
         assertEq(teeInstance.BLUEPRINT_NAME(), "ai-agent-sandbox-blueprint");
     }
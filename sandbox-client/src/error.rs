@@ -0,0 +1,49 @@
+use std::fmt;
+
+/// Errors returned by the client.
+#[derive(Debug)]
+pub enum ClientError {
+    /// Transport failure talking to the operator.
+    Http(String),
+    /// The operator answered with a non-2xx status.
+    Api {
+        status: u16,
+        message: String,
+        /// Machine-readable code, when the operator sent one.
+        code: Option<String>,
+    },
+    /// Signing or session exchange failed, or no session token is set.
+    Auth(String),
+    /// A response or job output could not be decoded.
+    Decode(String),
+    /// Invalid client input (bad key, bad URL).
+    Validation(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Http(msg) => write!(f, "http error: {msg}"),
+            ClientError::Api {
+                status, message, ..
+            } => write!(f, "operator returned {status}: {message}"),
+            ClientError::Auth(msg) => write!(f, "auth error: {msg}"),
+            ClientError::Decode(msg) => write!(f, "decode error: {msg}"),
+            ClientError::Validation(msg) => write!(f, "validation error: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() {
+            ClientError::Decode(err.to_string())
+        } else {
+            ClientError::Http(err.to_string())
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,210 @@
+//! On-chain job submission helpers.
+//!
+//! Jobs are submitted to the Tangle contract as `submitJob(serviceId,
+//! jobIndex, inputs)`, where `inputs` is the ABI encoding of the job's
+//! request struct. The request types and job IDs are the blueprints' own
+//! (re-exported as [`sandbox`] and [`instance`]), so payloads cannot drift
+//! from what the operators decode. Send [`JobCall::calldata`] with any
+//! Ethereum client, then decode the result bytes from `JobResultSubmitted`
+//! with [`decode_output`] or [`decode_json_output`].
+
+use alloy::primitives::Bytes;
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolType, SolValue};
+use serde_json::Value;
+
+use crate::error::{ClientError, Result};
+
+pub use ai_agent_instance_blueprint_lib as instance;
+pub use ai_agent_sandbox_blueprint_lib as sandbox;
+
+sol! {
+    /// Job entry point of the Tangle contract.
+    interface ITangleJobs {
+        function submitJob(uint64 serviceId, uint8 jobIndex, bytes inputs) external payable returns (uint64 callId);
+    }
+}
+
+/// A job index with its ABI-encoded inputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobCall {
+    pub job: u8,
+    pub inputs: Vec<u8>,
+}
+
+impl JobCall {
+    pub fn new<T: SolValue>(job: u8, request: &T) -> Self {
+        Self {
+            job,
+            inputs: request.abi_encode(),
+        }
+    }
+
+    /// Calldata for `submitJob` on `service_id`.
+    pub fn calldata(&self, service_id: u64) -> Vec<u8> {
+        ITangleJobs::submitJobCall {
+            serviceId: service_id,
+            jobIndex: self.job,
+            inputs: Bytes::from(self.inputs.clone()),
+        }
+        .abi_encode()
+    }
+}
+
+/// Call ID returned by a `submitJob` call (from `eth_call` or a trace).
+pub fn decode_call_id(returndata: &[u8]) -> Result<u64> {
+    ITangleJobs::submitJobCall::abi_decode_returns(returndata)
+        .map_err(|e| ClientError::Decode(format!("submitJob return: {e}")))
+}
+
+/// Decode a job's result bytes as its response struct.
+pub fn decode_output<T>(output: &[u8]) -> Result<T>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType>,
+{
+    T::abi_decode(output).map_err(|e| ClientError::Decode(format!("job output: {e}")))
+}
+
+/// Decode a `JsonResponse` result (most lifecycle jobs) into its JSON body.
+pub fn decode_json_output(output: &[u8]) -> Result<Value> {
+    let response: sandbox::JsonResponse = decode_output(output)?;
+    serde_json::from_str(&response.json)
+        .map_err(|e| ClientError::Decode(format!("job output json: {e}")))
+}
+
+pub fn sandbox_create(request: &sandbox::SandboxCreateRequest) -> JobCall {
+    JobCall::new(sandbox::JOB_SANDBOX_CREATE, request)
+}
+
+pub fn sandbox_delete(sandbox_id: impl Into<String>) -> JobCall {
+    let request = sandbox::SandboxIdRequest {
+        sandbox_id: sandbox_id.into(),
+    };
+    JobCall::new(sandbox::JOB_SANDBOX_DELETE, &request)
+}
+
+pub fn sandbox_restart(sandbox_id: impl Into<String>) -> JobCall {
+    let request = sandbox::SandboxIdRequest {
+        sandbox_id: sandbox_id.into(),
+    };
+    JobCall::new(sandbox::JOB_SANDBOX_RESTART, &request)
+}
+
+/// Rotate a sandbox's sidecar token (`JOB_TOKEN_ROTATE`). The result does not
+/// carry the new token.
+pub fn sandbox_token_rotate(sandbox_id: impl Into<String>) -> JobCall {
+    let request = sandbox::SandboxIdRequest {
+        sandbox_id: sandbox_id.into(),
+    };
+    JobCall::new(sandbox::JOB_TOKEN_ROTATE, &request)
+}
+
+pub fn workflow_create(request: &sandbox::WorkflowCreateRequest) -> JobCall {
+    JobCall::new(sandbox::JOB_WORKFLOW_CREATE, request)
+}
+
+pub fn workflow_trigger(workflow_id: u64) -> JobCall {
+    let request = sandbox::WorkflowControlRequest { workflow_id };
+    JobCall::new(sandbox::JOB_WORKFLOW_TRIGGER, &request)
+}
+
+pub fn workflow_cancel(workflow_id: u64) -> JobCall {
+    let request = sandbox::WorkflowControlRequest { workflow_id };
+    JobCall::new(sandbox::JOB_WORKFLOW_CANCEL, &request)
+}
+
+/// A page of the caller's sandboxes (`JOB_READ_SANDBOX_LIST`); `limit` 0
+/// uses the operator's default page size.
+pub fn read_sandbox_list(offset: u32, limit: u32) -> JobCall {
+    let request = sandbox::ReadPageRequest { offset, limit };
+    JobCall::new(sandbox::JOB_READ_SANDBOX_LIST, &request)
+}
+
+pub fn read_sandbox_get(sandbox_id: impl Into<String>) -> JobCall {
+    let request = sandbox::SandboxIdRequest {
+        sandbox_id: sandbox_id.into(),
+    };
+    JobCall::new(sandbox::JOB_READ_SANDBOX_GET, &request)
+}
+
+pub fn read_workflow_status(workflow_id: u64) -> JobCall {
+    let request = sandbox::WorkflowControlRequest { workflow_id };
+    JobCall::new(sandbox::JOB_READ_WORKFLOW_STATUS, &request)
+}
+
+/// The caller's usage and quota (`JOB_READ_USAGE`); takes no inputs.
+pub fn read_usage() -> JobCall {
+    JobCall {
+        job: sandbox::JOB_READ_USAGE,
+        inputs: Vec::new(),
+    }
+}
+
+/// Start a background command on an instance (`JOB_EXEC_ASYNC`).
+pub fn instance_exec_async(request: &instance::InstanceExecRequest) -> JobCall {
+    JobCall::new(instance::JOB_EXEC_ASYNC, request)
+}
+
+pub fn instance_exec_result(slot: impl Into<String>, execution_id: impl Into<String>) -> JobCall {
+    let request = instance::InstanceExecResultRequest {
+        slot: slot.into(),
+        execution_id: execution_id.into(),
+    };
+    JobCall::new(instance::JOB_EXEC_RESULT, &request)
+}
+
+/// Queue an agent task on an instance (`JOB_TASK_ASYNC`).
+pub fn instance_task_async(request: &instance::InstanceTaskRequest) -> JobCall {
+    JobCall::new(instance::JOB_TASK_ASYNC, request)
+}
+
+pub fn instance_task_result(slot: impl Into<String>, task_id: impl Into<String>) -> JobCall {
+    let request = instance::InstanceTaskResultRequest {
+        slot: slot.into(),
+        task_id: task_id.into(),
+    };
+    JobCall::new(instance::JOB_TASK_RESULT, &request)
+}
+
+/// Cancel a queued task on an instance (`JOB_TASK_CANCEL`).
+pub fn instance_task_cancel(slot: impl Into<String>, task_id: impl Into<String>) -> JobCall {
+    let request = instance::InstanceTaskCancelRequest {
+        slot: slot.into(),
+        task_id: task_id.into(),
+    };
+    JobCall::new(instance::JOB_TASK_CANCEL, &request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calldata_wraps_abi_encoded_request() {
+        let call = sandbox_delete("sb-1");
+        assert_eq!(call.job, sandbox::JOB_SANDBOX_DELETE);
+        let decoded: sandbox::SandboxIdRequest = decode_output(&call.inputs).unwrap();
+        assert_eq!(decoded.sandbox_id, "sb-1");
+
+        let calldata = call.calldata(42);
+        assert_eq!(
+            &calldata[..4],
+            ITangleJobs::submitJobCall::SELECTOR.as_slice()
+        );
+        let submitted = ITangleJobs::submitJobCall::abi_decode(&calldata).unwrap();
+        assert_eq!(submitted.serviceId, 42);
+        assert_eq!(submitted.jobIndex, sandbox::JOB_SANDBOX_DELETE);
+        assert_eq!(submitted.inputs.as_ref(), call.inputs.as_slice());
+    }
+
+    #[test]
+    fn json_outputs_decode() {
+        let output = sandbox::JsonResponse {
+            json: r#"{"status":"ok"}"#.to_string(),
+        }
+        .abi_encode();
+        assert_eq!(decode_json_output(&output).unwrap()["status"], "ok");
+        assert!(decode_json_output(b"junk").is_err());
+        assert_eq!(decode_call_id(&7u64.abi_encode()).unwrap(), 7);
+    }
+}
@@ -0,0 +1,36 @@
+//! Rust client for the AI agent sandbox blueprints.
+//!
+//! [`OperatorClient`] wraps the operator HTTP API: EIP-191 session login,
+//! exec, prompt, task, queued tasks, secrets and the SSE event streams.
+//! With the default `jobs` feature, [`jobs`] builds `submitJob` calldata from
+//! the blueprints' own `sol!` request types and decodes job results, so
+//! integrators never hand-encode ABI payloads.
+//!
+//! ```no_run
+//! # async fn demo() -> sandbox_client::Result<()> {
+//! use sandbox_client::{ExecRequest, LocalSigner, OperatorClient, Target};
+//!
+//! let signer = LocalSigner::from_hex("0x…private key…")?;
+//! let mut client = OperatorClient::new("https://operator.example.com")?;
+//! client.login(&signer).await?;
+//! let out = client
+//!     .exec(&Target::sandbox("sb-123"), &ExecRequest::new("ls -la"))
+//!     .await?;
+//! println!("{}", out.stdout);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod error;
+#[cfg(feature = "jobs")]
+pub mod jobs;
+pub mod operator;
+pub mod signer;
+pub mod sse;
+pub mod types;
+
+pub use error::{ClientError, Result};
+pub use operator::{OperatorClient, Target};
+pub use signer::LocalSigner;
+pub use sse::SseEvent;
+pub use types::*;
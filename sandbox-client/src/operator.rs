@@ -0,0 +1,309 @@
+//! HTTP client for one operator's API.
+
+use futures_util::{Stream, StreamExt, stream};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+
+use crate::error::{ClientError, Result};
+use crate::signer::LocalSigner;
+use crate::sse::{SseEvent, SseParser};
+use crate::types::*;
+
+/// Which sandbox a call addresses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// A cloud-mode sandbox: `/api/sandboxes/{id}/...`.
+    Sandbox(String),
+    /// The caller's instance sandbox: `/api/sandbox/...`. Pick a slot via
+    /// the request's `slot` field.
+    Instance,
+}
+
+impl Target {
+    pub fn sandbox(id: impl Into<String>) -> Self {
+        Target::Sandbox(id.into())
+    }
+
+    fn path(&self, suffix: &str) -> String {
+        match self {
+            Target::Sandbox(id) => format!("/api/sandboxes/{id}/{suffix}"),
+            Target::Instance => format!("/api/sandbox/{suffix}"),
+        }
+    }
+}
+
+/// Client for an operator API. Call [`OperatorClient::login`] (or
+/// [`OperatorClient::with_token`]) before authenticated routes.
+#[derive(Clone, Debug)]
+pub struct OperatorClient {
+    base_url: String,
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl OperatorClient {
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .build()
+            .map_err(|e| ClientError::Http(e.to_string()))?;
+        Ok(Self::with_http_client(base_url, http))
+    }
+
+    /// Use a preconfigured `reqwest` client (proxies, timeouts, TLS roots).
+    pub fn with_http_client(base_url: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http,
+            token: None,
+        }
+    }
+
+    /// Reuse a session token obtained earlier.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Sign a fresh challenge with `signer` and keep the session token.
+    pub async fn login(&mut self, signer: &LocalSigner) -> Result<SessionToken> {
+        let challenge: Challenge = self
+            .send(self.request_unauthed("/api/auth/challenge"))
+            .await?;
+        let signature = signer.sign_message(&challenge.message)?;
+        let session: SessionToken = self
+            .send(
+                self.request_unauthed("/api/auth/session")
+                    .json(&json!({ "nonce": challenge.nonce, "signature": signature })),
+            )
+            .await?;
+        self.token = Some(session.token.clone());
+        Ok(session)
+    }
+
+    /// Revoke the current session token.
+    pub async fn logout(&mut self) -> Result<()> {
+        let req = self.request(reqwest::Method::DELETE, "/api/auth/session")?;
+        let _: Value = self.send(req).await?;
+        self.token = None;
+        Ok(())
+    }
+
+    /// Sandboxes owned by the session's address.
+    pub async fn list_sandboxes(&self) -> Result<Value> {
+        self.get("/api/sandboxes").await
+    }
+
+    pub async fn exec(&self, target: &Target, req: &ExecRequest) -> Result<ExecResponse> {
+        self.post(&target.path("exec"), req).await
+    }
+
+    /// Start a prompt run; its output arrives on [`Self::chat_events`].
+    pub async fn prompt(&self, target: &Target, req: &PromptRequest) -> Result<RunAccepted> {
+        self.post(&target.path("prompt"), req).await
+    }
+
+    /// Start a multi-turn task run; its output arrives on
+    /// [`Self::chat_events`].
+    pub async fn task(&self, target: &Target, req: &TaskRequest) -> Result<RunAccepted> {
+        self.post(&target.path("task"), req).await
+    }
+
+    /// Queue a task; poll it with [`Self::get_task`] or follow
+    /// [`Self::task_events`].
+    pub async fn enqueue_task(&self, target: &Target, req: &TaskRequest) -> Result<QueuedTask> {
+        self.post(&target.path("tasks"), req).await
+    }
+
+    pub async fn get_task(&self, target: &Target, task_id: &str) -> Result<TaskRecord> {
+        self.get(&target.path(&format!("tasks/{task_id}"))).await
+    }
+
+    /// Cancel a queued or running task, aborting its sidecar run.
+    pub async fn cancel_task(&self, target: &Target, task_id: &str) -> Result<TaskRecord> {
+        self.post(&target.path(&format!("tasks/{task_id}/cancel")), &json!({}))
+            .await
+    }
+
+    /// `progress` events carrying a [`TaskRecord`], ending with `done`.
+    pub async fn task_events(
+        &self,
+        target: &Target,
+        task_id: &str,
+    ) -> Result<impl Stream<Item = Result<SseEvent>> + use<>> {
+        self.events(&target.path(&format!("tasks/{task_id}/stream")))
+            .await
+    }
+
+    /// Live events of a chat session (prompt and task runs).
+    pub async fn chat_events(
+        &self,
+        target: &Target,
+        session_id: &str,
+    ) -> Result<impl Stream<Item = Result<SseEvent>> + use<>> {
+        self.events(&target.path(&format!("live/chat/sessions/{session_id}/stream")))
+            .await
+    }
+
+    pub async fn get_secrets(&self, target: &Target) -> Result<Secrets> {
+        self.get(&target.path("secrets")).await
+    }
+
+    /// Store `env` as the sandbox's user secrets; the sidecar is recreated
+    /// to apply them.
+    pub async fn inject_secrets(
+        &self,
+        target: &Target,
+        env: Map<String, Value>,
+    ) -> Result<SecretsStatus> {
+        let body = InjectSecretsRequest {
+            env_json: env,
+            vault: None,
+        };
+        self.post(&target.path("secrets"), &body).await
+    }
+
+    pub async fn wipe_secrets(&self, target: &Target) -> Result<SecretsStatus> {
+        let req = self.request(reqwest::Method::DELETE, &target.path("secrets"))?;
+        self.send(req).await
+    }
+
+    /// Upload a snapshot of the sandbox to `req.destination`.
+    pub async fn snapshot(&self, target: &Target, req: &SnapshotRequest) -> Result<Value> {
+        self.post(&target.path("snapshot"), req).await
+    }
+
+    /// Operator-side audit events (SSH access, secret changes, rotations).
+    pub async fn audit_events(&self, target: &Target) -> Result<Vec<AuditEvent>> {
+        let resp: Value = self.get(&target.path("audit")).await?;
+        serde_json::from_value(resp["events"].clone())
+            .map_err(|e| ClientError::Decode(format!("audit events: {e}")))
+    }
+
+    /// Rotate the sandbox's secrets now under its rotation policy.
+    pub async fn run_secret_rotation(&self, target: &Target) -> Result<Value> {
+        self.post(&target.path("secrets/rotation/run"), &json!({}))
+            .await
+    }
+
+    /// Fresh TEE attestation with the server's verification verdict; a hex
+    /// `nonce` binds the report to this request.
+    pub async fn attestation(&self, sandbox_id: &str, nonce: Option<&str>) -> Result<Value> {
+        let path = Target::sandbox(sandbox_id).path("tee/attestation");
+        match nonce {
+            Some(nonce) => {
+                self.post(&path, &json!({ "attestation_nonce": nonce }))
+                    .await
+            }
+            None => self.get(&path).await,
+        }
+    }
+
+    /// Workflows owned by the session's address.
+    pub async fn list_workflows(&self) -> Result<Value> {
+        self.get("/api/workflows").await
+    }
+
+    /// Runtime status of one workflow, including its latest execution.
+    pub async fn workflow(&self, workflow_id: u64) -> Result<Value> {
+        self.get(&format!("/api/workflows/{workflow_id}")).await
+    }
+
+    /// GET an arbitrary authenticated route.
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send(self.request(reqwest::Method::GET, path)?).await
+    }
+
+    /// POST a JSON body to an arbitrary authenticated route.
+    pub async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.send(self.request(reqwest::Method::POST, path)?.json(body))
+            .await
+    }
+
+    /// Open an SSE route and yield its events.
+    pub async fn events(&self, path: &str) -> Result<impl Stream<Item = Result<SseEvent>> + use<>> {
+        let response = self
+            .request(reqwest::Method::GET, path)?
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await?;
+        let response = check_status(response).await?;
+        let chunks = Box::pin(response.bytes_stream());
+        let events = stream::unfold(
+            (chunks, SseParser::default()),
+            |(mut chunks, mut parser)| async move {
+                let chunk = chunks.next().await?;
+                let batch = match chunk {
+                    Ok(bytes) => parser.push(&bytes).into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(ClientError::from(e))],
+                };
+                Some((stream::iter(batch), (chunks, parser)))
+            },
+        );
+        Ok(events.flatten())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    fn request_unauthed(&self, path: &str) -> reqwest::RequestBuilder {
+        self.http.post(self.url(path))
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> Result<reqwest::RequestBuilder> {
+        let token = self
+            .token
+            .as_deref()
+            .ok_or_else(|| ClientError::Auth("Not logged in".into()))?;
+        Ok(self.http.request(method, self.url(path)).bearer_auth(token))
+    }
+
+    async fn send<T: DeserializeOwned>(&self, req: reqwest::RequestBuilder) -> Result<T> {
+        let response = check_status(req.send().await?).await?;
+        Ok(response.json().await?)
+    }
+}
+
+/// Turn a non-2xx answer into [`ClientError::Api`] using the operator's
+/// `{ "error", "code" }` body.
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    let message = body
+        .get("error")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| status.to_string());
+    let code = body.get("code").and_then(Value::as_str).map(str::to_string);
+    Err(ClientError::Api {
+        status: status.as_u16(),
+        message,
+        code,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_map_to_route_prefixes() {
+        assert_eq!(
+            Target::sandbox("sb-1").path("tasks/t-1/stream"),
+            "/api/sandboxes/sb-1/tasks/t-1/stream"
+        );
+        assert_eq!(Target::Instance.path("exec"), "/api/sandbox/exec");
+    }
+}
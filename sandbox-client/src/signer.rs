@@ -0,0 +1,178 @@
+//! EIP-191 `personal_sign` with a local secp256k1 key, for session login.
+
+use k256::ecdsa::SigningKey;
+
+use crate::error::{ClientError, Result};
+
+/// A local private key that signs operator auth challenges.
+#[derive(Clone)]
+pub struct LocalSigner {
+    key: SigningKey,
+    address: String,
+}
+
+impl std::fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalSigner")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LocalSigner {
+    pub fn new(key: SigningKey) -> Self {
+        let point = key.verifying_key().to_encoded_point(false);
+        let hash = keccak256(&point.as_bytes()[1..]);
+        let address = format!("0x{}", hex::encode(&hash[12..]));
+        Self { key, address }
+    }
+
+    /// Parse a hex private key, with or without `0x`.
+    pub fn from_hex(private_key: &str) -> Result<Self> {
+        let bytes = hex::decode(private_key.trim().trim_start_matches("0x"))
+            .map_err(|e| ClientError::Validation(format!("Invalid private key hex: {e}")))?;
+        let key = SigningKey::from_slice(&bytes)
+            .map_err(|e| ClientError::Validation(format!("Invalid private key: {e}")))?;
+        Ok(Self::new(key))
+    }
+
+    /// Load the ECDSA key of a blueprint keystore directory (as written by
+    /// `cargo tangle key import`), with or without `file://`, through the
+    /// blueprint SDK keystore. Errors when the keystore holds no ECDSA key,
+    /// more than one, or a key file it cannot read.
+    #[cfg(feature = "keystore")]
+    pub fn from_keystore(keystore: &str) -> Result<Self> {
+        use blueprint_sdk::crypto::k256::K256Ecdsa;
+        use blueprint_sdk::keystore::backends::Backend;
+        use blueprint_sdk::keystore::{Keystore, KeystoreConfig};
+
+        let root = keystore.strip_prefix("file://").unwrap_or(keystore);
+        let invalid = |e: &dyn std::fmt::Display| {
+            ClientError::Validation(format!("Cannot load keystore {root}: {e}"))
+        };
+        let dir = std::path::Path::new(root).join("Ecdsa");
+        let files = std::fs::read_dir(&dir)
+            .map_err(|e| ClientError::Validation(format!("Cannot read {}: {e}", dir.display())))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .count();
+        let store = Keystore::new(KeystoreConfig::new().fs_root(root)).map_err(|e| invalid(&e))?;
+        let keys = store.list_local::<K256Ecdsa>().map_err(|e| invalid(&e))?;
+        if keys.len() != files {
+            return Err(ClientError::Validation(format!(
+                "{} holds {files} files but {} readable ECDSA keys",
+                dir.display(),
+                keys.len()
+            )));
+        }
+        let public = match keys.as_slice() {
+            [public] => public,
+            [] => {
+                return Err(ClientError::Validation(format!(
+                    "No ECDSA key found under {}",
+                    dir.display()
+                )));
+            }
+            _ => {
+                return Err(ClientError::Validation(format!(
+                    "{} holds {} ECDSA keys; keep one or pass --private-key",
+                    dir.display(),
+                    keys.len()
+                )));
+            }
+        };
+        let secret = store
+            .get_secret::<K256Ecdsa>(public)
+            .map_err(|e| invalid(&e))?;
+        Ok(Self::new(secret.0))
+    }
+
+    /// Lowercase `0x` address of the key.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// 65-byte `0x` signature of `message` with the EIP-191 prefix, `v` in
+    /// `{27, 28}`.
+    pub fn sign_message(&self, message: &str) -> Result<String> {
+        let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+        let digest = keccak256(prefixed.as_bytes());
+        let (signature, recovery_id) = self
+            .key
+            .sign_prehash_recoverable(&digest)
+            .map_err(|e| ClientError::Auth(format!("Signing failed: {e}")))?;
+        let mut bytes = Vec::with_capacity(65);
+        bytes.extend_from_slice(&signature.to_bytes());
+        bytes.push(recovery_id.to_byte() + 27);
+        Ok(format!("0x{}", hex::encode(bytes)))
+    }
+}
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    use tiny_keccak::{Hasher, Keccak};
+    let mut hasher = Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(data);
+    hasher.finalize(&mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_address_and_signs_recoverably() {
+        let signer = LocalSigner::from_hex(
+            "0x0000000000000000000000000000000000000000000000000000000000000001",
+        )
+        .unwrap();
+        assert_eq!(
+            signer.address(),
+            "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf"
+        );
+        let signature = signer.sign_message("hello").unwrap();
+        assert_eq!(signature.len(), 2 + 130);
+        let recovered =
+            sandbox_runtime::session_auth::verify_eip191_signature("hello", &signature).unwrap();
+        assert_eq!(recovered, signer.address());
+        assert!(LocalSigner::from_hex("0xzz").is_err());
+    }
+
+    #[cfg(feature = "keystore")]
+    #[test]
+    fn loads_keystore_written_by_the_sdk() {
+        use blueprint_sdk::crypto::KeyType;
+        use blueprint_sdk::crypto::k256::K256Ecdsa;
+        use blueprint_sdk::keystore::backends::Backend;
+        use blueprint_sdk::keystore::{Keystore, KeystoreConfig};
+
+        let dir = tempfile::tempdir().unwrap();
+        let uri = format!("file://{}", dir.path().display());
+        assert!(
+            LocalSigner::from_keystore(&uri).is_err(),
+            "no Ecdsa dir yet"
+        );
+
+        let store = Keystore::new(KeystoreConfig::new().fs_root(dir.path())).unwrap();
+        let secret = K256Ecdsa::generate_with_seed(Some(&[7u8; 32])).unwrap();
+        store.insert::<K256Ecdsa>(&secret).unwrap();
+        let signer = LocalSigner::from_keystore(&uri).unwrap();
+        assert_eq!(
+            signer.address(),
+            LocalSigner::new(secret.0.clone()).address()
+        );
+
+        // An unreadable key file is an error, not skipped.
+        let broken = dir.path().join("Ecdsa").join("broken");
+        std::fs::write(&broken, "not a key").unwrap();
+        assert!(LocalSigner::from_keystore(&uri).is_err());
+        std::fs::remove_file(&broken).unwrap();
+
+        // A second key is ambiguous.
+        let other = K256Ecdsa::generate_with_seed(Some(&[8u8; 32])).unwrap();
+        store.insert::<K256Ecdsa>(&other).unwrap();
+        let err = LocalSigner::from_keystore(&uri).unwrap_err().to_string();
+        assert!(err.contains("2 ECDSA keys"), "{err}");
+    }
+}
@@ -0,0 +1,104 @@
+//! Incremental parser for the operator's `text/event-stream` responses.
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{ClientError, Result};
+
+/// One server-sent event.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// `event:` field; `message` when the server sent none.
+    pub event: String,
+    /// `data:` lines joined with `\n`.
+    pub data: String,
+    pub id: Option<String>,
+}
+
+impl SseEvent {
+    /// Decode `data` as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_str(&self.data)
+            .map_err(|e| ClientError::Decode(format!("SSE `{}` event: {e}", self.event)))
+    }
+}
+
+/// Buffers chunks and yields complete events. Comment lines (keep-alives)
+/// are dropped.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: SseEvent,
+    has_data: bool,
+}
+
+impl SseParser {
+    /// Feed raw bytes; multi-byte characters may straddle chunks.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let raw: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&raw);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if self.has_data {
+                    let mut event = std::mem::take(&mut self.event);
+                    if event.event.is_empty() {
+                        event.event = "message".to_string();
+                    }
+                    events.push(event);
+                }
+                self.event = SseEvent::default();
+                self.has_data = false;
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event.event = value.to_string(),
+                "id" => self.event.id = Some(value.to_string()),
+                "data" => {
+                    if self.has_data {
+                        self.event.data.push('\n');
+                    }
+                    self.event.data.push_str(value);
+                    self.has_data = true;
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_events_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b": keep-alive\n\nevent: progr").is_empty());
+        let events = parser.push(b"ess\ndata: {\"a\":1}\n\ndata: one\r\ndata: two\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: "progress".into(),
+                    data: "{\"a\":1}".into(),
+                    id: None,
+                },
+                SseEvent {
+                    event: "message".into(),
+                    data: "one\ntwo".into(),
+                    id: None,
+                },
+            ]
+        );
+        let value: serde_json::Value = events[0].json().unwrap();
+        assert_eq!(value["a"], 1);
+    }
+}
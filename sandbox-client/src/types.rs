@@ -0,0 +1,214 @@
+//! Operator API request and response bodies.
+//!
+//! These mirror `sandbox_runtime::api_types` on the wire. Empty strings and
+//! zero values mean "operator default", exactly as on the server.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Challenge {
+    pub nonce: String,
+    /// Text to sign with EIP-191.
+    pub message: String,
+    pub expires_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SessionToken {
+    pub token: String,
+    pub address: String,
+    pub expires_at: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ExecRequest {
+    pub command: String,
+    pub session_id: String,
+    pub cwd: String,
+    pub env_json: String,
+    pub timeout_ms: u64,
+    /// Instance slot (empty = `main`); ignored for per-sandbox targets.
+    pub slot: String,
+    /// Text piped to the command's stdin.
+    pub stdin: String,
+    /// Sandbox file piped to stdin instead; exclusive with `stdin`.
+    pub stdin_file: String,
+}
+
+impl ExecRequest {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExecResponse {
+    pub exit_code: u32,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether stdout or stderr was cut at the operator's output cap.
+    #[serde(default)]
+    pub truncated: bool,
+    /// Overflow artifact holding the full output, when truncated.
+    #[serde(default)]
+    pub overflow_ref: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PromptRequest {
+    pub message: String,
+    /// Live chat session to continue; empty starts a new one.
+    pub session_id: String,
+    pub backend_type: String,
+    pub model: String,
+    pub context_json: String,
+    pub timeout_ms: u64,
+    pub slot: String,
+}
+
+impl PromptRequest {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TaskRequest {
+    pub prompt: String,
+    pub session_id: String,
+    pub max_turns: u64,
+    pub backend_type: String,
+    pub model: String,
+    pub context_json: String,
+    pub timeout_ms: u64,
+    pub slot: String,
+}
+
+impl TaskRequest {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A prompt or task run accepted into a live chat session. Follow it with
+/// [`crate::OperatorClient::chat_events`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct RunAccepted {
+    pub accepted: bool,
+    pub run_id: String,
+    pub session_id: String,
+    pub status: String,
+    pub accepted_at: u64,
+}
+
+/// Answer to enqueueing a task.
+#[derive(Clone, Debug, Deserialize)]
+pub struct QueuedTask {
+    pub task_id: String,
+    pub sandbox_id: String,
+    pub status: String,
+    pub timeout_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TaskProgress {
+    #[serde(default)]
+    pub turns_completed: u32,
+    #[serde(default)]
+    pub current_tool: Option<String>,
+}
+
+/// A queued task as returned by the poll route and carried by its SSE
+/// `progress` and `done` events.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TaskRecord {
+    pub id: String,
+    pub sandbox_id: String,
+    /// `queued`, `running`, `completed`, `failed`, `interrupted` or
+    /// `cancelled`.
+    pub status: String,
+    #[serde(default)]
+    pub progress: TaskProgress,
+    #[serde(default)]
+    pub result: String,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub session_id: String,
+    #[serde(default)]
+    pub duration_ms: u64,
+    pub created_at: u64,
+    #[serde(default)]
+    pub completed_at: Option<u64>,
+}
+
+impl TaskRecord {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "completed" | "failed" | "interrupted" | "cancelled"
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct InjectSecretsRequest {
+    pub env_json: Map<String, Value>,
+    /// Vault source (`{ "path", ... }`) resolved by the operator.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault: Option<Value>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SecretsStatus {
+    #[serde(default)]
+    pub status: String,
+    pub sandbox_id: String,
+    pub credentials_available: bool,
+    #[serde(default)]
+    pub active_version: Option<u32>,
+}
+
+/// The sandbox's current user secrets.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Secrets {
+    pub sandbox_id: String,
+    pub env_json: Map<String, Value>,
+    pub credentials_available: bool,
+    #[serde(default)]
+    pub active_version: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SnapshotRequest {
+    /// `https://` or `s3://` upload target.
+    pub destination: String,
+    pub include_workspace: bool,
+    pub include_state: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AuditEvent {
+    pub id: String,
+    pub sandbox_id: String,
+    /// e.g. `ssh_key_provisioned`, `secrets_rotated`.
+    pub action: String,
+    pub actor: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub detail: Option<String>,
+    pub timestamp: u64,
+}
@@ -0,0 +1,156 @@
+//! `OperatorClient` against a mock operator speaking the real wire format.
+
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::StreamExt;
+use sandbox_client::{
+    ClientError, ExecRequest, LocalSigner, OperatorClient, Target, TaskRecord, TaskRequest,
+};
+use serde_json::{Value, json};
+
+const NONCE: &str = "nonce-1";
+const MESSAGE: &str = "Sign in to the sandbox operator\nnonce-1";
+const TOKEN: &str = "v4.local.test";
+
+fn authorized(headers: &HeaderMap) -> bool {
+    headers.get("authorization").and_then(|v| v.to_str().ok()) == Some("Bearer v4.local.test")
+}
+
+async fn session(State(address): State<String>, Json(body): Json<Value>) -> impl IntoResponse {
+    let signature = body["signature"].as_str().unwrap_or_default();
+    match sandbox_runtime::session_auth::verify_eip191_signature(MESSAGE, signature) {
+        Ok(recovered) if recovered == address && body["nonce"] == NONCE => (
+            StatusCode::OK,
+            Json(json!({ "token": TOKEN, "address": recovered, "expires_at": 1 })),
+        ),
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "Invalid signature" })),
+        ),
+    }
+}
+
+async fn exec(
+    headers: HeaderMap,
+    Path(sandbox_id): Path<String>,
+    Json(body): Json<Value>,
+) -> impl IntoResponse {
+    if !authorized(&headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "Missing session" })),
+        );
+    }
+    if sandbox_id != "sb-1" {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Sandbox not found", "code": "not_found" })),
+        );
+    }
+    let stdout = format!("ran {}", body["command"].as_str().unwrap_or_default());
+    (
+        StatusCode::OK,
+        Json(json!({ "exit_code": 0, "stdout": stdout, "stderr": "" })),
+    )
+}
+
+async fn enqueue(headers: HeaderMap, Json(body): Json<Value>) -> impl IntoResponse {
+    assert!(authorized(&headers));
+    assert_eq!(body["prompt"], "fix the tests");
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "task_id": "task-1",
+            "sandbox_id": "inst-1",
+            "status": "queued",
+            "timeout_ms": 60000,
+        })),
+    )
+}
+
+async fn task_stream(headers: HeaderMap) -> impl IntoResponse {
+    assert!(authorized(&headers));
+    let running = json!({ "id": "task-1", "sandbox_id": "inst-1", "status": "running",
+        "progress": { "turns_completed": 1 }, "created_at": 1 });
+    let done = json!({ "id": "task-1", "sandbox_id": "inst-1", "status": "completed",
+        "result": "all green", "created_at": 1, "completed_at": 2 });
+    (
+        [("content-type", "text/event-stream")],
+        format!(":\n\nevent: progress\ndata: {running}\n\nevent: done\ndata: {done}\n\n"),
+    )
+}
+
+async fn spawn_mock_operator(address: String) -> String {
+    let app = Router::new()
+        .route(
+            "/api/auth/challenge",
+            post(|| async { Json(json!({ "nonce": NONCE, "message": MESSAGE, "expires_at": 1 })) }),
+        )
+        .route("/api/auth/session", post(session))
+        .route("/api/sandboxes/{sandbox_id}/exec", post(exec))
+        .route("/api/sandbox/tasks", post(enqueue))
+        .route("/api/sandbox/tasks/{task_id}/stream", get(task_stream))
+        .with_state(address);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+    url
+}
+
+#[tokio::test]
+async fn login_exec_and_task_stream() {
+    let signer =
+        LocalSigner::from_hex("0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+            .unwrap();
+    let url = spawn_mock_operator(signer.address().to_string()).await;
+    let mut client = OperatorClient::new(url).unwrap();
+
+    let err = client
+        .exec(&Target::sandbox("sb-1"), &ExecRequest::new("ls"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ClientError::Auth(_)));
+
+    let session = client.login(&signer).await.unwrap();
+    assert_eq!(session.address, signer.address());
+    assert_eq!(client.token(), Some(TOKEN));
+
+    let out = client
+        .exec(&Target::sandbox("sb-1"), &ExecRequest::new("ls"))
+        .await
+        .unwrap();
+    assert_eq!((out.exit_code, out.stdout.as_str()), (0, "ran ls"));
+
+    match client
+        .exec(&Target::sandbox("sb-404"), &ExecRequest::new("ls"))
+        .await
+    {
+        Err(ClientError::Api { status, code, .. }) => {
+            assert_eq!(status, 404);
+            assert_eq!(code.as_deref(), Some("not_found"));
+        }
+        other => panic!("expected a 404, got {other:?}"),
+    }
+
+    let queued = client
+        .enqueue_task(&Target::Instance, &TaskRequest::new("fix the tests"))
+        .await
+        .unwrap();
+    assert_eq!(queued.task_id, "task-1");
+
+    let events: Vec<_> = client
+        .task_events(&Target::Instance, &queued.task_id)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    let events: Vec<_> = events.into_iter().map(Result::unwrap).collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].event, "progress");
+    let done: TaskRecord = events[1].json().unwrap();
+    assert!(done.is_terminal());
+    assert_eq!(done.result, "all green");
+}
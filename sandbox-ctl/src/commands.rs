@@ -0,0 +1,237 @@
+//! Subcommand implementations.
+
+use std::collections::HashSet;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use futures_util::StreamExt;
+use sandbox_client::jobs;
+use sandbox_client::{ExecRequest, SnapshotRequest, Target, TaskRecord, TaskRequest};
+use serde_json::Value;
+
+use crate::{Cli, Cmd, RotateCmd, WorkflowCmd};
+
+const LOG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+fn print_json(value: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn sandboxes(list: &Value) -> &[Value] {
+    list["sandboxes"].as_array().map_or(&[], Vec::as_slice)
+}
+
+pub(crate) async fn run(cli: Cli) -> Result<ExitCode> {
+    // Job calldata needs no session.
+    let job = match &cli.cmd {
+        Cmd::Workflow {
+            cmd:
+                WorkflowCmd::Trigger {
+                    workflow_id,
+                    service_id,
+                },
+        } => Some((jobs::workflow_trigger(*workflow_id), *service_id)),
+        Cmd::Workflow {
+            cmd:
+                WorkflowCmd::Cancel {
+                    workflow_id,
+                    service_id,
+                },
+        } => Some((jobs::workflow_cancel(*workflow_id), *service_id)),
+        _ => None,
+    };
+    if let Some((call, service_id)) = job {
+        return submit_job_hint(call, service_id);
+    }
+
+    let mut client = cli.conn.client().await?;
+    match cli.cmd {
+        Cmd::Login => {
+            println!("{}", client.token().unwrap_or_default());
+        }
+        Cmd::Logout => client.logout().await?,
+        Cmd::List => {
+            let list = client.list_sandboxes().await?;
+            println!("{:<40} {:<10} {:<24} IMAGE", "ID", "STATE", "NAME");
+            for sb in sandboxes(&list) {
+                println!(
+                    "{:<40} {:<10} {:<24} {}",
+                    sb["id"].as_str().unwrap_or_default(),
+                    sb["state"].as_str().unwrap_or_default(),
+                    sb["name"].as_str().unwrap_or_default(),
+                    sb["image"].as_str().unwrap_or_default(),
+                );
+            }
+        }
+        Cmd::Inspect { sandbox_id } => {
+            let list = client.list_sandboxes().await?;
+            let Some(sb) = sandboxes(&list).iter().find(|sb| sb["id"] == sandbox_id) else {
+                bail!("sandbox {sandbox_id} not found");
+            };
+            print_json(sb)?;
+        }
+        Cmd::Logs { target, follow } => {
+            let target = target.target();
+            let mut seen = HashSet::new();
+            loop {
+                let mut events = client.audit_events(&target).await?;
+                events.sort_by_key(|e| e.timestamp);
+                for event in events {
+                    if seen.insert(event.id.clone()) {
+                        println!(
+                            "{} {:<24} {} {}",
+                            event.timestamp,
+                            event.action,
+                            event.actor,
+                            event.detail.unwrap_or(event.username),
+                        );
+                    }
+                }
+                if !follow {
+                    break;
+                }
+                tokio::time::sleep(LOG_POLL_INTERVAL).await;
+            }
+        }
+        Cmd::Exec {
+            target,
+            cwd,
+            timeout_ms,
+            command,
+        } => {
+            let req = ExecRequest {
+                command: command.join(" "),
+                cwd: cwd.unwrap_or_default(),
+                timeout_ms,
+                ..Default::default()
+            };
+            let out = client.exec(&target.target(), &req).await?;
+            print!("{}", out.stdout);
+            eprint!("{}", out.stderr);
+            if let Some(overflow) = out.overflow_ref {
+                eprintln!("[output truncated; full output at {overflow}]");
+            }
+            return Ok(ExitCode::from(out.exit_code.min(255) as u8));
+        }
+        Cmd::Task {
+            target,
+            prompt,
+            model,
+            max_turns,
+            detach,
+        } => {
+            let target = target.target();
+            let req = TaskRequest {
+                prompt,
+                model: model.unwrap_or_default(),
+                max_turns,
+                ..Default::default()
+            };
+            let queued = client.enqueue_task(&target, &req).await?;
+            if detach {
+                println!("{}", queued.task_id);
+                return Ok(ExitCode::SUCCESS);
+            }
+            eprintln!("task {} queued", queued.task_id);
+            return follow_task(&client, &target, &queued.task_id).await;
+        }
+        Cmd::Events {
+            target,
+            task,
+            session,
+        } => {
+            let target = target.target();
+            let mut events = match (task, session) {
+                (Some(task_id), _) => client.task_events(&target, &task_id).await?.boxed(),
+                (None, Some(session_id)) => client.chat_events(&target, &session_id).await?.boxed(),
+                (None, None) => bail!("pass --task or --session"),
+            };
+            while let Some(event) = events.next().await {
+                let event = event?;
+                println!("{}: {}", event.event, event.data);
+            }
+        }
+        Cmd::Workflow { cmd } => match cmd {
+            WorkflowCmd::List => print_json(&client.list_workflows().await?)?,
+            WorkflowCmd::Show { workflow_id } => print_json(&client.workflow(workflow_id).await?)?,
+            WorkflowCmd::Trigger { .. } | WorkflowCmd::Cancel { .. } => unreachable!(),
+        },
+        Cmd::Snapshot {
+            target,
+            destination,
+            include_workspace,
+            include_state,
+        } => {
+            let req = SnapshotRequest {
+                destination,
+                include_workspace,
+                include_state,
+            };
+            print_json(&client.snapshot(&target.target(), &req).await?)?;
+        }
+        Cmd::Rotate { cmd } => match cmd {
+            RotateCmd::Secrets { target } => {
+                print_json(&client.run_secret_rotation(&target.target()).await?)?
+            }
+            RotateCmd::Session => {
+                // Without `--token` the client already signed in afresh.
+                if cli.conn.token.is_some() {
+                    let signer = cli.conn.signer()?;
+                    client.logout().await.context("revoking the old token")?;
+                    client.login(&signer).await?;
+                }
+                println!("{}", client.token().unwrap_or_default());
+            }
+        },
+        Cmd::Attestation { sandbox_id, nonce } => {
+            let report = client.attestation(&sandbox_id, nonce.as_deref()).await?;
+            print_json(&report)?;
+            let verdict = &report["verification"]["verdict"];
+            if verdict["verdict"] != "verified" {
+                eprintln!("attestation NOT verified by the operator: {verdict}");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Stream a queued task's progress to stderr and print its result.
+async fn follow_task(
+    client: &sandbox_client::OperatorClient,
+    target: &Target,
+    task_id: &str,
+) -> Result<ExitCode> {
+    let mut events = client.task_events(target, task_id).await?.boxed();
+    let mut turns = 0;
+    while let Some(event) = events.next().await {
+        let task: TaskRecord = event?.json()?;
+        if task.progress.turns_completed != turns {
+            turns = task.progress.turns_completed;
+            let tool = task.progress.current_tool.as_deref().unwrap_or("-");
+            eprintln!("  turn {turns} ({tool})");
+        }
+        if task.is_terminal() {
+            return Ok(match task.error {
+                None if task.status == "completed" => {
+                    println!("{}", task.result);
+                    ExitCode::SUCCESS
+                }
+                error => {
+                    eprintln!("task {}: {}", task.status, error.unwrap_or_default());
+                    ExitCode::FAILURE
+                }
+            });
+        }
+    }
+    bail!("event stream ended before task {task_id} finished")
+}
+
+fn submit_job_hint(call: jobs::JobCall, service_id: u64) -> Result<ExitCode> {
+    let calldata = format!("0x{}", hex::encode(call.calldata(service_id)));
+    println!("{calldata}");
+    eprintln!("send with: cast send $TANGLE_CONTRACT {calldata} --rpc-url $TANGLE_RPC_URL");
+    Ok(ExitCode::SUCCESS)
+}
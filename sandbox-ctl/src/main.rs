@@ -0,0 +1,207 @@
+//! `sandbox-ctl` — manage sandboxes through an operator's API.
+//!
+//! Authenticates with a blueprint keystore (`--keystore` / `KEYSTORE_URI`),
+//! a raw private key, or a saved session token, then drives the operator API
+//! through `sandbox-client`. Commands that mutate on-chain state (workflow
+//! trigger/cancel) print `submitJob` calldata to send with any wallet.
+
+mod commands;
+
+use std::process::ExitCode;
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, Parser, Subcommand};
+use sandbox_client::{LocalSigner, OperatorClient, Target};
+
+#[derive(Parser, Debug)]
+#[command(about = "Manage AI agent sandboxes through the operator API")]
+struct Cli {
+    #[command(flatten)]
+    conn: Connection,
+    #[command(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Args, Debug)]
+struct Connection {
+    /// Operator API base URL.
+    #[arg(
+        long,
+        env = "SANDBOX_OPERATOR_URL",
+        default_value = "http://127.0.0.1:9100"
+    )]
+    url: String,
+    /// Blueprint keystore holding the ECDSA key to sign in with.
+    #[arg(long, env = "KEYSTORE_URI")]
+    keystore: Option<String>,
+    /// Hex private key to sign in with (prefer `--keystore`).
+    #[arg(long, env = "SANDBOX_PRIVATE_KEY", hide_env_values = true)]
+    private_key: Option<String>,
+    /// Session token from `sandbox-ctl login`; skips signing in.
+    #[arg(long, env = "SANDBOX_SESSION_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+}
+
+/// `--sandbox <id>` targets a cloud-mode sandbox; without it, commands act
+/// on the caller's instance sandbox.
+#[derive(Args, Debug)]
+struct TargetArg {
+    #[arg(short, long)]
+    sandbox: Option<String>,
+}
+
+impl TargetArg {
+    fn target(&self) -> Target {
+        match &self.sandbox {
+            Some(id) => Target::sandbox(id),
+            None => Target::Instance,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Cmd {
+    /// Sign in and print a session token for `SANDBOX_SESSION_TOKEN`.
+    Login,
+    /// Revoke the current session token.
+    Logout,
+    /// List your sandboxes.
+    List,
+    /// Show one sandbox.
+    Inspect { sandbox_id: String },
+    /// Print the sandbox's audit log (SSH access, secret changes).
+    Logs {
+        #[command(flatten)]
+        target: TargetArg,
+        /// Keep polling for new events.
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// Run a command and exit with its exit code.
+    Exec {
+        #[command(flatten)]
+        target: TargetArg,
+        #[arg(long)]
+        cwd: Option<String>,
+        #[arg(long, default_value_t = 0)]
+        timeout_ms: u64,
+        #[arg(required = true, trailing_var_arg = true)]
+        command: Vec<String>,
+    },
+    /// Queue an agent task and follow its progress.
+    Task {
+        #[command(flatten)]
+        target: TargetArg,
+        prompt: String,
+        #[arg(long)]
+        model: Option<String>,
+        #[arg(long, default_value_t = 0)]
+        max_turns: u64,
+        /// Print the task ID and return without waiting.
+        #[arg(long)]
+        detach: bool,
+    },
+    /// Stream the events of a queued task or chat session.
+    Events {
+        #[command(flatten)]
+        target: TargetArg,
+        #[arg(long, conflicts_with = "session", required_unless_present = "session")]
+        task: Option<String>,
+        #[arg(long)]
+        session: Option<String>,
+    },
+    /// Inspect workflows or build their trigger/cancel jobs.
+    Workflow {
+        #[command(subcommand)]
+        cmd: WorkflowCmd,
+    },
+    /// Upload a snapshot of the sandbox.
+    Snapshot {
+        #[command(flatten)]
+        target: TargetArg,
+        /// `https://` or `s3://` destination.
+        destination: String,
+        #[arg(long)]
+        include_workspace: bool,
+        #[arg(long)]
+        include_state: bool,
+    },
+    /// Rotate credentials.
+    Rotate {
+        #[command(subcommand)]
+        cmd: RotateCmd,
+    },
+    /// Fetch and verify a TEE sandbox's attestation.
+    Attestation {
+        sandbox_id: String,
+        /// Hex nonce (32-64 bytes) to bind the report to.
+        #[arg(long)]
+        nonce: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum WorkflowCmd {
+    List,
+    Show {
+        workflow_id: u64,
+    },
+    /// Print `submitJob` calldata that runs the workflow now.
+    Trigger {
+        workflow_id: u64,
+        #[arg(long, env = "TANGLE_SERVICE_ID")]
+        service_id: u64,
+    },
+    /// Print `submitJob` calldata that cancels the workflow.
+    Cancel {
+        workflow_id: u64,
+        #[arg(long, env = "TANGLE_SERVICE_ID")]
+        service_id: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RotateCmd {
+    /// Rotate the sandbox's secrets now under its rotation policy.
+    Secrets {
+        #[command(flatten)]
+        target: TargetArg,
+    },
+    /// Sign in again and revoke the current session token.
+    Session,
+}
+
+impl Connection {
+    fn signer(&self) -> Result<LocalSigner> {
+        if let Some(keystore) = &self.keystore {
+            return LocalSigner::from_keystore(keystore).context("loading keystore");
+        }
+        if let Some(key) = &self.private_key {
+            return LocalSigner::from_hex(key).context("parsing private key");
+        }
+        bail!("no credentials: pass --keystore, --private-key or --token")
+    }
+
+    /// Client with a session: the saved token, or a fresh sign-in.
+    async fn client(&self) -> Result<OperatorClient> {
+        let client = OperatorClient::new(&self.url)?;
+        if let Some(token) = &self.token {
+            return Ok(client.with_token(token));
+        }
+        let mut client = client;
+        client.login(&self.signer()?).await.context("signing in")?;
+        Ok(client)
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match commands::run(cli).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
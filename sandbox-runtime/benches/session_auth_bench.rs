@@ -83,7 +83,7 @@ fn bench_exchange_signature_for_token(c: &mut Criterion) {
             if rand::random::<u8>().is_multiple_of(64) {
                 clear_all_for_testing();
             }
-            let challenge = create_challenge().expect("challenge");
+            let challenge = create_challenge(None, None).expect("challenge");
             let sig = sign_eip191(&signing_key, &challenge.message);
             let token = exchange_signature_for_token(&challenge.nonce, &sig).expect("exchange");
             black_box(token);
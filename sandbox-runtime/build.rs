@@ -0,0 +1,10 @@
+fn main() {
+    // gRPC stubs are generated only with the `grpc` feature (needs `protoc`).
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/operator.proto");
+        tonic_build::configure()
+            .compile_protos(&["proto/operator.proto"], &["proto"])
+            .expect("failed to compile proto/operator.proto");
+    }
+}
@@ -0,0 +1,203 @@
+//! Per-sandbox agent run limiter.
+//!
+//! A sidecar degrades quickly under many simultaneous agent runs, so each
+//! sandbox admits at most [`AGENT_RUN_CONCURRENCY_ENV`] runs at once
+//! (default [`DEFAULT_AGENT_RUN_CONCURRENCY`]). Further runs wait in a FIFO
+//! queue of at most [`AGENT_RUN_QUEUE_DEPTH_ENV`] (default
+//! [`DEFAULT_AGENT_RUN_QUEUE_DEPTH`]); past that, [`reserve_agent_run`]
+//! fails with a "busy" `Unavailable` error (`CAPACITY` for jobs,
+//! `503 SANDBOX_BUSY` over HTTP) instead of piling more work onto the
+//! sidecar.
+//!
+//! Reserving is synchronous so callers can reject a request before accepting
+//! it; the returned [`AgentRunSlot`] is awaited with [`AgentRunSlot::ready`]
+//! before the run starts and frees its place when dropped. The operator's own
+//! task queue waits without the queue bound ([`acquire_agent_run`]), since
+//! its depth is already capped by `TASK_QUEUE_CONCURRENCY`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::{Result, SandboxError};
+
+/// Env var capping concurrent agent runs per sandbox.
+pub const AGENT_RUN_CONCURRENCY_ENV: &str = "AGENT_RUN_CONCURRENCY";
+/// Concurrent runs per sandbox when [`AGENT_RUN_CONCURRENCY_ENV`] is unset.
+pub const DEFAULT_AGENT_RUN_CONCURRENCY: usize = 2;
+/// Env var capping agent runs waiting per sandbox (0 = no queueing).
+pub const AGENT_RUN_QUEUE_DEPTH_ENV: &str = "AGENT_RUN_QUEUE_DEPTH";
+/// Waiting runs per sandbox when [`AGENT_RUN_QUEUE_DEPTH_ENV`] is unset.
+pub const DEFAULT_AGENT_RUN_QUEUE_DEPTH: usize = 8;
+
+struct Limiter {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+    waiting: AtomicUsize,
+}
+
+static LIMITERS: Lazy<Mutex<HashMap<String, Arc<Limiter>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn env_usize(name: &str) -> Option<usize> {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+}
+
+/// Configured concurrent runs per sandbox.
+pub fn agent_run_concurrency() -> usize {
+    static LIMIT: Lazy<usize> = Lazy::new(|| {
+        env_usize(AGENT_RUN_CONCURRENCY_ENV)
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_AGENT_RUN_CONCURRENCY)
+    });
+    *LIMIT
+}
+
+/// Configured waiting runs per sandbox.
+pub fn agent_run_queue_depth() -> usize {
+    static DEPTH: Lazy<usize> =
+        Lazy::new(|| env_usize(AGENT_RUN_QUEUE_DEPTH_ENV).unwrap_or(DEFAULT_AGENT_RUN_QUEUE_DEPTH));
+    *DEPTH
+}
+
+/// A place in a sandbox's agent run limit: either a running slot or a
+/// position in its queue.
+pub struct AgentRunSlot {
+    sandbox_id: String,
+    limiter: Arc<Limiter>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl AgentRunSlot {
+    /// Wait until the run may start. Immediate when a slot was free when it
+    /// was reserved.
+    pub async fn ready(&mut self) {
+        if self.permit.is_some() {
+            return;
+        }
+        if let Ok(permit) = self.limiter.semaphore.clone().acquire_owned().await {
+            self.permit = Some(permit);
+            self.limiter.waiting.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for AgentRunSlot {
+    fn drop(&mut self) {
+        if self.permit.take().is_none() {
+            self.limiter.waiting.fetch_sub(1, Ordering::Relaxed);
+        }
+        // Forget the limiter once nothing else holds it; clones are taken
+        // under the same lock, so the count cannot grow behind our back.
+        let mut limiters = LIMITERS.lock().unwrap_or_else(|e| e.into_inner());
+        if Arc::strong_count(&self.limiter) == 2 {
+            limiters.remove(&self.sandbox_id);
+        }
+    }
+}
+
+fn limiter(sandbox_id: &str, limit: usize) -> Arc<Limiter> {
+    LIMITERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(sandbox_id.to_string())
+        .or_insert_with(|| {
+            Arc::new(Limiter {
+                limit,
+                semaphore: Arc::new(Semaphore::new(limit)),
+                waiting: AtomicUsize::new(0),
+            })
+        })
+        .clone()
+}
+
+fn reserve(sandbox_id: &str, limit: usize, queue_depth: usize) -> Result<AgentRunSlot> {
+    let limiter = limiter(sandbox_id, limit);
+    let permit = limiter.semaphore.clone().try_acquire_owned().ok();
+    if permit.is_none() {
+        let queued = limiter.waiting.fetch_add(1, Ordering::Relaxed);
+        if queued >= queue_depth {
+            limiter.waiting.fetch_sub(1, Ordering::Relaxed);
+            return Err(SandboxError::Unavailable(format!(
+                "Sandbox {sandbox_id} is busy: {} agent runs in progress and {queued} queued; retry later",
+                limiter.limit
+            )));
+        }
+    }
+    Ok(AgentRunSlot {
+        sandbox_id: sandbox_id.to_string(),
+        limiter,
+        permit,
+    })
+}
+
+/// Reserve an agent run on `sandbox_id`: a running slot when one is free, a
+/// queue position otherwise, or a busy error when the queue is full.
+pub fn reserve_agent_run(sandbox_id: &str) -> Result<AgentRunSlot> {
+    reserve(sandbox_id, agent_run_concurrency(), agent_run_queue_depth())
+}
+
+/// Wait for a running slot on `sandbox_id`, however long the queue.
+pub async fn acquire_agent_run(sandbox_id: &str) -> AgentRunSlot {
+    let limiter = limiter(sandbox_id, agent_run_concurrency());
+    limiter.waiting.fetch_add(1, Ordering::Relaxed);
+    let mut slot = AgentRunSlot {
+        sandbox_id: sandbox_id.to_string(),
+        limiter,
+        permit: None,
+    };
+    slot.ready().await;
+    slot
+}
+
+/// Runs in progress and waiting on `sandbox_id`.
+pub fn agent_run_load(sandbox_id: &str) -> (usize, usize) {
+    let limiters = LIMITERS.lock().unwrap_or_else(|e| e.into_inner());
+    limiters.get(sandbox_id).map_or((0, 0), |l| {
+        (
+            l.limit - l.semaphore.available_permits(),
+            l.waiting.load(Ordering::Relaxed),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn runs_past_the_limit_queue_then_fail_busy() {
+        let sb = "sb-agent-runs-1";
+        let first = reserve(sb, 1, 1).unwrap();
+        let mut queued = reserve(sb, 1, 1).unwrap();
+        assert_eq!(agent_run_load(sb), (1, 1));
+        let err = reserve(sb, 1, 1).err().unwrap();
+        assert!(err.to_string().contains("busy"), "got: {err}");
+
+        drop(first);
+        tokio::time::timeout(std::time::Duration::from_secs(1), queued.ready())
+            .await
+            .unwrap();
+        assert_eq!(agent_run_load(sb), (1, 0));
+        drop(queued);
+        assert_eq!(agent_run_load(sb), (0, 0));
+        assert!(LIMITERS.lock().unwrap().get(sb).is_none());
+    }
+
+    #[tokio::test]
+    async fn dropping_a_queued_slot_frees_its_place() {
+        let sb = "sb-agent-runs-2";
+        let _running = reserve(sb, 1, 1).unwrap();
+        let mut queued = reserve(sb, 1, 1).unwrap();
+        let wait = tokio::time::timeout(std::time::Duration::from_millis(20), queued.ready());
+        assert!(wait.await.is_err());
+        drop(queued);
+        assert_eq!(agent_run_load(sb), (1, 0));
+        assert!(reserve(sb, 1, 1).is_ok());
+    }
+}
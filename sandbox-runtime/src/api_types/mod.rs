@@ -14,7 +14,7 @@ const MAX_SSH_KEY_LEN: usize = crate::ssh_validation::MAX_SSH_KEY_LEN;
 const MAX_USERNAME_LEN: usize = crate::ssh_validation::MAX_USERNAME_LEN;
 
 /// Maximum number of secret keys.
-const MAX_SECRET_KEYS: usize = 256;
+pub const MAX_SECRET_KEYS: usize = 256;
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Validation helpers
@@ -48,7 +48,7 @@ fn validate_ssh_public_key(key: &str) -> Result<(), String> {
 // Exec
 // ─────────────────────────────────────────────────────────────────────────────
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
 pub struct ExecApiRequest {
     pub command: String,
     #[serde(default)]
@@ -59,11 +59,25 @@ pub struct ExecApiRequest {
     pub env_json: String,
     #[serde(default)]
     pub timeout_ms: u64,
+    /// Instance slot to target on `/api/sandbox/*` routes (empty = `main`).
+    /// Ignored on per-sandbox routes.
+    #[serde(default)]
+    pub slot: String,
+    /// Text piped to the command's stdin (see `exec_input`).
+    #[serde(default)]
+    pub stdin: String,
+    /// Sandbox file piped to stdin instead; exclusive with `stdin`.
+    #[serde(default)]
+    pub stdin_file: String,
+    /// Exec preset of the sandbox supplying defaults (see `exec_presets`).
+    #[serde(default)]
+    pub preset: String,
 }
 
 impl ExecApiRequest {
     pub fn validate(&self) -> Result<(), String> {
-        validate_required("command", &self.command, MAX_TEXT_LEN)
+        validate_required("command", &self.command, MAX_TEXT_LEN)?;
+        crate::exec_input::validate_exec_input(&self.stdin, &self.stdin_file)
     }
 }
 
@@ -128,6 +142,10 @@ pub struct PromptApiRequest {
     pub context_json: String,
     #[serde(default)]
     pub timeout_ms: u64,
+    /// Instance slot to target on `/api/sandbox/*` routes (empty = `main`).
+    /// Ignored on per-sandbox routes.
+    #[serde(default)]
+    pub slot: String,
 }
 
 impl PromptApiRequest {
@@ -141,6 +159,26 @@ pub struct ExecApiResponse {
     pub exit_code: u32,
     pub stdout: String,
     pub stderr: String,
+    /// True when stdout or stderr was cut at `EXEC_OUTPUT_MAX_BYTES`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Overflow artifact holding the full output (see `exec_output`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overflow_ref: Option<String>,
+}
+
+impl ExecApiResponse {
+    /// Apply the exec output cap, storing overflow under `sandbox_id`.
+    pub fn capped(self, sandbox_id: &str) -> Self {
+        let output = crate::exec_output::cap_exec_output(sandbox_id, self.stdout, self.stderr);
+        Self {
+            exit_code: self.exit_code,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            truncated: output.truncated,
+            overflow_ref: output.overflow_ref,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -156,11 +194,16 @@ pub struct PromptApiResponse {
 // Task
 // ─────────────────────────────────────────────────────────────────────────────
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct TaskApiRequest {
     pub prompt: String,
     #[serde(default)]
     pub session_id: String,
+    /// Operator-tracked session to run in instead of `session_id` (see
+    /// `named_sessions`). Queued tasks only; chat task routes track their
+    /// sessions themselves.
+    #[serde(default)]
+    pub session_name: String,
     #[serde(default)]
     pub max_turns: u64,
     #[serde(default)]
@@ -171,6 +214,10 @@ pub struct TaskApiRequest {
     pub context_json: String,
     #[serde(default)]
     pub timeout_ms: u64,
+    /// Instance slot to target on `/api/sandbox/*` routes (empty = `main`).
+    /// Ignored on per-sandbox routes.
+    #[serde(default)]
+    pub slot: String,
 }
 
 impl TaskApiRequest {
@@ -216,6 +263,10 @@ pub struct SshProvisionApiRequest {
     #[serde(default)]
     pub username: Option<String>,
     pub public_key: String,
+    /// Revoke the key automatically after this many seconds. Omitted or `0`
+    /// means no expiry.
+    #[serde(default)]
+    pub expires_in_seconds: Option<u64>,
 }
 
 impl SshProvisionApiRequest {
@@ -225,6 +276,15 @@ impl SshProvisionApiRequest {
         {
             validate_username(username)?;
         }
+        if self
+            .expires_in_seconds
+            .is_some_and(|ttl| ttl > crate::runtime::MAX_SSH_KEY_TTL_SECS)
+        {
+            return Err(format!(
+                "expires_in_seconds must be at most {}",
+                crate::runtime::MAX_SSH_KEY_TTL_SECS
+            ));
+        }
         validate_ssh_public_key(&self.public_key)
     }
 }
@@ -252,6 +312,46 @@ pub struct SshApiResponse {
     pub success: bool,
     pub username: String,
     pub result: serde_json::Value,
+    /// Unix timestamp at which a provisioned key is revoked, if it has a TTL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+/// Response for `GET .../ssh/keys`: every key in the login user's
+/// `authorized_keys`, plus recorded keys no longer installed.
+#[derive(Debug, Serialize)]
+pub struct SshKeysApiResponse {
+    pub success: bool,
+    pub username: String,
+    pub keys: Vec<crate::runtime::SshKeyInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogApiResponse {
+    pub success: bool,
+    pub events: Vec<crate::audit_log::AuditEvent>,
+}
+
+/// Most replay log entries returned by one export request.
+pub const MAX_JOB_REPLAY_EXPORT: usize = 10_000;
+
+/// Query for `GET /api/operator/audit/jobs`.
+#[derive(Debug, Default, Deserialize)]
+pub struct JobReplayQuery {
+    /// Return entries with a sequence number above this one.
+    #[serde(default)]
+    pub after: u64,
+    /// Most entries to return (default and cap [`MAX_JOB_REPLAY_EXPORT`]).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl JobReplayQuery {
+    pub fn effective_limit(&self) -> usize {
+        self.limit
+            .unwrap_or(MAX_JOB_REPLAY_EXPORT)
+            .clamp(1, MAX_JOB_REPLAY_EXPORT)
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -289,7 +389,7 @@ pub fn validate_secrets_map(
             return Err(format!("secret value for '{key}' exceeds max size (64 KB)"));
         }
     }
-    Ok(())
+    crate::secret_provisioning::validate_secret_refs(map)
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -304,224 +404,99 @@ pub struct LifecycleApiResponse {
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
-// Tests
+// Token rotation (no request body needed)
 // ─────────────────────────────────────────────────────────────────────────────
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
-
-    // ── validate_required ───────────────────────────────────────────────
-
-    #[test]
-    fn validate_required_empty() {
-        assert!(validate_required("f", "", 100).is_err());
-    }
-
-    #[test]
-    fn validate_required_whitespace_only() {
-        assert!(validate_required("f", "   \t\n", 100).is_err());
-    }
-
-    #[test]
-    fn validate_required_at_limit() {
-        let s = "a".repeat(100);
-        assert!(validate_required("f", &s, 100).is_ok());
-    }
-
-    #[test]
-    fn validate_required_over_limit() {
-        let s = "a".repeat(101);
-        assert!(validate_required("f", &s, 100).is_err());
-    }
-
-    #[test]
-    fn validate_required_valid() {
-        assert!(validate_required("f", "hello", 100).is_ok());
-    }
-
-    // ── validate_ssh_public_key ─────────────────────────────────────────
-
-    #[test]
-    fn ssh_key_empty() {
-        assert!(validate_ssh_public_key("").is_err());
-    }
-
-    #[test]
-    fn ssh_key_too_long() {
-        let key = format!("ssh-ed25519 {}", "A".repeat(MAX_SSH_KEY_LEN));
-        assert!(validate_ssh_public_key(&key).is_err());
-    }
-
-    #[test]
-    fn ssh_key_invalid_prefix() {
-        assert!(validate_ssh_public_key("pgp-key AAAA").is_err());
-    }
-
-    #[test]
-    fn ssh_key_missing_data() {
-        assert!(validate_ssh_public_key("ssh-ed25519").is_err());
-    }
-
-    #[test]
-    fn ssh_key_valid_ed25519() {
-        assert!(validate_ssh_public_key("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest").is_ok());
-    }
-
-    #[test]
-    fn ssh_key_valid_rsa() {
-        assert!(validate_ssh_public_key("ssh-rsa AAAAB3NzaC1yc2EAAAATest user@host").is_ok());
-    }
-
-    // ── validate_username ───────────────────────────────────────────────
-
-    #[test]
-    fn username_empty_defaults_ok() {
-        assert!(validate_username("").is_ok());
-    }
-
-    #[test]
-    fn username_too_long() {
-        let name = "a".repeat(MAX_USERNAME_LEN + 1);
-        assert!(validate_username(&name).is_err());
-    }
-
-    #[test]
-    fn username_invalid_at_sign() {
-        assert!(validate_username("user@host").is_err());
-    }
-
-    #[test]
-    fn username_invalid_spaces() {
-        assert!(validate_username("my user").is_err());
-    }
-
-    #[test]
-    fn username_valid_with_special() {
-        assert!(validate_username("my-user_1.0").is_ok());
-    }
+/// Returned once, to the authenticated owner; the previous token no longer
+/// authenticates against the sidecar.
+#[derive(Debug, Serialize)]
+pub struct TokenRotateApiResponse {
+    pub success: bool,
+    pub sandbox_id: String,
+    pub sidecar_url: String,
+    pub token: String,
+}
 
-    #[test]
-    fn username_at_limit() {
-        let name = "a".repeat(MAX_USERNAME_LEN);
-        assert!(validate_username(&name).is_ok());
-    }
+// ─────────────────────────────────────────────────────────────────────────────
+// File browser
+// ─────────────────────────────────────────────────────────────────────────────
 
-    // ── validate_secrets_map ────────────────────────────────────────────
+/// Maximum length of a workspace path in a file listing request.
+const MAX_FILE_PATH_LEN: usize = 4096;
 
-    #[test]
-    fn secrets_empty_map() {
-        let map = serde_json::Map::new();
-        assert!(validate_secrets_map(&map).is_err());
-    }
+#[derive(Debug, Deserialize, Default)]
+pub struct FileListQuery {
+    /// Directory to list, relative to the sidecar's workspace root.
+    #[serde(default)]
+    pub path: String,
+}
 
-    #[test]
-    fn secrets_too_many_keys() {
-        let mut map = serde_json::Map::new();
-        for i in 0..=MAX_SECRET_KEYS {
-            map.insert(format!("key{i}"), json!("val"));
+impl FileListQuery {
+    /// The requested directory as a normalized workspace-relative path
+    /// (`""` is the workspace root). Absolute paths, `..` components and NUL
+    /// bytes are rejected so a listing can never leave the workspace.
+    pub fn normalized_path(&self) -> Result<String, String> {
+        if self.path.len() > MAX_FILE_PATH_LEN {
+            return Err(format!(
+                "path exceeds maximum length ({MAX_FILE_PATH_LEN} bytes)"
+            ));
         }
-        assert!(validate_secrets_map(&map).is_err());
-    }
-
-    #[test]
-    fn secrets_empty_key() {
-        let mut map = serde_json::Map::new();
-        map.insert(String::new(), json!("val"));
-        assert!(validate_secrets_map(&map).is_err());
-    }
-
-    #[test]
-    fn secrets_key_too_long() {
-        let mut map = serde_json::Map::new();
-        map.insert("k".repeat(257), json!("val"));
-        assert!(validate_secrets_map(&map).is_err());
-    }
-
-    #[test]
-    fn secrets_value_too_large() {
-        let mut map = serde_json::Map::new();
-        map.insert("key".into(), json!("x".repeat(64 * 1024 + 1)));
-        assert!(validate_secrets_map(&map).is_err());
-    }
-
-    #[test]
-    fn secrets_valid_map() {
-        let mut map = serde_json::Map::new();
-        map.insert("API_KEY".into(), json!("sk-test123"));
-        map.insert("DB_URL".into(), json!("postgres://localhost/db"));
-        assert!(validate_secrets_map(&map).is_ok());
-    }
-
-    // ── Request validate() ──────────────────────────────────────────────
-
-    #[test]
-    fn exec_request_empty_command() {
-        let req = ExecApiRequest {
-            command: String::new(),
-            session_id: String::new(),
-            cwd: String::new(),
-            env_json: String::new(),
-            timeout_ms: 0,
-        };
-        assert!(req.validate().is_err());
-    }
-
-    #[test]
-    fn exec_request_valid() {
-        let req = ExecApiRequest {
-            command: "ls -la".into(),
-            session_id: String::new(),
-            cwd: String::new(),
-            env_json: String::new(),
-            timeout_ms: 0,
-        };
-        assert!(req.validate().is_ok());
+        if self.path.contains('\0') {
+            return Err("path must not contain NUL bytes".into());
+        }
+        if self.path.starts_with('/') {
+            return Err("path must be relative to the workspace root".into());
+        }
+        let mut parts = Vec::new();
+        for part in self.path.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => return Err("path must not contain '..' components".into()),
+                part => parts.push(part),
+            }
+        }
+        Ok(parts.join("/"))
     }
+}
 
-    #[test]
-    fn ssh_provision_invalid_key() {
-        let req = SshProvisionApiRequest {
-            username: Some("agent".into()),
-            public_key: "not-a-key".into(),
-        };
-        assert!(req.validate().is_err());
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileEntryKind {
+    File,
+    Directory,
+    Symlink,
+    Other,
+}
 
-    #[test]
-    fn ssh_provision_invalid_username() {
-        let req = SshProvisionApiRequest {
-            username: Some("bad user!".into()),
-            public_key: "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest".into(),
-        };
-        assert!(req.validate().is_err());
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub name: String,
+    /// Workspace-relative path of the entry, usable as the next `path` query.
+    #[serde(default)]
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: FileEntryKind,
+    /// Size in bytes (of the link itself for symlinks).
+    #[serde(default)]
+    pub size: u64,
+    /// Last modification time, unix seconds.
+    #[serde(default)]
+    pub mtime: u64,
+}
 
-    #[test]
-    fn ssh_provision_valid() {
-        let req = SshProvisionApiRequest {
-            username: Some("agent".into()),
-            public_key: "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest".into(),
-        };
-        assert!(req.validate().is_ok());
-    }
+#[derive(Debug, Serialize)]
+pub struct FileListApiResponse {
+    pub success: bool,
+    pub path: String,
+    /// Directories first, then files, each sorted by name.
+    pub entries: Vec<FileEntry>,
+    /// Whether the directory held more entries than were returned.
+    pub truncated: bool,
+}
 
-    #[test]
-    fn ssh_provision_blank_username_is_allowed() {
-        let req = SshProvisionApiRequest {
-            username: Some("   ".into()),
-            public_key: "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest".into(),
-        };
-        assert!(req.validate().is_ok());
-    }
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
 
-    #[test]
-    fn ssh_provision_missing_username_is_allowed() {
-        let req = SshProvisionApiRequest {
-            username: None,
-            public_key: "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest".into(),
-        };
-        assert!(req.validate().is_ok());
-    }
-}
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,276 @@
+use super::*;
+use serde_json::json;
+
+// ── validate_required ───────────────────────────────────────────────
+
+#[test]
+fn validate_required_empty() {
+    assert!(validate_required("f", "", 100).is_err());
+}
+
+#[test]
+fn validate_required_whitespace_only() {
+    assert!(validate_required("f", "   \t\n", 100).is_err());
+}
+
+#[test]
+fn validate_required_at_limit() {
+    let s = "a".repeat(100);
+    assert!(validate_required("f", &s, 100).is_ok());
+}
+
+#[test]
+fn validate_required_over_limit() {
+    let s = "a".repeat(101);
+    assert!(validate_required("f", &s, 100).is_err());
+}
+
+#[test]
+fn validate_required_valid() {
+    assert!(validate_required("f", "hello", 100).is_ok());
+}
+
+// ── validate_ssh_public_key ─────────────────────────────────────────
+
+#[test]
+fn ssh_key_empty() {
+    assert!(validate_ssh_public_key("").is_err());
+}
+
+#[test]
+fn ssh_key_too_long() {
+    let key = format!("ssh-ed25519 {}", "A".repeat(MAX_SSH_KEY_LEN));
+    assert!(validate_ssh_public_key(&key).is_err());
+}
+
+#[test]
+fn ssh_key_invalid_prefix() {
+    assert!(validate_ssh_public_key("pgp-key AAAA").is_err());
+}
+
+#[test]
+fn ssh_key_missing_data() {
+    assert!(validate_ssh_public_key("ssh-ed25519").is_err());
+}
+
+#[test]
+fn ssh_key_valid_ed25519() {
+    assert!(validate_ssh_public_key("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest").is_ok());
+}
+
+#[test]
+fn ssh_key_valid_rsa() {
+    assert!(validate_ssh_public_key("ssh-rsa AAAAB3NzaC1yc2EAAAATest user@host").is_ok());
+}
+
+// ── validate_username ───────────────────────────────────────────────
+
+#[test]
+fn username_empty_defaults_ok() {
+    assert!(validate_username("").is_ok());
+}
+
+#[test]
+fn username_too_long() {
+    let name = "a".repeat(MAX_USERNAME_LEN + 1);
+    assert!(validate_username(&name).is_err());
+}
+
+#[test]
+fn username_invalid_at_sign() {
+    assert!(validate_username("user@host").is_err());
+}
+
+#[test]
+fn username_invalid_spaces() {
+    assert!(validate_username("my user").is_err());
+}
+
+#[test]
+fn username_valid_with_special() {
+    assert!(validate_username("my-user_1.0").is_ok());
+}
+
+#[test]
+fn username_at_limit() {
+    let name = "a".repeat(MAX_USERNAME_LEN);
+    assert!(validate_username(&name).is_ok());
+}
+
+// ── validate_secrets_map ────────────────────────────────────────────
+
+#[test]
+fn secrets_empty_map() {
+    let map = serde_json::Map::new();
+    assert!(validate_secrets_map(&map).is_err());
+}
+
+#[test]
+fn secrets_too_many_keys() {
+    let mut map = serde_json::Map::new();
+    for i in 0..=MAX_SECRET_KEYS {
+        map.insert(format!("key{i}"), json!("val"));
+    }
+    assert!(validate_secrets_map(&map).is_err());
+}
+
+#[test]
+fn secrets_empty_key() {
+    let mut map = serde_json::Map::new();
+    map.insert(String::new(), json!("val"));
+    assert!(validate_secrets_map(&map).is_err());
+}
+
+#[test]
+fn secrets_key_too_long() {
+    let mut map = serde_json::Map::new();
+    map.insert("k".repeat(257), json!("val"));
+    assert!(validate_secrets_map(&map).is_err());
+}
+
+#[test]
+fn secrets_value_too_large() {
+    let mut map = serde_json::Map::new();
+    map.insert("key".into(), json!("x".repeat(64 * 1024 + 1)));
+    assert!(validate_secrets_map(&map).is_err());
+}
+
+#[test]
+fn secrets_valid_map() {
+    let mut map = serde_json::Map::new();
+    map.insert("API_KEY".into(), json!("sk-test123"));
+    map.insert("DB_URL".into(), json!("postgres://localhost/db"));
+    assert!(validate_secrets_map(&map).is_ok());
+}
+
+// ── Request validate() ──────────────────────────────────────────────
+
+#[test]
+fn exec_request_empty_command() {
+    let req = ExecApiRequest {
+        command: String::new(),
+        session_id: String::new(),
+        cwd: String::new(),
+        env_json: String::new(),
+        timeout_ms: 0,
+        slot: String::new(),
+        stdin: String::new(),
+        stdin_file: String::new(),
+        preset: String::new(),
+    };
+    assert!(req.validate().is_err());
+}
+
+#[test]
+fn exec_request_valid() {
+    let req = ExecApiRequest {
+        command: "ls -la".into(),
+        session_id: String::new(),
+        cwd: String::new(),
+        env_json: String::new(),
+        timeout_ms: 0,
+        slot: String::new(),
+        stdin: String::new(),
+        stdin_file: String::new(),
+        preset: String::new(),
+    };
+    assert!(req.validate().is_ok());
+}
+
+#[test]
+fn exec_request_rejects_stdin_with_stdin_file() {
+    let req = ExecApiRequest {
+        command: "psql".into(),
+        session_id: String::new(),
+        cwd: String::new(),
+        env_json: String::new(),
+        timeout_ms: 0,
+        slot: String::new(),
+        stdin: "SELECT 1;".into(),
+        stdin_file: "query.sql".into(),
+        preset: String::new(),
+    };
+    assert!(req.validate().is_err());
+}
+
+#[test]
+fn ssh_provision_invalid_key() {
+    let req = SshProvisionApiRequest {
+        username: Some("agent".into()),
+        public_key: "not-a-key".into(),
+        expires_in_seconds: None,
+    };
+    assert!(req.validate().is_err());
+}
+
+#[test]
+fn ssh_provision_invalid_username() {
+    let req = SshProvisionApiRequest {
+        username: Some("bad user!".into()),
+        public_key: "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest".into(),
+        expires_in_seconds: None,
+    };
+    assert!(req.validate().is_err());
+}
+
+#[test]
+fn ssh_provision_valid() {
+    let req = SshProvisionApiRequest {
+        username: Some("agent".into()),
+        public_key: "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest".into(),
+        expires_in_seconds: None,
+    };
+    assert!(req.validate().is_ok());
+}
+
+#[test]
+fn ssh_provision_rejects_ttl_over_max() {
+    let mut req = SshProvisionApiRequest {
+        username: None,
+        public_key: "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest".into(),
+        expires_in_seconds: Some(crate::runtime::MAX_SSH_KEY_TTL_SECS + 1),
+    };
+    assert!(req.validate().is_err());
+    req.expires_in_seconds = Some(3600);
+    assert!(req.validate().is_ok());
+}
+
+#[test]
+fn ssh_provision_blank_username_is_allowed() {
+    let req = SshProvisionApiRequest {
+        username: Some("   ".into()),
+        public_key: "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest".into(),
+        expires_in_seconds: None,
+    };
+    assert!(req.validate().is_ok());
+}
+
+#[test]
+fn ssh_provision_missing_username_is_allowed() {
+    let req = SshProvisionApiRequest {
+        username: None,
+        public_key: "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAITest".into(),
+        expires_in_seconds: None,
+    };
+    assert!(req.validate().is_ok());
+}
+
+// ── File browser ───────────────────────────────────────────────────
+
+#[test]
+fn file_list_path_is_normalized_within_workspace() {
+    let path = |p: &str| {
+        FileListQuery {
+            path: p.to_string(),
+        }
+        .normalized_path()
+    };
+    assert_eq!(path("").unwrap(), "");
+    assert_eq!(path(".").unwrap(), "");
+    assert_eq!(path("src//lib/./").unwrap(), "src/lib");
+    assert!(path("../etc").is_err());
+    assert!(path("src/../../etc").is_err());
+    assert!(path("/etc/passwd").is_err());
+    assert!(path("src\0").is_err());
+    assert!(path(&"a".repeat(MAX_FILE_PATH_LEN + 1)).is_err());
+}
@@ -0,0 +1,278 @@
+//! Persistent audit log of access to sandboxes.
+//!
+//! Records who granted or removed SSH access (provision, revoke, TTL expiry)
+//! and, when login auditing is enabled, the logins sshd actually accepted.
+//! Operators use it to answer "who had shell access when". Secret manager
+//! resolutions, secret rotations, sidecar token rotations and commands
+//! rejected by the exec policy are recorded as well. Events are persisted to
+//! `audit.json` in the state directory and pruned by the GC tick after
+//! [`AUDIT_RETENTION_SECS`].
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, SandboxError};
+use crate::store::PersistentStore;
+
+/// How long audit events are kept (one year).
+pub const AUDIT_RETENTION_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Env var that enables polling sandbox sshd logs for login events.
+pub const SSH_LOGIN_AUDIT_ENV: &str = "SANDBOX_SSH_LOGIN_AUDIT";
+
+/// Actor recorded for actions the operator takes on its own (TTL expiry).
+pub const OPERATOR_ACTOR: &str = "operator";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    SshKeyProvisioned,
+    SshKeyRevoked,
+    /// Revoked by the reaper because the key's TTL passed.
+    SshKeyExpired,
+    /// sshd accepted a login (from the sandbox's sshd log).
+    SshLogin,
+    /// A `secretRef` env value was fetched from a cloud secret manager.
+    SecretRefResolved,
+    /// Secrets were re-injected by a rotation policy.
+    SecretsRotated,
+    /// A scheduled or manual rotation failed.
+    SecretRotationFailed,
+    /// A command was rejected by the exec policy (see `exec_policy`).
+    CommandPolicyViolation,
+    /// The sandbox's sidecar auth token was replaced.
+    SidecarTokenRotated,
+    /// A sidecar token rotation failed.
+    SidecarTokenRotationFailed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: String,
+    pub sandbox_id: String,
+    pub action: AuditAction,
+    /// Caller address, or [`OPERATOR_ACTOR`]. Empty for login events.
+    pub actor: String,
+    /// Sandbox user the key belongs to / logged in as. Empty for secret
+    /// events.
+    pub username: String,
+    /// `SHA256:<base64>` key fingerprint, when known.
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    /// Extra context, e.g. the login's source address or the env key and
+    /// reference a secret was resolved for.
+    #[serde(default)]
+    pub detail: Option<String>,
+    pub timestamp: u64,
+}
+
+static AUDIT: OnceCell<PersistentStore<AuditEvent>> = OnceCell::new();
+
+/// Access the audit log persistent store.
+pub fn audit_events() -> Result<&'static PersistentStore<AuditEvent>> {
+    AUDIT
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("audit.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Whether sandbox sshd logs should be polled for login events.
+pub fn ssh_login_audit_enabled() -> bool {
+    std::env::var(SSH_LOGIN_AUDIT_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Append an SSH key event. `public_key` is reduced to its fingerprint.
+/// Best-effort: a store failure is logged, never returned, so auditing
+/// cannot block access changes.
+pub fn record_ssh_key_event(
+    sandbox_id: &str,
+    action: AuditAction,
+    actor: &str,
+    username: &str,
+    public_key: &str,
+) {
+    let fingerprint = crate::runtime::parse_authorized_key_line(public_key)
+        .and_then(|(_, blob, _)| crate::runtime::ssh_key_fingerprint(&blob));
+    let timestamp = crate::util::now_ts();
+    let event = AuditEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        sandbox_id: sandbox_id.to_string(),
+        action,
+        actor: actor.to_string(),
+        username: username.to_string(),
+        fingerprint,
+        detail: None,
+        timestamp,
+    };
+    if let Err(e) = audit_events().and_then(|s| s.insert(event.id.clone(), event)) {
+        tracing::warn!(sandbox_id, error = %e, "failed to record SSH audit event");
+    }
+}
+
+/// Record a login event keyed by its log line, so re-reading the same log
+/// does not duplicate it. Returns `true` when the event is new.
+pub fn record_ssh_login_event(
+    sandbox_id: &str,
+    log_line: &str,
+    username: &str,
+    fingerprint: Option<String>,
+    source: &str,
+) -> Result<bool> {
+    let digest = Sha256::digest(format!("{sandbox_id}\n{log_line}").as_bytes());
+    let id = format!("login-{}", hex::encode(&digest[..16]));
+    let store = audit_events()?;
+    if store.get(&id)?.is_some() {
+        return Ok(false);
+    }
+    store.insert(
+        id.clone(),
+        AuditEvent {
+            id,
+            sandbox_id: sandbox_id.to_string(),
+            action: AuditAction::SshLogin,
+            actor: String::new(),
+            username: username.to_string(),
+            fingerprint,
+            detail: Some(format!("from {source}")),
+            timestamp: crate::util::now_ts(),
+        },
+    )?;
+    Ok(true)
+}
+
+/// Append an event that names no user or key. Best-effort, like
+/// [`record_ssh_key_event`].
+pub fn record_event(sandbox_id: &str, action: AuditAction, actor: &str, detail: Option<String>) {
+    let event = AuditEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        sandbox_id: sandbox_id.to_string(),
+        action,
+        actor: actor.to_string(),
+        username: String::new(),
+        fingerprint: None,
+        detail,
+        timestamp: crate::util::now_ts(),
+    };
+    if let Err(e) = audit_events().and_then(|s| s.insert(event.id.clone(), event)) {
+        tracing::warn!(sandbox_id, error = %e, "failed to record audit event");
+    }
+}
+
+/// Record that the operator resolved `uri` for the env key `key`. The secret
+/// value is never recorded.
+pub fn record_secret_ref_event(sandbox_id: &str, key: &str, uri: &str) {
+    record_event(
+        sandbox_id,
+        AuditAction::SecretRefResolved,
+        OPERATOR_ACTOR,
+        Some(format!("{key} <- {uri}")),
+    );
+}
+
+/// Events for one sandbox, oldest first.
+pub fn list_audit_events(sandbox_id: &str) -> Result<Vec<AuditEvent>> {
+    let mut events: Vec<AuditEvent> = audit_events()?
+        .values()?
+        .into_iter()
+        .filter(|e| e.sandbox_id == sandbox_id)
+        .collect();
+    events.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.id.cmp(&b.id)));
+    Ok(events)
+}
+
+/// Remove events older than `max_age_secs`.
+pub fn gc_audit_events(max_age_secs: u64) -> Result<()> {
+    let cutoff = crate::util::now_ts().saturating_sub(max_age_secs);
+    let store = audit_events()?;
+    let to_remove: Vec<String> = store
+        .values()?
+        .into_iter()
+        .filter(|e| e.timestamp <= cutoff)
+        .map(|e| e.id)
+        .collect();
+    for key in to_remove {
+        store.remove(&key)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+    fn init() {
+        INIT.call_once(|| {
+            let dir = std::env::temp_dir().join(format!("audit-log-test-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).ok();
+            unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", dir) };
+        });
+    }
+
+    const KEY: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAYtf+1EJKzC0lYeIHeeIexjB/e6Sqf4l7Ma2jOSSyc/ alice@laptop";
+
+    #[test]
+    fn key_events_record_fingerprint_per_sandbox() {
+        init();
+        record_ssh_key_event(
+            "audit-sb-1",
+            AuditAction::SshKeyProvisioned,
+            "0xabc",
+            "agent",
+            KEY,
+        );
+        record_ssh_key_event(
+            "audit-sb-1",
+            AuditAction::SshKeyRevoked,
+            "0xabc",
+            "agent",
+            KEY,
+        );
+        record_ssh_key_event(
+            "audit-sb-2",
+            AuditAction::SshKeyProvisioned,
+            "0xdef",
+            "agent",
+            KEY,
+        );
+
+        let events = list_audit_events("audit-sb-1").unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.actor == "0xabc"));
+        assert!(
+            events
+                .iter()
+                .any(|e| e.action == AuditAction::SshKeyRevoked)
+        );
+        assert_eq!(
+            events[0].fingerprint.as_deref(),
+            Some("SHA256:6c70bKlqRgdGp46j3X6iXad78DBJWNRfqUHlPKsYxz4")
+        );
+    }
+
+    #[test]
+    fn login_events_are_deduplicated_by_log_line() {
+        init();
+        let line = "sshd[42]: Accepted publickey for agent from 10.0.0.5 port 51234 ssh2: ED25519 SHA256:abc";
+        let fp = Some("SHA256:abc".to_string());
+        assert!(
+            record_ssh_login_event("audit-sb-3", line, "agent", fp.clone(), "10.0.0.5").unwrap()
+        );
+        assert!(
+            !record_ssh_login_event("audit-sb-3", line, "agent", fp.clone(), "10.0.0.5").unwrap()
+        );
+        // Same line in another sandbox is a separate login.
+        assert!(record_ssh_login_event("audit-sb-4", line, "agent", fp, "10.0.0.5").unwrap());
+
+        let events = list_audit_events("audit-sb-3").unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].action, AuditAction::SshLogin);
+        assert_eq!(events[0].detail.as_deref(), Some("from 10.0.0.5"));
+    }
+}
@@ -0,0 +1,108 @@
+//! Failed instance auto-provisions (`auto-provision-failures.json`).
+//!
+//! When an instance operator gives up auto-provisioning its sandbox (after
+//! the retries in `auto_provision::run_auto_provision`), the paid service has
+//! nothing running. The failure is persisted here, keyed by service ID, so it
+//! survives restarts and shows up on `GET /api/operator/auto-provision`; a
+//! later successful provision clears it.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SandboxError};
+use crate::store::PersistentStore;
+
+/// Longest error kept in a failure record.
+const MAX_ERROR_LEN: usize = 2048;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoProvisionFailure {
+    pub service_id: u64,
+    /// Provision attempts made in the last run (0 when it failed before
+    /// provisioning, e.g. no service config).
+    pub attempts: u32,
+    pub last_error: String,
+    pub first_failed_at: u64,
+    pub failed_at: u64,
+    /// Transaction of the on-chain failure report, once sent.
+    #[serde(default)]
+    pub reported_tx: Option<String>,
+    /// Why the on-chain failure report could not be sent.
+    #[serde(default)]
+    pub report_error: Option<String>,
+}
+
+static FAILURES: OnceCell<PersistentStore<AutoProvisionFailure>> = OnceCell::new();
+
+fn failures() -> Result<&'static PersistentStore<AutoProvisionFailure>> {
+    FAILURES
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("auto-provision-failures.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Record that auto-provisioning `service_id` failed after `attempts`
+/// provision attempts. Keeps the time of the first failure.
+pub fn record_auto_provision_failure(
+    service_id: u64,
+    attempts: u32,
+    error: &str,
+) -> Result<AutoProvisionFailure> {
+    let now = crate::util::now_ts();
+    let store = failures()?;
+    let key = service_id.to_string();
+    let first_failed_at = store.get(&key)?.map(|f| f.first_failed_at).unwrap_or(now);
+    let mut last_error = error.to_string();
+    if last_error.len() > MAX_ERROR_LEN {
+        let mut end = MAX_ERROR_LEN;
+        while !last_error.is_char_boundary(end) {
+            end -= 1;
+        }
+        last_error.truncate(end);
+    }
+    let failure = AutoProvisionFailure {
+        service_id,
+        attempts,
+        last_error,
+        first_failed_at,
+        failed_at: now,
+        reported_tx: None,
+        report_error: None,
+    };
+    store.insert(key, failure.clone())?;
+    Ok(failure)
+}
+
+/// Record the outcome of the on-chain failure report: the transaction hash,
+/// or why it was not sent.
+pub fn record_auto_provision_failure_report(
+    service_id: u64,
+    outcome: std::result::Result<String, String>,
+) -> Result<()> {
+    failures()?.update(&service_id.to_string(), |f| match outcome {
+        Ok(tx) => {
+            f.reported_tx = Some(tx);
+            f.report_error = None;
+        }
+        Err(err) => f.report_error = Some(err),
+    })?;
+    Ok(())
+}
+
+/// Forget the failure of `service_id` (it is provisioned now).
+pub fn clear_auto_provision_failure(service_id: u64) -> Result<()> {
+    failures()?.remove(&service_id.to_string())?;
+    Ok(())
+}
+
+pub fn get_auto_provision_failure(service_id: u64) -> Result<Option<AutoProvisionFailure>> {
+    failures()?.get(&service_id.to_string())
+}
+
+pub fn list_auto_provision_failures() -> Result<Vec<AutoProvisionFailure>> {
+    let mut all = failures()?.values()?;
+    all.sort_by_key(|f| f.service_id);
+    Ok(all)
+}
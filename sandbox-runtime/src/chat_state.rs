@@ -238,6 +238,16 @@ pub fn get_session(session_id: &str) -> Result<Option<ChatSessionRecord>, String
     session_store()?.get(session_id).map_err(|e| e.to_string())
 }
 
+/// Chat scope of a sandbox reached through the multi-sandbox API.
+pub fn sandbox_scope(sandbox_id: &str) -> String {
+    format!("sandbox:{sandbox_id}")
+}
+
+/// Chat scope of an instance sandbox.
+pub fn instance_scope(sandbox_id: &str) -> String {
+    format!("instance:{sandbox_id}")
+}
+
 pub fn session_matches(session: &ChatSessionRecord, scope_id: &str, owner: &str) -> bool {
     session.scope_id == scope_id && session.owner.eq_ignore_ascii_case(owner)
 }
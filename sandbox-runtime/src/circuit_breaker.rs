@@ -66,7 +66,7 @@ static COOLDOWN: once_cell::sync::Lazy<u64> = once_cell::sync::Lazy::new(|| {
 });
 
 /// Read the configured cooldown in seconds.
-fn cooldown_secs() -> u64 {
+pub(crate) fn cooldown_secs() -> u64 {
     *COOLDOWN
 }
 
@@ -0,0 +1,449 @@
+//! Docker crash and OOM monitoring.
+//!
+//! A sandbox container that was OOM-killed or exited on its own used to sit
+//! dead until the owner noticed or the reaper's reconcile caught it.
+//! [`run_crash_monitor`] subscribes to Docker `oom`/`die` events and, for a
+//! container backing a running sandbox, applies `SANDBOX_CRASH_POLICY`:
+//!
+//! - `restart` (default): restart after an exponential backoff
+//!   (`SANDBOX_CRASH_BACKOFF_SECS`, doubling per restart, capped at
+//!   [`MAX_CRASH_BACKOFF_SECS`]). After `SANDBOX_CRASH_MAX_RESTARTS`
+//!   restarts within `SANDBOX_CRASH_WINDOW_SECS` the sandbox is marked
+//!   failed instead of crash-looping.
+//! - `notify`: leave the sandbox as it is.
+//! - `mark_failed`: mark the sandbox stopped.
+//!
+//! The owner gets a `sandbox.crashed` webhook in every case, and each crash
+//! is appended to the record's `crash_history` (last
+//! [`MAX_CRASH_HISTORY`] entries). Deaths the operator caused itself —
+//! stop, delete, recreate — are told apart by taking the sandbox's
+//! lifecycle lock first: afterwards the record is stopped, gone, or points
+//! at a different container.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use docktopus::bollard::system::EventsOptions;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio_stream::StreamExt;
+
+use crate::audit_log::OPERATOR_ACTOR;
+use crate::error::{Result, SandboxError};
+use crate::runtime::{
+    RuntimeBackend, SandboxRecord, SandboxState, acquire_lifecycle_lock, docker_builder,
+    get_sandbox_by_id, parse_runtime_backend_from_env, restart_sidecar, sandboxes,
+    with_lifecycle_actor,
+};
+use crate::util::now_ts;
+use crate::webhooks::WebhookEvent;
+
+/// Crash entries kept per sandbox.
+pub const MAX_CRASH_HISTORY: usize = 20;
+/// Upper bound on the restart backoff.
+pub const MAX_CRASH_BACKOFF_SECS: u64 = 300;
+const DEFAULT_CRASH_BACKOFF_SECS: u64 = 5;
+const DEFAULT_CRASH_MAX_RESTARTS: u32 = 3;
+const DEFAULT_CRASH_WINDOW_SECS: u64 = 3600;
+/// Delay before resubscribing after the event stream ends or fails.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashKind {
+    /// The kernel OOM killer ended the container.
+    Oom,
+    /// The container exited without the operator stopping it.
+    Died,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashAction {
+    Restarted,
+    Notified,
+    MarkedFailed,
+}
+
+/// One crash in a sandbox's `crash_history`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashRecord {
+    pub at: u64,
+    pub kind: CrashKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i64>,
+    pub action: CrashAction,
+    /// Why the restart failed, for `Restarted` entries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CrashPolicy {
+    #[default]
+    Restart,
+    Notify,
+    MarkFailed,
+}
+
+impl CrashPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "restart" => Some(Self::Restart),
+            "notify" => Some(Self::Notify),
+            "mark_failed" | "fail" => Some(Self::MarkFailed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrashPolicyConfig {
+    pub policy: CrashPolicy,
+    pub backoff_secs: u64,
+    pub max_restarts: u32,
+    pub window_secs: u64,
+}
+
+impl Default for CrashPolicyConfig {
+    fn default() -> Self {
+        Self {
+            policy: CrashPolicy::default(),
+            backoff_secs: DEFAULT_CRASH_BACKOFF_SECS,
+            max_restarts: DEFAULT_CRASH_MAX_RESTARTS,
+            window_secs: DEFAULT_CRASH_WINDOW_SECS,
+        }
+    }
+}
+
+fn env_u64(name: &str) -> Option<u64> {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+}
+
+impl CrashPolicyConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let policy = match std::env::var("SANDBOX_CRASH_POLICY") {
+            Ok(raw) if !raw.trim().is_empty() => CrashPolicy::parse(&raw).unwrap_or_else(|| {
+                tracing::warn!(value = %raw, "Unknown SANDBOX_CRASH_POLICY; using restart");
+                CrashPolicy::Restart
+            }),
+            _ => defaults.policy,
+        };
+        Self {
+            policy,
+            backoff_secs: env_u64("SANDBOX_CRASH_BACKOFF_SECS").unwrap_or(defaults.backoff_secs),
+            max_restarts: env_u64("SANDBOX_CRASH_MAX_RESTARTS")
+                .map_or(defaults.max_restarts, |v| {
+                    u32::try_from(v).unwrap_or(u32::MAX)
+                }),
+            window_secs: env_u64("SANDBOX_CRASH_WINDOW_SECS")
+                .filter(|&v| v > 0)
+                .unwrap_or(defaults.window_secs),
+        }
+    }
+
+    /// What to do about a crash at `now` given the sandbox's earlier crashes,
+    /// plus the backoff before a restart.
+    pub fn decide(&self, history: &[CrashRecord], now: u64) -> (CrashAction, Duration) {
+        match self.policy {
+            CrashPolicy::Notify => (CrashAction::Notified, Duration::ZERO),
+            CrashPolicy::MarkFailed => (CrashAction::MarkedFailed, Duration::ZERO),
+            CrashPolicy::Restart => {
+                let since = now.saturating_sub(self.window_secs);
+                let recent = history
+                    .iter()
+                    .filter(|c| c.action == CrashAction::Restarted && c.at >= since)
+                    .count() as u32;
+                if recent >= self.max_restarts {
+                    return (CrashAction::MarkedFailed, Duration::ZERO);
+                }
+                let backoff = self
+                    .backoff_secs
+                    .saturating_mul(1u64 << recent.min(16))
+                    .min(MAX_CRASH_BACKOFF_SECS);
+                (CrashAction::Restarted, Duration::from_secs(backoff))
+            }
+        }
+    }
+}
+
+/// The monitor runs when sandboxes default to the Docker backend, unless
+/// `SANDBOX_CRASH_MONITOR=false`.
+pub fn crash_monitor_enabled() -> bool {
+    let disabled = std::env::var("SANDBOX_CRASH_MONITOR")
+        .map(|v| v.trim().eq_ignore_ascii_case("false") || v.trim() == "0")
+        .unwrap_or(false);
+    !disabled && matches!(parse_runtime_backend_from_env(), Ok(RuntimeBackend::Docker))
+}
+
+/// Append `crash` to `history`, dropping the oldest beyond
+/// [`MAX_CRASH_HISTORY`].
+pub fn push_crash(history: &mut Vec<CrashRecord>, crash: CrashRecord) {
+    history.push(crash);
+    if history.len() > MAX_CRASH_HISTORY {
+        let excess = history.len() - MAX_CRASH_HISTORY;
+        history.drain(..excess);
+    }
+}
+
+/// Follow Docker container events until `shutdown` fires, resubscribing
+/// after the stream drops (e.g. a Docker daemon restart).
+pub async fn run_crash_monitor(mut shutdown: tokio::sync::watch::Receiver<()>) {
+    let config = CrashPolicyConfig::from_env();
+    tracing::info!(policy = ?config.policy, "Sandbox crash monitor started");
+    loop {
+        tokio::select! {
+            result = follow_events(config) => {
+                if let Err(e) = result {
+                    tracing::warn!("Crash monitor event stream failed: {e}");
+                }
+            }
+            _ = shutdown.changed() => break,
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(RESUBSCRIBE_DELAY) => {}
+            _ = shutdown.changed() => break,
+        }
+    }
+    tracing::info!("Sandbox crash monitor shutting down");
+}
+
+async fn follow_events(config: CrashPolicyConfig) -> Result<()> {
+    let builder = docker_builder().await?;
+    let filters = HashMap::from([
+        ("type".to_string(), vec!["container".to_string()]),
+        (
+            "event".to_string(),
+            vec!["oom".to_string(), "die".to_string()],
+        ),
+    ]);
+    let mut events = builder.client().events(Some(EventsOptions::<String> {
+        filters,
+        ..Default::default()
+    }));
+    // Docker reports an OOM kill as `oom` followed by `die`.
+    let mut oom_killed = HashSet::new();
+    while let Some(event) = events.next().await {
+        let event = event.map_err(|e| SandboxError::Docker(format!("Docker events: {e}")))?;
+        let Some(actor) = event.actor else { continue };
+        let Some(container_id) = actor.id else {
+            continue;
+        };
+        match event.action.as_deref() {
+            Some("oom") => {
+                oom_killed.insert(container_id);
+            }
+            Some("die") => {
+                let kind = if oom_killed.remove(&container_id) {
+                    CrashKind::Oom
+                } else {
+                    CrashKind::Died
+                };
+                let exit_code = actor
+                    .attributes
+                    .as_ref()
+                    .and_then(|a| a.get("exitCode"))
+                    .and_then(|v| v.parse::<i64>().ok());
+                if let Some(record) = sandbox_for_container(&container_id) {
+                    tokio::spawn(handle_crash(
+                        config,
+                        record.id,
+                        container_id,
+                        kind,
+                        exit_code,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn sandbox_for_container(container_id: &str) -> Option<SandboxRecord> {
+    sandboxes()
+        .and_then(|s| s.values())
+        .ok()?
+        .into_iter()
+        .find(|r| r.container_id == container_id && r.tee_deployment_id.is_none())
+}
+
+/// The record if `container_id` still backs a running sandbox — i.e. the
+/// operator didn't stop, delete or replace it.
+fn still_crashed(sandbox_id: &str, container_id: &str) -> Option<SandboxRecord> {
+    get_sandbox_by_id(sandbox_id)
+        .ok()
+        .filter(|r| r.state == SandboxState::Running && r.container_id == container_id)
+}
+
+async fn handle_crash(
+    config: CrashPolicyConfig,
+    sandbox_id: String,
+    container_id: String,
+    kind: CrashKind,
+    exit_code: Option<i64>,
+) {
+    let now = now_ts();
+    let (action, backoff) = {
+        let _lock = acquire_lifecycle_lock(&sandbox_id).await;
+        let Some(record) = still_crashed(&sandbox_id, &container_id) else {
+            return;
+        };
+        let (action, backoff) = config.decide(&record.crash_history, now);
+        let crash = CrashRecord {
+            at: now,
+            kind,
+            exit_code,
+            action,
+            error: None,
+        };
+        let updated = sandboxes().and_then(|s| {
+            s.update(&sandbox_id, |r| {
+                push_crash(&mut r.crash_history, crash.clone());
+                if action == CrashAction::MarkedFailed {
+                    r.state = SandboxState::Stopped;
+                    r.stopped_at = Some(now);
+                }
+            })
+        });
+        if let Err(e) = updated {
+            tracing::error!(sandbox_id = %sandbox_id, "Failed to record sandbox crash: {e}");
+        }
+        tracing::warn!(
+            sandbox_id = %sandbox_id,
+            ?kind,
+            ?exit_code,
+            ?action,
+            "Sandbox container crashed"
+        );
+        crate::webhooks::emit(
+            &record.owner,
+            WebhookEvent::SandboxCrashed,
+            json!({
+                "sandbox_id": record.id,
+                "service_id": record.service_id,
+                "crash": crash,
+            }),
+        );
+        (action, backoff)
+    };
+    if action != CrashAction::Restarted {
+        return;
+    }
+
+    tokio::time::sleep(backoff).await;
+    let _lock = acquire_lifecycle_lock(&sandbox_id).await;
+    if still_crashed(&sandbox_id, &container_id).is_none() {
+        return;
+    }
+    // The container is already dead: record it stopped so the restart goes
+    // straight to the resume path.
+    let stopped = sandboxes().and_then(|s| {
+        s.update(&sandbox_id, |r| {
+            r.state = SandboxState::Stopped;
+            r.stopped_at = Some(now_ts());
+        })?;
+        get_sandbox_by_id(&sandbox_id)
+    });
+    let result = match stopped {
+        Ok(record) => with_lifecycle_actor(OPERATOR_ACTOR, restart_sidecar(&record))
+            .await
+            .map(|_| ()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::error!(sandbox_id = %sandbox_id, "Crash restart failed: {e}");
+        let _ = sandboxes().and_then(|s| {
+            s.update(&sandbox_id, |r| {
+                if let Some(last) = r.crash_history.last_mut()
+                    && last.at == now
+                {
+                    last.error = Some(e.to_string());
+                }
+            })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn restarted_at(at: u64) -> CrashRecord {
+        CrashRecord {
+            at,
+            kind: CrashKind::Died,
+            exit_code: Some(1),
+            action: CrashAction::Restarted,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn restart_backoff_doubles_then_gives_up() {
+        let config = CrashPolicyConfig::default();
+        let now = 10_000;
+        let mut history = Vec::new();
+        for expected in [5, 10, 20] {
+            let (action, backoff) = config.decide(&history, now);
+            assert_eq!(action, CrashAction::Restarted);
+            assert_eq!(backoff, Duration::from_secs(expected));
+            history.push(restarted_at(now));
+        }
+        assert_eq!(config.decide(&history, now).0, CrashAction::MarkedFailed);
+    }
+
+    #[test]
+    fn restarts_outside_the_window_are_forgotten() {
+        let config = CrashPolicyConfig::default();
+        let history = vec![restarted_at(0), restarted_at(1), restarted_at(2)];
+        let (action, backoff) = config.decide(&history, 2 + DEFAULT_CRASH_WINDOW_SECS + 1);
+        assert_eq!(action, CrashAction::Restarted);
+        assert_eq!(backoff, Duration::from_secs(DEFAULT_CRASH_BACKOFF_SECS));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let config = CrashPolicyConfig {
+            backoff_secs: 200,
+            max_restarts: 10,
+            ..Default::default()
+        };
+        let history = vec![restarted_at(100)];
+        assert_eq!(
+            config.decide(&history, 100).1,
+            Duration::from_secs(MAX_CRASH_BACKOFF_SECS)
+        );
+    }
+
+    #[test]
+    fn policy_parses_and_non_restart_policies_act_immediately() {
+        assert_eq!(CrashPolicy::parse(" Notify "), Some(CrashPolicy::Notify));
+        assert_eq!(
+            CrashPolicy::parse("mark_failed"),
+            Some(CrashPolicy::MarkFailed)
+        );
+        assert_eq!(CrashPolicy::parse("reboot"), None);
+        let notify = CrashPolicyConfig {
+            policy: CrashPolicy::Notify,
+            ..Default::default()
+        };
+        assert_eq!(
+            notify.decide(&[], 0),
+            (CrashAction::Notified, Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn history_keeps_the_latest_entries() {
+        let mut history = Vec::new();
+        for at in 0..(MAX_CRASH_HISTORY as u64 + 5) {
+            push_crash(&mut history, restarted_at(at));
+        }
+        assert_eq!(history.len(), MAX_CRASH_HISTORY);
+        assert_eq!(history[0].at, 5);
+    }
+}
@@ -0,0 +1,285 @@
+//! Per-sandbox disk quota enforcement for the Docker backend.
+//!
+//! `disk_gb` used to be recorded but never enforced, so one sandbox could
+//! fill the host. Two mechanisms, usable together:
+//!
+//! - With `SANDBOX_DISK_STORAGE_OPT=true` new containers are created with
+//!   `--storage-opt size=<disk_gb>G`, a hard kernel-level cap. This needs a
+//!   storage driver that supports it (overlay2 on XFS mounted with `pquota`,
+//!   btrfs, zfs); Docker refuses to create the container otherwise.
+//! - [`disk_quota_tick`] periodically measures each running sandbox's
+//!   writable layer (Docker's `SizeRw`, a `du` of the container's upper
+//!   directory). At `SANDBOX_DISK_SOFT_LIMIT_PCT` of `disk_gb` the owner is
+//!   warned; at `disk_gb` the sandbox's writes are suspended — new agent
+//!   prompts, tasks and batch runs are refused by [`ensure_disk_writable`]
+//!   until usage drops back under the limit. Exec and SSH stay open so the
+//!   owner can delete files.
+//!
+//! Owners get a `sandbox.disk_quota` webhook on every state change, and the
+//! last measurement is reported in the sandbox listing.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use docktopus::bollard::container::{Config as BollardConfig, InspectContainerOptions};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::error::{Result, SandboxError};
+use crate::runtime::{
+    RuntimeBackend, SandboxRecord, SandboxState, docker_builder, docker_timeout,
+    parse_runtime_backend_from_env, sandboxes,
+};
+use crate::util::now_ts;
+use crate::webhooks::WebhookEvent;
+
+/// Seconds between usage checks when `SANDBOX_DISK_CHECK_INTERVAL_SECS` is unset.
+pub const DEFAULT_DISK_CHECK_INTERVAL_SECS: u64 = 300;
+/// Percentage of `disk_gb` at which the owner is warned.
+pub const DEFAULT_DISK_SOFT_LIMIT_PCT: u64 = 90;
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskQuotaState {
+    Ok,
+    /// Over the soft limit.
+    Warning,
+    /// At or over `disk_gb`: writes are suspended.
+    Suspended,
+}
+
+/// The last disk measurement of a sandbox.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DiskUsage {
+    pub used_bytes: u64,
+    pub limit_bytes: u64,
+    pub soft_limit_bytes: u64,
+    pub state: DiskQuotaState,
+    pub checked_at: u64,
+}
+
+impl DiskUsage {
+    pub fn measure(used_bytes: u64, disk_gb: u64, soft_limit_pct: u64, now: u64) -> Self {
+        let limit_bytes = disk_gb.saturating_mul(GIB);
+        let soft_limit_bytes = limit_bytes / 100 * soft_limit_pct.min(100);
+        let state = if used_bytes >= limit_bytes {
+            DiskQuotaState::Suspended
+        } else if used_bytes >= soft_limit_bytes {
+            DiskQuotaState::Warning
+        } else {
+            DiskQuotaState::Ok
+        };
+        Self {
+            used_bytes,
+            limit_bytes,
+            soft_limit_bytes,
+            state,
+            checked_at: now,
+        }
+    }
+}
+
+/// Sandbox ID → last measurement.
+static USAGE: Lazy<Mutex<HashMap<String, DiskUsage>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Checks run when sandboxes default to the Docker backend, unless
+/// `SANDBOX_DISK_QUOTA=false`.
+pub fn disk_quota_enabled() -> bool {
+    let disabled = std::env::var("SANDBOX_DISK_QUOTA")
+        .map(|v| v.trim().eq_ignore_ascii_case("false") || v.trim() == "0")
+        .unwrap_or(false);
+    !disabled && matches!(parse_runtime_backend_from_env(), Ok(RuntimeBackend::Docker))
+}
+
+/// Seconds between checks: `SANDBOX_DISK_CHECK_INTERVAL_SECS`, default
+/// [`DEFAULT_DISK_CHECK_INTERVAL_SECS`].
+pub fn disk_check_interval() -> u64 {
+    std::env::var("SANDBOX_DISK_CHECK_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_DISK_CHECK_INTERVAL_SECS)
+}
+
+/// `SANDBOX_DISK_SOFT_LIMIT_PCT`, default [`DEFAULT_DISK_SOFT_LIMIT_PCT`].
+pub fn disk_soft_limit_pct() -> u64 {
+    std::env::var("SANDBOX_DISK_SOFT_LIMIT_PCT")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&pct| (1..=100).contains(&pct))
+        .unwrap_or(DEFAULT_DISK_SOFT_LIMIT_PCT)
+}
+
+/// Cap the container's writable layer at `disk_gb` when
+/// `SANDBOX_DISK_STORAGE_OPT=true`.
+pub(crate) fn apply_storage_quota(config: &mut BollardConfig<String>, disk_gb: u64) {
+    let enabled = std::env::var("SANDBOX_DISK_STORAGE_OPT")
+        .is_ok_and(|v| v.trim() == "true" || v.trim() == "1");
+    if !enabled || disk_gb == 0 {
+        return;
+    }
+    if let Some(host_config) = config.host_config.as_mut() {
+        host_config.storage_opt =
+            Some(HashMap::from([("size".to_string(), format!("{disk_gb}G"))]));
+    }
+}
+
+/// The last measurement of `sandbox_id`, if one was taken.
+pub fn disk_usage(sandbox_id: &str) -> Option<DiskUsage> {
+    USAGE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(sandbox_id)
+        .cloned()
+}
+
+/// Refuse new agent runs in a sandbox whose writes are suspended.
+pub fn ensure_disk_writable(sandbox_id: &str) -> Result<()> {
+    match disk_usage(sandbox_id) {
+        Some(usage) if usage.state == DiskQuotaState::Suspended => {
+            Err(SandboxError::Unavailable(format!(
+                "Sandbox {sandbox_id} is over its disk quota ({} of {} bytes used); \
+                 delete files to resume",
+                usage.used_bytes, usage.limit_bytes
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Store `usage` for `sandbox_id`, returning the previous state.
+fn record_usage(sandbox_id: &str, usage: DiskUsage) -> Option<DiskQuotaState> {
+    USAGE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(sandbox_id.to_string(), usage)
+        .map(|prev| prev.state)
+}
+
+/// Measure every running Docker sandbox with a `disk_gb` and act on state
+/// changes. Measurements of sandboxes that are gone or stopped are dropped.
+pub async fn disk_quota_tick() -> Result<()> {
+    let soft_limit_pct = disk_soft_limit_pct();
+    let records: Vec<SandboxRecord> = sandboxes()?
+        .values()?
+        .into_iter()
+        .filter(|r| {
+            r.state == SandboxState::Running
+                && r.disk_gb > 0
+                && r.tee_deployment_id.is_none()
+                && !r.container_id.is_empty()
+        })
+        .collect();
+    USAGE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|id, _| records.iter().any(|r| &r.id == id));
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    let builder = docker_builder().await?;
+    for record in records {
+        let inspect = docker_timeout(
+            "inspect_container",
+            builder.client().inspect_container(
+                &record.container_id,
+                Some(InspectContainerOptions { size: true }),
+            ),
+        )
+        .await;
+        let used_bytes = match inspect {
+            Ok(inspect) => inspect.size_rw.map_or(0, |v| v.max(0) as u64),
+            Err(e) => {
+                tracing::debug!(sandbox_id = %record.id, "Disk usage check failed: {e}");
+                continue;
+            }
+        };
+        let usage = DiskUsage::measure(used_bytes, record.disk_gb, soft_limit_pct, now_ts());
+        let previous = record_usage(&record.id, usage.clone());
+        if previous.unwrap_or(DiskQuotaState::Ok) == usage.state {
+            continue;
+        }
+        match usage.state {
+            DiskQuotaState::Suspended => tracing::warn!(
+                sandbox_id = %record.id,
+                used_bytes,
+                limit_bytes = usage.limit_bytes,
+                "Sandbox over its disk quota; suspending writes"
+            ),
+            DiskQuotaState::Warning => tracing::info!(
+                sandbox_id = %record.id,
+                used_bytes,
+                soft_limit_bytes = usage.soft_limit_bytes,
+                "Sandbox over its disk soft limit"
+            ),
+            DiskQuotaState::Ok => tracing::info!(
+                sandbox_id = %record.id,
+                used_bytes,
+                "Sandbox back under its disk quota"
+            ),
+        }
+        crate::webhooks::emit(
+            &record.owner,
+            WebhookEvent::SandboxDiskQuota,
+            json!({
+                "sandbox_id": record.id,
+                "service_id": record.service_id,
+                "disk_usage": usage,
+            }),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usage_states_follow_the_limits() {
+        let ok = DiskUsage::measure(GIB, 10, 90, 1);
+        assert_eq!(ok.state, DiskQuotaState::Ok);
+        assert_eq!(ok.limit_bytes, 10 * GIB);
+        assert_eq!(ok.soft_limit_bytes, 9 * GIB);
+        assert_eq!(
+            DiskUsage::measure(9 * GIB, 10, 90, 1).state,
+            DiskQuotaState::Warning
+        );
+        assert_eq!(
+            DiskUsage::measure(10 * GIB, 10, 90, 1).state,
+            DiskQuotaState::Suspended
+        );
+    }
+
+    #[test]
+    fn suspended_sandboxes_refuse_writes_until_usage_drops() {
+        let id = "disk-quota-test-sandbox";
+        assert!(ensure_disk_writable(id).is_ok());
+        record_usage(id, DiskUsage::measure(11 * GIB, 10, 90, 1));
+        let err = ensure_disk_writable(id).unwrap_err();
+        assert!(matches!(err, SandboxError::Unavailable(_)), "got {err:?}");
+        assert!(err.to_string().contains("disk quota"), "{err}");
+        let previous = record_usage(id, DiskUsage::measure(GIB, 10, 90, 2));
+        assert_eq!(previous, Some(DiskQuotaState::Suspended));
+        assert!(ensure_disk_writable(id).is_ok());
+    }
+
+    #[test]
+    fn storage_opt_is_opt_in() {
+        let mut config = BollardConfig::<String> {
+            host_config: Some(Default::default()),
+            ..Default::default()
+        };
+        unsafe { std::env::remove_var("SANDBOX_DISK_STORAGE_OPT") };
+        apply_storage_quota(&mut config, 10);
+        assert!(config.host_config.as_ref().unwrap().storage_opt.is_none());
+        unsafe { std::env::set_var("SANDBOX_DISK_STORAGE_OPT", "true") };
+        apply_storage_quota(&mut config, 10);
+        unsafe { std::env::remove_var("SANDBOX_DISK_STORAGE_OPT") };
+        let opts = config.host_config.unwrap().storage_opt.unwrap();
+        assert_eq!(opts.get("size").map(String::as_str), Some("10G"));
+    }
+}
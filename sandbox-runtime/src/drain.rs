@@ -0,0 +1,202 @@
+//! Operator drain mode for maintenance windows.
+//!
+//! Before a host reboot or kernel upgrade the operator puts the node into
+//! drain mode, either at startup with `OPERATOR_DRAIN_MODE=true` or at runtime
+//! with `POST /api/operator/drain`. While draining, new sandboxes and resumes
+//! are refused with `503` (see [`ensure_accepting_provisions`]); running
+//! sandboxes keep working.
+//!
+//! A drain started through the API also schedules a window: owners of running
+//! sandboxes are told about it with a `maintenance.scheduled` webhook (and a
+//! `reaper.warning` shortly before), and once the window ends the reaper
+//! snapshots and/or stops every running sandbox (see
+//! `reaper::drain::enforce_drain_window`). The drain is persisted, so it
+//! survives an operator restart, and lasts until `DELETE /api/operator/drain`.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::error::{Result, SandboxError};
+use crate::runtime::{SandboxState, sandboxes};
+use crate::store::PersistentStore;
+use crate::webhooks::WebhookEvent;
+
+/// Window used when a drain request does not give one (30 minutes).
+pub const DEFAULT_DRAIN_WINDOW_SECS: u64 = 30 * 60;
+/// Longest accepted drain window (7 days).
+pub const MAX_DRAIN_WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
+const MAX_REASON_LEN: usize = 256;
+const DRAIN_KEY: &str = "current";
+
+/// A drain started through the operator API.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrainState {
+    pub started_at: u64,
+    /// End of the window: running sandboxes are snapshotted and/or stopped
+    /// on the first reaper tick at or after this time.
+    pub deadline: u64,
+    pub snapshot: bool,
+    pub stop: bool,
+    pub notify: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// When the window actions ran.
+    #[serde(default)]
+    pub completed_at: Option<u64>,
+    /// Sandboxes stopped by the drain.
+    #[serde(default)]
+    pub stopped: Vec<String>,
+    /// Sandboxes whose snapshot or stop failed.
+    #[serde(default)]
+    pub failed: Vec<String>,
+}
+
+impl DrainState {
+    /// The reaper action owners are told about.
+    pub fn action(&self) -> &'static str {
+        match (self.stop, self.snapshot) {
+            (true, _) => "stop",
+            (false, true) => "snapshot",
+            (false, false) => "none",
+        }
+    }
+}
+
+/// Options for [`start_drain`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct DrainRequest {
+    /// Seconds until running sandboxes are snapshotted and/or stopped
+    /// (default `OPERATOR_DRAIN_WINDOW_SECS`).
+    #[serde(default)]
+    pub window_secs: Option<u64>,
+    /// Snapshot running sandboxes at the end of the window (default true).
+    #[serde(default)]
+    pub snapshot: Option<bool>,
+    /// Stop running sandboxes at the end of the window (default true).
+    #[serde(default)]
+    pub stop: Option<bool>,
+    /// Send `maintenance.scheduled` webhooks to owners (default true).
+    #[serde(default)]
+    pub notify: Option<bool>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+static DRAIN: OnceCell<PersistentStore<DrainState>> = OnceCell::new();
+
+fn drain_store() -> Result<&'static PersistentStore<DrainState>> {
+    DRAIN
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("drain.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Whether `OPERATOR_DRAIN_MODE` puts the node in drain mode from startup.
+pub fn drain_mode_configured() -> bool {
+    std::env::var("OPERATOR_DRAIN_MODE")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Window for drains that do not specify one, from `OPERATOR_DRAIN_WINDOW_SECS`.
+pub fn default_drain_window_secs() -> u64 {
+    std::env::var("OPERATOR_DRAIN_WINDOW_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_DRAIN_WINDOW_SECS)
+}
+
+/// The drain started through the API, if any.
+pub fn current_drain() -> Result<Option<DrainState>> {
+    drain_store()?.get(DRAIN_KEY)
+}
+
+/// Whether the node is draining, from config or the API. A drain that cannot
+/// be read counts as draining: refusing work is the safe side.
+pub fn is_draining() -> bool {
+    drain_mode_configured() || !matches!(current_drain(), Ok(None))
+}
+
+/// Refuse new sandboxes and resumes while draining.
+pub fn ensure_accepting_provisions() -> Result<()> {
+    if is_draining() {
+        return Err(SandboxError::Unavailable(
+            "Operator is draining for maintenance and not accepting new sandboxes".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Start (or reschedule) a drain. Notifies owners of running sandboxes when
+/// `notify` is set.
+pub fn start_drain(request: DrainRequest) -> Result<DrainState> {
+    let window = request
+        .window_secs
+        .unwrap_or_else(default_drain_window_secs);
+    if window > MAX_DRAIN_WINDOW_SECS {
+        return Err(SandboxError::Validation(format!(
+            "window_secs must be at most {MAX_DRAIN_WINDOW_SECS}"
+        )));
+    }
+    let reason = request
+        .reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+    if reason.as_ref().is_some_and(|r| r.len() > MAX_REASON_LEN) {
+        return Err(SandboxError::Validation(format!(
+            "reason must be at most {MAX_REASON_LEN} bytes"
+        )));
+    }
+
+    let now = crate::util::now_ts();
+    let state = DrainState {
+        started_at: now,
+        deadline: now + window,
+        snapshot: request.snapshot.unwrap_or(true),
+        stop: request.stop.unwrap_or(true),
+        notify: request.notify.unwrap_or(true),
+        reason,
+        completed_at: None,
+        stopped: Vec::new(),
+        failed: Vec::new(),
+    };
+    drain_store()?.insert(DRAIN_KEY.to_string(), state.clone())?;
+
+    if state.notify {
+        for record in sandboxes()?.values()? {
+            if record.state != SandboxState::Running {
+                continue;
+            }
+            crate::webhooks::emit(
+                &record.owner,
+                WebhookEvent::MaintenanceScheduled,
+                json!({
+                    "sandbox_id": record.id,
+                    "action": state.action(),
+                    "deadline": state.deadline,
+                    "reason": state.reason,
+                }),
+            );
+        }
+    }
+    Ok(state)
+}
+
+/// End the API drain. Returns the drain that was active, if any. Drain mode
+/// from `OPERATOR_DRAIN_MODE` stays on until the config changes.
+pub fn end_drain() -> Result<Option<DrainState>> {
+    drain_store()?.remove(DRAIN_KEY)
+}
+
+/// Record the outcome of the window actions.
+pub fn mark_drain_completed(stopped: Vec<String>, failed: Vec<String>) -> Result<()> {
+    drain_store()?.update(DRAIN_KEY, |state| {
+        state.completed_at = Some(crate::util::now_ts());
+        state.stopped = stopped;
+        state.failed = failed;
+    })?;
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Standard input for exec commands.
+//!
+//! An exec request may carry `stdin` (text piped to the command) or
+//! `stdin_file` (a file already in the sandbox, uploaded ahead of the run,
+//! whose contents are piped instead), so commands like `psql`, `python -` or
+//! installers that prompt can be driven non-interactively. The sidecar caps
+//! request bodies at 1 MiB, so inline stdin is limited to
+//! [`MAX_EXEC_STDIN_BYTES`]; larger input goes through a file. Without
+//! either, the command's stdin is closed as before.
+
+use serde_json::{Map, Value};
+
+/// Largest inline `stdin` accepted (512 KiB).
+pub const MAX_EXEC_STDIN_BYTES: usize = 512 * 1024;
+
+const MAX_STDIN_FILE_LEN: usize = 4096;
+
+/// Check `stdin` / `stdin_file` (empty = unset).
+pub fn validate_exec_input(stdin: &str, stdin_file: &str) -> Result<(), String> {
+    if !stdin.is_empty() && !stdin_file.is_empty() {
+        return Err("stdin and stdin_file are mutually exclusive".into());
+    }
+    if stdin.len() > MAX_EXEC_STDIN_BYTES {
+        return Err(format!(
+            "stdin exceeds maximum length ({MAX_EXEC_STDIN_BYTES} bytes); use stdin_file"
+        ));
+    }
+    if stdin_file.len() > MAX_STDIN_FILE_LEN {
+        return Err(format!(
+            "stdin_file exceeds maximum length ({MAX_STDIN_FILE_LEN} bytes)"
+        ));
+    }
+    if stdin_file.contains('\0') || (!stdin_file.is_empty() && stdin_file.trim().is_empty()) {
+        return Err("stdin_file must be a file path".into());
+    }
+    Ok(())
+}
+
+/// Add the sidecar's `stdin` / `stdinFile` fields to a `/terminals/commands`
+/// payload. A relative `stdin_file` resolves against the command's `cwd`.
+pub fn insert_exec_input(payload: &mut Map<String, Value>, stdin: &str, stdin_file: &str) {
+    if !stdin.is_empty() {
+        payload.insert("stdin".to_string(), Value::String(stdin.to_string()));
+    }
+    if !stdin_file.is_empty() {
+        payload.insert(
+            "stdinFile".to_string(),
+            Value::String(stdin_file.to_string()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_input_validation() {
+        assert!(validate_exec_input("", "").is_ok());
+        assert!(validate_exec_input("SELECT 1;\n", "").is_ok());
+        assert!(validate_exec_input("", "inputs/answers.txt").is_ok());
+        assert!(validate_exec_input("x", "inputs/answers.txt").is_err());
+        assert!(validate_exec_input(&"x".repeat(MAX_EXEC_STDIN_BYTES + 1), "").is_err());
+        assert!(validate_exec_input("", "bad\0path").is_err());
+        assert!(validate_exec_input("", "   ").is_err());
+    }
+
+    #[test]
+    fn exec_input_payload_fields() {
+        let mut payload = Map::new();
+        insert_exec_input(&mut payload, "", "");
+        assert!(payload.is_empty());
+        insert_exec_input(&mut payload, "print(1)\n", "");
+        assert_eq!(payload["stdin"], "print(1)\n");
+        let mut payload = Map::new();
+        insert_exec_input(&mut payload, "", "/tmp/in.sql");
+        assert_eq!(payload["stdinFile"], "/tmp/in.sql");
+    }
+}
@@ -0,0 +1,316 @@
+//! Exec output truncation with retrievable overflow artifacts.
+//!
+//! Commands like `seq 1 10000000` produce far more output than fits in an
+//! on-chain result or a reasonable HTTP response. Each stream is capped at
+//! `EXEC_OUTPUT_MAX_BYTES` (default 64 KiB, `0` disables truncation); when
+//! either stream is cut, the full stdout/stderr are written to the operator's
+//! state directory and the response carries an `overflow_ref` that the
+//! sandbox owner can fetch through the operator API.
+//!
+//! Overflow artifacts are plain files (not a `PersistentStore`) so multi-MB
+//! outputs are never loaded into the shared store map. Artifacts older than
+//! `EXEC_OVERFLOW_TTL_SECS` (default 24h) are no longer served and are pruned
+//! on each new write, which also evicts the oldest artifacts to keep the
+//! directory under `EXEC_OVERFLOW_MAX_TOTAL_MB` (default 1 GiB).
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SandboxError};
+
+pub const DEFAULT_EXEC_OUTPUT_MAX_BYTES: usize = 64 * 1024;
+pub const DEFAULT_EXEC_OVERFLOW_TTL_SECS: u64 = 24 * 60 * 60;
+pub const DEFAULT_EXEC_OVERFLOW_MAX_TOTAL_MB: u64 = 1024;
+
+/// Full output of an exec whose response was truncated.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecOverflow {
+    pub id: String,
+    pub sandbox_id: String,
+    pub stdout: String,
+    pub stderr: String,
+    pub stdout_bytes: usize,
+    pub stderr_bytes: usize,
+    pub created_at: u64,
+}
+
+/// Exec output after applying the response size cap.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CappedExecOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub truncated: bool,
+    /// Overflow artifact ID; `None` when nothing was truncated or the
+    /// artifact could not be stored.
+    pub overflow_ref: Option<String>,
+}
+
+/// Per-stream byte limit from `EXEC_OUTPUT_MAX_BYTES`.
+pub fn exec_output_max_bytes() -> usize {
+    std::env::var("EXEC_OUTPUT_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_EXEC_OUTPUT_MAX_BYTES)
+}
+
+fn exec_overflow_ttl_secs() -> u64 {
+    std::env::var("EXEC_OVERFLOW_TTL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_EXEC_OVERFLOW_TTL_SECS)
+}
+
+fn exec_overflow_max_total_bytes() -> u64 {
+    std::env::var("EXEC_OVERFLOW_MAX_TOTAL_MB")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_EXEC_OVERFLOW_MAX_TOTAL_MB)
+        .saturating_mul(1024 * 1024)
+}
+
+fn overflow_dir() -> PathBuf {
+    crate::store::state_dir().join("exec-overflow")
+}
+
+/// Cap `stdout`/`stderr` for `sandbox_id`, storing the full output as an
+/// overflow artifact when either stream exceeds the limit.
+///
+/// Storage failures are logged and never fail the exec: the caller still
+/// gets the truncated output, just without an `overflow_ref`. An empty
+/// `sandbox_id` (sidecar not in the local store) truncates without storing.
+pub fn cap_exec_output(sandbox_id: &str, stdout: String, stderr: String) -> CappedExecOutput {
+    let limit = exec_output_max_bytes();
+    if limit == 0 || (stdout.len() <= limit && stderr.len() <= limit) {
+        return CappedExecOutput {
+            stdout,
+            stderr,
+            truncated: false,
+            overflow_ref: None,
+        };
+    }
+
+    let capped_stdout = truncate_utf8(&stdout, limit).to_string();
+    let capped_stderr = truncate_utf8(&stderr, limit).to_string();
+    let overflow = ExecOverflow {
+        id: uuid::Uuid::new_v4().simple().to_string(),
+        sandbox_id: sandbox_id.to_string(),
+        stdout_bytes: stdout.len(),
+        stderr_bytes: stderr.len(),
+        stdout,
+        stderr,
+        created_at: crate::util::now_ts(),
+    };
+    let overflow_ref = if sandbox_id.is_empty() {
+        None
+    } else {
+        match store_exec_overflow(&overflow) {
+            Ok(()) => Some(overflow.id),
+            Err(e) => {
+                tracing::warn!(sandbox_id, error = %e, "failed to store exec overflow artifact");
+                None
+            }
+        }
+    };
+
+    CappedExecOutput {
+        stdout: capped_stdout,
+        stderr: capped_stderr,
+        truncated: true,
+        overflow_ref,
+    }
+}
+
+/// Load an overflow artifact. Returns `NotFound` when the artifact does not
+/// exist, has expired, or belongs to a different sandbox.
+pub fn get_exec_overflow(sandbox_id: &str, overflow_id: &str) -> Result<ExecOverflow> {
+    let not_found = || SandboxError::NotFound(format!("Exec overflow '{overflow_id}' not found"));
+    if overflow_id.is_empty() || !overflow_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(not_found());
+    }
+    let path = overflow_dir().join(format!("{overflow_id}.json"));
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(not_found()),
+        Err(e) => {
+            return Err(SandboxError::Storage(format!(
+                "Failed to read exec overflow: {e}"
+            )));
+        }
+    };
+    let overflow: ExecOverflow = serde_json::from_slice(&bytes)
+        .map_err(|e| SandboxError::Storage(format!("Exec overflow is corrupt: {e}")))?;
+    if overflow.sandbox_id != sandbox_id {
+        return Err(not_found());
+    }
+    if crate::util::now_ts().saturating_sub(overflow.created_at) > exec_overflow_ttl_secs() {
+        let _ = std::fs::remove_file(&path);
+        return Err(not_found());
+    }
+    Ok(overflow)
+}
+
+fn store_exec_overflow(overflow: &ExecOverflow) -> Result<()> {
+    let dir = overflow_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| SandboxError::Storage(format!("Failed to create {}: {e}", dir.display())))?;
+    prune_expired_artifacts(&dir, exec_overflow_ttl_secs());
+    let bytes = serde_json::to_vec(overflow)
+        .map_err(|e| SandboxError::Storage(format!("Failed to encode exec overflow: {e}")))?;
+    let max_total = exec_overflow_max_total_bytes();
+    if bytes.len() as u64 > max_total {
+        return Err(SandboxError::Storage(format!(
+            "Exec overflow of {} bytes exceeds EXEC_OVERFLOW_MAX_TOTAL_MB",
+            bytes.len()
+        )));
+    }
+    prune_to_total_bytes(&dir, max_total - bytes.len() as u64);
+    std::fs::write(dir.join(format!("{}.json", overflow.id)), bytes)
+        .map_err(|e| SandboxError::Storage(format!("Failed to write exec overflow: {e}")))
+}
+
+/// Remove artifact files in `dir` older than `ttl_secs`.
+pub(crate) fn prune_expired_artifacts(dir: &std::path::Path, ttl_secs: u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let ttl = std::time::Duration::from_secs(ttl_secs);
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > ttl);
+        if expired {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Remove the oldest files in `dir` until the rest total at most `max_bytes`.
+fn prune_to_total_bytes(dir: &std::path::Path, max_bytes: u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort();
+    for (_, len, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(len);
+        }
+    }
+}
+
+/// Longest prefix of `s` that is at most `max` bytes and ends on a char boundary.
+pub(crate) fn truncate_utf8(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_utf8_respects_char_boundaries() {
+        assert_eq!(truncate_utf8("hello", 10), "hello");
+        assert_eq!(truncate_utf8("hello", 3), "hel");
+        // 'é' is two bytes; cutting in the middle backs off to the boundary.
+        assert_eq!(truncate_utf8("aé", 2), "a");
+    }
+
+    #[test]
+    fn cap_exec_output_stores_and_returns_overflow() {
+        let _guard = crate::TEST_ENV_GUARD
+            .lock()
+            .unwrap_or_else(|p| p.into_inner());
+        let dir = std::env::temp_dir().join(format!("exec-output-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        unsafe {
+            std::env::set_var("BLUEPRINT_STATE_DIR", &dir);
+            std::env::set_var("EXEC_OUTPUT_MAX_BYTES", "8");
+        }
+
+        let small = cap_exec_output("sb-1", "ok".into(), String::new());
+        assert!(!small.truncated);
+        assert!(small.overflow_ref.is_none());
+
+        let big = cap_exec_output("sb-1", "0123456789abcdef".into(), "err".into());
+        assert!(big.truncated);
+        assert_eq!(big.stdout, "01234567");
+        assert_eq!(big.stderr, "err");
+        let id = big.overflow_ref.expect("overflow stored");
+
+        let full = get_exec_overflow("sb-1", &id).unwrap();
+        assert_eq!(full.stdout, "0123456789abcdef");
+        assert_eq!(full.stdout_bytes, 16);
+        assert!(matches!(
+            get_exec_overflow("sb-other", &id),
+            Err(SandboxError::NotFound(_))
+        ));
+        assert!(get_exec_overflow("sb-1", "../secrets").is_err());
+
+        unsafe { std::env::remove_var("EXEC_OUTPUT_MAX_BYTES") };
+    }
+
+    #[test]
+    fn expired_overflow_is_not_served() {
+        let _guard = crate::TEST_ENV_GUARD
+            .lock()
+            .unwrap_or_else(|p| p.into_inner());
+        let dir = std::env::temp_dir().join(format!("exec-output-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).ok();
+        unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", &dir) };
+
+        let overflow = ExecOverflow {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            sandbox_id: "sb-1".into(),
+            stdout: "out".into(),
+            stderr: String::new(),
+            stdout_bytes: 3,
+            stderr_bytes: 0,
+            created_at: crate::util::now_ts() - DEFAULT_EXEC_OVERFLOW_TTL_SECS - 1,
+        };
+        store_exec_overflow(&overflow).unwrap();
+        assert!(matches!(
+            get_exec_overflow("sb-1", &overflow.id),
+            Err(SandboxError::NotFound(_))
+        ));
+        assert!(
+            !overflow_dir()
+                .join(format!("{}.json", overflow.id))
+                .exists()
+        );
+    }
+
+    #[test]
+    fn total_size_cap_evicts_oldest_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b", "c"] {
+            std::fs::write(dir.path().join(name), [0u8; 100]).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        prune_to_total_bytes(dir.path(), 250);
+        assert!(!dir.path().join("a").exists());
+        assert!(dir.path().join("b").exists() && dir.path().join("c").exists());
+        prune_to_total_bytes(dir.path(), 0);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,309 @@
+//! Operator-side command policy.
+//!
+//! Sandboxes are isolated, but operators still want a guardrail against
+//! `rm -rf /`-class requests. The policy is evaluated before exec, SSH and
+//! snapshot commands are forwarded to the sidecar; a violation is rejected
+//! with a validation error and recorded in the audit log as
+//! [`AuditAction::CommandPolicyViolation`](crate::audit_log::AuditAction).
+//!
+//! The policy is JSON, given inline in [`EXEC_POLICY_JSON_ENV`] or as a file
+//! path in [`EXEC_POLICY_FILE_ENV`], and read once per process:
+//!
+//! ```json
+//! {
+//!   "deny_patterns": ["rm\\s+-[a-z]*r[a-z]*f?\\s+/(\\s|$)", "mkfs\\."],
+//!   "max_timeout_ms": 600000,
+//!   "forbidden_paths": ["/", "/etc/shadow", "/var/lib/sidecar"]
+//! }
+//! ```
+//!
+//! - `deny_patterns` are regexes matched anywhere in the command text.
+//! - `max_timeout_ms` caps exec timeouts (`0` = no cap).
+//! - `forbidden_paths` reject commands naming the path (or anything below it,
+//!   except for `/` itself) as a word, and exec `cwd` / `stdin_file` under it.
+//!   Relative paths in the command are not resolved.
+//!
+//! With neither variable set every command passes. A policy that fails to
+//! parse rejects every command rather than silently disabling the guardrail.
+
+use once_cell::sync::OnceCell;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::audit_log::{AuditAction, record_event};
+use crate::error::{Result, SandboxError};
+
+/// Env var holding the policy JSON.
+pub const EXEC_POLICY_JSON_ENV: &str = "SANDBOX_EXEC_POLICY_JSON";
+/// Env var naming a file holding the policy JSON.
+pub const EXEC_POLICY_FILE_ENV: &str = "SANDBOX_EXEC_POLICY_FILE";
+
+/// Longest command excerpt kept in an audit event.
+const AUDIT_COMMAND_EXCERPT: usize = 256;
+
+/// What a command is forwarded for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommandKind {
+    #[default]
+    Exec,
+    Ssh,
+    Snapshot,
+}
+
+impl CommandKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CommandKind::Exec => "exec",
+            CommandKind::Ssh => "ssh",
+            CommandKind::Snapshot => "snapshot",
+        }
+    }
+}
+
+/// A command about to be forwarded to the sidecar. Empty / zero fields are
+/// not checked.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CommandInput<'a> {
+    pub kind: CommandKind,
+    pub command: &'a str,
+    pub cwd: &'a str,
+    pub stdin_file: &'a str,
+    pub timeout_ms: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExecPolicyConfig {
+    #[serde(default)]
+    deny_patterns: Vec<String>,
+    #[serde(default)]
+    max_timeout_ms: u64,
+    #[serde(default)]
+    forbidden_paths: Vec<String>,
+}
+
+/// A parsed command policy.
+#[derive(Debug, Default)]
+pub struct ExecPolicy {
+    deny_patterns: Vec<Regex>,
+    max_timeout_ms: u64,
+    forbidden_paths: Vec<String>,
+}
+
+impl ExecPolicy {
+    /// Parse a policy document.
+    pub fn from_json(raw: &str) -> Result<Self> {
+        let config: ExecPolicyConfig = serde_json::from_str(raw)
+            .map_err(|e| SandboxError::Validation(format!("Invalid exec policy: {e}")))?;
+        let deny_patterns = config
+            .deny_patterns
+            .iter()
+            .map(|p| {
+                Regex::new(p).map_err(|e| {
+                    SandboxError::Validation(format!("Invalid exec policy pattern {p:?}: {e}"))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut forbidden_paths = Vec::with_capacity(config.forbidden_paths.len());
+        for path in &config.forbidden_paths {
+            if !path.starts_with('/') {
+                return Err(SandboxError::Validation(format!(
+                    "Exec policy forbidden path {path:?} must be absolute"
+                )));
+            }
+            let trimmed = path.trim_end_matches('/');
+            forbidden_paths.push(if trimmed.is_empty() { "/" } else { trimmed }.to_string());
+        }
+        Ok(Self {
+            deny_patterns,
+            max_timeout_ms: config.max_timeout_ms,
+            forbidden_paths,
+        })
+    }
+
+    /// The reason `input` violates the policy, if it does.
+    pub fn violation(&self, input: &CommandInput<'_>) -> Option<String> {
+        if self.max_timeout_ms > 0 && input.timeout_ms > self.max_timeout_ms {
+            return Some(format!(
+                "timeout {} ms exceeds the policy maximum of {} ms",
+                input.timeout_ms, self.max_timeout_ms
+            ));
+        }
+        if let Some(pattern) = self
+            .deny_patterns
+            .iter()
+            .find(|re| re.is_match(input.command))
+        {
+            return Some(format!(
+                "command matches denied pattern {:?}",
+                pattern.as_str()
+            ));
+        }
+        for path in &self.forbidden_paths {
+            if names_path(input.command, path) {
+                return Some(format!("command references forbidden path {path}"));
+            }
+            for (field, value) in [("cwd", input.cwd), ("stdin_file", input.stdin_file)] {
+                if is_under(value, path) {
+                    return Some(format!("{field} is under forbidden path {path}"));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn is_under(value: &str, path: &str) -> bool {
+    if value.is_empty() {
+        return false;
+    }
+    let value = value.trim_end_matches('/');
+    if path == "/" {
+        return value.is_empty();
+    }
+    value == path || value.strip_prefix(path).is_some_and(|r| r.starts_with('/'))
+}
+
+/// Whether `path` appears in `command` as a word: preceded by the start, a
+/// separator or a quote, and followed by the end, a separator, a quote or
+/// (except for `/`) a deeper path component.
+fn names_path(command: &str, path: &str) -> bool {
+    let before_ok = |c: char| c.is_whitespace() || "'\"=;&|()<>`".contains(c);
+    let after_ok = |c: char| before_ok(c) || (c == '/' && path != "/");
+    command.match_indices(path).any(|(start, _)| {
+        let prev = command[..start].chars().next_back();
+        let next = command[start + path.len()..].chars().next();
+        prev.is_none_or(before_ok) && next.is_none_or(after_ok)
+    })
+}
+
+static POLICY: OnceCell<std::result::Result<ExecPolicy, String>> = OnceCell::new();
+
+fn load_policy() -> std::result::Result<ExecPolicy, String> {
+    let raw = match std::env::var(EXEC_POLICY_JSON_ENV) {
+        Ok(raw) if !raw.trim().is_empty() => raw,
+        _ => match std::env::var(EXEC_POLICY_FILE_ENV) {
+            Ok(path) if !path.trim().is_empty() => std::fs::read_to_string(path.trim())
+                .map_err(|e| format!("Failed to read {EXEC_POLICY_FILE_ENV}: {e}"))?,
+            _ => return Ok(ExecPolicy::default()),
+        },
+    };
+    ExecPolicy::from_json(&raw).map_err(|e| e.to_string())
+}
+
+/// The process-wide policy loaded from the environment.
+pub fn exec_policy() -> Result<&'static ExecPolicy> {
+    POLICY
+        .get_or_init(|| {
+            let policy = load_policy();
+            if let Err(e) = &policy {
+                tracing::error!(error = %e, "exec policy is invalid; rejecting all commands");
+            }
+            policy
+        })
+        .as_ref()
+        .map_err(|e| SandboxError::Validation(e.clone()))
+}
+
+/// Check `input` against the configured policy before forwarding it for
+/// `sandbox_id`. Violations are audited with `actor` and rejected.
+pub fn enforce_command_policy(
+    sandbox_id: &str,
+    actor: &str,
+    input: &CommandInput<'_>,
+) -> Result<()> {
+    let Some(reason) = exec_policy()?.violation(input) else {
+        return Ok(());
+    };
+    let excerpt: String = input.command.chars().take(AUDIT_COMMAND_EXCERPT).collect();
+    tracing::warn!(sandbox_id, actor, kind = input.kind.as_str(), %reason, "command rejected by policy");
+    record_event(
+        sandbox_id,
+        AuditAction::CommandPolicyViolation,
+        actor,
+        Some(format!("{}: {reason}: {excerpt}", input.kind.as_str())),
+    );
+    Err(SandboxError::Validation(format!(
+        "Command rejected by exec policy: {reason}"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exec(command: &str) -> CommandInput<'_> {
+        CommandInput {
+            command,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_policy_allows_everything() {
+        let policy = ExecPolicy::from_json("{}").unwrap();
+        assert!(policy.violation(&exec("rm -rf /")).is_none());
+    }
+
+    #[test]
+    fn invalid_policies_are_rejected() {
+        assert!(ExecPolicy::from_json(r#"{"deny_patterns": ["("]}"#).is_err());
+        assert!(ExecPolicy::from_json(r#"{"forbidden_paths": ["etc"]}"#).is_err());
+        assert!(ExecPolicy::from_json(r#"{"deny": []}"#).is_err());
+    }
+
+    #[test]
+    fn deny_patterns_and_timeout() {
+        let policy = ExecPolicy::from_json(
+            r#"{"deny_patterns": ["mkfs\\.", ":\\(\\)\\s*\\{"], "max_timeout_ms": 1000}"#,
+        )
+        .unwrap();
+        assert!(policy.violation(&exec("mkfs.ext4 /dev/sda")).is_some());
+        assert!(policy.violation(&exec(":(){ :|:& };:")).is_some());
+        assert!(policy.violation(&exec("ls -la")).is_none());
+        let slow = CommandInput {
+            timeout_ms: 5000,
+            ..exec("sleep 4")
+        };
+        assert!(policy.violation(&slow).unwrap().contains("timeout"));
+    }
+
+    #[test]
+    fn forbidden_paths_match_words_and_subpaths() {
+        let policy = ExecPolicy::from_json(
+            r#"{"forbidden_paths": ["/", "/etc/shadow", "/var/lib/sidecar/"]}"#,
+        )
+        .unwrap();
+        assert!(policy.violation(&exec("rm -rf /")).is_some());
+        assert!(
+            policy
+                .violation(&exec("rm -rf / --no-preserve-root"))
+                .is_some()
+        );
+        assert!(policy.violation(&exec("cat \"/etc/shadow\"")).is_some());
+        assert!(
+            policy
+                .violation(&exec("tar -czf x.tgz /var/lib/sidecar/db"))
+                .is_some()
+        );
+        assert!(policy.violation(&exec("rm -rf /tmp/build")).is_none());
+        assert!(policy.violation(&exec("cat /etc/shadow.bak")).is_none());
+        assert!(policy.violation(&exec("ls /var/lib/sidecars")).is_none());
+
+        let in_state = CommandInput {
+            cwd: "/var/lib/sidecar/state",
+            ..exec("ls")
+        };
+        assert!(policy.violation(&in_state).unwrap().starts_with("cwd"));
+        let from_shadow = CommandInput {
+            stdin_file: "/etc/shadow",
+            ..exec("cat")
+        };
+        assert!(policy.violation(&from_shadow).is_some());
+        let in_home = CommandInput {
+            cwd: "/home/agent",
+            ..exec("ls")
+        };
+        assert!(policy.violation(&in_home).is_none());
+    }
+}
@@ -0,0 +1,338 @@
+//! Per-sandbox exec presets.
+//!
+//! Owners register named exec settings (working directory, environment,
+//! timeout and shell) with a sandbox and select one per request with
+//! `preset: "build"` instead of resending a large `env_json` — on-chain
+//! requests stay small and secrets in the environment stay out of calldata.
+//! [`apply_exec_preset`] resolves a request against its preset:
+//!
+//! - `cwd` and `timeout_ms` from the request win when set;
+//! - the preset's `env` is the base, overridden key by key by the request's
+//!   `env_json`;
+//! - with a `shell`, the command runs as `<shell> -c '<command>'`.
+//!
+//! Presets are persisted in the state directory and removed by GC once their
+//! sandbox is gone. Env values are never returned by the API.
+
+use std::collections::BTreeMap;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api_types::ExecApiRequest;
+use crate::error::{Result, SandboxError};
+use crate::store::PersistentStore;
+
+/// Maximum presets per sandbox.
+pub const MAX_EXEC_PRESETS: usize = 32;
+/// Maximum combined size of a preset's fields.
+pub const MAX_EXEC_PRESET_BYTES: usize = 64 * 1024;
+const MAX_NAME_LEN: usize = 64;
+/// Shown in place of env values in API responses.
+const REDACTED: &str = "<redacted>";
+
+/// Preset fields supplied by the owner. Empty / zero fields leave the
+/// request's value alone.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecPresetInput {
+    #[serde(default)]
+    pub cwd: String,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub timeout_ms: u64,
+    /// Shell the command is run with, e.g. `bash` or `/bin/zsh`.
+    #[serde(default)]
+    pub shell: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecPreset {
+    pub name: String,
+    #[serde(flatten)]
+    pub fields: ExecPresetInput,
+    pub updated_at: u64,
+    /// Address that last wrote the preset.
+    pub updated_by: String,
+}
+
+impl ExecPreset {
+    /// The preset with env values replaced, for API responses.
+    pub fn redacted(mut self) -> Self {
+        for value in self.fields.env.values_mut() {
+            *value = REDACTED.to_string();
+        }
+        self
+    }
+}
+
+/// All presets of one sandbox, keyed by sandbox ID.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SandboxExecPresets {
+    pub sandbox_id: String,
+    #[serde(default)]
+    pub presets: BTreeMap<String, ExecPreset>,
+}
+
+static PRESETS: OnceCell<PersistentStore<SandboxExecPresets>> = OnceCell::new();
+
+/// Access the exec preset persistent store.
+pub fn exec_presets() -> Result<&'static PersistentStore<SandboxExecPresets>> {
+    PRESETS
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("exec_presets.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+fn valid_name(name: &str, extra: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || extra.contains(c))
+}
+
+fn validate(name: &str, input: &ExecPresetInput) -> Result<()> {
+    if !valid_name(name, "-.") {
+        return Err(SandboxError::Validation(format!(
+            "Preset name must be 1-{MAX_NAME_LEN} chars of [A-Za-z0-9_.-]"
+        )));
+    }
+    if let Some(key) = input.env.keys().find(|k| !valid_name(k, "")) {
+        return Err(SandboxError::Validation(format!(
+            "Invalid env var name {key:?} (use [A-Za-z0-9_])"
+        )));
+    }
+    if !input.shell.is_empty() && !valid_name(&input.shell, "-./") {
+        return Err(SandboxError::Validation(format!(
+            "Invalid shell {:?} (use a name or path of [A-Za-z0-9_./-])",
+            input.shell
+        )));
+    }
+    let size = input.cwd.len()
+        + input.shell.len()
+        + input
+            .env
+            .iter()
+            .map(|(k, v)| k.len() + v.len())
+            .sum::<usize>();
+    if size > MAX_EXEC_PRESET_BYTES {
+        return Err(SandboxError::Validation(format!(
+            "Preset exceeds {MAX_EXEC_PRESET_BYTES} bytes"
+        )));
+    }
+    Ok(())
+}
+
+/// Presets of `sandbox_id`, sorted by name.
+pub fn list_exec_presets(sandbox_id: &str) -> Result<Vec<ExecPreset>> {
+    Ok(exec_presets()?
+        .get(sandbox_id)?
+        .map(|p| p.presets.into_values().collect())
+        .unwrap_or_default())
+}
+
+pub fn get_exec_preset(sandbox_id: &str, name: &str) -> Result<Option<ExecPreset>> {
+    Ok(exec_presets()?
+        .get(sandbox_id)?
+        .and_then(|mut p| p.presets.remove(name)))
+}
+
+/// Create or replace preset `name` of `sandbox_id`.
+pub fn put_exec_preset(
+    sandbox_id: &str,
+    name: &str,
+    input: ExecPresetInput,
+    actor: &str,
+) -> Result<ExecPreset> {
+    validate(name, &input)?;
+    let store = exec_presets()?;
+    let mut all = store
+        .get(sandbox_id)?
+        .unwrap_or_else(|| SandboxExecPresets {
+            sandbox_id: sandbox_id.to_string(),
+            presets: BTreeMap::new(),
+        });
+    if !all.presets.contains_key(name) && all.presets.len() >= MAX_EXEC_PRESETS {
+        return Err(SandboxError::Validation(format!(
+            "Sandbox already has {MAX_EXEC_PRESETS} exec presets"
+        )));
+    }
+    let preset = ExecPreset {
+        name: name.to_string(),
+        fields: input,
+        updated_at: crate::util::now_ts(),
+        updated_by: actor.to_string(),
+    };
+    all.presets.insert(name.to_string(), preset.clone());
+    store.insert(sandbox_id.to_string(), all)?;
+    Ok(preset)
+}
+
+/// Delete preset `name` of `sandbox_id`. Returns whether it existed.
+pub fn delete_exec_preset(sandbox_id: &str, name: &str) -> Result<bool> {
+    let mut removed = false;
+    exec_presets()?.update(sandbox_id, |all| {
+        removed = all.presets.remove(name).is_some();
+    })?;
+    Ok(removed)
+}
+
+/// Drop the presets of sandboxes that no longer exist.
+pub fn gc_exec_presets() -> Result<()> {
+    let store = exec_presets()?;
+    for all in store.values()? {
+        if let Err(SandboxError::NotFound(_)) = crate::runtime::get_sandbox_by_id(&all.sandbox_id) {
+            store.remove(&all.sandbox_id)?;
+        }
+    }
+    Ok(())
+}
+
+/// `req` with its `preset` applied (see the module docs) and cleared. A
+/// request without a preset is returned unchanged; a missing preset is an
+/// error.
+pub fn apply_exec_preset(sandbox_id: &str, req: &ExecApiRequest) -> Result<ExecApiRequest> {
+    let name = req.preset.trim();
+    if name.is_empty() {
+        return Ok(req.clone());
+    }
+    let preset = get_exec_preset(sandbox_id, name)?
+        .ok_or_else(|| SandboxError::NotFound(format!("Exec preset '{name}' not found")))?
+        .fields;
+
+    let mut env: serde_json::Map<String, Value> = preset
+        .env
+        .into_iter()
+        .map(|(k, v)| (k, Value::String(v)))
+        .collect();
+    if let Some(Value::Object(overrides)) =
+        crate::util::parse_json_object(&req.env_json, "env_json")?
+    {
+        env.extend(overrides);
+    }
+
+    let mut resolved = req.clone();
+    resolved.preset = String::new();
+    if resolved.cwd.is_empty() {
+        resolved.cwd = preset.cwd;
+    }
+    if resolved.timeout_ms == 0 {
+        resolved.timeout_ms = preset.timeout_ms;
+    }
+    resolved.env_json = if env.is_empty() {
+        String::new()
+    } else {
+        Value::Object(env).to_string()
+    };
+    if !preset.shell.is_empty() {
+        resolved.command = format!(
+            "{} -c {}",
+            preset.shell,
+            crate::util::shell_escape(&req.command)
+        );
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn init() {
+        let dir = std::env::temp_dir().join(format!("exec-presets-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", dir) };
+    }
+
+    fn request(preset: &str) -> ExecApiRequest {
+        ExecApiRequest {
+            command: "cargo build".into(),
+            preset: preset.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn preset_fills_unset_fields_and_request_wins() {
+        init();
+        let input = ExecPresetInput {
+            cwd: "/workspace/app".into(),
+            env: BTreeMap::from([
+                ("RUST_LOG".to_string(), "info".to_string()),
+                ("API_KEY".to_string(), "sk-secret".to_string()),
+            ]),
+            timeout_ms: 600_000,
+            shell: "bash".into(),
+        };
+        put_exec_preset("sb-preset-apply", "build", input, "0xabc").unwrap();
+
+        let resolved = apply_exec_preset("sb-preset-apply", &request("build")).unwrap();
+        assert_eq!(resolved.command, "bash -c 'cargo build'");
+        assert_eq!(resolved.cwd, "/workspace/app");
+        assert_eq!(resolved.timeout_ms, 600_000);
+        assert!(resolved.preset.is_empty());
+        let env: Value = serde_json::from_str(&resolved.env_json).unwrap();
+        assert_eq!(env, json!({ "API_KEY": "sk-secret", "RUST_LOG": "info" }));
+
+        let overridden = ExecApiRequest {
+            cwd: "/tmp".into(),
+            timeout_ms: 1_000,
+            env_json: r#"{"RUST_LOG":"debug"}"#.into(),
+            ..request("build")
+        };
+        let resolved = apply_exec_preset("sb-preset-apply", &overridden).unwrap();
+        assert_eq!(resolved.cwd, "/tmp");
+        assert_eq!(resolved.timeout_ms, 1_000);
+        let env: Value = serde_json::from_str(&resolved.env_json).unwrap();
+        assert_eq!(env["RUST_LOG"], "debug");
+        assert_eq!(env["API_KEY"], "sk-secret");
+
+        let listed = list_exec_presets("sb-preset-apply").unwrap();
+        assert_eq!(listed[0].clone().redacted().fields.env["API_KEY"], REDACTED);
+    }
+
+    #[test]
+    fn no_preset_is_a_no_op_and_unknown_presets_fail() {
+        init();
+        let req = ExecApiRequest {
+            env_json: "not json".into(),
+            ..request("")
+        };
+        assert_eq!(apply_exec_preset("sb-preset-none", &req).unwrap(), req);
+        assert!(matches!(
+            apply_exec_preset("sb-preset-none", &request("missing")),
+            Err(SandboxError::NotFound(_))
+        ));
+
+        put_exec_preset("sb-preset-none", "test", Default::default(), "a").unwrap();
+        assert!(delete_exec_preset("sb-preset-none", "test").unwrap());
+        assert!(!delete_exec_preset("sb-preset-none", "test").unwrap());
+    }
+
+    #[test]
+    fn invalid_presets_are_rejected() {
+        init();
+        assert!(put_exec_preset("sb", "bad name", Default::default(), "a").is_err());
+        let input = ExecPresetInput {
+            env: BTreeMap::from([("BAD-KEY".to_string(), String::new())]),
+            ..Default::default()
+        };
+        assert!(put_exec_preset("sb", "ok", input, "a").is_err());
+        let input = ExecPresetInput {
+            shell: "bash; rm -rf /".into(),
+            ..Default::default()
+        };
+        assert!(put_exec_preset("sb", "ok", input, "a").is_err());
+        let input = ExecPresetInput {
+            cwd: "x".repeat(MAX_EXEC_PRESET_BYTES + 1),
+            ..Default::default()
+        };
+        assert!(put_exec_preset("sb", "ok", input, "a").is_err());
+    }
+}
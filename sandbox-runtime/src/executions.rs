@@ -0,0 +1,350 @@
+//! Background command executions.
+//!
+//! A synchronous exec must finish within the caller's request (and, for job
+//! callers, the on-chain job timeout), which rules out builds and test
+//! suites. [`start_execution`] instead records an [`ExecutionRecord`],
+//! runs the command on the sidecar in a background task and returns the
+//! execution ID at once; callers poll [`get_execution`] for the outcome.
+//!
+//! Output is capped like synchronous exec (see [`crate::exec_output`]). The
+//! sandbox's activity timestamp is refreshed while a command runs so the
+//! idle reaper does not stop it mid-build. Executions still running when the
+//! operator restarts are marked [`ExecutionStatus::Interrupted`]. Records
+//! are pruned by the GC tick after [`EXECUTION_RETENTION_SECS`].
+
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api_types::ExecApiRequest;
+use crate::error::{Result, SandboxError};
+use crate::exec_policy::{CommandInput, CommandKind, enforce_command_policy};
+use crate::runtime::SandboxRecord;
+use crate::sidecar_compat::SidecarApi;
+use crate::store::PersistentStore;
+
+/// Timeout applied when the request gives none (1 hour).
+pub const DEFAULT_EXECUTION_TIMEOUT_MS: u64 = 60 * 60 * 1000;
+/// Longest allowed execution timeout (24 hours).
+pub const MAX_EXECUTION_TIMEOUT_MS: u64 = 24 * 60 * 60 * 1000;
+/// How long finished executions are kept (7 days).
+pub const EXECUTION_RETENTION_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Slack on top of the command timeout before the operator gives up on the
+/// sidecar answering.
+const SIDECAR_GRACE: Duration = Duration::from_secs(30);
+/// How often a running execution refreshes the sandbox's activity time.
+const ACTIVITY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    Running,
+    /// The command ran; see `exit_code`.
+    Completed,
+    /// The command could not be run or the sidecar did not answer in time.
+    Failed,
+    /// The operator restarted while the command was running.
+    Interrupted,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    pub id: String,
+    pub sandbox_id: String,
+    /// Address that started the execution.
+    pub owner: String,
+    pub command: String,
+    pub status: ExecutionStatus,
+    pub timeout_ms: u64,
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<u32>,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    /// True when stdout or stderr was cut at `EXEC_OUTPUT_MAX_BYTES`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Overflow artifact holding the full output (see `exec_output`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overflow_ref: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+static EXECUTIONS: OnceCell<PersistentStore<ExecutionRecord>> = OnceCell::new();
+
+/// Access the execution store. The first access marks executions left
+/// running by a previous operator process as interrupted.
+pub fn executions() -> Result<&'static PersistentStore<ExecutionRecord>> {
+    EXECUTIONS
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("executions.json");
+            let store = PersistentStore::open(path)?;
+            interrupt_running(&store)?;
+            Ok(store)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+fn interrupt_running(store: &PersistentStore<ExecutionRecord>) -> Result<()> {
+    let now = crate::util::now_ts();
+    for execution in store.values()? {
+        if execution.status != ExecutionStatus::Running {
+            continue;
+        }
+        store.update(&execution.id, |e| {
+            e.status = ExecutionStatus::Interrupted;
+            e.error = Some("Operator restarted before the command completed".into());
+            e.completed_at = Some(now);
+        })?;
+    }
+    Ok(())
+}
+
+/// Effective timeout for a requested `timeout_ms` (0 = default).
+pub fn execution_timeout_ms(timeout_ms: u64) -> Result<u64> {
+    match timeout_ms {
+        0 => Ok(DEFAULT_EXECUTION_TIMEOUT_MS),
+        t if t > MAX_EXECUTION_TIMEOUT_MS => Err(SandboxError::Validation(format!(
+            "timeout_ms must be at most {MAX_EXECUTION_TIMEOUT_MS}"
+        ))),
+        t => Ok(t),
+    }
+}
+
+/// Record a new execution of `req.command` on `record` for `owner` and run it
+/// in the background, with `req.preset` applied (see `crate::exec_presets`).
+/// Returns the record in its `running` state.
+pub fn start_execution(
+    record: &SandboxRecord,
+    owner: &str,
+    req: &ExecApiRequest,
+) -> Result<ExecutionRecord> {
+    req.validate().map_err(SandboxError::Validation)?;
+    let req = &crate::exec_presets::apply_exec_preset(&record.id, req)?;
+    let timeout_ms = execution_timeout_ms(req.timeout_ms)?;
+    let input = CommandInput {
+        kind: CommandKind::Exec,
+        command: &req.command,
+        cwd: &req.cwd,
+        stdin_file: &req.stdin_file,
+        timeout_ms,
+    };
+    enforce_command_policy(&record.id, owner, &input)?;
+    let execution = ExecutionRecord {
+        id: format!("exec-{}", uuid::Uuid::new_v4().simple()),
+        sandbox_id: record.id.clone(),
+        owner: owner.to_string(),
+        command: req.command.clone(),
+        status: ExecutionStatus::Running,
+        timeout_ms,
+        created_at: crate::util::now_ts(),
+        completed_at: None,
+        exit_code: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        truncated: false,
+        overflow_ref: None,
+        error: None,
+    };
+    executions()?.insert(execution.id.clone(), execution.clone())?;
+    crate::runtime::touch_sandbox(&record.id);
+    crate::usage_ledger::record_usage(owner, crate::usage_ledger::UsageTotals::exec());
+
+    let payload = crate::operator_api::build_exec_payload(
+        &req.command,
+        &req.cwd,
+        &req.env_json,
+        timeout_ms,
+        &req.stdin,
+        &req.stdin_file,
+    );
+    let record = record.clone();
+    let id = execution.id.clone();
+    tokio::spawn(async move {
+        let outcome = run_on_sidecar(&record, payload, timeout_ms).await;
+        finish_execution(&id, &record.id, SidecarApi::for_record(&record), outcome);
+    });
+    Ok(execution)
+}
+
+async fn run_on_sidecar(record: &SandboxRecord, payload: Value, timeout_ms: u64) -> Result<Value> {
+    let call = crate::http::sidecar_post_json_without_timeout(
+        &record.sidecar_url,
+        "/terminals/commands",
+        &record.token,
+        payload,
+    );
+    let deadline = tokio::time::sleep(Duration::from_millis(timeout_ms) + SIDECAR_GRACE);
+    tokio::pin!(call, deadline);
+    let mut activity = tokio::time::interval(ACTIVITY_INTERVAL);
+    loop {
+        tokio::select! {
+            result = &mut call => return result,
+            _ = &mut deadline => {
+                return Err(SandboxError::Http(format!(
+                    "Sidecar did not finish the command within {timeout_ms} ms"
+                )));
+            }
+            _ = activity.tick() => crate::runtime::touch_sandbox(&record.id),
+        }
+    }
+}
+
+fn finish_execution(id: &str, sandbox_id: &str, api: SidecarApi, outcome: Result<Value>) {
+    let now = crate::util::now_ts();
+    let update = match outcome {
+        Ok(parsed) => {
+            let result = api.command_result(&parsed);
+            let field = |name: &str| {
+                result
+                    .and_then(|r| r.get(name))
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let exit_code = result
+                .and_then(|r| r.get("exitCode"))
+                .and_then(Value::as_u64)
+                .unwrap_or(0) as u32;
+            let output =
+                crate::exec_output::cap_exec_output(sandbox_id, field("stdout"), field("stderr"));
+            executions().and_then(|s| {
+                s.update(id, |e| {
+                    e.status = ExecutionStatus::Completed;
+                    e.exit_code = Some(exit_code);
+                    e.stdout = output.stdout.clone();
+                    e.stderr = output.stderr.clone();
+                    e.truncated = output.truncated;
+                    e.overflow_ref = output.overflow_ref.clone();
+                    e.completed_at = Some(now);
+                })
+            })
+        }
+        Err(err) => executions().and_then(|s| {
+            s.update(id, |e| {
+                e.status = ExecutionStatus::Failed;
+                e.error = Some(err.to_string());
+                e.completed_at = Some(now);
+            })
+        }),
+    };
+    if let Err(e) = update {
+        tracing::error!(execution_id = id, sandbox_id, error = %e, "failed to record execution result");
+    }
+    crate::runtime::touch_sandbox(sandbox_id);
+}
+
+/// Load an execution of `sandbox_id`. Returns `NotFound` when it does not
+/// exist or belongs to another sandbox.
+pub fn get_execution(sandbox_id: &str, execution_id: &str) -> Result<ExecutionRecord> {
+    executions()?
+        .get(execution_id)?
+        .filter(|e| e.sandbox_id == sandbox_id)
+        .ok_or_else(|| SandboxError::NotFound(format!("Execution '{execution_id}' not found")))
+}
+
+/// Remove finished executions older than `max_age_secs`.
+pub fn gc_executions(max_age_secs: u64) -> Result<()> {
+    let cutoff = crate::util::now_ts().saturating_sub(max_age_secs);
+    let store = executions()?;
+    let expired: Vec<String> = store
+        .values()?
+        .into_iter()
+        .filter(|e| e.completed_at.is_some_and(|at| at <= cutoff))
+        .map(|e| e.id)
+        .collect();
+    for id in expired {
+        store.remove(&id)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() {
+        let dir = std::env::temp_dir().join(format!("executions-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", dir) };
+    }
+
+    fn running(id: &str, sandbox_id: &str) -> ExecutionRecord {
+        ExecutionRecord {
+            id: id.to_string(),
+            sandbox_id: sandbox_id.to_string(),
+            owner: "0xowner".into(),
+            command: "make test".into(),
+            status: ExecutionStatus::Running,
+            timeout_ms: DEFAULT_EXECUTION_TIMEOUT_MS,
+            created_at: 1_700_000_000,
+            completed_at: None,
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            truncated: false,
+            overflow_ref: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn execution_timeout_defaults_and_caps() {
+        assert_eq!(
+            execution_timeout_ms(0).unwrap(),
+            DEFAULT_EXECUTION_TIMEOUT_MS
+        );
+        assert_eq!(execution_timeout_ms(5_000).unwrap(), 5_000);
+        assert!(execution_timeout_ms(MAX_EXECUTION_TIMEOUT_MS + 1).is_err());
+    }
+
+    #[test]
+    fn finished_execution_records_output_and_is_scoped_to_sandbox() {
+        init();
+        executions()
+            .unwrap()
+            .insert("exec-t1".into(), running("exec-t1", "exec-sb-1"))
+            .unwrap();
+        let response = serde_json::json!({
+            "result": { "exitCode": 2, "stdout": "built", "stderr": "1 failed" }
+        });
+        finish_execution("exec-t1", "exec-sb-1", SidecarApi::V1, Ok(response));
+
+        let execution = get_execution("exec-sb-1", "exec-t1").unwrap();
+        assert_eq!(execution.status, ExecutionStatus::Completed);
+        assert_eq!(execution.exit_code, Some(2));
+        assert_eq!(execution.stderr, "1 failed");
+        assert!(execution.completed_at.is_some());
+        assert!(matches!(
+            get_execution("exec-sb-2", "exec-t1"),
+            Err(SandboxError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn failed_sidecar_call_marks_execution_failed() {
+        init();
+        executions()
+            .unwrap()
+            .insert("exec-t2".into(), running("exec-t2", "exec-sb-3"))
+            .unwrap();
+        finish_execution(
+            "exec-t2",
+            "exec-sb-3",
+            SidecarApi::V1,
+            Err(SandboxError::Http("connection refused".into())),
+        );
+        let execution = get_execution("exec-sb-3", "exec-t2").unwrap();
+        assert_eq!(execution.status, ExecutionStatus::Failed);
+        assert!(execution.error.unwrap().contains("connection refused"));
+        assert_eq!(execution.exit_code, None);
+    }
+}
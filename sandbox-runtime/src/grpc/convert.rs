@@ -0,0 +1,74 @@
+//! Conversions between REST JSON and protobuf well-known types.
+
+use prost_types::value::Kind;
+use prost_types::{ListValue, Struct, Value as PbValue};
+use serde_json::{Map, Value};
+
+pub(crate) fn to_pb_value(value: Value) -> PbValue {
+    let kind = match value {
+        Value::Null => Kind::NullValue(0),
+        Value::Bool(b) => Kind::BoolValue(b),
+        Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        Value::String(s) => Kind::StringValue(s),
+        Value::Array(items) => Kind::ListValue(ListValue {
+            values: items.into_iter().map(to_pb_value).collect(),
+        }),
+        Value::Object(map) => Kind::StructValue(to_pb_struct(map)),
+    };
+    PbValue { kind: Some(kind) }
+}
+
+pub(crate) fn to_pb_struct(map: Map<String, Value>) -> Struct {
+    Struct {
+        fields: map.into_iter().map(|(k, v)| (k, to_pb_value(v))).collect(),
+    }
+}
+
+pub(crate) fn from_pb_value(value: PbValue) -> Value {
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => Value::Null,
+        Some(Kind::BoolValue(b)) => Value::Bool(b),
+        Some(Kind::NumberValue(n)) => {
+            // Integral numbers stay integers so env values round-trip.
+            if n.fract() == 0.0 && n.abs() < 9.0e15 {
+                Value::from(n as i64)
+            } else {
+                serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+            }
+        }
+        Some(Kind::StringValue(s)) => Value::String(s),
+        Some(Kind::ListValue(list)) => {
+            Value::Array(list.values.into_iter().map(from_pb_value).collect())
+        }
+        Some(Kind::StructValue(s)) => Value::Object(from_pb_struct(s)),
+    }
+}
+
+pub(crate) fn from_pb_struct(s: Struct) -> Map<String, Value> {
+    s.fields
+        .into_iter()
+        .map(|(k, v)| (k, from_pb_value(v)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn json_round_trips_through_struct() {
+        let value = json!({
+            "API_KEY": "sk-1",
+            "RETRIES": 3,
+            "RATIO": 0.5,
+            "ENABLED": true,
+            "NOTHING": null,
+            "LIST": ["a", 1, { "nested": false }],
+        });
+        let Value::Object(map) = value.clone() else {
+            unreachable!()
+        };
+        assert_eq!(Value::Object(from_pb_struct(to_pb_struct(map))), value);
+    }
+}
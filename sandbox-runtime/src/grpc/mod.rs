@@ -0,0 +1,32 @@
+//! gRPC surface of the operator API (feature `grpc`).
+//!
+//! `OperatorService` (see `proto/operator.proto`) mirrors the REST routes
+//! for listing, exec, prompt, task, lifecycle and secrets, and adds
+//! streaming RPCs for exec output and live chat/task/terminal events. Each
+//! RPC is transcoded into the matching REST request and dispatched
+//! in-process through the operator API router, so session auth, ownership
+//! checks, rate limits and command/model policies are shared with REST.
+//! Clients pass the session token as `authorization: Bearer <token>`
+//! metadata.
+//!
+//! [`grpc_router`] is merged into the operator API router: gRPC requests
+//! are matched by their `/sandbox.operator.v1.OperatorService/` path, so
+//! one port (and one BPM proxy registration) serves both protocols.
+
+mod convert;
+mod service;
+
+/// Generated protobuf messages, server and client for `sandbox.operator.v1`.
+pub mod pb {
+    tonic::include_proto!("sandbox.operator.v1");
+}
+
+pub use service::OperatorGrpc;
+
+/// Axum router serving [`OperatorGrpc`], to merge into the operator API.
+pub fn grpc_router() -> axum::Router {
+    tonic::service::Routes::new(pb::operator_service_server::OperatorServiceServer::new(
+        OperatorGrpc::default(),
+    ))
+    .into_axum_router()
+}
@@ -0,0 +1,591 @@
+//! `OperatorService` implementation: each RPC is transcoded into the
+//! matching REST request and dispatched through the operator API router.
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::time::Duration;
+
+use axum::Router;
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{HeaderValue, Method, StatusCode, header};
+use serde_json::{Map, Value, json};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Code, Request, Response, Status};
+use tower::ServiceExt;
+
+use super::convert::{from_pb_struct, to_pb_struct, to_pb_value};
+use super::pb::events_request::Source;
+use super::pb::exec_event::Event as ExecEventKind;
+use super::pb::operator_service_server::OperatorService;
+use super::pb::{self, LifecycleAction};
+use crate::executions::{ExecutionRecord, ExecutionStatus};
+
+/// Largest REST response body buffered for a unary RPC.
+const MAX_REST_BODY: usize = 16 * 1024 * 1024;
+/// How often `ExecStream` checks a running execution.
+const EXEC_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Largest `stdout`/`stderr` chunk per `ExecEvent`.
+const EXEC_CHUNK_BYTES: usize = 32 * 1024;
+
+type RpcStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// gRPC `OperatorService` backed by the operator REST router.
+#[derive(Clone)]
+pub struct OperatorGrpc {
+    rest: Router,
+}
+
+impl OperatorGrpc {
+    /// Serve RPCs through `rest`, normally
+    /// [`operator_api_router`](crate::operator_api::operator_api_router).
+    pub fn new(rest: Router) -> Self {
+        Self { rest }
+    }
+}
+
+impl Default for OperatorGrpc {
+    fn default() -> Self {
+        Self::new(crate::operator_api::operator_api_router())
+    }
+}
+
+/// Caller identity carried from the gRPC request onto its REST requests, so
+/// session auth and per-IP rate limits see the real client.
+#[derive(Clone)]
+struct Caller {
+    authorization: Option<HeaderValue>,
+    forwarded_for: Option<HeaderValue>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+}
+
+impl Caller {
+    fn of<T>(request: &Request<T>) -> Self {
+        let metadata = |name: &str| {
+            request
+                .metadata()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| HeaderValue::from_str(v).ok())
+        };
+        Self {
+            authorization: metadata("authorization"),
+            forwarded_for: metadata("x-forwarded-for"),
+            connect_info: request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .cloned(),
+        }
+    }
+}
+
+/// Reject IDs that would change the REST path they are spliced into.
+fn segment(id: &str) -> Result<&str, Status> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && id != "."
+        && id != "..";
+    if valid {
+        Ok(id)
+    } else {
+        Err(Status::invalid_argument(format!("Invalid ID '{id}'")))
+    }
+}
+
+/// REST path for `suffix` on `sandbox_id`, or on the instance sandbox when
+/// `sandbox_id` is empty.
+fn sandbox_path(sandbox_id: &str, suffix: &str) -> Result<String, Status> {
+    if sandbox_id.is_empty() {
+        Ok(format!("/api/sandbox{suffix}"))
+    } else {
+        Ok(format!("/api/sandboxes/{}{suffix}", segment(sandbox_id)?))
+    }
+}
+
+fn code_for(status: StatusCode) -> Code {
+    match status {
+        StatusCode::BAD_REQUEST
+        | StatusCode::PAYLOAD_TOO_LARGE
+        | StatusCode::UNPROCESSABLE_ENTITY => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::CONFLICT => Code::FailedPrecondition,
+        StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+        StatusCode::NOT_IMPLEMENTED => Code::Unimplemented,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        _ => Code::Internal,
+    }
+}
+
+async fn read_json(body: Body) -> Result<Value, Status> {
+    let bytes = axum::body::to_bytes(body, MAX_REST_BODY)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to read response: {e}")))?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| Status::internal(format!("Invalid response JSON: {e}")))
+}
+
+fn str_field(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn u64_field(value: &Value, key: &str) -> u64 {
+    value.get(key).and_then(Value::as_u64).unwrap_or_default()
+}
+
+fn bool_field(value: &Value, key: &str) -> bool {
+    value.get(key).and_then(Value::as_bool).unwrap_or_default()
+}
+
+impl OperatorGrpc {
+    /// Dispatch one REST request; non-2xx answers become a [`Status`]
+    /// carrying the REST error message.
+    async fn send(
+        &self,
+        caller: &Caller,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<axum::response::Response, Status> {
+        let mut builder = axum::http::Request::builder().method(method).uri(path);
+        if let Some(value) = &caller.authorization {
+            builder = builder.header(header::AUTHORIZATION, value.clone());
+        }
+        if let Some(value) = &caller.forwarded_for {
+            builder = builder.header("x-forwarded-for", value.clone());
+        }
+        let body = match body {
+            Some(json) => {
+                builder = builder.header(header::CONTENT_TYPE, "application/json");
+                Body::from(json.to_string())
+            }
+            None => Body::empty(),
+        };
+        let mut request = builder
+            .body(body)
+            .map_err(|e| Status::internal(format!("Failed to build request: {e}")))?;
+        if let Some(connect_info) = caller.connect_info {
+            request.extensions_mut().insert(connect_info);
+        }
+
+        let Ok(response) = self.rest.clone().oneshot(request).await;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let message = read_json(response.into_body())
+            .await
+            .ok()
+            .map(|body| str_field(&body, "error"))
+            .filter(|message| !message.is_empty())
+            .unwrap_or_else(|| status.to_string());
+        Err(Status::new(code_for(status), message))
+    }
+
+    async fn call(
+        &self,
+        caller: &Caller,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, Status> {
+        let response = self.send(caller, method, path, body).await?;
+        read_json(response.into_body()).await
+    }
+}
+
+fn exec_body(req: &pb::ExecRequest) -> Value {
+    json!({
+        "command": req.command,
+        "session_id": req.session_id,
+        "cwd": req.cwd,
+        "env_json": req.env_json,
+        "timeout_ms": req.timeout_ms,
+        "slot": req.slot,
+        "stdin": req.stdin,
+        "stdin_file": req.stdin_file,
+    })
+}
+
+fn run_accepted(body: &Value) -> pb::RunAccepted {
+    pb::RunAccepted {
+        run_id: str_field(body, "run_id"),
+        session_id: str_field(body, "session_id"),
+        status: str_field(body, "status"),
+        accepted_at: u64_field(body, "accepted_at"),
+    }
+}
+
+fn secrets_reply(body: &Value) -> pb::SecretsReply {
+    pb::SecretsReply {
+        sandbox_id: str_field(body, "sandbox_id"),
+        status: str_field(body, "status"),
+        env: body
+            .get("env_json")
+            .and_then(Value::as_object)
+            .cloned()
+            .map(to_pb_struct),
+        credentials_available: bool_field(body, "credentials_available"),
+        active_version: body
+            .get("active_version")
+            .and_then(Value::as_u64)
+            .and_then(|v| u32::try_from(v).ok()),
+    }
+}
+
+/// Split `text` into chunks of at most [`EXEC_CHUNK_BYTES`] on character
+/// boundaries.
+fn chunks(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(EXEC_CHUNK_BYTES);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        out.push(rest[..end].to_string());
+        rest = &rest[end..];
+    }
+    out
+}
+
+/// Output and exit events of a finished execution.
+fn exec_finished_events(record: ExecutionRecord) -> Vec<pb::ExecEvent> {
+    let event = |kind| pb::ExecEvent { event: Some(kind) };
+    let mut events: Vec<_> = chunks(&record.stdout)
+        .into_iter()
+        .map(|c| event(ExecEventKind::Stdout(c)))
+        .chain(
+            chunks(&record.stderr)
+                .into_iter()
+                .map(|c| event(ExecEventKind::Stderr(c))),
+        )
+        .collect();
+    let status = serde_json::to_value(record.status)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    events.push(event(ExecEventKind::Exit(pb::ExecExit {
+        status,
+        exit_code: record.exit_code,
+        truncated: record.truncated,
+        overflow_ref: record.overflow_ref.unwrap_or_default(),
+        error: record.error.unwrap_or_default(),
+    })));
+    events
+}
+
+#[tonic::async_trait]
+impl OperatorService for OperatorGrpc {
+    async fn list_sandboxes(
+        &self,
+        request: Request<pb::ListSandboxesRequest>,
+    ) -> Result<Response<pb::ListSandboxesReply>, Status> {
+        let caller = Caller::of(&request);
+        let body = self
+            .call(&caller, Method::GET, "/api/sandboxes", None)
+            .await?;
+        let sandboxes = body
+            .get("sandboxes")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|s| s.as_object().cloned())
+            .map(to_pb_struct)
+            .collect();
+        Ok(Response::new(pb::ListSandboxesReply { sandboxes }))
+    }
+
+    async fn exec(
+        &self,
+        request: Request<pb::ExecRequest>,
+    ) -> Result<Response<pb::ExecReply>, Status> {
+        let caller = Caller::of(&request);
+        let req = request.into_inner();
+        let path = sandbox_path(&req.sandbox_id, "/exec")?;
+        let body = self
+            .call(&caller, Method::POST, &path, Some(exec_body(&req)))
+            .await?;
+        Ok(Response::new(pb::ExecReply {
+            exit_code: u32::try_from(u64_field(&body, "exit_code")).unwrap_or(u32::MAX),
+            stdout: str_field(&body, "stdout"),
+            stderr: str_field(&body, "stderr"),
+            truncated: bool_field(&body, "truncated"),
+            overflow_ref: str_field(&body, "overflow_ref"),
+        }))
+    }
+
+    type ExecStreamStream = RpcStream<pb::ExecEvent>;
+
+    async fn exec_stream(
+        &self,
+        request: Request<pb::ExecRequest>,
+    ) -> Result<Response<Self::ExecStreamStream>, Status> {
+        let caller = Caller::of(&request);
+        let req = request.into_inner();
+        let path = sandbox_path(&req.sandbox_id, "/executions")?;
+        let started = self
+            .call(&caller, Method::POST, &path, Some(exec_body(&req)))
+            .await?;
+        // The start call checked ownership; poll the store directly so a
+        // long command does not spend the caller's read rate limit.
+        let sandbox_id = str_field(&started, "sandbox_id");
+        let execution_id = str_field(&started, "execution_id");
+        let first = pb::ExecEvent {
+            event: Some(ExecEventKind::Started(pb::ExecStarted {
+                execution_id: execution_id.clone(),
+                timeout_ms: u64_field(&started, "timeout_ms"),
+            })),
+        };
+
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            if tx.send(Ok(first)).await.is_err() {
+                return;
+            }
+            let record = loop {
+                tokio::time::sleep(EXEC_POLL_INTERVAL).await;
+                if tx.is_closed() {
+                    return;
+                }
+                match crate::executions::get_execution(&sandbox_id, &execution_id) {
+                    Ok(record) if record.status == ExecutionStatus::Running => {}
+                    Ok(record) => break record,
+                    Err(e) => {
+                        let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                        return;
+                    }
+                }
+            };
+            for event in exec_finished_events(record) {
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn prompt(
+        &self,
+        request: Request<pb::PromptRequest>,
+    ) -> Result<Response<pb::RunAccepted>, Status> {
+        let caller = Caller::of(&request);
+        let req = request.into_inner();
+        let path = sandbox_path(&req.sandbox_id, "/prompt")?;
+        let body = json!({
+            "message": req.message,
+            "session_id": req.session_id,
+            "backend_type": req.backend_type,
+            "model": req.model,
+            "context_json": req.context_json,
+            "timeout_ms": req.timeout_ms,
+            "slot": req.slot,
+        });
+        let body = self.call(&caller, Method::POST, &path, Some(body)).await?;
+        Ok(Response::new(run_accepted(&body)))
+    }
+
+    async fn task(
+        &self,
+        request: Request<pb::TaskRequest>,
+    ) -> Result<Response<pb::RunAccepted>, Status> {
+        let caller = Caller::of(&request);
+        let req = request.into_inner();
+        let path = sandbox_path(&req.sandbox_id, "/task")?;
+        let body = json!({
+            "prompt": req.prompt,
+            "session_id": req.session_id,
+            "max_turns": req.max_turns,
+            "backend_type": req.backend_type,
+            "model": req.model,
+            "context_json": req.context_json,
+            "timeout_ms": req.timeout_ms,
+            "slot": req.slot,
+        });
+        let body = self.call(&caller, Method::POST, &path, Some(body)).await?;
+        Ok(Response::new(run_accepted(&body)))
+    }
+
+    async fn lifecycle(
+        &self,
+        request: Request<pb::LifecycleRequest>,
+    ) -> Result<Response<pb::LifecycleReply>, Status> {
+        let caller = Caller::of(&request);
+        let req = request.into_inner();
+        let suffix = match req.action() {
+            LifecycleAction::Stop => "/stop",
+            LifecycleAction::Resume => "/resume",
+            LifecycleAction::Restart => "/restart",
+            LifecycleAction::Unspecified => {
+                return Err(Status::invalid_argument("action is required"));
+            }
+        };
+        let path = sandbox_path(&req.sandbox_id, suffix)?;
+        let body = self.call(&caller, Method::POST, &path, None).await?;
+        Ok(Response::new(pb::LifecycleReply {
+            success: bool_field(&body, "success"),
+            sandbox_id: str_field(&body, "sandbox_id"),
+            state: str_field(&body, "state"),
+        }))
+    }
+
+    async fn get_secrets(
+        &self,
+        request: Request<pb::SecretsRequest>,
+    ) -> Result<Response<pb::SecretsReply>, Status> {
+        let caller = Caller::of(&request);
+        let path = sandbox_path(&request.get_ref().sandbox_id, "/secrets")?;
+        let body = self.call(&caller, Method::GET, &path, None).await?;
+        Ok(Response::new(secrets_reply(&body)))
+    }
+
+    async fn inject_secrets(
+        &self,
+        request: Request<pb::InjectSecretsRequest>,
+    ) -> Result<Response<pb::SecretsReply>, Status> {
+        let caller = Caller::of(&request);
+        let req = request.into_inner();
+        let path = sandbox_path(&req.sandbox_id, "/secrets")?;
+        let mut body = Map::new();
+        body.insert(
+            "env_json".into(),
+            Value::Object(req.env.map(from_pb_struct).unwrap_or_default()),
+        );
+        if let Some(vault) = req.vault {
+            body.insert("vault".into(), Value::Object(from_pb_struct(vault)));
+        }
+        let body = self
+            .call(&caller, Method::POST, &path, Some(Value::Object(body)))
+            .await?;
+        Ok(Response::new(secrets_reply(&body)))
+    }
+
+    async fn wipe_secrets(
+        &self,
+        request: Request<pb::SecretsRequest>,
+    ) -> Result<Response<pb::SecretsReply>, Status> {
+        let caller = Caller::of(&request);
+        let path = sandbox_path(&request.get_ref().sandbox_id, "/secrets")?;
+        let body = self.call(&caller, Method::DELETE, &path, None).await?;
+        Ok(Response::new(secrets_reply(&body)))
+    }
+
+    type StreamEventsStream = RpcStream<pb::Event>;
+
+    async fn stream_events(
+        &self,
+        request: Request<pb::EventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        let caller = Caller::of(&request);
+        let req = request.into_inner();
+        let suffix = match &req.source {
+            Some(Source::ChatSessionId(id)) => {
+                format!("/live/chat/sessions/{}/stream", segment(id)?)
+            }
+            Some(Source::TaskId(id)) => format!("/tasks/{}/stream", segment(id)?),
+            Some(Source::TerminalSessionId(id)) => {
+                format!("/live/terminal/sessions/{}/stream", segment(id)?)
+            }
+            None => return Err(Status::invalid_argument("source is required")),
+        };
+        let path = sandbox_path(&req.sandbox_id, &suffix)?;
+        let response = self.send(&caller, Method::GET, &path, None).await?;
+
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            let mut body = response.into_body().into_data_stream();
+            let mut buffer = String::new();
+            while let Some(Ok(chunk)) = body.next().await {
+                buffer.push_str(&String::from_utf8_lossy(&chunk));
+                while let Some(index) = buffer.find("\n\n") {
+                    let frame: String = buffer.drain(..index + 2).collect();
+                    // Keep-alive comments carry no data and parse to `None`.
+                    let Some(event) = crate::operator_api::parse_sse_event(&frame) else {
+                        continue;
+                    };
+                    let event = pb::Event {
+                        event_type: event.event_type,
+                        data: Some(to_pb_value(event.data)),
+                    };
+                    if tx.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_cannot_escape_their_path_segment() {
+        assert_eq!(
+            sandbox_path("sandbox-1", "/exec").unwrap(),
+            "/api/sandboxes/sandbox-1/exec"
+        );
+        assert_eq!(sandbox_path("", "/exec").unwrap(), "/api/sandbox/exec");
+        for id in ["..", "a/b", "a?b", "a%2Fb", "a b"] {
+            assert_eq!(
+                sandbox_path(id, "/exec").unwrap_err().code(),
+                Code::InvalidArgument
+            );
+        }
+    }
+
+    #[test]
+    fn http_statuses_map_to_grpc_codes() {
+        assert_eq!(code_for(StatusCode::BAD_REQUEST), Code::InvalidArgument);
+        assert_eq!(code_for(StatusCode::UNAUTHORIZED), Code::Unauthenticated);
+        assert_eq!(code_for(StatusCode::FORBIDDEN), Code::PermissionDenied);
+        assert_eq!(code_for(StatusCode::NOT_FOUND), Code::NotFound);
+        assert_eq!(
+            code_for(StatusCode::TOO_MANY_REQUESTS),
+            Code::ResourceExhausted
+        );
+        assert_eq!(code_for(StatusCode::BAD_GATEWAY), Code::Unavailable);
+        assert_eq!(code_for(StatusCode::INTERNAL_SERVER_ERROR), Code::Internal);
+    }
+
+    #[test]
+    fn output_is_chunked_on_char_boundaries() {
+        let text = "é".repeat(EXEC_CHUNK_BYTES);
+        let parts = chunks(&text);
+        assert!(parts.iter().all(|p| p.len() <= EXEC_CHUNK_BYTES));
+        assert_eq!(parts.concat(), text);
+        assert!(chunks("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn rpcs_require_a_session_token() {
+        let service = OperatorGrpc::default();
+        let err = service
+            .list_sandboxes(Request::new(pb::ListSandboxesRequest {}))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        let err = service
+            .lifecycle(Request::new(pb::LifecycleRequest {
+                sandbox_id: "sandbox-1".into(),
+                action: LifecycleAction::Unspecified as i32,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+}
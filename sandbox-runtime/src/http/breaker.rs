@@ -0,0 +1,252 @@
+//! Per-endpoint circuit breaker for outbound sidecar calls.
+//!
+//! Keyed by URL origin (`scheme://host:port`), so every caller of the shared
+//! HTTP layer — jobs, batch fan-out, workflow ticks — sees the same state for
+//! a sidecar. Unlike the per-sandbox breaker in [`crate::circuit_breaker`],
+//! which the operator API trips on the first failure, this one opens only
+//! after [`failure_threshold`] consecutive unavailability failures (after
+//! retries), so a single slow request does not take a sidecar out. Only
+//! failed connections and `502`/`503`/`504` answers count: a timeout means
+//! the sidecar was reached, and long agent or exec runs legitimately hit
+//! their deadline (see [`record_send_error`]):
+//!
+//! - **Closed**: requests pass; failures are counted, any success resets.
+//! - **Open**: requests fail immediately with [`SandboxError::CircuitBreaker`]
+//!   until the cooldown (`CIRCUIT_BREAKER_COOLDOWN_SECS`) expires.
+//! - **Half-open**: one probe request is let through; success closes the
+//!   breaker, failure re-opens it for another cooldown.
+//!
+//! The threshold is `SIDECAR_BREAKER_FAILURES` (default 5; `0` disables).
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use reqwest::Url;
+
+use crate::error::{Result, SandboxError};
+
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Endpoints with no failure for this long are forgotten (and so closed).
+const IDLE_ENTRY_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Default)]
+struct EndpointState {
+    consecutive_failures: u32,
+    /// Set while open / half-open.
+    opened_at: Option<Instant>,
+    /// When the in-flight half-open probe started.
+    probe_started: Option<Instant>,
+    last_failure: Option<Instant>,
+}
+
+static ENDPOINTS: Lazy<Mutex<HashMap<String, EndpointState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static FAILURE_THRESHOLD: Lazy<u32> = Lazy::new(|| {
+    std::env::var("SIDECAR_BREAKER_FAILURES")
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .unwrap_or(DEFAULT_FAILURE_THRESHOLD)
+});
+
+/// Consecutive failures that open an endpoint's breaker (0 = disabled).
+pub fn failure_threshold() -> u32 {
+    *FAILURE_THRESHOLD
+}
+
+fn cooldown() -> Duration {
+    Duration::from_secs(crate::circuit_breaker::cooldown_secs())
+}
+
+fn endpoint_key(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+/// Reject the request quickly if `url`'s endpoint is open, or admit it (as
+/// the half-open probe once the cooldown has passed).
+pub fn check(url: &Url) -> Result<()> {
+    if failure_threshold() == 0 {
+        return Ok(());
+    }
+    let cooldown = cooldown();
+    let mut map = ENDPOINTS.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(state) = map.get_mut(&endpoint_key(url)) else {
+        return Ok(());
+    };
+    let Some(opened_at) = state.opened_at else {
+        return Ok(());
+    };
+    let elapsed = opened_at.elapsed();
+    if elapsed < cooldown {
+        return Err(SandboxError::CircuitBreaker {
+            remaining_secs: (cooldown - elapsed).as_secs().max(1),
+            probing: false,
+        });
+    }
+    // A probe that never reported back (its future was dropped) stops
+    // blocking after another cooldown.
+    if let Some(started) = state.probe_started
+        && started.elapsed() < cooldown
+    {
+        return Err(SandboxError::CircuitBreaker {
+            remaining_secs: 0,
+            probing: true,
+        });
+    }
+    state.probe_started = Some(Instant::now());
+    Ok(())
+}
+
+/// The endpoint answered: close its breaker.
+pub fn record_success(url: &Url) {
+    let mut map = ENDPOINTS.lock().unwrap_or_else(|e| e.into_inner());
+    map.remove(&endpoint_key(url));
+}
+
+/// The endpoint was unreachable or unavailable; open the breaker once the
+/// threshold is reached (immediately again after a failed probe).
+pub fn record_failure(url: &Url) {
+    let threshold = failure_threshold();
+    if threshold == 0 {
+        return;
+    }
+    let key = endpoint_key(url);
+    let mut map = ENDPOINTS.lock().unwrap_or_else(|e| e.into_inner());
+    map.retain(|_, state| {
+        state
+            .last_failure
+            .is_some_and(|at| at.elapsed() < IDLE_ENTRY_TTL)
+    });
+    let state = map.entry(key.clone()).or_default();
+    let now = Instant::now();
+    state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+    state.last_failure = Some(now);
+    let was_probing = state.probe_started.take().is_some();
+    if was_probing || state.consecutive_failures >= threshold {
+        if state.opened_at.is_none() {
+            tracing::warn!(
+                endpoint = %key,
+                failures = state.consecutive_failures,
+                "circuit breaker: opening for sidecar endpoint"
+            );
+        }
+        state.opened_at = Some(now);
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+pub fn clear_all_for_testing() {
+    ENDPOINTS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// A request to `url` failed before its response was read. Only a failed
+/// connection counts against the endpoint; any other error (a timeout, a
+/// dropped response) means the sidecar answered the connection.
+pub fn record_send_error(url: &Url, err: &reqwest::Error) {
+    if err.is_connect() {
+        record_failure(url);
+    } else {
+        record_success(url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(port: u16) -> Url {
+        Url::parse(&format!("http://127.0.0.1:{port}/terminals/commands")).unwrap()
+    }
+
+    fn force_cooldown_elapsed(url: &Url) {
+        let mut map = ENDPOINTS.lock().unwrap();
+        let state = map.get_mut(&endpoint_key(url)).unwrap();
+        state.opened_at = Some(Instant::now() - cooldown() - Duration::from_secs(1));
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures_only() {
+        let target = url(41001);
+        for _ in 0..failure_threshold() - 1 {
+            record_failure(&target);
+            assert!(check(&target).is_ok());
+        }
+        record_success(&target);
+        for _ in 0..failure_threshold() - 1 {
+            record_failure(&target);
+        }
+        assert!(check(&target).is_ok(), "success reset the count");
+        record_failure(&target);
+        assert!(matches!(
+            check(&target),
+            Err(SandboxError::CircuitBreaker { probing: false, .. })
+        ));
+        // Other paths on the same sidecar share the breaker; other sidecars don't.
+        let same_origin = Url::parse("http://127.0.0.1:41001/agents/run").unwrap();
+        assert!(check(&same_origin).is_err());
+        assert!(check(&url(41002)).is_ok());
+    }
+
+    #[test]
+    fn half_open_admits_one_probe() {
+        let target = url(41003);
+        for _ in 0..failure_threshold() {
+            record_failure(&target);
+        }
+        force_cooldown_elapsed(&target);
+        assert!(check(&target).is_ok(), "probe admitted");
+        assert!(matches!(
+            check(&target),
+            Err(SandboxError::CircuitBreaker { probing: true, .. })
+        ));
+
+        // Failed probe re-opens for a full cooldown.
+        record_failure(&target);
+        assert!(matches!(
+            check(&target),
+            Err(SandboxError::CircuitBreaker { probing: false, .. })
+        ));
+
+        force_cooldown_elapsed(&target);
+        assert!(check(&target).is_ok());
+        record_success(&target);
+        assert!(check(&target).is_ok());
+        assert!(check(&target).is_ok(), "closed after a successful probe");
+    }
+
+    #[tokio::test]
+    async fn only_connect_errors_count() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let slow = url(port);
+        // Accepts the connection but never answers.
+        let _server = tokio::spawn(async move {
+            let _conn = listener.accept().await;
+            std::future::pending::<()>().await;
+        });
+        let timeout = reqwest::Client::new()
+            .get(slow.clone())
+            .timeout(Duration::from_millis(50))
+            .send()
+            .await
+            .unwrap_err();
+        for _ in 0..failure_threshold() {
+            record_send_error(&slow, &timeout);
+        }
+        assert!(check(&slow).is_ok(), "timeouts must not open the breaker");
+
+        let closed = url(41004);
+        let refused = reqwest::Client::new()
+            .get(closed.clone())
+            .send()
+            .await
+            .unwrap_err();
+        for _ in 0..failure_threshold() {
+            record_send_error(&closed, &refused);
+        }
+        assert!(check(&closed).is_err());
+    }
+}
@@ -0,0 +1,375 @@
+pub mod breaker;
+mod retry;
+mod url_policy;
+
+use std::time::Duration;
+
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
+use reqwest::{Client, Method, Response, StatusCode, Url};
+use serde_json::Value;
+
+use crate::error::{Result, SandboxError};
+use crate::metrics::metrics;
+use crate::util::{http_client, http_client_no_timeout, request_timeout};
+
+pub use retry::{Failure, RetryPolicy};
+pub use url_policy::{PolicyResolver, UrlPolicy};
+pub(crate) use url_policy::{is_internal, parse_networks};
+
+/// Hard cap on the response body we will buffer from a sidecar or cloud
+/// attestation endpoint. Every byte ingested here is attacker-controlled in
+/// the TEE trust model (the sidecar/operator is untrusted), so a malicious
+/// producer must not be able to OOM the operator process by returning a
+/// multi-gigabyte body. Attestation reports are well under 128 KiB across all
+/// backends; 256 KiB leaves generous headroom while bounding allocation.
+const MAX_RESPONSE_BODY_BYTES: usize = 256 * 1024;
+
+/// Stream a response body into memory with a hard byte cap, failing closed once
+/// the cap is exceeded. Buffering with `response.text()`/`response.bytes()`
+/// allocates the entire (untrusted) body before we can inspect it; this reads
+/// chunk-by-chunk and aborts as soon as the accumulated length passes `max`, so
+/// a hostile producer cannot force unbounded allocation.
+async fn read_body_capped(mut response: Response, max: usize) -> Result<Vec<u8>> {
+    // Reject early if the producer advertises an over-cap body. This is an
+    // optimization only — the streaming loop below is the real enforcement,
+    // since Content-Length is itself attacker-controlled and may be absent.
+    if let Some(len) = response.content_length()
+        && len > max as u64
+    {
+        return Err(SandboxError::Http(format!(
+            "Response body too large: {len} bytes (max {max})"
+        )));
+    }
+
+    let mut buf: Vec<u8> = Vec::new();
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                if buf.len() + chunk.len() > max {
+                    return Err(SandboxError::Http(format!(
+                        "Response body exceeded {max} byte cap"
+                    )));
+                }
+                buf.extend_from_slice(&chunk);
+            }
+            Ok(None) => break,
+            Err(err) => {
+                return Err(SandboxError::Http(format!(
+                    "Failed to read response body: {err}"
+                )));
+            }
+        }
+    }
+    Ok(buf)
+}
+
+/// Join `path` onto a sidecar base URL. The result must stay on the base's
+/// origin and pass the [`UrlPolicy`], so a hostile sidecar URL or path cannot
+/// point the operator at internal services.
+pub fn build_url(base: &str, path: &str) -> Result<Url> {
+    let base_url =
+        Url::parse(base).map_err(|err| SandboxError::Http(format!("Invalid base URL: {err}")))?;
+    let url = base_url
+        .join(path)
+        .map_err(|err| SandboxError::Http(format!("Invalid path '{path}': {err}")))?;
+    if url.origin() != base_url.origin() {
+        return Err(SandboxError::Validation(format!(
+            "Path '{path}' leaves the sidecar origin"
+        )));
+    }
+    UrlPolicy::load().check(&url)?;
+    Ok(url)
+}
+
+pub fn auth_headers(token: &str) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    let value = HeaderValue::from_str(&format!("Bearer {token}"))
+        .map_err(|_| SandboxError::Auth("Invalid auth token".into()))?;
+    headers.insert(AUTHORIZATION, value);
+
+    Ok(headers)
+}
+
+/// Send a JSON request, retrying transient failures under [`RetryPolicy`].
+/// Endpoints whose [`breaker`] is open are rejected without a request.
+async fn send_json_with_client(
+    client: &Client,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+) -> Result<(StatusCode, String)> {
+    breaker::check(&url)?;
+    let policy = RetryPolicy::load();
+    let mut attempt = 0;
+    loop {
+        let mut request = client
+            .request(method.clone(), url.clone())
+            .headers(headers.clone());
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        if let Some(body) = &body {
+            request = request.json(body);
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => {
+                if policy.should_retry(&method, attempt, Failure::from_send_error(&err)) {
+                    backoff(policy, &method, &url, attempt, &err).await;
+                    attempt += 1;
+                    continue;
+                }
+                tracing::error!("reqwest send failed: {err:?}");
+                record_give_up(attempt);
+                breaker::record_send_error(&url, &err);
+                return Err(SandboxError::Http(format!("HTTP request failed: {err}")));
+            }
+        };
+        let status = response.status();
+        if !status.is_success() && policy.should_retry(&method, attempt, Failure::Status(status)) {
+            backoff(policy, &method, &url, attempt, &status).await;
+            attempt += 1;
+            continue;
+        }
+        if retry::is_transient_status(status) {
+            breaker::record_failure(&url);
+        } else {
+            breaker::record_success(&url);
+        }
+        let bytes = read_body_capped(response, MAX_RESPONSE_BODY_BYTES).await?;
+        let text = String::from_utf8(bytes)
+            .map_err(|_| SandboxError::Http("Response body was not valid UTF-8".into()))?;
+
+        if !status.is_success() {
+            record_give_up(attempt);
+            return Err(SandboxError::Http(format!("HTTP {status}: {text}")));
+        }
+
+        return Ok((status, text));
+    }
+}
+
+async fn backoff(
+    policy: &RetryPolicy,
+    method: &Method,
+    url: &Url,
+    attempt: u32,
+    reason: &dyn std::fmt::Display,
+) {
+    let delay = policy.delay(attempt);
+    metrics().record_sidecar_retry();
+    tracing::warn!(
+        %method,
+        %url,
+        attempt = attempt + 1,
+        delay_ms = delay.as_millis() as u64,
+        reason = %reason,
+        "Sidecar request failed, retrying"
+    );
+    tokio::time::sleep(delay).await;
+}
+
+/// Count requests that still failed after being retried.
+fn record_give_up(attempt: u32) {
+    if attempt > 0 {
+        metrics().record_sidecar_retries_exhausted();
+    }
+}
+
+pub async fn send_json(
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, String)> {
+    let client = http_client()?;
+    send_json_with_client(client, method, url, body, headers, None).await
+}
+
+/// Per-request timeout for a sidecar payload. Exec and agent payloads carry
+/// the sidecar-side deadline as `timeout` (ms); the HTTP wait follows it
+/// instead of the client default, so long runs are not cut off and short
+/// calls do not inherit a long global timeout.
+fn payload_timeout(payload: &Value) -> Duration {
+    let default = crate::runtime::SidecarRuntimeConfig::load().timeout;
+    let timeout_ms = payload.get("timeout").and_then(Value::as_u64).unwrap_or(0);
+    request_timeout(timeout_ms, default)
+}
+
+/// Correlation headers for a sidecar call, so sidecar logs and traces line
+/// up with the operator's:
+///
+/// - `x-request-id`: the operator API request ID, or the job trace ID when
+///   the call is made by an on-chain job;
+/// - `x-trace-id` and `traceparent`: the [`crate::job_trace`] of the job.
+pub fn correlation_headers(headers: &mut HeaderMap) {
+    let job = crate::job_trace::current();
+    let request_id = crate::operator_api::CURRENT_REQUEST_ID
+        .try_with(|id| id.clone())
+        .ok()
+        .or_else(|| job.as_ref().map(|t| t.trace_id.clone()));
+    if let Some(rid) = request_id
+        && let Ok(val) = HeaderValue::from_str(&rid)
+    {
+        headers.insert("x-request-id", val);
+    }
+    if let Some(trace) = job {
+        if let Ok(val) = HeaderValue::from_str(&trace.trace_id) {
+            headers.insert("x-trace-id", val);
+        }
+        if let Ok(val) = HeaderValue::from_str(&trace.traceparent()) {
+            headers.insert("traceparent", val);
+        }
+    }
+}
+
+pub async fn sidecar_post_json(
+    sidecar_url: &str,
+    path: &str,
+    token: &str,
+    payload: Value,
+) -> Result<Value> {
+    let url = build_url(sidecar_url, path)?;
+    let mut headers = auth_headers(token)?;
+
+    correlation_headers(&mut headers);
+
+    let timeout = payload_timeout(&payload);
+    let client = http_client()?;
+    let (_, body) = send_json_with_client(
+        client,
+        Method::POST,
+        url,
+        Some(payload),
+        headers,
+        Some(timeout),
+    )
+    .await?;
+    serde_json::from_str(&body)
+        .map_err(|err| SandboxError::Http(format!("Invalid sidecar response JSON: {err}")))
+}
+
+pub async fn sidecar_post_json_without_timeout(
+    sidecar_url: &str,
+    path: &str,
+    token: &str,
+    payload: Value,
+) -> Result<Value> {
+    let url = build_url(sidecar_url, path)?;
+    let mut headers = auth_headers(token)?;
+
+    correlation_headers(&mut headers);
+
+    let client = http_client_no_timeout()?;
+    let (_, body) =
+        send_json_with_client(client, Method::POST, url, Some(payload), headers, None).await?;
+    serde_json::from_str(&body)
+        .map_err(|err| SandboxError::Http(format!("Invalid sidecar response JSON: {err}")))
+}
+
+pub async fn sidecar_get_json(sidecar_url: &str, path: &str, token: &str) -> Result<Value> {
+    let url = build_url(sidecar_url, path)?;
+    let mut headers = auth_headers(token)?;
+
+    correlation_headers(&mut headers);
+
+    let (_, body) = send_json(Method::GET, url, None, headers).await?;
+    serde_json::from_str(&body)
+        .map_err(|err| SandboxError::Http(format!("Invalid sidecar response JSON: {err}")))
+}
+
+/// Headers that MUST NOT be forwarded from the client to the proxied backend.
+/// These are either hop-by-hop, security-sensitive (the operator's own auth),
+/// or set by the proxy itself.
+const STRIP_REQUEST_HEADERS: &[&str] = &[
+    "host",
+    "authorization", // operator PASETO — not for the backend
+    "connection",
+    "keep-alive",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+    "proxy-authorization",
+    "proxy-connection",
+    // Prevent leaking internal proxy topology to the container backend.
+    "x-forwarded-for",
+    "x-forwarded-proto",
+    "x-forwarded-host",
+    "x-real-ip",
+];
+
+/// Headers that MUST NOT be forwarded from the proxied backend to the client.
+const STRIP_RESPONSE_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+];
+
+/// Generic HTTP proxy: forward a request to a target URL and return the raw
+/// response (status, headers, body). Unlike [`sidecar_post_json`], this does
+/// not assume JSON and supports any HTTP method. Forwards safe request and
+/// response headers.
+pub async fn proxy_http(
+    target_url: Url,
+    method: Method,
+    request_headers: &HeaderMap,
+    body: Vec<u8>,
+) -> Result<(StatusCode, HeaderMap, Vec<u8>)> {
+    let client = http_client()?;
+    let mut request = client.request(method, target_url);
+
+    // Forward safe request headers
+    for (name, value) in request_headers.iter() {
+        if !STRIP_REQUEST_HEADERS
+            .iter()
+            .any(|&h| name.as_str().eq_ignore_ascii_case(h))
+        {
+            request = request.header(name, value);
+        }
+    }
+
+    // Propagate request ID for tracing
+    if let Ok(rid) = crate::operator_api::CURRENT_REQUEST_ID.try_with(|id| id.clone())
+        && let Ok(val) = HeaderValue::from_str(&rid)
+    {
+        request = request.header("x-request-id", val);
+    }
+
+    if !body.is_empty() {
+        request = request.body(body);
+    }
+
+    let response = request.send().await.map_err(|err| {
+        tracing::error!("proxy request failed: {err:?}");
+        SandboxError::Http(format!("Proxy request failed: {err}"))
+    })?;
+
+    let status = response.status();
+    let raw_headers = response.headers().clone();
+    let resp_body = read_body_capped(response, MAX_RESPONSE_BODY_BYTES).await?;
+
+    // Filter response headers
+    let mut resp_headers = HeaderMap::new();
+    for (name, value) in raw_headers.iter() {
+        if !STRIP_RESPONSE_HEADERS
+            .iter()
+            .any(|&h| name.as_str().eq_ignore_ascii_case(h))
+        {
+            resp_headers.append(name, value.clone());
+        }
+    }
+
+    Ok((status, resp_headers, resp_body))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,196 @@
+//! Retry policy for sidecar HTTP calls.
+//!
+//! A sidecar that is still waking up refuses connections, and the gateway in
+//! front of it can answer 502/503/504 for a moment. Those failures should not
+//! fail a whole on-chain job, so requests are retried with capped exponential
+//! backoff and full jitter — but only when a retry cannot run work twice:
+//!
+//! - connection failures (the request never reached the sidecar) are retried
+//!   for every method;
+//! - timeouts, dropped connections and gateway statuses are retried only for
+//!   idempotent methods (GET, HEAD, PUT, DELETE, OPTIONS).
+//!
+//! Configured with `SIDECAR_RETRY_ATTEMPTS` (total attempts, default 3; `1`
+//! disables retries), `SIDECAR_RETRY_BASE_MS` (default 200) and
+//! `SIDECAR_RETRY_MAX_MS` (default 5000).
+
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use rand::Rng;
+use reqwest::{Method, StatusCode};
+
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+pub const DEFAULT_RETRY_BASE_MS: u64 = 200;
+pub const DEFAULT_RETRY_MAX_MS: u64 = 5_000;
+
+static RETRY_POLICY: OnceCell<RetryPolicy> = OnceCell::new();
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first. Always at least 1.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_RETRY_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_RETRY_BASE_MS),
+            max_delay: Duration::from_millis(DEFAULT_RETRY_MAX_MS),
+        }
+    }
+}
+
+/// Why an attempt failed, as far as retrying is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Failure {
+    /// The connection could not be established; nothing was sent.
+    Connect,
+    /// The request may have reached the sidecar (timeout, reset, ...).
+    Transport,
+    /// The sidecar or gateway answered with this status.
+    Status(StatusCode),
+}
+
+impl Failure {
+    pub fn from_send_error(err: &reqwest::Error) -> Self {
+        if err.is_connect() {
+            Self::Connect
+        } else {
+            Self::Transport
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy from the environment, cached after the first call.
+    pub fn load() -> &'static RetryPolicy {
+        RETRY_POLICY.get_or_init(|| {
+            let env_u64 = |name: &str| {
+                std::env::var(name)
+                    .ok()
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+            };
+            let defaults = RetryPolicy::default();
+            RetryPolicy {
+                max_attempts: env_u64("SIDECAR_RETRY_ATTEMPTS")
+                    .map(|n| n.clamp(1, 10) as u32)
+                    .unwrap_or(defaults.max_attempts),
+                base_delay: env_u64("SIDECAR_RETRY_BASE_MS")
+                    .map(Duration::from_millis)
+                    .unwrap_or(defaults.base_delay),
+                max_delay: env_u64("SIDECAR_RETRY_MAX_MS")
+                    .map(Duration::from_millis)
+                    .unwrap_or(defaults.max_delay),
+            }
+        })
+    }
+
+    /// Whether a request that failed on attempt `attempt` (0-based) should
+    /// be sent again.
+    pub fn should_retry(&self, method: &Method, attempt: u32, failure: Failure) -> bool {
+        if attempt + 1 >= self.max_attempts {
+            return false;
+        }
+        match failure {
+            Failure::Connect => true,
+            Failure::Transport => is_idempotent(method),
+            Failure::Status(status) => is_idempotent(method) && is_transient_status(status),
+        }
+    }
+
+    /// Backoff before retry number `attempt + 1`: a random delay in
+    /// `[0, min(max_delay, base_delay * 2^attempt)]` ("full jitter").
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(self.max_delay);
+        let ceiling_ms = ceiling.as_millis() as u64;
+        if ceiling_ms == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::thread_rng().gen_range(0..=ceiling_ms))
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+/// Statuses a gateway returns while the upstream sidecar is unavailable.
+pub(super) fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(250),
+        }
+    }
+
+    #[test]
+    fn connect_failures_retry_for_any_method() {
+        let p = policy();
+        assert!(p.should_retry(&Method::POST, 0, Failure::Connect));
+        assert!(p.should_retry(&Method::GET, 1, Failure::Connect));
+        assert!(!p.should_retry(&Method::POST, 2, Failure::Connect));
+    }
+
+    #[test]
+    fn ambiguous_failures_retry_only_when_idempotent() {
+        let p = policy();
+        assert!(p.should_retry(&Method::GET, 0, Failure::Transport));
+        assert!(!p.should_retry(&Method::POST, 0, Failure::Transport));
+        let bad_gateway = Failure::Status(StatusCode::BAD_GATEWAY);
+        assert!(p.should_retry(&Method::GET, 0, bad_gateway));
+        assert!(!p.should_retry(&Method::POST, 0, bad_gateway));
+    }
+
+    #[test]
+    fn non_transient_statuses_never_retry() {
+        let p = policy();
+        for status in [
+            StatusCode::BAD_REQUEST,
+            StatusCode::NOT_FOUND,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ] {
+            assert!(!p.should_retry(&Method::GET, 0, Failure::Status(status)));
+        }
+    }
+
+    #[test]
+    fn single_attempt_disables_retries() {
+        let p = RetryPolicy {
+            max_attempts: 1,
+            ..policy()
+        };
+        assert!(!p.should_retry(&Method::GET, 0, Failure::Connect));
+    }
+
+    #[test]
+    fn delay_is_jittered_under_capped_exponential_ceiling() {
+        let p = policy();
+        for _ in 0..50 {
+            assert!(p.delay(0) <= Duration::from_millis(100));
+            assert!(p.delay(1) <= Duration::from_millis(200));
+            assert!(p.delay(5) <= Duration::from_millis(250));
+        }
+        assert!(p.delay(40) <= p.max_delay);
+    }
+}
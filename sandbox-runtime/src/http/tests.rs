@@ -0,0 +1,256 @@
+use super::*;
+
+// ── build_url ───────────────────────────────────────────────────────
+
+#[test]
+fn build_url_normal() {
+    let url = build_url("http://localhost:8080", "/api/test").unwrap();
+    assert_eq!(url.as_str(), "http://localhost:8080/api/test");
+}
+
+#[test]
+fn build_url_trailing_slash_on_base() {
+    let url = build_url("http://localhost:8080/", "/api/test").unwrap();
+    assert_eq!(url.as_str(), "http://localhost:8080/api/test");
+}
+
+#[test]
+fn build_url_no_leading_slash_on_path() {
+    let url = build_url("http://localhost:8080", "api/test").unwrap();
+    assert_eq!(url.as_str(), "http://localhost:8080/api/test");
+}
+
+#[test]
+fn build_url_empty_path() {
+    let url = build_url("http://localhost:8080", "").unwrap();
+    assert_eq!(url.as_str(), "http://localhost:8080/");
+}
+
+#[test]
+fn build_url_with_port_and_nested_path() {
+    let url = build_url("https://example.com:9443", "/v1/sandboxes/create").unwrap();
+    assert_eq!(url.as_str(), "https://example.com:9443/v1/sandboxes/create");
+}
+
+#[test]
+fn build_url_invalid_base() {
+    let result = build_url("not-a-url", "/api/test");
+    assert!(result.is_err());
+}
+
+#[test]
+fn build_url_base_with_path_prefix() {
+    // When the base already has a path segment, join should resolve relative to it
+    let url = build_url("http://localhost:8080/prefix/", "api/test").unwrap();
+    assert_eq!(url.as_str(), "http://localhost:8080/prefix/api/test");
+}
+
+#[test]
+fn build_url_rejects_cross_origin_path() {
+    let err = build_url("http://localhost:8080", "http://169.254.169.254/latest").unwrap_err();
+    assert!(matches!(err, SandboxError::Validation(_)), "{err}");
+    assert!(build_url("http://localhost:8080", "//example.com/x").is_err());
+}
+
+#[test]
+fn build_url_rejects_metadata_service() {
+    assert!(build_url("http://169.254.169.254", "/latest/meta-data/").is_err());
+    assert!(build_url("file:///etc/passwd", "").is_err());
+}
+
+// ── auth_headers ────────────────────────────────────────────────────
+
+#[test]
+fn auth_headers_contains_bearer_token() {
+    let headers = auth_headers("my-secret-token").unwrap();
+    let auth = headers.get(AUTHORIZATION).unwrap();
+    assert_eq!(auth.to_str().unwrap(), "Bearer my-secret-token");
+}
+
+#[test]
+fn auth_headers_contains_content_type() {
+    let headers = auth_headers("token").unwrap();
+    let ct = headers.get(CONTENT_TYPE).unwrap();
+    assert_eq!(ct.to_str().unwrap(), "application/json");
+}
+
+#[test]
+fn auth_headers_with_complex_token() {
+    let token = "v4.local.abcdef1234567890-complex.token";
+    let headers = auth_headers(token).unwrap();
+    let auth = headers.get(AUTHORIZATION).unwrap();
+    assert_eq!(
+        auth.to_str().unwrap(),
+        "Bearer v4.local.abcdef1234567890-complex.token"
+    );
+}
+
+#[test]
+fn auth_headers_rejects_invalid_token_chars() {
+    // Header values cannot contain certain control characters
+    let result = auth_headers("token\x00with\x01nulls");
+    assert!(result.is_err());
+}
+
+// ── read_body_capped ────────────────────────────────────────────────
+//
+// The body cap is the only thing standing between an untrusted sidecar
+// returning a multi-gigabyte attestation response and an operator-process
+// OOM, so it is covered directly. We serve the body in many small chunks
+// WITHOUT a Content-Length header (chunked transfer) to prove the cap is
+// enforced during streaming, not merely via the advertised length.
+
+use axum::Router;
+use axum::body::Body;
+use axum::routing::get;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+async fn spawn_body_server(total: usize) -> String {
+    let app = Router::new().route(
+        "/big",
+        get(move || async move {
+            // Stream `total` bytes in 8 KiB chunks with no Content-Length,
+            // forcing the reader to enforce the cap mid-stream.
+            let chunks = (0..total).step_by(8 * 1024).map(move |off| {
+                let len = (total - off).min(8 * 1024);
+                Ok::<_, std::convert::Infallible>(vec![b'a'; len])
+            });
+            let stream = tokio_stream::iter(chunks);
+            Body::from_stream(stream)
+        }),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("serve");
+    });
+    let base = format!("http://{addr}");
+    for _ in 0..50 {
+        if reqwest::get(format!("{base}/big")).await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    base
+}
+
+#[tokio::test]
+async fn read_body_capped_rejects_oversized_stream() {
+    let base = spawn_body_server(MAX_RESPONSE_BODY_BYTES + 64 * 1024).await;
+    let resp = reqwest::get(format!("{base}/big")).await.expect("request");
+    let err = read_body_capped(resp, MAX_RESPONSE_BODY_BYTES)
+        .await
+        .expect_err("over-cap body must fail closed");
+    match err {
+        SandboxError::Http(msg) => assert!(msg.contains("cap") || msg.contains("too large")),
+        other => panic!("expected Http cap error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn per_request_timeout_overrides_client_default() {
+    let app = Router::new().route(
+        "/slow",
+        get(|| async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            "{}"
+        }),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let url = build_url(
+        &format!("http://{}", listener.local_addr().unwrap()),
+        "/slow",
+    )
+    .unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+    let client = Client::builder()
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let short = send_json_with_client(
+        &client,
+        Method::GET,
+        url.clone(),
+        None,
+        HeaderMap::new(),
+        None,
+    )
+    .await;
+    assert!(short.is_err(), "client default timeout applies");
+
+    let (status, _) = send_json_with_client(
+        &client,
+        Method::GET,
+        url,
+        None,
+        HeaderMap::new(),
+        Some(Duration::from_secs(5)),
+    )
+    .await
+    .expect("per-request timeout outlasts the slow response");
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn gateway_errors_retry_only_idempotent_requests() {
+    use axum::extract::State;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    // Every other request (starting with the first) is answered with a 502.
+    async fn flaky(State(hits): State<Arc<AtomicU32>>) -> (StatusCode, &'static str) {
+        if hits.fetch_add(1, Ordering::SeqCst) % 2 == 0 {
+            (StatusCode::BAD_GATEWAY, "")
+        } else {
+            (StatusCode::OK, "{}")
+        }
+    }
+    let hits = Arc::new(AtomicU32::new(0));
+    let app = Router::new()
+        .route("/flaky", get(flaky).post(flaky))
+        .with_state(hits.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+    let client = Client::new();
+    let url = build_url(&base, "/flaky").unwrap();
+
+    let (status, _) = send_json_with_client(
+        &client,
+        Method::GET,
+        url.clone(),
+        None,
+        HeaderMap::new(),
+        None,
+    )
+    .await
+    .expect("GET retried past the 502");
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    let err = send_json_with_client(
+        &client,
+        Method::POST,
+        url,
+        Some(serde_json::json!({})),
+        HeaderMap::new(),
+        None,
+    )
+    .await
+    .expect_err("POST is not retried on a gateway error");
+    assert!(err.to_string().contains("502"));
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn read_body_capped_accepts_within_cap() {
+    let body_len = 16 * 1024;
+    let base = spawn_body_server(body_len).await;
+    let resp = reqwest::get(format!("{base}/big")).await.expect("request");
+    let bytes = read_body_capped(resp, MAX_RESPONSE_BODY_BYTES)
+        .await
+        .expect("under-cap body must succeed");
+    assert_eq!(bytes.len(), body_len);
+}
@@ -0,0 +1,333 @@
+//! Destination policy for sidecar-bound requests (SSRF protection).
+//!
+//! Sidecar URLs reach the operator from job arguments and stored records, and
+//! the operator attaches credentials to every request it sends to them. Each
+//! URL built by [`super::build_url`] must therefore pass:
+//!
+//! - **Scheme allowlist**: `SIDECAR_URL_SCHEMES` (default `http,https`).
+//! - **Address ranges**: IP literals (and `localhost`) in private, link-local
+//!   (including the `169.254.169.254` metadata service), carrier-grade NAT,
+//!   unique-local, multicast or unspecified ranges are rejected unless they
+//!   fall inside `SIDECAR_ALLOWED_NETWORKS` (CIDRs, default
+//!   `127.0.0.0/8,::1/128` for local Docker sidecars). The configured
+//!   `SIDECAR_PUBLIC_HOST` is always allowed when it is an IP.
+//! - **Domain allowlist** (optional): when `SIDECAR_ALLOWED_HOSTS` is set,
+//!   hostnames must equal an entry or, for entries starting with `.`, be a
+//!   subdomain of it.
+//! - **Resolved addresses**: the sidecar HTTP clients resolve hostnames
+//!   through [`PolicyResolver`], which drops addresses in the internal ranges
+//!   above, so `169.254.169.254.nip.io` fails like the literal would. They
+//!   do not follow redirects, so a sidecar cannot bounce a request past
+//!   [`UrlPolicy::check`] either.
+
+use std::net::{IpAddr, SocketAddr};
+
+use ipnet::IpNet;
+use once_cell::sync::OnceCell;
+use reqwest::Url;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::url::Host;
+
+use crate::error::{Result, SandboxError};
+
+const DEFAULT_SCHEMES: &[&str] = &["http", "https"];
+const DEFAULT_ALLOWED_NETWORKS: &str = "127.0.0.0/8,::1/128";
+
+static URL_POLICY: OnceCell<UrlPolicy> = OnceCell::new();
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UrlPolicy {
+    pub schemes: Vec<String>,
+    /// Internal ranges that are nonetheless reachable.
+    pub allowed_networks: Vec<IpNet>,
+    /// Empty means any hostname.
+    pub allowed_hosts: Vec<String>,
+}
+
+impl Default for UrlPolicy {
+    fn default() -> Self {
+        Self {
+            schemes: DEFAULT_SCHEMES.iter().map(|s| s.to_string()).collect(),
+            allowed_networks: parse_networks("SIDECAR_ALLOWED_NETWORKS", DEFAULT_ALLOWED_NETWORKS),
+            allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+fn csv(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(|s| s.trim().to_ascii_lowercase())
+        .filter(|s| !s.is_empty())
+}
+
+/// CIDRs (or bare IPs) from the comma-separated `value` of env var `var`.
+pub(crate) fn parse_networks(var: &str, value: &str) -> Vec<IpNet> {
+    csv(value)
+        .filter_map(|entry| {
+            entry
+                .parse::<IpNet>()
+                .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                .inspect_err(|_| {
+                    tracing::warn!(entry, "Ignoring invalid {var} entry");
+                })
+                .ok()
+        })
+        .collect()
+}
+
+impl UrlPolicy {
+    /// Policy from the environment, cached after the first call.
+    pub fn load() -> &'static UrlPolicy {
+        URL_POLICY.get_or_init(|| {
+            let mut policy = UrlPolicy::default();
+            if let Ok(schemes) = std::env::var("SIDECAR_URL_SCHEMES") {
+                policy.schemes = csv(&schemes).collect();
+            }
+            if let Ok(networks) = std::env::var("SIDECAR_ALLOWED_NETWORKS") {
+                policy.allowed_networks = parse_networks("SIDECAR_ALLOWED_NETWORKS", &networks);
+            }
+            if let Ok(host) = std::env::var("SIDECAR_PUBLIC_HOST")
+                && let Ok(ip) = host.trim().parse::<IpAddr>()
+            {
+                policy.allowed_networks.push(IpNet::from(ip));
+            }
+            if let Ok(hosts) = std::env::var("SIDECAR_ALLOWED_HOSTS") {
+                policy.allowed_hosts = csv(&hosts).collect();
+            }
+            policy
+        })
+    }
+
+    /// Reject `url` unless the policy allows sending a request to it.
+    pub fn check(&self, url: &Url) -> Result<()> {
+        let deny = |reason: String| {
+            Err(SandboxError::Validation(format!(
+                "Sidecar URL '{}' rejected: {reason}",
+                url.origin().ascii_serialization()
+            )))
+        };
+        if !self.schemes.iter().any(|s| s == url.scheme()) {
+            return deny(format!("scheme '{}' is not allowed", url.scheme()));
+        }
+        let ip = match url.host() {
+            None => return deny("missing host".into()),
+            Some(Host::Ipv4(v4)) => IpAddr::V4(v4),
+            Some(Host::Ipv6(v6)) => IpAddr::V6(v6),
+            Some(Host::Domain(domain)) => {
+                let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+                if domain == "localhost" || domain.ends_with(".localhost") {
+                    IpAddr::from([127, 0, 0, 1])
+                } else if self.host_allowed(&domain) {
+                    return Ok(());
+                } else {
+                    return deny(format!("host '{domain}' is not in SIDECAR_ALLOWED_HOSTS"));
+                }
+            }
+        };
+        if !self.ip_allowed(ip) {
+            return deny(format!(
+                "{} is an internal address (allow it with SIDECAR_ALLOWED_NETWORKS)",
+                canonical_ip(ip)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether a request may be sent to `ip`: public, or inside
+    /// `allowed_networks`.
+    pub fn ip_allowed(&self, ip: IpAddr) -> bool {
+        let ip = canonical_ip(ip);
+        !is_internal(ip) || self.allowed_networks.iter().any(|net| net.contains(&ip))
+    }
+
+    /// DNS resolver for clients that must only reach addresses this policy
+    /// allows.
+    pub fn resolver(&self) -> PolicyResolver {
+        PolicyResolver {
+            policy: self.clone(),
+        }
+    }
+
+    fn host_allowed(&self, domain: &str) -> bool {
+        self.allowed_hosts.is_empty()
+            || self
+                .allowed_hosts
+                .iter()
+                .any(|entry| match entry.strip_prefix('.') {
+                    Some(parent) => domain == parent || domain.ends_with(entry.as_str()),
+                    None => domain == entry,
+                })
+    }
+}
+
+/// Resolves hostnames with the system resolver and keeps only the addresses
+/// its [`UrlPolicy`] allows; a name with none left fails to resolve. IP
+/// literals never reach a resolver and are left to [`UrlPolicy::check`].
+#[derive(Clone, Debug)]
+pub struct PolicyResolver {
+    policy: UrlPolicy,
+}
+
+impl PolicyResolver {
+    fn filter(&self, host: &str, addrs: Vec<SocketAddr>) -> std::io::Result<Vec<SocketAddr>> {
+        let allowed: Vec<SocketAddr> = addrs
+            .into_iter()
+            .filter(|addr| self.policy.ip_allowed(addr.ip()))
+            .collect();
+        if allowed.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("host '{host}' resolves only to internal addresses"),
+            ));
+        }
+        Ok(allowed)
+    }
+}
+
+impl Resolve for PolicyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs = tokio::net::lookup_host((host, 0)).await?.collect();
+            let allowed = resolver.filter(host, addrs)?;
+            Ok::<Addrs, Box<dyn std::error::Error + Send + Sync>>(Box::new(allowed.into_iter()))
+        })
+    }
+}
+
+/// `ip` with an IPv4-mapped IPv6 address unwrapped.
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// Addresses that do not belong to a public sidecar (or any other public
+/// endpoint the operator is asked to call).
+pub(crate) fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                // Carrier-grade NAT (100.64.0.0/10)
+                || (a == 100 && (b & 0xc0) == 64)
+        }
+        IpAddr::V6(v6) => {
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                // Unique-local (fc00::/7)
+                || (v6.segments()[0] & 0xfe00) == 0xfc00
+                // Link-local (fe80::/10)
+                || (v6.segments()[0] & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(policy: &UrlPolicy, url: &str) -> Result<()> {
+        policy.check(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn default_allows_loopback_and_public_hosts() {
+        let p = UrlPolicy::default();
+        assert!(check(&p, "http://127.0.0.1:8080/terminals/commands").is_ok());
+        assert!(check(&p, "http://localhost:8080/").is_ok());
+        assert!(check(&p, "http://[::1]:8080/").is_ok());
+        assert!(check(&p, "https://sidecar.example.com/").is_ok());
+        assert!(check(&p, "https://203.0.113.7:9000/").is_ok());
+    }
+
+    #[test]
+    fn default_rejects_internal_ranges() {
+        let p = UrlPolicy::default();
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.5:8080/",
+            "http://192.168.1.1/",
+            "http://172.16.0.1/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0:8080/",
+            "http://[fe80::1]/",
+            "http://[fd00::1]/",
+            "http://[::ffff:169.254.169.254]/",
+        ] {
+            assert!(check(&p, url).is_err(), "{url} should be rejected");
+        }
+    }
+
+    #[test]
+    fn scheme_allowlist() {
+        let p = UrlPolicy::default();
+        assert!(check(&p, "file:///etc/passwd").is_err());
+        assert!(check(&p, "gopher://127.0.0.1:70/").is_err());
+        let https_only = UrlPolicy {
+            schemes: vec!["https".into()],
+            ..UrlPolicy::default()
+        };
+        assert!(check(&https_only, "http://127.0.0.1/").is_err());
+    }
+
+    #[test]
+    fn configured_networks_and_hosts() {
+        let p = UrlPolicy {
+            allowed_networks: parse_networks("TEST", "10.20.0.0/16, 192.168.7.4, bogus"),
+            allowed_hosts: vec![".sidecars.example.com".into(), "gw.example.net".into()],
+            ..UrlPolicy::default()
+        };
+        assert_eq!(p.allowed_networks.len(), 2);
+        assert!(check(&p, "http://10.20.3.4/").is_ok());
+        assert!(check(&p, "http://192.168.7.4/").is_ok());
+        assert!(check(&p, "http://10.21.0.1/").is_err());
+        assert!(
+            check(&p, "http://127.0.0.1/").is_err(),
+            "loopback no longer listed"
+        );
+
+        assert!(check(&p, "https://a.sidecars.example.com/").is_ok());
+        assert!(check(&p, "https://sidecars.example.com/").is_ok());
+        assert!(check(&p, "https://gw.example.net/").is_ok());
+        assert!(check(&p, "https://evilsidecars.example.com/").is_err());
+        assert!(check(&p, "https://other.example.net/").is_err());
+    }
+
+    #[test]
+    fn resolver_drops_internal_addresses() {
+        let resolver = UrlPolicy::default().resolver();
+        let addr = |s: &str| s.parse::<SocketAddr>().unwrap();
+        let kept = resolver
+            .filter(
+                "mixed.example",
+                vec![addr("169.254.169.254:0"), addr("203.0.113.7:0")],
+            )
+            .unwrap();
+        assert_eq!(kept, vec![addr("203.0.113.7:0")]);
+        assert!(
+            resolver
+                .filter("169.254.169.254.nip.io", vec![addr("169.254.169.254:0")])
+                .is_err()
+        );
+        assert!(
+            resolver
+                .filter("mapped.example", vec![addr("[::ffff:10.0.0.1]:0")])
+                .is_err()
+        );
+        assert!(
+            resolver
+                .filter("localhost", vec![addr("127.0.0.1:0")])
+                .is_ok()
+        );
+    }
+}
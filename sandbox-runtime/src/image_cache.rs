@@ -0,0 +1,362 @@
+//! Sidecar image pre-pull and local image cache management.
+//!
+//! On a fresh host the first provision stalled for minutes pulling the
+//! sidecar image. [`image_cache_tick`] pulls the configured `SIDECAR_IMAGE`
+//! plus any `IMAGE_PREPULL_EXTRA` images (comma-separated) at startup and
+//! every `IMAGE_PREPULL_INTERVAL_SECS`, so a provision finds them local. With
+//! `SIDECAR_PULL_IMAGE=false` nothing is pulled and the pass only checks the
+//! images are present. A reference pinned by digest (`repo@sha256:…`) must
+//! resolve to that digest locally, otherwise the image is reported with an
+//! error instead of being trusted.
+//!
+//! When the image layers on the host exceed `IMAGE_CACHE_MAX_GB`, each pass
+//! also removes images no container uses, oldest first, until back under the
+//! budget. Pre-pulled images, images a sandbox record was created from and
+//! snapshot images (left to the reaper GC) are never pruned.
+//!
+//! The last pass is kept in memory for `GET /api/operator/images`.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::runtime::{
+    RuntimeBackend, SidecarRuntimeConfig, current_sidecar_image, docker_builder, docker_timeout,
+    image_reference_with_tag, parse_runtime_backend_from_env, pull_image_with_progress,
+    retry_docker, sandboxes,
+};
+use crate::util::now_ts;
+
+/// Default seconds between [`image_cache_tick`] passes.
+pub const DEFAULT_IMAGE_PREPULL_INTERVAL_SECS: u64 = 3600;
+
+/// Repository prefix of committed sandbox snapshots (see
+/// [`crate::runtime::commit_container`]).
+const SNAPSHOT_REPO_PREFIX: &str = "sandbox-snapshot/";
+
+/// One pre-pulled image as of the last pass.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CachedImage {
+    pub reference: String,
+    pub present: bool,
+    pub image_id: Option<String>,
+    /// Registry digest the local image resolves to.
+    pub digest: Option<String>,
+    pub size_bytes: u64,
+    /// Unix time this pass pulled the image (`None` when not pulled).
+    pub pulled_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ImageCacheStatus {
+    pub images: Vec<CachedImage>,
+    /// Unix time of the last pass (0 = none yet).
+    pub checked_at: u64,
+    /// Size of all image layers on the host after the pass.
+    pub layers_bytes: u64,
+    /// `IMAGE_CACHE_MAX_GB` in bytes; `None` when pruning is off.
+    pub max_bytes: Option<u64>,
+    /// Image IDs the last pass pruned.
+    pub pruned: Vec<String>,
+    pub pruned_bytes: u64,
+}
+
+/// A local image as seen by the prune policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalImage {
+    pub id: String,
+    pub repo_tags: Vec<String>,
+    pub created: i64,
+    pub size_bytes: u64,
+    /// Containers (running or not) using the image.
+    pub containers: u64,
+}
+
+static STATUS: Lazy<Mutex<ImageCacheStatus>> = Lazy::new(Mutex::default);
+static PASS_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+/// Pre-pull runs when sandboxes default to the Docker backend, unless
+/// `IMAGE_PREPULL=false`.
+pub fn image_prepull_enabled() -> bool {
+    let disabled = std::env::var("IMAGE_PREPULL")
+        .map(|v| v.trim().eq_ignore_ascii_case("false") || v.trim() == "0")
+        .unwrap_or(false);
+    !disabled && matches!(parse_runtime_backend_from_env(), Ok(RuntimeBackend::Docker))
+}
+
+/// Seconds between passes: `IMAGE_PREPULL_INTERVAL_SECS`, default
+/// [`DEFAULT_IMAGE_PREPULL_INTERVAL_SECS`].
+pub fn image_prepull_interval() -> u64 {
+    std::env::var("IMAGE_PREPULL_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_IMAGE_PREPULL_INTERVAL_SECS)
+}
+
+/// `IMAGE_CACHE_MAX_GB` in bytes; unset or `0` turns pruning off.
+pub fn image_cache_max_bytes() -> Option<u64> {
+    std::env::var("IMAGE_CACHE_MAX_GB")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&gb| gb > 0)
+        .map(|gb| gb.saturating_mul(1024 * 1024 * 1024))
+}
+
+/// The sidecar image followed by the `IMAGE_PREPULL_EXTRA` images, tagged
+/// and without duplicates.
+pub fn prepull_images() -> Vec<String> {
+    let extra = std::env::var("IMAGE_PREPULL_EXTRA").unwrap_or_default();
+    let mut seen = HashSet::new();
+    std::iter::once(current_sidecar_image())
+        .chain(extra.split(',').map(str::to_string))
+        .map(|image| image.trim().to_string())
+        .filter(|image| !image.is_empty())
+        .map(|image| image_reference_with_tag(&image))
+        .filter(|image| seen.insert(image.clone()))
+        .collect()
+}
+
+/// The digest of `reference` among the image's `repo_digests`. A reference
+/// pinned by digest must be among them.
+pub fn resolve_image_digest(
+    reference: &str,
+    repo_digests: &[String],
+) -> std::result::Result<Option<String>, String> {
+    let digest_of = |repo_digest: &str| repo_digest.split_once('@').map(|(_, d)| d.to_string());
+    if let Some((_, pinned)) = reference.split_once('@') {
+        return if repo_digests
+            .iter()
+            .any(|d| digest_of(d).as_deref() == Some(pinned))
+        {
+            Ok(Some(pinned.to_string()))
+        } else {
+            Err(format!("Local image does not match pinned digest {pinned}"))
+        };
+    }
+    let repo = reference
+        .rsplit_once(':')
+        .filter(|(_, tag)| !tag.contains('/'))
+        .map_or(reference, |(repo, _)| repo);
+    Ok(repo_digests
+        .iter()
+        .find(|d| d.split_once('@').is_some_and(|(r, _)| r == repo))
+        .or_else(|| repo_digests.first())
+        .and_then(|d| digest_of(d)))
+}
+
+/// Image IDs to remove so `layers_bytes` drops to `max_bytes`: unused,
+/// unprotected images, oldest first. Protected images are matched by tag.
+pub fn plan_image_prune(
+    images: &[LocalImage],
+    protected: &HashSet<String>,
+    layers_bytes: u64,
+    max_bytes: u64,
+) -> Vec<String> {
+    let mut candidates: Vec<&LocalImage> = images
+        .iter()
+        .filter(|image| image.containers == 0)
+        .filter(|image| {
+            !image
+                .repo_tags
+                .iter()
+                .any(|tag| protected.contains(tag) || tag.starts_with(SNAPSHOT_REPO_PREFIX))
+        })
+        .collect();
+    candidates.sort_by_key(|image| image.created);
+
+    let mut remaining = layers_bytes;
+    let mut prune = Vec::new();
+    for image in candidates {
+        if remaining <= max_bytes {
+            break;
+        }
+        remaining = remaining.saturating_sub(image.size_bytes);
+        prune.push(image.id.clone());
+    }
+    prune
+}
+
+/// The last pass, or an empty status before the first one.
+pub fn image_cache_status() -> ImageCacheStatus {
+    STATUS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Pull (or check) every pre-pull image, then prune if over budget.
+/// Overlapping calls wait for the running pass.
+pub async fn image_cache_tick() -> Result<ImageCacheStatus> {
+    let _pass = PASS_LOCK.lock().await;
+    let builder = docker_builder().await?;
+    let pull = SidecarRuntimeConfig::load().pull_image;
+
+    let references = prepull_images();
+    let mut images = Vec::with_capacity(references.len());
+    for reference in &references {
+        let mut image = CachedImage {
+            reference: reference.clone(),
+            ..Default::default()
+        };
+        if pull {
+            match retry_docker("prepull_image", 2, 1000, || {
+                docker_timeout(
+                    "prepull_image",
+                    pull_image_with_progress(&builder, reference),
+                )
+            })
+            .await
+            {
+                Ok(()) => image.pulled_at = Some(now_ts()),
+                Err(err) => image.error = Some(err.to_string()),
+            }
+        }
+        match docker_timeout("inspect_image", builder.client().inspect_image(reference)).await {
+            Ok(inspect) => {
+                image.present = true;
+                image.image_id = inspect.id;
+                image.size_bytes = inspect.size.unwrap_or(0).max(0) as u64;
+                match resolve_image_digest(reference, &inspect.repo_digests.unwrap_or_default()) {
+                    Ok(digest) => image.digest = digest,
+                    Err(err) => image.error = Some(err),
+                }
+            }
+            // A failed pull already says why the image is missing.
+            Err(err) if image.error.is_none() => image.error = Some(err.to_string()),
+            Err(_) => {}
+        }
+        if let Some(err) = &image.error {
+            tracing::warn!(image = %reference, "Image pre-pull: {err}");
+        }
+        images.push(image);
+    }
+
+    let max_bytes = image_cache_max_bytes();
+    let usage = docker_timeout("df", builder.client().df()).await?;
+    let mut layers_bytes = usage.layers_size.unwrap_or(0).max(0) as u64;
+    let mut pruned = Vec::new();
+    let mut pruned_bytes = 0;
+    if let Some(max_bytes) = max_bytes.filter(|&max| layers_bytes > max) {
+        let local: Vec<LocalImage> = usage
+            .images
+            .unwrap_or_default()
+            .into_iter()
+            .map(|image| LocalImage {
+                id: image.id,
+                repo_tags: image.repo_tags,
+                created: image.created,
+                size_bytes: image.size.max(0) as u64,
+                containers: image.containers.max(0) as u64,
+            })
+            .collect();
+        let mut protected: HashSet<String> = references.iter().cloned().collect();
+        for record in sandboxes()?.values()? {
+            if !record.original_image.is_empty() {
+                protected.insert(image_reference_with_tag(&record.original_image));
+            }
+        }
+        for id in plan_image_prune(&local, &protected, layers_bytes, max_bytes) {
+            match docker_timeout(
+                "remove_image",
+                builder.client().remove_image(&id, None, None),
+            )
+            .await
+            {
+                Ok(_) => {
+                    let size = local
+                        .iter()
+                        .find(|image| image.id == id)
+                        .map_or(0, |image| image.size_bytes);
+                    pruned_bytes += size;
+                    layers_bytes = layers_bytes.saturating_sub(size);
+                    pruned.push(id);
+                }
+                Err(err) => tracing::warn!(image_id = %id, "Failed to prune image: {err}"),
+            }
+        }
+        tracing::info!(
+            pruned = pruned.len(),
+            pruned_bytes,
+            layers_bytes,
+            max_bytes,
+            "Pruned unused images over the image cache budget"
+        );
+    }
+
+    let status = ImageCacheStatus {
+        images,
+        checked_at: now_ts(),
+        layers_bytes,
+        max_bytes,
+        pruned,
+        pruned_bytes,
+    };
+    *STATUS.lock().unwrap_or_else(|e| e.into_inner()) = status.clone();
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(id: &str, tag: &str, created: i64, size: u64, containers: u64) -> LocalImage {
+        LocalImage {
+            id: id.to_string(),
+            repo_tags: vec![tag.to_string()],
+            created,
+            size_bytes: size,
+            containers,
+        }
+    }
+
+    #[test]
+    fn pinned_digest_must_match() {
+        let digests = vec!["ghcr.io/t/sidecar@sha256:aaa".to_string()];
+        assert_eq!(
+            resolve_image_digest("ghcr.io/t/sidecar@sha256:aaa", &digests),
+            Ok(Some("sha256:aaa".to_string()))
+        );
+        assert!(resolve_image_digest("ghcr.io/t/sidecar@sha256:bbb", &digests).is_err());
+        assert_eq!(
+            resolve_image_digest("ghcr.io/t/sidecar:latest", &digests),
+            Ok(Some("sha256:aaa".to_string()))
+        );
+        assert_eq!(resolve_image_digest("local/sidecar:dev", &[]), Ok(None));
+    }
+
+    #[test]
+    fn tagged_reference_prefers_its_own_repo_digest() {
+        let digests = vec![
+            "mirror.io/sidecar@sha256:aaa".to_string(),
+            "localhost:5000/sidecar@sha256:bbb".to_string(),
+        ];
+        assert_eq!(
+            resolve_image_digest("localhost:5000/sidecar:v2", &digests),
+            Ok(Some("sha256:bbb".to_string()))
+        );
+    }
+
+    #[test]
+    fn prune_removes_oldest_unused_until_under_budget() {
+        let images = vec![
+            image("newest", "old/tool:1", 30, 40, 0),
+            image("in-use", "app/web:1", 5, 100, 1),
+            image("oldest", "old/tool:0", 10, 30, 0),
+            image("sidecar", "ghcr.io/t/sidecar:latest", 1, 500, 0),
+            image("snap", "sandbox-snapshot/sb-1:latest", 2, 200, 0),
+            image("middle", "old/tool:0.5", 20, 30, 0),
+        ];
+        let protected = HashSet::from(["ghcr.io/t/sidecar:latest".to_string()]);
+        assert_eq!(
+            plan_image_prune(&images, &protected, 1000, 950),
+            vec!["oldest", "middle"]
+        );
+        assert!(plan_image_prune(&images, &protected, 900, 950).is_empty());
+        assert_eq!(
+            plan_image_prune(&images, &protected, 10_000, 0),
+            vec!["oldest", "middle", "newest"]
+        );
+    }
+}
@@ -14,12 +14,24 @@ sol! {
         string cwd;
         string env_json;
         uint64 timeout_ms;
+        /// Instance slot to run in (empty = `main`).
+        string slot;
+        /// Text piped to the command's stdin; see `crate::exec_input`.
+        string stdin;
+        /// Sandbox file piped to stdin instead (exclusive with `stdin`).
+        string stdin_file;
+        /// Exec preset of the sandbox supplying cwd, env, timeout and shell
+        /// defaults (empty = none); see `crate::exec_presets`.
+        string preset;
     }
 
+    /// `stdout`/`stderr` are capped at `EXEC_OUTPUT_MAX_BYTES` each; see
+    /// `crate::exec_output`. `overflow_ref` is empty unless output was cut.
     struct InstanceExecResponse {
         uint32 exit_code;
         string stdout;
         string stderr;
+        string overflow_ref;
     }
 
     // ── Prompt (instance-scoped — no sidecar_url/token) ─────────────────
@@ -30,6 +42,13 @@ sol! {
         string model;
         string context_json;
         uint64 timeout_ms;
+        /// Instance slot to run in (empty = `main`).
+        string slot;
+        /// Sidecar agent to run (empty = the instance's `agent_identifier`).
+        string agent_identifier;
+        /// Operator-tracked session to run in instead of `session_id`
+        /// (empty = none); see `crate::named_sessions`.
+        string session_name;
     }
 
     struct InstancePromptResponse {
@@ -51,6 +70,19 @@ sol! {
         string model;
         string context_json;
         uint64 timeout_ms;
+        /// Instance slot to run in (empty = `main`).
+        string slot;
+        /// JSON Schema the result must satisfy (empty = free text); see
+        /// `crate::output_schema`.
+        string output_schema_json;
+        /// Tool allowlist for the agent (empty = unrestricted); see
+        /// `crate::tool_policy`.
+        string tools_json;
+        /// Sidecar agent to run (empty = the instance's `agent_identifier`).
+        string agent_identifier;
+        /// Operator-tracked session to run in instead of `session_id`
+        /// (empty = none); see `crate::named_sessions`.
+        string session_name;
     }
 
     struct InstanceTaskResponse {
@@ -62,5 +94,23 @@ sol! {
         uint32 input_tokens;
         uint32 output_tokens;
         string session_id;
+        /// Tool policy the sidecar confirmed applying (empty = none).
+        string tools_json;
+    }
+}
+
+impl From<&InstanceExecRequest> for crate::api_types::ExecApiRequest {
+    fn from(request: &InstanceExecRequest) -> Self {
+        Self {
+            command: request.command.clone(),
+            session_id: String::new(),
+            cwd: request.cwd.clone(),
+            env_json: request.env_json.clone(),
+            timeout_ms: request.timeout_ms,
+            slot: request.slot.clone(),
+            stdin: request.stdin.clone(),
+            stdin_file: request.stdin_file.clone(),
+            preset: request.preset.clone(),
+        }
     }
 }
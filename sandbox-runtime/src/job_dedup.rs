@@ -0,0 +1,304 @@
+//! Job idempotency keyed by on-chain call ID.
+//!
+//! A `JobSubmitted` event can be delivered more than once (producer restarts,
+//! re-orgs, replayed blocks). Mutating job handlers run through [`run_once`],
+//! which records each successful result — ABI-encoded, with its keccak256
+//! hash — in `processed_calls.json` under `(service_id, call_id)`. A duplicate
+//! delivery returns the stored result instead of creating a second sandbox
+//! or running the command again, so the consumer resubmits identical bytes.
+//!
+//! Failed calls are not recorded: nothing was submitted for them, so a
+//! redelivery is a legitimate retry. The GC tick prunes records after
+//! `JOB_DEDUP_RETENTION_SECS` (default 30 days). Blueprint handlers use
+//! [`once`], which runs [`run_once`] under [`crate::job_trace::traced`].
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use alloy::sol_types::{SolType, SolValue};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SandboxError};
+use crate::job_replay::JobInput;
+use crate::job_trace::{JobOutput, traced};
+use crate::session_auth::keccak256_hex;
+use crate::store::PersistentStore;
+use crate::util::now_ts;
+
+const DEFAULT_RETENTION_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// A completed job call and the result it produced.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProcessedCall {
+    pub service_id: u64,
+    pub call_id: u64,
+    pub job: u8,
+    /// Hex ABI-encoded job output.
+    pub output: String,
+    /// `0x` keccak256 of the output bytes.
+    pub output_hash: String,
+    pub completed_at: u64,
+}
+
+static PROCESSED: OnceCell<PersistentStore<ProcessedCall>> = OnceCell::new();
+
+/// Per-call locks so a duplicate that arrives while the original is still
+/// running waits for its result instead of running concurrently.
+static IN_FLIGHT: Lazy<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static RETENTION_SECS: Lazy<u64> = Lazy::new(|| {
+    std::env::var("JOB_DEDUP_RETENTION_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_RETENTION_SECS)
+});
+
+/// Access the processed-call store (`processed_calls.json`).
+pub fn processed_calls() -> Result<&'static PersistentStore<ProcessedCall>> {
+    PROCESSED.get_or_try_init(|| {
+        let path = crate::store::state_dir().join("processed_calls.json");
+        PersistentStore::open(path)
+    })
+}
+
+fn call_key(service_id: u64, call_id: u64) -> String {
+    format!("{service_id}:{call_id}")
+}
+
+/// The stored output of an already-processed call, if its hash still matches.
+pub fn lookup(service_id: u64, call_id: u64, job: u8) -> Result<Option<Vec<u8>>> {
+    let Some(call) = processed_calls()?.get(&call_key(service_id, call_id))? else {
+        return Ok(None);
+    };
+    if call.job != job {
+        tracing::warn!(
+            service_id,
+            call_id,
+            stored_job = call.job,
+            job,
+            "Processed call recorded for a different job; ignoring"
+        );
+        return Ok(None);
+    }
+    let output = hex::decode(&call.output)
+        .map_err(|e| SandboxError::Storage(format!("Corrupt processed call output: {e}")))?;
+    if keccak256_hex(&output) != call.output_hash {
+        return Err(SandboxError::Storage(format!(
+            "Processed call {service_id}:{call_id} failed its integrity check"
+        )));
+    }
+    Ok(Some(output))
+}
+
+/// Record the ABI-encoded output of a successfully processed call.
+pub fn record(service_id: u64, call_id: u64, job: u8, output: &[u8]) -> Result<()> {
+    processed_calls()?.insert(
+        call_key(service_id, call_id),
+        ProcessedCall {
+            service_id,
+            call_id,
+            job,
+            output: hex::encode(output),
+            output_hash: keccak256_hex(output),
+            completed_at: now_ts(),
+        },
+    )
+}
+
+/// Drop processed calls older than `JOB_DEDUP_RETENTION_SECS`. Called from
+/// the GC tick.
+pub fn gc_processed_calls() -> Result<()> {
+    let store = processed_calls()?;
+    let now = now_ts();
+    let retention = *RETENTION_SECS;
+    for call in store.values()? {
+        if now.saturating_sub(call.completed_at) > retention {
+            store.remove(&call_key(call.service_id, call.call_id))?;
+        }
+    }
+    Ok(())
+}
+
+/// Run `job` for `(service_id, call_id)` at most once. A duplicate returns
+/// the recorded output; a failure is returned as-is and not recorded.
+pub async fn run_once<T, Fut>(
+    service_id: u64,
+    call_id: u64,
+    job: u8,
+    run: Fut,
+) -> std::result::Result<T, String>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType>,
+    Fut: Future<Output = std::result::Result<T, String>>,
+{
+    let key = call_key(service_id, call_id);
+    let lock = IN_FLIGHT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(key.clone())
+        .or_default()
+        .clone();
+    let result = {
+        let _guard = lock.lock().await;
+        run_locked(service_id, call_id, job, run).await
+    };
+    let mut in_flight = IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner());
+    // Two references: the map's and ours. Anyone else still waits on it.
+    if Arc::strong_count(&lock) <= 2 {
+        in_flight.remove(&key);
+    }
+    result
+}
+
+/// Run a mutating job at most once per `(service_id, call_id)` under
+/// [`traced`]: a redelivered `JobSubmitted` event gets the recorded result
+/// back instead of repeating the side effects.
+pub async fn once<T, Fut>(
+    service_id: u64,
+    call_id: u64,
+    job: u8,
+    input: JobInput,
+    run: Fut,
+) -> std::result::Result<T, String>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType> + JobOutput,
+    Fut: Future<Output = std::result::Result<T, String>>,
+{
+    let run = run_once(service_id, call_id, job, run);
+    traced(service_id, call_id, job, false, input, run).await
+}
+
+async fn run_locked<T, Fut>(
+    service_id: u64,
+    call_id: u64,
+    job: u8,
+    run: Fut,
+) -> std::result::Result<T, String>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType>,
+    Fut: Future<Output = std::result::Result<T, String>>,
+{
+    match lookup(service_id, call_id, job) {
+        Ok(Some(output)) => match T::abi_decode(&output) {
+            Ok(value) => {
+                tracing::info!(
+                    service_id,
+                    call_id,
+                    job,
+                    "Duplicate job call; returning recorded result"
+                );
+                return Ok(value);
+            }
+            Err(e) => {
+                tracing::warn!(service_id, call_id, job, error = %e, "Recorded result does not decode; re-running");
+            }
+        },
+        Ok(None) => {}
+        // A store we cannot read must not block jobs; run and try to record.
+        Err(e) => {
+            tracing::warn!(service_id, call_id, job, error = %e, "Processed-call lookup failed")
+        }
+    }
+
+    let value = run.await?;
+    if let Err(e) = record(service_id, call_id, job, &value.abi_encode()) {
+        tracing::warn!(service_id, call_id, job, error = %e, "Failed to record processed call");
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    alloy::sol! {
+        struct Out { string json; }
+    }
+
+    fn init() {
+        let dir = std::env::temp_dir().join(format!("job-dedup-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", dir) };
+    }
+
+    #[tokio::test]
+    async fn duplicate_call_returns_recorded_result() {
+        init();
+        let counter = AtomicU32::new(0);
+        let runs = &counter;
+        let job = move |n: &'static str| async move {
+            runs.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, String>(Out { json: n.into() })
+        };
+
+        let first = run_once(7, 100, 1, job("first")).await.unwrap();
+        let second = run_once(7, 100, 1, job("second")).await.unwrap();
+        assert_eq!(first.json, "first");
+        assert_eq!(second.json, "first", "replay returns the stored output");
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // Different service, call or job ID runs again.
+        assert_eq!(run_once(8, 100, 1, job("b")).await.unwrap().json, "b");
+        assert_eq!(run_once(7, 101, 1, job("c")).await.unwrap().json, "c");
+        assert_eq!(run_once(7, 100, 2, job("d")).await.unwrap().json, "d");
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn failures_are_not_recorded() {
+        init();
+        let err = run_once::<Out, _>(7, 200, 1, async { Err("boom".to_string()) }).await;
+        assert_eq!(err.unwrap_err(), "boom");
+        let ok = run_once(7, 200, 1, async {
+            Ok::<_, String>(Out { json: "ok".into() })
+        })
+        .await
+        .unwrap();
+        assert_eq!(ok.json, "ok");
+    }
+
+    #[test]
+    fn gc_drops_expired_calls() {
+        init();
+        record(7, 400, 1, b"new").unwrap();
+        record(7, 401, 1, b"old").unwrap();
+        processed_calls()
+            .unwrap()
+            .update(&call_key(7, 401), |c| c.completed_at = 0)
+            .unwrap();
+        gc_processed_calls().unwrap();
+        assert!(lookup(7, 400, 1).unwrap().is_some());
+        assert!(lookup(7, 401, 1).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn tampered_record_is_rejected() {
+        init();
+        record(
+            7,
+            300,
+            1,
+            &Out {
+                json: "real".into(),
+            }
+            .abi_encode(),
+        )
+        .unwrap();
+        processed_calls()
+            .unwrap()
+            .update(&call_key(7, 300), |c| {
+                c.output = hex::encode(
+                    Out {
+                        json: "fake".into(),
+                    }
+                    .abi_encode(),
+                )
+            })
+            .unwrap();
+        assert!(lookup(7, 300, 1).is_err());
+    }
+}
@@ -0,0 +1,287 @@
+//! Typed job errors.
+//!
+//! Job handlers return `Err(String)`. To let callers tell a bad request from
+//! a full operator, an unreachable sidecar or a TEE failure, the string is a
+//! structured JSON payload:
+//!
+//! ```json
+//! {"error":{"code":"SIDECAR_UNAVAILABLE","codeId":5,"message":"http error: ...","traceId":"..."}}
+//! ```
+//!
+//! [`structured`] classifies a handler's error message into an [`ErrorCode`]
+//! and wraps it; wrapping is idempotent. The same payload is available as
+//! the ABI [`ErrorResponse`] struct for on-chain consumers.
+
+use alloy::sol;
+use alloy::sol_types::SolValue;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::error::SandboxError;
+
+sol! {
+    /// ABI form of a failed job. `code` is an [`ErrorCode`] ID.
+    struct ErrorResponse {
+        uint16 code;
+        string name;
+        string message;
+    }
+}
+
+/// Error taxonomy shared by every blueprint job. IDs are stable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// Malformed or out-of-policy request arguments.
+    Validation = 1,
+    /// Caller does not own the resource or lacks permission.
+    Unauthorized = 2,
+    /// The sandbox, workflow, slot or task does not exist.
+    NotFound = 3,
+    /// Operator at capacity, rate limited or otherwise temporarily unavailable.
+    Capacity = 4,
+    /// The sidecar could not be reached or returned an error.
+    SidecarUnavailable = 5,
+    /// TEE provisioning, attestation or sealed-secret failure.
+    Tee = 6,
+    /// Container / VM runtime or cloud provider failure.
+    Runtime = 7,
+    /// Operator state could not be read or written.
+    Storage = 8,
+    /// The operation is not supported by this operator.
+    Unsupported = 9,
+    /// Anything not classified above.
+    Internal = 10,
+}
+
+impl ErrorCode {
+    pub fn id(self) -> u16 {
+        self as u16
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Validation => "VALIDATION",
+            Self::Unauthorized => "UNAUTHORIZED",
+            Self::NotFound => "NOT_FOUND",
+            Self::Capacity => "CAPACITY",
+            Self::SidecarUnavailable => "SIDECAR_UNAVAILABLE",
+            Self::Tee => "TEE",
+            Self::Runtime => "RUNTIME",
+            Self::Storage => "STORAGE",
+            Self::Unsupported => "UNSUPPORTED",
+            Self::Internal => "INTERNAL",
+        }
+    }
+
+    /// Best-effort classification of a free-form error message. Messages
+    /// produced by [`SandboxError`]'s `Display` are matched on their prefix
+    /// first; anything else falls back to keywords.
+    pub fn classify(message: &str) -> Self {
+        let lower = message.to_ascii_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+        let tee = has(&["attestation", "enclave", "sealed", "measurement"])
+            || lower
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .any(|word| word == "tee");
+
+        const PREFIXES: &[(&str, ErrorCode)] = &[
+            ("auth error:", ErrorCode::Unauthorized),
+            ("not found:", ErrorCode::NotFound),
+            ("service unavailable:", ErrorCode::Capacity),
+            ("http error:", ErrorCode::SidecarUnavailable),
+            ("circuit breaker:", ErrorCode::SidecarUnavailable),
+            ("storage error:", ErrorCode::Storage),
+            ("unsupported:", ErrorCode::Unsupported),
+            ("docker error:", ErrorCode::Runtime),
+            ("cloud provider error:", ErrorCode::Runtime),
+        ];
+        if let Some((_, code)) = PREFIXES.iter().find(|(p, _)| lower.starts_with(p)) {
+            return *code;
+        }
+        if lower.starts_with("validation error:") {
+            return if tee { Self::Tee } else { Self::Validation };
+        }
+
+        if has(&[
+            "does not own",
+            "unauthorized",
+            "not authorized",
+            "forbidden",
+        ]) {
+            Self::Unauthorized
+        } else if has(&["not found", "no such"]) {
+            Self::NotFound
+        } else if has(&["capacity", "rate limit", "too many", "quota"]) {
+            Self::Capacity
+        } else if tee {
+            Self::Tee
+        } else if has(&["sidecar"]) {
+            Self::SidecarUnavailable
+        } else if has(&["not supported"]) {
+            Self::Unsupported
+        } else if has(&[
+            "invalid", "must ", "required", "exceeds", "too long", "empty",
+        ]) {
+            Self::Validation
+        } else {
+            Self::Internal
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A classified job failure.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobError {
+    pub code: ErrorCode,
+    pub code_id: u16,
+    pub message: String,
+    /// [`crate::job_trace`] ID of the failed job, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+}
+
+impl JobError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            code_id: code.id(),
+            message: message.into(),
+            trace_id: None,
+        }
+    }
+
+    pub fn with_trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = Some(trace_id.into());
+        self
+    }
+
+    /// Parse a payload produced by [`JobError::to_json`].
+    pub fn parse(payload: &str) -> Option<Self> {
+        let value: serde_json::Value = serde_json::from_str(payload).ok()?;
+        serde_json::from_value(value.get("error")?.clone()).ok()
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({ "error": self }).to_string()
+    }
+
+    pub fn to_abi(&self) -> ErrorResponse {
+        ErrorResponse {
+            code: self.code.id(),
+            name: self.code.as_str().to_string(),
+            message: self.message.clone(),
+        }
+    }
+
+    pub fn abi_encode(&self) -> Vec<u8> {
+        self.to_abi().abi_encode()
+    }
+}
+
+impl From<SandboxError> for JobError {
+    fn from(err: SandboxError) -> Self {
+        let code = match &err {
+            SandboxError::Auth(_) => ErrorCode::Unauthorized,
+            SandboxError::NotFound(_) => ErrorCode::NotFound,
+            SandboxError::Unavailable(_) => ErrorCode::Capacity,
+            SandboxError::Http(_) | SandboxError::CircuitBreaker { .. } => {
+                ErrorCode::SidecarUnavailable
+            }
+            SandboxError::Storage(_) => ErrorCode::Storage,
+            SandboxError::Unsupported(_) => ErrorCode::Unsupported,
+            SandboxError::Docker(_) | SandboxError::CloudProvider(_) => ErrorCode::Runtime,
+            SandboxError::Validation(_) => ErrorCode::classify(&err.to_string()),
+        };
+        Self::new(code, err.to_string())
+    }
+}
+
+impl From<String> for JobError {
+    fn from(message: String) -> Self {
+        Self::parse(&message).unwrap_or_else(|| Self::new(ErrorCode::classify(&message), message))
+    }
+}
+
+impl From<JobError> for String {
+    fn from(err: JobError) -> Self {
+        err.to_json()
+    }
+}
+
+/// Wrap a handler's error message as a structured payload, tagged with the
+/// current job's trace ID. Already structured messages keep their fields.
+pub fn structured(message: String) -> String {
+    let mut err = JobError::from(message);
+    if err.trace_id.is_none() {
+        err.trace_id = crate::job_trace::current_trace_id();
+    }
+    err.to_json()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_sandbox_error_messages() {
+        let cases = [
+            (SandboxError::Auth("x".into()), ErrorCode::Unauthorized),
+            (SandboxError::NotFound("x".into()), ErrorCode::NotFound),
+            (SandboxError::Unavailable("x".into()), ErrorCode::Capacity),
+            (
+                SandboxError::Http("x".into()),
+                ErrorCode::SidecarUnavailable,
+            ),
+            (SandboxError::Storage("x".into()), ErrorCode::Storage),
+            (SandboxError::Docker("x".into()), ErrorCode::Runtime),
+            (
+                SandboxError::Validation("bad cpu".into()),
+                ErrorCode::Validation,
+            ),
+            (
+                SandboxError::Validation("TEE backend not configured".into()),
+                ErrorCode::Tee,
+            ),
+        ];
+        for (err, code) in cases {
+            // Handlers see the error as a string after `?`.
+            let message = String::from(err);
+            assert_eq!(ErrorCode::classify(&message), code, "{message}");
+        }
+        assert_eq!(
+            ErrorCode::classify("Caller 0xabc does not own sandbox 'sb'"),
+            ErrorCode::Unauthorized
+        );
+        assert_eq!(ErrorCode::classify("something odd"), ErrorCode::Internal);
+    }
+
+    #[test]
+    fn structured_payload_round_trips_and_is_idempotent() {
+        let payload = structured("http error: HTTP request failed: refused".into());
+        let parsed = JobError::parse(&payload).unwrap();
+        assert_eq!(parsed.code, ErrorCode::SidecarUnavailable);
+        assert_eq!(parsed.code_id, 5);
+        assert!(parsed.message.contains("refused"));
+        assert_eq!(structured(payload.clone()), payload);
+
+        let value: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(value["error"]["code"], "SIDECAR_UNAVAILABLE");
+    }
+
+    #[test]
+    fn abi_response_matches_json() {
+        let err = JobError::new(ErrorCode::Tee, "attestation failed");
+        let decoded = ErrorResponse::abi_decode(&err.abi_encode()).unwrap();
+        assert_eq!(decoded.code, 6);
+        assert_eq!(decoded.name, "TEE");
+        assert_eq!(decoded.message, "attestation failed");
+    }
+}
@@ -0,0 +1,512 @@
+//! Append-only job replay log.
+//!
+//! Every mutating on-chain job that runs through
+//! [`crate::job_trace::traced`] appends one line to `job_replay.jsonl` in the
+//! state directory: the job call, the caller, the request type and the
+//! keccak256 of the ABI-encoded request, and the keccak256 of the ABI-encoded
+//! response or of the error payload. Requests carry secrets and prompts, so
+//! only their hash is written; the calldata itself stays on-chain, where it
+//! is fetched by call ID and checked against `requestHash`. Operators use it
+//! to replay the exact sequence of work after an incident or during a
+//! dispute.
+//!
+//! Entries are hash-chained: `entryHash` is the keccak256 of the entry's
+//! canonical JSON (see [`crate::response_signing::canonical_json`]) without
+//! `entryHash`, and each entry's `prevHash` is the previous entry's
+//! `entryHash` (zero for the first), so [`verify_chain`] detects any edited,
+//! dropped or reordered line. The file itself is the export format; the
+//! operator API serves it from `GET /api/operator/audit/jobs`. Set
+//! `JOB_REPLAY_LOG=false` to disable recording.
+//!
+//! Once the active file reaches `JOB_REPLAY_SEGMENT_MAX_MB` it is rotated to
+//! `job_replay.<last seq>.jsonl`; entries are never rewritten. Only the
+//! newest `JOB_REPLAY_MAX_SEGMENTS` rotated segments are kept. Before the
+//! oldest is deleted, its last entry is saved as the [`ReplayCheckpoint`], so
+//! the oldest retained entry still verifies against a known `prevHash`.
+//!
+//! A torn write (a crash mid-append) leaves a corrupt tail on the active
+//! file; it is truncated back to the last intact entry before the next
+//! append. A corrupt line followed by intact ones is not a torn write: that
+//! file is moved aside as `job_replay.corrupt-<unix ts>.jsonl` and a new
+//! active file continues the chain from its last intact entry.
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use alloy::sol_types::SolValue;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Result, SandboxError};
+use crate::job_trace::JobTrace;
+use crate::session_auth::keccak256_hex;
+
+pub const ZERO_HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+const DEFAULT_SEGMENT_MAX_MB: u64 = 64;
+const DEFAULT_MAX_SEGMENTS: usize = 16;
+
+/// A job's request, captured before the handler runs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JobInput {
+    pub caller: String,
+    pub request_type: String,
+    /// `0x` keccak256 of the request's ABI encoding.
+    pub request_hash: String,
+}
+
+impl JobInput {
+    pub fn new<T: SolValue>(caller: &str, request: &T) -> Self {
+        let type_name = std::any::type_name::<T>();
+        Self {
+            caller: caller.to_string(),
+            request_type: type_name.rsplit("::").next().unwrap_or(type_name).into(),
+            request_hash: keccak256_hex(&request.abi_encode()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayOutcome {
+    Ok,
+    Error,
+}
+
+/// One line of the replay log.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayEntry {
+    pub seq: u64,
+    pub service_id: u64,
+    pub call_id: u64,
+    pub job: u8,
+    pub trace_id: String,
+    pub caller: String,
+    pub request_type: String,
+    /// `0x` keccak256 of the ABI-encoded request.
+    pub request_hash: String,
+    pub outcome: ReplayOutcome,
+    /// `0x` keccak256 of the ABI-encoded response, or of the error JSON.
+    pub response_hash: String,
+    pub recorded_at: u64,
+    pub prev_hash: String,
+    pub entry_hash: String,
+}
+
+impl ReplayEntry {
+    fn compute_hash(&self) -> String {
+        let mut value = serde_json::to_value(self).unwrap_or(Value::Null);
+        if let Value::Object(map) = &mut value {
+            map.remove("entryHash");
+        }
+        keccak256_hex(crate::response_signing::canonical_json(&value).as_bytes())
+    }
+}
+
+/// The last entry of the newest deleted segment: the entry the oldest
+/// retained one chains from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayCheckpoint {
+    pub seq: u64,
+    pub entry_hash: String,
+}
+
+/// Sequence number and hash of the last entry in the log at `path`.
+struct ChainHead {
+    path: PathBuf,
+    seq: u64,
+    hash: String,
+}
+
+static HEAD: Lazy<Mutex<Option<ChainHead>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn replay_log_enabled() -> bool {
+    std::env::var("JOB_REPLAY_LOG")
+        .map(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+        .unwrap_or(true)
+}
+
+/// The active segment, appended to until it is rotated.
+pub fn replay_log_path() -> PathBuf {
+    crate::store::state_dir().join("job_replay.jsonl")
+}
+
+fn segment_path(last_seq: u64) -> PathBuf {
+    crate::store::state_dir().join(format!("job_replay.{last_seq:020}.jsonl"))
+}
+
+fn checkpoint_path() -> PathBuf {
+    crate::store::state_dir().join("job_replay_checkpoint.json")
+}
+
+fn segment_max_bytes() -> u64 {
+    std::env::var("JOB_REPLAY_SEGMENT_MAX_MB")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|mb| *mb > 0)
+        .unwrap_or(DEFAULT_SEGMENT_MAX_MB)
+        .saturating_mul(1024 * 1024)
+}
+
+/// Rotated segments to keep; 0 keeps all of them.
+fn max_segments() -> usize {
+    std::env::var("JOB_REPLAY_MAX_SEGMENTS")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_SEGMENTS)
+}
+
+/// Rotated segments, oldest first, with the last sequence number of each.
+fn segments() -> Result<Vec<(u64, PathBuf)>> {
+    let dir = crate::store::state_dir();
+    let read_dir = match std::fs::read_dir(&dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(SandboxError::Storage(format!(
+                "Failed to list {}: {e}",
+                dir.display()
+            )));
+        }
+    };
+    let mut segments: Vec<_> = read_dir
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let seq = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix("job_replay.")?
+                .strip_suffix(".jsonl")?
+                .parse::<u64>()
+                .ok()?;
+            Some((seq, path))
+        })
+        .collect();
+    segments.sort();
+    Ok(segments)
+}
+
+/// The checkpoint left by the last segment deletion, if any.
+pub fn checkpoint() -> Result<Option<ReplayCheckpoint>> {
+    let path = checkpoint_path();
+    match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+            SandboxError::Storage(format!(
+                "Replay checkpoint {} is corrupt: {e}",
+                path.display()
+            ))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(SandboxError::Storage(format!(
+            "Failed to read {}: {e}",
+            path.display()
+        ))),
+    }
+}
+
+fn write_checkpoint(checkpoint: &ReplayCheckpoint) -> Result<()> {
+    let path = checkpoint_path();
+    let tmp = path.with_extension("json.tmp");
+    let bytes = serde_json::to_vec(checkpoint)
+        .map_err(|e| SandboxError::Storage(format!("Failed to encode checkpoint: {e}")))?;
+    std::fs::write(&tmp, bytes)
+        .and_then(|()| std::fs::rename(&tmp, &path))
+        .map_err(|e| SandboxError::Storage(format!("Failed to write {}: {e}", path.display())))
+}
+
+/// Move the active file aside as a segment ending at `last_seq`, then drop
+/// segments past `JOB_REPLAY_MAX_SEGMENTS`, checkpointing each first.
+fn rotate(active: &Path, last_seq: u64) -> Result<()> {
+    let segment = segment_path(last_seq);
+    std::fs::rename(active, &segment).map_err(|e| {
+        SandboxError::Storage(format!("Failed to rotate {}: {e}", active.display()))
+    })?;
+    let max = max_segments();
+    if max == 0 {
+        return Ok(());
+    }
+    let segments = segments()?;
+    let excess = segments.len().saturating_sub(max);
+    for (_, path) in segments.into_iter().take(excess) {
+        if let Some(last) = last_entry_in(&path)? {
+            write_checkpoint(&ReplayCheckpoint {
+                seq: last.seq,
+                entry_hash: last.entry_hash,
+            })?;
+        }
+        std::fs::remove_file(&path).map_err(|e| {
+            SandboxError::Storage(format!("Failed to remove {}: {e}", path.display()))
+        })?;
+    }
+    Ok(())
+}
+
+/// Append the outcome of a job call. `response` is the ABI-encoded output on
+/// success or the error payload on failure. Best-effort: a write failure is
+/// logged, never returned, so the log cannot block jobs.
+pub fn record(trace: &JobTrace, input: JobInput, outcome: ReplayOutcome, response: &[u8]) {
+    if !replay_log_enabled() {
+        return;
+    }
+    if let Err(e) = append(trace, input, outcome, response) {
+        tracing::warn!(
+            trace_id = %trace.trace_id,
+            error = %e,
+            "failed to append to the job replay log"
+        );
+    }
+}
+
+fn append(
+    trace: &JobTrace,
+    input: JobInput,
+    outcome: ReplayOutcome,
+    response: &[u8],
+) -> Result<()> {
+    let path = replay_log_path();
+    let mut head = HEAD.lock().unwrap_or_else(|e| e.into_inner());
+    if head.as_ref().is_none_or(|h| h.path != path) {
+        let (seq, hash) = match last_entry()? {
+            Some(last) => (last.seq, last.entry_hash),
+            None => match checkpoint()? {
+                Some(checkpoint) => (checkpoint.seq, checkpoint.entry_hash),
+                None => (0, ZERO_HASH.to_string()),
+            },
+        };
+        *head = Some(ChainHead {
+            path: path.clone(),
+            seq,
+            hash,
+        });
+    }
+    let Some(chain) = head.as_mut() else {
+        return Ok(());
+    };
+
+    let mut entry = ReplayEntry {
+        seq: chain.seq + 1,
+        service_id: trace.service_id,
+        call_id: trace.call_id,
+        job: trace.job,
+        trace_id: trace.trace_id.clone(),
+        caller: input.caller,
+        request_type: input.request_type,
+        request_hash: input.request_hash,
+        outcome,
+        response_hash: keccak256_hex(response),
+        recorded_at: crate::util::now_ts(),
+        prev_hash: chain.hash.clone(),
+        entry_hash: String::new(),
+    };
+    entry.entry_hash = entry.compute_hash();
+
+    let mut line = serde_json::to_string(&entry)
+        .map_err(|e| SandboxError::Storage(format!("Failed to encode replay entry: {e}")))?;
+    line.push('\n');
+    let written = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| {
+            file.write_all(line.as_bytes())?;
+            Ok(file.metadata()?.len())
+        });
+    let len = match written {
+        Ok(len) => len,
+        Err(e) => {
+            // The line may be half-written; reload (and repair) the head next time.
+            *head = None;
+            return Err(SandboxError::Storage(format!(
+                "Failed to write {}: {e}",
+                path.display()
+            )));
+        }
+    };
+
+    chain.seq = entry.seq;
+    chain.hash = entry.entry_hash;
+    if len >= segment_max_bytes()
+        && let Err(e) = rotate(&path, chain.seq)
+    {
+        tracing::warn!(error = %e, "failed to rotate the job replay log");
+    }
+    Ok(())
+}
+
+/// Entries with `seq > after_seq`, oldest first, at most `limit` of them.
+pub fn read_entries(after_seq: u64, limit: usize) -> Result<Vec<ReplayEntry>> {
+    let mut entries = Vec::new();
+    // Segments are named after their last entry, so older ones are skipped
+    // without being read.
+    let files = segments()?
+        .into_iter()
+        .filter(|(last_seq, _)| *last_seq > after_seq)
+        .map(|(_, path)| path)
+        .chain(std::iter::once(replay_log_path()));
+    for path in files {
+        if entries.len() >= limit {
+            break;
+        }
+        for_each_entry_in(&path, |entry| {
+            if entry.seq > after_seq {
+                entries.push(entry);
+            }
+            entries.len() < limit
+        })?;
+    }
+    Ok(entries)
+}
+
+/// The newest entry in the active file or, right after a rotation, in the
+/// newest segment. Repairs a corrupt active file first.
+fn last_entry() -> Result<Option<ReplayEntry>> {
+    if let Some(last) = repair_active(&replay_log_path())? {
+        return Ok(Some(last));
+    }
+    match segments()?.pop() {
+        Some((_, path)) => last_entry_in(&path),
+        None => Ok(None),
+    }
+}
+
+/// Cut a torn tail off the active file at `path`, or move the file aside when
+/// it is corrupt before its end, and return its last intact entry.
+fn repair_active(path: &Path) -> Result<Option<ReplayEntry>> {
+    let io_err = |e: std::io::Error| {
+        SandboxError::Storage(format!("Failed to repair {}: {e}", path.display()))
+    };
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(io_err(e)),
+    };
+    let mut reader = std::io::BufReader::new(file);
+    let mut line = Vec::new();
+    let (mut offset, mut intact_len) = (0u64, 0u64);
+    let mut last = None;
+    let (mut corrupt, mut intact_after_corrupt) = (false, false);
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line).map_err(io_err)?;
+        if read == 0 {
+            break;
+        }
+        offset += read as u64;
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        // Only a newline-terminated line was fully written.
+        match serde_json::from_slice::<ReplayEntry>(&line) {
+            Ok(entry) if line.ends_with(b"\n") => {
+                intact_after_corrupt |= corrupt;
+                if !corrupt {
+                    intact_len = offset;
+                }
+                last = Some(entry);
+            }
+            _ => corrupt = true,
+        }
+    }
+    if !corrupt {
+        return Ok(last);
+    }
+    if intact_after_corrupt {
+        let aside = path.with_file_name(format!(
+            "job_replay.corrupt-{}.jsonl",
+            crate::util::now_ts()
+        ));
+        std::fs::rename(path, &aside).map_err(io_err)?;
+        tracing::warn!(
+            moved_to = %aside.display(),
+            "job replay log is corrupt mid-file; starting a new active file"
+        );
+    } else {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_len(intact_len))
+            .map_err(io_err)?;
+        tracing::warn!(
+            path = %path.display(),
+            truncated_to = intact_len,
+            "dropped a torn tail from the job replay log"
+        );
+    }
+    Ok(last)
+}
+
+fn last_entry_in(path: &Path) -> Result<Option<ReplayEntry>> {
+    let mut last = None;
+    for_each_entry_in(path, |entry| {
+        last = Some(entry);
+        true
+    })?;
+    Ok(last)
+}
+
+/// Feed the entries of `path` to `f` in order until it returns `false`.
+fn for_each_entry_in(path: &Path, mut f: impl FnMut(ReplayEntry) -> bool) -> Result<()> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(SandboxError::Storage(format!(
+                "Failed to read {}: {e}",
+                path.display()
+            )));
+        }
+    };
+    for (index, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| SandboxError::Storage(format!("Failed to read: {e}")))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: ReplayEntry = serde_json::from_str(&line).map_err(|e| {
+            SandboxError::Storage(format!(
+                "{} line {} is corrupt: {e}",
+                path.display(),
+                index + 1
+            ))
+        })?;
+        if !f(entry) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Check that `entries` (a contiguous run of the log, oldest first) are
+/// intact and chained. The first entry's `prevHash` is only checked when it
+/// starts the log or directly follows `checkpoint`.
+pub fn verify_chain(
+    entries: &[ReplayEntry],
+    checkpoint: Option<&ReplayCheckpoint>,
+) -> std::result::Result<(), String> {
+    let mut prev: Option<&ReplayEntry> = None;
+    for entry in entries {
+        if entry.compute_hash() != entry.entry_hash {
+            return Err(format!("entry {} does not match its hash", entry.seq));
+        }
+        let expected_prev = match prev {
+            Some(p) => Some((p.seq + 1, p.entry_hash.as_str())),
+            None if entry.seq == 1 => Some((1, ZERO_HASH)),
+            None => checkpoint
+                .filter(|c| c.seq + 1 == entry.seq)
+                .map(|c| (entry.seq, c.entry_hash.as_str())),
+        };
+        if let Some((seq, hash)) = expected_prev
+            && (entry.seq != seq || entry.prev_hash != hash)
+        {
+            return Err(format!("entry {} breaks the chain", entry.seq));
+        }
+        prev = Some(entry);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
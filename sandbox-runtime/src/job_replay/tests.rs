@@ -0,0 +1,161 @@
+use super::*;
+
+alloy::sol! {
+    #[derive(Debug)]
+    struct Req { string sandbox_id; }
+}
+
+#[test]
+fn entries_are_appended_chained_and_verifiable() {
+    let _guard = crate::TEST_ENV_GUARD
+        .lock()
+        .unwrap_or_else(|p| p.into_inner());
+    let dir = std::env::temp_dir().join(format!("job-replay-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).ok();
+    unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", &dir) };
+
+    let request = Req {
+        sandbox_id: "sb-1".into(),
+    };
+    let input = JobInput::new("0xabc", &request);
+    assert_eq!(input.request_type, "Req");
+    assert_eq!(input.request_hash, keccak256_hex(&request.abi_encode()));
+    record(
+        &JobTrace::new(1, 10, 1),
+        input.clone(),
+        ReplayOutcome::Ok,
+        b"out",
+    );
+    record(&JobTrace::new(1, 11, 1), input, ReplayOutcome::Error, b"{}");
+
+    let entries = read_entries(0, 100).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].prev_hash, ZERO_HASH);
+    assert_eq!(entries[1].prev_hash, entries[0].entry_hash);
+    assert_eq!(entries[0].response_hash, keccak256_hex(b"out"));
+    let line = std::fs::read_to_string(replay_log_path()).unwrap();
+    assert!(!line.contains("sb-1"), "request fields must not be written");
+    assert!(verify_chain(&entries, None).is_ok());
+    assert_eq!(read_entries(1, 100).unwrap()[0].call_id, 11);
+
+    let mut tampered = entries.clone();
+    tampered[0].call_id = 99;
+    assert!(verify_chain(&tampered, None).is_err());
+    assert!(verify_chain(&entries[1..], None).is_ok());
+    assert!(verify_chain(&[entries[1].clone(), entries[0].clone()], None).is_err());
+}
+
+#[test]
+fn corrupt_tails_are_repaired_before_appending() {
+    let _guard = crate::TEST_ENV_GUARD
+        .lock()
+        .unwrap_or_else(|p| p.into_inner());
+    let dir = std::env::temp_dir().join(format!("job-replay-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).ok();
+    unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", &dir) };
+    let input = JobInput::new(
+        "0xabc",
+        &Req {
+            sandbox_id: "sb-1".into(),
+        },
+    );
+    let append_raw = |bytes: &[u8]| {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(replay_log_path())
+            .unwrap()
+            .write_all(bytes)
+            .unwrap();
+    };
+
+    record(
+        &JobTrace::new(1, 1, 1),
+        input.clone(),
+        ReplayOutcome::Ok,
+        b"out",
+    );
+    // A torn write: half a line with no newline.
+    append_raw(br#"{"seq":2,"serviceId":1"#);
+    *HEAD.lock().unwrap() = None;
+    record(
+        &JobTrace::new(1, 2, 1),
+        input.clone(),
+        ReplayOutcome::Ok,
+        b"out",
+    );
+    let entries = read_entries(0, 100).unwrap();
+    assert_eq!(entries.iter().map(|e| e.seq).collect::<Vec<_>>(), [1, 2]);
+    assert!(verify_chain(&entries, None).is_ok());
+
+    // Corruption before intact entries moves the file aside.
+    let intact = std::fs::read_to_string(replay_log_path()).unwrap();
+    std::fs::write(replay_log_path(), format!("garbage\n{intact}")).unwrap();
+    *HEAD.lock().unwrap() = None;
+    record(&JobTrace::new(1, 3, 1), input, ReplayOutcome::Ok, b"out");
+    let entries = read_entries(0, 100).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].seq, 3);
+    let checkpoint = ReplayCheckpoint {
+        seq: 2,
+        entry_hash: entries[0].prev_hash.clone(),
+    };
+    assert!(verify_chain(&entries, Some(&checkpoint)).is_ok());
+    let moved_aside = std::fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .any(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .starts_with("job_replay.corrupt-")
+        });
+    assert!(moved_aside);
+}
+
+#[test]
+fn rotated_segments_are_pruned_behind_a_checkpoint() {
+    let _guard = crate::TEST_ENV_GUARD
+        .lock()
+        .unwrap_or_else(|p| p.into_inner());
+    let dir = std::env::temp_dir().join(format!("job-replay-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).ok();
+    unsafe {
+        std::env::set_var("BLUEPRINT_STATE_DIR", &dir);
+        std::env::set_var("JOB_REPLAY_MAX_SEGMENTS", "1");
+    }
+
+    let input = JobInput::new(
+        "0xabc",
+        &Req {
+            sandbox_id: "sb-1".into(),
+        },
+    );
+    for call in 1..=3 {
+        record(
+            &JobTrace::new(1, call, 1),
+            input.clone(),
+            ReplayOutcome::Ok,
+            b"out",
+        );
+        rotate(&replay_log_path(), call).unwrap();
+    }
+    let kept = read_entries(0, 100).unwrap();
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].seq, 3);
+    let checkpoint = checkpoint().unwrap().unwrap();
+    assert_eq!(checkpoint.seq, 2);
+    assert_eq!(kept[0].prev_hash, checkpoint.entry_hash);
+    assert!(verify_chain(&kept, Some(&checkpoint)).is_ok());
+    let forged = ReplayCheckpoint {
+        seq: 2,
+        entry_hash: ZERO_HASH.into(),
+    };
+    assert!(verify_chain(&kept, Some(&forged)).is_err());
+
+    // A restart with an empty active file continues from the newest segment.
+    *HEAD.lock().unwrap() = None;
+    record(&JobTrace::new(1, 4, 1), input, ReplayOutcome::Ok, b"out");
+    let entries = read_entries(0, 100).unwrap();
+    assert_eq!(entries.iter().map(|e| e.seq).collect::<Vec<_>>(), [3, 4]);
+    assert!(verify_chain(&entries, Some(&checkpoint)).is_ok());
+    unsafe { std::env::remove_var("JOB_REPLAY_MAX_SEGMENTS") };
+}
@@ -0,0 +1,265 @@
+//! Per-job trace IDs.
+//!
+//! Every on-chain job gets a trace ID derived from `(service_id, call_id)`, so
+//! it can be recomputed from the chain alone. [`in_scope`] runs a job inside
+//! a `job` tracing span carrying the ID (every log line of the job includes
+//! it) and a task-local read by the sidecar HTTP helpers, which forward it as
+//! `x-trace-id` and a W3C `traceparent`. Job responses carry it as `traceId`
+//! and the last completed / failed job IDs are exported as metric exemplars.
+//!
+//! Blueprint job handlers run under [`traced`], which ties the trace to the
+//! shutdown guard, structured errors, response signing and the replay log.
+
+use std::future::Future;
+
+use alloy::sol_types::SolValue;
+use serde_json::Value;
+use tracing::Instrument;
+
+use crate::job_error::JobError;
+use crate::job_replay::{self, JobInput, ReplayOutcome};
+use crate::metrics::metrics;
+
+/// Trace context of the job running on the current task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobTrace {
+    pub trace_id: String,
+    pub service_id: u64,
+    pub call_id: u64,
+    pub job: u8,
+}
+
+tokio::task_local! {
+    static CURRENT_JOB_TRACE: JobTrace;
+}
+
+impl JobTrace {
+    pub fn new(service_id: u64, call_id: u64, job: u8) -> Self {
+        Self {
+            trace_id: trace_id(service_id, call_id),
+            service_id,
+            call_id,
+            job,
+        }
+    }
+
+    /// W3C `traceparent` for a request made on behalf of this job. Each call
+    /// gets a fresh parent span ID under the job's trace ID.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{:016x}-01",
+            self.trace_id,
+            rand::random::<u64>().max(1)
+        )
+    }
+}
+
+/// Deterministic 128-bit trace ID (32 hex chars) for a job call.
+pub fn trace_id(service_id: u64, call_id: u64) -> String {
+    let preimage = [
+        b"tangle-job-trace".as_slice(),
+        &service_id.to_be_bytes(),
+        &call_id.to_be_bytes(),
+    ]
+    .concat();
+    hex::encode(&crate::session_auth::keccak256(&preimage)[..16])
+}
+
+/// The trace ID of a W3C `traceparent` header value
+/// (`00-<32 hex trace id>-<16 hex parent id>-<2 hex flags>`); `None` for a
+/// malformed header or the all-zero invalid trace ID.
+pub fn parse_traceparent(value: &str) -> Option<String> {
+    let mut parts = value.trim().split('-');
+    let (version, trace_id, parent_id, flags) =
+        (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let is_hex = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit());
+    if !is_hex(version, 2) || !is_hex(trace_id, 32) || !is_hex(parent_id, 16) || !is_hex(flags, 2) {
+        return None;
+    }
+    if trace_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+    Some(trace_id.to_ascii_lowercase())
+}
+
+/// Run `fut` as the job described by `trace`.
+pub async fn in_scope<F: Future>(trace: JobTrace, fut: F) -> F::Output {
+    let span = tracing::info_span!(
+        "job",
+        trace_id = %trace.trace_id,
+        service_id = trace.service_id,
+        call_id = trace.call_id,
+        job = trace.job,
+    );
+    CURRENT_JOB_TRACE.scope(trace, fut.instrument(span)).await
+}
+
+/// The job trace of the current task, if it is running a job.
+pub fn current() -> Option<JobTrace> {
+    CURRENT_JOB_TRACE.try_with(Clone::clone).ok()
+}
+
+pub fn current_trace_id() -> Option<String> {
+    CURRENT_JOB_TRACE.try_with(|t| t.trace_id.clone()).ok()
+}
+
+/// Add `"traceId"` to a JSON object response. Other payloads are returned
+/// unchanged.
+pub fn tag_json(json: &str, trace_id: &str) -> String {
+    match serde_json::from_str::<Value>(json) {
+        Ok(Value::Object(mut map)) => {
+            map.insert("traceId".into(), Value::String(trace_id.to_string()));
+            Value::Object(map).to_string()
+        }
+        _ => json.to_string(),
+    }
+}
+
+/// Job outputs that carry the job's trace ID and, when enabled, the
+/// operator's [`crate::response_signing`] signature.
+pub trait JobOutput {
+    fn annotate(&mut self, trace: &JobTrace);
+}
+
+/// [`JobOutput::annotate`] for outputs whose payload is a JSON string. The
+/// signature is bound to the trace's service and call IDs.
+pub fn annotate_json(json: &str, trace: &JobTrace) -> String {
+    crate::response_signing::sign_json(
+        &tag_json(json, &trace.trace_id),
+        trace.service_id,
+        trace.call_id,
+    )
+}
+
+/// Capture a job's caller and request for the [`crate::job_replay`] log.
+pub fn job_input<T: SolValue>(caller: &[u8; 20], request: &T) -> JobInput {
+    JobInput::new(&format!("0x{}", hex::encode(caller)), request)
+}
+
+/// Run a job under its trace ID: logs and sidecar calls carry it, the output
+/// gets a `traceId` field (and a signature, see [`JobOutput`]), errors are
+/// returned as structured [`crate::job_error`] payloads, and unless the job
+/// is `read_only` its `input` is appended to the [`crate::job_replay`] log
+/// with a digest of the response. Jobs arriving after
+/// [`crate::shutdown::begin_shutdown`] are refused.
+pub async fn traced<T, Fut>(
+    service_id: u64,
+    call_id: u64,
+    job: u8,
+    read_only: bool,
+    input: JobInput,
+    run: Fut,
+) -> Result<T, String>
+where
+    T: JobOutput + SolValue,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let trace = JobTrace::new(service_id, call_id, job);
+    let trace_id = trace.trace_id.clone();
+    // Held until the job finishes so shutdown can wait for it; refused
+    // (without running) once shutdown has begun.
+    let result = match crate::shutdown::enter_job() {
+        Ok(_guard) => in_scope(trace.clone(), run).await,
+        Err(err) => Err(JobError::from(err).to_json()),
+    };
+    metrics().record_job_trace(&trace_id, result.is_ok());
+    match result {
+        Ok(mut output) => {
+            output.annotate(&trace);
+            // Reads change nothing there is to replay; keep them out of the log.
+            if !read_only {
+                job_replay::record(&trace, input, ReplayOutcome::Ok, &output.abi_encode());
+            }
+            Ok(output)
+        }
+        Err(err) => {
+            tracing::warn!(trace_id, service_id, call_id, job, error = %err, "Job failed");
+            let payload = JobError::from(err).with_trace_id(trace_id).to_json();
+            if !read_only {
+                job_replay::record(&trace, input, ReplayOutcome::Error, payload.as_bytes());
+            }
+            Err(payload)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_id_is_deterministic_per_call() {
+        let id = trace_id(1, 42);
+        assert_eq!(id.len(), 32);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(id, trace_id(1, 42));
+        assert_ne!(id, trace_id(1, 43));
+        assert_ne!(id, trace_id(2, 42));
+    }
+
+    #[test]
+    fn traceparent_uses_job_trace_id() {
+        let trace = JobTrace::new(3, 7, 0);
+        let parts: Vec<_> = trace.traceparent().split('-').map(String::from).collect();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], "00");
+        assert_eq!(parts[1], trace.trace_id);
+        assert_eq!(parts[2].len(), 16);
+        assert_eq!(parts[3], "01");
+    }
+
+    #[test]
+    fn parse_traceparent_extracts_valid_trace_ids() {
+        let trace = JobTrace::new(3, 7, 0);
+        assert_eq!(
+            parse_traceparent(&trace.traceparent()),
+            Some(trace.trace_id)
+        );
+        let zero = format!("00-{}-00f067aa0ba902b7-01", "0".repeat(32));
+        assert_eq!(parse_traceparent(&zero), None);
+        assert_eq!(parse_traceparent("00-abc-00f067aa0ba902b7-01"), None);
+        assert_eq!(parse_traceparent("garbage"), None);
+    }
+
+    #[tokio::test]
+    async fn current_is_set_only_inside_scope() {
+        assert!(current().is_none());
+        let trace = JobTrace::new(5, 9, 2);
+        let seen = in_scope(trace.clone(), async { current_trace_id() }).await;
+        assert_eq!(seen, Some(trace.trace_id));
+        assert!(current().is_none());
+    }
+
+    impl JobOutput for String {
+        fn annotate(&mut self, trace: &JobTrace) {
+            *self = tag_json(self, &trace.trace_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn traced_tags_outputs_and_errors_with_the_trace_id() {
+        let input = || job_input(&[0x11; 20], &"request".to_string());
+        let output = traced(4, 8, 0, true, input(), async {
+            Ok::<_, String>(r#"{"ok":true}"#.to_string())
+        })
+        .await
+        .unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["traceId"], trace_id(4, 8));
+
+        let err = traced::<String, _>(4, 9, 0, true, input(), async { Err("boom".into()) })
+            .await
+            .unwrap_err();
+        let err: Value = serde_json::from_str(&err).unwrap();
+        assert_eq!(err["traceId"], trace_id(4, 9));
+    }
+
+    #[test]
+    fn tag_json_only_touches_objects() {
+        let tagged: Value = serde_json::from_str(&tag_json(r#"{"ok":true}"#, "abc")).unwrap();
+        assert_eq!(tagged["traceId"], "abc");
+        assert_eq!(tagged["ok"], true);
+        assert_eq!(tag_json("[1]", "abc"), "[1]");
+        assert_eq!(tag_json("not json", "abc"), "not json");
+    }
+}
@@ -4,34 +4,75 @@
 //! and garbage collection primitives that can be reused across multiple
 //! blueprint implementations (event-driven, subscription, etc.).
 
+pub mod agent_runs;
 pub mod api_types;
+pub mod audit_log;
 pub mod auth;
+pub mod auto_provision_status;
 pub mod chat_state;
 pub mod circuit_breaker;
 pub mod contracts;
+pub mod crash_monitor;
+pub mod disk_quota;
 mod docker_warm;
+pub mod drain;
 pub mod error;
+pub mod exec_input;
+pub mod exec_output;
+pub mod exec_policy;
+pub mod exec_presets;
+pub mod executions;
 pub mod firecracker;
 mod firecracker_dnat;
 mod firecracker_lineage;
 mod firecracker_warm;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod http;
+pub mod image_cache;
 pub mod ingress_access_control;
 pub mod instance_types;
+pub mod job_dedup;
+pub mod job_error;
+pub mod job_replay;
+pub mod job_trace;
 pub mod live_operator_sessions;
+pub mod logging;
+pub mod mcp;
 pub mod metrics;
+pub mod model_policy;
+pub mod named_sessions;
 pub mod operator_api;
+pub mod operator_config;
+#[cfg(feature = "otel")]
+pub mod otel_logs;
+pub mod output_schema;
+pub mod owner_quota;
+pub mod prompt_templates;
 pub mod provision_progress;
+pub mod proxy_routes;
+#[cfg(feature = "qos")]
+pub mod qos;
 pub mod rate_limit;
 pub mod reaper;
+pub mod response_signing;
+pub mod result_commitment;
 pub mod runtime;
 pub mod scoped_session_auth;
 pub mod secret_provisioning;
 pub mod session_auth;
+pub mod session_export;
+pub mod shutdown;
+pub mod sidecar_compat;
 pub mod ssh_validation;
 pub mod store;
+pub mod task_queue;
 pub mod tee;
+pub mod templates;
+pub mod tool_policy;
+pub mod usage_ledger;
 pub mod util;
+pub mod webhooks;
 
 #[cfg(feature = "test-utils")]
 pub mod test_utils;
@@ -76,5 +117,6 @@ pub const DEFAULT_SIDECAR_IMAGE: &str = "ghcr.io/tangle-network/blueprint-sideca
 pub const DEFAULT_SIDECAR_HTTP_PORT: u16 = 8080;
 pub const DEFAULT_SIDECAR_SSH_PORT: u16 = 22;
 pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 5;
 /// Maximum number of extra user-requested ports per sandbox.
 pub const MAX_EXTRA_PORTS: usize = 8;
@@ -0,0 +1,272 @@
+//! Model Context Protocol (MCP) server primitives.
+//!
+//! The operator API serves MCP's Streamable HTTP transport at `POST
+//! /api/mcp` (see `operator_api::mcp`) so desktop LLM clients can drive the
+//! caller's sandboxes. Each owned sandbox is exposed through four tools —
+//! [`McpTool::RunCommand`], [`McpTool::RunTask`], [`McpTool::ReadFile`] and
+//! [`McpTool::WriteFile`] — whose `sandbox_id` argument enumerates the
+//! sandboxes the session owns. This module holds the JSON-RPC envelopes,
+//! tool schemas and the shell commands behind the file tools; dispatch and
+//! auth live with the other operator routes.
+
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::util::shell_escape;
+
+/// MCP revision implemented by the server.
+pub const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+/// Largest file `read_file` returns (256 KiB); longer files are truncated.
+pub const MAX_READ_FILE_BYTES: usize = 256 * 1024;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// A JSON-RPC 2.0 request or notification (no `id`).
+#[derive(Debug, Deserialize)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+pub fn result_response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub fn error_response(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() },
+    })
+}
+
+/// `notifications/progress` for a request that sent `_meta.progressToken`.
+pub fn progress_notification(token: &Value, progress: u64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/progress",
+        "params": { "progressToken": token, "progress": progress, "message": message },
+    })
+}
+
+/// Result of `initialize`.
+pub fn initialize_result() -> Value {
+    json!({
+        "protocolVersion": MCP_PROTOCOL_VERSION,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": {
+            "name": "tangle-sandbox-operator",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "instructions": "Each tool takes the `sandbox_id` of one of your sandboxes.",
+    })
+}
+
+/// Result of a `tools/call`.
+pub fn tool_result(text: impl Into<String>, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text.into() }],
+        "isError": is_error,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum McpTool {
+    RunCommand,
+    RunTask,
+    ReadFile,
+    WriteFile,
+}
+
+impl McpTool {
+    pub const ALL: [McpTool; 4] = [
+        McpTool::RunCommand,
+        McpTool::RunTask,
+        McpTool::ReadFile,
+        McpTool::WriteFile,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            McpTool::RunCommand => "run_command",
+            McpTool::RunTask => "run_task",
+            McpTool::ReadFile => "read_file",
+            McpTool::WriteFile => "write_file",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tool| tool.name() == name)
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            McpTool::RunCommand => {
+                "Run a shell command in the sandbox and return its output and exit code."
+            }
+            McpTool::RunTask => {
+                "Give the sandbox's coding agent a multi-turn task and return its final answer."
+            }
+            McpTool::ReadFile => "Read a text file from the sandbox.",
+            McpTool::WriteFile => {
+                "Write a text file in the sandbox, creating parent directories as needed."
+            }
+        }
+    }
+
+    fn properties(self) -> Value {
+        match self {
+            McpTool::RunCommand => json!({
+                "command": { "type": "string", "description": "Shell command to run." },
+                "cwd": { "type": "string", "description": "Working directory." },
+                "timeout_ms": { "type": "integer", "minimum": 0 },
+            }),
+            McpTool::RunTask => json!({
+                "prompt": { "type": "string", "description": "What the agent should do." },
+                "max_turns": { "type": "integer", "minimum": 0 },
+                "model": { "type": "string" },
+                "session_id": {
+                    "type": "string",
+                    "description": "Continue an earlier agent session.",
+                },
+            }),
+            McpTool::ReadFile => json!({
+                "path": { "type": "string", "description": "File path in the sandbox." },
+            }),
+            McpTool::WriteFile => json!({
+                "path": { "type": "string", "description": "File path in the sandbox." },
+                "content": { "type": "string" },
+            }),
+        }
+    }
+
+    fn required(self) -> &'static [&'static str] {
+        match self {
+            McpTool::RunCommand => &["sandbox_id", "command"],
+            McpTool::RunTask => &["sandbox_id", "prompt"],
+            McpTool::ReadFile => &["sandbox_id", "path"],
+            McpTool::WriteFile => &["sandbox_id", "path", "content"],
+        }
+    }
+
+    /// Tool definition for `tools/list`; `sandbox_ids` are the caller's
+    /// sandboxes.
+    pub fn definition(self, sandbox_ids: &[String]) -> Value {
+        let mut properties = self.properties();
+        properties.as_object_mut().expect("object").insert(
+            "sandbox_id".to_string(),
+            json!({
+                "type": "string",
+                "enum": sandbox_ids,
+                "description": "Sandbox to act on.",
+            }),
+        );
+        json!({
+            "name": self.name(),
+            "description": self.description(),
+            "inputSchema": {
+                "type": "object",
+                "properties": properties,
+                "required": self.required(),
+            },
+        })
+    }
+}
+
+/// Required string argument of a tool call.
+pub fn required_arg<'a>(args: &'a Map<String, Value>, key: &str) -> Result<&'a str, String> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| format!("`{key}` is required"))
+}
+
+/// Optional string argument of a tool call (empty when absent).
+pub fn optional_arg<'a>(args: &'a Map<String, Value>, key: &str) -> &'a str {
+    args.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn validate_path(path: &str) -> Result<(), String> {
+    if path.contains('\0') {
+        return Err("`path` must not contain NUL bytes".into());
+    }
+    Ok(())
+}
+
+/// Command printing up to one byte more than [`MAX_READ_FILE_BYTES`] of
+/// `path`, so the caller can tell the file was truncated.
+pub fn read_file_command(path: &str) -> Result<String, String> {
+    validate_path(path)?;
+    Ok(format!(
+        "head -c {} -- {}",
+        MAX_READ_FILE_BYTES + 1,
+        shell_escape(path)
+    ))
+}
+
+/// Command writing its stdin to `path`, creating parent directories.
+pub fn write_file_command(path: &str) -> Result<String, String> {
+    validate_path(path)?;
+    let path = shell_escape(path);
+    Ok(format!(
+        "mkdir -p -- \"$(dirname -- {path})\" && cat > {path}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_definitions_enumerate_owned_sandboxes() {
+        let ids = vec!["sb-1".to_string(), "sb-2".to_string()];
+        let def = McpTool::WriteFile.definition(&ids);
+        assert_eq!(def["name"], "write_file");
+        assert_eq!(
+            def["inputSchema"]["properties"]["sandbox_id"]["enum"],
+            json!(["sb-1", "sb-2"])
+        );
+        assert_eq!(
+            def["inputSchema"]["required"],
+            json!(["sandbox_id", "path", "content"])
+        );
+        for tool in McpTool::ALL {
+            assert_eq!(McpTool::from_name(tool.name()), Some(tool));
+        }
+        assert_eq!(McpTool::from_name("rm_rf"), None);
+    }
+
+    #[test]
+    fn file_commands_quote_paths() {
+        assert_eq!(
+            read_file_command("/tmp/it's.txt").unwrap(),
+            format!(
+                "head -c {} -- '/tmp/it'\"'\"'s.txt'",
+                MAX_READ_FILE_BYTES + 1
+            )
+        );
+        assert_eq!(
+            write_file_command("a b/c.txt").unwrap(),
+            "mkdir -p -- \"$(dirname -- 'a b/c.txt')\" && cat > 'a b/c.txt'"
+        );
+        assert!(read_file_command("bad\0path").is_err());
+    }
+
+    #[test]
+    fn arguments_are_checked() {
+        let args = json!({ "path": "  ", "cwd": "/srv" });
+        let args = args.as_object().unwrap();
+        assert!(required_arg(args, "path").is_err());
+        assert!(required_arg(args, "command").is_err());
+        assert_eq!(optional_arg(args, "cwd"), "/srv");
+        assert_eq!(optional_arg(args, "model"), "");
+    }
+}
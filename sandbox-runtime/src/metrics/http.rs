@@ -48,9 +48,44 @@ impl Default for EndpointStats {
     }
 }
 
+/// Labels for the status classes counted per route, by `status / 100 - 1`.
+pub const STATUS_CLASSES: [&str; 5] = ["1xx", "2xx", "3xx", "4xx", "5xx"];
+
+/// Per-route/method request counts by status class, latency histogram, and
+/// the trace ID of the latest request in each class.
+#[derive(Clone, Default)]
+pub struct RouteStats {
+    /// Request counts aligned with [`STATUS_CLASSES`].
+    pub by_class: [u64; 5],
+    pub total_ms: u64,
+    /// Histogram bucket counters aligned with [`HISTOGRAM_BUCKETS`].
+    pub histogram: [u64; 11],
+    /// Trace ID and duration (ms) of the latest request per status class.
+    pub exemplars: [Option<(String, u64)>; 5],
+}
+
+impl RouteStats {
+    pub fn count(&self) -> u64 {
+        self.by_class.iter().sum()
+    }
+}
+
+/// Index into [`STATUS_CLASSES`]; out-of-range statuses count as 5xx.
+fn status_class_index(status: u16) -> usize {
+    match status {
+        100..=199 => 0,
+        200..=299 => 1,
+        300..=399 => 2,
+        400..=499 => 3,
+        _ => 4,
+    }
+}
+
 /// Tracks per-endpoint HTTP latency and request counts.
 pub struct HttpMetrics {
     endpoints: Mutex<HashMap<String, EndpointStats>>,
+    /// Keyed by (method, route template).
+    routes: Mutex<HashMap<(String, String), RouteStats>>,
 }
 
 impl Default for HttpMetrics {
@@ -63,6 +98,7 @@ impl HttpMetrics {
     pub fn new() -> Self {
         Self {
             endpoints: Mutex::new(HashMap::new()),
+            routes: Mutex::new(HashMap::new()),
         }
     }
 
@@ -95,6 +131,39 @@ impl HttpMetrics {
         }
     }
 
+    /// Record a request to `route` by `method` that answered `status`.
+    /// `trace_id` becomes the exemplar for the status class.
+    pub fn record_route(
+        &self,
+        method: &str,
+        route: &str,
+        status: u16,
+        duration_ms: u64,
+        trace_id: Option<&str>,
+    ) {
+        let class = status_class_index(status);
+        let mut map = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = map
+            .entry((method.to_string(), route.to_string()))
+            .or_default();
+        entry.by_class[class] += 1;
+        entry.total_ms += duration_ms;
+        if let Some(i) = HISTOGRAM_BUCKETS.iter().position(|&b| duration_ms <= b) {
+            entry.histogram[i] += 1;
+        }
+        if let Some(trace_id) = trace_id {
+            entry.exemplars[class] = Some((trace_id.to_string(), duration_ms));
+        }
+    }
+
+    /// Snapshot all route stats, sorted by route then method.
+    pub fn route_snapshot(&self) -> Vec<((String, String), RouteStats)> {
+        let map = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut snap: Vec<_> = map.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        snap.sort_by(|a, b| (&a.0.1, &a.0.0).cmp(&(&b.0.1, &b.0.0)));
+        snap
+    }
+
     /// Snapshot all endpoint stats for Prometheus rendering.
     pub fn snapshot(&self) -> Vec<(String, EndpointStats)> {
         let map = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
@@ -174,14 +243,78 @@ impl HttpMetrics {
                 );
             }
         }
+        self.render_routes(&mut out);
         // Rate-limit rejection counter (global, not per-endpoint)
         let rl = rate_limit_rejections().load(Ordering::Relaxed);
         let _ = writeln!(out, "# TYPE rate_limit_rejections_total counter");
         let _ = writeln!(out, "rate_limit_rejections_total {rl}");
+        // Sign-in failures and the lockouts they triggered (see
+        // `session_auth::lockout`)
+        for (name, counter) in [
+            ("auth_failures_total", auth_failures()),
+            ("auth_lockouts_total", auth_lockouts()),
+            ("auth_lockout_rejections_total", auth_lockout_rejections()),
+        ] {
+            let _ = writeln!(out, "# TYPE {name} counter");
+            let _ = writeln!(out, "{name} {}", counter.load(Ordering::Relaxed));
+        }
         out
     }
 }
 
+impl HttpMetrics {
+    /// Per-route/method series. The latest trace ID per status class is
+    /// exported as `http_route_last_request_duration_ms{trace_id=…}` so a
+    /// slow or failing route can be looked up in the logs.
+    fn render_routes(&self, out: &mut String) {
+        let snap = self.route_snapshot();
+        if snap.is_empty() {
+            return;
+        }
+        let _ = writeln!(out, "# TYPE http_route_requests_total counter");
+        let _ = writeln!(out, "# TYPE http_route_request_duration_ms histogram");
+        let _ = writeln!(out, "# TYPE http_route_last_request_duration_ms gauge");
+        for ((method, route), stats) in &snap {
+            let labels = format!("method=\"{method}\",route=\"{route}\"");
+            for (class, count) in STATUS_CLASSES.iter().zip(stats.by_class) {
+                if count > 0 {
+                    let _ = writeln!(
+                        out,
+                        "http_route_requests_total{{{labels},status_class=\"{class}\"}} {count}"
+                    );
+                }
+            }
+            let mut cumulative = 0u64;
+            for (label, bucket) in BUCKET_LABELS.iter().zip(stats.histogram) {
+                cumulative += bucket;
+                let _ = writeln!(
+                    out,
+                    "http_route_request_duration_ms_bucket{{{labels},le=\"{label}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "http_route_request_duration_ms_sum{{{labels}}} {}",
+                stats.total_ms
+            );
+            let _ = writeln!(
+                out,
+                "http_route_request_duration_ms_count{{{labels}}} {}",
+                stats.count()
+            );
+            for (class, exemplar) in STATUS_CLASSES.iter().zip(&stats.exemplars) {
+                if let Some((trace_id, duration_ms)) = exemplar {
+                    let _ = writeln!(
+                        out,
+                        "http_route_last_request_duration_ms{{{labels},status_class=\"{class}\",\
+                         trace_id=\"{trace_id}\"}} {duration_ms}"
+                    );
+                }
+            }
+        }
+    }
+}
+
 static HTTP_METRICS: once_cell::sync::Lazy<HttpMetrics> =
     once_cell::sync::Lazy::new(HttpMetrics::new);
 
@@ -201,3 +334,26 @@ static RATE_LIMIT_REJECTIONS: AtomicU64 = AtomicU64::new(0);
 pub fn rate_limit_rejections() -> &'static AtomicU64 {
     &RATE_LIMIT_REJECTIONS
 }
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Sign-in lockout counters
+// ─────────────────────────────────────────────────────────────────────────────
+
+static AUTH_FAILURES: AtomicU64 = AtomicU64::new(0);
+static AUTH_LOCKOUTS: AtomicU64 = AtomicU64::new(0);
+static AUTH_LOCKOUT_REJECTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Failed sign-ins counted towards an address or IP lockout.
+pub fn auth_failures() -> &'static AtomicU64 {
+    &AUTH_FAILURES
+}
+
+/// Lockouts imposed after repeated sign-in failures.
+pub fn auth_lockouts() -> &'static AtomicU64 {
+    &AUTH_LOCKOUTS
+}
+
+/// Sign-in attempts refused because the address or IP was locked.
+pub fn auth_lockout_rejections() -> &'static AtomicU64 {
+    &AUTH_LOCKOUT_REJECTIONS
+}
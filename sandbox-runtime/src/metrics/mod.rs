@@ -4,9 +4,11 @@
 //! binary crate and pushed as on-chain metrics via `add_on_chain_metric()`.
 
 mod http;
+mod models;
 mod onchain;
 
 pub use http::*;
+pub use models::*;
 pub use onchain::*;
 
 #[cfg(test)]
@@ -107,6 +109,12 @@ mod tests {
         m.record_gc_container_removed();
         m.record_gc_image_removed();
         m.record_gc_s3_cleaned();
+        m.record_reaped_drain();
+        m.record_reaper_failure();
+        m.record_gc_failure();
+        m.record_gc_failure();
+        m.record_reaper_tick(1_700_000_000);
+        m.record_gc_tick(1_700_000_100);
 
         assert_eq!(m.reaped_idle.load(Ordering::Relaxed), 1);
         assert_eq!(m.reaped_lifetime.load(Ordering::Relaxed), 1);
@@ -116,6 +124,64 @@ mod tests {
         assert_eq!(m.gc_containers_removed.load(Ordering::Relaxed), 1);
         assert_eq!(m.gc_images_removed.load(Ordering::Relaxed), 1);
         assert_eq!(m.gc_s3_cleaned.load(Ordering::Relaxed), 1);
+
+        let snap: std::collections::HashMap<_, _> = m.snapshot().into_iter().collect();
+        assert_eq!(snap["reaped_drain"], 1);
+        assert_eq!(snap["reaper_failures"], 1);
+        assert_eq!(snap["gc_failures"], 2);
+        assert_eq!(snap["reaper_last_success_timestamp_seconds"], 1_700_000_000);
+        assert_eq!(snap["gc_last_success_timestamp_seconds"], 1_700_000_100);
+    }
+
+    #[test]
+    fn record_sidecar_retry_metrics() {
+        let m = OnChainMetrics::new();
+        m.record_sidecar_retry();
+        m.record_sidecar_retry();
+        m.record_sidecar_retries_exhausted();
+
+        let snap: std::collections::HashMap<_, _> = m.snapshot().into_iter().collect();
+        assert_eq!(snap["sidecar_retries"], 2);
+        assert_eq!(snap["sidecar_retries_exhausted"], 1);
+    }
+
+    #[test]
+    fn record_workflow_sync_metrics() {
+        let m = OnChainMetrics::new();
+        m.record_workflow_sync(3, 1_700_000_000);
+        m.record_workflow_sync(0, 1_700_000_300);
+        m.record_workflow_sync_failure();
+
+        let snap: std::collections::HashMap<_, _> = m.snapshot().into_iter().collect();
+        assert_eq!(snap["workflow_sync_drift"], 3);
+        assert_eq!(snap["workflow_sync_failures"], 1);
+        assert_eq!(
+            snap["workflow_sync_last_success_timestamp_seconds"],
+            1_700_000_300
+        );
+    }
+
+    #[test]
+    fn model_metrics_weight_billed_tokens() {
+        let mm = ModelMetrics::new();
+        mm.record("sonnet", 100, 50, 2.0);
+        mm.record("sonnet", 10, 0, 2.0);
+        mm.record("", 1, 1, 1.0);
+
+        let snap = mm.snapshot();
+        assert_eq!(snap[0].0, "default");
+        assert_eq!(
+            snap[1].1,
+            ModelUsage {
+                jobs: 2,
+                input_tokens: 110,
+                output_tokens: 50,
+                billed_tokens: 320,
+            }
+        );
+        let output = mm.render_prometheus();
+        assert!(output.contains("sandbox_model_billed_tokens_total{model=\"sonnet\"} 320"));
+        assert!(ModelMetrics::new().render_prometheus().is_empty());
     }
 
     #[test]
@@ -131,6 +197,8 @@ mod tests {
         assert!(output.contains("# TYPE sandbox_active_sandboxes gauge"));
         assert!(output.contains("# TYPE sandbox_allocated_cpu_cores gauge"));
         assert!(output.contains("# TYPE sandbox_peak_sandboxes gauge"));
+        assert!(output.contains("# TYPE sandbox_reaper_last_success_timestamp_seconds gauge"));
+        assert!(output.contains("# TYPE sandbox_gc_failures counter"));
 
         // Should contain actual values
         assert!(output.contains("sandbox_total_jobs 1"));
@@ -139,6 +207,18 @@ mod tests {
         assert!(output.contains("sandbox_allocated_memory_mb 1024"));
     }
 
+    #[test]
+    fn render_prometheus_job_trace_exemplars() {
+        let m = OnChainMetrics::new();
+        assert!(!m.render_prometheus().contains("_job_info"));
+        m.record_job_trace("aaaa", true);
+        m.record_job_trace("bbbb", false);
+        m.record_job_trace("cccc", false);
+        let output = m.render_prometheus();
+        assert!(output.contains("sandbox_last_job_info{trace_id=\"aaaa\"} 1"));
+        assert!(output.contains("sandbox_last_failed_job_info{trace_id=\"cccc\"} 1"));
+    }
+
     // ── HttpMetrics ─────────────────────────────────────────────────────
 
     #[test]
@@ -266,6 +346,10 @@ mod tests {
         // Rate limit counter
         assert!(output.contains("# TYPE rate_limit_rejections_total counter"));
         assert!(output.contains("rate_limit_rejections_total"));
+
+        // Sign-in lockout counters
+        assert!(output.contains("# TYPE auth_lockouts_total counter"));
+        assert!(output.contains("auth_lockout_rejections_total"));
     }
 
     #[test]
@@ -281,6 +365,51 @@ mod tests {
         assert_eq!(snap[0].1.total_ms, 5);
     }
 
+    #[test]
+    fn http_route_metrics_split_by_method_and_status_class() {
+        let hm = HttpMetrics::new();
+        hm.record_route("GET", "/api/sandboxes", 200, 12, Some("req-1"));
+        hm.record_route("GET", "/api/sandboxes", 503, 900, Some("req-2"));
+        hm.record_route("POST", "/api/sandboxes", 201, 30, None);
+        let snap = hm.route_snapshot();
+        assert_eq!(snap.len(), 2);
+        let (key, get) = &snap[0];
+        assert_eq!(key, &("GET".to_string(), "/api/sandboxes".to_string()));
+        assert_eq!(get.by_class, [0, 1, 0, 0, 1]);
+        assert_eq!(get.count(), 2);
+        assert_eq!(get.exemplars[4], Some(("req-2".to_string(), 900)));
+        assert!(snap[1].1.exemplars.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn http_route_metrics_render_prometheus_format() {
+        let hm = HttpMetrics::new();
+        hm.record("/api/sandboxes/{sandbox_id}", 42, true, false);
+        hm.record_route(
+            "DELETE",
+            "/api/sandboxes/{sandbox_id}",
+            500,
+            42,
+            Some("abcd"),
+        );
+        let output = hm.render_prometheus();
+        let labels = "method=\"DELETE\",route=\"/api/sandboxes/{sandbox_id}\"";
+        assert!(output.contains("# TYPE http_route_requests_total counter"));
+        assert!(output.contains(&format!(
+            "http_route_requests_total{{{labels},status_class=\"5xx\"}} 1"
+        )));
+        assert!(output.contains(&format!(
+            "http_route_request_duration_ms_bucket{{{labels},le=\"50\"}} 1"
+        )));
+        assert!(output.contains(&format!(
+            "http_route_request_duration_ms_count{{{labels}}} 1"
+        )));
+        assert!(output.contains(&format!(
+            "http_route_last_request_duration_ms{{{labels},status_class=\"5xx\",trace_id=\"abcd\"}} 42"
+        )));
+        assert!(!output.contains("status_class=\"2xx\""));
+    }
+
     #[test]
     fn endpoint_stats_default_min_is_max_u64() {
         let stats = EndpointStats::default();
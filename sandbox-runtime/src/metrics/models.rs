@@ -0,0 +1,91 @@
+//! Per-model agent usage counters, for billing by model.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Usage accumulated for one model.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelUsage {
+    pub jobs: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Input + output tokens weighted by the model's `price_multiplier`.
+    pub billed_tokens: u64,
+}
+
+/// Tracks agent usage per model. Runs without a model are recorded under
+/// `default` (the sidecar's configured model).
+pub struct ModelMetrics {
+    models: Mutex<HashMap<String, ModelUsage>>,
+}
+
+impl Default for ModelMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModelMetrics {
+    pub fn new() -> Self {
+        Self {
+            models: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a completed run of `model`.
+    pub fn record(&self, model: &str, input_tokens: u32, output_tokens: u32, multiplier: f64) {
+        let model = if model.is_empty() { "default" } else { model };
+        let tokens = u64::from(input_tokens) + u64::from(output_tokens);
+        let mut map = self.models.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = map.entry(model.to_string()).or_default();
+        entry.jobs += 1;
+        entry.input_tokens += u64::from(input_tokens);
+        entry.output_tokens += u64::from(output_tokens);
+        entry.billed_tokens += (tokens as f64 * multiplier).round() as u64;
+    }
+
+    /// Snapshot usage per model, sorted by model name.
+    pub fn snapshot(&self) -> Vec<(String, ModelUsage)> {
+        let map = self.models.lock().unwrap_or_else(|e| e.into_inner());
+        let mut snap: Vec<_> = map.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        snap.sort_by(|a, b| a.0.cmp(&b.0));
+        snap
+    }
+
+    /// Render per-model usage in Prometheus text exposition format.
+    pub fn render_prometheus(&self) -> String {
+        let snap = self.snapshot();
+        if snap.is_empty() {
+            return String::new();
+        }
+        let mut out = String::with_capacity(1024);
+        let _ = writeln!(out, "# TYPE sandbox_model_jobs_total counter");
+        let _ = writeln!(out, "# TYPE sandbox_model_input_tokens_total counter");
+        let _ = writeln!(out, "# TYPE sandbox_model_output_tokens_total counter");
+        let _ = writeln!(out, "# TYPE sandbox_model_billed_tokens_total counter");
+        for (model, usage) in &snap {
+            let model = model.replace(['\\', '"', '\n'], "_");
+            for (name, value) in [
+                ("jobs", usage.jobs),
+                ("input_tokens", usage.input_tokens),
+                ("output_tokens", usage.output_tokens),
+                ("billed_tokens", usage.billed_tokens),
+            ] {
+                let _ = writeln!(
+                    out,
+                    "sandbox_model_{name}_total{{model=\"{model}\"}} {value}"
+                );
+            }
+        }
+        out
+    }
+}
+
+static MODEL_METRICS: once_cell::sync::Lazy<ModelMetrics> =
+    once_cell::sync::Lazy::new(ModelMetrics::new);
+
+/// Returns the global per-model usage tracker.
+pub fn model_metrics() -> &'static ModelMetrics {
+    &MODEL_METRICS
+}
@@ -2,6 +2,7 @@
 //! periodically by the QoS background task and pushed on-chain.
 
 use std::fmt::Write;
+use std::sync::Mutex;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 
@@ -34,8 +35,19 @@ pub struct OnChainMetrics {
     pub reaped_idle: AtomicU64,
     /// Sandboxes reaped due to max lifetime exceeded.
     pub reaped_lifetime: AtomicU64,
+    /// Sandboxes stopped at the end of a drain window.
+    pub reaped_drain: AtomicU64,
+    /// Reaper actions (stop, delete, drain stop) that failed.
+    pub reaper_failures: AtomicU64,
+    /// Unix time of the last reaper tick that ran to completion (0 = never).
+    pub reaper_last_success_at: AtomicU64,
     /// Stopped sandboxes garbage collected past retention.
     pub garbage_collected: AtomicU64,
+    /// GC actions (container/image/snapshot removal, archive, pruning) that
+    /// failed.
+    pub gc_failures: AtomicU64,
+    /// Unix time of the last GC tick that ran to completion (0 = never).
+    pub gc_last_success_at: AtomicU64,
     /// Docker commits (snapshots) performed.
     pub snapshots_committed: AtomicU64,
     /// S3 snapshot uploads performed.
@@ -46,6 +58,21 @@ pub struct OnChainMetrics {
     pub gc_images_removed: AtomicU64,
     /// Cold->Gone GC transitions (S3 snapshots cleaned).
     pub gc_s3_cleaned: AtomicU64,
+    /// Sidecar requests re-sent after a transient failure.
+    pub sidecar_retries: AtomicU64,
+    /// Sidecar requests that still failed after being retried.
+    pub sidecar_retries_exhausted: AtomicU64,
+    /// Workflows created, updated or deactivated locally by a periodic chain
+    /// re-sync, i.e. drift between the local store and the chain.
+    pub workflow_sync_drift: AtomicU64,
+    /// Periodic workflow re-syncs that failed.
+    pub workflow_sync_failures: AtomicU64,
+    /// Unix time of the last workflow re-sync that succeeded (0 = never).
+    pub workflow_sync_last_success_at: AtomicU64,
+    /// Trace ID of the last on-chain job that completed (exemplar).
+    last_job_trace: Mutex<Option<String>>,
+    /// Trace ID of the last on-chain job that failed (exemplar).
+    last_failed_job_trace: Mutex<Option<String>>,
 }
 
 impl Default for OnChainMetrics {
@@ -69,12 +96,24 @@ impl OnChainMetrics {
             failed_jobs: AtomicU64::new(0),
             reaped_idle: AtomicU64::new(0),
             reaped_lifetime: AtomicU64::new(0),
+            reaped_drain: AtomicU64::new(0),
+            reaper_failures: AtomicU64::new(0),
+            reaper_last_success_at: AtomicU64::new(0),
             garbage_collected: AtomicU64::new(0),
+            gc_failures: AtomicU64::new(0),
+            gc_last_success_at: AtomicU64::new(0),
             snapshots_committed: AtomicU64::new(0),
             snapshots_uploaded: AtomicU64::new(0),
             gc_containers_removed: AtomicU64::new(0),
             gc_images_removed: AtomicU64::new(0),
             gc_s3_cleaned: AtomicU64::new(0),
+            sidecar_retries: AtomicU64::new(0),
+            sidecar_retries_exhausted: AtomicU64::new(0),
+            workflow_sync_drift: AtomicU64::new(0),
+            workflow_sync_failures: AtomicU64::new(0),
+            workflow_sync_last_success_at: AtomicU64::new(0),
+            last_job_trace: Mutex::new(None),
+            last_failed_job_trace: Mutex::new(None),
         }
     }
 
@@ -94,6 +133,17 @@ impl OnChainMetrics {
         self.failed_jobs.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Remember the trace ID of a finished on-chain job as an exemplar, so a
+    /// failure seen in metrics can be looked up in the logs.
+    pub fn record_job_trace(&self, trace_id: &str, ok: bool) {
+        let slot = if ok {
+            &self.last_job_trace
+        } else {
+            &self.last_failed_job_trace
+        };
+        *slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(trace_id.to_string());
+    }
+
     /// Record a sandbox reaped due to idle timeout.
     pub fn record_reaped_idle(&self) {
         self.reaped_idle.fetch_add(1, Ordering::Relaxed);
@@ -104,11 +154,36 @@ impl OnChainMetrics {
         self.reaped_lifetime.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Record a sandbox stopped at the end of a drain window.
+    pub fn record_reaped_drain(&self) {
+        self.reaped_drain.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a failed reaper action.
+    pub fn record_reaper_failure(&self) {
+        self.reaper_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a reaper tick that ran to completion at `now` (unix seconds).
+    pub fn record_reaper_tick(&self, now: u64) {
+        self.reaper_last_success_at.store(now, Ordering::Relaxed);
+    }
+
     /// Record a stopped sandbox garbage collected.
     pub fn record_garbage_collected(&self) {
         self.garbage_collected.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Record a failed GC action.
+    pub fn record_gc_failure(&self) {
+        self.gc_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a GC tick that ran to completion at `now` (unix seconds).
+    pub fn record_gc_tick(&self, now: u64) {
+        self.gc_last_success_at.store(now, Ordering::Relaxed);
+    }
+
     /// Record a docker commit (snapshot) performed.
     pub fn record_snapshot_committed(&self) {
         self.snapshots_committed.fetch_add(1, Ordering::Relaxed);
@@ -134,6 +209,30 @@ impl OnChainMetrics {
         self.gc_s3_cleaned.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Record a sidecar request being retried.
+    pub fn record_sidecar_retry(&self) {
+        self.sidecar_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a sidecar request that failed after exhausting its retries.
+    pub fn record_sidecar_retries_exhausted(&self) {
+        self.sidecar_retries_exhausted
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a workflow re-sync that succeeded at `now` (unix seconds) after
+    /// correcting `drift` local workflows.
+    pub fn record_workflow_sync(&self, drift: u64, now: u64) {
+        self.workflow_sync_drift.fetch_add(drift, Ordering::Relaxed);
+        self.workflow_sync_last_success_at
+            .store(now, Ordering::Relaxed);
+    }
+
+    /// Record a failed workflow re-sync.
+    pub fn record_workflow_sync_failure(&self) {
+        self.workflow_sync_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Record sandbox creation with its resource allocation.
     pub fn record_sandbox_created(&self, cpu_cores: u64, memory_mb: u64) {
         let current = self.active_sandboxes.fetch_add(1, Ordering::Relaxed) + 1;
@@ -230,10 +329,30 @@ impl OnChainMetrics {
                 "reaped_lifetime".into(),
                 self.reaped_lifetime.load(Ordering::Relaxed),
             ),
+            (
+                "reaped_drain".into(),
+                self.reaped_drain.load(Ordering::Relaxed),
+            ),
+            (
+                "reaper_failures".into(),
+                self.reaper_failures.load(Ordering::Relaxed),
+            ),
+            (
+                "reaper_last_success_timestamp_seconds".into(),
+                self.reaper_last_success_at.load(Ordering::Relaxed),
+            ),
             (
                 "garbage_collected".into(),
                 self.garbage_collected.load(Ordering::Relaxed),
             ),
+            (
+                "gc_failures".into(),
+                self.gc_failures.load(Ordering::Relaxed),
+            ),
+            (
+                "gc_last_success_timestamp_seconds".into(),
+                self.gc_last_success_at.load(Ordering::Relaxed),
+            ),
             (
                 "snapshots_committed".into(),
                 self.snapshots_committed.load(Ordering::Relaxed),
@@ -254,6 +373,26 @@ impl OnChainMetrics {
                 "gc_s3_cleaned".into(),
                 self.gc_s3_cleaned.load(Ordering::Relaxed),
             ),
+            (
+                "sidecar_retries".into(),
+                self.sidecar_retries.load(Ordering::Relaxed),
+            ),
+            (
+                "sidecar_retries_exhausted".into(),
+                self.sidecar_retries_exhausted.load(Ordering::Relaxed),
+            ),
+            (
+                "workflow_sync_drift".into(),
+                self.workflow_sync_drift.load(Ordering::Relaxed),
+            ),
+            (
+                "workflow_sync_failures".into(),
+                self.workflow_sync_failures.load(Ordering::Relaxed),
+            ),
+            (
+                "workflow_sync_last_success_timestamp_seconds".into(),
+                self.workflow_sync_last_success_at.load(Ordering::Relaxed),
+            ),
         ]
     }
 
@@ -265,6 +404,7 @@ impl OnChainMetrics {
             let mtype = if name.starts_with("active_")
                 || name.starts_with("allocated_")
                 || name.starts_with("peak_")
+                || name.ends_with("_timestamp_seconds")
             {
                 "gauge"
             } else {
@@ -273,6 +413,15 @@ impl OnChainMetrics {
             let _ = writeln!(out, "# TYPE {prom_name} {mtype}");
             let _ = writeln!(out, "{prom_name} {value}");
         }
+        for (name, slot) in [
+            ("sandbox_last_job_info", &self.last_job_trace),
+            ("sandbox_last_failed_job_info", &self.last_failed_job_trace),
+        ] {
+            if let Some(trace_id) = slot.lock().unwrap_or_else(|e| e.into_inner()).as_deref() {
+                let _ = writeln!(out, "# TYPE {name} gauge");
+                let _ = writeln!(out, "{name}{{trace_id=\"{trace_id}\"}} 1");
+            }
+        }
         out
     }
 }
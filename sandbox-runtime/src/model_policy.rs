@@ -0,0 +1,320 @@
+//! Operator model allowlist with per-model limits.
+//!
+//! Operators can only serve (and afford) some models. The policy maps each
+//! allowed model name to its limits:
+//!
+//! ```json
+//! {
+//!   "models": {
+//!     "claude-sonnet": { "max_tokens": 8192, "max_timeout_ms": 600000, "price_multiplier": 1.0 },
+//!     "claude-opus": { "max_tokens": 4096, "max_timeout_ms": 300000, "price_multiplier": 5.0 }
+//!   }
+//! }
+//! ```
+//!
+//! - `max_tokens` is forwarded to the sidecar as `backend.maxTokens` (`0` = no cap).
+//! - `max_timeout_ms` caps the request timeout and is used when the request
+//!   sets none (`0` = no cap).
+//! - `price_multiplier` weights the model's tokens in the per-model usage
+//!   counters (`sandbox_model_billed_tokens_total`). Defaults to `1.0`.
+//!
+//! The policy is read once per process from [`MODEL_POLICY_JSON_ENV`] or the
+//! file named by [`MODEL_POLICY_FILE_ENV`]. Unset (or an empty `models` map)
+//! allows every model. A request without a model uses the sidecar's
+//! configured default and is always allowed. A policy that fails to parse
+//! rejects every request naming a model.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+/// Env var holding the policy JSON.
+pub const MODEL_POLICY_JSON_ENV: &str = "SANDBOX_MODEL_POLICY_JSON";
+/// Env var naming a file holding the policy JSON.
+pub const MODEL_POLICY_FILE_ENV: &str = "SANDBOX_MODEL_POLICY_FILE";
+
+/// Error code for a model missing from the allowlist.
+pub const MODEL_NOT_ALLOWED: &str = "MODEL_NOT_ALLOWED";
+/// Error code for a timeout above the model's `max_timeout_ms`.
+pub const MODEL_TIMEOUT_EXCEEDED: &str = "MODEL_TIMEOUT_EXCEEDED";
+/// Error code for an unreadable or invalid policy.
+pub const MODEL_POLICY_INVALID: &str = "MODEL_POLICY_INVALID";
+
+/// Limits applied to one allowed model.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelLimits {
+    #[serde(default)]
+    pub max_tokens: u64,
+    #[serde(default)]
+    pub max_timeout_ms: u64,
+    #[serde(default = "default_price_multiplier")]
+    pub price_multiplier: f64,
+}
+
+fn default_price_multiplier() -> f64 {
+    1.0
+}
+
+impl Default for ModelLimits {
+    fn default() -> Self {
+        Self {
+            max_tokens: 0,
+            max_timeout_ms: 0,
+            price_multiplier: default_price_multiplier(),
+        }
+    }
+}
+
+/// A prompt/task rejected by the model policy. `code` is one of the
+/// `MODEL_*` constants so callers can branch on it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelRejection {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for ModelRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl From<ModelRejection> for String {
+    fn from(rejection: ModelRejection) -> Self {
+        rejection.to_string()
+    }
+}
+
+/// What a request may run with after the policy check.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelGrant {
+    /// Timeout to send to the sidecar (`0` = sidecar default).
+    pub timeout_ms: u64,
+    /// Output token cap to forward (`0` = none).
+    pub max_tokens: u64,
+}
+
+impl ModelGrant {
+    /// Forward the token cap as `backend.maxTokens`.
+    pub fn apply(&self, payload: &mut Map<String, Value>) {
+        if self.max_tokens == 0 {
+            return;
+        }
+        let backend = payload
+            .entry("backend")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(backend) = backend.as_object_mut() {
+            backend.insert("maxTokens".to_string(), json!(self.max_tokens));
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelPolicyConfig {
+    #[serde(default)]
+    models: BTreeMap<String, ModelLimits>,
+}
+
+/// A parsed model policy.
+#[derive(Debug, Default)]
+pub struct ModelPolicy {
+    models: BTreeMap<String, ModelLimits>,
+}
+
+impl ModelPolicy {
+    /// Parse a policy document.
+    pub fn from_json(raw: &str) -> Result<Self, String> {
+        let config: ModelPolicyConfig =
+            serde_json::from_str(raw).map_err(|e| format!("Invalid model policy: {e}"))?;
+        for (name, limits) in &config.models {
+            if name.trim().is_empty() {
+                return Err("Model policy has an empty model name".into());
+            }
+            if !limits.price_multiplier.is_finite() || limits.price_multiplier < 0.0 {
+                return Err(format!(
+                    "Model policy price_multiplier for {name:?} must be a non-negative number"
+                ));
+            }
+        }
+        Ok(Self {
+            models: config.models,
+        })
+    }
+
+    /// Limits for `model`, if the policy names it.
+    pub fn limits(&self, model: &str) -> Option<&ModelLimits> {
+        self.models.get(model)
+    }
+
+    /// Check a request for `model` with the requested `timeout_ms`.
+    pub fn check(&self, model: &str, timeout_ms: u64) -> Result<ModelGrant, ModelRejection> {
+        if self.models.is_empty() || model.is_empty() {
+            return Ok(ModelGrant {
+                timeout_ms,
+                max_tokens: 0,
+            });
+        }
+        let Some(limits) = self.models.get(model) else {
+            let allowed: Vec<&str> = self.models.keys().map(String::as_str).collect();
+            return Err(ModelRejection {
+                code: MODEL_NOT_ALLOWED,
+                message: format!(
+                    "model {model:?} is not offered by this operator (allowed: {})",
+                    allowed.join(", ")
+                ),
+            });
+        };
+        let max = limits.max_timeout_ms;
+        if max > 0 && timeout_ms > max {
+            return Err(ModelRejection {
+                code: MODEL_TIMEOUT_EXCEEDED,
+                message: format!(
+                    "timeout {timeout_ms} ms exceeds the {max} ms limit for model {model:?}"
+                ),
+            });
+        }
+        Ok(ModelGrant {
+            timeout_ms: if timeout_ms == 0 { max } else { timeout_ms },
+            max_tokens: limits.max_tokens,
+        })
+    }
+}
+
+static POLICY: OnceCell<Result<ModelPolicy, String>> = OnceCell::new();
+
+fn load_policy() -> Result<ModelPolicy, String> {
+    let raw = match std::env::var(MODEL_POLICY_JSON_ENV) {
+        Ok(raw) if !raw.trim().is_empty() => raw,
+        _ => match std::env::var(MODEL_POLICY_FILE_ENV) {
+            Ok(path) if !path.trim().is_empty() => std::fs::read_to_string(path.trim())
+                .map_err(|e| format!("Failed to read {MODEL_POLICY_FILE_ENV}: {e}"))?,
+            _ => return Ok(ModelPolicy::default()),
+        },
+    };
+    ModelPolicy::from_json(&raw)
+}
+
+/// The process-wide policy loaded from the environment.
+pub fn model_policy() -> Result<&'static ModelPolicy, ModelRejection> {
+    POLICY
+        .get_or_init(|| {
+            let policy = load_policy();
+            if let Err(e) = &policy {
+                tracing::error!(error = %e, "model policy is invalid; rejecting model requests");
+            }
+            policy
+        })
+        .as_ref()
+        .map_err(|e| ModelRejection {
+            code: MODEL_POLICY_INVALID,
+            message: e.clone(),
+        })
+}
+
+/// Check a prompt/task for `model` against the configured policy.
+pub fn check_model(model: &str, timeout_ms: u64) -> Result<ModelGrant, ModelRejection> {
+    if model.is_empty() {
+        return Ok(ModelGrant {
+            timeout_ms,
+            max_tokens: 0,
+        });
+    }
+    model_policy()?.check(model, timeout_ms)
+}
+
+/// The model an `/agents/run` payload asks for (empty = sidecar default).
+pub fn payload_model(payload: &Map<String, Value>) -> String {
+    payload
+        .get("backend")
+        .and_then(|b| b.get("model"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Record a completed agent run against `model` in the per-model usage
+/// counters, weighted by its `price_multiplier`.
+pub fn record_model_usage(model: &str, input_tokens: u32, output_tokens: u32) {
+    let multiplier = model_policy()
+        .ok()
+        .and_then(|p| p.limits(model))
+        .map_or(1.0, |l| l.price_multiplier);
+    crate::metrics::model_metrics().record(model, input_tokens, output_tokens, multiplier);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = r#"{"models": {
+        "sonnet": {"max_tokens": 8192, "max_timeout_ms": 60000, "price_multiplier": 1.5},
+        "haiku": {}
+    }}"#;
+
+    #[test]
+    fn empty_policy_allows_any_model() {
+        let policy = ModelPolicy::from_json("{}").unwrap();
+        assert_eq!(policy.check("anything", 5).unwrap().timeout_ms, 5);
+    }
+
+    #[test]
+    fn unknown_model_is_rejected_with_code() {
+        let policy = ModelPolicy::from_json(POLICY).unwrap();
+        let err = policy.check("opus", 0).unwrap_err();
+        assert_eq!(err.code, MODEL_NOT_ALLOWED);
+        assert!(err.message.contains("haiku, sonnet"));
+        assert!(String::from(err).starts_with("MODEL_NOT_ALLOWED: "));
+        assert!(policy.check("", 0).is_ok());
+    }
+
+    #[test]
+    fn limits_cap_and_default_timeout() {
+        let policy = ModelPolicy::from_json(POLICY).unwrap();
+        let grant = policy.check("sonnet", 0).unwrap();
+        assert_eq!(
+            grant,
+            ModelGrant {
+                timeout_ms: 60000,
+                max_tokens: 8192
+            }
+        );
+        assert_eq!(policy.check("sonnet", 1000).unwrap().timeout_ms, 1000);
+        assert_eq!(
+            policy.check("sonnet", 60001).unwrap_err().code,
+            MODEL_TIMEOUT_EXCEEDED
+        );
+        let haiku = policy.check("haiku", 0).unwrap();
+        assert_eq!(haiku, ModelGrant::default());
+        assert_eq!(policy.limits("haiku").unwrap().price_multiplier, 1.0);
+    }
+
+    #[test]
+    fn invalid_policies_are_rejected() {
+        assert!(ModelPolicy::from_json(r#"{"allow": []}"#).is_err());
+        assert!(ModelPolicy::from_json(r#"{"models": {"x": {"max_tokens": -1}}}"#).is_err());
+        assert!(ModelPolicy::from_json(r#"{"models": {"x": {"price_multiplier": -2}}}"#).is_err());
+        assert!(ModelPolicy::from_json(r#"{"models": {" ": {}}}"#).is_err());
+    }
+
+    #[test]
+    fn grant_forwards_max_tokens() {
+        let mut payload = Map::new();
+        payload.insert("backend".into(), json!({ "model": "sonnet" }));
+        ModelGrant {
+            timeout_ms: 0,
+            max_tokens: 100,
+        }
+        .apply(&mut payload);
+        assert_eq!(payload["backend"]["maxTokens"], 100);
+        assert_eq!(payload_model(&payload), "sonnet");
+
+        let mut bare = Map::new();
+        ModelGrant::default().apply(&mut bare);
+        assert!(bare.is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+//! Named agent sessions.
+//!
+//! Prompt and task requests may carry a `session_name` instead of a sidecar
+//! `session_id`. The operator keeps the mapping (sandbox, owner, name) →
+//! sidecar session ID, so callers — on-chain ones especially — do not have to
+//! track IDs across calls:
+//!
+//! - the first run under a name starts a new sidecar session, and the ID the
+//!   sidecar returns is recorded ([`record_named_session`]);
+//! - later runs resume that session ([`resolve_session`]);
+//! - when the sidecar answers with a different ID (it rotated the session) the
+//!   mapping follows it, and when it no longer knows the session
+//!   ([`is_stale_session_error`]) the caller forgets the mapping and starts a
+//!   fresh one under the same name.
+//!
+//! Mappings are persisted in the state directory and removed by GC once their
+//! sandbox is gone.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SandboxError};
+use crate::store::PersistentStore;
+
+/// Maximum named sessions per owner and sandbox.
+pub const MAX_NAMED_SESSIONS: usize = 64;
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedSession {
+    pub sandbox_id: String,
+    /// Lowercased address the name belongs to.
+    pub owner: String,
+    pub name: String,
+    /// Sidecar session ID the name currently resolves to.
+    pub session_id: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// Times the sidecar replaced the session.
+    #[serde(default)]
+    pub rotations: u32,
+}
+
+static SESSIONS: OnceCell<PersistentStore<NamedSession>> = OnceCell::new();
+
+/// Access the named session persistent store.
+pub fn named_sessions() -> Result<&'static PersistentStore<NamedSession>> {
+    SESSIONS
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("named_sessions.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+fn key(sandbox_id: &str, owner: &str, name: &str) -> String {
+    format!("{sandbox_id}/{}/{name}", owner.to_ascii_lowercase())
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(SandboxError::Validation(format!(
+            "session_name must be 1-{MAX_NAME_LEN} chars of [A-Za-z0-9_.-]"
+        )))
+    }
+}
+
+/// Sidecar session ID to run with. Without a `session_name` that is
+/// `session_id` as given; with one, the ID the name maps to, or empty for a
+/// new session. Giving both is an error.
+pub fn resolve_session(
+    sandbox_id: &str,
+    owner: &str,
+    session_name: &str,
+    session_id: &str,
+) -> Result<String> {
+    let name = session_name.trim();
+    if name.is_empty() {
+        return Ok(session_id.to_string());
+    }
+    validate_name(name)?;
+    if !session_id.trim().is_empty() {
+        return Err(SandboxError::Validation(
+            "Give either session_id or session_name, not both".into(),
+        ));
+    }
+    Ok(named_sessions()?
+        .get(&key(sandbox_id, owner, name))?
+        .map(|s| s.session_id)
+        .unwrap_or_default())
+}
+
+/// Point `session_name` at the sidecar session a run ended in. A no-op
+/// without a name or an ID.
+pub fn record_named_session(
+    sandbox_id: &str,
+    owner: &str,
+    session_name: &str,
+    session_id: &str,
+) -> Result<()> {
+    let name = session_name.trim();
+    if name.is_empty() || session_id.is_empty() {
+        return Ok(());
+    }
+    validate_name(name)?;
+    let store = named_sessions()?;
+    let key = key(sandbox_id, owner, name);
+    let now = crate::util::now_ts();
+    let session = match store.get(&key)? {
+        Some(existing) if existing.session_id == session_id => NamedSession {
+            updated_at: now,
+            ..existing
+        },
+        Some(existing) => {
+            tracing::info!(
+                sandbox_id,
+                session_name = name,
+                from = %existing.session_id,
+                to = session_id,
+                "Named session moved to a new sidecar session"
+            );
+            NamedSession {
+                session_id: session_id.to_string(),
+                updated_at: now,
+                rotations: existing.rotations + 1,
+                ..existing
+            }
+        }
+        None => {
+            if list_named_sessions(sandbox_id, owner)?.len() >= MAX_NAMED_SESSIONS {
+                return Err(SandboxError::Validation(format!(
+                    "Owner already has {MAX_NAMED_SESSIONS} named sessions on sandbox {sandbox_id}"
+                )));
+            }
+            NamedSession {
+                sandbox_id: sandbox_id.to_string(),
+                owner: owner.to_ascii_lowercase(),
+                name: name.to_string(),
+                session_id: session_id.to_string(),
+                created_at: now,
+                updated_at: now,
+                rotations: 0,
+            }
+        }
+    };
+    store.insert(key, session)
+}
+
+/// Forget `session_name`, so the next run under it starts a new session.
+/// Returns whether it was mapped.
+pub fn forget_named_session(sandbox_id: &str, owner: &str, session_name: &str) -> Result<bool> {
+    Ok(named_sessions()?
+        .remove(&key(sandbox_id, owner, session_name.trim()))?
+        .is_some())
+}
+
+/// Named sessions of `owner` on `sandbox_id`, sorted by name.
+pub fn list_named_sessions(sandbox_id: &str, owner: &str) -> Result<Vec<NamedSession>> {
+    let mut sessions: Vec<NamedSession> = named_sessions()?
+        .values()?
+        .into_iter()
+        .filter(|s| s.sandbox_id == sandbox_id && s.owner.eq_ignore_ascii_case(owner))
+        .collect();
+    sessions.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(sessions)
+}
+
+/// Whether a failed run's error means the sidecar no longer has the session.
+pub fn is_stale_session_error(error: &str) -> bool {
+    let error = error.to_ascii_lowercase();
+    error.contains("session")
+        && ["not found", "expired", "unknown", "does not exist"]
+            .iter()
+            .any(|needle| error.contains(needle))
+}
+
+/// Drop the named sessions of sandboxes that no longer exist.
+pub fn gc_named_sessions() -> Result<()> {
+    let store = named_sessions()?;
+    for session in store.values()? {
+        if let Err(SandboxError::NotFound(_)) =
+            crate::runtime::get_sandbox_by_id(&session.sandbox_id)
+        {
+            store.remove(&key(&session.sandbox_id, &session.owner, &session.name))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() {
+        let dir = std::env::temp_dir().join(format!("named-sessions-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", dir) };
+    }
+
+    #[test]
+    fn names_start_follow_and_forget_sidecar_sessions() {
+        init();
+        let (sb, owner) = ("sb-named-1", "0xABC");
+        assert_eq!(resolve_session(sb, owner, "", "raw-id").unwrap(), "raw-id");
+        assert_eq!(resolve_session(sb, owner, "review", "").unwrap(), "");
+
+        record_named_session(sb, owner, "review", "sess-1").unwrap();
+        assert_eq!(
+            resolve_session(sb, "0xabc", "review", "").unwrap(),
+            "sess-1"
+        );
+        assert_eq!(resolve_session(sb, "0xdef", "review", "").unwrap(), "");
+
+        record_named_session(sb, owner, "review", "sess-2").unwrap();
+        let listed = list_named_sessions(sb, owner).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].session_id, "sess-2");
+        assert_eq!(listed[0].rotations, 1);
+
+        assert!(forget_named_session(sb, owner, "review").unwrap());
+        assert_eq!(resolve_session(sb, owner, "review", "").unwrap(), "");
+    }
+
+    #[test]
+    fn invalid_requests_are_rejected() {
+        init();
+        assert!(resolve_session("sb", "0xa", "review", "sess-1").is_err());
+        assert!(resolve_session("sb", "0xa", "bad name", "").is_err());
+        assert!(is_stale_session_error("Session sess-1 not found"));
+        assert!(!is_stale_session_error("model not found"));
+    }
+}
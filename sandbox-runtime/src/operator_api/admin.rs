@@ -23,7 +23,7 @@ pub(crate) fn require_managing_operator(
         Some(op) if op.eq_ignore_ascii_case(address) => Ok(()),
         Some(_) => Err(api_error(
             StatusCode::FORBIDDEN,
-            "Only the managing operator may upgrade sidecar images".to_string(),
+            "Only the managing operator may perform this operation".to_string(),
         )),
         None => Err(api_error(
             StatusCode::FORBIDDEN,
@@ -106,3 +106,77 @@ pub(crate) async fn upgrade_stale_sidecar_images_handler(
     )
         .into_response()
 }
+
+// ---------------------------------------------------------------------------
+// Drain mode (maintenance windows)
+// ---------------------------------------------------------------------------
+//
+// Host reboots and kernel upgrades: stop taking new sandboxes, warn owners,
+// then snapshot/stop what is still running when the window ends (see
+// `crate::drain`). Operator-only, like the image upgrade endpoints.
+
+fn drain_status() -> std::result::Result<Value, (StatusCode, Json<ApiError>)> {
+    let drain = crate::drain::current_drain().map_err(classify_sandbox_error)?;
+    let configured = crate::drain::drain_mode_configured();
+    Ok(json!({
+        "draining": configured || drain.is_some(),
+        "configured": configured,
+        "drain": drain,
+    }))
+}
+
+/// GET /api/operator/drain — whether the node is draining, and the state of
+/// an API-started drain window.
+pub(crate) async fn drain_status_handler(SessionAuth(address): SessionAuth) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(drain_status()?)))
+}
+
+/// POST /api/operator/drain — start (or reschedule) a drain window.
+pub(crate) async fn start_drain_handler(
+    SessionAuth(address): SessionAuth,
+    Json(request): Json<crate::drain::DrainRequest>,
+) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    crate::drain::start_drain(request).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(drain_status()?)))
+}
+
+/// DELETE /api/operator/drain — end an API-started drain and accept new
+/// sandboxes again (unless `OPERATOR_DRAIN_MODE` is set).
+pub(crate) async fn end_drain_handler(SessionAuth(address): SessionAuth) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    crate::drain::end_drain().map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(drain_status()?)))
+}
+
+/// GET /api/operator/auto-provision — instance auto-provisions that gave up
+/// (see `crate::auto_provision_status`). Empty when every service is running.
+pub(crate) async fn auto_provision_failures_handler(
+    SessionAuth(address): SessionAuth,
+) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    let failures = crate::auto_provision_status::list_auto_provision_failures()
+        .map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "failures": failures }))))
+}
+
+/// GET /api/operator/capacity — sandbox count and running CPU/memory against
+/// the admission limits (`SANDBOX_MAX_COUNT`, `OPERATOR_MAX_CAPACITY` and the
+/// host budgets). Creates past a limit fail with a `CAPACITY` error.
+pub(crate) async fn capacity_handler(SessionAuth(address): SessionAuth) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    let snapshot = crate::runtime::capacity_snapshot().map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(snapshot)))
+}
+
+/// GET /api/operator/images — the last image pre-pull pass: each pre-pulled
+/// image with its digest, the host's image layer size against
+/// `IMAGE_CACHE_MAX_GB`, and what was pruned (see [`crate::image_cache`]).
+pub(crate) async fn image_cache_handler(SessionAuth(address): SessionAuth) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((
+        StatusCode::OK,
+        Json(crate::image_cache::image_cache_status()),
+    ))
+}
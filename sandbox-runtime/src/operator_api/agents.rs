@@ -1,6 +1,7 @@
 //! Extracted from operator_api.rs — agents route group.
 
 use super::*;
+use crate::sidecar_compat::SidecarApi;
 
 /// Build `/terminals/commands` payload for exec operations.
 pub(crate) fn build_exec_payload(
@@ -8,6 +9,8 @@ pub(crate) fn build_exec_payload(
     cwd: &str,
     env_json: &str,
     timeout_ms: u64,
+    stdin: &str,
+    stdin_file: &str,
 ) -> Value {
     let mut payload = Map::new();
     payload.insert("command".to_string(), Value::String(command.to_string()));
@@ -22,12 +25,13 @@ pub(crate) fn build_exec_payload(
     {
         payload.insert("env".to_string(), env_map);
     }
+    crate::exec_input::insert_exec_input(&mut payload, stdin, stdin_file);
     Value::Object(payload)
 }
 
 /// Parse exec response from sidecar.
-pub(crate) fn parse_exec_response(parsed: &Value) -> ExecApiResponse {
-    let result = parsed.get("result");
+pub(crate) fn parse_exec_response(api: SidecarApi, parsed: &Value) -> ExecApiResponse {
+    let result = api.command_result(parsed);
     ExecApiResponse {
         exit_code: result
             .and_then(|r| r.get("exitCode"))
@@ -43,6 +47,8 @@ pub(crate) fn parse_exec_response(parsed: &Value) -> ExecApiResponse {
             .and_then(Value::as_str)
             .unwrap_or_default()
             .to_string(),
+        truncated: false,
+        overflow_ref: None,
     }
 }
 
@@ -238,7 +244,24 @@ pub(crate) async fn exec_on_sidecar(
     record: &SandboxRecord,
     req: &ExecApiRequest,
 ) -> Result<ExecApiResponse, (StatusCode, Json<ApiError>)> {
-    let payload = build_exec_payload(&req.command, &req.cwd, &req.env_json, req.timeout_ms);
+    let req =
+        &crate::exec_presets::apply_exec_preset(&record.id, req).map_err(classify_sandbox_error)?;
+    let input = CommandInput {
+        kind: CommandKind::Exec,
+        command: &req.command,
+        cwd: &req.cwd,
+        stdin_file: &req.stdin_file,
+        timeout_ms: req.timeout_ms,
+    };
+    enforce_command_policy(&record.id, &record.owner, &input).map_err(classify_sandbox_error)?;
+    let payload = build_exec_payload(
+        &req.command,
+        &req.cwd,
+        &req.env_json,
+        req.timeout_ms,
+        &req.stdin,
+        &req.stdin_file,
+    );
     let parsed = sidecar_call(
         record,
         "/terminals/commands",
@@ -248,7 +271,8 @@ pub(crate) async fn exec_on_sidecar(
         true,
     )
     .await?;
-    Ok(parse_exec_response(&parsed))
+    crate::usage_ledger::record_usage(&record.owner, crate::usage_ledger::UsageTotals::exec());
+    Ok(parse_exec_response(SidecarApi::for_record(record), &parsed))
 }
 
 pub(crate) async fn sandbox_agents_handler(
@@ -290,7 +314,7 @@ pub(crate) async fn sandbox_exec_handler(
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
     let record = resolve_sandbox(&sandbox_id, &address)?;
-    let resp = exec_on_sidecar(&record, &req).await?;
+    let resp = exec_on_sidecar(&record, &req).await?.capped(&record.id);
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
 }
 
@@ -300,7 +324,76 @@ pub(crate) async fn instance_exec_handler(
 ) -> impl IntoResponse {
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
-    let record = resolve_instance(&address)?;
-    let resp = exec_on_sidecar(&record, &req).await?;
+    let record = resolve_instance_slot(&address, &req.slot)?;
+    let resp = exec_on_sidecar(&record, &req).await?.capped(&record.id);
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
 }
+
+/// GET /api/sandboxes/{id}/exec/overflow/{overflow_id} — full output of a
+/// truncated exec.
+pub(crate) async fn sandbox_exec_overflow_handler(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, overflow_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let overflow = crate::exec_output::get_exec_overflow(&record.id, &overflow_id)
+        .map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(overflow)))
+}
+
+/// GET /api/sandbox/exec/overflow/{overflow_id} — instance variant.
+pub(crate) async fn instance_exec_overflow_handler(
+    SessionAuth(address): SessionAuth,
+    Path(overflow_id): Path<String>,
+) -> impl IntoResponse {
+    let record = resolve_instance(&address)?;
+    let overflow = crate::exec_output::get_exec_overflow(&record.id, &overflow_id)
+        .map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(overflow)))
+}
+
+/// Full result behind a `resultCommitment`, served as the exact bytes that
+/// were hashed so `keccak256(body)` can be checked against the commitment.
+fn result_artifact_response(
+    artifact: crate::result_commitment::ResultArtifact,
+) -> axum::response::Response {
+    (
+        StatusCode::OK,
+        [
+            ("content-type", "application/json".to_string()),
+            ("x-result-hash", artifact.hash),
+        ],
+        artifact.payload,
+    )
+        .into_response()
+}
+
+/// GET /api/sandboxes/{id}/results/{artifact_id} — full payload of a job
+/// result that was replaced by a hash commitment.
+pub(crate) async fn sandbox_result_artifact_handler(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, artifact_id)): Path<(String, String)>,
+) -> Result<axum::response::Response, (StatusCode, Json<ApiError>)> {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let artifact = crate::result_commitment::get_result_artifact(&record.id, &artifact_id)
+        .map_err(classify_sandbox_error)?;
+    Ok(result_artifact_response(artifact))
+}
+
+/// GET /api/sandbox/results/{artifact_id} — instance variant. The artifact
+/// may belong to any slot the caller owns.
+pub(crate) async fn instance_result_artifact_handler(
+    SessionAuth(address): SessionAuth,
+    Path(artifact_id): Path<String>,
+) -> Result<axum::response::Response, (StatusCode, Json<ApiError>)> {
+    let artifact = crate::result_commitment::find_result_artifact(&artifact_id)
+        .map_err(classify_sandbox_error)?;
+    // Hide artifacts of sandboxes the caller does not own.
+    resolve_sandbox(&artifact.sandbox_id, &address).map_err(|_| {
+        api_error(
+            StatusCode::NOT_FOUND,
+            format!("Result artifact '{artifact_id}' not found"),
+        )
+    })?;
+    Ok(result_artifact_response(artifact))
+}
@@ -0,0 +1,70 @@
+//! Audit log route group: SSH key changes and logins per sandbox, and the
+//! operator-wide job replay log export.
+
+use super::*;
+
+fn audit_response(
+    record: &SandboxRecord,
+) -> Result<AuditLogApiResponse, (StatusCode, Json<ApiError>)> {
+    let events = crate::audit_log::list_audit_events(&record.id).map_err(classify_sandbox_error)?;
+    Ok(AuditLogApiResponse {
+        success: true,
+        events,
+    })
+}
+
+/// GET /api/sandboxes/{sandbox_id}/audit — audit events, oldest first.
+pub(crate) async fn sandbox_audit_handler(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let resp = audit_response(&record)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
+}
+
+/// GET /api/sandbox/audit — audit events for the instance sandbox.
+pub(crate) async fn instance_audit_handler(SessionAuth(address): SessionAuth) -> impl IntoResponse {
+    let record = resolve_instance(&address)?;
+    let resp = audit_response(&record)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
+}
+
+/// GET /api/operator/audit/jobs?after=&limit= — export of the job replay
+/// log as JSON lines, oldest first. Managing operator only: entries carry
+/// every customer's job requests. `x-replay-chain-valid` reports whether the
+/// returned run of entries passed [`crate::job_replay::verify_chain`];
+/// `x-replay-checkpoint` is the retention checkpoint it verified from, as
+/// `<seq>:<entryHash>` (empty until old segments have been deleted).
+pub(crate) async fn job_replay_export_handler(
+    SessionAuth(address): SessionAuth,
+    axum::extract::Query(query): axum::extract::Query<JobReplayQuery>,
+) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    let entries = crate::job_replay::read_entries(query.after, query.effective_limit())
+        .map_err(classify_sandbox_error)?;
+    let checkpoint = crate::job_replay::checkpoint().map_err(classify_sandbox_error)?;
+    let chain_valid = crate::job_replay::verify_chain(&entries, checkpoint.as_ref()).is_ok();
+    let checkpoint = checkpoint
+        .map(|c| format!("{}:{}", c.seq, c.entry_hash))
+        .unwrap_or_default();
+    let mut body = String::new();
+    for entry in &entries {
+        if let Ok(line) = serde_json::to_string(entry) {
+            body.push_str(&line);
+            body.push('\n');
+        }
+    }
+    Ok::<_, (StatusCode, Json<ApiError>)>((
+        StatusCode::OK,
+        [
+            ("content-type", "application/x-ndjson"),
+            (
+                "x-replay-chain-valid",
+                if chain_valid { "true" } else { "false" }.to_string(),
+            ),
+            ("x-replay-checkpoint", checkpoint),
+        ],
+        body,
+    ))
+}
@@ -1,5 +1,8 @@
 //! Extracted from operator_api.rs — auth route group.
 
+use axum::extract::FromRequest;
+use axum::response::Response;
+
 use super::*;
 
 // ---------------------------------------------------------------------------
@@ -12,9 +15,41 @@ pub(crate) struct SessionRequest {
     pub(crate) signature: String,
 }
 
-pub(crate) async fn create_challenge() -> impl IntoResponse {
-    let challenge = match session_auth::create_challenge() {
+/// Optional body of `POST /api/auth/challenge`: the address the client will
+/// sign with and the chain it is connected to, embedded in the challenge.
+#[derive(Deserialize, Default)]
+pub(crate) struct ChallengeRequest {
+    #[serde(default)]
+    pub(crate) address: Option<String>,
+    #[serde(default)]
+    pub(crate) chain_id: Option<u64>,
+}
+
+/// Issue a sign-in challenge. A locked client IP or address gets `429` with a
+/// `Retry-After` instead.
+pub(crate) async fn create_challenge(request: axum::extract::Request) -> impl IntoResponse {
+    let ip_key = session_auth::LockoutKey::Ip(rate_limit::client_ip(&request));
+    let req = match Option::<Json<ChallengeRequest>>::from_request(request, &()).await {
+        Ok(req) => req.map(|Json(r)| r).unwrap_or_default(),
+        Err(rejection) => return rejection.into_response(),
+    };
+    if let Some(secs) = session_auth::locked_for(&ip_key) {
+        metrics::auth_lockout_rejections().fetch_add(1, Ordering::Relaxed);
+        return locked_out(&ip_key, secs);
+    }
+    let challenge = match session_auth::create_challenge(req.address.as_deref(), req.chain_id) {
         Ok(c) => c,
+        Err(crate::error::SandboxError::Validation(msg)) => {
+            return api_error(StatusCode::BAD_REQUEST, msg).into_response();
+        }
+        // The only auth failure here is a locked address.
+        Err(crate::error::SandboxError::Auth(msg)) => {
+            let key = session_auth::LockoutKey::address(req.address.as_deref().unwrap_or_default());
+            return match session_auth::locked_for(&key) {
+                Some(secs) => locked_out(&key, secs),
+                None => api_error(StatusCode::TOO_MANY_REQUESTS, msg).into_response(),
+            };
+        }
         Err(e) => {
             return api_error(StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response();
         }
@@ -25,8 +60,31 @@ pub(crate) async fn create_challenge() -> impl IntoResponse {
     }
 }
 
-pub(crate) async fn create_session(Json(req): Json<SessionRequest>) -> impl IntoResponse {
+/// Exchange a signed challenge for a session token.
+///
+/// Failures count towards a lockout of the client IP, and of a bound
+/// challenge's address only when signed with its key (see
+/// [`session_auth::exchange_signature_for_token`]); locked callers get `429`
+/// with a `Retry-After` until the lockout ends.
+pub(crate) async fn create_session(request: axum::extract::Request) -> impl IntoResponse {
+    let ip_key = session_auth::LockoutKey::Ip(rate_limit::client_ip(&request));
+    let Json(req) = match Json::<SessionRequest>::from_request(request, &()).await {
+        Ok(req) => req,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let address_key = session_auth::challenge_address(&req.nonce)
+        .map(|address| session_auth::LockoutKey::address(&address));
+    for key in std::iter::once(&ip_key).chain(address_key.as_ref()) {
+        if let Some(secs) = session_auth::locked_for(key) {
+            metrics::auth_lockout_rejections().fetch_add(1, Ordering::Relaxed);
+            return locked_out(key, secs);
+        }
+    }
+
     match session_auth::exchange_signature_for_token(&req.nonce, &req.signature) {
+        // A success does not reset the IP's count: one working wallet must
+        // not let a client interleave unlimited guesses.
         Ok(token) => match serde_json::to_value(token) {
             Ok(val) => (StatusCode::OK, Json(val)).into_response(),
             Err(e) => json_serialization_error(e),
@@ -34,10 +92,24 @@ pub(crate) async fn create_session(Json(req): Json<SessionRequest>) -> impl Into
         Err(crate::error::SandboxError::Unavailable(msg)) => {
             api_error(StatusCode::SERVICE_UNAVAILABLE, msg).into_response()
         }
-        Err(e) => api_error(StatusCode::UNAUTHORIZED, e.to_string()).into_response(),
+        Err(e) => {
+            session_auth::record_auth_failure(&ip_key);
+            api_error(StatusCode::UNAUTHORIZED, e.to_string()).into_response()
+        }
     }
 }
 
+fn locked_out(key: &session_auth::LockoutKey, secs: u64) -> Response {
+    (
+        [(axum::http::header::RETRY_AFTER, secs.to_string())],
+        api_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Too many failed sign-ins for {key}; try again in {secs}s"),
+        ),
+    )
+        .into_response()
+}
+
 /// Revoke the current session token.
 pub(crate) async fn revoke_session(headers: HeaderMap) -> impl IntoResponse {
     let token = headers
@@ -61,3 +133,43 @@ pub(crate) async fn revoke_session(headers: HeaderMap) -> impl IntoResponse {
         None => api_error(StatusCode::BAD_REQUEST, "Missing Authorization header").into_response(),
     }
 }
+
+// ---------------------------------------------------------------------------
+// Sign-in lockouts (operator)
+// ---------------------------------------------------------------------------
+
+/// GET /api/operator/auth/lockouts — addresses and IPs with recent sign-in
+/// failures, locked ones first.
+pub(crate) async fn list_auth_lockouts_handler(
+    SessionAuth(address): SessionAuth,
+) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((
+        StatusCode::OK,
+        Json(json!({ "lockouts": session_auth::list_lockouts() })),
+    ))
+}
+
+/// DELETE /api/operator/auth/lockouts/{subject} — lift the lockout of an
+/// address (`0x…`) or IP and forget its failures.
+pub(crate) async fn clear_auth_lockout_handler(
+    SessionAuth(address): SessionAuth,
+    Path(subject): Path<String>,
+) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    let key = match subject.parse::<std::net::IpAddr>() {
+        Ok(ip) => session_auth::LockoutKey::Ip(ip),
+        Err(_) if subject.starts_with("0x") => session_auth::LockoutKey::address(&subject),
+        Err(_) => {
+            return Err(api_error(
+                StatusCode::BAD_REQUEST,
+                format!("{subject:?} is neither an address nor an IP"),
+            ));
+        }
+    };
+    let cleared = session_auth::clear_lockout(&key);
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "subject": key, "cleared": cleared })),
+    ))
+}
@@ -214,6 +214,8 @@ pub(crate) struct SpawnChatRunRequest {
     pub(crate) context_json: String,
     pub(crate) timeout_ms: u64,
     pub(crate) max_turns: Option<u64>,
+    /// Reserved by the handler; the run waits for it before starting.
+    pub(crate) agent_run: crate::agent_runs::AgentRunSlot,
 }
 
 pub(crate) fn spawn_chat_run(record: SandboxRecord, request: SpawnChatRunRequest) {
@@ -226,6 +228,7 @@ pub(crate) fn spawn_chat_run(record: SandboxRecord, request: SpawnChatRunRequest
         context_json,
         timeout_ms,
         max_turns,
+        mut agent_run,
     } = request;
     let spawned_run_id = run_id.clone();
     let handle = tokio::spawn(async move {
@@ -249,6 +252,7 @@ pub(crate) fn spawn_chat_run(record: SandboxRecord, request: SpawnChatRunRequest
             "queued",
             "Run accepted and queued by the operator.",
         );
+        agent_run.ready().await;
 
         let started_at = chat_state::now_ms();
         let _ = chat_state::update_run(&run_id, |run| {
@@ -370,6 +374,10 @@ pub(crate) fn spawn_chat_run(record: SandboxRecord, request: SpawnChatRunRequest
         match result {
             Ok(ar) => {
                 metrics::metrics().record_job(ar.duration_ms, ar.input_tokens, ar.output_tokens);
+                crate::usage_ledger::record_usage(
+                    &record.owner,
+                    crate::usage_ledger::UsageTotals::tokens(ar.input_tokens, ar.output_tokens),
+                );
                 let completed_at = chat_state::now_ms();
                 let final_status = if ar.success {
                     ChatRunStatus::Completed
@@ -31,9 +31,12 @@ pub(crate) async fn sandbox_prompt_handler(
 ) -> impl IntoResponse {
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    let grant = check_model(&req.model, req.timeout_ms).map_err(model_rejection_error)?;
     let record = resolve_sandbox(&sandbox_id, &address)?;
     let scope = live_scope_sandbox(&record.id);
     require_running(&record)?;
+    crate::disk_quota::ensure_disk_writable(&record.id).map_err(classify_sandbox_error)?;
+    let agent_run = reserve_agent_run(&record)?;
     let (session, run) = enqueue_chat_run(
         &scope,
         &address,
@@ -50,8 +53,9 @@ pub(crate) async fn sandbox_prompt_handler(
             backend_type: req.backend_type,
             model: req.model,
             context_json: req.context_json,
-            timeout_ms: req.timeout_ms,
+            timeout_ms: grant.timeout_ms,
             max_turns: None,
+            agent_run,
         },
     );
     Ok::<_, (StatusCode, Json<ApiError>)>((
@@ -66,9 +70,12 @@ pub(crate) async fn instance_prompt_handler(
 ) -> impl IntoResponse {
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
-    let record = resolve_instance(&address)?;
+    let grant = check_model(&req.model, req.timeout_ms).map_err(model_rejection_error)?;
+    let record = resolve_instance_slot(&address, &req.slot)?;
     let scope = live_scope_instance(&record);
     require_running(&record)?;
+    crate::disk_quota::ensure_disk_writable(&record.id).map_err(classify_sandbox_error)?;
+    let agent_run = reserve_agent_run(&record)?;
     let (session, run) = enqueue_chat_run(
         &scope,
         &address,
@@ -85,8 +92,9 @@ pub(crate) async fn instance_prompt_handler(
             backend_type: req.backend_type,
             model: req.model,
             context_json: req.context_json,
-            timeout_ms: req.timeout_ms,
+            timeout_ms: grant.timeout_ms,
             max_turns: None,
+            agent_run,
         },
     );
     Ok::<_, (StatusCode, Json<ApiError>)>((
@@ -97,6 +105,19 @@ pub(crate) async fn instance_prompt_handler(
 
 // ── Task ─────────────────────────────────────────────────────────────────
 
+/// Chat runs continue operator chat sessions (`session_id`); named sidecar
+/// sessions are for the task queue.
+fn reject_session_name(req: &TaskApiRequest) -> Result<(), (StatusCode, Json<ApiError>)> {
+    if req.session_name.trim().is_empty() {
+        Ok(())
+    } else {
+        Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "session_name is only supported for queued tasks; chat tasks continue a chat session_id",
+        ))
+    }
+}
+
 pub(crate) async fn sandbox_task_handler(
     SessionAuth(address): SessionAuth,
     Path(sandbox_id): Path<String>,
@@ -104,9 +125,13 @@ pub(crate) async fn sandbox_task_handler(
 ) -> impl IntoResponse {
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    reject_session_name(&req)?;
+    let grant = check_model(&req.model, req.timeout_ms).map_err(model_rejection_error)?;
     let record = resolve_sandbox(&sandbox_id, &address)?;
     let scope = live_scope_sandbox(&record.id);
     require_running(&record)?;
+    crate::disk_quota::ensure_disk_writable(&record.id).map_err(classify_sandbox_error)?;
+    let agent_run = reserve_agent_run(&record)?;
     let (session, run) = enqueue_chat_run(
         &scope,
         &address,
@@ -123,8 +148,9 @@ pub(crate) async fn sandbox_task_handler(
             backend_type: req.backend_type,
             model: req.model,
             context_json: req.context_json,
-            timeout_ms: req.timeout_ms,
+            timeout_ms: grant.timeout_ms,
             max_turns: Some(req.max_turns),
+            agent_run,
         },
     );
     Ok::<_, (StatusCode, Json<ApiError>)>((
@@ -139,9 +165,13 @@ pub(crate) async fn instance_task_handler(
 ) -> impl IntoResponse {
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
-    let record = resolve_instance(&address)?;
+    reject_session_name(&req)?;
+    let grant = check_model(&req.model, req.timeout_ms).map_err(model_rejection_error)?;
+    let record = resolve_instance_slot(&address, &req.slot)?;
     let scope = live_scope_instance(&record);
     require_running(&record)?;
+    crate::disk_quota::ensure_disk_writable(&record.id).map_err(classify_sandbox_error)?;
+    let agent_run = reserve_agent_run(&record)?;
     let (session, run) = enqueue_chat_run(
         &scope,
         &address,
@@ -158,8 +188,9 @@ pub(crate) async fn instance_task_handler(
             backend_type: req.backend_type,
             model: req.model,
             context_json: req.context_json,
-            timeout_ms: req.timeout_ms,
+            timeout_ms: grant.timeout_ms,
             max_turns: Some(req.max_turns),
+            agent_run,
         },
     );
     Ok::<_, (StatusCode, Json<ApiError>)>((
@@ -17,7 +17,7 @@ pub(crate) struct AgentPayloadRequest<'a> {
 pub(crate) fn build_agent_payload(request: AgentPayloadRequest<'_>) -> Value {
     let mut payload = Map::new();
     let identifier = if request.agent_identifier.is_empty() {
-        "default"
+        crate::runtime::DEFAULT_AGENT_IDENTIFIER
     } else {
         request.agent_identifier
     };
@@ -66,6 +66,10 @@ pub(crate) fn build_agent_payload(request: AgentPayloadRequest<'_>) -> Value {
     if request.timeout_ms > 0 {
         payload.insert("timeout".into(), json!(request.timeout_ms));
     }
+    // Handlers already rejected disallowed models; this forwards the token cap.
+    if let Ok(grant) = check_model(request.model, 0) {
+        grant.apply(&mut payload);
+    }
     Value::Object(payload)
 }
 
@@ -84,7 +88,7 @@ pub(crate) async fn agent_stream_on_sidecar(
     request: AgentStreamRequest<'_>,
     mut on_event: impl FnMut(&SidecarSseEvent),
 ) -> Result<AgentStreamOutcome, (StatusCode, Json<ApiError>)> {
-    let payload = build_agent_payload(AgentPayloadRequest {
+    let mut payload = build_agent_payload(AgentPayloadRequest {
         message: request.message,
         session_id: request.session_id,
         backend_type: request.backend_type,
@@ -94,6 +98,10 @@ pub(crate) async fn agent_stream_on_sidecar(
         max_turns: request.max_turns,
         agent_identifier: &record.agent_identifier,
     });
+    if let Some(payload) = payload.as_object_mut() {
+        crate::prompt_templates::apply_prompt_template(&record.id, payload)
+            .map_err(classify_sandbox_error)?;
+    }
     let client = crate::util::http_client_no_timeout().map_err(|err| {
         api_error(
             StatusCode::BAD_GATEWAY,
@@ -117,11 +125,7 @@ pub(crate) async fn agent_stream_on_sidecar(
             )
         })?;
 
-        if let Ok(rid) = CURRENT_REQUEST_ID.try_with(|id| id.clone())
-            && let Ok(value) = reqwest::header::HeaderValue::from_str(&rid)
-        {
-            headers.insert("x-request-id", value);
-        }
+        correlation_headers(&mut headers);
 
         let response = client
             .post(url)
@@ -59,6 +59,27 @@ pub(crate) fn circuit_breaker_api_error(err: SandboxError) -> (StatusCode, Json<
     }
 }
 
+/// Map a model policy rejection to a 400 carrying its `MODEL_*` code.
+pub(crate) fn model_rejection_error(err: ModelRejection) -> (StatusCode, Json<ApiError>) {
+    api_error_with_details(StatusCode::BAD_REQUEST, err.message, Some(err.code), None)
+}
+
+/// Reserve an agent run on `record`, mapping a full queue to
+/// `503 SANDBOX_BUSY` (see [`crate::agent_runs`]).
+pub(crate) fn reserve_agent_run(
+    record: &SandboxRecord,
+) -> std::result::Result<crate::agent_runs::AgentRunSlot, (StatusCode, Json<ApiError>)> {
+    crate::agent_runs::reserve_agent_run(&record.id).map_err(|err| match err {
+        SandboxError::Unavailable(msg) => api_error_with_details(
+            StatusCode::SERVICE_UNAVAILABLE,
+            msg,
+            Some("SANDBOX_BUSY"),
+            None,
+        ),
+        other => classify_sandbox_error(other),
+    })
+}
+
 /// Enforce the per-session fanout limiter for high-cost endpoints (port
 /// proxy, chat run/stream). NAT'd users would otherwise share an IP-tier
 /// bucket — this caps a single authenticated session's expensive
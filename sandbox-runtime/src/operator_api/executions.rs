@@ -0,0 +1,77 @@
+//! Background execution route group: start a command and poll its result.
+
+use super::*;
+use crate::executions::ExecutionRecord;
+
+fn start(
+    record: &SandboxRecord,
+    address: &str,
+    req: &ExecApiRequest,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<ApiError>)> {
+    req.validate()
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    let execution =
+        crate::executions::start_execution(record, address, req).map_err(classify_sandbox_error)?;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "execution_id": execution.id,
+            "sandbox_id": execution.sandbox_id,
+            "status": execution.status,
+            "timeout_ms": execution.timeout_ms,
+        })),
+    ))
+}
+
+/// POST /api/sandboxes/{sandbox_id}/executions — start a command in the
+/// background and return its execution ID.
+pub(crate) async fn sandbox_execution_start_handler(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+    Json(req): Json<ExecApiRequest>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    start(&record, &address, &req)
+}
+
+/// POST /api/sandbox/executions — instance variant (`slot` in the body).
+pub(crate) async fn instance_execution_start_handler(
+    SessionAuth(address): SessionAuth,
+    Json(req): Json<ExecApiRequest>,
+) -> impl IntoResponse {
+    let record = resolve_instance_slot(&address, &req.slot)?;
+    start(&record, &address, &req)
+}
+
+/// GET /api/sandboxes/{sandbox_id}/executions/{execution_id} — status and,
+/// once finished, exit code and output.
+pub(crate) async fn sandbox_execution_get_handler(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, execution_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let execution = crate::executions::get_execution(&record.id, &execution_id)
+        .map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(execution)))
+}
+
+/// GET /api/sandbox/executions/{execution_id} — instance variant. The
+/// execution may belong to any slot the caller owns.
+pub(crate) async fn instance_execution_get_handler(
+    SessionAuth(address): SessionAuth,
+    Path(execution_id): Path<String>,
+) -> impl IntoResponse {
+    let not_found = || {
+        api_error(
+            StatusCode::NOT_FOUND,
+            format!("Execution '{execution_id}' not found"),
+        )
+    };
+    let execution: ExecutionRecord = crate::executions::executions()
+        .and_then(|store| store.get(&execution_id))
+        .map_err(classify_sandbox_error)?
+        .ok_or_else(not_found)?;
+    // Hide executions of sandboxes the caller does not own.
+    resolve_sandbox(&execution.sandbox_id, &address).map_err(|_| not_found())?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(execution)))
+}
@@ -0,0 +1,143 @@
+//! File browser route group: workspace directory listings proxied from the
+//! sidecar's `GET /files/list`.
+
+use super::*;
+
+/// Most entries returned for one directory.
+const MAX_FILE_LIST_ENTRIES: usize = 1000;
+
+/// Read-rate-limited file browser routes.
+pub(crate) fn file_routes() -> Router {
+    Router::new()
+        .route(
+            "/api/sandboxes/{sandbox_id}/files/list",
+            get(sandbox_file_list_handler),
+        )
+        .route("/api/sandbox/files/list", get(instance_file_list_handler))
+        .layer(middleware::from_fn(rate_limit::read_rate_limit))
+}
+
+/// Percent-encode a query value, keeping `/` readable.
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Turn the sidecar's listing of `dir` into the API response. Entry names
+/// that could address anything outside `dir` are dropped, and each entry's
+/// `path` is derived here rather than trusted from the sidecar.
+pub(crate) fn parse_file_listing(
+    dir: &str,
+    parsed: &Value,
+) -> Result<FileListApiResponse, (StatusCode, Json<ApiError>)> {
+    let raw = parsed
+        .get("entries")
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::BAD_GATEWAY,
+                "Invalid sidecar file listing response",
+            )
+        })?;
+    let mut entries: Vec<FileEntry> = raw
+        .iter()
+        .filter_map(|v| serde_json::from_value::<FileEntry>(v.clone()).ok())
+        .filter(|e| !matches!(e.name.as_str(), "" | "." | "..") && !e.name.contains(['/', '\0']))
+        .map(|mut e| {
+            e.path = if dir.is_empty() {
+                e.name.clone()
+            } else {
+                format!("{dir}/{}", e.name)
+            };
+            e
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        (a.kind != FileEntryKind::Directory, &a.name)
+            .cmp(&(b.kind != FileEntryKind::Directory, &b.name))
+    });
+    let truncated = parsed
+        .get("truncated")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+        || entries.len() > MAX_FILE_LIST_ENTRIES;
+    entries.truncate(MAX_FILE_LIST_ENTRIES);
+    Ok(FileListApiResponse {
+        success: true,
+        path: dir.to_string(),
+        entries,
+        truncated,
+    })
+}
+
+/// Map a failed sidecar listing onto the status the caller should see: the
+/// sidecar's own path errors pass through, a sidecar without the endpoint is
+/// reported as unsupported, anything else stays a gateway error.
+fn file_list_error(err: (StatusCode, Json<ApiError>)) -> (StatusCode, Json<ApiError>) {
+    let message = err.1.0.error.as_str();
+    let status = match terminal_api_error_status_from_response(&err) {
+        Some(status) => status,
+        None => return err,
+    };
+    let passthrough = [
+        ("INVALID_PATH", StatusCode::BAD_REQUEST),
+        ("NOT_A_DIRECTORY", StatusCode::BAD_REQUEST),
+        ("PATH_OUTSIDE_WORKSPACE", StatusCode::FORBIDDEN),
+        ("PATH_NOT_FOUND", StatusCode::NOT_FOUND),
+    ];
+    if let Some((code, status)) = passthrough.iter().find(|(code, _)| message.contains(code)) {
+        return api_error_with_details(*status, message.to_string(), Some(*code), None);
+    }
+    if matches!(status, 404 | 405 | 501) {
+        return api_error(
+            StatusCode::NOT_IMPLEMENTED,
+            "File listing is not supported by this sandbox image",
+        );
+    }
+    err
+}
+
+async fn list_files(
+    record: &SandboxRecord,
+    query: &FileListQuery,
+) -> Result<FileListApiResponse, (StatusCode, Json<ApiError>)> {
+    let dir = query
+        .normalized_path()
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    let path = format!(
+        "/files/list?path={}&limit={MAX_FILE_LIST_ENTRIES}",
+        encode_query_value(&dir)
+    );
+    let parsed = sidecar_get_call(record, &path, SIDECAR_DEFAULT_TIMEOUT, "file list")
+        .await
+        .map_err(file_list_error)?;
+    parse_file_listing(&dir, &parsed)
+}
+
+/// GET /api/sandboxes/{sandbox_id}/files/list?path=
+pub(crate) async fn sandbox_file_list_handler(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<FileListQuery>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let resp = list_files(&record, &query).await?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
+}
+
+/// GET /api/sandbox/files/list?path=
+pub(crate) async fn instance_file_list_handler(
+    SessionAuth(address): SessionAuth,
+    axum::extract::Query(query): axum::extract::Query<FileListQuery>,
+) -> impl IntoResponse {
+    let record = resolve_instance(&address)?;
+    let resp = list_files(&record, &query).await?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
+}
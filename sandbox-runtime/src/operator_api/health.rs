@@ -1,6 +1,9 @@
 //! Extracted from operator_api.rs — health route group.
 
 use super::*;
+use axum::response::Response;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use provision_progress::ProvisionStatus;
 
 // ---------------------------------------------------------------------------
 // Provision progress endpoints
@@ -17,6 +20,70 @@ pub(crate) async fn get_provision(Path(call_id): Path<u64>) -> impl IntoResponse
     }
 }
 
+fn provision_event(status: &ProvisionStatus) -> Result<Event, std::convert::Infallible> {
+    let event_type = if status.phase.is_terminal() {
+        "done"
+    } else {
+        "progress"
+    };
+    Ok(Event::default()
+        .event(event_type)
+        .data(json!(status).to_string()))
+}
+
+/// Ends right after the first terminal status instead of waiting for the
+/// next (unrelated) broadcast to notice.
+struct UntilTerminal<S> {
+    inner: S,
+    done: bool,
+}
+
+impl<S: tokio_stream::Stream<Item = ProvisionStatus> + Unpin> tokio_stream::Stream
+    for UntilTerminal<S>
+{
+    type Item = ProvisionStatus;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<ProvisionStatus>> {
+        if self.done {
+            return std::task::Poll::Ready(None);
+        }
+        let next = std::pin::Pin::new(&mut self.inner).poll_next(cx);
+        if let std::task::Poll::Ready(Some(status)) = &next {
+            self.done = status.phase.is_terminal();
+        }
+        next
+    }
+}
+
+/// SSE of a provision: a snapshot, then a `progress` event per phase or
+/// image-pull change, ending with a `done` event once it is ready or failed.
+pub(crate) async fn provision_events(Path(call_id): Path<u64>) -> Response {
+    // Subscribe before reading so no change between the two is lost.
+    let rx = provision_progress::subscribe_provision_events();
+    let status = match provision_progress::get_provision(call_id) {
+        Ok(Some(status)) => status,
+        Ok(None) => return api_error(StatusCode::NOT_FOUND, "Provision not found").into_response(),
+        Err(e) => return classify_sandbox_error(e).into_response(),
+    };
+    let initial = tokio_stream::iter([provision_event(&status)]);
+    if status.phase.is_terminal() {
+        return Sse::new(initial).into_response();
+    }
+    let updates = UntilTerminal {
+        inner: tokio_stream::wrappers::BroadcastStream::new(rx)
+            .filter_map(|status| status.ok())
+            .filter(move |status| status.call_id == call_id),
+        done: false,
+    }
+    .map(|status| provision_event(&status));
+    Sse::new(initial.chain(updates))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
 pub(crate) async fn list_provisions() -> impl IntoResponse {
     match provision_progress::list_all_provisions() {
         Ok(provisions) => (
@@ -178,6 +245,7 @@ pub(crate) async fn readyz() -> impl IntoResponse {
 pub(crate) async fn prometheus_metrics() -> impl IntoResponse {
     let mut body = metrics::metrics().render_prometheus();
     body.push_str(&metrics::http_metrics().render_prometheus());
+    body.push_str(&metrics::model_metrics().render_prometheus());
     (
         StatusCode::OK,
         [("content-type", "text/plain; version=0.0.4; charset=utf-8")],
@@ -79,9 +79,7 @@ pub(crate) async fn instance_stop_handler(SessionAuth(address): SessionAuth) ->
     handle_lifecycle_outcome(stop_result, "already stopped")?;
 
     // Sync updated state back to instance store.
-    if let Ok(Some(updated)) = sandboxes().and_then(|s| s.get(&id)) {
-        let _ = runtime::instance_store().and_then(|s| s.insert("instance".to_string(), updated));
-    }
+    sync_instance_record(&id);
 
     Ok::<_, (StatusCode, Json<ApiError>)>((
         StatusCode::OK,
@@ -106,9 +104,7 @@ pub(crate) async fn instance_resume_handler(
     circuit_breaker::mark_healthy(&id);
 
     // Sync updated record (port mappings may have changed) back to instance store.
-    if let Ok(Some(updated)) = sandboxes().and_then(|s| s.get(&id)) {
-        let _ = runtime::instance_store().and_then(|s| s.insert("instance".to_string(), updated));
-    }
+    sync_instance_record(&id);
 
     Ok::<_, (StatusCode, Json<ApiError>)>((
         StatusCode::OK,
@@ -120,6 +116,112 @@ pub(crate) async fn instance_resume_handler(
     ))
 }
 
+// ── Restart ──────────────────────────────────────────────────────────────
+
+/// Timeout for restart (stop + resume + health wait). Kept under the
+/// router-wide 120s request timeout so callers get a 504, not a 408.
+pub(crate) const RESTART_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(110);
+
+/// Restart `record`'s sandbox under its lifecycle lock. The handlers resolved
+/// `record` before taking the lock; [`runtime::restart_sidecar`] re-reads it.
+pub(crate) async fn run_restart(
+    record: &SandboxRecord,
+) -> Result<SandboxRecord, (StatusCode, Json<ApiError>)> {
+    let _lock = runtime::acquire_lifecycle_lock(&record.id).await;
+    let restarted = tokio::time::timeout(RESTART_TIMEOUT, runtime::restart_sidecar(record))
+        .await
+        .map_err(|_| api_error(StatusCode::GATEWAY_TIMEOUT, "Restart operation timed out"))?
+        .map_err(|e| match e {
+            crate::SandboxError::Unavailable(msg) => {
+                api_error(StatusCode::SERVICE_UNAVAILABLE, msg)
+            }
+            e => classify_sandbox_error(e),
+        })?;
+    circuit_breaker::mark_healthy(&record.id);
+    Ok(restarted)
+}
+
+pub(crate) async fn sandbox_restart_handler(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let restarted = run_restart(&record).await?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((
+        StatusCode::OK,
+        Json(LifecycleApiResponse {
+            success: true,
+            sandbox_id: restarted.id,
+            state: "running".into(),
+        }),
+    ))
+}
+
+pub(crate) async fn instance_restart_handler(
+    SessionAuth(address): SessionAuth,
+) -> impl IntoResponse {
+    let record = resolve_instance(&address)?;
+    let restarted = run_restart(&record).await?;
+    sync_instance_record(&restarted.id);
+    Ok::<_, (StatusCode, Json<ApiError>)>((
+        StatusCode::OK,
+        Json(LifecycleApiResponse {
+            success: true,
+            sandbox_id: restarted.id,
+            state: "running".into(),
+        }),
+    ))
+}
+
+// ── Token rotation ───────────────────────────────────────────────────────
+
+/// Rotate the sidecar token (recreate + health wait). Same budget as restart.
+pub(crate) async fn run_token_rotate(
+    record: &SandboxRecord,
+    address: &str,
+) -> Result<SandboxRecord, (StatusCode, Json<ApiError>)> {
+    let _lock = runtime::acquire_lifecycle_lock(&record.id).await;
+    let rotated = tokio::time::timeout(
+        RESTART_TIMEOUT,
+        runtime::rotate_sidecar_token(&record.id, address, None),
+    )
+    .await
+    .map_err(|_| api_error(StatusCode::GATEWAY_TIMEOUT, "Token rotation timed out"))?
+    .map_err(classify_sandbox_error)?;
+    circuit_breaker::mark_healthy(&record.id);
+    Ok(rotated)
+}
+
+fn token_rotate_response(rotated: SandboxRecord) -> (StatusCode, Json<TokenRotateApiResponse>) {
+    (
+        StatusCode::OK,
+        Json(TokenRotateApiResponse {
+            success: true,
+            sandbox_id: rotated.id,
+            sidecar_url: rotated.sidecar_url,
+            token: rotated.token,
+        }),
+    )
+}
+
+pub(crate) async fn sandbox_token_rotate_handler(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let rotated = run_token_rotate(&record, &address).await?;
+    Ok::<_, (StatusCode, Json<ApiError>)>(token_rotate_response(rotated))
+}
+
+pub(crate) async fn instance_token_rotate_handler(
+    SessionAuth(address): SessionAuth,
+) -> impl IntoResponse {
+    let record = resolve_instance(&address)?;
+    let rotated = run_token_rotate(&record, &address).await?;
+    sync_instance_record(&rotated.id);
+    Ok::<_, (StatusCode, Json<ApiError>)>(token_rotate_response(rotated))
+}
+
 // ── Snapshot ─────────────────────────────────────────────────────────────
 
 pub(crate) async fn run_snapshot(
@@ -138,6 +240,12 @@ pub(crate) async fn run_snapshot(
         req.include_state,
     )
     .map_err(|e| api_error(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let input = CommandInput {
+        kind: CommandKind::Snapshot,
+        command: &command,
+        ..Default::default()
+    };
+    enforce_command_policy(&record.id, &record.owner, &input).map_err(classify_sandbox_error)?;
     let payload = json!({ "command": format!("sh -c {}", crate::util::shell_escape(&command)) });
     let parsed = sidecar_call(
         record,
@@ -148,6 +256,18 @@ pub(crate) async fn run_snapshot(
         true,
     )
     .await?;
+    let api = crate::sidecar_compat::SidecarApi::for_record(record);
+    let stdout = parse_exec_response(api, &parsed).stdout;
+    crate::usage_ledger::record_usage(
+        &record.owner,
+        crate::usage_ledger::UsageTotals::snapshot(crate::util::snapshot_bytes(&stdout)),
+    );
+    runtime::record_lifecycle_event(
+        &record.id,
+        runtime::LifecycleEventKind::Snapshotted,
+        &record.owner,
+        Some(req.destination.clone()),
+    );
     Ok(SnapshotApiResponse {
         success: true,
         result: parsed,
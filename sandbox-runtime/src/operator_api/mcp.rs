@@ -0,0 +1,262 @@
+//! MCP endpoint: `POST /api/mcp` speaks JSON-RPC over MCP's Streamable HTTP
+//! transport and maps tool calls onto the caller's sandboxes.
+
+use super::*;
+use crate::mcp::{
+    self, INVALID_PARAMS, INVALID_REQUEST, JsonRpcRequest, MAX_READ_FILE_BYTES, METHOD_NOT_FOUND,
+    McpTool, PARSE_ERROR, optional_arg, required_arg, tool_result,
+};
+use crate::task_queue::{self, TaskRecord, TaskStatus};
+use axum::body::Bytes;
+use axum::response::Response;
+use axum::response::sse::{Event, KeepAlive, Sse};
+
+type ToolOutcome = Result<Value, (i64, String)>;
+
+fn rpc_reply(message: Value) -> Response {
+    (StatusCode::OK, Json(message)).into_response()
+}
+
+/// IDs of the sandboxes `address` owns, for the tools' `sandbox_id` enum.
+fn owned_sandbox_ids(address: &str) -> Result<Vec<String>, (i64, String)> {
+    let records = sandboxes()
+        .and_then(|s| s.values())
+        .map_err(|e| (mcp::INTERNAL_ERROR, e.to_string()))?;
+    let mut ids: Vec<String> = records
+        .into_iter()
+        .filter(|r| !r.owner.is_empty() && r.owner.eq_ignore_ascii_case(address))
+        .map(|r| r.id)
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Tool-level failure: reported to the model as an `isError` result.
+fn failed(err: (StatusCode, Json<ApiError>)) -> Value {
+    tool_result(err.1.0.error, true)
+}
+
+async fn exec(record: &SandboxRecord, req: ExecApiRequest) -> Result<ExecApiResponse, Value> {
+    req.validate().map_err(|e| tool_result(e, true))?;
+    exec_on_sidecar(record, &req).await.map_err(failed)
+}
+
+async fn run_command(record: &SandboxRecord, args: &Map<String, Value>) -> ToolOutcome {
+    let command = required_arg(args, "command").map_err(|e| (INVALID_PARAMS, e))?;
+    let req = ExecApiRequest {
+        command: command.to_string(),
+        cwd: optional_arg(args, "cwd").to_string(),
+        timeout_ms: args.get("timeout_ms").and_then(Value::as_u64).unwrap_or(0),
+        ..Default::default()
+    };
+    let resp = match exec(record, req).await {
+        Ok(resp) => resp.capped(&record.id),
+        Err(result) => return Ok(result),
+    };
+    let mut text = resp.stdout;
+    if !resp.stderr.is_empty() {
+        text.push_str("\n[stderr]\n");
+        text.push_str(&resp.stderr);
+    }
+    if resp.truncated {
+        text.push_str("\n[output truncated]");
+    }
+    text.push_str(&format!("\n[exit code {}]", resp.exit_code));
+    Ok(tool_result(text, resp.exit_code != 0))
+}
+
+async fn read_file(record: &SandboxRecord, args: &Map<String, Value>) -> ToolOutcome {
+    let path = required_arg(args, "path").map_err(|e| (INVALID_PARAMS, e))?;
+    let command = mcp::read_file_command(path).map_err(|e| (INVALID_PARAMS, e))?;
+    let req = ExecApiRequest {
+        command,
+        ..Default::default()
+    };
+    let resp = match exec(record, req).await {
+        Ok(resp) => resp,
+        Err(result) => return Ok(result),
+    };
+    if resp.exit_code != 0 {
+        return Ok(tool_result(resp.stderr, true));
+    }
+    let mut text = resp.stdout;
+    if text.len() > MAX_READ_FILE_BYTES {
+        let mut cut = MAX_READ_FILE_BYTES;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        text.truncate(cut);
+        text.push_str(&format!("\n[truncated after {MAX_READ_FILE_BYTES} bytes]"));
+    }
+    Ok(tool_result(text, false))
+}
+
+async fn write_file(record: &SandboxRecord, args: &Map<String, Value>) -> ToolOutcome {
+    let path = required_arg(args, "path").map_err(|e| (INVALID_PARAMS, e))?;
+    let content = args
+        .get("content")
+        .and_then(Value::as_str)
+        .ok_or_else(|| (INVALID_PARAMS, "`content` is required".to_string()))?;
+    let command = mcp::write_file_command(path).map_err(|e| (INVALID_PARAMS, e))?;
+    let req = ExecApiRequest {
+        command,
+        stdin: content.to_string(),
+        ..Default::default()
+    };
+    Ok(match exec(record, req).await {
+        Ok(resp) if resp.exit_code == 0 => {
+            tool_result(format!("Wrote {} bytes to {path}", content.len()), false)
+        }
+        Ok(resp) => tool_result(resp.stderr, true),
+        Err(result) => result,
+    })
+}
+
+fn task_outcome(task: &TaskRecord) -> Value {
+    match task.status {
+        TaskStatus::Completed => tool_result(task.result.clone(), false),
+        status => tool_result(
+            task.error
+                .clone()
+                .unwrap_or_else(|| format!("Task ended as {status:?}")),
+            true,
+        ),
+    }
+}
+
+fn sse_message(message: &Value) -> Result<Event, std::convert::Infallible> {
+    Ok(Event::default().event("message").data(message.to_string()))
+}
+
+/// `run_task` answers with an SSE stream: `notifications/progress` while the
+/// agent works (when the client sent a progress token), then the response.
+fn run_task(
+    record: &SandboxRecord,
+    address: &str,
+    id: Value,
+    params: &Value,
+    args: &Map<String, Value>,
+) -> Result<Response, (i64, String)> {
+    let prompt = required_arg(args, "prompt").map_err(|e| (INVALID_PARAMS, e))?;
+    let req = TaskApiRequest {
+        prompt: prompt.to_string(),
+        session_id: optional_arg(args, "session_id").to_string(),
+        max_turns: args.get("max_turns").and_then(Value::as_u64).unwrap_or(0),
+        model: optional_arg(args, "model").to_string(),
+        ..Default::default()
+    };
+    let task = match task_queue::enqueue_task(record, address, &req) {
+        Ok(task) => task,
+        Err(e) => {
+            let result = tool_result(e.to_string(), true);
+            return Ok(rpc_reply(mcp::result_response(id, result)));
+        }
+    };
+    let Some(rx) = task_queue::subscribe_task_events(&task.id) else {
+        let task = task_queue::get_task(&task.sandbox_id, &task.id).unwrap_or(task);
+        return Ok(rpc_reply(mcp::result_response(id, task_outcome(&task))));
+    };
+    let progress_token = params
+        .pointer("/_meta/progressToken")
+        .filter(|token| token.is_string() || token.is_number())
+        .cloned();
+    let messages = tokio_stream::wrappers::BroadcastStream::new(rx)
+        .filter_map(|event| event.ok())
+        .map_while({
+            let mut done = false;
+            let mut progress = 0u64;
+            move |event| {
+                if done {
+                    return None;
+                }
+                let Ok(task) = serde_json::from_value::<TaskRecord>(event.payload) else {
+                    return Some(None);
+                };
+                if event.event_type == "done" {
+                    done = true;
+                    return Some(Some(mcp::result_response(id.clone(), task_outcome(&task))));
+                }
+                let Some(token) = &progress_token else {
+                    return Some(None);
+                };
+                progress += 1;
+                let message = match &task.progress.current_tool {
+                    Some(tool) => format!(
+                        "{} turns completed, using {tool}",
+                        task.progress.turns_completed
+                    ),
+                    None => format!("{} turns completed", task.progress.turns_completed),
+                };
+                Some(Some(mcp::progress_notification(token, progress, &message)))
+            }
+        })
+        .filter_map(|message| message.as_ref().map(sse_message));
+    Ok(Sse::new(messages)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+async fn call_tool(address: &str, id: Value, params: Value) -> Result<Response, (i64, String)> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let tool = McpTool::from_name(name)
+        .ok_or_else(|| (INVALID_PARAMS, format!("Unknown tool '{name}'")))?;
+    let args = params
+        .get("arguments")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    let sandbox_id = required_arg(&args, "sandbox_id").map_err(|e| (INVALID_PARAMS, e))?;
+    let record = resolve_sandbox(sandbox_id, address).map_err(|e| (INVALID_PARAMS, e.1.0.error))?;
+    let result = match tool {
+        McpTool::RunCommand => run_command(&record, &args).await?,
+        McpTool::ReadFile => read_file(&record, &args).await?,
+        McpTool::WriteFile => write_file(&record, &args).await?,
+        McpTool::RunTask => return run_task(&record, address, id, &params, &args),
+    };
+    Ok(rpc_reply(mcp::result_response(id, result)))
+}
+
+/// POST /api/mcp — MCP over Streamable HTTP. Notifications are acknowledged
+/// with 202; `run_task` calls stream their response as SSE.
+pub(crate) async fn mcp_handler(SessionAuth(address): SessionAuth, body: Bytes) -> Response {
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            return rpc_reply(mcp::error_response(
+                Value::Null,
+                PARSE_ERROR,
+                format!("Invalid JSON: {e}"),
+            ));
+        }
+    };
+    let req = match serde_json::from_value::<JsonRpcRequest>(message) {
+        Ok(req) if req.jsonrpc == "2.0" => req,
+        _ => {
+            return rpc_reply(mcp::error_response(
+                Value::Null,
+                INVALID_REQUEST,
+                "Expected a single JSON-RPC 2.0 request",
+            ));
+        }
+    };
+    let Some(id) = req.id else {
+        return StatusCode::ACCEPTED.into_response();
+    };
+    let outcome = match req.method.as_str() {
+        "initialize" => Ok(rpc_reply(mcp::result_response(
+            id.clone(),
+            mcp::initialize_result(),
+        ))),
+        "ping" => Ok(rpc_reply(mcp::result_response(id.clone(), json!({})))),
+        "tools/list" => owned_sandbox_ids(&address).map(|ids| {
+            let tools: Vec<Value> = McpTool::ALL.iter().map(|t| t.definition(&ids)).collect();
+            rpc_reply(mcp::result_response(id.clone(), json!({ "tools": tools })))
+        }),
+        "tools/call" => call_tool(&address, id.clone(), req.params).await,
+        other => Err((METHOD_NOT_FOUND, format!("Method '{other}' not found"))),
+    };
+    outcome.unwrap_or_else(|(code, message)| rpc_reply(mcp::error_response(id, code, message)))
+}
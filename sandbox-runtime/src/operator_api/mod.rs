@@ -3,8 +3,14 @@
 //! Provides REST endpoints for:
 //! - Listing active sandboxes
 //! - Querying provision progress
+//! - Sandbox template catalog
 //! - Session auth (challenge/response + PASETO tokens)
-//! - Sandbox operations (exec, prompt, task, stop, resume, snapshot, SSH)
+//! - Sandbox operations (exec, prompt, task, stop, resume, restart, token rotation,
+//!   snapshot, SSH)
+//! - Workspace file browser listings
+//! - MCP server exposing owned sandboxes as tools (`/api/mcp`)
+//! - Webhook registrations and delivery logs
+//! - Operator drain mode for maintenance windows
 
 use axum::extract::DefaultBodyLimit;
 use axum::middleware;
@@ -35,18 +41,21 @@ use crate::chat_state::{
 };
 use crate::circuit_breaker;
 use crate::error::SandboxError;
+use crate::exec_policy::{CommandInput, CommandKind, enforce_command_policy};
 use crate::http::{
-    auth_headers, build_url, sidecar_get_json, sidecar_post_json, sidecar_post_json_without_timeout,
+    auth_headers, build_url, correlation_headers, sidecar_get_json, sidecar_post_json,
+    sidecar_post_json_without_timeout,
 };
 use crate::live_operator_sessions::sse_from_json_events;
 use crate::metrics;
+use crate::model_policy::{ModelRejection, check_model};
 use crate::provision_progress;
 use crate::rate_limit;
 use crate::runtime::{
     self, SandboxRecord, SandboxState, sandboxes, workflow_runtime_credentials_available,
 };
 use crate::secret_provisioning;
-use crate::session_auth::{self, SessionAuth};
+use crate::session_auth::{self, SessionAuth, TerminalAuth};
 
 // ---------------------------------------------------------------------------
 // Per-operation sidecar call timeouts
@@ -76,17 +85,29 @@ static CHAT_RUN_ENQUEUE_GUARD: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
 mod admin;
 mod agents;
+mod audit;
 mod auth;
 mod chat;
 mod chat_handlers;
 mod chat_stream;
 mod errors;
+mod executions;
+mod files;
 mod health;
 mod lifecycle;
+mod mcp;
 mod mw;
+mod named_secrets;
+mod op_routes;
 mod ports;
+mod presets;
+mod prompts;
+mod quotas;
+mod readiness;
 mod resolve;
 mod sandboxes;
+mod secret_rotation;
+mod secret_versions;
 mod secrets;
 mod sessions_core;
 mod sessions_handlers;
@@ -94,20 +115,36 @@ mod sidecar_calls;
 mod sidecar_core;
 mod sse;
 mod ssh;
+mod tasks;
+mod tee_routes;
+mod templates;
+mod webhooks;
 
 pub(crate) use admin::*;
 pub(crate) use agents::*;
+pub(crate) use audit::*;
 pub(crate) use auth::*;
 pub(crate) use chat::*;
 pub(crate) use chat_handlers::*;
 pub(crate) use chat_stream::*;
 pub(crate) use errors::*;
+pub(crate) use executions::*;
+pub(crate) use files::*;
 pub(crate) use health::*;
 pub(crate) use lifecycle::*;
+pub(crate) use mcp::*;
 pub(crate) use mw::*;
+pub(crate) use named_secrets::*;
+pub(crate) use op_routes::*;
 pub(crate) use ports::*;
+pub(crate) use presets::*;
+pub(crate) use prompts::*;
+pub(crate) use quotas::*;
+pub(crate) use readiness::*;
 pub(crate) use resolve::*;
 pub(crate) use sandboxes::*;
+pub(crate) use secret_rotation::*;
+pub(crate) use secret_versions::*;
 pub(crate) use secrets::*;
 pub(crate) use sessions_core::*;
 pub(crate) use sessions_handlers::*;
@@ -115,10 +152,14 @@ pub(crate) use sidecar_calls::*;
 pub(crate) use sidecar_core::*;
 pub(crate) use sse::*;
 pub(crate) use ssh::*;
+pub(crate) use tasks::*;
+pub(crate) use tee_routes::*;
+pub(crate) use templates::*;
+pub(crate) use webhooks::*;
 
 // Externally-reachable items re-exported at their original (wider) visibility:
 pub use errors::ApiError;
-pub use mw::{RequestId, build_cors_layer, extract_session_from_headers};
+pub use mw::{CorsGroup, CorsPolicy, RequestId, build_cors_layer, extract_session_from_headers};
 
 // Router builder
 // ---------------------------------------------------------------------------
@@ -145,18 +186,21 @@ pub fn operator_api_router_with_tee(
 }
 
 /// Build the operator API router and merge additional routes before applying
-/// shared middleware such as CORS, request IDs, rate limits, and security
-/// headers. This is important for blueprint-specific routes like
-/// `/api/workflows/{workflow_id}` so browser preflight requests reach them too.
+/// shared middleware such as CORS (the [`CorsGroup::Api`] policy), request
+/// IDs, rate limits, and security headers. This is important for
+/// blueprint-specific routes like `/api/workflows/{workflow_id}` so browser
+/// preflight requests reach them too.
 pub fn operator_api_router_with_tee_and_routes(
     tee: Option<std::sync::Arc<dyn crate::tee::TeeBackend>>,
     extra_routes: Router,
 ) -> Router {
-    let cors = build_cors_layer();
-
     // Read endpoints: 120 req/min per IP
     let read_routes = Router::new()
         .route("/api/sandboxes", get(list_sandboxes))
+        .route(
+            "/api/webhooks/{webhook_id}/deliveries",
+            get(list_webhook_deliveries_handler),
+        )
         .route(
             "/api/sandboxes/{sandbox_id}/ports",
             get(sandbox_ports_handler),
@@ -166,6 +210,51 @@ pub fn operator_api_router_with_tee_and_routes(
             get(sandbox_agents_handler),
         )
         .route("/api/sandbox/ports", get(instance_ports_handler))
+        .route("/api/sandbox/slots", get(list_instance_slots_handler))
+        .route(
+            "/api/sandboxes/{sandbox_id}/tombstone",
+            get(sandbox_tombstone_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/exec/overflow/{overflow_id}",
+            get(sandbox_exec_overflow_handler),
+        )
+        .route(
+            "/api/sandbox/exec/overflow/{overflow_id}",
+            get(instance_exec_overflow_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/results/{artifact_id}",
+            get(sandbox_result_artifact_handler),
+        )
+        .route(
+            "/api/sandbox/results/{artifact_id}",
+            get(instance_result_artifact_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/executions/{execution_id}",
+            get(sandbox_execution_get_handler),
+        )
+        .route(
+            "/api/sandbox/executions/{execution_id}",
+            get(instance_execution_get_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/tasks/{task_id}",
+            get(sandbox_task_get_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/tasks/{task_id}/stream",
+            get(sandbox_task_stream_handler),
+        )
+        .route(
+            "/api/sandbox/tasks/{task_id}",
+            get(instance_task_get_handler),
+        )
+        .route(
+            "/api/sandbox/tasks/{task_id}/stream",
+            get(instance_task_stream_handler),
+        )
         .route("/api/sandbox/agents", get(instance_agents_handler))
         .route(
             "/api/sandboxes/{sandbox_id}/live/terminal/sessions",
@@ -212,8 +301,24 @@ pub fn operator_api_router_with_tee_and_routes(
     // Write endpoints: 30 req/min per IP
     let write_routes = Router::new()
         .route(
-            "/api/sandboxes/{sandbox_id}/secrets",
-            get(get_secrets).post(inject_secrets).delete(wipe_secrets),
+            "/api/sandboxes/{sandbox_id}/prompts",
+            get(list_prompt_templates),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/prompts/{name}",
+            get(get_prompt_template)
+                .put(put_prompt_template)
+                .delete(delete_prompt_template),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/exec-presets",
+            get(list_exec_presets),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/exec-presets/{name}",
+            get(get_exec_preset)
+                .put(put_exec_preset)
+                .delete(delete_exec_preset),
         )
         // Sidecar image upgrade (operator-gated; see handlers above).
         .route(
@@ -224,10 +329,52 @@ pub fn operator_api_router_with_tee_and_routes(
             "/api/operator/sidecar-image/upgrade-stale",
             post(upgrade_stale_sidecar_images_handler),
         )
+        .route("/api/operator/audit/jobs", get(job_replay_export_handler))
+        .route(
+            "/api/operator/drain",
+            get(drain_status_handler)
+                .post(start_drain_handler)
+                .delete(end_drain_handler),
+        )
+        .route(
+            "/api/operator/auto-provision",
+            get(auto_provision_failures_handler),
+        )
+        .route(
+            "/api/operator/auth/lockouts",
+            get(list_auth_lockouts_handler),
+        )
+        .route(
+            "/api/operator/auth/lockouts/{subject}",
+            axum::routing::delete(clear_auth_lockout_handler),
+        )
+        .route("/api/operator/capacity", get(capacity_handler))
+        .route("/api/operator/images", get(image_cache_handler))
+        .route("/api/operator/quotas", get(list_quotas_handler))
+        .route(
+            "/api/operator/quotas/{owner}",
+            axum::routing::put(set_quota_handler).delete(delete_quota_handler),
+        )
+        .route("/api/usage", get(usage_handler))
+        .route("/api/usage/history", get(usage_history_handler))
         .route(
             "/api/sandboxes/{sandbox_id}/upgrade-image",
             post(upgrade_sandbox_image_handler),
         )
+        // Template registry (operator-gated; listing is in infra_routes).
+        .route("/api/templates", post(register_template_handler))
+        .route(
+            "/api/webhooks",
+            get(list_webhooks_handler).post(create_webhook_handler),
+        )
+        .route(
+            "/api/webhooks/{webhook_id}",
+            axum::routing::delete(delete_webhook_handler),
+        )
+        .route(
+            "/api/templates/{name}",
+            axum::routing::delete(delete_template_handler),
+        )
         .route(
             "/api/sandboxes/{sandbox_id}/live/terminal/sessions",
             post(sandbox_terminal_session_create_handler),
@@ -248,11 +395,19 @@ pub fn operator_api_router_with_tee_and_routes(
             "/api/sandboxes/{sandbox_id}/live/chat/sessions/{session_id}/runs/{run_id}/cancel",
             post(sandbox_chat_run_cancel_handler),
         )
+        .route("/api/sandbox/prompts", get(instance_list_prompt_templates))
         .route(
-            "/api/sandbox/secrets",
-            get(instance_get_secrets)
-                .post(instance_inject_secrets)
-                .delete(instance_wipe_secrets),
+            "/api/sandbox/prompts/{name}",
+            get(instance_get_prompt_template)
+                .put(instance_put_prompt_template)
+                .delete(instance_delete_prompt_template),
+        )
+        .route("/api/sandbox/exec-presets", get(instance_list_exec_presets))
+        .route(
+            "/api/sandbox/exec-presets/{name}",
+            get(instance_get_exec_preset)
+                .put(instance_put_exec_preset)
+                .delete(instance_delete_exec_preset),
         )
         .route(
             "/api/sandbox/live/terminal/sessions",
@@ -276,93 +431,82 @@ pub fn operator_api_router_with_tee_and_routes(
         )
         .layer(middleware::from_fn(rate_limit::write_rate_limit));
 
-    let terminal_interactive_routes = Router::new()
+    // Secret endpoints: same write limit, but their own CORS group so
+    // operators can keep them stricter than the rest of the API.
+    let mut secret_routes = Router::new()
         .route(
-            "/api/sandboxes/{sandbox_id}/live/terminal/sessions/{session_id}",
-            patch(sandbox_terminal_session_resize_handler),
-        )
-        .route(
-            "/api/sandboxes/{sandbox_id}/live/terminal/sessions/{session_id}/input",
-            post(sandbox_terminal_session_input_handler),
-        )
-        .route(
-            "/api/sandbox/live/terminal/sessions/{session_id}",
-            patch(instance_terminal_session_resize_handler),
+            "/api/sandboxes/{sandbox_id}/secrets",
+            get(get_secrets).post(inject_secrets).delete(wipe_secrets),
         )
         .route(
-            "/api/sandbox/live/terminal/sessions/{session_id}/input",
-            post(instance_terminal_session_input_handler),
+            "/api/sandboxes/{sandbox_id}/secrets/rotation",
+            get(get_secret_rotation)
+                .put(put_secret_rotation)
+                .delete(delete_secret_rotation),
         )
-        .layer(middleware::from_fn(
-            rate_limit::terminal_interactive_rate_limit,
-        ));
-
-    // Sandbox-scoped operation endpoints (authenticated, write-rate-limited)
-    let sandbox_op_routes = Router::new()
         .route(
-            "/api/sandboxes/{sandbox_id}/exec",
-            post(sandbox_exec_handler),
+            "/api/sandboxes/{sandbox_id}/secrets/rotation/run",
+            post(run_secret_rotation),
         )
         .route(
-            "/api/sandboxes/{sandbox_id}/prompt",
-            post(sandbox_prompt_handler),
+            "/api/sandboxes/{sandbox_id}/secrets/versions",
+            get(get_secret_versions),
         )
         .route(
-            "/api/sandboxes/{sandbox_id}/task",
-            post(sandbox_task_handler),
+            "/api/sandboxes/{sandbox_id}/secrets/rollback",
+            post(rollback_secrets),
         )
+        .route("/api/secrets", get(list_named_secrets_handler))
         .route(
-            "/api/sandboxes/{sandbox_id}/stop",
-            post(sandbox_stop_handler),
+            "/api/secrets/{name}",
+            axum::routing::put(set_named_secret_handler).delete(delete_named_secret_handler),
         )
         .route(
-            "/api/sandboxes/{sandbox_id}/resume",
-            post(sandbox_resume_handler),
-        )
-        .route(
-            "/api/sandboxes/{sandbox_id}/snapshot",
-            post(sandbox_snapshot_handler),
+            "/api/sandbox/secrets",
+            get(instance_get_secrets)
+                .post(instance_inject_secrets)
+                .delete(instance_wipe_secrets),
         )
         .route(
-            "/api/sandboxes/{sandbox_id}/ssh",
-            post(sandbox_ssh_provision_handler).delete(sandbox_ssh_revoke_handler),
+            "/api/sandbox/secrets/rotation",
+            get(instance_get_secret_rotation)
+                .put(instance_put_secret_rotation)
+                .delete(instance_delete_secret_rotation),
         )
         .route(
-            "/api/sandboxes/{sandbox_id}/ssh/user",
-            get(sandbox_ssh_user_handler),
+            "/api/sandbox/secrets/rotation/run",
+            post(instance_run_secret_rotation),
         )
         .route(
-            "/api/sandboxes/{sandbox_id}/port/{port}/{*rest}",
-            any(sandbox_port_proxy_handler),
+            "/api/sandbox/secrets/versions",
+            get(instance_get_secret_versions),
         )
         .route(
-            "/api/sandboxes/{sandbox_id}/port/{port}",
-            any(sandbox_port_proxy_root_handler),
+            "/api/sandbox/secrets/rollback",
+            post(instance_rollback_secrets),
         )
         .layer(middleware::from_fn(rate_limit::write_rate_limit));
 
-    // Instance-scoped operation endpoints (singleton sandbox, authenticated)
-    let instance_op_routes = Router::new()
-        .route("/api/sandbox/exec", post(instance_exec_handler))
-        .route("/api/sandbox/prompt", post(instance_prompt_handler))
-        .route("/api/sandbox/task", post(instance_task_handler))
-        .route("/api/sandbox/stop", post(instance_stop_handler))
-        .route("/api/sandbox/resume", post(instance_resume_handler))
-        .route("/api/sandbox/snapshot", post(instance_snapshot_handler))
+    let terminal_interactive_routes = Router::new()
+        .route(
+            "/api/sandboxes/{sandbox_id}/live/terminal/sessions/{session_id}",
+            patch(sandbox_terminal_session_resize_handler),
+        )
         .route(
-            "/api/sandbox/ssh",
-            post(instance_ssh_provision_handler).delete(instance_ssh_revoke_handler),
+            "/api/sandboxes/{sandbox_id}/live/terminal/sessions/{session_id}/input",
+            post(sandbox_terminal_session_input_handler),
         )
-        .route("/api/sandbox/ssh/user", get(instance_ssh_user_handler))
         .route(
-            "/api/sandbox/port/{port}/{*rest}",
-            any(instance_port_proxy_handler),
+            "/api/sandbox/live/terminal/sessions/{session_id}",
+            patch(instance_terminal_session_resize_handler),
         )
         .route(
-            "/api/sandbox/port/{port}",
-            any(instance_port_proxy_root_handler),
+            "/api/sandbox/live/terminal/sessions/{session_id}/input",
+            post(instance_terminal_session_input_handler),
         )
-        .layer(middleware::from_fn(rate_limit::write_rate_limit));
+        .layer(middleware::from_fn(
+            rate_limit::terminal_interactive_rate_limit,
+        ));
 
     // Auth endpoints: 10 req/min per IP (stricter to prevent brute-force)
     let auth_routes = Router::new()
@@ -374,70 +518,41 @@ pub fn operator_api_router_with_tee_and_routes(
         .layer(middleware::from_fn(rate_limit::auth_rate_limit));
 
     // Health, metrics & provision progress: rate-limited but unauthenticated
-    // (liveness probes + pre-auth provision tracking need these)
+    // (liveness probes, pre-auth provision tracking and the template catalog
+    // need these)
     let infra_routes = Router::new()
         .route("/health", get(health))
         .route("/readyz", get(readyz))
+        .route("/health/ready", get(health_ready))
         .route("/api/capabilities", get(capabilities_handler))
+        .route("/api/templates", get(list_templates_handler))
         .route("/metrics", get(prometheus_metrics))
         .route("/api/provisions", get(list_provisions))
         .route("/api/provisions/{call_id}", get(get_provision))
+        .route("/api/provisions/{call_id}/events", get(provision_events))
         .layer(middleware::from_fn(rate_limit::read_rate_limit));
 
-    let mut router = Router::new()
-        .merge(infra_routes)
+    // Each group gets its own CORS policy (see `CorsGroup`), applied outside
+    // its rate limit so preflights are answered without spending quota.
+    let api_routes = Router::new()
         .merge(read_routes)
+        .merge(file_routes())
         .merge(write_routes)
         .merge(terminal_interactive_routes)
-        .merge(sandbox_op_routes)
-        .merge(instance_op_routes)
-        .merge(auth_routes);
+        .merge(sandbox_op_routes())
+        .merge(instance_op_routes())
+        .merge(extra_routes);
 
     // TEE sealed secrets endpoints (only when backend is configured)
     if let Some(backend) = tee {
-        // The read-only attestation route is always available — it returns the
-        // honest server-evaluated verdict and grants no trust by itself.
-        let mut tee_routes = Router::new().route(
-            "/api/sandboxes/{sandbox_id}/tee/attestation",
-            get(crate::tee::sealed_secrets_api::get_tee_attestation)
-                .post(crate::tee::sealed_secrets_api::post_tee_attestation),
-        );
-
-        // The trust-granting routes (public-key release, sealed-secret injection)
-        // are mounted only when the server can fail closed: an allowlist is pinned
-        // OR the operator explicitly opted into client-side-only verification.
-        // With the default config and no allowlist they are not served at all, so
-        // a misconfigured operator cannot hand back unverified material.
-        if crate::tee::sealed_secrets_api::release_routes_enabled() {
-            tee_routes = tee_routes
-                .route(
-                    "/api/sandboxes/{sandbox_id}/tee/public-key",
-                    get(crate::tee::sealed_secrets_api::get_tee_public_key),
-                )
-                .route(
-                    "/api/sandboxes/{sandbox_id}/tee/sealed-secrets",
-                    post(crate::tee::sealed_secrets_api::inject_sealed_secrets),
-                );
-        } else {
-            tracing::warn!(
-                "TEE sealed-secret/public-key release routes disabled: no \
-                 SANDBOX_TEE_EXPECTED_MEASUREMENTS allowlist is pinned. Set the allowlist, or set \
-                 SANDBOX_TEE_REQUIRE_PINNED_MEASUREMENT=false to serve them under client-side-only \
-                 verification."
-            );
-        }
-
-        let tee_routes = tee_routes
-            .layer(axum::Extension(
-                Some(backend) as Option<std::sync::Arc<dyn crate::tee::TeeBackend>>
-            ))
-            .layer(middleware::from_fn(rate_limit::write_rate_limit));
-
-        router = router.merge(tee_routes);
+        secret_routes = secret_routes.merge(tee_routes(backend));
     }
 
-    router
-        .merge(extra_routes)
+    Router::new()
+        .merge(with_cors(infra_routes, CorsGroup::Public))
+        .merge(with_cors(api_routes, CorsGroup::Api))
+        .merge(with_cors(secret_routes, CorsGroup::Secrets))
+        .merge(with_cors(auth_routes, CorsGroup::Auth))
         .layer(DefaultBodyLimit::max(1024 * 1024)) // 1 MB max request body
         .layer(middleware::from_fn(security_headers_middleware))
         .layer(middleware::from_fn(http_metrics_middleware))
@@ -447,7 +562,6 @@ pub fn operator_api_router_with_tee_and_routes(
             StatusCode::REQUEST_TIMEOUT,
             std::time::Duration::from_secs(120),
         ))
-        .layer(cors)
         // Outermost layer: assign a unique request ID before anything else runs.
         .layer(middleware::from_fn(request_id_middleware))
 }
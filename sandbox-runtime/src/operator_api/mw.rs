@@ -104,74 +104,157 @@ pub fn extract_session_from_headers(
 // CORS
 // ---------------------------------------------------------------------------
 
-/// Build CORS layer from `CORS_ALLOWED_ORIGINS` env var.
+/// Route groups that can carry their own CORS policy, so operators can keep
+/// auth and secrets strict while health and metrics stay open.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CorsGroup {
+    /// Health, readiness, metrics, capabilities, the template catalog and
+    /// provision progress.
+    Public,
+    /// Challenge and session endpoints.
+    Auth,
+    /// Secret injection, rotation, versions, named secrets and TEE sealed
+    /// secrets.
+    Secrets,
+    /// Everything else, including blueprint-specific extra routes.
+    Api,
+}
+
+impl CorsGroup {
+    /// Env var overriding `CORS_ALLOWED_ORIGINS` for this group.
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Self::Public => "CORS_PUBLIC_ALLOWED_ORIGINS",
+            Self::Auth => "CORS_AUTH_ALLOWED_ORIGINS",
+            Self::Secrets => "CORS_SECRETS_ALLOWED_ORIGINS",
+            Self::Api => "CORS_ALLOWED_ORIGINS",
+        }
+    }
+}
+
+/// A group's CORS policy, parsed from its origins setting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CorsPolicy {
+    /// `"none"`: no CORS layer at all — responses carry no CORS headers and
+    /// preflights are not answered (use when behind a proxy such as BPM that
+    /// handles CORS).
+    Disabled,
+    /// `"*"`: any origin, without credentials (development mode only).
+    Any,
+    /// Unset: the local dev UIs only, with credentials.
+    Localhost,
+    /// Comma-separated origins: strict whitelist with credentials.
+    Origins(Vec<String>),
+}
+
+/// Origins allowed when nothing is configured.
+const LOCALHOST_ORIGINS: &[&str] = &[
+    "http://localhost:1338",
+    "http://localhost:3000",
+    "http://localhost:5173",
+    "http://127.0.0.1:1338",
+    "http://127.0.0.1:3000",
+    "http://127.0.0.1:5173",
+];
+
+impl CorsPolicy {
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        if value.is_empty() {
+            Self::Localhost
+        } else if value.eq_ignore_ascii_case("none") {
+            Self::Disabled
+        } else if value == "*" {
+            Self::Any
+        } else {
+            Self::Origins(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            )
+        }
+    }
+
+    /// The policy for `group`: its own env var when set, else
+    /// `CORS_ALLOWED_ORIGINS`.
+    pub fn for_group(group: CorsGroup) -> Self {
+        let configured = |var: &str| std::env::var(var).ok().filter(|v| !v.trim().is_empty());
+        Self::parse(
+            &configured(group.env_var())
+                .or_else(|| configured(CorsGroup::Api.env_var()))
+                .unwrap_or_default(),
+        )
+    }
+
+    /// The layer enforcing this policy, `None` when disabled.
+    pub fn layer(&self) -> Option<CorsLayer> {
+        use axum::http::{Method, header};
+
+        let layer = CorsLayer::new()
+            .allow_methods(vec![
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+                Method::OPTIONS,
+            ])
+            .allow_headers(vec![
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                header::ACCEPT,
+            ]);
+        match self {
+            Self::Disabled => None,
+            Self::Any => Some(layer.allow_origin(AllowOrigin::any())),
+            Self::Localhost => Some(
+                layer
+                    .allow_origin(allow_list(LOCALHOST_ORIGINS.iter().copied()))
+                    .allow_credentials(true),
+            ),
+            Self::Origins(list) => Some(
+                layer
+                    .allow_origin(allow_list(list.iter().map(String::as_str)))
+                    .allow_credentials(true),
+            ),
+        }
+    }
+}
+
+/// Origins that fail to parse as header values are skipped.
+fn allow_list<'a>(origins: impl Iterator<Item = &'a str>) -> AllowOrigin {
+    AllowOrigin::list(origins.filter_map(|s| s.parse().ok()).collect::<Vec<_>>())
+}
+
+/// Build the CORS layer for one route group from its env var (see
+/// [`CorsGroup::env_var`]), falling back to `CORS_ALLOWED_ORIGINS`.
 ///
 /// - `"none"` → CORS disabled (use when behind BPM proxy that handles CORS).
 /// - Comma-separated origins → strict whitelist with credentials.
 /// - `"*"` → allow any origin (development mode only, must be explicit).
 /// - Unset → localhost-only with warning (safe default for production).
-pub fn build_cors_layer() -> CorsLayer {
-    use axum::http::{Method, header};
-
-    let allowed_methods = vec![
-        Method::GET,
-        Method::POST,
-        Method::PATCH,
-        Method::DELETE,
-        Method::OPTIONS,
-    ];
-    let allowed_headers = vec![header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT];
-
-    let origins_env = std::env::var("CORS_ALLOWED_ORIGINS").unwrap_or_default();
-
-    // Behind BPM proxy: disable CORS entirely (proxy handles it).
-    if origins_env.eq_ignore_ascii_case("none") {
-        return CorsLayer::new()
-            .allow_origin(AllowOrigin::exact(
-                "http://localhost".parse().expect("valid origin"),
-            ))
-            .allow_methods(allowed_methods)
-            .allow_headers(allowed_headers);
-    }
-
-    if origins_env == "*" {
-        tracing::warn!("CORS_ALLOWED_ORIGINS=* — wildcard CORS enabled (development mode only)");
-        CorsLayer::new()
-            .allow_origin(AllowOrigin::any())
-            .allow_methods(allowed_methods)
-            .allow_headers(allowed_headers)
-    } else if origins_env.is_empty() {
-        // Unset — restrictive default for production safety.
-        tracing::warn!(
+pub fn build_cors_layer(group: CorsGroup) -> Option<CorsLayer> {
+    let policy = CorsPolicy::for_group(group);
+    match &policy {
+        CorsPolicy::Any => tracing::warn!(?group, "Wildcard CORS enabled (development mode only)"),
+        CorsPolicy::Localhost => tracing::warn!(
+            ?group,
             "CORS_ALLOWED_ORIGINS not set; defaulting to localhost-only. \
              Set explicitly for production deployments."
-        );
-        let localhost_origins: Vec<_> = [
-            "http://localhost:1338",
-            "http://localhost:3000",
-            "http://localhost:5173",
-            "http://127.0.0.1:1338",
-            "http://127.0.0.1:3000",
-            "http://127.0.0.1:5173",
-        ]
-        .iter()
-        .filter_map(|s| s.parse().ok())
-        .collect();
-        CorsLayer::new()
-            .allow_origin(AllowOrigin::list(localhost_origins))
-            .allow_methods(allowed_methods)
-            .allow_headers(allowed_headers)
-            .allow_credentials(true)
-    } else {
-        let origins: Vec<_> = origins_env
-            .split(',')
-            .filter_map(|s| s.trim().parse().ok())
-            .collect();
-        CorsLayer::new()
-            .allow_origin(AllowOrigin::list(origins))
-            .allow_methods(allowed_methods)
-            .allow_headers(allowed_headers)
-            .allow_credentials(true)
+        ),
+        CorsPolicy::Disabled | CorsPolicy::Origins(_) => {}
+    }
+    policy.layer()
+}
+
+/// Apply `group`'s CORS policy to `router`; a disabled policy adds nothing.
+pub(crate) fn with_cors(router: Router, group: CorsGroup) -> Router {
+    match build_cors_layer(group) {
+        Some(cors) => router.layer(cors),
+        None => router,
     }
 }
 
@@ -192,12 +275,40 @@ pub(crate) async fn http_metrics_middleware(
         .get::<axum::extract::MatchedPath>()
         .map(|m| m.as_str().to_string())
         .unwrap_or_else(|| "unmatched".to_string());
+    // Non-standard methods share one label for the same reason.
+    use axum::http::Method;
+    let method = match *req.method() {
+        Method::GET
+        | Method::POST
+        | Method::PUT
+        | Method::PATCH
+        | Method::DELETE
+        | Method::HEAD
+        | Method::OPTIONS => req.method().as_str().to_string(),
+        _ => "OTHER".to_string(),
+    };
+    // The caller's W3C trace ID when it sent one, so the exemplar matches its
+    // trace; otherwise our request ID, which every log line carries.
+    let trace_id = req
+        .headers()
+        .get("traceparent")
+        .and_then(|v| v.to_str().ok())
+        .and_then(crate::job_trace::parse_traceparent)
+        .or_else(|| req.extensions().get::<RequestId>().map(|id| id.0.clone()));
     let start = std::time::Instant::now();
     let response = next.run(req).await;
     let duration_ms = start.elapsed().as_millis() as u64;
     let status = response.status();
     let is_server_error = status.is_server_error();
     let is_client_error = status.is_client_error();
-    metrics::http_metrics().record(&path, duration_ms, is_server_error, is_client_error);
+    let http_metrics = metrics::http_metrics();
+    http_metrics.record(&path, duration_ms, is_server_error, is_client_error);
+    http_metrics.record_route(
+        &method,
+        &path,
+        status.as_u16(),
+        duration_ms,
+        trace_id.as_deref(),
+    );
     response
 }
@@ -0,0 +1,39 @@
+//! Named secret routes: owner-scoped values that workflow definitions
+//! reference with `{"secretRef": "<name>"}`. Values are write-only.
+
+use super::*;
+use crate::secret_provisioning;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SetNamedSecretRequest {
+    pub value: String,
+}
+
+/// GET /api/secrets — names of the caller's secrets.
+pub(crate) async fn list_named_secrets_handler(
+    SessionAuth(address): SessionAuth,
+) -> impl IntoResponse {
+    let secrets =
+        secret_provisioning::list_named_secrets(&address).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "secrets": secrets }))))
+}
+
+/// PUT /api/secrets/{name} — create or replace a secret.
+pub(crate) async fn set_named_secret_handler(
+    SessionAuth(address): SessionAuth,
+    Path(name): Path<String>,
+    Json(req): Json<SetNamedSecretRequest>,
+) -> impl IntoResponse {
+    let summary = secret_provisioning::set_named_secret(&address, &name, &req.value)
+        .map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!(summary))))
+}
+
+/// DELETE /api/secrets/{name}
+pub(crate) async fn delete_named_secret_handler(
+    SessionAuth(address): SessionAuth,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    secret_provisioning::delete_named_secret(&address, &name).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>(StatusCode::NO_CONTENT)
+}
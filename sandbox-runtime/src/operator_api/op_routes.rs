@@ -0,0 +1,127 @@
+//! Sandbox- and instance-scoped operation route groups.
+
+use super::*;
+
+/// Sandbox-scoped operation endpoints (authenticated, write-rate-limited).
+pub(crate) fn sandbox_op_routes() -> Router {
+    Router::new()
+        .route(
+            "/api/sandboxes/{sandbox_id}/exec",
+            post(sandbox_exec_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/executions",
+            post(sandbox_execution_start_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/prompt",
+            post(sandbox_prompt_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/task",
+            post(sandbox_task_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/tasks",
+            post(sandbox_task_enqueue_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/tasks/{task_id}/cancel",
+            post(sandbox_task_cancel_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/stop",
+            post(sandbox_stop_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/resume",
+            post(sandbox_resume_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/restart",
+            post(sandbox_restart_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/token/rotate",
+            post(sandbox_token_rotate_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/terminal/token",
+            post(sandbox_terminal_token_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/snapshot",
+            post(sandbox_snapshot_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/ssh",
+            post(sandbox_ssh_provision_handler).delete(sandbox_ssh_revoke_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/ssh/user",
+            get(sandbox_ssh_user_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/ssh/keys",
+            get(sandbox_ssh_keys_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/audit",
+            get(sandbox_audit_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/port/{port}/{*rest}",
+            any(sandbox_port_proxy_handler),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/port/{port}",
+            any(sandbox_port_proxy_root_handler),
+        )
+        .layer(middleware::from_fn(rate_limit::write_rate_limit))
+}
+
+/// Instance-scoped operation endpoints (singleton sandbox, authenticated).
+pub(crate) fn instance_op_routes() -> Router {
+    Router::new()
+        .route("/api/sandbox/exec", post(instance_exec_handler))
+        .route(
+            "/api/sandbox/executions",
+            post(instance_execution_start_handler),
+        )
+        .route("/api/sandbox/prompt", post(instance_prompt_handler))
+        .route("/api/sandbox/task", post(instance_task_handler))
+        .route("/api/sandbox/tasks", post(instance_task_enqueue_handler))
+        .route(
+            "/api/sandbox/tasks/{task_id}/cancel",
+            post(instance_task_cancel_handler),
+        )
+        .route("/api/sandbox/stop", post(instance_stop_handler))
+        .route("/api/sandbox/resume", post(instance_resume_handler))
+        .route("/api/sandbox/restart", post(instance_restart_handler))
+        .route(
+            "/api/sandbox/token/rotate",
+            post(instance_token_rotate_handler),
+        )
+        .route(
+            "/api/sandbox/terminal/token",
+            post(instance_terminal_token_handler),
+        )
+        .route("/api/sandbox/snapshot", post(instance_snapshot_handler))
+        .route(
+            "/api/sandbox/ssh",
+            post(instance_ssh_provision_handler).delete(instance_ssh_revoke_handler),
+        )
+        .route("/api/sandbox/ssh/user", get(instance_ssh_user_handler))
+        .route("/api/sandbox/ssh/keys", get(instance_ssh_keys_handler))
+        .route("/api/sandbox/audit", get(instance_audit_handler))
+        .route(
+            "/api/sandbox/port/{port}/{*rest}",
+            any(instance_port_proxy_handler),
+        )
+        .route(
+            "/api/sandbox/port/{port}",
+            any(instance_port_proxy_root_handler),
+        )
+        .route("/api/mcp", post(mcp_handler))
+        .layer(middleware::from_fn(rate_limit::write_rate_limit))
+}
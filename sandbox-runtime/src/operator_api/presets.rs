@@ -0,0 +1,141 @@
+//! Exec preset route group.
+//!
+//! Owners register named exec settings with a sandbox and select one per
+//! exec request with `preset` (see `crate::exec_presets`). Env values are
+//! write-only: responses show the names only. Instance routes manage the
+//! presets of the instance's `main` sandbox.
+
+use super::*;
+use crate::exec_presets::{self, ExecPreset, ExecPresetInput};
+use axum::response::Response;
+
+fn presets_list(sandbox_id: &str) -> Response {
+    match exec_presets::list_exec_presets(sandbox_id) {
+        Ok(list) => {
+            let presets: Vec<ExecPreset> = list.into_iter().map(ExecPreset::redacted).collect();
+            (
+                StatusCode::OK,
+                Json(json!({ "sandbox_id": sandbox_id, "presets": presets })),
+            )
+                .into_response()
+        }
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+fn preset_get(sandbox_id: &str, name: &str) -> Response {
+    match exec_presets::get_exec_preset(sandbox_id, name) {
+        Ok(Some(preset)) => (StatusCode::OK, Json(json!(preset.redacted()))).into_response(),
+        Ok(None) => api_error(
+            StatusCode::NOT_FOUND,
+            format!("Exec preset '{name}' not found"),
+        )
+        .into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+fn preset_put(sandbox_id: &str, name: &str, address: &str, body: ExecPresetInput) -> Response {
+    match exec_presets::put_exec_preset(sandbox_id, name, body, address) {
+        Ok(preset) => (StatusCode::OK, Json(json!(preset.redacted()))).into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+fn preset_delete(sandbox_id: &str, name: &str) -> Response {
+    match exec_presets::delete_exec_preset(sandbox_id, name) {
+        Ok(removed) => (
+            StatusCode::OK,
+            Json(json!({ "sandbox_id": sandbox_id, "name": name, "removed": removed })),
+        )
+            .into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+/// GET /api/sandboxes/{sandbox_id}/exec-presets
+pub(crate) async fn list_exec_presets(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> Response {
+    match resolve_sandbox(&sandbox_id, &address) {
+        Ok(record) => presets_list(&record.id),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/sandboxes/{sandbox_id}/exec-presets/{name}
+pub(crate) async fn get_exec_preset(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, name)): Path<(String, String)>,
+) -> Response {
+    match resolve_sandbox(&sandbox_id, &address) {
+        Ok(record) => preset_get(&record.id, &name),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// PUT /api/sandboxes/{sandbox_id}/exec-presets/{name} — create or replace.
+pub(crate) async fn put_exec_preset(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, name)): Path<(String, String)>,
+    Json(body): Json<ExecPresetInput>,
+) -> Response {
+    match resolve_sandbox(&sandbox_id, &address) {
+        Ok(record) => preset_put(&record.id, &name, &address, body),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// DELETE /api/sandboxes/{sandbox_id}/exec-presets/{name}
+pub(crate) async fn delete_exec_preset(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, name)): Path<(String, String)>,
+) -> Response {
+    match resolve_sandbox(&sandbox_id, &address) {
+        Ok(record) => preset_delete(&record.id, &name),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/sandbox/exec-presets
+pub(crate) async fn instance_list_exec_presets(SessionAuth(address): SessionAuth) -> Response {
+    match resolve_instance(&address) {
+        Ok(record) => presets_list(&record.id),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/sandbox/exec-presets/{name}
+pub(crate) async fn instance_get_exec_preset(
+    SessionAuth(address): SessionAuth,
+    Path(name): Path<String>,
+) -> Response {
+    match resolve_instance(&address) {
+        Ok(record) => preset_get(&record.id, &name),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// PUT /api/sandbox/exec-presets/{name}
+pub(crate) async fn instance_put_exec_preset(
+    SessionAuth(address): SessionAuth,
+    Path(name): Path<String>,
+    Json(body): Json<ExecPresetInput>,
+) -> Response {
+    match resolve_instance(&address) {
+        Ok(record) => preset_put(&record.id, &name, &address, body),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// DELETE /api/sandbox/exec-presets/{name}
+pub(crate) async fn instance_delete_exec_preset(
+    SessionAuth(address): SessionAuth,
+    Path(name): Path<String>,
+) -> Response {
+    match resolve_instance(&address) {
+        Ok(record) => preset_delete(&record.id, &name),
+        Err(e) => e.into_response(),
+    }
+}
@@ -0,0 +1,142 @@
+//! Prompt template route group.
+//!
+//! Owners attach named prompt templates to a sandbox; they are applied to
+//! every agent run on it (see `crate::prompt_templates`). Instance routes
+//! manage the templates of the instance's `main` sandbox.
+
+use super::*;
+use crate::prompt_templates::{self, PromptTemplateInput};
+use axum::response::Response;
+
+fn templates_list(sandbox_id: &str) -> Response {
+    match prompt_templates::list_prompt_templates(sandbox_id) {
+        Ok(list) => (
+            StatusCode::OK,
+            Json(json!({ "sandbox_id": sandbox_id, "templates": list })),
+        )
+            .into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+fn template_get(sandbox_id: &str, name: &str) -> Response {
+    match prompt_templates::get_prompt_template(sandbox_id, name) {
+        Ok(Some(template)) => (StatusCode::OK, Json(json!(template))).into_response(),
+        Ok(None) => api_error(
+            StatusCode::NOT_FOUND,
+            format!("Prompt template '{name}' not found"),
+        )
+        .into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+fn template_put(
+    sandbox_id: &str,
+    name: &str,
+    address: &str,
+    body: PromptTemplateInput,
+) -> Response {
+    match prompt_templates::put_prompt_template(sandbox_id, name, body, address) {
+        Ok(template) => (StatusCode::OK, Json(json!(template))).into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+fn template_delete(sandbox_id: &str, name: &str) -> Response {
+    match prompt_templates::delete_prompt_template(sandbox_id, name) {
+        Ok(removed) => (
+            StatusCode::OK,
+            Json(json!({ "sandbox_id": sandbox_id, "name": name, "removed": removed })),
+        )
+            .into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+/// GET /api/sandboxes/{sandbox_id}/prompts
+pub(crate) async fn list_prompt_templates(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> Response {
+    match resolve_sandbox(&sandbox_id, &address) {
+        Ok(record) => templates_list(&record.id),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/sandboxes/{sandbox_id}/prompts/{name}
+pub(crate) async fn get_prompt_template(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, name)): Path<(String, String)>,
+) -> Response {
+    match resolve_sandbox(&sandbox_id, &address) {
+        Ok(record) => template_get(&record.id, &name),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// PUT /api/sandboxes/{sandbox_id}/prompts/{name} — create or replace.
+pub(crate) async fn put_prompt_template(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, name)): Path<(String, String)>,
+    Json(body): Json<PromptTemplateInput>,
+) -> Response {
+    match resolve_sandbox(&sandbox_id, &address) {
+        Ok(record) => template_put(&record.id, &name, &address, body),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// DELETE /api/sandboxes/{sandbox_id}/prompts/{name}
+pub(crate) async fn delete_prompt_template(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, name)): Path<(String, String)>,
+) -> Response {
+    match resolve_sandbox(&sandbox_id, &address) {
+        Ok(record) => template_delete(&record.id, &name),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/sandbox/prompts
+pub(crate) async fn instance_list_prompt_templates(SessionAuth(address): SessionAuth) -> Response {
+    match resolve_instance(&address) {
+        Ok(record) => templates_list(&record.id),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/sandbox/prompts/{name}
+pub(crate) async fn instance_get_prompt_template(
+    SessionAuth(address): SessionAuth,
+    Path(name): Path<String>,
+) -> Response {
+    match resolve_instance(&address) {
+        Ok(record) => template_get(&record.id, &name),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// PUT /api/sandbox/prompts/{name}
+pub(crate) async fn instance_put_prompt_template(
+    SessionAuth(address): SessionAuth,
+    Path(name): Path<String>,
+    Json(body): Json<PromptTemplateInput>,
+) -> Response {
+    match resolve_instance(&address) {
+        Ok(record) => template_put(&record.id, &name, &address, body),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// DELETE /api/sandbox/prompts/{name}
+pub(crate) async fn instance_delete_prompt_template(
+    SessionAuth(address): SessionAuth,
+    Path(name): Path<String>,
+) -> Response {
+    match resolve_instance(&address) {
+        Ok(record) => template_delete(&record.id, &name),
+        Err(e) => e.into_response(),
+    }
+}
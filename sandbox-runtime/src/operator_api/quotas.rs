@@ -0,0 +1,64 @@
+//! Per-owner quota route group: an owner's usage and usage history, and the
+//! managing operator's per-owner overrides (see `crate::owner_quota` and
+//! `crate::usage_ledger`).
+
+use super::*;
+use crate::owner_quota::{self, OwnerQuota};
+
+/// GET /api/usage — the caller's sandboxes, CPU, memory and running tasks
+/// next to the quota that applies to it (0 = unlimited).
+pub(crate) async fn usage_handler(SessionAuth(address): SessionAuth) -> impl IntoResponse {
+    let (usage, quota) = owner_quota::usage_for(&address).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((
+        StatusCode::OK,
+        Json(json!({ "usage": usage, "quota": quota })),
+    ))
+}
+
+/// GET /api/usage/history — the caller's recorded usage by month, oldest
+/// first (see `crate::usage_ledger`).
+pub(crate) async fn usage_history_handler(SessionAuth(address): SessionAuth) -> impl IntoResponse {
+    let months = crate::usage_ledger::usage_history(&address).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "months": months }))))
+}
+
+/// GET /api/operator/quotas — the default quota and every per-owner override.
+pub(crate) async fn list_quotas_handler(SessionAuth(address): SessionAuth) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    let overrides = owner_quota::list_quota_overrides().map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((
+        StatusCode::OK,
+        Json(json!({
+            "default": owner_quota::default_quota(),
+            "overrides": overrides,
+        })),
+    ))
+}
+
+/// PUT /api/operator/quotas/{owner} — set an owner's quota. Omitted limits
+/// are unlimited.
+pub(crate) async fn set_quota_handler(
+    SessionAuth(address): SessionAuth,
+    Path(owner): Path<String>,
+    Json(quota): Json<OwnerQuota>,
+) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    let entry = owner_quota::set_quota_override(&owner, quota).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(entry)))
+}
+
+/// DELETE /api/operator/quotas/{owner} — drop an owner's override so the
+/// default applies again.
+pub(crate) async fn delete_quota_handler(
+    SessionAuth(address): SessionAuth,
+    Path(owner): Path<String>,
+) -> impl IntoResponse {
+    require_managing_operator(&address)?;
+    if !owner_quota::remove_quota_override(&owner).map_err(classify_sandbox_error)? {
+        return Err(api_error(
+            StatusCode::NOT_FOUND,
+            format!("No quota override for {owner}"),
+        ));
+    }
+    Ok::<_, (StatusCode, Json<ApiError>)>(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,183 @@
+//! Deep readiness probe (`GET /health/ready`).
+//!
+//! `/health` and `/readyz` only answer "can this node serve sandboxes". For
+//! orchestration and alerting, `/health/ready` checks each dependency
+//! separately and reports per-component status plus an overall verdict:
+//!
+//! - `runtime`: the configured backend (Docker daemon ping, Firecracker
+//!   driver, TEE backend registration). Critical.
+//! - `store`: the state directory accepts a write and the sandbox store
+//!   reads. Critical.
+//! - `tangle_rpc`: `eth_chainId` against `HTTP_RPC_ENDPOINT` / `RPC_URL`;
+//!   skipped when neither is set or the endpoint is not HTTP.
+//! - `tee`: [`crate::tee::TeeBackend::health`] when a TEE backend is
+//!   registered. Critical when the runtime backend is `tee`.
+//!
+//! A failing critical component makes the node `unhealthy` (`503`); any other
+//! failure makes it `degraded` (`200`, the node still serves sandboxes).
+
+use super::*;
+
+const COMPONENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct ComponentCheck {
+    name: &'static str,
+    critical: bool,
+    /// `None` = skipped, `Some(Err)` = failed.
+    outcome: Option<std::result::Result<(), String>>,
+    latency_ms: u64,
+    detail: Option<String>,
+}
+
+impl ComponentCheck {
+    fn failed(&self) -> bool {
+        matches!(self.outcome, Some(Err(_)))
+    }
+
+    fn to_json(&self) -> Value {
+        let (status, error) = match &self.outcome {
+            None => ("skipped", None),
+            Some(Ok(())) => ("ok", None),
+            Some(Err(e)) => ("error", Some(e.clone())),
+        };
+        json!({
+            "status": status,
+            "critical": self.critical,
+            "latency_ms": self.latency_ms,
+            "detail": self.detail,
+            "error": error,
+        })
+    }
+}
+
+async fn timed<F>(fut: F) -> (std::result::Result<(), String>, u64)
+where
+    F: std::future::Future<Output = std::result::Result<(), String>>,
+{
+    let started = std::time::Instant::now();
+    let result = match tokio::time::timeout(COMPONENT_TIMEOUT, fut).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("timed out after {}s", COMPONENT_TIMEOUT.as_secs())),
+    };
+    (result, started.elapsed().as_millis() as u64)
+}
+
+fn check_store_writable() -> std::result::Result<(), String> {
+    let probe = crate::store::state_dir().join(".ready-probe");
+    std::fs::write(&probe, b"ok").map_err(|e| format!("state dir not writable: {e}"))?;
+    let _ = std::fs::remove_file(&probe);
+    runtime::sandboxes()
+        .and_then(|s| s.values())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Chain RPC endpoint the operator reports to, if configured.
+pub(crate) fn configured_rpc_endpoint() -> Option<String> {
+    std::env::var("HTTP_RPC_ENDPOINT")
+        .or_else(|_| std::env::var("RPC_URL"))
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+async fn check_tangle_rpc(endpoint: &str) -> std::result::Result<(), String> {
+    let client = crate::util::http_client().map_err(|e| e.to_string())?;
+    let resp = client
+        .post(endpoint)
+        .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_chainId", "params": [] }))
+        .send()
+        .await
+        .map_err(|e| format!("rpc unreachable: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("rpc returned status {}", resp.status()));
+    }
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("invalid rpc response: {e}"))?;
+    if body.get("result").and_then(Value::as_str).is_none() {
+        return Err(format!("rpc error: {}", body.get("error").unwrap_or(&body)));
+    }
+    Ok(())
+}
+
+pub(crate) async fn health_ready() -> impl IntoResponse {
+    let mut checks = Vec::with_capacity(4);
+
+    let started = std::time::Instant::now();
+    let (backend, runtime_ok, runtime_error) = probe_runtime_backend().await;
+    checks.push(ComponentCheck {
+        name: "runtime",
+        critical: true,
+        outcome: Some(if runtime_ok {
+            Ok(())
+        } else {
+            Err(runtime_error.unwrap_or_else(|| "runtime probe failed".into()))
+        }),
+        latency_ms: started.elapsed().as_millis() as u64,
+        detail: Some(backend.clone()),
+    });
+
+    let (outcome, latency_ms) = timed(async { check_store_writable() }).await;
+    checks.push(ComponentCheck {
+        name: "store",
+        critical: true,
+        outcome: Some(outcome),
+        latency_ms,
+        detail: None,
+    });
+
+    let rpc = configured_rpc_endpoint().filter(|url| url.starts_with("http"));
+    let (outcome, latency_ms) = match &rpc {
+        Some(url) => {
+            let (outcome, latency_ms) = timed(check_tangle_rpc(url)).await;
+            (Some(outcome), latency_ms)
+        }
+        None => (None, 0),
+    };
+    checks.push(ComponentCheck {
+        name: "tangle_rpc",
+        critical: false,
+        outcome,
+        latency_ms,
+        detail: None,
+    });
+
+    let tee_is_runtime = backend == "tee";
+    let (outcome, latency_ms) = match crate::tee::try_tee_backend() {
+        Some(tee) => {
+            let (outcome, latency_ms) =
+                timed(async { tee.health().await.map_err(|e| e.to_string()) }).await;
+            (Some(outcome), latency_ms)
+        }
+        None => (None, 0),
+    };
+    checks.push(ComponentCheck {
+        name: "tee",
+        critical: tee_is_runtime,
+        outcome,
+        latency_ms,
+        detail: None,
+    });
+
+    let (status, code) = if checks.iter().any(|c| c.critical && c.failed()) {
+        ("unhealthy", StatusCode::SERVICE_UNAVAILABLE)
+    } else if checks.iter().any(ComponentCheck::failed) {
+        ("degraded", StatusCode::OK)
+    } else {
+        ("healthy", StatusCode::OK)
+    };
+    let components: Map<String, Value> = checks
+        .iter()
+        .map(|c| (c.name.to_string(), c.to_json()))
+        .collect();
+
+    (
+        code,
+        Json(json!({
+            "status": status,
+            "components": components,
+        })),
+    )
+}
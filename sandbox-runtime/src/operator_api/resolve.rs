@@ -21,13 +21,31 @@ pub(crate) fn resolve_sandbox(
     })
 }
 
-/// Look up the singleton instance sandbox and validate ownership.
+/// Look up the primary (`main` slot) instance sandbox and validate ownership.
 pub(crate) fn resolve_instance(
     caller: &str,
 ) -> Result<SandboxRecord, (StatusCode, Json<ApiError>)> {
-    let record = runtime::get_instance_sandbox()
-        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Instance not provisioned"))?;
+    resolve_instance_slot(caller, "")
+}
+
+/// Look up the sandbox in a named instance slot (empty = `main`) and validate
+/// ownership.
+pub(crate) fn resolve_instance_slot(
+    caller: &str,
+    slot: &str,
+) -> Result<SandboxRecord, (StatusCode, Json<ApiError>)> {
+    let record = runtime::get_instance_sandbox_slot(slot)
+        .map_err(classify_sandbox_error)?
+        .ok_or_else(|| {
+            if slot.trim().is_empty() {
+                api_error(StatusCode::NOT_FOUND, "Instance not provisioned")
+            } else {
+                api_error(
+                    StatusCode::NOT_FOUND,
+                    format!("Instance slot '{}' not provisioned", slot.trim()),
+                )
+            }
+        })?;
 
     if record.owner.is_empty() {
         return Err(api_error(
@@ -13,6 +13,9 @@ pub(crate) struct SandboxSummary {
     pub(crate) sidecar_url: String,
     pub(crate) state: String,
     pub(crate) image: String,
+    /// Registry digest the image resolved to on this host's architecture.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) image_digest: Option<String>,
     #[serde(skip_serializing_if = "String::is_empty")]
     pub(crate) agent_identifier: String,
     pub(crate) cpu_cores: u64,
@@ -43,6 +46,16 @@ pub(crate) struct SandboxSummary {
     pub(crate) circuit_breaker_remaining_secs: Option<u64>,
     /// Whether a recovery probe is in flight.
     pub(crate) circuit_breaker_probing: bool,
+    /// Container OOM kills and unexpected exits, oldest first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) crash_history: Vec<crate::crash_monitor::CrashRecord>,
+    /// Last measured disk usage against `disk_gb` (Docker backend).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) disk_usage: Option<crate::disk_quota::DiskUsage>,
+    /// Provisions, stops, resumes, snapshots, secret injections, upgrades
+    /// and reaps, oldest first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) lifecycle_events: Vec<crate::runtime::LifecycleEvent>,
 }
 
 impl SandboxSummary {
@@ -57,6 +70,7 @@ impl SandboxSummary {
                 SandboxState::Stopped => "stopped".into(),
             },
             image: r.original_image.clone(),
+            image_digest: r.image_digest.clone(),
             agent_identifier: r.agent_identifier.clone(),
             cpu_cores: r.cpu_cores,
             memory_mb: r.memory_mb,
@@ -75,6 +89,9 @@ impl SandboxSummary {
             circuit_breaker_active: breaker.active,
             circuit_breaker_remaining_secs: breaker.remaining_secs,
             circuit_breaker_probing: breaker.probing,
+            crash_history: r.crash_history.clone(),
+            disk_usage: crate::disk_quota::disk_usage(&r.id),
+            lifecycle_events: r.lifecycle_events.clone(),
         }
     }
 }
@@ -123,35 +140,8 @@ pub(crate) fn derive_operator_address_from_secret(
 pub(crate) fn derive_operator_address_from_keystore_uri(
     keystore_uri: &str,
 ) -> std::result::Result<String, String> {
-    use std::fs;
-    use std::path::Path;
-
-    let keystore_path = keystore_uri.strip_prefix("file://").unwrap_or(keystore_uri);
-    let ecdsa_dir = Path::new(keystore_path).join("Ecdsa");
-    let mut entries = fs::read_dir(&ecdsa_dir)
-        .map_err(|err| format!("failed to read {}: {err}", ecdsa_dir.display()))?
-        .collect::<std::result::Result<Vec<_>, _>>()
-        .map_err(|err| format!("failed to enumerate {}: {err}", ecdsa_dir.display()))?;
-    entries.sort_by_key(|entry| entry.path());
-
-    for entry in entries {
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
-        let raw = fs::read_to_string(&path)
-            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
-        let components: Vec<Vec<u8>> = serde_json::from_str(&raw)
-            .map_err(|err| format!("failed to parse {}: {err}", path.display()))?;
-        if let Some(secret) = components.iter().rev().find(|part| part.len() == 32) {
-            return derive_operator_address_from_secret(secret);
-        }
-    }
-
-    Err(format!(
-        "no usable ECDSA secret found under {}",
-        ecdsa_dir.display()
-    ))
+    let secret = crate::response_signing::read_keystore_secret(keystore_uri)?;
+    derive_operator_address_from_secret(&secret)
 }
 
 pub(crate) fn current_managing_operator() -> Option<String> {
@@ -206,3 +196,38 @@ pub(crate) async fn list_sandboxes(SessionAuth(address): SessionAuth) -> impl In
         Err(e) => classify_sandbox_error(e).into_response(),
     }
 }
+
+/// GET /api/sandbox/slots — instance slots owned by the caller, `main` first.
+pub(crate) async fn list_instance_slots_handler(
+    SessionAuth(address): SessionAuth,
+) -> impl IntoResponse {
+    let managing_operator = current_managing_operator();
+    match runtime::list_instance_slots() {
+        Ok(slots) => {
+            let slots: Vec<Value> = slots
+                .into_iter()
+                .filter(|(_, r)| !r.owner.is_empty() && r.owner.eq_ignore_ascii_case(&address))
+                .map(|(slot, record)| {
+                    json!({
+                        "slot": slot,
+                        "sandbox": SandboxSummary::from_record(&record, managing_operator.as_deref()),
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(json!({ "slots": slots }))).into_response()
+        }
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+/// GET /api/sandboxes/{sandbox_id}/tombstone — what GC left of a removed
+/// sandbox of the caller: when it went and where its workspace archive or
+/// kept snapshot lives.
+pub(crate) async fn sandbox_tombstone_handler(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> impl IntoResponse {
+    let tombstone =
+        runtime::get_tombstone(&sandbox_id, &address).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(tombstone)))
+}
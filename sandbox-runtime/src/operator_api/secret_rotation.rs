@@ -0,0 +1,157 @@
+//! Scheduled secret rotation route group.
+//!
+//! Owners attach a rotation policy to a sandbox, inspect it, remove it, or
+//! trigger a rotation immediately. Outcomes land in the audit log.
+
+use super::*;
+use crate::secret_provisioning::{RotationPolicy, RotationSource};
+use axum::response::Response;
+
+#[derive(Deserialize)]
+pub(crate) struct SecretRotationRequest {
+    pub(crate) interval_secs: u64,
+    pub(crate) source: RotationSource,
+}
+
+fn policy_response(sandbox_id: &str, policy: Option<RotationPolicy>) -> Response {
+    (
+        StatusCode::OK,
+        Json(json!({
+            "sandbox_id": sandbox_id,
+            "policy": policy.map(|p| p.redacted()),
+        })),
+    )
+        .into_response()
+}
+
+fn rotation_get(sandbox_id: &str) -> Response {
+    match secret_provisioning::get_rotation_policy(sandbox_id) {
+        Ok(policy) => policy_response(sandbox_id, policy),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+fn rotation_put(sandbox_id: &str, address: &str, body: SecretRotationRequest) -> Response {
+    match secret_provisioning::set_rotation_policy(
+        sandbox_id,
+        body.interval_secs,
+        body.source,
+        address,
+    ) {
+        Ok(policy) => policy_response(sandbox_id, Some(policy)),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+fn rotation_delete(sandbox_id: &str) -> Response {
+    match secret_provisioning::remove_rotation_policy(sandbox_id) {
+        Ok(removed) => (
+            StatusCode::OK,
+            Json(json!({ "sandbox_id": sandbox_id, "removed": removed })),
+        )
+            .into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+async fn rotation_run(sandbox_id: &str, address: &str) -> Response {
+    let _lock = runtime::acquire_lifecycle_lock(sandbox_id).await;
+    match secret_provisioning::rotate_secrets(sandbox_id, address).await {
+        Ok(keys) => (
+            StatusCode::OK,
+            Json(json!({
+                "status": "secrets_rotated",
+                "sandbox_id": sandbox_id,
+                "rotated_keys": keys,
+            })),
+        )
+            .into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+/// GET /api/sandboxes/{sandbox_id}/secrets/rotation
+pub(crate) async fn get_secret_rotation(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> Response {
+    if let Err(e) = secret_provisioning::validate_secret_access(&sandbox_id, &address) {
+        return api_error(StatusCode::FORBIDDEN, e.to_string()).into_response();
+    }
+    rotation_get(&sandbox_id)
+}
+
+/// PUT /api/sandboxes/{sandbox_id}/secrets/rotation — create or replace.
+pub(crate) async fn put_secret_rotation(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+    Json(body): Json<SecretRotationRequest>,
+) -> Response {
+    if let Err(e) = secret_provisioning::validate_secret_access(&sandbox_id, &address) {
+        return api_error(StatusCode::FORBIDDEN, e.to_string()).into_response();
+    }
+    rotation_put(&sandbox_id, &address, body)
+}
+
+/// DELETE /api/sandboxes/{sandbox_id}/secrets/rotation
+pub(crate) async fn delete_secret_rotation(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> Response {
+    if let Err(e) = secret_provisioning::validate_secret_access(&sandbox_id, &address) {
+        return api_error(StatusCode::FORBIDDEN, e.to_string()).into_response();
+    }
+    rotation_delete(&sandbox_id)
+}
+
+/// POST /api/sandboxes/{sandbox_id}/secrets/rotation/run — rotate now.
+pub(crate) async fn run_secret_rotation(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> Response {
+    if let Err(e) = secret_provisioning::validate_secret_access(&sandbox_id, &address) {
+        return api_error(StatusCode::FORBIDDEN, e.to_string()).into_response();
+    }
+    rotation_run(&sandbox_id, &address).await
+}
+
+fn resolve_instance_for_rotation(address: &str) -> Result<SandboxRecord, Response> {
+    let record = resolve_instance(address).map_err(IntoResponse::into_response)?;
+    reject_instance_tee_secrets(&record).map_err(IntoResponse::into_response)?;
+    Ok(record)
+}
+
+/// GET /api/sandbox/secrets/rotation
+pub(crate) async fn instance_get_secret_rotation(SessionAuth(address): SessionAuth) -> Response {
+    match resolve_instance_for_rotation(&address) {
+        Ok(record) => rotation_get(&record.id),
+        Err(resp) => resp,
+    }
+}
+
+/// PUT /api/sandbox/secrets/rotation
+pub(crate) async fn instance_put_secret_rotation(
+    SessionAuth(address): SessionAuth,
+    Json(body): Json<SecretRotationRequest>,
+) -> Response {
+    match resolve_instance_for_rotation(&address) {
+        Ok(record) => rotation_put(&record.id, &address, body),
+        Err(resp) => resp,
+    }
+}
+
+/// DELETE /api/sandbox/secrets/rotation
+pub(crate) async fn instance_delete_secret_rotation(SessionAuth(address): SessionAuth) -> Response {
+    match resolve_instance_for_rotation(&address) {
+        Ok(record) => rotation_delete(&record.id),
+        Err(resp) => resp,
+    }
+}
+
+/// POST /api/sandbox/secrets/rotation/run
+pub(crate) async fn instance_run_secret_rotation(SessionAuth(address): SessionAuth) -> Response {
+    match resolve_instance_for_rotation(&address) {
+        Ok(record) => rotation_run(&record.id, &address).await,
+        Err(resp) => resp,
+    }
+}
@@ -0,0 +1,107 @@
+//! Secret version route group.
+//!
+//! Owners list the secret sets applied to a sandbox (hashes and key names,
+//! never values) and roll back to the set active before the latest change.
+
+use super::*;
+use axum::response::Response;
+
+fn versions_get(sandbox_id: &str) -> Response {
+    match secret_provisioning::secret_version_history(sandbox_id) {
+        Ok(history) => {
+            let rollback_version = history.rollback_target().map(|v| v.version);
+            // The sealed rollback copy stays operator-side.
+            let versions: Vec<_> = history
+                .versions
+                .iter()
+                .map(|v| {
+                    json!({
+                        "version": v.version,
+                        "hash": v.hash,
+                        "keys": v.keys,
+                        "created_at": v.created_at,
+                    })
+                })
+                .collect();
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "sandbox_id": sandbox_id,
+                    "active_version": history.active,
+                    "rollback_version": rollback_version,
+                    "versions": versions,
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+async fn rollback(sandbox_id: &str) -> Response {
+    let _lock = runtime::acquire_lifecycle_lock(sandbox_id).await;
+    match secret_provisioning::rollback_secrets(sandbox_id, None).await {
+        Ok((record, version)) => {
+            sync_instance_record(&record.id);
+            crate::circuit_breaker::mark_healthy(&record.id);
+            let creds = workflow_runtime_credentials_available(&record.effective_env_json())
+                .unwrap_or(false);
+            (
+                StatusCode::OK,
+                Json(SecretsResponse {
+                    status: "secrets_rolled_back".to_string(),
+                    sandbox_id: record.id,
+                    credentials_available: creds,
+                    active_version: Some(version),
+                }),
+            )
+                .into_response()
+        }
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+/// GET /api/sandboxes/{sandbox_id}/secrets/versions
+pub(crate) async fn get_secret_versions(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> Response {
+    if let Err(e) = secret_provisioning::validate_secret_access(&sandbox_id, &address) {
+        return api_error(StatusCode::FORBIDDEN, e.to_string()).into_response();
+    }
+    versions_get(&sandbox_id)
+}
+
+/// POST /api/sandboxes/{sandbox_id}/secrets/rollback — re-apply the
+/// previous secret version.
+pub(crate) async fn rollback_secrets(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> Response {
+    if let Err(e) = secret_provisioning::validate_secret_access(&sandbox_id, &address) {
+        return api_error(StatusCode::FORBIDDEN, e.to_string()).into_response();
+    }
+    rollback(&sandbox_id).await
+}
+
+fn resolve_instance_for_versions(address: &str) -> Result<SandboxRecord, Response> {
+    let record = resolve_instance(address).map_err(IntoResponse::into_response)?;
+    reject_instance_tee_secrets(&record).map_err(IntoResponse::into_response)?;
+    Ok(record)
+}
+
+/// GET /api/sandbox/secrets/versions
+pub(crate) async fn instance_get_secret_versions(SessionAuth(address): SessionAuth) -> Response {
+    match resolve_instance_for_versions(&address) {
+        Ok(record) => versions_get(&record.id),
+        Err(resp) => resp,
+    }
+}
+
+/// POST /api/sandbox/secrets/rollback
+pub(crate) async fn instance_rollback_secrets(SessionAuth(address): SessionAuth) -> Response {
+    match resolve_instance_for_versions(&address) {
+        Ok(record) => rollback(&record.id).await,
+        Err(resp) => resp,
+    }
+}
@@ -8,7 +8,35 @@ use super::*;
 
 #[derive(Deserialize)]
 pub(crate) struct InjectSecretsRequest {
+    #[serde(default)]
     pub(crate) env_json: serde_json::Map<String, serde_json::Value>,
+    /// Fetch secrets from Vault at injection time; `env_json` entries are
+    /// merged on top.
+    #[serde(default)]
+    pub(crate) vault: Option<secret_provisioning::VaultSecretSource>,
+}
+
+impl InjectSecretsRequest {
+    /// Checks that need no network access.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        match &self.vault {
+            Some(vault) => vault.validate(),
+            None => crate::api_types::validate_secrets_map(&self.env_json),
+        }
+    }
+
+    /// Resolve the final secret map, fetching from Vault when requested.
+    /// Call only after the caller's access has been checked.
+    pub(crate) async fn into_env(
+        self,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, (StatusCode, Json<ApiError>)> {
+        let env = secret_provisioning::resolve_secret_env(self.env_json, self.vault.as_ref())
+            .await
+            .map_err(classify_sandbox_error)?;
+        crate::api_types::validate_secrets_map(&env)
+            .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+        Ok(env)
+    }
 }
 
 #[derive(Serialize)]
@@ -17,6 +45,8 @@ pub(crate) struct SecretsResponse {
     pub(crate) sandbox_id: String,
     /// Whether AI credentials are available after this operation.
     pub(crate) credentials_available: bool,
+    /// Secret version active after this operation; `None` once wiped.
+    pub(crate) active_version: Option<u32>,
 }
 
 #[derive(Serialize)]
@@ -24,6 +54,7 @@ pub(crate) struct GetSecretsResponse {
     pub(crate) sandbox_id: String,
     pub(crate) env_json: serde_json::Map<String, serde_json::Value>,
     pub(crate) credentials_available: bool,
+    pub(crate) active_version: Option<u32>,
 }
 
 pub(crate) async fn instance_get_secrets(SessionAuth(address): SessionAuth) -> impl IntoResponse {
@@ -44,6 +75,7 @@ pub(crate) async fn instance_get_secrets(SessionAuth(address): SessionAuth) -> i
 
     let creds =
         workflow_runtime_credentials_available(&record.effective_env_json()).unwrap_or(false);
+    let active_version = secret_provisioning::active_secret_version(&record.id);
 
     (
         StatusCode::OK,
@@ -51,6 +83,7 @@ pub(crate) async fn instance_get_secrets(SessionAuth(address): SessionAuth) -> i
             sandbox_id: record.id,
             env_json: env_map,
             credentials_available: creds,
+            active_version,
         }),
     )
         .into_response()
@@ -60,7 +93,7 @@ pub(crate) async fn instance_inject_secrets(
     SessionAuth(address): SessionAuth,
     Json(body): Json<InjectSecretsRequest>,
 ) -> impl IntoResponse {
-    if let Err(e) = crate::api_types::validate_secrets_map(&body.env_json) {
+    if let Err(e) = body.validate() {
         return api_error(StatusCode::BAD_REQUEST, e).into_response();
     }
 
@@ -72,17 +105,23 @@ pub(crate) async fn instance_inject_secrets(
         return err.into_response();
     }
 
-    match secret_provisioning::inject_secrets(&record.id, body.env_json, None).await {
+    let env = match body.into_env().await {
+        Ok(env) => env,
+        Err(err) => return err.into_response(),
+    };
+    match secret_provisioning::inject_secrets(&record.id, env, None).await {
         Ok(updated) => {
             sync_instance_record(&updated.id);
             let creds = workflow_runtime_credentials_available(&updated.effective_env_json())
                 .unwrap_or(false);
+            let active_version = secret_provisioning::active_secret_version(&updated.id);
             (
                 StatusCode::OK,
                 Json(SecretsResponse {
                     status: "secrets_configured".to_string(),
                     sandbox_id: updated.id,
                     credentials_available: creds,
+                    active_version,
                 }),
             )
                 .into_response()
@@ -105,12 +144,14 @@ pub(crate) async fn instance_wipe_secrets(SessionAuth(address): SessionAuth) ->
             sync_instance_record(&updated.id);
             let creds = workflow_runtime_credentials_available(&updated.effective_env_json())
                 .unwrap_or(false);
+            let active_version = secret_provisioning::active_secret_version(&updated.id);
             (
                 StatusCode::OK,
                 Json(SecretsResponse {
                     status: "secrets_wiped".to_string(),
                     sandbox_id: updated.id,
                     credentials_available: creds,
+                    active_version,
                 }),
             )
                 .into_response()
@@ -141,6 +182,7 @@ pub(crate) async fn get_secrets(
 
     let creds =
         workflow_runtime_credentials_available(&record.effective_env_json()).unwrap_or(false);
+    let active_version = secret_provisioning::active_secret_version(&record.id);
 
     (
         StatusCode::OK,
@@ -148,6 +190,7 @@ pub(crate) async fn get_secrets(
             sandbox_id: record.id,
             env_json: env_map,
             credentials_available: creds,
+            active_version,
         }),
     )
         .into_response()
@@ -158,26 +201,32 @@ pub(crate) async fn inject_secrets(
     Path(sandbox_id): Path<String>,
     Json(body): Json<InjectSecretsRequest>,
 ) -> impl IntoResponse {
-    if let Err(e) = crate::api_types::validate_secrets_map(&body.env_json) {
+    if let Err(e) = body.validate() {
         return api_error(StatusCode::BAD_REQUEST, e).into_response();
     }
     if let Err(e) = secret_provisioning::validate_secret_access(&sandbox_id, &address) {
         return api_error(StatusCode::FORBIDDEN, e.to_string()).into_response();
     }
 
+    let env = match body.into_env().await {
+        Ok(env) => env,
+        Err(err) => return err.into_response(),
+    };
     // Lifecycle lock prevents concurrent inject/wipe from creating orphaned
     // containers via the stop → delete → create sequence in recreate_sidecar_with_env.
     let _lock = runtime::acquire_lifecycle_lock(&sandbox_id).await;
-    match secret_provisioning::inject_secrets(&sandbox_id, body.env_json, None).await {
+    match secret_provisioning::inject_secrets(&sandbox_id, env, None).await {
         Ok(record) => {
             let creds = workflow_runtime_credentials_available(&record.effective_env_json())
                 .unwrap_or(false);
+            let active_version = secret_provisioning::active_secret_version(&record.id);
             (
                 StatusCode::OK,
                 Json(SecretsResponse {
                     status: "secrets_configured".to_string(),
                     sandbox_id: record.id,
                     credentials_available: creds,
+                    active_version,
                 }),
             )
                 .into_response()
@@ -199,12 +248,14 @@ pub(crate) async fn wipe_secrets(
         Ok(record) => {
             let creds = workflow_runtime_credentials_available(&record.effective_env_json())
                 .unwrap_or(false);
+            let active_version = secret_provisioning::active_secret_version(&record.id);
             (
                 StatusCode::OK,
                 Json(SecretsResponse {
                     status: "secrets_wiped".to_string(),
                     sandbox_id: record.id,
                     credentials_available: creds,
+                    active_version,
                 }),
             )
                 .into_response()
@@ -227,7 +278,5 @@ pub(crate) fn reject_instance_tee_secrets(
 }
 
 pub(crate) fn sync_instance_record(id: &str) {
-    if let Ok(Some(updated)) = sandboxes().and_then(|s| s.get(id)) {
-        let _ = runtime::instance_store().and_then(|s| s.insert("instance".to_string(), updated));
-    }
+    let _ = runtime::sync_instance_slot_record(id);
 }
@@ -2,63 +2,116 @@
 
 use super::*;
 
-pub(crate) async fn sandbox_terminal_session_create_handler(
+/// Resolve the sandbox behind a terminal relay request, enforcing a terminal
+/// token's sandbox scope on top of the usual ownership check.
+fn resolve_terminal_sandbox(
+    auth: &TerminalAuth,
+    sandbox_id: &str,
+) -> Result<SandboxRecord, (StatusCode, Json<ApiError>)> {
+    let record = resolve_sandbox(sandbox_id, &auth.address)?;
+    auth.check_scope(&record.id)
+        .map_err(|e| api_error(StatusCode::FORBIDDEN, e.to_string()))?;
+    Ok(record)
+}
+
+fn resolve_terminal_instance(
+    auth: &TerminalAuth,
+) -> Result<SandboxRecord, (StatusCode, Json<ApiError>)> {
+    let record = resolve_instance(&auth.address)?;
+    auth.check_scope(&record.id)
+        .map_err(|e| api_error(StatusCode::FORBIDDEN, e.to_string()))?;
+    Ok(record)
+}
+
+fn mint_terminal_token(
+    address: &str,
+    record: &SandboxRecord,
+) -> Result<(StatusCode, Json<session_auth::TerminalToken>), (StatusCode, Json<ApiError>)> {
+    if runtime::web_terminal_disabled(record) {
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            "Web terminal is disabled for this sandbox",
+        ));
+    }
+    let token =
+        session_auth::issue_terminal_token(address, &record.id).map_err(classify_sandbox_error)?;
+    Ok((StatusCode::OK, Json(token)))
+}
+
+/// POST /api/sandboxes/{sandbox_id}/terminal/token
+pub(crate) async fn sandbox_terminal_token_handler(
     SessionAuth(address): SessionAuth,
     Path(sandbox_id): Path<String>,
-    req: Option<Json<CreateLiveTerminalSessionRequest>>,
 ) -> impl IntoResponse {
     let record = resolve_sandbox(&sandbox_id, &address)?;
+    mint_terminal_token(&address, &record)
+}
+
+/// POST /api/sandbox/terminal/token
+pub(crate) async fn instance_terminal_token_handler(
+    SessionAuth(address): SessionAuth,
+) -> impl IntoResponse {
+    let record = resolve_instance(&address)?;
+    mint_terminal_token(&address, &record)
+}
+
+pub(crate) async fn sandbox_terminal_session_create_handler(
+    auth: TerminalAuth,
+    Path(sandbox_id): Path<String>,
+    req: Option<Json<CreateLiveTerminalSessionRequest>>,
+) -> impl IntoResponse {
+    let record = resolve_terminal_sandbox(&auth, &sandbox_id)?;
     let req = req.map(|Json(body)| body).unwrap_or_default();
     let summary = create_terminal_session(&record, &req).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(summary)))
 }
 
 pub(crate) async fn sandbox_terminal_session_list_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     Path(sandbox_id): Path<String>,
 ) -> impl IntoResponse {
-    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let record = resolve_terminal_sandbox(&auth, &sandbox_id)?;
     let sessions = list_terminal_sessions(&record).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "sessions": sessions }))))
 }
 
 pub(crate) async fn sandbox_terminal_session_stream_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     Path((sandbox_id, session_id)): Path<(String, String)>,
 ) -> impl IntoResponse {
-    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let record = resolve_terminal_sandbox(&auth, &sandbox_id)?;
     stream_terminal_session(&record, &session_id).await
 }
 
 pub(crate) async fn sandbox_terminal_session_delete_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     Path((sandbox_id, session_id)): Path<(String, String)>,
 ) -> impl IntoResponse {
-    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let record = resolve_terminal_sandbox(&auth, &sandbox_id)?;
     let resp = delete_terminal_session(&record, &session_id).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
 }
 
 pub(crate) async fn sandbox_terminal_session_resize_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     Path((sandbox_id, session_id)): Path<(String, String)>,
     Json(req): Json<TerminalResizeApiRequest>,
 ) -> impl IntoResponse {
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
-    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let record = resolve_terminal_sandbox(&auth, &sandbox_id)?;
     resize_terminal_session_on_sidecar(&record, &session_id, req.cols, req.rows).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "success": true }))))
 }
 
 pub(crate) async fn sandbox_terminal_session_input_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     Path((sandbox_id, session_id)): Path<(String, String)>,
     Json(req): Json<TerminalInputApiRequest>,
 ) -> impl IntoResponse {
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
-    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let record = resolve_terminal_sandbox(&auth, &sandbox_id)?;
     send_terminal_input_to_sidecar(&record, &session_id, &req.data).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "success": true }))))
 }
@@ -126,60 +179,60 @@ pub(crate) async fn sandbox_chat_run_cancel_handler(
 }
 
 pub(crate) async fn instance_terminal_session_create_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     req: Option<Json<CreateLiveTerminalSessionRequest>>,
 ) -> impl IntoResponse {
-    let record = resolve_instance(&address)?;
+    let record = resolve_terminal_instance(&auth)?;
     let req = req.map(|Json(body)| body).unwrap_or_default();
     let summary = create_terminal_session(&record, &req).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(summary)))
 }
 
 pub(crate) async fn instance_terminal_session_list_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
 ) -> impl IntoResponse {
-    let record = resolve_instance(&address)?;
+    let record = resolve_terminal_instance(&auth)?;
     let sessions = list_terminal_sessions(&record).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "sessions": sessions }))))
 }
 
 pub(crate) async fn instance_terminal_session_stream_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     Path(session_id): Path<String>,
 ) -> impl IntoResponse {
-    let record = resolve_instance(&address)?;
+    let record = resolve_terminal_instance(&auth)?;
     stream_terminal_session(&record, &session_id).await
 }
 
 pub(crate) async fn instance_terminal_session_delete_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     Path(session_id): Path<String>,
 ) -> impl IntoResponse {
-    let record = resolve_instance(&address)?;
+    let record = resolve_terminal_instance(&auth)?;
     let resp = delete_terminal_session(&record, &session_id).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
 }
 
 pub(crate) async fn instance_terminal_session_resize_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     Path(session_id): Path<String>,
     Json(req): Json<TerminalResizeApiRequest>,
 ) -> impl IntoResponse {
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
-    let record = resolve_instance(&address)?;
+    let record = resolve_terminal_instance(&auth)?;
     resize_terminal_session_on_sidecar(&record, &session_id, req.cols, req.rows).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "success": true }))))
 }
 
 pub(crate) async fn instance_terminal_session_input_handler(
-    SessionAuth(address): SessionAuth,
+    auth: TerminalAuth,
     Path(session_id): Path<String>,
     Json(req): Json<TerminalInputApiRequest>,
 ) -> impl IntoResponse {
     req.validate()
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
-    let record = resolve_instance(&address)?;
+    let record = resolve_terminal_instance(&auth)?;
     send_terminal_input_to_sidecar(&record, &session_id, &req.data).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "success": true }))))
 }
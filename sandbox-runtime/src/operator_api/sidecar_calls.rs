@@ -321,11 +321,7 @@ pub(crate) async fn open_sidecar_stream_attempt(
         Err(err) => return Err(SidecarAttemptFailure::Error(err)),
     };
 
-    if let Ok(rid) = CURRENT_REQUEST_ID.try_with(|id| id.clone())
-        && let Ok(value) = reqwest::header::HeaderValue::from_str(&rid)
-    {
-        headers.insert("x-request-id", value);
-    }
+    correlation_headers(&mut headers);
 
     let client = match crate::util::http_client_no_timeout() {
         Ok(client) => client,
@@ -453,11 +449,7 @@ pub(crate) async fn terminal_sidecar_delete_call(
                 .map_err(|err| api_error(StatusCode::BAD_GATEWAY, err.to_string()))?;
             let mut headers = auth_headers(&token)
                 .map_err(|err| api_error(StatusCode::BAD_GATEWAY, err.to_string()))?;
-            if let Ok(rid) = CURRENT_REQUEST_ID.try_with(|id| id.clone())
-                && let Ok(value) = reqwest::header::HeaderValue::from_str(&rid)
-            {
-                headers.insert("x-request-id", value);
-            }
+            correlation_headers(&mut headers);
 
             let client = crate::util::http_client()
                 .map_err(|err| api_error(StatusCode::BAD_GATEWAY, err.to_string()))?
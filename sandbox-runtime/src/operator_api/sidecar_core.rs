@@ -174,11 +174,7 @@ pub(crate) async fn run_sidecar_patch_json_attempt(
     match tokio::time::timeout(timeout, async move {
         let url = build_url(&sidecar_url, &path)?;
         let mut headers = auth_headers(&token)?;
-        if let Ok(rid) = CURRENT_REQUEST_ID.try_with(|id| id.clone())
-            && let Ok(value) = reqwest::header::HeaderValue::from_str(&rid)
-        {
-            headers.insert("x-request-id", value);
-        }
+        correlation_headers(&mut headers);
 
         let response = crate::util::http_client()?
             .patch(url)
@@ -47,11 +47,11 @@ pub(crate) struct TerminalSessionDescriptor {
 }
 
 pub(crate) fn live_scope_sandbox(sandbox_id: &str) -> String {
-    format!("sandbox:{sandbox_id}")
+    chat_state::sandbox_scope(sandbox_id)
 }
 
 pub(crate) fn live_scope_instance(record: &SandboxRecord) -> String {
-    format!("instance:{}", record.id)
+    chat_state::instance_scope(&record.id)
 }
 
 pub(crate) fn chat_session_matches(session: &ChatSessionRecord, scope: &str, owner: &str) -> bool {
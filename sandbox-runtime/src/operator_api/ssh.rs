@@ -23,30 +23,54 @@ pub(crate) async fn detect_ssh_username(
 pub(crate) async fn run_ssh_provision(
     record: &SandboxRecord,
     req: &SshProvisionApiRequest,
+    actor: &str,
 ) -> Result<SshApiResponse, (StatusCode, Json<ApiError>)> {
-    let (username, parsed) =
-        runtime::provision_ssh_key(record, req.username.as_deref(), &req.public_key)
-            .await
-            .map_err(|e| api_error(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let expires_at = runtime::ssh_key_expiry(crate::util::now_ts(), req.expires_in_seconds)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let (username, parsed) = runtime::provision_ssh_key(
+        record,
+        req.username.as_deref(),
+        &req.public_key,
+        expires_at,
+        actor,
+    )
+    .await
+    .map_err(|e| api_error(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
     Ok(SshApiResponse {
         success: true,
         username,
         result: parsed,
+        expires_at,
     })
 }
 
 pub(crate) async fn run_ssh_revoke(
     record: &SandboxRecord,
     req: &SshRevokeApiRequest,
+    actor: &str,
 ) -> Result<SshApiResponse, (StatusCode, Json<ApiError>)> {
     let (username, parsed) =
-        runtime::revoke_ssh_key(record, req.username.as_deref(), &req.public_key)
+        runtime::revoke_ssh_key(record, req.username.as_deref(), &req.public_key, actor)
             .await
             .map_err(|e| api_error(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
     Ok(SshApiResponse {
         success: true,
         username,
         result: parsed,
+        expires_at: None,
+    })
+}
+
+pub(crate) async fn ssh_keys_response(
+    record: &SandboxRecord,
+) -> Result<SshKeysApiResponse, (StatusCode, Json<ApiError>)> {
+    let (username, keys) = runtime::list_authorized_ssh_keys(record)
+        .await
+        .map_err(|e| api_error(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    Ok(SshKeysApiResponse {
+        success: true,
+        username,
+        keys,
     })
 }
 
@@ -66,6 +90,16 @@ pub(crate) async fn sandbox_ssh_user_handler(
     ))
 }
 
+pub(crate) async fn sandbox_ssh_keys_handler(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    require_ssh(&record)?;
+    let resp = ssh_keys_response(&record).await?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
+}
+
 pub(crate) async fn sandbox_ssh_provision_handler(
     SessionAuth(address): SessionAuth,
     Path(sandbox_id): Path<String>,
@@ -75,7 +109,7 @@ pub(crate) async fn sandbox_ssh_provision_handler(
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
     let record = resolve_sandbox(&sandbox_id, &address)?;
     require_ssh(&record)?;
-    let resp = run_ssh_provision(&record, &req).await?;
+    let resp = run_ssh_provision(&record, &req, &address).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
 }
 
@@ -88,7 +122,7 @@ pub(crate) async fn sandbox_ssh_revoke_handler(
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
     let record = resolve_sandbox(&sandbox_id, &address)?;
     require_ssh(&record)?;
-    let resp = run_ssh_revoke(&record, &req).await?;
+    let resp = run_ssh_revoke(&record, &req, &address).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
 }
 
@@ -107,6 +141,15 @@ pub(crate) async fn instance_ssh_user_handler(
     ))
 }
 
+pub(crate) async fn instance_ssh_keys_handler(
+    SessionAuth(address): SessionAuth,
+) -> impl IntoResponse {
+    let record = resolve_instance(&address)?;
+    require_ssh(&record)?;
+    let resp = ssh_keys_response(&record).await?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
+}
+
 pub(crate) async fn instance_ssh_provision_handler(
     SessionAuth(address): SessionAuth,
     Json(req): Json<SshProvisionApiRequest>,
@@ -115,7 +158,7 @@ pub(crate) async fn instance_ssh_provision_handler(
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
     let record = resolve_instance(&address)?;
     require_ssh(&record)?;
-    let resp = run_ssh_provision(&record, &req).await?;
+    let resp = run_ssh_provision(&record, &req, &address).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
 }
 
@@ -127,6 +170,6 @@ pub(crate) async fn instance_ssh_revoke_handler(
         .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
     let record = resolve_instance(&address)?;
     require_ssh(&record)?;
-    let resp = run_ssh_revoke(&record, &req).await?;
+    let resp = run_ssh_revoke(&record, &req, &address).await?;
     Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(resp)))
 }
@@ -0,0 +1,160 @@
+//! Queued task route group: enqueue a task, poll it, stream its progress, or
+//! cancel it.
+
+use super::*;
+use crate::task_queue::{self, TaskRecord};
+use axum::response::Response;
+use axum::response::sse::{Event, KeepAlive, Sse};
+
+fn enqueue(
+    record: &SandboxRecord,
+    address: &str,
+    req: &TaskApiRequest,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<ApiError>)> {
+    req.validate()
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    check_model(&req.model, req.timeout_ms).map_err(model_rejection_error)?;
+    let task = task_queue::enqueue_task(record, address, req).map_err(classify_sandbox_error)?;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "task_id": task.id,
+            "sandbox_id": task.sandbox_id,
+            "status": task.status,
+            "timeout_ms": task.timeout_ms,
+        })),
+    ))
+}
+
+/// SSE of a task: a `progress` snapshot, then `progress` events until a
+/// final `done` event carrying the finished record.
+fn task_event_stream(task: TaskRecord) -> Response {
+    let snapshot = |event_type: &str, task: &TaskRecord| {
+        Ok::<_, std::convert::Infallible>(
+            Event::default()
+                .event(event_type)
+                .data(json!(task).to_string()),
+        )
+    };
+    let rx = (!task.status.is_terminal())
+        .then(|| task_queue::subscribe_task_events(&task.id))
+        .flatten();
+    let Some(rx) = rx else {
+        // Finished (or finished while subscribing): re-read the final state.
+        let task = task_queue::get_task(&task.sandbox_id, &task.id).unwrap_or(task);
+        let stream = tokio_stream::iter([snapshot("done", &task)]);
+        return Sse::new(stream).into_response();
+    };
+    let initial = tokio_stream::iter([snapshot("progress", &task)]);
+    let updates = tokio_stream::wrappers::BroadcastStream::new(rx)
+        .filter_map(|event| event.ok())
+        .map_while({
+            let mut done = false;
+            move |event| {
+                if done {
+                    return None;
+                }
+                done = event.event_type == "done";
+                Some(Ok(Event::default()
+                    .event(event.event_type)
+                    .data(event.payload.to_string())))
+            }
+        });
+    Sse::new(initial.chain(updates))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// POST /api/sandboxes/{sandbox_id}/tasks — queue a task and return its ID.
+pub(crate) async fn sandbox_task_enqueue_handler(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+    Json(req): Json<TaskApiRequest>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    enqueue(&record, &address, &req)
+}
+
+/// POST /api/sandbox/tasks — instance variant (`slot` in the body).
+pub(crate) async fn instance_task_enqueue_handler(
+    SessionAuth(address): SessionAuth,
+    Json(req): Json<TaskApiRequest>,
+) -> impl IntoResponse {
+    let record = resolve_instance_slot(&address, &req.slot)?;
+    enqueue(&record, &address, &req)
+}
+
+/// GET /api/sandboxes/{sandbox_id}/tasks/{task_id} — status, progress and,
+/// once finished, the result.
+pub(crate) async fn sandbox_task_get_handler(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, task_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let task = task_queue::get_task(&record.id, &task_id).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(task)))
+}
+
+/// GET /api/sandboxes/{sandbox_id}/tasks/{task_id}/stream — progress SSE.
+pub(crate) async fn sandbox_task_stream_handler(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, task_id)): Path<(String, String)>,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let task = task_queue::get_task(&record.id, &task_id).map_err(classify_sandbox_error)?;
+    Ok(task_event_stream(task))
+}
+
+/// Load a task of any sandbox the caller owns (instance routes span slots).
+fn owned_task(address: &str, task_id: &str) -> Result<TaskRecord, (StatusCode, Json<ApiError>)> {
+    let not_found = || api_error(StatusCode::NOT_FOUND, format!("Task '{task_id}' not found"));
+    let task = task_queue::tasks()
+        .and_then(|store| store.get(task_id))
+        .map_err(classify_sandbox_error)?
+        .ok_or_else(not_found)?;
+    // Hide tasks of sandboxes the caller does not own.
+    resolve_sandbox(&task.sandbox_id, address).map_err(|_| not_found())?;
+    Ok(task)
+}
+
+/// GET /api/sandbox/tasks/{task_id} — instance variant.
+pub(crate) async fn instance_task_get_handler(
+    SessionAuth(address): SessionAuth,
+    Path(task_id): Path<String>,
+) -> impl IntoResponse {
+    let task = owned_task(&address, &task_id)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(task)))
+}
+
+/// GET /api/sandbox/tasks/{task_id}/stream — instance variant.
+pub(crate) async fn instance_task_stream_handler(
+    SessionAuth(address): SessionAuth,
+    Path(task_id): Path<String>,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    Ok(task_event_stream(owned_task(&address, &task_id)?))
+}
+
+/// POST /api/sandboxes/{sandbox_id}/tasks/{task_id}/cancel — stop a queued or
+/// running task. Returns the cancelled record; finished tasks are a 400.
+pub(crate) async fn sandbox_task_cancel_handler(
+    SessionAuth(address): SessionAuth,
+    Path((sandbox_id, task_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let record = resolve_sandbox(&sandbox_id, &address)?;
+    let task = task_queue::cancel_task(&record.id, &task_id)
+        .await
+        .map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(task)))
+}
+
+/// POST /api/sandbox/tasks/{task_id}/cancel — instance variant.
+pub(crate) async fn instance_task_cancel_handler(
+    SessionAuth(address): SessionAuth,
+    Path(task_id): Path<String>,
+) -> impl IntoResponse {
+    let task = owned_task(&address, &task_id)?;
+    let task = task_queue::cancel_task(&task.sandbox_id, &task.id)
+        .await
+        .map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(task)))
+}
@@ -0,0 +1,50 @@
+//! TEE route group: attestation, deployment status, public-key release and
+//! sealed secrets.
+
+use super::*;
+
+/// Routes served when a TEE backend is configured.
+pub(crate) fn tee_routes(backend: std::sync::Arc<dyn crate::tee::TeeBackend>) -> Router {
+    // The read-only attestation and status routes are always available — they
+    // grant no trust by themselves.
+    let mut routes = Router::new()
+        .route(
+            "/api/sandboxes/{sandbox_id}/tee/attestation",
+            get(crate::tee::sealed_secrets_api::get_tee_attestation)
+                .post(crate::tee::sealed_secrets_api::post_tee_attestation),
+        )
+        .route(
+            "/api/sandboxes/{sandbox_id}/tee/status",
+            get(crate::tee::sealed_secrets_api::get_tee_status),
+        );
+
+    // The trust-granting routes (public-key release, sealed-secret injection)
+    // are mounted only when the server can fail closed: an allowlist is pinned
+    // OR the operator explicitly opted into client-side-only verification.
+    // With the default config and no allowlist they are not served at all, so
+    // a misconfigured operator cannot hand back unverified material.
+    if crate::tee::sealed_secrets_api::release_routes_enabled() {
+        routes = routes
+            .route(
+                "/api/sandboxes/{sandbox_id}/tee/public-key",
+                get(crate::tee::sealed_secrets_api::get_tee_public_key),
+            )
+            .route(
+                "/api/sandboxes/{sandbox_id}/tee/sealed-secrets",
+                post(crate::tee::sealed_secrets_api::inject_sealed_secrets),
+            );
+    } else {
+        tracing::warn!(
+            "TEE sealed-secret/public-key release routes disabled: no \
+             SANDBOX_TEE_EXPECTED_MEASUREMENTS allowlist is pinned. Set the allowlist, or set \
+             SANDBOX_TEE_REQUIRE_PINNED_MEASUREMENT=false to serve them under client-side-only \
+             verification."
+        );
+    }
+
+    routes
+        .layer(axum::Extension(
+            Some(backend) as Option<std::sync::Arc<dyn crate::tee::TeeBackend>>
+        ))
+        .layer(middleware::from_fn(rate_limit::write_rate_limit))
+}
@@ -0,0 +1,49 @@
+//! Sandbox template catalog route group.
+//!
+//! Listing is public so customers can discover templates before they have a
+//! sandbox (and therefore a session). Registering and removing templates is
+//! an operator action gated on the managing operator.
+
+use super::*;
+use crate::templates::{self, SandboxTemplate};
+
+/// GET /api/templates — list registered provisioning templates.
+pub(crate) async fn list_templates_handler() -> impl IntoResponse {
+    match templates::list_templates() {
+        Ok(list) => (StatusCode::OK, Json(json!({ "templates": list }))).into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+/// POST /api/templates — register or overwrite a template.
+pub(crate) async fn register_template_handler(
+    SessionAuth(address): SessionAuth,
+    Json(template): Json<SandboxTemplate>,
+) -> impl IntoResponse {
+    if let Err(e) = require_managing_operator(&address) {
+        return e.into_response();
+    }
+    match templates::register_template(template) {
+        Ok(saved) => (StatusCode::OK, Json(saved)).into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
+
+/// DELETE /api/templates/{name} — remove a template. Existing sandboxes
+/// created from it are unaffected.
+pub(crate) async fn delete_template_handler(
+    SessionAuth(address): SessionAuth,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    if let Err(e) = require_managing_operator(&address) {
+        return e.into_response();
+    }
+    match templates::remove_template(&name) {
+        Ok(removed) => (
+            StatusCode::OK,
+            Json(json!({ "name": removed.name, "deleted": true })),
+        )
+            .into_response(),
+        Err(e) => classify_sandbox_error(e).into_response(),
+    }
+}
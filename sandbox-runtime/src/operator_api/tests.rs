@@ -26,6 +26,7 @@ fn init() {
 fn reset_test_state() {
     crate::session_auth::clear_all_for_testing();
     crate::circuit_breaker::clear_all_for_testing();
+    crate::http::breaker::clear_all_for_testing();
     crate::provision_progress::clear_all_for_testing().expect("clear provision state");
     crate::chat_state::clear_all_for_testing().expect("clear chat state");
     sandboxes()
@@ -996,6 +997,79 @@ async fn test_provision_lifecycle() {
     .unwrap();
 }
 
+#[serial_test::serial]
+#[tokio::test]
+async fn test_provision_events_stream_pull_progress_until_done() {
+    init();
+    reset_test_state();
+
+    let call_id = 77778;
+    provision_progress::start_provision(call_id).unwrap();
+    provision_progress::update_provision(
+        call_id,
+        provision_progress::ProvisionPhase::ImagePull,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/provisions/{call_id}/events"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    provision_progress::update_image_pull_progress(
+        call_id,
+        provision_progress::ImagePullProgress {
+            layers_total: 2,
+            layers_done: 1,
+            bytes_total: 100,
+            bytes_done: 50,
+        },
+    )
+    .unwrap();
+    provision_progress::update_provision(
+        call_id,
+        provision_progress::ProvisionPhase::Ready,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+
+    // The stream ends by itself after the `done` event.
+    let bytes = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        response.into_body().collect(),
+    )
+    .await
+    .expect("stream should end after done")
+    .unwrap()
+    .to_bytes();
+    let text = String::from_utf8_lossy(&bytes);
+    assert!(text.contains("\"progress_pct\":30"));
+    assert!(text.contains("\"layers_done\":1"));
+    assert!(text.contains("event: done"));
+
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/provisions/999998/events")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
 #[serial_test::serial]
 #[tokio::test]
 async fn test_auth_challenge_returns_nonce() {
@@ -1021,6 +1095,40 @@ async fn test_auth_challenge_returns_nonce() {
     assert!(json["nonce"].as_str().unwrap().len() == 64); // 32 bytes hex
 }
 
+#[serial_test::serial]
+#[tokio::test]
+async fn test_auth_challenge_binds_address_and_chain() {
+    let _guard = crate::session_auth::capacity_test_lock_async().await;
+    crate::session_auth::clear_all_for_testing();
+
+    let challenge = |body: serde_json::Value| {
+        app().oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/auth/challenge")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+    };
+
+    let address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+    let response = challenge(serde_json::json!({ "address": address, "chain_id": 31337 }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response.into_body()).await;
+    let lowered = address.to_ascii_lowercase();
+    assert_eq!(json["address"], lowered.as_str());
+    assert_eq!(json["chain_id"], 31337);
+    assert!(json["message"].as_str().unwrap().contains(&lowered));
+
+    let response = challenge(serde_json::json!({ "address": "not-an-address" }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
 #[serial_test::serial]
 #[tokio::test]
 async fn test_auth_session_invalid_sig() {
@@ -1213,6 +1321,78 @@ async fn test_cors_preflight_for_extra_routes() {
     );
 }
 
+#[serial_test::serial]
+#[tokio::test]
+async fn test_cors_policy_is_configurable_per_route_group() {
+    let _default = EnvVarGuard::set("CORS_ALLOWED_ORIGINS", "https://app.example.com");
+    let _public = EnvVarGuard::set("CORS_PUBLIC_ALLOWED_ORIGINS", "*");
+    let _auth = EnvVarGuard::set("CORS_AUTH_ALLOWED_ORIGINS", "none");
+    let _secrets = EnvVarGuard::remove("CORS_SECRETS_ALLOWED_ORIGINS");
+    let app = app();
+    let preflight = |uri: &str, origin: &str, method: &str| {
+        let app = app.clone();
+        let request = Request::builder()
+            .method("OPTIONS")
+            .uri(uri)
+            .header("origin", origin)
+            .header("access-control-request-method", method)
+            .body(Body::empty())
+            .unwrap();
+        async move {
+            app.oneshot(request)
+                .await
+                .unwrap()
+                .headers()
+                .get("access-control-allow-origin")
+                .map(|v| v.to_str().unwrap().to_string())
+        }
+    };
+
+    assert_eq!(
+        preflight("/health", "https://evil.example", "GET")
+            .await
+            .as_deref(),
+        Some("*")
+    );
+    assert_eq!(
+        preflight("/api/sandboxes", "https://app.example.com", "GET")
+            .await
+            .as_deref(),
+        Some("https://app.example.com")
+    );
+    assert_eq!(
+        preflight("/api/sandboxes", "https://evil.example", "GET").await,
+        None
+    );
+    // Secrets fall back to CORS_ALLOWED_ORIGINS.
+    assert_eq!(
+        preflight("/api/secrets", "https://app.example.com", "GET")
+            .await
+            .as_deref(),
+        Some("https://app.example.com")
+    );
+    // Disabled: no CORS headers at all.
+    assert_eq!(
+        preflight("/api/auth/challenge", "https://app.example.com", "POST").await,
+        None
+    );
+}
+
+#[test]
+fn test_cors_policy_parse() {
+    assert_eq!(CorsPolicy::parse(""), CorsPolicy::Localhost);
+    assert_eq!(CorsPolicy::parse(" NONE "), CorsPolicy::Disabled);
+    assert!(CorsPolicy::parse("none").layer().is_none());
+    assert_eq!(CorsPolicy::parse("*"), CorsPolicy::Any);
+    assert_eq!(
+        CorsPolicy::parse("https://a.example, https://b.example,"),
+        CorsPolicy::Origins(vec![
+            "https://a.example".to_string(),
+            "https://b.example".to_string()
+        ])
+    );
+}
+
 // ── TEE sealed secrets API tests ──────────────────────────────────────
 
 fn tee_app() -> Router {
@@ -1282,6 +1462,11 @@ fn insert_tee_sandbox(id: &str, deployment_id: &str, owner: &str) {
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     };
     seal_record(&mut record).unwrap();
     sandboxes().unwrap().insert(id.to_string(), record).unwrap();
@@ -1335,6 +1520,11 @@ fn insert_plain_sandbox_with_state_and_url(
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     };
     seal_record(&mut record).unwrap();
     sandboxes().unwrap().insert(id.to_string(), record).unwrap();
@@ -1579,6 +1769,32 @@ async fn test_tee_attestation_accepts_nonce_challenge() {
     assert!(json["attestation"]["tee_type"].is_string());
 }
 
+#[serial_test::serial]
+#[tokio::test]
+async fn test_tee_status_reports_state_and_accrued_cost() {
+    insert_tee_sandbox("tee-status-1", "deploy-status-1", TEE_TEST_OWNER);
+    let auth = format!("Bearer {}", session_auth::create_test_token(TEE_TEST_OWNER));
+
+    let response = tee_app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/sandboxes/tee-status-1/tee/status")
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response.into_body()).await;
+    assert_eq!(json["sandbox_id"], "tee-status-1");
+    assert_eq!(json["status"]["deployment_id"], "deploy-status-1");
+    assert_eq!(json["status"]["state"], "running");
+    assert_eq!(json["billing"]["billed_secs"], 7200);
+    assert_eq!(json["billing"]["accrued_cost"], 1.0);
+}
+
 #[serial_test::serial]
 #[tokio::test]
 async fn test_tee_routes_absent_without_backend() {
@@ -1710,6 +1926,149 @@ async fn test_sandbox_exec_wrong_owner() {
     assert_eq!(response.status(), StatusCode::FORBIDDEN);
 }
 
+#[serial_test::serial]
+#[tokio::test]
+async fn test_prompt_on_busy_sandbox_is_rejected() {
+    insert_plain_sandbox("op-busy-1", OP_TEST_OWNER);
+    // Fill the sandbox's run slots and queue.
+    let mut held = Vec::new();
+    while let Ok(slot) = crate::agent_runs::reserve_agent_run("op-busy-1") {
+        held.push(slot);
+    }
+    assert_eq!(
+        held.len(),
+        crate::agent_runs::agent_run_concurrency() + crate::agent_runs::agent_run_queue_depth()
+    );
+    let auth = format!("Bearer {}", session_auth::create_test_token(OP_TEST_OWNER));
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/sandboxes/op-busy-1/prompt")
+                .header("authorization", &auth)
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "message": "hello" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let json = body_json(response.into_body()).await;
+    assert_eq!(json["code"], "SANDBOX_BUSY");
+    assert!(json["error"].as_str().unwrap().contains("busy"));
+    drop(held);
+    assert_eq!(crate::agent_runs::agent_run_load("op-busy-1"), (0, 0));
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_task_cancel_rejects_missing_and_finished_tasks() {
+    insert_plain_sandbox("op-task-cancel-1", OP_TEST_OWNER);
+    let task: crate::task_queue::TaskRecord = serde_json::from_value(json!({
+        "id": "task-op-cancel-done",
+        "sandbox_id": "op-task-cancel-1",
+        "owner": OP_TEST_OWNER,
+        "prompt": "hi",
+        "timeout_ms": 1000,
+        "status": "completed",
+        "created_at": 1,
+        "completed_at": 2,
+    }))
+    .unwrap();
+    crate::task_queue::tasks()
+        .unwrap()
+        .insert(task.id.clone(), task)
+        .unwrap();
+    let auth = format!("Bearer {}", session_auth::create_test_token(OP_TEST_OWNER));
+    let cancel = |uri: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("authorization", &auth)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let missing = app()
+        .oneshot(cancel(
+            "/api/sandboxes/op-task-cancel-1/tasks/task-missing/cancel",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+    let finished = app()
+        .oneshot(cancel(
+            "/api/sandboxes/op-task-cancel-1/tasks/task-op-cancel-done/cancel",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(finished.status(), StatusCode::BAD_REQUEST);
+    let json = body_json(finished.into_body()).await;
+    assert!(json["error"].as_str().unwrap().contains("already finished"));
+
+    let instance = app()
+        .oneshot(cancel("/api/sandbox/tasks/task-op-cancel-done/cancel"))
+        .await
+        .unwrap();
+    assert_eq!(instance.status(), StatusCode::BAD_REQUEST);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_exec_presets_are_write_only_and_resolved_by_name() {
+    insert_plain_sandbox("op-preset-1", OP_TEST_OWNER);
+    let auth = format!("Bearer {}", session_auth::create_test_token(OP_TEST_OWNER));
+    let put = app()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/api/sandboxes/op-preset-1/exec-presets/build")
+                .header("authorization", &auth)
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "cwd": "/workspace", "env": { "TOKEN": "s3cret" } }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(put.status(), StatusCode::OK);
+    let json = body_json(put.into_body()).await;
+    assert_eq!(json["cwd"], "/workspace");
+    assert_ne!(json["env"]["TOKEN"], "s3cret");
+
+    let list = app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/sandboxes/op-preset-1/exec-presets")
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let json = body_json(list.into_body()).await;
+    assert_eq!(json["presets"][0]["name"], "build");
+    assert!(!json.to_string().contains("s3cret"));
+
+    let exec = app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/sandboxes/op-preset-1/exec")
+                .header("authorization", &auth)
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "command": "make", "preset": "release" }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(exec.status(), StatusCode::NOT_FOUND);
+}
+
 #[serial_test::serial]
 #[tokio::test]
 async fn test_instance_exec_no_sandbox() {
@@ -1736,6 +2095,64 @@ async fn test_instance_exec_no_sandbox() {
     assert_ne!(response.status(), StatusCode::OK);
 }
 
+#[serial_test::serial]
+#[tokio::test]
+async fn test_instance_slots_list_and_exec_slot_resolution() {
+    insert_instance_sandbox("inst-slot-main", OP_TEST_OWNER);
+    insert_plain_sandbox_with_url("inst-slot-w1", OP_TEST_OWNER, "http://localhost:9999");
+    let mut worker = sandboxes()
+        .unwrap()
+        .get("inst-slot-w1")
+        .unwrap()
+        .expect("sandbox exists");
+    runtime::unseal_record(&mut worker).unwrap();
+    runtime::set_instance_sandbox_slot("worker-1", worker).unwrap();
+    let auth = format!("Bearer {}", session_auth::create_test_token(OP_TEST_OWNER));
+
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/sandbox/slots")
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response.into_body()).await;
+    let slots = json["slots"].as_array().unwrap();
+    assert_eq!(slots[0]["slot"], "main");
+    assert_eq!(slots[0]["sandbox"]["id"], "inst-slot-main");
+    assert!(
+        slots
+            .iter()
+            .any(|s| s["slot"] == "worker-1" && s["sandbox"]["id"] == "inst-slot-w1")
+    );
+
+    for (slot, expected) in [
+        ("missing", StatusCode::NOT_FOUND),
+        ("Bad Slot", StatusCode::BAD_REQUEST),
+    ] {
+        let body = serde_json::json!({ "command": "echo hello", "slot": slot });
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/sandbox/exec")
+                    .header("authorization", &auth)
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected, "slot {slot}");
+    }
+
+    runtime::remove_instance_sandbox_slot("worker-1").unwrap();
+}
+
 #[serial_test::serial]
 #[tokio::test]
 async fn test_instance_secrets_empty_env_rejected() {
@@ -1851,6 +2268,69 @@ async fn test_sandbox_prompt_requires_auth() {
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
 
+#[serial_test::serial]
+#[tokio::test]
+async fn test_sandbox_restart_requires_auth() {
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/sandboxes/some-id/restart")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_sandbox_token_rotate_requires_auth() {
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/sandboxes/some-id/token/rotate")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_templates_list_is_public_and_register_requires_auth() {
+    init();
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/templates")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response.into_body()).await;
+    assert!(json["templates"].is_array(), "unexpected body: {json}");
+
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/templates")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"name":"python-small"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
 #[serial_test::serial]
 #[tokio::test]
 async fn test_instance_routes_exist() {
@@ -1863,6 +2343,8 @@ async fn test_instance_routes_exist() {
         "/api/sandbox/secrets",
         "/api/sandbox/stop",
         "/api/sandbox/resume",
+        "/api/sandbox/restart",
+        "/api/sandbox/token/rotate",
         "/api/sandbox/snapshot",
     ] {
         let response = app()
@@ -2013,81 +2495,214 @@ async fn test_live_terminal_session_sandbox_crud_and_stream() {
     let created = body_json(create.into_body()).await;
     let session_id = created["session_id"].as_str().unwrap().to_string();
 
-    let list = app()
+    let list = app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/sandboxes/live-term-1/live/terminal/sessions")
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(list.status(), StatusCode::OK);
+    let listed = body_json(list.into_body()).await;
+    let ids: Vec<&str> = listed["sessions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|v| v.get("session_id").and_then(|s| s.as_str()))
+        .collect();
+    assert!(ids.iter().any(|id| *id == session_id));
+
+    let stream = app()
+        .oneshot(
+            Request::builder()
+                .uri(format!(
+                    "/api/sandboxes/live-term-1/live/terminal/sessions/{session_id}/stream"
+                ))
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(stream.status(), StatusCode::OK);
+    let ct = stream
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    assert!(ct.contains("text/event-stream"));
+
+    let deleted = app()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!(
+                    "/api/sandboxes/live-term-1/live/terminal/sessions/{session_id}"
+                ))
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(deleted.status(), StatusCode::OK);
+
+    let create_payload = sidecar_state
+        .last_terminal_create_payload
+        .lock()
+        .expect("terminal create payload lock")
+        .clone()
+        .expect("terminal create payload");
+    assert_eq!(
+        create_payload,
+        json!({
+            "env": {
+                "PS1": "\\u:\\w\\$ ",
+                "PROMPT_DIRTRIM": "0",
+            },
+            "cwd": "/home/sidecar",
+            "cols": 132,
+            "rows": 40,
+        })
+    );
+
+    server.abort();
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_terminal_token_is_scoped_to_terminal_relay_of_one_sandbox() {
+    let (sidecar_url, _sidecar_state, server) = spawn_mock_sidecar().await;
+    insert_plain_sandbox_with_url("live-termtok-1", OP_TEST_OWNER, &sidecar_url);
+    insert_plain_sandbox_with_url("live-termtok-2", OP_TEST_OWNER, &sidecar_url);
+    let session = session_auth::create_test_token(OP_TEST_OWNER);
+
+    let get = |uri: String, auth: Option<String>| {
+        let mut req = Request::builder().uri(uri);
+        if let Some(auth) = auth {
+            req = req.header("authorization", auth);
+        }
+        app().oneshot(req.body(Body::empty()).unwrap())
+    };
+
+    let minted = app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/sandboxes/live-termtok-1/terminal/token")
+                .header("authorization", format!("Bearer {session}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(minted.status(), StatusCode::OK);
+    let minted = body_json(minted.into_body()).await;
+    let token = minted["token"].as_str().unwrap().to_string();
+    assert_eq!(minted["sandbox_id"], "live-termtok-1");
+    assert_ne!(token, session);
+
+    let sessions = "live/terminal/sessions";
+    let ok = get(
+        format!("/api/sandboxes/live-termtok-1/{sessions}?token={token}"),
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(ok.status(), StatusCode::OK);
+
+    let other = get(
+        format!("/api/sandboxes/live-termtok-2/{sessions}?token={token}"),
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(other.status(), StatusCode::FORBIDDEN);
+
+    let session_in_query = get(
+        format!("/api/sandboxes/live-termtok-1/{sessions}?token={session}"),
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(session_in_query.status(), StatusCode::UNAUTHORIZED);
+
+    let outside_terminal = get(
+        "/api/sandboxes".to_string(),
+        Some(format!("Bearer {token}")),
+    )
+    .await
+    .unwrap();
+    assert_eq!(outside_terminal.status(), StatusCode::UNAUTHORIZED);
+
+    server.abort();
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_terminal_token_requires_session_auth() {
+    let response = app()
         .oneshot(
             Request::builder()
-                .uri("/api/sandboxes/live-term-1/live/terminal/sessions")
-                .header("authorization", &auth)
+                .method("POST")
+                .uri("/api/sandboxes/some-id/terminal/token")
                 .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
-    assert_eq!(list.status(), StatusCode::OK);
-    let listed = body_json(list.into_body()).await;
-    let ids: Vec<&str> = listed["sessions"]
-        .as_array()
-        .unwrap()
-        .iter()
-        .filter_map(|v| v.get("session_id").and_then(|s| s.as_str()))
-        .collect();
-    assert!(ids.iter().any(|id| *id == session_id));
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
 
-    let stream = app()
+#[serial_test::serial]
+#[tokio::test]
+async fn test_file_list_requires_auth_and_rejects_traversal() {
+    let response = app()
         .oneshot(
             Request::builder()
-                .uri(format!(
-                    "/api/sandboxes/live-term-1/live/terminal/sessions/{session_id}/stream"
-                ))
-                .header("authorization", &auth)
+                .uri("/api/sandboxes/some-id/files/list?path=src")
                 .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
-    assert_eq!(stream.status(), StatusCode::OK);
-    let ct = stream
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    assert!(ct.contains("text/event-stream"));
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 
-    let deleted = app()
+    insert_instance_sandbox("files-inst-1", OP_TEST_OWNER);
+    let auth = format!("Bearer {}", session_auth::create_test_token(OP_TEST_OWNER));
+    let response = app()
         .oneshot(
             Request::builder()
-                .method("DELETE")
-                .uri(format!(
-                    "/api/sandboxes/live-term-1/live/terminal/sessions/{session_id}"
-                ))
+                .uri("/api/sandbox/files/list?path=src/../../etc")
                 .header("authorization", &auth)
                 .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
-    assert_eq!(deleted.status(), StatusCode::OK);
-
-    let create_payload = sidecar_state
-        .last_terminal_create_payload
-        .lock()
-        .expect("terminal create payload lock")
-        .clone()
-        .expect("terminal create payload");
-    assert_eq!(
-        create_payload,
-        json!({
-            "env": {
-                "PS1": "\\u:\\w\\$ ",
-                "PROMPT_DIRTRIM": "0",
-            },
-            "cwd": "/home/sidecar",
-            "cols": 132,
-            "rows": 40,
-        })
-    );
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
 
-    server.abort();
+#[test]
+fn test_parse_file_listing_derives_paths_and_sorts() {
+    let parsed = serde_json::json!({
+        "entries": [
+            {"name": "b.txt", "type": "file", "size": 3, "mtime": 10},
+            {"name": "..", "type": "directory", "size": 0, "mtime": 0},
+            {"name": "a/../../x", "type": "file", "size": 0, "mtime": 0},
+            {"name": "src", "type": "directory", "size": 0, "mtime": 5},
+            {"name": "a.txt", "type": "file", "size": 1, "mtime": 1},
+        ],
+        "truncated": false,
+    });
+    let listing = parse_file_listing("app", &parsed).unwrap();
+    let paths: Vec<&str> = listing.entries.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, ["app/src", "app/a.txt", "app/b.txt"]);
+    assert!(!listing.truncated);
+    assert!(parse_file_listing("", &serde_json::json!({})).is_err());
 }
 
 #[serial_test::serial]
@@ -3452,39 +4067,495 @@ fn insert_sandbox_with_ports(id: &str, owner: &str, ports: std::collections::Has
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
+    };
+    seal_record(&mut record).unwrap();
+    sandboxes().unwrap().insert(id.to_string(), record).unwrap();
+}
+
+fn insert_sandbox_for_listing(id: &str, owner: &str, service_id: Option<u64>) {
+    insert_sandbox_with_ports(id, owner, std::collections::HashMap::new());
+    sandboxes()
+        .unwrap()
+        .update(id, |record| {
+            record.service_id = service_id;
+        })
+        .unwrap();
+}
+
+// =====================================================================
+// Phase 1A: Port Proxy Handler Tests
+// =====================================================================
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_sandbox_port_proxy_requires_auth() {
+    init();
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/sandboxes/any-id/port/8080/some/path")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_job_replay_export_is_managing_operator_only() {
+    init();
+    let operator = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+    let _operator_address = EnvVarGuard::remove("OPERATOR_ADDRESS");
+    let _keystore_uri = EnvVarGuard::remove("KEYSTORE_URI");
+    let auth = format!("Bearer {}", session_auth::create_test_token(operator));
+    let export = || {
+        Request::builder()
+            .uri("/api/operator/audit/jobs?after=0&limit=10")
+            .header("authorization", &auth)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let other = EnvVarGuard::set(
+        "MANAGING_OPERATOR_ADDRESS",
+        "0x1234567890abcdef1234567890abcdef12345678",
+    );
+    let response = app().oneshot(export()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    drop(other);
+
+    let _managing_operator = EnvVarGuard::set("MANAGING_OPERATOR_ADDRESS", operator);
+    let response = app().oneshot(export()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    assert_eq!(response.headers()["x-replay-chain-valid"], "true");
+}
+
+// Starting a real drain would refuse creates in tests running in parallel, so
+// this covers auth, validation and status only.
+#[serial_test::serial]
+#[tokio::test]
+async fn test_drain_endpoints_are_managing_operator_only_and_validate_window() {
+    init();
+    let operator = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+    let _operator_address = EnvVarGuard::remove("OPERATOR_ADDRESS");
+    let _keystore_uri = EnvVarGuard::remove("KEYSTORE_URI");
+    let _drain_mode = EnvVarGuard::remove("OPERATOR_DRAIN_MODE");
+    let auth = format!("Bearer {}", session_auth::create_test_token(operator));
+    let status = || {
+        Request::builder()
+            .uri("/api/operator/drain")
+            .header("authorization", &auth)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let other = EnvVarGuard::set(
+        "MANAGING_OPERATOR_ADDRESS",
+        "0x1234567890abcdef1234567890abcdef12345678",
+    );
+    let response = app().oneshot(status()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    drop(other);
+
+    let _managing_operator = EnvVarGuard::set("MANAGING_OPERATOR_ADDRESS", operator);
+    let too_long = crate::drain::MAX_DRAIN_WINDOW_SECS + 1;
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/operator/drain")
+                .header("authorization", &auth)
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "window_secs": too_long }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app().oneshot(status()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response.into_body()).await;
+    assert_eq!(body["draining"], false);
+    assert!(body["drain"].is_null());
+    assert!(crate::drain::ensure_accepting_provisions().is_ok());
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_auto_provision_failures_are_reported_to_managing_operator() {
+    init();
+    let operator = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+    let _operator_address = EnvVarGuard::remove("OPERATOR_ADDRESS");
+    let _keystore_uri = EnvVarGuard::remove("KEYSTORE_URI");
+    let _managing_operator = EnvVarGuard::set("MANAGING_OPERATOR_ADDRESS", operator);
+    let auth = format!("Bearer {}", session_auth::create_test_token(operator));
+    let list = || async {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/operator/auto-provision")
+                    .header("authorization", &auth)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        body_json(response.into_body()).await
+    };
+
+    let service_id = 990_001;
+    crate::auto_provision_status::record_auto_provision_failure(service_id, 3, "image pull failed")
+        .unwrap();
+    crate::auto_provision_status::record_auto_provision_failure_report(
+        service_id,
+        Ok("0xabc".into()),
+    )
+    .unwrap();
+    let body = list().await;
+    let failure = body["failures"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["service_id"] == service_id)
+        .cloned()
+        .unwrap();
+    assert_eq!(failure["attempts"], 3);
+    assert_eq!(failure["last_error"], "image pull failed");
+    assert_eq!(failure["reported_tx"], "0xabc");
+
+    crate::auto_provision_status::clear_auto_provision_failure(service_id).unwrap();
+    let body = list().await;
+    assert!(
+        !body["failures"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|f| f["service_id"] == service_id)
+    );
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_failed_sign_ins_lock_out_the_client_ip_until_cleared() {
+    init();
+    let _guard = crate::session_auth::capacity_test_lock_async().await;
+    crate::session_auth::clear_all_for_testing();
+    let operator = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+    let _operator_address = EnvVarGuard::remove("OPERATOR_ADDRESS");
+    let _keystore_uri = EnvVarGuard::remove("KEYSTORE_URI");
+    let _managing_operator = EnvVarGuard::set("MANAGING_OPERATOR_ADDRESS", operator);
+    let auth = format!("Bearer {}", session_auth::create_test_token(operator));
+    let ip = "203.0.113.77";
+    let sign_in = || async {
+        app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/auth/session")
+                    .header("content-type", "application/json")
+                    .header("x-forwarded-for", ip)
+                    .body(Body::from(
+                        json!({ "nonce": "unknown", "signature": "0xdeadbeef" }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    };
+
+    for _ in 0..session_auth::MAX_AUTH_FAILURES {
+        assert_eq!(sign_in().await.status(), StatusCode::UNAUTHORIZED);
+    }
+    let response = sign_in().await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/auth/challenge")
+                .header("x-forwarded-for", ip)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/operator/auth/lockouts")
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response.into_body()).await;
+    let lockout = &body["lockouts"][0];
+    assert_eq!(lockout["kind"], "ip");
+    assert_eq!(lockout["value"], ip);
+    assert_eq!(lockout["lockouts"], 1);
+    assert!(lockout["locked_until"].is_u64());
+
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/api/operator/auth/lockouts/{ip}"))
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response.into_body()).await["cleared"], true);
+    assert_eq!(sign_in().await.status(), StatusCode::UNAUTHORIZED);
+    crate::session_auth::clear_all_for_testing();
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_capacity_is_managing_operator_only_and_reports_limits() {
+    init();
+    let operator = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+    let _operator_address = EnvVarGuard::remove("OPERATOR_ADDRESS");
+    let _keystore_uri = EnvVarGuard::remove("KEYSTORE_URI");
+    let _managing_operator = EnvVarGuard::set("MANAGING_OPERATOR_ADDRESS", operator);
+    let get = |address: &'static str| async move {
+        app()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/operator/capacity")
+                    .header(
+                        "authorization",
+                        format!("Bearer {}", session_auth::create_test_token(address)),
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
     };
-    seal_record(&mut record).unwrap();
-    sandboxes().unwrap().insert(id.to_string(), record).unwrap();
+
+    let response = get("0x1111111111111111111111111111111111111111").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = get(operator).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response.into_body()).await;
+    let config = crate::runtime::SidecarRuntimeConfig::load();
+    assert_eq!(body["max_sandboxes"], config.sandbox_max_count);
+    assert_eq!(body["operator_max_capacity"], config.operator_max_capacity);
+    assert!(body["sandboxes"].is_u64());
+    assert!(body["running_memory_mb"].is_u64());
 }
 
-fn insert_sandbox_for_listing(id: &str, owner: &str, service_id: Option<u64>) {
-    insert_sandbox_with_ports(id, owner, std::collections::HashMap::new());
-    sandboxes()
-        .unwrap()
-        .update(id, |record| {
-            record.service_id = service_id;
-        })
-        .unwrap();
+#[serial_test::serial]
+#[tokio::test]
+async fn test_image_cache_status_is_managing_operator_only() {
+    init();
+    let operator = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+    let _operator_address = EnvVarGuard::remove("OPERATOR_ADDRESS");
+    let _keystore_uri = EnvVarGuard::remove("KEYSTORE_URI");
+    let _managing_operator = EnvVarGuard::set("MANAGING_OPERATOR_ADDRESS", operator);
+    let get = |address: &'static str| async move {
+        app()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/operator/images")
+                    .header(
+                        "authorization",
+                        format!("Bearer {}", session_auth::create_test_token(address)),
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    };
+
+    let response = get("0x1111111111111111111111111111111111111111").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = get(operator).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response.into_body()).await;
+    assert!(body["images"].is_array());
+    assert!(body["layers_bytes"].is_u64());
+    assert!(body["pruned"].is_array());
 }
 
-// =====================================================================
-// Phase 1A: Port Proxy Handler Tests
-// =====================================================================
+#[serial_test::serial]
+#[tokio::test]
+async fn test_owner_quota_overrides_show_in_usage() {
+    init();
+    reset_test_state();
+    let operator = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+    let owner = "0x1234567890abcdef1234567890abcdef12345678";
+    let _operator_address = EnvVarGuard::remove("OPERATOR_ADDRESS");
+    let _keystore_uri = EnvVarGuard::remove("KEYSTORE_URI");
+    let _managing_operator = EnvVarGuard::set("MANAGING_OPERATOR_ADDRESS", operator);
+    let send = |method: &'static str, uri: String, address: &'static str, body: Body| async move {
+        app()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(
+                        "authorization",
+                        format!("Bearer {}", session_auth::create_test_token(address)),
+                    )
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    };
+    let quota_uri = format!("/api/operator/quotas/{owner}");
+    let quota_body = || Body::from(r#"{"max_sandboxes":2,"max_concurrent_tasks":1}"#);
+
+    // Only the managing operator sets quotas.
+    let response = send("PUT", quota_uri.clone(), owner, quota_body()).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = send("PUT", quota_uri.clone(), operator, quota_body()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = send("GET", "/api/usage".into(), owner, Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response.into_body()).await;
+    assert_eq!(body["quota"]["max_sandboxes"], 2);
+    assert_eq!(body["quota"]["max_concurrent_tasks"], 1);
+    assert_eq!(body["quota"]["max_memory_mb"], 0);
+    assert_eq!(body["usage"]["sandboxes"], 0);
+
+    let response = send("DELETE", quota_uri.clone(), operator, Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = send("DELETE", quota_uri, operator, Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
 
 #[serial_test::serial]
 #[tokio::test]
-async fn test_sandbox_port_proxy_requires_auth() {
+async fn test_usage_history_returns_the_callers_months() {
     init();
+    reset_test_state();
+    let owner = "0x1234567890abcdef1234567890abcdef12345678";
+    crate::usage_ledger::record_usage(owner, crate::usage_ledger::UsageTotals::exec());
+    crate::usage_ledger::flush_usage(crate::util::now_ts()).unwrap();
+    crate::usage_ledger::record_usage(owner, crate::usage_ledger::UsageTotals::tokens(10, 5));
+
     let response = app()
         .oneshot(
             Request::builder()
-                .uri("/api/sandboxes/any-id/port/8080/some/path")
+                .uri("/api/usage/history")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", session_auth::create_test_token(owner)),
+                )
                 .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
-    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response.into_body()).await;
+    let months = body["months"].as_array().unwrap();
+    let current = months.last().unwrap();
+    assert_eq!(
+        current["month"],
+        crate::usage_ledger::month_key(crate::util::now_ts())
+    );
+    assert!(current["exec_count"].as_u64().unwrap() >= 1);
+    assert!(current["input_tokens"].as_u64().unwrap() >= 10);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_named_secrets_are_write_only_and_owner_scoped() {
+    init();
+    reset_test_state();
+    let owner = "0x1234567890abcdef1234567890abcdef12345678";
+    let other = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+    let send = |method: &'static str, uri: &'static str, address: &'static str, body: Body| async move {
+        app()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(
+                        "authorization",
+                        format!("Bearer {}", session_auth::create_test_token(address)),
+                    )
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    };
+
+    let response = send(
+        "PUT",
+        "/api/secrets/report-api-key",
+        owner,
+        Body::from(r#"{"value":"sk-live-123"}"#),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = send("GET", "/api/secrets", owner, Body::empty()).await;
+    let body = body_json(response.into_body()).await;
+    assert_eq!(body["secrets"][0]["name"], "report-api-key");
+    assert!(!body.to_string().contains("sk-live-123"));
+
+    // Placeholders resolve against the owner's secrets only.
+    let mut workflow = json!({"prompt": "p", "context": {"key": {"secretRef": "report-api-key"}}});
+    crate::secret_provisioning::validate_secret_placeholders(owner, &workflow).unwrap();
+    assert!(crate::secret_provisioning::validate_secret_placeholders(other, &workflow).is_err());
+    crate::secret_provisioning::resolve_secret_placeholders(owner, &mut workflow)
+        .await
+        .unwrap();
+    assert_eq!(workflow["context"]["key"], "sk-live-123");
+
+    let response = send(
+        "DELETE",
+        "/api/secrets/report-api-key",
+        other,
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = send(
+        "DELETE",
+        "/api/secrets/report-api-key",
+        owner,
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = send(
+        "PUT",
+        "/api/secrets/bad%20name",
+        owner,
+        Body::from(r#"{"value":"x"}"#),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
 #[serial_test::serial]
@@ -4487,6 +5558,8 @@ fn test_parse_detected_ssh_username_tolerates_terminal_noise() {
         exit_code: 0,
         stdout: "\u{1b}[?2004l\rsidecar\r\n\u{1b}[?2004hcontainer:/sidecar$ exit\r\n".to_string(),
         stderr: String::new(),
+        truncated: false,
+        overflow_ref: None,
     };
 
     let username = parse_detected_ssh_username(&exec).expect("username should parse");
@@ -4757,8 +5830,8 @@ async fn test_readyz_includes_runtime_backend() {
 #[tokio::test]
 async fn test_health_and_readyz_unauthenticated() {
     init();
-    // /health and /readyz should NOT require auth
-    for path in &["/health", "/readyz"] {
+    // /health, /readyz and /health/ready should NOT require auth
+    for path in &["/health", "/readyz", "/health/ready"] {
         let response = app()
             .clone()
             .oneshot(Request::builder().uri(*path).body(Body::empty()).unwrap())
@@ -4772,6 +5845,52 @@ async fn test_health_and_readyz_unauthenticated() {
     }
 }
 
+#[serial_test::serial]
+#[tokio::test]
+async fn test_health_ready_reports_components_and_rpc_failure_degrades() {
+    init();
+    let _http_rpc = EnvVarGuard::remove("HTTP_RPC_ENDPOINT");
+    let _rpc = EnvVarGuard::remove("RPC_URL");
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/health/ready")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = body_json(response.into_body()).await;
+    assert_eq!(body["components"]["store"]["status"], "ok");
+    assert_eq!(body["components"]["store"]["critical"], true);
+    assert_eq!(body["components"]["runtime"]["critical"], true);
+    assert_eq!(body["components"]["tangle_rpc"]["status"], "skipped");
+
+    // An unreachable RPC is not critical: degraded at worst, never unhealthy
+    // on its own account.
+    let _rpc = EnvVarGuard::set("RPC_URL", "http://127.0.0.1:1");
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/health/ready")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = body_json(response.into_body()).await;
+    assert_eq!(body["components"]["tangle_rpc"]["status"], "error");
+    assert_eq!(body["components"]["tangle_rpc"]["critical"], false);
+    if body["components"]["runtime"]["status"] == "ok" {
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "degraded");
+    } else {
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "unhealthy");
+    }
+}
+
 // =====================================================================
 // Phase 3D: Instance Store Sync Tests
 // =====================================================================
@@ -4846,3 +5965,266 @@ fn test_build_agent_payload_context_json_without_max_turns_override() {
     assert_eq!(backend.get("type").and_then(|v| v.as_str()), Some("gemini"));
     assert_eq!(backend.get("model").and_then(|v| v.as_str()), Some("gpt-4"));
 }
+
+#[test]
+fn test_model_rejection_error_carries_code() {
+    let (status, Json(body)) = model_rejection_error(ModelRejection {
+        code: crate::model_policy::MODEL_NOT_ALLOWED,
+        message: "model \"opus\" is not offered by this operator".into(),
+    });
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body.code.as_deref(), Some("MODEL_NOT_ALLOWED"));
+    assert!(body.error.contains("opus"));
+}
+
+async fn mcp_call(auth: &str, message: Value) -> Response {
+    app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/mcp")
+                .header("authorization", auth)
+                .header("content-type", "application/json")
+                .body(Body::from(message.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_mcp_lists_owned_sandboxes_and_runs_commands() {
+    let (sidecar_url, sidecar_state, server) = spawn_mock_sidecar().await;
+    insert_plain_sandbox_with_url("mcp-owned", OP_TEST_OWNER, &sidecar_url);
+    insert_plain_sandbox_with_url(
+        "mcp-foreign",
+        "0xOP00000000000000000000000000000000000002",
+        &sidecar_url,
+    );
+    let auth = format!("Bearer {}", session_auth::create_test_token(OP_TEST_OWNER));
+
+    let init = mcp_call(
+        &auth,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+    )
+    .await;
+    assert_eq!(init.status(), StatusCode::OK);
+    let init = body_json(init.into_body()).await;
+    assert_eq!(
+        init["result"]["protocolVersion"],
+        crate::mcp::MCP_PROTOCOL_VERSION
+    );
+
+    let ack = mcp_call(
+        &auth,
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+    )
+    .await;
+    assert_eq!(ack.status(), StatusCode::ACCEPTED);
+
+    let list = mcp_call(
+        &auth,
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+    )
+    .await;
+    let list = body_json(list.into_body()).await;
+    let tools = list["result"]["tools"].as_array().expect("tools");
+    assert_eq!(tools.len(), 4);
+    assert_eq!(
+        tools[0]["inputSchema"]["properties"]["sandbox_id"]["enum"],
+        json!(["mcp-owned"])
+    );
+
+    let call = mcp_call(
+        &auth,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "tools/call",
+            "params": {
+                "name": "run_command",
+                "arguments": { "sandbox_id": "mcp-owned", "command": "echo hi" },
+            },
+        }),
+    )
+    .await;
+    let call = body_json(call.into_body()).await;
+    assert_eq!(call["id"], 3);
+    assert_eq!(call["result"]["isError"], false, "body: {call}");
+    let text = call["result"]["content"][0]["text"].as_str().expect("text");
+    assert!(text.starts_with("mock-exec-stdout"), "text: {text}");
+    let payload = sidecar_state
+        .last_exec_payload
+        .lock()
+        .expect("payload lock")
+        .clone()
+        .expect("sidecar should have received exec payload");
+    assert_eq!(payload["command"], "echo hi");
+
+    let denied = mcp_call(
+        &auth,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "tools/call",
+            "params": {
+                "name": "read_file",
+                "arguments": { "sandbox_id": "mcp-foreign", "path": "/etc/hosts" },
+            },
+        }),
+    )
+    .await;
+    let denied = body_json(denied.into_body()).await;
+    assert_eq!(denied["error"]["code"], crate::mcp::INVALID_PARAMS);
+    server.abort();
+}
+
+type ReceivedWebhooks = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+/// Webhook receiver that answers 500 to the first delivery attempt.
+async fn spawn_webhook_receiver() -> (String, ReceivedWebhooks, JoinHandle<()>) {
+    let received: ReceivedWebhooks = Arc::default();
+    let app = Router::new()
+        .route(
+            "/hook",
+            post(
+                |State(received): State<ReceivedWebhooks>, headers: HeaderMap, body: String| async move {
+                    let mut received = received.lock().expect("received lock");
+                    received.push((headers, body));
+                    if received.len() == 1 {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::OK
+                    }
+                },
+            ),
+        )
+        .with_state(received.clone());
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind webhook receiver");
+    let addr = listener.local_addr().expect("webhook receiver addr");
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app)
+            .await
+            .expect("serve webhook receiver");
+    });
+    (format!("http://{addr}/hook"), received, server)
+}
+
+#[serial_test::serial]
+#[tokio::test]
+async fn test_webhook_registration_and_signed_delivery_with_retry() {
+    init();
+    reset_test_state();
+    // The receiver is on loopback, which webhooks may not target by default.
+    let _allowed = EnvVarGuard::set(crate::webhooks::WEBHOOK_ALLOWED_NETWORKS_ENV, "127.0.0.0/8");
+    let (url, received, server) = spawn_webhook_receiver().await;
+    let auth = format!("Bearer {}", session_auth::create_test_token(OP_TEST_OWNER));
+
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/webhooks")
+                .header("authorization", &auth)
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "url": url, "events": ["sandbox.deleted"] }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created = body_json(response.into_body()).await;
+    let webhook_id = created["id"].as_str().expect("id").to_string();
+    let secret = created["secret"].as_str().expect("secret").to_string();
+
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/webhooks")
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let listed = body_json(response.into_body()).await;
+    assert_eq!(listed["webhooks"][0]["id"], webhook_id.as_str());
+    assert!(listed["webhooks"][0].get("secret").is_none());
+
+    // Not subscribed: ignored. Subscribed: delivered on the second attempt.
+    crate::webhooks::emit(
+        OP_TEST_OWNER,
+        crate::webhooks::WebhookEvent::BillingAlert,
+        json!({}),
+    );
+    crate::webhooks::emit(
+        OP_TEST_OWNER,
+        crate::webhooks::WebhookEvent::SandboxDeleted,
+        json!({ "sandbox_id": "sb-gone" }),
+    );
+    for _ in 0..200 {
+        if received.lock().expect("received lock").len() >= 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let (headers, body) = received.lock().expect("received lock")[1].clone();
+    let timestamp: u64 = headers[crate::webhooks::TIMESTAMP_HEADER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let signature = headers[crate::webhooks::SIGNATURE_HEADER].to_str().unwrap();
+    assert!(crate::webhooks::verify_signature(
+        &secret, timestamp, &body, signature
+    ));
+    let payload: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(payload["event"], "sandbox.deleted");
+    assert_eq!(payload["data"]["sandbox_id"], "sb-gone");
+
+    let mut deliveries = Value::Null;
+    for _ in 0..100 {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/webhooks/{webhook_id}/deliveries"))
+                    .header("authorization", &auth)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        deliveries = body_json(response.into_body()).await;
+        if deliveries["deliveries"][0]["status"] == "delivered" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(deliveries["deliveries"].as_array().unwrap().len(), 1);
+    assert_eq!(deliveries["deliveries"][0]["status"], "delivered");
+    assert_eq!(deliveries["deliveries"][0]["attempts"], 2);
+
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/api/webhooks/{webhook_id}"))
+                .header("authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(
+        crate::webhooks::list_webhooks(OP_TEST_OWNER)
+            .unwrap()
+            .is_empty()
+    );
+    server.abort();
+}
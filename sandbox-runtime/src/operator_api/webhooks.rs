@@ -0,0 +1,55 @@
+//! Webhook route group: register, list and delete webhooks and read their
+//! delivery logs.
+
+use super::*;
+use crate::webhooks::{self, WebhookEvent};
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CreateWebhookRequest {
+    pub url: String,
+    /// Events to deliver; empty or omitted means all.
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+}
+
+/// GET /api/webhooks — the caller's webhooks (secrets are never returned).
+pub(crate) async fn list_webhooks_handler(SessionAuth(address): SessionAuth) -> impl IntoResponse {
+    let hooks = webhooks::list_webhooks(&address).map_err(classify_sandbox_error)?;
+    let hooks: Vec<Value> = hooks.iter().map(|h| h.summary()).collect();
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::OK, Json(json!({ "webhooks": hooks }))))
+}
+
+/// POST /api/webhooks — register a webhook. The signing secret is only
+/// returned here.
+pub(crate) async fn create_webhook_handler(
+    SessionAuth(address): SessionAuth,
+    Json(req): Json<CreateWebhookRequest>,
+) -> impl IntoResponse {
+    let (hook, secret) =
+        webhooks::create_webhook(&address, &req.url, req.events).map_err(classify_sandbox_error)?;
+    let mut body = hook.summary();
+    body["secret"] = Value::String(secret);
+    Ok::<_, (StatusCode, Json<ApiError>)>((StatusCode::CREATED, Json(body)))
+}
+
+/// DELETE /api/webhooks/{webhook_id}
+pub(crate) async fn delete_webhook_handler(
+    SessionAuth(address): SessionAuth,
+    Path(webhook_id): Path<String>,
+) -> impl IntoResponse {
+    webhooks::delete_webhook(&address, &webhook_id).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/webhooks/{webhook_id}/deliveries — delivery log, newest first.
+pub(crate) async fn list_webhook_deliveries_handler(
+    SessionAuth(address): SessionAuth,
+    Path(webhook_id): Path<String>,
+) -> impl IntoResponse {
+    let deliveries =
+        webhooks::list_deliveries(&address, &webhook_id).map_err(classify_sandbox_error)?;
+    Ok::<_, (StatusCode, Json<ApiError>)>((
+        StatusCode::OK,
+        Json(json!({ "deliveries": deliveries })),
+    ))
+}
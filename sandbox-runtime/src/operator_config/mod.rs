@@ -0,0 +1,457 @@
+//! Layered operator configuration.
+//!
+//! Operators can keep their settings in one TOML file instead of ~30
+//! environment variables. The file is passed with `--config <path>` or
+//! `OPERATOR_CONFIG_FILE`; every key maps to the environment variable the
+//! runtime already reads, and a variable that is set in the environment
+//! always wins over the file:
+//!
+//! ```toml
+//! [sidecar]
+//! image = "ghcr.io/tangle-network/blueprint-sidecar:all-harness"
+//! public_host = "203.0.113.7"
+//!
+//! [sandbox]
+//! max_count = 20
+//!
+//! [env]          # anything without a typed key, exported verbatim
+//! PHALA_API_KEY = "..."
+//! ```
+//!
+//! [`init`] loads the file and exports its values, so every existing
+//! `std::env::var` reader sees them. [`OperatorConfig::validate`] then checks
+//! the resolved configuration and reports every problem by key and variable
+//! name. `--print-config` prints the resolved configuration (secrets
+//! redacted) with the validation result and exits.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+mod validate;
+
+pub const CONFIG_FILE_ENV: &str = "OPERATOR_CONFIG_FILE";
+pub const PRINT_CONFIG_FLAG: &str = "--print-config";
+
+const REDACTED: &str = "<redacted>";
+
+/// A typed config value that can also be read from an environment variable.
+trait ConfigValue: Sized {
+    /// Parse an environment value; `Err` names what was expected.
+    fn parse_env(raw: &str) -> Result<Self, &'static str>;
+    fn render(&self) -> String;
+}
+
+macro_rules! int_config_value {
+    ($($ty:ty),*) => {$(
+        impl ConfigValue for $ty {
+            fn parse_env(raw: &str) -> Result<Self, &'static str> {
+                raw.trim().parse().map_err(|_| "a non-negative integer")
+            }
+
+            fn render(&self) -> String {
+                self.to_string()
+            }
+        }
+    )*};
+}
+
+int_config_value!(u16, u32, u64, usize);
+
+impl ConfigValue for bool {
+    fn parse_env(raw: &str) -> Result<Self, &'static str> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(true),
+            "false" | "0" | "no" | "off" => Ok(false),
+            _ => Err("true or false"),
+        }
+    }
+
+    fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ConfigValue for String {
+    fn parse_env(raw: &str) -> Result<Self, &'static str> {
+        Ok(raw.trim().to_string())
+    }
+
+    fn render(&self) -> String {
+        self.clone()
+    }
+}
+
+/// One typed key: where it lives in the file and which variable it sets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigEntry {
+    pub section: &'static str,
+    pub key: &'static str,
+    pub env: &'static str,
+    pub value: Option<String>,
+}
+
+macro_rules! config_section {
+    (
+        $(#[$meta:meta])*
+        $name:ident => $section:literal {
+            $($(#[$field_meta:meta])* $field:ident: $ty:ty => $env:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        pub struct $name {
+            $($(#[$field_meta])* pub $field: Option<$ty>,)*
+        }
+
+        impl $name {
+            fn entries(&self) -> Vec<ConfigEntry> {
+                vec![$(ConfigEntry {
+                    section: $section,
+                    key: stringify!($field),
+                    env: $env,
+                    value: self.$field.as_ref().map(ConfigValue::render),
+                },)*]
+            }
+
+            fn overlay_env(&mut self, errors: &mut Vec<String>) {
+                $(if let Ok(raw) = std::env::var($env) {
+                    match <$ty as ConfigValue>::parse_env(&raw) {
+                        Ok(value) => self.$field = Some(value),
+                        Err(expected) => errors.push(format!(
+                            "{}='{}' must be {expected} ([{}] {})",
+                            $env,
+                            raw.trim(),
+                            $section,
+                            stringify!($field),
+                        )),
+                    }
+                })*
+            }
+        }
+    };
+}
+
+config_section! {
+    /// Operator process, chain and API settings.
+    OperatorSection => "operator" {
+        api_port: u16 => "OPERATOR_API_PORT",
+        max_capacity: u32 => "OPERATOR_MAX_CAPACITY",
+        allow_standalone: bool => "ALLOW_STANDALONE",
+        state_dir: String => "BLUEPRINT_STATE_DIR",
+        keystore_uri: String => "KEYSTORE_URI",
+        rpc_endpoint: String => "HTTP_RPC_ENDPOINT",
+        cors_allowed_origins: String => "CORS_ALLOWED_ORIGINS",
+        cors_public_allowed_origins: String => "CORS_PUBLIC_ALLOWED_ORIGINS",
+        cors_auth_allowed_origins: String => "CORS_AUTH_ALLOWED_ORIGINS",
+        cors_secrets_allowed_origins: String => "CORS_SECRETS_ALLOWED_ORIGINS",
+        response_signing: bool => "OPERATOR_RESPONSE_SIGNING",
+        result_commitment_min_bytes: usize => "RESULT_COMMITMENT_MIN_BYTES",
+        job_dedup_retention_secs: u64 => "JOB_DEDUP_RETENTION_SECS",
+        job_replay_log: bool => "JOB_REPLAY_LOG",
+        drain_mode: bool => "OPERATOR_DRAIN_MODE",
+        drain_window_secs: u64 => "OPERATOR_DRAIN_WINDOW_SECS",
+    }
+}
+
+config_section! {
+    /// Session authentication.
+    AuthSection => "auth" {
+        session_secret: String => "SESSION_AUTH_SECRET",
+    }
+}
+
+config_section! {
+    /// Sidecar containers and the HTTP client that talks to them.
+    SidecarSection => "sidecar" {
+        image: String => "SIDECAR_IMAGE",
+        public_host: String => "SIDECAR_PUBLIC_HOST",
+        http_port: u16 => "SIDECAR_HTTP_PORT",
+        ssh_port: u16 => "SIDECAR_SSH_PORT",
+        pull_image: bool => "SIDECAR_PULL_IMAGE",
+        request_timeout_secs: u64 => "REQUEST_TIMEOUT_SECS",
+        connect_timeout_secs: u64 => "HTTP_CONNECT_TIMEOUT_SECS",
+        retry_attempts: u32 => "SIDECAR_RETRY_ATTEMPTS",
+        retry_base_ms: u64 => "SIDECAR_RETRY_BASE_MS",
+        retry_max_ms: u64 => "SIDECAR_RETRY_MAX_MS",
+        breaker_failures: u32 => "SIDECAR_BREAKER_FAILURES",
+        url_schemes: String => "SIDECAR_URL_SCHEMES",
+        allowed_networks: String => "SIDECAR_ALLOWED_NETWORKS",
+        allowed_hosts: String => "SIDECAR_ALLOWED_HOSTS",
+    }
+}
+
+config_section! {
+    /// Sandbox limits, lifetimes and the runtime backend.
+    SandboxSection => "sandbox" {
+        runtime_backend: String => "SANDBOX_RUNTIME_BACKEND",
+        max_count: usize => "SANDBOX_MAX_COUNT",
+        max_cpu_cores: u64 => "SANDBOX_MAX_CPU_CORES",
+        max_memory_mb: u64 => "SANDBOX_MAX_MEMORY_MB",
+        max_disk_gb: u64 => "SANDBOX_MAX_DISK_GB",
+        host_memory_budget_mb: u64 => "SANDBOX_HOST_MEMORY_BUDGET_MB",
+        host_cpu_budget: u64 => "SANDBOX_HOST_CPU_BUDGET",
+        default_idle_timeout: u64 => "SANDBOX_DEFAULT_IDLE_TIMEOUT",
+        default_max_lifetime: u64 => "SANDBOX_DEFAULT_MAX_LIFETIME",
+        max_idle_timeout: u64 => "SANDBOX_MAX_IDLE_TIMEOUT",
+        max_max_lifetime: u64 => "SANDBOX_MAX_MAX_LIFETIME",
+        reaper_interval: u64 => "SANDBOX_REAPER_INTERVAL",
+        activity_probe: bool => "SANDBOX_ACTIVITY_PROBE",
+        gc_interval: u64 => "SANDBOX_GC_INTERVAL",
+        gc_archive_prefix: String => "SANDBOX_GC_ARCHIVE_PREFIX",
+        gc_archive_max_mb: u64 => "SANDBOX_GC_ARCHIVE_MAX_MB",
+    }
+}
+
+config_section! {
+    /// On-chain QoS heartbeat and metrics (`qos` feature).
+    QosSection => "qos" {
+        enabled: bool => "QOS_ENABLED",
+        metrics_interval_secs: u64 => "QOS_METRICS_INTERVAL_SECS",
+        dry_run: bool => "QOS_DRY_RUN",
+    }
+}
+
+config_section! {
+    /// Instance subscription escrow watchdog.
+    BillingSection => "billing" {
+        check_interval_secs: u64 => "ESCROW_CHECK_INTERVAL_SECS",
+        max_consecutive_failures: u32 => "ESCROW_MAX_CONSECUTIVE_FAILURES",
+        low_balance_multiplier: u32 => "ESCROW_LOW_BALANCE_MULTIPLIER",
+        deprovision_grace_period_secs: u64 => "ESCROW_DEPROVISION_GRACE_PERIOD_SECS",
+    }
+}
+
+config_section! {
+    /// TEE backend selection. Backend credentials go in `[env]`.
+    TeeSection => "tee" {
+        backend: String => "TEE_BACKEND",
+    }
+}
+
+config_section! {
+    /// Workflow scheduling.
+    WorkflowSection => "workflow" {
+        cron_schedule: String => "WORKFLOW_CRON_SCHEDULE",
+    }
+}
+
+/// The operator's resolved configuration: file values with environment
+/// overrides applied.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OperatorConfig {
+    pub operator: OperatorSection,
+    pub auth: AuthSection,
+    pub sidecar: SidecarSection,
+    pub sandbox: SandboxSection,
+    pub qos: QosSection,
+    pub billing: BillingSection,
+    pub tee: TeeSection,
+    pub workflow: WorkflowSection,
+    /// Extra variables exported as-is.
+    pub env: BTreeMap<String, String>,
+    /// Where the file layer came from, if any.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    /// Environment overrides that did not parse.
+    #[serde(skip)]
+    env_errors: Vec<String>,
+}
+
+impl OperatorConfig {
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        toml::from_str(raw).map_err(|e| format!("invalid operator config: {e}"))
+    }
+
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read operator config {}: {e}", path.display()))?;
+        let mut config = Self::from_toml(&raw).map_err(|e| format!("{}: {e}", path.display()))?;
+        config.source = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Replace file values with any that are set in the environment.
+    pub fn with_env_overrides(mut self) -> Self {
+        let mut errors = Vec::new();
+        self.operator.overlay_env(&mut errors);
+        self.auth.overlay_env(&mut errors);
+        self.sidecar.overlay_env(&mut errors);
+        self.sandbox.overlay_env(&mut errors);
+        self.qos.overlay_env(&mut errors);
+        self.billing.overlay_env(&mut errors);
+        self.tee.overlay_env(&mut errors);
+        self.workflow.overlay_env(&mut errors);
+        for (name, value) in self.env.iter_mut() {
+            if let Ok(raw) = std::env::var(name) {
+                *value = raw;
+            }
+        }
+        self.env_errors = errors;
+        self
+    }
+
+    /// Every typed key with its environment variable and current value.
+    pub fn entries(&self) -> Vec<ConfigEntry> {
+        [
+            self.operator.entries(),
+            self.auth.entries(),
+            self.sidecar.entries(),
+            self.sandbox.entries(),
+            self.qos.entries(),
+            self.billing.entries(),
+            self.tee.entries(),
+            self.workflow.entries(),
+        ]
+        .concat()
+    }
+
+    /// Variables this configuration sets: typed keys first, then `[env]`.
+    pub fn env_vars(&self) -> Vec<(String, String)> {
+        self.entries()
+            .into_iter()
+            .filter_map(|e| e.value.map(|v| (e.env.to_string(), v)))
+            .chain(self.env.iter().map(|(k, v)| (k.clone(), v.clone())))
+            .collect()
+    }
+
+    /// TOML rendering of the resolved configuration with secrets redacted.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        match &self.source {
+            Some(path) => writeln!(out, "# operator config file: {}", path.display()),
+            None => writeln!(out, "# operator config file: none (environment only)"),
+        }
+        .ok();
+        let mut section = "";
+        for entry in self.entries() {
+            if entry.section != section {
+                section = entry.section;
+                writeln!(out, "\n[{section}]").ok();
+            }
+            match entry.value {
+                Some(value) => writeln!(
+                    out,
+                    "{} = {}  # {}",
+                    entry.key,
+                    display_value(entry.env, &value),
+                    entry.env
+                ),
+                None => writeln!(out, "# {} unset  # {}", entry.key, entry.env),
+            }
+            .ok();
+        }
+        if !self.env.is_empty() {
+            writeln!(out, "\n[env]").ok();
+            for (name, value) in &self.env {
+                writeln!(out, "{name} = {}", display_value(name, value)).ok();
+            }
+        }
+        out
+    }
+}
+
+fn is_secret(env: &str) -> bool {
+    [
+        "SECRET",
+        "TOKEN",
+        "PASSWORD",
+        "API_KEY",
+        "PRIVATE_KEY",
+        "CREDENTIALS",
+    ]
+    .iter()
+    .any(|marker| env.contains(marker))
+}
+
+fn display_value(env: &str, value: &str) -> String {
+    if is_secret(env) {
+        format!("\"{REDACTED}\"")
+    } else if value.parse::<u64>().is_ok() || value == "true" || value == "false" {
+        value.to_string()
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// Config file named by `--config <path>` / `--config=<path>` or
+/// `OPERATOR_CONFIG_FILE`.
+pub fn config_path() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    std::env::var(CONFIG_FILE_ENV)
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+pub fn print_config_requested() -> bool {
+    std::env::args().any(|a| a == PRINT_CONFIG_FLAG)
+}
+
+/// Resolve the configuration without touching the environment.
+pub fn resolve() -> Result<OperatorConfig, String> {
+    let config = match config_path() {
+        Some(path) => OperatorConfig::load_file(&path)?,
+        None => OperatorConfig::default(),
+    };
+    Ok(config.with_env_overrides())
+}
+
+/// Resolve the configuration and export file values that the environment
+/// does not already set. Call at the top of `main`, before anything reads
+/// configuration. Fails only when the file cannot be read or parsed; run
+/// [`OperatorConfig::validate`] on the result.
+pub fn init() -> Result<OperatorConfig, String> {
+    let config = resolve()?;
+    if let Some(path) = &config.source {
+        let mut exported = 0usize;
+        for (name, value) in config.env_vars() {
+            if std::env::var_os(&name).is_none() {
+                // SAFETY: runs at the top of `main`, before the operator
+                // starts tasks that read the environment.
+                unsafe { std::env::set_var(&name, value) };
+                exported += 1;
+            }
+        }
+        tracing::info!(path = %path.display(), exported, "Loaded operator config file");
+    }
+    Ok(config)
+}
+
+/// `--print-config`: print the resolved configuration and its validation
+/// result. Returns the process exit code.
+pub fn print_config() -> i32 {
+    let config = match resolve() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{err}");
+            return 2;
+        }
+    };
+    print!("{}", config.render());
+    match config.validate() {
+        Ok(()) => {
+            println!("\n# configuration is valid");
+            0
+        }
+        Err(err) => {
+            eprintln!("\n{err}");
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,141 @@
+use super::*;
+
+const SAMPLE: &str = r#"
+[operator]
+api_port = 9191
+allow_standalone = true
+
+[auth]
+session_secret = "0123456789abcdef0123456789abcdef"
+
+[sidecar]
+image = "ghcr.io/example/sidecar:latest"
+pull_image = false
+
+[sandbox]
+max_count = 20
+runtime_backend = "docker"
+
+[env]
+PHALA_API_KEY = "phala-secret"
+CUSTOM_FLAG = "on"
+"#;
+
+fn var<'a>(vars: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    vars.iter()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.as_str())
+}
+
+#[test]
+fn file_keys_map_to_environment_variables() {
+    let config = OperatorConfig::from_toml(SAMPLE).unwrap();
+    assert_eq!(config.sandbox.max_count, Some(20));
+    let vars = config.env_vars();
+    assert_eq!(var(&vars, "OPERATOR_API_PORT"), Some("9191"));
+    assert_eq!(var(&vars, "ALLOW_STANDALONE"), Some("true"));
+    assert_eq!(
+        var(&vars, "SIDECAR_IMAGE"),
+        Some("ghcr.io/example/sidecar:latest")
+    );
+    assert_eq!(var(&vars, "SIDECAR_PULL_IMAGE"), Some("false"));
+    assert_eq!(var(&vars, "SANDBOX_MAX_COUNT"), Some("20"));
+    assert_eq!(var(&vars, "CUSTOM_FLAG"), Some("on"));
+    assert_eq!(
+        var(&vars, "SANDBOX_GC_INTERVAL"),
+        None,
+        "unset keys export nothing"
+    );
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn unknown_and_mistyped_keys_are_rejected() {
+    let err = OperatorConfig::from_toml("[sidecar]\nimagee = \"x\"\n").unwrap_err();
+    assert!(err.contains("imagee"), "{err}");
+    assert!(OperatorConfig::from_toml("[sidecars]\n").is_err());
+    let err = OperatorConfig::from_toml("[sandbox]\nmax_count = \"lots\"\n").unwrap_err();
+    assert!(err.contains("max_count"), "{err}");
+}
+
+#[test]
+fn validation_reports_every_problem_by_key_and_variable() {
+    let config = OperatorConfig::from_toml(
+        r#"
+[operator]
+api_port = 0
+
+[sidecar]
+retry_attempts = 0
+allowed_networks = "10.0.0.0/8, not-a-cidr"
+
+[sandbox]
+runtime_backend = "kvm"
+default_idle_timeout = 7200
+max_idle_timeout = 3600
+
+[tee]
+backend = "sgx"
+
+[env]
+SIDECAR_IMAGE = "shadowed"
+lower_case = "x"
+"#,
+    )
+    .unwrap();
+    let err = config.validate().unwrap_err();
+    for expected in [
+        "OPERATOR_API_PORT",
+        "SIDECAR_RETRY_ATTEMPTS",
+        "'not-a-cidr'",
+        "SANDBOX_RUNTIME_BACKEND",
+        "SANDBOX_DEFAULT_IDLE_TIMEOUT",
+        "TEE_BACKEND",
+        "set [sidecar] image instead",
+        "[env] lower_case",
+    ] {
+        assert!(err.contains(expected), "missing '{expected}' in:\n{err}");
+    }
+    assert!(!err.contains("10.0.0.0/8"));
+}
+
+#[test]
+fn environment_overrides_file_values() {
+    let config = OperatorConfig::from_toml(
+        "[billing]\nmax_consecutive_failures = 3\nlow_balance_multiplier = 2\n\
+         [env]\nOPERATOR_CONFIG_TEST_PASSTHROUGH = \"file\"\n",
+    )
+    .unwrap();
+    unsafe {
+        std::env::set_var("ESCROW_MAX_CONSECUTIVE_FAILURES", "7");
+        std::env::set_var("ESCROW_LOW_BALANCE_MULTIPLIER", "many");
+        std::env::set_var("OPERATOR_CONFIG_TEST_PASSTHROUGH", "env");
+    }
+    let resolved = config.with_env_overrides();
+    unsafe {
+        std::env::remove_var("ESCROW_MAX_CONSECUTIVE_FAILURES");
+        std::env::remove_var("ESCROW_LOW_BALANCE_MULTIPLIER");
+        std::env::remove_var("OPERATOR_CONFIG_TEST_PASSTHROUGH");
+    }
+
+    assert_eq!(resolved.billing.max_consecutive_failures, Some(7));
+    assert_eq!(resolved.billing.low_balance_multiplier, Some(2));
+    assert_eq!(resolved.env["OPERATOR_CONFIG_TEST_PASSTHROUGH"], "env");
+    let err = resolved.validate().unwrap_err();
+    assert!(
+        err.contains("ESCROW_LOW_BALANCE_MULTIPLIER='many' must be a non-negative integer"),
+        "{err}"
+    );
+}
+
+#[test]
+fn render_redacts_secrets() {
+    let rendered = OperatorConfig::from_toml(SAMPLE).unwrap().render();
+    assert!(rendered.contains("[sidecar]"));
+    assert!(rendered.contains("image = \"ghcr.io/example/sidecar:latest\"  # SIDECAR_IMAGE"));
+    assert!(rendered.contains("max_count = 20  # SANDBOX_MAX_COUNT"));
+    assert!(rendered.contains("# gc_interval unset  # SANDBOX_GC_INTERVAL"));
+    assert!(!rendered.contains("0123456789abcdef"));
+    assert!(!rendered.contains("phala-secret"));
+    assert!(rendered.contains(REDACTED));
+}
@@ -0,0 +1,183 @@
+//! Startup validation of the resolved [`OperatorConfig`].
+
+use super::OperatorConfig;
+
+const TEE_BACKENDS: &[&str] = &["phala", "nitro", "aws", "gcp", "azure", "direct"];
+const RUNTIME_BACKENDS: &[&str] = &[
+    "docker",
+    "container",
+    "firecracker",
+    "microvm",
+    "tee",
+    "confidential",
+    "confidential-vm",
+];
+
+impl OperatorConfig {
+    /// Check the resolved configuration. Each error names the key and the
+    /// variable that sets it.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = self.env_errors.clone();
+        let mut check = |ok: bool, msg: String| {
+            if !ok {
+                errors.push(msg);
+            }
+        };
+
+        for (port, what) in [
+            (
+                self.operator.api_port,
+                "[operator] api_port (OPERATOR_API_PORT)",
+            ),
+            (
+                self.sidecar.http_port,
+                "[sidecar] http_port (SIDECAR_HTTP_PORT)",
+            ),
+            (
+                self.sidecar.ssh_port,
+                "[sidecar] ssh_port (SIDECAR_SSH_PORT)",
+            ),
+        ] {
+            check(
+                port != Some(0),
+                format!("{what} must be a port between 1 and 65535"),
+            );
+        }
+        for (value, what) in [
+            (
+                &self.auth.session_secret,
+                "[auth] session_secret (SESSION_AUTH_SECRET)",
+            ),
+            (&self.sidecar.image, "[sidecar] image (SIDECAR_IMAGE)"),
+            (
+                &self.sidecar.public_host,
+                "[sidecar] public_host (SIDECAR_PUBLIC_HOST)",
+            ),
+        ] {
+            check(
+                value.as_deref().is_none_or(|v| !v.trim().is_empty()),
+                format!("{what} is set but empty"),
+            );
+        }
+        for (value, what) in [
+            (
+                self.sidecar.request_timeout_secs,
+                "[sidecar] request_timeout_secs (REQUEST_TIMEOUT_SECS)",
+            ),
+            (
+                self.sandbox.reaper_interval,
+                "[sandbox] reaper_interval (SANDBOX_REAPER_INTERVAL)",
+            ),
+            (
+                self.sandbox.gc_interval,
+                "[sandbox] gc_interval (SANDBOX_GC_INTERVAL)",
+            ),
+            (
+                self.qos.metrics_interval_secs,
+                "[qos] metrics_interval_secs (QOS_METRICS_INTERVAL_SECS)",
+            ),
+            (
+                self.billing.check_interval_secs,
+                "[billing] check_interval_secs (ESCROW_CHECK_INTERVAL_SECS)",
+            ),
+        ] {
+            check(value != Some(0), format!("{what} must be greater than 0"));
+        }
+        check(
+            self.sidecar
+                .retry_attempts
+                .is_none_or(|n| (1..=10).contains(&n)),
+            "[sidecar] retry_attempts (SIDECAR_RETRY_ATTEMPTS) must be between 1 and 10".into(),
+        );
+        if let (Some(base), Some(max)) = (self.sidecar.retry_base_ms, self.sidecar.retry_max_ms) {
+            check(
+                base <= max,
+                format!(
+                    "[sidecar] retry_base_ms ({base}) must not exceed retry_max_ms ({max}) (SIDECAR_RETRY_BASE_MS / SIDECAR_RETRY_MAX_MS)"
+                ),
+            );
+        }
+        if let Some(networks) = &self.sidecar.allowed_networks {
+            for entry in networks.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                check(
+                    entry.parse::<ipnet::IpNet>().is_ok()
+                        || entry.parse::<std::net::IpAddr>().is_ok(),
+                    format!(
+                        "[sidecar] allowed_networks (SIDECAR_ALLOWED_NETWORKS): '{entry}' is not a CIDR or IP address"
+                    ),
+                );
+            }
+        }
+        if let Some(backend) = &self.sandbox.runtime_backend {
+            check(
+                RUNTIME_BACKENDS.contains(&backend.trim().to_ascii_lowercase().as_str()),
+                format!(
+                    "[sandbox] runtime_backend (SANDBOX_RUNTIME_BACKEND) must be one of docker, firecracker, tee (got '{backend}')"
+                ),
+            );
+        }
+        if let Some(backend) = &self.tee.backend {
+            check(
+                TEE_BACKENDS.contains(&backend.trim().to_ascii_lowercase().as_str()),
+                format!(
+                    "[tee] backend (TEE_BACKEND) must be one of {} (got '{backend}')",
+                    TEE_BACKENDS.join(", ")
+                ),
+            );
+        }
+        if let (Some(default), Some(max)) = (
+            self.sandbox.default_idle_timeout,
+            self.sandbox.max_idle_timeout,
+        ) {
+            check(
+                default <= max,
+                format!(
+                    "[sandbox] default_idle_timeout ({default}) exceeds max_idle_timeout ({max}); lower SANDBOX_DEFAULT_IDLE_TIMEOUT or raise SANDBOX_MAX_IDLE_TIMEOUT"
+                ),
+            );
+        }
+        if let (Some(default), Some(max)) = (
+            self.sandbox.default_max_lifetime,
+            self.sandbox.max_max_lifetime,
+        ) {
+            check(
+                default <= max,
+                format!(
+                    "[sandbox] default_max_lifetime ({default}) exceeds max_max_lifetime ({max}); lower SANDBOX_DEFAULT_MAX_LIFETIME or raise SANDBOX_MAX_MAX_LIFETIME"
+                ),
+            );
+        }
+
+        let typed = self.entries();
+        for name in self.env.keys() {
+            check(
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'),
+                format!("[env] {name}: variable names must be upper-case letters, digits and '_'"),
+            );
+            let shadowed = typed.iter().find(|e| e.env == name);
+            check(
+                shadowed.is_none(),
+                shadowed
+                    .map(|e| {
+                        format!(
+                            "[env] {name} duplicates a typed key; set [{}] {} instead",
+                            e.section, e.key
+                        )
+                    })
+                    .unwrap_or_default(),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "invalid operator configuration:\n  - {}",
+                errors.join("\n  - ")
+            ))
+        }
+    }
+}
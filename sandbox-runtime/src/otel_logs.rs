@@ -0,0 +1,221 @@
+//! OTLP log export (`otel` feature).
+//!
+//! When `OTEL_EXPORTER_OTLP_LOGS_ENDPOINT` or `OTEL_EXPORTER_OTLP_ENDPOINT` is
+//! set (and `OTEL_LOGS_EXPORTER` is not `none`), [`crate::logging::init`] adds
+//! [`layer`] next to the console output: every event that passes the log
+//! filter is sent over OTLP/HTTP to the collector as well, with the same
+//! redaction as the console ([`crate::logging::redact_field`]).
+//!
+//! Events inside a job carry the job's [`crate::job_trace`] ID as the log
+//! record's trace ID and the `job` span's fields (`service_id`, `call_id`,
+//! `job`) as attributes, so the collector correlates them with the sidecar
+//! calls that forwarded the same `traceparent`. The service name defaults to
+//! the binary name; `OTEL_SERVICE_NAME` and `OTEL_RESOURCE_ATTRIBUTES` apply
+//! as usual. Records are batched on a background thread and flushed by
+//! [`shutdown`].
+
+use std::time::SystemTime;
+
+use once_cell::sync::OnceCell;
+use opentelemetry::logs::{AnyValue, LogRecord as _, Logger as _, LoggerProvider as _, Severity};
+use opentelemetry::trace::{SpanId, TraceFlags, TraceId};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::logs::{SdkLogger, SdkLoggerProvider};
+use serde_json::{Map, Value};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::logging::FieldCollector;
+
+/// Targets never exported: the exporter's own HTTP stack would otherwise
+/// log about every export it makes.
+const SKIPPED_TARGETS: &[&str] = &["opentelemetry", "hyper", "reqwest", "h2", "tower"];
+
+static PROVIDER: OnceCell<SdkLoggerProvider> = OnceCell::new();
+
+/// Whether the environment names an OTLP endpoint for logs.
+pub fn otlp_logs_enabled() -> bool {
+    let set = |key: &str| std::env::var(key).is_ok_and(|v| !v.trim().is_empty());
+    let disabled =
+        std::env::var("OTEL_LOGS_EXPORTER").is_ok_and(|v| v.trim().eq_ignore_ascii_case("none"));
+    !disabled && (set("OTEL_EXPORTER_OTLP_LOGS_ENDPOINT") || set("OTEL_EXPORTER_OTLP_ENDPOINT"))
+}
+
+fn default_service_name() -> String {
+    std::env::args()
+        .next()
+        .as_deref()
+        .map(std::path::Path::new)
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "sandbox-operator".to_string())
+}
+
+/// The OTLP log layer, or `None` when export is not configured or the
+/// exporter cannot be built (reported on stderr, since logging is not up
+/// yet).
+pub fn layer() -> Option<OtlpLogLayer> {
+    if !otlp_logs_enabled() {
+        return None;
+    }
+    let exporter = match opentelemetry_otlp::LogExporter::builder()
+        .with_http()
+        .build()
+    {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("OTLP log export disabled: {e}");
+            return None;
+        }
+    };
+    let mut resource = Resource::builder();
+    if std::env::var("OTEL_SERVICE_NAME").is_err() {
+        resource = resource.with_service_name(default_service_name());
+    }
+    let provider = SdkLoggerProvider::builder()
+        .with_resource(resource.build())
+        .with_batch_exporter(exporter)
+        .build();
+    let logger = provider.logger("sandbox-runtime");
+    let _ = PROVIDER.set(provider);
+    Some(OtlpLogLayer { logger })
+}
+
+/// Flush buffered records and stop the exporter. A no-op when export is off.
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get()
+        && let Err(e) = provider.shutdown()
+    {
+        // The log pipeline is going away; do not route this through it.
+        eprintln!("OTLP log export shutdown failed: {e}");
+    }
+}
+
+/// Redacted fields of a `job` span, kept in its extensions.
+struct JobFields(Map<String, Value>);
+
+/// Sends each event to the OTLP collector as a log record.
+pub struct OtlpLogLayer {
+    logger: SdkLogger,
+}
+
+fn severity(level: &Level) -> Severity {
+    match *level {
+        Level::TRACE => Severity::Trace,
+        Level::DEBUG => Severity::Debug,
+        Level::INFO => Severity::Info,
+        Level::WARN => Severity::Warn,
+        Level::ERROR => Severity::Error,
+    }
+}
+
+fn any_value(value: Value) -> AnyValue {
+    match value {
+        Value::String(s) => AnyValue::String(s.into()),
+        Value::Bool(b) => AnyValue::Boolean(b),
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => AnyValue::Int(i),
+            (None, Some(f)) if n.is_f64() => AnyValue::Double(f),
+            _ => AnyValue::String(n.to_string().into()),
+        },
+        other => AnyValue::String(other.to_string().into()),
+    }
+}
+
+/// The trace ID of a job span's `trace_id` field.
+fn job_trace_id(fields: &Map<String, Value>) -> Option<TraceId> {
+    let id = TraceId::from_hex(fields.get("trace_id")?.as_str()?).ok()?;
+    (id != TraceId::INVALID).then_some(id)
+}
+
+impl<S> Layer<S> for OtlpLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != "job" {
+            return;
+        }
+        let mut collector = FieldCollector::default();
+        attrs.record(&mut collector);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut()
+                .insert(JobFields(collector.into_map()));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        if SKIPPED_TARGETS
+            .iter()
+            .any(|target| meta.target().starts_with(target))
+        {
+            return;
+        }
+        let mut collector = FieldCollector::default();
+        event.record(&mut collector);
+        let mut fields = collector.into_map();
+
+        let mut record = self.logger.create_log_record();
+        let now = SystemTime::now();
+        record.set_timestamp(now);
+        record.set_observed_timestamp(now);
+        record.set_severity_number(severity(meta.level()));
+        record.set_severity_text(meta.level().as_str());
+        record.set_target(meta.target());
+        if let Some(message) = fields.remove("message") {
+            record.set_body(any_value(message));
+        }
+
+        let job = ctx.event_scope(event).and_then(|mut scope| {
+            scope.find_map(|span| span.extensions().get::<JobFields>().map(|f| f.0.clone()))
+        });
+        if let Some(job) = job {
+            if let Some(trace_id) = job_trace_id(&job) {
+                record.set_trace_context(trace_id, SpanId::INVALID, Some(TraceFlags::SAMPLED));
+            }
+            for (name, value) in job {
+                if name != "trace_id" && !fields.contains_key(&name) {
+                    record.add_attribute(name, any_value(value));
+                }
+            }
+        }
+        for (name, value) in fields {
+            record.add_attribute(name, any_value(value));
+        }
+        self.logger.emit(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn job_spans_yield_their_trace_id() {
+        let trace_id = crate::job_trace::trace_id(1, 42);
+        let mut fields = Map::new();
+        fields.insert("trace_id".into(), Value::String(trace_id.clone()));
+        assert_eq!(
+            job_trace_id(&fields).map(|id| id.to_string()),
+            Some(trace_id)
+        );
+        fields.insert("trace_id".into(), Value::String("0".repeat(32)));
+        assert_eq!(job_trace_id(&fields), None);
+        assert_eq!(job_trace_id(&Map::new()), None);
+    }
+
+    #[test]
+    fn field_values_keep_their_types() {
+        assert_eq!(any_value(Value::from(7u64)), AnyValue::Int(7));
+        assert_eq!(any_value(Value::from(true)), AnyValue::Boolean(true));
+        assert_eq!(any_value(Value::from(0.5)), AnyValue::Double(0.5));
+        assert_eq!(
+            any_value(Value::from(u64::MAX)),
+            AnyValue::String(u64::MAX.to_string().into())
+        );
+    }
+}
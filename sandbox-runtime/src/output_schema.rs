@@ -0,0 +1,479 @@
+//! Structured output for task jobs.
+//!
+//! A task request may carry `output_schema_json`, a JSON Schema the result
+//! must satisfy. The schema is sent to the sidecar as `outputSchema` and
+//! spelled out in the prompt; the operator then validates the agent's reply.
+//! On a mismatch the agent is asked once, in the same session, to repair its
+//! answer; if that still fails the task is reported with `success = false`
+//! and the schema errors. A valid reply is returned as compact JSON, with
+//! any surrounding prose or code fences removed.
+//!
+//! The validator covers the commonly used subset of JSON Schema: `type`,
+//! `enum`, `const`, `properties`, `required`, `additionalProperties`,
+//! `items`, `minItems`/`maxItems`, `minLength`/`maxLength`,
+//! `minimum`/`maximum`, `anyOf`, `oneOf` and `allOf`. Other keywords are
+//! ignored.
+
+use serde_json::{Map, Value};
+
+use crate::instance_types::InstanceTaskResponse;
+
+/// Largest accepted `output_schema_json` (64 KiB).
+pub const MAX_OUTPUT_SCHEMA_BYTES: usize = 64 * 1024;
+
+/// Schema errors listed in a repair prompt or failure message.
+const MAX_REPORTED_ERRORS: usize = 10;
+
+/// Task response fields the schema check reads and rewrites.
+pub trait TaskOutput {
+    fn succeeded(&self) -> bool;
+    fn result_text(&self) -> &str;
+    fn session(&self) -> &str;
+    /// Tool policy the sidecar confirmed (see `crate::tool_policy`).
+    fn tools_echo(&self) -> &str;
+    /// Replace the result with the validated JSON.
+    fn set_result(&mut self, result: String);
+    /// Mark the task failed with `error`.
+    fn fail(&mut self, error: String);
+}
+
+impl TaskOutput for InstanceTaskResponse {
+    fn succeeded(&self) -> bool {
+        self.success
+    }
+    fn result_text(&self) -> &str {
+        &self.result
+    }
+    fn session(&self) -> &str {
+        &self.session_id
+    }
+    fn tools_echo(&self) -> &str {
+        &self.tools_json
+    }
+    fn set_result(&mut self, result: String) {
+        self.result = result;
+    }
+    fn fail(&mut self, error: String) {
+        self.success = false;
+        self.error = error;
+    }
+}
+
+/// Parse `output_schema_json` (empty = no schema).
+pub fn parse_output_schema(raw: &str) -> Result<Option<Value>, String> {
+    if raw.trim().is_empty() {
+        return Ok(None);
+    }
+    if raw.len() > MAX_OUTPUT_SCHEMA_BYTES {
+        return Err(format!(
+            "output_schema_json exceeds maximum length ({MAX_OUTPUT_SCHEMA_BYTES} bytes)"
+        ));
+    }
+    match serde_json::from_str::<Value>(raw) {
+        Ok(schema @ Value::Object(_)) => Ok(Some(schema)),
+        Ok(_) => Err("output_schema_json must be a JSON object".into()),
+        Err(e) => Err(format!("output_schema_json is not valid JSON: {e}")),
+    }
+}
+
+/// Add the schema to an `/agents/run` payload as `outputSchema`.
+pub fn insert_output_schema(payload: &mut Map<String, Value>, schema: Option<&Value>) {
+    if let Some(schema) = schema {
+        payload.insert("outputSchema".to_string(), schema.clone());
+    }
+}
+
+/// `prompt` followed by instructions to answer with JSON matching `schema`.
+pub fn schema_prompt(prompt: &str, schema: &Value) -> String {
+    format!(
+        "{prompt}\n\nRespond with only a JSON value that conforms to this JSON Schema, \
+         with no other text:\n{schema}"
+    )
+}
+
+/// Follow-up prompt asking the agent to fix a reply that failed validation.
+pub fn repair_prompt(schema: &Value, errors: &[String]) -> String {
+    format!(
+        "Your previous answer did not conform to the required JSON Schema:\n- {}\n\n\
+         Respond again with only a JSON value that conforms to this schema, \
+         with no other text:\n{schema}",
+        errors.join("\n- ")
+    )
+}
+
+/// Extract the JSON value from an agent reply: the whole text, a fenced code
+/// block, or the outermost `{...}` / `[...]` span.
+pub fn extract_json(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+    if let Some(start) = text.find("```") {
+        let body = &text[start + 3..];
+        let body = body.split_once('\n').map_or(body, |(_, rest)| rest);
+        if let Some(end) = body.find("```")
+            && let Ok(value) = serde_json::from_str(body[..end].trim())
+        {
+            return Some(value);
+        }
+    }
+    for (open, close) in [('{', '}'), ('[', ']')] {
+        if let (Some(start), Some(end)) = (text.find(open), text.rfind(close))
+            && start < end
+            && let Ok(value) = serde_json::from_str(&text[start..=end])
+        {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Validate an agent reply. Returns the compact JSON on success.
+pub fn check_output(text: &str, schema: &Value) -> Result<String, Vec<String>> {
+    let Some(value) = extract_json(text) else {
+        return Err(vec!["response is not valid JSON".into()]);
+    };
+    let mut errors = Vec::new();
+    validate_value(&value, schema, "$", &mut errors);
+    if errors.is_empty() {
+        Ok(value.to_string())
+    } else {
+        errors.truncate(MAX_REPORTED_ERRORS);
+        Err(errors)
+    }
+}
+
+/// Collect the schema violations of `value` at `path` into `errors`.
+pub fn validate_value(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{path}: no value is allowed"));
+        }
+        return;
+    };
+
+    if let Some(expected) = schema.get("type")
+        && !type_matches(value, expected)
+    {
+        errors.push(format!("{path}: expected type {expected}"));
+        return;
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        errors.push(format!(
+            "{path}: must be one of {}",
+            Value::from(allowed.clone())
+        ));
+    }
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        errors.push(format!("{path}: must equal {expected}"));
+    }
+
+    match value {
+        Value::Object(obj) => validate_object(obj, schema, path, errors),
+        Value::Array(items) => {
+            let count = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+                && count < min
+            {
+                errors.push(format!("{path}: must have at least {min} items"));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+                && count > max
+            {
+                errors.push(format!("{path}: must have at most {max} items"));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_value(item, item_schema, &format!("{path}[{i}]"), errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+                && len < min
+            {
+                errors.push(format!("{path}: must be at least {min} characters"));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+                && len > max
+            {
+                errors.push(format!("{path}: must be at most {max} characters"));
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+                && n < min
+            {
+                errors.push(format!("{path}: must be >= {min}"));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+                && n > max
+            {
+                errors.push(format!("{path}: must be <= {max}"));
+            }
+        }
+        Value::Bool(_) | Value::Null => {}
+    }
+
+    validate_combinators(value, schema, path, errors);
+}
+
+fn validate_object(
+    obj: &Map<String, Value>,
+    schema: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    if let Some(required) = schema.get("required").and_then(Value::as_array) {
+        for key in required.iter().filter_map(Value::as_str) {
+            if !obj.contains_key(key) {
+                errors.push(format!("{path}: missing required property {key:?}"));
+            }
+        }
+    }
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (key, child) in obj {
+        let child_path = format!("{path}.{key}");
+        match properties.and_then(|p| p.get(key)) {
+            Some(child_schema) => validate_value(child, child_schema, &child_path, errors),
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    errors.push(format!("{path}: unexpected property {key:?}"));
+                }
+                Some(extra @ Value::Object(_)) => {
+                    validate_value(child, extra, &child_path, errors);
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+fn validate_combinators(
+    value: &Value,
+    schema: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    let matches = |sub: &Value| {
+        let mut sub_errors = Vec::new();
+        validate_value(value, sub, path, &mut sub_errors);
+        sub_errors.is_empty()
+    };
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        for sub in all {
+            validate_value(value, sub, path, errors);
+        }
+    }
+    if let Some(any) = schema.get("anyOf").and_then(Value::as_array)
+        && !any.iter().any(matches)
+    {
+        errors.push(format!("{path}: does not match any schema in anyOf"));
+    }
+    if let Some(one) = schema.get("oneOf").and_then(Value::as_array)
+        && one.iter().filter(|sub| matches(sub)).count() != 1
+    {
+        errors.push(format!("{path}: must match exactly one schema in oneOf"));
+    }
+}
+
+fn type_matches(value: &Value, expected: &Value) -> bool {
+    match expected {
+        Value::String(name) => match name.as_str() {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            "number" => value.is_number(),
+            "integer" => {
+                value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            _ => true,
+        },
+        Value::Array(names) => names.iter().any(|name| type_matches(value, name)),
+        _ => true,
+    }
+}
+
+/// Run a task with an optional output schema.
+///
+/// `run(message, session_id)` performs one agent call. Without a schema it
+/// is called once with `prompt`. With one, the prompt carries the schema,
+/// the reply is validated, and a failing reply gets one repair round in the
+/// reply's session before the task is marked failed.
+pub async fn run_with_output_schema<R, F>(
+    schema: Option<&Value>,
+    prompt: &str,
+    session_id: &str,
+    mut run: F,
+) -> Result<R, String>
+where
+    R: TaskOutput,
+    F: AsyncFnMut(String, String) -> Result<R, String>,
+{
+    let Some(schema) = schema else {
+        return run(prompt.to_string(), session_id.to_string()).await;
+    };
+    let mut output = run(schema_prompt(prompt, schema), session_id.to_string()).await?;
+    if !output.succeeded() {
+        return Ok(output);
+    }
+    let errors = match check_output(output.result_text(), schema) {
+        Ok(json) => {
+            output.set_result(json);
+            return Ok(output);
+        }
+        Err(errors) => errors,
+    };
+    tracing::info!(
+        errors = errors.len(),
+        "task output failed schema validation; retrying"
+    );
+    let session = output.session().to_string();
+    let mut repaired = run(repair_prompt(schema, &errors), session).await?;
+    if !repaired.succeeded() {
+        return Ok(repaired);
+    }
+    match check_output(repaired.result_text(), schema) {
+        Ok(json) => repaired.set_result(json),
+        Err(errors) => repaired.fail(format!(
+            "Output does not match output_schema_json: {}",
+            errors.join("; ")
+        )),
+    }
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["verdict", "score"],
+            "additionalProperties": false,
+            "properties": {
+                "verdict": { "enum": ["pass", "fail"] },
+                "score": { "type": "integer", "minimum": 0, "maximum": 10 },
+                "notes": { "type": "array", "items": { "type": "string" }, "maxItems": 2 }
+            }
+        })
+    }
+
+    #[test]
+    fn parse_output_schema_rejects_non_objects() {
+        assert!(parse_output_schema("").unwrap().is_none());
+        assert!(
+            parse_output_schema(r#"{"type":"object"}"#)
+                .unwrap()
+                .is_some()
+        );
+        assert!(parse_output_schema("[1]").is_err());
+        assert!(parse_output_schema("{").is_err());
+    }
+
+    #[test]
+    fn check_output_accepts_fenced_json_and_compacts_it() {
+        let reply = "Here you go:\n```json\n{ \"verdict\": \"pass\", \"score\": 7 }\n```";
+        assert_eq!(
+            check_output(reply, &schema()).unwrap(),
+            r#"{"score":7,"verdict":"pass"}"#
+        );
+    }
+
+    #[test]
+    fn check_output_reports_violations_with_paths() {
+        let errors = check_output(
+            r#"{"verdict":"maybe","score":11,"notes":["a",2],"extra":true}"#,
+            &schema(),
+        )
+        .unwrap_err();
+        let joined = errors.join("\n");
+        assert!(joined.contains("$.verdict"));
+        assert!(joined.contains("$.score: must be <= 10"));
+        assert!(joined.contains("$.notes[1]: expected type"));
+        assert!(joined.contains("unexpected property \"extra\""));
+        assert!(check_output("no json here", &schema()).is_err());
+        assert!(
+            check_output(r#"{"verdict":"pass"}"#, &schema())
+                .unwrap_err()
+                .iter()
+                .any(|e| e.contains("\"score\""))
+        );
+    }
+
+    #[test]
+    fn combinators() {
+        let schema = json!({ "anyOf": [{ "type": "string" }, { "type": "integer" }] });
+        assert!(check_output("3", &schema).is_ok());
+        assert!(check_output("true", &schema).is_err());
+        let schema = json!({ "oneOf": [{ "type": "number" }, { "type": "integer" }] });
+        assert!(check_output("1.5", &schema).is_ok());
+        assert!(check_output("2", &schema).is_err());
+    }
+
+    fn reply(result: &str) -> InstanceTaskResponse {
+        InstanceTaskResponse {
+            success: true,
+            result: result.to_string(),
+            error: String::new(),
+            trace_id: String::new(),
+            duration_ms: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            session_id: "sess-1".into(),
+            tools_json: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn repairs_once_then_fails() {
+        let schema = schema();
+        let mut calls = Vec::new();
+        let out =
+            run_with_output_schema(Some(&schema), "grade it", "", async |message, session| {
+                calls.push((message, session));
+                Ok(reply(if calls.len() == 1 {
+                    "looks fine"
+                } else {
+                    r#"{"verdict":"pass","score":9}"#
+                }))
+            })
+            .await
+            .unwrap();
+        assert!(out.success);
+        assert_eq!(out.result, r#"{"score":9,"verdict":"pass"}"#);
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0].0.starts_with("grade it"));
+        assert!(calls[1].0.contains("did not conform"));
+        assert_eq!(calls[1].1, "sess-1");
+
+        let out = run_with_output_schema(Some(&schema), "grade it", "", async |_, _| {
+            Ok(reply("still prose"))
+        })
+        .await
+        .unwrap();
+        assert!(!out.success);
+        assert!(out.error.contains("output_schema_json"));
+    }
+
+    #[tokio::test]
+    async fn without_schema_runs_prompt_unchanged() {
+        let out = run_with_output_schema(None, "hi", "s", async |message, session| {
+            assert_eq!((message.as_str(), session.as_str()), ("hi", "s"));
+            Ok(reply("plain text"))
+        })
+        .await
+        .unwrap();
+        assert_eq!(out.result, "plain text");
+    }
+}
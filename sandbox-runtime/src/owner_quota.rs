@@ -0,0 +1,346 @@
+//! Per-owner resource quotas.
+//!
+//! Keeps one customer from exhausting a shared operator. Every owner gets
+//! the operator's default quota (`OWNER_MAX_SANDBOXES`,
+//! `OWNER_MAX_CPU_CORES`, `OWNER_MAX_MEMORY_MB`,
+//! `OWNER_MAX_CONCURRENT_TASKS`; 0 or unset = unlimited), which the managing
+//! operator can override per owner with `PUT /api/operator/quotas/{owner}`.
+//!
+//! Sandbox limits are checked at create time under the creation permit (see
+//! [`check_owner_create`]); CPU and memory count every sandbox the owner
+//! holds, stopped ones included, so a resume never needs a second check.
+//! Task limits are held as [`OwnerTaskGuard`]s for as long as a task is
+//! queued or running. Owners read their usage at `GET /api/usage`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SandboxError};
+use crate::runtime::{SandboxRecord, sandboxes};
+use crate::store::PersistentStore;
+
+/// Limits for one owner. 0 = unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnerQuota {
+    #[serde(default)]
+    pub max_sandboxes: u64,
+    /// Total CPU cores across the owner's sandboxes.
+    #[serde(default)]
+    pub max_cpu_cores: u64,
+    /// Total memory (MB) across the owner's sandboxes.
+    #[serde(default)]
+    pub max_memory_mb: u64,
+    /// Tasks queued or running at once.
+    #[serde(default)]
+    pub max_concurrent_tasks: u64,
+}
+
+/// A per-owner override as stored (and listed to the operator).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaOverride {
+    pub owner: String,
+    #[serde(flatten)]
+    pub quota: OwnerQuota,
+}
+
+/// What an owner currently holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct OwnerUsage {
+    pub sandboxes: u64,
+    pub cpu_cores: u64,
+    pub memory_mb: u64,
+    pub concurrent_tasks: u64,
+}
+
+static QUOTAS: OnceCell<PersistentStore<QuotaOverride>> = OnceCell::new();
+
+fn quota_store() -> Result<&'static PersistentStore<QuotaOverride>> {
+    QUOTAS
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("owner-quotas.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+fn owner_key(owner: &str) -> String {
+    owner.trim().to_ascii_lowercase()
+}
+
+fn env_limit(key: &str) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(0)
+}
+
+/// The operator-wide default quota, from the `OWNER_MAX_*` env vars.
+pub fn default_quota() -> OwnerQuota {
+    OwnerQuota {
+        max_sandboxes: env_limit("OWNER_MAX_SANDBOXES"),
+        max_cpu_cores: env_limit("OWNER_MAX_CPU_CORES"),
+        max_memory_mb: env_limit("OWNER_MAX_MEMORY_MB"),
+        max_concurrent_tasks: env_limit("OWNER_MAX_CONCURRENT_TASKS"),
+    }
+}
+
+/// The quota that applies to `owner`: its override, else the default.
+pub fn quota_for(owner: &str) -> Result<OwnerQuota> {
+    Ok(quota_store()?
+        .get(&owner_key(owner))?
+        .map(|o| o.quota)
+        .unwrap_or_else(default_quota))
+}
+
+/// Every per-owner override, sorted by owner.
+pub fn list_quota_overrides() -> Result<Vec<QuotaOverride>> {
+    let mut overrides = quota_store()?.values()?;
+    overrides.sort_by(|a, b| a.owner.cmp(&b.owner));
+    Ok(overrides)
+}
+
+/// Set `owner`'s override.
+pub fn set_quota_override(owner: &str, quota: OwnerQuota) -> Result<QuotaOverride> {
+    let owner = owner_key(owner);
+    if owner.is_empty() {
+        return Err(SandboxError::Validation("owner must not be empty".into()));
+    }
+    let entry = QuotaOverride {
+        owner: owner.clone(),
+        quota,
+    };
+    quota_store()?.insert(owner, entry.clone())?;
+    Ok(entry)
+}
+
+/// Drop `owner`'s override so the default applies again. Returns whether
+/// one existed.
+pub fn remove_quota_override(owner: &str) -> Result<bool> {
+    Ok(quota_store()?.remove(&owner_key(owner))?.is_some())
+}
+
+// ── Tasks ────────────────────────────────────────────────────────────────
+
+static RUNNING_TASKS: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(Default::default);
+
+/// Holds one of an owner's concurrent-task slots until dropped.
+pub struct OwnerTaskGuard(String);
+
+impl Drop for OwnerTaskGuard {
+    fn drop(&mut self) {
+        let mut running = RUNNING_TASKS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = running.get_mut(&self.0) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                running.remove(&self.0);
+            }
+        }
+    }
+}
+
+fn running_tasks(owner: &str) -> u64 {
+    RUNNING_TASKS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&owner_key(owner))
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Take a concurrent-task slot for `owner`, or refuse once the owner's
+/// `max_concurrent_tasks` are in use.
+pub fn acquire_task_slot(owner: &str) -> Result<OwnerTaskGuard> {
+    let key = owner_key(owner);
+    let limit = quota_for(&key)?.max_concurrent_tasks;
+    let mut running = RUNNING_TASKS.lock().unwrap_or_else(|e| e.into_inner());
+    let count = running.entry(key.clone()).or_insert(0);
+    if limit != 0 && *count >= limit {
+        return Err(quota_exceeded(format!("{count}/{limit} concurrent tasks")));
+    }
+    *count += 1;
+    Ok(OwnerTaskGuard(key))
+}
+
+/// Take up to `wanted` of `owner`'s task slots for a batch, at least one.
+/// Fewer are returned when the owner has fewer free; the batch runs with
+/// that much parallelism.
+pub fn acquire_task_slots(owner: &str, wanted: usize) -> Result<Vec<OwnerTaskGuard>> {
+    let mut slots = vec![acquire_task_slot(owner)?];
+    while slots.len() < wanted {
+        match acquire_task_slot(owner) {
+            Ok(slot) => slots.push(slot),
+            Err(_) => break,
+        }
+    }
+    Ok(slots)
+}
+
+// ── Sandboxes ────────────────────────────────────────────────────────────
+
+fn quota_exceeded(detail: String) -> SandboxError {
+    // "quota" classifies as CAPACITY for job callers.
+    SandboxError::Unavailable(format!(
+        "Owner quota exceeded ({detail}). Delete unused sandboxes or wait for running tasks to finish."
+    ))
+}
+
+/// Sandbox footprint of `owner` in `records`, skipping `excluded_id` (a
+/// sandbox being recreated in place).
+pub(crate) fn sandbox_usage_of(
+    records: &[SandboxRecord],
+    owner: &str,
+    excluded_id: Option<&str>,
+) -> OwnerUsage {
+    let mut usage = OwnerUsage::default();
+    for record in records {
+        if record.owner.is_empty()
+            || !record.owner.eq_ignore_ascii_case(owner)
+            || excluded_id == Some(record.id.as_str())
+        {
+            continue;
+        }
+        usage.sandboxes += 1;
+        usage.cpu_cores = usage.cpu_cores.saturating_add(record.cpu_cores);
+        usage.memory_mb = usage.memory_mb.saturating_add(record.memory_mb);
+    }
+    usage
+}
+
+/// Decision core of the sandbox quota: whether `count` more sandboxes of
+/// `cpu_cores`/`memory_mb` each fit next to `usage`. Sandboxes requesting
+/// unlimited (0) CPU or memory are refused when that resource is capped,
+/// since their footprint cannot be counted.
+pub(crate) fn check_sandbox_quota(
+    quota: &OwnerQuota,
+    usage: &OwnerUsage,
+    count: u64,
+    cpu_cores: u64,
+    memory_mb: u64,
+) -> Result<()> {
+    if quota.max_sandboxes != 0 && usage.sandboxes.saturating_add(count) > quota.max_sandboxes {
+        return Err(quota_exceeded(format!(
+            "{} sandboxes + {count} requested > {}",
+            usage.sandboxes, quota.max_sandboxes
+        )));
+    }
+    for (name, held, each, max) in [
+        ("cpu_cores", usage.cpu_cores, cpu_cores, quota.max_cpu_cores),
+        ("memory_mb", usage.memory_mb, memory_mb, quota.max_memory_mb),
+    ] {
+        if max == 0 {
+            continue;
+        }
+        if each == 0 {
+            return Err(SandboxError::Validation(format!(
+                "{name} must be set explicitly: this owner's {name} is capped by quota"
+            )));
+        }
+        let requested = each.saturating_mul(count);
+        if held.saturating_add(requested) > max {
+            return Err(quota_exceeded(format!(
+                "{held} {name} held + {requested} requested > {max}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check that `owner` may create `count` more sandboxes of the given size.
+/// `excluded_id` is a sandbox being replaced by this create. Sandboxes
+/// without an owner are not subject to quotas.
+pub fn check_owner_create(
+    owner: &str,
+    count: u64,
+    cpu_cores: u64,
+    memory_mb: u64,
+    excluded_id: Option<&str>,
+) -> Result<()> {
+    if owner.trim().is_empty() {
+        return Ok(());
+    }
+    let quota = quota_for(owner)?;
+    if quota.max_sandboxes == 0 && quota.max_cpu_cores == 0 && quota.max_memory_mb == 0 {
+        return Ok(());
+    }
+    let records = sandboxes()?.values()?;
+    let usage = sandbox_usage_of(&records, owner, excluded_id);
+    check_sandbox_quota(&quota, &usage, count, cpu_cores, memory_mb)
+}
+
+/// `owner`'s current usage and the quota that applies to it.
+pub fn usage_for(owner: &str) -> Result<(OwnerUsage, OwnerQuota)> {
+    let records = sandboxes()?.values()?;
+    let mut usage = sandbox_usage_of(&records, owner, None);
+    usage.concurrent_tasks = running_tasks(owner);
+    Ok((usage, quota_for(owner)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(sandboxes: u64, cpu: u64, memory: u64) -> OwnerQuota {
+        OwnerQuota {
+            max_sandboxes: sandboxes,
+            max_cpu_cores: cpu,
+            max_memory_mb: memory,
+            max_concurrent_tasks: 0,
+        }
+    }
+
+    fn usage(sandboxes: u64, cpu: u64, memory: u64) -> OwnerUsage {
+        OwnerUsage {
+            sandboxes,
+            cpu_cores: cpu,
+            memory_mb: memory,
+            concurrent_tasks: 0,
+        }
+    }
+
+    #[test]
+    fn unlimited_quota_admits_anything() {
+        assert!(check_sandbox_quota(&OwnerQuota::default(), &usage(99, 99, 99), 50, 0, 0).is_ok());
+    }
+
+    #[test]
+    fn sandbox_count_quota_counts_the_whole_batch() {
+        let q = quota(3, 0, 0);
+        assert!(check_sandbox_quota(&q, &usage(1, 0, 0), 2, 1, 512).is_ok());
+        let err = check_sandbox_quota(&q, &usage(1, 0, 0), 3, 1, 512).unwrap_err();
+        assert!(matches!(err, SandboxError::Unavailable(_)), "got {err:?}");
+        assert_eq!(
+            crate::job_error::JobError::from(err).code,
+            crate::job_error::ErrorCode::Capacity
+        );
+    }
+
+    #[test]
+    fn resource_quotas_sum_held_and_requested() {
+        let q = quota(0, 8, 4096);
+        assert!(check_sandbox_quota(&q, &usage(2, 4, 2048), 2, 2, 1024).is_ok());
+        let err = check_sandbox_quota(&q, &usage(2, 4, 2048), 1, 5, 1024).unwrap_err();
+        assert!(err.to_string().contains("cpu_cores"), "{err}");
+        let err = check_sandbox_quota(&q, &usage(2, 4, 2048), 1, 1, 4096).unwrap_err();
+        assert!(err.to_string().contains("memory_mb"), "{err}");
+    }
+
+    #[test]
+    fn capped_resource_requires_an_explicit_request() {
+        let err = check_sandbox_quota(&quota(0, 8, 0), &usage(0, 0, 0), 1, 0, 0).unwrap_err();
+        assert!(matches!(err, SandboxError::Validation(_)), "got {err:?}");
+    }
+
+    #[test]
+    fn task_slots_are_released_on_drop() {
+        let owner = "0xquota-task-slots";
+        let first = acquire_task_slot(owner).unwrap();
+        let second = acquire_task_slot(owner).unwrap();
+        assert_eq!(running_tasks(owner), 2);
+        drop(first);
+        drop(second);
+        assert_eq!(running_tasks(owner), 0);
+    }
+}
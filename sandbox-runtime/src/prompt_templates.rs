@@ -0,0 +1,397 @@
+//! Per-sandbox prompt templates.
+//!
+//! Owners attach named templates (system prompt, message prefix/suffix and
+//! default variables) to a sandbox once instead of resending the same
+//! context on every call. Every `/agents/run` payload sent for the sandbox
+//! passes through [`apply_prompt_template`]:
+//!
+//! - the template named in the request metadata as `promptTemplate` is used,
+//!   else the sandbox's `default` template, else nothing changes;
+//! - `{{variable}}` placeholders are filled from the template's `variables`,
+//!   overridden by a `promptVariables` object in the request metadata;
+//! - the message becomes `prefix`, message, `suffix` (blank-line separated);
+//! - `system_prompt` becomes `backend.profile.systemPrompt` unless the
+//!   request already set one.
+//!
+//! Templates are persisted in the state directory and removed by GC once
+//! their sandbox is gone.
+
+use std::collections::BTreeMap;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{Result, SandboxError};
+use crate::store::PersistentStore;
+
+/// Template applied when a request names none.
+pub const DEFAULT_PROMPT_TEMPLATE: &str = "default";
+/// Metadata key selecting a template for one request.
+pub const PROMPT_TEMPLATE_KEY: &str = "promptTemplate";
+/// Metadata key holding per-request variable overrides.
+pub const PROMPT_VARIABLES_KEY: &str = "promptVariables";
+
+/// Maximum templates per sandbox.
+pub const MAX_PROMPT_TEMPLATES: usize = 32;
+/// Maximum combined size of a template's text fields and variables.
+pub const MAX_PROMPT_TEMPLATE_BYTES: usize = 64 * 1024;
+const MAX_NAME_LEN: usize = 64;
+
+/// Template fields supplied by the owner.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptTemplateInput {
+    #[serde(default)]
+    pub system_prompt: String,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub suffix: String,
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    #[serde(flatten)]
+    pub fields: PromptTemplateInput,
+    pub updated_at: u64,
+    /// Address that last wrote the template.
+    pub updated_by: String,
+}
+
+/// All templates of one sandbox, keyed by sandbox ID.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SandboxPromptTemplates {
+    pub sandbox_id: String,
+    #[serde(default)]
+    pub templates: BTreeMap<String, PromptTemplate>,
+}
+
+static TEMPLATES: OnceCell<PersistentStore<SandboxPromptTemplates>> = OnceCell::new();
+
+/// Access the prompt template persistent store.
+pub fn prompt_templates() -> Result<&'static PersistentStore<SandboxPromptTemplates>> {
+    TEMPLATES
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("prompt_templates.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+fn valid_name(name: &str, extra: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || extra.contains(c))
+}
+
+fn validate(name: &str, input: &PromptTemplateInput) -> Result<()> {
+    if !valid_name(name, "-.") {
+        return Err(SandboxError::Validation(format!(
+            "Template name must be 1-{MAX_NAME_LEN} chars of [A-Za-z0-9_.-]"
+        )));
+    }
+    if let Some(var) = input.variables.keys().find(|v| !valid_name(v, "")) {
+        return Err(SandboxError::Validation(format!(
+            "Invalid template variable name {var:?} (use [A-Za-z0-9_])"
+        )));
+    }
+    let size = input.system_prompt.len()
+        + input.prefix.len()
+        + input.suffix.len()
+        + input
+            .variables
+            .iter()
+            .map(|(k, v)| k.len() + v.len())
+            .sum::<usize>();
+    if size > MAX_PROMPT_TEMPLATE_BYTES {
+        return Err(SandboxError::Validation(format!(
+            "Template exceeds {MAX_PROMPT_TEMPLATE_BYTES} bytes"
+        )));
+    }
+    Ok(())
+}
+
+/// Templates of `sandbox_id`, sorted by name.
+pub fn list_prompt_templates(sandbox_id: &str) -> Result<Vec<PromptTemplate>> {
+    Ok(prompt_templates()?
+        .get(sandbox_id)?
+        .map(|t| t.templates.into_values().collect())
+        .unwrap_or_default())
+}
+
+pub fn get_prompt_template(sandbox_id: &str, name: &str) -> Result<Option<PromptTemplate>> {
+    Ok(prompt_templates()?
+        .get(sandbox_id)?
+        .and_then(|mut t| t.templates.remove(name)))
+}
+
+/// Create or replace template `name` of `sandbox_id`.
+pub fn put_prompt_template(
+    sandbox_id: &str,
+    name: &str,
+    input: PromptTemplateInput,
+    actor: &str,
+) -> Result<PromptTemplate> {
+    validate(name, &input)?;
+    let store = prompt_templates()?;
+    let mut all = store
+        .get(sandbox_id)?
+        .unwrap_or_else(|| SandboxPromptTemplates {
+            sandbox_id: sandbox_id.to_string(),
+            templates: BTreeMap::new(),
+        });
+    if !all.templates.contains_key(name) && all.templates.len() >= MAX_PROMPT_TEMPLATES {
+        return Err(SandboxError::Validation(format!(
+            "Sandbox already has {MAX_PROMPT_TEMPLATES} prompt templates"
+        )));
+    }
+    let template = PromptTemplate {
+        name: name.to_string(),
+        fields: input,
+        updated_at: crate::util::now_ts(),
+        updated_by: actor.to_string(),
+    };
+    all.templates.insert(name.to_string(), template.clone());
+    store.insert(sandbox_id.to_string(), all)?;
+    Ok(template)
+}
+
+/// Delete template `name` of `sandbox_id`. Returns whether it existed.
+pub fn delete_prompt_template(sandbox_id: &str, name: &str) -> Result<bool> {
+    let mut removed = false;
+    prompt_templates()?.update(sandbox_id, |all| {
+        removed = all.templates.remove(name).is_some();
+    })?;
+    Ok(removed)
+}
+
+/// Drop the templates of sandboxes that no longer exist.
+pub fn gc_prompt_templates() -> Result<()> {
+    let store = prompt_templates()?;
+    for all in store.values()? {
+        if let Err(SandboxError::NotFound(_)) = crate::runtime::get_sandbox_by_id(&all.sandbox_id) {
+            store.remove(&all.sandbox_id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace `{{name}}` placeholders with `variables`; unknown ones are kept.
+pub fn render_template(text: &str, variables: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let key = after[..end].trim();
+                match variables.get(key) {
+                    Some(value) => out.push_str(value),
+                    None => out.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Apply `sandbox_id`'s prompt template to an `/agents/run` payload (see
+/// the module docs). A template named by the request but missing is an
+/// error; a sandbox without templates leaves the payload unchanged.
+pub fn apply_prompt_template(sandbox_id: &str, payload: &mut Map<String, Value>) -> Result<()> {
+    let (requested, overrides) = match payload.get_mut("metadata").and_then(Value::as_object_mut) {
+        Some(metadata) => (
+            metadata.remove(PROMPT_TEMPLATE_KEY),
+            metadata.remove(PROMPT_VARIABLES_KEY),
+        ),
+        None => (None, None),
+    };
+    if payload
+        .get("metadata")
+        .and_then(Value::as_object)
+        .is_some_and(Map::is_empty)
+    {
+        payload.remove("metadata");
+    }
+    let requested = match requested {
+        None => None,
+        Some(Value::String(name)) => Some(name),
+        Some(_) => {
+            return Err(SandboxError::Validation(format!(
+                "{PROMPT_TEMPLATE_KEY} must be a string"
+            )));
+        }
+    };
+
+    let mut templates = match sandbox_id {
+        "" => BTreeMap::new(),
+        id => prompt_templates()?
+            .get(id)?
+            .map(|t| t.templates)
+            .unwrap_or_default(),
+    };
+    let template = match requested {
+        Some(name) => templates
+            .remove(&name)
+            .ok_or_else(|| SandboxError::NotFound(format!("Prompt template '{name}' not found")))?,
+        None => match templates.remove(DEFAULT_PROMPT_TEMPLATE) {
+            Some(template) => template,
+            None => return Ok(()),
+        },
+    };
+
+    let mut variables = template.fields.variables;
+    match overrides {
+        None => {}
+        Some(Value::Object(map)) => variables.extend(map.into_iter().map(|(k, v)| match v {
+            Value::String(s) => (k, s),
+            other => (k, other.to_string()),
+        })),
+        Some(_) => {
+            return Err(SandboxError::Validation(format!(
+                "{PROMPT_VARIABLES_KEY} must be an object"
+            )));
+        }
+    }
+
+    let message = payload
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let prefix = render_template(&template.fields.prefix, &variables);
+    let suffix = render_template(&template.fields.suffix, &variables);
+    let message = [prefix.as_str(), message, suffix.as_str()]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    payload.insert("message".to_string(), Value::String(message));
+
+    let system_prompt = render_template(&template.fields.system_prompt, &variables);
+    if !system_prompt.is_empty() {
+        let backend = payload
+            .entry("backend")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(backend) = backend.as_object_mut() {
+            let profile = backend
+                .entry("profile")
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Some(profile) = profile.as_object_mut() {
+                profile
+                    .entry("systemPrompt")
+                    .or_insert(Value::String(system_prompt));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn init() {
+        let dir =
+            std::env::temp_dir().join(format!("prompt-templates-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", dir) };
+    }
+
+    fn payload(message: &str, metadata: Value) -> Map<String, Value> {
+        let mut payload = Map::new();
+        payload.insert("message".into(), json!(message));
+        payload.insert("metadata".into(), metadata);
+        payload
+    }
+
+    #[test]
+    fn render_fills_known_variables_only() {
+        let vars = BTreeMap::from([("name".to_string(), "Ada".to_string())]);
+        assert_eq!(
+            render_template("Hi {{ name }}, {{unknown}} {{", &vars),
+            "Hi Ada, {{unknown}} {{"
+        );
+    }
+
+    #[test]
+    fn default_template_is_applied() {
+        init();
+        let input = PromptTemplateInput {
+            system_prompt: "You are {{persona}}.".into(),
+            prefix: "Context: {{project}}".into(),
+            suffix: String::new(),
+            variables: BTreeMap::from([
+                ("persona".to_string(), "a reviewer".to_string()),
+                ("project".to_string(), "core".to_string()),
+            ]),
+        };
+        put_prompt_template("sb-tpl-default", "default", input, "0xabc").unwrap();
+
+        let mut p = payload(
+            "check this",
+            json!({ "promptVariables": { "project": "ui" } }),
+        );
+        apply_prompt_template("sb-tpl-default", &mut p).unwrap();
+        assert_eq!(p["message"], "Context: ui\n\ncheck this");
+        assert_eq!(
+            p["backend"]["profile"]["systemPrompt"],
+            "You are a reviewer."
+        );
+        assert!(p.get("metadata").is_none());
+    }
+
+    #[test]
+    fn named_template_and_errors() {
+        init();
+        let input = PromptTemplateInput {
+            suffix: "Answer in JSON.".into(),
+            ..Default::default()
+        };
+        put_prompt_template("sb-tpl-named", "json", input, "0xabc").unwrap();
+
+        let mut p = payload("list files", json!({ "promptTemplate": "json", "k": 1 }));
+        apply_prompt_template("sb-tpl-named", &mut p).unwrap();
+        assert_eq!(p["message"], "list files\n\nAnswer in JSON.");
+        assert_eq!(p["metadata"], json!({ "k": 1 }));
+
+        let mut missing = payload("x", json!({ "promptTemplate": "nope" }));
+        assert!(apply_prompt_template("sb-tpl-named", &mut missing).is_err());
+
+        let mut untouched = payload("x", json!({}));
+        apply_prompt_template("sb-tpl-named", &mut untouched).unwrap();
+        assert_eq!(untouched["message"], "x");
+
+        assert!(delete_prompt_template("sb-tpl-named", "json").unwrap());
+        assert!(!delete_prompt_template("sb-tpl-named", "json").unwrap());
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        init();
+        assert!(put_prompt_template("sb", "bad name", Default::default(), "a").is_err());
+        let input = PromptTemplateInput {
+            variables: BTreeMap::from([("a-b".to_string(), String::new())]),
+            ..Default::default()
+        };
+        assert!(put_prompt_template("sb", "ok", input, "a").is_err());
+        let input = PromptTemplateInput {
+            prefix: "x".repeat(MAX_PROMPT_TEMPLATE_BYTES + 1),
+            ..Default::default()
+        };
+        assert!(put_prompt_template("sb", "ok", input, "a").is_err());
+    }
+}
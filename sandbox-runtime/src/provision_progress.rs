@@ -6,9 +6,19 @@
 //! Progress is persisted to disk so it survives operator restarts and can be
 //! queried by external systems. The `metadata` field allows blueprint-specific
 //! data (e.g. `service_id`, `bot_id`) without modifying the core schema.
+//!
+//! A provision whose operator restarted mid-flight would otherwise sit at its
+//! last phase forever; [`reconcile_interrupted_provisions`] (run from
+//! `reaper::reconcile_on_startup`) settles each one against the sandbox store.
+//!
+//! While the sidecar image is pulled, [`update_image_pull_progress`] records
+//! layer and byte counts so the `image_pull` phase shows a real percentage.
+//! Every change is also published to [`subscribe_provision_events`], which
+//! backs the `GET /api/provisions/{call_id}/events` SSE stream.
 
-use once_cell::sync::OnceCell;
+use once_cell::sync::{Lazy, OnceCell};
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
 
 use crate::error::{Result, SandboxError};
 use crate::store::PersistentStore;
@@ -64,6 +74,34 @@ pub struct ProvisionStatus {
     /// Defaults to `null` for backward compatibility.
     #[serde(default)]
     pub metadata: serde_json::Value,
+    /// Layer/byte counts of the image pull, while and after it runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_pull: Option<ImagePullProgress>,
+}
+
+/// Progress of a Docker image pull. Byte totals only cover layers whose size
+/// Docker has reported so far, so they grow as the pull starts new layers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImagePullProgress {
+    pub layers_total: u32,
+    pub layers_done: u32,
+    pub bytes_total: u64,
+    pub bytes_done: u64,
+}
+
+impl ImagePullProgress {
+    /// Completion percentage (0–100): bytes when sizes are known, else layers.
+    pub fn pct(&self) -> u8 {
+        let (done, total) = if self.bytes_total > 0 {
+            (self.bytes_done, self.bytes_total)
+        } else {
+            (u64::from(self.layers_done), u64::from(self.layers_total))
+        };
+        if total == 0 {
+            return 0;
+        }
+        (done.min(total) * 100 / total) as u8
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -82,6 +120,20 @@ pub fn provisions() -> Result<&'static PersistentStore<ProvisionStatus>> {
         .map_err(|err: SandboxError| err)
 }
 
+const PROVISION_EVENT_BUFFER: usize = 256;
+
+static PROVISION_EVENTS: Lazy<broadcast::Sender<ProvisionStatus>> =
+    Lazy::new(|| broadcast::channel(PROVISION_EVENT_BUFFER).0);
+
+/// Subscribe to every provision change, of all calls.
+pub fn subscribe_provision_events() -> broadcast::Receiver<ProvisionStatus> {
+    PROVISION_EVENTS.subscribe()
+}
+
+fn publish(status: &ProvisionStatus) {
+    let _ = PROVISION_EVENTS.send(status.clone());
+}
+
 /// Begin tracking a new provision for the given call ID.
 pub fn start_provision(call_id: u64) -> Result<ProvisionStatus> {
     let now = crate::util::now_ts();
@@ -95,8 +147,10 @@ pub fn start_provision(call_id: u64) -> Result<ProvisionStatus> {
         progress_pct: 0,
         sidecar_url: None,
         metadata: serde_json::Value::Null,
+        image_pull: None,
     };
     provisions()?.insert(call_id.to_string(), status.clone())?;
+    publish(&status);
     Ok(status)
 }
 
@@ -127,11 +181,52 @@ pub fn update_provision(
         }
     })?;
 
-    if updated {
-        Ok(store.get(&key)?)
-    } else {
-        Ok(None)
+    if !updated {
+        return Ok(None);
+    }
+    let status = store.get(&key)?;
+    if let Some(status) = &status {
+        publish(status);
+    }
+    Ok(status)
+}
+
+/// Record image pull progress for a call. While the call is in the
+/// `image_pull` phase its `progress_pct` moves from that phase's value
+/// towards the next one's.
+pub fn update_image_pull_progress(
+    call_id: u64,
+    progress: ImagePullProgress,
+) -> Result<Option<ProvisionStatus>> {
+    let now = crate::util::now_ts();
+    let key = call_id.to_string();
+    let store = provisions()?;
+
+    let updated = store.update(&key, |entry| {
+        if entry.phase == ProvisionPhase::ImagePull {
+            let from = ProvisionPhase::ImagePull.progress_pct();
+            let to = ProvisionPhase::ContainerCreate.progress_pct();
+            entry.progress_pct = from + (to - from) * progress.pct() / 100;
+            entry.message = Some(format!(
+                "Pulling image: {}/{} layers, {}/{} MiB",
+                progress.layers_done,
+                progress.layers_total,
+                progress.bytes_done / (1024 * 1024),
+                progress.bytes_total / (1024 * 1024)
+            ));
+        }
+        entry.updated_at = now;
+        entry.image_pull = Some(progress);
+    })?;
+
+    if !updated {
+        return Ok(None);
+    }
+    let status = store.get(&key)?;
+    if let Some(status) = &status {
+        publish(status);
     }
+    Ok(status)
 }
 
 /// Update the metadata for a provision.
@@ -178,6 +273,66 @@ pub fn gc_provisions(max_age_secs: u64) -> Result<()> {
     Ok(())
 }
 
+/// Settle provisions left non-terminal by a previous operator process. Must
+/// run at startup, after sandbox records are reconciled with the runtime and
+/// before new jobs start. A provision whose sandbox is in the store and
+/// running becomes `Ready` (its post-start steps may not have run); every
+/// other one becomes `Failed` with the phase it was abandoned in. Returns the
+/// settled entries.
+pub fn reconcile_interrupted_provisions() -> Result<Vec<ProvisionStatus>> {
+    let interrupted = list_active_provisions()?;
+    if interrupted.is_empty() {
+        return Ok(interrupted);
+    }
+    let sandboxes = crate::runtime::sandboxes()?;
+    let mut settled = Vec::with_capacity(interrupted.len());
+    for status in interrupted {
+        let phase = status.phase;
+        let record = match status.sandbox_id.as_deref() {
+            Some(id) => sandboxes.get(id)?,
+            None => None,
+        };
+        let updated = match record {
+            Some(record) if record.state == crate::runtime::SandboxState::Running => {
+                update_provision(
+                    status.call_id,
+                    ProvisionPhase::Ready,
+                    Some(format!(
+                        "Operator restarted during {}; sandbox is running but setup after container start may be incomplete",
+                        phase_label(phase)
+                    )),
+                    None,
+                    Some(record.sidecar_url),
+                )?
+            }
+            record => update_provision(
+                status.call_id,
+                ProvisionPhase::Failed,
+                Some(format!(
+                    "Operator restarted during {}; {}",
+                    phase_label(phase),
+                    if record.is_some() {
+                        "sandbox is not running"
+                    } else {
+                        "provision abandoned"
+                    }
+                )),
+                None,
+                None,
+            )?,
+        };
+        settled.extend(updated);
+    }
+    Ok(settled)
+}
+
+fn phase_label(phase: ProvisionPhase) -> String {
+    serde_json::to_value(phase)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
 #[cfg(any(test, feature = "test-utils"))]
 pub fn clear_all_for_testing() -> Result<()> {
     provisions()?.replace(std::collections::HashMap::new())
@@ -257,4 +412,45 @@ mod tests {
         let fetched = get_provision(call_id).unwrap().unwrap();
         assert_eq!(fetched.metadata, meta);
     }
+
+    #[test]
+    fn image_pull_progress_interpolates_and_publishes() {
+        init();
+
+        let call_id = 42_000_003;
+        start_provision(call_id).unwrap();
+        update_provision(call_id, ProvisionPhase::ImagePull, None, None, None).unwrap();
+        let mut rx = subscribe_provision_events();
+
+        let progress = ImagePullProgress {
+            layers_total: 4,
+            layers_done: 1,
+            bytes_total: 400 * 1024 * 1024,
+            bytes_done: 200 * 1024 * 1024,
+        };
+        assert_eq!(progress.pct(), 50);
+        let status = update_image_pull_progress(call_id, progress.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.progress_pct, 30);
+        assert_eq!(status.image_pull.as_ref(), Some(&progress));
+        assert_eq!(
+            status.message.as_deref(),
+            Some("Pulling image: 1/4 layers, 200/400 MiB")
+        );
+
+        let published = std::iter::from_fn(|| rx.try_recv().ok())
+            .find(|s| s.call_id == call_id)
+            .unwrap();
+        assert_eq!(published.progress_pct, 30);
+
+        // Layers only, before any sizes are known.
+        let layers_only = ImagePullProgress {
+            layers_total: 4,
+            layers_done: 3,
+            ..Default::default()
+        };
+        assert_eq!(layers_only.pct(), 75);
+        assert_eq!(ImagePullProgress::default().pct(), 0);
+    }
 }
@@ -0,0 +1,206 @@
+//! Per-sandbox routes for the Blueprint Manager (BPM) proxy.
+//!
+//! The operator API is registered with the BPM proxy as one upstream, so all
+//! sandbox traffic passes through this process. When a
+//! [`ProxyRouteRegistrar`] is installed (the binary's adapter over the BPM
+//! bridge), [`reconcile_proxy_routes`] also publishes one route per running
+//! sandbox: the `/sandboxes/{id}` path prefix to that sandbox's sidecar URL,
+//! scoped to the sandbox owner, so the proxy can send the traffic straight
+//! to the sidecar. The routes last registered are kept in `proxy-routes.json`;
+//! each pass registers new or moved sandboxes and unregisters stopped or
+//! deleted ones, including those removed while the operator was down.
+//! Without a registrar nothing is published and traffic keeps going through
+//! the operator API.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::runtime::{SandboxRecord, SandboxState, sandboxes};
+use crate::store::PersistentStore;
+
+/// Default seconds between [`reconcile_proxy_routes`] passes.
+pub const DEFAULT_PROXY_ROUTE_SYNC_SECS: u64 = 15;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxRoute {
+    pub sandbox_id: String,
+    /// Request path prefix routed to `upstream`, e.g. `/sandboxes/{id}`.
+    pub path_prefix: String,
+    /// Sidecar base URL.
+    pub upstream: String,
+    /// Only this address's API keys may use the route.
+    pub owner: String,
+}
+
+/// Publishes routes to the proxy.
+#[async_trait]
+pub trait ProxyRouteRegistrar: Send + Sync {
+    async fn register(&self, route: &SandboxRoute) -> Result<()>;
+    async fn unregister(&self, route: &SandboxRoute) -> Result<()>;
+}
+
+static REGISTRAR: OnceCell<Arc<dyn ProxyRouteRegistrar>> = OnceCell::new();
+static ROUTES: OnceCell<PersistentStore<SandboxRoute>> = OnceCell::new();
+
+/// Install the registrar. Call once at startup, before the first sync.
+pub fn init_proxy_route_registrar(registrar: Arc<dyn ProxyRouteRegistrar>) {
+    if REGISTRAR.set(registrar).is_err() {
+        tracing::warn!("Proxy route registrar already initialized, ignoring duplicate init");
+    }
+}
+
+pub fn proxy_routes_enabled() -> bool {
+    REGISTRAR.get().is_some()
+}
+
+/// Seconds between syncs: `PROXY_ROUTE_SYNC_SECS`, default
+/// [`DEFAULT_PROXY_ROUTE_SYNC_SECS`].
+pub fn proxy_route_sync_interval() -> u64 {
+    std::env::var("PROXY_ROUTE_SYNC_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_PROXY_ROUTE_SYNC_SECS)
+}
+
+fn registered_routes() -> Result<&'static PersistentStore<SandboxRoute>> {
+    ROUTES.get_or_try_init(|| {
+        let path = crate::store::state_dir().join("proxy-routes.json");
+        PersistentStore::open(path)
+    })
+}
+
+/// The route `record` should have: running sandboxes with a sidecar URL and
+/// an owner only.
+pub fn route_for(record: &SandboxRecord) -> Option<SandboxRoute> {
+    let routable = record.state == SandboxState::Running
+        && !record.sidecar_url.is_empty()
+        && !record.owner.is_empty();
+    routable.then(|| SandboxRoute {
+        sandbox_id: record.id.clone(),
+        path_prefix: format!("/sandboxes/{}", record.id),
+        upstream: record.sidecar_url.clone(),
+        owner: record.owner.to_ascii_lowercase(),
+    })
+}
+
+/// Routes to register and routes to unregister to get from `registered` to
+/// `desired`. A route whose upstream or owner changed is unregistered and
+/// registered again.
+pub fn plan_route_changes(
+    registered: &[SandboxRoute],
+    desired: &[SandboxRoute],
+) -> (Vec<SandboxRoute>, Vec<SandboxRoute>) {
+    let current: HashMap<&str, &SandboxRoute> = registered
+        .iter()
+        .map(|r| (r.sandbox_id.as_str(), r))
+        .collect();
+    let wanted: HashMap<&str, &SandboxRoute> =
+        desired.iter().map(|r| (r.sandbox_id.as_str(), r)).collect();
+
+    let register = desired
+        .iter()
+        .filter(|r| current.get(r.sandbox_id.as_str()) != Some(r))
+        .cloned()
+        .collect();
+    let unregister = registered
+        .iter()
+        .filter(|r| wanted.get(r.sandbox_id.as_str()) != Some(r))
+        .cloned()
+        .collect();
+    (register, unregister)
+}
+
+/// Bring the proxy's routes in line with the sandbox store. Returns how many
+/// routes changed; failures are logged and retried on the next pass.
+pub async fn reconcile_proxy_routes() -> Result<usize> {
+    let Some(registrar) = REGISTRAR.get() else {
+        return Ok(0);
+    };
+    let store = registered_routes()?;
+    let desired: Vec<SandboxRoute> = sandboxes()?
+        .values()?
+        .iter()
+        .filter_map(route_for)
+        .collect();
+    let (register, unregister) = plan_route_changes(&store.values()?, &desired);
+
+    let mut changed = 0;
+    for route in unregister {
+        match registrar.unregister(&route).await {
+            Ok(()) => {
+                store.remove(&route.sandbox_id)?;
+                changed += 1;
+            }
+            Err(err) => tracing::warn!(
+                sandbox_id = %route.sandbox_id,
+                "Failed to unregister sandbox proxy route: {err}"
+            ),
+        }
+    }
+    for route in register {
+        match registrar.register(&route).await {
+            Ok(()) => {
+                store.insert(route.sandbox_id.clone(), route)?;
+                changed += 1;
+            }
+            Err(err) => tracing::warn!(
+                sandbox_id = %route.sandbox_id,
+                "Failed to register sandbox proxy route: {err}"
+            ),
+        }
+    }
+    if changed > 0 {
+        tracing::info!(changed, "Synced sandbox proxy routes");
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(id: &str, upstream: &str) -> SandboxRoute {
+        SandboxRoute {
+            sandbox_id: id.to_string(),
+            path_prefix: format!("/sandboxes/{id}"),
+            upstream: upstream.to_string(),
+            owner: "0xabc".to_string(),
+        }
+    }
+
+    #[test]
+    fn plan_registers_new_and_moved_routes_and_drops_gone_ones() {
+        let registered = vec![
+            route("kept", "http://127.0.0.1:9001"),
+            route("moved", "http://127.0.0.1:9002"),
+            route("gone", "http://127.0.0.1:9003"),
+        ];
+        let desired = vec![
+            route("kept", "http://127.0.0.1:9001"),
+            route("moved", "http://127.0.0.1:9102"),
+            route("new", "http://127.0.0.1:9004"),
+        ];
+        let (register, unregister) = plan_route_changes(&registered, &desired);
+        let ids = |routes: &[SandboxRoute]| -> Vec<String> {
+            routes.iter().map(|r| r.sandbox_id.clone()).collect()
+        };
+        assert_eq!(ids(&register), vec!["moved", "new"]);
+        assert_eq!(ids(&unregister), vec!["moved", "gone"]);
+        assert_eq!(register[0].upstream, "http://127.0.0.1:9102");
+        assert_eq!(unregister[0].upstream, "http://127.0.0.1:9002");
+    }
+
+    #[test]
+    fn plan_is_empty_when_in_sync() {
+        let routes = vec![route("a", "http://127.0.0.1:9001")];
+        let (register, unregister) = plan_route_changes(&routes, &routes);
+        assert!(register.is_empty());
+        assert!(unregister.is_empty());
+    }
+}
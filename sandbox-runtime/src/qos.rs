@@ -0,0 +1,220 @@
+//! QoS wiring shared by the blueprint binaries (`qos` feature).
+//!
+//! With `QOS_ENABLED=true`, [`start_qos_from_env`] builds the
+//! `blueprint_qos` service: metrics collection plus, when the heartbeat env
+//! is complete (see [`build_heartbeat_config`]), signed heartbeats to the
+//! `OperatorStatusRegistry` so the operator is not slashed for liveness.
+//! [`spawn_qos_metrics_loop`] then pushes the runtime's counters
+//! ([`crate::metrics`]) as on-chain metrics every `QOS_METRICS_INTERVAL_SECS`.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use blueprint_qos::QoSServiceBuilder;
+use blueprint_qos::heartbeat::{HeartbeatConfig, HeartbeatConsumer, HeartbeatStatus};
+use blueprint_qos::metrics::MetricsConfig;
+use blueprint_qos::metrics::provider::EnhancedMetricsProvider;
+use tracing::{error, info, warn};
+
+/// Parse a u64 from the first env var that's set in `keys`. Logs a warning
+/// and returns `None` if a value is set but doesn't parse — so operators
+/// see misconfiguration in observability instead of features silently
+/// disabling.
+pub fn parse_required_u64_env(keys: &[&str]) -> Option<u64> {
+    for key in keys {
+        match std::env::var(key) {
+            Ok(raw) => match raw.parse::<u64>() {
+                Ok(v) => return Some(v),
+                Err(e) => {
+                    warn!(
+                        env = key,
+                        value = %raw,
+                        err = %e,
+                        "env var is set but not a valid u64; falling back to next key"
+                    );
+                }
+            },
+            Err(_) => continue,
+        }
+    }
+    None
+}
+
+/// Build heartbeat config from environment variables.
+///
+/// Required env vars:
+///   - `SERVICE_ID` or `TANGLE_SERVICE_ID` — the service instance ID
+///   - `BLUEPRINT_ID` or `TANGLE_BLUEPRINT_ID` — the blueprint ID
+///   - `STATUS_REGISTRY_ADDRESS` — the OperatorStatusRegistry contract address
+///
+/// Optional:
+///   - `HEARTBEAT_INTERVAL_SECS` — heartbeat interval (default: 120)
+///   - `HEARTBEAT_MAX_MISSED` — max missed beats before slashing (default: 3)
+pub fn build_heartbeat_config() -> Option<HeartbeatConfig> {
+    let service_id: u64 = parse_required_u64_env(&["SERVICE_ID", "TANGLE_SERVICE_ID"])?;
+    let blueprint_id: u64 = parse_required_u64_env(&["BLUEPRINT_ID", "TANGLE_BLUEPRINT_ID"])?;
+
+    let registry_addr_str = std::env::var("STATUS_REGISTRY_ADDRESS").ok()?;
+    let status_registry_address = match alloy::primitives::Address::from_str(&registry_addr_str) {
+        Ok(addr) => addr,
+        Err(e) => {
+            warn!(
+                value = %registry_addr_str,
+                err = %e,
+                "STATUS_REGISTRY_ADDRESS is set but not a valid EVM address; heartbeat disabled"
+            );
+            return None;
+        }
+    };
+
+    let interval_secs: u64 = std::env::var("HEARTBEAT_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(120);
+
+    let max_missed: u32 = std::env::var("HEARTBEAT_MAX_MISSED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3);
+
+    Some(HeartbeatConfig {
+        interval_secs,
+        jitter_percent: 10,
+        service_id,
+        blueprint_id,
+        max_missed_heartbeats: max_missed,
+        status_registry_address,
+    })
+}
+
+/// Logging heartbeat consumer that records heartbeat submissions.
+///
+/// The actual on-chain submission is handled internally by `HeartbeatService`
+/// via ECDSA signing + `submitHeartbeat` contract call. This consumer provides
+/// a hook for blueprint-level logging/monitoring of heartbeat events.
+#[derive(Clone)]
+pub struct LoggingHeartbeatConsumer;
+
+impl HeartbeatConsumer for LoggingHeartbeatConsumer {
+    fn send_heartbeat(
+        &self,
+        status: &HeartbeatStatus,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = blueprint_qos::error::Result<()>> + Send + 'static>,
+    > {
+        let service_id = status.service_id;
+        let status_code = status.status_code;
+        let ts = status.timestamp;
+        Box::pin(async move {
+            info!("Heartbeat sent: service={service_id} status={status_code} ts={ts}");
+            Ok(())
+        })
+    }
+}
+
+/// A started QoS service whose metrics loop has not been spawned yet.
+pub struct QosMetrics {
+    provider: Arc<EnhancedMetricsProvider>,
+    interval_secs: u64,
+}
+
+/// Start QoS (heartbeat + metrics collection + on-chain reporting) when
+/// `QOS_ENABLED=true`. Failures are logged and QoS is skipped.
+///
+/// Env: `QOS_METRICS_INTERVAL_SECS` (default 60), `QOS_DRY_RUN` (default
+/// true), `HTTP_RPC_ENDPOINT` / `RPC_URL`, `KEYSTORE_URI`, plus the
+/// heartbeat env of [`build_heartbeat_config`].
+pub async fn start_qos_from_env() -> Option<QosMetrics> {
+    let qos_enabled = std::env::var("QOS_ENABLED")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    if !qos_enabled {
+        return None;
+    }
+
+    let metrics_interval = std::env::var("QOS_METRICS_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(60);
+
+    let dry_run = std::env::var("QOS_DRY_RUN")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(true);
+
+    let mut builder = QoSServiceBuilder::<LoggingHeartbeatConsumer>::new()
+        .with_metrics_config(MetricsConfig::default())
+        .with_dry_run(dry_run);
+
+    // Wire heartbeat if config is available (service_id and blueprint_id set)
+    if let Some(hb_config) = build_heartbeat_config() {
+        let rpc_endpoint = std::env::var("HTTP_RPC_ENDPOINT")
+            .or_else(|_| std::env::var("RPC_URL"))
+            .unwrap_or_else(|_| "http://localhost:9944".to_string());
+
+        let keystore_uri =
+            std::env::var("KEYSTORE_URI").unwrap_or_else(|_| "file:///tmp/keystore".to_string());
+
+        let registry_address = hb_config.status_registry_address;
+
+        info!(
+            "Configuring heartbeat: service_id={}, blueprint_id={}, interval={}s, registry={}",
+            hb_config.service_id, hb_config.blueprint_id, hb_config.interval_secs, registry_address,
+        );
+
+        builder = builder
+            .with_heartbeat_config(hb_config)
+            .with_heartbeat_consumer(Arc::new(LoggingHeartbeatConsumer))
+            .with_http_rpc_endpoint(rpc_endpoint)
+            .with_keystore_uri(keystore_uri)
+            .with_status_registry_address(registry_address);
+    } else {
+        warn!("QoS enabled without heartbeat config; liveness heartbeats are not sent");
+    }
+
+    match builder.build().await {
+        Ok(qos_service) => {
+            info!(
+                "QoS service initialized (metrics_interval={metrics_interval}s, dry_run={dry_run})"
+            );
+
+            if let Some(hb) = qos_service.heartbeat_service() {
+                match hb.start_heartbeat().await {
+                    Ok(()) => info!("Heartbeat service started"),
+                    Err(e) => error!("Failed to start heartbeat: {e}"),
+                }
+            }
+
+            qos_service.provider().map(|provider| QosMetrics {
+                provider,
+                interval_secs: metrics_interval,
+            })
+        }
+        Err(e) => {
+            error!("Failed to initialize QoS service: {e} — continuing without QoS");
+            None
+        }
+    }
+}
+
+/// Push [`crate::metrics`] snapshots as on-chain metrics until `shutdown`
+/// changes (or its sender is dropped).
+pub fn spawn_qos_metrics_loop(qos: QosMetrics, mut shutdown: tokio::sync::watch::Receiver<()>) {
+    tokio::spawn(async move {
+        use blueprint_qos::metrics::types::MetricsProvider;
+
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(qos.interval_secs));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    for (key, value) in crate::metrics::metrics().snapshot() {
+                        qos.provider.add_on_chain_metric(key, value).await;
+                    }
+                }
+                _ = shutdown.changed() => {
+                    info!("QoS metrics loop shutting down");
+                    break;
+                }
+            }
+        }
+    });
+}
@@ -7,6 +7,8 @@
 //! - `read_limiter()`: 120 req/min — for GET endpoints
 //! - `write_limiter()`: 30 req/min — for POST/DELETE endpoints
 //! - `terminal_interactive_limiter()`: 2400 req/min — for PTY input/resize
+//! - `check_read_job()`: 30 calls/min per caller — for the sandbox blueprint's
+//!   read-only jobs
 //!
 //! Usage in operator_api router:
 //! ```ignore
@@ -215,6 +217,20 @@ static SESSION_FANOUT_LIMITER: once_cell::sync::Lazy<SessionRateLimiter> =
         SessionRateLimiter::new(RateLimitConfig::new(per_minute, 60))
     });
 
+/// Per-caller limiter for the sandbox blueprint's read-only job family
+/// (`JOB_READ_*`), kept apart from the HTTP tiers so on-chain reads and API
+/// traffic can't exhaust each other. Default 30 calls/min, env-tunable via
+/// `READ_JOB_LIMIT_PER_MINUTE` (read at first use).
+static READ_JOB_LIMITER: once_cell::sync::Lazy<SessionRateLimiter> =
+    once_cell::sync::Lazy::new(|| {
+        let per_minute = std::env::var("READ_JOB_LIMIT_PER_MINUTE")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(30);
+        SessionRateLimiter::new(RateLimitConfig::new(per_minute, 60))
+    });
+
 /// Access the read-tier (120 req/min) limiter.
 pub fn read_limiter() -> &'static RateLimiter {
     &READ_LIMITER
@@ -280,6 +296,12 @@ fn extract_client_ip(req: &Request) -> Option<IpAddr> {
     }
 }
 
+/// Client IP of `req` as the rate limiters see it, for handlers that key
+/// their own state on it (e.g. sign-in lockouts).
+pub(crate) fn client_ip(req: &Request) -> IpAddr {
+    extract_client_ip(req).unwrap_or(UNKNOWN_IP)
+}
+
 /// Returns true if the IP is a loopback or private address (trusted proxy).
 fn is_trusted_proxy(ip: IpAddr) -> bool {
     match ip {
@@ -373,6 +395,17 @@ pub fn check_session_fanout(session_id: &str) -> std::result::Result<(), u64> {
     }
 }
 
+/// Check the read-job limiter for an on-chain caller. Returns
+/// `Err(retry_after_secs)` when the caller's bucket is exhausted.
+pub fn check_read_job(caller: &str) -> std::result::Result<(), u64> {
+    if READ_JOB_LIMITER.check(&caller.to_ascii_lowercase()) {
+        Ok(())
+    } else {
+        metrics::rate_limit_rejections().fetch_add(1, Ordering::Relaxed);
+        Err(60)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,81 @@
+//! Sidecar-reported activity for idle detection.
+//!
+//! `last_activity_at` only moves on operator-mediated calls, so a sandbox
+//! used over SSH, with an open terminal or with an agent working in the
+//! background looks idle. Before the reaper warns about or enforces an idle
+//! stop it asks the sidecar's `GET /activity` for open terminals, running
+//! processes, established SSH sessions and the newest workspace write, and
+//! moves `last_activity_at` forward accordingly. Disabled with
+//! `SANDBOX_ACTIVITY_PROBE=false`.
+
+use serde::Deserialize;
+
+use crate::runtime::{SandboxRecord, sandboxes};
+
+/// The sidecar's `GET /activity` summary.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SidecarActivity {
+    pub open_terminals: u32,
+    pub running_processes: u32,
+    pub ssh_sessions: u32,
+    /// Newest mtime in the workspace (epoch seconds, 0 = unknown).
+    pub last_fs_write_at: u64,
+}
+
+impl SidecarActivity {
+    /// Whether anything is using the sandbox right now.
+    pub fn busy(&self) -> bool {
+        self.open_terminals > 0 || self.running_processes > 0 || self.ssh_sessions > 0
+    }
+
+    /// Most recent activity this summary shows, as of `now`. A workspace
+    /// write reported in the future (clock skew) counts as `now`.
+    pub fn last_active_at(&self, now: u64) -> u64 {
+        if self.busy() {
+            now
+        } else {
+            self.last_fs_write_at.min(now)
+        }
+    }
+}
+
+pub fn activity_probe_enabled() -> bool {
+    std::env::var("SANDBOX_ACTIVITY_PROBE")
+        .map(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+        .unwrap_or(true)
+}
+
+async fn probe_sidecar_activity(record: &SandboxRecord) -> crate::error::Result<SidecarActivity> {
+    let parsed =
+        crate::http::sidecar_get_json(&record.sidecar_url, "/activity", &record.token).await?;
+    serde_json::from_value(parsed).map_err(|e| {
+        crate::error::SandboxError::Http(format!("Invalid sidecar activity response: {e}"))
+    })
+}
+
+/// Last activity of `record` after consulting the sidecar: `activity` moved
+/// forward to what the sidecar reports (and persisted), or unchanged when
+/// the probe is disabled or fails. A sidecar that cannot answer must not
+/// keep its sandbox alive.
+pub(super) async fn refresh_activity(record: &SandboxRecord, activity: u64, now: u64) -> u64 {
+    if !activity_probe_enabled() {
+        return activity;
+    }
+    let reported = match probe_sidecar_activity(record).await {
+        Ok(summary) => summary.last_active_at(now),
+        Err(e) => {
+            tracing::debug!(sandbox_id = %record.id, error = %e, "reaper: activity probe failed");
+            return activity;
+        }
+    };
+    if reported <= activity {
+        return activity;
+    }
+    if let Ok(store) = sandboxes() {
+        let _ = store.update(&record.id, |r| {
+            r.last_activity_at = r.last_activity_at.max(reported);
+        });
+    }
+    reported
+}
@@ -0,0 +1,170 @@
+//! Pre-GC workspace archives.
+//!
+//! GC's first tier removes a stopped sandbox's container; without a
+//! committed image or S3 snapshot that loses the workspace for good, and
+//! later tiers delete whatever snapshots there are. With
+//! `SANDBOX_GC_ARCHIVE_PREFIX` set, GC first tars `/home/agent` out of the
+//! stopped container and PUTs it to
+//! `{prefix}{sandbox_id}/workspace-{timestamp}.tar`, recording the URL in the
+//! sandbox's tombstone ([`crate::runtime::SandboxTombstone`]). The tar is
+//! streamed from Docker into the upload, so the destination must accept
+//! chunked PUTs. GC never deletes archives.
+//!
+//! Failures are recorded in the tombstone. A workspace larger than
+//! `SANDBOX_GC_ARCHIVE_MAX_MB` is never archived and GC proceeds without
+//! one; other failures keep the container for up to
+//! [`GC_ARCHIVE_MAX_ATTEMPTS`] GC ticks before GC gives up the same way.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use docktopus::bollard::container::DownloadFromContainerOptions;
+use tokio_stream::StreamExt;
+
+use super::*;
+
+/// Largest workspace archived before GC (`SANDBOX_GC_ARCHIVE_MAX_MB`).
+pub const DEFAULT_GC_ARCHIVE_MAX_MB: u64 = 2048;
+
+/// Failed archive attempts after which GC removes the container anyway.
+pub const GC_ARCHIVE_MAX_ATTEMPTS: u32 = 5;
+
+/// Why a workspace could not be archived.
+enum ArchiveFailure {
+    /// Over `SANDBOX_GC_ARCHIVE_MAX_MB`; retrying cannot help.
+    TooLarge,
+    /// Docker, network or destination error; worth retrying.
+    Failed(String),
+}
+
+impl std::fmt::Display for ArchiveFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooLarge => write!(
+                f,
+                "workspace exceeds SANDBOX_GC_ARCHIVE_MAX_MB ({} MiB)",
+                gc_archive_max_bytes() / (1024 * 1024)
+            ),
+            Self::Failed(err) => f.write_str(err),
+        }
+    }
+}
+
+/// Destination prefix for pre-GC archives, from `SANDBOX_GC_ARCHIVE_PREFIX`
+/// (unset = no archival).
+pub fn gc_archive_prefix() -> Option<String> {
+    std::env::var("SANDBOX_GC_ARCHIVE_PREFIX")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn gc_archive_max_bytes() -> usize {
+    let mb = std::env::var("SANDBOX_GC_ARCHIVE_MAX_MB")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_GC_ARCHIVE_MAX_MB);
+    usize::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX)
+}
+
+/// Archive URL for `sandbox_id` under `prefix`.
+pub fn gc_archive_url(prefix: &str, sandbox_id: &str, now: u64) -> String {
+    format!("{prefix}{sandbox_id}/workspace-{now}.tar")
+}
+
+/// Archive the workspace of a stopped sandbox before GC removes its
+/// container. A no-op when archival is off or the sandbox has no Docker
+/// container (TEE, Firecracker). `Err` means GC should keep the container
+/// and retry on a later tick; once retrying is pointless the failure is left
+/// in the tombstone and `Ok` lets GC proceed without an archive.
+pub(super) async fn archive_before_gc(
+    record: &crate::runtime::SandboxRecord,
+    now: u64,
+) -> std::result::Result<(), String> {
+    let Some(prefix) = gc_archive_prefix() else {
+        return Ok(());
+    };
+    if record.tee_deployment_id.is_some() || record_uses_firecracker(record) {
+        return Ok(());
+    }
+    if !prefix.starts_with("https://") {
+        return Err("SANDBOX_GC_ARCHIVE_PREFIX must use https://".into());
+    }
+
+    let failure = match upload_archive(record, &prefix, now).await {
+        Ok(url) => {
+            crate::runtime::record_archive(record, &url, now).map_err(|e| e.to_string())?;
+            info!("gc: archived workspace of sandbox {} to {url}", record.id);
+            return Ok(());
+        }
+        Err(failure) => failure,
+    };
+    let error = failure.to_string();
+    let attempts = crate::runtime::record_archive_failure(record, &error, now)
+        .map_err(|e| format!("{error} (and failed to record it: {e})"))?;
+    if matches!(failure, ArchiveFailure::Failed(_)) && attempts < GC_ARCHIVE_MAX_ATTEMPTS {
+        return Err(format!(
+            "{error} (attempt {attempts}/{GC_ARCHIVE_MAX_ATTEMPTS})"
+        ));
+    }
+    error!(
+        "gc: removing sandbox {} without an archive after {attempts} attempt(s): {error}",
+        record.id
+    );
+    metrics().record_gc_failure();
+    Ok(())
+}
+
+/// Stream the workspace tar of `record` to a new archive URL under `prefix`.
+async fn upload_archive(
+    record: &crate::runtime::SandboxRecord,
+    prefix: &str,
+    now: u64,
+) -> std::result::Result<String, ArchiveFailure> {
+    let builder = docker_builder()
+        .await
+        .map_err(|e| ArchiveFailure::Failed(e.to_string()))?;
+    let limit = gc_archive_max_bytes();
+    let too_large = Arc::new(AtomicBool::new(false));
+    let over_limit = too_large.clone();
+    let mut sent = 0usize;
+    let tar = builder
+        .client()
+        .download_from_container(
+            &record.container_id,
+            Some(DownloadFromContainerOptions {
+                path: crate::runtime::WORKSPACE_DIR,
+            }),
+        )
+        .map(move |chunk| {
+            let chunk = chunk.map_err(std::io::Error::other)?;
+            sent = sent.saturating_add(chunk.len());
+            if sent > limit {
+                // Aborts the upload; the destination never sees a complete tar.
+                over_limit.store(true, Ordering::Relaxed);
+                return Err(std::io::Error::other("workspace archive too large"));
+            }
+            Ok(chunk)
+        });
+
+    let url = gc_archive_url(prefix, &record.id, now);
+    let client =
+        crate::util::external_http_client().map_err(|e| ArchiveFailure::Failed(e.to_string()))?;
+    let result = client
+        .put(&url)
+        .header("content-type", "application/x-tar")
+        .body(reqwest::Body::wrap_stream(tar))
+        .send()
+        .await;
+    if too_large.load(Ordering::Relaxed) {
+        return Err(ArchiveFailure::TooLarge);
+    }
+    let resp = result.map_err(|e| ArchiveFailure::Failed(format!("archive upload failed: {e}")))?;
+    if !resp.status().is_success() {
+        return Err(ArchiveFailure::Failed(format!(
+            "archive upload returned status {}",
+            resp.status()
+        )));
+    }
+    Ok(url)
+}
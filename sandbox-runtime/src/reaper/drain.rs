@@ -0,0 +1,71 @@
+use super::*;
+
+/// Run the end-of-window actions of an API drain once its deadline has
+/// passed: snapshot and/or stop every running sandbox, then record the
+/// outcome so later ticks leave the node alone. See [`crate::drain`].
+pub(super) async fn enforce_drain_window(now: u64) {
+    let drain = match crate::drain::current_drain() {
+        Ok(Some(drain)) if drain.completed_at.is_none() && drain.deadline <= now => drain,
+        Ok(_) => return,
+        Err(err) => {
+            error!("reaper: failed to read drain state: {err}");
+            metrics().record_reaper_failure();
+            return;
+        }
+    };
+    let records = match sandboxes().and_then(|s| s.values()) {
+        Ok(v) => v,
+        Err(err) => {
+            error!("reaper: failed to read sandboxes: {err}");
+            metrics().record_reaper_failure();
+            return;
+        }
+    };
+    info!(
+        "reaper: drain window ended, {} running sandboxes (snapshot: {}, stop: {})",
+        records
+            .iter()
+            .filter(|r| r.state == SandboxState::Running)
+            .count(),
+        drain.snapshot,
+        drain.stop
+    );
+
+    let config = SidecarRuntimeConfig::load();
+    let mut stopped = Vec::new();
+    let mut failed = Vec::new();
+    for mut record in records {
+        if record.state != SandboxState::Running {
+            continue;
+        }
+        if let Err(err) = crate::runtime::unseal_record(&mut record) {
+            error!("reaper: failed to unseal sandbox {}: {err}", record.id);
+            metrics().record_reaper_failure();
+            failed.push(record.id);
+            continue;
+        }
+        let _lock = crate::runtime::acquire_lifecycle_lock(&record.id).await;
+        if drain.snapshot {
+            upload_pre_stop_snapshot(&record, config).await;
+        }
+        if !drain.stop {
+            continue;
+        }
+        if let Err(err) = reap_sidecar(&record, "drain").await {
+            error!("reaper: drain failed to stop sandbox {}: {err}", record.id);
+            metrics().record_reaper_failure();
+            failed.push(record.id);
+            continue;
+        }
+        if drain.snapshot {
+            commit_stopped_snapshot(&record).await;
+        }
+        metrics().record_reaped_drain();
+        stopped.push(record.id);
+    }
+
+    if let Err(err) = crate::drain::mark_drain_completed(stopped, failed) {
+        error!("reaper: failed to record drain completion: {err}");
+        metrics().record_reaper_failure();
+    }
+}
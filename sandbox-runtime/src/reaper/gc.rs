@@ -6,16 +6,88 @@ use super::*;
 ///   Hot (stopped container) -> Warm (committed image) -> Cold (S3 snapshot) -> Gone
 ///
 /// Each tier has a configurable retention period. User BYOS3 copies are never deleted.
+/// With `SANDBOX_GC_ARCHIVE_PREFIX` set, the workspace is archived before the
+/// container is removed (see [`super::archive`]). Every removed record leaves
+/// a [`crate::runtime::SandboxTombstone`] pointing at the data that survives;
+/// tombstones are pruned after [`crate::runtime::TOMBSTONE_RETENTION_SECS`].
+/// Audit events older than [`crate::audit_log::AUDIT_RETENTION_SECS`] and
+/// background executions finished more than
+/// [`crate::executions::EXECUTION_RETENTION_SECS`] ago are pruned, as are
+/// webhook delivery logs past [`crate::webhooks::DELIVERY_RETENTION_SECS`]
+/// and [`crate::job_dedup`] records past their retention.
+/// Clone images no record refers to any more are removed.
+/// Closed hours of owner usage are rolled up into monthly totals (see
+/// [`crate::usage_ledger`]).
 ///
 /// Called every `SANDBOX_GC_INTERVAL` seconds.
 pub async fn gc_tick() {
     let config = SidecarRuntimeConfig::load();
     let now = crate::util::now_ts();
 
+    if let Err(err) = crate::audit_log::gc_audit_events(crate::audit_log::AUDIT_RETENTION_SECS) {
+        error!("gc: failed to prune audit events: {err}");
+        metrics().record_gc_failure();
+    }
+    if let Err(err) = crate::executions::gc_executions(crate::executions::EXECUTION_RETENTION_SECS)
+    {
+        error!("gc: failed to prune executions: {err}");
+        metrics().record_gc_failure();
+    }
+
+    if let Err(err) = crate::task_queue::gc_tasks(crate::task_queue::TASK_RETENTION_SECS) {
+        error!("gc: failed to prune tasks: {err}");
+        metrics().record_gc_failure();
+    }
+
+    if let Err(err) = crate::prompt_templates::gc_prompt_templates() {
+        error!("gc: failed to prune prompt templates: {err}");
+        metrics().record_gc_failure();
+    }
+
+    if let Err(err) = crate::exec_presets::gc_exec_presets() {
+        error!("gc: failed to prune exec presets: {err}");
+        metrics().record_gc_failure();
+    }
+
+    if let Err(err) = crate::named_sessions::gc_named_sessions() {
+        error!("gc: failed to prune named sessions: {err}");
+        metrics().record_gc_failure();
+    }
+
+    if let Err(err) = crate::webhooks::gc_deliveries(crate::webhooks::DELIVERY_RETENTION_SECS) {
+        error!("gc: failed to prune webhook deliveries: {err}");
+        metrics().record_gc_failure();
+    }
+
+    if let Err(err) = crate::job_dedup::gc_processed_calls() {
+        error!("gc: failed to prune processed job calls: {err}");
+        metrics().record_gc_failure();
+    }
+
+    if let Err(err) = crate::usage_ledger::roll_up_usage(now) {
+        error!("gc: failed to roll up usage: {err}");
+        metrics().record_gc_failure();
+    }
+
+    if let Err(err) = crate::runtime::gc_tombstones(crate::runtime::TOMBSTONE_RETENTION_SECS) {
+        error!("gc: failed to prune sandbox tombstones: {err}");
+        metrics().record_gc_failure();
+    }
+
+    if matches!(
+        crate::runtime::parse_runtime_backend_from_env(),
+        Ok(crate::runtime::RuntimeBackend::Docker)
+    ) && let Err(err) = crate::runtime::gc_clone_images().await
+    {
+        error!("gc: failed to prune orphaned clone images: {err}");
+        metrics().record_gc_failure();
+    }
+
     let records = match sandboxes().and_then(|s| s.values()) {
         Ok(v) => v,
         Err(err) => {
             error!("gc: failed to read sandboxes: {err}");
+            metrics().record_gc_failure();
             return;
         }
     };
@@ -47,6 +119,7 @@ pub async fn gc_tick() {
                             "gc: failed to delete firecracker S3 snapshot for sandbox {}: {err}",
                             record.id
                         );
+                        metrics().record_gc_failure();
                     }
                     metrics().record_gc_s3_cleaned();
                 } else {
@@ -55,9 +128,8 @@ pub async fn gc_tick() {
                         record.id
                     );
                 }
-                if let Ok(store) = sandboxes() {
-                    let _ = store.remove(&record.id);
-                }
+                let kept = (!is_operator_managed).then_some(s3_url.as_str());
+                remove_record(&record, kept, now);
                 metrics().record_garbage_collected();
                 continue;
             }
@@ -70,9 +142,7 @@ pub async fn gc_tick() {
                     "gc: cleaning up empty firecracker record for sandbox {}",
                     record.id
                 );
-                if let Ok(store) = sandboxes() {
-                    let _ = store.remove(&record.id);
-                }
+                remove_record(&record, None, now);
                 metrics().record_garbage_collected();
                 continue;
             }
@@ -87,6 +157,14 @@ pub async fn gc_tick() {
         if record.container_removed_at.is_none()
             && stopped_at + config.sandbox_gc_hot_retention <= now
         {
+            if let Err(err) = archive_before_gc(&record, now).await {
+                error!(
+                    "gc: failed to archive sandbox {}, keeping its container: {err}",
+                    record.id
+                );
+                metrics().record_gc_failure();
+                continue;
+            }
             let has_snapshot =
                 record.snapshot_image_id.is_some() || record.snapshot_s3_url.is_some();
 
@@ -100,6 +178,7 @@ pub async fn gc_tick() {
                         "gc: failed to remove container for sandbox {}: {err}",
                         record.id
                     );
+                    metrics().record_gc_failure();
                     continue;
                 }
                 if let Ok(store) = sandboxes() {
@@ -117,11 +196,10 @@ pub async fn gc_tick() {
                 );
                 if let Err(err) = delete_sidecar(&record, None).await {
                     error!("gc: failed to delete sandbox {}: {err}", record.id);
+                    metrics().record_gc_failure();
                     continue;
                 }
-                if let Ok(store) = sandboxes() {
-                    let _ = store.remove(&record.id);
-                }
+                remove_record(&record, None, now);
                 metrics().record_garbage_collected();
             }
             continue;
@@ -141,6 +219,7 @@ pub async fn gc_tick() {
                     "gc: failed to remove snapshot image for sandbox {}: {err}",
                     record.id
                 );
+                metrics().record_gc_failure();
             }
             if let Ok(store) = sandboxes() {
                 let _ = store.update(&record.id, |r| {
@@ -152,9 +231,7 @@ pub async fn gc_tick() {
 
             // If no S3 snapshot exists, remove record entirely
             if record.snapshot_s3_url.is_none() {
-                if let Ok(store) = sandboxes() {
-                    let _ = store.remove(&record.id);
-                }
+                remove_record(&record, None, now);
                 metrics().record_garbage_collected();
             }
             continue;
@@ -178,6 +255,7 @@ pub async fn gc_tick() {
                         "gc: failed to delete S3 snapshot for sandbox {}: {err}",
                         record.id
                     );
+                    metrics().record_gc_failure();
                 }
                 metrics().record_gc_s3_cleaned();
             } else {
@@ -186,9 +264,8 @@ pub async fn gc_tick() {
                     record.id
                 );
             }
-            if let Ok(store) = sandboxes() {
-                let _ = store.remove(&record.id);
-            }
+            let kept = (!is_operator_managed).then_some(s3_url.as_str());
+            remove_record(&record, kept, now);
             metrics().record_garbage_collected();
             continue;
         }
@@ -199,10 +276,25 @@ pub async fn gc_tick() {
             && record.snapshot_s3_url.is_none()
         {
             info!("gc: cleaning up empty record for sandbox {}", record.id);
-            if let Ok(store) = sandboxes() {
-                let _ = store.remove(&record.id);
-            }
+            remove_record(&record, None, now);
             metrics().record_garbage_collected();
         }
     }
+
+    metrics().record_gc_tick(now);
+}
+
+/// Drop `record` from the store, leaving a tombstone. `kept_snapshot` is a
+/// user-owned snapshot GC did not delete.
+fn remove_record(record: &crate::runtime::SandboxRecord, kept_snapshot: Option<&str>, now: u64) {
+    if let Err(err) = crate::runtime::record_removal(record, kept_snapshot, now) {
+        error!(
+            "gc: failed to write tombstone for sandbox {}: {err}",
+            record.id
+        );
+        metrics().record_gc_failure();
+    }
+    if let Ok(store) = sandboxes() {
+        let _ = store.remove(&record.id);
+    }
 }
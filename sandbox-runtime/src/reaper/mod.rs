@@ -1,23 +1,36 @@
 //! Reaper and garbage collection for sandbox lifecycle enforcement.
 //!
-//! - `reaper_tick()`: stops idle sandboxes, deletes expired ones
-//! - `gc_tick()`: removes stopped sandboxes past retention period
-//! - `reconcile_on_startup()`: syncs store state with Docker reality
+//! - `reaper_tick()`: stops idle sandboxes (asking the sidecar for activity
+//!   the operator did not see), deletes expired ones, snapshots and/or stops
+//!   running ones when a drain window ends, revokes
+//!   expired SSH keys, polls SSH logins into the audit log, rotates secrets
+//! - `gc_tick()`: removes stopped sandboxes past retention period (archiving
+//!   their workspace first when configured, leaving tombstones), prunes old
+//!   audit events
+//! - `reconcile_on_startup()`: syncs store state with Docker reality and
+//!   settles provisions interrupted by the restart
 
 use crate::metrics::metrics;
 use crate::runtime::{
     SandboxState, SidecarRuntimeConfig, commit_container, delete_sidecar, docker_builder,
-    record_uses_firecracker, refresh_docker_sandbox_endpoint, remove_snapshot_image, sandboxes,
-    stop_sidecar, supports_docker_endpoint_refresh,
+    poll_ssh_logins, reap_sidecar, record_uses_firecracker, refresh_docker_sandbox_endpoint,
+    remove_snapshot_image, revoke_expired_ssh_keys, sandboxes, supports_docker_endpoint_refresh,
 };
 use blueprint_sdk::{error, info};
 use docktopus::bollard::container::InspectContainerOptions;
 
+mod activity;
+mod archive;
+mod drain;
 mod gc;
 mod reconcile;
 mod snapshot;
 mod tick;
 
+pub use activity::SidecarActivity;
+use activity::refresh_activity;
+use archive::archive_before_gc;
+use drain::enforce_drain_window;
 pub use gc::gc_tick;
 pub use reconcile::reconcile_on_startup;
 pub(crate) use snapshot::*;
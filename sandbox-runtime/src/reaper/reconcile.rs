@@ -1,6 +1,7 @@
 use super::*;
 
-/// Reconcile stored sandbox state with Docker reality on startup.
+/// Reconcile stored sandbox state with Docker reality on startup, then settle
+/// provisions the previous process left in flight.
 pub async fn reconcile_on_startup() {
     // Reap warm-pool VMs orphaned by a previous process first — before the
     // Docker connect below (which may legitimately fail on a Firecracker-only
@@ -9,11 +10,35 @@ pub async fn reconcile_on_startup() {
     // engine init never reaches.
     crate::firecracker::reconcile_warm_orphans();
 
+    let records_reconciled = reconcile_records().await;
+
+    // Provisions cut off by the restart: settle them against the records
+    // reconciled above so frontends stop polling a phase that will never move.
+    match crate::provision_progress::reconcile_interrupted_provisions() {
+        Ok(settled) => {
+            for status in settled {
+                info!(
+                    "reconcile: provision {} settled as {:?} after restart",
+                    status.call_id, status.phase
+                );
+            }
+        }
+        Err(err) => error!("reconcile: failed to settle interrupted provisions: {err}"),
+    }
+
+    if records_reconciled {
+        reconcile_images().await;
+    }
+}
+
+/// Sync sandbox records with Docker/Firecracker. Returns `false` when the
+/// store or Docker could not be read.
+async fn reconcile_records() -> bool {
     let builder = match docker_builder().await {
         Ok(b) => b,
         Err(err) => {
             error!("reconcile: failed to connect to Docker: {err}");
-            return;
+            return false;
         }
     };
 
@@ -30,7 +55,7 @@ pub async fn reconcile_on_startup() {
         Ok(v) => v,
         Err(err) => {
             error!("reconcile: failed to read sandboxes: {err}");
-            return;
+            return false;
         }
     };
 
@@ -191,7 +216,10 @@ pub async fn reconcile_on_startup() {
             }
         }
     }
+    true
+}
 
+async fn reconcile_images() {
     // Sidecar image drift remediation. Every blueprint calls
     // reconcile_on_startup() at boot, so folding this here means the cascade off
     // the manager's binary upgrade is universal: when the manager swaps the
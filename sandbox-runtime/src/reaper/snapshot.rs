@@ -24,7 +24,7 @@ pub(crate) async fn upload_s3_snapshot(
     let payload = serde_json::json!({
         "command": format!("sh -c {}", crate::util::shell_escape(&command)),
     });
-    crate::http::sidecar_post_json(
+    let parsed = crate::http::sidecar_post_json(
         &record.sidecar_url,
         "/terminals/commands",
         &record.token,
@@ -32,9 +32,91 @@ pub(crate) async fn upload_s3_snapshot(
     )
     .await
     .map_err(|e| e.to_string())?;
+    let stdout = crate::operator_api::parse_exec_response(
+        crate::sidecar_compat::SidecarApi::for_record(record),
+        &parsed,
+    )
+    .stdout;
+    crate::usage_ledger::record_usage(
+        &record.owner,
+        crate::usage_ledger::UsageTotals::snapshot(crate::util::snapshot_bytes(&stdout)),
+    );
     Ok(())
 }
 
+/// Pre-stop half of a reaper stop: upload the workspace to the sandbox's
+/// snapshot destination, if it has one, and record the URL. Failures are
+/// logged; the stop goes ahead.
+pub(crate) async fn upload_pre_stop_snapshot(
+    record: &crate::runtime::SandboxRecord,
+    config: &SidecarRuntimeConfig,
+) {
+    let Some(dest) = resolve_snapshot_destination(record, config) else {
+        return;
+    };
+    match upload_s3_snapshot(record, &dest).await {
+        Ok(()) => {
+            if let Ok(store) = sandboxes() {
+                let _ = store.update(&record.id, |r| {
+                    r.snapshot_s3_url = Some(dest.clone());
+                    push_reaper_snapshot_event(r, &dest);
+                });
+            }
+            metrics().record_snapshot_uploaded();
+            info!("reaper: uploaded S3 snapshot for sandbox {}", record.id);
+        }
+        Err(err) => {
+            error!(
+                "reaper: S3 snapshot upload failed for sandbox {}: {err}",
+                record.id
+            );
+            metrics().record_reaper_failure();
+        }
+    }
+}
+
+/// Post-stop half of a reaper stop: `docker commit` the stopped container to
+/// preserve its filesystem. TEE and Firecracker sandboxes have no Docker
+/// container to commit.
+pub(crate) async fn commit_stopped_snapshot(record: &crate::runtime::SandboxRecord) {
+    if record.tee_deployment_id.is_some() || record_uses_firecracker(record) {
+        return;
+    }
+    match commit_container(record).await {
+        Ok(image_id) => {
+            if let Ok(store) = sandboxes() {
+                let _ = store.update(&record.id, |r| {
+                    push_reaper_snapshot_event(r, &image_id);
+                    r.snapshot_image_id = Some(image_id);
+                });
+            }
+            metrics().record_snapshot_committed();
+            info!("reaper: committed snapshot for sandbox {}", record.id);
+        }
+        Err(err) => {
+            error!(
+                "reaper: docker commit failed for sandbox {}: {err}",
+                record.id
+            );
+            metrics().record_reaper_failure();
+        }
+    }
+}
+
+/// Note a reaper snapshot (S3 URL or committed image ID) in the sandbox's
+/// lifecycle history.
+fn push_reaper_snapshot_event(record: &mut crate::runtime::SandboxRecord, detail: &str) {
+    crate::runtime::push_lifecycle_event(
+        &mut record.lifecycle_events,
+        crate::runtime::LifecycleEvent {
+            at: crate::util::now_ts(),
+            kind: crate::runtime::LifecycleEventKind::Snapshotted,
+            actor: crate::audit_log::OPERATOR_ACTOR.to_string(),
+            detail: Some(detail.to_string()),
+        },
+    );
+}
+
 /// Check if an S3 URL is operator-managed (not user BYOS3).
 pub(crate) fn is_operator_s3(
     s3_url: &str,
@@ -52,7 +134,7 @@ pub(crate) fn is_operator_s3(
 
 /// Best-effort DELETE of an S3/HTTP snapshot URL via reqwest.
 pub(crate) async fn delete_s3_snapshot(url: &str) -> std::result::Result<(), String> {
-    let client = crate::util::http_client().map_err(|e| e.to_string())?;
+    let client = crate::util::external_http_client().map_err(|e| e.to_string())?;
     let resp = client
         .delete(url)
         .send()
@@ -42,6 +42,11 @@ fn test_record() -> SandboxRecord {
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     }
 }
 
@@ -49,10 +54,12 @@ fn test_record() -> SandboxRecord {
 fn test_config() -> SidecarRuntimeConfig {
     SidecarRuntimeConfig {
         image: "test:latest".to_string(),
+        platform_arch: "amd64".to_string(),
         public_host: "127.0.0.1".to_string(),
         container_port: 8080,
         ssh_port: 22,
         timeout: Duration::from_secs(30),
+        connect_timeout: Duration::from_secs(5),
         docker_host: None,
         pull_image: false,
         sandbox_default_idle_timeout: 300,
@@ -67,6 +74,7 @@ fn test_config() -> SidecarRuntimeConfig {
         snapshot_auto_commit: true,
         snapshot_destination_prefix: Some("s3://my-bucket/snapshots/".to_string()),
         sandbox_max_count: 100,
+        operator_max_capacity: 0,
         sandbox_max_cpu_cores: 0,
         sandbox_max_memory_mb: 0,
         sandbox_max_disk_gb: 0,
@@ -215,3 +223,170 @@ fn gc_skips_firecracker_sandboxes_without_container_removed() {
     // The Docker GC path (hot->warm->cold) is skipped for firecracker;
     // instead, firecracker has its own cold->gone path.
 }
+
+#[test]
+fn sidecar_activity_counts_open_sessions_as_active_now() {
+    let idle: SidecarActivity = serde_json::from_value(serde_json::json!({
+        "openTerminals": 0,
+        "runningProcesses": 0,
+        "sshSessions": 0,
+        "lastFsWriteAt": 1500,
+    }))
+    .unwrap();
+    assert!(!idle.busy());
+    assert_eq!(idle.last_active_at(2000), 1500);
+    assert_eq!(
+        SidecarActivity {
+            last_fs_write_at: 9999,
+            ..idle.clone()
+        }
+        .last_active_at(2000),
+        2000,
+        "future mtimes are clamped"
+    );
+
+    // Older sidecars may omit fields.
+    let ssh: SidecarActivity =
+        serde_json::from_value(serde_json::json!({ "sshSessions": 1 })).unwrap();
+    assert!(ssh.busy());
+    assert_eq!(ssh.last_active_at(2000), 2000);
+}
+
+#[test]
+fn gc_tombstone_keeps_archive_and_kept_snapshot_for_owner() {
+    let _guard = crate::TEST_ENV_GUARD
+        .lock()
+        .unwrap_or_else(|p| p.into_inner());
+    let dir = std::env::temp_dir().join(format!("reaper-tombstone-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).ok();
+    unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", &dir) };
+
+    let mut record = test_record();
+    record.id = format!("tombstone-{}", uuid::Uuid::new_v4().simple());
+    record.state = SandboxState::Stopped;
+    record.stopped_at = Some(1500);
+
+    let url = archive::gc_archive_url("https://archive.example.com/sb/", &record.id, 2000);
+    assert_eq!(
+        url,
+        format!(
+            "https://archive.example.com/sb/{}/workspace-2000.tar",
+            record.id
+        )
+    );
+    crate::runtime::record_archive(&record, &url, 2000).unwrap();
+    crate::runtime::record_removal(&record, Some("s3://user-bucket/snap.tar.gz"), 3000).unwrap();
+
+    let tombstone = crate::runtime::get_tombstone(&record.id, "0xDEADBEEF").unwrap();
+    assert_eq!(tombstone.archive_url.as_deref(), Some(url.as_str()));
+    assert_eq!(tombstone.archived_at, Some(2000));
+    assert_eq!(
+        tombstone.snapshot_url.as_deref(),
+        Some("s3://user-bucket/snap.tar.gz")
+    );
+    assert_eq!(tombstone.removed_at, Some(3000));
+    assert_eq!(tombstone.stopped_at, Some(1500));
+    assert!(crate::runtime::get_tombstone(&record.id, "0xother").is_err());
+}
+
+#[test]
+fn gc_tombstone_counts_archive_failures_until_one_succeeds() {
+    let _guard = crate::TEST_ENV_GUARD
+        .lock()
+        .unwrap_or_else(|p| p.into_inner());
+    let dir = std::env::temp_dir().join(format!("reaper-tombstone-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).ok();
+    unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", &dir) };
+
+    let mut record = test_record();
+    record.id = format!("tombstone-{}", uuid::Uuid::new_v4().simple());
+    record.stopped_at = Some(1500);
+
+    assert_eq!(
+        crate::runtime::record_archive_failure(&record, "upload returned 503", 2000).unwrap(),
+        1
+    );
+    assert_eq!(
+        crate::runtime::record_archive_failure(&record, "upload returned 500", 2100).unwrap(),
+        2
+    );
+    let tombstone = crate::runtime::get_tombstone(&record.id, "0xDEADBEEF").unwrap();
+    assert_eq!(
+        tombstone.archive_error.as_deref(),
+        Some("upload returned 500")
+    );
+    assert_eq!(tombstone.archive_attempts, 2);
+    assert_eq!(tombstone.archive_failed_at, Some(2100));
+    assert_eq!(tombstone.removed_at, None);
+
+    crate::runtime::record_archive(&record, "https://archive.example.com/a.tar", 2200).unwrap();
+    let tombstone = crate::runtime::get_tombstone(&record.id, "0xDEADBEEF").unwrap();
+    assert_eq!(tombstone.archive_error, None);
+    assert_eq!(tombstone.archive_attempts, 0);
+}
+
+#[test]
+fn interrupted_provisions_settle_against_sandbox_state() {
+    use crate::provision_progress::{self, ProvisionPhase};
+
+    let _guard = crate::TEST_ENV_GUARD
+        .lock()
+        .unwrap_or_else(|p| p.into_inner());
+    let dir = std::env::temp_dir().join(format!("reaper-provision-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).ok();
+    unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", &dir) };
+
+    let mut record = test_record();
+    record.id = format!("provision-{}", uuid::Uuid::new_v4().simple());
+    crate::runtime::sandboxes()
+        .unwrap()
+        .insert(record.id.clone(), record.clone())
+        .unwrap();
+
+    // Restarted after the container came up: recovered as ready.
+    let running = 43_000_001;
+    provision_progress::start_provision(running).unwrap();
+    provision_progress::update_provision(
+        running,
+        ProvisionPhase::ContainerStart,
+        None,
+        Some(record.id.clone()),
+        None,
+    )
+    .unwrap();
+    // Restarted while the container was being created: abandoned.
+    let abandoned = 43_000_002;
+    provision_progress::start_provision(abandoned).unwrap();
+    provision_progress::update_provision(
+        abandoned,
+        ProvisionPhase::ContainerCreate,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+
+    provision_progress::reconcile_interrupted_provisions().unwrap();
+
+    let running = provision_progress::get_provision(running).unwrap().unwrap();
+    assert_eq!(running.phase, ProvisionPhase::Ready);
+    assert_eq!(
+        running.sidecar_url.as_deref(),
+        Some(record.sidecar_url.as_str())
+    );
+    let abandoned = provision_progress::get_provision(abandoned)
+        .unwrap()
+        .unwrap();
+    assert_eq!(abandoned.phase, ProvisionPhase::Failed);
+    assert!(
+        abandoned
+            .message
+            .unwrap()
+            .contains("Operator restarted during container_create")
+    );
+
+    crate::runtime::sandboxes()
+        .unwrap()
+        .remove(&record.id)
+        .unwrap();
+}
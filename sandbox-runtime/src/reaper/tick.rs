@@ -1,33 +1,68 @@
 use super::*;
 
-/// Enforce idle timeout and max lifetime on running sandboxes.
+/// Enforce idle timeout (counting sidecar-reported activity, see
+/// [`super::activity`]), max lifetime and the end of a drain window on
+/// running sandboxes (sending `reaper.warning` webhooks shortly before),
+/// revoke SSH keys whose TTL has passed, when enabled record SSH logins, run
+/// due secret rotations, and sample and persist owner usage (see
+/// [`crate::usage_ledger`]).
 ///
 /// Called every `SANDBOX_REAPER_INTERVAL` seconds.
 pub async fn reaper_tick() {
     let now = crate::util::now_ts();
+    enforce_drain_window(now).await;
+    let drain_stop_deadline = crate::drain::current_drain()
+        .ok()
+        .flatten()
+        .filter(|d| d.stop && d.completed_at.is_none())
+        .map(|d| d.deadline);
 
     let records = match sandboxes().and_then(|s| s.values()) {
         Ok(v) => v,
         Err(err) => {
             error!("reaper: failed to read sandboxes: {err}");
+            metrics().record_reaper_failure();
             return;
         }
     };
+    crate::usage_ledger::sample_sandbox_time(&records, now);
 
     for mut record in records {
         if let Err(e) = crate::runtime::unseal_record(&mut record) {
             tracing::error!(id = %record.id, error = %e, "Failed to unseal record in reaper — skipping");
+            metrics().record_reaper_failure();
             continue;
         }
         if record.state != SandboxState::Running {
             continue;
         }
 
-        let activity = if record.last_activity_at > 0 {
+        let mut activity = if record.last_activity_at > 0 {
             record.last_activity_at
         } else {
             record.created_at
         };
+        // Operator calls are not the only use: ask the sidecar before warning
+        // about or enforcing an idle stop.
+        if record.idle_timeout_seconds > 0
+            && activity + record.idle_timeout_seconds
+                <= now + crate::webhooks::reaper_warning_secs()
+        {
+            activity = refresh_activity(&record, activity, now).await;
+        }
+
+        // Warn owners ahead of the deletion and stop below.
+        if record.max_lifetime_seconds > 0 {
+            let deadline = record.created_at + record.max_lifetime_seconds;
+            crate::webhooks::warn_reaper_action(&record, "delete", "max_lifetime", deadline, now);
+        }
+        if record.idle_timeout_seconds > 0 {
+            let deadline = activity + record.idle_timeout_seconds;
+            crate::webhooks::warn_reaper_action(&record, "stop", "idle_timeout", deadline, now);
+        }
+        if let Some(deadline) = drain_stop_deadline {
+            crate::webhooks::warn_reaper_action(&record, "stop", "maintenance", deadline, now);
+        }
 
         // Hard kill: exceeded max lifetime
         if record.max_lifetime_seconds > 0 && record.created_at + record.max_lifetime_seconds <= now
@@ -38,6 +73,7 @@ pub async fn reaper_tick() {
             );
             if let Err(err) = delete_sidecar(&record, None).await {
                 error!("reaper: failed to delete sandbox {}: {err}", record.id);
+                metrics().record_reaper_failure();
                 continue;
             }
             if let Ok(store) = sandboxes() {
@@ -47,6 +83,15 @@ pub async fn reaper_tick() {
             continue;
         }
 
+        // SSH key TTLs: revoke while the container is still up. Keys on
+        // stopped sandboxes are revoked on the first tick after resume.
+        revoke_expired_ssh_keys(&record, now).await;
+        if crate::audit_log::ssh_login_audit_enabled()
+            && let Err(e) = poll_ssh_logins(&record).await
+        {
+            tracing::warn!(sandbox_id = %record.id, error = %e, "reaper: SSH login poll failed");
+        }
+
         // Soft stop: idle too long
         if record.idle_timeout_seconds > 0 && activity + record.idle_timeout_seconds <= now {
             info!(
@@ -56,63 +101,29 @@ pub async fn reaper_tick() {
                 record.idle_timeout_seconds
             );
 
+            // Snapshot while the container is still up, stop, then commit.
             let config = SidecarRuntimeConfig::load();
-
-            // Pre-stop: upload S3 snapshot while container is still running
-            let snapshot_dest = resolve_snapshot_destination(&record, config);
-            if let Some(ref dest) = snapshot_dest {
-                match upload_s3_snapshot(&record, dest).await {
-                    Ok(()) => {
-                        if let Ok(store) = sandboxes() {
-                            let dest_clone = dest.clone();
-                            let _ = store.update(&record.id, |r| {
-                                r.snapshot_s3_url = Some(dest_clone);
-                            });
-                        }
-                        metrics().record_snapshot_uploaded();
-                        info!("reaper: uploaded S3 snapshot for sandbox {}", record.id);
-                    }
-                    Err(err) => {
-                        error!(
-                            "reaper: S3 snapshot upload failed for sandbox {}: {err}",
-                            record.id
-                        );
-                    }
-                }
-            }
-
-            // Stop the container
-            if let Err(err) = stop_sidecar(&record).await {
+            upload_pre_stop_snapshot(&record, config).await;
+            if let Err(err) = reap_sidecar(&record, "idle_timeout").await {
                 error!("reaper: failed to stop sandbox {}: {err}", record.id);
+                metrics().record_reaper_failure();
                 continue;
             }
-
-            // Post-stop: docker commit to preserve filesystem.
-            // TEE sandboxes have no Docker container to commit — skip.
-            if config.snapshot_auto_commit
-                && record.tee_deployment_id.is_none()
-                && !record_uses_firecracker(&record)
-            {
-                match commit_container(&record).await {
-                    Ok(image_id) => {
-                        if let Ok(store) = sandboxes() {
-                            let _ = store.update(&record.id, |r| {
-                                r.snapshot_image_id = Some(image_id);
-                            });
-                        }
-                        metrics().record_snapshot_committed();
-                        info!("reaper: committed snapshot for sandbox {}", record.id);
-                    }
-                    Err(err) => {
-                        error!(
-                            "reaper: docker commit failed for sandbox {}: {err}",
-                            record.id
-                        );
-                    }
-                }
+            if config.snapshot_auto_commit {
+                commit_stopped_snapshot(&record).await;
             }
 
             metrics().record_reaped_idle();
         }
     }
+
+    // Scheduled secret rotation for sandboxes still running after the pass.
+    crate::secret_provisioning::run_due_rotations(now).await;
+
+    if let Err(err) = crate::usage_ledger::flush_usage(now) {
+        error!("reaper: failed to persist usage: {err}");
+        metrics().record_reaper_failure();
+    }
+
+    metrics().record_reaper_tick(now);
 }
@@ -0,0 +1,441 @@
+//! Signed job results.
+//!
+//! With `OPERATOR_RESPONSE_SIGNING=true`, every JSON object a job returns
+//! carries an `operatorSignature` field, so customers and aggregators can
+//! check which operator produced a result and that nothing in between
+//! altered it:
+//!
+//! ```json
+//! "operatorSignature": {
+//!   "scheme": "eip191-keccak256-v2",
+//!   "operator": "0x…",
+//!   "serviceId": 1,
+//!   "callId": 42,
+//!   "payloadHash": "0x…",
+//!   "signature": "0x…"
+//! }
+//! ```
+//!
+//! `payloadHash` is the keccak256 of the response's canonical JSON (object
+//! keys sorted recursively, no whitespace) with `operatorSignature` removed.
+//! The signed message binds the job call and the signer to it: the keccak256
+//! of `abi.encodePacked(uint64 serviceId, uint64 callId, address operator,
+//! bytes32 payloadHash)` (see [`signed_digest`]), so a signed result cannot
+//! be replayed as the answer to another call or attributed to another
+//! operator. `signature` is an EIP-191 `personal_sign` over those 32 bytes
+//! (`r ‖ s ‖ v`, `v` = 27/28), i.e. what `verifyMessage(getBytes(digest),
+//! sig)` or `ECDSA.toEthSignedMessageHash(digest)` + `ecrecover` expect. The
+//! key is the operator's ECDSA key from `KEYSTORE_URI`; [`verify`] checks a
+//! signed response.
+
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use once_cell::sync::OnceCell;
+use serde_json::{Map, Value, json};
+
+use crate::session_auth::keccak256;
+
+pub const SIGNATURE_FIELD: &str = "operatorSignature";
+pub const SCHEME: &str = "eip191-keccak256-v2";
+
+static SIGNER: OnceCell<Option<ResponseSigner>> = OnceCell::new();
+
+/// Signs job responses with the operator's key.
+pub struct ResponseSigner {
+    key: SigningKey,
+    address: String,
+}
+
+impl ResponseSigner {
+    pub fn from_secret(secret: &[u8]) -> Result<Self, String> {
+        let key = SigningKey::from_slice(secret)
+            .map_err(|err| format!("invalid operator key bytes: {err}"))?;
+        let address = address_of(key.verifying_key());
+        Ok(Self { key, address })
+    }
+
+    /// Lower-case `0x` address of the signing key.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Add `operatorSignature` for job call `call_id` of `service_id` to a
+    /// JSON object. Any existing signature is replaced; other values are left
+    /// unchanged.
+    pub fn sign_value(
+        &self,
+        value: &mut Value,
+        service_id: u64,
+        call_id: u64,
+    ) -> Result<(), String> {
+        let Value::Object(map) = value else {
+            return Ok(());
+        };
+        map.remove(SIGNATURE_FIELD);
+        let hash = keccak256(canonical_json(&Value::Object(map.clone())).as_bytes());
+        let digest = signed_digest(service_id, call_id, &self.address, &hash)?;
+        let (signature, recovery_id) = self
+            .key
+            .sign_prehash_recoverable(&personal_digest(&digest))
+            .map_err(|err| format!("failed to sign response: {err}"))?;
+        let mut sig_bytes = Vec::with_capacity(65);
+        sig_bytes.extend_from_slice(&signature.to_bytes());
+        sig_bytes.push(recovery_id.to_byte() + 27);
+        map.insert(
+            SIGNATURE_FIELD.into(),
+            json!({
+                "scheme": SCHEME,
+                "operator": self.address,
+                "serviceId": service_id,
+                "callId": call_id,
+                "payloadHash": format!("0x{}", hex::encode(hash)),
+                "signature": format!("0x{}", hex::encode(sig_bytes)),
+            }),
+        );
+        Ok(())
+    }
+
+    /// [`Self::sign_value`] for a JSON string. Non-JSON payloads are returned
+    /// unchanged.
+    pub fn sign_json(&self, json: &str, service_id: u64, call_id: u64) -> Result<String, String> {
+        let Ok(mut value) = serde_json::from_str::<Value>(json) else {
+            return Ok(json.to_string());
+        };
+        if !value.is_object() {
+            return Ok(json.to_string());
+        }
+        self.sign_value(&mut value, service_id, call_id)?;
+        Ok(value.to_string())
+    }
+}
+
+/// The operator's response signer, if `OPERATOR_RESPONSE_SIGNING` is on and
+/// the keystore key could be loaded. Resolved once.
+pub fn signer() -> Option<&'static ResponseSigner> {
+    SIGNER
+        .get_or_init(|| {
+            if !std::env::var("OPERATOR_RESPONSE_SIGNING").is_ok_and(|v| v == "true" || v == "1") {
+                return None;
+            }
+            let loaded = std::env::var("KEYSTORE_URI")
+                .map_err(|_| "KEYSTORE_URI is not set".to_string())
+                .and_then(|uri| read_keystore_secret(&uri))
+                .and_then(|secret| ResponseSigner::from_secret(&secret));
+            match loaded {
+                Ok(signer) => {
+                    tracing::info!(operator = %signer.address, "Signing job responses");
+                    Some(signer)
+                }
+                Err(err) => {
+                    tracing::error!(error = %err, "OPERATOR_RESPONSE_SIGNING is set but no signing key is available; responses are unsigned");
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// Sign the JSON response of job call `call_id` of `service_id` when
+/// response signing is enabled. A signing failure is logged and the response
+/// returned unsigned.
+pub fn sign_json(json: &str, service_id: u64, call_id: u64) -> String {
+    let Some(signer) = signer() else {
+        return json.to_string();
+    };
+    signer
+        .sign_json(json, service_id, call_id)
+        .unwrap_or_else(|err| {
+            tracing::warn!(error = %err, "Failed to sign job response");
+            json.to_string()
+        })
+}
+
+/// Who signed a response, and for which job call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedOrigin {
+    /// Lower-case `0x` address recovered from the signature.
+    pub operator: String,
+    pub service_id: u64,
+    pub call_id: u64,
+}
+
+/// Verify a signed response and return the operator that signed it and the
+/// job call it was signed for.
+///
+/// The signature covers `serviceId`, `callId`, `operator` and `payloadHash`
+/// together, so none can be swapped without breaking it. It proves only
+/// that the operator returned this payload for that call: callers must
+/// still check that `service_id` and `call_id` are the call they submitted
+/// (and that `operator` is one they expect), or a genuine result of another
+/// call would be accepted.
+pub fn verify(json: &str) -> Result<SignedOrigin, String> {
+    let Value::Object(mut map) =
+        serde_json::from_str::<Value>(json).map_err(|err| format!("invalid JSON: {err}"))?
+    else {
+        return Err("response is not a JSON object".into());
+    };
+    let envelope = map
+        .remove(SIGNATURE_FIELD)
+        .ok_or_else(|| format!("response has no {SIGNATURE_FIELD}"))?;
+    let field = |name: &str| {
+        envelope
+            .get(name)
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("{SIGNATURE_FIELD}.{name} is missing"))
+    };
+    if field("scheme")? != SCHEME {
+        return Err(format!(
+            "unsupported signature scheme '{}'",
+            field("scheme")?
+        ));
+    }
+
+    let number = |name: &str| {
+        envelope
+            .get(name)
+            .and_then(Value::as_u64)
+            .ok_or_else(|| format!("{SIGNATURE_FIELD}.{name} is missing"))
+    };
+    let (service_id, call_id) = (number("serviceId")?, number("callId")?);
+
+    let hash = keccak256(canonical_json(&Value::Object(map)).as_bytes());
+    if !field("payloadHash")?.eq_ignore_ascii_case(&format!("0x{}", hex::encode(hash))) {
+        return Err("payload hash does not match the response".into());
+    }
+    let digest = signed_digest(service_id, call_id, field("operator")?, &hash)?;
+
+    let sig_bytes = hex::decode(field("signature")?.trim_start_matches("0x"))
+        .map_err(|err| format!("invalid signature hex: {err}"))?;
+    if sig_bytes.len() != 65 {
+        return Err(format!(
+            "signature must be 65 bytes, got {}",
+            sig_bytes.len()
+        ));
+    }
+    let (rs, v) = sig_bytes.split_at(64);
+    let recovery_id = match v[0] {
+        0 | 27 => RecoveryId::new(false, false),
+        1 | 28 => RecoveryId::new(true, false),
+        v => return Err(format!("invalid recovery id: {v}")),
+    };
+    let signature =
+        Signature::from_slice(rs).map_err(|err| format!("invalid ECDSA signature: {err}"))?;
+    let key =
+        VerifyingKey::recover_from_prehash(&personal_digest(&digest), &signature, recovery_id)
+            .map_err(|err| format!("signature recovery failed: {err}"))?;
+    let recovered = address_of(&key);
+    if !recovered.eq_ignore_ascii_case(field("operator")?) {
+        return Err(format!(
+            "signature was made by {recovered}, not the claimed operator"
+        ));
+    }
+    Ok(SignedOrigin {
+        operator: recovered,
+        service_id,
+        call_id,
+    })
+}
+
+/// The 32 bytes an operator signs for a response: keccak256 of
+/// `abi.encodePacked(uint64 serviceId, uint64 callId, address operator,
+/// bytes32 payloadHash)`.
+pub fn signed_digest(
+    service_id: u64,
+    call_id: u64,
+    operator: &str,
+    payload_hash: &[u8; 32],
+) -> Result<[u8; 32], String> {
+    let operator = hex::decode(operator.trim_start_matches("0x"))
+        .ok()
+        .filter(|bytes| bytes.len() == 20)
+        .ok_or_else(|| format!("invalid operator address '{operator}'"))?;
+    let mut message = Vec::with_capacity(8 + 8 + 20 + 32);
+    message.extend_from_slice(&service_id.to_be_bytes());
+    message.extend_from_slice(&call_id.to_be_bytes());
+    message.extend_from_slice(&operator);
+    message.extend_from_slice(payload_hash);
+    Ok(keccak256(&message))
+}
+
+/// JSON with object keys sorted at every level and no whitespace.
+pub fn canonical_json(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                Value::Object(
+                    entries
+                        .into_iter()
+                        .map(|(k, v)| (k.clone(), sorted(v)))
+                        .collect::<Map<_, _>>(),
+                )
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            other => other.clone(),
+        }
+    }
+    sorted(value).to_string()
+}
+
+/// Read the operator's ECDSA secret from a blueprint keystore directory
+/// (`{KEYSTORE_URI}/Ecdsa/*`).
+pub fn read_keystore_secret(keystore_uri: &str) -> Result<[u8; 32], String> {
+    use std::fs;
+    use std::path::Path;
+
+    let keystore_path = keystore_uri.strip_prefix("file://").unwrap_or(keystore_uri);
+    let ecdsa_dir = Path::new(keystore_path).join("Ecdsa");
+    let mut entries = fs::read_dir(&ecdsa_dir)
+        .map_err(|err| format!("failed to read {}: {err}", ecdsa_dir.display()))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to enumerate {}: {err}", ecdsa_dir.display()))?;
+    entries.sort_by_key(|entry| entry.path());
+
+    for entry in entries {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let raw = fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        let components: Vec<Vec<u8>> = serde_json::from_str(&raw)
+            .map_err(|err| format!("failed to parse {}: {err}", path.display()))?;
+        if let Some(secret) = components.iter().rev().find(|part| part.len() == 32) {
+            let mut key = [0u8; 32];
+            key.copy_from_slice(secret);
+            return Ok(key);
+        }
+    }
+
+    Err(format!(
+        "no usable ECDSA secret found under {}",
+        ecdsa_dir.display()
+    ))
+}
+
+/// EIP-191 digest of a 32-byte message.
+fn personal_digest(hash: &[u8; 32]) -> [u8; 32] {
+    let mut prefixed = b"\x19Ethereum Signed Message:\n32".to_vec();
+    prefixed.extend_from_slice(hash);
+    keccak256(&prefixed)
+}
+
+fn address_of(key: &VerifyingKey) -> String {
+    let pubkey = key.to_encoded_point(false);
+    let hash = keccak256(&pubkey.as_bytes()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_signer() -> ResponseSigner {
+        ResponseSigner::from_secret(&[7u8; 32]).unwrap()
+    }
+
+    #[test]
+    fn canonical_json_sorts_keys_recursively() {
+        let value: Value =
+            serde_json::from_str(r#"{"b":1,"a":{"d":[{"z":1,"y":2}],"c":null}}"#).unwrap();
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":{"c":null,"d":[{"y":2,"z":1}]},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn signed_response_verifies_regardless_of_key_order() {
+        let signer = test_signer();
+        let signed = signer
+            .sign_json(r#"{"sandboxId":"sb-1","traceId":"abc","ok":true}"#, 1, 42)
+            .unwrap();
+        let origin = verify(&signed).unwrap();
+        assert_eq!(origin.operator, signer.address());
+        assert_eq!((origin.service_id, origin.call_id), (1, 42));
+
+        // Re-serializing with another key order keeps the hash.
+        let mut value: Value = serde_json::from_str(&signed).unwrap();
+        let reordered = canonical_json(&value);
+        assert_eq!(verify(&reordered).unwrap(), origin);
+
+        // Re-signing replaces the previous signature.
+        signer.sign_value(&mut value, 1, 42).unwrap();
+        assert_eq!(value.to_string(), signed);
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let signer = test_signer();
+        let signed = signer.sign_json(r#"{"ok":true,"value":1}"#, 1, 42).unwrap();
+
+        let mut payload: Value = serde_json::from_str(&signed).unwrap();
+        payload["value"] = json!(2);
+        assert!(verify(&payload.to_string()).unwrap_err().contains("hash"));
+
+        let mut forged: Value = serde_json::from_str(&signed).unwrap();
+        forged[SIGNATURE_FIELD]["operator"] = json!("0x0000000000000000000000000000000000000001");
+        assert!(verify(&forged.to_string()).is_err());
+
+        let other = ResponseSigner::from_secret(&[9u8; 32]).unwrap();
+        let mut resigned: Value = serde_json::from_str(&signed).unwrap();
+        let theirs = other.sign_json(r#"{"ok":true,"value":1}"#, 1, 42).unwrap();
+        let theirs: Value = serde_json::from_str(&theirs).unwrap();
+        resigned[SIGNATURE_FIELD]["signature"] = theirs[SIGNATURE_FIELD]["signature"].clone();
+        assert!(
+            verify(&resigned.to_string())
+                .unwrap_err()
+                .contains("not the claimed operator")
+        );
+
+        // The signature does not carry over to another job call.
+        for field in ["serviceId", "callId"] {
+            let mut moved: Value = serde_json::from_str(&signed).unwrap();
+            moved[SIGNATURE_FIELD][field] = json!(7);
+            assert!(verify(&moved.to_string()).is_err(), "{field}");
+        }
+    }
+
+    #[test]
+    fn signed_digest_matches_abi_encode_packed() {
+        let operator = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+        let hash = [0xab; 32];
+        use alloy::primitives::{Address, B256};
+        use alloy::sol_types::SolValue;
+
+        let packed = alloy::primitives::keccak256(
+            (
+                1u64,
+                42u64,
+                operator.parse::<Address>().unwrap(),
+                B256::from(hash),
+            )
+                .abi_encode_packed(),
+        );
+        assert_eq!(signed_digest(1, 42, operator, &hash).unwrap(), packed.0);
+        assert!(signed_digest(1, 42, "0x1234", &hash).is_err());
+    }
+
+    #[test]
+    fn non_object_payloads_are_untouched() {
+        let signer = test_signer();
+        assert_eq!(signer.sign_json("[1,2]", 1, 1).unwrap(), "[1,2]");
+        assert_eq!(signer.sign_json("plain", 1, 1).unwrap(), "plain");
+        assert!(verify(r#"{"ok":true}"#).is_err());
+    }
+
+    #[test]
+    fn reads_secret_from_keystore_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let ecdsa = dir.path().join("Ecdsa");
+        std::fs::create_dir_all(&ecdsa).unwrap();
+        std::fs::write(
+            ecdsa.join("key"),
+            serde_json::to_string(&vec![vec![1u8; 33], vec![7u8; 32]]).unwrap(),
+        )
+        .unwrap();
+        let uri = format!("file://{}", dir.path().display());
+        assert_eq!(read_keystore_secret(&uri).unwrap(), [7u8; 32]);
+        assert!(read_keystore_secret("/nonexistent/keystore").is_err());
+    }
+}
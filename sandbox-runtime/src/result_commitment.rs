@@ -0,0 +1,202 @@
+//! Hash commitments for oversized job results.
+//!
+//! Exec and task result jobs can return far more than should be written into
+//! an on-chain result. With `RESULT_COMMITMENT_MIN_BYTES` set (default `0`,
+//! disabled), a result whose JSON is larger than that many bytes is replaced
+//! by a compact stub that keeps the identifying fields and adds:
+//!
+//! ```json
+//! "resultCommitment": {
+//!   "scheme": "keccak256",
+//!   "hash": "0x…",
+//!   "ref": "…",
+//!   "bytes": 1048576
+//! }
+//! ```
+//!
+//! The full result is stored as a result artifact and served verbatim by
+//! `GET /api/sandboxes/{id}/results/{ref}`; `hash` is the keccak256 of that
+//! exact response body, so anyone holding the on-chain stub can check the
+//! payload they fetched. Like exec overflow artifacts these are plain files
+//! in the state directory, pruned after `RESULT_ARTIFACT_TTL_SECS` (default
+//! 7 days) on each new write.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::error::{Result, SandboxError};
+
+pub const COMMITMENT_FIELD: &str = "resultCommitment";
+pub const SCHEME: &str = "keccak256";
+pub const DEFAULT_RESULT_ARTIFACT_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Full payload of a job result that was replaced by a commitment.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResultArtifact {
+    pub id: String,
+    pub sandbox_id: String,
+    /// `0x` keccak256 of `payload`.
+    pub hash: String,
+    /// The result JSON exactly as hashed.
+    pub payload: String,
+    pub created_at: u64,
+}
+
+/// Size above which results are committed, from `RESULT_COMMITMENT_MIN_BYTES`
+/// (`0` = never).
+pub fn result_commitment_min_bytes() -> usize {
+    std::env::var("RESULT_COMMITMENT_MIN_BYTES")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(0)
+}
+
+fn result_artifact_ttl_secs() -> u64 {
+    std::env::var("RESULT_ARTIFACT_TTL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_RESULT_ARTIFACT_TTL_SECS)
+}
+
+fn artifact_dir() -> PathBuf {
+    crate::store::state_dir().join("result-artifacts")
+}
+
+/// `0x` keccak256 of a result payload.
+pub fn result_hash(payload: &str) -> String {
+    crate::session_auth::keccak256_hex(payload.as_bytes())
+}
+
+/// Serialize `result` for a job response, replacing it with a commitment stub
+/// when it exceeds [`result_commitment_min_bytes`]. The stub keeps the `keep`
+/// fields of `result`.
+///
+/// Storage failures are logged and never fail the job: the caller gets the
+/// full result, as if commitments were disabled.
+pub fn commit_large_result(sandbox_id: &str, result: Value, keep: &[&str]) -> String {
+    let payload = result.to_string();
+    let min_bytes = result_commitment_min_bytes();
+    if min_bytes == 0 || payload.len() <= min_bytes {
+        return payload;
+    }
+
+    let artifact = ResultArtifact {
+        id: uuid::Uuid::new_v4().simple().to_string(),
+        sandbox_id: sandbox_id.to_string(),
+        hash: result_hash(&payload),
+        payload,
+        created_at: crate::util::now_ts(),
+    };
+    if let Err(e) = store_result_artifact(&artifact) {
+        tracing::warn!(sandbox_id, error = %e, "failed to store result artifact");
+        return artifact.payload;
+    }
+
+    let mut stub = Map::new();
+    for key in keep {
+        if let Some(value) = result.get(*key) {
+            stub.insert((*key).to_string(), value.clone());
+        }
+    }
+    stub.insert(
+        COMMITMENT_FIELD.into(),
+        json!({
+            "scheme": SCHEME,
+            "hash": artifact.hash,
+            "ref": artifact.id,
+            "bytes": artifact.payload.len(),
+        }),
+    );
+    Value::Object(stub).to_string()
+}
+
+/// Load a result artifact by ID alone. Callers must check that the requester
+/// owns `sandbox_id` before returning it.
+pub fn find_result_artifact(artifact_id: &str) -> Result<ResultArtifact> {
+    let not_found = || SandboxError::NotFound(format!("Result artifact '{artifact_id}' not found"));
+    if artifact_id.is_empty() || !artifact_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(not_found());
+    }
+    let path = artifact_dir().join(format!("{artifact_id}.json"));
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(not_found()),
+        Err(e) => {
+            return Err(SandboxError::Storage(format!(
+                "Failed to read result artifact: {e}"
+            )));
+        }
+    };
+    serde_json::from_slice(&bytes)
+        .map_err(|e| SandboxError::Storage(format!("Result artifact is corrupt: {e}")))
+}
+
+/// Load a result artifact of `sandbox_id`. Returns `NotFound` when the
+/// artifact does not exist, has expired, or belongs to a different sandbox.
+pub fn get_result_artifact(sandbox_id: &str, artifact_id: &str) -> Result<ResultArtifact> {
+    let artifact = find_result_artifact(artifact_id)?;
+    if artifact.sandbox_id != sandbox_id {
+        return Err(SandboxError::NotFound(format!(
+            "Result artifact '{artifact_id}' not found"
+        )));
+    }
+    Ok(artifact)
+}
+
+fn store_result_artifact(artifact: &ResultArtifact) -> Result<()> {
+    let dir = artifact_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| SandboxError::Storage(format!("Failed to create {}: {e}", dir.display())))?;
+    crate::exec_output::prune_expired_artifacts(&dir, result_artifact_ttl_secs());
+    let bytes = serde_json::to_vec(artifact)
+        .map_err(|e| SandboxError::Storage(format!("Failed to encode result artifact: {e}")))?;
+    std::fs::write(dir.join(format!("{}.json", artifact.id)), bytes)
+        .map_err(|e| SandboxError::Storage(format!("Failed to write result artifact: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_results_are_replaced_by_a_verifiable_commitment() {
+        let _guard = crate::TEST_ENV_GUARD
+            .lock()
+            .unwrap_or_else(|p| p.into_inner());
+        let dir = std::env::temp_dir().join(format!("result-commit-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        unsafe {
+            std::env::set_var("BLUEPRINT_STATE_DIR", &dir);
+            std::env::set_var("RESULT_COMMITMENT_MIN_BYTES", "64");
+        }
+
+        let small = json!({"taskId": "t1", "result": "ok"});
+        assert_eq!(
+            commit_large_result("sb-1", small.clone(), &["taskId"]),
+            small.to_string()
+        );
+
+        let big = json!({"taskId": "t2", "status": "completed", "result": "x".repeat(200)});
+        let stub: Value =
+            serde_json::from_str(&commit_large_result("sb-1", big.clone(), &["taskId"])).unwrap();
+        assert_eq!(stub["taskId"], "t2");
+        assert!(stub.get("result").is_none());
+        assert!(stub.get("status").is_none());
+        let commitment = &stub[COMMITMENT_FIELD];
+        assert_eq!(commitment["scheme"], SCHEME);
+        assert_eq!(commitment["bytes"], big.to_string().len());
+
+        let artifact = get_result_artifact("sb-1", commitment["ref"].as_str().unwrap()).unwrap();
+        assert_eq!(artifact.payload, big.to_string());
+        assert_eq!(commitment["hash"], result_hash(&artifact.payload));
+        assert!(matches!(
+            get_result_artifact("sb-other", &artifact.id),
+            Err(SandboxError::NotFound(_))
+        ));
+        assert!(find_result_artifact("../secrets").is_err());
+
+        unsafe { std::env::remove_var("RESULT_COMMITMENT_MIN_BYTES") };
+    }
+}
@@ -54,6 +54,27 @@ pub(crate) fn check_sandbox_count_limit(
     Ok(())
 }
 
+/// Decision core of the on-chain capacity cap: the operator registered
+/// `OPERATOR_MAX_CAPACITY` sandboxes and must not hold more, whatever the
+/// host could fit. `capacity == 0` = not registered. The `capacity` wording
+/// classifies as `CAPACITY` so callers retry on another operator.
+pub(crate) fn check_operator_capacity(
+    current: usize,
+    reusing_existing_slot: bool,
+    capacity: usize,
+) -> Result<()> {
+    if capacity == 0 {
+        return Ok(());
+    }
+    if adjusted_sandbox_count_for_limit(current, reusing_existing_slot) >= capacity {
+        return Err(SandboxError::Unavailable(format!(
+            "Operator capacity reached ({current}/{capacity} sandboxes, OPERATOR_MAX_CAPACITY). \
+             Retry on another operator."
+        )));
+    }
+    Ok(())
+}
+
 /// One-pass scan of the store's records for admission: total row count,
 /// whether the incoming create replaces an existing slot, and the running
 /// set's memory + CPU footprints. Pure over a record slice so it is
@@ -104,8 +125,9 @@ pub(crate) fn scan_records_for_admission(
 /// `enforce_host_memory_budget` + `enforce_host_cpu_budget` (called at
 /// admission) trio, which each deserialized the full store per create. Same
 /// decisions, same error precedence: memory budget, then CPU budget, then
-/// the count check the backends used to run last. When no limit is
-/// configured the store is not read at all.
+/// the count check the backends used to run last, then the operator's
+/// on-chain capacity. When no limit is configured the store is not read at
+/// all.
 pub(crate) fn enforce_store_admission(
     config: &SidecarRuntimeConfig,
     incoming_memory_mb: u64,
@@ -114,7 +136,7 @@ pub(crate) fn enforce_store_admission(
 ) -> Result<()> {
     let memory_budget_enabled = config.sandbox_host_memory_budget_mb != 0;
     let cpu_budget_enabled = config.sandbox_host_cpu_budget != 0;
-    let count_capped = config.sandbox_max_count != 0;
+    let count_capped = config.sandbox_max_count != 0 || config.operator_max_capacity != 0;
     if !memory_budget_enabled && !cpu_budget_enabled && !count_capped {
         return Ok(());
     }
@@ -154,9 +176,72 @@ pub(crate) fn enforce_store_admission(
         scan.total_count,
         scan.reusing_existing_slot,
         config.sandbox_max_count,
+    )?;
+    check_operator_capacity(
+        scan.total_count,
+        scan.reusing_existing_slot,
+        config.operator_max_capacity,
     )
 }
 
+/// Current usage against every admission limit, for the operator API.
+/// Limits of 0 are unset.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct CapacitySnapshot {
+    pub sandboxes: usize,
+    pub running: usize,
+    /// Accounted footprint of running sandboxes (see [`accounted_cpu_cores`]
+    /// and [`accounted_memory_mb`]; unaccountable ones count as 0).
+    pub running_cpu_cores: u64,
+    pub running_memory_mb: u64,
+    pub max_sandboxes: usize,
+    pub operator_max_capacity: usize,
+    pub host_cpu_budget: u64,
+    pub host_memory_budget_mb: u64,
+    /// Sandboxes that can still be created before a count limit rejects.
+    /// `None` when neither count limit is set.
+    pub remaining_sandboxes: Option<usize>,
+}
+
+pub(crate) fn capacity_snapshot_of(
+    config: &SidecarRuntimeConfig,
+    records: &[SandboxRecord],
+) -> CapacitySnapshot {
+    let scan = scan_records_for_admission(records, None);
+    let running_cpu_cores = scan
+        .running_cpu_cores
+        .iter()
+        .filter_map(|&c| accounted_cpu_cores(c, config.sandbox_max_cpu_cores))
+        .fold(0u64, u64::saturating_add);
+    let running_memory_mb = scan
+        .running_memory_mb
+        .iter()
+        .filter_map(|&m| accounted_memory_mb(m, config.sandbox_max_memory_mb))
+        .fold(0u64, u64::saturating_add);
+    let remaining_sandboxes = [config.sandbox_max_count, config.operator_max_capacity]
+        .into_iter()
+        .filter(|&limit| limit != 0)
+        .min()
+        .map(|limit| limit.saturating_sub(scan.total_count));
+    CapacitySnapshot {
+        sandboxes: scan.total_count,
+        running: scan.running_cpu_cores.len(),
+        running_cpu_cores,
+        running_memory_mb,
+        max_sandboxes: config.sandbox_max_count,
+        operator_max_capacity: config.operator_max_capacity,
+        host_cpu_budget: config.sandbox_host_cpu_budget,
+        host_memory_budget_mb: config.sandbox_host_memory_budget_mb,
+        remaining_sandboxes,
+    }
+}
+
+/// Snapshot of the store against the configured admission limits.
+pub fn capacity_snapshot() -> Result<CapacitySnapshot> {
+    let records = sandboxes()?.values()?;
+    Ok(capacity_snapshot_of(SidecarRuntimeConfig::load(), &records))
+}
+
 /// Apply a per-sandbox operator maximum to one requested resource value.
 ///
 /// `max == 0` means no cap: the request passes through, including 0 =
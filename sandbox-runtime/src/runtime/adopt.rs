@@ -0,0 +1,187 @@
+//! Re-adopting sandboxes whose records were lost with the state dir.
+//!
+//! The chain still knows which sandboxes the operator created and for whom
+//! (see the blueprint's `bootstrap_sandboxes_from_chain`). [`adopt_sandbox`]
+//! turns that into a store record, filled in from the surviving
+//! `sidecar-{id}` container where there is one: container ID, image, host
+//! ports, run state and the sidecar token it was started with. Only Docker
+//! sandboxes can be re-attached; Firecracker VMs and TEE deployments are
+//! recorded as gone so their owners can still see and delete them.
+
+use super::*;
+use docktopus::bollard::container::InspectContainerOptions;
+
+/// A sandbox rebuilt from its on-chain create job.
+#[derive(Clone, Debug)]
+pub struct RecoveredSandbox {
+    pub sandbox_id: String,
+    /// The create request, with the caller as `owner`.
+    pub params: CreateSandboxParams,
+    /// Endpoint and token from the create result, used when no container
+    /// is left to read them from.
+    pub sidecar_url: String,
+    pub token: String,
+    pub ssh_port: Option<u16>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdoptOutcome {
+    /// A record for the sandbox already exists; nothing was changed.
+    AlreadyKnown,
+    /// Re-attached to its running container.
+    Running,
+    /// Re-attached to its stopped container (resume refreshes the ports).
+    Stopped,
+    /// No container left: recorded as stopped with the container removed.
+    Missing,
+}
+
+fn container_env_value(env: &[String], key: &str) -> Option<String> {
+    env.iter()
+        .filter_map(|entry| entry.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v.to_string())
+}
+
+/// Rebuild and store the record for `recovered`.
+pub async fn adopt_sandbox(recovered: RecoveredSandbox) -> Result<AdoptOutcome> {
+    let RecoveredSandbox {
+        sandbox_id,
+        params,
+        sidecar_url,
+        token,
+        ssh_port,
+    } = recovered;
+    let _lock = acquire_lifecycle_lock(&sandbox_id).await;
+    if sandboxes()?.get(&sandbox_id)?.is_some() {
+        return Ok(AdoptOutcome::AlreadyKnown);
+    }
+
+    let config = SidecarRuntimeConfig::load();
+    let now = crate::util::now_ts();
+    let backend = resolve_runtime_backend(&params).unwrap_or(RuntimeBackend::Docker);
+    let metadata_json = match backend {
+        RuntimeBackend::Firecracker => {
+            metadata_with_runtime_backend(&params.metadata_json, backend)
+                .unwrap_or_else(|_| params.metadata_json.clone())
+        }
+        _ => params.metadata_json.clone(),
+    };
+    let snapshot_destination = parse_json_object(&params.metadata_json, "metadata_json")
+        .ok()
+        .flatten()
+        .and_then(|meta| {
+            meta.get("snapshot_destination")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+
+    let mut record = SandboxRecord {
+        id: sandbox_id.clone(),
+        container_id: String::new(),
+        sidecar_port: parse_url_port(&sidecar_url).unwrap_or(0),
+        sidecar_url,
+        ssh_port,
+        token,
+        created_at: now,
+        cpu_cores: params.cpu_cores,
+        memory_mb: params.memory_mb,
+        state: SandboxState::Stopped,
+        idle_timeout_seconds: config.effective_idle_timeout(params.idle_timeout_seconds),
+        max_lifetime_seconds: config.effective_max_lifetime(params.max_lifetime_seconds),
+        last_activity_at: now,
+        stopped_at: Some(now),
+        snapshot_image_id: None,
+        snapshot_s3_url: None,
+        container_removed_at: Some(now),
+        image_removed_at: None,
+        original_image: if params.image.is_empty() {
+            config.image.clone()
+        } else {
+            params.image.clone()
+        },
+        base_env_json: params.env_json.clone(),
+        user_env_json: params.user_env_json.clone(),
+        snapshot_destination,
+        tee_deployment_id: None,
+        tee_metadata_json: None,
+        tee_attestation_json: None,
+        name: params.name.clone(),
+        agent_identifier: params.agent_identifier.clone(),
+        metadata_json,
+        disk_gb: params.disk_gb,
+        stack: params.stack.clone(),
+        owner: params.owner.clone(),
+        service_id: params.service_id,
+        tee_config: params.tee_config.clone(),
+        extra_ports: HashMap::new(),
+        ssh_login_user: None,
+        ssh_authorized_keys: Vec::new(),
+        capabilities_json: params.capabilities_json.clone(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
+    };
+
+    let mut outcome = AdoptOutcome::Missing;
+    if backend == RuntimeBackend::Docker {
+        let builder = docker_builder().await?;
+        let inspect = docker_timeout(
+            "inspect_container",
+            builder.client().inspect_container(
+                &format!("sidecar-{sandbox_id}"),
+                None::<InspectContainerOptions>,
+            ),
+        )
+        .await;
+        if let Ok(inspect) = inspect
+            && let Some(container_id) = inspect.id.clone()
+        {
+            let container_config = inspect.config.clone().unwrap_or_default();
+            let env = container_config.env.unwrap_or_default();
+            if let Some(token) = container_env_value(&env, "SIDECAR_AUTH_TOKEN") {
+                record.token = token;
+            }
+            if let Some(image) = container_config.image {
+                record.original_image = image;
+            }
+            record.container_id = container_id.clone();
+            record.container_removed_at = None;
+            let running = inspect
+                .state
+                .as_ref()
+                .and_then(|s| s.running)
+                .unwrap_or(false);
+            outcome = AdoptOutcome::Stopped;
+            if running {
+                let extra_port_seed =
+                    parse_extra_ports(&params.metadata_json, &params.port_mappings)
+                        .into_iter()
+                        .map(|port| (port, 0u16))
+                        .collect::<HashMap<_, _>>();
+                let (url, port, ssh, extra) = refresh_port_mapping(
+                    builder.client(),
+                    &container_id,
+                    config.container_port,
+                    params.ssh_enabled,
+                    &config.public_host,
+                    &extra_port_seed,
+                )
+                .await?;
+                record.sidecar_url = url;
+                record.sidecar_port = port;
+                record.ssh_port = ssh;
+                record.extra_ports = extra;
+                record.state = SandboxState::Running;
+                record.stopped_at = None;
+                outcome = AdoptOutcome::Running;
+            }
+        }
+    }
+
+    seal_record(&mut record)?;
+    sandboxes()?.insert(sandbox_id, record)?;
+    Ok(outcome)
+}
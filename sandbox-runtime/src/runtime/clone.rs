@@ -0,0 +1,327 @@
+use super::*;
+
+/// Owner-specified overrides applied when cloning a sandbox.
+///
+/// Empty strings and zero values inherit the source sandbox's setting.
+/// `env_json` is merged on top of the source's base env.
+#[derive(Clone, Debug, Default)]
+pub struct CloneSandboxOverrides {
+    pub name: String,
+    pub env_json: String,
+    pub metadata_json: String,
+    pub cpu_cores: u64,
+    pub memory_mb: u64,
+    pub disk_gb: u64,
+    pub idle_timeout_seconds: u64,
+    pub max_lifetime_seconds: u64,
+    /// Carry the source's user-injected secrets over to the clone.
+    pub inherit_user_env: bool,
+}
+
+/// Create a new sandbox from an existing sandbox's current workspace.
+///
+/// The source container is committed to a clone image (paused only for the
+/// duration of the commit), and a fresh sandbox is created from that image
+/// with the source's config plus `overrides`. The clone gets a new sandbox
+/// ID and a freshly generated token; SSH keys are not copied.
+///
+/// A source whose container was already removed by GC is cloned from its
+/// committed snapshot image when one exists, re-committed with the same env
+/// reset (see [`commit_snapshot_clone_image`]). TEE and Firecracker
+/// sandboxes cannot be cloned (no image commit path).
+///
+/// The clone image is kept on the clone's record as `clone_image` and
+/// removed with the clone's container (see [`remove_clone_image`]).
+pub async fn clone_sidecar(
+    source: &SandboxRecord,
+    overrides: &CloneSandboxOverrides,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<SandboxRecord> {
+    if source.tee_deployment_id.is_some() {
+        return Err(SandboxError::Validation(
+            "Cloning is not supported for TEE sandboxes".into(),
+        ));
+    }
+    if record_uses_firecracker(source) {
+        return Err(SandboxError::Validation(
+            "Cloning is not supported for runtime_backend=firecracker".into(),
+        ));
+    }
+
+    let builder = docker_builder().await?;
+    // Prime the one-shot base image pull so the create below never tries to
+    // pull the local-only clone image from a registry.
+    ensure_image_pulled(&builder, &SidecarRuntimeConfig::load().image).await?;
+
+    let image = if source.container_removed_at.is_none() {
+        commit_clone_image(&builder, source).await?
+    } else if let Some(image_id) = &source.snapshot_image_id {
+        commit_snapshot_clone_image(&builder, source, image_id).await?
+    } else {
+        return Err(SandboxError::Validation(format!(
+            "Sandbox {} has no container or snapshot image to clone from",
+            source.id
+        )));
+    };
+
+    let params = clone_params(source, overrides, image.clone());
+    let created = match create_sidecar(&params, tee).await {
+        Ok((record, _attestation)) => record,
+        Err(err) => {
+            let _ = remove_snapshot_image(&image).await;
+            return Err(err);
+        }
+    };
+
+    // Keep the source's base image as the clone's lineage so image-drift
+    // detection and recreate treat the clone like its source.
+    sandboxes()?.update(&created.id, |r| {
+        r.original_image = source.original_image.clone();
+        r.image_digest = source.image_digest.clone();
+        r.clone_image = Some(image.clone());
+    })?;
+    tracing::info!(source = %source.id, clone = %created.id, "sandbox cloned");
+    get_sandbox_by_id(&created.id)
+}
+
+/// Build creation params for a clone of `source` booted from `image`.
+pub(crate) fn clone_params(
+    source: &SandboxRecord,
+    overrides: &CloneSandboxOverrides,
+    image: String,
+) -> CreateSandboxParams {
+    let pick = |value: u64, inherited: u64| if value > 0 { value } else { inherited };
+    CreateSandboxParams {
+        name: if overrides.name.trim().is_empty() {
+            format!("{}-clone", source.name)
+        } else {
+            overrides.name.clone()
+        },
+        image,
+        stack: source.stack.clone(),
+        agent_identifier: source.agent_identifier.clone(),
+        env_json: merge_env_json(&source.base_env_json, &overrides.env_json),
+        user_env_json: if overrides.inherit_user_env {
+            source.user_env_json.clone()
+        } else {
+            String::new()
+        },
+        metadata_json: if overrides.metadata_json.trim().is_empty() {
+            source.metadata_json.clone()
+        } else {
+            overrides.metadata_json.clone()
+        },
+        ssh_enabled: source.ssh_port.is_some(),
+        ssh_public_key: String::new(),
+        web_terminal_enabled: false,
+        max_lifetime_seconds: pick(overrides.max_lifetime_seconds, source.max_lifetime_seconds),
+        idle_timeout_seconds: pick(overrides.idle_timeout_seconds, source.idle_timeout_seconds),
+        cpu_cores: pick(overrides.cpu_cores, source.cpu_cores),
+        memory_mb: pick(overrides.memory_mb, source.memory_mb),
+        disk_gb: pick(overrides.disk_gb, source.disk_gb),
+        owner: source.owner.clone(),
+        service_id: source.service_id,
+        tee_config: None,
+        port_mappings: source.extra_ports.keys().copied().collect(),
+        capabilities_json: source.capabilities_json.clone(),
+    }
+}
+
+/// Commit the source container under a clone-specific tag so it never
+/// collides with the source's own auto-snapshot image. Returns the
+/// `sandbox-clone/<id>:<tag>` reference.
+///
+/// `docker commit` carries the container's whole environment into the
+/// image, including the source user's env and secrets. Every variable the
+/// base image does not set identically is reset (see
+/// [`clone_env_changes`]); the clone's own env is applied on create.
+async fn commit_clone_image(builder: &DockerBuilder, source: &SandboxRecord) -> Result<String> {
+    use docktopus::bollard::container::InspectContainerOptions;
+    let inspect = docker_timeout(
+        "inspect_container",
+        builder
+            .client()
+            .inspect_container(&source.container_id, None::<InspectContainerOptions>),
+    )
+    .await?;
+    let container_env = inspect.config.and_then(|c| c.env).unwrap_or_default();
+    commit_scrubbed(builder, source, &source.container_id, &container_env).await
+}
+
+/// [`commit_clone_image`] for a source whose container is gone: its
+/// auto-snapshot image (see [`commit_container`]) still holds the source's
+/// user env, secrets and `SIDECAR_AUTH_TOKEN`, so it is re-committed through
+/// a never-started scratch container with the same env reset.
+async fn commit_snapshot_clone_image(
+    builder: &DockerBuilder,
+    source: &SandboxRecord,
+    snapshot_image: &str,
+) -> Result<String> {
+    let snapshot_env = docker_timeout(
+        "inspect_image",
+        builder.client().inspect_image(snapshot_image),
+    )
+    .await?
+    .config
+    .and_then(|c| c.env)
+    .unwrap_or_default();
+    let mut scratch =
+        Container::new(builder.client(), snapshot_image.to_string()).with_name(format!(
+            "sidecar-{}-clone-{}",
+            source.id,
+            uuid::Uuid::new_v4().simple()
+        ));
+    docker_timeout("create_container", scratch.create()).await?;
+    let scratch_id = scratch
+        .id()
+        .ok_or_else(|| SandboxError::Docker("clone scratch container missing id".into()))?
+        .to_string();
+    let committed = commit_scrubbed(builder, source, &scratch_id, &snapshot_env).await;
+    let removed = scratch.remove(Some(RemoveContainerOptions {
+        force: true,
+        ..Default::default()
+    }));
+    if let Err(err) = docker_timeout("remove_container", removed).await {
+        tracing::warn!(
+            container_id = %scratch_id,
+            "failed to remove clone scratch container: {err}"
+        );
+    }
+    committed
+}
+
+/// Commit `container_id`, whose env is `container_env`, as a clone image of
+/// `source` with every variable the source's base image does not set reset.
+async fn commit_scrubbed(
+    builder: &DockerBuilder,
+    source: &SandboxRecord,
+    container_id: &str,
+    container_env: &[String],
+) -> Result<String> {
+    use docktopus::bollard::image::CommitContainerOptions;
+    let base_env = docker_timeout(
+        "inspect_image",
+        builder
+            .client()
+            .inspect_image(&image_reference_with_tag(&source.original_image)),
+    )
+    .await?
+    .config
+    .and_then(|c| c.env)
+    .unwrap_or_default();
+    let changes = clone_env_changes(container_env, &base_env);
+
+    let tag = uuid::Uuid::new_v4().simple().to_string();
+    let repo = format!("{CLONE_REPO_PREFIX}{}", source.id);
+    let options = CommitContainerOptions {
+        container: container_id.to_string(),
+        repo: repo.clone(),
+        tag: tag.clone(),
+        comment: format!("Clone of sandbox {}", source.id),
+        pause: true,
+        changes: (!changes.is_empty()).then(|| changes.join("\n")),
+        ..Default::default()
+    };
+    docker_timeout(
+        "commit_container",
+        builder
+            .client()
+            .commit_container(options, BollardConfig::<String>::default()),
+    )
+    .await?;
+    Ok(format!("{repo}:{tag}"))
+}
+
+/// Repository prefix of clone images (see [`commit_clone_image`]).
+pub(crate) const CLONE_REPO_PREFIX: &str = "sandbox-clone/";
+
+/// Clone images older than this with no record pointing at them are
+/// removed by [`gc_clone_images`]. Leaves room for a clone in progress.
+const ORPHAN_CLONE_IMAGE_SECS: i64 = 3600;
+
+/// Dockerfile `ENV` instructions resetting every `KEY=VALUE` of
+/// `container_env` that `base_env` does not contain verbatim: back to the
+/// base image's value, or to empty when the base image does not set it.
+/// Docker cannot unset a variable on commit, so the key stays but its value
+/// does not. Values are quoted with `\`, `"` and `$` escaped.
+pub(crate) fn clone_env_changes(container_env: &[String], base_env: &[String]) -> Vec<String> {
+    let quote = |value: &str| {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('$', "\\$");
+        format!("\"{escaped}\"")
+    };
+    container_env
+        .iter()
+        .filter(|entry| !base_env.contains(entry))
+        .filter_map(|entry| entry.split_once('=').map(|(key, _)| key))
+        .map(|key| {
+            let base = base_env
+                .iter()
+                .filter_map(|e| e.split_once('='))
+                .find(|(k, v)| *k == key && !v.contains('\n'))
+                .map_or("", |(_, v)| v);
+            format!("ENV {key}={}", quote(base))
+        })
+        .collect()
+}
+
+/// Remove the image `record` was cloned from, once its container is gone.
+/// Failures are logged; [`gc_clone_images`] retries orphans.
+pub(crate) async fn remove_clone_image(record: &SandboxRecord) {
+    let Some(image) = &record.clone_image else {
+        return;
+    };
+    if let Err(err) = remove_snapshot_image(image).await {
+        tracing::warn!(
+            sandbox_id = %record.id,
+            image = %image,
+            "failed to remove clone image: {err}"
+        );
+        return;
+    }
+    if let Ok(store) = sandboxes() {
+        let _ = store.update(&record.id, |r| r.clone_image = None);
+    }
+}
+
+/// Remove `sandbox-clone/*` images no sandbox record refers to: left behind
+/// by a clone that failed after the commit, or by an operator that stopped
+/// mid-clone. Docker refuses to remove one a container still uses.
+pub(crate) async fn gc_clone_images() -> Result<usize> {
+    use docktopus::bollard::image::ListImagesOptions;
+    let builder = docker_builder().await?;
+    let options = ListImagesOptions::<String> {
+        filters: HashMap::from([(
+            "reference".to_string(),
+            vec![format!("{CLONE_REPO_PREFIX}*")],
+        )]),
+        ..Default::default()
+    };
+    let images = docker_timeout("list_images", builder.client().list_images(Some(options))).await?;
+    let referenced: std::collections::HashSet<String> = sandboxes()?
+        .values()?
+        .into_iter()
+        .filter_map(|r| r.clone_image)
+        .collect();
+    let cutoff = crate::util::now_ts() as i64 - ORPHAN_CLONE_IMAGE_SECS;
+    let mut removed = 0;
+    for image in images {
+        if image.created > cutoff {
+            continue;
+        }
+        for tag in &image.repo_tags {
+            if !tag.starts_with(CLONE_REPO_PREFIX) || referenced.contains(tag) {
+                continue;
+            }
+            match remove_snapshot_image(tag).await {
+                Ok(()) => removed += 1,
+                Err(err) => {
+                    tracing::warn!(image = %tag, "gc: failed to remove clone image: {err}")
+                }
+            }
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,292 @@
+use super::*;
+
+/// Metadata key recording the web terminal preference. The terminal itself is
+/// served by the sidecar; the flag is stored so clients and recreates see the
+/// owner's choice.
+pub const WEB_TERMINAL_METADATA_KEY: &str = "web_terminal_enabled";
+
+/// Partial sandbox configuration update. `None` leaves a field unchanged.
+/// Values follow create semantics: a `0` lifetime takes the operator default
+/// and lifetimes are clamped to the operator maxima; a `0` resource means
+/// unlimited (clamped to the operator maximum when one is configured).
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct SandboxConfigUpdate {
+    pub idle_timeout_seconds: Option<u64>,
+    pub max_lifetime_seconds: Option<u64>,
+    pub cpu_cores: Option<u64>,
+    pub memory_mb: Option<u64>,
+    pub disk_gb: Option<u64>,
+    pub web_terminal_enabled: Option<bool>,
+}
+
+/// How a validated update reaches the sandbox.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigApplyMode {
+    /// Only stored fields change (lifetimes, web terminal flag, or any change
+    /// to a sandbox whose container was removed — applied on next resume).
+    RecordOnly,
+    /// CPU/memory limits changed on the existing container (`docker update`).
+    Live,
+    /// The container is recreated with the workspace carried over.
+    Recreate,
+}
+
+impl ConfigApplyMode {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RecordOnly => "record",
+            Self::Live => "live",
+            Self::Recreate => "recreate",
+        }
+    }
+}
+
+/// Validated diff between a sandbox record and a [`SandboxConfigUpdate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigUpdatePlan {
+    pub idle_timeout_seconds: u64,
+    pub max_lifetime_seconds: u64,
+    pub cpu_cores: u64,
+    pub memory_mb: u64,
+    pub disk_gb: u64,
+    pub web_terminal_enabled: Option<bool>,
+    /// Names of the fields that differ from the current record.
+    pub changed: Vec<&'static str>,
+    pub mode: ConfigApplyMode,
+}
+
+impl ConfigUpdatePlan {
+    pub(crate) fn apply_to(&self, record: &mut SandboxRecord) {
+        record.idle_timeout_seconds = self.idle_timeout_seconds;
+        record.max_lifetime_seconds = self.max_lifetime_seconds;
+        record.cpu_cores = self.cpu_cores;
+        record.memory_mb = self.memory_mb;
+        record.disk_gb = self.disk_gb;
+        if let Some(enabled) = self.web_terminal_enabled {
+            let mut metadata = serde_json::from_str::<Map<String, Value>>(&record.metadata_json)
+                .unwrap_or_default();
+            metadata.insert(WEB_TERMINAL_METADATA_KEY.into(), Value::Bool(enabled));
+            record.metadata_json = Value::Object(metadata).to_string();
+        }
+    }
+}
+
+/// Result of [`apply_sandbox_config_update`].
+#[derive(Clone, Debug)]
+pub struct ConfigUpdateOutcome {
+    pub record: SandboxRecord,
+    pub changed: Vec<&'static str>,
+    pub mode: ConfigApplyMode,
+}
+
+fn web_terminal_flag(record: &SandboxRecord) -> Option<bool> {
+    serde_json::from_str::<Value>(&record.metadata_json)
+        .ok()?
+        .get(WEB_TERMINAL_METADATA_KEY)?
+        .as_bool()
+}
+
+/// Whether the owner switched the web terminal off. Sandboxes that never set
+/// the flag keep the terminal available.
+pub fn web_terminal_disabled(record: &SandboxRecord) -> bool {
+    web_terminal_flag(record) == Some(false)
+}
+
+/// Validate `update` against `record` and decide how to apply it.
+///
+/// Rejects empty diffs, a max lifetime the sandbox has already outlived,
+/// resources above the operator maxima, and resource changes on TEE or
+/// Firecracker sandboxes (neither can be recreated in place).
+pub fn plan_config_update(
+    record: &SandboxRecord,
+    update: &SandboxConfigUpdate,
+    config: &SidecarRuntimeConfig,
+    now: u64,
+) -> Result<ConfigUpdatePlan> {
+    let mut changed = Vec::new();
+    let mut pick = |name: &'static str, requested: Option<u64>, current: u64| {
+        let value = requested.unwrap_or(current);
+        if value != current {
+            changed.push(name);
+        }
+        value
+    };
+    let idle_timeout_seconds = pick(
+        "idle_timeout_seconds",
+        update
+            .idle_timeout_seconds
+            .map(|v| config.effective_idle_timeout(v)),
+        record.idle_timeout_seconds,
+    );
+    let max_lifetime_seconds = pick(
+        "max_lifetime_seconds",
+        update
+            .max_lifetime_seconds
+            .map(|v| config.effective_max_lifetime(v)),
+        record.max_lifetime_seconds,
+    );
+    let cpu_cores = pick(
+        "cpu_cores",
+        update
+            .cpu_cores
+            .map(|v| enforce_resource_max(v, config.sandbox_max_cpu_cores, "cpu_cores"))
+            .transpose()?,
+        record.cpu_cores,
+    );
+    let memory_mb = pick(
+        "memory_mb",
+        update
+            .memory_mb
+            .map(|v| enforce_resource_max(v, config.sandbox_max_memory_mb, "memory_mb"))
+            .transpose()?,
+        record.memory_mb,
+    );
+    let disk_gb = pick(
+        "disk_gb",
+        update
+            .disk_gb
+            .map(|v| enforce_resource_max(v, config.sandbox_max_disk_gb, "disk_gb"))
+            .transpose()?,
+        record.disk_gb,
+    );
+    let web_terminal_enabled = update
+        .web_terminal_enabled
+        .filter(|enabled| web_terminal_flag(record) != Some(*enabled));
+    if web_terminal_enabled.is_some() {
+        changed.push("web_terminal_enabled");
+    }
+
+    if changed.is_empty() {
+        return Err(SandboxError::Validation(
+            "No configuration changes requested".into(),
+        ));
+    }
+    if max_lifetime_seconds > 0 && record.created_at.saturating_add(max_lifetime_seconds) <= now {
+        return Err(SandboxError::Validation(format!(
+            "max_lifetime_seconds {max_lifetime_seconds} has already elapsed for sandbox {}",
+            record.id
+        )));
+    }
+
+    let resources_changed = changed
+        .iter()
+        .any(|f| matches!(*f, "cpu_cores" | "memory_mb" | "disk_gb"));
+    if resources_changed && record.tee_deployment_id.is_some() {
+        return Err(SandboxError::Validation(
+            "Resource changes are not supported for TEE sandboxes".into(),
+        ));
+    }
+    if resources_changed && record_uses_firecracker(record) {
+        return Err(SandboxError::Validation(
+            "Resource changes are not supported for runtime_backend=firecracker".into(),
+        ));
+    }
+    let mode = if !resources_changed || record.container_removed_at.is_some() {
+        ConfigApplyMode::RecordOnly
+    } else if changed.contains(&"disk_gb")
+        || (changed.contains(&"cpu_cores") && cpu_cores == 0)
+        || (changed.contains(&"memory_mb") && memory_mb == 0)
+    {
+        // Docker cannot lift a limit or resize storage on an existing container.
+        ConfigApplyMode::Recreate
+    } else {
+        ConfigApplyMode::Live
+    };
+
+    Ok(ConfigUpdatePlan {
+        idle_timeout_seconds,
+        max_lifetime_seconds,
+        cpu_cores,
+        memory_mb,
+        disk_gb,
+        web_terminal_enabled,
+        changed,
+        mode,
+    })
+}
+
+/// Validate and apply a partial configuration update to a sandbox.
+///
+/// Lifetime and web terminal changes only touch the stored record (the
+/// reaper reads lifetimes from it). CPU/memory changes are applied to the
+/// running container with `docker update` and re-checked against the host
+/// budgets first. Disk changes and lifting a limit recreate the container
+/// with its workspace (rolling back on health failure). The stored record is
+/// updated only once the change has taken effect, so capacity accounting
+/// always matches the container. Callers must hold the lifecycle lock.
+pub async fn apply_sandbox_config_update(
+    sandbox_id: &str,
+    update: &SandboxConfigUpdate,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<ConfigUpdateOutcome> {
+    let record = get_sandbox_by_id(sandbox_id)?;
+    let config = SidecarRuntimeConfig::load();
+    let plan = plan_config_update(&record, update, config, crate::util::now_ts())?;
+    if plan.mode != ConfigApplyMode::RecordOnly && record.state == SandboxState::Running {
+        enforce_store_admission(config, plan.memory_mb, plan.cpu_cores, Some(&record.id))?;
+    }
+
+    let record = match plan.mode {
+        ConfigApplyMode::RecordOnly => {
+            sandboxes()?.update(&record.id, |r| plan.apply_to(r))?;
+            get_sandbox_by_id(&record.id)?
+        }
+        ConfigApplyMode::Live => {
+            update_container_limits(&record.container_id, plan.cpu_cores, plan.memory_mb).await?;
+            sandboxes()?.update(&record.id, |r| plan.apply_to(r))?;
+            get_sandbox_by_id(&record.id)?
+        }
+        ConfigApplyMode::Recreate => {
+            let image = if record.original_image.is_empty() {
+                current_sidecar_image()
+            } else {
+                record.original_image.clone()
+            };
+            let builder = docker_builder().await?;
+            let (recreated, _) = migrate_sidecar(
+                &builder,
+                &record,
+                &image,
+                &image,
+                "Config update",
+                |r| plan.apply_to(r),
+                tee,
+            )
+            .await?;
+            recreated
+        }
+    };
+    tracing::info!(
+        sandbox_id = %record.id,
+        changed = ?plan.changed,
+        mode = plan.mode.as_str(),
+        "sandbox configuration updated"
+    );
+    Ok(ConfigUpdateOutcome {
+        record,
+        changed: plan.changed,
+        mode: plan.mode,
+    })
+}
+
+async fn update_container_limits(container_id: &str, cpu_cores: u64, memory_mb: u64) -> Result<()> {
+    use docktopus::bollard::container::UpdateContainerOptions;
+    let memory = (memory_mb as i64) * 1024 * 1024;
+    let options = UpdateContainerOptions::<String> {
+        nano_cpus: (cpu_cores > 0).then(|| (cpu_cores as i64) * 1_000_000_000),
+        memory: (memory_mb > 0).then_some(memory),
+        // Docker's default swap allowance is 2x memory; keep it in step so a
+        // raised limit is not rejected by the old swap ceiling.
+        memory_swap: (memory_mb > 0).then_some(memory * 2),
+        ..Default::default()
+    };
+    let builder = docker_builder().await?;
+    let client = builder.client();
+    docker_timeout(
+        "update_container",
+        client.update_container(container_id, options),
+    )
+    .await
+}
@@ -1,17 +1,25 @@
 use super::*;
+use crate::webhooks::WebhookEvent;
 
 /// Create a new sandbox container.
 ///
 /// `token_override`: when `Some`, uses the given token instead of generating
 /// a new one. Used by `recreate_sidecar_with_env` to preserve the original
 /// token across container re-creation.
+///
+/// Refused while the operator is draining (see [`crate::drain`]).
 pub async fn create_sidecar(
     request: &CreateSandboxParams,
     tee: Option<&dyn crate::tee::TeeBackend>,
 ) -> Result<(SandboxRecord, Option<crate::tee::AttestationReport>)> {
+    crate::drain::ensure_accepting_provisions()?;
     create_sidecar_with_token(request, tee, None, None)
         .await
-        .map(|(record, attestation, _timings)| (record, attestation))
+        .map(|(record, attestation, _timings)| {
+            crate::webhooks::emit_sandbox_event(&record, WebhookEvent::SandboxCreated);
+            record_lifecycle_change(&record, LifecycleEventKind::Provisioned, None);
+            (record, attestation)
+        })
 }
 
 /// [`create_sidecar`] plus the measured per-stage [`CreateTimings`] breakdown.
@@ -27,7 +35,11 @@ pub async fn create_sidecar_timed(
     Option<crate::tee::AttestationReport>,
     CreateTimings,
 )> {
-    create_sidecar_with_token(request, tee, None, None).await
+    crate::drain::ensure_accepting_provisions()?;
+    let created = create_sidecar_with_token(request, tee, None, None).await?;
+    crate::webhooks::emit_sandbox_event(&created.0, WebhookEvent::SandboxCreated);
+    record_lifecycle_change(&created.0, LifecycleEventKind::Provisioned, None);
+    Ok(created)
 }
 
 /// Internal: create sidecar with optional token override.
@@ -48,15 +60,24 @@ pub(crate) async fn create_sidecar_with_token(
     let _creation_permit = acquire_creation_permit().await;
     let permit_wait = requested.elapsed();
     // Resource admission runs under the permit and before backend dispatch:
-    // per-sandbox maxima (reject over-max, clamp unlimited-to-max) and the
-    // host memory budget apply identically to Docker, Firecracker, and TEE.
+    // per-sandbox maxima (reject over-max, clamp unlimited-to-max), the
+    // host memory budget and the owner's quota apply identically to Docker,
+    // Firecracker, and TEE.
     let admission_span = std::time::Instant::now();
     let admitted =
         admit_sandbox_resources(SidecarRuntimeConfig::load(), request, sandbox_id_override)?;
+    crate::owner_quota::check_owner_create(
+        &admitted.owner,
+        1,
+        admitted.cpu_cores,
+        admitted.memory_mb,
+        sandbox_id_override,
+    )?;
     let admission = admission_span.elapsed();
     let request = &admitted;
+    crate::secret_provisioning::validate_env_json_secret_refs(&request.env_json)?;
     let backend = resolve_runtime_backend(request)?;
-    let (record, attestation, mut timings) = match backend {
+    let (mut record, attestation, mut timings) = match backend {
         RuntimeBackend::Tee => {
             let backend = tee.ok_or_else(|| {
                 SandboxError::Validation(
@@ -79,6 +100,9 @@ pub(crate) async fn create_sidecar_with_token(
             (record, None, timings)
         }
     };
+    let negotiation = std::time::Instant::now();
+    negotiate_record_sidecar_api(&mut record, tee).await?;
+    timings.api_negotiation = Some(negotiation.elapsed());
     timings.permit_wait = Some(permit_wait);
     timings.admission = Some(admission);
     timings.total = requested.elapsed();
@@ -86,6 +110,68 @@ pub(crate) async fn create_sidecar_with_token(
     Ok((record, attestation, timings))
 }
 
+/// Default wait for a new sidecar to answer `/health` during API negotiation.
+/// Matches the 30s window callers use for their own health wait.
+const DEFAULT_SIDECAR_API_NEGOTIATION_TIMEOUT_SECS: u64 = 30;
+
+/// How long provision waits for the sidecar API handshake, from
+/// `SIDECAR_API_NEGOTIATION_TIMEOUT_SECS`. `0` skips negotiation; the
+/// sandbox is then treated as API 1.
+fn sidecar_api_negotiation_timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_SIDECAR_API_NEGOTIATION_TIMEOUT_SECS),
+    )
+}
+
+/// Read the new sidecar's API version and persist it on `record`. A sidecar
+/// on an unsupported API is torn down and the create fails with the
+/// version mismatch; one that doesn't answer in time keeps `sidecar_api:
+/// None` (API 1) so a slow boot doesn't fail the provision.
+async fn negotiate_record_sidecar_api(
+    record: &mut SandboxRecord,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<()> {
+    let timeout = sidecar_api_negotiation_timeout();
+    if timeout.is_zero() {
+        return Ok(());
+    }
+    match crate::sidecar_compat::negotiate_sidecar_api(&record.sidecar_url, timeout).await {
+        Ok(Some(info)) => {
+            sandboxes()?.update(&record.id, |r| r.sidecar_api = Some(info.clone()))?;
+            record.sidecar_api = Some(info);
+            Ok(())
+        }
+        Ok(None) => {
+            tracing::warn!(
+                sandbox_id = %record.id,
+                timeout_secs = timeout.as_secs(),
+                "sidecar did not answer /health during API negotiation; assuming API 1"
+            );
+            Ok(())
+        }
+        Err(err) => {
+            tracing::error!(
+                sandbox_id = %record.id,
+                error = %err,
+                "sidecar API negotiation failed"
+            );
+            if let Err(cleanup) = delete_sidecar(record, tee).await {
+                tracing::warn!(
+                    sandbox_id = %record.id,
+                    error = %cleanup,
+                    "failed to remove sandbox with unsupported sidecar API"
+                );
+            }
+            // `delete_sidecar` only tears down the runtime side.
+            let _ = sandboxes()?.remove(&record.id);
+            Err(err)
+        }
+    }
+}
+
 pub(crate) fn validate_requested_tee_backend(
     request: &CreateSandboxParams,
     backend: &dyn crate::tee::TeeBackend,
@@ -143,6 +229,8 @@ pub(crate) async fn create_sidecar_tee(
     let extra_ports = parse_extra_ports(&request.metadata_json, &request.port_mappings);
     let mut tee_request = request.clone();
     tee_request.port_mappings = extra_ports;
+    tee_request.env_json =
+        crate::secret_provisioning::resolve_secret_refs(&sandbox_id, &request.env_json).await?;
 
     let tee_params = crate::tee::TeeDeployParams::from_sandbox_params(
         &sandbox_id,
@@ -196,6 +284,11 @@ pub(crate) async fn create_sidecar_tee(
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: request.capabilities_json.clone(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     };
 
     let mut sealed = record.clone();
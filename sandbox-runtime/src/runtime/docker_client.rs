@@ -144,7 +144,8 @@ pub(crate) async fn cleanup_orphaned_container(builder: &DockerBuilder, containe
 }
 
 /// Ensure the sidecar image is available locally. Pulls once on first call
-/// if `SIDECAR_PULL_IMAGE` is true. Subsequent calls are no-ops.
+/// if `SIDECAR_PULL_IMAGE` is true, reporting progress to the provision of
+/// the current job. Subsequent calls are no-ops.
 ///
 /// Image pulls are retried up to 2 times with 1-second backoff to handle
 /// transient registry errors.
@@ -154,7 +155,7 @@ pub(crate) async fn ensure_image_pulled(builder: &DockerBuilder, image: &str) ->
             let config = SidecarRuntimeConfig::load();
             if config.pull_image {
                 retry_docker("pull_image", 2, 1000, || {
-                    docker_timeout("pull_image", builder.pull_image(image, None))
+                    docker_timeout("pull_image", pull_image_with_progress(builder, image))
                 })
                 .await?;
             }
@@ -168,6 +168,20 @@ async fn finish_warm_claim_docker(
     } else {
         request.image.clone()
     };
+    // The pooled container already runs, so a failed lookup only loses the
+    // digest; the pool's own pull went through the same platform selection.
+    let image_digest = match docker_builder().await {
+        Ok(builder) => {
+            match resolve_local_image(&builder, &original_image, &config.platform_arch).await {
+                Ok(digest) => digest,
+                Err(err) => {
+                    tracing::warn!(sandbox_id, "Warm claim image lookup failed: {err}");
+                    None
+                }
+            }
+        }
+        Err(_) => None,
+    };
 
     let metadata = parse_json_object(&request.metadata_json, "metadata_json")?;
     let snapshot_destination = metadata
@@ -219,6 +233,11 @@ async fn finish_warm_claim_docker(
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: request.capabilities_json.clone(),
+        sidecar_api: None,
+        image_digest,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     };
 
     let insert = async {
@@ -282,6 +301,8 @@ pub(crate) async fn cold_create_sidecar_docker(
 
     let stage = std::time::Instant::now();
     ensure_image_pulled(&builder, &effective_image).await?;
+    let image_digest =
+        resolve_local_image(&builder, &effective_image, &config.platform_arch).await?;
     timings.image_pull = Some(stage.elapsed());
     let original_image = effective_image.clone();
 
@@ -291,7 +312,11 @@ pub(crate) async fn cold_create_sidecar_docker(
     };
     let container_name = format!("sidecar-{sandbox_id}");
 
-    let effective_env = merge_env_json(&request.env_json, &request.user_env_json);
+    let effective_env = crate::secret_provisioning::resolve_secret_refs(
+        &sandbox_id,
+        &merge_env_json(&request.env_json, &request.user_env_json),
+    )
+    .await?;
     let env_vars = build_env_vars(
         &effective_env,
         &token,
@@ -319,7 +344,7 @@ pub(crate) async fn cold_create_sidecar_docker(
     // Parse extra ports from metadata_json (e.g. {"ports": [3000, 8080]}).
     let extra_ports = parse_extra_ports(&request.metadata_json, &request.port_mappings);
 
-    let override_config = build_docker_config(
+    let mut override_config = build_docker_config(
         config,
         request.ssh_enabled,
         request.cpu_cores,
@@ -327,6 +352,7 @@ pub(crate) async fn cold_create_sidecar_docker(
         labels,
         &extra_ports,
     );
+    crate::disk_quota::apply_storage_quota(&mut override_config, request.disk_gb);
 
     let mut container = Container::new(builder.client(), effective_image)
         .with_name(container_name)
@@ -427,6 +453,11 @@ pub(crate) async fn cold_create_sidecar_docker(
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: request.capabilities_json.clone(),
+            sidecar_api: None,
+            image_digest,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         let stage = std::time::Instant::now();
@@ -95,32 +95,17 @@ pub fn workflow_runtime_credentials_available(env_json: &str) -> Result<bool> {
         return Ok(false);
     };
 
-    let has_native_provider_key = map
-        .get("ANTHROPIC_API_KEY")
-        .and_then(Value::as_str)
-        .map(str::trim)
-        .is_some_and(|value| !value.is_empty())
-        || map
-            .get("ZAI_API_KEY")
-            .and_then(Value::as_str)
-            .map(str::trim)
-            .is_some_and(|value| !value.is_empty());
-
-    let has_explicit_opencode = map
-        .get("OPENCODE_MODEL_PROVIDER")
-        .and_then(Value::as_str)
-        .map(str::trim)
-        .is_some_and(|value| !value.is_empty())
-        && map
-            .get("OPENCODE_MODEL_NAME")
-            .and_then(Value::as_str)
-            .map(str::trim)
-            .is_some_and(|value| !value.is_empty())
-        && map
-            .get("OPENCODE_MODEL_API_KEY")
-            .and_then(Value::as_str)
-            .map(str::trim)
-            .is_some_and(|value| !value.is_empty());
+    // A secret manager reference counts: it is resolved at container creation.
+    let present = |key: &str| {
+        map.get(key).is_some_and(|value| {
+            value.as_str().is_some_and(|v| !v.trim().is_empty())
+                || crate::secret_provisioning::secret_ref_uri(value).is_some()
+        })
+    };
+    let has_native_provider_key = present("ANTHROPIC_API_KEY") || present("ZAI_API_KEY");
+    let has_explicit_opencode = present("OPENCODE_MODEL_PROVIDER")
+        && present("OPENCODE_MODEL_NAME")
+        && present("OPENCODE_MODEL_API_KEY");
 
     Ok(has_native_provider_key || has_explicit_opencode)
 }
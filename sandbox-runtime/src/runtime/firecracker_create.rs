@@ -44,7 +44,11 @@ pub(crate) async fn create_sidecar_firecracker(
         _ => HashMap::new(),
     };
 
-    let effective_env = merge_env_json(&request.env_json, &request.user_env_json);
+    let effective_env = crate::secret_provisioning::resolve_secret_refs(
+        &sandbox_id,
+        &merge_env_json(&request.env_json, &request.user_env_json),
+    )
+    .await?;
     let mut env = HashMap::new();
     env.insert(
         "SIDECAR_PORT".to_string(),
@@ -158,6 +162,11 @@ pub(crate) async fn create_sidecar_firecracker(
         ssh_login_user: None,
         ssh_authorized_keys: Vec::new(),
         capabilities_json: request.capabilities_json.clone(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
     };
 
     let mut sealed = record.clone();
@@ -0,0 +1,141 @@
+//! Per-sandbox lifecycle history.
+//!
+//! Each [`SandboxRecord`] keeps an ordered list of what happened to it —
+//! provisioned, stopped, resumed, snapshotted, secrets injected, upgraded,
+//! reaped — with a timestamp and the actor, so "what happened to my
+//! sandbox" can be answered from the detail endpoint instead of operator
+//! logs. The list survives container recreation and is capped at
+//! [`MAX_LIFECYCLE_EVENTS`] entries, oldest dropped first.
+//!
+//! The actor is the sandbox owner unless the caller runs inside
+//! [`with_lifecycle_actor`]; the reaper, crash monitor and fleet upgrades
+//! record [`OPERATOR_ACTOR`](crate::audit_log::OPERATOR_ACTOR).
+
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// Lifecycle entries kept per sandbox.
+pub const MAX_LIFECYCLE_EVENTS: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleEventKind {
+    Provisioned,
+    Stopped,
+    Resumed,
+    Snapshotted,
+    SecretsInjected,
+    SecretsWiped,
+    /// The sidecar was recreated on a new image.
+    Upgraded,
+    /// Stopped by the reaper (idle timeout or operator drain).
+    Reaped,
+}
+
+/// One entry in a sandbox's `lifecycle_events`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleEvent {
+    pub at: u64,
+    pub kind: LifecycleEventKind,
+    /// Caller address, or [`OPERATOR_ACTOR`](crate::audit_log::OPERATOR_ACTOR).
+    pub actor: String,
+    /// Extra context, e.g. the snapshot destination or the target image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+tokio::task_local! {
+    static LIFECYCLE_ACTOR: String;
+}
+
+/// Run `fut` with lifecycle events attributed to `actor`.
+pub async fn with_lifecycle_actor<F: Future>(actor: &str, fut: F) -> F::Output {
+    LIFECYCLE_ACTOR.scope(actor.to_string(), fut).await
+}
+
+/// The actor of a lifecycle change on the current task, `owner` unless
+/// overridden by [`with_lifecycle_actor`].
+pub(crate) fn lifecycle_actor(owner: &str) -> String {
+    LIFECYCLE_ACTOR
+        .try_with(Clone::clone)
+        .unwrap_or_else(|_| owner.to_string())
+}
+
+pub(crate) fn push_lifecycle_event(events: &mut Vec<LifecycleEvent>, event: LifecycleEvent) {
+    events.push(event);
+    if events.len() > MAX_LIFECYCLE_EVENTS {
+        let excess = events.len() - MAX_LIFECYCLE_EVENTS;
+        events.drain(..excess);
+    }
+}
+
+/// Append an event to `sandbox_id`'s history. Best-effort: a store failure
+/// is logged, never surfaced to the lifecycle call that triggered it.
+pub fn record_lifecycle_event(
+    sandbox_id: &str,
+    kind: LifecycleEventKind,
+    actor: &str,
+    detail: Option<String>,
+) {
+    let event = LifecycleEvent {
+        at: crate::util::now_ts(),
+        kind,
+        actor: actor.to_string(),
+        detail,
+    };
+    let result = sandboxes().and_then(|store| {
+        store.update(sandbox_id, |r| {
+            push_lifecycle_event(&mut r.lifecycle_events, event.clone())
+        })
+    });
+    if let Err(e) = result {
+        tracing::warn!(sandbox_id, "Failed to record lifecycle event: {e}");
+    }
+}
+
+/// [`record_lifecycle_event`] with the actor taken from the current task.
+pub(crate) fn record_lifecycle_change(
+    record: &SandboxRecord,
+    kind: LifecycleEventKind,
+    detail: Option<String>,
+) {
+    record_lifecycle_event(&record.id, kind, &lifecycle_actor(&record.owner), detail);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(at: u64) -> LifecycleEvent {
+        LifecycleEvent {
+            at,
+            kind: LifecycleEventKind::Stopped,
+            actor: "0xowner".into(),
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn history_keeps_the_newest_events() {
+        let mut events = Vec::new();
+        for at in 0..(MAX_LIFECYCLE_EVENTS as u64 + 5) {
+            push_lifecycle_event(&mut events, event(at));
+        }
+        assert_eq!(events.len(), MAX_LIFECYCLE_EVENTS);
+        assert_eq!(events.first().unwrap().at, 5);
+        assert_eq!(events.last().unwrap().at, MAX_LIFECYCLE_EVENTS as u64 + 4);
+    }
+
+    #[tokio::test]
+    async fn actor_defaults_to_owner_and_can_be_overridden() {
+        assert_eq!(lifecycle_actor("0xowner"), "0xowner");
+        let actor = with_lifecycle_actor(crate::audit_log::OPERATOR_ACTOR, async {
+            lifecycle_actor("0xowner")
+        })
+        .await;
+        assert_eq!(actor, "operator");
+    }
+}
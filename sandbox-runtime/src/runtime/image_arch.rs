@@ -0,0 +1,117 @@
+//! Host-architecture-aware sidecar image selection.
+//!
+//! The sidecar image is published as a multi-arch manifest, but an operator
+//! on an arm64 host (or a mixed fleet) may need a different image per
+//! architecture. The host architecture comes from `SIDECAR_PLATFORM_ARCH`
+//! (for a remote `DOCKER_HOST` on another arch) or the operator binary's
+//! own; `SIDECAR_IMAGE_<ARCH>` (e.g. `SIDECAR_IMAGE_ARM64`) overrides
+//! `SIDECAR_IMAGE` on that architecture. Pulls request the `linux/<arch>`
+//! platform so Docker resolves the matching manifest entry, and after the
+//! pull the local image is checked against the host arch and its registry
+//! digest recorded on the sandbox.
+
+use super::*;
+
+/// Docker's name for `arch` (`x86_64` → `amd64`, `aarch64` → `arm64`);
+/// other values pass through lowercased.
+pub fn normalize_arch(arch: &str) -> String {
+    match arch.trim().to_ascii_lowercase().as_str() {
+        "x86_64" | "x86-64" | "amd64" => "amd64".to_string(),
+        "aarch64" | "arm64" | "arm64v8" => "arm64".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// The architecture sandboxes run on: `SIDECAR_PLATFORM_ARCH` when set,
+/// otherwise the operator host's.
+pub fn host_arch() -> String {
+    env::var("SIDECAR_PLATFORM_ARCH")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| normalize_arch(&v))
+        .unwrap_or_else(|| normalize_arch(std::env::consts::ARCH))
+}
+
+/// `SIDECAR_IMAGE_<ARCH>` when set for `arch`, else `default_image`.
+pub fn image_for_arch(default_image: &str, arch: &str) -> String {
+    env::var(format!("SIDECAR_IMAGE_{}", arch.to_ascii_uppercase()))
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| default_image.to_string())
+}
+
+/// The platform string Docker pulls by (`linux/arm64`).
+pub(crate) fn platform_for_arch(arch: &str) -> String {
+    format!("linux/{arch}")
+}
+
+/// Check `image` (already local) runs on `arch` and return its registry
+/// digest. `Ok(None)` for a locally built image without one.
+pub(crate) async fn resolve_local_image(
+    builder: &DockerBuilder,
+    image: &str,
+    arch: &str,
+) -> Result<Option<String>> {
+    let reference = image_reference_with_tag(image);
+    let inspect =
+        docker_timeout("inspect_image", builder.client().inspect_image(&reference)).await?;
+    check_image_arch(&reference, inspect.architecture.as_deref(), arch)?;
+    crate::image_cache::resolve_image_digest(&reference, &inspect.repo_digests.unwrap_or_default())
+        .map_err(SandboxError::Docker)
+}
+
+pub(crate) fn check_image_arch(
+    reference: &str,
+    image_arch: Option<&str>,
+    arch: &str,
+) -> Result<()> {
+    match image_arch.map(normalize_arch) {
+        Some(image_arch) if image_arch != arch => Err(SandboxError::Validation(format!(
+            "Image {reference} is built for {image_arch} but sandboxes run on {arch}; \
+             publish a multi-arch manifest or set SIDECAR_IMAGE_{}",
+            arch.to_ascii_uppercase()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arch_names_normalize_to_docker_names() {
+        assert_eq!(normalize_arch("x86_64"), "amd64");
+        assert_eq!(normalize_arch("aarch64"), "arm64");
+        assert_eq!(normalize_arch(" ARM64 "), "arm64");
+        assert_eq!(normalize_arch("riscv64"), "riscv64");
+        assert_eq!(platform_for_arch("arm64"), "linux/arm64");
+    }
+
+    #[test]
+    fn image_arch_mismatch_names_the_override() {
+        assert!(check_image_arch("sidecar:latest", Some("aarch64"), "arm64").is_ok());
+        assert!(check_image_arch("sidecar:latest", None, "arm64").is_ok());
+        let err = check_image_arch("sidecar:latest", Some("amd64"), "arm64")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("built for amd64"), "{err}");
+        assert!(err.contains("SIDECAR_IMAGE_ARM64"), "{err}");
+    }
+
+    #[test]
+    fn per_arch_image_overrides_default() {
+        // A made-up arch keeps this independent of the host's environment.
+        unsafe { std::env::set_var("SIDECAR_IMAGE_TESTARCH", "sidecar:testarch") };
+        assert_eq!(
+            image_for_arch("sidecar:latest", "testarch"),
+            "sidecar:testarch"
+        );
+        assert_eq!(
+            image_for_arch("sidecar:latest", "otherarch"),
+            "sidecar:latest"
+        );
+        unsafe { std::env::remove_var("SIDECAR_IMAGE_TESTARCH") };
+    }
+}
@@ -0,0 +1,128 @@
+//! Image pull with per-layer progress.
+//!
+//! `DockerBuilder::pull_image` drains Docker's progress stream without
+//! looking at it, so a multi-GB sidecar image sat at `image_pull` with no
+//! sign of life. This pulls through `create_image` instead and, when the pull
+//! runs inside a provision job, reports layer and byte counts to
+//! [`crate::provision_progress::update_image_pull_progress`] (at most once a
+//! second, plus on every finished layer).
+
+use super::*;
+use crate::provision_progress::{self, ImagePullProgress};
+use docktopus::bollard::image::CreateImageOptions;
+use docktopus::bollard::models::CreateImageInfo;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+const LAYER_STATUSES: &[&str] = &[
+    "Pulling fs layer",
+    "Waiting",
+    "Downloading",
+    "Verifying Checksum",
+    "Download complete",
+    "Extracting",
+    "Pull complete",
+    "Already exists",
+];
+
+/// `image` with an explicit tag: without one Docker pulls every tag.
+pub(crate) fn image_reference_with_tag(image: &str) -> String {
+    let name = image.rsplit('/').next().unwrap_or(image);
+    if image.contains('@') || name.contains(':') {
+        image.to_string()
+    } else {
+        format!("{image}:latest")
+    }
+}
+
+#[derive(Default)]
+struct LayerProgress {
+    current: u64,
+    total: u64,
+    done: bool,
+}
+
+/// Per-layer state folded from Docker's pull status messages.
+#[derive(Default)]
+pub(crate) struct PullTracker {
+    layers: HashMap<String, LayerProgress>,
+}
+
+impl PullTracker {
+    /// Fold one status message in. Returns `true` when a layer finished.
+    pub(crate) fn apply(&mut self, info: &CreateImageInfo) -> bool {
+        let (Some(id), Some(status)) = (info.id.as_deref(), info.status.as_deref()) else {
+            return false;
+        };
+        // Other messages carry the tag or digest as `id`, not a layer.
+        if !LAYER_STATUSES.contains(&status) && !self.layers.contains_key(id) {
+            return false;
+        }
+        let layer = self.layers.entry(id.to_string()).or_default();
+        if status == "Downloading"
+            && let Some(detail) = &info.progress_detail
+        {
+            layer.current = detail.current.unwrap_or(0).max(0) as u64;
+            layer.total = detail.total.unwrap_or(0).max(0) as u64;
+        }
+        if status == "Download complete" {
+            layer.current = layer.total;
+        }
+        let finished = matches!(status, "Pull complete" | "Already exists") && !layer.done;
+        if finished {
+            layer.done = true;
+            layer.current = layer.total;
+        }
+        finished
+    }
+
+    pub(crate) fn progress(&self) -> ImagePullProgress {
+        let mut progress = ImagePullProgress::default();
+        for layer in self.layers.values() {
+            progress.layers_total += 1;
+            progress.layers_done += u32::from(layer.done);
+            progress.bytes_total += layer.total;
+            progress.bytes_done += layer.current.min(layer.total);
+        }
+        progress
+    }
+}
+
+/// Pull `image` for the sandbox platform (see [`super::image_arch`]),
+/// reporting progress to the provision of the current job (if any).
+pub(crate) async fn pull_image_with_progress(builder: &DockerBuilder, image: &str) -> Result<()> {
+    let reference = image_reference_with_tag(image);
+    let platform = platform_for_arch(&SidecarRuntimeConfig::load().platform_arch);
+    let call_id = crate::job_trace::current().map(|t| t.call_id);
+    let mut stream = Box::pin(builder.client().create_image(
+        Some(CreateImageOptions {
+            from_image: reference.as_str(),
+            platform: platform.as_str(),
+            ..Default::default()
+        }),
+        None,
+        None,
+    ));
+    let mut tracker = PullTracker::default();
+    let mut last_report = std::time::Instant::now();
+    while let Some(info) = stream.next().await {
+        let info =
+            info.map_err(|e| SandboxError::Docker(format!("Failed to pull {reference}: {e}")))?;
+        if let Some(err) = info.error.as_deref() {
+            return Err(SandboxError::Docker(format!(
+                "Failed to pull {reference}: {err}"
+            )));
+        }
+        let finished = tracker.apply(&info);
+        if let Some(call_id) = call_id
+            && (finished || last_report.elapsed() >= REPORT_INTERVAL)
+        {
+            let _ = provision_progress::update_image_pull_progress(call_id, tracker.progress());
+            last_report = std::time::Instant::now();
+        }
+    }
+    if let Some(call_id) = call_id {
+        let _ = provision_progress::update_image_pull_progress(call_id, tracker.progress());
+    }
+    Ok(())
+}
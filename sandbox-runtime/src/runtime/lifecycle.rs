@@ -1,10 +1,55 @@
 use super::*;
+use crate::webhooks::WebhookEvent;
+
+/// Striped per-sandbox mutex preventing concurrent lifecycle mutations
+/// (stop, resume, delete, recreate) on the same sandbox. Without this,
+/// concurrent stop+resume or double-inject can create orphaned containers
+/// or divergent state.
+///
+/// Uses DashMap<String, Arc<tokio::sync::Mutex<()>>> so that acquiring a
+/// lock for sandbox A does not block operations on sandbox B.
+static LIFECYCLE_LOCKS: once_cell::sync::Lazy<
+    dashmap::DashMap<String, Arc<tokio::sync::Mutex<()>>>,
+> = once_cell::sync::Lazy::new(dashmap::DashMap::new);
+
+/// Acquire the per-sandbox lifecycle lock. The returned guard must be held
+/// for the entire duration of the lifecycle operation (state check → Docker
+/// call → store write). Dropping the guard releases the lock.
+pub async fn acquire_lifecycle_lock(sandbox_id: &str) -> tokio::sync::OwnedMutexGuard<()> {
+    let mutex = LIFECYCLE_LOCKS
+        .entry(sandbox_id.to_string())
+        .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(())))
+        .clone();
+    mutex.lock_owned().await
+}
 
 /// Stop a running sandbox container, updating its state to `Stopped`.
 ///
 /// For TEE-managed sandboxes, delegates to the TEE backend's `stop()` method.
 /// For standard Docker sandboxes, stops via the Docker API directly.
 pub async fn stop_sidecar(record: &SandboxRecord) -> Result<()> {
+    stop_sidecar_inner(record).await?;
+    crate::webhooks::emit_sandbox_event(record, WebhookEvent::SandboxStopped);
+    record_lifecycle_change(record, LifecycleEventKind::Stopped, None);
+    Ok(())
+}
+
+/// [`stop_sidecar`] on behalf of the reaper: recorded in the sandbox's
+/// history as reaped by the operator, with `reason` (`idle_timeout`,
+/// `drain`) as the detail.
+pub(crate) async fn reap_sidecar(record: &SandboxRecord, reason: &str) -> Result<()> {
+    stop_sidecar_inner(record).await?;
+    crate::webhooks::emit_sandbox_event(record, WebhookEvent::SandboxStopped);
+    record_lifecycle_event(
+        &record.id,
+        LifecycleEventKind::Reaped,
+        crate::audit_log::OPERATOR_ACTOR,
+        Some(reason.to_string()),
+    );
+    Ok(())
+}
+
+async fn stop_sidecar_inner(record: &SandboxRecord) -> Result<()> {
     if record.state == SandboxState::Stopped {
         return Err(SandboxError::Validation(
             "Sandbox is already stopped".into(),
@@ -135,7 +180,16 @@ pub(crate) async fn stop_started_container(
 }
 
 /// Resume a stopped sandbox, restoring from container, snapshot image, or S3 as available.
+/// Refused while the operator is draining (see [`crate::drain`]).
 pub async fn resume_sidecar(record: &SandboxRecord) -> Result<()> {
+    crate::drain::ensure_accepting_provisions()?;
+    resume_sidecar_inner(record).await?;
+    crate::webhooks::emit_sandbox_event(record, WebhookEvent::SandboxResumed);
+    record_lifecycle_change(record, LifecycleEventKind::Resumed, None);
+    Ok(())
+}
+
+async fn resume_sidecar_inner(record: &SandboxRecord) -> Result<()> {
     if record.state == SandboxState::Running {
         return Err(SandboxError::Validation(
             "Sandbox is already running".into(),
@@ -292,6 +346,9 @@ pub async fn delete_sidecar(
         duration_ms = start.elapsed().as_millis() as u64,
         "sandbox delete finished"
     );
+    if result.is_ok() {
+        crate::webhooks::emit_sandbox_event(record, WebhookEvent::SandboxDeleted);
+    }
     result
 }
 
@@ -339,6 +396,7 @@ pub(crate) async fn delete_sidecar_docker(record: &SandboxRecord) -> Result<()>
         })),
     )
     .await?;
+    remove_clone_image(record).await;
 
     crate::metrics::metrics().record_sandbox_deleted(record.cpu_cores, record.memory_mb);
 
@@ -45,6 +45,24 @@ pub fn get_sandbox_by_url_opt(sidecar_url: &str) -> Option<SandboxRecord> {
     })
 }
 
+/// Agent identifier used when neither the request nor the sandbox names one.
+pub const DEFAULT_AGENT_IDENTIFIER: &str = "default";
+
+/// Agent to run for an `/agents/run` call: `requested` when set, else the
+/// `agent_identifier` configured on `record`, else
+/// [`DEFAULT_AGENT_IDENTIFIER`].
+pub fn resolve_agent_identifier(requested: &str, record: Option<&SandboxRecord>) -> String {
+    [
+        requested,
+        record.map_or("", |r| r.agent_identifier.as_str()),
+    ]
+    .into_iter()
+    .map(str::trim)
+    .find(|id| !id.is_empty())
+    .unwrap_or(DEFAULT_AGENT_IDENTIFIER)
+    .to_string()
+}
+
 /// Validate that `caller` owns the sandbox, returning the record on success.
 pub fn require_sandbox_owner(sandbox_id: &str, caller: &str) -> Result<SandboxRecord> {
     let record = get_sandbox_by_id(sandbox_id)?;
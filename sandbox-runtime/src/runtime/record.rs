@@ -0,0 +1,147 @@
+use super::*;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SandboxState {
+    #[default]
+    Running,
+    Stopped,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SandboxRecord {
+    pub id: String,
+    pub container_id: String,
+    pub sidecar_url: String,
+    pub sidecar_port: u16,
+    pub ssh_port: Option<u16>,
+    pub token: String,
+    pub created_at: u64,
+    #[serde(default)]
+    pub cpu_cores: u64,
+    #[serde(default)]
+    pub memory_mb: u64,
+    #[serde(default)]
+    pub state: SandboxState,
+    #[serde(default)]
+    pub idle_timeout_seconds: u64,
+    #[serde(default)]
+    pub max_lifetime_seconds: u64,
+    #[serde(default)]
+    pub last_activity_at: u64,
+    #[serde(default)]
+    pub stopped_at: Option<u64>,
+    #[serde(default)]
+    pub snapshot_image_id: Option<String>,
+    #[serde(default)]
+    pub snapshot_s3_url: Option<String>,
+    #[serde(default)]
+    pub container_removed_at: Option<u64>,
+    #[serde(default)]
+    pub image_removed_at: Option<u64>,
+    #[serde(default)]
+    pub original_image: String,
+    /// Base environment variables set at creation time (immutable).
+    #[serde(default, alias = "env_json")]
+    pub base_env_json: String,
+    /// User-injected secrets via two-phase provisioning (mutable).
+    #[serde(default)]
+    pub user_env_json: String,
+    #[serde(default)]
+    pub snapshot_destination: Option<String>,
+    /// Backend-specific deployment ID for TEE sandboxes (e.g. Phala app_id).
+    #[serde(default)]
+    pub tee_deployment_id: Option<String>,
+    /// Opaque backend metadata JSON for TEE sandboxes.
+    #[serde(default)]
+    pub tee_metadata_json: Option<String>,
+    /// Deploy-time attestation report serialized as JSON.
+    #[serde(default)]
+    pub tee_attestation_json: Option<String>,
+    // ── Creation params preserved for recreation ──────────────────────────
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub agent_identifier: String,
+    #[serde(default)]
+    pub metadata_json: String,
+    #[serde(default)]
+    pub disk_gb: u64,
+    #[serde(default)]
+    pub stack: String,
+    /// On-chain address of the caller who created this sandbox. Used for
+    /// ownership checks — only the owner may stop, resume, or delete a sandbox.
+    #[serde(default)]
+    pub owner: String,
+    #[serde(default)]
+    pub service_id: Option<u64>,
+    /// TEE configuration used to create this sandbox (preserved for recreation).
+    #[serde(default)]
+    pub tee_config: Option<crate::tee::TeeConfig>,
+    /// Extra user-requested port mappings: container_port → host_port.
+    /// Populated from `metadata_json.ports` at creation time.
+    #[serde(default)]
+    pub extra_ports: HashMap<u16, u16>,
+    /// SSH login user chosen by the runtime when SSH is enabled.
+    #[serde(default)]
+    pub ssh_login_user: Option<String>,
+    /// Persisted SSH key assignments so they can be replayed after recreation.
+    #[serde(default)]
+    pub ssh_authorized_keys: Vec<SshAuthorizedKey>,
+    /// Sidecar capabilities the sandbox was created with (e.g.
+    /// `["computer_use"]`), preserved verbatim from the create request
+    /// so snapshot-restore and recreation hand the same capability set
+    /// back to the sidecar. Empty string when no extra capabilities
+    /// were requested.
+    #[serde(default)]
+    pub capabilities_json: String,
+    /// What the sidecar reported about its API at provision (see
+    /// [`crate::sidecar_compat`]). `None` for records created before
+    /// negotiation, or when the sidecar didn't answer in time; both are
+    /// treated as API 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sidecar_api: Option<crate::sidecar_compat::SidecarApiInfo>,
+    /// Registry digest `original_image` resolved to for the host
+    /// architecture when the container was created. `None` for non-Docker
+    /// backends, locally built images, and records from before it was
+    /// tracked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_digest: Option<String>,
+    /// Container OOM kills and unexpected exits, oldest first (see
+    /// [`crate::crash_monitor`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crash_history: Vec<crate::crash_monitor::CrashRecord>,
+    /// What happened to the sandbox and who did it, oldest first (see
+    /// [`super::history`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lifecycle_events: Vec<LifecycleEvent>,
+    /// Image committed from the source sandbox this one was cloned from
+    /// (see [`clone_sidecar`]), removed with the container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clone_image: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SshAuthorizedKey {
+    pub username: String,
+    pub public_key: String,
+    /// Unix timestamp of the (latest) provision. `0` for keys stored before
+    /// this was tracked.
+    #[serde(default)]
+    pub provisioned_at: u64,
+    /// Unix timestamp after which the reaper revokes the key. `None` = no TTL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl SandboxRecord {
+    /// Whether the user has injected secrets via two-phase provisioning.
+    pub fn has_user_secrets(&self) -> bool {
+        let s = self.user_env_json.trim();
+        !s.is_empty() && s != "{}"
+    }
+
+    /// Merge base + user env into a single JSON string for container creation.
+    pub fn effective_env_json(&self) -> String {
+        merge_env_json(&self.base_env_json, &self.user_env_json)
+    }
+}
@@ -0,0 +1,214 @@
+use super::*;
+
+/// Something [`repair_sandbox`] found wrong with a sandbox.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairIssue {
+    /// The record points at a container Docker no longer knows about.
+    ContainerMissing,
+    /// The record says running but the container has exited.
+    ContainerNotRunning,
+    /// The container runs but the sidecar fails `/health`.
+    SidecarUnhealthy,
+    /// The sidecar is healthy but rejects the stored token.
+    TokenMismatch,
+}
+
+/// Probe results and the issues derived from them.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairDiagnosis {
+    pub issues: Vec<RepairIssue>,
+    pub container: ContainerStatus,
+    pub health: SidecarHealthProbe,
+    /// Whether the sidecar accepted the stored token. `None` when the
+    /// sidecar was not healthy enough to ask.
+    pub token_accepted: Option<bool>,
+}
+
+/// Result of [`repair_sandbox`].
+#[derive(Clone, Debug)]
+pub struct RepairOutcome {
+    pub record: SandboxRecord,
+    pub diagnosis: RepairDiagnosis,
+    /// Human-readable steps taken, in order. Empty when nothing was broken.
+    pub actions: Vec<String>,
+    /// `false` only when a missing container had no snapshot to restore from.
+    pub workspace_preserved: bool,
+}
+
+/// Derive repair issues from probe results. A sandbox stopped on purpose is
+/// only broken if its container has disappeared; the reaper's own removals
+/// (`container_removed_at`) are not issues since resume restores them.
+pub fn diagnose_repair_issues(
+    record: &SandboxRecord,
+    container: &ContainerStatus,
+    health: &SidecarHealthProbe,
+    token_accepted: Option<bool>,
+) -> Vec<RepairIssue> {
+    if container.status == "missing" {
+        return vec![RepairIssue::ContainerMissing];
+    }
+    if record.state != SandboxState::Running || record.container_removed_at.is_some() {
+        return Vec::new();
+    }
+    if !container.running {
+        return vec![RepairIssue::ContainerNotRunning];
+    }
+    if !health.healthy {
+        return vec![RepairIssue::SidecarUnhealthy];
+    }
+    if token_accepted == Some(false) {
+        return vec![RepairIssue::TokenMismatch];
+    }
+    Vec::new()
+}
+
+/// Diagnose a Docker sandbox and fix only what is broken.
+///
+/// - Missing container: restored through the resume tiers (snapshot image,
+///   then S3) when a snapshot exists, otherwise recreated from its image with
+///   the stored token — the workspace is lost in that case only.
+/// - Exited container or wedged sidecar: restarted in place, keeping the
+///   container filesystem.
+/// - Token mismatch: recreated with the stored token, carrying the workspace
+///   over (rolled back if the new sidecar fails).
+///
+/// A healthy sandbox is left untouched. Callers must hold the lifecycle lock.
+/// TEE and Firecracker sandboxes are rejected.
+pub async fn repair_sandbox(
+    sandbox_id: &str,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<RepairOutcome> {
+    let record = get_sandbox_by_id(sandbox_id)?;
+    if record.tee_deployment_id.is_some() {
+        return Err(SandboxError::Validation(
+            "Repair is not supported for TEE sandboxes".into(),
+        ));
+    }
+    if record_uses_firecracker(&record) {
+        return Err(SandboxError::Validation(
+            "Repair is not supported for runtime_backend=firecracker".into(),
+        ));
+    }
+
+    let status = probe_sandbox_status(&record).await;
+    if status.container.status == "unknown" {
+        return Err(SandboxError::Unavailable(format!(
+            "Cannot diagnose sandbox {}: {}",
+            record.id,
+            status
+                .container
+                .error
+                .as_deref()
+                .unwrap_or("container state unknown")
+        )));
+    }
+    let token_accepted = if status.health.healthy {
+        probe_sidecar_token(&record.sidecar_url, &record.token).await
+    } else {
+        None
+    };
+    let diagnosis = RepairDiagnosis {
+        issues: diagnose_repair_issues(&record, &status.container, &status.health, token_accepted),
+        container: status.container,
+        health: status.health,
+        token_accepted,
+    };
+
+    let mut actions = Vec::new();
+    let mut workspace_preserved = true;
+    let repaired = match diagnosis.issues.first() {
+        None => record,
+        Some(RepairIssue::ContainerMissing) => {
+            let now = crate::util::now_ts();
+            sandboxes()?.update(&record.id, |r| {
+                r.state = SandboxState::Stopped;
+                r.stopped_at.get_or_insert(now);
+                r.container_removed_at = Some(now);
+            })?;
+            actions.push("marked missing container as removed".to_string());
+            let stopped = get_sandbox_by_id(&record.id)?;
+            if stopped.snapshot_image_id.is_some() || stopped.snapshot_s3_url.is_some() {
+                let restored = restart_sidecar(&stopped).await?;
+                actions.push("restored container from snapshot".to_string());
+                restored
+            } else {
+                let recreated =
+                    recreate_sidecar_impl(&record.id, &stopped.user_env_json, None, None, tee)
+                        .await?;
+                workspace_preserved = false;
+                actions.push("recreated container from image (no snapshot)".to_string());
+                recreated
+            }
+        }
+        Some(RepairIssue::ContainerNotRunning) => {
+            let now = crate::util::now_ts();
+            sandboxes()?.update(&record.id, |r| {
+                r.state = SandboxState::Stopped;
+                r.stopped_at = Some(now);
+            })?;
+            let restarted = restart_sidecar(&get_sandbox_by_id(&record.id)?).await?;
+            actions.push("started exited container".to_string());
+            restarted
+        }
+        Some(RepairIssue::SidecarUnhealthy) => {
+            let restarted = restart_sidecar(&record).await?;
+            actions.push("restarted container with unhealthy sidecar".to_string());
+            restarted
+        }
+        Some(RepairIssue::TokenMismatch) => {
+            let image = if record.original_image.is_empty() {
+                current_sidecar_image()
+            } else {
+                record.original_image.clone()
+            };
+            let builder = docker_builder().await?;
+            let (recreated, _) =
+                migrate_sidecar(&builder, &record, &image, &image, "Repair", |_| {}, tee).await?;
+            actions.push("recreated container with the stored token".to_string());
+            recreated
+        }
+    };
+
+    if !actions.is_empty()
+        && !wait_for_sidecar_health(&repaired.sidecar_url, RESTART_HEALTH_TIMEOUT_SECS).await
+    {
+        return Err(SandboxError::Unavailable(format!(
+            "Repair of sandbox {} finished ({}) but the sidecar is still unhealthy at {}",
+            repaired.id,
+            actions.join(", "),
+            repaired.sidecar_url
+        )));
+    }
+    tracing::info!(
+        sandbox_id = %repaired.id,
+        issues = ?diagnosis.issues,
+        actions = ?actions,
+        "sandbox repair finished"
+    );
+    Ok(RepairOutcome {
+        record: repaired,
+        diagnosis,
+        actions,
+        workspace_preserved,
+    })
+}
+
+/// Ask the sidecar whether it accepts `token`. `Some(false)` on 401/403,
+/// `Some(true)` on success, `None` when the answer is inconclusive.
+async fn probe_sidecar_token(sidecar_url: &str, token: &str) -> Option<bool> {
+    let response = crate::util::http_client()
+        .ok()?
+        .get(format!("{sidecar_url}/agents"))
+        .bearer_auth(token)
+        .timeout(Duration::from_secs(STATUS_HEALTH_TIMEOUT_SECS))
+        .send()
+        .await
+        .ok()?;
+    match response.status().as_u16() {
+        401 | 403 => Some(false),
+        code if (200..300).contains(&code) => Some(true),
+        _ => None,
+    }
+}
@@ -0,0 +1,38 @@
+use super::*;
+
+/// How long a restarted sidecar gets to pass `/health` before the restart is
+/// reported as failed.
+pub const RESTART_HEALTH_TIMEOUT_SECS: u64 = 60;
+
+/// Restart a sandbox and wait for its sidecar to become healthy.
+///
+/// Unlike resume, this also recovers a wedged sidecar whose container is still
+/// running: a running sandbox is stopped first, then brought back through the
+/// regular resume tiers (hot start, snapshot image, S3). A stopped sandbox is
+/// simply resumed. Refused while the operator is draining, since the resume
+/// half would be. Callers must hold the sandbox's lifecycle lock; `record`
+/// only names the sandbox, whose state is re-read under that lock (a record
+/// read before it may predate a concurrent stop, resume or recreate).
+///
+/// Returns the refreshed record (port mappings may change across a restart).
+pub async fn restart_sidecar(record: &SandboxRecord) -> Result<SandboxRecord> {
+    crate::drain::ensure_accepting_provisions()?;
+    let record = &get_sandbox_by_id(&record.id)?;
+    if record.state == SandboxState::Running {
+        stop_sidecar(record).await?;
+    }
+
+    let stopped = get_sandbox_by_id(&record.id)?;
+    resume_sidecar(&stopped).await?;
+
+    let restarted = get_sandbox_by_id(&record.id)?;
+    if !wait_for_sidecar_health(&restarted.sidecar_url, RESTART_HEALTH_TIMEOUT_SECS).await {
+        return Err(SandboxError::Unavailable(format!(
+            "Restart failed: sidecar for sandbox {} did not become healthy at {}",
+            record.id, restarted.sidecar_url
+        )));
+    }
+
+    tracing::info!(sandbox_id = %record.id, "sandbox restarted");
+    Ok(restarted)
+}
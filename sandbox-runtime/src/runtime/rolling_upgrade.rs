@@ -0,0 +1,248 @@
+use super::*;
+
+/// Workspace directory carried across an image upgrade and archived before GC.
+pub(crate) const WORKSPACE_DIR: &str = "/home/agent";
+
+/// Largest workspace archive an upgrade will carry over (`UPGRADE_MAX_WORKSPACE_MB`,
+/// default 2048). Larger workspaces abort the upgrade and the old container
+/// is resumed unchanged.
+pub const DEFAULT_UPGRADE_MAX_WORKSPACE_MB: u64 = 2048;
+
+/// Result of [`upgrade_sidecar_with_rollback`].
+#[derive(Clone, Debug)]
+pub struct ImageUpgradeOutcome {
+    pub record: SandboxRecord,
+    pub from_image: String,
+    pub to_image: String,
+    /// Bytes of workspace archive migrated into the new container.
+    pub workspace_bytes: usize,
+}
+
+fn upgrade_max_workspace_bytes() -> usize {
+    let mb = env::var("UPGRADE_MAX_WORKSPACE_MB")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_UPGRADE_MAX_WORKSPACE_MB);
+    usize::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX)
+}
+
+/// Move a sandbox onto `target_image`, carrying its workspace over and
+/// rolling back to the previous image if the new sidecar never turns healthy.
+///
+/// Steps:
+/// 1. Pull `target_image` while the old container keeps serving.
+/// 2. Stop the old container and archive `/home/agent` from it. If the
+///    archive fails, the old container is resumed and nothing changes.
+/// 3. Recreate the sidecar on `target_image` under the same sandbox ID and
+///    token (the stored record is replaced in one write), restore the
+///    workspace, and wait for `/health`.
+/// 4. On failure, recreate again on the previous image with the same
+///    workspace and report the upgrade as rolled back.
+///
+/// Unlike [`upgrade_sidecar_image`], the workspace survives the upgrade.
+/// Callers must hold the sandbox's lifecycle lock. TEE and Firecracker
+/// sandboxes are rejected.
+pub async fn upgrade_sidecar_with_rollback(
+    sandbox_id: &str,
+    target_image: &str,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<ImageUpgradeOutcome> {
+    let old = get_sandbox_by_id(sandbox_id)?;
+    if old.tee_deployment_id.is_some() {
+        return Err(SandboxError::Validation(
+            "Image upgrade is not supported for TEE sandboxes".into(),
+        ));
+    }
+    if record_uses_firecracker(&old) {
+        return Err(SandboxError::Validation(
+            "Image upgrade is not supported for runtime_backend=firecracker".into(),
+        ));
+    }
+    if old.container_removed_at.is_some() {
+        return Err(SandboxError::Validation(format!(
+            "Sandbox {} has no container; resume it before upgrading",
+            old.id
+        )));
+    }
+    let target_image = target_image.trim();
+    if target_image.is_empty() {
+        return Err(SandboxError::Validation("Target image is required".into()));
+    }
+    let from_image = if old.original_image.is_empty() {
+        current_sidecar_image()
+    } else {
+        old.original_image.clone()
+    };
+
+    let builder = docker_builder().await?;
+    if SidecarRuntimeConfig::load().pull_image {
+        retry_docker("pull_image", 2, 1000, || {
+            docker_timeout("pull_image", builder.pull_image(target_image, None))
+        })
+        .await?;
+    }
+
+    let label = format!("Upgrade to {target_image}");
+    let (record, workspace_bytes) = migrate_sidecar(
+        &builder,
+        &old,
+        target_image,
+        &from_image,
+        &label,
+        |_| {},
+        tee,
+    )
+    .await?;
+    tracing::info!(sandbox_id = %old.id, from = %from_image, to = %target_image, "sidecar image upgraded");
+    record_lifecycle_change(
+        &record,
+        LifecycleEventKind::Upgraded,
+        Some(format!("{from_image} -> {target_image}")),
+    );
+    Ok(ImageUpgradeOutcome {
+        record,
+        from_image,
+        to_image: target_image.to_string(),
+        workspace_bytes,
+    })
+}
+
+/// Recreate a Docker sandbox's container in place, carrying the workspace
+/// over. Shared by image upgrades and config updates that cannot be applied
+/// to a live container.
+///
+/// `apply` edits the stored record (e.g. new resource limits) after the
+/// workspace is archived and before the new container is created. If the
+/// new sidecar fails, the record's resource and lifetime fields are restored
+/// from `old` and the sandbox is recreated on `from_image`. Returns the new
+/// record and the migrated workspace size. Callers hold the lifecycle lock.
+pub(crate) async fn migrate_sidecar(
+    builder: &DockerBuilder,
+    old: &SandboxRecord,
+    target_image: &str,
+    from_image: &str,
+    label: &str,
+    apply: impl FnOnce(&mut SandboxRecord),
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<(SandboxRecord, usize)> {
+    if old.state == SandboxState::Running {
+        stop_sidecar(old).await?;
+    }
+    let workspace = match download_workspace(
+        builder,
+        &old.container_id,
+        upgrade_max_workspace_bytes(),
+        "UPGRADE_MAX_WORKSPACE_MB",
+    )
+    .await
+    {
+        Ok(archive) => archive,
+        Err(err) => {
+            if old.state == SandboxState::Running {
+                let _ = resume_sidecar(&get_sandbox_by_id(&old.id)?).await;
+            }
+            return Err(err);
+        }
+    };
+
+    sandboxes()?.update(&old.id, apply)?;
+    match replace_and_verify(builder, old, target_image, &workspace, tee).await {
+        Ok(record) => Ok((record, workspace.len())),
+        Err(err) => {
+            tracing::error!(sandbox_id = %old.id, error = %err, "{label} failed; rolling back");
+            sandboxes()?.update(&old.id, |r| {
+                r.cpu_cores = old.cpu_cores;
+                r.memory_mb = old.memory_mb;
+                r.disk_gb = old.disk_gb;
+                r.idle_timeout_seconds = old.idle_timeout_seconds;
+                r.max_lifetime_seconds = old.max_lifetime_seconds;
+                r.metadata_json = old.metadata_json.clone();
+            })?;
+            match replace_and_verify(builder, old, from_image, &workspace, tee).await {
+                Ok(_) => Err(SandboxError::Unavailable(format!(
+                    "{label} failed ({err}); rolled back to {from_image}"
+                ))),
+                Err(rollback_err) => Err(SandboxError::Unavailable(format!(
+                    "{label} failed ({err}) and rollback to {from_image} also failed: \
+                     {rollback_err}"
+                ))),
+            }
+        }
+    }
+}
+
+/// Recreate `old` on `image`, restore `workspace`, and wait for health.
+async fn replace_and_verify(
+    builder: &DockerBuilder,
+    old: &SandboxRecord,
+    image: &str,
+    workspace: &[u8],
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<SandboxRecord> {
+    let record = recreate_sidecar_impl(&old.id, &old.user_env_json, Some(image), None, tee).await?;
+    if !workspace.is_empty() {
+        restore_workspace(builder, &record.container_id, workspace).await?;
+        run_workspace_bootstrap(&builder.client(), &record.container_id, &record.id).await;
+    }
+    if !wait_for_sidecar_health(&record.sidecar_url, RESTART_HEALTH_TIMEOUT_SECS).await {
+        return Err(SandboxError::Unavailable(format!(
+            "sidecar on {image} did not become healthy at {}",
+            record.sidecar_url
+        )));
+    }
+    Ok(record)
+}
+
+/// Tar archive of [`WORKSPACE_DIR`] from a (stopped) container. Fails once
+/// the archive grows past `limit` bytes; `limit_var` names the setting in
+/// the error.
+pub(crate) async fn download_workspace(
+    builder: &DockerBuilder,
+    container_id: &str,
+    limit: usize,
+    limit_var: &str,
+) -> Result<Vec<u8>> {
+    use docktopus::bollard::container::DownloadFromContainerOptions;
+    let client = builder.client();
+    let mut stream = Box::pin(client.download_from_container(
+        container_id,
+        Some(DownloadFromContainerOptions {
+            path: WORKSPACE_DIR,
+        }),
+    ));
+    let mut archive = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk =
+            chunk.map_err(|e| SandboxError::Docker(format!("Failed to archive workspace: {e}")))?;
+        archive.extend_from_slice(&chunk);
+        if archive.len() > limit {
+            return Err(SandboxError::Validation(format!(
+                "Workspace exceeds {limit_var} ({} MiB)",
+                limit / (1024 * 1024)
+            )));
+        }
+    }
+    Ok(archive)
+}
+
+/// Extract a workspace archive (rooted at `agent/`) back under `/home`.
+async fn restore_workspace(
+    builder: &DockerBuilder,
+    container_id: &str,
+    archive: &[u8],
+) -> Result<()> {
+    use docktopus::bollard::container::UploadToContainerOptions;
+    let client = builder.client();
+    docker_timeout(
+        "upload_to_container",
+        client.upload_to_container(
+            container_id,
+            Some(UploadToContainerOptions {
+                path: "/home",
+                ..Default::default()
+            }),
+            archive.to_vec().into(),
+        ),
+    )
+    .await
+}
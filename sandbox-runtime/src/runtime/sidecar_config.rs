@@ -0,0 +1,249 @@
+use super::*;
+
+/// Runtime configuration loaded once at startup from environment variables.
+#[derive(Clone, Debug)]
+pub struct SidecarRuntimeConfig {
+    /// Sidecar image for [`Self::platform_arch`]: `SIDECAR_IMAGE_<ARCH>` when
+    /// set, else `SIDECAR_IMAGE`.
+    pub image: String,
+    /// Docker architecture sandboxes run on (`amd64`, `arm64`); images are
+    /// pulled for `linux/<platform_arch>`.
+    pub platform_arch: String,
+    pub public_host: String,
+    pub container_port: u16,
+    pub ssh_port: u16,
+    /// Default per-request timeout; requests that carry their own deadline
+    /// (exec/agent `timeout`) override it.
+    pub timeout: Duration,
+    /// TCP connect timeout, independent of how long a request may run.
+    pub connect_timeout: Duration,
+    pub docker_host: Option<String>,
+    pub pull_image: bool,
+    pub sandbox_default_idle_timeout: u64,
+    pub sandbox_default_max_lifetime: u64,
+    pub sandbox_max_idle_timeout: u64,
+    pub sandbox_max_max_lifetime: u64,
+    pub sandbox_reaper_interval: u64,
+    pub sandbox_gc_interval: u64,
+    pub sandbox_gc_hot_retention: u64,
+    pub sandbox_gc_warm_retention: u64,
+    pub sandbox_gc_cold_retention: u64,
+    pub snapshot_auto_commit: bool,
+    pub snapshot_destination_prefix: Option<String>,
+    pub sandbox_max_count: usize,
+    /// Sandboxes this operator registered on-chain (`OPERATOR_MAX_CAPACITY`),
+    /// enforced at admission alongside `sandbox_max_count`. 0 = not set.
+    pub operator_max_capacity: usize,
+    /// Per-sandbox CPU maximum (cores). 0 = no cap.
+    pub sandbox_max_cpu_cores: u64,
+    /// Per-sandbox memory maximum (MB). 0 = no cap. Also the value an
+    /// unlimited (0) request clamps to, and the footprint an unlimited
+    /// sandbox is accounted at in the host memory budget.
+    pub sandbox_max_memory_mb: u64,
+    /// Per-sandbox disk maximum (GB). 0 = no cap.
+    pub sandbox_max_disk_gb: u64,
+    /// Total memory (MB) admissible across all running sandboxes. 0 = disabled.
+    pub sandbox_host_memory_budget_mb: u64,
+    /// Total CPU cores admissible across all running sandboxes. 0 = disabled.
+    pub sandbox_host_cpu_budget: u64,
+}
+
+static RUNTIME_CONFIG: OnceCell<SidecarRuntimeConfig> = OnceCell::new();
+
+impl SidecarRuntimeConfig {
+    /// Compute the effective idle timeout: substitute default for 0, clamp to operator max.
+    pub fn effective_idle_timeout(&self, requested: u64) -> u64 {
+        let value = if requested == 0 {
+            self.sandbox_default_idle_timeout
+        } else {
+            requested
+        };
+        value.min(self.sandbox_max_idle_timeout)
+    }
+
+    /// Compute the effective max lifetime: substitute default for 0, clamp to operator max.
+    pub fn effective_max_lifetime(&self, requested: u64) -> u64 {
+        let value = if requested == 0 {
+            self.sandbox_default_max_lifetime
+        } else {
+            requested
+        };
+        value.min(self.sandbox_max_max_lifetime)
+    }
+
+    /// Load configuration from environment variables.
+    /// Cached after the first call — subsequent calls return the same config.
+    pub fn load() -> &'static SidecarRuntimeConfig {
+        RUNTIME_CONFIG.get_or_init(|| {
+            let platform_arch = host_arch();
+            let image = image_for_arch(
+                &env::var("SIDECAR_IMAGE").unwrap_or_else(|_| DEFAULT_SIDECAR_IMAGE.to_string()),
+                &platform_arch,
+            );
+            let public_host =
+                env::var("SIDECAR_PUBLIC_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+            let container_port = env::var("SIDECAR_HTTP_PORT")
+                .ok()
+                .and_then(|v| v.parse::<u16>().ok())
+                .unwrap_or(DEFAULT_SIDECAR_HTTP_PORT);
+            let ssh_port = env::var("SIDECAR_SSH_PORT")
+                .ok()
+                .and_then(|v| v.parse::<u16>().ok())
+                .unwrap_or(DEFAULT_SIDECAR_SSH_PORT);
+            let timeout = env::var("REQUEST_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(crate::DEFAULT_TIMEOUT_SECS);
+            let connect_timeout = env::var("HTTP_CONNECT_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(crate::DEFAULT_CONNECT_TIMEOUT_SECS);
+            let docker_host = env::var("DOCKER_HOST")
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .or_else(detect_docker_host_fallback);
+            let pull_image = env::var("SIDECAR_PULL_IMAGE")
+                .ok()
+                .and_then(|v| v.parse::<bool>().ok())
+                .unwrap_or(true);
+
+            let sandbox_default_idle_timeout = env::var("SANDBOX_DEFAULT_IDLE_TIMEOUT")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(1800);
+            let sandbox_default_max_lifetime = env::var("SANDBOX_DEFAULT_MAX_LIFETIME")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(86400);
+            let sandbox_max_idle_timeout = env::var("SANDBOX_MAX_IDLE_TIMEOUT")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(7200);
+            let sandbox_max_max_lifetime = env::var("SANDBOX_MAX_MAX_LIFETIME")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(172800);
+            let sandbox_reaper_interval = env::var("SANDBOX_REAPER_INTERVAL")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(30);
+            let sandbox_gc_interval = env::var("SANDBOX_GC_INTERVAL")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(3600);
+            let sandbox_gc_hot_retention = env::var("SANDBOX_GC_HOT_RETENTION")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .or_else(|| {
+                    env::var("SANDBOX_GC_STOPPED_RETENTION")
+                        .ok()
+                        .and_then(|v| v.parse::<u64>().ok())
+                })
+                .unwrap_or(86400);
+            let sandbox_gc_warm_retention = env::var("SANDBOX_GC_WARM_RETENTION")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(172800);
+            let sandbox_gc_cold_retention = env::var("SANDBOX_GC_COLD_RETENTION")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(604800);
+            let snapshot_auto_commit = env::var("SANDBOX_SNAPSHOT_AUTO_COMMIT")
+                .ok()
+                .and_then(|v| v.parse::<bool>().ok())
+                .unwrap_or(true);
+            let snapshot_destination_prefix = env::var("SANDBOX_SNAPSHOT_DESTINATION_PREFIX")
+                .ok()
+                .filter(|v| !v.trim().is_empty());
+            let sandbox_max_count = env::var("SANDBOX_MAX_COUNT")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(100);
+            let operator_max_capacity = env::var("OPERATOR_MAX_CAPACITY")
+                .ok()
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            let sandbox_max_cpu_cores = env::var("SANDBOX_MAX_CPU_CORES")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0);
+            let sandbox_max_memory_mb = env::var("SANDBOX_MAX_MEMORY_MB")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0);
+            let sandbox_max_disk_gb = env::var("SANDBOX_MAX_DISK_GB")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0);
+            let sandbox_host_memory_budget_mb = env::var("SANDBOX_HOST_MEMORY_BUDGET_MB")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0);
+            // Total CPU cores admissible across all running sandboxes. Primary
+            // name mirrors SANDBOX_HOST_MEMORY_BUDGET_MB; SANDBOX_CPU_BUDGET is
+            // accepted as an alias. 0 = disabled (unlimited).
+            let sandbox_host_cpu_budget = env::var("SANDBOX_HOST_CPU_BUDGET")
+                .or_else(|_| env::var("SANDBOX_CPU_BUDGET"))
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0);
+
+            // Validate critical configuration values. Panics are intentional here —
+            // these represent unrecoverable startup misconfigurations. Unlike process::exit,
+            // panic! unwinds the stack and runs destructors.
+            assert!(!image.trim().is_empty(), "SIDECAR_IMAGE must not be empty");
+            assert!(container_port > 0, "SIDECAR_HTTP_PORT must be > 0");
+            assert!(timeout > 0, "REQUEST_TIMEOUT_SECS must be > 0");
+
+            tracing::info!(
+                image = %image,
+                platform_arch = %platform_arch,
+                host = %public_host,
+                port = container_port,
+                idle_timeout = sandbox_default_idle_timeout,
+                max_lifetime = sandbox_default_max_lifetime,
+                reaper_interval = sandbox_reaper_interval,
+                gc_interval = sandbox_gc_interval,
+                max_sandboxes = sandbox_max_count,
+                operator_max_capacity,
+                max_cpu_cores = sandbox_max_cpu_cores,
+                max_memory_mb = sandbox_max_memory_mb,
+                max_disk_gb = sandbox_max_disk_gb,
+                host_memory_budget_mb = sandbox_host_memory_budget_mb,
+                host_cpu_budget = sandbox_host_cpu_budget,
+                "Runtime configuration loaded"
+            );
+
+            SidecarRuntimeConfig {
+                image,
+                platform_arch,
+                public_host,
+                container_port,
+                ssh_port,
+                timeout: Duration::from_secs(timeout),
+                connect_timeout: Duration::from_secs(connect_timeout),
+                docker_host,
+                pull_image,
+                sandbox_default_idle_timeout,
+                sandbox_default_max_lifetime,
+                sandbox_max_idle_timeout,
+                sandbox_max_max_lifetime,
+                sandbox_reaper_interval,
+                sandbox_gc_interval,
+                sandbox_gc_hot_retention,
+                sandbox_gc_warm_retention,
+                sandbox_gc_cold_retention,
+                snapshot_auto_commit,
+                snapshot_destination_prefix,
+                sandbox_max_count,
+                operator_max_capacity,
+                sandbox_max_cpu_cores,
+                sandbox_max_memory_mb,
+                sandbox_max_disk_gb,
+                sandbox_host_memory_budget_mb,
+                sandbox_host_cpu_budget,
+            }
+        })
+    }
+}
@@ -58,7 +58,7 @@ pub async fn create_from_snapshot_image(record: &SandboxRecord) -> Result<Sandbo
         &record.capabilities_json,
     )?;
     let ep: Vec<u16> = record.extra_ports.keys().copied().collect();
-    let override_config = build_docker_config(
+    let mut override_config = build_docker_config(
         config,
         ssh_enabled,
         record.cpu_cores,
@@ -66,6 +66,7 @@ pub async fn create_from_snapshot_image(record: &SandboxRecord) -> Result<Sandbo
         None,
         &ep,
     );
+    crate::disk_quota::apply_storage_quota(&mut override_config, record.disk_gb);
 
     let container_name = format!("sidecar-{}-warm", record.id);
     let mut container = Container::new(builder.client(), image_id.to_string())
@@ -156,7 +157,7 @@ pub async fn create_and_restore_from_s3(record: &SandboxRecord) -> Result<Sandbo
         &record.capabilities_json,
     )?;
     let ep: Vec<u16> = record.extra_ports.keys().copied().collect();
-    let override_config = build_docker_config(
+    let mut override_config = build_docker_config(
         config,
         ssh_enabled,
         record.cpu_cores,
@@ -164,6 +165,7 @@ pub async fn create_and_restore_from_s3(record: &SandboxRecord) -> Result<Sandbo
         None,
         &ep,
     );
+    crate::disk_quota::apply_storage_quota(&mut override_config, record.disk_gb);
 
     let container_name = format!("sidecar-{}-cold", record.id);
     let mut container = Container::new(builder.client(), image.to_string())
@@ -1,4 +1,6 @@
 use super::*;
+use crate::audit_log::AuditAction;
+use crate::sidecar_compat::SidecarApi;
 
 #[derive(Debug, Default, Clone)]
 pub(crate) struct ExecCommandResult {
@@ -34,8 +36,8 @@ pub(crate) fn summarize_exec_failure(result: &ExecCommandResult) -> String {
         .to_string()
 }
 
-pub(crate) fn parse_sidecar_exec_result(parsed: &Value) -> ExecCommandResult {
-    let result = parsed.get("result");
+pub(crate) fn parse_sidecar_exec_result(api: SidecarApi, parsed: &Value) -> ExecCommandResult {
+    let result = api.command_result(parsed);
     ExecCommandResult {
         exit_code: result
             .and_then(|r| r.get("exitCode"))
@@ -179,18 +181,28 @@ pub(crate) fn persist_ssh_login_user(sandbox_id: &str, username: &str) -> Result
     Ok(())
 }
 
+/// Record a provisioned key. Re-provisioning an existing key refreshes its
+/// timestamp and replaces its expiry (so dropping the TTL makes it permanent).
 pub(crate) fn persist_ssh_key_assignment(
     sandbox_id: &str,
     username: &str,
     public_key: &str,
+    expires_at: Option<u64>,
 ) -> Result<()> {
+    let entry = SshAuthorizedKey {
+        username: username.to_string(),
+        public_key: public_key.to_string(),
+        provisioned_at: crate::util::now_ts(),
+        expires_at,
+    };
     sandboxes()?.update(sandbox_id, |record| {
-        let entry = SshAuthorizedKey {
-            username: username.to_string(),
-            public_key: public_key.to_string(),
-        };
-        if !record.ssh_authorized_keys.contains(&entry) {
-            record.ssh_authorized_keys.push(entry);
+        match record
+            .ssh_authorized_keys
+            .iter_mut()
+            .find(|e| e.username == username && e.public_key == public_key)
+        {
+            Some(existing) => *existing = entry.clone(),
+            None => record.ssh_authorized_keys.push(entry.clone()),
         }
     })?;
     Ok(())
@@ -314,16 +326,27 @@ pub(crate) async fn detect_sidecar_ssh_username(record: &SandboxRecord) -> Resul
         payload,
     )
     .await?;
-    let username = extract_detected_ssh_username(&parse_sidecar_exec_result(&parsed))?;
+    let exec = parse_sidecar_exec_result(SidecarApi::for_record(record), &parsed);
+    let username = extract_detected_ssh_username(&exec)?;
     persist_ssh_login_user(&record.id, &username)?;
     Ok(username)
 }
 
+fn enforce_ssh_command_policy(record: &SandboxRecord, command: &str) -> Result<()> {
+    let input = crate::exec_policy::CommandInput {
+        kind: crate::exec_policy::CommandKind::Ssh,
+        command,
+        ..Default::default()
+    };
+    crate::exec_policy::enforce_command_policy(&record.id, &record.owner, &input)
+}
+
 pub(crate) async fn execute_docker_ssh_command(
     record: &SandboxRecord,
     user: &str,
     command: &str,
 ) -> Result<ExecCommandResult> {
+    enforce_ssh_command_policy(record, command)?;
     let result = docker_exec_as_user(&record.container_id, user, command).await?;
     if result.exit_code != 0 {
         return Err(SandboxError::Validation(format!(
@@ -340,6 +363,7 @@ pub(crate) async fn execute_sidecar_ssh_command(
     record: &SandboxRecord,
     command: &str,
 ) -> Result<Value> {
+    enforce_ssh_command_policy(record, command)?;
     let payload = json!({ "command": format!("sh -c {}", shell_escape(command)) });
     crate::http::sidecar_post_json(
         &record.sidecar_url,
@@ -388,10 +412,15 @@ pub async fn detect_ssh_username(record: &SandboxRecord) -> Result<String> {
     detect_sidecar_ssh_username(&record).await
 }
 
+/// Install `public_key` for the sandbox's SSH user and record it. With
+/// `expires_at` (see [`ssh_key_expiry`]), the reaper revokes the key once
+/// that time passes. `actor` is recorded in the audit log.
 pub async fn provision_ssh_key(
     record: &SandboxRecord,
     requested_username: Option<&str>,
     public_key: &str,
+    expires_at: Option<u64>,
+    actor: &str,
 ) -> Result<(String, Value)> {
     crate::ssh_validation::validate_ssh_public_key(public_key).map_err(SandboxError::Validation)?;
     let requested = normalize_requested_ssh_username(requested_username)?;
@@ -420,7 +449,7 @@ pub async fn provision_ssh_key(
             &build_sidecar_ssh_key_install_command(&username, public_key),
         )
         .await?;
-        let exec = parse_sidecar_exec_result(&parsed);
+        let exec = parse_sidecar_exec_result(SidecarApi::for_record(&ready_record), &parsed);
         if exec.exit_code != 0 {
             return Err(SandboxError::Validation(format!(
                 "SSH provision failed for user '{username}' (exit {}): {}",
@@ -432,14 +461,41 @@ pub async fn provision_ssh_key(
     };
 
     persist_ssh_login_user(&ready_record.id, &username)?;
-    persist_ssh_key_assignment(&ready_record.id, &username, public_key)?;
+    persist_ssh_key_assignment(&ready_record.id, &username, public_key, expires_at)?;
+    crate::audit_log::record_ssh_key_event(
+        &ready_record.id,
+        AuditAction::SshKeyProvisioned,
+        actor,
+        &username,
+        public_key,
+    );
     Ok((username, result_json))
 }
 
+/// Remove `public_key` from the sandbox's SSH user and forget it. `actor` is
+/// recorded in the audit log.
 pub async fn revoke_ssh_key(
     record: &SandboxRecord,
     requested_username: Option<&str>,
     public_key: &str,
+    actor: &str,
+) -> Result<(String, Value)> {
+    revoke_ssh_key_audited(
+        record,
+        requested_username,
+        public_key,
+        actor,
+        AuditAction::SshKeyRevoked,
+    )
+    .await
+}
+
+pub(crate) async fn revoke_ssh_key_audited(
+    record: &SandboxRecord,
+    requested_username: Option<&str>,
+    public_key: &str,
+    actor: &str,
+    action: AuditAction,
 ) -> Result<(String, Value)> {
     crate::ssh_validation::validate_ssh_public_key(public_key).map_err(SandboxError::Validation)?;
     let requested = normalize_requested_ssh_username(requested_username)?;
@@ -468,7 +524,7 @@ pub async fn revoke_ssh_key(
             &build_sidecar_ssh_key_revoke_command(&username, public_key),
         )
         .await?;
-        let exec = parse_sidecar_exec_result(&parsed);
+        let exec = parse_sidecar_exec_result(SidecarApi::for_record(&ready_record), &parsed);
         if exec.exit_code != 0 {
             return Err(SandboxError::Validation(format!(
                 "SSH revoke failed for user '{username}' (exit {}): {}",
@@ -481,6 +537,7 @@ pub async fn revoke_ssh_key(
 
     persist_ssh_login_user(&ready_record.id, &username)?;
     remove_ssh_key_assignment(&ready_record.id, &username, public_key)?;
+    crate::audit_log::record_ssh_key_event(&ready_record.id, action, actor, &username, public_key);
     Ok((username, result_json))
 }
 
@@ -1,5 +1,9 @@
 use super::*;
 
+/// Where the Docker SSH bootstrap tells sshd to log. Polled for accepted
+/// logins when login auditing is enabled.
+pub(crate) const SSHD_LOG_PATH: &str = "/var/log/sshd.log";
+
 pub(crate) fn build_docker_ssh_bootstrap_command(username: &str) -> String {
     let user_arg = shell_escape(username);
     format!(
@@ -80,7 +84,7 @@ if ! awk 'NR > 1 {{ split($2,a,":"); if (toupper(a[2]) == "0016" && $4 == "0A")
     sleep 1;
   fi;
   rm -f /run/sshd.pid;
-  /usr/sbin/sshd -f /etc/ssh/sshd_config.tangle;
+  /usr/sbin/sshd -f /etc/ssh/sshd_config.tangle -E {SSHD_LOG_PATH};
 fi;
 awk 'NR > 1 {{ split($2,a,":"); if (toupper(a[2]) == "0016" && $4 == "0A") found=1 }} END {{ exit(found ? 0 : 1) }}' /proc/net/tcp /proc/net/tcp6 2>/dev/null"#,
     )
@@ -146,6 +150,17 @@ fi"#
     )
 }
 
+/// Print `username`'s `authorized_keys` (nothing if the file is missing).
+pub(crate) fn build_ssh_authorized_keys_read_command(username: &str) -> String {
+    let user_arg = shell_escape(username);
+    format!(
+        "set -eu; user={user_arg}; \
+home=$(getent passwd \"${{user}}\" | cut -d: -f6); \
+if [ -z \"$home\" ]; then echo \"User ${{user}} does not exist\" >&2; exit 1; fi; \
+if [ -f \"$home/.ssh/authorized_keys\" ]; then cat \"$home/.ssh/authorized_keys\"; fi"
+    )
+}
+
 pub(crate) fn build_sidecar_ssh_key_install_command(username: &str, public_key: &str) -> String {
     let user_arg = shell_escape(username);
     let key_arg = shell_escape(public_key);
@@ -0,0 +1,208 @@
+use super::*;
+use crate::sidecar_compat::SidecarApi;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+/// One entry of a sandbox user's `authorized_keys`, joined with the
+/// operator's provisioning record for the same key.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct SshKeyInfo {
+    pub username: String,
+    /// Key algorithm, e.g. `ssh-ed25519`.
+    pub key_type: String,
+    /// `SHA256:<base64>`, as printed by `ssh-keygen -lf`.
+    pub fingerprint: String,
+    pub comment: String,
+    /// Whether the key is in `authorized_keys`. `false` for keys the
+    /// operator recorded that are no longer installed.
+    pub installed: bool,
+    /// Set when the key was provisioned through the operator.
+    pub provisioned_at: Option<u64>,
+    pub expires_at: Option<u64>,
+}
+
+/// Key type, base64 blob and comment of an `authorized_keys` line, skipping
+/// any leading options (`from="...",no-pty ...`). `None` for blank lines,
+/// comments and lines without a recognizable key.
+pub fn parse_authorized_key_line(line: &str) -> Option<(String, String, String)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let type_idx = tokens.iter().position(|t| is_ssh_key_type(t))?;
+    let blob = tokens.get(type_idx + 1)?;
+    Some((
+        tokens[type_idx].to_string(),
+        (*blob).to_string(),
+        tokens[type_idx + 2..].join(" "),
+    ))
+}
+
+fn is_ssh_key_type(token: &str) -> bool {
+    token.starts_with("ssh-") || token.starts_with("ecdsa-") || token.starts_with("sk-")
+}
+
+/// OpenSSH SHA-256 fingerprint of a base64 key blob. `None` if the blob is
+/// not valid base64.
+pub fn ssh_key_fingerprint(blob: &str) -> Option<String> {
+    let raw = base64::engine::general_purpose::STANDARD
+        .decode(blob)
+        .ok()?;
+    let digest = Sha256::digest(&raw);
+    Some(format!(
+        "SHA256:{}",
+        base64::engine::general_purpose::STANDARD_NO_PAD.encode(digest)
+    ))
+}
+
+/// Upper bound for `expires_in_seconds` (one year).
+pub const MAX_SSH_KEY_TTL_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Expiry timestamp for a key provisioned at `now` with a TTL of
+/// `expires_in_seconds`. `None` or `Some(0)` means no expiry.
+pub fn ssh_key_expiry(now: u64, expires_in_seconds: Option<u64>) -> Result<Option<u64>> {
+    match expires_in_seconds {
+        None | Some(0) => Ok(None),
+        Some(ttl) if ttl > MAX_SSH_KEY_TTL_SECS => Err(SandboxError::Validation(format!(
+            "expires_in_seconds must be at most {MAX_SSH_KEY_TTL_SECS}"
+        ))),
+        Some(ttl) => Ok(Some(now + ttl)),
+    }
+}
+
+/// Revoke every key on `record` whose expiry is at or before `now`, using
+/// the regular revoke command. Failures are logged and retried on the next
+/// call. Returns the number of keys revoked.
+pub async fn revoke_expired_ssh_keys(record: &SandboxRecord, now: u64) -> usize {
+    let expired: Vec<SshAuthorizedKey> = record
+        .ssh_authorized_keys
+        .iter()
+        .filter(|k| k.expires_at.is_some_and(|at| at <= now))
+        .cloned()
+        .collect();
+    let mut revoked = 0;
+    for key in expired {
+        match revoke_ssh_key_audited(
+            record,
+            Some(&key.username),
+            &key.public_key,
+            crate::audit_log::OPERATOR_ACTOR,
+            crate::audit_log::AuditAction::SshKeyExpired,
+        )
+        .await
+        {
+            Ok(_) => {
+                revoked += 1;
+                tracing::info!(
+                    sandbox_id = %record.id,
+                    username = %key.username,
+                    "revoked expired SSH key"
+                );
+            }
+            Err(e) => tracing::warn!(
+                sandbox_id = %record.id,
+                username = %key.username,
+                error = %e,
+                "failed to revoke expired SSH key"
+            ),
+        }
+    }
+    revoked
+}
+
+/// Keys recorded by the operator for the sandbox, with provisioning time and
+/// expiry. Does not look inside the sandbox; see [`list_authorized_ssh_keys`].
+pub fn list_ssh_keys(sandbox_id: &str) -> Result<Vec<SshAuthorizedKey>> {
+    Ok(get_sandbox_by_id(sandbox_id)?.ssh_authorized_keys)
+}
+
+/// Join the login user's `authorized_keys` contents with the stored
+/// provisioning records. Keys added by hand inside the sandbox are listed
+/// without provisioning metadata; recorded keys missing from the file are
+/// listed with `installed: false`.
+pub fn merge_ssh_key_listing(
+    username: &str,
+    authorized_keys: &str,
+    recorded: &[SshAuthorizedKey],
+) -> Vec<SshKeyInfo> {
+    let recorded: Vec<(&SshAuthorizedKey, Option<(String, String, String)>)> = recorded
+        .iter()
+        .map(|r| (r, parse_authorized_key_line(&r.public_key)))
+        .collect();
+    let find_record = |key_type: &str, blob: &str| {
+        recorded.iter().find_map(|(r, parsed)| match parsed {
+            Some((t, b, _)) if t == key_type && b == blob && r.username == username => Some(*r),
+            _ => None,
+        })
+    };
+
+    let mut keys = Vec::new();
+    let mut seen = Vec::new();
+    for (key_type, blob, comment) in authorized_keys
+        .lines()
+        .filter_map(parse_authorized_key_line)
+    {
+        let record = find_record(&key_type, &blob);
+        keys.push(SshKeyInfo {
+            username: username.to_string(),
+            fingerprint: ssh_key_fingerprint(&blob).unwrap_or_default(),
+            key_type,
+            comment,
+            installed: true,
+            provisioned_at: record.map(|r| r.provisioned_at),
+            expires_at: record.and_then(|r| r.expires_at),
+        });
+        seen.push(blob);
+    }
+    for (record, parsed) in &recorded {
+        let Some((key_type, blob, comment)) = parsed else {
+            continue;
+        };
+        if seen.contains(blob) {
+            continue;
+        }
+        keys.push(SshKeyInfo {
+            username: record.username.clone(),
+            key_type: key_type.clone(),
+            fingerprint: ssh_key_fingerprint(blob).unwrap_or_default(),
+            comment: comment.clone(),
+            installed: false,
+            provisioned_at: Some(record.provisioned_at),
+            expires_at: record.expires_at,
+        });
+    }
+    keys
+}
+
+/// Read the login user's `authorized_keys` from the sandbox (Docker exec or
+/// sidecar exec) and return every key with fingerprint, comment and the
+/// operator's provisioning metadata.
+pub async fn list_authorized_ssh_keys(record: &SandboxRecord) -> Result<(String, Vec<SshKeyInfo>)> {
+    let (ready_record, docker_managed) = prepare_ssh_access(record).await?;
+    let username = match &ready_record.ssh_login_user {
+        Some(username) => username.clone(),
+        None if docker_managed => SSH_DEFAULT_LOGIN_USER.to_string(),
+        None => detect_sidecar_ssh_username(&ready_record).await?,
+    };
+    let command = build_ssh_authorized_keys_read_command(&username);
+    let contents = if docker_managed {
+        execute_docker_ssh_command(&ready_record, &username, &command)
+            .await?
+            .stdout
+    } else {
+        let parsed = execute_sidecar_ssh_command(&ready_record, &command).await?;
+        let exec = parse_sidecar_exec_result(SidecarApi::for_record(&ready_record), &parsed);
+        if exec.exit_code != 0 {
+            return Err(SandboxError::Validation(format!(
+                "SSH key listing failed for user '{username}' (exit {}): {}",
+                exec.exit_code,
+                summarize_exec_failure(&exec)
+            )));
+        }
+        exec.stdout
+    };
+    let recorded = get_sandbox_by_id(&ready_record.id)?.ssh_authorized_keys;
+    let keys = merge_ssh_key_listing(&username, &contents, &recorded);
+    Ok((username, keys))
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+/// Lines of the sshd log read per poll.
+const SSH_LOGIN_POLL_LINES: usize = 500;
+
+/// User, source address and key fingerprint of an sshd `Accepted publickey`
+/// log line. `None` for any other line.
+pub fn parse_sshd_accepted_login(line: &str) -> Option<(String, String, Option<String>)> {
+    const MARKER: &str = "Accepted publickey for ";
+    let rest = &line[line.find(MARKER)? + MARKER.len()..];
+    let mut tokens = rest.split_whitespace();
+    let username = tokens.next()?;
+    if tokens.next()? != "from" {
+        return None;
+    }
+    let source = tokens.next()?;
+    let fingerprint = tokens
+        .find(|t| t.starts_with("SHA256:"))
+        .map(str::to_string);
+    Some((username.to_string(), source.to_string(), fingerprint))
+}
+
+/// Read the tail of the sandbox's sshd log and record each accepted login
+/// in the audit log once. Only Docker sandboxes are polled: their sshd is
+/// started by the operator's bootstrap, which logs to a known file. Returns
+/// the number of new login events.
+pub async fn poll_ssh_logins(record: &SandboxRecord) -> Result<usize> {
+    if record.ssh_port.is_none() || !supports_docker_endpoint_refresh(record) {
+        return Ok(0);
+    }
+    let command = format!("tail -n {SSH_LOGIN_POLL_LINES} {SSHD_LOG_PATH} 2>/dev/null || true");
+    let result = docker_exec_as_user(&record.container_id, "root", &command).await?;
+    let mut recorded = 0;
+    for line in result.stdout.lines() {
+        if let Some((username, source, fingerprint)) = parse_sshd_accepted_login(line)
+            && crate::audit_log::record_ssh_login_event(
+                &record.id,
+                line,
+                &username,
+                fingerprint,
+                &source,
+            )?
+        {
+            recorded += 1;
+        }
+    }
+    Ok(recorded)
+}
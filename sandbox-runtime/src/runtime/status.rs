@@ -0,0 +1,191 @@
+use super::*;
+
+/// How long a status probe waits for the sidecar's `/health` endpoint.
+pub const STATUS_HEALTH_TIMEOUT_SECS: u64 = 5;
+
+/// Result of a single `/health` probe against a sidecar.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarHealthProbe {
+    pub healthy: bool,
+    pub status_code: Option<u16>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Container-level view of a sandbox as reported by its backend.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerStatus {
+    /// Backend status (`running`, `exited`, ...), or `removed`, `tee`,
+    /// `firecracker`, `missing` when Docker no longer has the container, and
+    /// `unknown` when Docker cannot be asked.
+    pub status: String,
+    pub running: bool,
+    pub started_at: Option<String>,
+    pub restart_count: Option<i64>,
+    pub oom_killed: Option<bool>,
+    pub error: Option<String>,
+}
+
+/// Live status of a sandbox: stored record fields plus what the sidecar and
+/// container report right now.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxLiveStatus {
+    pub sandbox_id: String,
+    pub state: SandboxState,
+    pub image: String,
+    pub health: SidecarHealthProbe,
+    pub container: ContainerStatus,
+    /// Seconds since the container last started (Docker) or since creation
+    /// when the start time is unknown. `None` when stopped.
+    pub uptime_secs: Option<u64>,
+    pub created_at: u64,
+    pub last_activity_at: u64,
+    pub idle_secs: u64,
+    /// Age of the stored TEE attestation report. `None` for non-TEE sandboxes.
+    pub tee_attestation_age_secs: Option<u64>,
+    /// Provider-side state and accrued cost. `None` for non-TEE sandboxes or
+    /// when no TEE backend is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tee: Option<TeeDeploymentProbe>,
+}
+
+/// Probe a sandbox's sidecar and container and assemble its live status.
+///
+/// Never fails: probe errors are reported inside the returned status so a
+/// dead sandbox still produces a useful answer. Stopped sandboxes skip the
+/// health probe.
+pub async fn probe_sandbox_status(record: &SandboxRecord) -> SandboxLiveStatus {
+    let now = crate::util::now_ts();
+    let running = record.state == SandboxState::Running;
+    let health = if running {
+        probe_sidecar_health(&record.sidecar_url, STATUS_HEALTH_TIMEOUT_SECS).await
+    } else {
+        SidecarHealthProbe {
+            error: Some("sandbox is stopped".into()),
+            ..Default::default()
+        }
+    };
+    let container = container_status(record).await;
+    let started_at = container
+        .started_at
+        .as_deref()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .and_then(|t| u64::try_from(t.timestamp()).ok())
+        .filter(|ts| *ts > 0);
+    let uptime_secs = running.then(|| now.saturating_sub(started_at.unwrap_or(record.created_at)));
+
+    SandboxLiveStatus {
+        sandbox_id: record.id.clone(),
+        state: record.state.clone(),
+        image: record.original_image.clone(),
+        health,
+        container,
+        uptime_secs,
+        created_at: record.created_at,
+        last_activity_at: record.last_activity_at,
+        idle_secs: now.saturating_sub(record.last_activity_at),
+        tee_attestation_age_secs: tee_attestation_age_secs(record, now),
+        tee: probe_tee_deployment(record).await,
+    }
+}
+
+/// Single `/health` request with a short timeout (no retry loop, unlike
+/// [`wait_for_sidecar_health`]).
+pub async fn probe_sidecar_health(sidecar_url: &str, timeout_secs: u64) -> SidecarHealthProbe {
+    let start = std::time::Instant::now();
+    let url = format!("{sidecar_url}/health");
+    let result = match crate::util::http_client() {
+        Ok(client) => client
+            .get(&url)
+            .timeout(Duration::from_secs(timeout_secs))
+            .send()
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    let latency_ms = start.elapsed().as_millis() as u64;
+    match result {
+        Ok(resp) => SidecarHealthProbe {
+            healthy: resp.status().is_success(),
+            status_code: Some(resp.status().as_u16()),
+            latency_ms,
+            error: None,
+        },
+        Err(error) => SidecarHealthProbe {
+            latency_ms,
+            error: Some(error),
+            ..Default::default()
+        },
+    }
+}
+
+async fn container_status(record: &SandboxRecord) -> ContainerStatus {
+    let running = record.state == SandboxState::Running;
+    let backend_only = |status: &str| ContainerStatus {
+        status: status.to_string(),
+        running,
+        ..Default::default()
+    };
+    if record.container_removed_at.is_some() {
+        return ContainerStatus {
+            status: "removed".into(),
+            ..Default::default()
+        };
+    }
+    if record.tee_deployment_id.is_some() {
+        return backend_only("tee");
+    }
+    if record_uses_firecracker(record) {
+        return backend_only("firecracker");
+    }
+
+    use docktopus::bollard::container::InspectContainerOptions;
+    let inspect = match docker_builder().await {
+        Ok(builder) => {
+            docker_timeout(
+                "inspect_container",
+                builder
+                    .client()
+                    .inspect_container(&record.container_id, None::<InspectContainerOptions>),
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    match inspect {
+        Ok(inspect) => {
+            let state = inspect.state.unwrap_or_default();
+            ContainerStatus {
+                status: state
+                    .status
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "unknown".into()),
+                running: state.running.unwrap_or(false),
+                started_at: state.started_at,
+                restart_count: inspect.restart_count,
+                oom_killed: state.oom_killed,
+                error: None,
+            }
+        }
+        Err(e) => {
+            let error = e.to_string();
+            // bollard reports a deleted container as a 404 from the daemon.
+            let missing = error.contains("status code 404");
+            ContainerStatus {
+                status: if missing { "missing" } else { "unknown" }.into(),
+                error: Some(error),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// Seconds since the stored attestation report was generated.
+pub fn tee_attestation_age_secs(record: &SandboxRecord, now: u64) -> Option<u64> {
+    let report: crate::tee::AttestationReport =
+        serde_json::from_str(record.tee_attestation_json.as_deref()?).ok()?;
+    Some(now.saturating_sub(report.timestamp))
+}
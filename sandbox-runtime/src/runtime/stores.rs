@@ -0,0 +1,230 @@
+//! Persistent sandbox stores (fleet `sandboxes.json` and instance `instance.json`).
+
+use super::*;
+use crate::store::PersistentStore;
+
+static SANDBOXES: OnceCell<PersistentStore<SandboxRecord>> = OnceCell::new();
+static INSTANCE_STORE: OnceCell<PersistentStore<SandboxRecord>> = OnceCell::new();
+
+/// Access the fleet-mode sandbox store (`sandboxes.json`), initializing it on first call.
+pub fn sandboxes() -> Result<&'static PersistentStore<SandboxRecord>> {
+    SANDBOXES
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("sandboxes.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Best-effort repair for legacy cloud sandbox records that were persisted
+/// without their `service_id`.
+///
+/// We only backfill when the provision tracker can prove the relationship via
+/// `metadata.service_id` for the same `sandbox_id`. If no lineage is present,
+/// the record is left unchanged.
+pub fn repair_sandbox_service_links_from_provisions() -> Result<usize> {
+    let provisions = crate::provision_progress::list_all_provisions()?;
+    if provisions.is_empty() {
+        return Ok(0);
+    }
+
+    let mut service_by_sandbox_id = HashMap::<String, u64>::new();
+    for provision in provisions {
+        let Some(sandbox_id) = provision.sandbox_id else {
+            continue;
+        };
+        let Some(service_id) = provision
+            .metadata
+            .get("service_id")
+            .and_then(serde_json::Value::as_u64)
+        else {
+            continue;
+        };
+        service_by_sandbox_id
+            .entry(sandbox_id)
+            .or_insert(service_id);
+    }
+
+    if service_by_sandbox_id.is_empty() {
+        return Ok(0);
+    }
+
+    let store = sandboxes()?;
+    let records = store.values()?;
+    let mut repaired = 0usize;
+
+    for record in records {
+        if record.service_id.is_some() {
+            continue;
+        }
+        let Some(service_id) = service_by_sandbox_id.get(&record.id).copied() else {
+            continue;
+        };
+        if store.update(&record.id, |entry| {
+            if entry.service_id.is_none() {
+                entry.service_id = Some(service_id);
+            }
+        })? {
+            repaired += 1;
+        }
+    }
+
+    Ok(repaired)
+}
+
+/// Slot name of the primary instance sandbox (the one auto-provisioned from
+/// the service config).
+pub const DEFAULT_INSTANCE_SLOT: &str = "main";
+
+/// Maximum length of an instance slot name.
+pub const MAX_INSTANCE_SLOT_LEN: usize = 32;
+
+/// A sandbox held in a named (non-`main`) instance slot.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct InstanceSlot {
+    pub slot: String,
+    pub record: SandboxRecord,
+}
+
+static INSTANCE_SLOTS: OnceCell<PersistentStore<InstanceSlot>> = OnceCell::new();
+
+/// Access the instance-mode primary sandbox store (`instance.json`).
+///
+/// In instance mode, the `main` slot sandbox is stored under key `"instance"`.
+/// This is the same file written by `set_instance_sandbox()` in the instance
+/// blueprint lib. The operator API reads from it for `/api/sandbox/*` routes.
+/// Additional named slots live in [`instance_slots`].
+pub fn instance_store() -> Result<&'static PersistentStore<SandboxRecord>> {
+    INSTANCE_STORE
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("instance.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Access the named instance slot store (`instance-slots.json`), keyed by
+/// slot name. Never contains `main`.
+pub fn instance_slots() -> Result<&'static PersistentStore<InstanceSlot>> {
+    INSTANCE_SLOTS
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("instance-slots.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Normalize and validate a slot name. Empty means `main`; other names must
+/// be 1-32 lowercase letters, digits, `-` or `_`.
+pub fn normalize_instance_slot(slot: &str) -> Result<String> {
+    let slot = slot.trim();
+    if slot.is_empty() {
+        return Ok(DEFAULT_INSTANCE_SLOT.to_string());
+    }
+    if slot.len() > MAX_INSTANCE_SLOT_LEN
+        || !slot
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'))
+    {
+        return Err(SandboxError::Validation(format!(
+            "Slot name must be 1-{MAX_INSTANCE_SLOT_LEN} characters of [a-z0-9-_]"
+        )));
+    }
+    Ok(slot.to_string())
+}
+
+/// Get the instance-mode primary (`main` slot) sandbox, if provisioned.
+pub fn get_instance_sandbox() -> Result<Option<SandboxRecord>> {
+    match instance_store()?.get("instance")? {
+        Some(mut r) => {
+            unseal_record(&mut r)?;
+            Ok(Some(r))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Get the sandbox in a named instance slot, if provisioned.
+pub fn get_instance_sandbox_slot(slot: &str) -> Result<Option<SandboxRecord>> {
+    let slot = normalize_instance_slot(slot)?;
+    if slot == DEFAULT_INSTANCE_SLOT {
+        return get_instance_sandbox();
+    }
+    match instance_slots()?.get(&slot)? {
+        Some(InstanceSlot { mut record, .. }) => {
+            unseal_record(&mut record)?;
+            Ok(Some(record))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Store a sandbox in a named (non-`main`) slot.
+pub fn set_instance_sandbox_slot(slot: &str, mut record: SandboxRecord) -> Result<()> {
+    let slot = normalize_instance_slot(slot)?;
+    if slot == DEFAULT_INSTANCE_SLOT {
+        return Err(SandboxError::Validation(
+            "The main slot is managed by instance provisioning".into(),
+        ));
+    }
+    seal_record(&mut record)?;
+    instance_slots()?.insert(slot.clone(), InstanceSlot { slot, record })
+}
+
+/// Remove a named (non-`main`) slot, returning its record if it existed.
+pub fn remove_instance_sandbox_slot(slot: &str) -> Result<Option<SandboxRecord>> {
+    let slot = normalize_instance_slot(slot)?;
+    if slot == DEFAULT_INSTANCE_SLOT {
+        return Err(SandboxError::Validation(
+            "The main slot is managed by instance provisioning".into(),
+        ));
+    }
+    match instance_slots()?.remove(&slot)? {
+        Some(InstanceSlot { mut record, .. }) => {
+            unseal_record(&mut record)?;
+            Ok(Some(record))
+        }
+        None => Ok(None),
+    }
+}
+
+/// List every provisioned slot as `(slot, record)`: `main` first, then named
+/// slots sorted by name.
+pub fn list_instance_slots() -> Result<Vec<(String, SandboxRecord)>> {
+    let mut slots = Vec::new();
+    if let Some(main) = get_instance_sandbox()? {
+        slots.push((DEFAULT_INSTANCE_SLOT.to_string(), main));
+    }
+    let mut named = instance_slots()?.values()?;
+    named.sort_by(|a, b| a.slot.cmp(&b.slot));
+    for InstanceSlot { slot, mut record } in named {
+        unseal_record(&mut record)?;
+        slots.push((slot, record));
+    }
+    Ok(slots)
+}
+
+/// Copy the fleet store's current record for `sandbox_id` into whichever
+/// instance slot holds it. No-op when no slot holds that sandbox.
+pub fn sync_instance_slot_record(sandbox_id: &str) -> Result<()> {
+    let Some(updated) = sandboxes()?.get(sandbox_id)? else {
+        return Ok(());
+    };
+    if instance_store()?
+        .get("instance")?
+        .is_some_and(|r| r.id == sandbox_id)
+    {
+        return instance_store()?.insert("instance".to_string(), updated);
+    }
+    let slots = instance_slots()?;
+    if let Some(slot) = slots.find(|s| s.record.id == sandbox_id)? {
+        slots.insert(
+            slot.slot.clone(),
+            InstanceSlot {
+                slot: slot.slot,
+                record: updated,
+            },
+        )?;
+    }
+    Ok(())
+}
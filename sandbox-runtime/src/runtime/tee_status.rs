@@ -0,0 +1,31 @@
+//! Provider-side status and billing of TEE sandboxes.
+
+use super::*;
+
+/// What the TEE backend reports about a sandbox's deployment. Backend
+/// errors are reported next to whichever half succeeded.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeeDeploymentProbe {
+    pub status: Option<crate::tee::TeeDeploymentStatus>,
+    pub billing: Option<crate::tee::TeeBillingInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// Ask the configured TEE backend for a TEE sandbox's provider status and
+/// billing. `None` for non-TEE sandboxes or without a backend.
+pub async fn probe_tee_deployment(record: &SandboxRecord) -> Option<TeeDeploymentProbe> {
+    let deployment_id = record.tee_deployment_id.as_deref()?;
+    let backend = crate::tee::try_tee_backend()?;
+    let mut probe = TeeDeploymentProbe::default();
+    match backend.status(deployment_id).await {
+        Ok(status) => probe.status = Some(status),
+        Err(e) => probe.errors.push(format!("status: {e}")),
+    }
+    match backend.billing_info(deployment_id).await {
+        Ok(billing) => probe.billing = Some(billing),
+        Err(e) => probe.errors.push(format!("billing: {e}")),
+    }
+    Some(probe)
+}
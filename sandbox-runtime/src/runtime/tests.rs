@@ -618,7 +618,7 @@ mod tee_tests {
 
     static INIT: Once = Once::new();
 
-    fn init() {
+    pub(super) fn init() {
         INIT.call_once(|| {
             let dir = std::env::temp_dir().join(format!("runtime-tee-test-{}", std::process::id()));
             std::fs::create_dir_all(&dir).ok();
@@ -626,6 +626,7 @@ mod tee_tests {
                 std::env::set_var("BLUEPRINT_STATE_DIR", dir.to_str().unwrap());
                 std::env::set_var("SIDECAR_IMAGE", "test:latest");
                 std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+                std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
             }
         });
     }
@@ -831,6 +832,11 @@ mod seal_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         seal_record(&mut record).unwrap();
@@ -964,13 +970,15 @@ mod core_logic_tests {
 
     // ── effective_idle_timeout ───────────────────────────────────────────
 
-    fn test_config() -> SidecarRuntimeConfig {
+    pub(super) fn test_config() -> SidecarRuntimeConfig {
         SidecarRuntimeConfig {
             image: "test".into(),
+            platform_arch: "amd64".into(),
             public_host: "127.0.0.1".into(),
             container_port: 3000,
             ssh_port: 2222,
             timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(5),
             docker_host: None,
             pull_image: false,
             sandbox_default_idle_timeout: 1800,
@@ -985,6 +993,7 @@ mod core_logic_tests {
             snapshot_auto_commit: true,
             snapshot_destination_prefix: None,
             sandbox_max_count: 100,
+            operator_max_capacity: 0,
             sandbox_max_cpu_cores: 0,
             sandbox_max_memory_mb: 0,
             sandbox_max_disk_gb: 0,
@@ -1026,6 +1035,24 @@ mod core_logic_tests {
         assert!(check_sandbox_count_limit(2, false, 3).is_ok());
     }
 
+    #[test]
+    fn operator_capacity_rejection_is_classified_as_capacity() {
+        let err = check_operator_capacity(5, false, 5).unwrap_err();
+        assert!(matches!(err, SandboxError::Unavailable(_)), "got {err:?}");
+        assert!(err.to_string().contains("OPERATOR_MAX_CAPACITY"));
+        assert_eq!(
+            crate::job_error::JobError::from(err).code,
+            crate::job_error::ErrorCode::Capacity
+        );
+
+        assert!(
+            check_operator_capacity(10_000, false, 0).is_ok(),
+            "0 = unset"
+        );
+        assert!(check_operator_capacity(5, true, 5).is_ok());
+        assert!(check_operator_capacity(4, false, 5).is_ok());
+    }
+
     #[test]
     fn resource_max_uncapped_passthrough() {
         assert_eq!(enforce_resource_max(0, 0, "memory_mb").unwrap(), 0);
@@ -1446,6 +1473,15 @@ mod core_logic_tests {
             SandboxState::Stopped
         );
     }
+
+    #[test]
+    fn resolve_agent_identifier_prefers_request_then_default() {
+        assert_eq!(resolve_agent_identifier(" coder ", None), "coder");
+        assert_eq!(
+            resolve_agent_identifier("  ", None),
+            DEFAULT_AGENT_IDENTIFIER
+        );
+    }
 }
 
 /// Single-pass store admission scan (admission.rs): one `values()` walk must
@@ -1494,6 +1530,11 @@ mod admission_scan_tests {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         }
     }
 
@@ -1619,6 +1660,35 @@ mod admission_scan_tests {
             }
         }
     }
+
+    #[test]
+    fn capacity_snapshot_sums_running_footprints_and_remaining_slots() {
+        let mut config = super::core_logic_tests::test_config();
+        config.sandbox_max_count = 10;
+        config.operator_max_capacity = 4;
+        config.sandbox_max_memory_mb = 256;
+        let records = vec![
+            record("a", SandboxState::Running, 1024, 2),
+            record("b", SandboxState::Stopped, 2048, 8),
+            // Unlimited memory is accounted at SANDBOX_MAX_MEMORY_MB; unlimited
+            // CPU with no cap is unaccountable.
+            record("c", SandboxState::Running, 0, 0),
+        ];
+        let snapshot = capacity_snapshot_of(&config, &records);
+        assert_eq!(snapshot.sandboxes, 3);
+        assert_eq!(snapshot.running, 2);
+        assert_eq!(snapshot.running_cpu_cores, 2);
+        assert_eq!(snapshot.running_memory_mb, 1024 + 256);
+        // The tighter of the two count limits.
+        assert_eq!(snapshot.remaining_sandboxes, Some(1));
+
+        config.sandbox_max_count = 0;
+        config.operator_max_capacity = 0;
+        assert_eq!(
+            capacity_snapshot_of(&config, &records).remaining_sandboxes,
+            None
+        );
+    }
 }
 
 /// Invariants of the merged workspace-bootstrap exec (docker_create.rs).
@@ -1713,3 +1783,747 @@ mod workspace_bootstrap_tests {
         assert!(WORKSPACE_BOOTSTRAP_AGENT_FALLBACK_CMD.contains(CONFIG_DIR));
     }
 }
+
+#[cfg(test)]
+mod clone_params_tests {
+    use super::*;
+
+    fn source() -> SandboxRecord {
+        SandboxRecord {
+            id: "src-1".into(),
+            container_id: "ctr-src-1".into(),
+            sidecar_url: "http://127.0.0.1:0".into(),
+            sidecar_port: 0,
+            ssh_port: Some(2222),
+            token: "source-token".into(),
+            created_at: 0,
+            cpu_cores: 2,
+            memory_mb: 2048,
+            state: SandboxState::Running,
+            idle_timeout_seconds: 600,
+            max_lifetime_seconds: 3600,
+            last_activity_at: 0,
+            stopped_at: None,
+            snapshot_image_id: None,
+            snapshot_s3_url: None,
+            container_removed_at: None,
+            image_removed_at: None,
+            original_image: "base:latest".into(),
+            base_env_json: r#"{"BASE":"1","SHARED":"base"}"#.into(),
+            user_env_json: r#"{"API_KEY":"secret"}"#.into(),
+            snapshot_destination: None,
+            tee_deployment_id: None,
+            tee_metadata_json: None,
+            tee_attestation_json: None,
+            name: "agent".into(),
+            agent_identifier: "default".into(),
+            metadata_json: r#"{"ports":[3000]}"#.into(),
+            disk_gb: 20,
+            stack: "node".into(),
+            owner: "0xowner".into(),
+            service_id: Some(7),
+            tee_config: None,
+            extra_ports: HashMap::from([(3000, 33000)]),
+            ssh_login_user: None,
+            ssh_authorized_keys: Vec::new(),
+            capabilities_json: r#"["computer_use"]"#.into(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
+        }
+    }
+
+    #[test]
+    fn clone_inherits_source_config_by_default() {
+        let params = clone_params(
+            &source(),
+            &CloneSandboxOverrides::default(),
+            "clone:img".into(),
+        );
+        assert_eq!(params.name, "agent-clone");
+        assert_eq!(params.image, "clone:img");
+        assert_eq!(params.cpu_cores, 2);
+        assert_eq!(params.memory_mb, 2048);
+        assert_eq!(params.disk_gb, 20);
+        assert_eq!(params.owner, "0xowner");
+        assert_eq!(params.service_id, Some(7));
+        assert_eq!(params.port_mappings, vec![3000]);
+        assert_eq!(params.capabilities_json, r#"["computer_use"]"#);
+        assert!(params.ssh_enabled);
+        assert!(
+            params.user_env_json.is_empty(),
+            "secrets must not be copied unless requested"
+        );
+    }
+
+    #[test]
+    fn clone_applies_overrides() {
+        let overrides = CloneSandboxOverrides {
+            name: "branch".into(),
+            env_json: r#"{"SHARED":"clone"}"#.into(),
+            memory_mb: 4096,
+            inherit_user_env: true,
+            ..Default::default()
+        };
+        let params = clone_params(&source(), &overrides, "clone:img".into());
+        assert_eq!(params.name, "branch");
+        assert_eq!(params.memory_mb, 4096);
+        assert_eq!(params.cpu_cores, 2);
+        assert_eq!(params.user_env_json, r#"{"API_KEY":"secret"}"#);
+        let env: Map<String, Value> = serde_json::from_str(&params.env_json).unwrap();
+        assert_eq!(env["BASE"], "1");
+        assert_eq!(env["SHARED"], "clone");
+    }
+
+    #[test]
+    fn clone_image_env_drops_source_values() {
+        let base = vec!["PATH=/usr/bin".to_string(), "LANG=C".to_string()];
+        let container = vec![
+            "PATH=/usr/bin".to_string(),
+            "LANG=en_US".to_string(),
+            "API_KEY=secret".to_string(),
+        ];
+        assert_eq!(
+            clone_env_changes(&container, &base),
+            vec![r#"ENV LANG="C""#, r#"ENV API_KEY="""#]
+        );
+        let base = vec![r#"PS1=\u "$ "#.to_string()];
+        assert_eq!(
+            clone_env_changes(&["PS1=>".to_string()], &base),
+            vec![r#"ENV PS1="\\u \"\$ ""#]
+        );
+        assert!(clone_env_changes(&base, &base).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod status_tests {
+    use super::*;
+
+    pub(super) fn stopped_tee_record() -> SandboxRecord {
+        SandboxRecord {
+            id: "status-1".into(),
+            container_id: "ctr-status-1".into(),
+            sidecar_url: "http://127.0.0.1:0".into(),
+            sidecar_port: 0,
+            ssh_port: None,
+            token: "tok".into(),
+            created_at: 100,
+            cpu_cores: 1,
+            memory_mb: 512,
+            state: SandboxState::Stopped,
+            idle_timeout_seconds: 0,
+            max_lifetime_seconds: 0,
+            last_activity_at: 150,
+            stopped_at: Some(160),
+            snapshot_image_id: None,
+            snapshot_s3_url: None,
+            container_removed_at: Some(170),
+            image_removed_at: None,
+            original_image: "base:latest".into(),
+            base_env_json: String::new(),
+            user_env_json: String::new(),
+            snapshot_destination: None,
+            tee_deployment_id: Some("dep-1".into()),
+            tee_metadata_json: None,
+            tee_attestation_json: Some(
+                r#"{"tee_type":"Tdx","evidence":[],"measurement":[],"timestamp":1000}"#.into(),
+            ),
+            name: String::new(),
+            agent_identifier: String::new(),
+            metadata_json: String::new(),
+            disk_gb: 0,
+            stack: String::new(),
+            owner: String::new(),
+            service_id: None,
+            tee_config: None,
+            extra_ports: HashMap::new(),
+            ssh_login_user: None,
+            ssh_authorized_keys: Vec::new(),
+            capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
+        }
+    }
+
+    #[test]
+    fn attestation_age_reads_report_timestamp() {
+        let record = stopped_tee_record();
+        assert_eq!(tee_attestation_age_secs(&record, 1600), Some(600));
+        let plain = SandboxRecord {
+            tee_attestation_json: None,
+            ..record
+        };
+        assert_eq!(tee_attestation_age_secs(&plain, 1600), None);
+    }
+
+    #[tokio::test]
+    async fn stopped_sandbox_status_skips_probes() {
+        let status = probe_sandbox_status(&stopped_tee_record()).await;
+        assert!(!status.health.healthy);
+        assert_eq!(status.container.status, "removed");
+        assert!(!status.container.running);
+        assert_eq!(status.uptime_secs, None);
+        assert_eq!(status.last_activity_at, 150);
+        assert!(status.tee_attestation_age_secs.is_some());
+    }
+}
+
+#[cfg(test)]
+mod config_update_tests {
+    use super::*;
+
+    fn docker_record() -> SandboxRecord {
+        SandboxRecord {
+            state: SandboxState::Running,
+            container_removed_at: None,
+            tee_deployment_id: None,
+            tee_attestation_json: None,
+            cpu_cores: 2,
+            memory_mb: 2048,
+            disk_gb: 10,
+            idle_timeout_seconds: 1800,
+            max_lifetime_seconds: 86400,
+            ..super::status_tests::stopped_tee_record()
+        }
+    }
+
+    fn plan(record: &SandboxRecord, update: SandboxConfigUpdate) -> Result<ConfigUpdatePlan> {
+        let mut config = super::core_logic_tests::test_config();
+        config.sandbox_max_memory_mb = 8192;
+        plan_config_update(record, &update, &config, 1000)
+    }
+
+    #[test]
+    fn lifetime_only_changes_touch_the_record() {
+        let plan = plan(
+            &docker_record(),
+            SandboxConfigUpdate {
+                idle_timeout_seconds: Some(600),
+                max_lifetime_seconds: Some(999_999),
+                web_terminal_enabled: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(plan.mode, ConfigApplyMode::RecordOnly);
+        assert_eq!(plan.idle_timeout_seconds, 600);
+        assert_eq!(plan.max_lifetime_seconds, 172800, "clamped to operator max");
+        assert_eq!(
+            plan.changed,
+            vec![
+                "idle_timeout_seconds",
+                "max_lifetime_seconds",
+                "web_terminal_enabled"
+            ]
+        );
+        let mut record = docker_record();
+        plan.apply_to(&mut record);
+        assert_eq!(
+            serde_json::from_str::<Value>(&record.metadata_json).unwrap()
+                [WEB_TERMINAL_METADATA_KEY],
+            true
+        );
+    }
+
+    #[test]
+    fn resource_changes_pick_live_or_recreate() {
+        let live = plan(
+            &docker_record(),
+            SandboxConfigUpdate {
+                cpu_cores: Some(4),
+                memory_mb: Some(4096),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(live.mode, ConfigApplyMode::Live);
+
+        let disk = plan(
+            &docker_record(),
+            SandboxConfigUpdate {
+                disk_gb: Some(20),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(disk.mode, ConfigApplyMode::Recreate);
+
+        let unlimited_cpu = plan(
+            &docker_record(),
+            SandboxConfigUpdate {
+                cpu_cores: Some(0),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(unlimited_cpu.mode, ConfigApplyMode::Recreate);
+    }
+
+    #[test]
+    fn invalid_updates_are_rejected() {
+        let record = docker_record();
+        assert!(plan(&record, SandboxConfigUpdate::default()).is_err());
+        assert!(
+            plan(
+                &record,
+                SandboxConfigUpdate {
+                    cpu_cores: Some(2),
+                    ..Default::default()
+                },
+            )
+            .is_err(),
+            "unchanged values are not a diff"
+        );
+        assert!(
+            plan(
+                &record,
+                SandboxConfigUpdate {
+                    memory_mb: Some(16384),
+                    ..Default::default()
+                },
+            )
+            .is_err()
+        );
+        assert!(
+            plan(
+                &record,
+                SandboxConfigUpdate {
+                    max_lifetime_seconds: Some(500),
+                    ..Default::default()
+                },
+            )
+            .is_err(),
+            "created_at 100 + 500 is already past now=1000"
+        );
+        let tee = SandboxRecord {
+            tee_deployment_id: Some("dep".into()),
+            ..docker_record()
+        };
+        assert!(
+            plan(
+                &tee,
+                SandboxConfigUpdate {
+                    memory_mb: Some(4096),
+                    ..Default::default()
+                },
+            )
+            .is_err()
+        );
+    }
+}
+
+mod repair_tests {
+    use super::*;
+
+    fn running_record() -> SandboxRecord {
+        SandboxRecord {
+            state: SandboxState::Running,
+            container_removed_at: None,
+            tee_deployment_id: None,
+            tee_attestation_json: None,
+            ..super::status_tests::stopped_tee_record()
+        }
+    }
+
+    fn container(status: &str, running: bool) -> ContainerStatus {
+        ContainerStatus {
+            status: status.into(),
+            running,
+            ..Default::default()
+        }
+    }
+
+    fn health(healthy: bool) -> SidecarHealthProbe {
+        SidecarHealthProbe {
+            healthy,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn healthy_sandbox_has_no_issues() {
+        let issues = diagnose_repair_issues(
+            &running_record(),
+            &container("running", true),
+            &health(true),
+            Some(true),
+        );
+        assert!(issues.is_empty());
+        let issues = diagnose_repair_issues(
+            &running_record(),
+            &container("running", true),
+            &health(true),
+            None,
+        );
+        assert!(
+            issues.is_empty(),
+            "inconclusive token probe is not an issue"
+        );
+    }
+
+    #[test]
+    fn broken_sandbox_reports_the_root_issue() {
+        let record = running_record();
+        let cases = [
+            (
+                container("missing", false),
+                false,
+                None,
+                RepairIssue::ContainerMissing,
+            ),
+            (
+                container("exited", false),
+                false,
+                None,
+                RepairIssue::ContainerNotRunning,
+            ),
+            (
+                container("running", true),
+                false,
+                None,
+                RepairIssue::SidecarUnhealthy,
+            ),
+            (
+                container("running", true),
+                true,
+                Some(false),
+                RepairIssue::TokenMismatch,
+            ),
+        ];
+        for (container, healthy, token, expected) in cases {
+            assert_eq!(
+                diagnose_repair_issues(&record, &container, &health(healthy), token),
+                vec![expected]
+            );
+        }
+    }
+
+    #[test]
+    fn stopped_sandbox_is_only_broken_when_its_container_is_gone() {
+        let stopped = SandboxRecord {
+            state: SandboxState::Stopped,
+            ..running_record()
+        };
+        assert!(
+            diagnose_repair_issues(&stopped, &container("exited", false), &health(false), None)
+                .is_empty()
+        );
+        assert_eq!(
+            diagnose_repair_issues(&stopped, &container("missing", false), &health(false), None),
+            vec![RepairIssue::ContainerMissing]
+        );
+        let reaped = SandboxRecord {
+            container_removed_at: Some(1),
+            ..stopped
+        };
+        assert!(
+            diagnose_repair_issues(&reaped, &container("removed", false), &health(false), None)
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn tee_sandboxes_cannot_be_repaired() {
+        super::tee_tests::init();
+        let record = SandboxRecord {
+            id: "repair-tee".into(),
+            ..super::status_tests::stopped_tee_record()
+        };
+        sandboxes()
+            .unwrap()
+            .insert(record.id.clone(), record.clone())
+            .unwrap();
+        let err = repair_sandbox(&record.id, None).await.unwrap_err();
+        assert!(err.to_string().contains("TEE"), "got: {err}");
+        sandboxes().unwrap().remove(&record.id).unwrap();
+    }
+}
+
+mod token_rotation_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tee_sandbox_tokens_cannot_be_rotated() {
+        super::tee_tests::init();
+        let record = SandboxRecord {
+            id: "rotate-tee".into(),
+            ..super::status_tests::stopped_tee_record()
+        };
+        sandboxes()
+            .unwrap()
+            .insert(record.id.clone(), record.clone())
+            .unwrap();
+        let err = rotate_sidecar_token(&record.id, "0xowner", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SandboxError::Unsupported(_)), "got: {err}");
+        assert_eq!(get_sandbox_by_id(&record.id).unwrap().token, record.token);
+        sandboxes().unwrap().remove(&record.id).unwrap();
+    }
+}
+
+mod ssh_expiry_tests {
+    use super::*;
+
+    fn key(public_key: &str, expires_at: Option<u64>) -> SshAuthorizedKey {
+        SshAuthorizedKey {
+            username: "agent".into(),
+            public_key: public_key.into(),
+            provisioned_at: 100,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn ttl_becomes_absolute_expiry() {
+        assert_eq!(ssh_key_expiry(1000, None).unwrap(), None);
+        assert_eq!(ssh_key_expiry(1000, Some(0)).unwrap(), None);
+        assert_eq!(ssh_key_expiry(1000, Some(60)).unwrap(), Some(1060));
+        assert!(ssh_key_expiry(1000, Some(MAX_SSH_KEY_TTL_SECS + 1)).is_err());
+    }
+
+    #[test]
+    fn keys_stored_without_expiry_fields_still_load() {
+        let parsed: SshAuthorizedKey =
+            serde_json::from_str(r#"{"username":"agent","public_key":"ssh-ed25519 AAAA"}"#)
+                .unwrap();
+        assert_eq!(parsed.provisioned_at, 0);
+        assert_eq!(parsed.expires_at, None);
+    }
+
+    #[test]
+    fn reprovisioning_a_key_replaces_its_expiry() {
+        super::tee_tests::init();
+        let record = SandboxRecord {
+            id: "ssh-expiry-reprovision".into(),
+            ..super::status_tests::stopped_tee_record()
+        };
+        sandboxes()
+            .unwrap()
+            .insert(record.id.clone(), record.clone())
+            .unwrap();
+
+        persist_ssh_key_assignment(&record.id, "agent", "ssh-ed25519 AAAA", Some(500)).unwrap();
+        persist_ssh_key_assignment(&record.id, "agent", "ssh-ed25519 AAAA", None).unwrap();
+        let keys = list_ssh_keys(&record.id).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].expires_at, None);
+        assert!(keys[0].provisioned_at > 0);
+
+        sandboxes().unwrap().remove(&record.id).unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_expiry_revocations_are_kept_for_retry() {
+        super::tee_tests::init();
+        // No SSH port: the revoke command cannot run, so the expired key must
+        // stay recorded for the next reaper tick.
+        let record = SandboxRecord {
+            id: "ssh-expiry-retry".into(),
+            state: SandboxState::Running,
+            ssh_authorized_keys: vec![
+                key("ssh-ed25519 EXPIRED", Some(200)),
+                key("ssh-ed25519 FRESH", Some(5000)),
+                key("ssh-ed25519 PERMANENT", None),
+            ],
+            ..super::status_tests::stopped_tee_record()
+        };
+        sandboxes()
+            .unwrap()
+            .insert(record.id.clone(), record.clone())
+            .unwrap();
+
+        assert_eq!(revoke_expired_ssh_keys(&record, 1000).await, 0);
+        assert_eq!(list_ssh_keys(&record.id).unwrap().len(), 3);
+
+        sandboxes().unwrap().remove(&record.id).unwrap();
+    }
+}
+
+mod ssh_key_listing_tests {
+    use super::*;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAYtf+1EJKzC0lYeIHeeIexjB/e6Sqf4l7Ma2jOSSyc/";
+
+    #[test]
+    fn fingerprint_matches_ssh_keygen() {
+        let (_, blob, _) = parse_authorized_key_line(KEY).unwrap();
+        assert_eq!(
+            ssh_key_fingerprint(&blob).as_deref(),
+            Some("SHA256:6c70bKlqRgdGp46j3X6iXad78DBJWNRfqUHlPKsYxz4")
+        );
+        assert_eq!(ssh_key_fingerprint("not base64!"), None);
+    }
+
+    #[test]
+    fn authorized_key_lines_skip_options_and_comments() {
+        let (key_type, _, comment) =
+            parse_authorized_key_line(&format!("no-pty,from=\"10.0.0.1\" {KEY} alice@laptop"))
+                .unwrap();
+        assert_eq!(key_type, "ssh-ed25519");
+        assert_eq!(comment, "alice@laptop");
+        assert!(parse_authorized_key_line("# managed by operator").is_none());
+        assert!(parse_authorized_key_line("   ").is_none());
+    }
+
+    #[test]
+    fn listing_joins_file_with_provisioning_records() {
+        let manual = "ssh-rsa AAAAB3NzaC1yc2EAAAADAQAB bob@desk";
+        let stale = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBBB removed@host";
+        let recorded = vec![
+            SshAuthorizedKey {
+                username: "agent".into(),
+                public_key: format!("{KEY} alice@laptop"),
+                provisioned_at: 100,
+                expires_at: Some(200),
+            },
+            SshAuthorizedKey {
+                username: "agent".into(),
+                public_key: stale.into(),
+                provisioned_at: 50,
+                expires_at: None,
+            },
+        ];
+        // The file copy has no comment; matching is by key material.
+        let file = format!("{KEY}\n{manual}\n");
+
+        let keys = merge_ssh_key_listing("agent", &file, &recorded);
+        assert_eq!(keys.len(), 3);
+        assert!(keys[0].installed);
+        assert_eq!(keys[0].provisioned_at, Some(100));
+        assert_eq!(keys[0].expires_at, Some(200));
+        assert_eq!(keys[1].comment, "bob@desk");
+        assert_eq!(keys[1].provisioned_at, None);
+        assert!(!keys[2].installed);
+        assert_eq!(keys[2].comment, "removed@host");
+    }
+
+    #[test]
+    fn sshd_accepted_login_lines_are_parsed() {
+        let line = "sshd[311]: Accepted publickey for agent from 172.17.0.1 port 40522 ssh2: ED25519 SHA256:6c70bKlqRgdGp46j3X6iXad78DBJWNRfqUHlPKsYxz4";
+        let (user, source, fingerprint) = parse_sshd_accepted_login(line).unwrap();
+        assert_eq!(user, "agent");
+        assert_eq!(source, "172.17.0.1");
+        assert_eq!(
+            fingerprint.as_deref(),
+            Some("SHA256:6c70bKlqRgdGp46j3X6iXad78DBJWNRfqUHlPKsYxz4")
+        );
+        assert!(
+            parse_sshd_accepted_login("sshd[311]: Failed password for root from 1.2.3.4").is_none()
+        );
+    }
+}
+
+#[cfg(test)]
+mod image_pull_tests {
+    use super::*;
+    use docktopus::bollard::models::{CreateImageInfo, ProgressDetail};
+
+    fn info(id: &str, status: &str, progress: Option<(i64, i64)>) -> CreateImageInfo {
+        CreateImageInfo {
+            id: Some(id.to_string()),
+            status: Some(status.to_string()),
+            progress_detail: progress.map(|(current, total)| ProgressDetail {
+                current: Some(current),
+                total: Some(total),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn image_reference_gets_latest_only_without_tag_or_digest() {
+        assert_eq!(image_reference_with_tag("ubuntu"), "ubuntu:latest");
+        assert_eq!(
+            image_reference_with_tag("localhost:5000/sidecar"),
+            "localhost:5000/sidecar:latest"
+        );
+        assert_eq!(
+            image_reference_with_tag("ghcr.io/x/sidecar:v1"),
+            "ghcr.io/x/sidecar:v1"
+        );
+        assert_eq!(
+            image_reference_with_tag("sidecar@sha256:abc"),
+            "sidecar@sha256:abc"
+        );
+    }
+
+    #[test]
+    fn pull_tracker_counts_layers_and_bytes() {
+        let mut tracker = PullTracker::default();
+        assert!(!tracker.apply(&info("latest", "Pulling from tangle/sidecar", None)));
+        assert!(tracker.apply(&info("aaa", "Already exists", None)));
+        tracker.apply(&info("bbb", "Pulling fs layer", None));
+        tracker.apply(&info("ccc", "Waiting", None));
+        tracker.apply(&info("bbb", "Downloading", Some((30, 100))));
+        tracker.apply(&info("ccc", "Downloading", Some((0, 300))));
+
+        let progress = tracker.progress();
+        assert_eq!(progress.layers_total, 3);
+        assert_eq!(progress.layers_done, 1);
+        assert_eq!(progress.bytes_total, 400);
+        assert_eq!(progress.bytes_done, 30);
+
+        tracker.apply(&info("bbb", "Download complete", None));
+        assert!(tracker.apply(&info("bbb", "Pull complete", None)));
+        assert!(!tracker.apply(&info("bbb", "Pull complete", None)));
+        let progress = tracker.progress();
+        assert_eq!(progress.layers_done, 2);
+        assert_eq!(progress.bytes_done, 100);
+        assert_eq!(progress.pct(), 25);
+    }
+}
+
+#[cfg(test)]
+mod adopt_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn adopt_without_container_records_sandbox_as_gone() {
+        super::tee_tests::init();
+        let recovered = RecoveredSandbox {
+            sandbox_id: "sandbox-adopt-tee-1".into(),
+            params: CreateSandboxParams {
+                name: "recovered".into(),
+                owner: "0xabcdef".into(),
+                service_id: Some(7),
+                cpu_cores: 2,
+                memory_mb: 2048,
+                tee_config: Some(crate::tee::TeeConfig {
+                    required: true,
+                    tee_type: crate::tee::TeeType::Tdx,
+                    attestation_nonce: None,
+                }),
+                ..Default::default()
+            },
+            sidecar_url: "http://10.0.0.5:9100".into(),
+            token: "chain-token".into(),
+            ssh_port: None,
+        };
+
+        let outcome = adopt_sandbox(recovered.clone()).await.unwrap();
+        assert_eq!(outcome, AdoptOutcome::Missing);
+
+        let record = get_sandbox_by_id("sandbox-adopt-tee-1").unwrap();
+        assert_eq!(record.owner, "0xabcdef");
+        assert_eq!(record.service_id, Some(7));
+        assert_eq!(record.token, "chain-token");
+        assert_eq!(record.sidecar_port, 9100);
+        assert_eq!(record.state, SandboxState::Stopped);
+        assert!(record.container_removed_at.is_some());
+
+        // Re-running the bootstrap leaves the record alone.
+        assert_eq!(
+            adopt_sandbox(recovered).await.unwrap(),
+            AdoptOutcome::AlreadyKnown
+        );
+        let _ = sandboxes().unwrap().remove("sandbox-adopt-tee-1");
+    }
+}
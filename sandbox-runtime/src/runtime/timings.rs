@@ -48,6 +48,11 @@ pub struct CreateTimings {
     pub warm_claim: Option<Duration>,
     /// SSH readiness bootstrap; only present when `ssh_enabled`.
     pub ssh_ready: Option<Duration>,
+    /// Sidecar API negotiation (`/health` read at provision, see
+    /// [`crate::sidecar_compat`]). Includes the wait for the sidecar to
+    /// answer, so it absorbs boot time callers used to spend in their own
+    /// health wait.
+    pub api_negotiation: Option<Duration>,
     /// End-to-end create as observed by the caller, including permit wait.
     pub total: Duration,
 }
@@ -78,6 +83,7 @@ impl CreateTimings {
         push_stage(&mut out, "warm_claim", self.warm_claim);
         push_stage(&mut out, "store_insert", self.store_insert);
         push_stage(&mut out, "ssh_ready", self.ssh_ready);
+        push_stage(&mut out, "api_negotiation", self.api_negotiation);
         out
     }
 
@@ -0,0 +1,47 @@
+use super::*;
+
+/// Rotate a sandbox's sidecar auth token.
+///
+/// Mints a fresh token and recreates the sidecar with it as
+/// `SIDECAR_AUTH_TOKEN`, replaying env, secrets, ports and identity from the
+/// stored record. The recreate writes the new token into the record in the
+/// same store update that records the new container, and the old container
+/// (the only thing that accepted the old token) is deleted, so the old token
+/// stops working as soon as this returns. Callers must hold the sandbox's
+/// lifecycle lock.
+///
+/// TEE sandboxes are rejected: recreating them would invalidate attestation.
+pub async fn rotate_sidecar_token(
+    sandbox_id: &str,
+    actor: &str,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<SandboxRecord> {
+    let old = get_sandbox_by_id(sandbox_id)?;
+    if old.tee_deployment_id.is_some() {
+        return Err(SandboxError::Unsupported(
+            "Token rotation is not supported for TEE sandboxes".into(),
+        ));
+    }
+
+    let new_token = crate::auth::generate_token();
+    let rotated =
+        recreate_sidecar_impl(sandbox_id, &old.user_env_json, None, Some(&new_token), tee)
+            .await
+            .inspect_err(|e| {
+                crate::audit_log::record_event(
+                    sandbox_id,
+                    crate::audit_log::AuditAction::SidecarTokenRotationFailed,
+                    actor,
+                    Some(e.to_string()),
+                );
+            })?;
+
+    crate::audit_log::record_event(
+        sandbox_id,
+        crate::audit_log::AuditAction::SidecarTokenRotated,
+        actor,
+        None,
+    );
+    tracing::info!(sandbox_id, "sidecar token rotated");
+    Ok(rotated)
+}
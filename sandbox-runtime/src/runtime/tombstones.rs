@@ -0,0 +1,144 @@
+//! Tombstones for sandboxes removed by GC (`sandbox_tombstones.json`).
+//!
+//! When GC drops a sandbox record it leaves a tombstone behind: who owned
+//! the sandbox, when it was removed, and where its data still lives, i.e.
+//! the pre-GC workspace archive (see `reaper::archive`) and any user-owned
+//! snapshot GC leaves in place. Tombstones are pruned after
+//! [`TOMBSTONE_RETENTION_SECS`]; the archives themselves are never deleted by
+//! the operator.
+
+use super::*;
+use crate::store::PersistentStore;
+
+/// How long tombstones are kept (90 days).
+pub const TOMBSTONE_RETENTION_SECS: u64 = 90 * 24 * 60 * 60;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SandboxTombstone {
+    pub sandbox_id: String,
+    pub owner: String,
+    #[serde(default)]
+    pub service_id: Option<u64>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub stopped_at: Option<u64>,
+    /// Workspace archive written before GC removed the container.
+    #[serde(default)]
+    pub archive_url: Option<String>,
+    #[serde(default)]
+    pub archived_at: Option<u64>,
+    /// Why the last archive attempt failed. Still set after removal when GC
+    /// gave up and removed the container without an archive.
+    #[serde(default)]
+    pub archive_error: Option<String>,
+    /// Failed archive attempts since the last successful one.
+    #[serde(default)]
+    pub archive_attempts: u32,
+    #[serde(default)]
+    pub archive_failed_at: Option<u64>,
+    /// User-owned (BYOS3) snapshot that GC left in place.
+    #[serde(default)]
+    pub snapshot_url: Option<String>,
+    /// When GC removed the record; `None` while only the archive exists.
+    #[serde(default)]
+    pub removed_at: Option<u64>,
+}
+
+impl SandboxTombstone {
+    fn for_record(record: &SandboxRecord) -> Self {
+        Self {
+            sandbox_id: record.id.clone(),
+            owner: record.owner.clone(),
+            service_id: record.service_id,
+            created_at: record.created_at,
+            stopped_at: record.stopped_at,
+            archive_url: None,
+            archived_at: None,
+            archive_error: None,
+            archive_attempts: 0,
+            archive_failed_at: None,
+            snapshot_url: None,
+            removed_at: None,
+        }
+    }
+}
+
+static TOMBSTONES: OnceCell<PersistentStore<SandboxTombstone>> = OnceCell::new();
+
+/// Access the tombstone store, initializing it on first call.
+pub fn tombstones() -> Result<&'static PersistentStore<SandboxTombstone>> {
+    TOMBSTONES
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("sandbox_tombstones.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+fn upsert(record: &SandboxRecord, f: impl FnOnce(&mut SandboxTombstone)) -> Result<()> {
+    let store = tombstones()?;
+    let mut tombstone = store
+        .get(&record.id)?
+        .unwrap_or_else(|| SandboxTombstone::for_record(record));
+    f(&mut tombstone);
+    store.insert(record.id.clone(), tombstone)
+}
+
+/// Remember where the workspace of `record` was archived before GC.
+pub fn record_archive(record: &SandboxRecord, archive_url: &str, now: u64) -> Result<()> {
+    upsert(record, |t| {
+        t.archive_url = Some(archive_url.to_string());
+        t.archived_at = Some(now);
+        t.archive_error = None;
+        t.archive_attempts = 0;
+    })
+}
+
+/// Record a failed archive attempt for `record`; returns the number of
+/// failed attempts so far.
+pub fn record_archive_failure(record: &SandboxRecord, error: &str, now: u64) -> Result<u32> {
+    let mut attempts = 0;
+    upsert(record, |t| {
+        t.archive_error = Some(error.to_string());
+        t.archive_attempts = t.archive_attempts.saturating_add(1);
+        t.archive_failed_at = Some(now);
+        attempts = t.archive_attempts;
+    })?;
+    Ok(attempts)
+}
+
+/// Mark `record` as removed by GC. `snapshot_url` is a user-owned snapshot
+/// that outlives the record.
+pub fn record_removal(record: &SandboxRecord, snapshot_url: Option<&str>, now: u64) -> Result<()> {
+    upsert(record, |t| {
+        t.stopped_at = record.stopped_at;
+        t.snapshot_url = snapshot_url.map(str::to_string);
+        t.removed_at = Some(now);
+    })
+}
+
+/// The tombstone of `sandbox_id`, if `owner` owned it.
+pub fn get_tombstone(sandbox_id: &str, owner: &str) -> Result<SandboxTombstone> {
+    tombstones()?
+        .get(sandbox_id)?
+        .filter(|t| t.owner.eq_ignore_ascii_case(owner))
+        .ok_or_else(|| SandboxError::NotFound(format!("No tombstone for sandbox '{sandbox_id}'")))
+}
+
+/// Drop tombstones whose sandbox was removed (or, failing that, archived or
+/// last failed to archive) more than `max_age_secs` ago.
+pub fn gc_tombstones(max_age_secs: u64) -> Result<()> {
+    let cutoff = crate::util::now_ts().saturating_sub(max_age_secs);
+    let store = tombstones()?;
+    for tombstone in store.values()? {
+        if tombstone
+            .removed_at
+            .or(tombstone.archived_at)
+            .or(tombstone.archive_failed_at)
+            .is_some_and(|at| at <= cutoff)
+        {
+            store.remove(&tombstone.sandbox_id)?;
+        }
+    }
+    Ok(())
+}
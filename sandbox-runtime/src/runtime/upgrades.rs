@@ -11,11 +11,15 @@ use super::*;
 ///
 /// Returns the new [`SandboxRecord`] for the recreated container.
 /// The sidecar image the operator is currently configured to run
-/// (`SIDECAR_IMAGE` env, falling back to the build-time default). This is the
-/// target for fleet image upgrades — see [`upgrade_sidecar_image`].
+/// (`SIDECAR_IMAGE_<ARCH>` for the host architecture, else `SIDECAR_IMAGE`,
+/// falling back to the build-time default). This is the target for fleet
+/// image upgrades — see [`upgrade_sidecar_image`].
 #[must_use]
 pub fn current_sidecar_image() -> String {
-    env::var("SIDECAR_IMAGE").unwrap_or_else(|_| DEFAULT_SIDECAR_IMAGE.to_string())
+    image_for_arch(
+        &env::var("SIDECAR_IMAGE").unwrap_or_else(|_| DEFAULT_SIDECAR_IMAGE.to_string()),
+        &host_arch(),
+    )
 }
 
 /// List sandboxes whose container was created from an image other than the
@@ -137,7 +141,21 @@ pub async fn upgrade_sidecar_image(
 ) -> Result<SandboxRecord> {
     let old = get_sandbox_by_id(sandbox_id)?;
     let preserved_user_env = old.user_env_json.clone();
-    recreate_sidecar_impl(sandbox_id, &preserved_user_env, Some(target_image), tee).await
+    let record = recreate_sidecar_impl(
+        sandbox_id,
+        &preserved_user_env,
+        Some(target_image),
+        None,
+        tee,
+    )
+    .await?;
+    record_lifecycle_event(
+        sandbox_id,
+        LifecycleEventKind::Upgraded,
+        crate::audit_log::OPERATOR_ACTOR,
+        Some(target_image.to_string()),
+    );
+    Ok(record)
 }
 
 pub async fn recreate_sidecar_with_env(
@@ -145,17 +163,20 @@ pub async fn recreate_sidecar_with_env(
     user_env_json: &str,
     tee: Option<&dyn crate::tee::TeeBackend>,
 ) -> Result<SandboxRecord> {
-    recreate_sidecar_impl(sandbox_id, user_env_json, None, tee).await
+    recreate_sidecar_impl(sandbox_id, user_env_json, None, None, tee).await
 }
 
 /// Shared recreate engine. `image_override = Some(img)` swaps the sidecar onto
 /// `img` (image upgrade); `None` preserves the sandbox's existing image (the
-/// secret re-injection / wipe path). Everything else — env, token, ports,
-/// capabilities, identity — is replayed faithfully from the stored record.
+/// secret re-injection / wipe path). `token_override = Some(tok)` boots the
+/// new sidecar with `tok` (token rotation); `None` keeps the existing token.
+/// Everything else — env, ports, capabilities, identity — is replayed
+/// faithfully from the stored record.
 pub(crate) async fn recreate_sidecar_impl(
     sandbox_id: &str,
     user_env_json: &str,
     image_override: Option<&str>,
+    token_override: Option<&str>,
     tee: Option<&dyn crate::tee::TeeBackend>,
 ) -> Result<SandboxRecord> {
     let old = get_sandbox_by_id(sandbox_id)?;
@@ -170,11 +191,14 @@ pub(crate) async fn recreate_sidecar_impl(
         ));
     }
 
-    // Stop if running, then delete
+    // Stop if running, then delete. A container the reaper (or repair)
+    // already removed has nothing left to delete.
     if old.state == SandboxState::Running {
         let _ = stop_sidecar(&old).await;
     }
-    delete_sidecar(&old, tee).await?;
+    if old.container_removed_at.is_none() {
+        delete_sidecar(&old, tee).await?;
+    }
 
     // Rebuild creation params faithfully from the stored record. An explicit
     // `image_override` (fleet upgrade) wins; otherwise keep the sandbox's own
@@ -185,7 +209,7 @@ pub(crate) async fn recreate_sidecar_impl(
         None => old.original_image.clone(),
     };
 
-    let old_token = old.token.clone();
+    let token = token_override.unwrap_or(&old.token).to_string();
     let params = CreateSandboxParams {
         name: old.name.clone(),
         image,
@@ -213,12 +237,15 @@ pub(crate) async fn recreate_sidecar_impl(
         capabilities_json: old.capabilities_json.clone(),
     };
 
-    // Preserve the original token so existing workflows/references keep working.
+    // Preserve the original token unless rotating, so existing
+    // workflows/references keep working.
     let (_new_record, _attestation, _timings) =
-        create_sidecar_with_token(&params, tee, Some(&old_token), Some(&old.id)).await?;
+        create_sidecar_with_token(&params, tee, Some(&token), Some(&old.id)).await?;
     let updated = sandboxes()?.update(&old.id, |record| {
         record.ssh_login_user = old.ssh_login_user.clone();
         record.ssh_authorized_keys = old.ssh_authorized_keys.clone();
+        record.crash_history = old.crash_history.clone();
+        record.lifecycle_events = old.lifecycle_events.clone();
     })?;
     if !updated {
         return Err(SandboxError::NotFound(format!(
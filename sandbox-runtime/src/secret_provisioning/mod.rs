@@ -0,0 +1,234 @@
+//! Two-phase secret provisioning for sandboxes.
+//!
+//! Phase 1 (on-chain): Sandbox is created with base configuration only.
+//! No secrets appear in transaction calldata.
+//!
+//! Phase 2 (off-chain): The sandbox owner sends secrets via a signed HTTP
+//! request to the operator API. The operator recreates the sidecar container
+//! with the full environment (base config + secrets).
+//!
+//! This pattern ensures that API keys, private keys, and other sensitive
+//! values never touch the blockchain.
+//!
+//! Instead of raw values, phase 2 may name a HashiCorp Vault path and role
+//! ([`VaultSecretSource`]). The operator logs in with the caller's
+//! short-lived JWT, reads the secret at injection time and revokes its Vault
+//! token, so long-lived secrets never transit the operator API.
+//!
+//! Env values may also be cloud secret manager references
+//! (`{"secretRef": "aws-sm://..."}`), resolved at container creation; see
+//! [`SecretRef`].
+//!
+//! A [`RotationPolicy`] re-injects fresh values on a schedule.
+//!
+//! Each distinct user env applied is recorded as a [`SecretVersion`] (hash
+//! and key names only), and [`rollback_secrets`] re-applies the set that was
+//! active before the latest change.
+
+use serde_json::{Map, Value};
+use zeroize::Zeroizing;
+
+use crate::error::{Result, SandboxError};
+use crate::runtime::{
+    LifecycleEventKind, SandboxRecord, get_sandbox_by_id, record_lifecycle_change,
+    recreate_sidecar_with_env,
+};
+
+mod named_secrets;
+mod rotation;
+mod secret_refs;
+mod vault;
+mod versions;
+
+pub use named_secrets::{
+    MAX_NAMED_SECRET_BYTES, MAX_NAMED_SECRETS_PER_OWNER, NamedSecret, NamedSecretSummary,
+    delete_named_secret, list_named_secrets, named_secrets, redact_secret_placeholders,
+    resolve_secret_placeholders, set_named_secret, validate_secret_placeholders,
+};
+
+pub use rotation::{
+    MAX_ROTATION_INTERVAL_SECS, MIN_ROTATION_INTERVAL_SECS, RotationPolicy, RotationSource,
+    get_rotation_policy, remove_rotation_policy, rotate_secrets, rotation_policies,
+    run_due_rotations, set_rotation_policy,
+};
+
+pub use secret_refs::{
+    SECRET_REF_FIELD, SecretRef, resolve_secret_ref, resolve_secret_refs, secret_ref_uri,
+    validate_env_json_secret_refs, validate_secret_refs, validate_secret_refs_only,
+};
+
+pub use versions::{
+    MAX_SECRET_VERSIONS, SecretVersion, SecretVersionHistory, secret_env_hash,
+    secret_version_history, secret_versions,
+};
+
+pub use vault::{
+    VAULT_ADDR_ENV, VAULT_AUTH_MOUNT_ENV, VAULT_NAMESPACE_ENV, VaultSecretSource,
+    fetch_vault_secrets, resolve_secret_env,
+};
+
+/// Inject user secrets into a sandbox by recreating it with merged environment.
+///
+/// The sandbox's `base_env_json` is preserved. The provided `secret_env` is
+/// stored as `user_env_json` and merged on top of the base at container creation.
+/// User values override base values when keys collide.
+///
+/// **TEE restriction:** This function is not supported for TEE sandboxes because
+/// recreation would invalidate the attestation, break sealed secrets, and orphan
+/// the on-chain deployment ID. TEE sandboxes should use the sealed-secrets API
+/// (`POST /tee/sealed-secrets`) instead.
+///
+/// Returns the new `SandboxRecord` for the recreated sandbox.
+pub async fn inject_secrets(
+    sandbox_id: &str,
+    secret_env: Map<String, Value>,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<SandboxRecord> {
+    // Wrap the serialized secrets so the heap-resident JSON is wiped on
+    // drop. `recreate_sidecar_with_env` borrows it as `&str`; once that
+    // call returns, the only persisted copy is the at-rest-encrypted form
+    // sealed via `SEAL_KEY`.
+    let user_env_json: Zeroizing<String> = Zeroizing::new(
+        serde_json::to_string(&secret_env)
+            .map_err(|e| SandboxError::Validation(format!("Invalid secret env: {e}")))?,
+    );
+
+    let record = apply_user_env(sandbox_id, &user_env_json, tee).await?;
+    let keys: Vec<&str> = secret_env.keys().map(String::as_str).collect();
+    record_lifecycle_change(
+        &record,
+        LifecycleEventKind::SecretsInjected,
+        Some(keys.join(", ")),
+    );
+    Ok(record)
+}
+
+/// Remove all user-injected secrets from a sandbox by recreating it with
+/// only the base environment. The `base_env_json` is preserved.
+///
+/// **TEE restriction:** Not supported for TEE sandboxes — see [`inject_secrets`].
+///
+/// Returns the new `SandboxRecord` for the recreated sandbox.
+pub async fn wipe_secrets(
+    sandbox_id: &str,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<SandboxRecord> {
+    let record = apply_user_env(sandbox_id, "", tee).await?;
+    record_lifecycle_change(&record, LifecycleEventKind::SecretsWiped, None);
+    Ok(record)
+}
+
+/// Re-apply the secret version that was active before the latest inject,
+/// wipe or rollback. Rolling back twice returns to where it started.
+///
+/// **TEE restriction:** Not supported for TEE sandboxes — see [`inject_secrets`].
+///
+/// Returns the new `SandboxRecord` and the now-active version number.
+pub async fn rollback_secrets(
+    sandbox_id: &str,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<(SandboxRecord, u32)> {
+    let history = secret_version_history(sandbox_id)?;
+    let (version, env_json) = versions::rollback_env(&history)?;
+    let env_json = Zeroizing::new(env_json);
+    let new_record = apply_user_env(sandbox_id, &env_json, tee).await?;
+    record_lifecycle_change(
+        &new_record,
+        LifecycleEventKind::SecretsInjected,
+        Some(format!("rollback to v{version}")),
+    );
+    Ok((new_record, version))
+}
+
+/// The active secret version of `sandbox_id`, `None` when no user secrets
+/// are applied or the history cannot be read.
+pub fn active_secret_version(sandbox_id: &str) -> Option<u32> {
+    secret_version_history(sandbox_id)
+        .ok()
+        .and_then(|history| history.active)
+}
+
+/// Recreate the sidecar with `user_env_json` and record the version change.
+/// Versioning is best-effort: the secrets are already applied when it runs.
+async fn apply_user_env(
+    sandbox_id: &str,
+    user_env_json: &str,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<SandboxRecord> {
+    let previous = Zeroizing::new(get_sandbox_by_id(sandbox_id)?.user_env_json);
+    let new_record = recreate_sidecar_with_env(sandbox_id, user_env_json, tee).await?;
+    if let Err(e) = versions::record_env_change(sandbox_id, &previous, user_env_json) {
+        tracing::warn!(sandbox_id, error = %e, "failed to record secret version");
+    }
+    Ok(new_record)
+}
+
+/// Update the user environment of a sandbox and apply it by recreating the sidecar.
+///
+/// By default `updates` is merged on top of the currently persisted
+/// `user_env_json`; keys mapped to `null` are removed. With `replace`, the
+/// user env is overwritten wholesale by the non-null entries of `updates`.
+/// Token, ports, SSH access and `sandbox_id` are preserved by the recreate.
+///
+/// **TEE restriction:** Not supported for TEE sandboxes — see [`inject_secrets`].
+pub async fn update_user_env(
+    sandbox_id: &str,
+    updates: Map<String, Value>,
+    replace: bool,
+    tee: Option<&dyn crate::tee::TeeBackend>,
+) -> Result<SandboxRecord> {
+    let record = get_sandbox_by_id(sandbox_id)?;
+    let merged = merge_user_env(&record.user_env_json, updates, replace)?;
+    if merged.is_empty() {
+        return wipe_secrets(sandbox_id, tee).await;
+    }
+    inject_secrets(sandbox_id, merged, tee).await
+}
+
+/// Compute the next user env from the persisted `current` JSON and `updates`,
+/// rejecting a result larger than [`crate::api_types::MAX_SECRET_KEYS`].
+pub(crate) fn merge_user_env(
+    current: &str,
+    updates: Map<String, Value>,
+    replace: bool,
+) -> Result<Map<String, Value>> {
+    let mut env: Map<String, Value> = if replace || current.trim().is_empty() {
+        Map::new()
+    } else {
+        serde_json::from_str(current)
+            .map_err(|e| SandboxError::Storage(format!("Stored user env is invalid: {e}")))?
+    };
+    for (key, value) in updates {
+        if value.is_null() {
+            env.remove(&key);
+        } else {
+            env.insert(key, value);
+        }
+    }
+    if env.len() > crate::api_types::MAX_SECRET_KEYS {
+        return Err(SandboxError::Validation(format!(
+            "User env would hold {} keys (max {})",
+            env.len(),
+            crate::api_types::MAX_SECRET_KEYS
+        )));
+    }
+    Ok(env)
+}
+
+/// Validate that the caller (identified by session address) owns the sandbox.
+pub fn validate_secret_access(sandbox_id: &str, caller_address: &str) -> Result<SandboxRecord> {
+    let record = get_sandbox_by_id(sandbox_id)?;
+    if record.owner.is_empty() {
+        return Err(SandboxError::Auth("Sandbox has no owner configured".into()));
+    }
+    if record.owner.eq_ignore_ascii_case(caller_address) {
+        Ok(record)
+    } else {
+        Err(SandboxError::Auth(format!(
+            "Address {caller_address} does not own sandbox '{sandbox_id}'"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,239 @@
+//! Owner-scoped named secrets for workflow definitions.
+//!
+//! `workflow_json` is stored in plaintext on-chain and in the operator
+//! store, so a workflow names the credentials it needs with
+//! `{"secretRef": "<name>"}` (or a cloud reference such as
+//! `{"secretRef": "aws-sm://..."}`) anywhere a string is expected. Owners
+//! register named values with `PUT /api/secrets/{name}`; they are sealed at
+//! rest and only [`resolve_secret_placeholders`] turns them back into
+//! plaintext, in memory, for the run that needs them.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::secret_refs::{resolve_secret_ref, secret_ref_uri};
+use crate::error::{Result, SandboxError};
+use crate::store::PersistentStore;
+
+/// Most named secrets one owner may hold.
+pub const MAX_NAMED_SECRETS_PER_OWNER: usize = 100;
+/// Largest value accepted for a named secret (64 KiB).
+pub const MAX_NAMED_SECRET_BYTES: usize = 64 * 1024;
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NamedSecret {
+    pub owner: String,
+    pub name: String,
+    /// Value sealed with the record encryption key.
+    pub value: String,
+    pub updated_at: u64,
+}
+
+/// What the API returns about a secret: never the value.
+#[derive(Clone, Debug, Serialize)]
+pub struct NamedSecretSummary {
+    pub name: String,
+    pub updated_at: u64,
+}
+
+static NAMED_SECRETS: OnceCell<PersistentStore<NamedSecret>> = OnceCell::new();
+
+pub fn named_secrets() -> Result<&'static PersistentStore<NamedSecret>> {
+    NAMED_SECRETS.get_or_try_init(|| {
+        let path = crate::store::state_dir().join("named-secrets.json");
+        PersistentStore::open(path)
+    })
+}
+
+fn secret_key(owner: &str, name: &str) -> String {
+    format!("{}/{name}", owner.to_ascii_lowercase())
+}
+
+fn validate_name(name: &str) -> std::result::Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Secret name '{name}' must be 1-{MAX_NAME_LEN} characters of [A-Za-z0-9_.-]"
+        ))
+    }
+}
+
+/// Names of `owner`'s secrets, sorted.
+pub fn list_named_secrets(owner: &str) -> Result<Vec<NamedSecretSummary>> {
+    let mut secrets: Vec<NamedSecretSummary> = named_secrets()?
+        .values()?
+        .into_iter()
+        .filter(|s| s.owner.eq_ignore_ascii_case(owner))
+        .map(|s| NamedSecretSummary {
+            name: s.name,
+            updated_at: s.updated_at,
+        })
+        .collect();
+    secrets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(secrets)
+}
+
+/// Create or replace `owner`'s secret `name`.
+pub fn set_named_secret(owner: &str, name: &str, value: &str) -> Result<NamedSecretSummary> {
+    validate_name(name).map_err(SandboxError::Validation)?;
+    if value.is_empty() || value.len() > MAX_NAMED_SECRET_BYTES {
+        return Err(SandboxError::Validation(format!(
+            "Secret value must be 1-{MAX_NAMED_SECRET_BYTES} bytes"
+        )));
+    }
+    let key = secret_key(owner, name);
+    let store = named_secrets()?;
+    if store.get(&key)?.is_none() && list_named_secrets(owner)?.len() >= MAX_NAMED_SECRETS_PER_OWNER
+    {
+        return Err(SandboxError::Validation(format!(
+            "At most {MAX_NAMED_SECRETS_PER_OWNER} secrets per owner"
+        )));
+    }
+    let secret = NamedSecret {
+        owner: owner.to_string(),
+        name: name.to_string(),
+        value: crate::runtime::seal_field(value)?,
+        updated_at: crate::util::now_ts(),
+    };
+    let summary = NamedSecretSummary {
+        name: secret.name.clone(),
+        updated_at: secret.updated_at,
+    };
+    store.insert(key, secret)?;
+    Ok(summary)
+}
+
+pub fn delete_named_secret(owner: &str, name: &str) -> Result<()> {
+    named_secrets()?
+        .remove(&secret_key(owner, name))?
+        .map(|_| ())
+        .ok_or_else(|| SandboxError::NotFound(format!("Secret '{name}' not found")))
+}
+
+fn named_secret_value(owner: &str, name: &str) -> Result<zeroize::Zeroizing<String>> {
+    let secret = named_secrets()?
+        .get(&secret_key(owner, name))?
+        .ok_or_else(|| SandboxError::NotFound(format!("Secret '{name}' not found")))?;
+    crate::runtime::unseal_field(&secret.value).map(zeroize::Zeroizing::new)
+}
+
+/// Apply `f` to every `{"secretRef": ...}` placeholder in `value`.
+fn visit_placeholders<E>(
+    value: &mut Value,
+    f: &mut impl FnMut(&str, &mut Value) -> std::result::Result<(), E>,
+) -> std::result::Result<(), E> {
+    if let Some(uri) = secret_ref_uri(value).map(str::to_string) {
+        return f(&uri, value);
+    }
+    match value {
+        Value::Array(items) => items.iter_mut().try_for_each(|v| visit_placeholders(v, f)),
+        Value::Object(map) => map.values_mut().try_for_each(|v| visit_placeholders(v, f)),
+        _ => Ok(()),
+    }
+}
+
+fn is_cloud_ref(uri: &str) -> bool {
+    uri.contains("://")
+}
+
+/// Check every placeholder in `value`: cloud references must parse and be
+/// enabled, named ones must exist for `owner`.
+pub fn validate_secret_placeholders(owner: &str, value: &Value) -> Result<()> {
+    let mut value = value.clone();
+    visit_placeholders(&mut value, &mut |uri, _| {
+        if is_cloud_ref(uri) {
+            return super::SecretRef::parse(uri)
+                .and_then(|secret_ref| secret_ref.ensure_enabled())
+                .map_err(SandboxError::Validation);
+        }
+        validate_name(uri).map_err(SandboxError::Validation)?;
+        match named_secrets()?.get(&secret_key(owner, uri))? {
+            Some(_) => Ok(()),
+            None => Err(SandboxError::Validation(format!(
+                "secretRef '{uri}' does not name a secret of {owner}"
+            ))),
+        }
+    })
+}
+
+/// Replace every placeholder with an empty string, for code that only
+/// inspects the definition. Returns how many were replaced.
+pub fn redact_secret_placeholders(value: &mut Value) -> usize {
+    let mut count = 0;
+    let _ = visit_placeholders::<()>(value, &mut |_, slot| {
+        *slot = Value::String(String::new());
+        count += 1;
+        Ok(())
+    });
+    count
+}
+
+/// Replace every placeholder in `value` with the secret it names: cloud
+/// references via the secret manager, names from `owner`'s secrets.
+pub async fn resolve_secret_placeholders(owner: &str, value: &mut Value) -> Result<()> {
+    let mut uris = Vec::new();
+    let _ = visit_placeholders::<()>(value, &mut |uri, _| {
+        uris.push(uri.to_string());
+        Ok(())
+    });
+    if uris.is_empty() {
+        return Ok(());
+    }
+    if owner.is_empty() {
+        return Err(SandboxError::Auth(
+            "secretRef placeholders need a workflow owner to resolve against".into(),
+        ));
+    }
+
+    let mut resolved = std::collections::HashMap::new();
+    for uri in uris {
+        if resolved.contains_key(&uri) {
+            continue;
+        }
+        let secret = if is_cloud_ref(&uri) {
+            resolve_secret_ref(&uri).await?
+        } else {
+            named_secret_value(owner, &uri)?
+        };
+        resolved.insert(uri, secret);
+    }
+    visit_placeholders::<SandboxError>(value, &mut |uri, slot| {
+        *slot = Value::String(resolved[uri].to_string());
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redaction_replaces_nested_placeholders_only() {
+        let mut value = json!({
+            "prompt": "call the API",
+            "context": {"headers": [{"secretRef": "api-key"}, "plain"]},
+            "not_a_ref": {"secretRef": "x", "other": 1},
+        });
+        assert_eq!(redact_secret_placeholders(&mut value), 1);
+        assert_eq!(value["context"]["headers"][0], "");
+        assert_eq!(value["context"]["headers"][1], "plain");
+        assert_eq!(value["not_a_ref"]["other"], 1);
+    }
+
+    #[test]
+    fn secret_names_are_restricted() {
+        assert!(validate_name("github-token.v2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("has space").is_err());
+        assert!(validate_name(&"a".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+}
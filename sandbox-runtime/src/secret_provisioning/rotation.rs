@@ -0,0 +1,317 @@
+//! Scheduled secret rotation.
+//!
+//! A sandbox owner attaches a [`RotationPolicy`] to a sandbox: an interval
+//! and a [`RotationSource`] for fresh values. The reaper tick calls
+//! [`run_due_rotations`], which re-injects the new values (recreating the
+//! sidecar, so the agent process restarts with them) and records every
+//! rotation or failure in the audit log for compliance history.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::audit_log::{AuditAction, OPERATOR_ACTOR};
+use crate::error::{Result, SandboxError};
+use crate::runtime::{SandboxState, get_sandbox_by_id};
+use crate::store::PersistentStore;
+
+/// Shortest allowed rotation interval (5 minutes).
+pub const MIN_ROTATION_INTERVAL_SECS: u64 = 300;
+/// Longest allowed rotation interval (one year).
+pub const MAX_ROTATION_INTERVAL_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Where a rotation gets its new values.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RotationSource {
+    /// Re-resolve the sandbox's `secretRef` env values from their secret
+    /// managers. Rotate the secret there; the sandbox picks it up.
+    SecretRefs,
+    /// POST `{"sandboxId", "keys"}` to `url` and merge the `env_json` object
+    /// it returns into the user env.
+    Webhook {
+        url: String,
+        /// Sent as `Authorization: Bearer`. Sealed at rest.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bearer_token: Option<String>,
+    },
+}
+
+impl RotationSource {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let RotationSource::Webhook { url, .. } = self else {
+            return Ok(());
+        };
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid webhook url: {e}"))?;
+        let loopback = matches!(parsed.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+        match parsed.scheme() {
+            "https" => Ok(()),
+            "http" if loopback => Ok(()),
+            _ => Err("webhook url must use https (http is allowed for loopback only)".into()),
+        }
+    }
+
+    /// Copy without the bearer token, for API responses.
+    pub fn redacted(&self) -> Self {
+        match self {
+            RotationSource::Webhook { url, .. } => RotationSource::Webhook {
+                url: url.clone(),
+                bearer_token: None,
+            },
+            other => other.clone(),
+        }
+    }
+}
+
+/// Rotation schedule for one sandbox, keyed by sandbox ID.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RotationPolicy {
+    pub sandbox_id: String,
+    pub interval_secs: u64,
+    pub source: RotationSource,
+    /// Address that set the policy.
+    pub created_by: String,
+    pub created_at: u64,
+    pub next_run_at: u64,
+    #[serde(default)]
+    pub last_rotated_at: Option<u64>,
+    /// Error from the last attempt, cleared by the next success.
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl RotationPolicy {
+    /// Copy safe to return from the API (no webhook token).
+    pub fn redacted(&self) -> Self {
+        Self {
+            source: self.source.redacted(),
+            ..self.clone()
+        }
+    }
+}
+
+static POLICIES: OnceCell<PersistentStore<RotationPolicy>> = OnceCell::new();
+
+/// Access the rotation policy persistent store.
+pub fn rotation_policies() -> Result<&'static PersistentStore<RotationPolicy>> {
+    POLICIES
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("secret_rotation.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Create or replace the rotation policy of `sandbox_id`. The first
+/// rotation runs one interval from now. TEE sandboxes are rejected, as for
+/// plain secret injection.
+pub fn set_rotation_policy(
+    sandbox_id: &str,
+    interval_secs: u64,
+    source: RotationSource,
+    actor: &str,
+) -> Result<RotationPolicy> {
+    if !(MIN_ROTATION_INTERVAL_SECS..=MAX_ROTATION_INTERVAL_SECS).contains(&interval_secs) {
+        return Err(SandboxError::Validation(format!(
+            "interval_secs must be between {MIN_ROTATION_INTERVAL_SECS} and {MAX_ROTATION_INTERVAL_SECS}"
+        )));
+    }
+    source.validate().map_err(SandboxError::Validation)?;
+    let record = get_sandbox_by_id(sandbox_id)?;
+    if record.tee_deployment_id.is_some() || record.tee_config.is_some() {
+        return Err(SandboxError::Validation(
+            "Secret rotation is not supported for TEE sandboxes".into(),
+        ));
+    }
+
+    let source = match source {
+        RotationSource::Webhook { url, bearer_token } => RotationSource::Webhook {
+            url,
+            bearer_token: bearer_token
+                .filter(|t| !t.is_empty())
+                .map(|t| crate::runtime::seal_field(&t))
+                .transpose()?,
+        },
+        other => other,
+    };
+    let now = crate::util::now_ts();
+    let policy = RotationPolicy {
+        sandbox_id: sandbox_id.to_string(),
+        interval_secs,
+        source,
+        created_by: actor.to_string(),
+        created_at: now,
+        next_run_at: now + interval_secs,
+        last_rotated_at: None,
+        last_error: None,
+    };
+    rotation_policies()?.insert(sandbox_id.to_string(), policy.clone())?;
+    Ok(policy)
+}
+
+pub fn get_rotation_policy(sandbox_id: &str) -> Result<Option<RotationPolicy>> {
+    rotation_policies()?.get(sandbox_id)
+}
+
+/// Remove the policy of `sandbox_id`. Returns whether one existed.
+pub fn remove_rotation_policy(sandbox_id: &str) -> Result<bool> {
+    Ok(rotation_policies()?.remove(sandbox_id)?.is_some())
+}
+
+/// Rotate `sandbox_id`'s secrets now using its policy, recording the outcome
+/// in the audit log and on the policy. Callers must hold the lifecycle lock.
+/// Returns the rotated env keys.
+pub async fn rotate_secrets(sandbox_id: &str, actor: &str) -> Result<Vec<String>> {
+    let policy = get_rotation_policy(sandbox_id)?.ok_or_else(|| {
+        SandboxError::NotFound(format!("No rotation policy for sandbox '{sandbox_id}'"))
+    })?;
+    let result =
+        crate::runtime::with_lifecycle_actor(actor, rotate_with_source(sandbox_id, &policy.source))
+            .await;
+    let now = crate::util::now_ts();
+    let (action, detail, last_error) = match &result {
+        Ok(keys) => (
+            AuditAction::SecretsRotated,
+            format!("rotated {}", keys.join(", ")),
+            None,
+        ),
+        Err(e) => (
+            AuditAction::SecretRotationFailed,
+            e.to_string(),
+            Some(e.to_string()),
+        ),
+    };
+    crate::audit_log::record_event(sandbox_id, action, actor, Some(detail));
+    rotation_policies()?.update(sandbox_id, |p| {
+        p.next_run_at = now + p.interval_secs;
+        if result.is_ok() {
+            p.last_rotated_at = Some(now);
+        }
+        p.last_error = last_error.clone();
+    })?;
+    result
+}
+
+async fn rotate_with_source(sandbox_id: &str, source: &RotationSource) -> Result<Vec<String>> {
+    let record = get_sandbox_by_id(sandbox_id)?;
+    let keys = match source {
+        RotationSource::SecretRefs => {
+            let mut keys = Vec::new();
+            for env_json in [&record.base_env_json, &record.user_env_json] {
+                if let Ok(Value::Object(env)) = serde_json::from_str::<Value>(env_json) {
+                    keys.extend(
+                        env.iter()
+                            .filter(|(_, v)| super::secret_ref_uri(v).is_some())
+                            .map(|(k, _)| k.clone()),
+                    );
+                }
+            }
+            if keys.is_empty() {
+                return Err(SandboxError::Validation(
+                    "Sandbox env has no secretRef values to rotate".into(),
+                ));
+            }
+            // Recreating with the stored env re-resolves every reference.
+            let recreated =
+                crate::runtime::recreate_sidecar_with_env(sandbox_id, &record.user_env_json, None)
+                    .await?;
+            crate::runtime::record_lifecycle_change(
+                &recreated,
+                crate::runtime::LifecycleEventKind::SecretsInjected,
+                Some(keys.join(", ")),
+            );
+            keys
+        }
+        RotationSource::Webhook { url, bearer_token } => {
+            let current: Map<String, Value> =
+                serde_json::from_str(&record.user_env_json).unwrap_or_default();
+            let token = bearer_token
+                .as_deref()
+                .map(crate::runtime::unseal_field)
+                .transpose()?;
+            let updates =
+                fetch_webhook_secrets(url, token.as_deref(), sandbox_id, current.keys().collect())
+                    .await?;
+            let keys = updates.keys().cloned().collect();
+            super::update_user_env(sandbox_id, updates, false, None).await?;
+            keys
+        }
+    };
+    let _ = crate::runtime::sync_instance_slot_record(sandbox_id);
+    crate::circuit_breaker::mark_healthy(sandbox_id);
+    Ok(keys)
+}
+
+/// Ask the rotation webhook for new values.
+pub(crate) async fn fetch_webhook_secrets(
+    url: &str,
+    bearer_token: Option<&str>,
+    sandbox_id: &str,
+    keys: Vec<&String>,
+) -> Result<Map<String, Value>> {
+    let mut request = crate::util::http_client()?
+        .post(url)
+        .json(&json!({ "sandboxId": sandbox_id, "keys": keys }));
+    if let Some(token) = bearer_token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| SandboxError::Http(format!("Rotation webhook request failed: {e}")))?;
+    if !response.status().is_success() {
+        return Err(SandboxError::Http(format!(
+            "Rotation webhook returned HTTP {}",
+            response.status()
+        )));
+    }
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| SandboxError::Http(format!("Invalid rotation webhook response: {e}")))?;
+    let env = body
+        .get("env_json")
+        .and_then(Value::as_object)
+        .cloned()
+        .ok_or_else(|| {
+            SandboxError::Validation("Rotation webhook response has no env_json object".into())
+        })?;
+    crate::api_types::validate_secrets_map(&env).map_err(SandboxError::Validation)?;
+    Ok(env)
+}
+
+/// Rotate every running sandbox whose policy is due. Policies of deleted
+/// sandboxes are dropped; stopped sandboxes rotate on the first tick after
+/// they resume. Called from the reaper tick.
+pub async fn run_due_rotations(now: u64) {
+    let policies = match rotation_policies().and_then(|s| s.values()) {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::error!(error = %e, "secret rotation: failed to read policies");
+            return;
+        }
+    };
+    for policy in policies.into_iter().filter(|p| p.next_run_at <= now) {
+        let record = match get_sandbox_by_id(&policy.sandbox_id) {
+            Ok(record) => record,
+            Err(SandboxError::NotFound(_)) => {
+                let _ = remove_rotation_policy(&policy.sandbox_id);
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(sandbox_id = %policy.sandbox_id, error = %e, "secret rotation: lookup failed");
+                continue;
+            }
+        };
+        if record.state != SandboxState::Running {
+            continue;
+        }
+        let _lock = crate::runtime::acquire_lifecycle_lock(&record.id).await;
+        match rotate_secrets(&record.id, OPERATOR_ACTOR).await {
+            Ok(keys) => tracing::info!(sandbox_id = %record.id, keys = ?keys, "rotated secrets"),
+            Err(e) => {
+                tracing::warn!(sandbox_id = %record.id, error = %e, "secret rotation failed")
+            }
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! Cloud secret manager references in env maps.
+//!
+//! An env value of the form `{"secretRef": "aws-sm://name"}` is persisted as
+//! the reference and resolved with the operator's cloud credentials each time
+//! a container is created, so the secret value is never stored by the
+//! operator. Every resolution is recorded in the audit log.
+//!
+//! - `aws-sm://<secret-id>` — AWS Secrets Manager (name or ARN), feature
+//!   `secrets-aws`.
+//! - `gcp-sm://<project>/<secret>[/<version>]` — GCP Secret Manager, feature
+//!   `secrets-gcp`. The version defaults to `latest`.
+//!
+//! Either may end in `#<field>` to select one field of a JSON secret.
+
+use serde_json::{Map, Value};
+use zeroize::Zeroizing;
+
+use crate::error::{Result, SandboxError};
+
+/// Object key marking an env value as a secret manager reference.
+pub const SECRET_REF_FIELD: &str = "secretRef";
+
+const AWS_DISABLED: &str =
+    "aws-sm:// references need an operator built with the 'secrets-aws' feature";
+const GCP_DISABLED: &str =
+    "gcp-sm:// references need an operator built with the 'secrets-gcp' feature";
+
+/// A parsed `secretRef` URI.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SecretRef {
+    AwsSecretsManager {
+        secret_id: String,
+        field: Option<String>,
+    },
+    GcpSecretManager {
+        project: String,
+        secret: String,
+        version: String,
+        field: Option<String>,
+    },
+}
+
+impl SecretRef {
+    pub fn parse(uri: &str) -> std::result::Result<Self, String> {
+        let (location, field) = match uri.split_once('#') {
+            Some((_, "")) => return Err(format!("secretRef '{uri}' has an empty #field")),
+            Some((location, field)) => (location, Some(field.to_string())),
+            None => (uri, None),
+        };
+        if location.chars().any(char::is_whitespace) {
+            return Err(format!("secretRef '{uri}' must not contain whitespace"));
+        }
+        if let Some(secret_id) = location.strip_prefix("aws-sm://") {
+            if secret_id.is_empty() {
+                return Err(format!("secretRef '{uri}' is missing the secret id"));
+            }
+            return Ok(Self::AwsSecretsManager {
+                secret_id: secret_id.to_string(),
+                field,
+            });
+        }
+        if let Some(path) = location.strip_prefix("gcp-sm://") {
+            let segments: Vec<&str> = path.split('/').collect();
+            let valid = |s: &str| {
+                !s.is_empty()
+                    && s.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            };
+            return match segments.as_slice() {
+                [project, secret] | [project, secret, _] if segments.iter().all(|s| valid(s)) => {
+                    Ok(Self::GcpSecretManager {
+                        project: project.to_string(),
+                        secret: secret.to_string(),
+                        version: segments.get(2).unwrap_or(&"latest").to_string(),
+                        field,
+                    })
+                }
+                _ => Err(format!(
+                    "secretRef '{uri}' must be gcp-sm://<project>/<secret>[/<version>]"
+                )),
+            };
+        }
+        Err(format!(
+            "secretRef '{uri}' must use the aws-sm:// or gcp-sm:// scheme"
+        ))
+    }
+
+    /// Error unless this operator was built with the reference's provider.
+    pub fn ensure_enabled(&self) -> std::result::Result<(), String> {
+        match self {
+            Self::AwsSecretsManager { .. } if !cfg!(feature = "secrets-aws") => {
+                Err(AWS_DISABLED.into())
+            }
+            Self::GcpSecretManager { .. } if !cfg!(feature = "secrets-gcp") => {
+                Err(GCP_DISABLED.into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The reference URI when `value` is `{"secretRef": "<uri>"}`.
+pub fn secret_ref_uri(value: &Value) -> Option<&str> {
+    let obj = value.as_object()?;
+    if obj.len() != 1 {
+        return None;
+    }
+    obj.get(SECRET_REF_FIELD)?.as_str()
+}
+
+/// Check that every reference in `env` parses and that its provider is
+/// enabled, so a bad reference fails at request time rather than at
+/// container creation.
+pub fn validate_secret_refs(env: &Map<String, Value>) -> std::result::Result<(), String> {
+    for (key, value) in env {
+        let is_ref = value
+            .as_object()
+            .is_some_and(|obj| obj.contains_key(SECRET_REF_FIELD));
+        if !is_ref {
+            continue;
+        }
+        let uri = secret_ref_uri(value).ok_or_else(|| {
+            format!("'{key}': a secretRef value must be exactly {{\"secretRef\": \"<uri>\"}}")
+        })?;
+        SecretRef::parse(uri)
+            .and_then(|secret_ref| secret_ref.ensure_enabled())
+            .map_err(|e| format!("'{key}': {e}"))?;
+    }
+    Ok(())
+}
+
+/// Check that `env` carries no plaintext: every value must be `null` (a
+/// removal) or a valid `{"secretRef": "<uri>"}`. Used for updates that arrive
+/// as on-chain calldata, where a literal value would be public.
+pub fn validate_secret_refs_only(env: &Map<String, Value>) -> std::result::Result<(), String> {
+    if let Some(key) = env
+        .iter()
+        .find(|(_, value)| !value.is_null() && secret_ref_uri(value).is_none())
+        .map(|(key, _)| key)
+    {
+        return Err(format!(
+            "'{key}': on-chain env updates accept only secretRef values or null; \
+             send plaintext values through the operator API secrets endpoint"
+        ));
+    }
+    validate_secret_refs(env)
+}
+
+/// [`validate_secret_refs`] for an env JSON string. Non-object input is left
+/// to the env parser to reject.
+pub fn validate_env_json_secret_refs(env_json: &str) -> Result<()> {
+    match serde_json::from_str::<Value>(env_json) {
+        Ok(Value::Object(env)) => validate_secret_refs(&env).map_err(SandboxError::Validation),
+        _ => Ok(()),
+    }
+}
+
+/// Replace every reference in the env JSON object `env_json` with the secret
+/// it names, recording an audit event per resolution against `sandbox_id`.
+/// Input without references is returned unchanged.
+pub async fn resolve_secret_refs(sandbox_id: &str, env_json: &str) -> Result<String> {
+    let Ok(Value::Object(mut env)) = serde_json::from_str::<Value>(env_json) else {
+        return Ok(env_json.to_string());
+    };
+    if !env.values().any(|v| secret_ref_uri(v).is_some()) {
+        return Ok(env_json.to_string());
+    }
+    for (key, value) in env.iter_mut() {
+        let Some(uri) = secret_ref_uri(value).map(str::to_string) else {
+            continue;
+        };
+        let secret_ref = SecretRef::parse(&uri).map_err(SandboxError::Validation)?;
+        let secret = fetch_secret(&secret_ref).await?;
+        crate::audit_log::record_secret_ref_event(sandbox_id, key, &uri);
+        *value = Value::String(secret.to_string());
+    }
+    serde_json::to_string(&env)
+        .map_err(|e| SandboxError::Validation(format!("Invalid env_json: {e}")))
+}
+
+/// Resolve one reference URI to the secret it names.
+pub async fn resolve_secret_ref(uri: &str) -> Result<Zeroizing<String>> {
+    let secret_ref = SecretRef::parse(uri).map_err(SandboxError::Validation)?;
+    secret_ref
+        .ensure_enabled()
+        .map_err(SandboxError::Validation)?;
+    fetch_secret(&secret_ref).await
+}
+
+async fn fetch_secret(secret_ref: &SecretRef) -> Result<Zeroizing<String>> {
+    match secret_ref {
+        SecretRef::AwsSecretsManager { secret_id, field } => {
+            select_secret_field(fetch_aws_secret(secret_id).await?, field.as_deref())
+        }
+        SecretRef::GcpSecretManager {
+            project,
+            secret,
+            version,
+            field,
+        } => select_secret_field(
+            fetch_gcp_secret(project, secret, version).await?,
+            field.as_deref(),
+        ),
+    }
+}
+
+/// The whole secret, or one field of it when the secret is a JSON object.
+pub(crate) fn select_secret_field(
+    raw: Zeroizing<String>,
+    field: Option<&str>,
+) -> Result<Zeroizing<String>> {
+    let Some(field) = field else {
+        return Ok(raw);
+    };
+    let parsed: Value = serde_json::from_str(&raw).map_err(|_| {
+        SandboxError::Validation(format!("secret is not JSON; cannot select field '{field}'"))
+    })?;
+    match parsed.get(field) {
+        Some(Value::String(s)) => Ok(Zeroizing::new(s.clone())),
+        Some(other) => Ok(Zeroizing::new(other.to_string())),
+        None => Err(SandboxError::Validation(format!(
+            "secret has no field '{field}'"
+        ))),
+    }
+}
+
+#[cfg(feature = "secrets-aws")]
+async fn fetch_aws_secret(secret_id: &str) -> Result<Zeroizing<String>> {
+    static CLIENT: tokio::sync::OnceCell<aws_sdk_secretsmanager::Client> =
+        tokio::sync::OnceCell::const_new();
+    let client = CLIENT
+        .get_or_init(|| async {
+            let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+                .load()
+                .await;
+            aws_sdk_secretsmanager::Client::new(&config)
+        })
+        .await;
+    let output = client
+        .get_secret_value()
+        .secret_id(secret_id)
+        .send()
+        .await
+        .map_err(|e| {
+            SandboxError::CloudProvider(format!(
+                "AWS Secrets Manager read of '{secret_id}': {}",
+                aws_sdk_secretsmanager::error::DisplayErrorContext(&e)
+            ))
+        })?;
+    output
+        .secret_string()
+        .map(|s| Zeroizing::new(s.to_string()))
+        .ok_or_else(|| {
+            SandboxError::CloudProvider(format!("AWS secret '{secret_id}' has no string value"))
+        })
+}
+
+#[cfg(not(feature = "secrets-aws"))]
+async fn fetch_aws_secret(_secret_id: &str) -> Result<Zeroizing<String>> {
+    Err(SandboxError::Validation(AWS_DISABLED.into()))
+}
+
+#[cfg(feature = "secrets-gcp")]
+async fn fetch_gcp_secret(project: &str, secret: &str, version: &str) -> Result<Zeroizing<String>> {
+    use base64::Engine;
+
+    static AUTH: tokio::sync::OnceCell<std::sync::Arc<dyn gcp_auth::TokenProvider>> =
+        tokio::sync::OnceCell::const_new();
+    let auth = AUTH
+        .get_or_try_init(|| async {
+            gcp_auth::provider()
+                .await
+                .map_err(|e| SandboxError::CloudProvider(format!("GCP auth init: {e}")))
+        })
+        .await?;
+    let token = auth
+        .token(&["https://www.googleapis.com/auth/cloud-platform"])
+        .await
+        .map_err(|e| SandboxError::CloudProvider(format!("GCP token: {e}")))?;
+    let url = format!(
+        "https://secretmanager.googleapis.com/v1/projects/{project}/secrets/{secret}/versions/{version}:access"
+    );
+    let response = crate::util::external_http_client()?
+        .get(url)
+        .bearer_auth(token.as_str())
+        .send()
+        .await
+        .map_err(|e| SandboxError::CloudProvider(format!("GCP Secret Manager request: {e}")))?;
+    if !response.status().is_success() {
+        return Err(SandboxError::CloudProvider(format!(
+            "GCP Secret Manager read of '{project}/{secret}/{version}': HTTP {}",
+            response.status()
+        )));
+    }
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| SandboxError::CloudProvider(format!("GCP Secret Manager response: {e}")))?;
+    let data = body
+        .pointer("/payload/data")
+        .and_then(Value::as_str)
+        .ok_or_else(|| SandboxError::CloudProvider("GCP secret has no payload".into()))?;
+    let bytes = Zeroizing::new(
+        base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| SandboxError::CloudProvider(format!("GCP secret payload: {e}")))?,
+    );
+    let text = std::str::from_utf8(&bytes)
+        .map_err(|_| SandboxError::CloudProvider("GCP secret is not UTF-8".into()))?;
+    Ok(Zeroizing::new(text.to_string()))
+}
+
+#[cfg(not(feature = "secrets-gcp"))]
+async fn fetch_gcp_secret(
+    _project: &str,
+    _secret: &str,
+    _version: &str,
+) -> Result<Zeroizing<String>> {
+    Err(SandboxError::Validation(GCP_DISABLED.into()))
+}
@@ -0,0 +1,557 @@
+use crate::runtime::merge_env_json;
+
+#[test]
+fn merge_env_empty_base() {
+    let result = merge_env_json("", r#"{"API_KEY": "secret123"}"#);
+    assert_eq!(result, r#"{"API_KEY":"secret123"}"#);
+}
+
+#[test]
+fn merge_env_user_overrides_base() {
+    let result = merge_env_json(
+        r#"{"BASE": "original", "OTHER": "keep"}"#,
+        r#"{"API_KEY": "secret123", "BASE": "override"}"#,
+    );
+    let parsed: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&result).unwrap();
+    assert_eq!(parsed["API_KEY"], "secret123");
+    assert_eq!(parsed["BASE"], "override");
+    assert_eq!(parsed["OTHER"], "keep");
+}
+
+#[test]
+fn merge_env_empty_user_returns_base() {
+    let result = merge_env_json(r#"{"FOO": "bar"}"#, "");
+    assert_eq!(result, r#"{"FOO": "bar"}"#);
+}
+
+#[test]
+fn merge_env_empty_object_user_returns_base() {
+    let result = merge_env_json(r#"{"FOO": "bar"}"#, "{}");
+    assert_eq!(result, r#"{"FOO": "bar"}"#);
+}
+
+#[test]
+fn merge_user_env_overwrites_and_removes_null_keys() {
+    let updates = serde_json::json!({"API_KEY": "rotated", "OLD": null})
+        .as_object()
+        .cloned()
+        .unwrap();
+    let merged = crate::secret_provisioning::merge_user_env(
+        r#"{"API_KEY": "stale", "OLD": "x", "KEEP": "y"}"#,
+        updates,
+        false,
+    )
+    .unwrap();
+    assert_eq!(merged["API_KEY"], "rotated");
+    assert_eq!(merged["KEEP"], "y");
+    assert!(!merged.contains_key("OLD"));
+}
+
+#[test]
+fn merge_user_env_replace_drops_existing_keys() {
+    let updates = serde_json::json!({"NEW": "1"})
+        .as_object()
+        .cloned()
+        .unwrap();
+    let merged =
+        crate::secret_provisioning::merge_user_env(r#"{"OLD": "x"}"#, updates, true).unwrap();
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged["NEW"], "1");
+}
+
+#[test]
+fn merge_user_env_rejects_more_than_max_keys() {
+    let max = crate::api_types::MAX_SECRET_KEYS;
+    let current: serde_json::Map<String, serde_json::Value> = (0..max)
+        .map(|i| (format!("K{i}"), serde_json::json!("v")))
+        .collect();
+    let current = serde_json::Value::Object(current).to_string();
+    let one_more = serde_json::json!({"EXTRA": "v"})
+        .as_object()
+        .cloned()
+        .unwrap();
+    let err =
+        crate::secret_provisioning::merge_user_env(&current, one_more.clone(), false).unwrap_err();
+    assert!(err.to_string().contains("max"), "{err}");
+
+    let overwrite = serde_json::json!({"K0": "w"}).as_object().cloned().unwrap();
+    assert!(crate::secret_provisioning::merge_user_env(&current, overwrite, false).is_ok());
+    assert!(crate::secret_provisioning::merge_user_env(&current, one_more, true).is_ok());
+}
+
+fn vault_source(path: &str) -> crate::secret_provisioning::VaultSecretSource {
+    crate::secret_provisioning::VaultSecretSource {
+        path: path.to_string(),
+        role: "sandbox".to_string(),
+        jwt: "eyJ.test.jwt".to_string(),
+    }
+}
+
+#[test]
+fn vault_source_rejects_traversal_and_missing_fields() {
+    assert!(vault_source("secret/data/app").validate().is_ok());
+    assert!(vault_source("/secret/data/app").validate().is_err());
+    assert!(vault_source("secret/../sys/seal").validate().is_err());
+    let mut source = vault_source("secret/data/app");
+    source.jwt = " ".into();
+    assert!(source.validate().is_err());
+    assert!(!format!("{source:?}").contains("eyJ"));
+}
+
+#[test]
+fn vault_secret_env_reads_kv_v1_and_v2() {
+    use super::vault::vault_secret_env;
+    let v2 = serde_json::json!({
+        "data": { "data": { "API_KEY": "k", "PORT": 8080 }, "metadata": { "version": 3 } }
+    });
+    let env = vault_secret_env(&v2).unwrap();
+    assert_eq!(env["API_KEY"], "k");
+    assert_eq!(env["PORT"], "8080");
+    assert!(!env.contains_key("metadata"));
+
+    let v1 = serde_json::json!({ "data": { "TOKEN": "t" } });
+    assert_eq!(vault_secret_env(&v1).unwrap()["TOKEN"], "t");
+    assert!(vault_secret_env(&serde_json::json!({ "errors": [] })).is_err());
+}
+
+#[tokio::test]
+async fn vault_fetch_logs_in_reads_and_revokes() {
+    use axum::routing::{get, post};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let revoked = Arc::new(AtomicBool::new(false));
+    let revoked_flag = revoked.clone();
+    let app = axum::Router::new()
+        .route(
+            "/v1/auth/jwt/login",
+            post(
+                |axum::Json(body): axum::Json<serde_json::Value>| async move {
+                    assert_eq!(body["role"], "sandbox");
+                    axum::Json(serde_json::json!({ "auth": { "client_token": "s.short" } }))
+                },
+            ),
+        )
+        .route(
+            "/v1/secret/data/app",
+            get(|headers: axum::http::HeaderMap| async move {
+                assert_eq!(headers["x-vault-token"], "s.short");
+                axum::Json(serde_json::json!({
+                    "data": { "data": { "OPENAI_API_KEY": "sk-vault" }, "metadata": {} }
+                }))
+            }),
+        )
+        .route(
+            "/v1/auth/token/revoke-self",
+            post(move || async move {
+                revoked_flag.store(true, Ordering::SeqCst);
+                axum::http::StatusCode::NO_CONTENT
+            }),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+
+    let env = super::vault::fetch_vault_secrets_from(
+        &addr,
+        "jwt",
+        None,
+        &vault_source("secret/data/app"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(env["OPENAI_API_KEY"], "sk-vault");
+    assert!(
+        revoked.load(Ordering::SeqCst),
+        "login token must be revoked"
+    );
+
+    let missing = super::vault::fetch_vault_secrets_from(
+        &addr,
+        "jwt",
+        None,
+        &vault_source("secret/data/missing"),
+    )
+    .await;
+    assert!(matches!(
+        missing,
+        Err(crate::error::SandboxError::Validation(_))
+    ));
+}
+
+#[test]
+fn secret_ref_uris_parse() {
+    use crate::secret_provisioning::SecretRef;
+    assert_eq!(
+        SecretRef::parse("aws-sm://prod/openai#api_key").unwrap(),
+        SecretRef::AwsSecretsManager {
+            secret_id: "prod/openai".into(),
+            field: Some("api_key".into()),
+        }
+    );
+    assert_eq!(
+        SecretRef::parse("gcp-sm://my-proj/anthropic-key").unwrap(),
+        SecretRef::GcpSecretManager {
+            project: "my-proj".into(),
+            secret: "anthropic-key".into(),
+            version: "latest".into(),
+            field: None,
+        }
+    );
+    assert!(SecretRef::parse("gcp-sm://my-proj/key/3").is_ok());
+    assert!(SecretRef::parse("gcp-sm://my-proj").is_err());
+    assert!(SecretRef::parse("gcp-sm://my-proj/../x").is_err());
+    assert!(SecretRef::parse("aws-sm://").is_err());
+    assert!(SecretRef::parse("aws-sm://name#").is_err());
+    assert!(SecretRef::parse("vault://x").is_err());
+}
+
+#[test]
+fn secret_ref_validation_rejects_malformed_refs() {
+    use crate::secret_provisioning::validate_secret_refs;
+    let extra_field = serde_json::json!({ "KEY": { "secretRef": "aws-sm://x", "other": 1 } });
+    assert!(validate_secret_refs(extra_field.as_object().unwrap()).is_err());
+    let plain = serde_json::json!({ "KEY": "value", "NESTED": { "a": 1 } });
+    assert!(validate_secret_refs(plain.as_object().unwrap()).is_ok());
+
+    let aws = serde_json::json!({ "KEY": { "secretRef": "aws-sm://prod/key" } });
+    let result = validate_secret_refs(aws.as_object().unwrap());
+    assert_eq!(result.is_ok(), cfg!(feature = "secrets-aws"), "{result:?}");
+}
+
+#[test]
+fn secret_refs_only_rejects_plaintext_values() {
+    use crate::secret_provisioning::validate_secret_refs_only;
+    let removal = serde_json::json!({ "OLD": null });
+    assert!(validate_secret_refs_only(removal.as_object().unwrap()).is_ok());
+    let plain = serde_json::json!({ "API_KEY": "sk-live", "OLD": null });
+    let err = validate_secret_refs_only(plain.as_object().unwrap()).unwrap_err();
+    assert!(err.contains("API_KEY"), "{err}");
+    let nested = serde_json::json!({ "KEY": { "value": "x" } });
+    assert!(validate_secret_refs_only(nested.as_object().unwrap()).is_err());
+
+    let aws = serde_json::json!({ "KEY": { "secretRef": "aws-sm://prod/key" } });
+    let result = validate_secret_refs_only(aws.as_object().unwrap());
+    assert_eq!(result.is_ok(), cfg!(feature = "secrets-aws"), "{result:?}");
+}
+
+#[test]
+fn secret_field_selection_reads_json_secrets() {
+    use super::secret_refs::select_secret_field;
+    use zeroize::Zeroizing;
+    let raw = || Zeroizing::new(r#"{"api_key":"sk-1","port":443}"#.to_string());
+    assert_eq!(
+        select_secret_field(raw(), None).unwrap().as_str(),
+        raw().as_str()
+    );
+    assert_eq!(
+        select_secret_field(raw(), Some("api_key"))
+            .unwrap()
+            .as_str(),
+        "sk-1"
+    );
+    assert_eq!(
+        select_secret_field(raw(), Some("port")).unwrap().as_str(),
+        "443"
+    );
+    assert!(select_secret_field(raw(), Some("missing")).is_err());
+    assert!(select_secret_field(Zeroizing::new("plain".into()), Some("k")).is_err());
+}
+
+#[tokio::test]
+async fn resolve_secret_refs_passes_plain_env_through() {
+    let env = r#"{"A":"1","B":{"nested":true}}"#;
+    let resolved = crate::secret_provisioning::resolve_secret_refs("sb-plain", env)
+        .await
+        .unwrap();
+    assert_eq!(resolved, env);
+    assert_eq!(
+        crate::secret_provisioning::resolve_secret_refs("sb-plain", "")
+            .await
+            .unwrap(),
+        ""
+    );
+}
+
+#[test]
+fn secret_refs_count_as_workflow_credentials() {
+    let env = r#"{"ANTHROPIC_API_KEY": {"secretRef": "aws-sm://prod/anthropic"}}"#;
+    assert!(crate::runtime::workflow_runtime_credentials_available(env).unwrap());
+}
+
+// ── Phase 1E: Secret Provisioning Identity Immutability Tests ────────
+
+#[test]
+fn merge_env_preserves_base_keys() {
+    // After merge then clear (empty user env), base keys must survive
+    let base = r#"{"BASE_KEY": "base_value", "SHARED": "original"}"#;
+    let user = r#"{"SECRET": "s3cr3t", "SHARED": "override"}"#;
+
+    // Step 1: merge user on top of base
+    let merged = merge_env_json(base, user);
+    let parsed: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&merged).unwrap();
+    assert_eq!(parsed["BASE_KEY"], "base_value");
+    assert_eq!(parsed["SECRET"], "s3cr3t");
+    assert_eq!(parsed["SHARED"], "override");
+
+    // Step 2: clear user secrets (merge with empty)
+    let cleared = merge_env_json(base, "");
+    let parsed_cleared: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&cleared).unwrap();
+    assert_eq!(
+        parsed_cleared["BASE_KEY"], "base_value",
+        "base key must survive inject/wipe cycle"
+    );
+    assert_eq!(
+        parsed_cleared["SHARED"], "original",
+        "base value must revert after wipe"
+    );
+    assert!(
+        !parsed_cleared.contains_key("SECRET"),
+        "user secret must be gone after wipe"
+    );
+}
+
+/// Insert a running sandbox record owned by `owner` into the store.
+fn insert_test_record(sandbox_id: &str, owner: &str, tee_deployment_id: Option<&str>) {
+    use crate::runtime::{SandboxRecord, SandboxState, sandboxes, seal_record};
+
+    // Ensure store is initialized
+    let dir = std::env::temp_dir().join(format!("secret-prov-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).ok();
+    unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", &dir) };
+
+    let mut record = SandboxRecord {
+        id: sandbox_id.to_string(),
+        container_id: format!("ctr-{sandbox_id}"),
+        sidecar_url: "http://localhost:9999".to_string(),
+        sidecar_port: 9999,
+        ssh_port: None,
+        token: "test".into(),
+        created_at: 1_700_000_000,
+        cpu_cores: 1,
+        memory_mb: 1024,
+        state: SandboxState::Running,
+        idle_timeout_seconds: 1800,
+        max_lifetime_seconds: 86400,
+        last_activity_at: 1_700_000_000,
+        stopped_at: None,
+        snapshot_image_id: None,
+        snapshot_s3_url: None,
+        container_removed_at: None,
+        image_removed_at: None,
+        original_image: "test:latest".into(),
+        base_env_json: r#"{"BASE":"val"}"#.into(),
+        user_env_json: String::new(),
+        snapshot_destination: None,
+        tee_deployment_id: tee_deployment_id.map(str::to_string),
+        tee_metadata_json: None,
+        tee_attestation_json: None,
+        name: "test".into(),
+        agent_identifier: String::new(),
+        metadata_json: "{}".into(),
+        disk_gb: 10,
+        stack: String::new(),
+        owner: owner.to_string(),
+        service_id: None,
+        tee_config: None,
+        extra_ports: std::collections::HashMap::new(),
+        ssh_login_user: None,
+        ssh_authorized_keys: Vec::new(),
+        capabilities_json: String::new(),
+        sidecar_api: None,
+        image_digest: None,
+        crash_history: Vec::new(),
+        lifecycle_events: Vec::new(),
+        clone_image: None,
+    };
+    seal_record(&mut record).unwrap();
+    sandboxes()
+        .unwrap()
+        .insert(sandbox_id.to_string(), record)
+        .unwrap();
+}
+
+#[test]
+fn validate_secret_access_same_id_after_wipe() {
+    // Verifies that sandbox_id is stable across inject/wipe by testing
+    // the validate_secret_access function's ID-based lookup. Since we
+    // can't run full sidecar recreation in unit tests, we verify the
+    // ID-based access function works correctly.
+    use crate::runtime::sandboxes;
+
+    let sandbox_id = "secret-id-stable-1";
+    let owner = "0xSECRETOWNER00000000000000000000000001";
+    insert_test_record(sandbox_id, owner, None);
+
+    // Validate access returns the same sandbox_id
+    let accessed = crate::secret_provisioning::validate_secret_access(sandbox_id, owner)
+        .expect("should validate");
+    assert_eq!(
+        accessed.id, sandbox_id,
+        "sandbox_id must be stable across access validation"
+    );
+
+    // Simulate wipe: update user_env_json to empty
+    sandboxes()
+        .unwrap()
+        .update(sandbox_id, |r| {
+            r.user_env_json = String::new();
+        })
+        .unwrap();
+
+    // Re-validate: same sandbox_id
+    let accessed_after = crate::secret_provisioning::validate_secret_access(sandbox_id, owner)
+        .expect("should still validate after wipe");
+    assert_eq!(
+        accessed_after.id, sandbox_id,
+        "sandbox_id must be immutable across secrets inject/wipe"
+    );
+}
+
+#[test]
+fn rotation_policy_validates_interval_source_and_tee() {
+    use crate::secret_provisioning::{RotationSource, set_rotation_policy};
+    insert_test_record("rotation-validate-1", "0xROTATIONOWNER", None);
+    insert_test_record("rotation-tee-1", "0xROTATIONOWNER", Some("tee-deploy-1"));
+
+    let too_short = set_rotation_policy(
+        "rotation-validate-1",
+        60,
+        RotationSource::SecretRefs,
+        "0xROTATIONOWNER",
+    );
+    assert!(too_short.is_err());
+    let plain_http = RotationSource::Webhook {
+        url: "http://rotator.example.com/keys".into(),
+        bearer_token: None,
+    };
+    assert!(
+        set_rotation_policy("rotation-validate-1", 3600, plain_http, "0xROTATIONOWNER").is_err()
+    );
+    let tee = set_rotation_policy(
+        "rotation-tee-1",
+        3600,
+        RotationSource::SecretRefs,
+        "0xROTATIONOWNER",
+    );
+    assert!(tee.is_err(), "TEE sandboxes must be rejected");
+}
+
+#[test]
+fn rotation_policy_seals_and_redacts_webhook_token() {
+    use crate::secret_provisioning::{
+        RotationSource, get_rotation_policy, remove_rotation_policy, set_rotation_policy,
+    };
+    insert_test_record("rotation-store-1", "0xROTATIONOWNER", None);
+    let source = RotationSource::Webhook {
+        url: "https://rotator.example.com/keys".into(),
+        bearer_token: Some("hook-secret".into()),
+    };
+    let policy = set_rotation_policy("rotation-store-1", 3600, source, "0xROTATIONOWNER").unwrap();
+    assert_eq!(policy.next_run_at, policy.created_at + 3600);
+
+    let stored = get_rotation_policy("rotation-store-1").unwrap().unwrap();
+    let RotationSource::Webhook { bearer_token, .. } = &stored.source else {
+        panic!("expected webhook source");
+    };
+    assert_ne!(
+        bearer_token.as_deref(),
+        Some("hook-secret"),
+        "token sealed at rest"
+    );
+    let redacted = serde_json::to_string(&stored.redacted()).unwrap();
+    assert!(!redacted.contains("bearer_token"));
+
+    assert!(remove_rotation_policy("rotation-store-1").unwrap());
+    assert!(get_rotation_policy("rotation-store-1").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn rotation_webhook_returns_validated_env() {
+    use axum::routing::post;
+
+    let app =
+        axum::Router::new().route(
+            "/rotate",
+            post(
+                |headers: axum::http::HeaderMap,
+                 axum::Json(body): axum::Json<serde_json::Value>| async move {
+                    assert_eq!(headers["authorization"], "Bearer hook-secret");
+                    assert_eq!(body["sandboxId"], "rotation-hook-1");
+                    axum::Json(serde_json::json!({ "env_json": { "API_KEY": "rotated" } }))
+                },
+            ),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/rotate", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+
+    let key = "API_KEY".to_string();
+    let env = super::rotation::fetch_webhook_secrets(
+        &url,
+        Some("hook-secret"),
+        "rotation-hook-1",
+        vec![&key],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env["API_KEY"], "rotated");
+}
+
+#[test]
+fn secret_env_hash_ignores_key_order() {
+    use crate::secret_provisioning::secret_env_hash;
+    let a = serde_json::json!({"A": "1", "B": "2"});
+    let b = serde_json::json!({"B": "2", "A": "1"});
+    let c = serde_json::json!({"A": "1", "B": "3"});
+    let hash = secret_env_hash(a.as_object().unwrap());
+    assert!(hash.starts_with("sha256:"));
+    assert_eq!(hash, secret_env_hash(b.as_object().unwrap()));
+    assert_ne!(hash, secret_env_hash(c.as_object().unwrap()));
+}
+
+#[test]
+fn secret_versions_record_changes_and_toggle_on_rollback() {
+    use super::versions::{record_env_change, rollback_env};
+    use crate::secret_provisioning::{secret_version_history, secret_versions};
+    insert_test_record("versions-1", "0xVERSIONOWNER", None);
+    let v1 = r#"{"API_KEY":"sk-alpha-111"}"#;
+    let v2 = r#"{"API_KEY":"sk-beta-222"}"#;
+
+    // Env applied before versioning becomes version 1.
+    assert_eq!(record_env_change("versions-1", v1, v2).unwrap(), Some(2));
+    // Re-injecting the active set adds no version.
+    assert_eq!(record_env_change("versions-1", v2, v2).unwrap(), Some(2));
+    let history = secret_version_history("versions-1").unwrap();
+    assert_eq!(history.versions.len(), 2);
+    assert_eq!(history.versions[0].keys, vec!["API_KEY".to_string()]);
+
+    let (target, env) = rollback_env(&history).unwrap();
+    assert_eq!((target, env.as_str()), (1, v1));
+    assert_eq!(record_env_change("versions-1", v2, v1).unwrap(), Some(1));
+    // A second rollback returns to version 2.
+    let (target, env) = rollback_env(&secret_version_history("versions-1").unwrap()).unwrap();
+    assert_eq!((target, env.as_str()), (2, v2));
+
+    // Wiping leaves no active version but keeps the wiped set restorable.
+    assert_eq!(record_env_change("versions-1", v1, "").unwrap(), None);
+    let history = secret_version_history("versions-1").unwrap();
+    assert_eq!(history.active, None);
+    assert_eq!(history.rollback_target().map(|v| v.version), Some(1));
+
+    // Only the sealed form is persisted.
+    let stored =
+        serde_json::to_string(&secret_versions().unwrap().get("versions-1").unwrap()).unwrap();
+    assert!(!stored.contains("sk-alpha") && !stored.contains("sk-beta"));
+}
+
+#[test]
+fn secret_rollback_without_previous_version_is_rejected() {
+    use super::versions::{record_env_change, rollback_env};
+    use crate::secret_provisioning::secret_version_history;
+    insert_test_record("versions-2", "0xVERSIONOWNER", None);
+    assert!(rollback_env(&secret_version_history("versions-2").unwrap()).is_err());
+    record_env_change("versions-2", "", r#"{"API_KEY":"first"}"#).unwrap();
+    assert!(rollback_env(&secret_version_history("versions-2").unwrap()).is_err());
+}
@@ -0,0 +1,183 @@
+//! HashiCorp Vault secret source: the operator logs in with the caller's
+//! JWT, reads one secret and revokes its token.
+
+use std::fmt;
+
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use zeroize::Zeroizing;
+
+use crate::error::{Result, SandboxError};
+
+/// Env var holding the Vault server address, e.g. `https://vault.internal:8200`.
+pub const VAULT_ADDR_ENV: &str = "VAULT_ADDR";
+/// Env var naming the Vault JWT auth mount (default `jwt`).
+pub const VAULT_AUTH_MOUNT_ENV: &str = "VAULT_AUTH_MOUNT";
+/// Env var holding an optional Vault Enterprise namespace.
+pub const VAULT_NAMESPACE_ENV: &str = "VAULT_NAMESPACE";
+
+const DEFAULT_VAULT_AUTH_MOUNT: &str = "jwt";
+
+/// Vault location of secrets the operator fetches at injection time.
+#[derive(Clone, Deserialize)]
+pub struct VaultSecretSource {
+    /// API path under `/v1/`, e.g. `secret/data/my-app` (KV v2) or
+    /// `kv/my-app` (KV v1).
+    pub path: String,
+    /// Vault role to log in as through the JWT auth method.
+    pub role: String,
+    /// Short-lived identity token presented to Vault for `role`.
+    pub jwt: String,
+}
+
+impl fmt::Debug for VaultSecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VaultSecretSource")
+            .field("path", &self.path)
+            .field("role", &self.role)
+            .field("jwt", &"<redacted>")
+            .finish()
+    }
+}
+
+impl VaultSecretSource {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let path = self.path.trim();
+        if path.is_empty() || path.starts_with('/') {
+            return Err("vault.path must be a non-empty path relative to /v1/".into());
+        }
+        if path.split('/').any(|seg| seg.is_empty() || seg == "..") {
+            return Err("vault.path must not contain empty or '..' segments".into());
+        }
+        if self.role.trim().is_empty() {
+            return Err("vault.role is required".into());
+        }
+        if self.jwt.trim().is_empty() {
+            return Err("vault.jwt is required".into());
+        }
+        Ok(())
+    }
+}
+
+/// Secrets to inject: the entries read from `vault` (if any) with `env_json`
+/// merged on top, so explicit values win on key collisions.
+pub async fn resolve_secret_env(
+    env_json: Map<String, Value>,
+    vault: Option<&VaultSecretSource>,
+) -> Result<Map<String, Value>> {
+    let Some(source) = vault else {
+        return Ok(env_json);
+    };
+    let mut env = fetch_vault_secrets(source).await?;
+    env.extend(env_json);
+    Ok(env)
+}
+
+/// Read the secret at `source.path` from the Vault configured through
+/// [`VAULT_ADDR_ENV`].
+pub async fn fetch_vault_secrets(source: &VaultSecretSource) -> Result<Map<String, Value>> {
+    let addr = std::env::var(VAULT_ADDR_ENV)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| {
+            SandboxError::Unavailable(format!(
+                "Vault is not configured on this operator ({VAULT_ADDR_ENV} is unset)"
+            ))
+        })?;
+    let mount = std::env::var(VAULT_AUTH_MOUNT_ENV)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_VAULT_AUTH_MOUNT.to_string());
+    let namespace = std::env::var(VAULT_NAMESPACE_ENV).ok();
+    fetch_vault_secrets_from(&addr, &mount, namespace.as_deref(), source).await
+}
+
+/// Log in to the Vault at `addr` as `source.role`, read `source.path`, then
+/// revoke the login token whatever the read returned.
+pub(crate) async fn fetch_vault_secrets_from(
+    addr: &str,
+    mount: &str,
+    namespace: Option<&str>,
+    source: &VaultSecretSource,
+) -> Result<Map<String, Value>> {
+    source.validate().map_err(SandboxError::Validation)?;
+    let client = crate::util::external_http_client()?;
+    let base = addr.trim_end_matches('/');
+    let with_namespace = |req: reqwest::RequestBuilder| match namespace {
+        Some(ns) if !ns.trim().is_empty() => req.header("X-Vault-Namespace", ns),
+        _ => req,
+    };
+
+    let login = with_namespace(client.post(format!("{base}/v1/auth/{mount}/login")))
+        .json(&json!({ "role": source.role, "jwt": source.jwt }))
+        .send()
+        .await
+        .map_err(|e| SandboxError::Http(format!("Vault login request failed: {e}")))?;
+    if !login.status().is_success() {
+        return Err(SandboxError::Validation(format!(
+            "Vault login as role '{}' failed: HTTP {}",
+            source.role,
+            login.status()
+        )));
+    }
+    let login: Value = login
+        .json()
+        .await
+        .map_err(|e| SandboxError::Http(format!("Invalid Vault login response: {e}")))?;
+    let token = Zeroizing::new(
+        login
+            .pointer("/auth/client_token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| SandboxError::Http("Vault login response has no client token".into()))?
+            .to_string(),
+    );
+
+    let read = with_namespace(client.get(format!("{base}/v1/{}", source.path.trim())))
+        .header("X-Vault-Token", token.as_str())
+        .send()
+        .await;
+    if let Err(e) = with_namespace(client.post(format!("{base}/v1/auth/token/revoke-self")))
+        .header("X-Vault-Token", token.as_str())
+        .send()
+        .await
+    {
+        tracing::warn!(error = %e, "failed to revoke Vault token after secret read");
+    }
+    let read = read.map_err(|e| SandboxError::Http(format!("Vault read request failed: {e}")))?;
+    if !read.status().is_success() {
+        return Err(SandboxError::Validation(format!(
+            "Vault read of '{}' failed: HTTP {}",
+            source.path,
+            read.status()
+        )));
+    }
+    let body: Value = read
+        .json()
+        .await
+        .map_err(|e| SandboxError::Http(format!("Invalid Vault read response: {e}")))?;
+    vault_secret_env(&body)
+}
+
+/// Env entries from a Vault read response. KV v2 nests the secret under
+/// `data.data`, KV v1 returns it directly under `data`. Non-string values
+/// are JSON-encoded.
+pub(crate) fn vault_secret_env(body: &Value) -> Result<Map<String, Value>> {
+    let data = body
+        .get("data")
+        .and_then(Value::as_object)
+        .ok_or_else(|| SandboxError::Validation("Vault secret has no data".into()))?;
+    let secret = match (data.get("data"), data.get("metadata")) {
+        (Some(Value::Object(inner)), Some(_)) => inner,
+        _ => data,
+    };
+    Ok(secret
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(_) => value.clone(),
+                other => Value::String(other.to_string()),
+            };
+            (key.clone(), value)
+        })
+        .collect())
+}
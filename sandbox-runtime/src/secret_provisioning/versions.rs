@@ -0,0 +1,179 @@
+//! Secret set versioning.
+//!
+//! Every distinct user env applied to a sandbox becomes an immutable
+//! [`SecretVersion`] identified by a SHA-256 hash of its canonical JSON, so
+//! callers can tell which set is active without the operator keeping
+//! plaintext history. The one exception is the set most recently replaced:
+//! it is kept sealed with `SEAL_KEY` (like the record's own env) so
+//! [`super::rollback_secrets`] can re-apply it. Older versions keep only
+//! their hash and key names.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, SandboxError};
+use crate::store::PersistentStore;
+
+/// Versions kept per sandbox; older entries are dropped.
+pub const MAX_SECRET_VERSIONS: usize = 50;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SecretVersion {
+    pub version: u32,
+    /// `sha256:<hex>` of the canonical env JSON.
+    pub hash: String,
+    pub keys: Vec<String>,
+    pub created_at: u64,
+    /// Sealed env of the most recently replaced version, kept for rollback.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sealed_env: Option<String>,
+}
+
+impl SecretVersion {
+    /// Whether [`super::rollback_secrets`] can re-apply this version.
+    pub fn restorable(&self) -> bool {
+        self.sealed_env.is_some()
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SecretVersionHistory {
+    pub sandbox_id: String,
+    /// Active version, `None` when no user secrets are applied.
+    pub active: Option<u32>,
+    pub versions: Vec<SecretVersion>,
+}
+
+impl SecretVersionHistory {
+    /// The version [`super::rollback_secrets`] would re-apply.
+    pub fn rollback_target(&self) -> Option<&SecretVersion> {
+        self.versions
+            .iter()
+            .rev()
+            .find(|v| v.restorable() && Some(v.version) != self.active)
+    }
+}
+
+static VERSIONS: OnceCell<PersistentStore<SecretVersionHistory>> = OnceCell::new();
+
+/// Access the secret version persistent store.
+pub fn secret_versions() -> Result<&'static PersistentStore<SecretVersionHistory>> {
+    VERSIONS
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("secret_versions.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Version history of `sandbox_id` (empty when nothing was ever injected).
+pub fn secret_version_history(sandbox_id: &str) -> Result<SecretVersionHistory> {
+    Ok(secret_versions()?
+        .get(sandbox_id)?
+        .unwrap_or_else(|| SecretVersionHistory {
+            sandbox_id: sandbox_id.to_string(),
+            ..Default::default()
+        }))
+}
+
+/// Parse a user env JSON string; empty means no secrets.
+fn parse_env(env_json: &str) -> Result<Option<Map<String, Value>>> {
+    if env_json.trim().is_empty() {
+        return Ok(None);
+    }
+    let env: Map<String, Value> = serde_json::from_str(env_json)
+        .map_err(|e| SandboxError::Storage(format!("Stored user env is invalid: {e}")))?;
+    Ok((!env.is_empty()).then_some(env))
+}
+
+/// `sha256:<hex>` over the env with keys in sorted order.
+pub fn secret_env_hash(env: &Map<String, Value>) -> String {
+    let sorted: std::collections::BTreeMap<&String, &Value> = env.iter().collect();
+    let canonical = serde_json::to_string(&sorted).unwrap_or_default();
+    format!(
+        "sha256:{}",
+        hex::encode(Sha256::digest(canonical.as_bytes()))
+    )
+}
+
+/// Record that the sandbox's user env changed from `previous` to `current`
+/// (both as stored in `user_env_json`). Re-applying the active set adds no
+/// version. Returns the now-active version.
+pub(crate) fn record_env_change(
+    sandbox_id: &str,
+    previous: &str,
+    current: &str,
+) -> Result<Option<u32>> {
+    let mut history = secret_version_history(sandbox_id)?;
+    let previous_env = parse_env(previous)?;
+    let current_env = parse_env(current)?;
+    let now = crate::util::now_ts();
+
+    // Env applied before versioning existed becomes version 1.
+    if history.versions.is_empty()
+        && let Some(env) = &previous_env
+    {
+        history.versions.push(new_version(1, env, now));
+        history.active = Some(1);
+    }
+
+    let active = match &current_env {
+        None => None,
+        Some(env) => {
+            let hash = secret_env_hash(env);
+            match history.versions.iter().find(|v| v.hash == hash) {
+                Some(existing) => Some(existing.version),
+                None => {
+                    let next = history.versions.last().map_or(1, |v| v.version + 1);
+                    history.versions.push(new_version(next, env, now));
+                    Some(next)
+                }
+            }
+        }
+    };
+    let left = history.active.filter(|v| Some(*v) != active);
+    set_active(&mut history, active, left, previous)?;
+    let keep_from = history.versions.len().saturating_sub(MAX_SECRET_VERSIONS);
+    history.versions.drain(..keep_from);
+    secret_versions()?.insert(sandbox_id.to_string(), history)?;
+    Ok(active)
+}
+
+fn new_version(version: u32, env: &Map<String, Value>, now: u64) -> SecretVersion {
+    SecretVersion {
+        version,
+        hash: secret_env_hash(env),
+        keys: env.keys().cloned().collect(),
+        created_at: now,
+        sealed_env: None,
+    }
+}
+
+/// Make `active` current. When a version `left` was replaced, its env
+/// (`left_env`) is sealed onto it as the only rollback copy.
+fn set_active(
+    history: &mut SecretVersionHistory,
+    active: Option<u32>,
+    left: Option<u32>,
+    left_env: &str,
+) -> Result<()> {
+    if let Some(left) = left {
+        let sealed = crate::runtime::seal_field(left_env)?;
+        for v in history.versions.iter_mut() {
+            v.sealed_env = (v.version == left).then(|| sealed.clone());
+        }
+    }
+    history.active = active;
+    Ok(())
+}
+
+/// Env JSON of the rollback target, unsealed.
+pub(crate) fn rollback_env(history: &SecretVersionHistory) -> Result<(u32, String)> {
+    let target = history.rollback_target().ok_or_else(|| {
+        SandboxError::Validation("No previous secret version to roll back to".into())
+    })?;
+    let sealed = target.sealed_env.as_deref().unwrap_or_default();
+    Ok((target.version, crate::runtime::unseal_field(sealed)?))
+}
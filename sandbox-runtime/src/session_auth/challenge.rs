@@ -4,23 +4,43 @@ use super::*;
 
 /// Generate a random challenge nonce for EIP-191 signing.
 ///
+/// When the client names the `address` it will sign with (and optionally the
+/// `chain_id` it is connected to), both are embedded in the signed message
+/// and stored with the challenge, and the exchange rejects a signature that
+/// recovers to any other address. A locked address gets no challenge (the
+/// HTTP handler also refuses a locked client IP).
+///
 /// Returns an error if the challenge store is at capacity ([`MAX_CHALLENGES`]),
 /// preventing memory exhaustion from unauthenticated requests.
-pub fn create_challenge() -> Result<Challenge> {
+pub fn create_challenge(address: Option<&str>, chain_id: Option<u64>) -> Result<Challenge> {
+    let address = address.map(normalize_address).transpose()?;
+    if let Some(address) = &address {
+        ensure_not_locked(&LockoutKey::address(address))?;
+    }
+
     let mut nonce_bytes = [0u8; 32];
     OsRng.fill_bytes(&mut nonce_bytes);
     let nonce = hex::encode(nonce_bytes);
     let now = now_secs();
 
-    let message = format!(
-        "Sign this message to authenticate with Tangle Sandbox.\n\nNonce: {nonce}\nExpires: {}",
-        now + CHALLENGE_TTL_SECS,
-    );
+    let mut message = String::from("Sign this message to authenticate with Tangle Sandbox.\n\n");
+    if let Some(address) = &address {
+        message.push_str(&format!("Address: {address}\n"));
+    }
+    if let Some(chain_id) = chain_id {
+        message.push_str(&format!("Chain ID: {chain_id}\n"));
+    }
+    message.push_str(&format!(
+        "Nonce: {nonce}\nExpires: {}",
+        now + CHALLENGE_TTL_SECS
+    ));
 
     let challenge = Challenge {
         nonce: nonce.clone(),
         message,
         expires_at: now + CHALLENGE_TTL_SECS,
+        address,
+        chain_id,
     };
 
     let mut map = CHALLENGES.lock().unwrap_or_else(|e| e.into_inner());
@@ -34,16 +54,33 @@ pub fn create_challenge() -> Result<Challenge> {
     Ok(challenge)
 }
 
-/// Consume and validate a challenge nonce. Returns the challenge message if valid.
-pub(crate) fn consume_challenge(nonce: &str) -> Result<String> {
-    let mut map = CHALLENGES.lock().unwrap_or_else(|e| e.into_inner());
-    let challenge = map
+/// Consume a challenge nonce, expired or not: the exchange checks expiry
+/// after the signature (see [`exchange_signature_for_token`]).
+pub(crate) fn take_challenge(nonce: &str) -> Result<Challenge> {
+    CHALLENGES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
         .remove(nonce)
-        .ok_or_else(|| SandboxError::Auth("Challenge not found or already consumed".into()))?;
+        .ok_or_else(|| SandboxError::Auth("Challenge not found or already consumed".into()))
+}
 
-    if now_secs() > challenge.expires_at {
-        return Err(SandboxError::Auth("Challenge expired".into()));
-    }
+/// The address a pending challenge is bound to, without consuming it.
+pub fn challenge_address(nonce: &str) -> Option<String> {
+    CHALLENGES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(nonce)
+        .and_then(|c| c.address.clone())
+}
 
-    Ok(challenge.message)
+/// Lowercase `0x`-prefixed 20-byte hex address, the form signatures recover to.
+fn normalize_address(address: &str) -> Result<String> {
+    let address = address.trim().to_ascii_lowercase();
+    let hex = address.strip_prefix("0x").unwrap_or(&address);
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(SandboxError::Validation(format!(
+            "Invalid address {address:?}: expected 0x followed by 40 hex characters"
+        )));
+    }
+    Ok(format!("0x{hex}"))
 }
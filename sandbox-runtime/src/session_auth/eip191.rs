@@ -48,6 +48,7 @@ pub fn verify_eip191_signature(message: &str, signature_hex: &str) -> Result<Str
     Ok(address)
 }
 
+/// Keccak-256 of `data`, the crate's one implementation.
 pub(crate) fn keccak256(data: &[u8]) -> [u8; 32] {
     use tiny_keccak::{Hasher, Keccak};
     let mut hasher = Keccak::v256();
@@ -56,3 +57,8 @@ pub(crate) fn keccak256(data: &[u8]) -> [u8; 32] {
     hasher.finalize(&mut output);
     output
 }
+
+/// [`keccak256`] as `0x`-prefixed lowercase hex.
+pub(crate) fn keccak256_hex(data: &[u8]) -> String {
+    format!("0x{}", hex::encode(keccak256(data)))
+}
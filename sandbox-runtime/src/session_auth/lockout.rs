@@ -0,0 +1,172 @@
+//! Progressive lockout after repeated sign-in failures.
+//!
+//! Failures are counted per client IP (every failed
+//! `POST /api/auth/session`, whatever it claimed) and per address, the latter
+//! only for failures whose signature proves the caller holds the address's
+//! key: binding a challenge to someone else's address and failing it counts
+//! against the caller's IP alone. After
+//! [`MAX_AUTH_FAILURES`] failures within [`FAILURE_WINDOW_SECS`] the
+//! subject is locked for [`LOCKOUT_SECS`], doubling with each further lockout
+//! up to [`MAX_LOCKOUT_SECS`]. A successful sign-in clears its address's
+//! record (not the IP's); escalation is otherwise forgotten
+//! [`LOCKOUT_MEMORY_SECS`] after the last lockout ends.
+
+use std::net::IpAddr;
+use std::sync::atomic::Ordering;
+
+use super::*;
+use crate::metrics;
+
+/// Failed exchanges tolerated per subject within the window.
+pub const MAX_AUTH_FAILURES: u32 = 5;
+/// Window in which failures are counted.
+pub const FAILURE_WINDOW_SECS: u64 = 900;
+/// Length of the first lockout.
+pub const LOCKOUT_SECS: u64 = 900;
+/// Upper bound on an escalated lockout.
+pub const MAX_LOCKOUT_SECS: u64 = 86_400;
+/// How long past its last lockout a subject's escalation is remembered.
+pub const LOCKOUT_MEMORY_SECS: u64 = 86_400;
+
+/// What a lockout applies to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum LockoutKey {
+    /// Lowercased `0x` address.
+    Address(String),
+    Ip(IpAddr),
+}
+
+impl LockoutKey {
+    pub fn address(address: &str) -> Self {
+        Self::Address(address.to_ascii_lowercase())
+    }
+}
+
+impl std::fmt::Display for LockoutKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "address {address}"),
+            Self::Ip(ip) => write!(f, "IP {ip}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AuthFailures {
+    pub(crate) count: u32,
+    pub(crate) first_at: u64,
+    pub(crate) locked_until: Option<u64>,
+    /// Lockouts so far; the next one lasts `LOCKOUT_SECS * 2^lockouts`.
+    pub(crate) lockouts: u32,
+}
+
+/// A subject's failure record, as shown on the operator lockout endpoint.
+#[derive(Clone, Debug, Serialize)]
+pub struct LockoutStatus {
+    #[serde(flatten)]
+    pub key: LockoutKey,
+    /// Failures in the current window.
+    pub failures: u32,
+    pub lockouts: u32,
+    /// Unix seconds the current lockout ends, when locked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked_until: Option<u64>,
+}
+
+pub(crate) static AUTH_FAILURES: Lazy<Mutex<HashMap<LockoutKey, AuthFailures>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Seconds until `key` may sign in again, `None` when it is not locked.
+pub fn locked_for(key: &LockoutKey) -> Option<u64> {
+    let now = now_secs();
+    AUTH_FAILURES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(key)
+        .and_then(|f| f.locked_until)
+        .filter(|until| *until > now)
+        .map(|until| until - now)
+}
+
+pub(crate) fn ensure_not_locked(key: &LockoutKey) -> Result<()> {
+    match locked_for(key) {
+        Some(secs) => {
+            metrics::auth_lockout_rejections().fetch_add(1, Ordering::Relaxed);
+            Err(SandboxError::Auth(format!(
+                "Too many failed sign-ins for {key}; try again in {secs}s"
+            )))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Count a failed sign-in against `key`, locking it at the threshold.
+pub(crate) fn record_auth_failure(key: &LockoutKey) {
+    let now = now_secs();
+    metrics::auth_failures().fetch_add(1, Ordering::Relaxed);
+    let mut map = AUTH_FAILURES.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = map.entry(key.clone()).or_default();
+    if now.saturating_sub(entry.first_at) > FAILURE_WINDOW_SECS {
+        entry.count = 0;
+        entry.first_at = now;
+    }
+    entry.count += 1;
+    if entry.count >= MAX_AUTH_FAILURES {
+        let secs = LOCKOUT_SECS
+            .saturating_mul(1u64 << entry.lockouts.min(16))
+            .min(MAX_LOCKOUT_SECS);
+        entry.lockouts += 1;
+        entry.count = 0;
+        entry.first_at = now;
+        entry.locked_until = Some(now + secs);
+        metrics::auth_lockouts().fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(
+            subject = %key,
+            lockouts = entry.lockouts,
+            lockout_secs = secs,
+            "Locking out after repeated sign-in failures"
+        );
+    }
+}
+
+/// Forget `key`'s failures and lift any lockout. Returns whether it had a
+/// record.
+pub fn clear_lockout(key: &LockoutKey) -> bool {
+    AUTH_FAILURES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(key)
+        .is_some()
+}
+
+/// Every subject with a failure record, locked ones first.
+pub fn list_lockouts() -> Vec<LockoutStatus> {
+    let now = now_secs();
+    let mut list: Vec<LockoutStatus> = AUTH_FAILURES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(key, f)| LockoutStatus {
+            key: key.clone(),
+            failures: f.count,
+            lockouts: f.lockouts,
+            locked_until: f.locked_until.filter(|until| *until > now),
+        })
+        .collect();
+    list.sort_by_key(|s| std::cmp::Reverse(s.locked_until));
+    list
+}
+
+/// Drop records whose window, lockout and escalation memory have all passed.
+pub(crate) fn gc_auth_failures(now: u64) {
+    AUTH_FAILURES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|_, f| {
+            let locked_until = f.locked_until.unwrap_or(0);
+            locked_until > now
+                || now.saturating_sub(f.first_at) <= FAILURE_WINDOW_SECS
+                || (f.lockouts > 0 && now.saturating_sub(locked_until) <= LOCKOUT_MEMORY_SECS)
+        });
+}
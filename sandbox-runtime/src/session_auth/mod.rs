@@ -4,10 +4,21 @@
 //! and protobuf deps while providing multi-tenant wallet-based auth.
 //!
 //! Flow:
-//! 1. Client requests a challenge: `POST /api/auth/challenge`
+//! 1. Client requests a challenge: `POST /api/auth/challenge`, naming the
+//!    address (and chain ID) it will sign with
 //! 2. Client signs the challenge with their wallet (EIP-191 personal_sign)
 //! 3. Client exchanges the signature for a session token: `POST /api/auth/session`
 //! 4. Client includes the PASETO token in `Authorization: Bearer <token>` headers
+//!
+//! Browsers cannot set headers on `EventSource`/`WebSocket` connections, so a
+//! session can also mint a short-lived terminal token scoped to one sandbox
+//! (see [`issue_terminal_token`]) that the terminal relay accepts as a
+//! `?token=` query parameter.
+//!
+//! Challenges bound to an address only accept that address's signature;
+//! repeated failures from one client IP lock it out, for longer each time
+//! (see [`locked_for`]). An address is only locked by failures signed with
+//! its own key.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -24,12 +35,16 @@ use crate::error::{Result, SandboxError};
 mod challenge;
 mod eip191;
 mod extractor;
+mod lockout;
 mod session;
+mod terminal;
 
 pub use challenge::*;
 pub use eip191::*;
 pub use extractor::*;
+pub use lockout::*;
 pub use session::*;
+pub use terminal::*;
 
 #[cfg(test)]
 mod tests;
@@ -46,6 +61,12 @@ pub(crate) const SESSION_TTL_SECS: u64 = 3600;
 pub(crate) const MAX_CHALLENGES: usize = 10_000;
 /// Maximum number of active sessions to prevent memory exhaustion.
 pub(crate) const MAX_SESSIONS: usize = 50_000;
+/// Terminal token TTL in seconds (5 minutes).
+pub const TERMINAL_TOKEN_TTL_SECS: u64 = 300;
+/// Prefix that tells terminal tokens apart from PASETO session tokens.
+pub(crate) const TERMINAL_TOKEN_PREFIX: &str = "term_";
+/// Maximum number of outstanding terminal tokens.
+pub(crate) const MAX_TERMINAL_TOKENS: usize = 50_000;
 
 // ---------------------------------------------------------------------------
 // Types
@@ -56,6 +77,11 @@ pub struct Challenge {
     pub nonce: String,
     pub message: String,
     pub expires_at: u64,
+    /// Address the signature must recover to, when the client named one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -72,6 +98,22 @@ pub struct SessionClaims {
     pub expires_at: u64,
 }
 
+/// A terminal token handed to the browser. Only valid for the terminal relay
+/// of `sandbox_id`, and only for the wallet that minted it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerminalToken {
+    pub token: String,
+    pub sandbox_id: String,
+    pub expires_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerminalTokenClaims {
+    pub address: String,
+    pub sandbox_id: String,
+    pub expires_at: u64,
+}
+
 // ---------------------------------------------------------------------------
 // In-memory stores
 // ---------------------------------------------------------------------------
@@ -89,6 +131,11 @@ pub(crate) static SESSIONS: Lazy<Mutex<HashMap<String, SessionClaims>>> =
 pub(crate) static REVOKED: Lazy<Mutex<HashMap<String, u64>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
+/// Outstanding terminal tokens. Not persisted: they live for minutes, and a
+/// restarted operator simply has the browser mint a new one.
+pub(crate) static TERMINAL_TOKENS: Lazy<Mutex<HashMap<String, TerminalTokenClaims>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 pub(crate) fn now_secs() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -54,9 +54,35 @@ pub(crate) fn derive_symmetric_key(ikm: &[u8]) -> Zeroizing<[u8; 32]> {
 }
 
 /// Verify a challenge signature and issue a PASETO session token.
+///
+/// A challenge bound to an address must be signed by that address. Failures
+/// count towards the address's lockout only once the signature proves the
+/// caller holds its key (a valid signature over an expired challenge), so a
+/// third party cannot lock an address out by binding challenges to it; every
+/// other failure is counted against the client IP by the HTTP handler.
 pub fn exchange_signature_for_token(nonce: &str, signature_hex: &str) -> Result<SessionToken> {
-    let message = consume_challenge(nonce)?;
-    let address = verify_eip191_signature(&message, signature_hex)?;
+    let challenge = take_challenge(nonce)?;
+    let expired = now_secs() > challenge.expires_at;
+    let address = match &challenge.address {
+        Some(expected) => {
+            let key = LockoutKey::address(expected);
+            ensure_not_locked(&key)?;
+            let recovered = verify_eip191_signature(&challenge.message, signature_hex)?;
+            if !recovered.eq_ignore_ascii_case(expected) {
+                return Err(SandboxError::Auth(
+                    "Signature does not match the challenge address".into(),
+                ));
+            }
+            if expired {
+                record_auth_failure(&key);
+                return Err(SandboxError::Auth("Challenge expired".into()));
+            }
+            clear_lockout(&key);
+            recovered
+        }
+        None if expired => return Err(SandboxError::Auth("Challenge expired".into())),
+        None => verify_eip191_signature(&challenge.message, signature_hex)?,
+    };
 
     let now = now_secs();
     let expires_at = now + SESSION_TTL_SECS;
@@ -213,9 +239,10 @@ pub fn revoke_session(token: &str) -> bool {
     claims.is_some()
 }
 
-/// Revoke all sessions for a specific address.
-/// Returns the number of sessions revoked.
+/// Revoke all sessions for a specific address, along with the terminal tokens
+/// they minted. Returns the number of sessions revoked.
 pub fn revoke_sessions_for_address(address: &str) -> usize {
+    revoke_terminal_tokens_for_address(address);
     let mut sessions = SESSIONS.lock().unwrap_or_else(|e| e.into_inner());
     let mut revoked = REVOKED.lock().unwrap_or_else(|e| e.into_inner());
 
@@ -232,7 +259,8 @@ pub fn revoke_sessions_for_address(address: &str) -> usize {
     count
 }
 
-/// Remove expired challenges, sessions, and revocation blacklist entries.
+/// Remove expired challenges, sessions, terminal tokens, revocation
+/// blacklist entries, and stale signature-failure records.
 pub fn gc_sessions() {
     let now = now_secs();
     CHALLENGES
@@ -247,9 +275,15 @@ pub fn gc_sessions() {
         .lock()
         .unwrap_or_else(|e| e.into_inner())
         .retain(|_, expires_at| *expires_at > now);
+    TERMINAL_TOKENS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|_, t| t.expires_at > now);
+    gc_auth_failures(now);
 }
 
-/// Clear all challenges, sessions, and revocation blacklist entries.
+/// Clear all challenges, sessions, terminal tokens, revocation blacklist
+/// entries, and sign-in failure records.
 /// Test/bench-only — prevents cross-test pollution when capacity tests fill
 /// the global maps, and lets benches start from a clean slate.
 #[cfg(any(test, feature = "test-utils"))]
@@ -257,6 +291,14 @@ pub fn clear_all_for_testing() {
     CHALLENGES.lock().unwrap_or_else(|e| e.into_inner()).clear();
     SESSIONS.lock().unwrap_or_else(|e| e.into_inner()).clear();
     REVOKED.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    TERMINAL_TOKENS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+    AUTH_FAILURES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
 }
 
 /// Shared lock backing both sync and async capacity-test guards.
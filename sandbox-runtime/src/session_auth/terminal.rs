@@ -0,0 +1,140 @@
+//! Short-lived, sandbox-scoped terminal tokens and the `TerminalAuth`
+//! extractor used by the terminal relay routes.
+//!
+//! A terminal token is an opaque random string, distinct from both the PASETO
+//! session token it was minted with and the sidecar's admin token (which never
+//! leaves the operator). It authenticates only the terminal relay of one
+//! sandbox, for [`TERMINAL_TOKEN_TTL_SECS`].
+
+use super::*;
+
+/// Mint a terminal token for `address` scoped to `sandbox_id`. The caller must
+/// already have checked that `address` owns the sandbox.
+pub fn issue_terminal_token(address: &str, sandbox_id: &str) -> Result<TerminalToken> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = format!("{TERMINAL_TOKEN_PREFIX}{}", hex::encode(bytes));
+    let expires_at = now_secs() + TERMINAL_TOKEN_TTL_SECS;
+
+    let mut tokens = TERMINAL_TOKENS.lock().unwrap_or_else(|e| e.into_inner());
+    if tokens.len() >= MAX_TERMINAL_TOKENS {
+        return Err(SandboxError::Unavailable(
+            "Terminal token capacity exceeded, try again later".into(),
+        ));
+    }
+    tokens.insert(
+        token.clone(),
+        TerminalTokenClaims {
+            address: address.to_string(),
+            sandbox_id: sandbox_id.to_string(),
+            expires_at,
+        },
+    );
+
+    Ok(TerminalToken {
+        token,
+        sandbox_id: sandbox_id.to_string(),
+        expires_at,
+    })
+}
+
+/// Validate a terminal token and return its claims. The sandbox scope is
+/// checked separately by [`TerminalAuth::check_scope`].
+pub fn validate_terminal_token(token: &str) -> Result<TerminalTokenClaims> {
+    let tokens = TERMINAL_TOKENS.lock().unwrap_or_else(|e| e.into_inner());
+    match tokens.get(token) {
+        Some(claims) if now_secs() <= claims.expires_at => Ok(claims.clone()),
+        Some(_) => Err(SandboxError::Auth("Terminal token expired".into())),
+        None => Err(SandboxError::Auth("Invalid terminal token".into())),
+    }
+}
+
+/// Drop every terminal token minted by `address`. Returns how many were
+/// removed.
+pub fn revoke_terminal_tokens_for_address(address: &str) -> usize {
+    let mut tokens = TERMINAL_TOKENS.lock().unwrap_or_else(|e| e.into_inner());
+    let before = tokens.len();
+    tokens.retain(|_, claims| !claims.address.eq_ignore_ascii_case(address));
+    before - tokens.len()
+}
+
+/// Axum extractor for the terminal relay routes. Accepts a session token or a
+/// terminal token in the `Authorization` header, or a terminal token in the
+/// `token` query parameter (for browser `EventSource`/`WebSocket` clients).
+/// Session tokens are never accepted from the query string.
+///
+/// Handlers must call [`TerminalAuth::check_scope`] with the resolved
+/// sandbox ID before serving the request.
+#[derive(Clone, Debug)]
+pub struct TerminalAuth {
+    pub address: String,
+    /// Sandbox a terminal token is bound to; `None` for session tokens.
+    pub sandbox_id: Option<String>,
+}
+
+impl TerminalAuth {
+    fn from_token(token: &str) -> Result<Self> {
+        if token.starts_with(TERMINAL_TOKEN_PREFIX) {
+            let claims = validate_terminal_token(token)?;
+            return Ok(Self {
+                address: claims.address,
+                sandbox_id: Some(claims.sandbox_id),
+            });
+        }
+        let claims = validate_session_token(token)?;
+        Ok(Self {
+            address: claims.address,
+            sandbox_id: None,
+        })
+    }
+
+    /// Reject a terminal token used against a sandbox it was not minted for.
+    pub fn check_scope(&self, sandbox_id: &str) -> Result<()> {
+        match &self.sandbox_id {
+            Some(scope) if scope != sandbox_id => Err(SandboxError::Auth(
+                "Terminal token is not valid for this sandbox".into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TerminalTokenQuery {
+    token: Option<String>,
+}
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for TerminalAuth {
+    type Rejection = (axum::http::StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let unauthorized = |msg: String| (axum::http::StatusCode::UNAUTHORIZED, msg);
+
+        if let Some(auth_header) = parts
+            .headers
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+        {
+            let token = extract_bearer_token(auth_header)
+                .ok_or_else(|| unauthorized("Invalid Authorization header format".into()))?;
+            return Self::from_token(token).map_err(|e| unauthorized(e.to_string()));
+        }
+
+        let axum::extract::Query(query) =
+            axum::extract::Query::<TerminalTokenQuery>::from_request_parts(parts, state)
+                .await
+                .map_err(|_| unauthorized("Invalid query string".into()))?;
+        match query.token {
+            Some(token) if token.starts_with(TERMINAL_TOKEN_PREFIX) => {
+                Self::from_token(&token).map_err(|e| unauthorized(e.to_string()))
+            }
+            Some(_) => Err(unauthorized(
+                "Only terminal tokens may be passed as a query parameter".into(),
+            )),
+            None => Err(unauthorized("Missing Authorization header".into())),
+        }
+    }
+}
@@ -5,17 +5,17 @@ use super::*;
 #[test]
 fn challenge_lifecycle() {
     let _guard = capacity_test_lock();
-    let challenge = create_challenge().unwrap();
+    let challenge = create_challenge(None, None).unwrap();
     assert!(!challenge.nonce.is_empty());
     assert!(challenge.message.contains(&challenge.nonce));
     assert!(challenge.expires_at > now_secs());
 
     // Should be consumable once
-    let msg = consume_challenge(&challenge.nonce);
+    let msg = take_challenge(&challenge.nonce);
     assert!(msg.is_ok());
 
     // Should not be consumable again
-    let msg2 = consume_challenge(&challenge.nonce);
+    let msg2 = take_challenge(&challenge.nonce);
     assert!(msg2.is_err());
 }
 
@@ -32,10 +32,12 @@ fn challenge_expiry() {
         nonce: nonce.clone(),
         message: "test message".into(),
         expires_at: now_secs().saturating_sub(10), // 10 seconds in the past
+        address: None,
+        chain_id: None,
     };
     CHALLENGES.lock().unwrap().insert(nonce.clone(), challenge);
 
-    let result = consume_challenge(&nonce);
+    let result = exchange_signature_for_token(&nonce, "0xdeadbeef");
     assert!(result.is_err());
     let err_msg = format!("{}", result.unwrap_err());
     assert!(
@@ -94,7 +96,7 @@ fn token_roundtrip() {
     let expected_address = format!("0x{}", hex::encode(&address_hash[12..]));
 
     // Step 1: Create challenge
-    let challenge = create_challenge().unwrap();
+    let challenge = create_challenge(None, None).unwrap();
 
     // Step 2: Sign the challenge message
     let prefixed = format!(
@@ -156,6 +158,8 @@ fn gc_sessions_cleans_expired() {
             nonce: expired_nonce.clone(),
             message: "expired".into(),
             expires_at: now_secs().saturating_sub(1),
+            address: None,
+            chain_id: None,
         },
     );
 
@@ -236,12 +240,14 @@ fn challenge_capacity_blocks_when_full() {
                     nonce: format!("cap-ch-{i}"),
                     message: "cap".into(),
                     expires_at: now_secs() + 600,
+                    address: None,
+                    chain_id: None,
                 },
             );
         }
     }
 
-    let result = create_challenge();
+    let result = create_challenge(None, None);
 
     // Clean up before assertions
     CHALLENGES
@@ -270,6 +276,8 @@ fn gc_restores_challenge_capacity_after_expiry() {
                     nonce: format!("gc-ch-{i}"),
                     message: "expired".into(),
                     expires_at: now_secs().saturating_sub(1),
+                    address: None,
+                    chain_id: None,
                 },
             );
         }
@@ -277,7 +285,7 @@ fn gc_restores_challenge_capacity_after_expiry() {
 
     gc_sessions();
 
-    let result = create_challenge();
+    let result = create_challenge(None, None);
 
     if let Ok(ref c) = result {
         CHALLENGES.lock().unwrap().remove(&c.nonce);
@@ -306,7 +314,7 @@ fn session_capacity_blocks_when_full() {
 
     use k256::ecdsa::SigningKey;
     let signing_key = SigningKey::random(&mut OsRng);
-    let challenge = create_challenge().unwrap();
+    let challenge = create_challenge(None, None).unwrap();
     let prefixed = format!(
         "\x19Ethereum Signed Message:\n{}{}",
         challenge.message.len(),
@@ -463,3 +471,175 @@ fn revoke_unknown_token_still_blacklists() {
         "unknown token should be blacklisted defensively"
     );
 }
+
+// ── Terminal tokens ────────────────────────────────────────────────
+
+#[test]
+fn terminal_token_is_scoped_and_not_a_session_token() {
+    let _guard = capacity_test_lock();
+    clear_all_for_testing();
+    let addr = "0xabcdef1234567890abcdef1234567890abcdef12";
+
+    let minted = issue_terminal_token(addr, "sb-1").unwrap();
+    assert!(minted.token.starts_with(TERMINAL_TOKEN_PREFIX));
+    assert!(minted.expires_at <= now_secs() + TERMINAL_TOKEN_TTL_SECS);
+
+    let claims = validate_terminal_token(&minted.token).unwrap();
+    assert_eq!(claims.address, addr);
+    assert_eq!(claims.sandbox_id, "sb-1");
+    assert!(validate_session_token(&minted.token).is_err());
+
+    let auth = TerminalAuth {
+        address: claims.address,
+        sandbox_id: Some(claims.sandbox_id),
+    };
+    assert!(auth.check_scope("sb-1").is_ok());
+    assert!(auth.check_scope("sb-2").is_err());
+}
+
+#[test]
+fn terminal_tokens_expire_and_die_with_their_sessions() {
+    let _guard = capacity_test_lock();
+    clear_all_for_testing();
+    let addr = "0xabcdef1234567890abcdef1234567890abcdef12";
+
+    let expired = issue_terminal_token(addr, "sb-1").unwrap();
+    TERMINAL_TOKENS
+        .lock()
+        .unwrap()
+        .get_mut(&expired.token)
+        .unwrap()
+        .expires_at = now_secs() - 1;
+    assert!(validate_terminal_token(&expired.token).is_err());
+    gc_sessions();
+    assert!(!TERMINAL_TOKENS.lock().unwrap().contains_key(&expired.token));
+
+    let live = issue_terminal_token(addr, "sb-1").unwrap();
+    revoke_sessions_for_address(addr);
+    assert!(validate_terminal_token(&live.token).is_err());
+}
+
+fn signer() -> (k256::ecdsa::SigningKey, String) {
+    let signing_key = k256::ecdsa::SigningKey::random(&mut OsRng);
+    let pubkey_bytes = signing_key.verifying_key().to_encoded_point(false);
+    let address_hash = keccak256(&pubkey_bytes.as_bytes()[1..]);
+    let address = format!("0x{}", hex::encode(&address_hash[12..]));
+    (signing_key, address)
+}
+
+fn sign(signing_key: &k256::ecdsa::SigningKey, message: &str) -> String {
+    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+    let (signature, recovery_id) = signing_key
+        .sign_prehash_recoverable(&keccak256(prefixed.as_bytes()))
+        .expect("signing failed");
+    let mut sig_bytes = signature.to_bytes().to_vec();
+    sig_bytes.push(recovery_id.to_byte() + 27);
+    format!("0x{}", hex::encode(&sig_bytes))
+}
+
+#[test]
+fn bound_challenge_only_accepts_its_address() {
+    let _guard = capacity_test_lock();
+    let (key, address) = signer();
+    let (other_key, _) = signer();
+
+    let challenge = create_challenge(
+        Some(&address.to_uppercase().replace("0X", "0x")),
+        Some(8453),
+    )
+    .unwrap();
+    assert_eq!(challenge.address.as_deref(), Some(address.as_str()));
+    assert!(challenge.message.contains(&format!("Address: {address}")));
+    assert!(challenge.message.contains("Chain ID: 8453"));
+
+    let err = exchange_signature_for_token(&challenge.nonce, &sign(&other_key, &challenge.message))
+        .unwrap_err();
+    assert!(err.to_string().contains("challenge address"), "{err}");
+
+    let challenge = create_challenge(Some(&address), Some(8453)).unwrap();
+    let token =
+        exchange_signature_for_token(&challenge.nonce, &sign(&key, &challenge.message)).unwrap();
+    assert_eq!(token.address, address);
+    assert!(
+        !AUTH_FAILURES
+            .lock()
+            .unwrap()
+            .contains_key(&LockoutKey::address(&address))
+    );
+
+    assert!(create_challenge(Some("0x1234"), None).is_err());
+}
+
+#[test]
+fn other_signers_cannot_lock_an_address_out() {
+    let _guard = capacity_test_lock();
+    let (key, address) = signer();
+    let (other_key, _) = signer();
+
+    for _ in 0..MAX_AUTH_FAILURES {
+        let challenge = create_challenge(Some(&address), None).unwrap();
+        let sig = sign(&other_key, &challenge.message);
+        assert!(exchange_signature_for_token(&challenge.nonce, &sig).is_err());
+        assert!(exchange_signature_for_token(&challenge.nonce, "0xdeadbeef").is_err());
+    }
+    assert_eq!(locked_for(&LockoutKey::address(&address)), None);
+    let challenge = create_challenge(Some(&address), None).unwrap();
+    assert!(
+        exchange_signature_for_token(&challenge.nonce, &sign(&key, &challenge.message)).is_ok()
+    );
+}
+
+#[test]
+fn repeated_failures_signed_by_the_address_lock_it() {
+    let _guard = capacity_test_lock();
+    let (key, address) = signer();
+
+    for _ in 0..MAX_AUTH_FAILURES {
+        let mut challenge = create_challenge(Some(&address), None).unwrap();
+        challenge.expires_at = now_secs().saturating_sub(10);
+        CHALLENGES
+            .lock()
+            .unwrap()
+            .insert(challenge.nonce.clone(), challenge.clone());
+        let sig = sign(&key, &challenge.message);
+        let err = exchange_signature_for_token(&challenge.nonce, &sig).unwrap_err();
+        assert!(err.to_string().contains("expired"), "{err}");
+    }
+    assert!(locked_for(&LockoutKey::address(&address)).is_some_and(|secs| secs <= LOCKOUT_SECS));
+    let err = create_challenge(Some(&address), None).unwrap_err();
+    assert!(
+        err.to_string().contains("Too many failed sign-ins"),
+        "{err}"
+    );
+
+    // Clearing the record lifts the lock.
+    assert!(clear_lockout(&LockoutKey::address(&address)));
+    let challenge = create_challenge(Some(&address), None).unwrap();
+    assert!(
+        exchange_signature_for_token(&challenge.nonce, &sign(&key, &challenge.message)).is_ok()
+    );
+}
+
+#[test]
+fn lockouts_escalate_and_are_forgotten_after_a_quiet_day() {
+    let _guard = capacity_test_lock();
+    let key = LockoutKey::Ip("198.51.100.9".parse().unwrap());
+    let lock = || {
+        for _ in 0..MAX_AUTH_FAILURES {
+            record_auth_failure(&key);
+        }
+        locked_for(&key).unwrap()
+    };
+
+    assert!(lock() <= LOCKOUT_SECS);
+    // A second lockout lasts twice as long.
+    assert!(lock() > LOCKOUT_SECS);
+    let status = list_lockouts().into_iter().find(|s| s.key == key).unwrap();
+    assert_eq!(status.lockouts, 2);
+
+    let now = now_secs();
+    gc_auth_failures(now);
+    assert!(locked_for(&key).is_some());
+    gc_auth_failures(now + MAX_LOCKOUT_SECS + LOCKOUT_MEMORY_SECS + 1);
+    assert!(list_lockouts().iter().all(|s| s.key != key));
+}
@@ -0,0 +1,137 @@
+//! Chat session export.
+//!
+//! Collects the persisted chat history of a sandbox (messages plus the runs
+//! that produced them) into one JSON archive and uploads it to a
+//! customer-supplied URL, for audit trails and training data. Only sessions
+//! owned by the requesting address are exported.
+
+use serde::{Deserialize, Serialize};
+
+use crate::chat_state::{self, ChatRunRecord, ChatSessionRecord};
+use crate::error::{Result, SandboxError};
+
+/// Current [`SessionExport`] format version.
+pub const SESSION_EXPORT_VERSION: u32 = 1;
+
+/// One exported session with its runs in creation order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportedSession {
+    #[serde(flatten)]
+    pub session: ChatSessionRecord,
+    pub runs: Vec<ChatRunRecord>,
+}
+
+/// The uploaded archive.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionExport {
+    pub version: u32,
+    pub sandbox_id: String,
+    pub owner: String,
+    pub exported_at: u64,
+    pub sessions: Vec<ExportedSession>,
+}
+
+impl SessionExport {
+    pub fn message_count(&self) -> usize {
+        self.sessions.iter().map(|s| s.session.messages.len()).sum()
+    }
+}
+
+/// Validate an export destination: `https://`, or `http://` on loopback.
+pub fn validate_export_destination(destination: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(destination.trim())
+        .map_err(|e| SandboxError::Validation(format!("Invalid export destination: {e}")))?;
+    let loopback = matches!(parsed.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    match parsed.scheme() {
+        "https" => Ok(()),
+        "http" if loopback => Ok(()),
+        _ => Err(SandboxError::Validation(
+            "Export destination must use https (http is allowed for loopback only)".into(),
+        )),
+    }
+}
+
+/// Build the archive of `owner`'s chat sessions on `sandbox_id`.
+///
+/// With a non-empty `session_id` only that session is exported; it matches
+/// either the operator session ID or a sidecar session ID used by one of
+/// its runs.
+pub fn build_session_export(
+    sandbox_id: &str,
+    owner: &str,
+    session_id: &str,
+) -> Result<SessionExport> {
+    let scopes = [
+        chat_state::sandbox_scope(sandbox_id),
+        chat_state::instance_scope(sandbox_id),
+    ];
+    let mut sessions = Vec::new();
+    for scope in &scopes {
+        sessions.extend(chat_state::list_sessions(scope, owner).map_err(SandboxError::Storage)?);
+    }
+    sessions.sort_by_key(|s| s.created_at);
+
+    let mut exported = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        let runs = chat_state::list_runs_for_session(&session.id).map_err(SandboxError::Storage)?;
+        let wanted = session_id.is_empty()
+            || session.id == session_id
+            || session.latest_sidecar_session_id.as_deref() == Some(session_id)
+            || runs
+                .iter()
+                .any(|r| r.sidecar_session_id.as_deref() == Some(session_id));
+        if wanted {
+            exported.push(ExportedSession { session, runs });
+        }
+    }
+    if !session_id.is_empty() && exported.is_empty() {
+        return Err(SandboxError::NotFound(format!(
+            "Chat session '{session_id}' not found"
+        )));
+    }
+
+    Ok(SessionExport {
+        version: SESSION_EXPORT_VERSION,
+        sandbox_id: sandbox_id.to_string(),
+        owner: owner.to_string(),
+        exported_at: crate::util::now_ts(),
+        sessions: exported,
+    })
+}
+
+/// Upload `export` as JSON to `destination` with a `PUT` (e.g. a presigned
+/// object-store URL). Returns the archive size in bytes.
+pub async fn upload_session_export(destination: &str, export: &SessionExport) -> Result<usize> {
+    validate_export_destination(destination)?;
+    let body = serde_json::to_vec(export)
+        .map_err(|e| SandboxError::Storage(format!("Failed to encode session export: {e}")))?;
+    let size = body.len();
+    let response = crate::util::http_client()?
+        .put(destination.trim())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| SandboxError::Http(format!("Session export upload failed: {e}")))?;
+    if !response.status().is_success() {
+        return Err(SandboxError::Http(format!(
+            "Session export destination returned HTTP {}",
+            response.status()
+        )));
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destination_requires_https_or_loopback() {
+        assert!(validate_export_destination("https://bucket.example.com/e.json?sig=1").is_ok());
+        assert!(validate_export_destination("http://127.0.0.1:9000/e.json").is_ok());
+        assert!(validate_export_destination("http://example.com/e.json").is_err());
+        assert!(validate_export_destination("file:///etc/passwd").is_err());
+        assert!(validate_export_destination("not a url").is_err());
+    }
+}
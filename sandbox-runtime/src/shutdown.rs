@@ -0,0 +1,160 @@
+//! Coordinated operator shutdown.
+//!
+//! Job handlers hold a [`JobGuard`] for as long as they run (see
+//! [`enter_job`]). On shutdown the binary calls [`begin_shutdown`], after
+//! which new jobs are refused with `503`, then waits for the guards still
+//! held to drop (bounded by `SHUTDOWN_JOB_DRAIN_TIMEOUT_SECS`) before
+//! stopping its background tasks and syncing the state dir to disk with
+//! [`crate::store::sync_state_dir`].
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::error::{Result, SandboxError};
+
+/// Wait used when `SHUTDOWN_JOB_DRAIN_TIMEOUT_SECS` is unset (30 seconds).
+pub const DEFAULT_JOB_DRAIN_TIMEOUT_SECS: u64 = 30;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Counts running jobs and refuses new ones once shutdown has begun.
+pub struct JobTracker {
+    shutting_down: AtomicBool,
+    in_flight: AtomicUsize,
+}
+
+impl JobTracker {
+    pub const fn new() -> Self {
+        Self {
+            shutting_down: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Register a running job, or refuse it once shutdown has begun.
+    pub fn enter(&self) -> Result<JobGuard<'_>> {
+        // Count first so a concurrent `begin_shutdown` either sees this job
+        // or this job sees the flag.
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = JobGuard(self);
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return Err(SandboxError::Unavailable(
+                "Operator is shutting down and not accepting new jobs".into(),
+            ));
+        }
+        Ok(guard)
+    }
+
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Wait until no job is running. Returns the number still running when
+    /// `timeout` expires (0 when all finished).
+    pub async fn wait_idle(&self, timeout: Duration) -> usize {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let running = self.in_flight();
+            if running == 0 || tokio::time::Instant::now() >= deadline {
+                return running;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Default for JobTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// RAII guard that marks a job finished when dropped.
+pub struct JobGuard<'a>(&'a JobTracker);
+
+impl Drop for JobGuard<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+static JOBS: JobTracker = JobTracker::new();
+
+/// Returns the global job tracker.
+pub fn jobs() -> &'static JobTracker {
+    &JOBS
+}
+
+/// Register a running job with the global tracker.
+pub fn enter_job() -> Result<JobGuard<'static>> {
+    JOBS.enter()
+}
+
+/// Refuse new jobs from now on.
+pub fn begin_shutdown() {
+    JOBS.begin_shutdown();
+}
+
+pub fn is_shutting_down() -> bool {
+    JOBS.is_shutting_down()
+}
+
+/// How long shutdown waits for running jobs, from
+/// `SHUTDOWN_JOB_DRAIN_TIMEOUT_SECS`.
+pub fn job_drain_timeout() -> Duration {
+    let secs = std::env::var("SHUTDOWN_JOB_DRAIN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_JOB_DRAIN_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_count_running_jobs() {
+        let tracker = JobTracker::new();
+        let a = tracker.enter().unwrap();
+        let b = tracker.enter().unwrap();
+        assert_eq!(tracker.in_flight(), 2);
+        drop(a);
+        assert_eq!(tracker.in_flight(), 1);
+        drop(b);
+        assert_eq!(tracker.in_flight(), 0);
+    }
+
+    #[test]
+    fn new_jobs_are_refused_after_shutdown_begins() {
+        let tracker = JobTracker::new();
+        let running = tracker.enter().unwrap();
+        tracker.begin_shutdown();
+        let err = tracker.enter().err().expect("job accepted during shutdown");
+        assert!(matches!(err, SandboxError::Unavailable(_)));
+        // A refused job leaves the count alone.
+        assert_eq!(tracker.in_flight(), 1);
+        drop(running);
+        assert_eq!(tracker.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn wait_idle_returns_when_jobs_finish_or_times_out() {
+        static TRACKER: JobTracker = JobTracker::new();
+        let guard = TRACKER.enter().unwrap();
+        assert_eq!(TRACKER.wait_idle(Duration::from_millis(50)).await, 1);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+        assert_eq!(TRACKER.wait_idle(Duration::from_secs(5)).await, 0);
+    }
+}
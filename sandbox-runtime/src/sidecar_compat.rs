@@ -0,0 +1,205 @@
+//! Sidecar API version negotiation.
+//!
+//! Sidecar images have drifted in API shape: API 1 sidecars return command
+//! results as `{ result: { exitCode, stdout, stderr } }`, API 2 sidecars use
+//! the `{ success, data: { … } }` envelope of their session endpoints. At
+//! provision the runtime reads the sidecar's `/health`
+//! (`{ status, version, apiVersion, capabilities }`), keeps it on the record
+//! as [`SidecarApiInfo`], and later requests pick their response adapter with
+//! [`SidecarApi::for_record`]. A sidecar reporting an `apiVersion` this
+//! runtime does not speak fails the provision with an error naming both
+//! versions, instead of every later call failing on a malformed response. A
+//! `/health` without `apiVersion` comes from a sidecar that predates
+//! versioning, which speaks API 1.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Result, SandboxError};
+use crate::runtime::SandboxRecord;
+
+/// Oldest sidecar API this runtime speaks.
+pub const MIN_SIDECAR_API_VERSION: u32 = 1;
+/// Newest sidecar API this runtime speaks.
+pub const MAX_SIDECAR_API_VERSION: u32 = 2;
+
+/// What a sidecar reported about itself at provision.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SidecarApiInfo {
+    /// Sidecar release, empty when the sidecar doesn't report one.
+    #[serde(default)]
+    pub version: String,
+    pub api_version: u32,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+impl Default for SidecarApiInfo {
+    fn default() -> Self {
+        Self {
+            version: String::new(),
+            api_version: MIN_SIDECAR_API_VERSION,
+            capabilities: Vec::new(),
+        }
+    }
+}
+
+/// Request/response adapter for one sidecar API version.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SidecarApi {
+    #[default]
+    V1,
+    V2,
+}
+
+impl SidecarApi {
+    pub fn from_version(api_version: u32) -> Result<Self> {
+        match api_version {
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            other => Err(SandboxError::Validation(format!(
+                "Sidecar API version {other} is not supported (this operator supports \
+                 {MIN_SIDECAR_API_VERSION}..={MAX_SIDECAR_API_VERSION}); \
+                 use a compatible SIDECAR_IMAGE"
+            ))),
+        }
+    }
+
+    /// The API `record` negotiated; records from before negotiation speak
+    /// API 1.
+    pub fn for_record(record: &SandboxRecord) -> Self {
+        record
+            .sidecar_api
+            .as_ref()
+            .and_then(|info| Self::from_version(info.api_version).ok())
+            .unwrap_or_default()
+    }
+
+    /// [`Self::for_record`] of the sandbox at `sidecar_url`, API 1 when no
+    /// record matches.
+    pub fn for_url(sidecar_url: &str) -> Self {
+        crate::runtime::get_sandbox_by_url_opt(sidecar_url)
+            .map(|record| Self::for_record(&record))
+            .unwrap_or_default()
+    }
+
+    /// The command result object of a `/terminals/commands` response.
+    pub fn command_result(self, response: &Value) -> Option<&Value> {
+        match self {
+            Self::V1 => response.get("result"),
+            Self::V2 => response.get("data"),
+        }
+    }
+}
+
+/// Parse a sidecar `/health` body. Anything that isn't a JSON object with an
+/// `apiVersion` is a pre-versioning sidecar.
+pub fn parse_sidecar_health(body: &str) -> SidecarApiInfo {
+    let Ok(Value::Object(health)) = serde_json::from_str::<Value>(body) else {
+        return SidecarApiInfo::default();
+    };
+    SidecarApiInfo {
+        version: health
+            .get("version")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        api_version: health
+            .get("apiVersion")
+            .and_then(Value::as_u64)
+            .map_or(MIN_SIDECAR_API_VERSION, |v| {
+                u32::try_from(v).unwrap_or(u32::MAX)
+            }),
+        capabilities: health
+            .get("capabilities")
+            .and_then(Value::as_array)
+            .map(|caps| {
+                caps.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// Wait up to `timeout` for the sidecar at `sidecar_url` to answer
+/// `/health`, then read its API version. Any HTTP answer ends the wait — a
+/// non-2xx status is an image without the versioned health endpoint, so API
+/// 1. `Ok(None)` when it never answered; an error when it speaks an
+/// unsupported API.
+pub async fn negotiate_sidecar_api(
+    sidecar_url: &str,
+    timeout: Duration,
+) -> Result<Option<SidecarApiInfo>> {
+    let url = format!("{sidecar_url}/health");
+    let client = crate::util::http_client()?;
+    let body = tokio::time::timeout(timeout, async {
+        loop {
+            if let Ok(resp) = client.get(&url).send().await {
+                if !resp.status().is_success() {
+                    return String::new();
+                }
+                if let Ok(body) = resp.text().await {
+                    return body;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    })
+    .await;
+    let Ok(body) = body else {
+        return Ok(None);
+    };
+    let info = parse_sidecar_health(&body);
+    SidecarApi::from_version(info.api_version)?;
+    Ok(Some(info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn health_without_api_version_is_api_1() {
+        assert_eq!(
+            parse_sidecar_health(r#"{"status":"ok"}"#),
+            SidecarApiInfo::default()
+        );
+        assert_eq!(parse_sidecar_health("OK"), SidecarApiInfo::default());
+    }
+
+    #[test]
+    fn health_reports_version_and_capabilities() {
+        let info = parse_sidecar_health(
+            r#"{"status":"ok","version":"1.4.0","apiVersion":2,"capabilities":["computer_use",7]}"#,
+        );
+        assert_eq!(info.version, "1.4.0");
+        assert_eq!(info.api_version, 2);
+        assert_eq!(info.capabilities, vec!["computer_use"]);
+        assert_eq!(
+            SidecarApi::from_version(info.api_version).unwrap(),
+            SidecarApi::V2
+        );
+    }
+
+    #[test]
+    fn unsupported_api_version_names_the_range() {
+        let err = SidecarApi::from_version(3).unwrap_err().to_string();
+        assert!(err.contains("version 3"), "{err}");
+        assert!(err.contains("1..=2"), "{err}");
+        assert!(SidecarApi::from_version(0).is_err());
+    }
+
+    #[test]
+    fn command_result_follows_the_api_envelope() {
+        let v1 = json!({ "result": { "exitCode": 3 } });
+        let v2 = json!({ "success": true, "data": { "exitCode": 4 } });
+        assert_eq!(SidecarApi::V1.command_result(&v1).unwrap()["exitCode"], 3);
+        assert_eq!(SidecarApi::V2.command_result(&v2).unwrap()["exitCode"], 4);
+        assert!(SidecarApi::V1.command_result(&v2).is_none());
+    }
+}
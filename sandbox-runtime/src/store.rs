@@ -38,6 +38,34 @@ pub fn state_dir() -> PathBuf {
     dir
 }
 
+/// Sync every file in the state dir (and the dir itself) to disk, so writes
+/// made just before the process exits survive a host crash. Returns the
+/// number of files synced.
+pub fn sync_state_dir() -> Result<usize> {
+    let dir = state_dir();
+    let entries = std::fs::read_dir(&dir).map_err(|e| {
+        SandboxError::Storage(format!("Failed to read state dir {}: {e}", dir.display()))
+    })?;
+    let mut synced = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        std::fs::File::open(&path)
+            .and_then(|file| file.sync_all())
+            .map_err(|e| {
+                SandboxError::Storage(format!("Failed to sync {}: {e}", path.display()))
+            })?;
+        synced += 1;
+    }
+    #[cfg(unix)]
+    if let Ok(handle) = std::fs::File::open(&dir) {
+        let _ = handle.sync_all();
+    }
+    Ok(synced)
+}
+
 /// Convenience wrapper that bridges `LocalDatabase` to our `SandboxError` types.
 /// Keys are serialized to strings for storage.
 ///
@@ -118,6 +146,23 @@ where
         Ok(db.update(key, f)?)
     }
 
+    /// Modify `key` in place, starting from `default()` when it is absent.
+    /// The read and the write happen under one write lock, so concurrent
+    /// `update`s and `upsert`s of the same key are never lost.
+    pub fn upsert<D, F>(&self, key: &str, default: D, f: F) -> Result<()>
+    where
+        D: FnOnce() -> V,
+        F: FnOnce(&mut V),
+    {
+        let db = self
+            .db
+            .write()
+            .map_err(|_| SandboxError::Storage("PersistentStore RwLock poisoned (write)".into()))?;
+        let mut value = db.get(key)?.unwrap_or_else(default);
+        f(&mut value);
+        Ok(db.set(key, value)?)
+    }
+
     pub fn replace(&self, map: HashMap<String, V>) -> Result<()> {
         let db = self
             .db
@@ -158,6 +203,16 @@ mod tests {
         assert_eq!(val, Some("second".to_string()));
     }
 
+    #[test]
+    fn upsert_starts_from_default_then_updates() {
+        let (store, _dir) = temp_store();
+        store.upsert("k", || "a".into(), |v| v.push('b')).unwrap();
+        store
+            .upsert("k", || "unused".into(), |v| v.push('c'))
+            .unwrap();
+        assert_eq!(store.get("k").unwrap(), Some("abc".to_string()));
+    }
+
     #[test]
     fn remove_returns_removed_value() {
         let (store, _dir) = temp_store();
@@ -0,0 +1,595 @@
+//! Queued agent tasks with progress tracking.
+//!
+//! Multi-turn tasks routinely outlive a synchronous job or HTTP request.
+//! [`enqueue_task`] records a [`TaskRecord`] and returns its ID at once; a
+//! background worker runs the task over the sidecar's streaming endpoint
+//! and writes progress (turns completed, current tool) to the record as
+//! events arrive. Callers poll [`get_task`] or subscribe to
+//! [`subscribe_task_events`] for `progress` and `done` events.
+//!
+//! At most [`TASK_QUEUE_CONCURRENCY_ENV`] tasks (default
+//! [`DEFAULT_TASK_CONCURRENCY`]) run at once per operator; the rest wait
+//! as `queued`, as do tasks whose sandbox is at its agent run limit (see
+//! [`crate::agent_runs`]). Tasks queued or running when the operator
+//! restarts are marked [`TaskStatus::Interrupted`]. [`cancel_task`] stops a
+//! queued or running task: the record is marked cancelled at once, the
+//! sidecar is asked to abort its run and the worker slot and owner quota
+//! slot are released.
+//! Finished records are pruned by the GC tick after [`TASK_RETENTION_SECS`].
+//! A task queued under a `session_name` runs in that named session and moves
+//! it to the session the sidecar finished in (see [`crate::named_sessions`]).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::Json;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::{Notify, Semaphore, broadcast};
+
+use crate::api_types::TaskApiRequest;
+use crate::error::{Result, SandboxError};
+use crate::live_operator_sessions::LiveJsonEvent;
+use crate::runtime::SandboxRecord;
+use crate::store::PersistentStore;
+
+/// Env var capping concurrently running tasks.
+pub const TASK_QUEUE_CONCURRENCY_ENV: &str = "TASK_QUEUE_CONCURRENCY";
+/// Concurrent tasks when [`TASK_QUEUE_CONCURRENCY_ENV`] is unset.
+pub const DEFAULT_TASK_CONCURRENCY: usize = 4;
+/// How long finished tasks are kept (7 days).
+pub const TASK_RETENTION_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Slack on top of the task timeout before the operator gives up on the
+/// sidecar answering.
+const SIDECAR_GRACE: Duration = Duration::from_secs(30);
+/// How often a running task refreshes the sandbox's activity time.
+const ACTIVITY_INTERVAL: Duration = Duration::from_secs(60);
+const TASK_EVENT_BUFFER: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// Waiting for a worker slot.
+    Queued,
+    Running,
+    Completed,
+    /// The agent reported an error or the sidecar did not answer in time.
+    Failed,
+    /// The operator restarted before the task finished.
+    Interrupted,
+    /// Cancelled by the owner (see [`cancel_task`]).
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub fn is_terminal(self) -> bool {
+        !matches!(self, Self::Queued | Self::Running)
+    }
+}
+
+/// Progress observed on the sidecar stream.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskProgress {
+    pub turns_completed: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_tool: Option<String>,
+}
+
+impl TaskProgress {
+    /// Fold a sidecar stream event into the progress. Returns whether it
+    /// changed: `step-finish` parts complete a turn, `tool` parts name the
+    /// tool in use.
+    pub fn observe(&mut self, event_type: &str, data: &Value) -> bool {
+        if event_type != "message.part.updated" {
+            return false;
+        }
+        let Some(part) = data.get("part") else {
+            return false;
+        };
+        match part.get("type").and_then(Value::as_str) {
+            Some("step-finish") => {
+                self.turns_completed += 1;
+                true
+            }
+            Some("tool") => {
+                let tool = part
+                    .get("tool")
+                    .or_else(|| part.get("name"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                if tool.is_none() || tool == self.current_tool {
+                    return false;
+                }
+                self.current_tool = tool;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub id: String,
+    pub sandbox_id: String,
+    /// Address that queued the task.
+    pub owner: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub session_id: String,
+    /// Named session the task runs in (see `crate::named_sessions`).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub session_name: String,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub max_turns: u64,
+    pub timeout_ms: u64,
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub progress: TaskProgress,
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<u64>,
+    #[serde(default)]
+    pub result: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub trace_id: String,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub input_tokens: u32,
+    #[serde(default)]
+    pub output_tokens: u32,
+}
+
+static TASKS: OnceCell<PersistentStore<TaskRecord>> = OnceCell::new();
+static TASK_SLOTS: Lazy<Semaphore> = Lazy::new(|| {
+    let permits = std::env::var(TASK_QUEUE_CONCURRENCY_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_TASK_CONCURRENCY);
+    Semaphore::new(permits)
+});
+static TASK_STREAMS: Lazy<Mutex<HashMap<String, broadcast::Sender<LiveJsonEvent>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// Cancellation signals of unfinished tasks, by task ID.
+static TASK_CANCELS: Lazy<Mutex<HashMap<String, Arc<Notify>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Access the task store. The first access marks tasks left queued or
+/// running by a previous operator process as interrupted.
+pub fn tasks() -> Result<&'static PersistentStore<TaskRecord>> {
+    TASKS
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("tasks.json");
+            let store = PersistentStore::open(path)?;
+            interrupt_unfinished(&store)?;
+            Ok(store)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+fn interrupt_unfinished(store: &PersistentStore<TaskRecord>) -> Result<()> {
+    let now = crate::util::now_ts();
+    for task in store.values()? {
+        if task.status.is_terminal() {
+            continue;
+        }
+        store.update(&task.id, |t| {
+            t.status = TaskStatus::Interrupted;
+            t.error = Some("Operator restarted before the task completed".into());
+            t.completed_at = Some(now);
+        })?;
+    }
+    Ok(())
+}
+
+/// Subscribe to `progress` and `done` events of an unfinished task. `None`
+/// once the task has finished.
+pub fn subscribe_task_events(task_id: &str) -> Option<broadcast::Receiver<LiveJsonEvent>> {
+    let streams = TASK_STREAMS.lock().unwrap_or_else(|e| e.into_inner());
+    streams.get(task_id).map(broadcast::Sender::subscribe)
+}
+
+fn emit(task_id: &str, event_type: &str, task: &TaskRecord) {
+    let streams = TASK_STREAMS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(tx) = streams.get(task_id) {
+        let _ = tx.send(LiveJsonEvent {
+            event_type: event_type.to_string(),
+            payload: json!(task),
+        });
+    }
+}
+
+/// Record a new task for `owner` on `record` and queue it. Returns the
+/// record in its `queued` state. Counts against the owner's concurrent-task
+/// quota (see [`crate::owner_quota`]) until it finishes.
+pub fn enqueue_task(
+    record: &SandboxRecord,
+    owner: &str,
+    req: &TaskApiRequest,
+) -> Result<TaskRecord> {
+    req.validate().map_err(SandboxError::Validation)?;
+    let grant = crate::model_policy::check_model(&req.model, req.timeout_ms)
+        .map_err(|e| SandboxError::Validation(e.to_string()))?;
+    let timeout_ms = crate::executions::execution_timeout_ms(grant.timeout_ms)?;
+    crate::disk_quota::ensure_disk_writable(&record.id)?;
+    let session_id = crate::named_sessions::resolve_session(
+        &record.id,
+        owner,
+        &req.session_name,
+        &req.session_id,
+    )?;
+    // Held while the task is queued and running.
+    let quota_slot = crate::owner_quota::acquire_task_slot(owner)?;
+    let task = TaskRecord {
+        id: format!("task-{}", uuid::Uuid::new_v4().simple()),
+        sandbox_id: record.id.clone(),
+        owner: owner.to_string(),
+        prompt: req.prompt.clone(),
+        session_id,
+        session_name: req.session_name.trim().to_string(),
+        model: req.model.clone(),
+        max_turns: req.max_turns,
+        timeout_ms,
+        status: TaskStatus::Queued,
+        progress: TaskProgress::default(),
+        created_at: crate::util::now_ts(),
+        started_at: None,
+        completed_at: None,
+        result: String::new(),
+        error: None,
+        trace_id: String::new(),
+        duration_ms: 0,
+        input_tokens: 0,
+        output_tokens: 0,
+    };
+    tasks()?.insert(task.id.clone(), task.clone())?;
+    let (tx, _rx) = broadcast::channel(TASK_EVENT_BUFFER);
+    TASK_STREAMS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(task.id.clone(), tx);
+    let cancel = Arc::new(Notify::new());
+    TASK_CANCELS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(task.id.clone(), cancel.clone());
+    crate::runtime::touch_sandbox(&record.id);
+
+    let backend_type = req.backend_type.clone();
+    let context_json = req.context_json.clone();
+    let queued = task.clone();
+    tokio::spawn(async move {
+        let _quota_slot = quota_slot;
+        // A run slot on the sandbox (see `crate::agent_runs`), then a worker
+        // slot; the task stays `queued` until it has both. In this order a
+        // task waiting on a busy sandbox holds no worker other sandboxes'
+        // tasks need.
+        let slots = async {
+            let agent_run = crate::agent_runs::acquire_agent_run(&queued.sandbox_id).await;
+            let permit = TASK_SLOTS.acquire().await;
+            (permit, agent_run)
+        };
+        let (permit, _agent_run) = tokio::select! {
+            slots = slots => slots,
+            _ = cancel.notified() => {
+                close_task(&queued.id);
+                return;
+            }
+        };
+        let Ok(_permit) = permit else {
+            return;
+        };
+        run_task(queued, &backend_type, &context_json, &cancel).await;
+    });
+    Ok(task)
+}
+
+/// Cancel a queued or running task of `sandbox_id` and return its record.
+/// A running task's sidecar is asked to abort the in-flight run; the worker
+/// slot and the owner's quota slot are released once the worker stops. A
+/// finished task is a validation error.
+pub async fn cancel_task(sandbox_id: &str, task_id: &str) -> Result<TaskRecord> {
+    get_task(sandbox_id, task_id)?;
+    let now = crate::util::now_ts();
+    let mut was = None;
+    tasks()?.update(task_id, |t| {
+        if t.status.is_terminal() {
+            return;
+        }
+        was = Some(t.status);
+        t.status = TaskStatus::Cancelled;
+        t.error = Some("Task cancelled by owner".into());
+        t.completed_at = Some(now);
+        t.progress.current_tool = None;
+    })?;
+    let task = get_task(sandbox_id, task_id)?;
+    let Some(was) = was else {
+        return Err(SandboxError::Validation(format!(
+            "Task '{task_id}' has already finished"
+        )));
+    };
+    if let Some(cancel) = TASK_CANCELS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(task_id)
+    {
+        cancel.notify_one();
+    }
+    if was == TaskStatus::Running
+        && let Ok(record) = crate::runtime::get_sandbox_by_id(sandbox_id)
+    {
+        crate::operator_api::best_effort_cancel_sidecar_run(&record).await;
+    }
+    tracing::info!(task_id, sandbox_id, "Task cancelled");
+    Ok(task)
+}
+
+/// Send the final `done` event of a task and drop its stream and
+/// cancellation signal.
+fn close_task(task_id: &str) {
+    if let Ok(Some(task)) = tasks().and_then(|store| store.get(task_id)) {
+        emit(task_id, "done", &task);
+    }
+    TASK_STREAMS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(task_id);
+    TASK_CANCELS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(task_id);
+}
+
+fn update_task(id: &str, f: impl FnOnce(&mut TaskRecord)) -> Option<TaskRecord> {
+    let store = match tasks() {
+        Ok(store) => store,
+        Err(e) => {
+            tracing::error!(task_id = id, error = %e, "failed to open task store");
+            return None;
+        }
+    };
+    if let Err(e) = store.update(id, f) {
+        tracing::error!(task_id = id, error = %e, "failed to update task");
+        return None;
+    }
+    store.get(id).ok().flatten()
+}
+
+async fn run_task(task: TaskRecord, backend_type: &str, context_json: &str, cancel: &Notify) {
+    let now = crate::util::now_ts();
+    let Some(task) = update_task(&task.id, |t| {
+        if t.status == TaskStatus::Queued {
+            t.status = TaskStatus::Running;
+            t.started_at = Some(now);
+        }
+    }) else {
+        return;
+    };
+    if task.status != TaskStatus::Running {
+        // Cancelled while waiting for the slot.
+        close_task(&task.id);
+        return;
+    }
+    emit(&task.id, "progress", &task);
+
+    let run = async {
+        match crate::runtime::get_sandbox_by_id(&task.sandbox_id) {
+            Ok(record) => {
+                let outcome = run_on_sidecar(&record, &task, backend_type, context_json).await;
+                if named_session_is_stale(&task, &outcome) {
+                    // The sidecar dropped the named session: start a new one.
+                    let _ = crate::named_sessions::forget_named_session(
+                        &task.sandbox_id,
+                        &task.owner,
+                        &task.session_name,
+                    );
+                    let fresh = TaskRecord {
+                        session_id: String::new(),
+                        ..task.clone()
+                    };
+                    run_on_sidecar(&record, &fresh, backend_type, context_json).await
+                } else {
+                    outcome
+                }
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    };
+    // Cancellation drops the sidecar call; `cancel_task` already finalized
+    // the record.
+    let outcome = tokio::select! {
+        outcome = run => outcome,
+        _ = cancel.notified() => Err("Task cancelled".to_string()),
+    };
+    let now = crate::util::now_ts();
+    let finished = update_task(&task.id, |t| {
+        if t.status != TaskStatus::Running {
+            return;
+        }
+        t.completed_at = Some(now);
+        match outcome {
+            Ok(outcome) if outcome.success => {
+                t.status = TaskStatus::Completed;
+                t.result = outcome.response;
+                t.trace_id = outcome.trace_id;
+                t.session_id = outcome.session_id;
+                t.duration_ms = outcome.duration_ms;
+                t.input_tokens = outcome.input_tokens;
+                t.output_tokens = outcome.output_tokens;
+                t.progress.current_tool = None;
+            }
+            Ok(outcome) => {
+                t.status = TaskStatus::Failed;
+                t.result = outcome.response;
+                t.error = Some(outcome.error);
+            }
+            Err(err) => {
+                t.status = TaskStatus::Failed;
+                t.error = Some(err);
+            }
+        }
+    });
+    if let Some(finished) = &finished {
+        if finished.status == TaskStatus::Completed {
+            crate::model_policy::record_model_usage(
+                &finished.model,
+                finished.input_tokens,
+                finished.output_tokens,
+            );
+            crate::usage_ledger::record_usage(
+                &finished.owner,
+                crate::usage_ledger::UsageTotals::tokens(
+                    finished.input_tokens,
+                    finished.output_tokens,
+                ),
+            );
+            if let Err(e) = crate::named_sessions::record_named_session(
+                &finished.sandbox_id,
+                &finished.owner,
+                &finished.session_name,
+                &finished.session_id,
+            ) {
+                tracing::warn!(task_id = %finished.id, error = %e, "failed to record named session");
+            }
+        }
+    }
+    close_task(&task.id);
+    crate::runtime::touch_sandbox(&task.sandbox_id);
+}
+
+/// Whether `task` resumed a named session the sidecar no longer has.
+fn named_session_is_stale(
+    task: &TaskRecord,
+    outcome: &std::result::Result<crate::operator_api::AgentStreamOutcome, String>,
+) -> bool {
+    if task.session_name.is_empty() || task.session_id.is_empty() {
+        return false;
+    }
+    match outcome {
+        Ok(outcome) => {
+            !outcome.success && crate::named_sessions::is_stale_session_error(&outcome.error)
+        }
+        Err(err) => crate::named_sessions::is_stale_session_error(err),
+    }
+}
+
+async fn run_on_sidecar(
+    record: &SandboxRecord,
+    task: &TaskRecord,
+    backend_type: &str,
+    context_json: &str,
+) -> std::result::Result<crate::operator_api::AgentStreamOutcome, String> {
+    let mut progress = task.progress.clone();
+    let request = crate::operator_api::AgentStreamRequest {
+        message: &task.prompt,
+        session_id: &task.session_id,
+        backend_type,
+        model: &task.model,
+        context_json,
+        timeout_ms: task.timeout_ms,
+        max_turns: (task.max_turns > 0).then_some(task.max_turns),
+    };
+    let call = crate::operator_api::agent_stream_on_sidecar(record, request, |event| {
+        if progress.observe(&event.event_type, &event.data) {
+            let snapshot = progress.clone();
+            if let Some(updated) = update_task(&task.id, |t| t.progress = snapshot) {
+                emit(&task.id, "progress", &updated);
+            }
+        }
+    });
+    let deadline = tokio::time::sleep(Duration::from_millis(task.timeout_ms) + SIDECAR_GRACE);
+    tokio::pin!(call, deadline);
+    let mut activity = tokio::time::interval(ACTIVITY_INTERVAL);
+    loop {
+        tokio::select! {
+            result = &mut call => return result.map_err(|(_, Json(err))| err.error),
+            _ = &mut deadline => {
+                return Err(format!(
+                    "Sidecar did not finish the task within {} ms",
+                    task.timeout_ms
+                ));
+            }
+            _ = activity.tick() => crate::runtime::touch_sandbox(&record.id),
+        }
+    }
+}
+
+/// Load a task of `sandbox_id`. Returns `NotFound` when it does not exist or
+/// belongs to another sandbox.
+pub fn get_task(sandbox_id: &str, task_id: &str) -> Result<TaskRecord> {
+    tasks()?
+        .get(task_id)?
+        .filter(|t| t.sandbox_id == sandbox_id)
+        .ok_or_else(|| SandboxError::NotFound(format!("Task '{task_id}' not found")))
+}
+
+/// Remove finished tasks older than `max_age_secs`.
+pub fn gc_tasks(max_age_secs: u64) -> Result<()> {
+    let cutoff = crate::util::now_ts().saturating_sub(max_age_secs);
+    let store = tasks()?;
+    let expired: Vec<String> = store
+        .values()?
+        .into_iter()
+        .filter(|t| t.completed_at.is_some_and(|at| at <= cutoff))
+        .map(|t| t.id)
+        .collect();
+    for id in expired {
+        store.remove(&id)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_counts_turns_and_tracks_tool() {
+        let mut progress = TaskProgress::default();
+        let part = |part: Value| json!({ "part": part });
+        assert!(progress.observe(
+            "message.part.updated",
+            &part(json!({ "type": "tool", "tool": "bash" }))
+        ));
+        assert!(!progress.observe(
+            "message.part.updated",
+            &part(json!({ "type": "tool", "tool": "bash" }))
+        ));
+        assert!(!progress.observe(
+            "message.part.updated",
+            &part(json!({ "type": "text", "text": "hi" }))
+        ));
+        assert!(progress.observe(
+            "message.part.updated",
+            &part(json!({ "type": "step-finish" }))
+        ));
+        assert!(!progress.observe("result", &part(json!({ "type": "step-finish" }))));
+        assert_eq!(
+            progress,
+            TaskProgress {
+                turns_completed: 1,
+                current_tool: Some("bash".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn terminal_statuses() {
+        assert!(!TaskStatus::Queued.is_terminal());
+        assert!(!TaskStatus::Running.is_terminal());
+        assert!(TaskStatus::Completed.is_terminal());
+        assert!(TaskStatus::Interrupted.is_terminal());
+        assert!(TaskStatus::Cancelled.is_terminal());
+    }
+}
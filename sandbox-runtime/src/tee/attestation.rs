@@ -13,6 +13,9 @@
 //! | TDX    | `/dev/tdx_guest`   | `TDX_CMD_GET_REPORT0` |
 //! | SEV-SNP| `/dev/sev-guest`   | `SNP_GET_REPORT`      |
 //! | Nitro  | `/dev/nsm`         | Not supported (use sidecar) |
+//!
+//! On TDX hosts with configfs-tsm the report is a DCAP quote from
+//! [`super::tsm`] instead; the local TDREPORT is only the fallback.
 
 use crate::error::{Result, SandboxError};
 use crate::tee::{AttestationReport, TeeType};
@@ -162,6 +165,7 @@ pub fn generate_native_attestation(
     report_data: &[u8; 64],
 ) -> Result<AttestationReport> {
     match tee_type {
+        TeeType::Tdx if super::tsm::tsm_available() => super::tsm::generate_tdx_quote(report_data),
         TeeType::Tdx => generate_tdx_attestation(report_data),
         TeeType::Sev => generate_sev_attestation(report_data),
         TeeType::Nitro => Err(SandboxError::Validation(
@@ -10,8 +10,8 @@
 //! ┌─────────────────────────────────────────────┐
 //! │  Parent EC2 Instance (EnclaveOptions=true)  │
 //! │  ┌─────────────────┐  ┌──────────────────┐ │
-//! │  │  vsock-proxy    │  │  TCP listener    │ │
-//! │  │  KMS via vsock  │  │  forwards to     │ │
+//! │  │  vsock-proxy    │  │  socat per port  │ │
+//! │  │  KMS via vsock  │  │  forwards TCP to │ │
 //! │  │                 │  │  enclave vsock   │ │
 //! │  └────────┬────────┘  └──────┬───────────┘ │
 //! │           │ vsock             │ vsock       │
@@ -27,6 +27,12 @@
 //! └─────────────────────────────────────────────┘
 //! ```
 //!
+//! The forwarders are systemd units managed per deployment by
+//! [`proxy::ProxyPlan`]: HTTP, SSH and every extra port get a parent port,
+//! the units restart on failure and follow the enclave across instance
+//! stop/start, and deploy waits for the HTTP forwarder to listen before
+//! waiting on the sidecar, so a broken proxy is reported as such.
+//!
 //! # Sealed secrets
 //!
 //! The enclave generates an ephemeral RSA-2048 key pair and embeds the public
@@ -48,6 +54,10 @@ use super::sealed_secrets::{SealedSecret, SealedSecretResult, TeePublicKey};
 use super::{AttestationReport, TeeBackend, TeeDeployParams, TeeDeployment, TeeType};
 use crate::error::{Result, SandboxError};
 
+pub mod proxy;
+
+use proxy::ProxyPlan;
+
 /// Configuration for the AWS Nitro backend, read from environment variables.
 #[derive(Clone, Debug)]
 pub struct NitroConfig {
@@ -58,6 +68,8 @@ pub struct NitroConfig {
     pub instance_type: String,
     pub kms_key_id: Option<String>,
     pub iam_instance_profile: Option<String>,
+    /// Parent ports extra enclave ports are forwarded from.
+    pub proxy_port_range: (u16, u16),
 }
 
 impl NitroConfig {
@@ -66,7 +78,8 @@ impl NitroConfig {
     /// Required: `AWS_REGION`, `AWS_NITRO_SUBNET_ID`, `AWS_NITRO_SECURITY_GROUP_ID`,
     /// `AWS_NITRO_AMI_ID`.
     /// Optional: `AWS_NITRO_INSTANCE_TYPE` (default: c5.xlarge),
-    /// `AWS_NITRO_KMS_KEY_ID`, `AWS_NITRO_IAM_INSTANCE_PROFILE`.
+    /// `AWS_NITRO_KMS_KEY_ID`, `AWS_NITRO_IAM_INSTANCE_PROFILE`,
+    /// `AWS_NITRO_PROXY_PORT_RANGE` (default: 20000-20999).
     pub fn from_env() -> Result<Self> {
         Ok(Self {
             region: require_env("AWS_REGION")?,
@@ -77,6 +90,14 @@ impl NitroConfig {
                 .unwrap_or_else(|_| "c5.xlarge".to_string()),
             kms_key_id: std::env::var("AWS_NITRO_KMS_KEY_ID").ok(),
             iam_instance_profile: std::env::var("AWS_NITRO_IAM_INSTANCE_PROFILE").ok(),
+            proxy_port_range: match std::env::var("AWS_NITRO_PROXY_PORT_RANGE") {
+                Ok(v) => proxy::parse_port_range(&v).ok_or_else(|| {
+                    SandboxError::Validation(format!(
+                        "AWS_NITRO_PROXY_PORT_RANGE must be <start>-<end>, got {v:?}"
+                    ))
+                })?,
+                Err(_) => proxy::DEFAULT_PROXY_PORT_RANGE,
+            },
         })
     }
 }
@@ -110,9 +131,9 @@ impl NitroBackend {
 
     /// Build the user-data script that configures the enclave on the parent EC2 instance.
     ///
-    /// The AMI must have `aws-nitro-enclaves-cli` pre-installed and the sidecar
-    /// EIF at `/opt/enclave/sidecar.eif`.
-    fn build_user_data(&self, params: &TeeDeployParams) -> String {
+    /// The AMI must have `aws-nitro-enclaves-cli` and `socat` pre-installed
+    /// and the sidecar EIF at `/opt/enclave/sidecar.eif`.
+    fn build_user_data(&self, params: &TeeDeployParams, plan: &ProxyPlan) -> String {
         let mut script = String::from("#!/bin/bash\nset -ex\n\n");
 
         // Configure the Nitro Enclaves allocator with requested resources.
@@ -127,41 +148,30 @@ impl NitroBackend {
             params.cpu_cores.max(2),
         ));
 
-        // Start a TCP-to-vsock forwarder so the operator can reach the sidecar
-        // HTTP API on the parent's public IP. socat bridges TCP on the parent
-        // to vsock on the enclave (CID 16).
-        script.push_str(&format!(
-            "nohup socat TCP4-LISTEN:{port},fork,reuseaddr VSOCK-CONNECT:16:{port} &\n\n",
-            port = params.http_port,
-        ));
-
-        if let Some(ssh_port) = params.ssh_port {
-            script.push_str(&format!(
-                "nohup socat TCP4-LISTEN:{ssh_port},fork,reuseaddr VSOCK-CONNECT:16:22 &\n\n",
-            ));
-        }
-
         // Write env vars as JSON for the enclave to read via vsock.
         let env_map: serde_json::Map<String, serde_json::Value> = params
             .env_vars
             .iter()
-            .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+            .cloned()
+            .chain(plan.enclave_env())
+            .map(|(k, v)| (k, serde_json::Value::String(v)))
             .collect();
         script.push_str("mkdir -p /opt/enclave\n");
         script.push_str("cat > /opt/enclave/env.json << 'ENVEOF'\n");
         script.push_str(&serde_json::to_string_pretty(&env_map).unwrap_or_default());
         script.push_str("\nENVEOF\n\n");
 
-        // Launch the enclave from the pre-baked EIF.
-        script.push_str(&format!(
-            "nitro-cli run-enclave \\\n\
-             \t--cpu-count {} \\\n\
-             \t--memory {} \\\n\
-             \t--eif-path /opt/enclave/sidecar.eif \\\n\
-             \t--enclave-cid 16\n",
+        // Launch the enclave from the pre-baked EIF, with a TCP-to-vsock
+        // forwarder per exposed port so the operator can reach the sidecar
+        // on the parent's public IP.
+        let run_enclave = format!(
+            "/usr/bin/nitro-cli run-enclave --cpu-count {} --memory {} \
+             --eif-path /opt/enclave/sidecar.eif --enclave-cid {}",
             params.cpu_cores.max(2),
             params.memory_mb.max(512),
-        ));
+            proxy::ENCLAVE_CID,
+        );
+        script.push_str(&plan.render_setup(&run_enclave));
 
         script
     }
@@ -220,8 +230,17 @@ impl TeeBackend for NitroBackend {
     async fn deploy(&self, params: &TeeDeployParams) -> Result<TeeDeployment> {
         let ec2 = self.ec2().await;
 
+        let plan = ProxyPlan::for_params(
+            params,
+            self.config
+                .kms_key_id
+                .as_ref()
+                .map(|_| self.config.region.as_str()),
+            self.config.proxy_port_range,
+        )?;
+
         // Base64-encode user data (EC2 requirement).
-        let user_data = self.build_user_data(params);
+        let user_data = self.build_user_data(params, &plan);
         let user_data_b64 = base64::engine::general_purpose::STANDARD.encode(user_data.as_bytes());
 
         // Launch EC2 instance with enclave support.
@@ -262,6 +281,10 @@ impl TeeBackend for NitroBackend {
         let public_ip = self.wait_for_running(&instance_id).await?;
         let sidecar_url = format!("http://{}:{}", public_ip, params.http_port);
 
+        // The HTTP forwarder comes up once user data has run; until it
+        // listens the sidecar cannot be reached at all.
+        wait_for_proxy(&public_ip, params.http_port, Duration::from_secs(300)).await?;
+
         // Wait for sidecar to be healthy inside the enclave.
         super::wait_for_sidecar_health(
             &sidecar_url,
@@ -285,19 +308,16 @@ impl TeeBackend for NitroBackend {
             "public_ip": public_ip,
             "region": self.config.region,
             "instance_type": self.config.instance_type,
+            "vsock_proxies": plan,
         });
 
-        if !params.extra_ports.is_empty() {
-            tracing::warn!("Extra ports not yet supported for AWS Nitro backend — ignored");
-        }
-
         Ok(TeeDeployment {
             deployment_id: instance_id,
             sidecar_url,
             ssh_port: params.ssh_port,
             attestation,
             metadata_json: metadata.to_string(),
-            extra_ports: std::collections::HashMap::new(),
+            extra_ports: plan.extra_ports(),
         })
     }
 
@@ -307,9 +327,16 @@ impl TeeBackend for NitroBackend {
         report_data: Option<[u8; 64]>,
     ) -> Result<AttestationReport> {
         let (sidecar_url, token) = super::sidecar_info_for_deployment(deployment_id)?;
-        let attestation =
-            super::fetch_sidecar_attestation_with_report_data(&sidecar_url, &token, report_data)
-                .await?;
+        let attestation = match super::fetch_sidecar_attestation_with_report_data(
+            &sidecar_url,
+            &token,
+            report_data,
+        )
+        .await
+        {
+            Ok(attestation) => attestation,
+            Err(err) => return Err(explain_unreachable(&sidecar_url, err).await),
+        };
         super::validate_attestation_report(&attestation, &TeeType::Nitro)?;
         Ok(attestation)
     }
@@ -364,6 +391,38 @@ impl TeeBackend for NitroBackend {
     }
 }
 
+/// Wait until the parent's forwarder on `port` accepts connections.
+async fn wait_for_proxy(host: &str, port: u16, timeout: Duration) -> Result<()> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while !proxy::proxy_listening(host, port).await {
+        if tokio::time::Instant::now() > deadline {
+            return Err(SandboxError::CloudProvider(format!(
+                "vsock proxy on {host}:{port} did not start listening; check the parent \
+                 instance's nitro-proxy-http.service"
+            )));
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+    Ok(())
+}
+
+/// Tell a dead forwarder apart from a sidecar that does not answer.
+async fn explain_unreachable(sidecar_url: &str, err: SandboxError) -> SandboxError {
+    let Some((host, port)) = reqwest::Url::parse(sidecar_url)
+        .ok()
+        .and_then(|url| Some((url.host_str()?.to_string(), url.port()?)))
+    else {
+        return err;
+    };
+    if proxy::proxy_listening(&host, port).await {
+        return err;
+    }
+    SandboxError::CloudProvider(format!(
+        "vsock proxy on {host}:{port} is not accepting connections \
+         (nitro-proxy-http.service on the parent instance): {err}"
+    ))
+}
+
 fn require_env(name: &str) -> Result<String> {
     std::env::var(name).map_err(|_| {
         SandboxError::Validation(format!(
@@ -385,6 +444,7 @@ mod tests {
             instance_type: "c5.xlarge".into(),
             kms_key_id: None,
             iam_instance_profile: None,
+            proxy_port_range: proxy::DEFAULT_PROXY_PORT_RANGE,
         })
     }
 
@@ -0,0 +1,299 @@
+//! Parent-instance vsock proxies for a Nitro enclave.
+//!
+//! The enclave has no network interface, so everything that reaches the
+//! sidecar — the operator's HTTP calls, SSH, extra user ports — goes through
+//! a TCP → vsock forwarder on the parent instance, and the enclave's own KMS
+//! calls go out through AWS's `vsock-proxy`. A [`ProxyPlan`] allocates the
+//! parent ports for one deployment and renders each forwarder as a systemd
+//! unit bound to the enclave unit: the forwarders restart when they die,
+//! stop when the enclave stops, and come back with it when a stopped
+//! instance is started again. The parent instance needs `socat` (and
+//! `vsock-proxy` from `aws-nitro-enclaves-cli` when a KMS key is set).
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::super::TeeDeployParams;
+use crate::error::{Result, SandboxError};
+
+/// CID the enclave is launched with.
+pub const ENCLAVE_CID: u32 = 16;
+/// CID of the parent instance as seen from inside the enclave.
+pub const PARENT_CID: u32 = 3;
+/// Parent vsock port the KMS proxy listens on.
+pub const KMS_PROXY_VSOCK_PORT: u16 = 8000;
+/// Enclave port the sidecar's sshd listens on.
+const ENCLAVE_SSH_PORT: u16 = 22;
+/// Parent ports extra user ports are mapped onto when
+/// `AWS_NITRO_PROXY_PORT_RANGE` is unset.
+pub const DEFAULT_PROXY_PORT_RANGE: (u16, u16) = (20000, 20999);
+/// systemd unit that runs the enclave.
+pub const ENCLAVE_UNIT: &str = "nitro-sidecar-enclave.service";
+
+/// One TCP (parent) → vsock (enclave) forwarder.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InboundProxy {
+    /// `http`, `ssh` or `port-<enclave_port>`.
+    pub name: String,
+    pub parent_port: u16,
+    pub enclave_port: u16,
+}
+
+/// The forwarders of one deployment.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyPlan {
+    pub inbound: Vec<InboundProxy>,
+    /// `kms.<region>.amazonaws.com`, when the enclave decrypts with KMS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kms_endpoint: Option<String>,
+}
+
+/// Parse `AWS_NITRO_PROXY_PORT_RANGE` (`20000-20999`).
+pub fn parse_port_range(value: &str) -> Option<(u16, u16)> {
+    let (start, end) = value.trim().split_once('-')?;
+    let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+    (start > 0 && start <= end).then_some((start, end))
+}
+
+impl ProxyPlan {
+    /// Plan the forwarders for `params`: the sidecar HTTP port and SSH keep
+    /// their requested parent ports, extra ports get the lowest free ports
+    /// of `port_range`.
+    pub fn for_params(
+        params: &TeeDeployParams,
+        kms_region: Option<&str>,
+        port_range: (u16, u16),
+    ) -> Result<Self> {
+        let mut inbound = vec![InboundProxy {
+            name: "http".to_string(),
+            parent_port: params.http_port,
+            enclave_port: params.http_port,
+        }];
+        if let Some(ssh_port) = params.ssh_port {
+            inbound.push(InboundProxy {
+                name: "ssh".to_string(),
+                parent_port: ssh_port,
+                enclave_port: ENCLAVE_SSH_PORT,
+            });
+        }
+        let mut taken: HashSet<u16> = inbound.iter().map(|p| p.parent_port).collect();
+        let mut candidates = port_range.0..=port_range.1;
+        let mut extra: Vec<u16> = params.extra_ports.clone();
+        extra.sort_unstable();
+        extra.dedup();
+        for enclave_port in extra {
+            if enclave_port == params.http_port {
+                continue;
+            }
+            let parent_port = candidates.find(|p| !taken.contains(p)).ok_or_else(|| {
+                SandboxError::Validation(format!(
+                    "No free parent port in {}-{} for enclave port {enclave_port}; \
+                         widen AWS_NITRO_PROXY_PORT_RANGE",
+                    port_range.0, port_range.1
+                ))
+            })?;
+            taken.insert(parent_port);
+            inbound.push(InboundProxy {
+                name: format!("port-{enclave_port}"),
+                parent_port,
+                enclave_port,
+            });
+        }
+        Ok(Self {
+            inbound,
+            kms_endpoint: kms_region.map(|region| format!("kms.{region}.amazonaws.com")),
+        })
+    }
+
+    /// The parent port the sidecar HTTP API is reachable on.
+    pub fn http_port(&self) -> Option<u16> {
+        self.port_of("http")
+    }
+
+    fn port_of(&self, name: &str) -> Option<u16> {
+        self.inbound
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.parent_port)
+    }
+
+    /// Extra ports as `enclave port → parent port`, for `TeeDeployment`.
+    pub fn extra_ports(&self) -> HashMap<u16, u16> {
+        self.inbound
+            .iter()
+            .filter(|p| p.name.starts_with("port-"))
+            .map(|p| (p.enclave_port, p.parent_port))
+            .collect()
+    }
+
+    /// Env vars telling the sidecar how to reach the parent's proxies.
+    pub fn enclave_env(&self) -> Vec<(String, String)> {
+        match self.kms_endpoint {
+            Some(_) => vec![(
+                "KMS_VSOCK_PROXY".to_string(),
+                format!("{PARENT_CID}:{KMS_PROXY_VSOCK_PORT}"),
+            )],
+            None => Vec::new(),
+        }
+    }
+
+    /// User-data shell that installs the enclave and proxy units and starts
+    /// them. `run_enclave` is the `nitro-cli run-enclave` command line.
+    pub fn render_setup(&self, run_enclave: &str) -> String {
+        let mut script = String::new();
+        script.push_str(&unit_file(
+            ENCLAVE_UNIT,
+            &format!(
+                "[Unit]\n\
+                 Description=Sandbox sidecar Nitro enclave\n\
+                 After=nitro-enclaves-allocator.service\n\
+                 Requires=nitro-enclaves-allocator.service\n\n\
+                 [Service]\n\
+                 Type=oneshot\n\
+                 RemainAfterExit=yes\n\
+                 ExecStart={run_enclave}\n\
+                 ExecStop=/usr/bin/nitro-cli terminate-enclave --all\n\n\
+                 [Install]\n\
+                 WantedBy=multi-user.target\n"
+            ),
+        ));
+        let mut units = vec![ENCLAVE_UNIT.to_string()];
+        for proxy in &self.inbound {
+            let unit = format!("nitro-proxy-{}.service", proxy.name);
+            script.push_str(&unit_file(
+                &unit,
+                &proxy_unit(
+                    &format!(
+                        "Enclave {} proxy (tcp:{} -> vsock:{ENCLAVE_CID}:{})",
+                        proxy.name, proxy.parent_port, proxy.enclave_port
+                    ),
+                    &format!(
+                        "/usr/bin/socat TCP4-LISTEN:{},fork,reuseaddr VSOCK-CONNECT:{ENCLAVE_CID}:{}",
+                        proxy.parent_port, proxy.enclave_port
+                    ),
+                ),
+            ));
+            units.push(unit);
+        }
+        if let Some(endpoint) = &self.kms_endpoint {
+            let unit = "nitro-proxy-kms.service".to_string();
+            script.push_str(&unit_file(
+                &unit,
+                &proxy_unit(
+                    &format!("Enclave KMS proxy (vsock:{KMS_PROXY_VSOCK_PORT} -> {endpoint}:443)"),
+                    &format!("/usr/bin/vsock-proxy {KMS_PROXY_VSOCK_PORT} {endpoint} 443"),
+                ),
+            ));
+            units.push(unit);
+        }
+        script.push_str("systemctl daemon-reload\n");
+        script.push_str(&format!("systemctl enable --now {}\n\n", units.join(" ")));
+        script
+    }
+}
+
+fn unit_file(name: &str, body: &str) -> String {
+    format!("cat > /etc/systemd/system/{name} << 'UNITEOF'\n{body}UNITEOF\n\n")
+}
+
+/// A forwarder that restarts on failure and lives and dies with the enclave.
+fn proxy_unit(description: &str, exec_start: &str) -> String {
+    format!(
+        "[Unit]\n\
+         Description={description}\n\
+         BindsTo={ENCLAVE_UNIT}\n\
+         After={ENCLAVE_UNIT}\n\n\
+         [Service]\n\
+         ExecStart={exec_start}\n\
+         Restart=always\n\
+         RestartSec=2\n\n\
+         [Install]\n\
+         WantedBy={ENCLAVE_UNIT}\n"
+    )
+}
+
+/// Whether the parent's forwarder on `port` accepts TCP connections.
+pub async fn proxy_listening(host: &str, port: u16) -> bool {
+    matches!(
+        tokio::time::timeout(
+            std::time::Duration::from_secs(3),
+            tokio::net::TcpStream::connect((host, port)),
+        )
+        .await,
+        Ok(Ok(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(extra_ports: Vec<u16>) -> TeeDeployParams {
+        TeeDeployParams {
+            sandbox_id: "sb-1".into(),
+            image: "sidecar:latest".into(),
+            env_vars: Vec::new(),
+            cpu_cores: 2,
+            memory_mb: 1024,
+            disk_gb: 10,
+            http_port: 8080,
+            ssh_port: Some(2222),
+            sidecar_token: "token".into(),
+            extra_ports,
+            attestation_report_data: None,
+        }
+    }
+
+    #[test]
+    fn extra_ports_get_free_parent_ports_from_the_range() {
+        let plan =
+            ProxyPlan::for_params(&params(vec![3000, 5173, 3000]), None, (2222, 2224)).unwrap();
+        assert_eq!(plan.http_port(), Some(8080));
+        assert_eq!(plan.port_of("ssh"), Some(2222));
+        assert_eq!(
+            plan.extra_ports(),
+            HashMap::from([(3000, 2223), (5173, 2224)])
+        );
+        let err =
+            ProxyPlan::for_params(&params(vec![3000, 5173, 9000]), None, (2222, 2224)).unwrap_err();
+        assert!(
+            err.to_string().contains("AWS_NITRO_PROXY_PORT_RANGE"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn setup_binds_every_proxy_to_the_enclave_unit() {
+        let plan =
+            ProxyPlan::for_params(&params(vec![3000]), Some("us-east-1"), (20000, 20999)).unwrap();
+        let script = plan.render_setup("/usr/bin/nitro-cli run-enclave --enclave-cid 16");
+        assert!(script.contains("TCP4-LISTEN:8080,fork,reuseaddr VSOCK-CONNECT:16:8080"));
+        assert!(script.contains("TCP4-LISTEN:2222,fork,reuseaddr VSOCK-CONNECT:16:22"));
+        assert!(script.contains("TCP4-LISTEN:20000,fork,reuseaddr VSOCK-CONNECT:16:3000"));
+        assert!(script.contains("vsock-proxy 8000 kms.us-east-1.amazonaws.com 443"));
+        assert_eq!(
+            script
+                .matches("BindsTo=nitro-sidecar-enclave.service")
+                .count(),
+            4
+        );
+        assert!(script.contains(
+            "systemctl enable --now nitro-sidecar-enclave.service nitro-proxy-http.service \
+             nitro-proxy-ssh.service nitro-proxy-port-3000.service nitro-proxy-kms.service"
+        ));
+        assert_eq!(
+            plan.enclave_env(),
+            vec![("KMS_VSOCK_PROXY".to_string(), "3:8000".to_string())]
+        );
+    }
+
+    #[test]
+    fn port_ranges_parse() {
+        assert_eq!(parse_port_range("20000-20999"), Some((20000, 20999)));
+        assert_eq!(parse_port_range(" 3000 - 3000 "), Some((3000, 3000)));
+        assert_eq!(parse_port_range("3001-3000"), None);
+        assert_eq!(parse_port_range("0-10"), None);
+        assert_eq!(parse_port_range("abc"), None);
+    }
+}
@@ -19,6 +19,49 @@ pub struct TeeDeployment {
     pub extra_ports: std::collections::HashMap<u16, u16>,
 }
 
+/// Provider-side state of a TEE deployment.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TeeDeploymentStatus {
+    pub deployment_id: String,
+    /// State as the provider reports it (`running`, `stopped`, `starting`, ...).
+    pub state: String,
+    pub running: bool,
+    /// Seconds since the deployment last started. `None` when it is not
+    /// running or the provider does not say.
+    pub uptime_secs: Option<u64>,
+}
+
+/// What a TEE deployment has cost so far, for billing reconciliation.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TeeBillingInfo {
+    pub deployment_id: String,
+    /// Billable seconds since the deployment last started.
+    pub billed_secs: u64,
+    /// Hourly price in `currency`, when known.
+    pub hourly_rate: Option<f64>,
+    /// `billed_secs` at `hourly_rate`.
+    pub accrued_cost: Option<f64>,
+    pub currency: String,
+}
+
+impl TeeBillingInfo {
+    /// Bill `status`'s uptime at `hourly_rate`.
+    pub fn from_uptime(
+        status: &TeeDeploymentStatus,
+        hourly_rate: Option<f64>,
+        currency: &str,
+    ) -> Self {
+        let billed_secs = status.uptime_secs.unwrap_or(0);
+        Self {
+            deployment_id: status.deployment_id.clone(),
+            billed_secs,
+            hourly_rate,
+            accrued_cost: hourly_rate.map(|rate| rate * billed_secs as f64 / 3600.0),
+            currency: currency.to_string(),
+        }
+    }
+}
+
 /// Async trait for TEE backend implementations.
 ///
 /// Each backend (Phala dstack, operator-managed TDX/SEV hardware, cloud TEE, etc.)
@@ -50,6 +93,33 @@ pub trait TeeBackend: Send + Sync {
         false
     }
 
+    /// Whether the backend's control plane is responding, for `/health/ready`.
+    /// Default: always healthy once registered.
+    async fn health(&self) -> crate::error::Result<()> {
+        Ok(())
+    }
+
+    // ── Provider status and billing (optional, default: not supported) ──
+
+    /// Provider-side state and uptime of a deployment.
+    async fn status(&self, deployment_id: &str) -> crate::error::Result<TeeDeploymentStatus> {
+        let _ = deployment_id;
+        Err(crate::error::SandboxError::Validation(format!(
+            "Deployment status not supported by {:?} backend",
+            self.tee_type()
+        )))
+    }
+
+    /// Cost accrued by a deployment, for reconciling CVM charges against
+    /// what the instance was billed.
+    async fn billing_info(&self, deployment_id: &str) -> crate::error::Result<TeeBillingInfo> {
+        let _ = deployment_id;
+        Err(crate::error::SandboxError::Validation(format!(
+            "Billing info not supported by {:?} backend",
+            self.tee_type()
+        )))
+    }
+
     // ── Sealed secrets (optional, default: not supported) ────────────────
 
     /// Derive a TEE-bound public key for sealed secret encryption.
@@ -38,7 +38,16 @@ pub fn backend_from_env() -> Result<Arc<dyn TeeBackend>> {
             // PhalaBackend::new has copied what it needs.
             let api_key = zeroize::Zeroizing::new(require_env("PHALA_API_KEY")?);
             let api_endpoint = std::env::var("PHALA_API_ENDPOINT").ok();
-            let backend = super::phala::PhalaBackend::new(&api_key, api_endpoint)?;
+            let hourly_rate = match std::env::var("PHALA_HOURLY_RATE_USD") {
+                Ok(v) => Some(v.trim().parse::<f64>().map_err(|_| {
+                    SandboxError::Validation(format!(
+                        "PHALA_HOURLY_RATE_USD must be a number, got {v:?}"
+                    ))
+                })?),
+                Err(_) => None,
+            };
+            let backend = super::phala::PhalaBackend::new(&api_key, api_endpoint)?
+                .with_hourly_rate(hourly_rate);
             Ok(Arc::new(backend))
         }
 
@@ -0,0 +1,484 @@
+//! GCP Confidential Space attestation token verification.
+//!
+//! A Confidential Space workload proves where it runs with an OIDC token
+//! minted by Google's attestation service: an RS256 JWT signed by a key
+//! published in the service's JWKS, whose claims describe the VM (hardware
+//! model, debug status, secure boot), the Confidential Space image and the
+//! workload container. The token is only as good as the checks applied to
+//! it, so [`verify_token_with_jwks`] fails closed on every one:
+//!
+//! - the signature verifies against the JWKS key named by the header `kid`;
+//! - `iss` is [`CONFIDENTIAL_SPACE_ISSUER`], the token is inside its
+//!   `nbf`/`exp` window, and `aud` matches when an audience is expected;
+//! - `swname` is `CONFIDENTIAL_SPACE`, `dbgstat` is `disabled-since-boot`
+//!   and `secboot` is set (a debug image exposes the workload to the host);
+//! - the Confidential Space image carries the required support attributes
+//!   (`STABLE` by default);
+//! - the container's `image_digest` is in the pinned allowlist;
+//! - when a nonce was requested, it is one of the token's `eat_nonce`s.
+//!
+//! The operator checks tokens the GCP backend receives; customers run the
+//! same checks against a token they fetched themselves with [`verify_token`]
+//! (the `gcp-attestation` feature, without the rest of the GCP backend).
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use rsa::{BigUint, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+
+/// `iss` of every Confidential Space attestation token.
+pub const CONFIDENTIAL_SPACE_ISSUER: &str = "https://confidentialcomputing.googleapis.com";
+/// `swname` of a workload running on the Confidential Space image.
+pub const CONFIDENTIAL_SPACE_SWNAME: &str = "CONFIDENTIAL_SPACE";
+/// `dbgstat` of a production (non-debug) Confidential Space image.
+pub const DEBUG_DISABLED: &str = "disabled-since-boot";
+/// Clock skew tolerated on `exp`/`nbf`/`iat`.
+pub const TOKEN_LEEWAY_SECS: u64 = 60;
+
+/// One RSA signing key of the attestation service's JWKS.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Jwk {
+    pub kid: String,
+    pub kty: String,
+    #[serde(default)]
+    pub alg: Option<String>,
+    /// Base64url modulus.
+    pub n: String,
+    /// Base64url public exponent.
+    pub e: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+/// `aud` and `eat_nonce` may be a single string or an array.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    pub fn contains(&self, value: &str) -> bool {
+        match self {
+            Self::None => false,
+            Self::One(v) => v == value,
+            Self::Many(vs) => vs.iter().any(|v| v == value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerClaims {
+    #[serde(default)]
+    pub image_reference: String,
+    #[serde(default)]
+    pub image_digest: String,
+    #[serde(default)]
+    pub restart_policy: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfidentialSpaceSubmod {
+    #[serde(default)]
+    pub support_attributes: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Submods {
+    #[serde(default)]
+    pub container: ContainerClaims,
+    #[serde(default)]
+    pub confidential_space: ConfidentialSpaceSubmod,
+}
+
+/// The claims of a verified token that callers act on.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfidentialSpaceClaims {
+    pub iss: String,
+    #[serde(default)]
+    pub aud: OneOrMany,
+    #[serde(default)]
+    pub sub: String,
+    pub exp: u64,
+    #[serde(default)]
+    pub iat: u64,
+    #[serde(default)]
+    pub nbf: Option<u64>,
+    /// `GCP_AMD_SEV`, `GCP_AMD_SEV_SNP` or `GCP_INTEL_TDX`.
+    #[serde(default)]
+    pub hwmodel: String,
+    #[serde(default)]
+    pub swname: String,
+    #[serde(default)]
+    pub swversion: Vec<String>,
+    #[serde(default)]
+    pub dbgstat: String,
+    #[serde(default)]
+    pub secboot: bool,
+    #[serde(default)]
+    pub eat_nonce: OneOrMany,
+    #[serde(default)]
+    pub submods: Submods,
+}
+
+/// What a token must attest to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenExpectations {
+    /// Required `aud`; `None` accepts any audience.
+    pub audience: Option<String>,
+    /// Allowed container digests (`sha256:<hex>`). Empty never matches.
+    pub image_digests: Vec<String>,
+    /// Support attributes the Confidential Space image must carry.
+    pub support_attributes: Vec<String>,
+    /// Nonce the token must carry in `eat_nonce`.
+    pub nonce: Option<String>,
+}
+
+impl TokenExpectations {
+    /// Expect a production image running one of `image_digests`.
+    pub fn for_digests(image_digests: Vec<String>) -> Self {
+        Self {
+            audience: None,
+            image_digests,
+            support_attributes: vec!["STABLE".to_string()],
+            nonce: None,
+        }
+    }
+}
+
+/// Whether `token` looks like a compact JWT (three base64url segments), as
+/// opposed to a raw hardware quote.
+pub fn is_jwt(token: &[u8]) -> bool {
+    let Ok(token) = std::str::from_utf8(token) else {
+        return false;
+    };
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    parts.len() == 3
+        && parts.iter().all(|p| {
+            !p.is_empty()
+                && p.bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        })
+}
+
+fn decode_segment(label: &str, segment: &str) -> Result<Vec<u8>, String> {
+    URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|e| format!("token {label} is not valid base64url: {e}"))
+}
+
+/// Verify `token` against `jwks` and `expect` at `now_secs`, returning its
+/// claims. Any failed check is an `Err` naming it.
+pub fn verify_token_with_jwks(
+    token: &str,
+    jwks: &Jwks,
+    expect: &TokenExpectations,
+    now_secs: u64,
+) -> Result<ConfidentialSpaceClaims, String> {
+    let mut parts = token.trim().split('.');
+    let (Some(header_b64), Some(payload_b64), Some(sig_b64), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err("token is not a compact JWT".to_string());
+    };
+
+    let header: JwtHeader = serde_json::from_slice(&decode_segment("header", header_b64)?)
+        .map_err(|e| format!("token header is invalid: {e}"))?;
+    if header.alg != "RS256" {
+        return Err(format!("token alg {} is not RS256", header.alg));
+    }
+    let kid = header.kid.ok_or("token header has no kid")?;
+    let jwk = jwks
+        .keys
+        .iter()
+        .find(|k| k.kid == kid)
+        .ok_or_else(|| format!("token kid {kid} is not in the attestation service JWKS"))?;
+    if jwk.kty != "RSA" || jwk.alg.as_deref().is_some_and(|alg| alg != "RS256") {
+        return Err(format!("JWKS key {kid} is not an RS256 key"));
+    }
+    let key = RsaPublicKey::new(
+        BigUint::from_bytes_be(&decode_segment("key modulus", &jwk.n)?),
+        BigUint::from_bytes_be(&decode_segment("key exponent", &jwk.e)?),
+    )
+    .map_err(|e| format!("JWKS key {kid} is invalid: {e}"))?;
+    let signature = Signature::try_from(decode_segment("signature", sig_b64)?.as_slice())
+        .map_err(|e| format!("token signature is malformed: {e}"))?;
+    let signing_input = &token.trim()[..header_b64.len() + 1 + payload_b64.len()];
+    VerifyingKey::<sha2::Sha256>::new(key)
+        .verify(signing_input.as_bytes(), &signature)
+        .map_err(|_| "token signature does not verify against the JWKS key".to_string())?;
+
+    let claims: ConfidentialSpaceClaims =
+        serde_json::from_slice(&decode_segment("payload", payload_b64)?)
+            .map_err(|e| format!("token claims are invalid: {e}"))?;
+    check_claims(&claims, expect, now_secs)?;
+    Ok(claims)
+}
+
+fn check_claims(
+    claims: &ConfidentialSpaceClaims,
+    expect: &TokenExpectations,
+    now_secs: u64,
+) -> Result<(), String> {
+    if claims.iss != CONFIDENTIAL_SPACE_ISSUER {
+        return Err(format!(
+            "token issuer {} is not Confidential Space",
+            claims.iss
+        ));
+    }
+    if claims.exp + TOKEN_LEEWAY_SECS <= now_secs {
+        return Err(format!("token expired at {}", claims.exp));
+    }
+    let not_before = claims.nbf.unwrap_or(claims.iat);
+    if not_before > now_secs + TOKEN_LEEWAY_SECS {
+        return Err(format!("token is not valid before {not_before}"));
+    }
+    if let Some(audience) = &expect.audience
+        && !claims.aud.contains(audience)
+    {
+        return Err(format!("token audience does not include {audience}"));
+    }
+    if claims.swname != CONFIDENTIAL_SPACE_SWNAME {
+        return Err(format!(
+            "token swname {} is not {CONFIDENTIAL_SPACE_SWNAME}",
+            claims.swname
+        ));
+    }
+    if claims.dbgstat != DEBUG_DISABLED {
+        return Err(format!(
+            "workload runs on a debug Confidential Space image (dbgstat {})",
+            claims.dbgstat
+        ));
+    }
+    if !claims.secboot {
+        return Err("workload VM did not boot with secure boot".to_string());
+    }
+    let support = &claims.submods.confidential_space.support_attributes;
+    if let Some(missing) = expect
+        .support_attributes
+        .iter()
+        .find(|attr| !support.contains(attr))
+    {
+        return Err(format!(
+            "Confidential Space image lacks support attribute {missing} (has [{}])",
+            support.join(", ")
+        ));
+    }
+    let digest = &claims.submods.container.image_digest;
+    if expect.image_digests.is_empty() {
+        return Err("no expected image digest configured".to_string());
+    }
+    if !expect.image_digests.iter().any(|d| d == digest) {
+        return Err(format!("container image digest {digest} is not allowed"));
+    }
+    if let Some(nonce) = &expect.nonce
+        && !claims.eat_nonce.contains(nonce)
+    {
+        return Err("token does not carry the requested nonce (possible replay)".to_string());
+    }
+    Ok(())
+}
+
+/// Fetch the attestation service's JWKS via its OIDC discovery document.
+pub async fn fetch_jwks(http: &reqwest::Client) -> Result<Jwks, String> {
+    let discovery: serde_json::Value = http
+        .get(format!(
+            "{CONFIDENTIAL_SPACE_ISSUER}/.well-known/openid-configuration"
+        ))
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| format!("OIDC discovery request failed: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("OIDC discovery document is invalid: {e}"))?;
+    let jwks_uri = discovery["jwks_uri"]
+        .as_str()
+        .ok_or("OIDC discovery document has no jwks_uri")?;
+    http.get(jwks_uri)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| format!("JWKS request failed: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("JWKS is invalid: {e}"))
+}
+
+/// Fetch Google's current JWKS and verify `token` against it now.
+pub async fn verify_token(
+    token: &str,
+    expect: &TokenExpectations,
+) -> Result<ConfidentialSpaceClaims, String> {
+    let http = crate::util::external_http_client().map_err(|e| e.to_string())?;
+    let jwks = fetch_jwks(http).await?;
+    verify_token_with_jwks(token, &jwks, expect, crate::util::now_ts())
+}
+
+/// `sha256:<hex>` digest pinned in an image reference (`repo@sha256:...`).
+pub fn pinned_image_digest(image: &str) -> Option<String> {
+    image
+        .rsplit_once('@')
+        .map(|(_, digest)| digest.trim().to_string())
+        .filter(|digest| digest.starts_with("sha256:"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::RsaPrivateKey;
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::signature::{SignatureEncoding, Signer};
+    use rsa::traits::PublicKeyParts;
+
+    const DIGEST: &str = "sha256:1111111111111111111111111111111111111111111111111111111111111111";
+    const NOW: u64 = 1_700_000_000;
+
+    fn key_pair() -> (RsaPrivateKey, Jwks) {
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).expect("rsa key");
+        let jwks = Jwks {
+            keys: vec![Jwk {
+                kid: "test-kid".into(),
+                kty: "RSA".into(),
+                alg: Some("RS256".into()),
+                n: URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+                e: URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+            }],
+        };
+        (key, jwks)
+    }
+
+    fn claims() -> serde_json::Value {
+        serde_json::json!({
+            "iss": CONFIDENTIAL_SPACE_ISSUER,
+            "aud": "https://sts.googleapis.com",
+            "sub": "https://www.googleapis.com/compute/v1/projects/p/zones/z/instances/i",
+            "exp": NOW + 3600,
+            "iat": NOW - 10,
+            "nbf": NOW - 10,
+            "hwmodel": "GCP_INTEL_TDX",
+            "swname": "CONFIDENTIAL_SPACE",
+            "swversion": ["240500"],
+            "dbgstat": "disabled-since-boot",
+            "secboot": true,
+            "eat_nonce": ["nonce-1", "nonce-2"],
+            "submods": {
+                "container": {
+                    "image_reference": "ghcr.io/example/sidecar@sha256:1111",
+                    "image_digest": DIGEST,
+                    "restart_policy": "Never"
+                },
+                "confidential_space": { "support_attributes": ["LATEST", "STABLE", "USABLE"] }
+            }
+        })
+    }
+
+    fn sign(key: &RsaPrivateKey, claims: &serde_json::Value) -> String {
+        let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"RS256","kid":"test-kid","typ":"JWT"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let input = format!("{header}.{payload}");
+        let signature = SigningKey::<sha2::Sha256>::new(key.clone()).sign(input.as_bytes());
+        format!("{input}.{}", URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+    }
+
+    #[test]
+    fn genuine_token_verifies_and_binds_the_nonce() {
+        let (key, jwks) = key_pair();
+        let token = sign(&key, &claims());
+        assert!(is_jwt(token.as_bytes()));
+        let mut expect = TokenExpectations::for_digests(vec![DIGEST.into()]);
+        expect.audience = Some("https://sts.googleapis.com".into());
+        expect.nonce = Some("nonce-2".into());
+        let verified = verify_token_with_jwks(&token, &jwks, &expect, NOW).unwrap();
+        assert_eq!(verified.hwmodel, "GCP_INTEL_TDX");
+        assert_eq!(verified.submods.container.image_digest, DIGEST);
+
+        expect.nonce = Some("other".into());
+        let err = verify_token_with_jwks(&token, &jwks, &expect, NOW).unwrap_err();
+        assert!(err.contains("nonce"), "{err}");
+    }
+
+    #[test]
+    fn tampered_or_foreign_tokens_are_rejected() {
+        let (key, jwks) = key_pair();
+        let expect = TokenExpectations::for_digests(vec![DIGEST.into()]);
+        let token = sign(&key, &claims());
+        let (input, _) = token.rsplit_once('.').unwrap();
+        let (header, _) = input.split_once('.').unwrap();
+        let mut forged = claims();
+        forged["submods"]["container"]["image_digest"] = "sha256:22".into();
+        let tampered = format!(
+            "{header}.{}.{}",
+            URL_SAFE_NO_PAD.encode(forged.to_string()),
+            token.rsplit_once('.').unwrap().1
+        );
+        let err = verify_token_with_jwks(&tampered, &jwks, &expect, NOW).unwrap_err();
+        assert!(err.contains("signature"), "{err}");
+
+        let (other_key, _) = key_pair();
+        let err =
+            verify_token_with_jwks(&sign(&other_key, &claims()), &jwks, &expect, NOW).unwrap_err();
+        assert!(err.contains("signature"), "{err}");
+    }
+
+    #[test]
+    fn claims_checks_fail_closed() {
+        let (key, jwks) = key_pair();
+        let expect = TokenExpectations::for_digests(vec![DIGEST.into()]);
+        let cases: [(&str, serde_json::Value, &str); 6] = [
+            ("/exp", serde_json::json!(NOW - 3600), "expired"),
+            ("/dbgstat", serde_json::json!("enabled"), "debug"),
+            ("/secboot", serde_json::json!(false), "secure boot"),
+            ("/swname", serde_json::json!("GCE"), "swname"),
+            (
+                "/submods/confidential_space/support_attributes",
+                serde_json::json!([]),
+                "STABLE",
+            ),
+            (
+                "/submods/container/image_digest",
+                serde_json::json!("sha256:22"),
+                "not allowed",
+            ),
+        ];
+        for (pointer, value, reason) in cases {
+            let mut claims = claims();
+            *claims.pointer_mut(pointer).unwrap() = value;
+            let err =
+                verify_token_with_jwks(&sign(&key, &claims), &jwks, &expect, NOW).unwrap_err();
+            assert!(err.contains(reason), "{pointer}: {err}");
+        }
+        let err = verify_token_with_jwks(
+            &sign(&key, &claims()),
+            &jwks,
+            &TokenExpectations::for_digests(Vec::new()),
+            NOW,
+        )
+        .unwrap_err();
+        assert!(err.contains("no expected image digest"), "{err}");
+    }
+
+    #[test]
+    fn pinned_digests_come_from_the_image_reference() {
+        assert_eq!(
+            pinned_image_digest("ghcr.io/x/sidecar@sha256:abc").as_deref(),
+            Some("sha256:abc")
+        );
+        assert_eq!(pinned_image_digest("ghcr.io/x/sidecar:latest"), None);
+    }
+}
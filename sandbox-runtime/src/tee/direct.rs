@@ -5,8 +5,11 @@
 //! or have TEE device nodes available (`/dev/tdx_guest`, `/dev/sev-guest`,
 //! `/dev/nsm`).
 //!
-//! Attestation is fetched from the sidecar's `/tee/attestation` endpoint,
-//! which queries the host TEE device from inside the container.
+//! Attestation is generated on the host: a DCAP quote through configfs-tsm
+//! on TDX (see [`super::tsm`]), an `SNP_GET_REPORT` on SEV-SNP. Without a
+//! caller nonce the report data binds the evidence to the sidecar token and
+//! its sealed-secrets public key. Hosts without native support fall back to
+//! the sidecar's `/tee/attestation` endpoint for nonce-free requests.
 //!
 //! # Required environment
 //!
@@ -31,7 +34,7 @@ use docktopus::bollard::models::{DeviceMapping, HostConfig, PortBinding, PortMap
 use docktopus::container::Container;
 
 use super::sealed_secrets::{SealedSecret, SealedSecretResult, TeePublicKey};
-use super::{AttestationReport, TeeBackend, TeeDeployParams, TeeDeployment, TeeType};
+use super::{AttestationReport, TeeBackend, TeeDeployParams, TeeDeployment, TeeType, tsm};
 use crate::error::{Result, SandboxError};
 use crate::runtime::{SidecarRuntimeConfig, docker_builder, docker_timeout};
 
@@ -199,11 +202,7 @@ impl DirectTeeBackend {
 #[async_trait::async_trait]
 impl TeeBackend for DirectTeeBackend {
     async fn deploy(&self, params: &TeeDeployParams) -> Result<TeeDeployment> {
-        if params.attestation_report_data.is_some() && self.tee_type == TeeType::Tdx {
-            return Err(SandboxError::Validation(
-                "Direct TDX nonce-bound remote attestation requires a DCAP TD quote; /dev/tdx_guest TDX_CMD_GET_REPORT0 returns a local TDREPORT only".into(),
-            ));
-        }
+        tsm::ensure_report_data_supported(&self.tee_type, params.attestation_report_data.as_ref())?;
 
         let builder = docker_builder().await?;
         let config = SidecarRuntimeConfig::load();
@@ -280,11 +279,10 @@ impl TeeBackend for DirectTeeBackend {
         .await?;
 
         // Try native attestation first, fall back to sidecar.
-        let nonce = params.attestation_report_data.unwrap_or_else(|| {
-            let mut nonce = [0u8; 64];
-            rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
-            nonce
-        });
+        let nonce = match params.attestation_report_data {
+            Some(report_data) => report_data,
+            None => tsm::sidecar_binding_report_data(&sidecar_url, &params.sidecar_token).await,
+        };
         // The native-attestation ioctl round-trips to firmware/PSP and can block
         // for milliseconds; run it off the async worker thread (matching the
         // firecracker.rs convention) so it never stalls the tokio runtime.
@@ -302,7 +300,8 @@ impl TeeBackend for DirectTeeBackend {
                 att
             }
             Err(native_err) => {
-                if params.attestation_report_data.is_some() {
+                if params.attestation_report_data.is_some() || tsm::quotes_natively(&self.tee_type)
+                {
                     return Err(native_err);
                 }
                 tracing::warn!(error = %native_err, "Native attestation unavailable, falling back to sidecar");
@@ -330,17 +329,13 @@ impl TeeBackend for DirectTeeBackend {
         deployment_id: &str,
         report_data: Option<[u8; 64]>,
     ) -> Result<AttestationReport> {
-        if report_data.is_some() && self.tee_type == TeeType::Tdx {
-            return Err(SandboxError::Validation(
-                "Direct TDX nonce-bound remote attestation requires a DCAP TD quote; /dev/tdx_guest TDX_CMD_GET_REPORT0 returns a local TDREPORT only".into(),
-            ));
-        }
+        tsm::ensure_report_data_supported(&self.tee_type, report_data.as_ref())?;
 
-        let nonce = report_data.unwrap_or_else(|| {
-            let mut nonce = [0u8; 64];
-            rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
-            nonce
-        });
+        let (sidecar_url, token) = super::sidecar_info_for_deployment(deployment_id)?;
+        let nonce = match report_data {
+            Some(report_data) => report_data,
+            None => tsm::sidecar_binding_report_data(&sidecar_url, &token).await,
+        };
         // Run the blocking device ioctl off the async worker thread.
         let att_tee_type = self.tee_type.clone();
         let native_result = tokio::task::spawn_blocking(move || {
@@ -353,10 +348,9 @@ impl TeeBackend for DirectTeeBackend {
         match native_result {
             Ok(att) => Ok(att),
             Err(err) => {
-                if report_data.is_some() {
+                if report_data.is_some() || tsm::quotes_natively(&self.tee_type) {
                     return Err(err);
                 }
-                let (sidecar_url, token) = super::sidecar_info_for_deployment(deployment_id)?;
                 super::fetch_sidecar_attestation(&sidecar_url, &token).await
             }
         }
@@ -405,7 +399,7 @@ impl TeeBackend for DirectTeeBackend {
     }
 
     fn supports_attestation_report_data(&self) -> bool {
-        matches!(self.tee_type, TeeType::Sev)
+        self.tee_type == TeeType::Sev || tsm::quotes_natively(&self.tee_type)
     }
 
     // ── Sealed secrets ──────────────────────────────────────────────────────
@@ -455,13 +449,19 @@ mod tests {
 
     #[test]
     fn report_data_support_is_limited_to_remotely_verifiable_direct_backends() {
-        assert!(!DirectTeeBackend::new(TeeType::Tdx).supports_attestation_report_data());
+        assert_eq!(
+            DirectTeeBackend::new(TeeType::Tdx).supports_attestation_report_data(),
+            tsm::tsm_available()
+        );
         assert!(DirectTeeBackend::new(TeeType::Sev).supports_attestation_report_data());
         assert!(!DirectTeeBackend::new(TeeType::Nitro).supports_attestation_report_data());
     }
 
     #[tokio::test]
     async fn direct_tdx_rejects_nonce_bound_attestation_without_dcap_quote() {
+        if tsm::tsm_available() {
+            return;
+        }
         let backend = DirectTeeBackend::new(TeeType::Tdx);
         let result = backend.attestation("missing", Some([7u8; 64])).await;
 
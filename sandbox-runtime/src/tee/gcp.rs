@@ -29,11 +29,20 @@
 //! | N2D    | AMD SEV-SNP | `SEV` or `SEV_SNP`     |
 //! | C3     | Intel TDX   | `TDX`                  |
 //! | C2D    | AMD SEV     | `SEV`                  |
+//!
+//! # Attestation
+//!
+//! When the sidecar reports a Confidential Space OIDC token as its evidence,
+//! the token is verified with [`super::confidential_space`] on deploy and on
+//! every attestation fetch: signature against Google's JWKS, production
+//! image, secure boot, `STABLE` support, and a container digest pinned by the
+//! deployed image reference or `GCP_EXPECTED_IMAGE_DIGESTS`.
 
 use std::time::Duration;
 
 use tokio::sync::OnceCell;
 
+use super::confidential_space::{self, TokenExpectations};
 use super::sealed_secrets::{SealedSecret, SealedSecretResult, TeePublicKey};
 use super::{AttestationReport, TeeBackend, TeeDeployParams, TeeDeployment, TeeType};
 use crate::error::{Result, SandboxError};
@@ -51,6 +60,11 @@ pub struct GcpConfig {
     pub network: Option<String>,
     pub subnet: Option<String>,
     pub kms_key_resource: Option<String>,
+    /// Container digests attestation tokens may carry, on top of the digest
+    /// pinned in the deployed image reference.
+    pub expected_image_digests: Vec<String>,
+    /// Required `aud` of attestation tokens.
+    pub attestation_audience: Option<String>,
 }
 
 impl GcpConfig {
@@ -59,7 +73,8 @@ impl GcpConfig {
     /// Required: `GCP_PROJECT_ID`, `GCP_ZONE`, `GCP_CONFIDENTIAL_SPACE_IMAGE`.
     /// Optional: `GCP_MACHINE_TYPE` (default: n2d-standard-4),
     /// `GCP_SERVICE_ACCOUNT_EMAIL`, `GCP_NETWORK`, `GCP_SUBNET`,
-    /// `GCP_KMS_KEY_RESOURCE`.
+    /// `GCP_KMS_KEY_RESOURCE`, `GCP_EXPECTED_IMAGE_DIGESTS` (comma-separated
+    /// `sha256:` digests), `GCP_ATTESTATION_AUDIENCE`.
     pub fn from_env() -> Result<Self> {
         Ok(Self {
             project_id: require_env("GCP_PROJECT_ID")?,
@@ -71,6 +86,17 @@ impl GcpConfig {
             network: std::env::var("GCP_NETWORK").ok(),
             subnet: std::env::var("GCP_SUBNET").ok(),
             kms_key_resource: std::env::var("GCP_KMS_KEY_RESOURCE").ok(),
+            expected_image_digests: std::env::var("GCP_EXPECTED_IMAGE_DIGESTS")
+                .map(|v| {
+                    v.split(',')
+                        .map(|d| d.trim().to_string())
+                        .filter(|d| !d.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            attestation_audience: std::env::var("GCP_ATTESTATION_AUDIENCE")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
         })
     }
 
@@ -230,6 +256,42 @@ impl GcpConfidentialSpaceBackend {
         body
     }
 
+    /// Verify the Confidential Space token carried as `report`'s evidence.
+    /// Evidence that is not a token, or a deployment without any pinned
+    /// digest to check it against, is passed through with a warning.
+    async fn check_attestation_token(
+        &self,
+        report: &AttestationReport,
+        image: Option<&str>,
+    ) -> Result<()> {
+        if !confidential_space::is_jwt(&report.evidence) {
+            tracing::warn!(
+                "GCP attestation evidence is not a Confidential Space token; not verified"
+            );
+            return Ok(());
+        }
+        let mut digests = self.config.expected_image_digests.clone();
+        digests.extend(image.and_then(confidential_space::pinned_image_digest));
+        if digests.is_empty() {
+            tracing::warn!(
+                "Confidential Space token not verified: image is not pinned by digest and \
+                 GCP_EXPECTED_IMAGE_DIGESTS is unset"
+            );
+            return Ok(());
+        }
+        let mut expect = TokenExpectations::for_digests(digests);
+        expect.audience = self.config.attestation_audience.clone();
+        let token = String::from_utf8_lossy(&report.evidence);
+        confidential_space::verify_token(&token, &expect)
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                SandboxError::CloudProvider(format!(
+                    "Confidential Space attestation token rejected: {e}"
+                ))
+            })
+    }
+
     /// Poll Compute Engine until the instance is RUNNING, then return its external IP.
     async fn wait_for_running(&self, instance_name: &str) -> Result<String> {
         let deadline = tokio::time::Instant::now() + Duration::from_secs(300);
@@ -318,6 +380,8 @@ impl TeeBackend for GcpConfidentialSpaceBackend {
         // Fetch attestation from the sidecar (which reads from teeserver.sock).
         let attestation =
             super::fetch_sidecar_attestation(&sidecar_url, &params.sidecar_token).await?;
+        self.check_attestation_token(&attestation, Some(&params.image))
+            .await?;
 
         let metadata = serde_json::json!({
             "gcp_project": self.config.project_id,
@@ -347,7 +411,13 @@ impl TeeBackend for GcpConfidentialSpaceBackend {
         _report_data: Option<[u8; 64]>,
     ) -> Result<AttestationReport> {
         let (sidecar_url, token) = super::sidecar_info_for_deployment(deployment_id)?;
-        super::fetch_sidecar_attestation(&sidecar_url, &token).await
+        let report = super::fetch_sidecar_attestation(&sidecar_url, &token).await?;
+        let image = crate::runtime::sandboxes()?
+            .find(|r| r.tee_deployment_id.as_deref() == Some(deployment_id))?
+            .map(|r| r.original_image);
+        self.check_attestation_token(&report, image.as_deref())
+            .await?;
+        Ok(report)
     }
 
     async fn stop(&self, deployment_id: &str) -> Result<()> {
@@ -115,6 +115,25 @@ impl TeeBackend for MockTeeBackend {
         self.support_report_data.load(Ordering::Relaxed)
     }
 
+    async fn status(&self, deployment_id: &str) -> crate::error::Result<TeeDeploymentStatus> {
+        if self.should_fail.load(Ordering::Relaxed) {
+            return Err(crate::error::SandboxError::CloudProvider(
+                "Mock status failure".into(),
+            ));
+        }
+        Ok(TeeDeploymentStatus {
+            deployment_id: deployment_id.to_string(),
+            state: "running".into(),
+            running: true,
+            uptime_secs: Some(7200),
+        })
+    }
+
+    async fn billing_info(&self, deployment_id: &str) -> crate::error::Result<TeeBillingInfo> {
+        let status = self.status(deployment_id).await?;
+        Ok(TeeBillingInfo::from_uptime(&status, Some(0.5), "USD"))
+    }
+
     async fn derive_public_key(
         &self,
         _deployment_id: &str,
@@ -13,16 +13,23 @@ pub mod attestation;
 #[cfg(feature = "tee-direct")]
 pub mod direct;
 
+#[cfg(feature = "tee-direct")]
+pub mod tsm;
+
 #[cfg(feature = "tee-aws-nitro")]
 pub mod aws_nitro;
 
 #[cfg(feature = "tee-gcp")]
 pub mod gcp;
 
+#[cfg(feature = "gcp-attestation")]
+pub mod confidential_space;
+
 #[cfg(feature = "tee-azure")]
 pub mod azure;
 
 pub mod backend_factory;
+pub mod replication;
 pub mod sealed_secrets;
 pub mod sealed_secrets_api;
 
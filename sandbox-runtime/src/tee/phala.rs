@@ -3,6 +3,10 @@
 //! Deploys sidecar containers as Phala CVMs via `phala-tee-deploy-rs`.
 //! The sidecar image is wrapped in a docker-compose.yml and deployed
 //! to Phala Cloud, which runs it inside a TDX-based confidential VM.
+//!
+//! CVM state and accrued cost come from the Phala Cloud REST API
+//! (`GET /cvms/{app_id}`); the cost is the CVM's uptime since it last started
+//! at `PHALA_HOURLY_RATE_USD`, the operator's contracted hourly price.
 
 use std::collections::HashMap;
 use std::time::Duration;
@@ -10,12 +14,21 @@ use std::time::Duration;
 use phala_tee_deploy_rs::{TeeDeployer, TeeDeployerBuilder};
 
 use super::sealed_secrets::{SealedSecret, SealedSecretResult, TeePublicKey};
-use super::{AttestationReport, TeeBackend, TeeDeployParams, TeeDeployment, TeeType};
+use super::{
+    AttestationReport, TeeBackend, TeeBillingInfo, TeeDeployParams, TeeDeployment,
+    TeeDeploymentStatus, TeeType,
+};
 use crate::error::{Result, SandboxError};
 
+/// Phala Cloud REST API used for CVM status when `PHALA_API_ENDPOINT` is unset.
+pub const DEFAULT_PHALA_API_ENDPOINT: &str = "https://cloud-api.phala.network/api/v1";
+
 /// TEE backend that deploys containers to Phala Cloud CVMs.
 pub struct PhalaBackend {
     deployer: TeeDeployer,
+    api_key: zeroize::Zeroizing<String>,
+    api_endpoint: String,
+    hourly_rate: Option<f64>,
 }
 
 impl PhalaBackend {
@@ -24,13 +37,79 @@ impl PhalaBackend {
     /// Optionally provide a custom API endpoint (defaults to Phala Cloud production).
     pub fn new(api_key: &str, api_endpoint: Option<String>) -> Result<Self> {
         let mut builder = TeeDeployerBuilder::new().with_api_key(api_key);
-        if let Some(endpoint) = api_endpoint {
+        if let Some(endpoint) = api_endpoint.clone() {
             builder = builder.with_api_endpoint(endpoint);
         }
         let deployer = builder.build().map_err(|e| {
             SandboxError::Validation(format!("Failed to create Phala deployer: {e}"))
         })?;
-        Ok(Self { deployer })
+        Ok(Self {
+            deployer,
+            api_key: zeroize::Zeroizing::new(api_key.to_string()),
+            api_endpoint: api_endpoint
+                .unwrap_or_else(|| DEFAULT_PHALA_API_ENDPOINT.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            hourly_rate: None,
+        })
+    }
+
+    /// Price CVM uptime at `rate` USD per hour in [`TeeBackend::billing_info`].
+    pub fn with_hourly_rate(mut self, rate: Option<f64>) -> Self {
+        self.hourly_rate = rate;
+        self
+    }
+
+    /// Fetch the CVM record from the Phala Cloud API.
+    async fn cvm_info(&self, app_id: &str) -> Result<serde_json::Value> {
+        let url = format!("{}/cvms/{app_id}", self.api_endpoint);
+        let resp = crate::util::external_http_client()?
+            .get(&url)
+            .header("X-API-Key", self.api_key.as_str())
+            .send()
+            .await
+            .map_err(|e| SandboxError::CloudProvider(format!("Phala CVM status failed: {e}")))?;
+        let status = resp.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(SandboxError::NotFound(format!(
+                "Phala CVM {app_id} not found"
+            )));
+        }
+        if !status.is_success() {
+            return Err(SandboxError::CloudProvider(format!(
+                "Phala CVM status returned HTTP {status}"
+            )));
+        }
+        resp.json().await.map_err(|e| {
+            SandboxError::CloudProvider(format!("Invalid Phala CVM status response: {e}"))
+        })
+    }
+
+    /// Map a Phala CVM record onto [`TeeDeploymentStatus`]. Uptime comes from
+    /// `started_at` when present, else the numeric `uptime` the API reports.
+    fn status_from_cvm(app_id: &str, cvm: &serde_json::Value, now: u64) -> TeeDeploymentStatus {
+        let state = cvm["status"]
+            .as_str()
+            .map(str::to_lowercase)
+            .unwrap_or_else(|| "unknown".into());
+        let running = state == "running";
+        let started_at = cvm["started_at"]
+            .as_str()
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .and_then(|t| u64::try_from(t.timestamp()).ok());
+        let uptime_secs = running
+            .then(|| {
+                started_at
+                    .map(|ts| now.saturating_sub(ts))
+                    .or_else(|| cvm["uptime"].as_u64())
+            })
+            .flatten();
+        TeeDeploymentStatus {
+            deployment_id: app_id.to_string(),
+            state,
+            running,
+            uptime_secs,
+        }
     }
 
     /// Build a verifiable [`AttestationReport`] from a dstack attestation
@@ -294,6 +373,24 @@ impl TeeBackend for PhalaBackend {
         TeeType::Tdx
     }
 
+    async fn status(&self, deployment_id: &str) -> Result<TeeDeploymentStatus> {
+        let cvm = self.cvm_info(deployment_id).await?;
+        Ok(Self::status_from_cvm(
+            deployment_id,
+            &cvm,
+            crate::util::now_ts(),
+        ))
+    }
+
+    async fn billing_info(&self, deployment_id: &str) -> Result<TeeBillingInfo> {
+        let status = self.status(deployment_id).await?;
+        Ok(TeeBillingInfo::from_uptime(
+            &status,
+            self.hourly_rate,
+            "USD",
+        ))
+    }
+
     // ── Sealed secrets ──────────────────────────────────────────────────────
 
     async fn derive_public_key(&self, deployment_id: &str) -> Result<TeePublicKey> {
@@ -308,3 +405,39 @@ impl TeeBackend for PhalaBackend {
         super::sidecar_inject_sealed_secrets(deployment_id, sealed).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_cvm_uptime_comes_from_started_at() {
+        let cvm = serde_json::json!({
+            "status": "Running",
+            "started_at": "2024-01-01T00:00:00Z",
+            "uptime": 5,
+        });
+        let status = PhalaBackend::status_from_cvm("app-1", &cvm, 1_704_067_200 + 7200);
+        assert_eq!(status.state, "running");
+        assert!(status.running);
+        assert_eq!(status.uptime_secs, Some(7200));
+
+        let billing = TeeBillingInfo::from_uptime(&status, Some(0.25), "USD");
+        assert_eq!(billing.billed_secs, 7200);
+        assert_eq!(billing.accrued_cost, Some(0.5));
+    }
+
+    #[test]
+    fn stopped_cvm_has_no_uptime() {
+        let cvm = serde_json::json!({ "status": "stopped", "uptime": 300 });
+        let status = PhalaBackend::status_from_cvm("app-1", &cvm, 1_000);
+        assert!(!status.running);
+        assert_eq!(status.uptime_secs, None);
+        let billing = TeeBillingInfo::from_uptime(&status, None, "USD");
+        assert_eq!(billing.billed_secs, 0);
+        assert_eq!(billing.accrued_cost, None);
+
+        let unknown = PhalaBackend::status_from_cvm("app-1", &serde_json::json!({}), 1_000);
+        assert_eq!(unknown.state, "unknown");
+    }
+}
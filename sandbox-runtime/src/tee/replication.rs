@@ -0,0 +1,463 @@
+//! N-of-M agreement for replicated TEE services.
+//!
+//! A replicated service runs the same instance on M operators. For a
+//! replicated job every operator runs the request in its TEE and returns a
+//! [`ReplicaResponse`]: the result, its hash, and an attestation whose report
+//! data binds that hash to the caller's request nonce (see
+//! [`replica_report_data`]). Job response signing
+//! ([`crate::response_signing`]) adds the operator's signature over the whole
+//! response.
+//!
+//! [`aggregate`] — for the customer, or an aggregator operator collecting the
+//! replicas' job results — checks each replica's signature, attestation and
+//! hash, then whether at least `threshold` accepted replicas agree on one
+//! result. The [`ReplicationVerdict`] it returns has an ABI form
+//! ([`ReplicationVerdict::abi_encode`]) for on-chain submission.
+
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+use alloy::primitives::{Address, FixedBytes};
+use alloy::sol;
+use alloy::sol_types::SolValue;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{AttestationReport, AttestationVerification, TeeType, verify_attestation};
+use crate::error::{Result, SandboxError};
+use crate::response_signing::{self, canonical_json};
+use crate::session_auth::keccak256;
+
+/// `scheme` of a [`ReplicaResponse`].
+pub const REPLICA_SCHEME: &str = "tee-replica-v1";
+
+sol! {
+    /// ABI form of a [`ReplicationVerdict`].
+    struct ReplicatedResultVerdict {
+        bytes32 requestNonce;
+        /// Zero when no result reached the threshold.
+        bytes32 resultHash;
+        bool agreed;
+        uint32 threshold;
+        address[] agreeing;
+        address[] dissenting;
+        /// Replicas that failed signature, attestation or hash checks.
+        uint32 rejected;
+    }
+}
+
+/// One operator's answer to a replicated job.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicaResponse {
+    pub scheme: String,
+    pub sandbox_id: String,
+    /// `0x` hex of the caller's 32-byte request nonce.
+    pub request_nonce: String,
+    /// What replicas must agree on.
+    pub result: Value,
+    /// `0x` keccak256 of the canonical JSON of `result` (see [`result_hash`]).
+    pub result_hash: String,
+    /// Attestation over [`replica_report_data`].
+    pub attestation: AttestationReport,
+}
+
+impl ReplicaResponse {
+    pub fn new(
+        sandbox_id: &str,
+        request_nonce: &[u8; 32],
+        result: Value,
+        attestation: AttestationReport,
+    ) -> Self {
+        Self {
+            scheme: REPLICA_SCHEME.to_string(),
+            sandbox_id: sandbox_id.to_string(),
+            request_nonce: format!("0x{}", hex::encode(request_nonce)),
+            result_hash: format!("0x{}", hex::encode(result_hash(&result))),
+            result,
+            attestation,
+        }
+    }
+}
+
+/// keccak256 of `result`'s canonical JSON, so replicas that produce the same
+/// value hash the same regardless of key order.
+pub fn result_hash(result: &Value) -> [u8; 32] {
+    keccak256(canonical_json(result).as_bytes())
+}
+
+/// Report data of a replica's attestation: the request nonce, then the
+/// result hash. A verified quote over it proves the enclave produced that
+/// result for this request, not an earlier one.
+pub fn replica_report_data(request_nonce: &[u8; 32], result_hash: &[u8; 32]) -> [u8; 64] {
+    let mut report_data = [0u8; 64];
+    report_data[..32].copy_from_slice(request_nonce);
+    report_data[32..].copy_from_slice(result_hash);
+    report_data
+}
+
+/// Parse a `0x`-optional hex 32-byte request nonce.
+pub fn decode_request_nonce(hex_nonce: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(hex_nonce.trim().trim_start_matches("0x"))
+        .map_err(|e| SandboxError::Validation(format!("Invalid request nonce hex: {e}")))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        SandboxError::Validation(format!(
+            "Request nonce must be 32 bytes, got {}",
+            bytes.len()
+        ))
+    })
+}
+
+/// What a replica must satisfy to count.
+#[derive(Clone, Debug, Default)]
+pub struct ReplicationPolicy {
+    /// Accepted replicas that must agree on one result.
+    pub threshold: usize,
+    pub tee_type: TeeType,
+    /// Measurements a replica's quote must carry (hex-decoded).
+    pub expected_measurements: Vec<Vec<u8>>,
+    /// Operators allowed to contribute (`0x` addresses); empty allows any.
+    pub operators: Vec<String>,
+    /// Count replicas whose attestation is structurally valid but not
+    /// hardware-verified. Development only: without a verified quote nothing
+    /// ties the result to the enclave.
+    pub allow_unverified_attestation: bool,
+}
+
+/// The outcome of checking one replica.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicaCheck {
+    /// Signing operator, once the signature checked out.
+    pub operator: Option<String>,
+    pub result_hash: Option<String>,
+    pub verification: Option<AttestationVerification>,
+    /// Why the replica was rejected; `None` when accepted.
+    pub error: Option<String>,
+}
+
+impl ReplicaCheck {
+    pub fn accepted(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Consolidated result of a replicated job.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationVerdict {
+    pub request_nonce: String,
+    /// Whether at least `threshold` accepted replicas agree on `result_hash`.
+    pub agreed: bool,
+    /// The result the most accepted replicas agree on, when unique.
+    pub result_hash: Option<String>,
+    pub result: Option<Value>,
+    pub threshold: usize,
+    pub agreeing: Vec<String>,
+    /// Accepted replicas that returned a different result.
+    pub dissenting: Vec<String>,
+    pub replicas: Vec<ReplicaCheck>,
+}
+
+impl ReplicationVerdict {
+    /// The verdict as a [`ReplicatedResultVerdict`].
+    pub fn to_abi(&self) -> Result<ReplicatedResultVerdict> {
+        let bytes32 = |hex_value: Option<&str>| -> Result<FixedBytes<32>> {
+            match hex_value {
+                Some(value) => FixedBytes::from_str(value)
+                    .map_err(|e| SandboxError::Validation(format!("Invalid bytes32 {value}: {e}"))),
+                None => Ok(FixedBytes::ZERO),
+            }
+        };
+        let addresses = |list: &[String]| -> Result<Vec<Address>> {
+            list.iter()
+                .map(|a| {
+                    Address::from_str(a)
+                        .map_err(|e| SandboxError::Validation(format!("Invalid address {a}: {e}")))
+                })
+                .collect()
+        };
+        Ok(ReplicatedResultVerdict {
+            requestNonce: bytes32(Some(&self.request_nonce))?,
+            resultHash: bytes32(self.result_hash.as_deref().filter(|_| self.agreed))?,
+            agreed: self.agreed,
+            threshold: self.threshold as u32,
+            agreeing: addresses(&self.agreeing)?,
+            dissenting: addresses(&self.dissenting)?,
+            rejected: self.replicas.iter().filter(|r| !r.accepted()).count() as u32,
+        })
+    }
+
+    /// ABI-encoded [`ReplicatedResultVerdict`].
+    pub fn abi_encode(&self) -> Result<Vec<u8>> {
+        Ok(self.to_abi()?.abi_encode())
+    }
+}
+
+/// Check every replica's signed job response against `policy` and decide
+/// whether enough of them agree.
+pub fn aggregate(
+    responses: &[String],
+    request_nonce: &[u8; 32],
+    policy: &ReplicationPolicy,
+) -> ReplicationVerdict {
+    let mut seen = HashSet::new();
+    let mut replicas = Vec::with_capacity(responses.len());
+    let mut accepted: Vec<(String, String, Value)> = Vec::new();
+    for response in responses {
+        let (check, parsed) = check_replica(response, request_nonce, policy);
+        let check = match (&check.operator, parsed) {
+            (Some(operator), Some(parsed)) if check.accepted() => {
+                if seen.insert(operator.clone()) {
+                    accepted.push((operator.clone(), parsed.result_hash, parsed.result));
+                    check
+                } else {
+                    ReplicaCheck {
+                        error: Some(format!("Duplicate replica from operator {operator}")),
+                        ..check
+                    }
+                }
+            }
+            _ => check,
+        };
+        replicas.push(check);
+    }
+
+    let mut groups: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (operator, hash, _) in &accepted {
+        groups.entry(hash.as_str()).or_default().push(operator);
+    }
+    let largest = groups.values().map(Vec::len).max().unwrap_or(0);
+    let mut leaders = groups.iter().filter(|(_, ops)| ops.len() == largest);
+    let winner = match (leaders.next(), leaders.next()) {
+        (Some((hash, _)), None) => Some(hash.to_string()),
+        _ => None,
+    };
+    let agreed = winner.is_some() && policy.threshold > 0 && largest >= policy.threshold;
+
+    let (agreeing, dissenting) = accepted
+        .iter()
+        .map(|(operator, hash, _)| (operator.clone(), Some(hash) == winner.as_ref()))
+        .partition::<Vec<_>, _>(|(_, agrees)| *agrees);
+    let result = winner.as_ref().and_then(|winner| {
+        accepted
+            .iter()
+            .find(|(_, hash, _)| hash == winner)
+            .map(|(_, _, result)| result.clone())
+    });
+
+    ReplicationVerdict {
+        request_nonce: format!("0x{}", hex::encode(request_nonce)),
+        agreed,
+        result_hash: winner,
+        result,
+        threshold: policy.threshold,
+        agreeing: agreeing.into_iter().map(|(op, _)| op).collect(),
+        dissenting: dissenting.into_iter().map(|(op, _)| op).collect(),
+        replicas,
+    }
+}
+
+/// Verify one signed replica response. Returns the check and, when it
+/// passed, the parsed response.
+fn check_replica(
+    response: &str,
+    request_nonce: &[u8; 32],
+    policy: &ReplicationPolicy,
+) -> (ReplicaCheck, Option<ReplicaResponse>) {
+    let mut check = ReplicaCheck {
+        operator: None,
+        result_hash: None,
+        verification: None,
+        error: None,
+    };
+    let reject = |mut check: ReplicaCheck, error: String| {
+        check.error = Some(error);
+        (check, None)
+    };
+
+    // The request nonce, not the job call, binds replicas to one request.
+    let operator = match response_signing::verify(response) {
+        Ok(origin) => origin.operator,
+        Err(e) => return reject(check, format!("Invalid operator signature: {e}")),
+    };
+    check.operator = Some(operator.clone());
+    if !policy.operators.is_empty()
+        && !policy
+            .operators
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(&operator))
+    {
+        return reject(
+            check,
+            format!("Operator {operator} is not a replica of this service"),
+        );
+    }
+
+    let replica: ReplicaResponse = match serde_json::from_str(response) {
+        Ok(replica) => replica,
+        Err(e) => return reject(check, format!("Not a replica response: {e}")),
+    };
+    check.result_hash = Some(replica.result_hash.clone());
+    if replica.scheme != REPLICA_SCHEME {
+        return reject(
+            check,
+            format!("Unsupported replica scheme {:?}", replica.scheme),
+        );
+    }
+    if decode_request_nonce(&replica.request_nonce).ok() != Some(*request_nonce) {
+        return reject(check, "Replica answered a different request nonce".into());
+    }
+    let hash = result_hash(&replica.result);
+    if !replica
+        .result_hash
+        .eq_ignore_ascii_case(&format!("0x{}", hex::encode(hash)))
+    {
+        return reject(check, "Result hash does not match the result".into());
+    }
+
+    let verification = verify_attestation(
+        &replica.attestation,
+        &policy.tee_type,
+        &policy.expected_measurements,
+        Some(&replica_report_data(request_nonce, &hash)),
+    );
+    let attested = verification.is_trusted()
+        || (policy.allow_unverified_attestation && verification.structural_ok);
+    let reason = format!("{:?}", verification.verdict);
+    check.verification = Some(verification);
+    if !attested {
+        return reject(check, format!("Attestation not accepted: {reason}"));
+    }
+    (check, Some(replica))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response_signing::ResponseSigner;
+    use serde_json::json;
+
+    const NONCE: [u8; 32] = [9u8; 32];
+
+    fn signer(seed: u8) -> ResponseSigner {
+        ResponseSigner::from_secret(&[seed; 32]).unwrap()
+    }
+
+    fn replica(signer: &ResponseSigner, nonce: &[u8; 32], stdout: &str) -> String {
+        let response = ReplicaResponse::new(
+            "sb-1",
+            nonce,
+            json!({ "exitCode": 0, "stdout": stdout }),
+            AttestationReport {
+                tee_type: TeeType::Tdx,
+                evidence: vec![1],
+                measurement: vec![2],
+                timestamp: crate::util::now_ts(),
+            },
+        );
+        signer
+            .sign_json(&serde_json::to_string(&response).unwrap(), 1, 1)
+            .unwrap()
+    }
+
+    fn policy(threshold: usize) -> ReplicationPolicy {
+        ReplicationPolicy {
+            threshold,
+            tee_type: TeeType::Tdx,
+            allow_unverified_attestation: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn threshold_of_agreeing_replicas_is_reached() {
+        let (a, b, c) = (signer(1), signer(2), signer(3));
+        let responses = vec![
+            replica(&a, &NONCE, "42"),
+            replica(&b, &NONCE, "42"),
+            replica(&c, &NONCE, "41"),
+        ];
+        let verdict = aggregate(&responses, &NONCE, &policy(2));
+        assert!(verdict.agreed);
+        assert_eq!(verdict.agreeing, vec![a.address(), b.address()]);
+        assert_eq!(verdict.dissenting, vec![c.address()]);
+        assert_eq!(verdict.result.as_ref().unwrap()["stdout"], "42");
+
+        let abi = ReplicatedResultVerdict::abi_decode(&verdict.abi_encode().unwrap()).unwrap();
+        assert!(abi.agreed);
+        assert_eq!(abi.agreeing.len(), 2);
+        assert_eq!(abi.rejected, 0);
+        assert_eq!(
+            format!("{}", abi.resultHash),
+            verdict.result_hash.clone().unwrap()
+        );
+
+        assert!(!aggregate(&responses, &NONCE, &policy(3)).agreed);
+    }
+
+    #[test]
+    fn bad_replicas_are_rejected_and_do_not_count() {
+        let (a, b) = (signer(1), signer(2));
+        let mut tampered: Value = serde_json::from_str(&replica(&b, &NONCE, "42")).unwrap();
+        tampered["result"]["stdout"] = json!("43");
+        let responses = vec![
+            replica(&a, &NONCE, "42"),
+            replica(&a, &NONCE, "42"),
+            tampered.to_string(),
+            replica(&b, &[1u8; 32], "42"),
+        ];
+        let verdict = aggregate(&responses, &NONCE, &policy(2));
+        assert!(!verdict.agreed);
+        assert_eq!(verdict.agreeing, vec![a.address()]);
+        let errors: Vec<_> = verdict
+            .replicas
+            .iter()
+            .filter_map(|r| r.error.as_deref())
+            .collect();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("Duplicate"), "{errors:?}");
+        assert!(errors[1].contains("signature"), "{errors:?}");
+        assert!(errors[2].contains("request nonce"), "{errors:?}");
+
+        // Outside the operator set, or without a verified quote.
+        let strict = ReplicationPolicy {
+            operators: vec![b.address().to_string()],
+            ..policy(1)
+        };
+        let verdict = aggregate(&[replica(&a, &NONCE, "42")], &NONCE, &strict);
+        assert!(
+            verdict.replicas[0]
+                .error
+                .as_deref()
+                .unwrap()
+                .contains("not a replica")
+        );
+        let verified_only = ReplicationPolicy {
+            allow_unverified_attestation: false,
+            ..policy(1)
+        };
+        let verdict = aggregate(&[replica(&a, &NONCE, "42")], &NONCE, &verified_only);
+        assert!(!verdict.agreed);
+        assert!(
+            verdict.replicas[0]
+                .error
+                .as_deref()
+                .unwrap()
+                .contains("Attestation")
+        );
+    }
+
+    #[test]
+    fn ties_reach_no_verdict() {
+        let responses = vec![
+            replica(&signer(1), &NONCE, "a"),
+            replica(&signer(2), &NONCE, "b"),
+        ];
+        let verdict = aggregate(&responses, &NONCE, &policy(1));
+        assert!(!verdict.agreed);
+        assert!(verdict.result_hash.is_none());
+        assert_eq!(verdict.dissenting.len(), 2);
+        assert_eq!(verdict.to_abi().unwrap().resultHash, FixedBytes::<32>::ZERO);
+    }
+}
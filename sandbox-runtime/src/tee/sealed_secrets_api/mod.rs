@@ -7,6 +7,7 @@
 //! - `POST /api/sandboxes/{id}/tee/sealed-secrets`   — inject encrypted secrets
 //! - `GET  /api/sandboxes/{id}/tee/attestation`      — fetch fresh attestation
 //! - `POST /api/sandboxes/{id}/tee/attestation`      — fetch nonce-bound attestation
+//! - `GET  /api/sandboxes/{id}/tee/status`           — provider state and accrued cost
 //!
 //! This module is intentionally isolated — it can be removed without affecting
 //! the existing operator API or 2-phase plaintext secret provisioning.
@@ -17,9 +18,10 @@ use std::sync::Arc;
 
 use super::sealed_secrets::{SealedSecret, TeePublicKey};
 use super::{
-    AttestationReport, AttestationVerification, TeeBackend, expected_measurements_from_env,
-    verify_attestation,
+    AttestationReport, AttestationVerification, TeeBackend, TeeBillingInfo, TeeDeploymentStatus,
+    expected_measurements_from_env, verify_attestation,
 };
+use crate::error::SandboxError;
 use crate::operator_api::api_error;
 use crate::runtime::get_sandbox_by_id;
 use crate::secret_provisioning::validate_secret_access;
@@ -95,30 +97,30 @@ struct PublicKeyResponse {
 /// (`report_data_matched`). A stale/replayed genuine quote therefore cannot pass
 /// the gate. Backends that cannot bind report data fail closed.
 ///
-/// Returns `Ok(server_enforced)` when release may proceed, or an HTTP error
-/// response.
+/// Returns `Ok(server_enforced)` when release may proceed. Refusals are
+/// [`SandboxError::Auth`]; a failed attestation fetch is returned as-is.
+/// Shared by the HTTP routes and the on-chain sealed-secrets job.
 ///
 /// `expected` is the operator-independent allowlist of known-good measurements,
 /// snapshotted by the caller (from `expected_measurements_from_env()`) so the
 /// async gate never reads process env while a request is in flight.
-async fn enforce_release_gate(
+pub async fn check_release_gate(
     backend: &dyn TeeBackend,
     deployment_id: &str,
     expected: &[Vec<u8>],
-) -> GateOutcome {
+) -> crate::error::Result<bool> {
     if expected.is_empty() {
         // No operator-pinned measurement → the server has nothing to enforce
         // against. Fail closed unless the operator has explicitly opted into the
         // client-side-only trust model.
         if require_pinned_measurement_from_env() {
-            return Err(api_error(
-                StatusCode::FORBIDDEN,
+            return Err(SandboxError::Auth(
                 "TEE release refused: no server-pinned enclave measurement \
                  (SANDBOX_TEE_EXPECTED_MEASUREMENTS is unset). Pin an allowlist, or set \
                  SANDBOX_TEE_REQUIRE_PINNED_MEASUREMENT=false to accept client-side-only \
-                 verification.",
-            )
-            .into_response());
+                 verification."
+                    .into(),
+            ));
         }
         // Explicit opt-out: release proceeds but is NOT server-verified. Make the
         // unenforced gate visible to operators and to the caller.
@@ -136,45 +138,54 @@ async fn enforce_release_gate(
     // freshness binding is only meaningful if the backend can embed the nonce in
     // the hardware-signed report data, so fail closed when it cannot.
     if !backend.supports_attestation_report_data() {
-        return Err(api_error(
-            StatusCode::FORBIDDEN,
-            format!(
-                "TEE backend {:?} cannot bind a freshness nonce into the attestation report \
-                 data; refusing to release sealed-secret material without replay protection",
-                backend.tee_type()
-            ),
-        )
-        .into_response());
+        return Err(SandboxError::Auth(format!(
+            "TEE backend {:?} cannot bind a freshness nonce into the attestation report \
+             data; refusing to release sealed-secret material without replay protection",
+            backend.tee_type()
+        )));
     }
 
     let mut nonce = [0u8; 64];
     rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
 
-    let att = backend
-        .attestation(deployment_id, Some(nonce))
-        .await
-        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    let att = backend.attestation(deployment_id, Some(nonce)).await?;
     let verification = verify_attestation(&att, &backend.tee_type(), expected, Some(&nonce));
     if verification.is_trusted() {
         Ok(true)
     } else {
-        Err(api_error(
-            StatusCode::FORBIDDEN,
-            format!(
-                "TEE attestation not verified server-side (verdict: {:?}); refusing to release \
-                 sealed-secret material",
-                verification.verdict
-            ),
-        )
-        .into_response())
+        Err(SandboxError::Auth(format!(
+            "TEE attestation not verified server-side (verdict: {:?}); refusing to release \
+             sealed-secret material",
+            verification.verdict
+        )))
     }
 }
 
+/// [`check_release_gate`] for the HTTP routes: refusals become `403`, backend
+/// failures `500`.
+async fn enforce_release_gate(
+    backend: &dyn TeeBackend,
+    deployment_id: &str,
+    expected: &[Vec<u8>],
+) -> GateOutcome {
+    check_release_gate(backend, deployment_id, expected)
+        .await
+        .map_err(|e| {
+            let status = match &e {
+                SandboxError::Auth(_) => StatusCode::FORBIDDEN,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            api_error(status, e.to_string()).into_response()
+        })
+}
+
 mod attestation;
 mod keys;
+mod status;
 
 pub use attestation::*;
 pub use keys::*;
+pub use status::*;
 
 // tee-level attestation-nonce helpers the moved endpoint code reaches via `super::`.
 use super::{decode_attestation_nonce_hex, pad_attestation_nonce};
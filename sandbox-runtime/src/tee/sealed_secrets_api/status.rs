@@ -0,0 +1,71 @@
+//! TEE deployment status and billing endpoint.
+
+use super::*;
+
+/// Response for `GET /api/sandboxes/{id}/tee/status`.
+#[derive(Serialize)]
+struct TeeStatusResponse {
+    sandbox_id: String,
+    status: TeeDeploymentStatus,
+    /// `None` when the backend cannot price the deployment; see `billing_error`.
+    billing: Option<TeeBillingInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    billing_error: Option<String>,
+}
+
+/// `GET /api/sandboxes/{sandbox_id}/tee/status`
+///
+/// Returns the provider-side state, uptime and accrued cost of the sandbox's
+/// TEE deployment, for reconciling CVM charges against instance billing.
+pub async fn get_tee_status(
+    SessionAuth(address): SessionAuth,
+    Path(sandbox_id): Path<String>,
+    tee_backend: axum::Extension<Option<Arc<dyn TeeBackend>>>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_secret_access(&sandbox_id, &address) {
+        return api_error(StatusCode::FORBIDDEN, e.to_string()).into_response();
+    }
+
+    let record = match get_sandbox_by_id(&sandbox_id) {
+        Ok(r) => r,
+        Err(e) => return api_error(StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    };
+
+    let Some(deployment_id) = record.tee_deployment_id.clone() else {
+        return api_error(StatusCode::BAD_REQUEST, "Sandbox is not a TEE deployment")
+            .into_response();
+    };
+
+    let Some(backend) = tee_backend.as_ref() else {
+        return api_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "TEE backend not configured",
+        )
+        .into_response();
+    };
+
+    let status = match backend.status(&deployment_id).await {
+        Ok(status) => status,
+        Err(SandboxError::Validation(msg)) => {
+            return api_error(StatusCode::NOT_IMPLEMENTED, msg).into_response();
+        }
+        Err(e) => {
+            return api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+        }
+    };
+    let (billing, billing_error) = match backend.billing_info(&deployment_id).await {
+        Ok(billing) => (Some(billing), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    (
+        StatusCode::OK,
+        Json(TeeStatusResponse {
+            sandbox_id,
+            status,
+            billing,
+            billing_error,
+        }),
+    )
+        .into_response()
+}
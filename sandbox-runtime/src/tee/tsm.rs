@@ -0,0 +1,285 @@
+//! Intel TDX quote generation through the kernel's configfs-tsm interface.
+//!
+//! `/dev/tdx_guest` only hands out a local TDREPORT, which nothing outside
+//! the TD can verify. Linux 6.7+ exposes the quoting flow under
+//! `/sys/kernel/config/tsm/report`: create an entry, write 64 bytes of report
+//! data to `inblob`, read the DCAP quote signed by the host's quoting enclave
+//! back from `outblob`. The quote verifies against Intel's PCS collateral like
+//! any other TDX quote, so the Direct backend can serve nonce-bound
+//! attestation without trusting the sidecar to self-report.
+//!
+//! When the caller does not supply a nonce, the report data binds the quote
+//! to the deployment instead — see [`binding_report_data`].
+
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, SandboxError};
+use crate::tee::{AttestationReport, TeeType};
+
+/// Where configfs-tsm report entries are created.
+pub const TSM_REPORT_ROOT: &str = "/sys/kernel/config/tsm/report";
+/// `provider` of entries backed by the TDX guest driver.
+const TDX_PROVIDER: &str = "tdx_guest";
+
+/// Size of the DCAP quote header.
+const QUOTE_HEADER_SIZE: usize = 48;
+/// `tee_type` of a TDX quote.
+const TDX_QUOTE_TEE_TYPE: u32 = 0x81;
+/// Size of the TD 1.0 report body.
+const TD_REPORT_BODY_SIZE: usize = 584;
+/// Offset of MRTD within the report body.
+const MRTD_OFFSET: usize = 136;
+/// Size of MRTD (SHA-384).
+const MRTD_SIZE: usize = 48;
+/// Offset of REPORTDATA within the report body.
+const REPORTDATA_OFFSET: usize = 520;
+
+/// Whether this host can produce TDX quotes through configfs-tsm.
+pub fn tsm_available() -> bool {
+    Path::new(TSM_REPORT_ROOT).is_dir()
+}
+
+/// Report data binding a quote to one deployment: SHA-256 of the sidecar
+/// token, then SHA-256 of the sidecar's sealed-secrets public key (zeros when
+/// it has none). A verifier who knows the token and fetched the key can
+/// recompute it, proving the quote came from the TD serving that sidecar and
+/// that the key sealed secrets are encrypted to lives inside it.
+pub fn binding_report_data(sidecar_token: &str, public_key: Option<&[u8]>) -> [u8; 64] {
+    let mut report_data = [0u8; 64];
+    report_data[..32].copy_from_slice(&Sha256::digest(sidecar_token.as_bytes()));
+    if let Some(key) = public_key {
+        report_data[32..].copy_from_slice(&Sha256::digest(key));
+    }
+    report_data
+}
+
+/// [`binding_report_data`] for a running sidecar, with the public key it
+/// serves. A sidecar without one is bound by its token alone.
+pub async fn sidecar_binding_report_data(sidecar_url: &str, token: &str) -> [u8; 64] {
+    let public_key = match super::fetch_sidecar_public_key(sidecar_url, token).await {
+        Ok(key) => Some(key.public_key_bytes),
+        Err(e) => {
+            tracing::debug!(error = %e, "Sidecar public key unavailable; binding token only");
+            None
+        }
+    };
+    binding_report_data(token, public_key.as_deref())
+}
+
+/// Whether a `tee_type` host produces remotely verifiable quotes: TDX with
+/// configfs-tsm.
+pub fn quotes_natively(tee_type: &TeeType) -> bool {
+    *tee_type == TeeType::Tdx && tsm_available()
+}
+
+/// Caller report data can only be honoured where the host signs it into
+/// remotely verifiable evidence; a TDX host without configfs-tsm cannot.
+pub fn ensure_report_data_supported(
+    tee_type: &TeeType,
+    report_data: Option<&[u8; 64]>,
+) -> Result<()> {
+    if report_data.is_some() && *tee_type == TeeType::Tdx && !quotes_natively(tee_type) {
+        return Err(SandboxError::Validation(format!(
+            "Direct TDX nonce-bound remote attestation requires a DCAP TD quote from \
+             configfs-tsm ({TSM_REPORT_ROOT} not found); /dev/tdx_guest TDX_CMD_GET_REPORT0 \
+             returns a local TDREPORT only"
+        )));
+    }
+    Ok(())
+}
+
+/// Generate a TDX quote over `report_data`.
+pub fn generate_tdx_quote(report_data: &[u8; 64]) -> Result<AttestationReport> {
+    generate_tdx_quote_in(Path::new(TSM_REPORT_ROOT), report_data)
+}
+
+fn generate_tdx_quote_in(root: &Path, report_data: &[u8; 64]) -> Result<AttestationReport> {
+    if !root.is_dir() {
+        return Err(SandboxError::CloudProvider(format!(
+            "configfs-tsm is not available at {}; TDX quotes need Linux 6.7+ with \
+             configfs mounted at /sys/kernel/config",
+            root.display()
+        )));
+    }
+    let entry = ReportEntry::create(root)?;
+    read_quote(&entry.0, report_data)
+}
+
+/// Run one report request against an existing entry directory.
+fn read_quote(entry: &Path, report_data: &[u8; 64]) -> Result<AttestationReport> {
+    let provider = read_attr(entry, "provider")?;
+    if provider.trim() != TDX_PROVIDER {
+        return Err(SandboxError::CloudProvider(format!(
+            "configfs-tsm provider is {:?}, expected {TDX_PROVIDER}",
+            provider.trim()
+        )));
+    }
+
+    std::fs::write(entry.join("inblob"), report_data).map_err(|e| tsm_error("inblob", e))?;
+    let generation = read_attr(entry, "generation")?;
+    let quote = std::fs::read(entry.join("outblob")).map_err(|e| tsm_error("outblob", e))?;
+    // Every write bumps `generation`; a change means another writer touched
+    // the entry between our write and read and the quote may not be ours.
+    if read_attr(entry, "generation")? != generation {
+        return Err(SandboxError::CloudProvider(
+            "configfs-tsm report entry changed while reading the quote".into(),
+        ));
+    }
+
+    let (measurement, quoted_report_data) = parse_tdx_quote(&quote)?;
+    if quoted_report_data != report_data[..] {
+        return Err(SandboxError::CloudProvider(
+            "TDX quote report data does not match the requested report data".into(),
+        ));
+    }
+    let report = AttestationReport {
+        tee_type: TeeType::Tdx,
+        evidence: quote,
+        measurement,
+        timestamp: crate::util::now_ts(),
+    };
+    super::validate_attestation_report(&report, &TeeType::Tdx)?;
+    Ok(report)
+}
+
+/// Extract MRTD and REPORTDATA from a version 4 or 5 TDX quote.
+fn parse_tdx_quote(quote: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let malformed = |why: &str| SandboxError::CloudProvider(format!("Malformed TDX quote: {why}"));
+    if quote.len() < QUOTE_HEADER_SIZE {
+        return Err(malformed("shorter than the quote header"));
+    }
+    let version = u16::from_le_bytes([quote[0], quote[1]]);
+    let tee_type = u32::from_le_bytes([quote[4], quote[5], quote[6], quote[7]]);
+    if tee_type != TDX_QUOTE_TEE_TYPE {
+        return Err(malformed(&format!("tee_type 0x{tee_type:x} is not TDX")));
+    }
+    let body_start = match version {
+        4 => QUOTE_HEADER_SIZE,
+        // v5 prefixes the body with a u16 type and a u32 size.
+        5 => QUOTE_HEADER_SIZE + 6,
+        other => return Err(malformed(&format!("unsupported version {other}"))),
+    };
+    let body = quote
+        .get(body_start..body_start + TD_REPORT_BODY_SIZE)
+        .ok_or_else(|| malformed("truncated report body"))?;
+    Ok((
+        body[MRTD_OFFSET..MRTD_OFFSET + MRTD_SIZE].to_vec(),
+        body[REPORTDATA_OFFSET..REPORTDATA_OFFSET + 64].to_vec(),
+    ))
+}
+
+fn read_attr(entry: &Path, name: &str) -> Result<String> {
+    std::fs::read_to_string(entry.join(name)).map_err(|e| tsm_error(name, e))
+}
+
+fn tsm_error(attr: &str, err: std::io::Error) -> SandboxError {
+    SandboxError::CloudProvider(format!("configfs-tsm {attr}: {err}"))
+}
+
+/// A report entry of our own, removed on drop so entries don't pile up.
+struct ReportEntry(PathBuf);
+
+impl ReportEntry {
+    fn create(root: &Path) -> Result<Self> {
+        let name = format!(
+            "sandbox-{}-{:016x}",
+            std::process::id(),
+            rand::random::<u64>()
+        );
+        let path = root.join(name);
+        std::fs::create_dir(&path).map_err(|e| {
+            SandboxError::CloudProvider(format!(
+                "Failed to create configfs-tsm report entry {}: {e}",
+                path.display()
+            ))
+        })?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for ReportEntry {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(version: u16, report_data: &[u8; 64]) -> Vec<u8> {
+        let body_start = if version == 5 {
+            QUOTE_HEADER_SIZE + 6
+        } else {
+            QUOTE_HEADER_SIZE
+        };
+        let mut quote = vec![0u8; body_start + TD_REPORT_BODY_SIZE + 16];
+        quote[..2].copy_from_slice(&version.to_le_bytes());
+        quote[4..8].copy_from_slice(&TDX_QUOTE_TEE_TYPE.to_le_bytes());
+        let body = &mut quote[body_start..];
+        body[MRTD_OFFSET..MRTD_OFFSET + MRTD_SIZE].fill(0xAB);
+        body[REPORTDATA_OFFSET..REPORTDATA_OFFSET + 64].copy_from_slice(report_data);
+        quote
+    }
+
+    /// A directory laid out like a configfs-tsm entry after the kernel
+    /// produced `outblob`.
+    fn fake_entry(provider: &str, outblob: &[u8]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("provider"), format!("{provider}\n")).unwrap();
+        std::fs::write(dir.path().join("generation"), "1\n").unwrap();
+        std::fs::write(dir.path().join("outblob"), outblob).unwrap();
+        dir
+    }
+
+    #[test]
+    fn quotes_of_both_versions_parse() {
+        let report_data = [7u8; 64];
+        for version in [4, 5] {
+            let (mrtd, quoted) = parse_tdx_quote(&quote(version, &report_data)).unwrap();
+            assert_eq!(mrtd, vec![0xAB; MRTD_SIZE]);
+            assert_eq!(quoted, report_data.to_vec());
+        }
+        let mut sgx = quote(4, &report_data);
+        sgx[4..8].copy_from_slice(&0u32.to_le_bytes());
+        assert!(parse_tdx_quote(&sgx).is_err());
+        assert!(parse_tdx_quote(&quote(4, &report_data)[..600]).is_err());
+    }
+
+    #[test]
+    fn report_entry_returns_a_quote_bound_to_the_report_data() {
+        let report_data = binding_report_data("token", Some(b"public-key"));
+        let entry = fake_entry("tdx_guest", &quote(4, &report_data));
+        let report = read_quote(entry.path(), &report_data).unwrap();
+        assert_eq!(report.tee_type, TeeType::Tdx);
+        assert_eq!(report.measurement, vec![0xAB; MRTD_SIZE]);
+        assert_eq!(
+            std::fs::read(entry.path().join("inblob")).unwrap(),
+            report_data.to_vec()
+        );
+
+        let err = read_quote(entry.path(), &[0u8; 64]).unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err}");
+        let sev = fake_entry("sev_guest", &quote(4, &report_data));
+        assert!(read_quote(sev.path(), &report_data).is_err());
+    }
+
+    #[test]
+    fn binding_covers_token_and_public_key() {
+        let token_only = binding_report_data("token", None);
+        assert_eq!(token_only[32..], [0u8; 32]);
+        let with_key = binding_report_data("token", Some(b"key"));
+        assert_eq!(token_only[..32], with_key[..32]);
+        assert_ne!(with_key, binding_report_data("other", Some(b"key")));
+        assert_ne!(with_key, binding_report_data("token", Some(b"other")));
+    }
+
+    #[test]
+    fn missing_configfs_is_reported() {
+        let err =
+            generate_tdx_quote_in(Path::new("/nonexistent/tsm/report"), &[0u8; 64]).unwrap_err();
+        assert!(err.to_string().contains("configfs-tsm"), "{err}");
+    }
+}
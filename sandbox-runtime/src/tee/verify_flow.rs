@@ -145,8 +145,17 @@ pub(crate) async fn sidecar_derive_public_key(
     deployment_id: &str,
 ) -> crate::error::Result<sealed_secrets::TeePublicKey> {
     let (sidecar_url, token) = sidecar_info_for_deployment(deployment_id)?;
-    let url = crate::http::build_url(&sidecar_url, "/tee/public-key")?;
-    let headers = crate::http::auth_headers(&token)?;
+    fetch_sidecar_public_key(&sidecar_url, &token).await
+}
+
+/// Fetch the sealed-secrets public key from a sidecar by URL.
+#[allow(dead_code)] // Used by TEE backends
+pub(crate) async fn fetch_sidecar_public_key(
+    sidecar_url: &str,
+    token: &str,
+) -> crate::error::Result<sealed_secrets::TeePublicKey> {
+    let url = crate::http::build_url(sidecar_url, "/tee/public-key")?;
+    let headers = crate::http::auth_headers(token)?;
     let (_status, body) = crate::http::send_json(reqwest::Method::GET, url, None, headers).await?;
     serde_json::from_str(&body).map_err(|e| {
         crate::error::SandboxError::Http(format!("Invalid TeePublicKey response: {e}"))
@@ -0,0 +1,243 @@
+//! Operator-registered sandbox provisioning templates.
+//!
+//! A template is a named preset (image, stack, resources, env skeleton) that
+//! pre-fills `CreateSandboxParams` so customers can provision by name instead
+//! of hand-crafting every parameter. Fields set explicitly on the request
+//! always win; the template only fills what the request left empty or zero.
+//!
+//! Templates are persisted in the state directory and survive restarts.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Result, SandboxError};
+use crate::runtime::{CreateSandboxParams, merge_env_json};
+use crate::store::PersistentStore;
+use crate::util::parse_json_object;
+
+/// Maximum length of a template name.
+pub const MAX_TEMPLATE_NAME_LEN: usize = 64;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub stack: String,
+    #[serde(default)]
+    pub agent_identifier: String,
+    /// Env skeleton (JSON object). Request `env_json` keys override these.
+    #[serde(default)]
+    pub env_json: String,
+    /// Metadata defaults (JSON object). Request keys override these.
+    #[serde(default)]
+    pub metadata_json: String,
+    #[serde(default)]
+    pub capabilities_json: String,
+    #[serde(default)]
+    pub cpu_cores: u64,
+    #[serde(default)]
+    pub memory_mb: u64,
+    #[serde(default)]
+    pub disk_gb: u64,
+    #[serde(default)]
+    pub idle_timeout_seconds: u64,
+    #[serde(default)]
+    pub max_lifetime_seconds: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+static TEMPLATES: OnceCell<PersistentStore<SandboxTemplate>> = OnceCell::new();
+
+/// Access the template persistent store.
+pub fn templates() -> Result<&'static PersistentStore<SandboxTemplate>> {
+    TEMPLATES
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("templates.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// List all registered templates, sorted by name.
+pub fn list_templates() -> Result<Vec<SandboxTemplate>> {
+    let mut all = templates()?.values()?;
+    all.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(all)
+}
+
+/// Look up a template by name.
+pub fn get_template(name: &str) -> Result<SandboxTemplate> {
+    templates()?
+        .get(name.trim())?
+        .ok_or_else(|| SandboxError::NotFound(format!("Template '{}' not found", name.trim())))
+}
+
+/// Validate and register (or overwrite) a template.
+pub fn register_template(mut template: SandboxTemplate) -> Result<SandboxTemplate> {
+    template.name = template.name.trim().to_string();
+    validate_template(&template)?;
+    template.updated_at = crate::util::now_ts();
+    templates()?.insert(template.name.clone(), template.clone())?;
+    Ok(template)
+}
+
+/// Remove a template. Returns `NotFound` if it was never registered.
+pub fn remove_template(name: &str) -> Result<SandboxTemplate> {
+    templates()?
+        .remove(name.trim())?
+        .ok_or_else(|| SandboxError::NotFound(format!("Template '{}' not found", name.trim())))
+}
+
+/// Pre-fill `params` from the named template.
+///
+/// Empty strings and zero values on `params` take the template's value.
+/// `env_json` and `metadata_json` are merged key-by-key with the request's
+/// keys taking precedence.
+pub fn apply_template(params: &mut CreateSandboxParams, name: &str) -> Result<()> {
+    let template = get_template(name)?;
+    apply_template_to(params, &template)
+}
+
+pub(crate) fn apply_template_to(
+    params: &mut CreateSandboxParams,
+    template: &SandboxTemplate,
+) -> Result<()> {
+    fn fill(value: &mut String, default: &str) {
+        if value.trim().is_empty() {
+            *value = default.to_string();
+        }
+    }
+    fn fill_u64(value: &mut u64, default: u64) {
+        if *value == 0 {
+            *value = default;
+        }
+    }
+
+    fill(&mut params.image, &template.image);
+    fill(&mut params.stack, &template.stack);
+    fill(&mut params.agent_identifier, &template.agent_identifier);
+    fill(&mut params.capabilities_json, &template.capabilities_json);
+    fill_u64(&mut params.cpu_cores, template.cpu_cores);
+    fill_u64(&mut params.memory_mb, template.memory_mb);
+    fill_u64(&mut params.disk_gb, template.disk_gb);
+    fill_u64(
+        &mut params.idle_timeout_seconds,
+        template.idle_timeout_seconds,
+    );
+    fill_u64(
+        &mut params.max_lifetime_seconds,
+        template.max_lifetime_seconds,
+    );
+
+    if !template.env_json.trim().is_empty() {
+        parse_json_object(&params.env_json, "env_json")?;
+        params.env_json = merge_env_json(&template.env_json, &params.env_json);
+    }
+    if !template.metadata_json.trim().is_empty() {
+        let mut merged = match parse_json_object(&template.metadata_json, "metadata_json")? {
+            Some(Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        if let Some(Value::Object(overrides)) =
+            parse_json_object(&params.metadata_json, "metadata_json")?
+        {
+            merged.extend(overrides);
+        }
+        params.metadata_json = Value::Object(merged).to_string();
+    }
+    Ok(())
+}
+
+fn validate_template(template: &SandboxTemplate) -> Result<()> {
+    let name = &template.name;
+    if name.is_empty() || name.len() > MAX_TEMPLATE_NAME_LEN {
+        return Err(SandboxError::Validation(format!(
+            "Template name must be 1-{MAX_TEMPLATE_NAME_LEN} characters"
+        )));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(SandboxError::Validation(
+            "Template name may only contain letters, digits, '-', '_' and '.'".into(),
+        ));
+    }
+    parse_json_object(&template.env_json, "env_json")?;
+    parse_json_object(&template.metadata_json, "metadata_json")?;
+    if !template.capabilities_json.trim().is_empty()
+        && !serde_json::from_str::<Value>(&template.capabilities_json).is_ok_and(|v| v.is_array())
+    {
+        return Err(SandboxError::Validation(
+            "capabilities_json must be a JSON array".into(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template() -> SandboxTemplate {
+        SandboxTemplate {
+            name: "python-small".into(),
+            image: "ghcr.io/example/python:3.12".into(),
+            stack: "python".into(),
+            env_json: r#"{"PYTHONUNBUFFERED":"1","MODE":"template"}"#.into(),
+            metadata_json: r#"{"tier":"small"}"#.into(),
+            cpu_cores: 2,
+            memory_mb: 2048,
+            disk_gb: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn apply_template_fills_only_unset_fields() {
+        let mut params = CreateSandboxParams {
+            memory_mb: 4096,
+            env_json: r#"{"MODE":"request"}"#.into(),
+            metadata_json: r#"{"team":"ml"}"#.into(),
+            ..Default::default()
+        };
+        apply_template_to(&mut params, &template()).unwrap();
+
+        assert_eq!(params.image, "ghcr.io/example/python:3.12");
+        assert_eq!(params.stack, "python");
+        assert_eq!(params.cpu_cores, 2);
+        assert_eq!(params.memory_mb, 4096, "explicit request value wins");
+        let env: serde_json::Map<String, Value> = serde_json::from_str(&params.env_json).unwrap();
+        assert_eq!(env["MODE"], "request");
+        assert_eq!(env["PYTHONUNBUFFERED"], "1");
+        let metadata: Value = serde_json::from_str(&params.metadata_json).unwrap();
+        assert_eq!(metadata["tier"], "small");
+        assert_eq!(metadata["team"], "ml");
+    }
+
+    #[test]
+    fn validate_template_rejects_bad_names_and_json() {
+        assert!(validate_template(&template()).is_ok());
+        let bad_name = SandboxTemplate {
+            name: "has space".into(),
+            ..template()
+        };
+        assert!(validate_template(&bad_name).is_err());
+        let bad_env = SandboxTemplate {
+            env_json: "[1]".into(),
+            ..template()
+        };
+        assert!(validate_template(&bad_env).is_err());
+        let bad_caps = SandboxTemplate {
+            capabilities_json: r#"{"computer_use":true}"#.into(),
+            ..template()
+        };
+        assert!(validate_template(&bad_caps).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Per-request tool allowlists for agent tasks.
+//!
+//! A task request may carry `tools_json` to bound what an autonomous agent
+//! may do:
+//!
+//! ```json
+//! { "allow": ["Read", "Grep", "Write"], "deny": ["WebFetch"],
+//!   "network": false, "write_paths": ["/home/agent/out"] }
+//! ```
+//!
+//! - `allow`: tool names the agent may use (empty = the harness defaults).
+//! - `deny`: tool names removed from the allowed set.
+//! - `network`: `false` removes network tools.
+//! - `write_paths`: absolute directories file writes are confined to.
+//!
+//! The policy is forwarded in the `/agents/run` metadata as `tools`. The
+//! sidecar enforces it on the harness and echoes the policy it applied in
+//! the response's `metadata.tools`; a sidecar that does not echo it (an
+//! older image, or a harness that cannot enforce it) fails the task rather
+//! than running it unrestricted. The echo is returned to the caller as the
+//! response's `tools_json`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::output_schema::TaskOutput;
+
+/// Largest accepted `tools_json`.
+pub const MAX_TOOLS_JSON_BYTES: usize = 16 * 1024;
+
+const MAX_TOOL_NAME_LEN: usize = 64;
+const MAX_TOOL_ENTRIES: usize = 64;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct ToolPolicy {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<bool>,
+    #[serde(default, alias = "write_paths", skip_serializing_if = "Vec::is_empty")]
+    pub write_paths: Vec<String>,
+}
+
+impl ToolPolicy {
+    fn validate(&self) -> Result<(), String> {
+        for name in self.allow.iter().chain(&self.deny) {
+            let valid = !name.is_empty()
+                && name.len() <= MAX_TOOL_NAME_LEN
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "_-.:".contains(c));
+            if !valid {
+                return Err(format!("tools_json: invalid tool name {name:?}"));
+            }
+        }
+        if self.allow.len() + self.deny.len() + self.write_paths.len() > MAX_TOOL_ENTRIES {
+            return Err(format!("tools_json: at most {MAX_TOOL_ENTRIES} entries"));
+        }
+        for path in &self.write_paths {
+            if !path.starts_with('/')
+                || path.split('/').any(|part| part == "..")
+                || path.contains(['\0', ',', '(', ')', '*'])
+            {
+                return Err(format!(
+                    "tools_json: write path {path:?} must be an absolute directory"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse `tools_json` (empty = no restriction).
+pub fn parse_tool_policy(raw: &str) -> Result<Option<ToolPolicy>, String> {
+    if raw.trim().is_empty() {
+        return Ok(None);
+    }
+    if raw.len() > MAX_TOOLS_JSON_BYTES {
+        return Err(format!(
+            "tools_json exceeds maximum length ({MAX_TOOLS_JSON_BYTES} bytes)"
+        ));
+    }
+    let policy: ToolPolicy =
+        serde_json::from_str(raw).map_err(|e| format!("tools_json is invalid: {e}"))?;
+    policy.validate()?;
+    Ok(Some(policy))
+}
+
+/// Forward the policy to the sidecar as `metadata.tools`.
+pub fn insert_tool_policy(payload: &mut Map<String, Value>, policy: Option<&ToolPolicy>) {
+    let Some(policy) = policy else {
+        return;
+    };
+    let metadata = payload
+        .entry("metadata")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(metadata) = metadata.as_object_mut() {
+        metadata.insert("tools".to_string(), serde_json::json!(policy));
+    }
+}
+
+/// The tool policy the sidecar reports having applied, as JSON (empty when
+/// it reported none).
+pub fn echoed_tools(parsed: &Value) -> String {
+    parsed
+        .get("metadata")
+        .or_else(|| parsed.get("data").and_then(|d| d.get("metadata")))
+        .and_then(|m| m.get("tools"))
+        .filter(|tools| tools.is_object())
+        .map(Value::to_string)
+        .unwrap_or_default()
+}
+
+/// Fail a successful `output` whose sidecar did not confirm `policy`.
+pub fn require_tool_echo<R: TaskOutput>(output: &mut R, policy: Option<&ToolPolicy>) {
+    if policy.is_some() && output.succeeded() && output.tools_echo().is_empty() {
+        output.set_result(String::new());
+        output.fail(
+            "Sidecar did not confirm the tools_json restrictions; the agent's output was discarded"
+                .into(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_and_forward_policy() {
+        assert!(parse_tool_policy(" ").unwrap().is_none());
+        let policy = parse_tool_policy(
+            r#"{"allow":["Read","Write"],"network":false,"write_paths":["/home/agent/out"]}"#,
+        )
+        .unwrap()
+        .unwrap();
+        let mut payload = Map::new();
+        payload.insert("metadata".into(), json!({ "project": "x" }));
+        insert_tool_policy(&mut payload, Some(&policy));
+        assert_eq!(payload["metadata"]["project"], "x");
+        assert_eq!(
+            payload["metadata"]["tools"],
+            json!({
+                "allow": ["Read", "Write"],
+                "deny": [],
+                "network": false,
+                "writePaths": ["/home/agent/out"]
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_policies() {
+        assert!(parse_tool_policy(r#"{"allow":["rm -rf"]}"#).is_err());
+        assert!(parse_tool_policy(r#"{"write_paths":["out"]}"#).is_err());
+        assert!(parse_tool_policy(r#"{"write_paths":["/home/../etc"]}"#).is_err());
+        assert!(parse_tool_policy(r#"{"shell":true}"#).is_err());
+    }
+
+    #[test]
+    fn echo_is_read_from_response_metadata() {
+        let parsed = json!({ "success": true, "metadata": { "tools": { "allow": ["Read"] } } });
+        assert_eq!(echoed_tools(&parsed), r#"{"allow":["Read"]}"#);
+        assert_eq!(echoed_tools(&json!({ "success": true })), "");
+    }
+}
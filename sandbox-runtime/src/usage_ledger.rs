@@ -0,0 +1,375 @@
+//! Per-owner usage ledger.
+//!
+//! The metrics counters are volatile and not attributed to anyone, so billing
+//! cannot be built on them. The ledger records what each owner consumed:
+//!
+//! - `sandbox_seconds` — wall time of the owner's running sandboxes, sampled
+//!   by the reaper ([`sample_sandbox_time`]);
+//! - `exec_count` — commands run through the operator API and exec jobs;
+//! - `input_tokens` / `output_tokens` — reported by completed agent runs;
+//! - `snapshot_bytes` — archives uploaded by sidecar snapshots.
+//!
+//! Usage is collected in memory and flushed into hourly samples in the state
+//! directory on every reaper tick ([`flush_usage`]), so at most one tick is
+//! lost on a crash. GC rolls closed hours up into monthly totals
+//! ([`roll_up_usage`]) and drops months older than
+//! [`USAGE_RETENTION_MONTHS`]. Owners read their history at
+//! `GET /api/usage/history`; the billing keeper reads
+//! [`current_month_usage`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Datelike};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SandboxError};
+use crate::runtime::{SandboxRecord, SandboxState};
+use crate::store::PersistentStore;
+
+/// Months of history kept per owner, the current one included.
+pub const USAGE_RETENTION_MONTHS: u32 = 24;
+/// Longest gap between two samples credited as sandbox time; longer gaps
+/// (operator downtime, a stalled reaper) are not billed.
+pub const MAX_SAMPLE_GAP_SECS: u64 = 600;
+const HOUR_SECS: u64 = 3600;
+
+/// Consumption over some period.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    #[serde(default)]
+    pub sandbox_seconds: u64,
+    #[serde(default)]
+    pub exec_count: u64,
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub snapshot_bytes: u64,
+}
+
+impl UsageTotals {
+    /// One command run.
+    pub fn exec() -> Self {
+        Self {
+            exec_count: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Tokens of one agent run.
+    pub fn tokens(input_tokens: u32, output_tokens: u32) -> Self {
+        Self {
+            input_tokens: input_tokens.into(),
+            output_tokens: output_tokens.into(),
+            ..Default::default()
+        }
+    }
+
+    /// One uploaded snapshot archive.
+    pub fn snapshot(bytes: u64) -> Self {
+        Self {
+            snapshot_bytes: bytes,
+            ..Default::default()
+        }
+    }
+
+    pub fn add(&mut self, other: &UsageTotals) {
+        self.sandbox_seconds += other.sandbox_seconds;
+        self.exec_count += other.exec_count;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.snapshot_bytes += other.snapshot_bytes;
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Usage within one hour, not yet rolled up.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageSample {
+    /// Start of the hour (unix seconds).
+    pub hour: u64,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// Usage within one calendar month (UTC).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonthlyUsage {
+    /// `YYYY-MM`.
+    pub month: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// Everything recorded for one owner, keyed by lowercased owner.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OwnerLedger {
+    pub owner: String,
+    #[serde(default)]
+    pub samples: Vec<UsageSample>,
+    /// Rolled-up totals by `YYYY-MM`.
+    #[serde(default)]
+    pub months: BTreeMap<String, UsageTotals>,
+}
+
+static LEDGER: OnceCell<PersistentStore<OwnerLedger>> = OnceCell::new();
+/// Usage recorded since the last flush, by owner.
+static PENDING: Lazy<Mutex<HashMap<String, UsageTotals>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// When sandbox time was last sampled in this process.
+static LAST_SAMPLE: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Access the usage ledger persistent store.
+pub fn usage_ledger() -> Result<&'static PersistentStore<OwnerLedger>> {
+    LEDGER
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("usage_ledger.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+fn owner_key(owner: &str) -> String {
+    owner.trim().to_ascii_lowercase()
+}
+
+fn hour_start(ts: u64) -> u64 {
+    ts - ts % HOUR_SECS
+}
+
+/// Months since year 0 of the month containing `ts`.
+fn month_index(ts: u64) -> i64 {
+    let date = DateTime::from_timestamp(ts as i64, 0).unwrap_or_default();
+    i64::from(date.year()) * 12 + i64::from(date.month0())
+}
+
+fn month_key_of_index(index: i64) -> String {
+    format!(
+        "{:04}-{:02}",
+        index.div_euclid(12),
+        index.rem_euclid(12) + 1
+    )
+}
+
+/// `YYYY-MM` of the month containing `ts`.
+pub fn month_key(ts: u64) -> String {
+    month_key_of_index(month_index(ts))
+}
+
+/// Add `delta` to `owner`'s usage. Cheap; persisted on the next flush.
+pub fn record_usage(owner: &str, delta: UsageTotals) {
+    let owner = owner_key(owner);
+    if owner.is_empty() || delta.is_zero() {
+        return;
+    }
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    pending.entry(owner).or_default().add(&delta);
+}
+
+/// Add `delta` to the usage of `sandbox_id`'s owner. Unknown sandboxes are
+/// ignored.
+pub fn record_sandbox_usage(sandbox_id: &str, delta: UsageTotals) {
+    if let Ok(record) = crate::runtime::get_sandbox_by_id(sandbox_id) {
+        record_usage(&record.owner, delta);
+    }
+}
+
+/// Credit each running sandbox in `records` with the time since the previous
+/// sample (at most [`MAX_SAMPLE_GAP_SECS`], and never more than the sandbox
+/// has existed). The first call in a process only sets the baseline.
+pub fn sample_sandbox_time(records: &[SandboxRecord], now: u64) {
+    let elapsed = {
+        let mut last = LAST_SAMPLE.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = last.replace(now);
+        match previous {
+            Some(previous) => now.saturating_sub(previous).min(MAX_SAMPLE_GAP_SECS),
+            None => return,
+        }
+    };
+    for record in records {
+        if record.state != SandboxState::Running {
+            continue;
+        }
+        let seconds = elapsed.min(now.saturating_sub(record.created_at));
+        record_usage(
+            &record.owner,
+            UsageTotals {
+                sandbox_seconds: seconds,
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// Persist usage recorded since the last flush into the samples for the hour
+/// of `now`. Usage that cannot be written is kept for the next flush.
+pub fn flush_usage(now: u64) -> Result<()> {
+    let pending = std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()));
+    let hour = hour_start(now);
+    let mut failed = None;
+    for (owner, delta) in pending {
+        if failed.is_some() {
+            record_usage(&owner, delta);
+            continue;
+        }
+        if let Err(e) = add_to_sample(&owner, hour, &delta) {
+            record_usage(&owner, delta);
+            failed = Some(e);
+        }
+    }
+    failed.map_or(Ok(()), Err)
+}
+
+fn add_to_sample(owner: &str, hour: u64, delta: &UsageTotals) -> Result<()> {
+    usage_ledger()?.upsert(
+        owner,
+        || OwnerLedger {
+            owner: owner.to_string(),
+            ..Default::default()
+        },
+        |ledger| match ledger.samples.iter_mut().find(|s| s.hour == hour) {
+            Some(sample) => sample.totals.add(delta),
+            None => ledger.samples.push(UsageSample {
+                hour,
+                totals: *delta,
+            }),
+        },
+    )
+}
+
+/// Fold samples of hours before `now`'s into monthly totals and drop months
+/// older than [`USAGE_RETENTION_MONTHS`].
+pub fn roll_up_usage(now: u64) -> Result<()> {
+    let store = usage_ledger()?;
+    let current_hour = hour_start(now);
+    let oldest = month_key_of_index(month_index(now) - i64::from(USAGE_RETENTION_MONTHS) + 1);
+    for ledger in store.values()? {
+        let key = ledger.owner.clone();
+        store.update(&key, |ledger| {
+            let (closed, open): (Vec<_>, Vec<_>) = std::mem::take(&mut ledger.samples)
+                .into_iter()
+                .partition(|s| s.hour < current_hour);
+            for sample in closed {
+                ledger
+                    .months
+                    .entry(month_key(sample.hour))
+                    .or_default()
+                    .add(&sample.totals);
+            }
+            ledger.samples = open;
+            ledger.months.retain(|month, _| *month >= oldest);
+        })?;
+        if store
+            .get(&key)?
+            .is_some_and(|l| l.samples.is_empty() && l.months.is_empty())
+        {
+            store.remove(&key)?;
+        }
+    }
+    Ok(())
+}
+
+/// `owner`'s usage by month, oldest first, including samples not yet rolled
+/// up and usage not yet flushed.
+pub fn usage_history(owner: &str) -> Result<Vec<MonthlyUsage>> {
+    let owner = owner_key(owner);
+    let mut months = BTreeMap::new();
+    if let Some(ledger) = usage_ledger()?.get(&owner)? {
+        months = ledger.months;
+        for sample in ledger.samples {
+            months
+                .entry(month_key(sample.hour))
+                .or_insert_with(UsageTotals::default)
+                .add(&sample.totals);
+        }
+    }
+    let pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(delta) = pending.get(&owner) {
+        months
+            .entry(month_key(crate::util::now_ts()))
+            .or_default()
+            .add(delta);
+    }
+    Ok(months
+        .into_iter()
+        .map(|(month, totals)| MonthlyUsage { month, totals })
+        .collect())
+}
+
+/// `owner`'s usage in the current month so far.
+pub fn current_month_usage(owner: &str) -> Result<MonthlyUsage> {
+    let month = month_key(crate::util::now_ts());
+    let totals = usage_history(owner)?
+        .into_iter()
+        .find(|m| m.month == month)
+        .map(|m| m.totals)
+        .unwrap_or_default();
+    Ok(MonthlyUsage { month, totals })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() {
+        let dir = std::env::temp_dir().join(format!("usage-ledger-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok();
+        unsafe { std::env::set_var("BLUEPRINT_STATE_DIR", dir) };
+    }
+
+    #[test]
+    fn month_keys_follow_the_utc_calendar() {
+        // 2026-01-31T23:59:59Z and one second later.
+        assert_eq!(month_key(1_769_903_999), "2026-01");
+        assert_eq!(month_key(1_769_904_000), "2026-02");
+        assert_eq!(
+            month_key_of_index(month_index(1_769_904_000) - 2),
+            "2025-12"
+        );
+    }
+
+    #[test]
+    fn usage_is_flushed_rolled_up_and_kept_per_owner() {
+        init();
+        let owner = "0xUSAGE1";
+        // Two hours in 2020-03, long closed by now.
+        let hour = 1_583_020_800;
+        record_usage(owner, UsageTotals::exec());
+        record_usage(owner, UsageTotals::tokens(100, 40));
+        flush_usage(hour + 10).unwrap();
+        record_usage("0xusage1", UsageTotals::snapshot(2048));
+        flush_usage(hour + HOUR_SECS + 10).unwrap();
+
+        let ledger = usage_ledger().unwrap().get("0xusage1").unwrap().unwrap();
+        assert_eq!(ledger.samples.len(), 2);
+
+        roll_up_usage(hour + 2 * HOUR_SECS).unwrap();
+        let ledger = usage_ledger().unwrap().get("0xusage1").unwrap().unwrap();
+        assert!(ledger.samples.is_empty());
+        let march = UsageTotals {
+            exec_count: 1,
+            input_tokens: 100,
+            output_tokens: 40,
+            snapshot_bytes: 2048,
+            ..Default::default()
+        };
+        assert_eq!(ledger.months["2020-03"], march);
+        assert_eq!(
+            usage_history(owner).unwrap(),
+            vec![MonthlyUsage {
+                month: "2020-03".into(),
+                totals: march,
+            }]
+        );
+
+        // Past retention, the month and then the ledger are dropped.
+        roll_up_usage(crate::util::now_ts()).unwrap();
+        assert!(usage_ledger().unwrap().get("0xusage1").unwrap().is_none());
+    }
+}
@@ -1,31 +1,100 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use once_cell::sync::OnceCell;
-use reqwest::Client;
+use reqwest::{Client, redirect};
 
 use crate::error::{Result, SandboxError};
 
 static HTTP_CLIENT: OnceCell<Client> = OnceCell::new();
 static HTTP_CLIENT_NO_TIMEOUT: OnceCell<Client> = OnceCell::new();
+static EXTERNAL_HTTP_CLIENT: OnceCell<Client> = OnceCell::new();
+
+/// Idle pooled connections are dropped after this long.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+/// Idle connections kept per host (sidecars are one host each).
+const POOL_MAX_IDLE_PER_HOST: usize = 16;
+const TCP_KEEPALIVE: Duration = Duration::from_secs(30);
+/// Slack on top of a sidecar-side deadline so the sidecar can report its own
+/// timeout before the HTTP request gives up.
+const SIDECAR_DEADLINE_GRACE: Duration = Duration::from_secs(15);
+
+fn base_builder(timeout: Option<Duration>) -> reqwest::ClientBuilder {
+    let config = crate::runtime::SidecarRuntimeConfig::load();
+    let mut builder = Client::builder()
+        .connect_timeout(config.connect_timeout)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+        .tcp_keepalive(TCP_KEEPALIVE);
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    builder
+}
+
+fn finish(builder: reqwest::ClientBuilder) -> Result<Client> {
+    builder
+        .build()
+        .map_err(|err| SandboxError::Http(format!("Failed to build HTTP client: {err}")))
+}
+
+fn build_client(timeout: Option<Duration>) -> Result<Client> {
+    build_policy_client(crate::http::UrlPolicy::load(), timeout)
+}
 
-/// Get the shared HTTP client. The timeout is set from `SidecarRuntimeConfig`
-/// on first initialization and reused for all subsequent calls.
+/// Client bound by `policy`: hostnames only resolve to addresses the policy
+/// allows and redirects are not followed.
+pub(crate) fn build_policy_client(
+    policy: &crate::http::UrlPolicy,
+    timeout: Option<Duration>,
+) -> Result<Client> {
+    finish(
+        base_builder(timeout)
+            .redirect(redirect::Policy::none())
+            .dns_resolver(Arc::new(policy.resolver())),
+    )
+}
+
+/// Get the shared, pooled HTTP client for sidecars and other URLs that come
+/// from jobs or API requests. It refuses addresses outside
+/// [`crate::http::UrlPolicy`] and does not follow redirects. Its timeout
+/// (`REQUEST_TIMEOUT_SECS`) is only the default: a request that sets
+/// `RequestBuilder::timeout` (see [`request_timeout`]) overrides it.
 pub fn http_client() -> Result<&'static Client> {
     HTTP_CLIENT
         .get_or_try_init(|| {
-            let config = crate::runtime::SidecarRuntimeConfig::load();
-            Client::builder()
-                .timeout(config.timeout)
-                .build()
-                .map_err(|err| SandboxError::Http(format!("Failed to build HTTP client: {err}")))
+            build_client(Some(crate::runtime::SidecarRuntimeConfig::load().timeout))
         })
         .map_err(|err| SandboxError::Http(err.to_string()))
 }
 
+/// Pooled client with no overall timeout, for streams and calls bounded
+/// elsewhere. Connecting is still bounded by `HTTP_CONNECT_TIMEOUT_SECS`.
 pub fn http_client_no_timeout() -> Result<&'static Client> {
     HTTP_CLIENT_NO_TIMEOUT
+        .get_or_try_init(|| build_client(None))
+        .map_err(|err| SandboxError::Http(err.to_string()))
+}
+
+/// Pooled client for endpoints the operator configured itself (object
+/// stores, Vault, cloud and TEE provider APIs), which may live on internal
+/// networks. Follows redirects; never hand it a URL taken from a request.
+pub fn external_http_client() -> Result<&'static Client> {
+    EXTERNAL_HTTP_CLIENT
         .get_or_try_init(|| {
-            Client::builder()
-                .build()
-                .map_err(|err| SandboxError::Http(format!("Failed to build HTTP client: {err}")))
+            finish(base_builder(Some(
+                crate::runtime::SidecarRuntimeConfig::load().timeout,
+            )))
         })
         .map_err(|err| SandboxError::Http(err.to_string()))
 }
+
+/// HTTP timeout for a sidecar call that asks the sidecar to run for up to
+/// `timeout_ms`: that deadline plus a grace period, or the default timeout
+/// when no deadline (0) is given. Never shorter than `default`.
+pub fn request_timeout(timeout_ms: u64, default: Duration) -> Duration {
+    if timeout_ms == 0 {
+        return default;
+    }
+    (Duration::from_millis(timeout_ms) + SIDECAR_DEADLINE_GRACE).max(default)
+}
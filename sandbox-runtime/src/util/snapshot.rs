@@ -105,6 +105,12 @@ fn validate_snapshot_destination(destination: &str) -> Result<()> {
     Ok(())
 }
 
+/// Prefix of the line a snapshot command prints with the archive size.
+pub const SNAPSHOT_BYTES_MARKER: &str = "snapshot-bytes=";
+
+/// Build the sidecar command that archives the workspace and/or agent state
+/// and uploads the archive to `destination`. The command ends by printing
+/// the archive size (see [`snapshot_bytes`]).
 pub fn build_snapshot_command(
     destination: &str,
     include_workspace: bool,
@@ -131,6 +137,89 @@ pub fn build_snapshot_command(
         "set -euo pipefail; tmp=$(mktemp /tmp/snapshot-XXXXXX); \
  tar -czf \"$tmp\" {targets}; \
  curl -fsSL -X PUT --upload-file \"$tmp\" {dest}; \
+ echo; echo \"{SNAPSHOT_BYTES_MARKER}$(wc -c < \"$tmp\")\"; \
+ rm -f \"$tmp\""
+    ))
+}
+
+/// Archive size printed by a snapshot command, from its stdout. 0 when the
+/// output has none.
+pub fn snapshot_bytes(stdout: &str) -> u64 {
+    stdout
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix(SNAPSHOT_BYTES_MARKER))
+        .and_then(|n| n.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Build the sidecar command that archives one file or directory at the
+/// absolute `path` and uploads the archive to `destination`.
+pub fn build_artifact_command(destination: &str, path: &str) -> Result<String> {
+    validate_snapshot_destination(destination)?;
+    let relative = path.trim_start_matches('/');
+    if !path.starts_with('/')
+        || relative.is_empty()
+        || path.contains('\0')
+        || relative.split('/').any(|part| part == "..")
+    {
+        return Err(SandboxError::Validation(format!(
+            "Artifact path must be an absolute path without '..': {path}"
+        )));
+    }
+
+    let dest = shell_escape(destination);
+    let target = shell_escape(relative);
+    Ok(format!(
+        "set -euo pipefail; tmp=$(mktemp /tmp/artifact-XXXXXX); \
+ tar -czf \"$tmp\" -C / {target}; \
+ curl -fsSL -X PUT --upload-file \"$tmp\" {dest}; \
+ echo; echo \"{SNAPSHOT_BYTES_MARKER}$(wc -c < \"$tmp\")\"; \
+ rm -f \"$tmp\""
+    ))
+}
+
+/// Archive size printed by a snapshot command, from its stdout. 0 when the
+/// output has none.
+pub fn snapshot_bytes(stdout: &str) -> u64 {
+    stdout
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix(SNAPSHOT_BYTES_MARKER))
+        .and_then(|n| n.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// File name of the record manifest at the root of an instance backup archive.
+pub const BACKUP_MANIFEST_FILE: &str = "instance-backup.json";
+
+/// Build the sidecar command that archives the workspace, sidecar state and
+/// a record manifest (`manifest_json`, written as [`BACKUP_MANIFEST_FILE`] at
+/// the archive root) and uploads the archive to `destination`.
+pub fn build_backup_command(destination: &str, manifest_json: &str) -> Result<String> {
+    validate_snapshot_destination(destination)?;
+    let dest = shell_escape(destination);
+    let manifest = shell_escape(manifest_json);
+    Ok(format!(
+        "set -euo pipefail; dir=$(mktemp -d /tmp/backup-XXXXXX); tmp=\"$dir.tar.gz\"; \
+ printf '%s' {manifest} > \"$dir/{BACKUP_MANIFEST_FILE}\"; \
+ tar -czf \"$tmp\" -C / home/agent var/lib/sidecar -C \"$dir\" {BACKUP_MANIFEST_FILE}; \
+ curl -fsSL -X PUT --upload-file \"$tmp\" {dest}; \
+ rm -rf \"$dir\" \"$tmp\""
+    ))
+}
+
+/// Build the sidecar command that downloads a backup archive from `source`,
+/// extracts the workspace and sidecar state over `/`, and prints the archive's
+/// manifest (empty output when the archive has none).
+pub fn build_restore_command(source: &str) -> Result<String> {
+    validate_snapshot_destination(source)?;
+    let src = shell_escape(source);
+    Ok(format!(
+        "set -euo pipefail; tmp=$(mktemp /tmp/restore-XXXXXX); \
+ curl -fsSL {src} -o \"$tmp\"; \
+ tar -xzf \"$tmp\" -C / --exclude={BACKUP_MANIFEST_FILE}; \
+ tar -xzf \"$tmp\" -O {BACKUP_MANIFEST_FILE} 2>/dev/null || true; \
  rm -f \"$tmp\""
     ))
 }
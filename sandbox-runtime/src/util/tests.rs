@@ -56,6 +56,34 @@ fn build_snapshot_command_valid_s3() {
     assert!(!cmd.contains("/var/lib/sidecar"));
 }
 
+#[test]
+fn snapshot_bytes_reads_the_size_line() {
+    let cmd = build_snapshot_command("s3://my-bucket/snap.tar.gz", true, false).unwrap();
+    assert!(cmd.contains(SNAPSHOT_BYTES_MARKER));
+    assert_eq!(
+        snapshot_bytes("{\"etag\":\"x\"}\nsnapshot-bytes=  4096\n"),
+        4096
+    );
+    assert_eq!(snapshot_bytes("uploaded"), 0);
+}
+
+#[test]
+fn build_artifact_command_archives_one_path() {
+    let cmd = build_artifact_command("s3://bucket/wf/report.tar.gz", "/home/agent/out/report.md")
+        .unwrap();
+    assert!(cmd.contains("-C / 'home/agent/out/report.md'"));
+    assert!(cmd.contains("'s3://bucket/wf/report.tar.gz'"));
+}
+
+#[test]
+fn build_artifact_command_rejects_bad_paths_and_destinations() {
+    let dest = "s3://bucket/a.tar.gz";
+    assert!(build_artifact_command(dest, "relative/path").is_err());
+    assert!(build_artifact_command(dest, "/").is_err());
+    assert!(build_artifact_command(dest, "/home/agent/../../etc").is_err());
+    assert!(build_artifact_command("https://10.0.0.1/a", "/home/agent/out").is_err());
+}
+
 #[test]
 fn build_snapshot_command_rejects_private_ip() {
     let result = build_snapshot_command("https://192.168.1.1/snap", true, true);
@@ -219,6 +247,24 @@ fn build_snapshot_command_rejects_ipv6_unspecified() {
     assert!(result.is_err());
 }
 
+// ── build_backup_command / build_restore_command ─────────────────────
+
+#[test]
+fn build_backup_command_embeds_manifest_and_both_paths() {
+    let cmd = build_backup_command("https://93.184.216.34/b.tar.gz", r#"{"version":1}"#).unwrap();
+    assert!(cmd.contains("home/agent var/lib/sidecar"));
+    assert!(cmd.contains(BACKUP_MANIFEST_FILE));
+    assert!(cmd.contains(r#"'{"version":1}'"#));
+}
+
+#[test]
+fn build_restore_command_validates_source() {
+    let cmd = build_restore_command("s3://bucket/b.tar.gz").unwrap();
+    assert!(cmd.contains(&format!("--exclude={BACKUP_MANIFEST_FILE}")));
+    assert!(build_restore_command("https://10.0.0.1/b.tar.gz").is_err());
+    assert!(build_backup_command("http://93.184.216.34/b", "{}").is_err());
+}
+
 // ── normalize_username ──────────────────────────────────────────────
 
 #[test]
@@ -368,3 +414,21 @@ fn merge_metadata_string_value_errors() {
     let result = merge_metadata(metadata, "img", "");
     assert!(result.is_err());
 }
+
+// ── request_timeout ─────────────────────────────────────────────────
+
+#[test]
+fn request_timeout_defaults_without_deadline() {
+    let default = std::time::Duration::from_secs(30);
+    assert_eq!(request_timeout(0, default), default);
+}
+
+#[test]
+fn request_timeout_follows_long_deadline_with_grace() {
+    let default = std::time::Duration::from_secs(30);
+    let timeout = request_timeout(600_000, default);
+    assert!(timeout > std::time::Duration::from_secs(600));
+    assert!(timeout < std::time::Duration::from_secs(700));
+    // Short deadlines never undercut the default.
+    assert_eq!(request_timeout(1_000, default), default);
+}
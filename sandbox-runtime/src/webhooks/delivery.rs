@@ -0,0 +1,222 @@
+//! Signed webhook delivery with retries.
+//!
+//! Receivers verify `X-Tangle-Signature: v1=<hex>`, the HMAC-SHA256 of
+//! `{X-Tangle-Timestamp}.{raw body}` under the webhook secret, and should
+//! reject stale timestamps to block replays. Non-2xx answers and transport
+//! errors are retried up to [`MAX_DELIVERY_ATTEMPTS`] times with growing
+//! delays, except 4xx responses other than 408 and 429, which fail at once.
+//! Redirects are not followed (a 3xx fails the delivery), and hostnames only
+//! resolve to addresses [`super::webhook_url_policy`] allows.
+
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use once_cell::sync::OnceCell;
+use serde_json::{Value, json};
+use sha2::Sha256;
+
+use super::{
+    DeliveryStatus, WebhookDelivery, WebhookEvent, WebhookRecord, validate_url, webhook_deliveries,
+    webhook_url_policy,
+};
+use crate::error::Result;
+
+pub const SIGNATURE_HEADER: &str = "x-tangle-signature";
+pub const TIMESTAMP_HEADER: &str = "x-tangle-timestamp";
+const EVENT_HEADER: &str = "x-tangle-event";
+const DELIVERY_HEADER: &str = "x-tangle-delivery";
+
+/// Attempts per delivery, including the first.
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Delay before each retry.
+#[cfg(not(test))]
+const RETRY_DELAYS: [Duration; 4] = [
+    Duration::from_secs(5),
+    Duration::from_secs(30),
+    Duration::from_secs(120),
+    Duration::from_secs(600),
+];
+#[cfg(test)]
+const RETRY_DELAYS: [Duration; 4] = [Duration::from_millis(10); 4];
+
+static WEBHOOK_CLIENT: OnceCell<reqwest::Client> = OnceCell::new();
+
+fn webhook_client() -> Result<&'static reqwest::Client> {
+    WEBHOOK_CLIENT.get_or_try_init(|| {
+        crate::util::build_policy_client(&webhook_url_policy(), Some(ATTEMPT_TIMEOUT))
+    })
+}
+
+/// `v1=<hex>` signature of `body` sent at `timestamp`.
+pub fn sign_payload(secret: &str, timestamp: u64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+    format!("v1={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Constant-time check of a signature header, for receivers written in Rust.
+pub fn verify_signature(secret: &str, timestamp: u64, body: &str, signature: &str) -> bool {
+    use subtle::ConstantTimeEq;
+    let expected = sign_payload(secret, timestamp, body);
+    expected.as_bytes().ct_eq(signature.as_bytes()).into()
+}
+
+/// Record a pending delivery of `event` to `hook` and send it in the
+/// background.
+pub(super) fn spawn_delivery(hook: WebhookRecord, event: WebhookEvent, data: Value) -> Result<()> {
+    let now = crate::util::now_ts();
+    let delivery = WebhookDelivery {
+        id: format!("whd-{}", uuid::Uuid::new_v4().simple()),
+        webhook_id: hook.id.clone(),
+        owner: hook.owner.clone(),
+        event,
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        response_status: None,
+        last_error: None,
+        created_at: now,
+        completed_at: None,
+    };
+    let body = json!({
+        "id": delivery.id,
+        "event": event,
+        "created_at": now,
+        "data": data,
+    })
+    .to_string();
+    let secret = crate::runtime::unseal_field(&hook.secret)?;
+    webhook_deliveries()?.insert(delivery.id.clone(), delivery.clone())?;
+    tokio::spawn(deliver(hook, delivery, secret, body));
+    Ok(())
+}
+
+enum AttemptOutcome {
+    Delivered(u16),
+    Retry(Option<u16>, String),
+    Fail(Option<u16>, String),
+}
+
+async fn attempt(
+    hook: &WebhookRecord,
+    delivery: &WebhookDelivery,
+    secret: &str,
+    body: &str,
+) -> AttemptOutcome {
+    // Registered before the current policy, or the allowlist shrank since.
+    if let Err(e) = validate_url(&hook.url) {
+        return AttemptOutcome::Fail(None, e.to_string());
+    }
+    let client = match webhook_client() {
+        Ok(client) => client,
+        Err(e) => return AttemptOutcome::Retry(None, e.to_string()),
+    };
+    let timestamp = crate::util::now_ts();
+    let response = client
+        .post(&hook.url)
+        .timeout(ATTEMPT_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, delivery.event.as_str())
+        .header(DELIVERY_HEADER, &delivery.id)
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(SIGNATURE_HEADER, sign_payload(secret, timestamp, body))
+        .body(body.to_string())
+        .send()
+        .await;
+    let status = match response {
+        Ok(response) => response.status(),
+        Err(e) => return AttemptOutcome::Retry(None, e.to_string()),
+    };
+    let code = Some(status.as_u16());
+    if status.is_success() {
+        AttemptOutcome::Delivered(status.as_u16())
+    } else if status.is_redirection() {
+        AttemptOutcome::Fail(
+            code,
+            format!("Endpoint answered {status}; redirects are not followed"),
+        )
+    } else if status.is_client_error() && !matches!(status.as_u16(), 408 | 429) {
+        AttemptOutcome::Fail(code, format!("Endpoint answered {status}"))
+    } else {
+        AttemptOutcome::Retry(code, format!("Endpoint answered {status}"))
+    }
+}
+
+fn record_attempt(delivery_id: &str, outcome: &AttemptOutcome, last: bool) -> Result<()> {
+    let now = crate::util::now_ts();
+    webhook_deliveries()?.update(delivery_id, |d| {
+        d.attempts += 1;
+        match outcome {
+            AttemptOutcome::Delivered(code) => {
+                d.status = DeliveryStatus::Delivered;
+                d.response_status = Some(*code);
+                d.last_error = None;
+                d.completed_at = Some(now);
+            }
+            AttemptOutcome::Retry(code, error) | AttemptOutcome::Fail(code, error) => {
+                d.response_status = *code;
+                d.last_error = Some(error.clone());
+                if last || matches!(outcome, AttemptOutcome::Fail(..)) {
+                    d.status = DeliveryStatus::Failed;
+                    d.completed_at = Some(now);
+                }
+            }
+        }
+    })?;
+    Ok(())
+}
+
+async fn deliver(hook: WebhookRecord, delivery: WebhookDelivery, secret: String, body: String) {
+    for n in 0..MAX_DELIVERY_ATTEMPTS {
+        if n > 0 {
+            tokio::time::sleep(RETRY_DELAYS[(n - 1) as usize]).await;
+        }
+        let outcome = attempt(&hook, &delivery, &secret, &body).await;
+        let last = n + 1 == MAX_DELIVERY_ATTEMPTS;
+        if let Err(e) = record_attempt(&delivery.id, &outcome, last) {
+            tracing::warn!(delivery_id = %delivery.id, error = %e, "webhooks: failed to log attempt");
+        }
+        match outcome {
+            AttemptOutcome::Delivered(_) => return,
+            AttemptOutcome::Fail(_, error) => {
+                tracing::warn!(webhook_id = %hook.id, delivery_id = %delivery.id, %error, "webhooks: delivery rejected");
+                return;
+            }
+            AttemptOutcome::Retry(_, error) if last => {
+                tracing::warn!(webhook_id = %hook.id, delivery_id = %delivery.id, %error, "webhooks: delivery failed after retries");
+            }
+            AttemptOutcome::Retry(..) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_is_hmac_of_timestamp_and_body() {
+        let sig = sign_payload("whsec_test", 1_700_000_000, r#"{"a":1}"#);
+        assert!(sig.starts_with("v1="));
+        assert_eq!(sig.len(), 3 + 64);
+        assert!(verify_signature(
+            "whsec_test",
+            1_700_000_000,
+            r#"{"a":1}"#,
+            &sig
+        ));
+        assert!(!verify_signature(
+            "whsec_test",
+            1_700_000_001,
+            r#"{"a":1}"#,
+            &sig
+        ));
+        assert!(!verify_signature(
+            "other",
+            1_700_000_000,
+            r#"{"a":1}"#,
+            &sig
+        ));
+    }
+}
@@ -0,0 +1,456 @@
+//! Per-owner webhook notifications.
+//!
+//! Owners register an endpoint URL ([`WebhookRecord`]) and choose the
+//! [`WebhookEvent`]s it receives: sandbox lifecycle changes and crashes,
+//! workflow and batch completions, reaper warnings, billing alerts and
+//! maintenance notices. The operator generates the signing secret at registration and
+//! returns it once; it is sealed at rest.
+//!
+//! [`emit`] fans an event out to the owner's matching webhooks in the
+//! background. Each delivery POSTs a JSON envelope
+//! (`{id, event, created_at, data}`) signed with HMAC-SHA256 over
+//! `{timestamp}.{body}` and retries failures with backoff (see
+//! [`delivery`]). Every delivery is logged as a [`WebhookDelivery`]; logs
+//! are pruned by the GC tick after [`DELIVERY_RETENTION_SECS`].
+//!
+//! Webhook URLs cannot target loopback, private, link-local or CGNAT
+//! addresses (unless listed in [`WEBHOOK_ALLOWED_NETWORKS_ENV`]), whether
+//! given as IP literals or reached by resolving a hostname, and deliveries
+//! do not follow redirects: the delivery log would otherwise let any signed-in
+//! wallet probe the operator's internal network.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::url::Host;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::error::{Result, SandboxError};
+use crate::http::{UrlPolicy, is_internal, parse_networks};
+use crate::store::PersistentStore;
+
+mod delivery;
+
+pub use delivery::{
+    MAX_DELIVERY_ATTEMPTS, SIGNATURE_HEADER, TIMESTAMP_HEADER, sign_payload, verify_signature,
+};
+
+/// Maximum webhooks per owner.
+pub const MAX_WEBHOOKS_PER_OWNER: usize = 10;
+/// How long delivery logs are kept (7 days).
+pub const DELIVERY_RETENTION_SECS: u64 = 7 * 24 * 60 * 60;
+const MAX_URL_LEN: usize = 2048;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "sandbox.created")]
+    SandboxCreated,
+    #[serde(rename = "sandbox.stopped")]
+    SandboxStopped,
+    #[serde(rename = "sandbox.resumed")]
+    SandboxResumed,
+    #[serde(rename = "sandbox.deleted")]
+    SandboxDeleted,
+    /// The sandbox container was OOM-killed or exited unexpectedly.
+    #[serde(rename = "sandbox.crashed")]
+    SandboxCrashed,
+    /// The sandbox crossed its disk soft limit or quota, or came back under.
+    #[serde(rename = "sandbox.disk_quota")]
+    SandboxDiskQuota,
+    /// A workflow run finished, successfully or not.
+    #[serde(rename = "workflow.completed")]
+    WorkflowCompleted,
+    /// A batch create, exec or task job finished.
+    #[serde(rename = "batch.completed")]
+    BatchCompleted,
+    /// The reaper will stop or delete a sandbox soon.
+    #[serde(rename = "reaper.warning")]
+    ReaperWarning,
+    /// The subscription escrow is running low or was exhausted.
+    #[serde(rename = "billing.alert")]
+    BillingAlert,
+    /// The operator started draining for maintenance; the sandbox will be
+    /// snapshotted and/or stopped at the end of the window.
+    #[serde(rename = "maintenance.scheduled")]
+    MaintenanceScheduled,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SandboxCreated => "sandbox.created",
+            Self::SandboxStopped => "sandbox.stopped",
+            Self::SandboxResumed => "sandbox.resumed",
+            Self::SandboxDeleted => "sandbox.deleted",
+            Self::SandboxCrashed => "sandbox.crashed",
+            Self::SandboxDiskQuota => "sandbox.disk_quota",
+            Self::WorkflowCompleted => "workflow.completed",
+            Self::BatchCompleted => "batch.completed",
+            Self::ReaperWarning => "reaper.warning",
+            Self::BillingAlert => "billing.alert",
+            Self::MaintenanceScheduled => "maintenance.scheduled",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookRecord {
+    pub id: String,
+    pub owner: String,
+    pub url: String,
+    /// Signing secret, sealed with the record encryption key.
+    pub secret: String,
+    /// Events delivered to this webhook; empty means all.
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    pub created_at: u64,
+}
+
+impl WebhookRecord {
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+
+    /// API view: everything but the secret.
+    pub fn summary(&self) -> Value {
+        json!({
+            "id": self.id,
+            "url": self.url,
+            "events": self.events,
+            "created_at": self.created_at,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Not yet delivered; attempts remain.
+    Pending,
+    Delivered,
+    /// Gave up after the last attempt or a non-retryable response.
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub owner: String,
+    pub event: WebhookEvent,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// HTTP status of the last attempt, if the endpoint answered.
+    #[serde(default)]
+    pub response_status: Option<u16>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub completed_at: Option<u64>,
+}
+
+static WEBHOOKS: OnceCell<PersistentStore<WebhookRecord>> = OnceCell::new();
+static DELIVERIES: OnceCell<PersistentStore<WebhookDelivery>> = OnceCell::new();
+
+/// Access the webhook registration store.
+pub fn webhooks() -> Result<&'static PersistentStore<WebhookRecord>> {
+    WEBHOOKS
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("webhooks.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Access the delivery log store.
+pub fn webhook_deliveries() -> Result<&'static PersistentStore<WebhookDelivery>> {
+    DELIVERIES
+        .get_or_try_init(|| {
+            let path = crate::store::state_dir().join("webhook_deliveries.json");
+            PersistentStore::open(path)
+        })
+        .map_err(|err: SandboxError| err)
+}
+
+/// Env var listing CIDRs webhook URLs may target even though they are
+/// internal (e.g. a receiver on the operator host). Unset by default.
+pub const WEBHOOK_ALLOWED_NETWORKS_ENV: &str = "WEBHOOK_ALLOWED_NETWORKS";
+
+/// Where webhook deliveries may go: public addresses, plus
+/// [`WEBHOOK_ALLOWED_NETWORKS_ENV`].
+pub(crate) fn webhook_url_policy() -> UrlPolicy {
+    let networks = std::env::var(WEBHOOK_ALLOWED_NETWORKS_ENV).unwrap_or_default();
+    UrlPolicy {
+        schemes: vec!["https".into(), "http".into()],
+        allowed_networks: parse_networks(WEBHOOK_ALLOWED_NETWORKS_ENV, &networks),
+        allowed_hosts: Vec::new(),
+    }
+}
+
+/// Reject URLs that are malformed or point at loopback, private,
+/// link-local or CGNAT addresses (see [`crate::http::UrlPolicy`]). Hostnames
+/// are checked again when delivery resolves them.
+pub(crate) fn validate_url(url: &str) -> Result<()> {
+    let invalid = |msg: &str| Err(SandboxError::Validation(format!("Webhook URL {msg}")));
+    if url.len() > MAX_URL_LEN {
+        return invalid(&format!("exceeds {MAX_URL_LEN} chars"));
+    }
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return invalid("is not a valid URL");
+    };
+    let ip = match parsed.host() {
+        None => return invalid("has no host"),
+        Some(Host::Ipv4(v4)) => Some(IpAddr::V4(v4)),
+        Some(Host::Ipv6(v6)) => Some(IpAddr::V6(v6)),
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            (domain == "localhost" || domain.ends_with(".localhost"))
+                .then_some(IpAddr::from([127, 0, 0, 1]))
+        }
+    };
+    if let Some(ip) = ip
+        && !webhook_url_policy().ip_allowed(ip)
+    {
+        return invalid(&format!(
+            "must not target an internal address ({ip}; allow it with {WEBHOOK_ALLOWED_NETWORKS_ENV})"
+        ));
+    }
+    // Plain http only reaches a receiver the operator explicitly allowed.
+    let allowed_internal = ip.is_some_and(is_internal);
+    match parsed.scheme() {
+        "https" => Ok(()),
+        "http" if allowed_internal => Ok(()),
+        _ => invalid(&format!(
+            "must use https (http is allowed for {WEBHOOK_ALLOWED_NETWORKS_ENV} only)"
+        )),
+    }
+}
+
+/// Webhooks registered by `owner`, oldest first.
+pub fn list_webhooks(owner: &str) -> Result<Vec<WebhookRecord>> {
+    let mut hooks: Vec<WebhookRecord> = webhooks()?
+        .values()?
+        .into_iter()
+        .filter(|w| w.owner.eq_ignore_ascii_case(owner))
+        .collect();
+    hooks.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+    Ok(hooks)
+}
+
+/// Register a webhook for `owner`. Returns the record and the plaintext
+/// signing secret, which is not retrievable later.
+pub fn create_webhook(
+    owner: &str,
+    url: &str,
+    events: Vec<WebhookEvent>,
+) -> Result<(WebhookRecord, String)> {
+    let url = url.trim();
+    validate_url(url)?;
+    if list_webhooks(owner)?.len() >= MAX_WEBHOOKS_PER_OWNER {
+        return Err(SandboxError::Validation(format!(
+            "At most {MAX_WEBHOOKS_PER_OWNER} webhooks per owner"
+        )));
+    }
+    let mut events = events;
+    events.sort_by_key(|e| e.as_str());
+    events.dedup();
+
+    let mut raw = [0u8; 32];
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut raw);
+    let secret = format!("whsec_{}", hex::encode(raw));
+    let record = WebhookRecord {
+        id: format!("wh-{}", uuid::Uuid::new_v4().simple()),
+        owner: owner.to_string(),
+        url: url.to_string(),
+        secret: crate::runtime::seal_field(&secret)?,
+        events,
+        created_at: crate::util::now_ts(),
+    };
+    webhooks()?.insert(record.id.clone(), record.clone())?;
+    Ok((record, secret))
+}
+
+fn owned_webhook(owner: &str, webhook_id: &str) -> Result<WebhookRecord> {
+    webhooks()?
+        .get(webhook_id)?
+        .filter(|w| w.owner.eq_ignore_ascii_case(owner))
+        .ok_or_else(|| SandboxError::NotFound(format!("Webhook '{webhook_id}' not found")))
+}
+
+/// Remove a webhook of `owner` along with its delivery log.
+pub fn delete_webhook(owner: &str, webhook_id: &str) -> Result<()> {
+    owned_webhook(owner, webhook_id)?;
+    webhooks()?.remove(webhook_id)?;
+    let store = webhook_deliveries()?;
+    for delivery in store.values()? {
+        if delivery.webhook_id == webhook_id {
+            store.remove(&delivery.id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Delivery log of a webhook of `owner`, newest first.
+pub fn list_deliveries(owner: &str, webhook_id: &str) -> Result<Vec<WebhookDelivery>> {
+    owned_webhook(owner, webhook_id)?;
+    let mut deliveries: Vec<WebhookDelivery> = webhook_deliveries()?
+        .values()?
+        .into_iter()
+        .filter(|d| d.webhook_id == webhook_id)
+        .collect();
+    deliveries.sort_by(|a, b| (b.created_at, &b.id).cmp(&(a.created_at, &a.id)));
+    Ok(deliveries)
+}
+
+/// Notify `owner`'s webhooks subscribed to `event`. Deliveries run in the
+/// background; outside a Tokio runtime, or for an empty owner, this is a
+/// no-op.
+pub fn emit(owner: &str, event: WebhookEvent, data: Value) {
+    if owner.is_empty() || tokio::runtime::Handle::try_current().is_err() {
+        return;
+    }
+    let hooks = match list_webhooks(owner) {
+        Ok(hooks) => hooks,
+        Err(e) => {
+            tracing::warn!(event = event.as_str(), error = %e, "webhooks: failed to load registrations");
+            return;
+        }
+    };
+    for hook in hooks.into_iter().filter(|h| h.wants(event)) {
+        if let Err(e) = delivery::spawn_delivery(hook, event, data.clone()) {
+            tracing::warn!(event = event.as_str(), error = %e, "webhooks: failed to queue delivery");
+        }
+    }
+}
+
+/// Send a `sandbox.*` event for `record` to its owner.
+pub fn emit_sandbox_event(record: &crate::runtime::SandboxRecord, event: WebhookEvent) {
+    emit(
+        &record.owner,
+        event,
+        json!({ "sandbox_id": record.id, "service_id": record.service_id }),
+    );
+}
+
+/// Seconds before the reaper stops or deletes a sandbox to send
+/// `reaper.warning` ([`DEFAULT_REAPER_WARNING_SECS`] when unset; 0 disables).
+pub const REAPER_WARNING_ENV: &str = "WEBHOOK_REAPER_WARNING_SECS";
+pub const DEFAULT_REAPER_WARNING_SECS: u64 = 10 * 60;
+
+/// Deadlines already warned about, keyed by `{sandbox}:{reason}:{deadline}`.
+static REAPER_WARNED: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) fn reaper_warning_secs() -> u64 {
+    std::env::var(REAPER_WARNING_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_REAPER_WARNING_SECS)
+}
+
+/// Send `reaper.warning` once when `deadline` (the reaper's `action` for
+/// `reason`) is within the warning window. Called by the reaper tick.
+pub fn warn_reaper_action(
+    record: &crate::runtime::SandboxRecord,
+    action: &str,
+    reason: &str,
+    deadline: u64,
+    now: u64,
+) {
+    let window = reaper_warning_secs();
+    if window == 0 || deadline <= now || deadline - now > window {
+        return;
+    }
+    let key = format!("{}:{reason}:{deadline}", record.id);
+    {
+        let mut warned = REAPER_WARNED.lock().unwrap_or_else(|e| e.into_inner());
+        warned.retain(|_, &mut at| at > now);
+        if warned.insert(key, deadline).is_some() {
+            return;
+        }
+    }
+    emit(
+        &record.owner,
+        WebhookEvent::ReaperWarning,
+        json!({
+            "sandbox_id": record.id,
+            "action": action,
+            "reason": reason,
+            "deadline": deadline,
+        }),
+    );
+}
+
+/// Drop delivery logs that finished more than `max_age_secs` ago.
+pub fn gc_deliveries(max_age_secs: u64) -> Result<()> {
+    let cutoff = crate::util::now_ts().saturating_sub(max_age_secs);
+    let store = webhook_deliveries()?;
+    for delivery in store.values()? {
+        if delivery.completed_at.is_some_and(|at| at <= cutoff) {
+            store.remove(&delivery.id)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_names_match_serde() {
+        for event in [
+            WebhookEvent::SandboxCreated,
+            WebhookEvent::SandboxCrashed,
+            WebhookEvent::SandboxDiskQuota,
+            WebhookEvent::WorkflowCompleted,
+            WebhookEvent::ReaperWarning,
+            WebhookEvent::BillingAlert,
+            WebhookEvent::MaintenanceScheduled,
+        ] {
+            assert_eq!(json!(event), json!(event.as_str()));
+        }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn urls_require_https_and_a_public_target() {
+        assert!(validate_url("https://hooks.slack.com/services/T/B/x").is_ok());
+        assert!(validate_url("https://203.0.113.7/hook").is_ok());
+        for url in [
+            "http://127.0.0.1:9000/hook",
+            "http://localhost/hook",
+            "https://localhost:8443/hook",
+            "https://10.0.0.5/hook",
+            "https://169.254.169.254/latest/meta-data/",
+            "https://100.64.0.1/",
+            "https://[::1]/",
+            "https://[::ffff:192.168.1.1]/",
+            "http://example.com/hook",
+            "ftp://example.com",
+            "not a url",
+        ] {
+            assert!(validate_url(url).is_err(), "{url} should be rejected");
+        }
+    }
+
+    #[test]
+    fn empty_event_filter_matches_everything() {
+        let mut hook = WebhookRecord {
+            id: "wh-1".into(),
+            owner: "0xabc".into(),
+            url: "https://example.com".into(),
+            secret: String::new(),
+            events: Vec::new(),
+            created_at: 0,
+        };
+        assert!(hook.wants(WebhookEvent::BillingAlert));
+        hook.events = vec![WebhookEvent::SandboxDeleted];
+        assert!(hook.wants(WebhookEvent::SandboxDeleted));
+        assert!(!hook.wants(WebhookEvent::BillingAlert));
+        assert!(hook.summary().get("secret").is_none());
+    }
+}
@@ -59,6 +59,8 @@ async fn admission_control_end_to_end() {
             std::env::set_var("BLUEPRINT_STATE_DIR", state_dir.path());
             std::env::set_var("SIDECAR_IMAGE", "test:latest");
             std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+            // Mock sidecars never answer `/health`; skip the API handshake.
+            std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
             std::env::set_var("SANDBOX_MAX_COUNT", "3");
             std::env::set_var("SANDBOX_MAX_CPU_CORES", "4");
             std::env::set_var("SANDBOX_MAX_MEMORY_MB", "2048");
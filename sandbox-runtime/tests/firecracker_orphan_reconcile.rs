@@ -94,6 +94,8 @@ fn reconcile_reaps_orphan_warm_fc_but_spares_live_sandbox() {
             std::env::set_var("BLUEPRINT_STATE_DIR", &store_dir);
             std::env::set_var("SIDECAR_IMAGE", "test:latest");
             std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+            // Mock sidecars never answer `/health`; skip the API handshake.
+            std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
         }
     }
 
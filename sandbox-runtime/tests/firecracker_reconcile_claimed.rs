@@ -72,6 +72,8 @@ fn reconcile_spares_claimed_warm_sandbox_whose_socket_moved() {
             std::env::set_var("BLUEPRINT_STATE_DIR", tmp.path().join("store"));
             std::env::set_var("SIDECAR_IMAGE", "test:latest");
             std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+            // Mock sidecars never answer `/health`; skip the API handshake.
+            std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
         }
     }
 
@@ -46,6 +46,8 @@ async fn rejected_create_never_touches_warm_pool() {
             std::env::set_var("BLUEPRINT_STATE_DIR", state_dir.path());
             std::env::set_var("SIDECAR_IMAGE", "test:latest");
             std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+            // Mock sidecars never answer `/health`; skip the API handshake.
+            std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
             std::env::set_var("SANDBOX_MAX_COUNT", "1");
             // Warm pool nominally ON — the point of the test. Also point
             // the FC primitive at nonexistent artifacts so any accidental
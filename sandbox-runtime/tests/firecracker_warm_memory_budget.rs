@@ -53,6 +53,8 @@ async fn warm_pool_reservation_counts_against_host_budget() {
             std::env::set_var("BLUEPRINT_STATE_DIR", state_dir.path());
             std::env::set_var("SIDECAR_IMAGE", "test:latest");
             std::env::set_var("SIDECAR_PUBLIC_HOST", "127.0.0.1");
+            // Mock sidecars never answer `/health`; skip the API handshake.
+            std::env::set_var("SIDECAR_API_NEGOTIATION_TIMEOUT_SECS", "0");
             std::env::set_var("SANDBOX_HOST_MEMORY_BUDGET_MB", "4096");
             std::env::set_var("SANDBOX_MAX_MEMORY_MB", "2048");
             // Count is deliberately not the binding limit — the budget is.
@@ -165,7 +165,7 @@ async fn docker_ssh_supports_commands_and_interactive_shell() {
             .ssh_login_user
             .clone()
             .expect("ssh login user should be detected during creation");
-        let (username, _) = provision_ssh_key(&record, None, &public_key)
+        let (username, _) = provision_ssh_key(&record, None, &public_key, None, "test")
             .await
             .expect("ssh key should provision");
         assert_eq!(username, login_user);
@@ -219,6 +219,11 @@ mod tee_integration {
             ssh_login_user: None,
             ssh_authorized_keys: Vec::new(),
             capabilities_json: String::new(),
+            sidecar_api: None,
+            image_digest: None,
+            crash_history: Vec::new(),
+            lifecycle_events: Vec::new(),
+            clone_image: None,
         };
 
         // The idempotent path reads from record.tee_attestation_json
@@ -194,18 +194,18 @@ section "1" "On-Chain State Validation"
 
 # Verify sandbox BSM is accessible
 SANDBOX_JOB_COUNT=$(cast call "$SANDBOX_BSM" "jobCount()(uint256)" --rpc-url "$RPC_URL" 2>/dev/null || echo "0")
-if [ "$SANDBOX_JOB_COUNT" -eq 5 ] 2>/dev/null; then
-    pass "Sandbox BSM has $SANDBOX_JOB_COUNT jobs (expected 5)"
+if [ "$SANDBOX_JOB_COUNT" -eq 13 ] 2>/dev/null; then
+    pass "Sandbox BSM has $SANDBOX_JOB_COUNT jobs (expected 13)"
 else
-    fail "Sandbox BSM jobCount=$SANDBOX_JOB_COUNT (expected 5)"
+    fail "Sandbox BSM jobCount=$SANDBOX_JOB_COUNT (expected 13)"
 fi
 
 # Verify instance BSM is accessible
 INSTANCE_JOB_COUNT=$(cast call "$INSTANCE_BSM" "jobCount()(uint256)" --rpc-url "$RPC_URL" 2>/dev/null || echo "0")
-if [ "$INSTANCE_JOB_COUNT" -eq 3 ] 2>/dev/null; then
-    pass "Instance BSM has $INSTANCE_JOB_COUNT jobs (expected 3 workflow jobs)"
+if [ "$INSTANCE_JOB_COUNT" -eq 16 ] 2>/dev/null; then
+    pass "Instance BSM has $INSTANCE_JOB_COUNT jobs (expected 16)"
 else
-    fail "Instance BSM jobCount=$INSTANCE_JOB_COUNT (expected 3)"
+    fail "Instance BSM jobCount=$INSTANCE_JOB_COUNT (expected 16)"
 fi
 
 # Verify operators have capacity on sandbox BSM
@@ -372,14 +372,15 @@ else
     # SandboxCreateRequest: (string name, string image, string stack, string agent_id,
     #   string env_json, string metadata_json, bool ssh, string ssh_key, bool web_term,
     #   uint64 max_life, uint64 idle, uint64 cpu, uint64 mem, uint64 disk,
-    #   bool tee, uint8 tee_type, string attestation_nonce, string capabilities_json)
+    #   bool tee, uint8 tee_type, string attestation_nonce, string capabilities_json,
+    #   string template)
     CREATE_ARGS=$(cast abi-encode \
-        "f(string,string,string,string,string,string,bool,string,bool,uint64,uint64,uint64,uint64,uint64,bool,uint8,string,string)" \
+        "f(string,string,string,string,string,string,bool,string,bool,uint64,uint64,uint64,uint64,uint64,bool,uint8,string,string,string)" \
         "e2e-sandbox" "${TANGLE_E2E_IMAGE:-${SIDECAR_IMAGE:-blueprint-sidecar:all-harness}}" "default" "default-agent" "{}" "{}" \
         false "" true \
         3600 900 2 2048 10 \
         false 0 "" \
-        "")
+        "" "")
 
     SANDBOX_CALL_ID=$(submit_job "$SANDBOX_SERVICE_ID" 0 "$CREATE_ARGS" "$CREATE_RATE") || true
     if [ "$SANDBOX_CALL_ID" = "REVERT" ] || [ "$SANDBOX_CALL_ID" = "TX_FAIL" ]; then
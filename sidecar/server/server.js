@@ -13,6 +13,15 @@ const childUid = Number(process.env.AGENT_SUBPROCESS_UID || 1000)
 const childGid = Number(process.env.AGENT_SUBPROCESS_GID || 1000)
 const terminalShell = process.env.SIDECAR_TERMINAL_SHELL || '/bin/bash'
 const terminalSessions = new Map()
+// Reported on /health so the operator can pick the matching response
+// adapter at provision. API 1: command results under `result`.
+const sidecarVersion = process.env.SIDECAR_VERSION || ''
+const sidecarApiVersion = 1
+const sidecarCapabilities = (process.env.SIDECAR_CAPABILITIES || '')
+  .split(',')
+  .map((c) => c.trim())
+  .filter(Boolean)
+let runningProcesses = 0
 
 const agents = [
   {
@@ -126,10 +135,11 @@ function runProcess(command, args, options = {}) {
       cwd,
       env: childEnv,
       shell: false,
-      stdio: ['ignore', 'pipe', 'pipe'],
+      stdio: [typeof options.stdin === 'string' ? 'pipe' : 'ignore', 'pipe', 'pipe'],
       uid: process.getuid && process.getuid() === 0 ? childUid : undefined,
       gid: process.getuid && process.getuid() === 0 ? childGid : undefined,
     })
+    runningProcesses += 1
 
     const timer = timeout > 0
       ? setTimeout(() => {
@@ -139,14 +149,27 @@ function runProcess(command, args, options = {}) {
       }, timeout)
       : null
 
+    if (child.stdin) {
+      // The command may exit without draining its input.
+      child.stdin.on('error', () => {})
+      child.stdin.end(options.stdin)
+    }
     child.stdout.on('data', (chunk) => { stdout += chunk.toString() })
     child.stderr.on('data', (chunk) => { stderr += chunk.toString() })
-    child.on('error', (err) => {
+    let settled = false
+    const settle = () => {
+      if (settled) return false
+      settled = true
+      runningProcesses -= 1
       if (timer) clearTimeout(timer)
+      return true
+    }
+    child.on('error', (err) => {
+      if (!settle()) return
       resolve({ exitCode: 127, stdout, stderr: stderr + err.message })
     })
     child.on('close', (code, signal) => {
-      if (timer) clearTimeout(timer)
+      if (!settle()) return
       resolve({
         exitCode: timedOut ? 124 : (code ?? 1),
         stdout,
@@ -158,13 +181,141 @@ function runProcess(command, args, options = {}) {
 }
 
 function shellCommand(command, payload) {
-  return runProcess('/bin/sh', ['-lc', command], {
+  const stdinFile = typeof payload.stdinFile === 'string' ? payload.stdinFile : ''
+  // The input file is opened by the shell, as the agent user and relative to
+  // cwd, so it is subject to the same permissions as the command itself.
+  const args = stdinFile
+    ? ['-lc', 'exec <"$1" || exit 1; shift; eval "$1"', 'sh', stdinFile, command]
+    : ['-lc', command]
+  return runProcess('/bin/sh', args, {
     cwd: typeof payload.cwd === 'string' ? payload.cwd : workspaceRoot,
     timeout: Number(payload.timeout || payload.timeout_ms || 0),
     env: payload.env,
+    stdin: typeof payload.stdin === 'string' ? payload.stdin : undefined,
   })
 }
 
+const maxFileListEntries = 1000
+
+function entryKind(stat) {
+  if (stat.isDirectory()) return 'directory'
+  if (stat.isFile()) return 'file'
+  if (stat.isSymbolicLink()) return 'symlink'
+  return 'other'
+}
+
+// Lists one directory under the workspace root. The requested path is resolved
+// (following symlinks) and must stay inside the real workspace root.
+async function listFiles(relPath, limit) {
+  const root = await fs.promises.realpath(workspaceRoot)
+  if (relPath.includes('\0') || path.isAbsolute(relPath)) {
+    return { status: 400, code: 'INVALID_PATH', message: 'path must be relative to the workspace root' }
+  }
+  let dir
+  try {
+    dir = await fs.promises.realpath(path.resolve(root, relPath))
+  } catch {
+    return { status: 404, code: 'PATH_NOT_FOUND', message: 'Directory not found' }
+  }
+  if (dir !== root && !dir.startsWith(root + path.sep)) {
+    return { status: 403, code: 'PATH_OUTSIDE_WORKSPACE', message: 'path is outside the workspace' }
+  }
+  let dirents
+  try {
+    dirents = await fs.promises.readdir(dir, { withFileTypes: true })
+  } catch (err) {
+    const status = err.code === 'ENOTDIR' ? 400 : 404
+    return { status, code: 'NOT_A_DIRECTORY', message: 'path is not a readable directory' }
+  }
+  const max = Math.min(Math.max(Number(limit) || maxFileListEntries, 1), maxFileListEntries)
+  const entries = []
+  for (const dirent of dirents.slice(0, max)) {
+    try {
+      const stat = await fs.promises.lstat(path.join(dir, dirent.name))
+      entries.push({
+        name: dirent.name,
+        type: entryKind(stat),
+        size: stat.size,
+        mtime: Math.floor(stat.mtimeMs / 1000),
+      })
+    } catch {
+      // Removed between readdir and lstat.
+    }
+  }
+  return {
+    status: 200,
+    body: { path: path.relative(root, dir), entries, truncated: dirents.length > max },
+  }
+}
+
+const activityScanMaxEntries = 5000
+const activityScanMaxDepth = 4
+
+// Newest mtime (epoch seconds) under the workspace, from a bounded walk that
+// skips dependency and VCS directories.
+async function lastWorkspaceWrite() {
+  let newest = 0
+  let seen = 0
+  const walk = async (dir, depth) => {
+    let dirents
+    try {
+      dirents = await fs.promises.readdir(dir, { withFileTypes: true })
+    } catch {
+      return
+    }
+    for (const dirent of dirents) {
+      if (seen >= activityScanMaxEntries) return
+      seen += 1
+      const full = path.join(dir, dirent.name)
+      try {
+        const stat = await fs.promises.lstat(full)
+        newest = Math.max(newest, Math.floor(stat.mtimeMs / 1000))
+      } catch {
+        continue
+      }
+      if (dirent.isDirectory() && depth < activityScanMaxDepth
+        && !['.git', 'node_modules', 'target', '.cache'].includes(dirent.name)) {
+        await walk(full, depth + 1)
+      }
+    }
+  }
+  await walk(workspaceRoot, 0)
+  return newest
+}
+
+// Established inbound connections to sshd (port 22), from /proc/net/tcp{,6}.
+function sshSessionCount() {
+  let count = 0
+  for (const file of ['/proc/net/tcp', '/proc/net/tcp6']) {
+    let text
+    try {
+      text = fs.readFileSync(file, 'utf8')
+    } catch {
+      continue
+    }
+    for (const line of text.split('\n').slice(1)) {
+      const fields = line.trim().split(/\s+/)
+      if (fields.length < 4) continue
+      const localPort = fields[1].split(':').pop()
+      if (localPort === '0016' && fields[3] === '01') count += 1
+    }
+  }
+  return count
+}
+
+// Summary the operator's reaper uses to tell an idle sandbox from one that is
+// busy without going through the operator API (SSH, agents, terminals).
+async function activitySummary() {
+  const openTerminals = Array.from(terminalSessions.values()).filter((s) => s.running).length
+  return {
+    openTerminals,
+    runningProcesses,
+    sshSessions: sshSessionCount(),
+    lastFsWriteAt: await lastWorkspaceWrite(),
+    checkedAt: Math.floor(Date.now() / 1000),
+  }
+}
+
 function terminalSummary(session) {
   return {
     sessionId: session.id,
@@ -277,20 +428,60 @@ function selectHarness(identifier, backend) {
   return 'opencode'
 }
 
+const DEFAULT_CLAUDE_TOOLS = ['Bash', 'Read', 'Edit', 'Write', 'Glob', 'Grep', 'WebFetch', 'WebSearch']
+const NETWORK_TOOLS = new Set(['WebFetch', 'WebSearch'])
+const WRITE_TOOLS = new Set(['Edit', 'Write'])
+
+function toolPolicy(payload) {
+  const tools = payload.metadata?.tools
+  return tools && typeof tools === 'object' && !Array.isArray(tools) ? tools : null
+}
+
+// Translate a task tool policy into claude's --allowedTools list. Bash can
+// reach the network and write anywhere, so it is dropped when either is
+// restricted unless the caller allowed it by name.
+function claudeAllowedTools(tools) {
+  const allow = Array.isArray(tools.allow) && tools.allow.length ? tools.allow : DEFAULT_CLAUDE_TOOLS
+  const deny = new Set(Array.isArray(tools.deny) ? tools.deny : [])
+  const writePaths = Array.isArray(tools.writePaths) ? tools.writePaths : []
+  const explicit = new Set(Array.isArray(tools.allow) ? tools.allow : [])
+  const allowed = []
+  for (const name of allow) {
+    if (deny.has(name)) continue
+    if (tools.network === false && NETWORK_TOOLS.has(name)) continue
+    if (name === 'Bash' && (tools.network === false || writePaths.length) && !explicit.has('Bash')) {
+      continue
+    }
+    if (WRITE_TOOLS.has(name) && writePaths.length) {
+      for (const dir of writePaths) allowed.push(`${name}(${dir.replace(/\/+$/, '')}/**)`)
+      continue
+    }
+    allowed.push(name)
+  }
+  return allowed
+}
+
 function harnessCommand(harness, payload) {
   const message = String(payload.message || '')
   const model = payload.backend?.model ? String(payload.backend.model) : ''
   const timeout = Number(payload.timeout || 0)
+  const tools = toolPolicy(payload)
 
   if (process.env.SIDECAR_AGENT_COMMAND) {
+    const env = { SIDECAR_AGENT_MESSAGE: message, SIDECAR_AGENT_MODEL: model }
+    if (tools) env.SIDECAR_TOOL_POLICY = JSON.stringify(tools)
     return {
       command: '/bin/sh',
       args: ['-lc', process.env.SIDECAR_AGENT_COMMAND],
-      env: { SIDECAR_AGENT_MESSAGE: message, SIDECAR_AGENT_MODEL: model },
+      env,
       timeout,
     }
   }
 
+  if (tools && harness !== 'claude') {
+    return { error: `Harness ${harness} cannot enforce a tool policy` }
+  }
+
   switch (harness) {
     case 'codex':
       return {
@@ -301,7 +492,9 @@ function harnessCommand(harness, payload) {
     case 'claude':
       return {
         command: 'claude',
-        args: ['-p', message, '--dangerously-skip-permissions'],
+        args: tools
+          ? ['-p', message, '--allowedTools', claudeAllowedTools(tools).join(',')]
+          : ['-p', message, '--dangerously-skip-permissions'],
         timeout,
       }
     case 'gemini':
@@ -349,6 +542,9 @@ async function runAgent(payload) {
       error: `No command registered for harness ${harness}`,
     }
   }
+  if (spec.error) {
+    return { success: false, status: 400, error: spec.error }
+  }
 
   const result = await runProcess(spec.command, spec.args, {
     cwd: workspaceRoot,
@@ -363,6 +559,7 @@ async function runAgent(payload) {
     stderr: result.stderr,
     exitCode: result.exitCode,
     harness,
+    tools: toolPolicy(payload),
   }
 }
 
@@ -375,7 +572,12 @@ async function handle(req, res) {
   const url = new URL(req.url, `http://${req.headers.host || 'localhost'}`)
 
   if (req.method === 'GET' && url.pathname === '/health') {
-    sendJson(res, 200, { status: 'ok' })
+    sendJson(res, 200, {
+      status: 'ok',
+      version: sidecarVersion,
+      apiVersion: sidecarApiVersion,
+      capabilities: sidecarCapabilities,
+    })
     return
   }
 
@@ -403,6 +605,21 @@ async function handle(req, res) {
     return
   }
 
+  if (req.method === 'GET' && url.pathname === '/activity') {
+    sendJson(res, 200, await activitySummary())
+    return
+  }
+
+  if (req.method === 'GET' && url.pathname === '/files/list') {
+    const result = await listFiles(url.searchParams.get('path') || '', url.searchParams.get('limit'))
+    if (result.status !== 200) {
+      sendError(res, result.status, result.code, result.message)
+      return
+    }
+    sendJson(res, 200, result.body)
+    return
+  }
+
   if (req.method === 'GET' && url.pathname === '/terminals') {
     const data = Array.from(terminalSessions.values()).map(terminalSummary)
     sendJson(res, 200, { data, terminals: data })
@@ -491,7 +708,9 @@ async function handle(req, res) {
       traceId: randomUUID(),
       sessionId: payload.sessionId || randomUUID(),
       usage: {},
-      metadata: { harness: result.harness },
+      metadata: result.tools
+        ? { harness: result.harness, tools: result.tools }
+        : { harness: result.harness },
     })
     return
   }
@@ -522,6 +741,7 @@ async function handle(req, res) {
         sessionId: payload.sessionId || randomUUID(),
         traceId: randomUUID(),
         harness: result.harness,
+        ...(result.tools ? { tools: result.tools } : {}),
       },
       tokenUsage: {},
     })
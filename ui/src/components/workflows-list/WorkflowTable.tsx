@@ -90,6 +90,7 @@ function WorkflowTableRow({
   const triggerLabel: Record<string, string> = {
     cron: 'Cron',
     manual: 'Manual',
+    workflow_complete: 'After workflow',
   };
 
   const isPending = workflow.kind === 'pending';
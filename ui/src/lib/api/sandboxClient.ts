@@ -223,6 +223,7 @@ export class SandboxClient {
       stdout: data.stdout ?? data.output ?? '',
       stderr: data.stderr ?? '',
       exitCode: data.exitCode ?? data.exit_code ?? 0,
+      overflowRef: data.overflowRef ?? data.overflow_ref ?? undefined,
     };
   }
 
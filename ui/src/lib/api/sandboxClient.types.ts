@@ -77,6 +77,8 @@ export interface ExecResult {
   stdout: string;
   stderr: string;
   exitCode: number;
+  /** Set when output exceeded the operator's cap; fetch the full output via `exec/overflow/{ref}`. */
+  overflowRef?: string;
 }
 
 export interface PromptResult {
@@ -23,7 +23,7 @@ export function createInstanceJobs(opts?: {
       // ABI: ProvisionRequest { name, image, stack, agent_identifier, env_json, metadata_json,
       //   ssh_enabled, ssh_public_key, web_terminal_enabled, max_lifetime_seconds,
       //   idle_timeout_seconds, cpu_cores, memory_mb, disk_gb, tee_required, tee_type,
-      //   attestation_nonce, capabilities_json }
+      //   attestation_nonce, capabilities_json, template, slot }
       // Not an on-chain submitJob target — the encoded fields are passed as requestInputs
       // to requestService (Path B) or used by the operator's auto-provision decoder.
       id: INSTANCE_JOB_IDS.PROVISION,
@@ -62,6 +62,8 @@ export function createInstanceJobs(opts?: {
         { name: 'teeType', label: 'TEE Type', type: 'select', defaultValue: '0', abiType: 'uint8', abiParam: 'tee_type', options: TEE_TYPE_OPTIONS },
         { name: 'attestationNonce', label: 'Attestation Nonce', type: 'text', defaultValue: '', abiType: 'string', abiParam: 'attestation_nonce', internal: true },
         { name: 'capabilitiesJson', label: 'Capabilities (JSON)', type: 'json', placeholder: '[]', defaultValue: '[]', abiType: 'string', abiParam: 'capabilities_json', internal: true },
+        { name: 'template', label: 'Template', type: 'text', placeholder: 'python-small', defaultValue: '', helperText: 'Operator template from GET /api/templates; pre-fills fields left empty', abiType: 'string', abiParam: 'template', internal: true },
+        { name: 'slot', label: 'Slot', type: 'text', defaultValue: '', helperText: 'Instance slot; empty targets main. Auto-provision always uses main.', abiType: 'string', abiParam: 'slot', internal: true },
       ],
     },
     {
@@ -44,7 +44,7 @@ const SANDBOX_JOBS: JobDefinition[] = [
   {
     // ABI: SandboxCreateRequest { name, image, stack, agent_identifier, env_json, metadata_json,
     //   ssh_enabled, ssh_public_key, web_terminal_enabled, max_lifetime_seconds, idle_timeout_seconds,
-    //   cpu_cores, memory_mb, disk_gb, tee_required, tee_type, attestation_nonce, capabilities_json }
+    //   cpu_cores, memory_mb, disk_gb, tee_required, tee_type, attestation_nonce, capabilities_json, template }
     id: JOB_IDS.SANDBOX_CREATE,
     name: 'sandbox_create',
     label: 'Create Sandbox',
@@ -82,6 +82,7 @@ const SANDBOX_JOBS: JobDefinition[] = [
       { name: 'teeType', label: 'TEE Type', type: 'select', defaultValue: '0', abiType: 'uint8', abiParam: 'tee_type', options: TEE_TYPE_OPTIONS },
       { name: 'attestationNonce', label: 'Attestation Nonce', type: 'text', defaultValue: '', abiType: 'string', abiParam: 'attestation_nonce', internal: true },
       { name: 'capabilitiesJson', label: 'Capabilities (JSON)', type: 'json', placeholder: '[]', defaultValue: '[]', abiType: 'string', abiParam: 'capabilities_json', internal: true },
+      { name: 'template', label: 'Template', type: 'text', placeholder: 'python-small', defaultValue: '', helperText: 'Operator template from GET /api/templates; pre-fills fields left empty', abiType: 'string', abiParam: 'template', internal: true },
     ],
   },
   {